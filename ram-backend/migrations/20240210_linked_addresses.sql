@@ -0,0 +1,11 @@
+-- Addresses currently linked to each wallet, maintained by the indexer from
+-- AddressLinked / AddressUnlinked events
+CREATE TABLE IF NOT EXISTS linked_addresses (
+    handle TEXT NOT NULL,
+    address TEXT NOT NULL,
+    label TEXT NOT NULL DEFAULT '',
+    linked_at_ms BIGINT NOT NULL,
+    PRIMARY KEY (handle, address)
+);
+
+CREATE INDEX IF NOT EXISTS idx_linked_addresses_address ON linked_addresses(address);
@@ -0,0 +1,7 @@
+-- Keep the details the typed columns don't capture: BioAuth result codes
+-- (the unused `result` column becomes `result_code`) and the full parsed
+-- event JSON so fields added to events later aren't lost
+ALTER TABLE ram_events RENAME COLUMN result TO result_code;
+ALTER TABLE ram_events ADD COLUMN IF NOT EXISTS raw_json JSONB;
+
+CREATE INDEX IF NOT EXISTS idx_coin_type ON ram_events(coin_type);
@@ -0,0 +1,21 @@
+-- Events the indexer failed to process, retried with exponential backoff
+-- until they succeed or exhaust their attempts ('dead'); operators can
+-- requeue dead events through the admin API
+CREATE TABLE IF NOT EXISTS failed_events (
+    id BIGSERIAL PRIMARY KEY,
+    tx_digest TEXT NOT NULL,
+    event_seq TEXT NOT NULL,
+    event_type TEXT NOT NULL,
+    -- Full Sui event as fetched, replayed through the indexer on retry
+    event_json JSONB NOT NULL,
+    last_error TEXT NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 1,
+    next_attempt_ms BIGINT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending',
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT unique_failed_event UNIQUE (tx_digest, event_seq)
+);
+
+CREATE INDEX IF NOT EXISTS idx_failed_events_due ON failed_events(status, next_attempt_ms);
@@ -0,0 +1,10 @@
+-- Support search: trigram indexes for partial handle / digest matches and a
+-- full-text index over the stored event payloads
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+CREATE INDEX IF NOT EXISTS idx_handle_trgm ON ram_events USING GIN (handle gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS idx_from_handle_trgm ON ram_events USING GIN (from_handle gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS idx_to_handle_trgm ON ram_events USING GIN (to_handle gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS idx_transaction_digest_trgm ON ram_events USING GIN (transaction_digest gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS idx_raw_json_fts ON ram_events
+    USING GIN (to_tsvector('simple', COALESCE(raw_json::TEXT, '')));
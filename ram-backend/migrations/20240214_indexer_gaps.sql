@@ -0,0 +1,12 @@
+-- Ranges of history the indexer could not ingest because the fullnode pruned
+-- events past the stored cursor; recorded when the indexer re-anchors to the
+-- earliest event still available
+CREATE TABLE IF NOT EXISTS indexer_gaps (
+    id BIGSERIAL PRIMARY KEY,
+    -- Last cursor ingested before the gap
+    stale_cursor TEXT NOT NULL,
+    -- First event ingested after re-anchoring (NULL if none were available)
+    resumed_from TEXT,
+    error TEXT NOT NULL,
+    detected_at_ms BIGINT NOT NULL
+);
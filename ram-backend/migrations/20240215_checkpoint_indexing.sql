@@ -0,0 +1,12 @@
+-- Transaction context captured by the checkpoint indexer (NULL for events
+-- ingested through suix_queryEvents, which doesn't carry gas data)
+ALTER TABLE ram_events ADD COLUMN IF NOT EXISTS gas_used BIGINT;
+
+-- Last checkpoint fully ingested by the checkpoint indexer; kept apart from
+-- indexer_state so switching INDEXER_SOURCE doesn't clobber the event cursor
+CREATE TABLE IF NOT EXISTS checkpoint_state (
+    id INTEGER PRIMARY KEY DEFAULT 1,
+    last_checkpoint BIGINT NOT NULL,
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT single_row CHECK (id = 1)
+);
@@ -0,0 +1,6 @@
+-- Address that signed the transaction emitting each event, so investigators
+-- can tell which linked address initiated a withdrawal or transfer
+ALTER TABLE ram_events ADD COLUMN IF NOT EXISTS sender TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_sender ON ram_events(sender);
+CREATE INDEX IF NOT EXISTS idx_sender_trgm ON ram_events USING GIN (sender gin_trgm_ops);
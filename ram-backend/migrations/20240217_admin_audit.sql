@@ -0,0 +1,22 @@
+-- Audit trail of administrative and configuration changes: who made each one,
+-- what it touched and when. Rows are written before the change is applied and
+-- completed with its outcome, so no change goes untraced
+CREATE TABLE IF NOT EXISTS admin_audit (
+    id BIGSERIAL PRIMARY KEY,
+    -- Operator named in X-Admin-Actor ("admin" if the call didn't say)
+    actor TEXT NOT NULL,
+    -- Fingerprint of the admin token presented, to tell rotated tokens apart
+    credential TEXT NOT NULL,
+    -- What was changed, e.g. voice_baseline.reset
+    action TEXT NOT NULL,
+    -- What it was changed on: a handle, event ID or setting name
+    target TEXT NOT NULL,
+    -- Action-specific context, e.g. a setting's previous and new values
+    details JSONB NOT NULL DEFAULT '{}',
+    -- HTTP status the change ended with (NULL if it never completed)
+    status INTEGER,
+    created_at_ms BIGINT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_admin_audit_created ON admin_audit(created_at_ms DESC);
+CREATE INDEX IF NOT EXISTS idx_admin_audit_action ON admin_audit(action, created_at_ms DESC);
@@ -0,0 +1,23 @@
+-- Sponsored wallet creations (`/api/onboard`): the transaction prepared for
+-- the user to sign, then its outcome once submitted with the sponsor's
+-- signature. Executed rows also count against the sponsor's daily limit
+CREATE TABLE IF NOT EXISTS onboardings (
+    id BIGSERIAL PRIMARY KEY,
+    handle TEXT NOT NULL,
+    -- Address creating the wallet (the transaction sender, not the gas owner)
+    sender TEXT NOT NULL,
+    -- BCS TransactionData the user and sponsor sign
+    tx_bytes BYTEA NOT NULL,
+    -- pending: awaiting the user's signature; submitting while it executes;
+    -- then executed or failed
+    status TEXT NOT NULL DEFAULT 'pending',
+    tx_digest TEXT,
+    wallet_id TEXT,
+    error TEXT,
+    created_at_ms BIGINT NOT NULL,
+    updated_at_ms BIGINT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_onboardings_status ON onboardings(status, updated_at_ms DESC);
+CREATE INDEX IF NOT EXISTS idx_onboardings_handle ON onboardings(handle);
+CREATE INDEX IF NOT EXISTS idx_onboardings_sender ON onboardings(sender);
@@ -0,0 +1,14 @@
+-- Public receive-page data for a wallet (`GET /api/receive/:handle`), edited
+-- by the wallet's linked addresses through signed updates
+CREATE TABLE IF NOT EXISTS profiles (
+    handle TEXT PRIMARY KEY,
+    display_name TEXT,
+    -- Full Move type tags the wallet asks to be paid in
+    accepted_coin_types TEXT[] NOT NULL,
+    -- Hex SHA-256 of the avatar image, served from content-addressed storage
+    avatar_hash TEXT,
+    -- Linked address that signed the latest update
+    updated_by TEXT NOT NULL,
+    -- Signed timestamp of the latest update; older updates are refused
+    updated_at_ms BIGINT NOT NULL
+);
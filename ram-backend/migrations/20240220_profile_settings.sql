@@ -0,0 +1,5 @@
+-- Private profile settings, only returned to the wallet's linked addresses
+ALTER TABLE profiles ADD COLUMN IF NOT EXISTS preferred_locale TEXT;
+-- Which notifications the wallet wants (see models::NotificationSettings)
+ALTER TABLE profiles ADD COLUMN IF NOT EXISTS notifications JSONB NOT NULL
+    DEFAULT '{"incoming_transfers": true, "outgoing_transfers": true, "security_alerts": true}';
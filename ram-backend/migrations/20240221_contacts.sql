@@ -0,0 +1,10 @@
+-- Each wallet's saved recipients. Nicknames are unique per wallet, ignoring
+-- case, and name another wallet's handle
+CREATE TABLE IF NOT EXISTS contacts (
+    handle TEXT NOT NULL,
+    nickname TEXT NOT NULL,
+    contact_handle TEXT NOT NULL,
+    created_at_ms BIGINT NOT NULL
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_contacts_nickname ON contacts(handle, lower(nickname));
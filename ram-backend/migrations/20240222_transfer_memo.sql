@@ -0,0 +1,3 @@
+-- Memo a signed transfer carried on-chain (e.g. an invoice number), so
+-- payments can be matched to what they pay for
+ALTER TABLE ram_events ADD COLUMN IF NOT EXISTS memo TEXT;
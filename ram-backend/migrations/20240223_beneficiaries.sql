@@ -0,0 +1,21 @@
+-- Each wallet's inactivity beneficiary, maintained by the indexer from
+-- BeneficiarySet events. `warned_at_ms` is when the owner was last warned
+-- that the claim window is about to open
+CREATE TABLE IF NOT EXISTS beneficiaries (
+    handle TEXT PRIMARY KEY,
+    beneficiary_handle TEXT NOT NULL,
+    inactivity_timeout_ms BIGINT NOT NULL,
+    set_at_ms BIGINT NOT NULL,
+    warned_at_ms BIGINT
+);
+
+-- Notifications waiting for a wallet's owner, newest read first
+CREATE TABLE IF NOT EXISTS notifications (
+    id BIGSERIAL PRIMARY KEY,
+    handle TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    data JSONB NOT NULL,
+    created_at_ms BIGINT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_notifications_handle ON notifications(handle, created_at_ms DESC);
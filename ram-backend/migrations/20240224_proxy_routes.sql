@@ -0,0 +1,17 @@
+-- Proxy route aliases: backend paths forwarded to a differently named enclave
+-- route, edited through /admin/routes and reloaded by every backend without a
+-- restart. `route_class` picks the deadline (metadata, bio_auth or default).
+CREATE TABLE IF NOT EXISTS proxy_routes (
+    path TEXT PRIMARY KEY,
+    upstream_path TEXT NOT NULL,
+    route_class TEXT NOT NULL DEFAULT 'default',
+    updated_at_ms BIGINT NOT NULL
+);
+
+-- The legacy process_* names of the routes the enclave serves
+INSERT INTO proxy_routes (path, upstream_path, route_class, updated_at_ms) VALUES
+    ('/process_create_wallet', '/create_wallet', 'default', 0),
+    ('/process_link_address', '/link_address', 'default', 0),
+    ('/process_unlink_address', '/unlink_address', 'default', 0),
+    ('/process_bio_auth', '/bio_auth', 'bio_auth', 0)
+ON CONFLICT (path) DO NOTHING;
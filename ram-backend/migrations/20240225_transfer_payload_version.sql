@@ -0,0 +1,4 @@
+-- Layout version of the enclave payload a Transferred event was verified
+-- against (0 = wallet-signed). NULL for events emitted before the field existed,
+-- which were all v1.
+ALTER TABLE ram_events ADD COLUMN IF NOT EXISTS payload_version SMALLINT;
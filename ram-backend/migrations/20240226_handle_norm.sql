@@ -0,0 +1,51 @@
+-- Handles are matched case-insensitively and Unicode-normalized: NFKC, then
+-- lowercased (see handles.rs), so "Alice", "alice" and "ａｌｉｃｅ" are one wallet.
+
+-- Events keep handles exactly as emitted on-chain; lookups go through the
+-- normalized copies
+ALTER TABLE ram_events
+    ADD COLUMN IF NOT EXISTS handle_norm TEXT GENERATED ALWAYS AS (lower(normalize(handle, NFKC))) STORED,
+    ADD COLUMN IF NOT EXISTS from_handle_norm TEXT GENERATED ALWAYS AS (lower(normalize(from_handle, NFKC))) STORED,
+    ADD COLUMN IF NOT EXISTS to_handle_norm TEXT GENERATED ALWAYS AS (lower(normalize(to_handle, NFKC))) STORED;
+
+CREATE INDEX IF NOT EXISTS idx_handle_norm ON ram_events(handle_norm);
+CREATE INDEX IF NOT EXISTS idx_from_handle_norm ON ram_events(from_handle_norm);
+CREATE INDEX IF NOT EXISTS idx_to_handle_norm ON ram_events(to_handle_norm);
+
+-- Profiles, beneficiaries and linked addresses name wallets as they are
+-- registered on-chain, and enclave payloads are verified against that exact
+-- spelling, so those handles are kept as stored and matched through a
+-- normalized copy. Rows that only differed by case are merged into the most
+-- recently updated one
+DELETE FROM profiles p USING profiles newer
+WHERE lower(normalize(p.handle, NFKC)) = lower(normalize(newer.handle, NFKC))
+  AND (p.updated_at_ms, p.handle) < (newer.updated_at_ms, newer.handle);
+ALTER TABLE profiles ADD COLUMN IF NOT EXISTS handle_norm TEXT
+    GENERATED ALWAYS AS (lower(normalize(handle, NFKC))) STORED;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_profiles_handle_norm ON profiles(handle_norm);
+
+DELETE FROM beneficiaries b USING beneficiaries newer
+WHERE lower(normalize(b.handle, NFKC)) = lower(normalize(newer.handle, NFKC))
+  AND (b.set_at_ms, b.handle) < (newer.set_at_ms, newer.handle);
+ALTER TABLE beneficiaries ADD COLUMN IF NOT EXISTS handle_norm TEXT
+    GENERATED ALWAYS AS (lower(normalize(handle, NFKC))) STORED;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_beneficiaries_handle_norm ON beneficiaries(handle_norm);
+
+DELETE FROM linked_addresses l USING linked_addresses newer
+WHERE lower(normalize(l.handle, NFKC)) = lower(normalize(newer.handle, NFKC))
+  AND l.address = newer.address
+  AND (l.linked_at_ms, l.handle) < (newer.linked_at_ms, newer.handle);
+ALTER TABLE linked_addresses ADD COLUMN IF NOT EXISTS handle_norm TEXT
+    GENERATED ALWAYS AS (lower(normalize(handle, NFKC))) STORED;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_linked_addresses_handle_norm ON linked_addresses(handle_norm, address);
+
+-- Backend-only keys are stored normalized. A contact's handle stays as saved:
+-- it becomes the recipient of a signed transfer
+DELETE FROM contacts c USING contacts newer
+WHERE lower(normalize(c.handle, NFKC)) = lower(normalize(newer.handle, NFKC))
+  AND lower(c.nickname) = lower(newer.nickname)
+  AND (c.created_at_ms, c.handle) < (newer.created_at_ms, newer.handle);
+UPDATE contacts SET handle = lower(normalize(handle, NFKC));
+
+UPDATE notifications SET handle = lower(normalize(handle, NFKC));
+UPDATE onboardings SET handle = lower(normalize(handle, NFKC));
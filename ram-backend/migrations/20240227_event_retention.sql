@@ -0,0 +1,30 @@
+-- Per-wallet history is read newest first, so index each handle column
+-- together with the timestamp instead of on its own
+DROP INDEX IF EXISTS idx_handle_norm;
+DROP INDEX IF EXISTS idx_from_handle_norm;
+DROP INDEX IF EXISTS idx_to_handle_norm;
+CREATE INDEX IF NOT EXISTS idx_handle_norm_time ON ram_events(handle_norm, timestamp_ms DESC);
+CREATE INDEX IF NOT EXISTS idx_from_handle_norm_time ON ram_events(from_handle_norm, timestamp_ms DESC);
+CREATE INDEX IF NOT EXISTS idx_to_handle_norm_time ON ram_events(to_handle_norm, timestamp_ms DESC);
+
+-- Events older than EVENT_RETENTION_MONTHS are moved here by the archival
+-- job (see retention.rs), keeping ram_events, its search indexes and the
+-- indexer's inserts small. Columns are copied as plain columns, in the same
+-- order, so a row moves with `INSERT ... SELECT *`: a migration that adds a
+-- column to ram_events must add it here too
+CREATE TABLE IF NOT EXISTS ram_events_archive (
+    LIKE ram_events,
+    PRIMARY KEY (id),
+    CONSTRAINT unique_archived_tx_event UNIQUE (transaction_digest, event_type, handle)
+);
+
+CREATE INDEX IF NOT EXISTS idx_archive_handle_norm_time ON ram_events_archive(handle_norm, timestamp_ms DESC);
+CREATE INDEX IF NOT EXISTS idx_archive_from_handle_norm_time ON ram_events_archive(from_handle_norm, timestamp_ms DESC);
+CREATE INDEX IF NOT EXISTS idx_archive_to_handle_norm_time ON ram_events_archive(to_handle_norm, timestamp_ms DESC);
+
+-- Full history, for reads that must not change when events are archived
+-- (a wallet's event list, coin flows, bio-auths and last activity)
+CREATE OR REPLACE VIEW ram_events_history AS
+    SELECT * FROM ram_events
+    UNION ALL
+    SELECT * FROM ram_events_archive;
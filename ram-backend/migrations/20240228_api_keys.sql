@@ -0,0 +1,17 @@
+-- API keys a wallet's owner issues to bots and merchant integrations. Only a
+-- SHA-256 hash of each key is kept; `id` is the public part of the key and
+-- `scope` is `read` or `transfer`. Rotation replaces the hash under the same
+-- ID, and revoked keys are kept for their history
+CREATE TABLE IF NOT EXISTS api_keys (
+    id TEXT PRIMARY KEY,
+    handle TEXT NOT NULL,
+    key_hash TEXT NOT NULL,
+    scope TEXT NOT NULL CHECK (scope IN ('read', 'transfer')),
+    label TEXT NOT NULL,
+    created_at_ms BIGINT NOT NULL,
+    rotated_at_ms BIGINT,
+    last_used_at_ms BIGINT,
+    revoked_at_ms BIGINT
+);
+
+CREATE INDEX IF NOT EXISTS idx_api_keys_handle ON api_keys(handle, created_at_ms);
@@ -0,0 +1,29 @@
+-- Merchant webhooks: a wallet's owner registers one URL, and every transfer
+-- the wallet receives is POSTed to it, signed with the webhook's secret
+-- (HMAC-SHA256, see webhooks.rs). Saving a URL again replaces the secret
+CREATE TABLE IF NOT EXISTS webhooks (
+    handle TEXT PRIMARY KEY,
+    url TEXT NOT NULL,
+    secret TEXT NOT NULL,
+    created_at_ms BIGINT NOT NULL,
+    updated_at_ms BIGINT NOT NULL
+);
+
+-- Notifications queued by the indexer and sent by the delivery job, retried
+-- with exponential backoff until delivered or out of attempts ('dead'). `id`
+-- is derived from the event, so an event indexed twice is queued once and
+-- merchants can drop replays by ID
+CREATE TABLE IF NOT EXISTS webhook_deliveries (
+    id TEXT PRIMARY KEY,
+    handle TEXT NOT NULL,
+    payload JSONB NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'delivered', 'dead')),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt_ms BIGINT NOT NULL,
+    last_error TEXT,
+    created_at_ms BIGINT NOT NULL,
+    delivered_at_ms BIGINT
+);
+
+CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_due ON webhook_deliveries(status, next_attempt_ms);
+CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_handle ON webhook_deliveries(handle, created_at_ms DESC);
@@ -0,0 +1,23 @@
+-- Refunds a merchant starts for a transfer it received. The merchant then
+-- sends the refund like any transfer (bio-auth included), naming
+-- `refund_id`; the backend fills in the payer, amount and coin and signs
+-- `refund:<id>` into the memo, and the indexer marks the refund completed
+-- when that transfer lands, linking the two transactions. A payment can be
+-- refunded in parts, up to its amount
+CREATE TABLE IF NOT EXISTS refunds (
+    id TEXT PRIMARY KEY,
+    -- Merchant (the payment's recipient) and payer, normalized
+    handle TEXT NOT NULL,
+    payer_handle TEXT NOT NULL,
+    payment_tx_digest TEXT NOT NULL,
+    amount BIGINT NOT NULL CHECK (amount > 0),
+    coin_type TEXT NOT NULL,
+    reason TEXT,
+    status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'completed', 'cancelled')),
+    refund_tx_digest TEXT UNIQUE,
+    created_at_ms BIGINT NOT NULL,
+    completed_at_ms BIGINT
+);
+
+CREATE INDEX IF NOT EXISTS idx_refunds_payment ON refunds(payment_tx_digest);
+CREATE INDEX IF NOT EXISTS idx_refunds_handle ON refunds(handle, created_at_ms DESC);
@@ -0,0 +1,21 @@
+-- Travel-rule envelopes: originator/beneficiary information attached to a
+-- transfer, encrypted by the client (the backend never sees it in the
+-- clear). The hex SHA-256 of the ciphertext is signed into the transfer's
+-- memo as `travel:<hash>`, so the on-chain transfer commits to the envelope;
+-- the indexer fills in `tx_digest` when that transfer lands
+CREATE TABLE IF NOT EXISTS travel_rule_envelopes (
+    hash TEXT PRIMARY KEY,
+    -- Originator and beneficiary, normalized
+    from_handle TEXT NOT NULL,
+    to_handle TEXT NOT NULL,
+    amount BIGINT NOT NULL,
+    coin_type TEXT NOT NULL,
+    -- How the client encrypted it, e.g. `x25519-xsalsa20-poly1305`
+    scheme TEXT NOT NULL,
+    ciphertext TEXT NOT NULL,
+    tx_digest TEXT UNIQUE,
+    created_at_ms BIGINT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_travel_rule_from ON travel_rule_envelopes(from_handle, created_at_ms DESC);
+CREATE INDEX IF NOT EXISTS idx_travel_rule_to ON travel_rule_envelopes(to_handle, created_at_ms DESC);
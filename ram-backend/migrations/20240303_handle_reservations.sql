@@ -0,0 +1,12 @@
+-- Handles held while their user completes onboarding (`/api/handles/reserve`).
+-- Only the hash of the reservation token is kept. A reservation that has
+-- expired is free to be taken over by anyone; until then wallet creations
+-- of the handle must present the token
+CREATE TABLE IF NOT EXISTS handle_reservations (
+    -- Normalized handle
+    handle TEXT PRIMARY KEY,
+    -- Hex SHA-256 of the reservation token
+    token_hash TEXT NOT NULL,
+    created_at_ms BIGINT NOT NULL,
+    expires_at_ms BIGINT NOT NULL
+);
@@ -0,0 +1,9 @@
+-- Progress of the analytics export (see exports.rs): the highest
+-- ram_events id each sink has acknowledged. It only moves once a batch is
+-- accepted, so a crash or failed send repeats events rather than losing
+-- them, and a new sink starts from the first event
+CREATE TABLE IF NOT EXISTS event_export_cursors (
+    sink TEXT PRIMARY KEY,
+    last_event_id BIGINT NOT NULL,
+    updated_at_ms BIGINT NOT NULL
+);
@@ -0,0 +1,3 @@
+-- The public explorer aggregates one event type over a time window
+CREATE INDEX IF NOT EXISTS idx_event_type_time ON ram_events(event_type, timestamp_ms);
+CREATE INDEX IF NOT EXISTS idx_archive_event_type_time ON ram_events_archive(event_type, timestamp_ms);
@@ -0,0 +1,20 @@
+-- Feature flags gating risky features in the backend and the enclave, edited
+-- through /admin/flags. A flag is on where `enabled`, in the listed
+-- environments (all of them when empty) and for `rollout_percent` of
+-- subjects, bucketed by a hash of the flag and subject.
+CREATE TABLE IF NOT EXISTS feature_flags (
+    name TEXT PRIMARY KEY,
+    description TEXT NOT NULL DEFAULT '',
+    enabled BOOLEAN NOT NULL,
+    rollout_percent SMALLINT NOT NULL DEFAULT 100 CHECK (rollout_percent BETWEEN 0 AND 100),
+    environments TEXT[] NOT NULL DEFAULT '{}',
+    updated_at_ms BIGINT NOT NULL
+);
+
+-- The features gated so far, on everywhere as they were before flags
+INSERT INTO feature_flags (name, description, enabled, rollout_percent, environments, updated_at_ms) VALUES
+    ('gray_zone_retry', 'Enclave: second analysis pass for borderline stress scores (per handle)', TRUE, 100, '{}', 0),
+    ('duress_decoy', 'Enclave: sign duress as an OK-looking decoy where BIOAUTH_DURESS_DECOY is set (per handle)', TRUE, 100, '{}', 0),
+    ('hume_stream', 'Enclave: score over Hume''s streaming API before batch (share of analyses)', TRUE, 100, '{}', 0),
+    ('public_explorer', 'Backend: /api/explorer aggregates (per client address)', TRUE, 100, '{}', 0)
+ON CONFLICT (name) DO NOTHING;
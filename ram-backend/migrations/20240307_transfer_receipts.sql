@@ -0,0 +1,27 @@
+-- Proof-of-payment receipts: one per indexed transfer, written by the
+-- indexer and served countersigned by the backend's receipt key at
+-- GET /api/receipts/:tx_digest. Kept apart from ram_events so archival
+-- doesn't take receipts with it.
+CREATE TABLE IF NOT EXISTS transfer_receipts (
+    tx_digest TEXT PRIMARY KEY,
+    payer_handle TEXT NOT NULL,
+    payee_handle TEXT NOT NULL,
+    amount BIGINT NOT NULL,
+    coin_type TEXT NOT NULL,
+    memo TEXT,
+    timestamp_ms BIGINT NOT NULL,
+    created_at_ms BIGINT NOT NULL
+);
+
+-- Transfers indexed before receipts existed
+INSERT INTO transfer_receipts (tx_digest, payer_handle, payee_handle, amount, coin_type, memo, timestamp_ms, created_at_ms)
+SELECT transaction_digest, from_handle, to_handle, amount, coin_type, memo, timestamp_ms, timestamp_ms
+FROM (
+    SELECT transaction_digest, from_handle, to_handle, amount, coin_type, memo, timestamp_ms
+    FROM ram_events WHERE event_type = 'Transferred'
+    UNION ALL
+    SELECT transaction_digest, from_handle, to_handle, amount, coin_type, memo, timestamp_ms
+    FROM ram_events_archive WHERE event_type = 'Transferred'
+) transfers
+WHERE from_handle IS NOT NULL AND to_handle IS NOT NULL AND amount IS NOT NULL AND coin_type IS NOT NULL
+ON CONFLICT (tx_digest) DO NOTHING;
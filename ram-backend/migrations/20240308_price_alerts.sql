@@ -0,0 +1,16 @@
+-- Alerts on the USD value of a wallet's balance of a coin (see
+-- price_alerts.rs). `triggered_at_ms` is set while the condition holds and
+-- the owner has been notified, and cleared once the value is back past the
+-- threshold so the alert can fire again.
+CREATE TABLE IF NOT EXISTS price_alerts (
+    id BIGSERIAL PRIMARY KEY,
+    handle TEXT NOT NULL,
+    coin_type TEXT NOT NULL,
+    direction TEXT NOT NULL CHECK (direction IN ('below', 'above')),
+    threshold_usd DOUBLE PRECISION NOT NULL,
+    triggered_at_ms BIGINT,
+    created_at_ms BIGINT NOT NULL,
+    updated_at_ms BIGINT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_price_alerts_handle ON price_alerts(handle);
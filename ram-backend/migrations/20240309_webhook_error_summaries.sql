@@ -0,0 +1,12 @@
+-- Delivery errors are shown to the wallet's owner (GET /api/webhook), so
+-- only their kind is kept (see webhooks.rs): errors stored before then held
+-- the raw client error, which could describe the backend's network
+UPDATE webhook_deliveries
+SET last_error = 'answered ' || substring(last_error FROM 'endpoint answered (\d)') || 'xx'
+WHERE last_error ~ '^endpoint answered \d';
+
+UPDATE webhook_deliveries
+SET last_error = 'unreachable'
+WHERE last_error IS NOT NULL
+  AND last_error NOT LIKE 'answered %'
+  AND last_error <> 'webhook removed';
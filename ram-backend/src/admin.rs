@@ -0,0 +1,547 @@
+// Admin endpoints for RAM backend operators
+// All routes require `Authorization: Bearer <ADMIN_API_TOKEN>`; the ones that
+// change something are recorded in the audit trail (see audit.rs)
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use reqwest::Client;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tracing::{error, info, warn};
+
+use crate::aliases::{self, RouteClass};
+use crate::audit;
+use crate::database::{self, Database, DbPool};
+use crate::flags;
+use crate::indexer::IndexerProgress;
+use crate::models::{
+    AdminAuditEntry, AdminAuditQuery, AnalyticsStatus, BioAuthHistoryQuery, BioAuthHistoryResponse, FailedEvent, FailedEventsQuery, FeatureFlag,
+    FeatureFlagQuery, IndexerGap, RouteAlias, RouteAliasQuery, SaveFeatureFlagRequest, SaveRouteAliasRequest, SearchQuery,
+    SearchResponse,
+};
+use crate::rpc::EndpointMetrics;
+use crate::signing::sign_request;
+use crate::AppState;
+
+/// Verify the admin bearer token; admin routes are disabled when no token is configured
+pub fn require_admin(state: &AppState, headers: &HeaderMap) -> Result<(), StatusCode> {
+    let expected = match &state.admin_token {
+        Some(token) if !token.is_empty() => token,
+        _ => return Err(StatusCode::FORBIDDEN),
+    };
+
+    let provided = headers
+        .get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .unwrap_or("");
+
+    if constant_time_eq(provided.as_bytes(), expected.as_bytes()) {
+        Ok(())
+    } else {
+        Err(StatusCode::UNAUTHORIZED)
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Fetch BioAuth diagnostics for a request ID over the privileged enclave channel
+pub async fn get_bioauth_diagnostics(
+    State(state): State<Arc<AppState>>,
+    Path(request_id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    require_admin(&state, &headers)?;
+
+    info!("Fetching BioAuth diagnostics for request {}", request_id);
+    forward_privileged(
+        &state,
+        reqwest::Method::GET,
+        &format!("/bio_auth/diagnostics/{}", request_id),
+    )
+    .await
+}
+
+/// Reset a handle's enrolled voice baseline so the user can re-enroll
+pub async fn reset_voice_baseline(
+    State(state): State<Arc<AppState>>,
+    Path(handle): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    require_admin(&state, &headers)?;
+    let change = audit::begin(&state, &headers, "voice_baseline.reset", &handle, json!({})).await?;
+
+    info!("Resetting voice baseline for {}", handle);
+    let result = forward_privileged(
+        &state,
+        reqwest::Method::DELETE,
+        &format!("/bio_auth/baseline/{}", handle),
+    )
+    .await;
+    change.complete(&state, result.as_ref().map_or_else(|status| *status, Response::status)).await;
+    result
+}
+
+/// Call the enclaves' privileged channel, passing the first status and body
+/// other than 404 through
+///
+/// Diagnostics and voice baselines live on whichever enclave served the
+/// wallet, which may have changed since: lookups stop at the first enclave
+/// that has it, changes go to every enclave. Unreachable enclaves are skipped.
+async fn forward_privileged(
+    state: &AppState,
+    method: reqwest::Method,
+    path: &str,
+) -> Result<Response, StatusCode> {
+    let secret = state
+        .nautilus_diagnostics_secret
+        .as_deref()
+        .ok_or_else(|| {
+            warn!("Privileged enclave call requested but NAUTILUS_DIAGNOSTICS_SECRET is not set");
+            StatusCode::SERVICE_UNAVAILABLE
+        })?;
+
+    let mut answer: Option<(StatusCode, Value)> = None;
+    for enclave in state.enclaves.enclaves() {
+        let request = Client::new()
+            .request(method.clone(), format!("{}{}", enclave.url, path))
+            .bearer_auth(secret);
+        let response = match sign_request(request, state.nautilus_signing_key.as_deref(), method.as_str(), path, &[])
+            .send()
+            .await
+        {
+            Ok(response) => response,
+            Err(e) => {
+                error!("Failed to reach Nautilus privileged channel at {}: {}", enclave.url, e);
+                continue;
+            }
+        };
+
+        let status = StatusCode::from_u16(response.status().as_u16())
+            .unwrap_or(StatusCode::BAD_GATEWAY);
+        let body: Value = response.json().await.map_err(|e| {
+            error!("Failed to parse Nautilus privileged response from {}: {}", enclave.url, e);
+            StatusCode::BAD_GATEWAY
+        })?;
+        if answer.as_ref().is_none_or(|(first, _)| *first == StatusCode::NOT_FOUND) {
+            answer = Some((status, body));
+        }
+        if status != StatusCode::NOT_FOUND && method == reqwest::Method::GET {
+            break;
+        }
+    }
+
+    let (status, body) = answer.ok_or(StatusCode::BAD_GATEWAY)?;
+    Ok((status, Json(body)).into_response())
+}
+
+/// List events the indexer failed to process (`?status=pending|dead`)
+pub async fn list_failed_events(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<FailedEventsQuery>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<FailedEvent>>, StatusCode> {
+    require_admin(&state, &headers)?;
+
+    let events = Database::list_failed_events(&state.db, query.status.as_deref(), query.limit)
+        .await
+        .map_err(|e| {
+            error!("Failed to list failed events: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(events))
+}
+
+/// Reset a failed event's attempts so the indexer retries it on its next poll
+pub async fn requeue_failed_event(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    headers: HeaderMap,
+) -> Result<Json<FailedEvent>, StatusCode> {
+    require_admin(&state, &headers)?;
+    let change = audit::begin(&state, &headers, "failed_event.requeue", &id.to_string(), json!({})).await?;
+
+    let event = Database::requeue_failed_event(&state.db, id, chrono::Utc::now().timestamp_millis())
+        .await
+        .map_err(|e| {
+            error!("Failed to requeue failed event {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+        .and_then(|event| event.ok_or(StatusCode::NOT_FOUND));
+    change.complete(&state, event.as_ref().map_or_else(|status| *status, |_| StatusCode::OK)).await;
+    let event = event?;
+
+    info!("Requeued failed event {} ({}:{})", id, event.tx_digest, event.event_seq);
+    Ok(Json(event))
+}
+
+/// Audited administrative and configuration changes, newest first
+/// (`?action=&actor=&target=&limit=&offset=`)
+pub async fn list_admin_audit(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<AdminAuditQuery>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<AdminAuditEntry>>, StatusCode> {
+    require_admin(&state, &headers)?;
+
+    let entries = Database::list_admin_audit(
+        &state.db,
+        query.action.as_deref(),
+        query.actor.as_deref(),
+        query.target.as_deref(),
+        query.limit.clamp(1, 200),
+        query.offset.max(0),
+    )
+    .await
+    .map_err(|e| {
+        error!("Failed to list admin audit entries: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(entries))
+}
+
+/// Proxy route aliases as stored (see aliases.rs)
+pub async fn list_route_aliases(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<RouteAlias>>, StatusCode> {
+    require_admin(&state, &headers)?;
+
+    let aliases = Database::list_route_aliases(&state.db)
+        .await
+        .map_err(|e| {
+            error!("Failed to list route aliases: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(aliases))
+}
+
+/// Add or change a route alias, in effect on this backend at once
+pub async fn save_route_alias(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<SaveRouteAliasRequest>,
+) -> Result<Json<RouteAlias>, StatusCode> {
+    require_admin(&state, &headers)?;
+    if !aliases::is_valid_path(&request.path)
+        || !aliases::is_valid_path(&request.upstream_path)
+        || request.route_class.parse::<RouteClass>().is_err()
+    {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let alias = RouteAlias {
+        path: request.path,
+        upstream_path: request.upstream_path,
+        route_class: request.route_class,
+        updated_at_ms: chrono::Utc::now().timestamp_millis(),
+    };
+    let previous = Database::get_route_alias(&state.db, &alias.path).await.map_err(|e| {
+        error!("Failed to fetch route alias {}: {}", alias.path, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let change = audit::begin(
+        &state,
+        &headers,
+        "proxy_route.save",
+        &alias.path,
+        json!({ "previous": previous, "new": alias }),
+    )
+    .await?;
+
+    let saved = Database::upsert_route_alias(&state.db, &alias).await.map_err(|e| {
+        error!("Failed to save route alias {}: {}", alias.path, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    });
+    change.complete(&state, saved.as_ref().map_or_else(|status| *status, |_| StatusCode::OK)).await;
+    saved?;
+
+    info!("Route alias {} -> {} ({})", alias.path, alias.upstream_path, alias.route_class);
+    reload_route_aliases(&state).await;
+    Ok(Json(alias))
+}
+
+/// Remove a route alias (`?path=`), in effect on this backend at once
+pub async fn delete_route_alias(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<RouteAliasQuery>,
+    headers: HeaderMap,
+) -> Result<Json<RouteAlias>, StatusCode> {
+    require_admin(&state, &headers)?;
+    let change = audit::begin(&state, &headers, "proxy_route.delete", &query.path, json!({})).await?;
+
+    let removed = Database::delete_route_alias(&state.db, &query.path)
+        .await
+        .map_err(|e| {
+            error!("Failed to delete route alias {}: {}", query.path, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+        .and_then(|alias| alias.ok_or(StatusCode::NOT_FOUND));
+    change.complete(&state, removed.as_ref().map_or_else(|status| *status, |_| StatusCode::OK)).await;
+    let removed = removed?;
+
+    info!("Removed route alias {} -> {}", removed.path, removed.upstream_path);
+    reload_route_aliases(&state).await;
+    Ok(Json(removed))
+}
+
+/// Pick up a route alias change without waiting for the periodic reload
+async fn reload_route_aliases(state: &AppState) {
+    if let Err(e) = state.route_aliases.reload(&state.db).await {
+        error!("Failed to reload route aliases: {}", e);
+    }
+}
+
+/// Feature flags as stored (see flags.rs)
+pub async fn list_feature_flags(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<FeatureFlag>>, StatusCode> {
+    require_admin(&state, &headers)?;
+
+    let flags = Database::list_feature_flags(&state.db)
+        .await
+        .map_err(|e| {
+            error!("Failed to list feature flags: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(flags))
+}
+
+/// Add or change a feature flag, in effect on this backend at once
+pub async fn save_feature_flag(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<SaveFeatureFlagRequest>,
+) -> Result<Json<FeatureFlag>, StatusCode> {
+    require_admin(&state, &headers)?;
+    if !flags::is_valid_name(&request.name)
+        || !(0..=100).contains(&request.rollout_percent)
+        || !request.environments.iter().all(|env| flags::is_valid_name(env))
+    {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let flag = FeatureFlag {
+        name: request.name,
+        description: request.description,
+        enabled: request.enabled,
+        rollout_percent: request.rollout_percent,
+        environments: request.environments,
+        updated_at_ms: chrono::Utc::now().timestamp_millis(),
+    };
+    let previous = Database::get_feature_flag(&state.db, &flag.name).await.map_err(|e| {
+        error!("Failed to fetch feature flag {}: {}", flag.name, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let change = audit::begin(
+        &state,
+        &headers,
+        "feature_flag.save",
+        &flag.name,
+        json!({ "previous": previous, "new": flag }),
+    )
+    .await?;
+
+    let saved = Database::upsert_feature_flag(&state.db, &flag).await.map_err(|e| {
+        error!("Failed to save feature flag {}: {}", flag.name, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    });
+    change.complete(&state, saved.as_ref().map_or_else(|status| *status, |_| StatusCode::OK)).await;
+    saved?;
+
+    info!(
+        "Feature flag {} {} for {}% in {:?}",
+        flag.name,
+        if flag.enabled { "enabled" } else { "disabled" },
+        flag.rollout_percent,
+        flag.environments
+    );
+    reload_feature_flags(&state).await;
+    Ok(Json(flag))
+}
+
+/// Remove a feature flag (`?name=`), leaving its feature at its default
+pub async fn delete_feature_flag(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<FeatureFlagQuery>,
+    headers: HeaderMap,
+) -> Result<Json<FeatureFlag>, StatusCode> {
+    require_admin(&state, &headers)?;
+    let change = audit::begin(&state, &headers, "feature_flag.delete", &query.name, json!({})).await?;
+
+    let removed = Database::delete_feature_flag(&state.db, &query.name)
+        .await
+        .map_err(|e| {
+            error!("Failed to delete feature flag {}: {}", query.name, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+        .and_then(|flag| flag.ok_or(StatusCode::NOT_FOUND));
+    change.complete(&state, removed.as_ref().map_or_else(|status| *status, |_| StatusCode::OK)).await;
+    let removed = removed?;
+
+    info!("Removed feature flag {}", removed.name);
+    reload_feature_flags(&state).await;
+    Ok(Json(removed))
+}
+
+/// Pick up a feature flag change without waiting for the periodic reload
+async fn reload_feature_flags(state: &AppState) {
+    if let Err(e) = state.feature_flags.reload(&state.db).await {
+        error!("Failed to reload feature flags: {}", e);
+    }
+}
+
+/// Per-query latency counters and pool utilization
+pub async fn get_db_metrics(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Json<Value>, StatusCode> {
+    require_admin(&state, &headers)?;
+
+    let pool_stats = |pool: &DbPool| json!({ "size": pool.size(), "idle": pool.num_idle() });
+    Ok(Json(json!({
+        "queries": database::query_stats(),
+        "pools": {
+            "primary": pool_stats(&state.db),
+            "read": pool_stats(&state.read_db),
+        },
+    })))
+}
+
+/// Health and latency of each configured Sui RPC endpoint
+pub async fn get_rpc_metrics(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<EndpointMetrics>>, StatusCode> {
+    require_admin(&state, &headers)?;
+    Ok(Json(state.sui_rpc.metrics()))
+}
+
+/// Indexer position, throughput and estimated catch-up time
+pub async fn get_indexer_progress(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Json<IndexerProgress>, StatusCode> {
+    require_admin(&state, &headers)?;
+    Ok(Json(state.indexer_throttle.report(&state.db, chrono::Utc::now().timestamp_millis())))
+}
+
+/// Compare the analytics store with ram_events_history now, and report its state
+pub async fn check_analytics_store(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Json<AnalyticsStatus>, StatusCode> {
+    require_admin(&state, &headers)?;
+    let store = state.analytics.as_ref().ok_or(StatusCode::NOT_FOUND)?;
+
+    store.check(&state.db, chrono::Utc::now().timestamp_millis()).await.map_err(|e| {
+        error!("Failed to check the analytics store: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(Json(store.status()))
+}
+
+/// History gaps the indexer skipped after its cursor was pruned
+pub async fn list_indexer_gaps(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<IndexerGap>>, StatusCode> {
+    require_admin(&state, &headers)?;
+
+    let gaps = Database::list_indexer_gaps(&state.db, 100)
+        .await
+        .map_err(|e| {
+            error!("Failed to list indexer gaps: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(gaps))
+}
+
+/// Shortest query worth searching; trigram matching needs at least 3 characters
+const MIN_SEARCH_LEN: usize = 3;
+
+/// Locate wallets and transactions by partial handle, digest or event contents
+///
+/// Mounted at `/api/search` for support tooling but admin-only, since it
+/// searches across every wallet's history.
+pub async fn search(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<SearchQuery>,
+    headers: HeaderMap,
+) -> Result<Json<SearchResponse>, StatusCode> {
+    require_admin(&state, &headers)?;
+
+    let q = query.q.trim();
+    if q.chars().count() < MIN_SEARCH_LEN {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let limit = query.limit.clamp(1, 200);
+
+    let handles = Database::search_handles(&state.read_db, q, limit)
+        .await
+        .map_err(|e| {
+            error!("Handle search failed: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    let events = Database::search_events(&state.read_db, q, limit)
+        .await
+        .map_err(|e| {
+            error!("Event search failed: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(SearchResponse {
+        query: q.to_string(),
+        handles,
+        events,
+    }))
+}
+
+/// A wallet's past bio-auths with their results and transactions
+///
+/// Mounted at `/api/bioauth_history/:handle` so a user can go through it with
+/// support and spot someone trying to move their funds. Admin-only like
+/// search: failed attempts are exactly what a coercer would want to watch.
+pub async fn bioauth_history(
+    State(state): State<Arc<AppState>>,
+    Path(handle): Path<String>,
+    Query(query): Query<BioAuthHistoryQuery>,
+    headers: HeaderMap,
+) -> Result<Json<BioAuthHistoryResponse>, StatusCode> {
+    require_admin(&state, &headers)?;
+
+    let limit = query.limit.clamp(1, 200);
+    let offset = query.offset.max(0);
+    let attempts = Database::get_bioauth_history(&state.read_db, &handle, limit, offset)
+        .await
+        .map_err(|e| {
+            error!("Failed to fetch bio-auth history: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    let failed_total = Database::count_failed_bioauths(&state.read_db, &handle)
+        .await
+        .map_err(|e| {
+            error!("Failed to count failed bio-auths: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(BioAuthHistoryResponse {
+        handle,
+        attempts,
+        failed_total,
+        limit,
+        offset,
+    }))
+}
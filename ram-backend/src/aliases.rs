@@ -0,0 +1,123 @@
+// Proxy route aliases
+// Backend paths forwarded to a differently named enclave route (the legacy
+// `/process_*` names, or a path kept stable while the enclave renames one) live
+// in `proxy_routes` rather than in the router: operators edit them through
+// /admin/routes, the backend that took the change reloads at once and the
+// others within PROXY_ROUTES_RELOAD_SECS. Fixed routes take precedence, so an
+// alias can only add a path, never hijack an existing one.
+
+use anyhow::Result;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tracing::{error, info};
+
+use crate::config::ProxyConfig;
+use crate::database::{Database, DbPool};
+use crate::models::RouteAlias;
+
+/// Deadline an aliased route gets, as for the fixed route classes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RouteClass {
+    Metadata,
+    BioAuth,
+    Default,
+}
+
+impl RouteClass {
+    pub fn timeout(&self, proxy: &ProxyConfig) -> Duration {
+        match self {
+            RouteClass::Metadata => proxy.metadata_timeout,
+            RouteClass::BioAuth => proxy.bio_auth_timeout,
+            RouteClass::Default => proxy.default_timeout,
+        }
+    }
+}
+
+impl FromStr for RouteClass {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "metadata" => Ok(Self::Metadata),
+            "bio_auth" => Ok(Self::BioAuth),
+            "default" => Ok(Self::Default),
+            other => Err(format!("expected 'metadata', 'bio_auth' or 'default', got '{}'", other)),
+        }
+    }
+}
+
+/// Whether `path` can be an alias or its target: absolute, without a query,
+/// host or `..` segment
+pub fn is_valid_path(path: &str) -> bool {
+    path.starts_with('/')
+        && path.len() > 1
+        && !path.contains(['?', '#', '\\'])
+        && !path.contains("//")
+        && !path.split('/').any(|segment| segment == "..")
+}
+
+/// The aliases currently in effect, by path
+#[derive(Default)]
+pub struct RouteAliases {
+    routes: RwLock<HashMap<String, RouteAlias>>,
+}
+
+impl RouteAliases {
+    /// Where a request to `path` goes, if it's an alias
+    pub fn resolve(&self, path: &str) -> Option<RouteAlias> {
+        self.routes.read().unwrap().get(path).cloned()
+    }
+
+    /// Replace the aliases with the ones in the database, returning how many there are
+    pub async fn reload(&self, pool: &DbPool) -> Result<usize> {
+        let aliases = Database::list_route_aliases(pool).await?;
+        let count = aliases.len();
+        *self.routes.write().unwrap() = aliases.into_iter().map(|alias| (alias.path.clone(), alias)).collect();
+        Ok(count)
+    }
+
+    /// Reload the aliases every `interval`, for as long as it runs
+    /// (supervised, see supervisor.rs)
+    pub async fn reload_every(self: Arc<Self>, pool: DbPool, interval: Duration) -> Result<()> {
+        loop {
+            tokio::time::sleep(interval).await;
+            if let Err(e) = self.reload(&pool).await {
+                error!("Failed to reload route aliases: {}", e);
+            }
+        }
+    }
+
+    /// Load the aliases at startup
+    pub async fn load(pool: &DbPool) -> Result<Self> {
+        let aliases = Self::default();
+        let count = aliases.reload(pool).await?;
+        info!("Loaded {} proxy route aliases", count);
+        Ok(aliases)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alias_paths_stay_on_the_enclave() {
+        assert!(is_valid_path("/process_create_wallet"));
+        assert!(is_valid_path("/bio_auth/challenge"));
+        assert!(!is_valid_path("/"));
+        assert!(!is_valid_path("create_wallet"));
+        assert!(!is_valid_path("//evil.example/x"));
+        assert!(!is_valid_path("/admin/../create_wallet"));
+        assert!(!is_valid_path("/create_wallet?debug=1"));
+    }
+
+    #[test]
+    fn test_route_classes() {
+        let proxy = ProxyConfig::default();
+        assert_eq!("bio_auth".parse::<RouteClass>().unwrap().timeout(&proxy), proxy.bio_auth_timeout);
+        assert_eq!("default".parse::<RouteClass>().unwrap(), RouteClass::Default);
+        assert!("slow".parse::<RouteClass>().is_err());
+    }
+}
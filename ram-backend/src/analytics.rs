@@ -0,0 +1,589 @@
+// Embedded analytics store
+// With ANALYTICS_STORE_DIR set, the backend keeps its own copy of the event
+// columns the explorer aggregates over, as Parquet segment files named after
+// the id range they hold (`events-<first id>-<last id>.parquet`, zero-padded),
+// and computes /api/explorer/* from them instead of scanning
+// ram_events_history:
+// - dual write: the indexer hands every event it stores to the store, which
+//   buffers them and writes a segment every ANALYTICS_FLUSH_INTERVAL_SECS
+// - at boot, and after a failed write or an overflowing buffer, the store
+//   first catches up from ram_events_history (the events after its last id),
+//   so nothing stored while it wasn't listening is missed
+// - compaction merges runs of small segments into ones of up to
+//   ANALYTICS_SEGMENT_ROWS rows every ANALYTICS_COMPACTION_INTERVAL_SECS
+// - the consistency checker compares per-day counts of each event type over
+//   the explorer's longest window with ram_events_history, after catching up
+//   and then every ANALYTICS_CHECK_INTERVAL_SECS (or on
+//   GET /admin/analytics_consistency). While they differ the explorer goes
+//   back to Postgres; deleting the directory and restarting rebuilds it.
+//   Events another backend's indexer stored first never reach this one's
+//   buffer, so with several indexers expect the checker to flag them.
+// Postgres stays the source of truth: until the store has caught up, or when
+// reading it fails, the explorer queries Postgres as before.
+
+use anyhow::{anyhow, Result};
+use parquet::data_type::{ByteArray, ByteArrayType, Int64Type};
+use parquet::file::properties::WriterProperties;
+use parquet::file::reader::{FileReader, SerializedFileReader};
+use parquet::file::writer::SerializedFileWriter;
+use parquet::record::Field;
+use parquet::schema::parser::parse_message_type;
+use std::collections::{BTreeMap, HashSet};
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tracing::{error, info, warn};
+
+use crate::config::AnalyticsStoreConfig;
+use crate::database::{Database, DbPool};
+use crate::explorer::{window_start_ms, MAX_EXPLORER_DAYS};
+use crate::handles::normalize_handle;
+use crate::models::{
+    AnalyticsConsistency, AnalyticsRow, AnalyticsStatus, CountMismatch, DailyTransferVolume, DailyWalletCreations,
+    RamEvent,
+};
+
+/// Layout of a segment file; columns are read back in this order
+const SCHEMA: &str = "
+    message ram_event {
+        REQUIRED INT64 id;
+        REQUIRED BINARY event_type (UTF8);
+        REQUIRED INT64 timestamp_ms;
+        OPTIONAL BINARY coin_type (UTF8);
+        OPTIONAL INT64 amount;
+        OPTIONAL BINARY from_handle_norm (UTF8);
+        OPTIONAL BINARY to_handle_norm (UTF8);
+    }
+";
+/// Events read per query while catching up from Postgres
+const CATCH_UP_BATCH_SIZE: i64 = 5_000;
+/// Events buffered before the store drops them and catches up from Postgres instead
+const MAX_PENDING_ROWS: usize = 100_000;
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// File name of the segment holding `first_id` to `last_id`; zero-padded so
+/// names sort in id order
+pub fn segment_name(first_id: i64, last_id: i64) -> String {
+    format!("events-{:020}-{:020}.parquet", first_id, last_id)
+}
+
+/// Id range of a segment file name
+fn parse_segment_name(name: &str) -> Option<(i64, i64)> {
+    let (first, last) = name.strip_prefix("events-")?.strip_suffix(".parquet")?.split_once('-')?;
+    Some((first.parse().ok()?, last.parse().ok()?))
+}
+
+/// A segment file, and what queries need to know about it without reading it
+#[derive(Debug, Clone)]
+struct Segment {
+    path: PathBuf,
+    first_id: i64,
+    last_id: i64,
+    rows: usize,
+    max_timestamp_ms: i64,
+    wallets_created: i64,
+}
+
+impl Segment {
+    fn of(path: PathBuf, rows: &[AnalyticsRow]) -> Self {
+        Self {
+            path,
+            first_id: rows.first().map_or(0, |row| row.id),
+            last_id: rows.last().map_or(0, |row| row.id),
+            rows: rows.len(),
+            max_timestamp_ms: rows.iter().map(|row| row.timestamp_ms).max().unwrap_or(i64::MIN),
+            wallets_created: rows.iter().filter(|row| row.event_type == "WalletCreated").count() as i64,
+        }
+    }
+}
+
+/// Write `rows` (sorted by id) to a new segment in `dir`
+fn write_segment(dir: &Path, rows: &[AnalyticsRow]) -> Result<Segment> {
+    let (Some(first), Some(last)) = (rows.first(), rows.last()) else {
+        return Err(anyhow!("empty segment"));
+    };
+    let path = dir.join(segment_name(first.id, last.id));
+    // Written aside and renamed, so a crash never leaves half a segment
+    let partial = path.with_extension("parquet.tmp");
+
+    let schema = Arc::new(parse_message_type(SCHEMA)?);
+    let mut writer =
+        SerializedFileWriter::new(File::create(&partial)?, schema, Arc::new(WriterProperties::builder().build()))?;
+    let mut group = writer.next_row_group()?;
+    let mut column = 0;
+    while let Some(mut writer) = group.next_column()? {
+        match column {
+            0 => longs(&mut writer, rows.iter().map(|row| Some(row.id)))?,
+            1 => strings(&mut writer, rows.iter().map(|row| Some(row.event_type.as_str())))?,
+            2 => longs(&mut writer, rows.iter().map(|row| Some(row.timestamp_ms)))?,
+            3 => strings(&mut writer, rows.iter().map(|row| row.coin_type.as_deref()))?,
+            4 => longs(&mut writer, rows.iter().map(|row| row.amount))?,
+            5 => strings(&mut writer, rows.iter().map(|row| row.from_handle_norm.as_deref()))?,
+            _ => strings(&mut writer, rows.iter().map(|row| row.to_handle_norm.as_deref()))?,
+        }
+        writer.close()?;
+        column += 1;
+    }
+    group.close()?;
+    writer.close()?;
+
+    std::fs::rename(&partial, &path)?;
+    Ok(Segment::of(path, rows))
+}
+
+/// Definition levels of an optional column (1 = present); a required one ignores them
+fn definition_levels<T>(values: &[Option<T>]) -> Vec<i16> {
+    values.iter().map(|value| i16::from(value.is_some())).collect()
+}
+
+fn longs(
+    writer: &mut parquet::file::writer::SerializedColumnWriter<'_>,
+    values: impl Iterator<Item = Option<i64>>,
+) -> Result<()> {
+    let values: Vec<Option<i64>> = values.collect();
+    let present: Vec<i64> = values.iter().flatten().copied().collect();
+    writer.typed::<Int64Type>().write_batch(&present, Some(&definition_levels(&values)), None)?;
+    Ok(())
+}
+
+fn strings<'a>(
+    writer: &mut parquet::file::writer::SerializedColumnWriter<'_>,
+    values: impl Iterator<Item = Option<&'a str>>,
+) -> Result<()> {
+    let values: Vec<Option<&str>> = values.collect();
+    let present: Vec<ByteArray> = values.iter().flatten().map(|value| ByteArray::from(*value)).collect();
+    writer.typed::<ByteArrayType>().write_batch(&present, Some(&definition_levels(&values)), None)?;
+    Ok(())
+}
+
+fn read_segment(path: &Path) -> Result<Vec<AnalyticsRow>> {
+    let reader = SerializedFileReader::new(File::open(path)?)?;
+    reader.get_row_iter(None)?.map(|row| row_of(row?)).collect()
+}
+
+fn row_of(row: parquet::record::Row) -> Result<AnalyticsRow> {
+    let mut fields = row.into_columns().into_iter().map(|(_, field)| field);
+    let mut next = || fields.next().ok_or_else(|| anyhow!("segment row is missing columns"));
+    Ok(AnalyticsRow {
+        id: required(long(next()?)?)?,
+        event_type: required(string(next()?)?)?,
+        timestamp_ms: required(long(next()?)?)?,
+        coin_type: string(next()?)?,
+        amount: long(next()?)?,
+        from_handle_norm: string(next()?)?,
+        to_handle_norm: string(next()?)?,
+    })
+}
+
+fn required<T>(value: Option<T>) -> Result<T> {
+    value.ok_or_else(|| anyhow!("segment row is missing a required value"))
+}
+
+fn long(field: Field) -> Result<Option<i64>> {
+    match field {
+        Field::Long(value) => Ok(Some(value)),
+        Field::Null => Ok(None),
+        other => Err(anyhow!("expected an INT64, found {:?}", other)),
+    }
+}
+
+fn string(field: Field) -> Result<Option<String>> {
+    match field {
+        Field::Str(value) => Ok(Some(value)),
+        Field::Null => Ok(None),
+        other => Err(anyhow!("expected a UTF8 string, found {:?}", other)),
+    }
+}
+
+/// Parquet copy of the event columns the explorer aggregates over
+pub struct AnalyticsStore {
+    config: AnalyticsStoreConfig,
+    dir: PathBuf,
+    /// In id order, without overlaps
+    segments: Mutex<Vec<Segment>>,
+    /// Events stored by the indexer since the last flush
+    pending: Mutex<Vec<AnalyticsRow>>,
+    caught_up: AtomicBool,
+    last_check: Mutex<Option<AnalyticsConsistency>>,
+}
+
+impl AnalyticsStore {
+    /// The store in `config.dir`, with the segments already there; None if
+    /// no directory is configured
+    pub fn open(config: AnalyticsStoreConfig) -> Result<Option<Self>> {
+        let Some(dir) = config.dir.clone() else {
+            return Ok(None);
+        };
+        std::fs::create_dir_all(&dir)?;
+
+        let mut found = Vec::new();
+        for entry in std::fs::read_dir(&dir)? {
+            let path = entry?.path();
+            let name = path.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+            if name.ends_with(".parquet.tmp") {
+                std::fs::remove_file(&path)?;
+            } else if let Some((first_id, last_id)) = parse_segment_name(name) {
+                found.push((first_id, last_id, path));
+            }
+        }
+        // Longest first, so the output of a compaction that crashed before
+        // removing its inputs wins over them
+        found.sort_by_key(|(first_id, last_id, _)| (*first_id, std::cmp::Reverse(*last_id)));
+
+        let mut segments: Vec<Segment> = Vec::new();
+        for (_, last_id, path) in found {
+            if segments.last().is_some_and(|kept| last_id <= kept.last_id) {
+                std::fs::remove_file(&path)?;
+                continue;
+            }
+            let rows = read_segment(&path).map_err(|e| anyhow!("Unreadable segment {}: {}", path.display(), e))?;
+            segments.push(Segment::of(path, &rows));
+        }
+        info!("Analytics store at {} opened with {} segments", dir.display(), segments.len());
+
+        Ok(Some(Self {
+            config,
+            dir,
+            segments: Mutex::new(segments),
+            pending: Mutex::default(),
+            caught_up: AtomicBool::new(false),
+            last_check: Mutex::default(),
+        }))
+    }
+
+    /// Buffer an event the indexer just stored under `id` (0 if it was a repeat)
+    pub fn record(&self, id: i64, event: &RamEvent) {
+        if id == 0 {
+            return;
+        }
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        if pending.len() >= MAX_PENDING_ROWS {
+            // Flushes are failing; Postgres has them all anyway
+            if self.caught_up.swap(false, Ordering::Relaxed) {
+                warn!("Analytics store buffer is full, catching up from Postgres instead");
+            }
+            pending.clear();
+            return;
+        }
+        pending.push(AnalyticsRow {
+            id,
+            event_type: event.event_type.clone(),
+            timestamp_ms: event.timestamp.timestamp_millis(),
+            coin_type: event.coin_type.clone(),
+            amount: event.amount,
+            from_handle_norm: event.from_handle.as_deref().map(normalize_handle),
+            to_handle_norm: event.to_handle.as_deref().map(normalize_handle),
+        });
+    }
+
+    /// Last event id written to a segment (0 if none)
+    pub fn last_id(&self) -> i64 {
+        self.segments.lock().unwrap().last().map_or(0, |segment| segment.last_id)
+    }
+
+    /// Whether explorer aggregates can be computed from the store
+    pub fn is_serving(&self) -> bool {
+        let consistent = self.last_check.lock().unwrap().as_ref().is_none_or(|check| check.mismatches.is_empty());
+        self.caught_up.load(Ordering::Relaxed) && consistent
+    }
+
+    pub fn status(&self) -> AnalyticsStatus {
+        let segments = self.segments.lock().unwrap();
+        AnalyticsStatus {
+            serving: self.is_serving(),
+            caught_up: self.caught_up.load(Ordering::Relaxed),
+            last_id: segments.last().map_or(0, |segment| segment.last_id),
+            segments: segments.len(),
+            rows: segments.iter().map(|segment| segment.rows).sum(),
+            pending: self.pending.lock().unwrap().len(),
+            last_check: self.last_check.lock().unwrap().clone(),
+        }
+    }
+
+    /// Write the rows after the last id as a new segment
+    fn append(&self, mut rows: Vec<AnalyticsRow>) -> Result<usize> {
+        let last_id = self.last_id();
+        rows.retain(|row| row.id > last_id);
+        rows.sort_by_key(|row| row.id);
+        rows.dedup_by_key(|row| row.id);
+        if rows.is_empty() {
+            return Ok(0);
+        }
+        let segment = write_segment(&self.dir, &rows)?;
+        self.segments.lock().unwrap().push(segment);
+        Ok(rows.len())
+    }
+
+    /// Write the buffered events out
+    fn flush(&self) -> Result<usize> {
+        let rows = std::mem::take(&mut *self.pending.lock().unwrap());
+        self.append(rows)
+    }
+
+    /// Merge runs of adjacent segments into ones of up to `segment_rows` rows;
+    /// returns how many segments were merged away
+    fn compact(&self) -> Result<usize> {
+        let snapshot = self.segments.lock().unwrap().clone();
+        let mut runs: Vec<Vec<Segment>> = Vec::new();
+        let mut run: Vec<Segment> = Vec::new();
+        for segment in snapshot {
+            let rows: usize = run.iter().map(|s| s.rows).sum();
+            if rows + segment.rows > self.config.segment_rows {
+                runs.push(std::mem::take(&mut run));
+            }
+            run.push(segment);
+        }
+        runs.push(run);
+
+        let mut merged_away = 0;
+        for run in runs.into_iter().filter(|run| run.len() > 1) {
+            let mut rows = Vec::new();
+            for segment in &run {
+                rows.extend(read_segment(&segment.path)?);
+            }
+            let merged = write_segment(&self.dir, &rows)?;
+            {
+                let mut segments = self.segments.lock().unwrap();
+                let start = segments.iter().position(|s| s.path == run[0].path).ok_or_else(|| anyhow!("segment vanished"))?;
+                segments.splice(start..start + run.len(), [merged]);
+            }
+            for segment in &run {
+                std::fs::remove_file(&segment.path)?;
+            }
+            merged_away += run.len() - 1;
+        }
+        Ok(merged_away)
+    }
+
+    /// Call `visit` on every stored row from `since_ms` on, up to `up_to_id`
+    fn scan(&self, since_ms: i64, up_to_id: i64, mut visit: impl FnMut(&AnalyticsRow)) -> Result<()> {
+        let segments = self.segments.lock().unwrap().clone();
+        for segment in segments.iter().filter(|s| s.max_timestamp_ms >= since_ms && s.first_id <= up_to_id) {
+            for row in read_segment(&segment.path)? {
+                if row.timestamp_ms >= since_ms && row.id <= up_to_id {
+                    visit(&row);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Run a blocking part of the store off the async workers
+    async fn blocking<T: Send + 'static>(
+        self: &Arc<Self>,
+        work: impl FnOnce(&Self) -> Result<T> + Send + 'static,
+    ) -> Result<T> {
+        let store = self.clone();
+        tokio::task::spawn_blocking(move || work(&store)).await?
+    }
+
+    /// Same figures as `Database::daily_transfer_volumes`
+    pub async fn daily_transfer_volumes(self: &Arc<Self>, since_ms: i64) -> Result<Vec<DailyTransferVolume>> {
+        self.blocking(move |store| {
+            #[derive(Default)]
+            struct Bucket {
+                transfers: i64,
+                volume: i128,
+                senders: HashSet<String>,
+                recipients: HashSet<String>,
+            }
+            let mut buckets: BTreeMap<(i64, String), Bucket> = BTreeMap::new();
+            store.scan(since_ms, i64::MAX, |row| {
+                let (Some(coin_type), "Transferred") = (&row.coin_type, row.event_type.as_str()) else {
+                    return;
+                };
+                let bucket = buckets.entry(((row.timestamp_ms / DAY_MS) * DAY_MS, coin_type.clone())).or_default();
+                bucket.transfers += 1;
+                bucket.volume += i128::from(row.amount.unwrap_or(0));
+                bucket.senders.extend(row.from_handle_norm.clone());
+                bucket.recipients.extend(row.to_handle_norm.clone());
+            })?;
+            Ok(buckets
+                .into_iter()
+                .map(|((day_ms, coin_type), bucket)| DailyTransferVolume {
+                    day_ms,
+                    coin_type,
+                    transfers: bucket.transfers,
+                    volume: bucket.volume.to_string(),
+                    senders: bucket.senders.len() as i64,
+                    recipients: bucket.recipients.len() as i64,
+                })
+                .collect())
+        })
+        .await
+    }
+
+    /// Same figures as `Database::daily_wallet_creations`
+    pub async fn daily_wallet_creations(self: &Arc<Self>, since_ms: i64) -> Result<Vec<DailyWalletCreations>> {
+        self.blocking(move |store| {
+            let mut days: BTreeMap<i64, i64> = BTreeMap::new();
+            store.scan(since_ms, i64::MAX, |row| {
+                if row.event_type == "WalletCreated" {
+                    *days.entry((row.timestamp_ms / DAY_MS) * DAY_MS).or_default() += 1;
+                }
+            })?;
+            Ok(days.into_iter().map(|(day_ms, created)| DailyWalletCreations { day_ms, created }).collect())
+        })
+        .await
+    }
+
+    /// Same figure as `Database::count_wallets`
+    pub fn count_wallets(&self) -> i64 {
+        self.segments.lock().unwrap().iter().map(|segment| segment.wallets_created).sum()
+    }
+
+    /// Read what Postgres has after the last id into new segments
+    pub async fn catch_up(self: &Arc<Self>, pool: &DbPool) -> Result<usize> {
+        let mut after = self.last_id();
+        let mut batch = Vec::new();
+        let mut written = 0;
+        loop {
+            let rows = Database::get_analytics_rows_after(pool, after, CATCH_UP_BATCH_SIZE).await?;
+            let done = (rows.len() as i64) < CATCH_UP_BATCH_SIZE;
+            after = rows.last().map_or(after, |row| row.id);
+            batch.extend(rows);
+            if done || batch.len() >= self.config.segment_rows {
+                let rows = std::mem::take(&mut batch);
+                written += self.blocking(move |store| store.append(rows)).await?;
+            }
+            if done {
+                break;
+            }
+        }
+        self.caught_up.store(true, Ordering::Relaxed);
+        Ok(written)
+    }
+
+    /// Compare per-day counts of each event type over the explorer's longest
+    /// window with ram_events_history
+    pub async fn check(self: &Arc<Self>, pool: &DbPool, now_ms: i64) -> Result<AnalyticsConsistency> {
+        let since_ms = window_start_ms(now_ms, MAX_EXPLORER_DAYS);
+        let up_to_id = self.last_id();
+        let primary = Database::daily_event_counts(pool, since_ms, up_to_id).await?;
+        let stored = self
+            .blocking(move |store| {
+                let mut counts: BTreeMap<(i64, String), i64> = BTreeMap::new();
+                store.scan(since_ms, up_to_id, |row| {
+                    *counts.entry(((row.timestamp_ms / DAY_MS) * DAY_MS, row.event_type.clone())).or_default() += 1;
+                })?;
+                Ok(counts)
+            })
+            .await?;
+
+        let mut counts: BTreeMap<(i64, String), (i64, i64)> =
+            stored.into_iter().map(|(key, store)| (key, (0, store))).collect();
+        for day in primary {
+            counts.entry((day.day_ms, day.event_type)).or_default().0 = day.events;
+        }
+        let check = AnalyticsConsistency {
+            checked_at_ms: now_ms,
+            since_ms,
+            up_to_id,
+            mismatches: counts
+                .into_iter()
+                .filter(|(_, (primary, store))| primary != store)
+                .map(|((day_ms, event_type), (primary, store))| CountMismatch { day_ms, event_type, primary, store })
+                .collect(),
+        };
+        if !check.mismatches.is_empty() {
+            warn!(
+                "Analytics store disagrees with ram_events_history on {} day(s), explorer reads Postgres until it doesn't",
+                check.mismatches.len()
+            );
+        }
+        *self.last_check.lock().unwrap() = Some(check.clone());
+        Ok(check)
+    }
+}
+
+/// Keep the store in step with ram_events for as long as it runs
+/// (supervised, see supervisor.rs)
+pub async fn run_analytics_store(pool: DbPool, store: Arc<AnalyticsStore>) -> Result<()> {
+    let mut compacted_at = Instant::now();
+    let mut checked_at: Option<Instant> = None;
+    loop {
+        let synced = if store.caught_up.load(Ordering::Relaxed) {
+            store.blocking(|store| store.flush()).await
+        } else {
+            store.catch_up(&pool).await.inspect(|written| info!("Analytics store caught up ({} events)", written))
+        };
+        if let Err(e) = synced {
+            // Whatever was buffered is in Postgres; read it back from there
+            error!("Analytics store write failed, catching up from Postgres next: {}", e);
+            store.caught_up.store(false, Ordering::Relaxed);
+            store.pending.lock().unwrap().clear();
+        }
+
+        if compacted_at.elapsed() >= store.config.compaction_interval {
+            match store.blocking(|store| store.compact()).await {
+                Ok(merged) if merged > 0 => info!("Analytics store compacted {} segments", merged),
+                Ok(_) => {}
+                Err(e) => error!("Analytics store compaction failed: {}", e),
+            }
+            compacted_at = Instant::now();
+        }
+
+        let check_due = checked_at.is_none_or(|at| at.elapsed() >= store.config.check_interval);
+        if store.caught_up.load(Ordering::Relaxed) && check_due {
+            if let Err(e) = store.check(&pool, chrono::Utc::now().timestamp_millis()).await {
+                error!("Analytics store consistency check failed: {}", e);
+            }
+            checked_at = Some(Instant::now());
+        }
+
+        tokio::time::sleep(store.config.flush_interval).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(id: i64, event_type: &str, timestamp_ms: i64, from: &str, to: &str, amount: i64) -> AnalyticsRow {
+        AnalyticsRow {
+            id,
+            event_type: event_type.to_string(),
+            timestamp_ms,
+            coin_type: (event_type == "Transferred").then(|| "0x2::sui::SUI".to_string()),
+            amount: Some(amount),
+            from_handle_norm: Some(from.to_string()),
+            to_handle_norm: (!to.is_empty()).then(|| to.to_string()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_segments_compact_survive_reopening_and_aggregate() {
+        let dir = std::env::temp_dir().join(format!("ram-analytics-{}", uuid::Uuid::new_v4()));
+        let config = AnalyticsStoreConfig { dir: Some(dir.clone()), segment_rows: 3, ..Default::default() };
+        let store = Arc::new(AnalyticsStore::open(config.clone()).unwrap().unwrap());
+
+        assert_eq!(store.append(vec![row(2, "Transferred", DAY_MS + 5, "alice", "bob", 7), row(1, "WalletCreated", 5, "alice", "", 0)]).unwrap(), 2);
+        // Ids already written are dropped
+        assert_eq!(store.append(vec![row(2, "Transferred", DAY_MS + 5, "alice", "bob", 7)]).unwrap(), 0);
+        store.append(vec![row(3, "Transferred", DAY_MS + 9, "carol", "bob", i64::MAX)]).unwrap();
+        store.append(vec![row(5, "WalletCreated", DAY_MS, "dave", "", 0)]).unwrap();
+        assert_eq!(store.compact().unwrap(), 1);
+        assert_eq!(store.status().segments, 2);
+
+        let volumes = store.daily_transfer_volumes(DAY_MS).await.unwrap();
+        assert_eq!(volumes.len(), 1);
+        assert_eq!((volumes[0].transfers, volumes[0].senders, volumes[0].recipients), (2, 2, 1));
+        assert_eq!(volumes[0].volume, (i128::from(i64::MAX) + 7).to_string());
+        let created = store.daily_wallet_creations(0).await.unwrap();
+        assert_eq!(created, [DailyWalletCreations { day_ms: 0, created: 1 }, DailyWalletCreations { day_ms: DAY_MS, created: 1 }]);
+
+        // An input a crashed compaction didn't remove, and a half-written segment
+        write_segment(&dir, &[row(1, "WalletCreated", 5, "alice", "", 0)]).unwrap();
+        std::fs::write(dir.join("events-x.parquet.tmp"), b"partial").unwrap();
+
+        let reopened = AnalyticsStore::open(config).unwrap().unwrap();
+        assert_eq!(reopened.last_id(), 5);
+        assert_eq!((reopened.status().segments, reopened.status().rows), (2, 4));
+        assert!(!dir.join(segment_name(1, 1)).exists() && !dir.join("events-x.parquet.tmp").exists());
+        assert_eq!(reopened.count_wallets(), 2);
+        assert!(!reopened.is_serving());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
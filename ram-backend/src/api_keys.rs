@@ -0,0 +1,285 @@
+// API keys for programmatic access
+// Bots and merchant integrations can't sign each request with a wallet's
+// linked address. A wallet's owner (signing as usual, scope `api_keys`)
+// issues them a key instead, `ram_<id>_<secret>`, which they send as
+// `Authorization: Bearer <key>` wherever a signed request is accepted (see
+// wallet_auth). Only a SHA-256 hash of the key is stored. `read` keys can
+// only read; `transfer` keys can also initiate transfers. Keys can never
+// manage keys, and nothing they do changes a profile or contacts.
+
+use axum::{
+    body::Bytes,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    Json,
+};
+use sha2::{Digest, Sha256};
+use std::str::FromStr;
+use std::sync::Arc;
+use tracing::{error, info, warn};
+
+use crate::database::Database;
+use crate::handles::normalize_handle;
+use crate::logging::extract_handle;
+use crate::models::{ApiKey, ApiKeyRequest, ApiKeysQuery, CreateApiKeyRequest, IssuedApiKey};
+use crate::wallet_auth::{authenticate, SignedRequest};
+use crate::AppState;
+
+/// Prefix of every key, so leaked keys are easy to scan for
+pub const API_KEY_PREFIX: &str = "ram_";
+/// Most active keys one wallet can have
+pub const MAX_API_KEYS: i64 = 10;
+/// Longest key label, in characters
+pub const MAX_LABEL_LEN: usize = 64;
+/// Scope of the signed requests that manage keys, which keys can't use
+pub const API_KEYS_SCOPE: &str = "api_keys";
+/// Proxied routes that start a transfer: its challenge, and the transfer itself
+const TRANSFER_INITIATION_PATHS: &[&str] = &["/bio_auth/challenge", "/transfer"];
+
+/// What a key may do
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiKeyScope {
+    /// `read` actions only
+    Read,
+    /// `read` actions, and initiating transfers (action `transfer`)
+    Transfer,
+}
+
+impl ApiKeyScope {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Read => "read",
+            Self::Transfer => "transfer",
+        }
+    }
+
+    /// Whether a key of this scope can be used for a request's action
+    pub fn allows(self, action: &str) -> bool {
+        match self {
+            Self::Read => action == "read",
+            Self::Transfer => action == "read" || action == "transfer",
+        }
+    }
+}
+
+impl FromStr for ApiKeyScope {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        match s {
+            "read" => Ok(Self::Read),
+            "transfer" => Ok(Self::Transfer),
+            _ => Err(()),
+        }
+    }
+}
+
+/// A fresh key ID and full key
+fn generate_key() -> (String, String) {
+    // The first 12 hex digits of a v4 UUID are all random
+    let id = uuid::Uuid::new_v4().simple().to_string()[..12].to_string();
+    let key = new_key(&id);
+    (id, key)
+}
+
+/// A key under `id` with a fresh secret (two v4 UUIDs, 244 random bits)
+fn new_key(id: &str) -> String {
+    format!("{}{}_{}{}", API_KEY_PREFIX, id, uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple())
+}
+
+/// What's stored for a key
+pub fn hash_key(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+/// The key ID of a well-formed key
+fn key_id(key: &str) -> Option<&str> {
+    let (id, secret) = key.strip_prefix(API_KEY_PREFIX)?.split_once('_')?;
+    (!id.is_empty() && !secret.is_empty()).then_some(id)
+}
+
+/// The API key a request carries, if any
+pub fn presented_key(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .filter(|key| key.starts_with(API_KEY_PREFIX))
+}
+
+/// Check a presented key for an action on a wallet: 401 if it's unknown,
+/// malformed or revoked, 403 if it's another wallet's or its scope doesn't
+/// allow the action
+pub async fn authenticate_key(
+    state: &AppState,
+    key: &str,
+    scope: &str,
+    action: &str,
+    handle: &str,
+) -> Result<SignedRequest, StatusCode> {
+    let id = key_id(key).ok_or(StatusCode::UNAUTHORIZED)?;
+    let stored = Database::get_api_key(&state.db, id)
+        .await
+        .map_err(|e| {
+            error!("Failed to fetch API key: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .filter(|stored| stored.revoked_at_ms.is_none() && stored.key_hash == hash_key(key))
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    let allowed = stored.scope.parse::<ApiKeyScope>().is_ok_and(|s| s.allows(action));
+    if stored.handle != normalize_handle(handle) || scope == API_KEYS_SCOPE || !allowed {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let now_ms = chrono::Utc::now().timestamp_millis();
+    if let Err(e) = Database::touch_api_key(&state.db, id, now_ms).await {
+        warn!("Failed to record use of API key {}: {}", id, e);
+    }
+    Ok(SignedRequest { address: format!("api_key:{}", id), timestamp_ms: now_ms })
+}
+
+/// Check a key presented on a proxied route that initiates a transfer
+///
+/// Without a key the request goes through as before, on the strength of the
+/// bio-auth it needs anyway. With one, the key must be a `transfer` key of
+/// the wallet the transfer is from.
+pub async fn check_transfer_initiation(
+    state: &AppState,
+    key: Option<&str>,
+    path: &str,
+    body: &[u8],
+) -> Result<(), StatusCode> {
+    let Some(key) = key.filter(|_| TRANSFER_INITIATION_PATHS.contains(&path)) else {
+        return Ok(());
+    };
+    let handle = serde_json::from_slice(body).ok().and_then(|body| extract_handle(&body)).unwrap_or_default();
+    authenticate_key(state, key, "transfers", "transfer", &handle).await.map(|_| ())
+}
+
+/// List a wallet's API keys, without their secrets
+///
+/// GET /api/keys?handle= (signed, scope `api_keys`, action `read`)
+pub async fn list_api_keys(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ApiKeysQuery>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<ApiKey>>, StatusCode> {
+    authenticate(&state, &headers, API_KEYS_SCOPE, "read", &query.handle, &[]).await?;
+
+    let keys = Database::list_api_keys(&state.db, &query.handle).await.map_err(|e| {
+        error!("Failed to list API keys: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(Json(keys))
+}
+
+/// Issue an API key; its secret is only ever returned here
+///
+/// POST /api/keys (signed, action `create`) — 409 once the wallet has
+/// `MAX_API_KEYS` active keys
+pub async fn create_api_key(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<(StatusCode, Json<IssuedApiKey>), StatusCode> {
+    let request: CreateApiKeyRequest = serde_json::from_slice(&body).map_err(|_| StatusCode::BAD_REQUEST)?;
+    let label = request.label.trim();
+    let scope = request.scope.parse::<ApiKeyScope>().map_err(|_| StatusCode::BAD_REQUEST)?;
+    if label.is_empty() || label.chars().count() > MAX_LABEL_LEN || label.chars().any(char::is_control) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    authenticate(&state, &headers, API_KEYS_SCOPE, "create", &request.handle, &body).await?;
+
+    let active = Database::count_active_api_keys(&state.db, &request.handle).await.map_err(|e| {
+        error!("Failed to count API keys: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    if active >= MAX_API_KEYS {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let (id, key) = generate_key();
+    let now_ms = chrono::Utc::now().timestamp_millis();
+    let api_key = Database::insert_api_key(&state.db, &id, &request.handle, &hash_key(&key), scope.as_str(), label, now_ms)
+        .await
+        .map_err(|e| {
+            error!("Failed to store API key: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    info!("{} issued {} API key {}", request.handle, scope.as_str(), id);
+
+    Ok((StatusCode::CREATED, Json(IssuedApiKey { key, api_key })))
+}
+
+/// Give a key a new secret; the old one stops working at once
+///
+/// POST /api/keys/:id/rotate (signed, action `rotate`)
+pub async fn rotate_api_key(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<IssuedApiKey>, StatusCode> {
+    let request: ApiKeyRequest = serde_json::from_slice(&body).map_err(|_| StatusCode::BAD_REQUEST)?;
+    authenticate(&state, &headers, API_KEYS_SCOPE, "rotate", &request.handle, &body).await?;
+
+    // Same ID, so integrations can tell which key they were given
+    let key = new_key(&id);
+    let now_ms = chrono::Utc::now().timestamp_millis();
+    let api_key = Database::rotate_api_key(&state.db, &id, &request.handle, &hash_key(&key), now_ms)
+        .await
+        .map_err(|e| {
+            error!("Failed to rotate API key: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    info!("{} rotated API key {}", request.handle, id);
+
+    Ok(Json(IssuedApiKey { key, api_key }))
+}
+
+/// Revoke a key for good
+///
+/// DELETE /api/keys/:id (signed, action `revoke`)
+pub async fn revoke_api_key(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<StatusCode, StatusCode> {
+    let request: ApiKeyRequest = serde_json::from_slice(&body).map_err(|_| StatusCode::BAD_REQUEST)?;
+    authenticate(&state, &headers, API_KEYS_SCOPE, "revoke", &request.handle, &body).await?;
+
+    let now_ms = chrono::Utc::now().timestamp_millis();
+    let revoked = Database::revoke_api_key(&state.db, &id, &request.handle, now_ms).await.map_err(|e| {
+        error!("Failed to revoke API key: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    if !revoked {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    info!("{} revoked API key {}", request.handle, id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keys_are_well_formed_and_scopes_limit_actions() {
+        let (id, key) = generate_key();
+        assert_eq!(id.len(), 12);
+        assert_eq!(key_id(&key), Some(id.as_str()));
+        assert_ne!(generate_key().1, key);
+        assert_eq!(key_id("ram_abc"), None);
+        assert_eq!(key_id("sk_abc_def"), None);
+
+        assert!(ApiKeyScope::Read.allows("read"));
+        assert!(!ApiKeyScope::Read.allows("transfer"));
+        assert!(ApiKeyScope::Transfer.allows("transfer"));
+        assert!(!ApiKeyScope::Transfer.allows("update"));
+        assert!("admin".parse::<ApiKeyScope>().is_err());
+    }
+}
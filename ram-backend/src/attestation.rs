@@ -0,0 +1,271 @@
+// Nitro attestation documents
+// An enclave's `/get_attestation` answers with an AWS Nitro attestation
+// document: a COSE_Sign1 structure (CBOR) whose payload is a CBOR map with
+// the enclave's PCRs, its public key and when it was made. The document is
+// only read here, to see what it attests to before it's registered on-chain;
+// its signature and certificate chain are verified by
+// `0x2::nitro_attestation` when it is.
+
+use anyhow::{anyhow, Context, Result};
+use std::collections::BTreeMap;
+
+/// CBOR tag of a COSE_Sign1 structure
+const COSE_SIGN1_TAG: u64 = 18;
+/// Deepest nesting read; attestation documents go three levels deep
+const MAX_DEPTH: usize = 8;
+
+/// What an attestation document attests to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttestationDocument {
+    /// Enclave image the document was issued for
+    pub module_id: String,
+    /// When the Nitro hypervisor made the document (ms since the epoch)
+    pub timestamp_ms: u64,
+    /// Platform configuration registers by index
+    pub pcrs: BTreeMap<u64, Vec<u8>>,
+    /// Key the enclave committed to, if any
+    pub public_key: Option<Vec<u8>>,
+}
+
+impl AttestationDocument {
+    /// Read a serialized COSE_Sign1 attestation document
+    pub fn parse(bytes: &[u8]) -> Result<Self> {
+        let mut reader = Reader { bytes, position: 0 };
+        let mut envelope = reader.value(0)?;
+        if let Cbor::Tag(COSE_SIGN1_TAG, inner) = envelope {
+            envelope = *inner;
+        }
+        let Cbor::Array(parts) = envelope else {
+            return Err(anyhow!("Attestation is not a COSE_Sign1 structure"));
+        };
+        let Some(Cbor::Bytes(payload)) = parts.get(2) else {
+            return Err(anyhow!("Attestation has no payload"));
+        };
+
+        let mut reader = Reader { bytes: payload, position: 0 };
+        let Cbor::Map(fields) = reader.value(0).context("Attestation payload is not CBOR")? else {
+            return Err(anyhow!("Attestation payload is not a map"));
+        };
+        let field = |name: &str| {
+            fields.iter().find_map(|(key, value)| matches!(key, Cbor::Text(key) if key == name).then_some(value))
+        };
+
+        let Some(Cbor::Text(module_id)) = field("module_id") else {
+            return Err(anyhow!("Attestation has no module_id"));
+        };
+        let Some(Cbor::Uint(timestamp_ms)) = field("timestamp") else {
+            return Err(anyhow!("Attestation has no timestamp"));
+        };
+        let Some(Cbor::Map(entries)) = field("pcrs") else {
+            return Err(anyhow!("Attestation has no PCRs"));
+        };
+        let pcrs = entries
+            .iter()
+            .map(|entry| match entry {
+                (Cbor::Uint(index), Cbor::Bytes(value)) => Ok((*index, value.clone())),
+                _ => Err(anyhow!("Attestation has a malformed PCR")),
+            })
+            .collect::<Result<_>>()?;
+        let public_key = match field("public_key") {
+            Some(Cbor::Bytes(key)) => Some(key.clone()),
+            None | Some(Cbor::Null) => None,
+            Some(_) => return Err(anyhow!("Attestation has a malformed public_key")),
+        };
+
+        Ok(Self { module_id: module_id.clone(), timestamp_ms: *timestamp_ms, pcrs, public_key })
+    }
+
+    /// PCR `index`, empty if the document doesn't have it
+    pub fn pcr(&self, index: u64) -> &[u8] {
+        self.pcrs.get(&index).map_or(&[], Vec::as_slice)
+    }
+}
+
+// ============================================================================
+// CBOR (the definite-length subset attestation documents use)
+// ============================================================================
+
+#[derive(Debug)]
+enum Cbor {
+    Uint(u64),
+    NegativeInt,
+    Bytes(Vec<u8>),
+    Text(String),
+    Array(Vec<Cbor>),
+    Map(Vec<(Cbor, Cbor)>),
+    Tag(u64, Box<Cbor>),
+    Null,
+    /// Booleans, undefined and floats, which nothing here reads
+    Simple,
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl Reader<'_> {
+    fn take(&mut self, len: usize) -> Result<&[u8]> {
+        let end = self.position.checked_add(len).filter(|end| *end <= self.bytes.len());
+        let end = end.ok_or_else(|| anyhow!("CBOR ends early"))?;
+        let taken = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(taken)
+    }
+
+    /// The argument of a head: the value itself, a length or a count
+    fn argument(&mut self, additional: u8) -> Result<u64> {
+        let width = match additional {
+            0..=23 => return Ok(additional as u64),
+            24 => 1,
+            25 => 2,
+            26 => 4,
+            27 => 8,
+            _ => return Err(anyhow!("Indefinite-length CBOR is not supported")),
+        };
+        Ok(self.take(width)?.iter().fold(0, |value, byte| value << 8 | *byte as u64))
+    }
+
+    /// A length that fits in what's left to read
+    fn length(&mut self, additional: u8) -> Result<usize> {
+        let length = self.argument(additional)?;
+        usize::try_from(length)
+            .ok()
+            .filter(|length| *length <= self.bytes.len() - self.position)
+            .ok_or_else(|| anyhow!("CBOR length {} overruns the document", length))
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Cbor> {
+        if depth > MAX_DEPTH {
+            return Err(anyhow!("CBOR nests too deep"));
+        }
+        let head = self.take(1)?[0];
+        let additional = head & 0x1f;
+        Ok(match head >> 5 {
+            0 => Cbor::Uint(self.argument(additional)?),
+            1 => {
+                self.argument(additional)?;
+                Cbor::NegativeInt
+            }
+            2 => {
+                let length = self.length(additional)?;
+                Cbor::Bytes(self.take(length)?.to_vec())
+            }
+            3 => {
+                let length = self.length(additional)?;
+                Cbor::Text(String::from_utf8(self.take(length)?.to_vec()).context("CBOR text is not UTF-8")?)
+            }
+            4 => {
+                let count = self.length(additional)?;
+                Cbor::Array((0..count).map(|_| self.value(depth + 1)).collect::<Result<_>>()?)
+            }
+            5 => {
+                let count = self.length(additional)?;
+                Cbor::Map(
+                    (0..count)
+                        .map(|_| Ok((self.value(depth + 1)?, self.value(depth + 1)?)))
+                        .collect::<Result<_>>()?,
+                )
+            }
+            6 => {
+                let tag = self.argument(additional)?;
+                Cbor::Tag(tag, Box::new(self.value(depth + 1)?))
+            }
+            _ => match additional {
+                22 => Cbor::Null,
+                24..=27 => {
+                    self.argument(additional)?;
+                    Cbor::Simple
+                }
+                0..=23 => Cbor::Simple,
+                _ => return Err(anyhow!("Unsupported CBOR simple value")),
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn head(major: u8, value: u64) -> Vec<u8> {
+        match value {
+            0..=23 => vec![major << 5 | value as u8],
+            24..=0xff => vec![major << 5 | 24, value as u8],
+            0x100..=0xffff => [vec![major << 5 | 25], (value as u16).to_be_bytes().to_vec()].concat(),
+            _ => [vec![major << 5 | 27], value.to_be_bytes().to_vec()].concat(),
+        }
+    }
+
+    fn bytes(value: &[u8]) -> Vec<u8> {
+        [head(2, value.len() as u64), value.to_vec()].concat()
+    }
+
+    fn text(value: &str) -> Vec<u8> {
+        [head(3, value.len() as u64), value.as_bytes().to_vec()].concat()
+    }
+
+    /// A tagged COSE_Sign1 attestation document as the Nitro hypervisor lays it out
+    fn attestation_document(pcrs: [[u8; 48]; 3], public_key: &[u8], timestamp_ms: u64) -> Vec<u8> {
+        let mut payload = head(5, 9);
+        payload.extend(text("module_id"));
+        payload.extend(text("i-0abc-enc0123"));
+        payload.extend(text("digest"));
+        payload.extend(text("SHA384"));
+        payload.extend(text("timestamp"));
+        payload.extend(head(0, timestamp_ms));
+        payload.extend(text("pcrs"));
+        payload.extend(head(5, 4));
+        for (index, pcr) in pcrs.iter().chain([&[0u8; 48]]).enumerate() {
+            payload.extend(head(0, index as u64));
+            payload.extend(bytes(pcr));
+        }
+        payload.extend(text("certificate"));
+        payload.extend(bytes(&[0x30; 300]));
+        payload.extend(text("cabundle"));
+        payload.extend(head(4, 2));
+        payload.extend(bytes(&[0x30; 40]));
+        payload.extend(bytes(&[0x31; 40]));
+        payload.extend(text("public_key"));
+        payload.extend(bytes(public_key));
+        payload.extend(text("user_data"));
+        payload.push(0xf6);
+        payload.extend(text("nonce"));
+        payload.push(0xf6);
+
+        let mut document = head(6, COSE_SIGN1_TAG);
+        document.extend(head(4, 4));
+        document.extend(bytes(&[0xa1, 0x01, 0x38, 0x22]));
+        document.extend(head(5, 0));
+        document.extend(bytes(&payload));
+        document.extend(bytes(&[7; 96]));
+        document
+    }
+
+    #[test]
+    fn test_document_fields_are_read() {
+        let document = attestation_document([[1; 48], [2; 48], [3; 48]], &[9; 32], 1_760_000_000_123);
+        let parsed = AttestationDocument::parse(&document).unwrap();
+        assert_eq!(parsed.module_id, "i-0abc-enc0123");
+        assert_eq!(parsed.timestamp_ms, 1_760_000_000_123);
+        assert_eq!((parsed.pcr(0), parsed.pcr(2)), (&[1; 48][..], &[3; 48][..]));
+        assert_eq!(parsed.pcrs.len(), 4);
+        assert_eq!(parsed.pcr(16), &[] as &[u8]);
+        assert_eq!(parsed.public_key, Some(vec![9; 32]));
+
+        // Untagged documents are read the same way
+        assert_eq!(AttestationDocument::parse(&document[1..]).unwrap(), parsed);
+    }
+
+    #[test]
+    fn test_malformed_documents_are_rejected() {
+        let document = attestation_document([[1; 48], [2; 48], [3; 48]], &[9; 32], 1);
+        assert!(AttestationDocument::parse(&document[..document.len() - 10]).is_err());
+        assert!(AttestationDocument::parse(&[]).is_err());
+        assert!(AttestationDocument::parse(&text("not a document")).is_err());
+        // A length past the end of the document
+        assert!(AttestationDocument::parse(&[0x5b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]).is_err());
+        // Arrays nested past the limit
+        assert!(AttestationDocument::parse(&[0x81; 64]).is_err());
+    }
+}
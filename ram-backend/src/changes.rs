@@ -0,0 +1,292 @@
+// Handle change feed
+// The indexer publishes a change for every handle an ingested event touches.
+// Cached reads for the handle are dropped on it, and clients watching the
+// handle over GET /api/changes/:handle (a WebSocket) are pushed it, so reads
+// catch up with the chain as soon as the event is indexed instead of when a
+// cache entry expires.
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path, State,
+    },
+    response::Response,
+};
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::{debug, warn};
+
+use crate::handles::normalize_handle;
+use crate::models::{PortfolioResponse, RamEvent};
+use crate::AppState;
+
+/// Changes buffered per subscriber before the slowest one starts missing some
+const CHANGE_FEED_CAPACITY: usize = 1024;
+/// How long a read is cached if its handle doesn't change (READ_CACHE_TTL_SECS)
+pub const DEFAULT_READ_CACHE_TTL: Duration = Duration::from_secs(30);
+
+/// An indexed event that touched a handle
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HandleChange {
+    pub handle: String,
+    pub event_type: String,
+    pub tx_digest: String,
+    pub timestamp_ms: i64,
+}
+
+/// In-process broadcast of handle changes, from the indexer to readers
+pub struct ChangeFeed {
+    sender: broadcast::Sender<HandleChange>,
+}
+
+impl Default for ChangeFeed {
+    fn default() -> Self {
+        Self { sender: broadcast::channel(CHANGE_FEED_CAPACITY).0 }
+    }
+}
+
+impl ChangeFeed {
+    /// Publish a change for every handle `event` names (owner, sender, recipient),
+    /// in normalized form
+    pub fn publish(&self, event: &RamEvent) {
+        let handles: BTreeSet<String> = [&event.handle, &event.from_handle, &event.to_handle]
+            .into_iter()
+            .flatten()
+            .filter(|handle| !handle.is_empty())
+            .map(|handle| normalize_handle(handle))
+            .collect();
+        for handle in handles {
+            // No subscribers is not an error: nothing is cached or watched yet
+            let _ = self.sender.send(HandleChange {
+                handle,
+                event_type: event.event_type.clone(),
+                tx_digest: event.tx_digest.clone(),
+                timestamp_ms: event.timestamp.timestamp_millis(),
+            });
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<HandleChange> {
+        self.sender.subscribe()
+    }
+}
+
+/// Short-lived per-handle cache of read endpoints backed by live RPC calls
+///
+/// Entries expire after the TTL, and are dropped as soon as the change feed
+/// reports the handle changed. A read racing an invalidation isn't stored.
+pub struct ReadCache {
+    ttl: Duration,
+    /// Bumped on every invalidation; reads only store if it hasn't moved
+    generation: AtomicU64,
+    portfolios: Mutex<HashMap<String, (Instant, PortfolioResponse)>>,
+}
+
+impl Default for ReadCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_READ_CACHE_TTL)
+    }
+}
+
+impl ReadCache {
+    /// Cache entries for `ttl` (zero disables the cache)
+    pub fn new(ttl: Duration) -> Self {
+        Self { ttl, generation: AtomicU64::new(0), portfolios: Mutex::new(HashMap::new()) }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.ttl.is_zero()
+    }
+
+    /// Generation to hand back to `put_portfolio` once the read is done
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::SeqCst)
+    }
+
+    pub fn portfolio(&self, handle: &str) -> Option<PortfolioResponse> {
+        let portfolios = self.portfolios.lock().unwrap();
+        portfolios
+            .get(handle)
+            .filter(|(stored_at, _)| stored_at.elapsed() < self.ttl)
+            .map(|(_, portfolio)| portfolio.clone())
+    }
+
+    /// Store a portfolio read at `generation`, unless a change arrived since
+    pub fn put_portfolio(&self, generation: u64, portfolio: &PortfolioResponse) {
+        if !self.is_enabled() {
+            return;
+        }
+        let mut portfolios = self.portfolios.lock().unwrap();
+        if self.generation() != generation {
+            return;
+        }
+        portfolios.retain(|_, (stored_at, _)| stored_at.elapsed() < self.ttl);
+        portfolios.insert(portfolio.handle.clone(), (Instant::now(), portfolio.clone()));
+    }
+
+    pub fn invalidate(&self, handle: &str) {
+        let mut portfolios = self.portfolios.lock().unwrap();
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        portfolios.remove(handle);
+    }
+
+    pub fn clear(&self) {
+        let mut portfolios = self.portfolios.lock().unwrap();
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        portfolios.clear();
+    }
+
+    /// Drop entries as the feed reports changes, until the feed closes
+    pub fn spawn_invalidation(self: &Arc<Self>, feed: &ChangeFeed) {
+        let cache = self.clone();
+        let mut changes = feed.subscribe();
+        tokio::spawn(async move {
+            loop {
+                match changes.recv().await {
+                    Ok(change) => cache.invalidate(&change.handle),
+                    // Some changes were missed, so any entry may be stale
+                    Err(RecvError::Lagged(missed)) => {
+                        warn!("Read cache missed {} changes, clearing it", missed);
+                        cache.clear();
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+    }
+}
+
+/// What a watcher is sent
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Push<'a> {
+    Change(&'a HandleChange),
+    /// Changes were missed; the client should reload what it shows
+    Resync { handle: &'a str },
+}
+
+/// Push a handle's changes over a WebSocket as they're indexed
+///
+/// GET /api/changes/:handle
+pub async fn watch_handle(
+    State(state): State<Arc<AppState>>,
+    Path(handle): Path<String>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    // Subscribed before the upgrade, so nothing indexed after it is missed
+    let changes = state.changes.subscribe();
+    let handle = normalize_handle(&handle);
+    ws.on_upgrade(move |socket| push_changes(socket, handle, changes))
+}
+
+async fn push_changes(mut socket: WebSocket, handle: String, mut changes: broadcast::Receiver<HandleChange>) {
+    loop {
+        let push = tokio::select! {
+            change = changes.recv() => match change {
+                Ok(change) if change.handle == handle => serde_json::to_string(&Push::Change(&change)),
+                Ok(_) => continue,
+                Err(RecvError::Lagged(_)) => serde_json::to_string(&Push::Resync { handle: &handle }),
+                Err(RecvError::Closed) => break,
+            },
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                // Pings are answered by axum; anything else from the client is ignored
+                Some(Ok(_)) => continue,
+            },
+        };
+        let Ok(push) = push else { continue };
+        if socket.send(Message::Text(push)).await.is_err() {
+            break;
+        }
+    }
+    debug!("Stopped pushing changes for {}", handle);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn transfer(from: &str, to: &str) -> RamEvent {
+        RamEvent {
+            handle: Some(from.to_string()),
+            event_type: "Transferred".to_string(),
+            amount: Some(5),
+            coin_type: None,
+            result_code: None,
+            from_handle: Some(from.to_string()),
+            to_handle: Some(to.to_string()),
+            memo: None,
+            payload_version: None,
+            owner: None,
+            wallet_id: None,
+            tx_digest: "0xdigest".to_string(),
+            timestamp: Utc::now(),
+            raw_json: None,
+            gas_used: None,
+            sender: None,
+            sender_label: None,
+            from_display_name: None,
+            to_display_name: None,
+            refund_of: None,
+            refunded_in: Vec::new(),
+        }
+    }
+
+    fn portfolio(handle: &str) -> PortfolioResponse {
+        PortfolioResponse { handle: handle.to_string(), wallet_id: None, coins: Vec::new() }
+    }
+
+    #[test]
+    fn test_publish_names_each_handle_once() {
+        let feed = ChangeFeed::default();
+        let mut changes = feed.subscribe();
+        feed.publish(&transfer("alice", "Bob"));
+
+        let first = changes.try_recv().unwrap();
+        assert_eq!((first.handle.as_str(), first.event_type.as_str()), ("alice", "Transferred"));
+        assert_eq!(changes.try_recv().unwrap().handle, "bob");
+        assert!(changes.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_reads_racing_an_invalidation_are_not_stored() {
+        let cache = ReadCache::default();
+        let generation = cache.generation();
+        cache.put_portfolio(generation, &portfolio("alice"));
+        assert!(cache.portfolio("alice").is_some());
+
+        let stale = cache.generation();
+        cache.invalidate("alice");
+        assert!(cache.portfolio("alice").is_none());
+        cache.put_portfolio(stale, &portfolio("alice"));
+        assert!(cache.portfolio("alice").is_none());
+
+        let disabled = ReadCache::new(Duration::ZERO);
+        disabled.put_portfolio(disabled.generation(), &portfolio("alice"));
+        assert!(disabled.portfolio("alice").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_indexed_changes_invalidate_the_cache() {
+        let feed = ChangeFeed::default();
+        let cache = Arc::new(ReadCache::default());
+        cache.spawn_invalidation(&feed);
+        cache.put_portfolio(cache.generation(), &portfolio("bob"));
+        cache.put_portfolio(cache.generation(), &portfolio("carol"));
+
+        feed.publish(&transfer("alice", "bob"));
+        for _ in 0..100 {
+            if cache.portfolio("bob").is_none() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert!(cache.portfolio("bob").is_none());
+        assert!(cache.portfolio("carol").is_some());
+    }
+}
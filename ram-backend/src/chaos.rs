@@ -0,0 +1,124 @@
+// Fault injection for resilience testing
+// Builds with the `chaos` feature can be told, through CHAOS_* env vars, to
+// delay upstream calls, drop requests to Nautilus and time out database
+// queries on a percentage of calls, so RPC failover, indexer retries and
+// client retry/backoff can be exercised in staging. Injected faults fail the
+// same way the real ones do (502 from the proxy, `PoolTimedOut` from sqlx).
+// Without the feature the settings are ignored and every hook is a no-op.
+
+use anyhow::{anyhow, Result};
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::Duration;
+use tracing::warn;
+
+/// What to inject and how often (percentages of calls, 0-100)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Faults {
+    /// Extra delay before Nautilus and Sui RPC calls
+    pub upstream_latency: Duration,
+    pub upstream_latency_percent: u8,
+    /// Nautilus requests failed as if the connection dropped
+    pub nautilus_drop_percent: u8,
+    /// Database queries failed as if no pooled connection became free
+    pub db_timeout_percent: u8,
+}
+
+impl Faults {
+    /// Check the percentages are within 0-100
+    pub fn validate(&self) -> Result<()> {
+        for (name, percent) in [
+            ("CHAOS_UPSTREAM_LATENCY_PERCENT", self.upstream_latency_percent),
+            ("CHAOS_NAUTILUS_DROP_PERCENT", self.nautilus_drop_percent),
+            ("CHAOS_DB_TIMEOUT_PERCENT", self.db_timeout_percent),
+        ] {
+            if percent > 100 {
+                return Err(anyhow!("{} must be 0-100, got {}", name, percent));
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether any fault is configured
+    pub fn is_active(&self) -> bool {
+        (self.upstream_latency_percent > 0 && !self.upstream_latency.is_zero())
+            || self.nautilus_drop_percent > 0
+            || self.db_timeout_percent > 0
+    }
+}
+
+static FAULTS: OnceLock<Faults> = OnceLock::new();
+static ROLLS: AtomicU64 = AtomicU64::new(0);
+
+/// Turn on injection for the rest of the process; ignored without the `chaos` feature
+pub fn install(faults: Faults) {
+    if !faults.is_active() {
+        return;
+    }
+    if cfg!(feature = "chaos") {
+        warn!("Fault injection enabled: {:?}", faults);
+        let _ = FAULTS.set(faults);
+    } else {
+        warn!("CHAOS_* settings ignored: built without the `chaos` feature");
+    }
+}
+
+/// True on roughly `percent`% of calls
+fn roll(percent: u8) -> bool {
+    match percent {
+        0 => false,
+        100.. => true,
+        _ => {
+            let n = RandomState::new().hash_one(ROLLS.fetch_add(1, Ordering::Relaxed));
+            n % 100 < percent as u64
+        }
+    }
+}
+
+/// Delay an upstream call, if latency injection fires
+pub async fn upstream_latency() {
+    if let Some(faults) = FAULTS.get() {
+        if roll(faults.upstream_latency_percent) {
+            tokio::time::sleep(faults.upstream_latency).await;
+        }
+    }
+}
+
+/// Whether to drop this Nautilus request
+pub fn drop_nautilus_request() -> bool {
+    FAULTS.get().is_some_and(|f| roll(f.nautilus_drop_percent))
+}
+
+/// Fail a database query with a pool timeout, if injection fires
+pub fn db_timeout() -> Result<()> {
+    match FAULTS.get() {
+        Some(faults) if roll(faults.db_timeout_percent) => Err(sqlx::Error::PoolTimedOut.into()),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roll_extremes_and_rate() {
+        assert!((0..1000).all(|_| !roll(0)));
+        assert!((0..1000).all(|_| roll(100)));
+        let hits = (0..10_000).filter(|_| roll(30)).count();
+        assert!((2_500..3_500).contains(&hits), "{} hits", hits);
+    }
+
+    #[test]
+    fn test_validate_and_activity() {
+        assert!(!Faults::default().is_active());
+        let latency_without_delay = Faults { upstream_latency_percent: 50, ..Faults::default() };
+        assert!(!latency_without_delay.is_active());
+        let drops = Faults { nautilus_drop_percent: 10, ..Faults::default() };
+        assert!(drops.is_active());
+        assert!(drops.validate().is_ok());
+        assert!(Faults { db_timeout_percent: 101, ..Faults::default() }.validate().is_err());
+    }
+}
@@ -0,0 +1,1161 @@
+// Backend configuration loaded from environment variables
+
+use crate::chaos::Faults;
+use crate::forwarding::HeaderPolicy;
+use crate::rpc::parse_urls;
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Database connection and pool settings
+#[derive(Debug, Clone)]
+pub struct DbConfig {
+    /// Primary (read-write) database, used by the indexer and admin routes
+    pub url: String,
+    /// Optional read replica for the heavy events/analytics API queries
+    pub read_url: Option<String>,
+    pub max_connections: u32,
+    pub min_connections: u32,
+    pub acquire_timeout: Duration,
+    /// Queries slower than this are logged at warn level
+    pub slow_query_threshold: Duration,
+}
+
+impl DbConfig {
+    /// Defaults for a single database without a replica
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            read_url: None,
+            max_connections: 5,
+            min_connections: 0,
+            acquire_timeout: Duration::from_secs(30),
+            slow_query_threshold: Duration::from_millis(200),
+        }
+    }
+
+    fn from_env() -> Result<Self> {
+        let defaults = Self::new(
+            std::env::var("DATABASE_URL").unwrap_or_else(|_| "sqlite:ram.db".to_string()),
+        );
+        Ok(Self {
+            read_url: optional_var("DATABASE_READ_URL"),
+            max_connections: parse_var("DB_MAX_CONNECTIONS", defaults.max_connections)?,
+            min_connections: parse_var("DB_MIN_CONNECTIONS", defaults.min_connections)?,
+            acquire_timeout: Duration::from_secs(parse_var(
+                "DB_ACQUIRE_TIMEOUT_SECS",
+                defaults.acquire_timeout.as_secs(),
+            )?),
+            slow_query_threshold: Duration::from_millis(parse_var(
+                "DB_SLOW_QUERY_MS",
+                defaults.slow_query_threshold.as_millis() as u64,
+            )?),
+            ..defaults
+        })
+    }
+}
+
+/// One Nautilus enclave behind the backend
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnclaveConfig {
+    pub url: String,
+    /// Hex Ed25519 key the enclave signs payloads with (not verified if unset)
+    pub public_key: Option<String>,
+}
+
+impl EnclaveConfig {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self { url: url.into(), public_key: None }
+    }
+
+    /// Enclaves from NAUTILUS_URL, with NAUTILUS_PUBLIC_KEYS in the same order
+    fn from_env() -> Result<Vec<Self>> {
+        let urls = parse_urls(&std::env::var("NAUTILUS_URL").unwrap_or_else(|_| "http://localhost:3000".to_string()));
+        if urls.is_empty() {
+            return Err(anyhow!("NAUTILUS_URL must list at least one enclave"));
+        }
+        let Some(keys) = optional_var("NAUTILUS_PUBLIC_KEYS") else {
+            return Ok(urls.into_iter().map(Self::new).collect());
+        };
+        let keys = parse_list(&keys);
+        if keys.len() != urls.len() {
+            return Err(anyhow!(
+                "NAUTILUS_PUBLIC_KEYS lists {} keys for {} NAUTILUS_URL enclaves",
+                keys.len(),
+                urls.len()
+            ));
+        }
+        Ok(urls
+            .into_iter()
+            .zip(keys)
+            .map(|(url, public_key)| Self { url, public_key: Some(public_key) })
+            .collect())
+    }
+}
+
+/// Timeouts and retries for routes proxied to the enclave
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProxyConfig {
+    /// Health, attestation, queue/shadow metrics and job polling
+    pub metadata_timeout: Duration,
+    /// Voice and typed bio-auth, which wait on LLM/emotion analysis
+    pub bio_auth_timeout: Duration,
+    /// Every other route, backend API routes included
+    pub default_timeout: Duration,
+    /// Extra attempts for proxied GETs after a connection failure or 502/503/504
+    pub get_retries: u32,
+    /// Wait before the first retry, doubled for each one after it
+    pub retry_backoff: Duration,
+    /// How often route aliases are reloaded from the database, picking up
+    /// changes made through another backend
+    pub routes_reload_interval: Duration,
+}
+
+impl Default for ProxyConfig {
+    fn default() -> Self {
+        Self {
+            metadata_timeout: Duration::from_secs(2),
+            bio_auth_timeout: Duration::from_secs(30),
+            default_timeout: Duration::from_secs(10),
+            get_retries: 2,
+            retry_backoff: Duration::from_millis(100),
+            routes_reload_interval: Duration::from_secs(30),
+        }
+    }
+}
+
+impl ProxyConfig {
+    fn from_env() -> Result<Self> {
+        let defaults = Self::default();
+        let millis = |name: &str, default: Duration| -> Result<Duration> {
+            Ok(Duration::from_millis(parse_var(name, default.as_millis() as u64)?))
+        };
+        Ok(Self {
+            metadata_timeout: millis("PROXY_METADATA_TIMEOUT_MS", defaults.metadata_timeout)?,
+            bio_auth_timeout: millis("PROXY_BIOAUTH_TIMEOUT_MS", defaults.bio_auth_timeout)?,
+            default_timeout: millis("PROXY_DEFAULT_TIMEOUT_MS", defaults.default_timeout)?,
+            get_retries: parse_var("PROXY_GET_RETRIES", defaults.get_retries)?,
+            retry_backoff: millis("PROXY_RETRY_BACKOFF_MS", defaults.retry_backoff)?,
+            routes_reload_interval: Duration::from_secs(parse_var(
+                "PROXY_ROUTES_RELOAD_SECS",
+                defaults.routes_reload_interval.as_secs(),
+            )?),
+        })
+    }
+}
+
+/// Gas sponsorship for `/api/onboard` (disabled unless a sponsor key is set)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SponsorConfig {
+    /// Hex Ed25519 private key of the address paying for onboarding gas
+    pub private_key: String,
+    /// Shared `RamRegistry` object
+    pub registry_id: String,
+    /// Registered `Enclave` object of each enclave, in NAUTILUS_URL order
+    pub enclave_ids: Vec<String>,
+    /// Package defining the enclave witness type (`<pkg>::core::XWALLET`)
+    pub enclave_package_id: String,
+    /// Gas budget of one wallet creation, in MIST
+    pub gas_budget: u64,
+    /// Most wallet creations sponsored per rolling 24 hours
+    pub daily_limit: i64,
+    /// How long a prepared transaction can be signed and submitted
+    pub prepared_ttl: Duration,
+    /// How long submission waits for the indexer to see the new wallet
+    pub wallet_timeout: Duration,
+}
+
+impl SponsorConfig {
+    /// Defaults for everything but the key and objects
+    pub fn new(private_key: String, registry_id: String, enclave_ids: Vec<String>, enclave_package_id: String) -> Self {
+        Self {
+            private_key,
+            registry_id,
+            enclave_ids,
+            enclave_package_id,
+            gas_budget: 50_000_000,
+            daily_limit: 1_000,
+            prepared_ttl: Duration::from_secs(10 * 60),
+            wallet_timeout: Duration::from_secs(30),
+        }
+    }
+
+    fn from_env(package_id: &str) -> Result<Option<Self>> {
+        let Some(private_key) = optional_var("SPONSOR_PRIVATE_KEY") else {
+            return Ok(None);
+        };
+        let defaults = Self::new(
+            private_key,
+            optional_var("RAM_REGISTRY_ID").context("RAM_REGISTRY_ID must be set to sponsor onboarding")?,
+            parse_list(&optional_var("RAM_ENCLAVE_ID").context("RAM_ENCLAVE_ID must be set to sponsor onboarding")?),
+            optional_var("RAM_ENCLAVE_PACKAGE_ID").unwrap_or_else(|| package_id.to_string()),
+        );
+        Ok(Some(Self {
+            gas_budget: parse_var("SPONSOR_GAS_BUDGET", defaults.gas_budget)?,
+            daily_limit: parse_var("SPONSOR_DAILY_LIMIT", defaults.daily_limit)?,
+            prepared_ttl: Duration::from_secs(parse_var(
+                "ONBOARD_PREPARED_TTL_SECS",
+                defaults.prepared_ttl.as_secs(),
+            )?),
+            wallet_timeout: Duration::from_millis(parse_var(
+                "ONBOARD_WALLET_TIMEOUT_MS",
+                defaults.wallet_timeout.as_millis() as u64,
+            )?),
+            ..defaults
+        }))
+    }
+}
+
+/// On-chain enclave registration prepared by `/admin/register_enclave`
+/// (disabled unless the enclave config object is set)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnclaveRegistrationConfig {
+    /// Package of the Nautilus `enclave` module
+    pub framework_package_id: String,
+    /// Shared `EnclaveConfig<XWALLET>` holding the PCRs enclaves must attest to
+    pub config_id: String,
+    /// `Cap<XWALLET>` allowed to update those PCRs, owned by the registering address
+    pub cap_id: Option<String>,
+    /// Package defining the enclave witness type (`<pkg>::core::XWALLET`)
+    pub enclave_package_id: String,
+}
+
+impl EnclaveRegistrationConfig {
+    fn from_env(package_id: &str) -> Result<Option<Self>> {
+        let Some(config_id) = optional_var("ENCLAVE_CONFIG_ID") else {
+            return Ok(None);
+        };
+        Ok(Some(Self {
+            framework_package_id: optional_var("ENCLAVE_FRAMEWORK_PACKAGE_ID")
+                .context("ENCLAVE_FRAMEWORK_PACKAGE_ID must be set to register enclaves")?,
+            config_id,
+            cap_id: optional_var("ENCLAVE_CAP_ID"),
+            enclave_package_id: optional_var("RAM_ENCLAVE_PACKAGE_ID").unwrap_or_else(|| package_id.to_string()),
+        }))
+    }
+}
+
+/// Gas recommendations from dry runs (`/api/tx/estimate`, sponsored onboarding)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GasConfig {
+    /// Added on top of the dry run's cost when recommending a budget, in percent
+    pub budget_margin_percent: u64,
+    /// `/api/tx/estimate` requests per minute each client may make
+    pub estimate_rate_limit_per_minute: u32,
+}
+
+impl Default for GasConfig {
+    fn default() -> Self {
+        Self { budget_margin_percent: 20, estimate_rate_limit_per_minute: 30 }
+    }
+}
+
+impl GasConfig {
+    fn from_env() -> Result<Self> {
+        let defaults = Self::default();
+        let budget_margin_percent = parse_var("GAS_BUDGET_MARGIN_PERCENT", defaults.budget_margin_percent)?;
+        if budget_margin_percent > 1_000 {
+            return Err(anyhow!("GAS_BUDGET_MARGIN_PERCENT must be at most 1000"));
+        }
+        let estimate_rate_limit_per_minute =
+            parse_var("GAS_ESTIMATE_RATE_LIMIT_PER_MIN", defaults.estimate_rate_limit_per_minute)?;
+        if estimate_rate_limit_per_minute == 0 {
+            return Err(anyhow!("GAS_ESTIMATE_RATE_LIMIT_PER_MIN must be positive"));
+        }
+        Ok(Self { budget_margin_percent, estimate_rate_limit_per_minute })
+    }
+}
+
+/// Warnings to owners whose inactivity beneficiary can soon claim their wallet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InactivityConfig {
+    /// How often wallets with a beneficiary are checked
+    pub check_interval: Duration,
+    /// How long before the claim window opens the owner is warned
+    pub warning_period: Duration,
+}
+
+impl Default for InactivityConfig {
+    fn default() -> Self {
+        Self {
+            check_interval: Duration::from_secs(60 * 60),
+            warning_period: Duration::from_secs(7 * 24 * 60 * 60),
+        }
+    }
+}
+
+impl InactivityConfig {
+    fn from_env() -> Result<Self> {
+        let defaults = Self::default();
+        Ok(Self {
+            check_interval: Duration::from_secs(parse_var(
+                "INACTIVITY_CHECK_INTERVAL_SECS",
+                defaults.check_interval.as_secs(),
+            )?),
+            warning_period: Duration::from_secs(
+                parse_var("INACTIVITY_WARNING_DAYS", defaults.warning_period.as_secs() / 86_400)? * 86_400,
+            ),
+        })
+    }
+}
+
+/// Archival of old events out of ram_events (see retention.rs)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetentionConfig {
+    /// Events older than this many months are archived (0 = keep all in ram_events)
+    pub retention_months: u32,
+    /// How often old events are looked for
+    pub check_interval: Duration,
+    /// Events moved per statement, so one run doesn't hold locks for long
+    pub batch_size: i64,
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self { retention_months: 0, check_interval: Duration::from_secs(60 * 60), batch_size: 5_000 }
+    }
+}
+
+impl RetentionConfig {
+    fn from_env() -> Result<Self> {
+        let defaults = Self::default();
+        let batch_size = parse_var("EVENT_ARCHIVE_BATCH_SIZE", defaults.batch_size)?;
+        if batch_size <= 0 {
+            return Err(anyhow!("EVENT_ARCHIVE_BATCH_SIZE must be positive"));
+        }
+        Ok(Self {
+            retention_months: parse_var("EVENT_RETENTION_MONTHS", defaults.retention_months)?,
+            check_interval: Duration::from_secs(parse_var(
+                "EVENT_ARCHIVE_INTERVAL_SECS",
+                defaults.check_interval.as_secs(),
+            )?),
+            batch_size,
+        })
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.retention_months > 0
+    }
+}
+
+/// Delivery of merchant webhook notifications (see webhooks.rs)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebhookConfig {
+    /// How often due deliveries are looked for
+    pub poll_interval: Duration,
+    /// Deadline for a merchant's endpoint to answer
+    pub timeout: Duration,
+    /// Deliveries are marked dead after this many failed attempts
+    pub max_attempts: i32,
+    /// Also accept http:// URLs and deliver to loopback and private addresses
+    /// (local development only: it lets wallet owners reach internal services)
+    pub allow_private_targets: bool,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            poll_interval: Duration::from_secs(5),
+            timeout: Duration::from_secs(10),
+            max_attempts: 8,
+            allow_private_targets: false,
+        }
+    }
+}
+
+impl WebhookConfig {
+    fn from_env() -> Result<Self> {
+        let defaults = Self::default();
+        let max_attempts = parse_var("WEBHOOK_MAX_ATTEMPTS", defaults.max_attempts)?;
+        if max_attempts <= 0 {
+            return Err(anyhow!("WEBHOOK_MAX_ATTEMPTS must be positive"));
+        }
+        Ok(Self {
+            poll_interval: Duration::from_secs(parse_var(
+                "WEBHOOK_POLL_INTERVAL_SECS",
+                defaults.poll_interval.as_secs(),
+            )?),
+            timeout: Duration::from_secs(parse_var("WEBHOOK_TIMEOUT_SECS", defaults.timeout.as_secs())?),
+            max_attempts,
+            allow_private_targets: parse_var("WEBHOOK_ALLOW_PRIVATE_TARGETS", defaults.allow_private_targets)?,
+        })
+    }
+}
+
+/// Where indexed events are exported for analytics (see exports.rs)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExportSink {
+    /// Batches POSTed as JSON to an HTTP endpoint
+    Http { url: String },
+    /// Records produced to a Kafka topic through a Kafka REST Proxy (v2 API)
+    Kafka { rest_url: String, topic: String },
+    /// One newline-delimited JSON object per batch in an S3 bucket
+    S3 {
+        bucket: String,
+        region: String,
+        /// `https://s3.<region>.amazonaws.com` unless an S3-compatible store is used
+        endpoint: String,
+        /// Prepended to every object key
+        prefix: String,
+        access_key_id: String,
+        secret_access_key: String,
+    },
+}
+
+impl ExportSink {
+    /// Name the sink's progress is stored under
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExportSink::Http { .. } => "http",
+            ExportSink::Kafka { .. } => "kafka",
+            ExportSink::S3 { .. } => "s3",
+        }
+    }
+
+    fn from_env() -> Result<Option<Self>> {
+        let required = |name: &str| optional_var(name).ok_or_else(|| anyhow!("{} is required by EXPORT_SINK", name));
+        let sink = match optional_var("EXPORT_SINK").map(|s| s.to_ascii_lowercase()).as_deref() {
+            None | Some("off") => return Ok(None),
+            Some("http") => ExportSink::Http { url: required("EXPORT_HTTP_URL")? },
+            Some("kafka") => ExportSink::Kafka {
+                rest_url: required("EXPORT_KAFKA_REST_URL")?.trim_end_matches('/').to_string(),
+                topic: required("EXPORT_KAFKA_TOPIC")?,
+            },
+            Some("s3") => {
+                let region = optional_var("EXPORT_S3_REGION").unwrap_or_else(|| "us-east-1".to_string());
+                ExportSink::S3 {
+                    bucket: required("EXPORT_S3_BUCKET")?,
+                    endpoint: optional_var("EXPORT_S3_ENDPOINT")
+                        .unwrap_or_else(|| format!("https://s3.{}.amazonaws.com", region))
+                        .trim_end_matches('/')
+                        .to_string(),
+                    region,
+                    prefix: optional_var("EXPORT_S3_PREFIX").unwrap_or_else(|| "ram-events/".to_string()),
+                    access_key_id: required("AWS_ACCESS_KEY_ID")?,
+                    secret_access_key: required("AWS_SECRET_ACCESS_KEY")?,
+                }
+            }
+            Some(other) => return Err(anyhow!("Invalid EXPORT_SINK '{}': expected http, kafka, s3 or off", other)),
+        };
+        Ok(Some(sink))
+    }
+}
+
+/// Export of every indexed event to an analytics sink (see exports.rs)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportConfig {
+    /// None = no export
+    pub sink: Option<ExportSink>,
+    /// Events sent per batch
+    pub batch_size: i64,
+    /// How often new events are looked for once caught up
+    pub poll_interval: Duration,
+    /// Deadline for the sink to accept a batch
+    pub timeout: Duration,
+}
+
+impl Default for ExportConfig {
+    fn default() -> Self {
+        Self { sink: None, batch_size: 500, poll_interval: Duration::from_secs(10), timeout: Duration::from_secs(30) }
+    }
+}
+
+impl ExportConfig {
+    fn from_env() -> Result<Self> {
+        let defaults = Self::default();
+        let batch_size = parse_var("EXPORT_BATCH_SIZE", defaults.batch_size)?;
+        if batch_size <= 0 {
+            return Err(anyhow!("EXPORT_BATCH_SIZE must be positive"));
+        }
+        Ok(Self {
+            sink: ExportSink::from_env()?,
+            batch_size,
+            poll_interval: Duration::from_secs(parse_var(
+                "EXPORT_POLL_INTERVAL_SECS",
+                defaults.poll_interval.as_secs(),
+            )?),
+            timeout: Duration::from_secs(parse_var("EXPORT_TIMEOUT_SECS", defaults.timeout.as_secs())?),
+        })
+    }
+}
+
+/// Embedded Parquet copy of the event columns the explorer aggregates (see analytics.rs)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnalyticsStoreConfig {
+    /// Directory of the segment files (None = aggregates run on Postgres)
+    pub dir: Option<PathBuf>,
+    /// How often buffered events are written out as a segment
+    pub flush_interval: Duration,
+    /// Rows compaction merges small segments up to
+    pub segment_rows: usize,
+    pub compaction_interval: Duration,
+    /// How often per-day counts are compared with ram_events_history
+    pub check_interval: Duration,
+}
+
+impl Default for AnalyticsStoreConfig {
+    fn default() -> Self {
+        Self {
+            dir: None,
+            flush_interval: Duration::from_secs(10),
+            segment_rows: 100_000,
+            compaction_interval: Duration::from_secs(3600),
+            check_interval: Duration::from_secs(3600),
+        }
+    }
+}
+
+impl AnalyticsStoreConfig {
+    fn from_env() -> Result<Self> {
+        let defaults = Self::default();
+        let secs = |name: &str, default: Duration| -> Result<Duration> {
+            match parse_var(name, default.as_secs())? {
+                0 => Err(anyhow!("{} must be at least 1", name)),
+                secs => Ok(Duration::from_secs(secs)),
+            }
+        };
+        let segment_rows = parse_var("ANALYTICS_SEGMENT_ROWS", defaults.segment_rows)?;
+        if segment_rows == 0 {
+            return Err(anyhow!("ANALYTICS_SEGMENT_ROWS must be positive"));
+        }
+        Ok(Self {
+            dir: optional_var("ANALYTICS_STORE_DIR").map(PathBuf::from),
+            flush_interval: secs("ANALYTICS_FLUSH_INTERVAL_SECS", defaults.flush_interval)?,
+            segment_rows,
+            compaction_interval: secs("ANALYTICS_COMPACTION_INTERVAL_SECS", defaults.compaction_interval)?,
+            check_interval: secs("ANALYTICS_CHECK_INTERVAL_SECS", defaults.check_interval)?,
+        })
+    }
+}
+
+/// Public explorer aggregates and their rate limit (see explorer.rs)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExplorerConfig {
+    /// Fewest distinct wallets behind any published figure
+    pub k_anonymity: i64,
+    /// Requests per minute each client may make
+    pub rate_limit_per_minute: u32,
+    /// Proxies in front of the backend that append to `X-Forwarded-For`; the
+    /// client is the hop the outermost of them added (0 = the connection's
+    /// address, the header is ignored)
+    pub trusted_proxies: usize,
+}
+
+impl Default for ExplorerConfig {
+    fn default() -> Self {
+        Self { k_anonymity: 5, rate_limit_per_minute: 60, trusted_proxies: 0 }
+    }
+}
+
+impl ExplorerConfig {
+    fn from_env() -> Result<Self> {
+        let defaults = Self::default();
+        let k_anonymity = parse_var("EXPLORER_K_ANONYMITY", defaults.k_anonymity)?;
+        if k_anonymity < 2 {
+            return Err(anyhow!("EXPLORER_K_ANONYMITY must be at least 2"));
+        }
+        let rate_limit_per_minute = parse_var("EXPLORER_RATE_LIMIT_PER_MIN", defaults.rate_limit_per_minute)?;
+        if rate_limit_per_minute == 0 {
+            return Err(anyhow!("EXPLORER_RATE_LIMIT_PER_MIN must be positive"));
+        }
+        Ok(Self {
+            k_anonymity,
+            rate_limit_per_minute,
+            trusted_proxies: parse_var("EXPLORER_TRUSTED_PROXIES", defaults.trusted_proxies)?,
+        })
+    }
+}
+
+/// Where feature flags are evaluated and how often they're reloaded (see flags.rs)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeatureFlagConfig {
+    /// Deployment environment flags are evaluated in, e.g. `staging`
+    pub environment: String,
+    /// How often flags are reloaded from the database, picking up changes
+    /// made through other backends
+    pub reload_interval: Duration,
+}
+
+impl Default for FeatureFlagConfig {
+    fn default() -> Self {
+        Self { environment: "production".to_string(), reload_interval: Duration::from_secs(30) }
+    }
+}
+
+impl FeatureFlagConfig {
+    fn from_env() -> Result<Self> {
+        let defaults = Self::default();
+        Ok(Self {
+            environment: optional_var("RAM_ENVIRONMENT").unwrap_or(defaults.environment),
+            reload_interval: Duration::from_secs(parse_var(
+                "FEATURE_FLAGS_RELOAD_SECS",
+                defaults.reload_interval.as_secs(),
+            )?),
+        })
+    }
+}
+
+/// Transfers that must carry a travel-rule envelope (see travel_rule.rs)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TravelRuleConfig {
+    /// Smallest raw amount per coin type (keyed by `travel_rule::coin_key`)
+    /// that requires an envelope; coins not listed never do
+    pub thresholds: BTreeMap<String, i64>,
+}
+
+impl TravelRuleConfig {
+    /// Envelope threshold of `coin_type`, if it has one
+    pub fn threshold(&self, coin_type: &str) -> Option<i64> {
+        self.thresholds.get(&crate::travel_rule::coin_key(coin_type)).copied()
+    }
+
+    /// `coin_type=raw_amount` pairs, comma-separated
+    pub fn parse(value: &str) -> Result<Self> {
+        let mut thresholds = BTreeMap::new();
+        for entry in parse_list(value) {
+            let (coin_type, amount) = entry
+                .split_once('=')
+                .ok_or_else(|| anyhow!("Invalid TRAVEL_RULE_THRESHOLDS entry '{}': expected coin_type=amount", entry))?;
+            let amount: i64 = amount
+                .trim()
+                .parse()
+                .ok()
+                .filter(|a| *a > 0)
+                .ok_or_else(|| anyhow!("Invalid TRAVEL_RULE_THRESHOLDS amount in '{}'", entry))?;
+            thresholds.insert(crate::travel_rule::coin_key(coin_type.trim()), amount);
+        }
+        Ok(Self { thresholds })
+    }
+
+    fn from_env() -> Result<Self> {
+        optional_var("TRAVEL_RULE_THRESHOLDS").map_or_else(|| Ok(Self::default()), |v| Self::parse(&v))
+    }
+}
+
+/// How hard the indexer may lean on the fullnodes and the database while
+/// catching up (see indexer/throttle.rs)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexerThrottleConfig {
+    /// RPC calls per second the indexer may make (None = unlimited)
+    pub rpc_calls_per_sec: Option<u32>,
+    /// Share of the pool's connections in use, in percent, at which event
+    /// writes wait and pages shrink
+    pub db_high_water_percent: u8,
+}
+
+impl Default for IndexerThrottleConfig {
+    fn default() -> Self {
+        Self { rpc_calls_per_sec: Some(10), db_high_water_percent: 75 }
+    }
+}
+
+impl IndexerThrottleConfig {
+    fn from_env() -> Result<Self> {
+        let defaults = Self::default();
+        let rpc_calls_per_sec: u32 =
+            parse_var("INDEXER_RPC_RATE_LIMIT", defaults.rpc_calls_per_sec.unwrap_or(0))?;
+        let db_high_water_percent = parse_var("INDEXER_DB_HIGH_WATER_PERCENT", defaults.db_high_water_percent)?;
+        if !(1..=100).contains(&db_high_water_percent) {
+            return Err(anyhow!("INDEXER_DB_HIGH_WATER_PERCENT must be between 1 and 100"));
+        }
+        Ok(Self { rpc_calls_per_sec: (rpc_calls_per_sec > 0).then_some(rpc_calls_per_sec), db_high_water_percent })
+    }
+}
+
+/// USD prices of held coins, and the balance alerts valued with them (see
+/// prices.rs and price_alerts.rs)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PriceConfig {
+    /// Base URL of a CoinGecko-compatible price API (None = price alerts disabled)
+    pub feed_url: Option<String>,
+    /// The feed's ID for each priced coin, keyed by `travel_rule::coin_key`
+    pub coin_ids: BTreeMap<String, String>,
+    /// How long fetched prices are reused
+    pub cache_ttl: Duration,
+    /// How often price alerts are evaluated
+    pub alert_interval: Duration,
+}
+
+impl Default for PriceConfig {
+    fn default() -> Self {
+        Self {
+            feed_url: None,
+            coin_ids: BTreeMap::from([("0x2::sui::SUI".to_string(), "sui".to_string())]),
+            cache_ttl: Duration::from_secs(60),
+            alert_interval: Duration::from_secs(60),
+        }
+    }
+}
+
+impl PriceConfig {
+    fn from_env() -> Result<Self> {
+        let defaults = Self::default();
+        let coin_ids = match optional_var("PRICE_FEED_COINS") {
+            Some(value) => parse_list(&value)
+                .into_iter()
+                .map(|entry| match entry.split_once('=') {
+                    Some((coin_type, id)) if !coin_type.trim().is_empty() && !id.trim().is_empty() => {
+                        Ok((crate::travel_rule::coin_key(coin_type.trim()), id.trim().to_string()))
+                    }
+                    _ => Err(anyhow!("Invalid PRICE_FEED_COINS entry '{}': expected coin_type=feed_id", entry)),
+                })
+                .collect::<Result<_>>()?,
+            None => defaults.coin_ids,
+        };
+        let alert_interval = parse_var("PRICE_ALERT_INTERVAL_SECS", defaults.alert_interval.as_secs())?;
+        if alert_interval == 0 {
+            return Err(anyhow!("PRICE_ALERT_INTERVAL_SECS must be at least 1"));
+        }
+        Ok(Self {
+            feed_url: optional_var("PRICE_FEED_URL").map(|url| url.trim_end_matches('/').to_string()),
+            coin_ids,
+            cache_ttl: Duration::from_secs(parse_var("PRICE_CACHE_SECS", defaults.cache_ttl.as_secs())?),
+            alert_interval: Duration::from_secs(alert_interval),
+        })
+    }
+}
+
+/// How supervised background tasks are restarted after failing (see supervisor.rs)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RestartPolicy {
+    /// Wait before the first restart, doubled on each consecutive one
+    pub initial_backoff: Duration,
+    /// Upper bound on the wait between restarts
+    pub max_backoff: Duration,
+    /// Consecutive failures after which a task is left down
+    pub max_restarts: u32,
+    /// A task that ran this long before failing starts counting afresh
+    pub reset_after: Duration,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self {
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+            max_restarts: 10,
+            reset_after: Duration::from_secs(10 * 60),
+        }
+    }
+}
+
+impl RestartPolicy {
+    fn from_env() -> Result<Self> {
+        let defaults = Self::default();
+        let policy = Self {
+            initial_backoff: Duration::from_millis(parse_var(
+                "TASK_RESTART_BACKOFF_MS",
+                defaults.initial_backoff.as_millis() as u64,
+            )?),
+            max_backoff: Duration::from_millis(parse_var(
+                "TASK_RESTART_MAX_BACKOFF_MS",
+                defaults.max_backoff.as_millis() as u64,
+            )?),
+            max_restarts: parse_var("TASK_MAX_RESTARTS", defaults.max_restarts)?,
+            reset_after: Duration::from_secs(parse_var("TASK_RESTART_RESET_SECS", defaults.reset_after.as_secs())?),
+        };
+        if policy.initial_backoff > policy.max_backoff {
+            return Err(anyhow!("TASK_RESTART_BACKOFF_MS exceeds TASK_RESTART_MAX_BACKOFF_MS"));
+        }
+        Ok(policy)
+    }
+
+    /// Wait before restart number `restart` (1-based)
+    pub fn backoff(&self, restart: u32) -> Duration {
+        let exponent = restart.saturating_sub(1).min(20);
+        self.initial_backoff.saturating_mul(1 << exponent).min(self.max_backoff)
+    }
+}
+
+/// Where the indexer reads RAM events from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexerSource {
+    /// Page `suix_queryEvents` filtered to the RAM events module
+    Events,
+    /// Walk every checkpoint and its transactions, capturing sender and gas
+    Checkpoints,
+}
+
+impl FromStr for IndexerSource {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "events" => Ok(Self::Events),
+            "checkpoints" => Ok(Self::Checkpoints),
+            other => Err(format!("expected 'events' or 'checkpoints', got '{}'", other)),
+        }
+    }
+}
+
+/// Chain identifier of Sui mainnet (first 4 bytes of its genesis checkpoint digest)
+pub const MAINNET_CHAIN_ID: &str = "35834a8a";
+/// Chain identifier of Sui testnet
+pub const TESTNET_CHAIN_ID: &str = "4c78adac";
+
+/// Sui network the backend indexes, sponsors and proxies for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Network {
+    Mainnet,
+    Testnet,
+    Devnet,
+    Localnet,
+}
+
+impl Network {
+    pub const ALL: [Network; 4] = [Network::Mainnet, Network::Testnet, Network::Devnet, Network::Localnet];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Network::Mainnet => "mainnet",
+            Network::Testnet => "testnet",
+            Network::Devnet => "devnet",
+            Network::Localnet => "localnet",
+        }
+    }
+
+    /// Chain identifier that never changes (devnet and localnet are regenesised)
+    pub fn known_chain_id(&self) -> Option<&'static str> {
+        match self {
+            Network::Mainnet => Some(MAINNET_CHAIN_ID),
+            Network::Testnet => Some(TESTNET_CHAIN_ID),
+            Network::Devnet | Network::Localnet => None,
+        }
+    }
+
+    pub fn default_rpc_url(&self) -> &'static str {
+        match self {
+            Network::Mainnet => "https://fullnode.mainnet.sui.io:443",
+            Network::Testnet => "https://fullnode.testnet.sui.io:443",
+            Network::Devnet => "https://fullnode.devnet.sui.io:443",
+            Network::Localnet => "http://127.0.0.1:9000",
+        }
+    }
+}
+
+impl std::fmt::Display for Network {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Network {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Network::ALL
+            .into_iter()
+            .find(|network| network.as_str().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| format!("expected mainnet, testnet, devnet or localnet, got '{}'", s))
+    }
+}
+
+/// The Sui network the backend works against, and the RAM package on it
+///
+/// Must agree with the enclaves' own chain configuration: layout v3 payloads
+/// are signed for one package, and enclaves reporting another network or
+/// package are marked unhealthy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChainConfig {
+    pub network: Network,
+    /// Chain identifier, if known for this network
+    pub chain_id: Option<String>,
+    /// Original RAM package ID on each network it's published on (0x + 64 hex)
+    pub package_ids: BTreeMap<Network, String>,
+    /// Sui fullnodes in order of preference, failed over on outage
+    pub rpc_urls: Vec<String>,
+}
+
+impl Default for ChainConfig {
+    /// Localnet with a zero package, like an enclave without chain configuration
+    fn default() -> Self {
+        Self {
+            network: Network::Localnet,
+            chain_id: None,
+            package_ids: BTreeMap::from([(Network::Localnet, format!("0x{}", "0".repeat(64)))]),
+            rpc_urls: vec![Network::Localnet.default_rpc_url().to_string()],
+        }
+    }
+}
+
+impl ChainConfig {
+    /// SUI_NETWORK, SUI_CHAIN_ID, RAM_PACKAGE_IDS (`network=0x…` pairs),
+    /// RAM_PACKAGE_ID (the package on SUI_NETWORK) and SUI_RPC_URL
+    fn from_env() -> Result<Self> {
+        let network = parse_var("SUI_NETWORK", Network::Testnet)?;
+        let mut package_ids = BTreeMap::new();
+        for pair in parse_list(&optional_var("RAM_PACKAGE_IDS").unwrap_or_default()) {
+            let (name, id) = pair
+                .split_once('=')
+                .ok_or_else(|| anyhow!("RAM_PACKAGE_IDS: expected network=0x…, got '{}'", pair))?;
+            let name: Network = name.parse().map_err(|e| anyhow!("RAM_PACKAGE_IDS: {}", e))?;
+            if package_ids.insert(name, id.trim().to_string()).is_some() {
+                return Err(anyhow!("RAM_PACKAGE_IDS lists {} twice", name));
+            }
+        }
+        if let Some(id) = optional_var("RAM_PACKAGE_ID") {
+            package_ids.insert(network, id);
+        }
+        let rpc_urls = match optional_var("SUI_RPC_URL") {
+            Some(urls) => parse_urls(&urls),
+            None => vec![network.default_rpc_url().to_string()],
+        };
+        Self {
+            network,
+            chain_id: optional_var("SUI_CHAIN_ID").or_else(|| network.known_chain_id().map(str::to_string)),
+            package_ids,
+            rpc_urls,
+        }
+        .validated()
+    }
+
+    /// This configuration with package IDs normalized, or why it's inconsistent
+    pub fn validated(mut self) -> Result<Self> {
+        if let Some(chain_id) = &self.chain_id {
+            if chain_id.len() != 8 || !chain_id.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(anyhow!("SUI_CHAIN_ID '{}' must be 8 hex characters", chain_id));
+            }
+            if let Some(known) = self.network.known_chain_id() {
+                if !chain_id.eq_ignore_ascii_case(known) {
+                    return Err(anyhow!("SUI_CHAIN_ID {} is not {}'s ({})", chain_id, self.network, known));
+                }
+            }
+            self.chain_id = Some(chain_id.to_ascii_lowercase());
+        }
+
+        for (network, id) in self.package_ids.iter_mut() {
+            *id = normalize_package_id(id).with_context(|| format!("{} package ID", network))?;
+        }
+        let mut seen: BTreeMap<&str, Network> = BTreeMap::new();
+        for (network, id) in &self.package_ids {
+            if let Some(other) = seen.insert(id, *network) {
+                return Err(anyhow!("{} and {} are configured with the same package {}", other, network, id));
+            }
+        }
+        if !self.package_ids.contains_key(&self.network) {
+            return Err(anyhow!("no RAM package ID for {} (set RAM_PACKAGE_ID or RAM_PACKAGE_IDS)", self.network));
+        }
+
+        if self.rpc_urls.is_empty() {
+            return Err(anyhow!("SUI_RPC_URL must list at least one endpoint"));
+        }
+        for url in &self.rpc_urls {
+            // A public fullnode of another network is a copy-paste mistake, not a choice
+            let host = url.split("://").nth(1).unwrap_or_default().split(['/', ':']).next().unwrap_or_default();
+            if let Some(other) = Network::ALL
+                .into_iter()
+                .find(|n| *n != self.network && host == format!("fullnode.{}.sui.io", n))
+            {
+                return Err(anyhow!("SUI_RPC_URL {} is a {} fullnode, not {}", url, other, self.network));
+            }
+        }
+        Ok(self)
+    }
+
+    /// RAM package on the configured network
+    pub fn package_id(&self) -> &str {
+        // validated() guarantees it; the default config has one too
+        self.package_ids.get(&self.network).map(String::as_str).unwrap_or_default()
+    }
+
+    /// What `/health` and `/meta/chain` report (not the RPC URLs, which may
+    /// carry provider keys)
+    pub fn info(&self) -> ChainInfo {
+        ChainInfo {
+            network: self.network,
+            chain_id: self.chain_id.clone(),
+            package_id: self.package_id().to_string(),
+        }
+    }
+}
+
+/// The chain a service works against, as the backend and enclaves report it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainInfo {
+    pub network: Network,
+    pub chain_id: Option<String>,
+    pub package_id: String,
+}
+
+/// `0x`-prefixed, zero-padded 64 hex characters
+fn normalize_package_id(id: &str) -> Result<String> {
+    let hex = id.trim().strip_prefix("0x").ok_or_else(|| anyhow!("'{}' must start with 0x", id))?;
+    if hex.is_empty() || hex.len() > 64 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(anyhow!("'{}' must be 0x followed by 1 to 64 hex characters", id));
+    }
+    Ok(format!("0x{:0>64}", hex.to_ascii_lowercase()))
+}
+
+/// Full backend configuration
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub database: DbConfig,
+    /// Enclaves requests are balanced across
+    pub enclaves: Vec<EnclaveConfig>,
+    /// Network, RAM package and fullnodes
+    pub chain: ChainConfig,
+    pub indexer_source: IndexerSource,
+    /// First checkpoint for the checkpoint indexer when it has no stored
+    /// progress (defaults to the latest checkpoint)
+    pub indexer_start_checkpoint: Option<u64>,
+    /// RPC rate limit and database pacing of the indexer
+    pub indexer_throttle: IndexerThrottleConfig,
+    /// Bearer token for /admin routes (admin API disabled if unset)
+    pub admin_token: Option<String>,
+    /// Shared secret for the enclave's privileged diagnostics channel
+    pub nautilus_diagnostics_secret: Option<String>,
+    /// HMAC key for signing requests to the enclave
+    pub nautilus_signing_key: Option<String>,
+    /// Hex Ed25519 private key countersigning transfer receipts
+    /// (None = receipts can't be downloaded)
+    pub receipt_signing_key: Option<String>,
+    /// Inbound request headers the proxy forwards to the enclave
+    pub nautilus_forward_headers: HeaderPolicy,
+    /// Per-route-class timeouts and GET retries
+    pub proxy: ProxyConfig,
+    /// Gas sponsorship for onboarding (None = `/api/onboard` disabled)
+    pub sponsor: Option<SponsorConfig>,
+    /// Gas budget recommendations
+    pub gas: GasConfig,
+    /// Enclave registration (None = `/admin/register_enclave` disabled)
+    pub enclave_registration: Option<EnclaveRegistrationConfig>,
+    /// Inactivity warnings for wallets with a beneficiary
+    pub inactivity: InactivityConfig,
+    /// Archival of old events
+    pub retention: RetentionConfig,
+    /// Merchant webhook deliveries
+    pub webhooks: WebhookConfig,
+    /// Export of indexed events to an analytics sink
+    pub exports: ExportConfig,
+    /// Public explorer aggregates
+    pub explorer: ExplorerConfig,
+    /// Embedded analytics store the explorer aggregates from
+    pub analytics: AnalyticsStoreConfig,
+    /// Feature flag environment and reloads
+    pub feature_flags: FeatureFlagConfig,
+    /// Transfer amounts that require a travel-rule envelope
+    pub travel_rule: TravelRuleConfig,
+    /// Price feed and balance value alerts
+    pub prices: PriceConfig,
+    /// How long portfolio reads are cached when the handle doesn't change
+    /// (zero disables the cache)
+    pub read_cache_ttl: Duration,
+    /// How long `/api/handles/reserve` holds a handle during onboarding
+    pub handle_reservation_ttl: Duration,
+    /// Restarts of the indexer and other background tasks
+    pub restart_policy: RestartPolicy,
+    pub port: u16,
+    /// Injected faults (only honored by builds with the `chaos` feature)
+    pub chaos: Faults,
+}
+
+impl Config {
+    pub fn from_env() -> Result<Self> {
+        let database = DbConfig::from_env()?;
+        if database.min_connections > database.max_connections {
+            return Err(anyhow!(
+                "DB_MIN_CONNECTIONS ({}) exceeds DB_MAX_CONNECTIONS ({})",
+                database.min_connections,
+                database.max_connections
+            ));
+        }
+
+        let chain = ChainConfig::from_env().context("Invalid chain configuration")?;
+
+        let chaos = Faults {
+            upstream_latency: Duration::from_millis(parse_var("CHAOS_UPSTREAM_LATENCY_MS", 0)?),
+            upstream_latency_percent: parse_var("CHAOS_UPSTREAM_LATENCY_PERCENT", 0)?,
+            nautilus_drop_percent: parse_var("CHAOS_NAUTILUS_DROP_PERCENT", 0)?,
+            db_timeout_percent: parse_var("CHAOS_DB_TIMEOUT_PERCENT", 0)?,
+        };
+        chaos.validate()?;
+
+        let enclaves = EnclaveConfig::from_env()?;
+        let sponsor = SponsorConfig::from_env(chain.package_id())?;
+        let enclave_registration = EnclaveRegistrationConfig::from_env(chain.package_id())?;
+        if let Some(sponsor) = &sponsor {
+            if sponsor.enclave_ids.len() != enclaves.len() {
+                return Err(anyhow!(
+                    "RAM_ENCLAVE_ID lists {} Enclave objects for {} NAUTILUS_URL enclaves",
+                    sponsor.enclave_ids.len(),
+                    enclaves.len()
+                ));
+            }
+        }
+
+        Ok(Self {
+            database,
+            enclaves,
+            chain,
+            indexer_source: parse_var("INDEXER_SOURCE", IndexerSource::Events)?,
+            indexer_start_checkpoint: parse_optional_var("INDEXER_START_CHECKPOINT")?,
+            indexer_throttle: IndexerThrottleConfig::from_env()?,
+            admin_token: optional_var("ADMIN_API_TOKEN"),
+            nautilus_diagnostics_secret: optional_var("NAUTILUS_DIAGNOSTICS_SECRET"),
+            nautilus_signing_key: optional_var("NAUTILUS_REQUEST_SIGNING_KEY"),
+            receipt_signing_key: optional_var("RECEIPT_SIGNING_KEY"),
+            nautilus_forward_headers: parse_var("NAUTILUS_FORWARD_HEADERS", HeaderPolicy::default())?,
+            proxy: ProxyConfig::from_env()?,
+            sponsor,
+            gas: GasConfig::from_env()?,
+            enclave_registration,
+            inactivity: InactivityConfig::from_env()?,
+            retention: RetentionConfig::from_env()?,
+            webhooks: WebhookConfig::from_env()?,
+            exports: ExportConfig::from_env()?,
+            explorer: ExplorerConfig::from_env()?,
+            analytics: AnalyticsStoreConfig::from_env()?,
+            feature_flags: FeatureFlagConfig::from_env()?,
+            travel_rule: TravelRuleConfig::from_env()?,
+            prices: PriceConfig::from_env()?,
+            read_cache_ttl: Duration::from_secs(parse_var(
+                "READ_CACHE_TTL_SECS",
+                crate::changes::DEFAULT_READ_CACHE_TTL.as_secs(),
+            )?),
+            handle_reservation_ttl: handle_reservation_ttl()?,
+            restart_policy: RestartPolicy::from_env()?,
+            port: parse_var("PORT", 4000)?,
+            chaos,
+        })
+    }
+}
+
+/// Split a comma-separated list, dropping blanks
+fn parse_list(value: &str) -> Vec<String> {
+    value.split(',').map(str::trim).filter(|v| !v.is_empty()).map(str::to_string).collect()
+}
+
+/// Env var treated as unset when empty
+/// HANDLE_RESERVATION_MINUTES, which can't be zero: a reservation has to
+/// outlive the onboarding it covers
+fn handle_reservation_ttl() -> Result<Duration> {
+    let minutes = parse_var("HANDLE_RESERVATION_MINUTES", crate::reservations::DEFAULT_RESERVATION_TTL.as_secs() / 60)?;
+    if minutes == 0 {
+        return Err(anyhow!("HANDLE_RESERVATION_MINUTES must be at least 1"));
+    }
+    Ok(Duration::from_secs(minutes * 60))
+}
+
+fn optional_var(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|v| !v.is_empty())
+}
+
+fn parse_var<T: FromStr>(name: &str, default: T) -> Result<T>
+where
+    T::Err: std::fmt::Display,
+{
+    Ok(parse_optional_var(name)?.unwrap_or(default))
+}
+
+fn parse_optional_var<T: FromStr>(name: &str) -> Result<Option<T>>
+where
+    T::Err: std::fmt::Display,
+{
+    optional_var(name)
+        .map(|v| v.parse().map_err(|e| anyhow!("Invalid {} '{}': {}", name, v, e)))
+        .transpose()
+}
@@ -0,0 +1,164 @@
+// Contact book: frequent recipients saved under nicknames
+// A wallet's contacts are only read or changed with a request signed by one
+// of its linked addresses (see wallet_auth). Transfers can then name the
+// recipient by nickname with `to_contact` instead of `to_handle`; the proxy
+// swaps in the saved handle before the enclave sees the request, so what
+// gets signed always names a real handle.
+
+use axum::{
+    body::Bytes,
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use serde_json::Value;
+use std::sync::Arc;
+use tracing::{error, info};
+
+use crate::database::Database;
+use crate::handles::normalize_handle;
+use crate::models::{Contact, ContactsQuery, DeleteContactRequest, SaveContactRequest};
+use crate::wallet_auth::authenticate;
+use crate::AppState;
+
+/// Longest nickname, in characters
+pub const MAX_NICKNAME_LEN: usize = 32;
+/// Most contacts one wallet can save
+pub const MAX_CONTACTS: i64 = 200;
+
+/// Proxied route whose recipient can be a contact
+const TRANSFER_PATH: &str = "/transfer";
+
+/// List a wallet's contacts
+///
+/// GET /api/contacts?handle= (signed, action `read`)
+pub async fn list_contacts(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ContactsQuery>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<Contact>>, StatusCode> {
+    authenticate(&state, &headers, "contacts", "read", &query.handle, &[]).await?;
+
+    let contacts = Database::list_contacts(&state.db, &query.handle).await.map_err(|e| {
+        error!("Failed to list contacts: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(Json(contacts))
+}
+
+/// Save a recipient under a nickname, replacing what the nickname named before
+///
+/// POST /api/contacts (signed, action `save`) — 404 if the contact has no
+/// wallet, 409 once the contact book is full
+pub async fn save_contact(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<Contact>, StatusCode> {
+    let request: SaveContactRequest = serde_json::from_slice(&body).map_err(|_| StatusCode::BAD_REQUEST)?;
+    let nickname = request.nickname.trim();
+    let is_self = normalize_handle(&request.contact_handle) == normalize_handle(&request.handle);
+    if !valid_nickname(nickname) || request.contact_handle.is_empty() || is_self {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    authenticate(&state, &headers, "contacts", "save", &request.handle, &body).await?;
+
+    // Saved as registered on-chain, the spelling a transfer to it is signed for
+    let contact_handle = Database::get_registered_handle(&state.db, &request.contact_handle)
+        .await
+        .map_err(|e| {
+            error!("Failed to fetch wallet: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let saved = Database::count_contacts(&state.db, &request.handle).await.map_err(|e| {
+        error!("Failed to count contacts: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let replacing = Database::resolve_contact(&state.db, &request.handle, nickname)
+        .await
+        .map_err(|e| {
+            error!("Failed to resolve contact: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .is_some();
+    if saved >= MAX_CONTACTS && !replacing {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let contact = Database::upsert_contact(
+        &state.db,
+        &request.handle,
+        nickname,
+        &contact_handle,
+        chrono::Utc::now().timestamp_millis(),
+    )
+    .await
+    .map_err(|e| {
+        error!("Failed to save contact: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    info!("{} saved {} as a contact", request.handle, contact.contact_handle);
+
+    Ok(Json(contact))
+}
+
+/// Remove a saved recipient
+///
+/// DELETE /api/contacts (signed, action `delete`)
+pub async fn delete_contact(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<StatusCode, StatusCode> {
+    let request: DeleteContactRequest = serde_json::from_slice(&body).map_err(|_| StatusCode::BAD_REQUEST)?;
+    authenticate(&state, &headers, "contacts", "delete", &request.handle, &body).await?;
+
+    let deleted = Database::delete_contact(&state.db, &request.handle, request.nickname.trim())
+        .await
+        .map_err(|e| {
+            error!("Failed to delete contact: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    if !deleted {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Replace a transfer's `to_contact` with the handle saved under it
+///
+/// Other requests pass through untouched. 400 if a transfer names both a
+/// handle and a contact, 422 if the sender has no such contact.
+pub async fn resolve_recipient(state: &AppState, path: &str, body: Bytes) -> Result<Bytes, StatusCode> {
+    if path != TRANSFER_PATH {
+        return Ok(body);
+    }
+    let Ok(mut request) = serde_json::from_slice::<Value>(&body) else {
+        return Ok(body);
+    };
+    let payload = &mut request["payload"];
+    let (Some(from_handle), Some(nickname)) = (payload["from_handle"].as_str(), payload["to_contact"].as_str()) else {
+        return Ok(body);
+    };
+    if !payload["to_handle"].is_null() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let contact_handle = Database::resolve_contact(&state.db, from_handle, nickname.trim())
+        .await
+        .map_err(|e| {
+            error!("Failed to resolve contact: {}", e);
+            StatusCode::SERVICE_UNAVAILABLE
+        })?
+        .ok_or(StatusCode::UNPROCESSABLE_ENTITY)?;
+
+    let payload = payload.as_object_mut().ok_or(StatusCode::BAD_REQUEST)?;
+    payload.remove("to_contact");
+    payload.insert("to_handle".to_string(), Value::String(contact_handle));
+    serde_json::to_vec(&request).map(Bytes::from).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+fn valid_nickname(nickname: &str) -> bool {
+    !nickname.is_empty() && nickname.chars().count() <= MAX_NICKNAME_LEN && !nickname.chars().any(char::is_control)
+}
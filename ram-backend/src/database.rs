@@ -1,23 +1,91 @@
 // Database layer for RAM backend
 
-use crate::models::RamEvent;
+use crate::chaos;
+use crate::config::DbConfig;
+use crate::handles::normalize_handle;
+use crate::models::{
+    AdminAuditEntry, AnalyticsRow, ApiKey, ApiKeyRecord, BioAuthAttempt, CoinFlows, Contact, DailyEventCount, DailyTransferVolume, DailyWalletCreations, DueWebhookDelivery, ExportedEvent, FailedEvent, FeatureFlag, InactiveWallet,
+    IndexerGap, LinkedAddress, Notification, Onboarding, PolicyEvent, PriceAlert, Profile, RamEvent, Refund, RefundablePayment, RouteAlias, Webhook,
+    TransferReceipt, TravelRuleEnvelope, WebhookDelivery,
+};
 use anyhow::Result;
+use serde::Serialize;
+use serde_json::Value;
 use sqlx::{postgres::PgPoolOptions, Pool, Postgres};
-use tracing::info;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+use tracing::{info, warn};
 
 pub type DbPool = Pool<Postgres>;
 
+/// Slow-query threshold in ms, set from `DbConfig` at connect time
+static SLOW_QUERY_MS: AtomicU64 = AtomicU64::new(200);
+
+static QUERY_STATS: Mutex<BTreeMap<&'static str, QueryStats>> = Mutex::new(BTreeMap::new());
+
+/// Latency counters for one named query
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct QueryStats {
+    pub calls: u64,
+    pub slow_calls: u64,
+    pub total_ms: f64,
+    pub max_ms: f64,
+}
+
+/// Records a query's latency when dropped, so early `?` returns are timed too
+struct QueryTimer {
+    name: &'static str,
+    started: Instant,
+}
+
+impl QueryTimer {
+    /// Every query starts here, so it's also where injected DB timeouts fire
+    fn start(name: &'static str) -> Result<Self> {
+        chaos::db_timeout()?;
+        Ok(Self { name, started: Instant::now() })
+    }
+}
+
+impl Drop for QueryTimer {
+    fn drop(&mut self) {
+        let elapsed_ms = self.started.elapsed().as_secs_f64() * 1000.0;
+        let slow = elapsed_ms >= SLOW_QUERY_MS.load(Ordering::Relaxed) as f64;
+        if slow {
+            warn!("Slow query {}: {:.1}ms", self.name, elapsed_ms);
+        }
+
+        let mut stats = QUERY_STATS.lock().unwrap();
+        let entry = stats.entry(self.name).or_default();
+        entry.calls += 1;
+        entry.total_ms += elapsed_ms;
+        entry.max_ms = entry.max_ms.max(elapsed_ms);
+        if slow {
+            entry.slow_calls += 1;
+        }
+    }
+}
+
+/// Snapshot of per-query latency counters since startup
+pub fn query_stats() -> BTreeMap<&'static str, QueryStats> {
+    QUERY_STATS.lock().unwrap().clone()
+}
+
 pub struct Database;
 
 impl Database {
-    /// Initialize database connection pool
+    /// Initialize database connection pool with default pool settings
     pub async fn init(database_url: &str) -> Result<DbPool> {
-        info!("Connecting to database: {}", database_url);
-        
-        let pool = PgPoolOptions::new()
-            .max_connections(5)
-            .connect(database_url)
-            .await?;
+        Self::connect(&DbConfig::new(database_url)).await
+    }
+
+    /// Connect the primary pool and run migrations
+    pub async fn connect(config: &DbConfig) -> Result<DbPool> {
+        info!("Connecting to database: {}", config.url);
+        SLOW_QUERY_MS.store(config.slow_query_threshold.as_millis() as u64, Ordering::Relaxed);
+
+        let pool = pool_options(config).connect(&config.url).await?;
 
         // Run migrations
         info!("Running database migrations...");
@@ -29,16 +97,29 @@ impl Database {
         Ok(pool)
     }
 
+    /// Connect the read-only pool, falling back to the primary when no replica is configured
+    pub async fn connect_read_pool(config: &DbConfig, primary: &DbPool) -> Result<DbPool> {
+        match &config.read_url {
+            Some(url) => {
+                info!("Connecting to read replica: {}", url);
+                Ok(pool_options(config).connect(url).await?)
+            }
+            None => Ok(primary.clone()),
+        }
+    }
+
     /// Insert a new event into the database
     pub async fn insert_event(pool: &DbPool, event: &RamEvent) -> Result<i64> {
+        let _timer = QueryTimer::start("insert_event")?;
         let timestamp_ms = event.timestamp.timestamp_millis();
         
         let result = sqlx::query!(
             r#"
             INSERT INTO ram_events (
                 event_type, transaction_digest, timestamp_ms,
-                handle, from_handle, to_handle, amount
-            ) VALUES ($1, $2, $3, $4, $5, $6, $7)
+                handle, from_handle, to_handle, amount, coin_type, wallet_id,
+                result_code, raw_json, gas_used, sender, memo, payload_version
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)
             ON CONFLICT (transaction_digest, event_type, handle) DO NOTHING
             RETURNING id
             "#,
@@ -48,7 +129,15 @@ impl Database {
             event.handle,
             event.from_handle,
             event.to_handle,
-            event.amount
+            event.amount,
+            event.coin_type,
+            event.wallet_id,
+            event.result_code,
+            event.raw_json,
+            event.gas_used,
+            event.sender,
+            event.memo,
+            event.payload_version
         )
         .fetch_optional(pool)
         .await?;
@@ -56,22 +145,38 @@ impl Database {
         Ok(result.map(|r| r.id).unwrap_or(0))
     }
 
-    /// Get events for a specific handle with pagination
+    /// Get events for a specific handle with pagination, archived ones included
     pub async fn get_events_by_handle(
         pool: &DbPool,
         handle: &str,
         limit: i64,
         offset: i64,
     ) -> Result<Vec<RamEvent>> {
+        let _timer = QueryTimer::start("get_events_by_handle")?;
+        let handle = normalize_handle(handle);
         let rows = sqlx::query!(
             r#"
             SELECT 
-                event_type, transaction_digest as tx_digest, 
-                to_timestamp(timestamp_ms / 1000.0) as "timestamp!",
-                handle, from_handle, to_handle, amount
-            FROM ram_events
-            WHERE handle = $1 OR from_handle = $1 OR to_handle = $1
-            ORDER BY timestamp_ms DESC
+                e.event_type as "event_type!", e.transaction_digest as "tx_digest!", 
+                to_timestamp(e.timestamp_ms / 1000.0) as "timestamp!",
+                e.handle, e.from_handle, e.to_handle, e.memo, e.payload_version, e.amount, e.coin_type, e.wallet_id,
+                e.result_code, e.raw_json, e.gas_used, e.sender,
+                la.label as "sender_label?",
+                fp.display_name as "from_display_name?", tp.display_name as "to_display_name?",
+                rf.payment_tx_digest as "refund_of?",
+                ARRAY(
+                    SELECT r.refund_tx_digest FROM refunds r
+                    WHERE r.payment_tx_digest = e.transaction_digest AND r.status = 'completed'
+                      AND e.event_type = 'Transferred'
+                    ORDER BY r.completed_at_ms
+                ) as "refunded_in!: Vec<String>"
+            FROM ram_events_history e
+            LEFT JOIN linked_addresses la ON la.handle_norm = e.handle_norm AND la.address = e.sender
+            LEFT JOIN profiles fp ON fp.handle_norm = e.from_handle_norm
+            LEFT JOIN profiles tp ON tp.handle_norm = e.to_handle_norm
+            LEFT JOIN refunds rf ON rf.refund_tx_digest = e.transaction_digest AND e.event_type = 'Transferred'
+            WHERE e.handle_norm = $1 OR e.from_handle_norm = $1 OR e.to_handle_norm = $1
+            ORDER BY e.timestamp_ms DESC, e.id DESC
             LIMIT $2 OFFSET $3
             "#,
             handle,
@@ -90,12 +195,2244 @@ impl Database {
                 handle: row.handle,
                 from_handle: row.from_handle,
                 to_handle: row.to_handle,
+                memo: row.memo,
+                payload_version: row.payload_version,
                 amount: row.amount,
+                coin_type: row.coin_type,
+                result_code: row.result_code,
                 owner: None,
+                wallet_id: row.wallet_id,
+                raw_json: row.raw_json,
+                gas_used: row.gas_used,
+                sender: row.sender,
+                sender_label: row.sender_label,
+                from_display_name: row.from_display_name,
+                to_display_name: row.to_display_name,
+                refund_of: row.refund_of,
+                refunded_in: row.refunded_in,
             })
             .collect();
 
         Ok(events)
     }
-}
 
+    /// Link an address to a handle, as spelled on-chain, or update its label if already linked
+    pub async fn upsert_linked_address(
+        pool: &DbPool,
+        handle: &str,
+        address: &str,
+        label: &str,
+        linked_at_ms: i64,
+    ) -> Result<()> {
+        let _timer = QueryTimer::start("upsert_linked_address")?;
+        sqlx::query!(
+            r#"
+            INSERT INTO linked_addresses (handle, address, label, linked_at_ms)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (handle_norm, address) DO UPDATE SET label = $3, linked_at_ms = $4
+            "#,
+            handle,
+            address,
+            label,
+            linked_at_ms
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Remove an address from a handle's linked addresses
+    pub async fn remove_linked_address(pool: &DbPool, handle: &str, address: &str) -> Result<()> {
+        let _timer = QueryTimer::start("remove_linked_address")?;
+        let handle = normalize_handle(handle);
+        sqlx::query!(
+            "DELETE FROM linked_addresses WHERE handle_norm = $1 AND address = $2",
+            handle,
+            address
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Get all addresses currently linked to a handle, oldest first
+    pub async fn get_linked_addresses(pool: &DbPool, handle: &str) -> Result<Vec<LinkedAddress>> {
+        let _timer = QueryTimer::start("get_linked_addresses")?;
+        let handle = normalize_handle(handle);
+        let addresses = sqlx::query_as!(
+            LinkedAddress,
+            r#"
+            SELECT address, label, linked_at_ms
+            FROM linked_addresses
+            WHERE handle_norm = $1
+            ORDER BY linked_at_ms ASC
+            "#,
+            handle
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(addresses)
+    }
+
+    /// Receive-page data stored for a handle
+    pub async fn get_profile(pool: &DbPool, handle: &str) -> Result<Option<Profile>> {
+        let _timer = QueryTimer::start("get_profile")?;
+        let handle = normalize_handle(handle);
+        let profile = sqlx::query_as!(
+            Profile,
+            r#"
+            SELECT handle, display_name, accepted_coin_types, avatar_hash, preferred_locale, notifications,
+                   updated_by, updated_at_ms
+            FROM profiles
+            WHERE handle_norm = $1
+            "#,
+            handle
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(profile)
+    }
+
+    /// Store a profile unless a newer update is already stored; false if it was stale
+    ///
+    /// New profiles take the handle as the wallet was registered on-chain.
+    pub async fn upsert_profile(pool: &DbPool, profile: &Profile) -> Result<bool> {
+        let _timer = QueryTimer::start("upsert_profile")?;
+        let handle = Self::get_registered_handle(pool, &profile.handle)
+            .await?
+            .unwrap_or_else(|| profile.handle.clone());
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO profiles (
+                handle, display_name, accepted_coin_types, avatar_hash, preferred_locale, notifications,
+                updated_by, updated_at_ms
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            ON CONFLICT (handle_norm) DO UPDATE SET
+                display_name = $2, accepted_coin_types = $3, avatar_hash = $4,
+                preferred_locale = $5, notifications = $6, updated_by = $7, updated_at_ms = $8
+            WHERE profiles.updated_at_ms < $8
+            "#,
+            handle,
+            profile.display_name,
+            &profile.accepted_coin_types,
+            profile.avatar_hash,
+            profile.preferred_locale,
+            profile.notifications,
+            profile.updated_by,
+            profile.updated_at_ms
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Delete a profile last updated before a time; false if there was none
+    pub async fn delete_profile(pool: &DbPool, handle: &str, before_ms: i64) -> Result<bool> {
+        let _timer = QueryTimer::start("delete_profile")?;
+        let handle = normalize_handle(handle);
+        let result = sqlx::query!(
+            "DELETE FROM profiles WHERE handle_norm = $1 AND updated_at_ms < $2",
+            handle,
+            before_ms
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// A wallet's contacts, by nickname
+    pub async fn list_contacts(pool: &DbPool, handle: &str) -> Result<Vec<Contact>> {
+        let _timer = QueryTimer::start("list_contacts")?;
+        let handle = normalize_handle(handle);
+        let contacts = sqlx::query_as!(
+            Contact,
+            r#"
+            SELECT nickname, contact_handle, created_at_ms
+            FROM contacts
+            WHERE handle = $1
+            ORDER BY lower(nickname)
+            "#,
+            handle
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(contacts)
+    }
+
+    pub async fn count_contacts(pool: &DbPool, handle: &str) -> Result<i64> {
+        let _timer = QueryTimer::start("count_contacts")?;
+        let handle = normalize_handle(handle);
+        let count = sqlx::query_scalar!(
+            r#"SELECT COUNT(*) AS "count!" FROM contacts WHERE handle = $1"#,
+            handle
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    /// Save a contact, repointing the nickname if it's already used
+    pub async fn upsert_contact(
+        pool: &DbPool,
+        handle: &str,
+        nickname: &str,
+        contact_handle: &str,
+        created_at_ms: i64,
+    ) -> Result<Contact> {
+        let _timer = QueryTimer::start("upsert_contact")?;
+        let handle = normalize_handle(handle);
+        let contact = sqlx::query_as!(
+            Contact,
+            r#"
+            INSERT INTO contacts (handle, nickname, contact_handle, created_at_ms)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (handle, lower(nickname)) DO UPDATE SET
+                nickname = $2, contact_handle = $3, created_at_ms = $4
+            RETURNING nickname, contact_handle, created_at_ms
+            "#,
+            handle,
+            nickname,
+            contact_handle,
+            created_at_ms
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(contact)
+    }
+
+    /// Remove a contact; false if there was none
+    pub async fn delete_contact(pool: &DbPool, handle: &str, nickname: &str) -> Result<bool> {
+        let _timer = QueryTimer::start("delete_contact")?;
+        let handle = normalize_handle(handle);
+        let result = sqlx::query!(
+            "DELETE FROM contacts WHERE handle = $1 AND lower(nickname) = lower($2)",
+            handle,
+            nickname
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Handle a wallet saved under a nickname
+    pub async fn resolve_contact(pool: &DbPool, handle: &str, nickname: &str) -> Result<Option<String>> {
+        let _timer = QueryTimer::start("resolve_contact")?;
+        let handle = normalize_handle(handle);
+        let contact_handle = sqlx::query_scalar!(
+            "SELECT contact_handle FROM contacts WHERE handle = $1 AND lower(nickname) = lower($2)",
+            handle,
+            nickname
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(contact_handle)
+    }
+
+    /// A wallet's price alerts, oldest first
+    pub async fn list_price_alerts(pool: &DbPool, handle: &str) -> Result<Vec<PriceAlert>> {
+        let _timer = QueryTimer::start("list_price_alerts")?;
+        let handle = normalize_handle(handle);
+        let alerts = sqlx::query_as!(
+            PriceAlert,
+            r#"
+            SELECT id, handle, coin_type, direction, threshold_usd, triggered_at_ms, created_at_ms, updated_at_ms
+            FROM price_alerts
+            WHERE handle = $1
+            ORDER BY id
+            "#,
+            handle
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(alerts)
+    }
+
+    /// Every wallet's price alerts, grouped by wallet
+    pub async fn get_all_price_alerts(pool: &DbPool) -> Result<Vec<PriceAlert>> {
+        let _timer = QueryTimer::start("get_all_price_alerts")?;
+        let alerts = sqlx::query_as!(
+            PriceAlert,
+            r#"
+            SELECT id, handle, coin_type, direction, threshold_usd, triggered_at_ms, created_at_ms, updated_at_ms
+            FROM price_alerts
+            ORDER BY handle, id
+            "#
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(alerts)
+    }
+
+    pub async fn count_price_alerts(pool: &DbPool, handle: &str) -> Result<i64> {
+        let _timer = QueryTimer::start("count_price_alerts")?;
+        let handle = normalize_handle(handle);
+        let count = sqlx::query_scalar!(
+            r#"SELECT COUNT(*) AS "count!" FROM price_alerts WHERE handle = $1"#,
+            handle
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    /// Store a new, armed price alert
+    pub async fn insert_price_alert(
+        pool: &DbPool,
+        handle: &str,
+        coin_type: &str,
+        direction: &str,
+        threshold_usd: f64,
+        now_ms: i64,
+    ) -> Result<PriceAlert> {
+        let _timer = QueryTimer::start("insert_price_alert")?;
+        let handle = normalize_handle(handle);
+        let alert = sqlx::query_as!(
+            PriceAlert,
+            r#"
+            INSERT INTO price_alerts (handle, coin_type, direction, threshold_usd, created_at_ms, updated_at_ms)
+            VALUES ($1, $2, $3, $4, $5, $5)
+            RETURNING id, handle, coin_type, direction, threshold_usd, triggered_at_ms, created_at_ms, updated_at_ms
+            "#,
+            handle,
+            coin_type,
+            direction,
+            threshold_usd,
+            now_ms
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(alert)
+    }
+
+    /// Replace a wallet's price alert's condition and re-arm it; None if the
+    /// wallet has no such alert
+    pub async fn update_price_alert(
+        pool: &DbPool,
+        handle: &str,
+        id: i64,
+        coin_type: &str,
+        direction: &str,
+        threshold_usd: f64,
+        now_ms: i64,
+    ) -> Result<Option<PriceAlert>> {
+        let _timer = QueryTimer::start("update_price_alert")?;
+        let handle = normalize_handle(handle);
+        let alert = sqlx::query_as!(
+            PriceAlert,
+            r#"
+            UPDATE price_alerts
+            SET coin_type = $3, direction = $4, threshold_usd = $5, triggered_at_ms = NULL, updated_at_ms = $6
+            WHERE id = $1 AND handle = $2
+            RETURNING id, handle, coin_type, direction, threshold_usd, triggered_at_ms, created_at_ms, updated_at_ms
+            "#,
+            id,
+            handle,
+            coin_type,
+            direction,
+            threshold_usd,
+            now_ms
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(alert)
+    }
+
+    /// Remove a wallet's price alert; false if it has no such alert
+    pub async fn delete_price_alert(pool: &DbPool, handle: &str, id: i64) -> Result<bool> {
+        let _timer = QueryTimer::start("delete_price_alert")?;
+        let handle = normalize_handle(handle);
+        let result = sqlx::query!("DELETE FROM price_alerts WHERE id = $1 AND handle = $2", id, handle)
+            .execute(pool)
+            .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Mark an armed price alert triggered and notify its owner; false if it
+    /// was changed, removed or already triggered meanwhile
+    pub async fn trigger_price_alert(pool: &DbPool, alert: &PriceAlert, data: &Value, now_ms: i64) -> Result<bool> {
+        let _timer = QueryTimer::start("trigger_price_alert")?;
+        let mut tx = pool.begin().await?;
+        let result = sqlx::query!(
+            r#"
+            UPDATE price_alerts SET triggered_at_ms = $3
+            WHERE id = $1 AND updated_at_ms = $2 AND triggered_at_ms IS NULL
+            "#,
+            alert.id,
+            alert.updated_at_ms,
+            now_ms
+        )
+        .execute(&mut *tx)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+        sqlx::query!(
+            r#"
+            INSERT INTO notifications (handle, kind, data, created_at_ms)
+            VALUES ($1, 'price_alert', $2, $3)
+            "#,
+            alert.handle,
+            data,
+            now_ms
+        )
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(true)
+    }
+
+    /// Re-arm a triggered price alert whose condition no longer holds
+    pub async fn rearm_price_alert(pool: &DbPool, alert: &PriceAlert) -> Result<()> {
+        let _timer = QueryTimer::start("rearm_price_alert")?;
+        sqlx::query!(
+            "UPDATE price_alerts SET triggered_at_ms = NULL WHERE id = $1 AND updated_at_ms = $2",
+            alert.id,
+            alert.updated_at_ms
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Name a wallet's inactivity beneficiary, both as spelled on-chain, forgetting any earlier warning
+    pub async fn upsert_beneficiary(
+        pool: &DbPool,
+        handle: &str,
+        beneficiary_handle: &str,
+        inactivity_timeout_ms: i64,
+        set_at_ms: i64,
+    ) -> Result<()> {
+        let _timer = QueryTimer::start("upsert_beneficiary")?;
+        sqlx::query!(
+            r#"
+            INSERT INTO beneficiaries (handle, beneficiary_handle, inactivity_timeout_ms, set_at_ms)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (handle_norm) DO UPDATE SET
+                beneficiary_handle = $2, inactivity_timeout_ms = $3, set_at_ms = $4, warned_at_ms = NULL
+            "#,
+            handle,
+            beneficiary_handle,
+            inactivity_timeout_ms,
+            set_at_ms
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn remove_beneficiary(pool: &DbPool, handle: &str) -> Result<()> {
+        let _timer = QueryTimer::start("remove_beneficiary")?;
+        let handle = normalize_handle(handle);
+        sqlx::query!("DELETE FROM beneficiaries WHERE handle_norm = $1", handle)
+            .execute(pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Wallets whose claim window opens within `warning_ms` of `now_ms` and
+    /// whose owner hasn't been warned since their last activity
+    ///
+    /// Activity is the latest event of one of `activity_events` under the
+    /// wallet's handle, or the beneficiary being set.
+    pub async fn get_unwarned_inactive_wallets(
+        pool: &DbPool,
+        activity_events: &[String],
+        now_ms: i64,
+        warning_ms: i64,
+    ) -> Result<Vec<InactiveWallet>> {
+        let _timer = QueryTimer::start("get_unwarned_inactive_wallets")?;
+        let wallets = sqlx::query_as!(
+            InactiveWallet,
+            r#"
+            SELECT handle as "handle!", beneficiary_handle as "beneficiary_handle!",
+                   last_active_ms as "last_active_ms!",
+                   (last_active_ms + inactivity_timeout_ms) as "claimable_at_ms!"
+            FROM (
+                SELECT b.handle, b.beneficiary_handle, b.inactivity_timeout_ms, b.warned_at_ms,
+                       GREATEST(b.set_at_ms, COALESCE(MAX(e.timestamp_ms), 0)) AS last_active_ms
+                FROM beneficiaries b
+                LEFT JOIN ram_events_history e ON e.handle_norm = b.handle_norm AND e.event_type = ANY($1)
+                GROUP BY b.handle
+            ) w
+            WHERE last_active_ms + inactivity_timeout_ms - $3 <= $2
+              AND (warned_at_ms IS NULL OR warned_at_ms < last_active_ms)
+            ORDER BY last_active_ms
+            "#,
+            activity_events,
+            now_ms,
+            warning_ms
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(wallets)
+    }
+
+    /// Queue a notification for a wallet's owner and mark them warned
+    pub async fn record_inactivity_warning(pool: &DbPool, handle: &str, data: &Value, now_ms: i64) -> Result<()> {
+        let _timer = QueryTimer::start("record_inactivity_warning")?;
+        let handle = normalize_handle(handle);
+        let mut tx = pool.begin().await?;
+        sqlx::query!(
+            r#"
+            INSERT INTO notifications (handle, kind, data, created_at_ms)
+            VALUES ($1, 'inactivity_warning', $2, $3)
+            "#,
+            handle,
+            data,
+            now_ms
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query!("UPDATE beneficiaries SET warned_at_ms = $2 WHERE handle_norm = $1", handle, now_ms)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// A wallet's notifications, newest first
+    pub async fn list_notifications(pool: &DbPool, handle: &str, limit: i64) -> Result<Vec<Notification>> {
+        let _timer = QueryTimer::start("list_notifications")?;
+        let handle = normalize_handle(handle);
+        let notifications = sqlx::query_as!(
+            Notification,
+            r#"
+            SELECT id, kind, data, created_at_ms
+            FROM notifications
+            WHERE handle = $1
+            ORDER BY created_at_ms DESC, id DESC
+            LIMIT $2
+            "#,
+            handle,
+            limit
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(notifications)
+    }
+
+    /// A wallet's API keys, revoked ones included, oldest first
+    pub async fn list_api_keys(pool: &DbPool, handle: &str) -> Result<Vec<ApiKey>> {
+        let _timer = QueryTimer::start("list_api_keys")?;
+        let handle = normalize_handle(handle);
+        let keys = sqlx::query_as!(
+            ApiKey,
+            r#"
+            SELECT id, scope, label, created_at_ms, rotated_at_ms, last_used_at_ms, revoked_at_ms
+            FROM api_keys
+            WHERE handle = $1
+            ORDER BY created_at_ms, id
+            "#,
+            handle
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(keys)
+    }
+
+    pub async fn count_active_api_keys(pool: &DbPool, handle: &str) -> Result<i64> {
+        let _timer = QueryTimer::start("count_active_api_keys")?;
+        let handle = normalize_handle(handle);
+        let count = sqlx::query_scalar!(
+            r#"SELECT COUNT(*) AS "count!" FROM api_keys WHERE handle = $1 AND revoked_at_ms IS NULL"#,
+            handle
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    pub async fn insert_api_key(
+        pool: &DbPool,
+        id: &str,
+        handle: &str,
+        key_hash: &str,
+        scope: &str,
+        label: &str,
+        created_at_ms: i64,
+    ) -> Result<ApiKey> {
+        let _timer = QueryTimer::start("insert_api_key")?;
+        let handle = normalize_handle(handle);
+        let key = sqlx::query_as!(
+            ApiKey,
+            r#"
+            INSERT INTO api_keys (id, handle, key_hash, scope, label, created_at_ms)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING id, scope, label, created_at_ms, rotated_at_ms, last_used_at_ms, revoked_at_ms
+            "#,
+            id,
+            handle,
+            key_hash,
+            scope,
+            label,
+            created_at_ms
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(key)
+    }
+
+    pub async fn get_api_key(pool: &DbPool, id: &str) -> Result<Option<ApiKeyRecord>> {
+        let _timer = QueryTimer::start("get_api_key")?;
+        let key = sqlx::query_as!(
+            ApiKeyRecord,
+            "SELECT id, handle, key_hash, scope, revoked_at_ms FROM api_keys WHERE id = $1",
+            id
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(key)
+    }
+
+    /// Replace an active key's hash; None if the wallet has no such active key
+    pub async fn rotate_api_key(
+        pool: &DbPool,
+        id: &str,
+        handle: &str,
+        key_hash: &str,
+        now_ms: i64,
+    ) -> Result<Option<ApiKey>> {
+        let _timer = QueryTimer::start("rotate_api_key")?;
+        let handle = normalize_handle(handle);
+        let key = sqlx::query_as!(
+            ApiKey,
+            r#"
+            UPDATE api_keys SET key_hash = $3, rotated_at_ms = $4
+            WHERE id = $1 AND handle = $2 AND revoked_at_ms IS NULL
+            RETURNING id, scope, label, created_at_ms, rotated_at_ms, last_used_at_ms, revoked_at_ms
+            "#,
+            id,
+            handle,
+            key_hash,
+            now_ms
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(key)
+    }
+
+    /// Revoke an active key; false if the wallet has no such active key
+    pub async fn revoke_api_key(pool: &DbPool, id: &str, handle: &str, now_ms: i64) -> Result<bool> {
+        let _timer = QueryTimer::start("revoke_api_key")?;
+        let handle = normalize_handle(handle);
+        let result = sqlx::query!(
+            "UPDATE api_keys SET revoked_at_ms = $3 WHERE id = $1 AND handle = $2 AND revoked_at_ms IS NULL",
+            id,
+            handle,
+            now_ms
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    pub async fn touch_api_key(pool: &DbPool, id: &str, now_ms: i64) -> Result<()> {
+        let _timer = QueryTimer::start("touch_api_key")?;
+        sqlx::query!("UPDATE api_keys SET last_used_at_ms = $2 WHERE id = $1", id, now_ms)
+            .execute(pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_webhook(pool: &DbPool, handle: &str) -> Result<Option<Webhook>> {
+        let _timer = QueryTimer::start("get_webhook")?;
+        let handle = normalize_handle(handle);
+        let webhook = sqlx::query_as!(
+            Webhook,
+            "SELECT url, created_at_ms, updated_at_ms FROM webhooks WHERE handle = $1",
+            handle
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(webhook)
+    }
+
+    /// Register a wallet's webhook, or point it at a new URL with a new secret
+    pub async fn upsert_webhook(pool: &DbPool, handle: &str, url: &str, secret: &str, now_ms: i64) -> Result<Webhook> {
+        let _timer = QueryTimer::start("upsert_webhook")?;
+        let handle = normalize_handle(handle);
+        let webhook = sqlx::query_as!(
+            Webhook,
+            r#"
+            INSERT INTO webhooks (handle, url, secret, created_at_ms, updated_at_ms)
+            VALUES ($1, $2, $3, $4, $4)
+            ON CONFLICT (handle) DO UPDATE SET url = $2, secret = $3, updated_at_ms = $4
+            RETURNING url, created_at_ms, updated_at_ms
+            "#,
+            handle,
+            url,
+            secret,
+            now_ms
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(webhook)
+    }
+
+    /// Remove a wallet's webhook, giving up on its pending deliveries; false
+    /// if it had none
+    pub async fn delete_webhook(pool: &DbPool, handle: &str) -> Result<bool> {
+        let _timer = QueryTimer::start("delete_webhook")?;
+        let handle = normalize_handle(handle);
+        let deleted = sqlx::query_scalar!(
+            r#"
+            WITH removed AS (
+                DELETE FROM webhooks WHERE handle = $1 RETURNING handle
+            ), abandoned AS (
+                UPDATE webhook_deliveries
+                SET status = 'dead', last_error = 'webhook removed'
+                WHERE handle IN (SELECT handle FROM removed) AND status = 'pending'
+            )
+            SELECT COUNT(*) AS "count!" FROM removed
+            "#,
+            handle
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(deleted > 0)
+    }
+
+    /// Queue a notification for the wallet's webhook; false if the wallet has
+    /// none or the event was already queued
+    pub async fn queue_webhook_delivery(
+        pool: &DbPool,
+        id: &str,
+        handle: &str,
+        payload: &Value,
+        now_ms: i64,
+    ) -> Result<bool> {
+        let _timer = QueryTimer::start("queue_webhook_delivery")?;
+        let handle = normalize_handle(handle);
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO webhook_deliveries (id, handle, payload, next_attempt_ms, created_at_ms)
+            SELECT $1, handle, $3, $4, $4 FROM webhooks WHERE handle = $2
+            ON CONFLICT (id) DO NOTHING
+            "#,
+            id,
+            handle,
+            payload,
+            now_ms
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Transfers per UTC day and coin since `since_ms`, archived ones included
+    pub async fn daily_transfer_volumes(pool: &DbPool, since_ms: i64) -> Result<Vec<DailyTransferVolume>> {
+        let _timer = QueryTimer::start("daily_transfer_volumes")?;
+        let volumes = sqlx::query_as!(
+            DailyTransferVolume,
+            r#"
+            SELECT (timestamp_ms / 86400000) * 86400000 as "day_ms!", coin_type as "coin_type!",
+                   COUNT(*) as "transfers!", COALESCE(SUM(amount), 0)::TEXT as "volume!",
+                   COUNT(DISTINCT from_handle_norm) as "senders!", COUNT(DISTINCT to_handle_norm) as "recipients!"
+            FROM ram_events_history
+            WHERE event_type = 'Transferred' AND timestamp_ms >= $1 AND coin_type IS NOT NULL
+            GROUP BY 1, 2
+            ORDER BY 1, 2
+            "#,
+            since_ms
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(volumes)
+    }
+
+    /// Wallets created per UTC day since `since_ms`, days without any left out
+    pub async fn daily_wallet_creations(pool: &DbPool, since_ms: i64) -> Result<Vec<DailyWalletCreations>> {
+        let _timer = QueryTimer::start("daily_wallet_creations")?;
+        let days = sqlx::query_as!(
+            DailyWalletCreations,
+            r#"
+            SELECT (timestamp_ms / 86400000) * 86400000 as "day_ms!", COUNT(*) as "created!"
+            FROM ram_events_history
+            WHERE event_type = 'WalletCreated' AND timestamp_ms >= $1
+            GROUP BY 1
+            ORDER BY 1
+            "#,
+            since_ms
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(days)
+    }
+
+    /// Wallets ever created
+    pub async fn count_wallets(pool: &DbPool) -> Result<i64> {
+        let _timer = QueryTimer::start("count_wallets")?;
+        let count = sqlx::query_scalar!(
+            r#"SELECT COUNT(*) as "count!" FROM ram_events_history WHERE event_type = 'WalletCreated'"#
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    /// Columns the analytics store keeps of the events after `after_id`,
+    /// archived ones included, oldest first
+    pub async fn get_analytics_rows_after(pool: &DbPool, after_id: i64, limit: i64) -> Result<Vec<AnalyticsRow>> {
+        let _timer = QueryTimer::start("get_analytics_rows_after")?;
+        let rows = sqlx::query_as!(
+            AnalyticsRow,
+            r#"
+            SELECT id as "id!", event_type as "event_type!", timestamp_ms as "timestamp_ms!", coin_type, amount,
+                   from_handle_norm, to_handle_norm
+            FROM ram_events_history
+            WHERE id > $1
+            ORDER BY id
+            LIMIT $2
+            "#,
+            after_id,
+            limit
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(rows)
+    }
+
+    /// Events stored per UTC day and type since `since_ms`, up to `up_to_id`
+    pub async fn daily_event_counts(pool: &DbPool, since_ms: i64, up_to_id: i64) -> Result<Vec<DailyEventCount>> {
+        let _timer = QueryTimer::start("daily_event_counts")?;
+        let counts = sqlx::query_as!(
+            DailyEventCount,
+            r#"
+            SELECT (timestamp_ms / 86400000) * 86400000 as "day_ms!", event_type as "event_type!",
+                   COUNT(*) as "events!"
+            FROM ram_events_history
+            WHERE timestamp_ms >= $1 AND id <= $2
+            GROUP BY 1, 2
+            ORDER BY 1, 2
+            "#,
+            since_ms,
+            up_to_id
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(counts)
+    }
+
+    /// Stored events after `after_id`, archived ones included, oldest first
+    pub async fn get_events_after(pool: &DbPool, after_id: i64, limit: i64) -> Result<Vec<ExportedEvent>> {
+        let _timer = QueryTimer::start("get_events_after")?;
+        let events = sqlx::query_as!(
+            ExportedEvent,
+            r#"
+            SELECT id as "id!", event_type as "event_type!", transaction_digest as "tx_digest!",
+                   timestamp_ms as "timestamp_ms!", handle, from_handle, to_handle, amount, coin_type, memo,
+                   payload_version, wallet_id, result_code, sender, gas_used, raw_json
+            FROM ram_events_history
+            WHERE id > $1
+            ORDER BY id
+            LIMIT $2
+            "#,
+            after_id,
+            limit
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(events)
+    }
+
+    /// Highest event id the sink has acknowledged (0 before its first batch)
+    pub async fn get_export_cursor(pool: &DbPool, sink: &str) -> Result<i64> {
+        let _timer = QueryTimer::start("get_export_cursor")?;
+        let last_event_id =
+            sqlx::query_scalar!("SELECT last_event_id FROM event_export_cursors WHERE sink = $1", sink)
+                .fetch_optional(pool)
+                .await?;
+
+        Ok(last_event_id.unwrap_or(0))
+    }
+
+    /// Record that the sink has acknowledged every event up to `last_event_id`
+    pub async fn set_export_cursor(pool: &DbPool, sink: &str, last_event_id: i64, now_ms: i64) -> Result<()> {
+        let _timer = QueryTimer::start("set_export_cursor")?;
+        sqlx::query!(
+            r#"
+            INSERT INTO event_export_cursors (sink, last_event_id, updated_at_ms)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (sink) DO UPDATE SET
+                last_event_id = GREATEST(event_export_cursors.last_event_id, EXCLUDED.last_event_id),
+                updated_at_ms = EXCLUDED.updated_at_ms
+            "#,
+            sink,
+            last_event_id,
+            now_ms
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// A wallet's latest webhook deliveries, newest first
+    pub async fn list_webhook_deliveries(pool: &DbPool, handle: &str, limit: i64) -> Result<Vec<WebhookDelivery>> {
+        let _timer = QueryTimer::start("list_webhook_deliveries")?;
+        let handle = normalize_handle(handle);
+        let deliveries = sqlx::query_as!(
+            WebhookDelivery,
+            r#"
+            SELECT id, payload, status, attempts, next_attempt_ms, last_error, created_at_ms, delivered_at_ms
+            FROM webhook_deliveries
+            WHERE handle = $1
+            ORDER BY created_at_ms DESC, id
+            LIMIT $2
+            "#,
+            handle,
+            limit
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(deliveries)
+    }
+
+    /// Pending deliveries whose next attempt is due, oldest first
+    pub async fn get_due_webhook_deliveries(pool: &DbPool, now_ms: i64, limit: i64) -> Result<Vec<DueWebhookDelivery>> {
+        let _timer = QueryTimer::start("get_due_webhook_deliveries")?;
+        let deliveries = sqlx::query_as!(
+            DueWebhookDelivery,
+            r#"
+            SELECT d.id, d.handle, w.url, w.secret, d.payload, d.attempts
+            FROM webhook_deliveries d
+            JOIN webhooks w ON w.handle = d.handle
+            WHERE d.status = 'pending' AND d.next_attempt_ms <= $1
+            ORDER BY d.next_attempt_ms ASC
+            LIMIT $2
+            "#,
+            now_ms,
+            limit
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(deliveries)
+    }
+
+    pub async fn mark_webhook_delivered(pool: &DbPool, id: &str, now_ms: i64) -> Result<()> {
+        let _timer = QueryTimer::start("mark_webhook_delivered")?;
+        sqlx::query!(
+            r#"
+            UPDATE webhook_deliveries
+            SET status = 'delivered', attempts = attempts + 1, last_error = NULL, delivered_at_ms = $2
+            WHERE id = $1
+            "#,
+            id,
+            now_ms
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Record a failed attempt; `next_attempt_ms` of None marks the delivery dead
+    pub async fn reschedule_webhook_delivery(
+        pool: &DbPool,
+        id: &str,
+        error: &str,
+        next_attempt_ms: Option<i64>,
+    ) -> Result<()> {
+        let _timer = QueryTimer::start("reschedule_webhook_delivery")?;
+        sqlx::query!(
+            r#"
+            UPDATE webhook_deliveries
+            SET attempts = attempts + 1,
+                last_error = $2,
+                next_attempt_ms = COALESCE($3, next_attempt_ms),
+                status = CASE WHEN $3::BIGINT IS NULL THEN 'dead' ELSE 'pending' END
+            WHERE id = $1
+            "#,
+            id,
+            error,
+            next_attempt_ms
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// A transfer to `handle` in transaction `tx_digest`, with how much of it
+    /// is already being or has been refunded
+    pub async fn get_refundable_payment(
+        pool: &DbPool,
+        handle: &str,
+        tx_digest: &str,
+    ) -> Result<Option<RefundablePayment>> {
+        let _timer = QueryTimer::start("get_refundable_payment")?;
+        let handle = normalize_handle(handle);
+        let payment = sqlx::query_as!(
+            RefundablePayment,
+            r#"
+            SELECT e.from_handle_norm as "payer_handle!", e.amount as "amount!", e.coin_type as "coin_type!",
+                   COALESCE((
+                       SELECT SUM(r.amount) FROM refunds r
+                       WHERE r.payment_tx_digest = e.transaction_digest AND r.status <> 'cancelled'
+                   ), 0)::BIGINT as "refunded!"
+            FROM ram_events_history e
+            WHERE e.event_type = 'Transferred' AND e.transaction_digest = $2 AND e.to_handle_norm = $1
+              AND e.from_handle IS NOT NULL AND e.amount IS NOT NULL AND e.coin_type IS NOT NULL
+            LIMIT 1
+            "#,
+            handle,
+            tx_digest
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(payment)
+    }
+
+    /// Store a pending refund of a payment of `payment_amount`; None if it
+    /// would take the payment's refunds past its amount
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create_refund(
+        pool: &DbPool,
+        id: &str,
+        handle: &str,
+        payer_handle: &str,
+        payment_tx_digest: &str,
+        payment_amount: i64,
+        amount: i64,
+        coin_type: &str,
+        reason: Option<&str>,
+        now_ms: i64,
+    ) -> Result<Option<Refund>> {
+        let _timer = QueryTimer::start("create_refund")?;
+        let handle = normalize_handle(handle);
+        let payer_handle = normalize_handle(payer_handle);
+        let mut tx = pool.begin().await?;
+        // Refunds of one payment are checked against its amount one at a time
+        sqlx::query!("SELECT pg_advisory_xact_lock(hashtext($1))", payment_tx_digest)
+            .execute(&mut *tx)
+            .await?;
+        let refund = sqlx::query_as!(
+            Refund,
+            r#"
+            INSERT INTO refunds (id, handle, payer_handle, payment_tx_digest, amount, coin_type, reason, created_at_ms)
+            SELECT $1, $2, $3, $4, $6::BIGINT, $7, $8, $9
+            WHERE $6::BIGINT <= $5::BIGINT - COALESCE((
+                SELECT SUM(amount) FROM refunds WHERE payment_tx_digest = $4 AND status <> 'cancelled'
+            ), 0)
+            RETURNING id, handle, payer_handle, payment_tx_digest, amount, coin_type, reason, status,
+                      refund_tx_digest, created_at_ms, completed_at_ms
+            "#,
+            id,
+            handle,
+            payer_handle,
+            payment_tx_digest,
+            payment_amount,
+            amount,
+            coin_type,
+            reason,
+            now_ms
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(refund)
+    }
+
+    pub async fn get_refund(pool: &DbPool, id: &str) -> Result<Option<Refund>> {
+        let _timer = QueryTimer::start("get_refund")?;
+        let refund = sqlx::query_as!(
+            Refund,
+            r#"
+            SELECT id, handle, payer_handle, payment_tx_digest, amount, coin_type, reason, status,
+                   refund_tx_digest, created_at_ms, completed_at_ms
+            FROM refunds WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(refund)
+    }
+
+    /// A merchant's refunds, newest first
+    pub async fn list_refunds(pool: &DbPool, handle: &str, limit: i64) -> Result<Vec<Refund>> {
+        let _timer = QueryTimer::start("list_refunds")?;
+        let handle = normalize_handle(handle);
+        let refunds = sqlx::query_as!(
+            Refund,
+            r#"
+            SELECT id, handle, payer_handle, payment_tx_digest, amount, coin_type, reason, status,
+                   refund_tx_digest, created_at_ms, completed_at_ms
+            FROM refunds
+            WHERE handle = $1
+            ORDER BY created_at_ms DESC, id
+            LIMIT $2
+            "#,
+            handle,
+            limit
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(refunds)
+    }
+
+    /// Cancel a pending refund; false if the merchant has no such pending refund
+    pub async fn cancel_refund(pool: &DbPool, id: &str, handle: &str) -> Result<bool> {
+        let _timer = QueryTimer::start("cancel_refund")?;
+        let handle = normalize_handle(handle);
+        let result = sqlx::query!(
+            "UPDATE refunds SET status = 'cancelled' WHERE id = $1 AND handle = $2 AND status = 'pending'",
+            id,
+            handle
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Link a pending refund to the transfer that paid it out; false unless
+    /// the transfer matches the refund
+    #[allow(clippy::too_many_arguments)]
+    pub async fn complete_refund(
+        pool: &DbPool,
+        id: &str,
+        from_handle: &str,
+        to_handle: &str,
+        amount: i64,
+        coin_type: &str,
+        refund_tx_digest: &str,
+        now_ms: i64,
+    ) -> Result<bool> {
+        let _timer = QueryTimer::start("complete_refund")?;
+        let from_handle = normalize_handle(from_handle);
+        let to_handle = normalize_handle(to_handle);
+        let result = sqlx::query!(
+            r#"
+            UPDATE refunds SET status = 'completed', refund_tx_digest = $6, completed_at_ms = $7
+            WHERE id = $1 AND handle = $2 AND payer_handle = $3 AND amount = $4 AND coin_type = $5
+              AND status = 'pending'
+            "#,
+            id,
+            from_handle,
+            to_handle,
+            amount,
+            coin_type,
+            refund_tx_digest,
+            now_ms
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Store a transfer's travel-rule envelope; None if the same ciphertext
+    /// is already stored for a different transfer (storing it again for the
+    /// same one, e.g. on a retried request, is fine)
+    #[allow(clippy::too_many_arguments)]
+    pub async fn store_travel_rule_envelope(
+        pool: &DbPool,
+        hash: &str,
+        from_handle: &str,
+        to_handle: &str,
+        amount: i64,
+        coin_type: &str,
+        scheme: &str,
+        ciphertext: &str,
+        now_ms: i64,
+    ) -> Result<Option<String>> {
+        let _timer = QueryTimer::start("store_travel_rule_envelope")?;
+        let from_handle = normalize_handle(from_handle);
+        let to_handle = normalize_handle(to_handle);
+        let stored = sqlx::query_scalar!(
+            r#"
+            INSERT INTO travel_rule_envelopes
+                (hash, from_handle, to_handle, amount, coin_type, scheme, ciphertext, created_at_ms)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            ON CONFLICT (hash) DO UPDATE SET hash = EXCLUDED.hash
+            WHERE travel_rule_envelopes.from_handle = EXCLUDED.from_handle
+              AND travel_rule_envelopes.to_handle = EXCLUDED.to_handle
+              AND travel_rule_envelopes.amount = EXCLUDED.amount
+              AND travel_rule_envelopes.coin_type = EXCLUDED.coin_type
+              AND travel_rule_envelopes.tx_digest IS NULL
+            RETURNING hash
+            "#,
+            hash,
+            from_handle,
+            to_handle,
+            amount,
+            coin_type,
+            scheme,
+            ciphertext,
+            now_ms
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(stored)
+    }
+
+    pub async fn get_travel_rule_envelope(pool: &DbPool, hash: &str) -> Result<Option<TravelRuleEnvelope>> {
+        let _timer = QueryTimer::start("get_travel_rule_envelope")?;
+        let envelope = sqlx::query_as!(
+            TravelRuleEnvelope,
+            r#"
+            SELECT hash, from_handle, to_handle, amount, coin_type, scheme, ciphertext, tx_digest, created_at_ms
+            FROM travel_rule_envelopes WHERE hash = $1
+            "#,
+            hash
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(envelope)
+    }
+
+    /// Record the transfer an envelope travelled with; false unless the
+    /// transfer matches the envelope
+    #[allow(clippy::too_many_arguments)]
+    pub async fn link_travel_rule_envelope(
+        pool: &DbPool,
+        hash: &str,
+        from_handle: &str,
+        to_handle: &str,
+        amount: i64,
+        coin_type: &str,
+        tx_digest: &str,
+    ) -> Result<bool> {
+        let _timer = QueryTimer::start("link_travel_rule_envelope")?;
+        let from_handle = normalize_handle(from_handle);
+        let to_handle = normalize_handle(to_handle);
+        let result = sqlx::query!(
+            r#"
+            UPDATE travel_rule_envelopes SET tx_digest = $6
+            WHERE hash = $1 AND from_handle = $2 AND to_handle = $3 AND amount = $4 AND coin_type = $5
+              AND tx_digest IS NULL
+            "#,
+            hash,
+            from_handle,
+            to_handle,
+            amount,
+            coin_type,
+            tx_digest
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Every proxy route alias
+    pub async fn list_route_aliases(pool: &DbPool) -> Result<Vec<RouteAlias>> {
+        let _timer = QueryTimer::start("list_route_aliases")?;
+        let aliases = sqlx::query_as!(
+            RouteAlias,
+            "SELECT path, upstream_path, route_class, updated_at_ms FROM proxy_routes ORDER BY path"
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(aliases)
+    }
+
+    /// The route alias at `path`, if any
+    pub async fn get_route_alias(pool: &DbPool, path: &str) -> Result<Option<RouteAlias>> {
+        let _timer = QueryTimer::start("get_route_alias")?;
+        let alias = sqlx::query_as!(
+            RouteAlias,
+            "SELECT path, upstream_path, route_class, updated_at_ms FROM proxy_routes WHERE path = $1",
+            path
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(alias)
+    }
+
+    /// Add or replace a route alias
+    pub async fn upsert_route_alias(pool: &DbPool, alias: &RouteAlias) -> Result<()> {
+        let _timer = QueryTimer::start("upsert_route_alias")?;
+        sqlx::query!(
+            r#"
+            INSERT INTO proxy_routes (path, upstream_path, route_class, updated_at_ms)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (path) DO UPDATE SET
+                upstream_path = $2, route_class = $3, updated_at_ms = $4
+            "#,
+            alias.path,
+            alias.upstream_path,
+            alias.route_class,
+            alias.updated_at_ms
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Remove a route alias, returning it if there was one
+    pub async fn delete_route_alias(pool: &DbPool, path: &str) -> Result<Option<RouteAlias>> {
+        let _timer = QueryTimer::start("delete_route_alias")?;
+        let removed = sqlx::query_as!(
+            RouteAlias,
+            "DELETE FROM proxy_routes WHERE path = $1 RETURNING path, upstream_path, route_class, updated_at_ms",
+            path
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(removed)
+    }
+
+    /// Every feature flag, by name
+    pub async fn list_feature_flags(pool: &DbPool) -> Result<Vec<FeatureFlag>> {
+        let _timer = QueryTimer::start("list_feature_flags")?;
+        let flags = sqlx::query_as!(
+            FeatureFlag,
+            r#"
+            SELECT name, description, enabled, rollout_percent, environments, updated_at_ms
+            FROM feature_flags
+            ORDER BY name
+            "#
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(flags)
+    }
+
+    /// The feature flag called `name`, if any
+    pub async fn get_feature_flag(pool: &DbPool, name: &str) -> Result<Option<FeatureFlag>> {
+        let _timer = QueryTimer::start("get_feature_flag")?;
+        let flag = sqlx::query_as!(
+            FeatureFlag,
+            r#"
+            SELECT name, description, enabled, rollout_percent, environments, updated_at_ms
+            FROM feature_flags
+            WHERE name = $1
+            "#,
+            name
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(flag)
+    }
+
+    /// Add or replace a feature flag
+    pub async fn upsert_feature_flag(pool: &DbPool, flag: &FeatureFlag) -> Result<()> {
+        let _timer = QueryTimer::start("upsert_feature_flag")?;
+        sqlx::query!(
+            r#"
+            INSERT INTO feature_flags (name, description, enabled, rollout_percent, environments, updated_at_ms)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (name) DO UPDATE SET
+                description = $2, enabled = $3, rollout_percent = $4, environments = $5, updated_at_ms = $6
+            "#,
+            flag.name,
+            flag.description,
+            flag.enabled,
+            flag.rollout_percent,
+            &flag.environments,
+            flag.updated_at_ms
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Remove a feature flag, returning it if it existed
+    pub async fn delete_feature_flag(pool: &DbPool, name: &str) -> Result<Option<FeatureFlag>> {
+        let _timer = QueryTimer::start("delete_feature_flag")?;
+        let removed = sqlx::query_as!(
+            FeatureFlag,
+            r#"
+            DELETE FROM feature_flags WHERE name = $1
+            RETURNING name, description, enabled, rollout_percent, environments, updated_at_ms
+            "#,
+            name
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(removed)
+    }
+
+    /// Record the receipt of a transfer; false if it already has one
+    pub async fn insert_transfer_receipt(pool: &DbPool, receipt: &TransferReceipt, now_ms: i64) -> Result<bool> {
+        let _timer = QueryTimer::start("insert_transfer_receipt")?;
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO transfer_receipts (
+                tx_digest, payer_handle, payee_handle, amount, coin_type, memo, timestamp_ms, created_at_ms
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            ON CONFLICT (tx_digest) DO NOTHING
+            "#,
+            receipt.tx_digest,
+            receipt.payer_handle,
+            receipt.payee_handle,
+            receipt.amount,
+            receipt.coin_type,
+            receipt.memo,
+            receipt.timestamp_ms,
+            now_ms
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// The receipt of the transfer in `tx_digest`, if any
+    pub async fn get_transfer_receipt(pool: &DbPool, tx_digest: &str) -> Result<Option<TransferReceipt>> {
+        let _timer = QueryTimer::start("get_transfer_receipt")?;
+        let receipt = sqlx::query_as!(
+            TransferReceipt,
+            r#"
+            SELECT tx_digest, payer_handle, payee_handle, amount, coin_type, memo, timestamp_ms
+            FROM transfer_receipts
+            WHERE tx_digest = $1
+            "#,
+            tx_digest
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(receipt)
+    }
+
+    /// A handle as the wallet was registered under it on-chain, the spelling
+    /// enclave payloads are verified against
+    pub async fn get_registered_handle(pool: &DbPool, handle: &str) -> Result<Option<String>> {
+        let _timer = QueryTimer::start("get_registered_handle")?;
+        let handle = normalize_handle(handle);
+        let registered = sqlx::query_scalar!(
+            r#"
+            SELECT handle
+            FROM ram_events
+            WHERE event_type = 'WalletCreated' AND handle_norm = $1 AND wallet_id IS NOT NULL
+            ORDER BY timestamp_ms DESC, id DESC
+            LIMIT 1
+            "#,
+            handle
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(registered.flatten())
+    }
+
+    /// Object ID of the wallet registered under a handle
+    pub async fn get_wallet_id(pool: &DbPool, handle: &str) -> Result<Option<String>> {
+        let _timer = QueryTimer::start("get_wallet_id")?;
+        let handle = normalize_handle(handle);
+        let wallet_id = sqlx::query_scalar!(
+            r#"
+            SELECT wallet_id
+            FROM ram_events
+            WHERE event_type = 'WalletCreated' AND handle_norm = $1 AND wallet_id IS NOT NULL
+            ORDER BY timestamp_ms DESC, id DESC
+            LIMIT 1
+            "#,
+            handle
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(wallet_id.flatten())
+    }
+
+    /// Sum a wallet's indexed deposits, withdrawals and transfers per coin type
+    pub async fn get_coin_flows(pool: &DbPool, handle: &str) -> Result<Vec<CoinFlows>> {
+        let _timer = QueryTimer::start("get_coin_flows")?;
+        let handle = normalize_handle(handle);
+        let flows = sqlx::query_as!(
+            CoinFlows,
+            r#"
+            SELECT
+                coin_type as "coin_type!",
+                COALESCE(SUM(amount) FILTER (WHERE event_type = 'Deposited'), 0)::BIGINT
+                    as "deposited!",
+                COALESCE(SUM(amount) FILTER (WHERE event_type = 'Withdrawn'), 0)::BIGINT
+                    as "withdrawn!",
+                COALESCE(SUM(amount) FILTER (
+                    WHERE event_type IN ('Transferred', 'InheritanceClaimed') AND to_handle_norm = $1
+                ), 0)::BIGINT as "transferred_in!",
+                COALESCE(SUM(amount) FILTER (
+                    WHERE event_type IN ('Transferred', 'InheritanceClaimed') AND from_handle_norm = $1
+                ), 0)::BIGINT as "transferred_out!"
+            FROM ram_events_history
+            WHERE coin_type IS NOT NULL
+              AND event_type IN ('Deposited', 'Withdrawn', 'Transferred', 'InheritanceClaimed')
+              AND (handle_norm = $1 OR to_handle_norm = $1)
+            GROUP BY coin_type
+            ORDER BY coin_type
+            "#,
+            handle
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(flows)
+    }
+
+    /// A wallet's locks, freezes, unfreezes and daily limits, oldest first
+    pub async fn get_policy_events(pool: &DbPool, handle: &str) -> Result<Vec<PolicyEvent>> {
+        let _timer = QueryTimer::start("get_policy_events")?;
+        let handle = normalize_handle(handle);
+        let events = sqlx::query_as!(
+            PolicyEvent,
+            r#"
+            SELECT event_type as "event_type!", coin_type, amount, raw_json, timestamp_ms as "timestamp_ms!"
+            FROM ram_events_history
+            WHERE handle_norm = $1
+              AND event_type IN ('WalletLocked', 'WalletFrozen', 'WalletUnfrozen', 'DailyLimitSet')
+            ORDER BY timestamp_ms, id
+            "#,
+            handle
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(events)
+    }
+
+    /// What a wallet transferred out or withdrew since `since_ms`, per coin type
+    pub async fn get_recent_spending(pool: &DbPool, handle: &str, since_ms: i64) -> Result<Vec<(String, i64)>> {
+        let _timer = QueryTimer::start("get_recent_spending")?;
+        let handle = normalize_handle(handle);
+        let rows = sqlx::query!(
+            r#"
+            SELECT coin_type as "coin_type!", COALESCE(SUM(amount), 0)::BIGINT as "spent!"
+            FROM ram_events_history
+            WHERE coin_type IS NOT NULL AND timestamp_ms >= $2
+              AND ((event_type = 'Transferred' AND from_handle_norm = $1)
+                OR (event_type = 'Withdrawn' AND handle_norm = $1))
+            GROUP BY coin_type
+            "#,
+            handle,
+            since_ms
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| (row.coin_type, row.spent)).collect())
+    }
+
+    /// A wallet's indexed bio-auths, newest first
+    pub async fn get_bioauth_history(
+        pool: &DbPool,
+        handle: &str,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<BioAuthAttempt>> {
+        let _timer = QueryTimer::start("get_bioauth_history")?;
+        let handle = normalize_handle(handle);
+        let rows = sqlx::query!(
+            r#"
+            SELECT transaction_digest as "tx_digest!", timestamp_ms as "timestamp_ms!", amount, result_code, raw_json, sender
+            FROM ram_events_history
+            WHERE handle_norm = $1 AND event_type IN ('BioAuthSuccess', 'BioAuthFailed')
+            ORDER BY timestamp_ms DESC, id DESC
+            LIMIT $2 OFFSET $3
+            "#,
+            handle,
+            limit,
+            offset
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let result_code = row.result_code.unwrap_or(-1);
+                let raw = row.raw_json.unwrap_or(Value::Null);
+                BioAuthAttempt {
+                    result_code,
+                    result: match result_code {
+                        0 => "ok",
+                        1 => "invalid_amount",
+                        2 => "duress",
+                        _ => "unknown",
+                    }
+                    .to_string(),
+                    amount: row.amount,
+                    // Events from before typed auth carry no method
+                    method: match json_u64(&raw["method"]) {
+                        Some(1) => "typed",
+                        _ => "voice",
+                    }
+                    .to_string(),
+                    destination_verified: raw["destination_verified"].as_bool(),
+                    tx_digest: row.tx_digest,
+                    timestamp_ms: row.timestamp_ms,
+                    sender: row.sender,
+                }
+            })
+            .collect())
+    }
+
+    /// Number of a wallet's indexed bio-auths that didn't return OK
+    pub async fn count_failed_bioauths(pool: &DbPool, handle: &str) -> Result<i64> {
+        let _timer = QueryTimer::start("count_failed_bioauths")?;
+        let handle = normalize_handle(handle);
+        let count = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) as "count!"
+            FROM ram_events_history
+            WHERE handle_norm = $1 AND event_type = 'BioAuthFailed'
+            "#,
+            handle
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    /// Move up to `limit` events older than `before_ms` to the archive, oldest
+    /// first; returns how many left ram_events
+    ///
+    /// WalletCreated events stay: handle lookups and search need them, and
+    /// there is only one per wallet.
+    pub async fn archive_events_before(pool: &DbPool, before_ms: i64, limit: i64) -> Result<i64> {
+        let _timer = QueryTimer::start("archive_events_before")?;
+        let moved = sqlx::query_scalar!(
+            r#"
+            WITH moved AS (
+                DELETE FROM ram_events
+                WHERE id IN (
+                    SELECT id FROM ram_events
+                    WHERE timestamp_ms < $1 AND event_type <> 'WalletCreated'
+                    ORDER BY timestamp_ms, id
+                    LIMIT $2
+                )
+                RETURNING *
+            ), archived AS (
+                INSERT INTO ram_events_archive SELECT * FROM moved ON CONFLICT DO NOTHING
+            )
+            SELECT COUNT(*) AS "count!" FROM moved
+            "#,
+            before_ms,
+            limit
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(moved)
+    }
+
+    /// Queue an event that failed processing for its first retry
+    pub async fn record_failed_event(
+        pool: &DbPool,
+        tx_digest: &str,
+        event_seq: &str,
+        event_type: &str,
+        event_json: &Value,
+        error: &str,
+        next_attempt_ms: i64,
+    ) -> Result<()> {
+        let _timer = QueryTimer::start("record_failed_event")?;
+        sqlx::query!(
+            r#"
+            INSERT INTO failed_events (
+                tx_digest, event_seq, event_type, event_json, last_error, next_attempt_ms
+            ) VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (tx_digest, event_seq) DO NOTHING
+            "#,
+            tx_digest,
+            event_seq,
+            event_type,
+            event_json,
+            error,
+            next_attempt_ms
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Pending failed events whose next attempt is due, oldest first
+    pub async fn get_due_failed_events(
+        pool: &DbPool,
+        now_ms: i64,
+        limit: i64,
+    ) -> Result<Vec<FailedEvent>> {
+        let _timer = QueryTimer::start("get_due_failed_events")?;
+        let events = sqlx::query_as!(
+            FailedEvent,
+            r#"
+            SELECT id, tx_digest, event_seq, event_type, event_json, last_error,
+                   attempts, next_attempt_ms, status
+            FROM failed_events
+            WHERE status = 'pending' AND next_attempt_ms <= $1
+            ORDER BY next_attempt_ms ASC
+            LIMIT $2
+            "#,
+            now_ms,
+            limit
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(events)
+    }
+
+    /// Record another failed attempt; `next_attempt_ms` of None marks the event dead
+    pub async fn reschedule_failed_event(
+        pool: &DbPool,
+        id: i64,
+        error: &str,
+        next_attempt_ms: Option<i64>,
+    ) -> Result<()> {
+        let _timer = QueryTimer::start("reschedule_failed_event")?;
+        sqlx::query!(
+            r#"
+            UPDATE failed_events
+            SET attempts = attempts + 1,
+                last_error = $2,
+                next_attempt_ms = COALESCE($3, next_attempt_ms),
+                status = CASE WHEN $3::BIGINT IS NULL THEN 'dead' ELSE 'pending' END,
+                updated_at = NOW()
+            WHERE id = $1
+            "#,
+            id,
+            error,
+            next_attempt_ms
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Drop a failed event once it has been processed
+    pub async fn delete_failed_event(pool: &DbPool, id: i64) -> Result<()> {
+        let _timer = QueryTimer::start("delete_failed_event")?;
+        sqlx::query!("DELETE FROM failed_events WHERE id = $1", id)
+            .execute(pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// List failed events, optionally filtered by status, most recently updated first
+    pub async fn list_failed_events(
+        pool: &DbPool,
+        status: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<FailedEvent>> {
+        let _timer = QueryTimer::start("list_failed_events")?;
+        let events = sqlx::query_as!(
+            FailedEvent,
+            r#"
+            SELECT id, tx_digest, event_seq, event_type, event_json, last_error,
+                   attempts, next_attempt_ms, status
+            FROM failed_events
+            WHERE $1::TEXT IS NULL OR status = $1
+            ORDER BY updated_at DESC
+            LIMIT $2
+            "#,
+            status,
+            limit
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(events)
+    }
+
+    /// Reset a failed event's attempts and make it due immediately
+    pub async fn requeue_failed_event(
+        pool: &DbPool,
+        id: i64,
+        now_ms: i64,
+    ) -> Result<Option<FailedEvent>> {
+        let _timer = QueryTimer::start("requeue_failed_event")?;
+        let event = sqlx::query_as!(
+            FailedEvent,
+            r#"
+            UPDATE failed_events
+            SET attempts = 0, status = 'pending', next_attempt_ms = $2, updated_at = NOW()
+            WHERE id = $1
+            RETURNING id, tx_digest, event_seq, event_type, event_json, last_error,
+                      attempts, next_attempt_ms, status
+            "#,
+            id,
+            now_ms
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(event)
+    }
+    /// Wallet handles containing `query`, closest matches first
+    pub async fn search_handles(pool: &DbPool, query: &str, limit: i64) -> Result<Vec<String>> {
+        let _timer = QueryTimer::start("search_handles")?;
+        let handles = sqlx::query_scalar!(
+            r#"
+            SELECT handle as "handle!"
+            FROM ram_events
+            WHERE event_type = 'WalletCreated' AND handle ILIKE '%' || $1 || '%'
+            GROUP BY handle
+            ORDER BY MAX(similarity(handle, $2)) DESC, handle
+            LIMIT $3
+            "#,
+            like_escape(query),
+            query,
+            limit
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(handles)
+    }
+
+    /// Events whose handles, digest or sender contain `query`, or whose payload matches it as text
+    pub async fn search_events(pool: &DbPool, query: &str, limit: i64) -> Result<Vec<RamEvent>> {
+        let _timer = QueryTimer::start("search_events")?;
+        let rows = sqlx::query!(
+            r#"
+            SELECT
+                e.event_type, e.transaction_digest as tx_digest,
+                to_timestamp(e.timestamp_ms / 1000.0) as "timestamp!",
+                e.handle, e.from_handle, e.to_handle, e.memo, e.payload_version, e.amount, e.coin_type, e.wallet_id,
+                e.result_code, e.raw_json, e.gas_used, e.sender,
+                la.label as "sender_label?",
+                fp.display_name as "from_display_name?", tp.display_name as "to_display_name?"
+            FROM ram_events e
+            LEFT JOIN linked_addresses la ON la.handle_norm = e.handle_norm AND la.address = e.sender
+            LEFT JOIN profiles fp ON fp.handle_norm = e.from_handle_norm
+            LEFT JOIN profiles tp ON tp.handle_norm = e.to_handle_norm
+            WHERE e.handle ILIKE '%' || $1 || '%'
+               OR e.from_handle ILIKE '%' || $1 || '%'
+               OR e.to_handle ILIKE '%' || $1 || '%'
+               OR e.transaction_digest ILIKE '%' || $1 || '%'
+               OR e.sender ILIKE '%' || $1 || '%'
+               OR to_tsvector('simple', COALESCE(e.raw_json::TEXT, '')) @@ plainto_tsquery('simple', $2)
+            ORDER BY
+                GREATEST(
+                    similarity(e.handle, $2),
+                    similarity(e.from_handle, $2),
+                    similarity(e.to_handle, $2),
+                    similarity(e.transaction_digest, $2),
+                    similarity(e.sender, $2)
+                ) DESC NULLS LAST,
+                e.timestamp_ms DESC
+            LIMIT $3
+            "#,
+            like_escape(query),
+            query,
+            limit
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| RamEvent {
+                event_type: row.event_type,
+                tx_digest: row.tx_digest,
+                timestamp: row.timestamp,
+                handle: row.handle,
+                from_handle: row.from_handle,
+                to_handle: row.to_handle,
+                memo: row.memo,
+                payload_version: row.payload_version,
+                amount: row.amount,
+                coin_type: row.coin_type,
+                result_code: row.result_code,
+                owner: None,
+                wallet_id: row.wallet_id,
+                raw_json: row.raw_json,
+                gas_used: row.gas_used,
+                sender: row.sender,
+                sender_label: row.sender_label,
+                from_display_name: row.from_display_name,
+                to_display_name: row.to_display_name,
+                refund_of: None,
+                refunded_in: Vec::new(),
+            })
+            .collect())
+    }
+
+    /// Record a range of history lost to a pruned cursor
+    pub async fn record_indexer_gap(
+        pool: &DbPool,
+        stale_cursor: &str,
+        resumed_from: Option<&str>,
+        error: &str,
+        detected_at_ms: i64,
+    ) -> Result<()> {
+        let _timer = QueryTimer::start("record_indexer_gap")?;
+        sqlx::query!(
+            r#"
+            INSERT INTO indexer_gaps (stale_cursor, resumed_from, error, detected_at_ms)
+            VALUES ($1, $2, $3, $4)
+            "#,
+            stale_cursor,
+            resumed_from,
+            error,
+            detected_at_ms
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Open an audit entry for a change about to be made; returns its ID
+    pub async fn insert_admin_audit(
+        pool: &DbPool,
+        actor: &str,
+        credential: &str,
+        action: &str,
+        target: &str,
+        details: &Value,
+        created_at_ms: i64,
+    ) -> Result<i64> {
+        let _timer = QueryTimer::start("insert_admin_audit")?;
+        let id = sqlx::query_scalar!(
+            r#"
+            INSERT INTO admin_audit (actor, credential, action, target, details, created_at_ms)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING id
+            "#,
+            actor,
+            credential,
+            action,
+            target,
+            details,
+            created_at_ms
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(id)
+    }
+
+    /// Record the HTTP status an audited change ended with
+    pub async fn complete_admin_audit(pool: &DbPool, id: i64, status: i32) -> Result<()> {
+        let _timer = QueryTimer::start("complete_admin_audit")?;
+        sqlx::query!("UPDATE admin_audit SET status = $2 WHERE id = $1", id, status)
+            .execute(pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Audited changes matching the optional filters, most recent first
+    pub async fn list_admin_audit(
+        pool: &DbPool,
+        action: Option<&str>,
+        actor: Option<&str>,
+        target: Option<&str>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<AdminAuditEntry>> {
+        let _timer = QueryTimer::start("list_admin_audit")?;
+        let entries = sqlx::query_as!(
+            AdminAuditEntry,
+            r#"
+            SELECT id, actor, credential, action, target, details, status, created_at_ms
+            FROM admin_audit
+            WHERE ($1::TEXT IS NULL OR action = $1)
+              AND ($2::TEXT IS NULL OR actor = $2)
+              AND ($3::TEXT IS NULL OR target = $3)
+            ORDER BY created_at_ms DESC, id DESC
+            LIMIT $4 OFFSET $5
+            "#,
+            action,
+            actor,
+            target,
+            limit,
+            offset
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(entries)
+    }
+
+    /// Store a prepared onboarding transaction; returns its ID
+    pub async fn insert_onboarding(
+        pool: &DbPool,
+        handle: &str,
+        sender: &str,
+        tx_bytes: &[u8],
+        now_ms: i64,
+    ) -> Result<i64> {
+        let _timer = QueryTimer::start("insert_onboarding")?;
+        let handle = normalize_handle(handle);
+        let id = sqlx::query_scalar!(
+            r#"
+            INSERT INTO onboardings (handle, sender, tx_bytes, created_at_ms, updated_at_ms)
+            VALUES ($1, $2, $3, $4, $4)
+            RETURNING id
+            "#,
+            handle,
+            sender,
+            tx_bytes,
+            now_ms
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(id)
+    }
+
+    pub async fn get_onboarding(pool: &DbPool, id: i64) -> Result<Option<Onboarding>> {
+        let _timer = QueryTimer::start("get_onboarding")?;
+        let onboarding = sqlx::query_as!(
+            Onboarding,
+            r#"
+            SELECT id, handle, sender, tx_bytes, status, tx_digest, wallet_id, error,
+                   created_at_ms, updated_at_ms
+            FROM onboardings
+            WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(onboarding)
+    }
+
+    /// Whether a handle or sender already went through a sponsored creation
+    pub async fn onboarding_executed(pool: &DbPool, handle: &str, sender: &str) -> Result<bool> {
+        let _timer = QueryTimer::start("onboarding_executed")?;
+        let handle = normalize_handle(handle);
+        let executed = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS (
+                SELECT 1 FROM onboardings
+                WHERE status = 'executed' AND (handle = $1 OR sender = $2)
+            ) AS "executed!"
+            "#,
+            handle,
+            sender
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(executed)
+    }
+
+    /// Sponsored creations executed since a time, for the daily limit
+    pub async fn count_onboardings_executed_since(pool: &DbPool, since_ms: i64) -> Result<i64> {
+        let _timer = QueryTimer::start("count_onboardings_executed_since")?;
+        let count = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) AS "count!"
+            FROM onboardings
+            WHERE status = 'executed' AND updated_at_ms >= $1
+            "#,
+            since_ms
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    /// Take a pending onboarding for submission; false if it was already taken
+    pub async fn claim_onboarding(pool: &DbPool, id: i64, now_ms: i64) -> Result<bool> {
+        let _timer = QueryTimer::start("claim_onboarding")?;
+        let result = sqlx::query!(
+            r#"
+            UPDATE onboardings
+            SET status = 'submitting', updated_at_ms = $2
+            WHERE id = $1 AND status = 'pending'
+            "#,
+            id,
+            now_ms
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Record the outcome of a claimed onboarding (executed or failed)
+    pub async fn finish_onboarding(
+        pool: &DbPool,
+        id: i64,
+        status: &str,
+        tx_digest: Option<&str>,
+        error: Option<&str>,
+        now_ms: i64,
+    ) -> Result<()> {
+        let _timer = QueryTimer::start("finish_onboarding")?;
+        sqlx::query!(
+            r#"
+            UPDATE onboardings
+            SET status = $2, tx_digest = $3, error = $4, updated_at_ms = $5
+            WHERE id = $1
+            "#,
+            id,
+            status,
+            tx_digest,
+            error,
+            now_ms
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Record the wallet an executed onboarding created
+    pub async fn set_onboarding_wallet(pool: &DbPool, id: i64, wallet_id: &str, now_ms: i64) -> Result<()> {
+        let _timer = QueryTimer::start("set_onboarding_wallet")?;
+        sqlx::query!(
+            "UPDATE onboardings SET wallet_id = $2, updated_at_ms = $3 WHERE id = $1",
+            id,
+            wallet_id,
+            now_ms
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Hold a handle under `token_hash` until `expires_at_ms`; false if
+    /// someone else's reservation of it is still running. Renewing with the
+    /// same token extends it.
+    pub async fn reserve_handle(
+        pool: &DbPool,
+        handle: &str,
+        token_hash: &str,
+        expires_at_ms: i64,
+        now_ms: i64,
+    ) -> Result<bool> {
+        let _timer = QueryTimer::start("reserve_handle")?;
+        let handle = normalize_handle(handle);
+        let reserved = sqlx::query_scalar!(
+            r#"
+            INSERT INTO handle_reservations (handle, token_hash, created_at_ms, expires_at_ms)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (handle) DO UPDATE SET
+                token_hash = EXCLUDED.token_hash,
+                created_at_ms = CASE
+                    WHEN handle_reservations.token_hash = EXCLUDED.token_hash THEN handle_reservations.created_at_ms
+                    ELSE EXCLUDED.created_at_ms
+                END,
+                expires_at_ms = EXCLUDED.expires_at_ms
+            WHERE handle_reservations.expires_at_ms <= EXCLUDED.created_at_ms
+               OR handle_reservations.token_hash = EXCLUDED.token_hash
+            RETURNING handle
+            "#,
+            handle,
+            token_hash,
+            now_ms,
+            expires_at_ms
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(reserved.is_some())
+    }
+
+    /// Token hash of the handle's running reservation, if any
+    pub async fn active_handle_reservation(pool: &DbPool, handle: &str, now_ms: i64) -> Result<Option<String>> {
+        let _timer = QueryTimer::start("active_handle_reservation")?;
+        let handle = normalize_handle(handle);
+        let token_hash = sqlx::query_scalar!(
+            "SELECT token_hash FROM handle_reservations WHERE handle = $1 AND expires_at_ms > $2",
+            handle,
+            now_ms
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(token_hash)
+    }
+
+    /// Recorded indexer gaps, most recent first
+    pub async fn list_indexer_gaps(pool: &DbPool, limit: i64) -> Result<Vec<IndexerGap>> {
+        let _timer = QueryTimer::start("list_indexer_gaps")?;
+        let gaps = sqlx::query_as!(
+            IndexerGap,
+            r#"
+            SELECT id, stale_cursor, resumed_from, error, detected_at_ms
+            FROM indexer_gaps
+            ORDER BY detected_at_ms DESC, id DESC
+            LIMIT $1
+            "#,
+            limit
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(gaps)
+    }
+}
+
+/// Move integers arrive as JSON numbers or, for u64 and wider, strings
+pub(crate) fn json_u64(value: &Value) -> Option<u64> {
+    value.as_u64().or_else(|| value.as_str().and_then(|s| s.parse().ok()))
+}
+
+/// Escape LIKE wildcards so user input matches literally
+fn like_escape(query: &str) -> String {
+    query
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+fn pool_options(config: &DbConfig) -> PgPoolOptions {
+    PgPoolOptions::new()
+        .max_connections(config.max_connections)
+        .min_connections(config.min_connections)
+        .acquire_timeout(config.acquire_timeout)
+}
@@ -0,0 +1,163 @@
+// Error envelope
+// Every error response, the backend's own or an enclave's relayed by the
+// proxy, has the shape clients parse as `ram_sdk::ApiErrorBody`:
+// `{code, message, details, request_id}`. Handlers keep returning bare
+// StatusCodes; `envelope_errors` gives those a body, and `translate` brings
+// anything not yet in the envelope (an older enclave's `{"error": ...}`, a
+// plain-text rejection) into it. Each request gets an `x-request-id`, which is
+// forwarded to the enclave and quoted in the envelope, so one ID finds a
+// failure in both services' logs.
+
+use axum::{
+    body::{to_bytes, Body},
+    extract::Request,
+    http::{header, HeaderMap, HeaderName, HeaderValue, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use ram_sdk::{ApiErrorBody, ErrorCode};
+use serde_json::{Map, Value};
+
+/// Request ID header, taken from the client or assigned here
+pub const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Longest client-supplied request ID kept; longer ones are replaced
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// Largest error body read for translation (errors are small JSON documents)
+const MAX_ERROR_BODY_BYTES: usize = 1024 * 1024;
+
+/// Bring an error body of any shape into the envelope
+///
+/// Envelopes pass through. JSON with an `error` message is the enclave's
+/// older shape: its `code` (if a known one) is kept and the remaining keys
+/// become `details`. Anything else keeps its text, or the status reason.
+pub fn translate(status: StatusCode, body: &[u8]) -> ApiErrorBody {
+    if let Ok(error) = serde_json::from_slice::<ApiErrorBody>(body) {
+        return error;
+    }
+    let fallback = ErrorCode::from_status(status.as_u16());
+    let reason = status.canonical_reason().unwrap_or("Error");
+
+    match serde_json::from_slice::<Value>(body) {
+        Ok(Value::Object(mut fields)) => {
+            let message = fields
+                .remove("error")
+                .or_else(|| fields.remove("message"))
+                .and_then(|v| v.as_str().map(str::to_string))
+                .unwrap_or_else(|| reason.to_string());
+            let code = fields
+                .remove("code")
+                .and_then(|v| serde_json::from_value::<ErrorCode>(v).ok())
+                .filter(|code| *code != ErrorCode::Unknown)
+                .unwrap_or(fallback);
+            let error = ApiErrorBody::new(code, message);
+            if fields.is_empty() {
+                error
+            } else {
+                error.with_details(Value::Object(Map::from_iter(fields)))
+            }
+        }
+        _ => {
+            let text = String::from_utf8_lossy(body);
+            let message = if text.trim().is_empty() { reason } else { text.trim() };
+            ApiErrorBody::new(fallback, message)
+        }
+    }
+}
+
+/// An error response with `headers` and the envelope as its body
+pub fn error_response(status: StatusCode, mut headers: HeaderMap, error: &ApiErrorBody) -> Response {
+    headers.remove(header::CONTENT_LENGTH);
+    headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    let mut response = Response::new(Body::from(serde_json::to_vec(error).unwrap_or_default()));
+    *response.status_mut() = status;
+    *response.headers_mut() = headers;
+    response
+}
+
+/// The client's request ID if it's usable, a fresh one otherwise
+fn request_id(headers: &HeaderMap) -> HeaderValue {
+    headers
+        .get(&REQUEST_ID_HEADER)
+        .filter(|v| !v.is_empty() && v.len() <= MAX_REQUEST_ID_LEN && v.to_str().is_ok())
+        .cloned()
+        .unwrap_or_else(|| HeaderValue::from_str(&uuid::Uuid::new_v4().to_string()).unwrap())
+}
+
+/// Middleware assigning request IDs and answering every error in the envelope
+pub async fn envelope_errors(mut req: Request, next: Next) -> Response {
+    let id = request_id(req.headers());
+    req.headers_mut().insert(REQUEST_ID_HEADER, id.clone());
+
+    let response = next.run(req).await;
+    let (mut parts, body) = response.into_parts();
+    parts.headers.insert(REQUEST_ID_HEADER, id.clone());
+    if !parts.status.is_client_error() && !parts.status.is_server_error() {
+        return Response::from_parts(parts, body);
+    }
+
+    let bytes = to_bytes(body, MAX_ERROR_BODY_BYTES).await.unwrap_or_default();
+    let mut error = translate(parts.status, &bytes);
+    error.request_id = id.to_str().ok().map(str::to_string);
+    error_response(parts.status, parts.headers, &error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn translated(status: StatusCode, body: Value) -> ApiErrorBody {
+        translate(status, &serde_json::to_vec(&body).unwrap())
+    }
+
+    #[test]
+    fn test_envelopes_pass_through() {
+        let body = json!({ "code": "replay_detected", "message": "clip reused", "details": null, "request_id": "r1" });
+        let error = translated(StatusCode::UNPROCESSABLE_ENTITY, body);
+        assert_eq!(error.code, ErrorCode::ReplayDetected);
+        assert_eq!(error.request_id.as_deref(), Some("r1"));
+    }
+
+    #[test]
+    fn test_legacy_enclave_errors_are_translated() {
+        let error = translated(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            json!({ "error": "Request validation failed", "fields": [{ "field": "payload.amount", "message": "must be greater than 0" }] }),
+        );
+        assert_eq!(error.code, ErrorCode::ValidationFailed);
+        assert_eq!(error.message, "Request validation failed");
+        assert_eq!(error.fields().unwrap()[0].field, "payload.amount");
+
+        let error = translated(
+            StatusCode::FORBIDDEN,
+            json!({ "error": "Peer not allowed", "code": "peer_not_allowed", "peer": "10.0.0.1" }),
+        );
+        assert_eq!(error.code, ErrorCode::PeerNotAllowed);
+        assert_eq!(error.details, Some(json!({ "peer": "10.0.0.1" })));
+
+        let error = translated(StatusCode::CONFLICT, json!({ "error": "busy", "code": "teapot" }));
+        assert_eq!(error.code, ErrorCode::Conflict);
+        assert_eq!(error.details, None);
+    }
+
+    #[test]
+    fn test_bare_statuses_get_a_message() {
+        let error = translate(StatusCode::NOT_FOUND, b"");
+        assert_eq!((error.code, error.message.as_str()), (ErrorCode::NotFound, "Not Found"));
+
+        let error = translate(StatusCode::BAD_GATEWAY, b"upstream connect error");
+        assert_eq!((error.code, error.message.as_str()), (ErrorCode::EnclaveUnavailable, "upstream connect error"));
+    }
+
+    #[test]
+    fn test_request_ids() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(request_id(&headers).len(), 36);
+        headers.insert(REQUEST_ID_HEADER, HeaderValue::from_static("req-1"));
+        assert_eq!(request_id(&headers), "req-1");
+        headers.insert(REQUEST_ID_HEADER, HeaderValue::from_str(&"x".repeat(200)).unwrap());
+        assert_eq!(request_id(&headers).len(), 36);
+    }
+}
@@ -0,0 +1,301 @@
+// Public explorer
+// Unauthenticated aggregates for a public stats page: daily transfer counts
+// and volumes per coin, and daily wallet creations. Nothing that names a
+// wallet is ever returned, and a figure is only published when at least
+// EXPLORER_K_ANONYMITY distinct wallets are behind it: a day's transfers in
+// a coin need that many senders and that many recipients, and a day's
+// creations that many wallets; anything smaller is withheld. Every client
+// (by address) gets EXPLORER_RATE_LIMIT_PER_MIN requests a minute. The
+// `public_explorer` feature flag can take the explorer down (see flags.rs).
+
+use axum::{
+    extract::{ConnectInfo, Query, Request, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{error, warn};
+
+use crate::config::ExplorerConfig;
+use crate::database::Database;
+use crate::flags;
+use crate::models::{
+    DailyTransferVolume, DailyWalletCreations, ExplorerQuery, ExplorerTransfers, ExplorerWallets, PublicTransferVolume,
+    PublicWalletCreations,
+};
+use crate::AppState;
+
+/// Days covered unless the query says otherwise
+pub const DEFAULT_EXPLORER_DAYS: i64 = 30;
+/// Longest window that can be asked for
+pub const MAX_EXPLORER_DAYS: i64 = 90;
+/// Length of a rate-limit window
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+/// Clients tracked before windows that have passed are dropped
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// Per-client request counts, in fixed one-minute windows
+pub struct RateLimiter {
+    per_minute: u32,
+    windows: Mutex<HashMap<Option<IpAddr>, (Instant, u32)>>,
+}
+
+impl RateLimiter {
+    pub fn new(per_minute: u32) -> Self {
+        Self { per_minute, windows: Mutex::default() }
+    }
+
+    /// Count a request from `client` at `now`; `Err` with how long until
+    /// its window resets once it's over the limit
+    pub fn admit(&self, client: Option<IpAddr>, now: Instant) -> Result<(), Duration> {
+        let mut windows = self.windows.lock().unwrap_or_else(|e| e.into_inner());
+        if windows.len() >= MAX_TRACKED_CLIENTS {
+            windows.retain(|_, (start, _)| now.duration_since(*start) < RATE_WINDOW);
+        }
+        let (start, count) = windows.entry(client).or_insert((now, 0));
+        if now.duration_since(*start) >= RATE_WINDOW {
+            *start = now;
+            *count = 0;
+        }
+        if *count >= self.per_minute {
+            return Err(RATE_WINDOW.saturating_sub(now.duration_since(*start)));
+        }
+        *count += 1;
+        Ok(())
+    }
+}
+
+/// Explorer settings and per-client request counts
+pub struct Explorer {
+    pub config: ExplorerConfig,
+    limiter: RateLimiter,
+}
+
+impl Default for Explorer {
+    fn default() -> Self {
+        Self::new(ExplorerConfig::default())
+    }
+}
+
+impl Explorer {
+    pub fn new(config: ExplorerConfig) -> Self {
+        Self { limiter: RateLimiter::new(config.rate_limit_per_minute), config }
+    }
+}
+
+/// Rate-limit explorer routes per client; 429 with `Retry-After` once over,
+/// 404 for clients the `public_explorer` flag is off for
+pub async fn rate_limit(State(state): State<Arc<AppState>>, request: Request, next: Next) -> Response {
+    let client = client_ip(&state, &request);
+    let subject = client.map(|ip| ip.to_string()).unwrap_or_default();
+    if !state.feature_flags.is_enabled(flags::PUBLIC_EXPLORER, &subject, true) {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+    throttle(&state.explorer.limiter, client, request, next).await
+}
+
+/// Run `request` if `limiter` admits `client`; 429 with `Retry-After` once
+/// it's over the limit
+pub(crate) async fn throttle(limiter: &RateLimiter, client: Option<IpAddr>, request: Request, next: Next) -> Response {
+    match limiter.admit(client, Instant::now()) {
+        Ok(()) => next.run(request).await,
+        Err(retry_after) => {
+            let seconds = retry_after.as_secs().max(1).to_string();
+            let mut response = StatusCode::TOO_MANY_REQUESTS.into_response();
+            if let Ok(value) = HeaderValue::from_str(&seconds) {
+                response.headers_mut().insert(header::RETRY_AFTER, value);
+            }
+            response
+        }
+    }
+}
+
+/// Address of the client making a request: the connection's, or behind
+/// trusted proxies the hop the outermost of them added
+pub(crate) fn client_ip(state: &AppState, request: &Request) -> Option<IpAddr> {
+    let peer = request.extensions().get::<ConnectInfo<SocketAddr>>().map(|ConnectInfo(addr)| addr.ip());
+    match state.explorer.config.trusted_proxies {
+        0 => peer,
+        proxies => forwarded_client(request.headers(), proxies).or(peer),
+    }
+}
+
+/// The `X-Forwarded-For` hop `proxies` from the right, the one the outermost
+/// trusted proxy appended; hops left of it are whatever the client sent
+fn forwarded_client(headers: &HeaderMap, proxies: usize) -> Option<IpAddr> {
+    let mut hops = Vec::new();
+    for value in headers.get_all("x-forwarded-for") {
+        hops.extend(value.to_str().ok()?.split(',').map(str::trim));
+    }
+    hops.len().checked_sub(proxies).and_then(|i| hops[i].parse().ok())
+}
+
+/// Start of the window `days` back from `now_ms`, on a UTC day boundary
+pub(crate) fn window_start_ms(now_ms: i64, days: i64) -> i64 {
+    (now_ms / DAY_MS - (days - 1)) * DAY_MS
+}
+
+fn days_of(query: &ExplorerQuery) -> Result<i64, StatusCode> {
+    let days = query.days.unwrap_or(DEFAULT_EXPLORER_DAYS);
+    if !(1..=MAX_EXPLORER_DAYS).contains(&days) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    Ok(days)
+}
+
+/// Buckets with at least `k` senders and `k` recipients, without either count
+pub fn publishable_volumes(volumes: Vec<DailyTransferVolume>, k: i64) -> Vec<PublicTransferVolume> {
+    volumes
+        .into_iter()
+        .filter(|bucket| bucket.senders >= k && bucket.recipients >= k)
+        .map(|bucket| PublicTransferVolume {
+            day_ms: bucket.day_ms,
+            coin_type: bucket.coin_type,
+            transfers: bucket.transfers,
+            volume: bucket.volume,
+        })
+        .collect()
+}
+
+/// Every day of the window, with creations withheld on days with fewer than `k`
+pub fn publishable_creations(
+    days: &[DailyWalletCreations],
+    since_ms: i64,
+    window_days: i64,
+    k: i64,
+) -> Vec<PublicWalletCreations> {
+    (0..window_days)
+        .map(|i| {
+            let day_ms = since_ms + i * DAY_MS;
+            let created = days.iter().find(|day| day.day_ms == day_ms).map_or(0, |day| day.created);
+            // Zero is withheld too, so a withheld day doesn't tell a quiet day from a small one
+            PublicWalletCreations { day_ms, created: (created >= k).then_some(created) }
+        })
+        .collect()
+}
+
+/// Transfer volumes from the analytics store while it's serving, from
+/// Postgres otherwise or when the store can't be read
+async fn transfer_volumes(state: &AppState, since_ms: i64) -> anyhow::Result<Vec<DailyTransferVolume>> {
+    if let Some(store) = state.analytics.as_ref().filter(|store| store.is_serving()) {
+        match store.daily_transfer_volumes(since_ms).await {
+            Ok(volumes) => return Ok(volumes),
+            Err(e) => warn!("Analytics store unreadable, aggregating transfers on Postgres: {}", e),
+        }
+    }
+    Database::daily_transfer_volumes(&state.read_db, since_ms).await
+}
+
+/// Daily wallet creations and the number of wallets, from the same source
+/// as `transfer_volumes`
+async fn wallet_creations(state: &AppState, since_ms: i64) -> anyhow::Result<(Vec<DailyWalletCreations>, i64)> {
+    if let Some(store) = state.analytics.as_ref().filter(|store| store.is_serving()) {
+        match store.daily_wallet_creations(since_ms).await {
+            Ok(created) => return Ok((created, store.count_wallets())),
+            Err(e) => warn!("Analytics store unreadable, aggregating wallets on Postgres: {}", e),
+        }
+    }
+    tokio::try_join!(
+        Database::daily_wallet_creations(&state.read_db, since_ms),
+        Database::count_wallets(&state.read_db),
+    )
+}
+
+/// Daily transfer counts and volumes per coin
+///
+/// GET /api/explorer/transfers?days= (1 to 90, default 30)
+pub async fn get_transfer_volumes(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ExplorerQuery>,
+) -> Result<Json<ExplorerTransfers>, StatusCode> {
+    let days = days_of(&query)?;
+    let since_ms = window_start_ms(chrono::Utc::now().timestamp_millis(), days);
+    let volumes = transfer_volumes(&state, since_ms).await.map_err(|e| {
+        error!("Failed to aggregate transfer volumes: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let k = state.explorer.config.k_anonymity;
+    Ok(Json(ExplorerTransfers { days, since_ms, k_anonymity: k, volumes: publishable_volumes(volumes, k) }))
+}
+
+/// Daily wallet creations and the number of wallets
+///
+/// GET /api/explorer/wallets?days= (1 to 90, default 30)
+pub async fn get_wallet_creations(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ExplorerQuery>,
+) -> Result<Json<ExplorerWallets>, StatusCode> {
+    let days = days_of(&query)?;
+    let since_ms = window_start_ms(chrono::Utc::now().timestamp_millis(), days);
+    let (created, total) = wallet_creations(&state, since_ms).await.map_err(|e| {
+        error!("Failed to aggregate wallet creations: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let k = state.explorer.config.k_anonymity;
+    Ok(Json(ExplorerWallets {
+        days,
+        since_ms,
+        k_anonymity: k,
+        total_wallets: (total >= k).then_some(total),
+        daily: publishable_creations(&created, since_ms, days, k),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bucket(senders: i64, recipients: i64) -> DailyTransferVolume {
+        DailyTransferVolume {
+            day_ms: 0,
+            coin_type: "0x2::sui::SUI".to_string(),
+            transfers: senders.max(recipients),
+            volume: "100".to_string(),
+            senders,
+            recipients,
+        }
+    }
+
+    #[test]
+    fn test_figures_below_k_are_withheld() {
+        assert_eq!(publishable_volumes(vec![bucket(5, 5), bucket(4, 9), bucket(9, 4)], 5).len(), 1);
+
+        let days = [DailyWalletCreations { day_ms: DAY_MS, created: 7 }, DailyWalletCreations { day_ms: 2 * DAY_MS, created: 2 }];
+        let daily = publishable_creations(&days, 0, 3, 5);
+        let created: Vec<Option<i64>> = daily.iter().map(|day| day.created).collect();
+        assert_eq!(created, [None, Some(7), None]);
+        assert_eq!(daily[2].day_ms, 2 * DAY_MS);
+        assert_eq!(window_start_ms(10 * DAY_MS + 5, 3), 8 * DAY_MS);
+    }
+
+    #[test]
+    fn test_clients_are_limited_per_window() {
+        let explorer = Explorer::new(ExplorerConfig { rate_limit_per_minute: 2, ..ExplorerConfig::default() });
+        let limiter = &explorer.limiter;
+        let (alice, bob) = (Some("10.0.0.1".parse().unwrap()), Some("10.0.0.2".parse().unwrap()));
+        let start = Instant::now();
+        assert!(limiter.admit(alice, start).is_ok());
+        assert!(limiter.admit(alice, start).is_ok());
+        assert_eq!(limiter.admit(alice, start + Duration::from_secs(20)), Err(Duration::from_secs(40)));
+        assert!(limiter.admit(bob, start).is_ok());
+        assert!(limiter.admit(alice, start + RATE_WINDOW).is_ok());
+
+        // The client prepends what it likes; only hops the proxies added count
+        let mut headers = HeaderMap::new();
+        headers.insert("x-forwarded-for", HeaderValue::from_static("6.6.6.6, 203.0.113.7"));
+        headers.append("x-forwarded-for", HeaderValue::from_static("10.0.0.1"));
+        assert_eq!(forwarded_client(&headers, 1), Some("10.0.0.1".parse().unwrap()));
+        assert_eq!(forwarded_client(&headers, 2), Some("203.0.113.7".parse().unwrap()));
+        assert_eq!(forwarded_client(&headers, 4), None);
+        headers.insert("x-forwarded-for", HeaderValue::from_static("203.0.113.7, garbage"));
+        assert_eq!(forwarded_client(&headers, 1), None);
+    }
+}
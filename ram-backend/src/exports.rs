@@ -0,0 +1,210 @@
+// Event export for analytics
+// Every indexed event is sent, in id order, to the sink EXPORT_SINK names,
+// so data teams can build dashboards off their own copy instead of querying
+// the production database:
+// - `http`: batches POSTed to EXPORT_HTTP_URL as `{"events": [...]}`
+// - `kafka`: one record per event, keyed by handle, produced to
+//   EXPORT_KAFKA_TOPIC through the Kafka REST Proxy at EXPORT_KAFKA_REST_URL
+// - `s3`: one newline-delimited JSON object per batch, named after the id
+//   range it holds (`<prefix><first id>-<last id>.ndjson`, zero-padded)
+// Delivery is at least once: the sink's cursor only moves past a batch once
+// the sink has accepted it, so a failed send or a restart repeats events and
+// never skips them. Events carry their `id` for consumers to drop repeats.
+
+use anyhow::{anyhow, Result};
+use hmac::{Hmac, Mac};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use tracing::{debug, error, info};
+
+use crate::config::{ExportConfig, ExportSink};
+use crate::database::{Database, DbPool};
+use crate::models::ExportedEvent;
+
+/// Content type of the Kafka REST Proxy's JSON embedded format
+const KAFKA_JSON_CONTENT_TYPE: &str = "application/vnd.kafka.json.v2+json";
+
+/// Send the events after the sink's cursor, up to a batch, and move the
+/// cursor past them; returns how many were exported
+pub async fn export_batch(
+    pool: &DbPool,
+    client: &reqwest::Client,
+    sink: &ExportSink,
+    batch_size: i64,
+) -> Result<usize> {
+    let cursor = Database::get_export_cursor(pool, sink.name()).await?;
+    let events = Database::get_events_after(pool, cursor, batch_size).await?;
+    let Some(last) = events.last() else {
+        return Ok(0);
+    };
+
+    send(client, sink, &events).await?;
+    Database::set_export_cursor(pool, sink.name(), last.id, chrono::Utc::now().timestamp_millis()).await?;
+    debug!("Exported events {}..={} to {}", events[0].id, last.id, sink.name());
+    Ok(events.len())
+}
+
+/// Export new events for as long as it runs (supervised, see supervisor.rs);
+/// full batches are followed right away until the sink has caught up
+pub async fn run_event_exports(pool: DbPool, config: ExportConfig) -> Result<()> {
+    let Some(sink) = config.sink else {
+        return Ok(());
+    };
+    let client = reqwest::Client::builder()
+        .timeout(config.timeout)
+        .redirect(reqwest::redirect::Policy::none())
+        .build()?;
+    info!("Exporting indexed events to {}", sink.name());
+    loop {
+        match export_batch(&pool, &client, &sink, config.batch_size).await {
+            Ok(exported) if exported as i64 == config.batch_size => continue,
+            Ok(_) => {}
+            Err(e) => error!("Event export to {} failed: {}", sink.name(), e),
+        }
+        tokio::time::sleep(config.poll_interval).await;
+    }
+}
+
+async fn send(client: &reqwest::Client, sink: &ExportSink, events: &[ExportedEvent]) -> Result<()> {
+    let request = match sink {
+        ExportSink::Http { url } => client.post(url).json(&json!({ "events": events })),
+        ExportSink::Kafka { rest_url, topic } => {
+            let records: Vec<Value> = events.iter().map(|event| json!({ "key": event.handle, "value": event })).collect();
+            let body = serde_json::to_vec(&json!({ "records": records }))?;
+            client
+                .post(format!("{}/topics/{}", rest_url, topic))
+                .header(reqwest::header::CONTENT_TYPE, KAFKA_JSON_CONTENT_TYPE)
+                .body(body)
+        }
+        ExportSink::S3 { .. } => {
+            let mut body = Vec::new();
+            for event in events {
+                serde_json::to_writer(&mut body, event)?;
+                body.push(b'\n');
+            }
+            let key = batch_key(events[0].id, events[events.len() - 1].id);
+            s3_put(client, sink, &key, body, chrono::Utc::now())?
+        }
+    };
+
+    let response = request.send().await?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(anyhow!("{} sink answered {}", sink.name(), status));
+    }
+    // The REST Proxy answers 200 with per-record errors when some failed
+    if let ExportSink::Kafka { .. } = sink {
+        let produced: Value = response.json().await?;
+        let failed = produced["offsets"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find_map(|offset| offset["error"].as_str().map(str::to_string));
+        if let Some(error) = failed {
+            return Err(anyhow!("kafka sink refused records: {}", error));
+        }
+    }
+    Ok(())
+}
+
+/// Object name of the batch from `first_id` to `last_id`, before the prefix;
+/// zero-padded so names sort in id order
+pub fn batch_key(first_id: i64, last_id: i64) -> String {
+    format!("{:020}-{:020}.ndjson", first_id, last_id)
+}
+
+/// A SigV4-signed PutObject of `body` at `key` (under the sink's prefix),
+/// addressed path-style so S3-compatible stores work too
+fn s3_put(
+    client: &reqwest::Client,
+    sink: &ExportSink,
+    key: &str,
+    body: Vec<u8>,
+    now: chrono::DateTime<chrono::Utc>,
+) -> Result<reqwest::RequestBuilder> {
+    let ExportSink::S3 { bucket, region, endpoint, prefix, access_key_id, secret_access_key } = sink else {
+        return Err(anyhow!("not an S3 sink"));
+    };
+    let path = format!("/{}/{}", uri_encode(bucket), uri_encode(&format!("{}{}", prefix, key)));
+    let url = reqwest::Url::parse(&format!("{}{}", endpoint, path))?;
+    let host = match (url.host_str(), url.port()) {
+        (Some(host), Some(port)) => format!("{}:{}", host, port),
+        (Some(host), None) => host.to_string(),
+        (None, _) => return Err(anyhow!("EXPORT_S3_ENDPOINT has no host")),
+    };
+
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = &amz_date[..8];
+    let payload_hash = hex::encode(Sha256::digest(&body));
+    let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+    let canonical_request = format!(
+        "PUT\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+        path, host, payload_hash, amz_date, signed_headers, payload_hash
+    );
+    let scope = format!("{}/{}/s3/aws4_request", date, region);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        scope,
+        hex::encode(Sha256::digest(canonical_request.as_bytes()))
+    );
+    let signature = hex::encode(hmac(&signing_key(secret_access_key, date, region, "s3"), string_to_sign.as_bytes()));
+
+    Ok(client
+        .put(url)
+        .header("x-amz-date", amz_date)
+        .header("x-amz-content-sha256", payload_hash)
+        .header(
+            reqwest::header::AUTHORIZATION,
+            format!(
+                "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+                access_key_id, scope, signed_headers, signature
+            ),
+        )
+        .header(reqwest::header::CONTENT_TYPE, "application/x-ndjson")
+        .body(body))
+}
+
+/// SigV4 signing key of a secret for one day, region and service
+fn signing_key(secret: &str, date: &str, region: &str, service: &str) -> Vec<u8> {
+    let key = hmac(format!("AWS4{}", secret).as_bytes(), date.as_bytes());
+    let key = hmac(&key, region.as_bytes());
+    let key = hmac(&key, service.as_bytes());
+    hmac(&key, b"aws4_request")
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC can take key of any size");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Percent-encode everything but unreserved characters and `/`, as SigV4
+/// canonical URIs for S3 require
+fn uri_encode(path: &str) -> String {
+    path.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sigv4_signing_key_matches_aws_example() {
+        // AWS's documented derivation example
+        let key = signing_key("wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY", "20120215", "us-east-1", "iam");
+        assert_eq!(hex::encode(key), "f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d");
+    }
+
+    #[test]
+    fn test_batch_keys_sort_by_id_and_encode_safely() {
+        assert_eq!(batch_key(7, 42), "00000000000000000007-00000000000000000042.ndjson");
+        assert!(batch_key(9, 10) < batch_key(10, 11));
+        assert_eq!(uri_encode("ram events/2024+x"), "ram%20events/2024%2Bx");
+    }
+}
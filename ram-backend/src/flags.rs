@@ -0,0 +1,175 @@
+// Feature flags
+// Risky features are gated by flags in `feature_flags`, so they can be turned
+// off or rolled out to a share of users without a deploy. A flag is on where
+// it's enabled, the environment (RAM_ENVIRONMENT) is one of its environments
+// (or it lists none) and the subject (a handle, a client address) falls in
+// its rollout: subjects are bucketed 0-99 by SHA-256(flag ":" subject), so a
+// subject that's in stays in as the percentage grows. The enclave evaluates
+// the same flags the same way from GET /api/flags. Operators edit them
+// through /admin/flags; the backend that took the change reloads at once and
+// the others within FEATURE_FLAGS_RELOAD_SECS. A feature whose flag doesn't
+// exist keeps its default.
+
+use anyhow::Result;
+use axum::{extract::State, Json};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tracing::{error, info};
+
+use crate::config::FeatureFlagConfig;
+use crate::database::{Database, DbPool};
+use crate::models::{FeatureFlag, FeatureFlagList};
+use crate::AppState;
+
+/// Public explorer aggregates, per client address (see explorer.rs)
+pub const PUBLIC_EXPLORER: &str = "public_explorer";
+
+/// Longest flag or environment name
+const MAX_NAME_LEN: usize = 64;
+
+/// Rollout bucket (0-99) of `subject` for the flag `name`
+pub fn bucket(name: &str, subject: &str) -> u8 {
+    let digest = Sha256::new()
+        .chain_update(name.as_bytes())
+        .chain_update(b":")
+        .chain_update(subject.as_bytes())
+        .finalize();
+    let mut head = [0u8; 8];
+    head.copy_from_slice(&digest[..8]);
+    (u64::from_be_bytes(head) % 100) as u8
+}
+
+/// Whether `flag` is on for `subject` in `environment`
+pub fn is_on(flag: &FeatureFlag, environment: &str, subject: &str) -> bool {
+    flag.enabled
+        && (flag.environments.is_empty() || flag.environments.iter().any(|env| env == environment))
+        && i16::from(bucket(&flag.name, subject)) < flag.rollout_percent
+}
+
+/// Whether `name` can name a flag or an environment: lowercase letters,
+/// digits, `_` and `-`
+pub fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= MAX_NAME_LEN
+        && name.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'_' || b == b'-')
+}
+
+/// The flags currently in effect, by name
+pub struct FeatureFlags {
+    environment: String,
+    flags: RwLock<HashMap<String, FeatureFlag>>,
+}
+
+impl Default for FeatureFlags {
+    fn default() -> Self {
+        Self::new(FeatureFlagConfig::default().environment)
+    }
+}
+
+impl FeatureFlags {
+    /// No flags yet, evaluated in `environment`
+    pub fn new(environment: impl Into<String>) -> Self {
+        Self { environment: environment.into(), flags: RwLock::default() }
+    }
+
+    pub fn environment(&self) -> &str {
+        &self.environment
+    }
+
+    /// Whether the feature behind `name` is on for `subject`; `default` if
+    /// there's no such flag
+    pub fn is_enabled(&self, name: &str, subject: &str, default: bool) -> bool {
+        match self.flags.read().unwrap().get(name) {
+            Some(flag) => is_on(flag, &self.environment, subject),
+            None => default,
+        }
+    }
+
+    /// Every flag in effect, by name
+    pub fn snapshot(&self) -> Vec<FeatureFlag> {
+        let mut flags: Vec<FeatureFlag> = self.flags.read().unwrap().values().cloned().collect();
+        flags.sort_by(|a, b| a.name.cmp(&b.name));
+        flags
+    }
+
+    /// Replace the flags with the ones in the database, returning how many there are
+    pub async fn reload(&self, pool: &DbPool) -> Result<usize> {
+        let flags = Database::list_feature_flags(pool).await?;
+        let count = flags.len();
+        *self.flags.write().unwrap() = flags.into_iter().map(|flag| (flag.name.clone(), flag)).collect();
+        Ok(count)
+    }
+
+    /// Reload the flags every `interval`, for as long as it runs
+    /// (supervised, see supervisor.rs)
+    pub async fn reload_every(self: Arc<Self>, pool: DbPool, interval: Duration) -> Result<()> {
+        loop {
+            tokio::time::sleep(interval).await;
+            if let Err(e) = self.reload(&pool).await {
+                error!("Failed to reload feature flags: {}", e);
+            }
+        }
+    }
+
+    /// Load the flags at startup
+    pub async fn load(pool: &DbPool, environment: impl Into<String>) -> Result<Self> {
+        let flags = Self::new(environment);
+        let count = flags.reload(pool).await?;
+        info!("Loaded {} feature flags for environment {}", count, flags.environment);
+        Ok(flags)
+    }
+}
+
+/// Every flag in effect, for the enclave to evaluate in its own environment
+///
+/// GET /api/flags
+pub async fn list_flags(State(state): State<Arc<AppState>>) -> Json<FeatureFlagList> {
+    Json(FeatureFlagList { flags: state.feature_flags.snapshot() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flag(rollout_percent: i16, environments: &[&str]) -> FeatureFlag {
+        FeatureFlag {
+            name: "gray_zone_retry".to_string(),
+            description: String::new(),
+            enabled: true,
+            rollout_percent,
+            environments: environments.iter().map(|env| env.to_string()).collect(),
+            updated_at_ms: 0,
+        }
+    }
+
+    #[test]
+    fn test_rollout_is_by_environment_and_stable_bucket() {
+        // Shared with the enclave's feature_flags.rs, which must bucket alike
+        assert_eq!(bucket("gray_zone_retry", "alice"), 46);
+
+        assert!(is_on(&flag(100, &[]), "production", "alice"));
+        assert!(!is_on(&flag(100, &["staging"]), "production", "alice"));
+        assert!(is_on(&flag(47, &["staging"]), "staging", "alice"));
+        assert!(!is_on(&flag(46, &[]), "production", "alice"));
+        assert!(!is_on(&FeatureFlag { enabled: false, ..flag(100, &[]) }, "production", "alice"));
+
+        // Roughly the configured share of subjects
+        let on = (0..1000).filter(|i| is_on(&flag(30, &[]), "production", &format!("user{}", i))).count();
+        assert!((250..350).contains(&on), "{} of 1000", on);
+    }
+
+    #[test]
+    fn test_missing_flags_keep_the_default() {
+        let flags = FeatureFlags::default();
+        assert_eq!(flags.environment(), "production");
+        assert!(flags.is_enabled(PUBLIC_EXPLORER, "10.0.0.1", true));
+        assert!(!flags.is_enabled(PUBLIC_EXPLORER, "10.0.0.1", false));
+
+        assert!(is_valid_name("gray_zone_retry"));
+        assert!(is_valid_name("eu-staging"));
+        assert!(!is_valid_name("Gray Zone"));
+        assert!(!is_valid_name(""));
+    }
+}
@@ -0,0 +1,216 @@
+// Header forwarding policy for proxied enclave requests
+// Inbound headers only reach the enclave if the policy allowlists them
+// (request ID, idempotency key, locale, authorization for the enclave ACL by
+// default; NAUTILUS_FORWARD_HEADERS replaces the list). Hop-by-hop headers and
+// the ones the proxy sets itself are never forwarded, whatever the allowlist
+// says, and hop-by-hop headers are stripped from enclave responses too.
+
+use axum::http::{header, HeaderMap, HeaderName};
+use std::str::FromStr;
+
+use crate::signing::{SIGNATURE_HEADER, TIMESTAMP_HEADER};
+
+/// Inbound headers forwarded when NAUTILUS_FORWARD_HEADERS is unset
+pub const DEFAULT_FORWARDED_HEADERS: &[&str] = &[
+    "x-request-id",
+    "idempotency-key",
+    "accept-language",
+    "authorization",
+    // Lets the enclave tell SSE clients apart
+    "accept",
+];
+
+/// Headers that only describe one hop (RFC 9110 §7.6.1)
+const HOP_BY_HOP_HEADERS: &[&str] = &[
+    "connection",
+    "keep-alive",
+    "proxy-connection",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "te",
+    "transfer-encoding",
+    "upgrade",
+];
+
+/// Headers the proxy sets on enclave requests, so clients can't supply them
+/// (a forged signature or a body length that doesn't match the relayed body)
+const PROXY_OWNED_HEADERS: &[&str] = &[
+    "host",
+    "content-length",
+    "content-type",
+    TIMESTAMP_HEADER,
+    SIGNATURE_HEADER,
+];
+
+/// Which inbound request headers are forwarded to the enclave
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeaderPolicy {
+    allowed: Vec<HeaderName>,
+}
+
+impl HeaderPolicy {
+    /// Forward exactly these headers (hop-by-hop and proxy-owned ones excepted)
+    pub fn new(allowed: Vec<HeaderName>) -> Self {
+        Self { allowed }
+    }
+
+    /// Whether an inbound header may be forwarded
+    pub fn allows(&self, name: &HeaderName) -> bool {
+        self.allowed.contains(name)
+            && !HOP_BY_HOP_HEADERS.contains(&name.as_str())
+            && !PROXY_OWNED_HEADERS.contains(&name.as_str())
+    }
+
+    /// The inbound headers to send on to the enclave
+    pub fn request_headers(&self, inbound: &HeaderMap) -> HeaderMap {
+        let listed = connection_listed(inbound);
+        inbound
+            .iter()
+            .filter(|(name, _)| self.allows(name) && !listed.contains(name))
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect()
+    }
+}
+
+impl Default for HeaderPolicy {
+    fn default() -> Self {
+        Self::new(DEFAULT_FORWARDED_HEADERS.iter().map(|name| HeaderName::from_static(name)).collect())
+    }
+}
+
+/// Comma-separated header names, e.g. `x-request-id, accept-language`
+impl FromStr for HeaderPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(|name| {
+                HeaderName::from_str(name).map_err(|_| format!("'{}' is not a valid header name", name))
+            })
+            .collect::<Result<_, _>>()
+            .map(Self::new)
+    }
+}
+
+/// Response headers minus the hop-by-hop ones, which apply to a single connection
+///
+/// `Trailer` is kept: it announces the trailer fields the body ends with, and
+/// hyper only sends trailers that it names (to clients sending `TE: trailers`).
+pub fn end_to_end_headers(headers: &HeaderMap) -> HeaderMap {
+    let listed = connection_listed(headers);
+    headers
+        .iter()
+        .filter(|(name, _)| !HOP_BY_HOP_HEADERS.contains(&name.as_str()) && !listed.contains(name))
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect()
+}
+
+/// Further per-connection headers named by `Connection`
+fn connection_listed(headers: &HeaderMap) -> Vec<HeaderName> {
+    headers
+        .get_all(header::CONNECTION)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .filter_map(|name| HeaderName::from_str(name.trim()).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn headers(pairs: &[(&str, &str)]) -> HeaderMap {
+        pairs
+            .iter()
+            .map(|(name, value)| (HeaderName::from_str(name).unwrap(), HeaderValue::from_str(value).unwrap()))
+            .collect()
+    }
+
+    fn names(headers: &HeaderMap) -> Vec<&str> {
+        let mut names: Vec<&str> = headers.keys().map(HeaderName::as_str).collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_default_policy_forwards_safe_headers() {
+        let inbound = headers(&[
+            ("x-request-id", "req-1"),
+            ("idempotency-key", "key-1"),
+            ("accept-language", "vi-VN"),
+            ("authorization", "Bearer abc"),
+            ("accept", "text/event-stream"),
+        ]);
+        let forwarded = HeaderPolicy::default().request_headers(&inbound);
+        assert_eq!(forwarded, inbound);
+    }
+
+    #[test]
+    fn test_unlisted_headers_are_dropped() {
+        let inbound = headers(&[("cookie", "session=1"), ("x-forwarded-for", "1.2.3.4"), ("x-request-id", "req-1")]);
+        assert_eq!(names(&HeaderPolicy::default().request_headers(&inbound)), ["x-request-id"]);
+    }
+
+    #[test]
+    fn test_hop_by_hop_headers_never_forwarded() {
+        let policy: HeaderPolicy = "connection, keep-alive, te, transfer-encoding, upgrade, proxy-authorization, x-request-id"
+            .parse()
+            .unwrap();
+        let inbound = headers(&[
+            ("connection", "keep-alive"),
+            ("keep-alive", "timeout=5"),
+            ("te", "trailers"),
+            ("transfer-encoding", "chunked"),
+            ("upgrade", "websocket"),
+            ("proxy-authorization", "Basic abc"),
+            ("x-request-id", "req-1"),
+        ]);
+        assert_eq!(names(&policy.request_headers(&inbound)), ["x-request-id"]);
+    }
+
+    #[test]
+    fn test_connection_listed_headers_are_hop_by_hop() {
+        let inbound = headers(&[("connection", "x-request-id"), ("x-request-id", "req-1"), ("accept-language", "en")]);
+        assert_eq!(names(&HeaderPolicy::default().request_headers(&inbound)), ["accept-language"]);
+    }
+
+    #[test]
+    fn test_proxy_owned_headers_cannot_be_supplied() {
+        let policy: HeaderPolicy = "host, content-length, content-type, x-ram-timestamp, x-ram-signature".parse().unwrap();
+        let inbound = headers(&[
+            ("host", "evil.example"),
+            ("content-length", "1"),
+            ("content-type", "text/plain"),
+            ("x-ram-timestamp", "1"),
+            ("x-ram-signature", "forged"),
+        ]);
+        assert!(policy.request_headers(&inbound).is_empty());
+    }
+
+    #[test]
+    fn test_policy_parses_from_env_list() {
+        let policy: HeaderPolicy = " X-Request-ID ,,x-tenant".parse().unwrap();
+        assert!(policy.allows(&HeaderName::from_static("x-request-id")));
+        assert!(policy.allows(&HeaderName::from_static("x-tenant")));
+        // The list replaces the defaults
+        assert!(!policy.allows(&header::AUTHORIZATION));
+        assert!("x-request-id, bad header".parse::<HeaderPolicy>().is_err());
+    }
+
+    #[test]
+    fn test_responses_lose_hop_by_hop_headers_but_keep_trailer() {
+        let upstream = headers(&[
+            ("connection", "close, x-upstream-hint"),
+            ("x-upstream-hint", "1"),
+            ("keep-alive", "timeout=5"),
+            ("transfer-encoding", "chunked"),
+            ("trailer", "x-checksum"),
+            ("content-type", "application/json"),
+        ]);
+        assert_eq!(names(&end_to_end_headers(&upstream)), ["content-type", "trailer"]);
+    }
+}
@@ -0,0 +1,179 @@
+// Gas recommendations from dry runs
+// `POST /api/tx/estimate` takes a built transaction, dry-runs it on the
+// fullnode and answers with the gas price and budget to set on it: the
+// reference gas price, and the dry run's computation cost plus a safety
+// overhead and its net storage cost (as the Sui SDKs budget), raised by
+// GAS_BUDGET_MARGIN_PERCENT. Sponsored onboarding and transfer previews
+// budget their transactions the same way. Nothing is signed or submitted.
+// Every dry run costs the fullnode, so each client gets
+// GAS_ESTIMATE_RATE_LIMIT_PER_MIN estimates a minute, counted apart from the
+// explorer's requests.
+
+use anyhow::{anyhow, Result};
+use axum::{
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::Response,
+    Json,
+};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tracing::{error, warn};
+
+use crate::config::GasConfig;
+use crate::database::json_u64;
+use crate::explorer;
+use crate::models::{GasEstimateRequest, GasRecommendation, GasUsed};
+use crate::rpc::{RpcError, SuiRpcClient};
+use crate::AppState;
+
+/// Largest transaction Sui accepts, in bytes
+pub const MAX_TX_BYTES: usize = 128 * 1024;
+/// Computation units added to the dry run's, as the Sui SDKs do, so small
+/// differences at execution don't run out of gas
+const SAFE_OVERHEAD_UNITS: u64 = 1_000;
+
+/// Budget covering `gas_used` at `gas_price`, raised by `margin_percent`
+pub fn recommended_budget(gas_used: &GasUsed, gas_price: u64, margin_percent: u64) -> u64 {
+    let computation = gas_used.computation_cost.saturating_add(SAFE_OVERHEAD_UNITS.saturating_mul(gas_price));
+    let with_storage = computation
+        .saturating_add(gas_used.storage_cost)
+        .saturating_sub(gas_used.storage_rebate);
+    let budget = computation.max(with_storage) as u128 * (100 + margin_percent as u128) / 100;
+    budget.min(u64::MAX as u128) as u64
+}
+
+/// Net fee of a dry run (MIST): its computation and storage cost, less the
+/// rebate; an error if the figures overflow or the rebate exceeds the cost
+fn net_fee(gas_used: &GasUsed) -> Result<u64> {
+    let cost = gas_used
+        .computation_cost
+        .checked_add(gas_used.storage_cost)
+        .ok_or_else(|| anyhow!("Dry run gas cost overflows"))?;
+    cost.checked_sub(gas_used.storage_rebate)
+        .ok_or_else(|| anyhow!("Dry run storage rebate {} exceeds its cost {}", gas_used.storage_rebate, cost))
+}
+
+/// Dry-run transaction bytes and recommend a gas price and budget for them
+pub async fn dry_run(rpc: &SuiRpcClient, tx_bytes: &[u8], config: &GasConfig) -> Result<GasRecommendation> {
+    let result = rpc.call("sui_dryRunTransactionBlock", json!([STANDARD.encode(tx_bytes)])).await?;
+    let gas_price = json_u64(&rpc.call("suix_getReferenceGasPrice", json!([])).await?)
+        .ok_or_else(|| anyhow!("unexpected reference gas price"))?;
+
+    let effects = &result["effects"];
+    let gas_used = parse_gas_used(&effects["gasUsed"]).ok_or_else(|| anyhow!("No gas usage in dry run result"))?;
+    let status = effects["status"]["status"].as_str().unwrap_or("failure").to_string();
+    let error = (status != "success")
+        .then(|| effects["status"]["error"].as_str().unwrap_or("unknown error").to_string());
+    let fee = net_fee(&gas_used)?;
+
+    Ok(GasRecommendation {
+        budget: recommended_budget(&gas_used, gas_price, config.budget_margin_percent),
+        status,
+        error,
+        gas_used,
+        fee,
+        gas_price,
+    })
+}
+
+fn parse_gas_used(value: &Value) -> Option<GasUsed> {
+    Some(GasUsed {
+        computation_cost: json_u64(&value["computationCost"])?,
+        storage_cost: json_u64(&value["storageCost"])?,
+        storage_rebate: json_u64(&value["storageRebate"])?,
+        non_refundable_storage_fee: json_u64(&value["nonRefundableStorageFee"]).unwrap_or(0),
+    })
+}
+
+/// Rate-limit estimates per client, with their own limit; 429 with
+/// `Retry-After` once over
+pub async fn rate_limit(State(state): State<Arc<AppState>>, request: Request, next: Next) -> Response {
+    let client = explorer::client_ip(&state, &request);
+    explorer::throttle(&state.gas_estimates, client, request, next).await
+}
+
+/// Recommend a gas price and budget for a built transaction
+///
+/// POST /api/tx/estimate — `{ "tx_bytes": <base64 TransactionData> }`; 400 if
+/// the bytes aren't base64 or the fullnode rejects them as a transaction,
+/// 413 past Sui's size limit, 502 if the fullnode can't be reached. A
+/// transaction that would abort is still answered, with status "failure".
+pub async fn estimate_transaction(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<GasEstimateRequest>,
+) -> Result<Json<GasRecommendation>, StatusCode> {
+    let tx_bytes = STANDARD.decode(request.tx_bytes.trim()).map_err(|_| StatusCode::BAD_REQUEST)?;
+    if tx_bytes.is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    if tx_bytes.len() > MAX_TX_BYTES {
+        return Err(StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    dry_run(&state.sui_rpc, &tx_bytes, &state.gas).await.map(Json).map_err(|e| {
+        if e.downcast_ref::<RpcError>().is_some() {
+            warn!("Fullnode rejected a transaction to estimate: {}", e);
+            StatusCode::BAD_REQUEST
+        } else {
+            error!("Failed to estimate gas: {}", e);
+            StatusCode::BAD_GATEWAY
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_budget_covers_computation_and_net_storage() {
+        let gas_used = GasUsed {
+            computation_cost: 1_000_000,
+            storage_cost: 2_000_000,
+            storage_rebate: 500_000,
+            non_refundable_storage_fee: 5_000,
+        };
+        // (1_000_000 + 1_000 * 750 + 2_000_000 - 500_000) * 1.2
+        assert_eq!(recommended_budget(&gas_used, 750, 20), 3_900_000);
+        assert_eq!(recommended_budget(&gas_used, 750, 0), 3_250_000);
+
+        // A rebate larger than the storage cost never lowers the budget below computation
+        let rebated = GasUsed { storage_rebate: 5_000_000, ..gas_used };
+        assert_eq!(recommended_budget(&rebated, 750, 0), 1_750_000);
+        assert_eq!(recommended_budget(&GasUsed { computation_cost: u64::MAX, ..rebated }, 750, 20), u64::MAX);
+    }
+
+    #[test]
+    fn test_fee_is_checked() {
+        let gas_used = GasUsed {
+            computation_cost: 1_000_000,
+            storage_cost: 2_000_000,
+            storage_rebate: 500_000,
+            non_refundable_storage_fee: 5_000,
+        };
+        assert_eq!(net_fee(&gas_used).unwrap(), 2_500_000);
+        assert!(net_fee(&GasUsed { storage_rebate: 3_000_001, ..gas_used }).is_err());
+        assert!(net_fee(&GasUsed { computation_cost: u64::MAX, ..gas_used }).is_err());
+    }
+
+    #[test]
+    fn test_gas_used_accepts_rpc_strings() {
+        let value = json!({
+            "computationCost": "1000000", "storageCost": "1976000",
+            "storageRebate": "978120", "nonRefundableStorageFee": "9880",
+        });
+        assert_eq!(
+            parse_gas_used(&value),
+            Some(GasUsed {
+                computation_cost: 1_000_000,
+                storage_cost: 1_976_000,
+                storage_rebate: 978_120,
+                non_refundable_storage_fee: 9_880,
+            })
+        );
+        assert_eq!(parse_gas_used(&json!({ "computationCost": "1" })), None);
+    }
+}
@@ -0,0 +1,31 @@
+// Handle normalization
+// Handles are matched case-insensitively and Unicode-normalized, so "Alice",
+// "alice" and "ａｌｉｃｅ" (fullwidth) are the same wallet: NFKC folds
+// compatibility forms, then the result is lowercased. The 20240226 migration
+// applies the same rule in Postgres (`lower(normalize(handle, NFKC))`), so
+// every handle taken from a request goes through `normalize_handle` before it
+// reaches a query. The normalized form is only for matching: wallets keep the
+// spelling they were registered under on-chain, which is what the enclave
+// signs and the Move contract checks, so handles returned for use in a
+// payload (receive links, contacts) are that spelling.
+
+use unicode_normalization::UnicodeNormalization;
+
+/// The canonical form of a handle: NFKC, then lowercased
+pub fn normalize_handle(handle: &str) -> String {
+    handle.nfkc().collect::<String>().to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_case_and_compatibility_forms_fold_together() {
+        assert_eq!(normalize_handle("Alice"), "alice");
+        assert_eq!(normalize_handle("ＡＬＩＣＥ"), "alice");
+        assert_eq!(normalize_handle("bob_smith+1@x.io"), "bob_smith+1@x.io");
+        // Precomposed and combining accents are the same character
+        assert_eq!(normalize_handle("Ame\u{301}lie"), normalize_handle("am\u{e9}lie"));
+    }
+}
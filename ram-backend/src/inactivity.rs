@@ -0,0 +1,72 @@
+// Inactivity warnings for wallets with a beneficiary
+// A wallet that names an inactivity beneficiary can be claimed by it once the
+// owner has done nothing for the whole timeout. On-chain, activity is any
+// operation the owner signs; here it's approximated from the indexed events
+// those operations emit. Shortly before the claim window opens, the owner
+// gets an `inactivity_warning` notification, once per inactive stretch, so a
+// forgotten wallet isn't handed over without notice.
+
+use anyhow::Result;
+use serde_json::json;
+use std::time::Duration;
+use tracing::{error, info};
+
+use crate::config::InactivityConfig;
+use crate::database::{Database, DbPool};
+
+/// Indexed events that show the owner acted (deposits and claims don't:
+/// anyone can send them)
+pub const ACTIVITY_EVENTS: &[&str] = &[
+    "AddressLinked",
+    "AddressUnlinked",
+    "Withdrawn",
+    "Transferred",
+    "WalletLocked",
+    "BioAuthSuccess",
+    "BioAuthFailed",
+    "DailyLimitSet",
+    "WalletFrozen",
+    "WalletUnfrozen",
+    "BeneficiarySet",
+    "CheckedIn",
+];
+
+/// Warn every owner whose claim window opens within `warning_period` of
+/// `now_ms`, returning how many were warned
+pub async fn warn_inactive_wallets(pool: &DbPool, warning_period: Duration, now_ms: i64) -> Result<usize> {
+    let activity_events: Vec<String> = ACTIVITY_EVENTS.iter().map(|e| e.to_string()).collect();
+    let wallets = Database::get_unwarned_inactive_wallets(
+        pool,
+        &activity_events,
+        now_ms,
+        warning_period.as_millis() as i64,
+    )
+    .await?;
+
+    for wallet in &wallets {
+        let data = json!({
+            "beneficiary_handle": wallet.beneficiary_handle,
+            "last_active_ms": wallet.last_active_ms,
+            "claimable_at_ms": wallet.claimable_at_ms,
+        });
+        Database::record_inactivity_warning(pool, &wallet.handle, &data, now_ms).await?;
+        info!(
+            "Warned {} that {} can claim the wallet from {}",
+            wallet.handle, wallet.beneficiary_handle, wallet.claimable_at_ms
+        );
+    }
+
+    Ok(wallets.len())
+}
+
+/// Check for inactive wallets every `check_interval`, for as long as it runs
+/// (supervised, see supervisor.rs)
+pub async fn run_inactivity_warnings(pool: DbPool, config: InactivityConfig) -> Result<()> {
+    loop {
+        let now_ms = chrono::Utc::now().timestamp_millis();
+        if let Err(e) = warn_inactive_wallets(&pool, config.warning_period, now_ms).await {
+            error!("Inactivity check failed: {}", e);
+        }
+        tokio::time::sleep(config.check_interval).await;
+    }
+}
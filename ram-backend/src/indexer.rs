@@ -1,17 +1,56 @@
+use crate::analytics::AnalyticsStore;
+use crate::changes::ChangeFeed;
 use crate::models::RamEvent;
 use crate::database::Database;
+use crate::receipts;
+use crate::refunds;
+use crate::travel_rule;
+use crate::rpc::{RpcError, SuiRpcClient};
+use crate::webhooks;
 use chrono::{Utc, TimeZone};
-use reqwest::Client as HttpClient;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use sqlx::PgPool;
-use std::time::Duration;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tracing::{info, warn, error};
 use anyhow::{Result, anyhow};
 
+mod checkpoints;
+mod throttle;
+
+pub use throttle::{IndexerProgress, Throttle};
+
 const POLL_INTERVAL: Duration = Duration::from_secs(5);
 const BATCH_SIZE: u64 = 50;
 
+/// Delay before the first retry of a failed event, doubled on each attempt
+const RETRY_BASE_DELAY_MS: i64 = 10_000;
+/// Upper bound on the retry backoff (1 hour)
+const RETRY_MAX_DELAY_MS: i64 = 3_600_000;
+/// Failed events are marked dead after this many attempts
+pub const MAX_RETRY_ATTEMPTS: i32 = 8;
+/// Failed events retried per poll
+const RETRY_BATCH_SIZE: i64 = 20;
+
+/// Backoff before the next retry of an event that has failed `attempts` times
+pub fn retry_delay_ms(attempts: i32) -> i64 {
+    let exponent = attempts.saturating_sub(1).clamp(0, 20) as u32;
+    RETRY_BASE_DELAY_MS
+        .saturating_mul(1 << exponent)
+        .min(RETRY_MAX_DELAY_MS)
+}
+
+/// Payload layout version of a Transferred event, None for events emitted
+/// before the field existed (which were all v1)
+pub fn transfer_payload_version(parsed_json: &Value) -> Option<i16> {
+    match &parsed_json["payload_version"] {
+        Value::Number(n) => n.as_i64().and_then(|v| i16::try_from(v).ok()),
+        Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct EventId {
@@ -45,7 +84,7 @@ pub struct EventPage {
     pub has_next_page: bool,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SuiEvent {
     pub id: EventId,
@@ -53,65 +92,173 @@ pub struct SuiEvent {
     pub event_type: String,
     pub parsed_json: Value,
     pub timestamp_ms: Option<String>,
+    /// Address that signed the emitting transaction
+    pub sender: Option<String>,
+    /// Emitting transaction's context, only known to the checkpoint indexer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tx_context: Option<TxContext>,
 }
 
-#[derive(Debug, Deserialize)]
-struct RpcResponse<T> {
-    jsonrpc: String,
-    result: Option<T>,
-    error: Option<RpcError>,
-    id: i64,
-}
-
-#[derive(Debug, Deserialize)]
-struct RpcError {
-    code: i64,
-    message: String,
+/// Transaction-level data that `suix_queryEvents` doesn't return
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TxContext {
+    pub sender: String,
+    /// Net gas in MIST: computation + storage - storage rebate
+    pub gas_used: i64,
 }
 
 pub struct Indexer {
-    http_client: HttpClient,
-    rpc_url: String,
+    rpc: Arc<SuiRpcClient>,
     package_id: String,
     pool: PgPool,
+    /// Told about every handle a stored event touches (see changes.rs)
+    changes: Option<Arc<ChangeFeed>>,
+    /// Paces catch-up and tracks progress (see indexer/throttle.rs)
+    throttle: Arc<Throttle>,
+    /// Also handed every stored event (see analytics.rs)
+    analytics: Option<Arc<AnalyticsStore>>,
 }
 
 impl Indexer {
-    pub fn new(rpc_url: String, package_id: String, pool: PgPool) -> Self {
+    /// Indexer over a comma-separated list of RPC URLs
+    pub fn new(rpc_urls: String, package_id: String, pool: PgPool) -> Self {
+        Self::with_rpc(Arc::new(SuiRpcClient::from_list(&rpc_urls)), package_id, pool)
+    }
+
+    /// Indexer sharing an existing RPC client (and its endpoint health) with the API
+    pub fn with_rpc(rpc: Arc<SuiRpcClient>, package_id: String, pool: PgPool) -> Self {
         Self {
-            http_client: HttpClient::new(),
-            rpc_url,
+            rpc,
             package_id,
             pool,
+            changes: None,
+            throttle: Arc::default(),
+            analytics: None,
         }
     }
 
+    /// Publish the handles of stored events to `changes`
+    pub fn with_changes(mut self, changes: Arc<ChangeFeed>) -> Self {
+        self.changes = Some(changes);
+        self
+    }
+
+    /// Write every stored event to the analytics store as well
+    pub fn with_analytics(mut self, analytics: Arc<AnalyticsStore>) -> Self {
+        self.analytics = Some(analytics);
+        self
+    }
+
+    /// Pace catch-up with `throttle`, shared with `/admin/indexer_progress`
+    pub fn with_throttle(mut self, throttle: Arc<Throttle>) -> Self {
+        self.throttle = throttle;
+        self
+    }
+
     pub async fn run(&self) -> Result<()> {
         info!("Starting indexer for package {}", self.package_id);
         
-        let mut cursor = self.load_cursor().await?;
-        
         loop {
-            match self.fetch_and_process_events(cursor.as_ref()).await {
+            // Reloaded every poll since re-anchoring may reset the stored cursor
+            let cursor = self.load_cursor().await?;
+            let caught_up = match self.fetch_and_process_events(cursor.as_ref()).await {
                 Ok(new_cursor) => {
                     if let Some(new_cursor) = new_cursor {
                         self.save_cursor(&new_cursor).await?;
-                        cursor = Some(new_cursor);
                     }
+                    self.throttle.is_caught_up()
                 }
                 Err(e) => {
                     error!("Error processing events: {}", e);
+                    self.throttle.batch_failed();
+                    true
                 }
+            };
+
+            if let Err(e) = self.retry_failed_events().await {
+                error!("Error retrying failed events: {}", e);
+            }
+
+            // While catching up, the throttle paces the next page instead
+            if caught_up {
+                tokio::time::sleep(POLL_INTERVAL).await;
             }
-            
-            tokio::time::sleep(POLL_INTERVAL).await;
         }
     }
 
-    async fn fetch_and_process_events(
+    /// Fetch and ingest one page of events after `cursor`, returning the next cursor
+    ///
+    /// If the fullnode no longer knows the cursor, the gap is recorded and
+    /// ingestion restarts from the earliest event still available.
+    pub async fn fetch_and_process_events(
         &self,
         cursor: Option<&EventId>,
     ) -> Result<Option<EventId>> {
+        let limit = self.throttle.batch_size(BATCH_SIZE);
+        let event_page = match (self.query_events(cursor, limit).await, cursor) {
+            (Err(e), Some(stale)) if e.downcast_ref::<RpcError>().is_some_and(RpcError::is_stale_cursor) => {
+                self.reanchor(stale, &e, limit).await?
+            }
+            (result, _) => result?,
+        };
+        
+        if event_page.data.is_empty() {
+            self.throttle.finish_batch(throttle::Batch {
+                events: 0,
+                position: None,
+                chain_time_ms: None,
+                caught_up: true,
+                size: limit,
+            });
+            return Ok(None);
+        }
+
+        info!("Fetched {} events", event_page.data.len());
+
+        for event in &event_page.data {
+            self.throttle.before_write(&self.pool).await;
+            if let Err(e) = self.process_event(event).await {
+                warn!("Failed to process event {:?}, queued for retry: {}", event.id, e);
+                self.record_failure(event, &e).await?;
+            }
+        }
+
+        self.throttle.finish_batch(throttle::Batch {
+            events: event_page.data.len() as u64,
+            position: event_page.next_cursor.as_ref().map(EventId::to_cursor),
+            chain_time_ms: event_page.data.last().and_then(|e| e.timestamp_ms.as_deref()?.parse().ok()),
+            caught_up: !event_page.has_next_page,
+            size: limit,
+        });
+
+        Ok(event_page.next_cursor)
+    }
+
+    /// Record a gap for a pruned cursor and fetch the earliest available page instead
+    async fn reanchor(&self, stale: &EventId, err: &anyhow::Error, limit: u64) -> Result<EventPage> {
+        warn!(
+            "Cursor {} is no longer available ({}), re-anchoring to earliest event",
+            stale.to_cursor(),
+            err
+        );
+
+        let page = self.query_events(None, limit).await?;
+        let resumed_from = page.data.first().map(|e| e.id.to_cursor());
+        Database::record_indexer_gap(
+            &self.pool,
+            &stale.to_cursor(),
+            resumed_from.as_deref(),
+            &err.to_string(),
+            Utc::now().timestamp_millis(),
+        )
+        .await?;
+        self.clear_cursor().await?;
+
+        Ok(page)
+    }
+
+    async fn query_events(&self, cursor: Option<&EventId>, limit: u64) -> Result<EventPage> {
         let filter = json!({
             "MoveEventModule": {
                 "package": self.package_id,
@@ -123,40 +270,71 @@ impl Indexer {
             .map(|c| json!(c))
             .unwrap_or(Value::Null);
         
-        let payload = json!({
-            "jsonrpc": "2.0",
-            "method": "suix_queryEvents",
-            "params": [filter, cursor_value, BATCH_SIZE, false],
-            "id": 1
-        });
-
-        let resp = self.http_client
-            .post(&self.rpc_url)
-            .json(&payload)
-            .send()
+        let result = self
+            .rpc_call("suix_queryEvents", json!([filter, cursor_value, limit, false]))
             .await?;
 
-        let rpc_resp: RpcResponse<EventPage> = resp.json().await?;
-        
-        if let Some(error) = rpc_resp.error {
-            return Err(anyhow!("RPC error: {} ({})", error.message, error.code));
-        }
-        
-        let event_page = rpc_resp.result.ok_or_else(|| anyhow!("No result in RPC response"))?;
-        
-        if event_page.data.is_empty() {
-            return Ok(None);
-        }
+        Ok(serde_json::from_value(result)?)
+    }
 
-        info!("Fetched {} events", event_page.data.len());
+    /// Call the fullnodes within the throttle's rate limit, telling it how the call went
+    async fn rpc_call(&self, method: &str, params: Value) -> Result<Value> {
+        self.throttle.before_rpc_call().await;
+        let started = Instant::now();
+        let result = self.rpc.call(method, params).await;
+        self.throttle.rpc_call_finished(started.elapsed(), result.is_ok());
+        result
+    }
 
-        for event in &event_page.data {
-            if let Err(e) = self.process_event(event).await {
-                warn!("Failed to process event {:?}: {}", event.id, e);
+    /// Queue a failed event so the cursor can advance without losing it
+    async fn record_failure(&self, event: &SuiEvent, err: &anyhow::Error) -> Result<()> {
+        Database::record_failed_event(
+            &self.pool,
+            &event.id.tx_digest,
+            &event.id.event_seq,
+            &event.event_type,
+            &serde_json::to_value(event)?,
+            &err.to_string(),
+            Utc::now().timestamp_millis() + retry_delay_ms(1),
+        )
+        .await
+    }
+
+    /// Re-process failed events whose backoff has elapsed, returning how many succeeded
+    pub async fn retry_failed_events(&self) -> Result<usize> {
+        let now_ms = Utc::now().timestamp_millis();
+        let due = Database::get_due_failed_events(&self.pool, now_ms, RETRY_BATCH_SIZE).await?;
+
+        let mut recovered = 0;
+        for failed in due {
+            let result = match serde_json::from_value::<SuiEvent>(failed.event_json.clone()) {
+                Ok(event) => self.process_event(&event).await,
+                Err(e) => Err(anyhow!("Stored event is unreadable: {}", e)),
+            };
+
+            match result {
+                Ok(()) => {
+                    Database::delete_failed_event(&self.pool, failed.id).await?;
+                    info!("Recovered failed event {}:{}", failed.tx_digest, failed.event_seq);
+                    recovered += 1;
+                }
+                Err(e) => {
+                    let attempts = failed.attempts + 1;
+                    let next_attempt_ms = (attempts < MAX_RETRY_ATTEMPTS)
+                        .then(|| now_ms + retry_delay_ms(attempts));
+                    if next_attempt_ms.is_none() {
+                        error!(
+                            "Giving up on event {}:{} after {} attempts: {}",
+                            failed.tx_digest, failed.event_seq, attempts, e
+                        );
+                    }
+                    Database::reschedule_failed_event(&self.pool, failed.id, &e.to_string(), next_attempt_ms)
+                        .await?;
+                }
             }
         }
 
-        Ok(event_page.next_cursor)
+        Ok(recovered)
     }
 
     async fn process_event(&self, event: &SuiEvent) -> Result<()> {
@@ -173,31 +351,94 @@ impl Indexer {
             Utc::now()
         };
 
-        let ram_event = match *event_name {
+        let mut ram_event = match *event_name {
             "WalletCreated" => {
                 let owner = event.parsed_json["owner"].as_str().unwrap_or("").to_string();
+                let wallet_id = event.parsed_json["wallet_id"].as_str().map(str::to_string);
                 RamEvent {
                     handle: Some(handle.clone()),
                     event_type: "WalletCreated".to_string(),
                     amount: None,
+                    coin_type: None,
+                    result_code: None,
                     from_handle: None,
                     to_handle: None,
+                    memo: None,
+                    payload_version: None,
                     owner: Some(owner),
+                    wallet_id,
                     tx_digest: tx_digest.clone(),
                     timestamp,
+                    raw_json: Some(event.parsed_json.clone()),
+                    gas_used: None,
+                    sender: None,
+                    sender_label: None,
+                    from_display_name: None,
+                    to_display_name: None,
+                    refund_of: None,
+                    refunded_in: Vec::new(),
                 }
             }
             "AddressLinked" => {
-                let address = event.parsed_json["address"].as_str().unwrap_or("").to_string();
+                let address = event.parsed_json["linked_address"].as_str().unwrap_or("").to_string();
+                let label = event.parsed_json["label"].as_str().unwrap_or("");
+                Database::upsert_linked_address(
+                    &self.pool,
+                    &handle,
+                    &address,
+                    label,
+                    timestamp.timestamp_millis(),
+                )
+                .await?;
                 RamEvent {
                     handle: Some(handle.clone()),
                     event_type: "AddressLinked".to_string(),
                     amount: None,
+                    coin_type: None,
+                    result_code: None,
                     from_handle: None,
                     to_handle: Some(address),
+                    memo: None,
+                    payload_version: None,
                     owner: None,
+                    wallet_id: None,
                     tx_digest: tx_digest.clone(),
                     timestamp,
+                    raw_json: Some(event.parsed_json.clone()),
+                    gas_used: None,
+                    sender: None,
+                    sender_label: None,
+                    from_display_name: None,
+                    to_display_name: None,
+                    refund_of: None,
+                    refunded_in: Vec::new(),
+                }
+            }
+            "AddressUnlinked" => {
+                let address = event.parsed_json["unlinked_address"].as_str().unwrap_or("").to_string();
+                Database::remove_linked_address(&self.pool, &handle, &address).await?;
+                RamEvent {
+                    handle: Some(handle.clone()),
+                    event_type: "AddressUnlinked".to_string(),
+                    amount: None,
+                    coin_type: None,
+                    result_code: None,
+                    from_handle: None,
+                    to_handle: Some(address),
+                    memo: None,
+                    payload_version: None,
+                    owner: None,
+                    wallet_id: None,
+                    tx_digest: tx_digest.clone(),
+                    timestamp,
+                    raw_json: Some(event.parsed_json.clone()),
+                    gas_used: None,
+                    sender: None,
+                    sender_label: None,
+                    from_display_name: None,
+                    to_display_name: None,
+                    refund_of: None,
+                    refunded_in: Vec::new(),
                 }
             }
             "Deposited" => {
@@ -205,15 +446,62 @@ impl Indexer {
                     .as_str()
                     .and_then(|s| s.parse::<i64>().ok())
                     .unwrap_or(0);
+                let coin_type = event.parsed_json["coin_type"].as_str().map(str::to_string);
                 RamEvent {
                     handle: Some(handle.clone()),
                     event_type: "Deposited".to_string(),
                     amount: Some(amount),
+                    coin_type,
+                    result_code: None,
+                    from_handle: None,
+                    to_handle: None,
+                    memo: None,
+                    payload_version: None,
+                    owner: None,
+                    wallet_id: None,
+                    tx_digest: tx_digest.clone(),
+                    timestamp,
+                    raw_json: Some(event.parsed_json.clone()),
+                    gas_used: None,
+                    sender: None,
+                    sender_label: None,
+                    from_display_name: None,
+                    to_display_name: None,
+                    refund_of: None,
+                    refunded_in: Vec::new(),
+                }
+            }
+            "DepositProved" => {
+                // Accompanies a Deposited event, which is what balances count;
+                // this one records the exchange's deposit ID as the memo
+                let amount = event.parsed_json["amount"]
+                    .as_str()
+                    .and_then(|s| s.parse::<i64>().ok())
+                    .unwrap_or(0);
+                let coin_type = event.parsed_json["coin_type"].as_str().map(str::to_string);
+                let deposit_id = event.parsed_json["deposit_id"].as_str().map(str::to_string);
+                RamEvent {
+                    handle: Some(handle.clone()),
+                    event_type: "DepositProved".to_string(),
+                    amount: Some(amount),
+                    coin_type,
+                    result_code: None,
                     from_handle: None,
                     to_handle: None,
+                    memo: deposit_id,
+                    payload_version: None,
                     owner: None,
+                    wallet_id: None,
                     tx_digest: tx_digest.clone(),
                     timestamp,
+                    raw_json: Some(event.parsed_json.clone()),
+                    gas_used: None,
+                    sender: None,
+                    sender_label: None,
+                    from_display_name: None,
+                    to_display_name: None,
+                    refund_of: None,
+                    refunded_in: Vec::new(),
                 }
             }
             "Withdrawn" => {
@@ -221,15 +509,29 @@ impl Indexer {
                     .as_str()
                     .and_then(|s| s.parse::<i64>().ok())
                     .unwrap_or(0);
+                let coin_type = event.parsed_json["coin_type"].as_str().map(str::to_string);
                 RamEvent {
                     handle: Some(handle.clone()),
                     event_type: "Withdrawn".to_string(),
                     amount: Some(amount),
+                    coin_type,
+                    result_code: None,
                     from_handle: None,
                     to_handle: None,
+                    memo: None,
+                    payload_version: None,
                     owner: None,
+                    wallet_id: None,
                     tx_digest: tx_digest.clone(),
                     timestamp,
+                    raw_json: Some(event.parsed_json.clone()),
+                    gas_used: None,
+                    sender: None,
+                    sender_label: None,
+                    from_display_name: None,
+                    to_display_name: None,
+                    refund_of: None,
+                    refunded_in: Vec::new(),
                 }
             }
             "Transferred" => {
@@ -237,16 +539,33 @@ impl Indexer {
                     .as_str()
                     .and_then(|s| s.parse::<i64>().ok())
                     .unwrap_or(0);
+                let coin_type = event.parsed_json["coin_type"].as_str().map(str::to_string);
                 let to_handle = event.parsed_json["to_handle"].as_str().unwrap_or("").to_string();
+                // Empty when the transfer carried no memo
+                let memo = event.parsed_json["memo"].as_str().filter(|m| !m.is_empty()).map(str::to_string);
+                let payload_version = transfer_payload_version(&event.parsed_json);
                 RamEvent {
                     handle: Some(handle.clone()),
                     event_type: "Transferred".to_string(),
                     amount: Some(amount),
+                    coin_type,
+                    result_code: None,
                     from_handle: Some(handle.clone()),
                     to_handle: Some(to_handle),
+                    memo,
+                    payload_version,
                     owner: None,
+                    wallet_id: None,
                     tx_digest: tx_digest.clone(),
                     timestamp,
+                    raw_json: Some(event.parsed_json.clone()),
+                    gas_used: None,
+                    sender: None,
+                    sender_label: None,
+                    from_display_name: None,
+                    to_display_name: None,
+                    refund_of: None,
+                    refunded_in: Vec::new(),
                 }
             }
             "WalletLocked" => {
@@ -254,24 +573,236 @@ impl Indexer {
                     handle: Some(handle.clone()),
                     event_type: "WalletLocked".to_string(),
                     amount: None,
+                    coin_type: None,
+                    result_code: None,
                     from_handle: None,
                     to_handle: None,
+                    memo: None,
+                    payload_version: None,
                     owner: None,
+                    wallet_id: None,
                     tx_digest: tx_digest.clone(),
                     timestamp,
+                    raw_json: Some(event.parsed_json.clone()),
+                    gas_used: None,
+                    sender: None,
+                    sender_label: None,
+                    from_display_name: None,
+                    to_display_name: None,
+                    refund_of: None,
+                    refunded_in: Vec::new(),
                 }
             }
             "BioAuthCompleted" => {
-                let success = event.parsed_json["success"].as_bool().unwrap_or(false);
+                let result_code = event.parsed_json["result"]
+                    .as_i64()
+                    .or_else(|| event.parsed_json["result"].as_str().and_then(|s| s.parse().ok()))
+                    .map(|r| r as i32);
+                let amount = event.parsed_json["amount"]
+                    .as_str()
+                    .and_then(|s| s.parse::<i64>().ok());
+                RamEvent {
+                    handle: Some(handle.clone()),
+                    event_type: if result_code == Some(0) { "BioAuthSuccess" } else { "BioAuthFailed" }.to_string(),
+                    amount,
+                    coin_type: None,
+                    result_code,
+                    from_handle: None,
+                    to_handle: None,
+                    memo: None,
+                    payload_version: None,
+                    owner: None,
+                    wallet_id: None,
+                    tx_digest: tx_digest.clone(),
+                    timestamp,
+                    raw_json: Some(event.parsed_json.clone()),
+                    gas_used: None,
+                    sender: None,
+                    sender_label: None,
+                    from_display_name: None,
+                    to_display_name: None,
+                    refund_of: None,
+                    refunded_in: Vec::new(),
+                }
+            }
+            "DailyLimitSet" => {
+                let daily_limit = event.parsed_json["daily_limit"]
+                    .as_str()
+                    .and_then(|s| s.parse::<i64>().ok())
+                    .unwrap_or(0);
+                let coin_type = event.parsed_json["coin_type"].as_str().map(str::to_string);
                 RamEvent {
                     handle: Some(handle.clone()),
-                    event_type: if success { "BioAuthSuccess" } else { "BioAuthFailed" }.to_string(),
+                    event_type: "DailyLimitSet".to_string(),
+                    amount: Some(daily_limit),
+                    coin_type,
+                    result_code: None,
+                    from_handle: None,
+                    to_handle: None,
+                    memo: None,
+                    payload_version: None,
+                    owner: None,
+                    wallet_id: None,
+                    tx_digest: tx_digest.clone(),
+                    timestamp,
+                    raw_json: Some(event.parsed_json.clone()),
+                    gas_used: None,
+                    sender: None,
+                    sender_label: None,
+                    from_display_name: None,
+                    to_display_name: None,
+                    refund_of: None,
+                    refunded_in: Vec::new(),
+                }
+            }
+            "WalletFrozen" => {
+                RamEvent {
+                    handle: Some(handle.clone()),
+                    event_type: "WalletFrozen".to_string(),
                     amount: None,
+                    coin_type: None,
+                    result_code: None,
                     from_handle: None,
                     to_handle: None,
+                    memo: None,
+                    payload_version: None,
                     owner: None,
+                    wallet_id: None,
                     tx_digest: tx_digest.clone(),
                     timestamp,
+                    raw_json: Some(event.parsed_json.clone()),
+                    gas_used: None,
+                    sender: None,
+                    sender_label: None,
+                    from_display_name: None,
+                    to_display_name: None,
+                    refund_of: None,
+                    refunded_in: Vec::new(),
+                }
+            }
+            "WalletUnfrozen" => {
+                // When the freeze lifts, in raw_json as `unfrozen_at_ms`
+                RamEvent {
+                    handle: Some(handle.clone()),
+                    event_type: "WalletUnfrozen".to_string(),
+                    amount: None,
+                    coin_type: None,
+                    result_code: None,
+                    from_handle: None,
+                    to_handle: None,
+                    memo: None,
+                    payload_version: None,
+                    owner: None,
+                    wallet_id: None,
+                    tx_digest: tx_digest.clone(),
+                    timestamp,
+                    raw_json: Some(event.parsed_json.clone()),
+                    gas_used: None,
+                    sender: None,
+                    sender_label: None,
+                    from_display_name: None,
+                    to_display_name: None,
+                    refund_of: None,
+                    refunded_in: Vec::new(),
+                }
+            }
+            "BeneficiarySet" => {
+                // An empty beneficiary removes it
+                let beneficiary = event.parsed_json["beneficiary_handle"].as_str().unwrap_or("").to_string();
+                let inactivity_timeout_ms = event.parsed_json["inactivity_timeout_ms"]
+                    .as_str()
+                    .and_then(|s| s.parse::<i64>().ok())
+                    .unwrap_or(0);
+                if beneficiary.is_empty() {
+                    Database::remove_beneficiary(&self.pool, &handle).await?;
+                } else {
+                    Database::upsert_beneficiary(
+                        &self.pool,
+                        &handle,
+                        &beneficiary,
+                        inactivity_timeout_ms,
+                        timestamp.timestamp_millis(),
+                    )
+                    .await?;
+                }
+                RamEvent {
+                    handle: Some(handle.clone()),
+                    event_type: "BeneficiarySet".to_string(),
+                    amount: None,
+                    coin_type: None,
+                    result_code: None,
+                    from_handle: None,
+                    to_handle: Some(beneficiary).filter(|b| !b.is_empty()),
+                    memo: None,
+                    payload_version: None,
+                    owner: None,
+                    wallet_id: None,
+                    tx_digest: tx_digest.clone(),
+                    timestamp,
+                    raw_json: Some(event.parsed_json.clone()),
+                    gas_used: None,
+                    sender: None,
+                    sender_label: None,
+                    from_display_name: None,
+                    to_display_name: None,
+                    refund_of: None,
+                    refunded_in: Vec::new(),
+                }
+            }
+            "CheckedIn" => {
+                RamEvent {
+                    handle: Some(handle.clone()),
+                    event_type: "CheckedIn".to_string(),
+                    amount: None,
+                    coin_type: None,
+                    result_code: None,
+                    from_handle: None,
+                    to_handle: None,
+                    memo: None,
+                    payload_version: None,
+                    owner: None,
+                    wallet_id: None,
+                    tx_digest: tx_digest.clone(),
+                    timestamp,
+                    raw_json: Some(event.parsed_json.clone()),
+                    gas_used: None,
+                    sender: None,
+                    sender_label: None,
+                    from_display_name: None,
+                    to_display_name: None,
+                    refund_of: None,
+                    refunded_in: Vec::new(),
+                }
+            }
+            "InheritanceClaimed" => {
+                let amount = event.parsed_json["amount"]
+                    .as_str()
+                    .and_then(|s| s.parse::<i64>().ok())
+                    .unwrap_or(0);
+                let coin_type = event.parsed_json["coin_type"].as_str().map(str::to_string);
+                let beneficiary = event.parsed_json["beneficiary_handle"].as_str().unwrap_or("").to_string();
+                RamEvent {
+                    handle: Some(handle.clone()),
+                    event_type: "InheritanceClaimed".to_string(),
+                    amount: Some(amount),
+                    coin_type,
+                    result_code: None,
+                    from_handle: Some(handle.clone()),
+                    to_handle: Some(beneficiary),
+                    memo: None,
+                    payload_version: None,
+                    owner: None,
+                    wallet_id: None,
+                    tx_digest: tx_digest.clone(),
+                    timestamp,
+                    raw_json: Some(event.parsed_json.clone()),
+                    gas_used: None,
+                    sender: None,
+                    sender_label: None,
+                    from_display_name: None,
+                    to_display_name: None,
+                    refund_of: None,
+                    refunded_in: Vec::new(),
                 }
             }
             _ => {
@@ -280,7 +811,24 @@ impl Indexer {
             }
         };
 
-        Database::insert_event(&self.pool, &ram_event).await?;
+        ram_event.sender = event.sender.clone();
+        if let Some(ctx) = &event.tx_context {
+            ram_event.gas_used = Some(ctx.gas_used);
+            ram_event.sender.get_or_insert_with(|| ctx.sender.clone());
+        }
+
+        let id = Database::insert_event(&self.pool, &ram_event).await?;
+        if let Some(analytics) = &self.analytics {
+            analytics.record(id, &ram_event);
+        }
+        let now_ms = Utc::now().timestamp_millis();
+        refunds::link_refund(&self.pool, &ram_event, now_ms).await?;
+        travel_rule::link_envelope(&self.pool, &ram_event).await?;
+        webhooks::queue_payment(&self.pool, &ram_event, now_ms).await?;
+        receipts::record_receipt(&self.pool, &ram_event, now_ms).await?;
+        if let Some(changes) = &self.changes {
+            changes.publish(&ram_event);
+        }
         info!(
             "Processed {} event for handle {:?}", 
             ram_event.event_type, 
@@ -310,6 +858,14 @@ impl Indexer {
         Ok(result.and_then(|cursor| EventId::from_cursor(&cursor)))
     }
 
+    async fn clear_cursor(&self) -> Result<()> {
+        sqlx::query("DELETE FROM indexer_state WHERE id = 1")
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
     async fn save_cursor(&self, cursor: &EventId) -> Result<()> {
         let cursor_str = cursor.to_cursor();
         
@@ -325,3 +881,28 @@ impl Indexer {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_delay_backs_off_and_caps() {
+        assert_eq!(retry_delay_ms(1), 10_000);
+        assert_eq!(retry_delay_ms(2), 20_000);
+        assert_eq!(retry_delay_ms(4), 80_000);
+        assert_eq!(retry_delay_ms(MAX_RETRY_ATTEMPTS + 20), RETRY_MAX_DELAY_MS);
+    }
+
+    #[test]
+    fn test_transfer_payload_version_reads_both_event_shapes() {
+        let v1 = json!({ "from_handle": "alice", "to_handle": "bob", "amount": "5" });
+        assert_eq!(transfer_payload_version(&v1), None);
+
+        let mut v2 = v1.clone();
+        v2["payload_version"] = 2.into();
+        assert_eq!(transfer_payload_version(&v2), Some(2));
+        v2["payload_version"] = "0".into();
+        assert_eq!(transfer_payload_version(&v2), Some(0));
+    }
+}
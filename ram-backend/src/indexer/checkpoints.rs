@@ -0,0 +1,238 @@
+// Checkpoint-based indexer backend
+//
+// Walks every checkpoint in order and pulls RAM events out of each
+// transaction, instead of paging suix_queryEvents. A checkpoint is only
+// marked done once all of its transactions were fetched, so a fullnode hiccup
+// can't silently skip events, and each event comes with its transaction's
+// sender and gas.
+
+use super::throttle::Batch;
+use super::{Indexer, SuiEvent, TxContext, POLL_INTERVAL};
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tracing::{error, info, warn};
+
+/// Checkpoints requested per `sui_getCheckpoints` page
+const CHECKPOINT_BATCH_SIZE: u64 = 20;
+/// Fullnode limit on digests per `sui_multiGetTransactionBlocks` call
+const TX_BATCH_SIZE: usize = 50;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CheckpointPage {
+    data: Vec<Checkpoint>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Checkpoint {
+    sequence_number: String,
+    timestamp_ms: Option<String>,
+    transactions: Vec<String>,
+}
+
+impl Indexer {
+    /// Index by walking checkpoints, starting after the stored checkpoint
+    ///
+    /// With no stored progress, starts at `start_checkpoint`, or at the
+    /// fullnode's latest checkpoint if that isn't set either.
+    pub async fn run_checkpoints(&self, start_checkpoint: Option<u64>) -> Result<()> {
+        info!("Starting checkpoint indexer for package {}", self.package_id);
+
+        let mut after = match self.load_checkpoint().await? {
+            Some(seq) => Some(seq),
+            None => match start_checkpoint {
+                Some(start) => start.checked_sub(1),
+                None => Some(self.latest_checkpoint().await?),
+            },
+        };
+
+        loop {
+            let caught_up = match self.fetch_and_process_checkpoints(after).await {
+                Ok(Some(last)) => {
+                    self.save_checkpoint(last).await?;
+                    after = Some(last);
+                    false
+                }
+                Ok(None) => true,
+                Err(e) => {
+                    error!("Error processing checkpoints: {}", e);
+                    self.throttle.batch_failed();
+                    true
+                }
+            };
+
+            if let Err(e) = self.retry_failed_events().await {
+                error!("Error retrying failed events: {}", e);
+            }
+
+            if caught_up {
+                tokio::time::sleep(POLL_INTERVAL).await;
+            }
+        }
+    }
+
+    /// Ingest RAM events from the checkpoints after `after` (from genesis if
+    /// `None`), returning the last checkpoint processed
+    pub async fn fetch_and_process_checkpoints(&self, after: Option<u64>) -> Result<Option<u64>> {
+        let cursor = after.map(|seq| json!(seq.to_string())).unwrap_or(Value::Null);
+        let limit = self.throttle.batch_size(CHECKPOINT_BATCH_SIZE);
+        let result = self
+            .rpc_call("sui_getCheckpoints", json!([cursor, limit, false]))
+            .await?;
+        let page: CheckpointPage = serde_json::from_value(result)?;
+
+        let Some(last) = page.data.last() else {
+            self.throttle.finish_batch(Batch { events: 0, position: None, chain_time_ms: None, caught_up: true, size: limit });
+            return Ok(None);
+        };
+        let chain_time_ms = last.timestamp_ms.as_deref().and_then(|t| t.parse().ok());
+        let last = last.sequence_number.parse::<u64>()?;
+
+        let mut processed = 0;
+        for checkpoint in &page.data {
+            for event in self.checkpoint_events(checkpoint).await? {
+                self.throttle.before_write(&self.pool).await;
+                if let Err(e) = self.process_event(&event).await {
+                    warn!("Failed to process event {:?}, queued for retry: {}", event.id, e);
+                    self.record_failure(&event, &e).await?;
+                }
+                processed += 1;
+            }
+        }
+
+        info!(
+            "Processed checkpoints {}..={} ({} RAM events)",
+            page.data[0].sequence_number, last, processed
+        );
+        self.throttle.finish_batch(Batch {
+            events: processed,
+            position: Some(last.to_string()),
+            chain_time_ms,
+            // A short page ends at the fullnode's latest checkpoint
+            caught_up: (page.data.len() as u64) < limit,
+            size: limit,
+        });
+
+        Ok(Some(last))
+    }
+
+    /// RAM events emitted by a checkpoint's transactions, in execution order
+    async fn checkpoint_events(&self, checkpoint: &Checkpoint) -> Result<Vec<SuiEvent>> {
+        let event_prefix = format!("{}::events::", self.package_id);
+        let mut events = Vec::new();
+
+        for digests in checkpoint.transactions.chunks(TX_BATCH_SIZE) {
+            let result = self
+                .rpc_call(
+                    "sui_multiGetTransactionBlocks",
+                    json!([digests, { "showInput": true, "showEffects": true, "showEvents": true }]),
+                )
+                .await?;
+            let txs = result
+                .as_array()
+                .ok_or_else(|| anyhow!("Unexpected sui_multiGetTransactionBlocks response"))?;
+
+            for tx in txs {
+                let ram_events: Vec<&Value> = tx["events"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter(|e| e["type"].as_str().is_some_and(|t| t.starts_with(&event_prefix)))
+                    .collect();
+                if ram_events.is_empty() {
+                    continue;
+                }
+
+                let tx_context = tx_context(tx)?;
+                for raw in ram_events {
+                    let mut event: SuiEvent = serde_json::from_value(raw.clone())?;
+                    // Events inside a transaction block omit the timestamp
+                    event.timestamp_ms = event
+                        .timestamp_ms
+                        .or_else(|| tx["timestampMs"].as_str().map(str::to_string))
+                        .or_else(|| checkpoint.timestamp_ms.clone());
+                    event.tx_context = Some(tx_context.clone());
+                    events.push(event);
+                }
+            }
+        }
+
+        Ok(events)
+    }
+
+    async fn latest_checkpoint(&self) -> Result<u64> {
+        let result = self.rpc_call("sui_getLatestCheckpointSequenceNumber", json!([])).await?;
+        result
+            .as_str()
+            .and_then(|s| s.parse().ok())
+            .ok_or_else(|| anyhow!("Unexpected latest checkpoint: {}", result))
+    }
+
+    async fn load_checkpoint(&self) -> Result<Option<u64>> {
+        let result = sqlx::query_scalar::<_, i64>(
+            "SELECT last_checkpoint FROM checkpoint_state WHERE id = 1"
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(result.map(|seq| seq as u64))
+    }
+
+    async fn save_checkpoint(&self, seq: u64) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO checkpoint_state (id, last_checkpoint, updated_at)
+             VALUES (1, $1, NOW())
+             ON CONFLICT (id) DO UPDATE SET last_checkpoint = $1, updated_at = NOW()"
+        )
+        .bind(seq as i64)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// Sender and net gas of a transaction block fetched with input and effects
+fn tx_context(tx: &Value) -> Result<TxContext> {
+    let sender = tx["transaction"]["data"]["sender"]
+        .as_str()
+        .ok_or_else(|| anyhow!("Transaction {} has no sender", tx["digest"]))?
+        .to_string();
+
+    let gas = &tx["effects"]["gasUsed"];
+    let cost = |field: &str| -> Result<i64> {
+        gas[field]
+            .as_str()
+            .and_then(|v| v.parse().ok())
+            .ok_or_else(|| anyhow!("Transaction {} has no {}", tx["digest"], field))
+    };
+
+    Ok(TxContext {
+        sender,
+        gas_used: cost("computationCost")? + cost("storageCost")? - cost("storageRebate")?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tx_context_nets_storage_rebate() {
+        let tx = json!({
+            "digest": "8xQm",
+            "transaction": { "data": { "sender": "0xa11ce" } },
+            "effects": { "gasUsed": {
+                "computationCost": "1000000",
+                "storageCost": "2964000",
+                "storageRebate": "978120",
+            } },
+        });
+
+        let ctx = tx_context(&tx).unwrap();
+        assert_eq!(ctx.sender, "0xa11ce");
+        assert_eq!(ctx.gas_used, 2_985_880);
+    }
+}
@@ -0,0 +1,315 @@
+// Catch-up throttling and progress reporting
+//
+// During a backfill (a fresh deployment, or an indexer that was down for a
+// while) pages used to be fetched back to back, competing with the API for the
+// fullnodes and the primary pool. While catching up, three brakes apply:
+//
+// - RPC calls are spaced to INDEXER_RPC_RATE_LIMIT per second
+// - before each event is written, the indexer waits (up to a second) while
+//   INDEXER_DB_HIGH_WATER_PERCENT of the pool's connections or more are in
+//   use, leaving them to API requests
+// - pages shrink by half after a batch that met a failing or slow RPC call or
+//   a busy pool, and grow back by a tenth of the maximum after a healthy one
+//
+// Progress is served at GET /admin/indexer_progress. The completion estimate
+// compares how fast chain time is being covered with wall time over the last
+// minute, so it takes a few batches to settle and is rough when events are
+// sparse.
+
+use crate::config::IndexerThrottleConfig;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Smallest share of the maximum page size a page shrinks to
+const MIN_BATCH_SCALE: f64 = 0.1;
+/// Share of the maximum page size regained after each healthy batch
+const BATCH_SCALE_STEP: f64 = 0.1;
+/// An RPC call slower than this counts as backpressure
+const SLOW_RPC_CALL: Duration = Duration::from_secs(2);
+/// How long a write waits between two looks at a busy pool
+const WRITE_PAUSE_STEP: Duration = Duration::from_millis(25);
+/// Longest a single write waits for the pool
+const MAX_WRITE_PAUSE: Duration = Duration::from_secs(1);
+/// Window events per second and the completion estimate are computed over
+const PROGRESS_WINDOW: Duration = Duration::from_secs(60);
+
+/// What one processed batch tells the throttle
+pub(super) struct Batch {
+    /// Events fetched in the batch
+    pub(super) events: u64,
+    /// Cursor or checkpoint indexed up to, if the batch moved it
+    pub(super) position: Option<String>,
+    /// Chain timestamp of the last item indexed, if known
+    pub(super) chain_time_ms: Option<i64>,
+    /// Whether the batch reached the chain's tip
+    pub(super) caught_up: bool,
+    /// Page size the batch asked for
+    pub(super) size: u64,
+}
+
+/// Totals after a batch, as the progress window keeps them
+struct Sample {
+    at: Instant,
+    events_indexed: u64,
+    chain_time_ms: Option<i64>,
+}
+
+#[derive(Default)]
+struct Progress {
+    samples: VecDeque<Sample>,
+    events_indexed: u64,
+    position: Option<String>,
+    chain_time_ms: Option<i64>,
+    caught_up: bool,
+    batch_size: u64,
+}
+
+/// Where the indexer is and how fast it's going, as served by `/admin/indexer_progress`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexerProgress {
+    /// Whether the last batch reached the chain's tip
+    pub caught_up: bool,
+    /// Event cursor or checkpoint indexed up to
+    pub position: Option<String>,
+    /// Events indexed since the backend started
+    pub events_indexed: u64,
+    /// Over the last minute
+    pub events_per_sec: f64,
+    /// How far the last indexed item is behind the chain
+    pub lag_ms: Option<i64>,
+    /// When the indexer should catch up at its current pace (None once caught
+    /// up, or while it can't tell)
+    pub estimated_completion_ms: Option<i64>,
+    /// Page size of the last batch
+    pub batch_size: u64,
+    /// RPC calls per second the indexer is held to (None = unlimited)
+    pub rpc_calls_per_sec: Option<u32>,
+    /// Share of the primary pool's connections in use (0-1)
+    pub db_pool_utilization: f64,
+    /// Writes that waited for the pool since the backend started
+    pub write_pauses: u64,
+}
+
+/// Backpressure state of the indexer, shared with the admin API
+pub struct Throttle {
+    config: IndexerThrottleConfig,
+    /// Share (0.1-1) of the maximum page size the next batch asks for
+    batch_scale: Mutex<f64>,
+    /// Whether the current batch met backpressure
+    pressured: AtomicBool,
+    /// Earliest time the next RPC call may start
+    next_rpc_call: tokio::sync::Mutex<Instant>,
+    write_pauses: AtomicU64,
+    progress: Mutex<Progress>,
+}
+
+impl Default for Throttle {
+    fn default() -> Self {
+        Self::new(IndexerThrottleConfig::default())
+    }
+}
+
+/// Share of `pool`'s connections in use (0-1)
+fn pool_utilization(pool: &PgPool) -> f64 {
+    let max = pool.options().get_max_connections();
+    if max == 0 {
+        return 0.0;
+    }
+    let busy = (pool.size() as usize).saturating_sub(pool.num_idle());
+    busy as f64 / f64::from(max)
+}
+
+impl Throttle {
+    pub fn new(config: IndexerThrottleConfig) -> Self {
+        Self {
+            config,
+            batch_scale: Mutex::new(1.0),
+            pressured: AtomicBool::new(false),
+            next_rpc_call: tokio::sync::Mutex::new(Instant::now()),
+            write_pauses: AtomicU64::new(0),
+            progress: Mutex::new(Progress::default()),
+        }
+    }
+
+    /// Page size for the next batch, out of at most `max`
+    pub(super) fn batch_size(&self, max: u64) -> u64 {
+        let scale = *self.batch_scale.lock().unwrap();
+        ((max as f64 * scale).ceil() as u64).clamp(1, max)
+    }
+
+    /// Wait for the next RPC call slot
+    pub(super) async fn before_rpc_call(&self) {
+        let Some(rate) = self.config.rpc_calls_per_sec else {
+            return;
+        };
+        let mut next = self.next_rpc_call.lock().await;
+        tokio::time::sleep_until((*next).into()).await;
+        *next = Instant::now() + Duration::from_secs(1) / rate;
+    }
+
+    /// Note how an RPC call went; a failing or slow one shrinks the next page
+    pub(super) fn rpc_call_finished(&self, elapsed: Duration, succeeded: bool) {
+        if !succeeded || elapsed > SLOW_RPC_CALL {
+            self.pressured.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Wait (up to `MAX_WRITE_PAUSE`) while `pool` is above the high-water mark
+    pub(super) async fn before_write(&self, pool: &PgPool) {
+        let high_water = f64::from(self.config.db_high_water_percent) / 100.0;
+        let mut waited = Duration::ZERO;
+        while waited < MAX_WRITE_PAUSE && pool_utilization(pool) >= high_water {
+            tokio::time::sleep(WRITE_PAUSE_STEP).await;
+            waited += WRITE_PAUSE_STEP;
+        }
+        if !waited.is_zero() {
+            self.pressured.store(true, Ordering::Relaxed);
+            self.write_pauses.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Size the next page after `batch` and add it to the progress
+    pub(super) fn finish_batch(&self, batch: Batch) {
+        self.adjust_batch_scale();
+        self.record(batch, Instant::now());
+    }
+
+    /// Shrink the next page after a batch that failed
+    pub(super) fn batch_failed(&self) {
+        self.pressured.store(true, Ordering::Relaxed);
+        self.adjust_batch_scale();
+    }
+
+    /// Whether the last batch reached the chain's tip
+    pub(super) fn is_caught_up(&self) -> bool {
+        self.progress.lock().unwrap().caught_up
+    }
+
+    /// Progress as of now
+    pub fn report(&self, pool: &PgPool, now_ms: i64) -> IndexerProgress {
+        self.report_at(Instant::now(), now_ms, pool_utilization(pool))
+    }
+
+    fn adjust_batch_scale(&self) {
+        let mut scale = self.batch_scale.lock().unwrap();
+        *scale = if self.pressured.swap(false, Ordering::Relaxed) {
+            (*scale / 2.0).max(MIN_BATCH_SCALE)
+        } else {
+            (*scale + BATCH_SCALE_STEP).min(1.0)
+        };
+    }
+
+    fn record(&self, batch: Batch, at: Instant) {
+        let mut progress = self.progress.lock().unwrap();
+        progress.events_indexed += batch.events;
+        progress.position = batch.position.or(progress.position.take());
+        progress.chain_time_ms = batch.chain_time_ms.or(progress.chain_time_ms);
+        progress.caught_up = batch.caught_up;
+        progress.batch_size = batch.size;
+
+        let sample = Sample { at, events_indexed: progress.events_indexed, chain_time_ms: progress.chain_time_ms };
+        progress.samples.push_back(sample);
+        while progress.samples.front().is_some_and(|s| at.duration_since(s.at) > PROGRESS_WINDOW) {
+            progress.samples.pop_front();
+        }
+    }
+
+    fn report_at(&self, now: Instant, now_ms: i64, db_pool_utilization: f64) -> IndexerProgress {
+        let progress = self.progress.lock().unwrap();
+        let lag_ms = progress.chain_time_ms.map(|t| now_ms.saturating_sub(t).max(0));
+
+        let (mut events_per_sec, mut estimated_completion_ms) = (0.0, None);
+        if let (Some(first), Some(last)) = (progress.samples.front(), progress.samples.back()) {
+            let elapsed_ms = last.at.duration_since(first.at).as_millis() as f64;
+            // Stale once the indexer stops making batches
+            let recent = now.duration_since(last.at) <= PROGRESS_WINDOW;
+            if elapsed_ms > 0.0 && recent {
+                events_per_sec = (last.events_indexed - first.events_indexed) as f64 * 1000.0 / elapsed_ms;
+                // Chain time covered per unit of wall time; above 1 the lag shrinks
+                let chain_speed = match (first.chain_time_ms, last.chain_time_ms) {
+                    (Some(from), Some(to)) => (to - from) as f64 / elapsed_ms,
+                    _ => 0.0,
+                };
+                if !progress.caught_up && chain_speed > 1.0 {
+                    estimated_completion_ms =
+                        lag_ms.map(|lag| now_ms + (lag as f64 / (chain_speed - 1.0)).round() as i64);
+                }
+            }
+        }
+
+        IndexerProgress {
+            caught_up: progress.caught_up,
+            position: progress.position.clone(),
+            events_indexed: progress.events_indexed,
+            events_per_sec,
+            lag_ms,
+            estimated_completion_ms,
+            batch_size: progress.batch_size,
+            rpc_calls_per_sec: self.config.rpc_calls_per_sec,
+            db_pool_utilization,
+            write_pauses: self.write_pauses.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pages_halve_under_pressure_and_grow_back() {
+        let throttle = Throttle::default();
+        assert_eq!(throttle.batch_size(50), 50);
+
+        throttle.rpc_call_finished(Duration::from_millis(100), false);
+        throttle.adjust_batch_scale();
+        assert_eq!(throttle.batch_size(50), 25);
+        throttle.rpc_call_finished(Duration::from_secs(3), true);
+        throttle.adjust_batch_scale();
+        assert_eq!(throttle.batch_size(50), 13);
+
+        // A healthy batch regains a tenth of the maximum
+        throttle.rpc_call_finished(Duration::from_millis(100), true);
+        throttle.adjust_batch_scale();
+        assert_eq!(throttle.batch_size(50), 18);
+
+        for _ in 0..10 {
+            throttle.batch_failed();
+        }
+        assert_eq!(throttle.batch_size(50), 5);
+        assert_eq!(throttle.batch_size(5), 1);
+    }
+
+    #[test]
+    fn test_completion_is_estimated_from_chain_time_covered() {
+        let throttle = Throttle::default();
+        let start = Instant::now();
+        let batch = |events, chain_time_ms| Batch {
+            events,
+            position: Some("cursor".to_string()),
+            chain_time_ms: Some(chain_time_ms),
+            caught_up: false,
+            size: 50,
+        };
+        throttle.record(batch(0, 1_000_000), start);
+        // 10s of indexing covered 100s of chain time
+        throttle.record(batch(500, 1_100_000), start + Duration::from_secs(10));
+
+        let now_ms = 2_000_000;
+        let progress = throttle.report_at(start + Duration::from_secs(10), now_ms, 0.5);
+        assert_eq!(progress.events_indexed, 500);
+        assert_eq!(progress.events_per_sec, 50.0);
+        assert_eq!(progress.lag_ms, Some(900_000));
+        // Gaining 9s on the chain every second
+        assert_eq!(progress.estimated_completion_ms, Some(now_ms + 100_000));
+
+        throttle.record(Batch { caught_up: true, ..batch(0, 1_100_000) }, start + Duration::from_secs(11));
+        let progress = throttle.report_at(start + Duration::from_secs(11), now_ms, 0.5);
+        assert!(progress.caught_up);
+        assert_eq!(progress.estimated_completion_ms, None);
+    }
+}
@@ -0,0 +1,313 @@
+// RAM Backend library
+// Shared state and router so the server can also be spun up in-process by tests
+
+pub mod admin;
+pub mod aliases;
+pub mod analytics;
+pub mod api_keys;
+pub mod attestation;
+pub mod audit;
+pub mod changes;
+pub mod chaos;
+pub mod config;
+pub mod contacts;
+pub mod database;
+pub mod errors;
+pub mod explorer;
+pub mod exports;
+pub mod flags;
+pub mod forwarding;
+pub mod gas;
+pub mod handles;
+pub mod inactivity;
+pub mod indexer;
+pub mod logging;
+pub mod models;
+pub mod notifications;
+pub mod onboarding;
+pub mod portfolio;
+pub mod preview;
+pub mod price_alerts;
+pub mod prices;
+pub mod profile;
+pub mod proxy;
+pub mod receipts;
+pub mod refunds;
+pub mod registration;
+pub mod reservations;
+pub mod retention;
+pub mod routing;
+pub mod rpc;
+pub mod signing;
+pub mod sponsor;
+pub mod supervisor;
+pub mod travel_rule;
+pub mod versioning;
+pub mod wallet_auth;
+pub mod webhooks;
+
+use axum::{
+    http::{header, StatusCode},
+    middleware,
+    routing::{any, delete, get, post},
+    Router,
+};
+use database::DbPool;
+use rpc::SuiRpcClient;
+use std::sync::Arc;
+use std::time::Duration;
+use tower_http::cors::{Any, CorsLayer};
+use tower_http::timeout::TimeoutLayer;
+
+/// Application state shared across handlers
+#[derive(Clone)]
+pub struct AppState {
+    pub db: DbPool,
+    /// Read-only pool for heavy API reads (the primary pool if no replica is configured)
+    pub read_db: DbPool,
+    /// Enclaves proxied and backend-initiated requests are routed across
+    pub enclaves: Arc<routing::EnclaveRouter>,
+    /// Sui fullnodes for live on-chain reads (balances), shared with the indexer
+    pub sui_rpc: Arc<SuiRpcClient>,
+    /// Bearer token for /admin routes (admin API disabled if unset)
+    pub admin_token: Option<String>,
+    /// Shared secret for the enclave's privileged diagnostics channel
+    pub nautilus_diagnostics_secret: Option<String>,
+    /// HMAC key signing every request to the enclave (unsigned if unset)
+    pub nautilus_signing_key: Option<String>,
+    /// Inbound request headers passed on to the enclave
+    pub forward_headers: forwarding::HeaderPolicy,
+    /// Per-route-class timeouts and GET retries
+    pub proxy: config::ProxyConfig,
+    /// Paths forwarded to a differently named enclave route, editable at runtime
+    pub route_aliases: Arc<aliases::RouteAliases>,
+    /// Gas sponsor for onboarding (`/api/onboard` disabled if unset)
+    pub sponsor: Option<sponsor::Sponsor>,
+    /// Network and RAM package the backend works against
+    pub chain: config::ChainConfig,
+    /// Handles touched by newly indexed events, published by the indexer
+    pub changes: Arc<changes::ChangeFeed>,
+    /// Portfolio reads, dropped when the handle changes
+    pub read_cache: Arc<changes::ReadCache>,
+    /// Indexer and periodic jobs, restarted when they fail
+    pub supervisor: Arc<supervisor::Supervisor>,
+    /// Transfer amounts that require a travel-rule envelope
+    pub travel_rule: config::TravelRuleConfig,
+    /// How long `/api/handles/reserve` holds a handle
+    pub handle_reservation_ttl: Duration,
+    /// Public explorer settings and its per-client rate limit
+    pub explorer: Arc<explorer::Explorer>,
+    /// Feature flags gating risky features, editable at runtime
+    pub feature_flags: Arc<flags::FeatureFlags>,
+    /// Countersigns transfer receipts (`/api/receipts` disabled if unset)
+    pub receipts: Option<receipts::ReceiptSigner>,
+    /// Catch-up pacing and progress of the indexer
+    pub indexer_throttle: Arc<indexer::Throttle>,
+    /// Parquet copy of indexed events the explorer aggregates from (None = Postgres)
+    pub analytics: Option<Arc<analytics::AnalyticsStore>>,
+    /// USD prices for balance alerts (`/api/price_alerts` can't save alerts if unset)
+    pub prices: Option<Arc<prices::PriceService>>,
+    /// Margin on gas budgets recommended from dry runs
+    pub gas: config::GasConfig,
+    /// Per-client limit on `/api/tx/estimate`, apart from the explorer's
+    pub gas_estimates: Arc<explorer::RateLimiter>,
+    /// Objects enclave registration touches (`/admin/register_enclave` disabled if unset)
+    pub enclave_registration: Option<config::EnclaveRegistrationConfig>,
+    /// Merchant webhook delivery, and which URLs can be registered
+    pub webhooks: config::WebhookConfig,
+}
+
+/// Build the backend router with all backend-specific and proxied routes
+///
+/// Routes are served under /v1; the same routes at their original flat paths
+/// keep working with deprecation headers until the legacy sunset.
+pub fn build_router(state: Arc<AppState>) -> Router {
+    // Setup CORS; browsers only show clients the version headers if exposed
+    let cors = CorsLayer::new()
+        .allow_origin(Any)
+        .allow_methods(Any)
+        .allow_headers(Any)
+        .expose_headers([
+            versioning::API_VERSION_HEADER,
+            versioning::DEPRECATION_HEADER,
+            versioning::SUNSET_HEADER,
+            routing::ENCLAVE_KEY_HEADER,
+            errors::REQUEST_ID_HEADER,
+            header::LINK,
+        ]);
+
+    // Each route class gets its own deadline, answered with 504 once it passes;
+    // for streamed responses it covers the wait for response headers
+    let limits = &state.proxy;
+    let timeout = |duration| TimeoutLayer::with_status_code(StatusCode::GATEWAY_TIMEOUT, duration);
+
+    let health = Router::new()
+        .route("/health", get(proxy::health_check))
+        .route("/meta/chain", get(proxy::chain_info))
+        .route_layer(timeout(limits.metadata_timeout));
+
+    // Proxied Nautilus routes, logged with PII redaction
+    let metadata = Router::new()
+        .route("/health_check", get(proxy::proxy_to_nautilus))
+        .route("/get_attestation", get(proxy::proxy_to_nautilus))
+        .route("/meta/intents", get(proxy::proxy_to_nautilus))
+        .route("/create_wallet/gate", get(proxy::proxy_to_nautilus))
+        .route("/bio_auth/queue", get(proxy::proxy_to_nautilus))
+        .route("/bio_auth/shadow", get(proxy::proxy_to_nautilus))
+        .route("/bio_auth/:job_id", get(proxy::proxy_to_nautilus))
+        .route_layer(timeout(limits.metadata_timeout));
+    let bio_auth = Router::new()
+        .route("/bio_auth", post(proxy::proxy_to_nautilus))
+        .route("/bio_auth/stream", post(proxy::proxy_to_nautilus))
+        .route("/typed_auth", post(proxy::proxy_to_nautilus))
+        .route("/bio_auth/enroll", post(proxy::proxy_to_nautilus))
+        .route("/set_limit", post(proxy::proxy_to_nautilus))
+        .route("/request_unfreeze", post(proxy::proxy_to_nautilus))
+        .route_layer(timeout(limits.bio_auth_timeout));
+    let proxied = Router::new()
+        .route("/create_wallet", post(proxy::proxy_to_nautilus))
+        .route("/link_address", post(proxy::proxy_to_nautilus))
+        .route("/link_zklogin", post(proxy::proxy_to_nautilus))
+        .route("/unlink_address", post(proxy::proxy_to_nautilus))
+        .route("/bio_auth/challenge", post(proxy::proxy_to_nautilus))
+        .route(
+            "/bio_auth/challenge_audio/:challenge_id",
+            get(proxy::proxy_to_nautilus),
+        )
+        .route("/transfer", post(proxy::proxy_to_nautilus))
+        .route("/withdraw", post(proxy::proxy_to_nautilus))
+        .route("/freeze_wallet", post(proxy::proxy_to_nautilus))
+        .route("/unfreeze/challenge", post(proxy::proxy_to_nautilus))
+        .route("/set_beneficiary", post(proxy::proxy_to_nautilus))
+        .route("/prove_deposit", post(proxy::proxy_to_nautilus))
+        .route("/preferences", post(proxy::proxy_to_nautilus))
+        .route_layer(timeout(limits.default_timeout))
+        .merge(metadata)
+        .merge(bio_auth)
+        // Any other path may be a route alias, each with its own deadline
+        .route("/*alias", any(proxy::proxy_alias))
+        .route_layer(middleware::from_fn(logging::log_proxied_requests));
+
+    // Public stats, unauthenticated and rate-limited per client
+    let public = Router::new()
+        .route("/api/explorer/transfers", get(explorer::get_transfer_volumes))
+        .route("/api/explorer/wallets", get(explorer::get_wallet_creations))
+        .route_layer(middleware::from_fn_with_state(state.clone(), explorer::rate_limit))
+        .route_layer(timeout(limits.default_timeout));
+
+    // Gas estimates for any transaction, rate-limited per client on their own
+    let estimates = Router::new()
+        .route("/api/tx/estimate", post(gas::estimate_transaction))
+        .route_layer(middleware::from_fn_with_state(state.clone(), gas::rate_limit))
+        .route_layer(timeout(limits.default_timeout));
+
+    // Submission also waits for the indexer to see the new wallet
+    let wallet_timeout = state.sponsor.as_ref().map_or(Duration::ZERO, |s| s.config.wallet_timeout);
+    let onboard = Router::new()
+        .route("/api/onboard", post(onboarding::start_onboarding))
+        .route(
+            "/api/onboard/:id",
+            get(onboarding::get_onboarding).post(onboarding::submit_onboarding),
+        )
+        .route_layer(timeout(limits.default_timeout + wallet_timeout));
+
+    let api = Router::new()
+        // Backend-specific endpoints
+        .route("/api/events", post(proxy::get_wallet_events))
+        .route("/api/stats", post(proxy::get_wallet_stats))
+        .route("/api/addresses", post(proxy::get_linked_addresses))
+        .route("/api/portfolio/:handle", get(portfolio::get_portfolio))
+        .route("/api/transfer/preview", post(preview::preview_transfer))
+        .route("/api/changes/:handle", get(changes::watch_handle))
+        .route("/api/receive/:handle", get(profile::get_receive_info))
+        .route("/api/handles/reserve", post(reservations::reserve_handle))
+        .route("/api/flags", get(flags::list_flags))
+        .route("/api/receipts/public_key", get(receipts::get_receipt_key))
+        .route("/api/receipts/:tx_digest", get(receipts::get_receipt))
+        .route(
+            "/api/contacts",
+            get(contacts::list_contacts)
+                .post(contacts::save_contact)
+                .delete(contacts::delete_contact),
+        )
+        .route("/api/notifications", get(notifications::list_notifications))
+        .route(
+            "/api/price_alerts",
+            get(price_alerts::list_price_alerts)
+                .post(price_alerts::create_price_alert)
+                .put(price_alerts::update_price_alert)
+                .delete(price_alerts::delete_price_alert),
+        )
+        .route("/api/keys", get(api_keys::list_api_keys).post(api_keys::create_api_key))
+        .route("/api/keys/:id", delete(api_keys::revoke_api_key))
+        .route("/api/keys/:id/rotate", post(api_keys::rotate_api_key))
+        .route("/api/refunds", get(refunds::list_refunds).post(refunds::create_refund))
+        .route("/api/refunds/:id/cancel", post(refunds::cancel_refund))
+        .route("/api/travel_rule/:hash", get(travel_rule::get_envelope))
+        .route(
+            "/api/webhook",
+            get(webhooks::get_webhook)
+                .put(webhooks::save_webhook)
+                .delete(webhooks::delete_webhook),
+        )
+        .route(
+            "/api/profile/:handle",
+            get(profile::get_profile)
+                .put(profile::update_profile)
+                .delete(profile::delete_profile),
+        )
+        // Support search across all wallets (requires ADMIN_API_TOKEN)
+        .route("/api/search", get(admin::search))
+        // Bio-auth attempts for a wallet (requires ADMIN_API_TOKEN)
+        .route("/api/bioauth_history/:handle", get(admin::bioauth_history))
+        // Admin endpoints (require ADMIN_API_TOKEN)
+        .route(
+            "/admin/bioauth/:request_id",
+            get(admin::get_bioauth_diagnostics),
+        )
+        .route(
+            "/admin/voice_baseline/:handle",
+            delete(admin::reset_voice_baseline),
+        )
+        .route("/admin/db_metrics", get(admin::get_db_metrics))
+        .route("/admin/rpc_metrics", get(admin::get_rpc_metrics))
+        .route("/admin/indexer_progress", get(admin::get_indexer_progress))
+        .route("/admin/analytics_consistency", get(admin::check_analytics_store))
+        .route("/admin/failed_events", get(admin::list_failed_events))
+        .route("/admin/indexer_gaps", get(admin::list_indexer_gaps))
+        .route("/admin/audit", get(admin::list_admin_audit))
+        .route(
+            "/admin/routes",
+            get(admin::list_route_aliases)
+                .put(admin::save_route_alias)
+                .delete(admin::delete_route_alias),
+        )
+        .route(
+            "/admin/flags",
+            get(admin::list_feature_flags)
+                .put(admin::save_feature_flag)
+                .delete(admin::delete_feature_flag),
+        )
+        .route(
+            "/admin/failed_events/:id/requeue",
+            post(admin::requeue_failed_event),
+        )
+        .route("/admin/register_enclave", post(registration::register_enclave))
+        .route_layer(timeout(limits.default_timeout))
+        .merge(health)
+        .merge(public)
+        .merge(estimates)
+        .merge(onboard)
+        // Proxy all Nautilus endpoints
+        .merge(proxied);
+
+    Router::new()
+        .nest(
+            versioning::CURRENT_API_PREFIX,
+            api.clone().layer(middleware::from_fn(versioning::negotiate_version)),
+        )
+        .merge(api.layer(middleware::from_fn(versioning::deprecate_legacy_routes)))
+        .with_state(state)
+        .layer(middleware::from_fn(errors::envelope_errors))
+        .layer(cors)
+}
@@ -0,0 +1,158 @@
+// Request/response logging for proxied Nautilus routes
+// Records method, path, status, latency and handle; bodies are only logged at
+// debug level and always with audio, signatures and panic phrases redacted.
+// Responses are only buffered for that debug line, and only when their size is
+// known, so event streams, chunked responses and their trailers pass through
+// untouched; latency is time to response headers.
+
+use axum::{
+    body::{to_bytes, Body, Bytes},
+    extract::Request,
+    http::header,
+    middleware::Next,
+    response::Response,
+};
+use serde_json::Value;
+use std::time::Instant;
+use tracing::{debug, info, warn, Level};
+
+/// Fields never written to logs
+const REDACTED_FIELDS: &[&str] = &["audio_base64", "signature", "wallet_signature", "zklogin_signature", "panic_phrase"];
+
+/// Largest body the middleware will buffer for logging (proxied audio included)
+const MAX_LOGGED_BODY_BYTES: usize = 32 * 1024 * 1024;
+
+/// Replace sensitive fields anywhere in a JSON value with a placeholder
+pub fn redact(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, v) in map.iter_mut() {
+                if REDACTED_FIELDS.contains(&key.as_str()) {
+                    *v = Value::String("[REDACTED]".to_string());
+                } else {
+                    redact(v);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact),
+        _ => {}
+    }
+}
+
+/// Handle the request acts on, from `payload.handle` / `payload.from_handle` or a top-level `handle`
+pub fn extract_handle(body: &Value) -> Option<String> {
+    let payload = body.get("payload").unwrap_or(body);
+    ["handle", "from_handle"]
+        .iter()
+        .find_map(|key| payload.get(*key).and_then(Value::as_str))
+        .map(str::to_string)
+}
+
+/// Render a body for debug logs: redacted JSON, or just its size otherwise
+fn describe_body(bytes: &Bytes) -> String {
+    match serde_json::from_slice::<Value>(bytes) {
+        Ok(mut json) => {
+            redact(&mut json);
+            json.to_string()
+        }
+        Err(_) => format!("<{} bytes>", bytes.len()),
+    }
+}
+
+/// Middleware logging one line per proxied request
+pub async fn log_proxied_requests(req: Request, next: Next) -> Result<Response, Response> {
+    let start = Instant::now();
+    let method = req.method().clone();
+    let path = req.uri().path().to_string();
+
+    let (parts, body) = req.into_parts();
+    let req_bytes = buffer(body).await?;
+    let handle = serde_json::from_slice::<Value>(&req_bytes)
+        .ok()
+        .and_then(|v| extract_handle(&v))
+        .unwrap_or_else(|| "-".to_string());
+    debug!("{} {} request body: {}", method, path, describe_body(&req_bytes));
+
+    let response = next
+        .run(Request::from_parts(parts, Body::from(req_bytes)))
+        .await;
+
+    let (parts, body) = response.into_parts();
+    let sized = parts
+        .headers
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok()?.parse::<usize>().ok())
+        .is_some_and(|len| len <= MAX_LOGGED_BODY_BYTES);
+    let body = if sized && tracing::enabled!(Level::DEBUG) {
+        let resp_bytes = buffer(body).await?;
+        debug!("{} {} response body: {}", method, path, describe_body(&resp_bytes));
+        Body::from(resp_bytes)
+    } else {
+        body
+    };
+    let status = parts.status.as_u16();
+    let latency_ms = start.elapsed().as_millis();
+
+    if parts.status.is_server_error() {
+        warn!(%method, %path, status, latency_ms, %handle, "proxied request failed");
+    } else {
+        info!(%method, %path, status, latency_ms, %handle, "proxied request");
+    }
+
+    Ok(Response::from_parts(parts, body))
+}
+
+async fn buffer(body: Body) -> Result<Bytes, Response> {
+    to_bytes(body, MAX_LOGGED_BODY_BYTES).await.map_err(|e| {
+        warn!("Failed to buffer body for logging: {}", e);
+        Response::builder()
+            .status(axum::http::StatusCode::PAYLOAD_TOO_LARGE)
+            .body(Body::empty())
+            .unwrap()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_redacts_nested_sensitive_fields() {
+        let mut body = json!({
+            "payload": { "handle": "alice", "audio_base64": "UklGRg==", "wallet_signature": "abc" },
+            "signature": "deadbeef",
+            "items": [{ "signature": "x" }],
+        });
+        redact(&mut body);
+        assert_eq!(body["payload"]["handle"], "alice");
+        assert_eq!(body["payload"]["audio_base64"], "[REDACTED]");
+        assert_eq!(body["payload"]["wallet_signature"], "[REDACTED]");
+        assert_eq!(body["signature"], "[REDACTED]");
+        assert_eq!(body["items"][0]["signature"], "[REDACTED]");
+    }
+
+    #[test]
+    fn test_redacts_enrolled_panic_phrase() {
+        // `/bio_auth/enroll` bodies carry the duress phrase alongside the clips
+        let body = Bytes::from(
+            json!({ "payload": { "handle": "alice", "panic_phrase": "the tulips are early" } }).to_string(),
+        );
+        let logged = describe_body(&body);
+        assert!(!logged.contains("tulips"));
+        assert!(logged.contains(r#""panic_phrase":"[REDACTED]""#));
+    }
+
+    #[test]
+    fn test_extracts_handle() {
+        assert_eq!(extract_handle(&json!({ "payload": { "handle": "alice" } })).as_deref(), Some("alice"));
+        assert_eq!(extract_handle(&json!({ "payload": { "from_handle": "bob" } })).as_deref(), Some("bob"));
+        assert_eq!(extract_handle(&json!({ "handle": "carol" })).as_deref(), Some("carol"));
+        assert_eq!(extract_handle(&json!({})), None);
+    }
+
+    #[test]
+    fn test_non_json_body_logs_size_only() {
+        assert_eq!(describe_body(&Bytes::from_static(b"RIFF....")), "<8 bytes>");
+    }
+}
@@ -1,27 +1,22 @@
 // RAM Backend Server
 // Proxy layer between frontend and Nautilus server + Event indexer
 
-mod database;
-mod indexer;
-mod models;
-mod proxy;
-
 use anyhow::Result;
-use axum::{
-    routing::{get, post},
-    Router,
+use ram_backend::{
+    aliases::RouteAliases, analytics::{self, AnalyticsStore}, build_router, changes::{ChangeFeed, ReadCache}, config::{ChainConfig, Config, IndexerSource},
+    database, explorer::{Explorer, RateLimiter}, exports, flags::FeatureFlags, inactivity, indexer, price_alerts, prices::PriceService, receipts::ReceiptSigner, retention, routing::EnclaveRouter, rpc::SuiRpcClient, sponsor::Sponsor, supervisor::Supervisor,
+    webhooks,
+    AppState,
 };
-use database::DbPool;
+use serde_json::json;
 use std::sync::Arc;
-use tower_http::cors::{Any, CorsLayer};
-use tracing::info;
-
-/// Application state shared across handlers
-#[derive(Clone)]
-pub struct AppState {
-    pub db: DbPool,
-    pub nautilus_url: String,
-}
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// How often unhealthy Sui RPC endpoints are re-probed
+const RPC_HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+/// How often every enclave's /health_check is probed
+const ENCLAVE_HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(10);
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -42,87 +37,256 @@ async fn main() -> Result<()> {
     info!("Starting RAM Backend Server");
 
     // Load configuration
-    let database_url =
-        std::env::var("DATABASE_URL").unwrap_or_else(|_| "sqlite:ram.db".to_string());
-    let nautilus_url =
-        std::env::var("NAUTILUS_URL").unwrap_or_else(|_| "http://localhost:3000".to_string());
-    let sui_rpc_url =
-        std::env::var("SUI_RPC_URL").expect("SUI_RPC_URL must be set in environment");
-    let package_id = std::env::var("RAM_PACKAGE_ID").expect("RAM_PACKAGE_ID must be set");
-    let server_port = std::env::var("PORT")
-        .unwrap_or_else(|_| "4000".to_string())
-        .parse::<u16>()?;
+    let config = Config::from_env()?;
 
     info!("Configuration:");
-    info!("  Database: {}", database_url);
-    info!("  Nautilus Server: {}", nautilus_url);
-    info!("  Sui RPC: {}", sui_rpc_url);
-    info!("  RAM Package ID: {}", package_id);
-    info!("  Server Port: {}", server_port);
+    info!("  Database: {}", config.database.url);
+    info!(
+        "  Read Replica: {}",
+        config.database.read_url.as_deref().unwrap_or("none (primary)")
+    );
+    info!(
+        "  DB Pool: {}-{} connections",
+        config.database.min_connections, config.database.max_connections
+    );
+    for enclave in &config.enclaves {
+        info!(
+            "  Nautilus Server: {} (public key {})",
+            enclave.url,
+            enclave.public_key.as_deref().unwrap_or("not verified")
+        );
+    }
+    info!(
+        "  Chain: {} (chain ID {})",
+        config.chain.network,
+        config.chain.chain_id.as_deref().unwrap_or("unknown")
+    );
+    info!("  Sui RPC: {}", config.chain.rpc_urls.join(", "));
+    info!("  RAM Package ID: {}", config.chain.package_id());
+    info!("  Indexer Source: {:?}", config.indexer_source);
+    info!("  Server Port: {}", config.port);
+    info!("  Admin API: {}", if config.admin_token.is_some() { "enabled" } else { "disabled" });
+    info!("  Enclave request signing: {}", if config.nautilus_signing_key.is_some() { "enabled" } else { "disabled" });
+    info!(
+        "  Inactivity warnings: {} days ahead, checked every {}s",
+        config.inactivity.warning_period.as_secs() / 86_400,
+        config.inactivity.check_interval.as_secs()
+    );
+    if config.retention.is_enabled() {
+        info!("  Event retention: {} months, then archived", config.retention.retention_months);
+    } else {
+        info!("  Event retention: unlimited");
+    }
+
+    let sponsor = config
+        .sponsor
+        .clone()
+        .map(|sponsor| Sponsor::new(sponsor, config.chain.package_id()))
+        .transpose()?;
+    match &sponsor {
+        Some(sponsor) => info!(
+            "  Onboarding sponsor: {} (up to {} wallets/day)",
+            sponsor.address(),
+            sponsor.config.daily_limit
+        ),
+        None => info!("  Onboarding sponsor: disabled"),
+    }
+    match &config.enclave_registration {
+        Some(registration) => info!("  Enclave registration: against config {}", registration.config_id),
+        None => info!("  Enclave registration: disabled"),
+    }
+
+    let receipts = config.receipt_signing_key.as_deref().map(ReceiptSigner::from_hex).transpose()?;
+    match &receipts {
+        Some(signer) => info!("  Transfer receipts: signed by {}", signer.public_key()),
+        None => info!("  Transfer receipts: disabled"),
+    }
+
+    let indexer_throttle = Arc::new(indexer::Throttle::new(config.indexer_throttle.clone()));
+    match config.indexer_throttle.rpc_calls_per_sec {
+        Some(rate) => info!("  Indexer RPC rate limit: {}/s", rate),
+        None => info!("  Indexer RPC rate limit: unlimited"),
+    }
+
+    let analytics = AnalyticsStore::open(config.analytics.clone())?.map(Arc::new);
+    match &config.analytics.dir {
+        Some(dir) => info!("  Analytics store: {}", dir.display()),
+        None => info!("  Analytics store: disabled (explorer aggregates on Postgres)"),
+    }
+
+    ram_backend::chaos::install(config.chaos.clone());
 
     // Initialize database
-    let db = database::Database::init(&database_url).await?;
+    let db = database::Database::connect(&config.database).await?;
+    let read_db = database::Database::connect_read_pool(&config.database, &db).await?;
+
+    // Sui RPC endpoints shared by the indexer and balance queries
+    let sui_rpc = Arc::new(SuiRpcClient::new(config.chain.rpc_urls.clone()));
+    sui_rpc.spawn_health_checks(RPC_HEALTH_CHECK_INTERVAL);
+    check_chain_identifier(&sui_rpc, &config.chain).await?;
+
+    // Prices the balance alerts are valued at
+    let prices = PriceService::new(config.prices.clone(), sui_rpc.clone())?.map(Arc::new);
+    match &config.prices.feed_url {
+        Some(url) => info!("  Price feed: {} ({} coins)", url, config.prices.coin_ids.len()),
+        None => info!("  Price feed: disabled (no price alerts)"),
+    }
+
+    // Enclaves proxied requests are balanced across, on the same chain only
+    let enclaves = Arc::new(EnclaveRouter::new(config.enclaves.clone())?.with_chain(config.chain.info()));
+    enclaves.spawn_health_checks(ENCLAVE_HEALTH_CHECK_INTERVAL);
+
+    // Route aliases, reloaded to pick up changes made through other backends
+    let route_aliases = Arc::new(RouteAliases::load(&db).await?);
+
+    // Background tasks are restarted with backoff when they fail or panic
+    let supervisor = Arc::new(Supervisor::new(config.restart_policy.clone()));
+    let (aliases, aliases_db, reload_interval) =
+        (route_aliases.clone(), db.clone(), config.proxy.routes_reload_interval);
+    supervisor.spawn("route_aliases", move || aliases.clone().reload_every(aliases_db.clone(), reload_interval));
+
+    // Feature flags, reloaded the same way
+    let feature_flags = Arc::new(FeatureFlags::load(&db, config.feature_flags.environment.clone()).await?);
+    let (flags, flags_db, flags_interval) = (feature_flags.clone(), db.clone(), config.feature_flags.reload_interval);
+    supervisor.spawn("feature_flags", move || flags.clone().reload_every(flags_db.clone(), flags_interval));
+
+    // Indexed changes drop cached reads and are pushed to watchers
+    let changes = Arc::new(ChangeFeed::default());
+    let read_cache = Arc::new(ReadCache::new(config.read_cache_ttl));
+    read_cache.spawn_invalidation(&changes);
+    if read_cache.is_enabled() {
+        info!("  Read cache: {}s, invalidated by indexed events", config.read_cache_ttl.as_secs());
+    } else {
+        info!("  Read cache: disabled");
+    }
 
     // Create app state
     let state = Arc::new(AppState {
         db: db.clone(),
-        nautilus_url: nautilus_url.clone(),
+        read_db,
+        enclaves,
+        sui_rpc: sui_rpc.clone(),
+        admin_token: config.admin_token.clone(),
+        nautilus_diagnostics_secret: config.nautilus_diagnostics_secret.clone(),
+        nautilus_signing_key: config.nautilus_signing_key.clone(),
+        forward_headers: config.nautilus_forward_headers.clone(),
+        proxy: config.proxy.clone(),
+        route_aliases,
+        sponsor,
+        chain: config.chain.clone(),
+        changes: changes.clone(),
+        read_cache,
+        supervisor: supervisor.clone(),
+        travel_rule: config.travel_rule.clone(),
+        handle_reservation_ttl: config.handle_reservation_ttl,
+        explorer: Arc::new(Explorer::new(config.explorer.clone())),
+        feature_flags,
+        receipts,
+        indexer_throttle: indexer_throttle.clone(),
+        analytics: analytics.clone(),
+        prices: prices.clone(),
+        gas: config.gas.clone(),
+        gas_estimates: Arc::new(RateLimiter::new(config.gas.estimate_rate_limit_per_minute)),
+        enclave_registration: config.enclave_registration.clone(),
+        webhooks: config.webhooks.clone(),
     });
 
-    // Start event indexer in background
-    let indexer_db = db.clone();
-    let indexer_rpc = sui_rpc_url.clone();
-    let indexer_package = package_id.clone();
-    tokio::spawn(async move {
-        info!("Starting event indexer...");
-        let indexer = indexer::Indexer::new(
-            indexer_rpc,
-            indexer_package,
-            indexer_db,
-        );
-
-        if let Err(e) = indexer.run().await {
-            tracing::error!("Indexer error: {}", e);
+    // Start event indexer in background; a restart resumes from the stored cursor
+    let mut indexer = indexer::Indexer::with_rpc(sui_rpc.clone(), config.chain.package_id().to_string(), db.clone())
+        .with_changes(changes)
+        .with_throttle(indexer_throttle);
+    if let Some(analytics) = &analytics {
+        indexer = indexer.with_analytics(analytics.clone());
+    }
+    let indexer = Arc::new(indexer);
+    let indexer_source = config.indexer_source;
+    let indexer_start_checkpoint = config.indexer_start_checkpoint;
+    info!("Starting event indexer...");
+    supervisor.spawn("indexer", move || {
+        let indexer = indexer.clone();
+        async move {
+            match indexer_source {
+                IndexerSource::Events => indexer.run().await,
+                IndexerSource::Checkpoints => indexer.run_checkpoints(indexer_start_checkpoint).await,
+            }
         }
     });
 
-    // Setup CORS
-    let cors = CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods(Any)
-        .allow_headers(Any);
+    // Warn owners before their inactivity beneficiary can claim
+    let (inactivity_db, inactivity) = (db.clone(), config.inactivity.clone());
+    supervisor.spawn("inactivity_warnings", move || {
+        inactivity::run_inactivity_warnings(inactivity_db.clone(), inactivity.clone())
+    });
+
+    // Move events past the retention window to the archive
+    if config.retention.is_enabled() {
+        let (retention_db, retention) = (db.clone(), config.retention.clone());
+        supervisor.spawn("event_archival", move || {
+            retention::run_event_archival(retention_db.clone(), retention.clone())
+        });
+    }
+
+    // Send merchant webhook notifications queued by the indexer
+    let (webhooks_db, webhooks) = (db.clone(), config.webhooks.clone());
+    supervisor.spawn("webhook_deliveries", move || {
+        webhooks::run_webhook_deliveries(webhooks_db.clone(), webhooks.clone())
+    });
+
+    // Export indexed events to the analytics sink
+    if config.exports.sink.is_some() {
+        let (exports_db, exports) = (db.clone(), config.exports.clone());
+        supervisor.spawn("event_export", move || exports::run_event_exports(exports_db.clone(), exports.clone()));
+    }
+
+    // Keep the analytics store in step with ram_events, compacted and checked
+    if let Some(analytics) = analytics {
+        let analytics_db = db.clone();
+        supervisor.spawn("analytics_store", move || {
+            analytics::run_analytics_store(analytics_db.clone(), analytics.clone())
+        });
+    }
+
+    // Notify owners whose alerted balances crossed their thresholds
+    if let Some(prices) = prices {
+        let (alerts_db, alerts_rpc, interval) = (db.clone(), sui_rpc.clone(), config.prices.alert_interval);
+        supervisor.spawn("price_alerts", move || {
+            price_alerts::run_price_alerts(alerts_db.clone(), alerts_rpc.clone(), prices.clone(), interval)
+        });
+    }
 
     // Build router
-    let app = Router::new()
-        // Backend-specific endpoints
-        .route("/health", get(proxy::health_check))
-        .route("/api/events", post(proxy::get_wallet_events))
-        .route("/api/stats", post(proxy::get_wallet_stats))
-        // Proxy all Nautilus endpoints
-        .route("/health_check", get(proxy::proxy_to_nautilus))
-        .route("/process_create_wallet", post(proxy::proxy_to_nautilus))
-        .route("/process_link_address", post(proxy::proxy_to_nautilus))
-        .route("/process_bio_auth", post(proxy::proxy_to_nautilus))
-        .route("/process_tweet", post(proxy::proxy_to_nautilus))
-        .route("/process_init_account", post(proxy::proxy_to_nautilus))
-        .route("/process_update_handle", post(proxy::proxy_to_nautilus))
-        .route("/process_secure_link_wallet", post(proxy::proxy_to_nautilus))
-        .route("/get_attestation", get(proxy::proxy_to_nautilus))
-        // Frontend-facing proxy routes (simpler names)
-        .route("/create_wallet", post(proxy::proxy_to_nautilus))
-        .route("/link_address", post(proxy::proxy_to_nautilus))
-        .route("/bio_auth", post(proxy::proxy_to_nautilus))
-        .route("/transfer", post(proxy::proxy_to_nautilus))
-        .route("/withdraw", post(proxy::proxy_to_nautilus))
-        .with_state(state)
-        .layer(cors);
+    let app = build_router(state);
 
     // Start server
-    let addr = format!("0.0.0.0:{}", server_port);
+    let addr = format!("0.0.0.0:{}", config.port);
     let listener = tokio::net::TcpListener::bind(&addr).await?;
     info!("RAM Backend listening on {}", listener.local_addr()?);
 
-    axum::serve(listener, app.into_make_service())
+    // Connection addresses key the explorer's rate limit
+    axum::serve(listener, app.into_make_service_with_connect_info::<std::net::SocketAddr>())
         .await
         .map_err(|e| anyhow::anyhow!("Server error: {}", e))
 }
+
+/// Refuse to start against fullnodes of another chain than the configured one
+///
+/// Unreachable fullnodes only get a warning: the RPC failover and health
+/// checks already deal with outages.
+async fn check_chain_identifier(rpc: &SuiRpcClient, chain: &ChainConfig) -> Result<()> {
+    let Some(expected) = &chain.chain_id else {
+        return Ok(());
+    };
+    match rpc.call("sui_getChainIdentifier", json!([])).await {
+        Ok(actual) if actual.as_str() == Some(expected.as_str()) => Ok(()),
+        Ok(actual) => Err(anyhow::anyhow!(
+            "SUI_RPC_URL serves chain {}, not {} ({})",
+            actual,
+            chain.network,
+            expected
+        )),
+        Err(e) => {
+            warn!("Could not check the fullnodes' chain identifier: {}", e);
+            Ok(())
+        }
+    }
+}
@@ -2,6 +2,7 @@
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 /// RAM event stored in database
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -9,11 +10,198 @@ pub struct RamEvent {
     pub handle: Option<String>,
     pub event_type: String,
     pub amount: Option<i64>,
+    pub coin_type: Option<String>,
+    /// BioAuth result: 0=OK, 1=InvalidAmount, 2=Duress
+    pub result_code: Option<i32>,
     pub from_handle: Option<String>,
     pub to_handle: Option<String>,
+    /// Sender's reference on a Transferred event, if it carried one
+    pub memo: Option<String>,
+    /// Payload layout a Transferred event was verified against (0 = wallet-signed,
+    /// None = emitted before layouts were versioned)
+    pub payload_version: Option<i16>,
     pub owner: Option<String>,
+    /// Wallet object ID, set on WalletCreated
+    pub wallet_id: Option<String>,
     pub tx_digest: String,
     pub timestamp: DateTime<Utc>,
+    /// Event fields exactly as emitted on-chain
+    pub raw_json: Option<Value>,
+    /// Net gas paid by the emitting transaction, set by the checkpoint indexer
+    pub gas_used: Option<i64>,
+    /// Address that signed the emitting transaction
+    pub sender: Option<String>,
+    /// Label of the sender if it is currently linked to the event's handle
+    /// (read-only, resolved when querying)
+    pub sender_label: Option<String>,
+    /// Profile display names of the transfer's sender and recipient
+    /// (read-only, resolved when querying)
+    pub from_display_name: Option<String>,
+    pub to_display_name: Option<String>,
+    /// On a refund, the transaction of the payment it refunds (read-only,
+    /// resolved when querying)
+    pub refund_of: Option<String>,
+    /// On a payment, the transactions of its completed refunds (read-only,
+    /// resolved when querying)
+    #[serde(default)]
+    pub refunded_in: Vec<String>,
+}
+
+/// An indexed event as exported to analytics sinks (see exports.rs)
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportedEvent {
+    /// Position in ram_events: increases with every stored event, and is
+    /// what consumers drop repeats by
+    pub id: i64,
+    pub event_type: String,
+    pub tx_digest: String,
+    pub timestamp_ms: i64,
+    pub handle: Option<String>,
+    pub from_handle: Option<String>,
+    pub to_handle: Option<String>,
+    pub amount: Option<i64>,
+    pub coin_type: Option<String>,
+    pub memo: Option<String>,
+    pub payload_version: Option<i16>,
+    pub wallet_id: Option<String>,
+    pub result_code: Option<i32>,
+    pub sender: Option<String>,
+    pub gas_used: Option<i64>,
+    /// Event fields exactly as emitted on-chain
+    pub raw_json: Option<Value>,
+}
+
+/// One day's transfers in one coin, with how many distinct wallets sent
+/// and received them (explorer.rs publishes it only if both reach k)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DailyTransferVolume {
+    pub day_ms: i64,
+    pub coin_type: String,
+    pub transfers: i64,
+    /// Sum of raw amounts, as a decimal string (it may not fit an i64)
+    pub volume: String,
+    pub senders: i64,
+    pub recipients: i64,
+}
+
+/// Wallets created on one day
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DailyWalletCreations {
+    pub day_ms: i64,
+    pub created: i64,
+}
+
+/// Window of an explorer aggregate (`GET /api/explorer/*`)
+#[derive(Debug, Deserialize)]
+pub struct ExplorerQuery {
+    /// UTC days covered, today included
+    pub days: Option<i64>,
+}
+
+/// A day's transfers in one coin that enough wallets were part of
+#[derive(Debug, Clone, Serialize)]
+pub struct PublicTransferVolume {
+    pub day_ms: i64,
+    pub coin_type: String,
+    pub transfers: i64,
+    pub volume: String,
+}
+
+/// Transfer volumes for the public explorer (`GET /api/explorer/transfers`)
+#[derive(Debug, Serialize)]
+pub struct ExplorerTransfers {
+    pub days: i64,
+    pub since_ms: i64,
+    pub k_anonymity: i64,
+    /// Days and coins with fewer than `k_anonymity` senders or recipients are left out
+    pub volumes: Vec<PublicTransferVolume>,
+}
+
+/// Wallets created on a day; None if fewer than the explorer's k
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PublicWalletCreations {
+    pub day_ms: i64,
+    pub created: Option<i64>,
+}
+
+/// Wallet creations for the public explorer (`GET /api/explorer/wallets`)
+#[derive(Debug, Serialize)]
+pub struct ExplorerWallets {
+    pub days: i64,
+    pub since_ms: i64,
+    pub k_anonymity: i64,
+    pub total_wallets: Option<i64>,
+    /// Every day of the window, oldest first
+    pub daily: Vec<PublicWalletCreations>,
+}
+
+/// Columns of an event the analytics store keeps (see analytics.rs)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnalyticsRow {
+    pub id: i64,
+    pub event_type: String,
+    pub timestamp_ms: i64,
+    pub coin_type: Option<String>,
+    pub amount: Option<i64>,
+    pub from_handle_norm: Option<String>,
+    pub to_handle_norm: Option<String>,
+}
+
+/// Events of one type stored on one day
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DailyEventCount {
+    pub day_ms: i64,
+    pub event_type: String,
+    pub events: i64,
+}
+
+/// A day and event type the analytics store and ram_events_history disagree on
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CountMismatch {
+    pub day_ms: i64,
+    pub event_type: String,
+    pub primary: i64,
+    pub store: i64,
+}
+
+/// Outcome of comparing the analytics store with ram_events_history
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnalyticsConsistency {
+    pub checked_at_ms: i64,
+    /// Events from this day on were compared...
+    pub since_ms: i64,
+    /// ...up to this id, the last one written to the store
+    pub up_to_id: i64,
+    pub mismatches: Vec<CountMismatch>,
+}
+
+/// State of the analytics store (`GET /admin/analytics_consistency`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnalyticsStatus {
+    /// Whether explorer aggregates are computed from the store
+    pub serving: bool,
+    /// Whether the store has everything Postgres had when it last looked
+    pub caught_up: bool,
+    pub last_id: i64,
+    pub segments: usize,
+    pub rows: usize,
+    /// Events waiting for the next flush
+    pub pending: usize,
+    pub last_check: Option<AnalyticsConsistency>,
+}
+
+/// Sui address linked to a wallet, with its label
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinkedAddress {
+    pub address: String,
+    pub label: String,
+    pub linked_at_ms: i64,
+}
+
+/// Request to list a wallet's linked addresses
+#[derive(Debug, Deserialize)]
+pub struct GetLinkedAddressesRequest {
+    pub handle: String,
 }
 
 /// Request to get events for a wallet
@@ -49,3 +237,830 @@ pub struct WalletStats {
     pub total_transfers_received: i64,
 }
 
+
+/// Indexed coin movements for one coin type, summed over a wallet's history
+#[derive(Debug, Clone, Serialize)]
+pub struct CoinFlows {
+    pub coin_type: String,
+    pub deposited: i64,
+    pub withdrawn: i64,
+    pub transferred_in: i64,
+    pub transferred_out: i64,
+}
+
+/// One coin in a wallet's portfolio
+#[derive(Debug, Clone, Serialize)]
+pub struct CoinPosition {
+    #[serde(flatten)]
+    pub flows: CoinFlows,
+    /// deposited + transferred_in - withdrawn - transferred_out
+    pub net_flow: i64,
+    /// On-chain balance, None if the wallet or fullnode couldn't be read
+    pub live_balance: Option<u64>,
+}
+
+/// Multi-coin portfolio for a wallet
+#[derive(Debug, Clone, Serialize)]
+pub struct PortfolioResponse {
+    pub handle: String,
+    pub wallet_id: Option<String>,
+    pub coins: Vec<CoinPosition>,
+}
+
+/// Event the indexer failed to process, queued for retry
+#[derive(Debug, Clone, Serialize)]
+pub struct FailedEvent {
+    pub id: i64,
+    pub tx_digest: String,
+    pub event_seq: String,
+    pub event_type: String,
+    pub event_json: Value,
+    pub last_error: String,
+    pub attempts: i32,
+    pub next_attempt_ms: i64,
+    /// "pending" while retries remain, "dead" once attempts are exhausted
+    pub status: String,
+}
+
+/// Filter for listing failed events
+#[derive(Debug, Deserialize)]
+pub struct FailedEventsQuery {
+    pub status: Option<String>,
+    #[serde(default = "default_limit")]
+    pub limit: i64,
+}
+
+/// Support search query (`GET /api/search?q=`)
+#[derive(Debug, Deserialize)]
+pub struct SearchQuery {
+    pub q: String,
+    #[serde(default = "default_limit")]
+    pub limit: i64,
+}
+
+/// Wallet handles and events matching a search, best matches first
+#[derive(Debug, Serialize)]
+pub struct SearchResponse {
+    pub query: String,
+    pub handles: Vec<String>,
+    pub events: Vec<RamEvent>,
+}
+
+/// Paging for a wallet's bio-auth history (`GET /api/bioauth_history/:handle`)
+#[derive(Debug, Deserialize)]
+pub struct BioAuthHistoryQuery {
+    #[serde(default = "default_limit")]
+    pub limit: i64,
+    #[serde(default)]
+    pub offset: i64,
+}
+
+/// One bio-auth submitted on-chain for a wallet
+#[derive(Debug, Clone, Serialize)]
+pub struct BioAuthAttempt {
+    /// 0=OK, 1=InvalidAmount, 2=Duress
+    pub result_code: i32,
+    /// "ok", "invalid_amount" or "duress"
+    pub result: String,
+    /// Amount the bio-auth confirmed, in the coin's base units
+    pub amount: Option<i64>,
+    /// "voice" or "typed"
+    pub method: String,
+    pub destination_verified: Option<bool>,
+    pub tx_digest: String,
+    pub timestamp_ms: i64,
+    /// Address that submitted the bio-auth
+    pub sender: Option<String>,
+}
+
+/// A wallet's bio-auth attempts, newest first
+#[derive(Debug, Serialize)]
+pub struct BioAuthHistoryResponse {
+    pub handle: String,
+    pub attempts: Vec<BioAuthAttempt>,
+    /// Attempts that didn't return OK, across the whole history
+    pub failed_total: i64,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+/// History the indexer skipped after its cursor was pruned by the fullnode
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexerGap {
+    pub id: i64,
+    pub stale_cursor: String,
+    pub resumed_from: Option<String>,
+    pub error: String,
+    pub detected_at_ms: i64,
+}
+
+/// One administrative or configuration change
+#[derive(Debug, Clone, Serialize)]
+pub struct AdminAuditEntry {
+    pub id: i64,
+    /// Operator named in `X-Admin-Actor`
+    pub actor: String,
+    /// Fingerprint of the admin token used
+    pub credential: String,
+    pub action: String,
+    pub target: String,
+    pub details: Value,
+    /// HTTP status the change ended with (None if it never completed)
+    pub status: Option<i32>,
+    pub created_at_ms: i64,
+}
+
+/// Filter for the admin audit trail (`GET /admin/audit`)
+#[derive(Debug, Deserialize)]
+pub struct AdminAuditQuery {
+    pub action: Option<String>,
+    pub actor: Option<String>,
+    pub target: Option<String>,
+    #[serde(default = "default_limit")]
+    pub limit: i64,
+    #[serde(default)]
+    pub offset: i64,
+}
+
+/// Request to hold a handle during onboarding (`POST /api/handles/reserve`)
+#[derive(Debug, Deserialize)]
+pub struct ReserveHandleRequest {
+    pub handle: String,
+    /// Token of the caller's current reservation, to extend it
+    #[serde(default)]
+    pub reservation_token: Option<String>,
+}
+
+/// A handle held for its reserver until `expires_at_ms`
+#[derive(Debug, Serialize)]
+pub struct HandleReservation {
+    /// Normalized handle
+    pub handle: String,
+    /// Presented with the wallet creation; only its hash is stored
+    pub reservation_token: String,
+    pub expires_at_ms: i64,
+}
+
+/// Request to prepare a sponsored wallet creation (`POST /api/onboard`)
+#[derive(Debug, Deserialize)]
+pub struct OnboardRequest {
+    pub handle: String,
+    /// Address that will own the wallet and sign the transaction
+    pub sender: String,
+    /// Passed on to the enclave when its registration gate asks for a proof of work
+    #[serde(default)]
+    pub pow_nonce: Option<String>,
+    /// Passed on to the enclave when its registration gate asks for a CAPTCHA
+    #[serde(default)]
+    pub captcha_token: Option<String>,
+    /// Token of the handle's reservation, required while someone holds it
+    #[serde(default)]
+    pub reservation_token: Option<String>,
+}
+
+/// Prepared transaction for the user to sign
+#[derive(Debug, Serialize)]
+pub struct OnboardStartResponse {
+    pub id: i64,
+    /// Base64 BCS TransactionData
+    pub tx_bytes: String,
+    /// Address paying the gas
+    pub sponsor: String,
+    pub expires_at_ms: i64,
+}
+
+/// The user's signature over a prepared transaction (`POST /api/onboard/:id`)
+#[derive(Debug, Deserialize)]
+pub struct OnboardSubmitRequest {
+    /// Serialized Sui signature (base64 flag || signature || public key)
+    pub signature: String,
+}
+
+/// A sponsored wallet creation and its outcome
+#[derive(Debug, Clone, Serialize)]
+pub struct Onboarding {
+    pub id: i64,
+    pub handle: String,
+    pub sender: String,
+    #[serde(skip)]
+    pub tx_bytes: Vec<u8>,
+    /// pending | submitting | executed | failed
+    pub status: String,
+    pub tx_digest: Option<String>,
+    /// Set once the indexer has seen the wallet
+    pub wallet_id: Option<String>,
+    pub error: Option<String>,
+    pub created_at_ms: i64,
+    pub updated_at_ms: i64,
+}
+
+/// A wallet's profile, as stored
+#[derive(Debug, Clone, Serialize)]
+pub struct Profile {
+    pub handle: String,
+    pub display_name: Option<String>,
+    pub accepted_coin_types: Vec<String>,
+    /// Hex SHA-256 of the avatar image
+    pub avatar_hash: Option<String>,
+    /// BCP 47 language tag, e.g. `vi-VN`
+    pub preferred_locale: Option<String>,
+    /// Stored `NotificationSettings`
+    pub notifications: Value,
+    /// Linked address that signed the latest update
+    pub updated_by: String,
+    pub updated_at_ms: i64,
+}
+
+/// Data behind a shareable receive link or QR code
+#[derive(Debug, Serialize)]
+pub struct ReceiveInfo {
+    pub handle: String,
+    pub display_name: Option<String>,
+    /// Full Move type tags the wallet accepts
+    pub accepted_coin_types: Vec<String>,
+    /// Hex SHA-256 of the avatar image
+    pub avatar_hash: Option<String>,
+}
+
+/// Notifications a wallet wants; every kind is on unless turned off
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NotificationSettings {
+    pub incoming_transfers: bool,
+    pub outgoing_transfers: bool,
+    /// Failed or duress bio-auths and address link changes
+    pub security_alerts: bool,
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        Self {
+            incoming_transfers: true,
+            outgoing_transfers: true,
+            security_alerts: true,
+        }
+    }
+}
+
+/// New profile contents (`PUT /api/profile/:handle`)
+#[derive(Debug, Deserialize)]
+pub struct UpdateProfileRequest {
+    pub display_name: Option<String>,
+    #[serde(default = "default_accepted_coin_types")]
+    pub accepted_coin_types: Vec<String>,
+    pub avatar_hash: Option<String>,
+    pub preferred_locale: Option<String>,
+    #[serde(default)]
+    pub notifications: NotificationSettings,
+}
+
+fn default_accepted_coin_types() -> Vec<String> {
+    crate::profile::DEFAULT_ACCEPTED_COIN_TYPES.iter().map(|t| t.to_string()).collect()
+}
+
+/// A saved recipient
+#[derive(Debug, Clone, Serialize)]
+pub struct Contact {
+    pub nickname: String,
+    pub contact_handle: String,
+    pub created_at_ms: i64,
+}
+
+/// Whose contacts to list (`GET /api/contacts`)
+#[derive(Debug, Deserialize)]
+pub struct ContactsQuery {
+    pub handle: String,
+}
+
+/// Save a recipient under a nickname (`POST /api/contacts`)
+#[derive(Debug, Deserialize)]
+pub struct SaveContactRequest {
+    pub handle: String,
+    pub nickname: String,
+    pub contact_handle: String,
+}
+
+/// Remove a saved recipient (`DELETE /api/contacts`)
+#[derive(Debug, Deserialize)]
+pub struct DeleteContactRequest {
+    pub handle: String,
+    pub nickname: String,
+}
+
+/// Alert on the USD value of a wallet's balance of a coin
+#[derive(Debug, Clone, Serialize)]
+pub struct PriceAlert {
+    pub id: i64,
+    pub handle: String,
+    pub coin_type: String,
+    /// `below` or `above`
+    pub direction: String,
+    pub threshold_usd: f64,
+    /// When the owner was last notified, while the condition still holds
+    /// (None = armed)
+    pub triggered_at_ms: Option<i64>,
+    pub created_at_ms: i64,
+    pub updated_at_ms: i64,
+}
+
+/// Whose price alerts to list (`GET /api/price_alerts`)
+#[derive(Debug, Deserialize)]
+pub struct PriceAlertsQuery {
+    pub handle: String,
+}
+
+/// Create a price alert (`POST /api/price_alerts`)
+#[derive(Debug, Deserialize)]
+pub struct CreatePriceAlertRequest {
+    pub handle: String,
+    pub coin_type: String,
+    pub direction: String,
+    pub threshold_usd: f64,
+}
+
+/// Replace a price alert's condition, re-arming it (`PUT /api/price_alerts`)
+#[derive(Debug, Deserialize)]
+pub struct UpdatePriceAlertRequest {
+    pub handle: String,
+    pub id: i64,
+    pub coin_type: String,
+    pub direction: String,
+    pub threshold_usd: f64,
+}
+
+/// Remove a price alert (`DELETE /api/price_alerts`)
+#[derive(Debug, Deserialize)]
+pub struct DeletePriceAlertRequest {
+    pub handle: String,
+    pub id: i64,
+}
+
+/// A wallet with a beneficiary whose claim window is about to open
+#[derive(Debug, Clone)]
+pub struct InactiveWallet {
+    pub handle: String,
+    pub beneficiary_handle: String,
+    /// Latest indexed owner activity (or when the beneficiary was set)
+    pub last_active_ms: i64,
+    /// When the beneficiary can claim if nothing happens before then
+    pub claimable_at_ms: i64,
+}
+
+/// Something the backend told a wallet's owner
+#[derive(Debug, Clone, Serialize)]
+pub struct Notification {
+    pub id: i64,
+    /// e.g. `inactivity_warning`
+    pub kind: String,
+    pub data: Value,
+    pub created_at_ms: i64,
+}
+
+/// Whose notifications to list (`GET /api/notifications`)
+#[derive(Debug, Deserialize)]
+pub struct NotificationsQuery {
+    pub handle: String,
+}
+
+/// A backend path forwarded to a differently named enclave route
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RouteAlias {
+    pub path: String,
+    pub upstream_path: String,
+    /// Deadline class: `metadata`, `bio_auth` or `default`
+    pub route_class: String,
+    pub updated_at_ms: i64,
+}
+
+/// Add or change a route alias (`PUT /admin/routes`)
+#[derive(Debug, Deserialize)]
+pub struct SaveRouteAliasRequest {
+    pub path: String,
+    pub upstream_path: String,
+    #[serde(default = "default_route_class")]
+    pub route_class: String,
+}
+
+fn default_route_class() -> String {
+    "default".to_string()
+}
+
+/// Which route alias to remove (`DELETE /admin/routes`)
+#[derive(Debug, Deserialize)]
+pub struct RouteAliasQuery {
+    pub path: String,
+}
+
+/// A feature flag gating a risky feature (see flags.rs)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeatureFlag {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub enabled: bool,
+    /// Share of subjects (0-100) the flag is on for
+    pub rollout_percent: i16,
+    /// Environments the flag is on in; empty for every environment
+    pub environments: Vec<String>,
+    pub updated_at_ms: i64,
+}
+
+/// Add or change a feature flag (`PUT /admin/flags`)
+#[derive(Debug, Deserialize)]
+pub struct SaveFeatureFlagRequest {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub enabled: bool,
+    #[serde(default = "default_rollout_percent")]
+    pub rollout_percent: i16,
+    #[serde(default)]
+    pub environments: Vec<String>,
+}
+
+fn default_rollout_percent() -> i16 {
+    100
+}
+
+/// Which feature flag to remove (`DELETE /admin/flags`)
+#[derive(Debug, Deserialize)]
+pub struct FeatureFlagQuery {
+    pub name: String,
+}
+
+/// Every flag, for servers that evaluate them themselves (`GET /api/flags`)
+#[derive(Debug, Serialize)]
+pub struct FeatureFlagList {
+    pub flags: Vec<FeatureFlag>,
+}
+
+/// What a transfer receipt attests to (see receipts.rs)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TransferReceipt {
+    pub tx_digest: String,
+    pub payer_handle: String,
+    pub payee_handle: String,
+    /// Raw amount, in the coin's smallest unit
+    pub amount: i64,
+    pub coin_type: String,
+    pub memo: Option<String>,
+    pub timestamp_ms: i64,
+}
+
+/// A receipt with the backend's countersignature (`GET /api/receipts/:tx_digest`)
+#[derive(Debug, Clone, Serialize)]
+pub struct SignedReceipt {
+    #[serde(flatten)]
+    pub receipt: TransferReceipt,
+    /// Hex Ed25519 public key of the receipt key
+    pub signer_public_key: String,
+    /// Hex Ed25519 signature over the receipt's message
+    pub signature: String,
+}
+
+/// How a receipt is downloaded: `json` (default) or `pdf`
+#[derive(Debug, Deserialize)]
+pub struct ReceiptQuery {
+    pub format: Option<String>,
+}
+
+/// Key receipts are countersigned with (`GET /api/receipts/public_key`)
+#[derive(Debug, Serialize)]
+pub struct ReceiptKey {
+    pub algorithm: &'static str,
+    pub public_key: String,
+}
+
+/// An API key, as its owner sees it (the secret is only shown when issued)
+#[derive(Debug, Clone, Serialize)]
+pub struct ApiKey {
+    pub id: String,
+    /// `read` or `transfer`
+    pub scope: String,
+    pub label: String,
+    pub created_at_ms: i64,
+    pub rotated_at_ms: Option<i64>,
+    pub last_used_at_ms: Option<i64>,
+    pub revoked_at_ms: Option<i64>,
+}
+
+/// A stored API key, for checking a presented one
+#[derive(Debug, Clone)]
+pub struct ApiKeyRecord {
+    pub id: String,
+    pub handle: String,
+    pub key_hash: String,
+    pub scope: String,
+    pub revoked_at_ms: Option<i64>,
+}
+
+/// A newly issued or rotated key, with its secret
+#[derive(Debug, Serialize)]
+pub struct IssuedApiKey {
+    /// `ram_<id>_<secret>`, sent as `Authorization: Bearer <key>`
+    pub key: String,
+    #[serde(flatten)]
+    pub api_key: ApiKey,
+}
+
+/// Whose API keys to list (`GET /api/keys`)
+#[derive(Debug, Deserialize)]
+pub struct ApiKeysQuery {
+    pub handle: String,
+}
+
+/// Issue an API key (`POST /api/keys`)
+#[derive(Debug, Deserialize)]
+pub struct CreateApiKeyRequest {
+    pub handle: String,
+    pub label: String,
+    pub scope: String,
+}
+
+/// Which wallet a key being rotated or revoked belongs to
+#[derive(Debug, Deserialize)]
+pub struct ApiKeyRequest {
+    pub handle: String,
+}
+
+/// A wallet's webhook, as its owner sees it (the secret is only shown when saved)
+#[derive(Debug, Clone, Serialize)]
+pub struct Webhook {
+    pub url: String,
+    pub created_at_ms: i64,
+    pub updated_at_ms: i64,
+}
+
+/// A newly saved webhook, with the secret its notifications are signed with
+#[derive(Debug, Serialize)]
+pub struct SavedWebhook {
+    /// `whsec_...`, the HMAC-SHA256 key of `X-Ram-Webhook-Signature`
+    pub secret: String,
+    #[serde(flatten)]
+    pub webhook: Webhook,
+}
+
+/// A notification sent, or still to be sent, to a wallet's webhook
+#[derive(Debug, Clone, Serialize)]
+pub struct WebhookDelivery {
+    /// Event ID, the same on every attempt
+    pub id: String,
+    pub payload: Value,
+    /// "pending" while attempts remain, then "delivered" or "dead"
+    pub status: String,
+    pub attempts: i32,
+    pub next_attempt_ms: i64,
+    pub last_error: Option<String>,
+    pub created_at_ms: i64,
+    pub delivered_at_ms: Option<i64>,
+}
+
+/// A wallet's webhook and its latest deliveries (`GET /api/webhook`)
+#[derive(Debug, Serialize)]
+pub struct WebhookStatus {
+    #[serde(flatten)]
+    pub webhook: Webhook,
+    pub deliveries: Vec<WebhookDelivery>,
+}
+
+/// A delivery whose next attempt is due, with where and how to send it
+#[derive(Debug, Clone)]
+pub struct DueWebhookDelivery {
+    pub id: String,
+    pub handle: String,
+    pub url: String,
+    pub secret: String,
+    pub payload: Value,
+    pub attempts: i32,
+}
+
+/// Whose webhook to show (`GET /api/webhook`)
+#[derive(Debug, Deserialize)]
+pub struct WebhookQuery {
+    pub handle: String,
+}
+
+/// Register or replace a wallet's webhook (`PUT /api/webhook`)
+#[derive(Debug, Deserialize)]
+pub struct SaveWebhookRequest {
+    pub handle: String,
+    pub url: String,
+}
+
+/// Remove a wallet's webhook (`DELETE /api/webhook`)
+#[derive(Debug, Deserialize)]
+pub struct DeleteWebhookRequest {
+    pub handle: String,
+}
+
+/// A refund of a payment a merchant received
+#[derive(Debug, Clone, Serialize)]
+pub struct Refund {
+    pub id: String,
+    /// The merchant, which sends the refund
+    pub handle: String,
+    /// Who made the payment, and receives the refund
+    pub payer_handle: String,
+    pub payment_tx_digest: String,
+    pub amount: i64,
+    pub coin_type: String,
+    pub reason: Option<String>,
+    /// "pending" until the refund transfer is indexed ("completed") or the
+    /// merchant cancels it
+    pub status: String,
+    pub refund_tx_digest: Option<String>,
+    pub created_at_ms: i64,
+    pub completed_at_ms: Option<i64>,
+}
+
+/// An indexed transfer to a merchant, as far as refunding it goes
+#[derive(Debug, Clone)]
+pub struct RefundablePayment {
+    pub payer_handle: String,
+    pub amount: i64,
+    pub coin_type: String,
+    /// Pending and completed refunds so far
+    pub refunded: i64,
+}
+
+/// Whose refunds to list (`GET /api/refunds`)
+#[derive(Debug, Deserialize)]
+pub struct RefundsQuery {
+    pub handle: String,
+}
+
+/// Start a refund (`POST /api/refunds`)
+#[derive(Debug, Deserialize)]
+pub struct CreateRefundRequest {
+    pub handle: String,
+    /// Transaction of the payment to refund
+    pub tx_digest: String,
+    /// Defaults to what's left to refund of the payment
+    pub amount: Option<i64>,
+    pub reason: Option<String>,
+}
+
+/// Which merchant a refund being cancelled belongs to
+#[derive(Debug, Deserialize)]
+pub struct CancelRefundRequest {
+    pub handle: String,
+}
+
+/// An encrypted travel-rule envelope and the transfer it travels with
+#[derive(Debug, Clone, Serialize)]
+pub struct TravelRuleEnvelope {
+    /// Hex SHA-256 of the ciphertext, signed into the transfer's memo
+    pub hash: String,
+    /// Originator
+    pub from_handle: String,
+    /// Beneficiary
+    pub to_handle: String,
+    pub amount: i64,
+    pub coin_type: String,
+    pub scheme: String,
+    /// Base64, as the client encrypted it
+    pub ciphertext: String,
+    /// Set once the transfer is indexed
+    pub tx_digest: Option<String>,
+    pub created_at_ms: i64,
+}
+
+/// Envelope attached to a transfer as `travel_rule` in its payload
+#[derive(Debug, Deserialize)]
+pub struct TravelRuleEnvelopeRequest {
+    pub scheme: String,
+    /// Base64 ciphertext
+    pub ciphertext: String,
+}
+
+/// Which party is reading an envelope (`GET /api/travel_rule/:hash`)
+#[derive(Debug, Deserialize)]
+pub struct TravelRuleQuery {
+    pub handle: String,
+}
+
+/// An indexed event that bears on whether a wallet can send: a lock,
+/// freeze, unfreeze or daily limit
+#[derive(Debug, Clone)]
+pub struct PolicyEvent {
+    pub event_type: String,
+    pub coin_type: Option<String>,
+    pub amount: Option<i64>,
+    pub raw_json: Option<Value>,
+    pub timestamp_ms: i64,
+}
+
+/// One step of the transfer policy chain, as a preview sees it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PolicyCheck {
+    /// `recipient`, `wallet_state`, `balance`, `daily_limit` or `travel_rule`
+    pub check: String,
+    /// "pass", "warn" (may fail, e.g. near a soft limit), "block" or
+    /// "unknown" (couldn't be evaluated)
+    pub outcome: String,
+    pub message: String,
+}
+
+/// Gas a transfer is expected to need, paid by the sending address
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GasEstimate {
+    /// Current reference gas price (MIST per unit), if the fullnode answered
+    pub reference_gas_price: Option<u64>,
+    /// Budget to set on the transaction (MIST), from a dry run of the
+    /// `tx_bytes` previewed with the margin `/api/tx/estimate` adds; None
+    /// without them, or if the dry run couldn't be made or would abort
+    pub budget: Option<u64>,
+}
+
+/// Dry run of a transfer (`POST /api/transfer/preview`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransferPreview {
+    pub from_handle: String,
+    /// The recipient, once a contact or refund is resolved
+    pub to_handle: String,
+    pub amount: i64,
+    pub coin_type: String,
+    /// Memo the enclave would sign, once a refund is resolved
+    pub memo: Option<String>,
+    /// False if any check blocks
+    pub allowed: bool,
+    pub checks: Vec<PolicyCheck>,
+    /// Live balance of the coin, if the fullnode answered
+    pub balance: Option<i64>,
+    pub projected_balance: Option<i64>,
+    pub estimated_gas: GasEstimate,
+    /// Whether the transfer needs a bio-auth confirmation first
+    pub bio_auth_required: bool,
+}
+
+/// Transaction to dry-run for a gas recommendation (`POST /api/tx/estimate`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GasEstimateRequest {
+    /// BCS `TransactionData` (base64), with its sender, gas payment and a
+    /// provisional budget set
+    pub tx_bytes: String,
+}
+
+/// Gas a dry run charged, in MIST
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GasUsed {
+    pub computation_cost: u64,
+    pub storage_cost: u64,
+    pub storage_rebate: u64,
+    pub non_refundable_storage_fee: u64,
+}
+
+/// Gas price and budget to set on a transaction, from a dry run of it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GasRecommendation {
+    /// "success", or "failure" if the transaction would abort on-chain
+    pub status: String,
+    /// Why the dry run failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub gas_used: GasUsed,
+    /// Net fee the dry run charged (MIST): its cost less the storage rebate
+    pub fee: u64,
+    /// Current reference gas price (MIST per unit), the price to set
+    pub gas_price: u64,
+    /// Budget to set (MIST): the dry run's cost plus a margin
+    pub budget: u64,
+}
+
+/// Which enclave to register, and who signs the registration
+/// (`POST /admin/register_enclave`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegisterEnclaveRequest {
+    /// Address that will sign and pay for the transaction (and own the `Enclave`)
+    pub sender: String,
+    /// Index of the enclave in NAUTILUS_URL order
+    #[serde(default)]
+    pub enclave: usize,
+    /// Set the config's PCRs to the attested ones first, if they differ
+    /// (needs ENCLAVE_CAP_ID, owned by `sender`)
+    #[serde(default)]
+    pub update_pcrs: bool,
+}
+
+/// A prepared enclave registration, for the sender to sign and execute
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnclaveRegistration {
+    pub enclave_url: String,
+    /// Attested Ed25519 public key (hex), which the `Enclave` object will hold
+    pub public_key: String,
+    /// Attested PCR0, PCR1 and PCR2 (hex)
+    pub pcrs: Vec<String>,
+    pub module_id: String,
+    /// When the attestation was made
+    pub attested_at_ms: u64,
+    /// Whether the transaction updates the config's PCRs before registering
+    pub updates_pcrs: bool,
+    /// Config version the `Enclave` object will be registered against
+    pub config_version: u64,
+    /// Last epoch the transaction can execute in
+    pub expires_after_epoch: u64,
+    /// BCS `TransactionData` (base64) for the sender to sign
+    pub tx_bytes: String,
+    /// Gas set on the transaction, from a dry run of it
+    pub gas: GasRecommendation,
+}
@@ -0,0 +1,38 @@
+// Notifications for a wallet's owner
+// Written by background jobs (see inactivity) and read by the wallet's
+// linked addresses with a signed request, like contacts and profiles.
+
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use std::sync::Arc;
+use tracing::error;
+
+use crate::database::Database;
+use crate::models::{Notification, NotificationsQuery};
+use crate::wallet_auth::authenticate;
+use crate::AppState;
+
+/// Most notifications returned, newest first
+pub const MAX_NOTIFICATIONS: i64 = 100;
+
+/// List a wallet's notifications
+///
+/// GET /api/notifications?handle= (signed, action `read`)
+pub async fn list_notifications(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<NotificationsQuery>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<Notification>>, StatusCode> {
+    authenticate(&state, &headers, "notifications", "read", &query.handle, &[]).await?;
+
+    let notifications = Database::list_notifications(&state.db, &query.handle, MAX_NOTIFICATIONS)
+        .await
+        .map_err(|e| {
+            error!("Failed to list notifications: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    Ok(Json(notifications))
+}
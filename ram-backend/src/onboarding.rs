@@ -0,0 +1,294 @@
+// Sponsored onboarding: wallet creation without the user holding SUI
+// POST /api/onboard gets the enclave's create-wallet authorization and
+// prepares the transaction with the backend's sponsor paying gas; the user
+// signs it and POSTs the signature to /api/onboard/:id, which adds the
+// sponsor signature, executes it and waits for the indexer to see the wallet.
+// Sponsorship is capped per rolling day, and each handle or address is
+// sponsored once. A reserved handle needs its reservation token.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{error, info, warn};
+
+use crate::database::Database;
+use crate::models::{OnboardRequest, OnboardStartResponse, OnboardSubmitRequest, Onboarding};
+use crate::reservations::check_reservation;
+use crate::routing::Affinity;
+use crate::signing::sign_request;
+use crate::sponsor::{execute, parse_address, CreateWalletAuthorization, Sponsor};
+use crate::AppState;
+
+/// Enclave route authorizing a wallet creation
+const CREATE_WALLET_PATH: &str = "/create_wallet";
+/// Intent wallet creations are signed under (CREATE_WALLET_INTENT in core.move)
+const CREATE_WALLET_INTENT: u8 = 0;
+/// Window of the sponsor's daily limit
+const DAILY_WINDOW_MS: i64 = 24 * 60 * 60 * 1000;
+/// How often submission checks whether the indexer has seen the wallet
+const WALLET_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Enclave create-wallet response (payload fields we need)
+#[derive(Deserialize)]
+struct SignedCreateWallet {
+    payload: CreateWalletPayload,
+    timestamp_ms: u64,
+    signature: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CreateWalletPayload {
+    handle: Vec<u8>,
+}
+
+/// Prepare a sponsored wallet creation for the user to sign
+///
+/// POST /api/onboard
+pub async fn start_onboarding(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<OnboardRequest>,
+) -> Result<Json<OnboardStartResponse>, StatusCode> {
+    let sponsor = configured_sponsor(&state)?;
+    if request.handle.is_empty() || parse_address(&request.sender).is_err() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let already_onboarded = Database::onboarding_executed(&state.db, &request.handle, &request.sender)
+        .await
+        .map_err(|e| {
+            error!("Failed to check onboardings: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    let wallet_id = Database::get_wallet_id(&state.db, &request.handle)
+        .await
+        .map_err(|e| {
+            error!("Failed to fetch wallet id: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    if already_onboarded || wallet_id.is_some() {
+        return Err(StatusCode::CONFLICT);
+    }
+    check_reservation(&state, &request.handle, request.reservation_token.as_deref()).await?;
+    check_daily_limit(&state, sponsor).await?;
+
+    let authorization = authorize_create_wallet(&state, &request).await?;
+    let tx_bytes = sponsor
+        .build_create_wallet(&state.sui_rpc, &request.sender, &authorization, &state.gas)
+        .await
+        .map_err(|e| {
+            error!("Failed to prepare onboarding for {}: {}", request.handle, e);
+            StatusCode::BAD_GATEWAY
+        })?;
+
+    let created_at_ms = now_ms();
+    let id = Database::insert_onboarding(&state.db, &request.handle, &request.sender, &tx_bytes, created_at_ms)
+        .await
+        .map_err(|e| {
+            error!("Failed to store onboarding: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(OnboardStartResponse {
+        id,
+        tx_bytes: STANDARD.encode(&tx_bytes),
+        sponsor: sponsor.address(),
+        expires_at_ms: created_at_ms + sponsor.config.prepared_ttl.as_millis() as i64,
+    }))
+}
+
+/// Submit a prepared wallet creation with the user's signature
+///
+/// POST /api/onboard/:id — 200 with the wallet once indexed, 202 if the
+/// transaction executed but the wallet hasn't been indexed yet
+pub async fn submit_onboarding(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    Json(request): Json<OnboardSubmitRequest>,
+) -> Result<(StatusCode, Json<Onboarding>), StatusCode> {
+    let sponsor = configured_sponsor(&state)?;
+    let onboarding = load(&state, id).await?;
+    if onboarding.status != "pending" {
+        return Err(StatusCode::CONFLICT);
+    }
+    if now_ms() > onboarding.created_at_ms + sponsor.config.prepared_ttl.as_millis() as i64 {
+        return Err(StatusCode::GONE);
+    }
+    check_daily_limit(&state, sponsor).await?;
+
+    // Claiming first keeps a double submit from executing twice
+    let claimed = Database::claim_onboarding(&state.db, id, now_ms()).await.map_err(|e| {
+        error!("Failed to claim onboarding {}: {}", id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    if !claimed {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let signatures = [request.signature, sponsor.sign(&onboarding.tx_bytes)];
+    let outcome = execute(&state.sui_rpc, &onboarding.tx_bytes, &signatures).await;
+    let finished = match &outcome {
+        Ok(digest) => {
+            Database::finish_onboarding(&state.db, id, "executed", Some(digest), None, now_ms()).await
+        }
+        Err(e) => {
+            Database::finish_onboarding(&state.db, id, "failed", None, Some(&e.to_string()), now_ms()).await
+        }
+    };
+    if let Err(e) = finished {
+        error!("Failed to record outcome of onboarding {}: {}", id, e);
+    }
+    let digest = outcome.map_err(|e| {
+        warn!("Sponsored wallet creation for {} failed: {}", onboarding.handle, e);
+        StatusCode::UNPROCESSABLE_ENTITY
+    })?;
+    info!("Sponsored wallet creation for {} executed in {}", onboarding.handle, digest);
+
+    let status = match wait_for_wallet(&state, &onboarding.handle, sponsor.config.wallet_timeout).await {
+        Some(wallet_id) => {
+            if let Err(e) = Database::set_onboarding_wallet(&state.db, id, &wallet_id, now_ms()).await {
+                error!("Failed to record wallet of onboarding {}: {}", id, e);
+            }
+            StatusCode::OK
+        }
+        None => StatusCode::ACCEPTED,
+    };
+
+    Ok((status, Json(load(&state, id).await?)))
+}
+
+/// Get a sponsored wallet creation and its outcome
+///
+/// GET /api/onboard/:id
+pub async fn get_onboarding(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Result<Json<Onboarding>, StatusCode> {
+    let mut onboarding = load(&state, id).await?;
+
+    // Submission may have timed out before the indexer caught up
+    if onboarding.status == "executed" && onboarding.wallet_id.is_none() {
+        onboarding.wallet_id = Database::get_wallet_id(&state.read_db, &onboarding.handle)
+            .await
+            .map_err(|e| {
+                error!("Failed to fetch wallet id: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+    }
+
+    Ok(Json(onboarding))
+}
+
+fn configured_sponsor(state: &AppState) -> Result<&Sponsor, StatusCode> {
+    state.sponsor.as_ref().ok_or_else(|| {
+        warn!("Onboarding requested but SPONSOR_PRIVATE_KEY is not set");
+        StatusCode::SERVICE_UNAVAILABLE
+    })
+}
+
+async fn load(state: &AppState, id: i64) -> Result<Onboarding, StatusCode> {
+    Database::get_onboarding(&state.db, id)
+        .await
+        .map_err(|e| {
+            error!("Failed to fetch onboarding {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// 429 once the sponsor has paid for its daily share of wallet creations
+async fn check_daily_limit(state: &AppState, sponsor: &Sponsor) -> Result<(), StatusCode> {
+    let executed = Database::count_onboardings_executed_since(&state.db, now_ms() - DAILY_WINDOW_MS)
+        .await
+        .map_err(|e| {
+            error!("Failed to count onboardings: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    if executed >= sponsor.config.daily_limit {
+        warn!("Sponsor daily limit of {} wallet creations reached", sponsor.config.daily_limit);
+        return Err(StatusCode::TOO_MANY_REQUESTS);
+    }
+    Ok(())
+}
+
+/// Ask the handle's enclave to sign the wallet creation, with the request's
+/// registration-gate proof, passing its refusals through; the signature must
+/// verify before the sponsor pays for it
+async fn authorize_create_wallet(
+    state: &AppState,
+    request: &OnboardRequest,
+) -> Result<CreateWalletAuthorization, StatusCode> {
+    let handle = request.handle.as_str();
+    let body = serde_json::to_vec(&json!({
+        "payload": {
+            "handle": handle,
+            "pow_nonce": request.pow_nonce,
+            "captcha_token": request.captcha_token,
+        }
+    }))
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let enclave = state.enclaves.route(&Affinity::Handle(handle.to_string()));
+    let request = Client::new()
+        .post(format!("{}{}", enclave.url, CREATE_WALLET_PATH))
+        .header("content-type", "application/json")
+        .timeout(state.proxy.default_timeout);
+    let response = sign_request(request, state.nautilus_signing_key.as_deref(), "POST", CREATE_WALLET_PATH, &body)
+        .body(body)
+        .send()
+        .await
+        .map_err(|e| {
+            error!("Failed to reach Nautilus at {} for onboarding: {}", enclave.url, e);
+            StatusCode::BAD_GATEWAY
+        })?;
+
+    if !response.status().is_success() {
+        warn!("Nautilus refused wallet creation for {}: {}", handle, response.status());
+        return Err(StatusCode::from_u16(response.status().as_u16()).unwrap_or(StatusCode::BAD_GATEWAY));
+    }
+    let signed: SignedCreateWallet = response.json().await.map_err(|e| {
+        error!("Failed to parse Nautilus create-wallet response: {}", e);
+        StatusCode::BAD_GATEWAY
+    })?;
+    if !enclave.verifies(CREATE_WALLET_INTENT, signed.timestamp_ms, &signed.payload, &signed.signature) {
+        error!("Nautilus at {} returned a create-wallet signature that doesn't verify", enclave.url);
+        return Err(StatusCode::BAD_GATEWAY);
+    }
+    let signature = hex::decode(&signed.signature).map_err(|e| {
+        error!("Nautilus returned a malformed signature: {}", e);
+        StatusCode::BAD_GATEWAY
+    })?;
+
+    Ok(CreateWalletAuthorization {
+        handle: signed.payload.handle,
+        timestamp_ms: signed.timestamp_ms,
+        signature,
+        enclave: enclave.index,
+    })
+}
+
+/// Poll the indexer for the handle's wallet until it appears or time runs out
+async fn wait_for_wallet(state: &AppState, handle: &str, timeout: Duration) -> Option<String> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        match Database::get_wallet_id(&state.db, handle).await {
+            Ok(Some(wallet_id)) => return Some(wallet_id),
+            Ok(None) => {}
+            Err(e) => warn!("Failed to check for {}'s wallet: {}", handle, e),
+        }
+        if Instant::now() + WALLET_POLL_INTERVAL > deadline {
+            return None;
+        }
+        tokio::time::sleep(WALLET_POLL_INTERVAL).await;
+    }
+}
+
+fn now_ms() -> i64 {
+    chrono::Utc::now().timestamp_millis()
+}
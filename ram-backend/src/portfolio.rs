@@ -0,0 +1,196 @@
+// Multi-coin portfolio view: indexed coin flows combined with live balances
+// Responses are cached per handle until the indexer reports a change to it
+// (see changes.rs) or READ_CACHE_TTL_SECS passes.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use anyhow::{anyhow, Result};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tracing::{error, warn};
+
+use crate::database::Database;
+use crate::handles::normalize_handle;
+use crate::models::{CoinFlows, CoinPosition, PortfolioResponse};
+use crate::rpc::SuiRpcClient;
+use crate::AppState;
+
+/// Get a wallet's per-coin history and balances
+///
+/// GET /api/portfolio/:handle
+pub async fn get_portfolio(
+    State(state): State<Arc<AppState>>,
+    Path(handle): Path<String>,
+) -> Result<Json<PortfolioResponse>, StatusCode> {
+    let handle = normalize_handle(&handle);
+    if let Some(portfolio) = state.read_cache.portfolio(&handle) {
+        return Ok(Json(portfolio));
+    }
+    let generation = state.read_cache.generation();
+
+    let flows = Database::get_coin_flows(&state.read_db, &handle)
+        .await
+        .map_err(|e| {
+            error!("Failed to fetch coin flows: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    let wallet_id = Database::get_wallet_id(&state.read_db, &handle)
+        .await
+        .map_err(|e| {
+            error!("Failed to fetch wallet id: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    // Live balances are best-effort: indexed history is still useful if the fullnode is down
+    let balances = match &wallet_id {
+        Some(id) => match fetch_wallet_balances(&state.sui_rpc, id).await {
+            Ok(balances) => Some(balances),
+            Err(e) => {
+                warn!("Failed to fetch live balances for {}: {}", handle, e);
+                None
+            }
+        },
+        None => None,
+    };
+    let balances_read = wallet_id.is_none() || balances.is_some();
+
+    let portfolio = PortfolioResponse {
+        handle,
+        wallet_id,
+        coins: merge_positions(flows, balances),
+    };
+    // Without live balances the response is incomplete; the next read retries them
+    if balances_read {
+        state.read_cache.put_portfolio(generation, &portfolio);
+    }
+    Ok(Json(portfolio))
+}
+
+/// Combine indexed flows with live balances, including coins only one side knows about
+fn merge_positions(
+    flows: Vec<CoinFlows>,
+    balances: Option<BTreeMap<String, u64>>,
+) -> Vec<CoinPosition> {
+    let mut by_coin: BTreeMap<String, CoinFlows> = flows
+        .into_iter()
+        .map(|f| (f.coin_type.clone(), f))
+        .collect();
+    for coin_type in balances.iter().flat_map(|b| b.keys()) {
+        by_coin.entry(coin_type.clone()).or_insert_with(|| CoinFlows {
+            coin_type: coin_type.clone(),
+            deposited: 0,
+            withdrawn: 0,
+            transferred_in: 0,
+            transferred_out: 0,
+        });
+    }
+
+    by_coin
+        .into_values()
+        .map(|flows| CoinPosition {
+            net_flow: flows.deposited + flows.transferred_in
+                - flows.withdrawn
+                - flows.transferred_out,
+            // A coin missing from a readable Bag has a zero balance
+            live_balance: balances
+                .as_ref()
+                .map(|b| b.get(&flows.coin_type).copied().unwrap_or(0)),
+            flows,
+        })
+        .collect()
+}
+
+/// Read every `Balance<T>` held in a wallet's `balances` Bag, keyed by coin type
+pub(crate) async fn fetch_wallet_balances(rpc: &SuiRpcClient, wallet_id: &str) -> Result<BTreeMap<String, u64>> {
+    let wallet = rpc
+        .call("sui_getObject", json!([wallet_id, { "showContent": true }]))
+        .await?;
+    let bag_id = wallet["data"]["content"]["fields"]["balances"]["fields"]["id"]["id"]
+        .as_str()
+        .ok_or_else(|| anyhow!("Wallet {} has no balances bag", wallet_id))?
+        .to_string();
+
+    // Bag entries are dynamic fields keyed by the coin's type name
+    let mut entries = Vec::new();
+    let mut cursor = Value::Null;
+    loop {
+        let page = rpc
+            .call("suix_getDynamicFields", json!([bag_id, cursor, null]))
+            .await?;
+        for field in page["data"].as_array().into_iter().flatten() {
+            if let (Some(coin_type), Some(object_id)) =
+                (field["name"]["value"].as_str(), field["objectId"].as_str())
+            {
+                entries.push((coin_type.to_string(), object_id.to_string()));
+            }
+        }
+        if !page["hasNextPage"].as_bool().unwrap_or(false) {
+            break;
+        }
+        cursor = page["nextCursor"].clone();
+    }
+
+    if entries.is_empty() {
+        return Ok(BTreeMap::new());
+    }
+
+    let ids: Vec<&str> = entries.iter().map(|(_, id)| id.as_str()).collect();
+    let objects = rpc
+        .call("sui_multiGetObjects", json!([ids, { "showContent": true }]))
+        .await?;
+    let objects = objects
+        .as_array()
+        .ok_or_else(|| anyhow!("Unexpected sui_multiGetObjects response"))?;
+
+    let mut balances = BTreeMap::new();
+    for ((coin_type, _), object) in entries.into_iter().zip(objects) {
+        let value = &object["data"]["content"]["fields"]["value"];
+        // Balance<T> is rendered either as its u64 value or as { fields: { value } }
+        let amount = value
+            .as_str()
+            .or_else(|| value["fields"]["value"].as_str())
+            .and_then(|v| v.parse::<u64>().ok())
+            .ok_or_else(|| anyhow!("Unreadable balance for {}", coin_type))?;
+        balances.insert(coin_type, amount);
+    }
+
+    Ok(balances)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flows(coin_type: &str, deposited: i64, transferred_out: i64) -> CoinFlows {
+        CoinFlows {
+            coin_type: coin_type.to_string(),
+            deposited,
+            withdrawn: 0,
+            transferred_in: 0,
+            transferred_out,
+        }
+    }
+
+    #[test]
+    fn test_merge_positions_includes_both_sides() {
+        let balances = BTreeMap::from([("0x2::sui::SUI".to_string(), 4), ("0xusdc::usdc::USDC".to_string(), 7)]);
+        let coins = merge_positions(vec![flows("0x2::sui::SUI", 10, 6)], Some(balances));
+
+        assert_eq!(coins.len(), 2);
+        assert_eq!(coins[0].flows.coin_type, "0x2::sui::SUI");
+        assert_eq!(coins[0].net_flow, 4);
+        assert_eq!(coins[0].live_balance, Some(4));
+        assert_eq!(coins[1].net_flow, 0);
+        assert_eq!(coins[1].live_balance, Some(7));
+    }
+
+    #[test]
+    fn test_merge_positions_without_live_balances() {
+        let coins = merge_positions(vec![flows("0x2::sui::SUI", 10, 0)], None);
+        assert_eq!(coins[0].live_balance, None);
+    }
+}
@@ -0,0 +1,307 @@
+// Transfer preview: a dry run of the transfer policy chain
+// `POST /api/transfer/preview` takes the payload the proxied `/transfer`
+// would (contact nicknames and refunds included) and, without signing or
+// storing anything, reports what the transfer would do: the balance left
+// afterwards, the gas to budget (from a dry run of the built transaction,
+// when it's sent along), and each check that could stop it: the
+// recipient's wallet, locks and freezes on either wallet, the balance, the
+// sender's daily limit and a travel-rule envelope. Daily limits are a soft
+// estimate: the contract's window starts at the first spend in it, while the
+// preview counts the last 24 hours of indexed spending, so a transfer close
+// to the limit is a warning rather than a block. Compliance screening runs
+// in the enclave and isn't previewed.
+
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tracing::{error, warn};
+
+use crate::database::{json_u64, Database};
+use crate::models::{GasEstimate, PolicyCheck, PolicyEvent, TransferPreview};
+use crate::portfolio::fetch_wallet_balances;
+use crate::travel_rule::coin_key;
+use crate::wallet_auth::authenticate;
+use crate::{contacts, gas, refunds, AppState};
+
+/// The contract's daily limit window
+const LIMIT_WINDOW_MS: i64 = 86_400_000;
+/// Proxied route whose payload is previewed
+const TRANSFER_PATH: &str = "/transfer";
+
+/// Locks, freezes and daily limits of a wallet, as its indexed events leave them
+#[derive(Debug, Default, PartialEq, Eq)]
+struct WalletPolicy {
+    /// Until when a duress lock holds (0 = never locked)
+    locked_until_ms: i64,
+    /// Until when the wallet is frozen; `i64::MAX` until an unfreeze is requested
+    frozen_until_ms: Option<i64>,
+    /// Daily limit per coin (keyed by `coin_key`)
+    daily_limits: BTreeMap<String, i64>,
+}
+
+impl WalletPolicy {
+    fn from_events(events: &[PolicyEvent]) -> Self {
+        let mut policy = Self::default();
+        let field = |event: &PolicyEvent, name: &str| {
+            event.raw_json.as_ref().and_then(|raw| json_u64(&raw[name])).map(|v| v.min(i64::MAX as u64) as i64)
+        };
+        for event in events {
+            match event.event_type.as_str() {
+                "WalletLocked" => {
+                    policy.locked_until_ms = policy.locked_until_ms.max(field(event, "locked_until_ms").unwrap_or(0));
+                }
+                "WalletFrozen" => policy.frozen_until_ms = Some(i64::MAX),
+                "WalletUnfrozen" => policy.frozen_until_ms = field(event, "unfrozen_at_ms"),
+                "DailyLimitSet" => {
+                    if let (Some(coin_type), Some(limit)) = (&event.coin_type, event.amount) {
+                        policy.daily_limits.insert(coin_key(coin_type), limit);
+                    }
+                }
+                _ => {}
+            }
+        }
+        policy
+    }
+
+    /// Why the wallet can't move funds at `now_ms`, if it can't
+    fn blocked(&self, now_ms: i64) -> Option<String> {
+        if self.frozen_until_ms.is_some_and(|until| until > now_ms) {
+            return Some(match self.frozen_until_ms {
+                Some(i64::MAX) => "is frozen".to_string(),
+                Some(until) => format!("is frozen until {}", until),
+                None => unreachable!(),
+            });
+        }
+        (self.locked_until_ms > now_ms).then(|| format!("is locked until {}", self.locked_until_ms))
+    }
+}
+
+fn check(name: &str, outcome: &str, message: impl Into<String>) -> PolicyCheck {
+    PolicyCheck { check: name.to_string(), outcome: outcome.to_string(), message: message.into() }
+}
+
+/// The daily limit check for sending `amount` with `spent` already sent in
+/// the last 24 hours
+fn daily_limit_check(limit: Option<i64>, spent: i64, amount: i64) -> PolicyCheck {
+    match limit {
+        None => check("daily_limit", "pass", "No daily limit for this coin"),
+        Some(limit) if amount > limit => {
+            check("daily_limit", "block", format!("Amount exceeds the daily limit of {}", limit))
+        }
+        Some(limit) if spent.saturating_add(amount) > limit => check(
+            "daily_limit",
+            "warn",
+            format!("{} of the daily limit of {} was spent in the last 24 hours; this may exceed it", spent, limit),
+        ),
+        Some(limit) => check("daily_limit", "pass", format!("{} left of the daily limit of {}", limit - spent - amount, limit)),
+    }
+}
+
+/// Preview a transfer without signing it
+///
+/// POST /api/transfer/preview (signed by the sender, scope `transfers`,
+/// action `read`) — the `/transfer` payload, and optionally the built
+/// transaction as `tx_bytes` (base64) to budget gas from its dry run; 400
+/// without `from_handle`, a recipient, `amount` or `coin_type`, or with
+/// `tx_bytes` that aren't base64, 413 if they're past Sui's size limit
+pub async fn preview_transfer(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<TransferPreview>, StatusCode> {
+    let payload: Value = serde_json::from_slice(&body).map_err(|_| StatusCode::BAD_REQUEST)?;
+    let from_handle = payload["from_handle"].as_str().ok_or(StatusCode::BAD_REQUEST)?.to_string();
+    let tx_bytes = match payload["tx_bytes"].as_str() {
+        Some(encoded) => Some(STANDARD.decode(encoded.trim()).map_err(|_| StatusCode::BAD_REQUEST)?),
+        None => None,
+    };
+    if tx_bytes.as_ref().is_some_and(|bytes| bytes.len() > gas::MAX_TX_BYTES) {
+        return Err(StatusCode::PAYLOAD_TOO_LARGE);
+    }
+    authenticate(&state, &headers, "transfers", "read", &from_handle, &body).await?;
+
+    let mut checks = Vec::new();
+    // The same resolution the proxy applies, on a copy that's never forwarded
+    let wrapped = Bytes::from(json!({ "payload": payload }).to_string());
+    let resolved = match contacts::resolve_recipient(&state, TRANSFER_PATH, wrapped).await {
+        Ok(resolved) => refunds::apply_refund(&state, TRANSFER_PATH, resolved).await,
+        Err(status) => Err(status),
+    };
+    let payload = match resolved {
+        Ok(resolved) => serde_json::from_slice::<Value>(&resolved).map_err(|_| StatusCode::BAD_REQUEST)?["payload"].take(),
+        Err(status) => {
+            checks.push(check("recipient", "block", format!("Recipient couldn't be resolved ({})", status)));
+            payload
+        }
+    };
+    let (Some(to_handle), Some(amount), Some(coin_type)) =
+        (payload["to_handle"].as_str(), payload["amount"].as_i64(), payload["coin_type"].as_str())
+    else {
+        return Err(StatusCode::BAD_REQUEST);
+    };
+    let memo = payload["memo"].as_str().map(str::to_string);
+    let internal = |e: anyhow::Error| {
+        error!("Failed to preview transfer: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
+
+    let now_ms = chrono::Utc::now().timestamp_millis();
+    let from_wallet = Database::get_wallet_id(&state.read_db, &from_handle).await.map_err(internal)?;
+    let to_wallet = Database::get_wallet_id(&state.read_db, to_handle).await.map_err(internal)?;
+    if checks.is_empty() {
+        checks.push(match &to_wallet {
+            Some(_) => check("recipient", "pass", format!("{} has a wallet", to_handle)),
+            None => check("recipient", "block", format!("{} has no wallet", to_handle)),
+        });
+    }
+
+    // Both wallets must be neither locked nor frozen
+    let sender = WalletPolicy::from_events(&Database::get_policy_events(&state.read_db, &from_handle).await.map_err(internal)?);
+    let recipient = WalletPolicy::from_events(&Database::get_policy_events(&state.read_db, to_handle).await.map_err(internal)?);
+    checks.push(match (sender.blocked(now_ms), recipient.blocked(now_ms)) {
+        (Some(why), _) => check("wallet_state", "block", format!("{} {}", from_handle, why)),
+        (None, Some(why)) => check("wallet_state", "block", format!("{} {}", to_handle, why)),
+        (None, None) => check("wallet_state", "pass", "Neither wallet is locked or frozen"),
+    });
+
+    // Live balances are best-effort, like the portfolio's
+    let balance = match &from_wallet {
+        Some(id) => match fetch_wallet_balances(&state.sui_rpc, id).await {
+            Ok(balances) => Some(
+                balances
+                    .iter()
+                    .filter(|(coin, _)| coin_key(coin) == coin_key(coin_type))
+                    .map(|(_, balance)| *balance as i64)
+                    .sum::<i64>(),
+            ),
+            Err(e) => {
+                warn!("Failed to fetch live balances for {}: {}", from_handle, e);
+                None
+            }
+        },
+        None => None,
+    };
+    let projected_balance = balance.map(|b| b - amount);
+    checks.push(match (&from_wallet, projected_balance) {
+        (None, _) => check("balance", "block", format!("{} has no wallet", from_handle)),
+        (Some(_), None) => check("balance", "unknown", "Live balance unavailable"),
+        (Some(_), Some(left)) if left < 0 => check("balance", "block", "Insufficient balance"),
+        (Some(_), Some(_)) => check("balance", "pass", "Balance covers the amount"),
+    });
+
+    let spent = Database::get_recent_spending(&state.read_db, &from_handle, now_ms - LIMIT_WINDOW_MS)
+        .await
+        .map_err(internal)?
+        .into_iter()
+        .filter(|(coin, _)| coin_key(coin) == coin_key(coin_type))
+        .map(|(_, spent)| spent)
+        .sum();
+    checks.push(daily_limit_check(sender.daily_limits.get(&coin_key(coin_type)).copied(), spent, amount));
+
+    let threshold = state.travel_rule.threshold(coin_type).filter(|t| amount >= *t);
+    let has_envelope = !payload["travel_rule"].is_null();
+    checks.push(match (threshold, has_envelope) {
+        (Some(threshold), false) => {
+            check("travel_rule", "block", format!("Transfers of {} or more need a travel-rule envelope", threshold))
+        }
+        (_, true) => check("travel_rule", "pass", "Travel-rule envelope attached"),
+        (None, false) => check("travel_rule", "pass", "No travel-rule envelope needed"),
+    });
+
+    // Budgeted as /api/tx/estimate would; a dry run that aborts only covers
+    // the gas used up to the abort, so it gives no budget
+    let dry_run = match &tx_bytes {
+        Some(tx_bytes) => match gas::dry_run(&state.sui_rpc, tx_bytes, &state.gas).await {
+            Ok(estimate) if estimate.status == "success" => Some(estimate),
+            Ok(estimate) => {
+                warn!("Previewed transfer from {} would fail: {}", from_handle, estimate.error.unwrap_or_default());
+                None
+            }
+            Err(e) => {
+                warn!("Failed to dry-run a previewed transfer: {}", e);
+                None
+            }
+        },
+        None => None,
+    };
+    let estimated_gas = match dry_run {
+        Some(estimate) => GasEstimate { reference_gas_price: Some(estimate.gas_price), budget: Some(estimate.budget) },
+        None => {
+            let reference_gas_price = match state.sui_rpc.call("suix_getReferenceGasPrice", json!([])).await {
+                Ok(price) => json_u64(&price),
+                Err(e) => {
+                    warn!("Failed to fetch reference gas price: {}", e);
+                    None
+                }
+            };
+            GasEstimate { reference_gas_price, budget: None }
+        }
+    };
+
+    Ok(Json(TransferPreview {
+        allowed: !checks.iter().any(|c| c.outcome == "block"),
+        from_handle,
+        to_handle: to_handle.to_string(),
+        amount,
+        coin_type: coin_type.to_string(),
+        memo,
+        checks,
+        balance,
+        projected_balance,
+        estimated_gas,
+        // Every transfer is confirmed by voice (or typed) bio-auth
+        bio_auth_required: true,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(event_type: &str, raw_json: Value, coin_type: Option<&str>, amount: Option<i64>) -> PolicyEvent {
+        PolicyEvent {
+            event_type: event_type.to_string(),
+            coin_type: coin_type.map(str::to_string),
+            amount,
+            raw_json: Some(raw_json),
+            timestamp_ms: 0,
+        }
+    }
+
+    #[test]
+    fn test_wallet_policy_follows_locks_freezes_and_limits() {
+        let sui = "0000000000000000000000000000000000000000000000000000000000000002::sui::SUI";
+        let mut events = vec![
+            event("WalletLocked", json!({ "locked_until_ms": "2000" }), None, None),
+            event("DailyLimitSet", json!({}), Some(sui), Some(50)),
+            event("DailyLimitSet", json!({}), Some(sui), Some(20)),
+        ];
+        let policy = WalletPolicy::from_events(&events);
+        assert_eq!(policy.daily_limits.get("0x2::sui::SUI"), Some(&20));
+        assert_eq!(policy.blocked(1_000), Some("is locked until 2000".to_string()));
+        assert_eq!(policy.blocked(2_000), None);
+
+        events.push(event("WalletFrozen", json!({}), None, None));
+        assert_eq!(WalletPolicy::from_events(&events).blocked(5_000), Some("is frozen".to_string()));
+        events.push(event("WalletUnfrozen", json!({ "unfrozen_at_ms": 9000 }), None, None));
+        let policy = WalletPolicy::from_events(&events);
+        assert!(policy.blocked(8_999).is_some());
+        assert_eq!(policy.blocked(9_000), None);
+    }
+
+    #[test]
+    fn test_daily_limit_is_a_soft_warning_near_the_limit() {
+        assert_eq!(daily_limit_check(None, 0, 100).outcome, "pass");
+        assert_eq!(daily_limit_check(Some(100), 0, 60).outcome, "pass");
+        assert_eq!(daily_limit_check(Some(100), 50, 60).outcome, "warn");
+        assert_eq!(daily_limit_check(Some(100), 0, 101).outcome, "block");
+        assert_eq!(daily_limit_check(Some(0), 0, 1).outcome, "block");
+    }
+}
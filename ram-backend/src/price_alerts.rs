@@ -0,0 +1,307 @@
+// Balance value alerts
+// A wallet's owner (signing as usual, scope `price_alerts`) asks to be told
+// when the USD value of the wallet's balance of a coin drops below, or rises
+// above, a threshold. A background job values every alerted balance from
+// live balances and the price service (see prices.rs); when an alert's
+// condition starts to hold, the owner gets a `price_alert` notification.
+// The alert then stays quiet until the value is back REARM_MARGIN past the
+// threshold, so a balance hovering around it doesn't notify on every check.
+
+use anyhow::Result;
+use axum::{
+    body::Bytes,
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+use crate::database::{Database, DbPool};
+use crate::models::{
+    CreatePriceAlertRequest, DeletePriceAlertRequest, PriceAlert, PriceAlertsQuery, UpdatePriceAlertRequest,
+};
+use crate::portfolio::fetch_wallet_balances;
+use crate::prices::PriceService;
+use crate::profile::valid_coin_type;
+use crate::rpc::SuiRpcClient;
+use crate::travel_rule::coin_key;
+use crate::wallet_auth::authenticate;
+use crate::AppState;
+
+/// Notify when the value drops below the threshold
+pub const BELOW: &str = "below";
+/// Notify when the value rises above the threshold
+pub const ABOVE: &str = "above";
+/// Most price alerts one wallet can have
+pub const MAX_PRICE_ALERTS: i64 = 20;
+/// How far past the threshold, relative to it, the value must get back
+/// before a triggered alert can fire again
+const REARM_MARGIN: f64 = 0.01;
+
+/// List a wallet's price alerts
+///
+/// GET /api/price_alerts?handle= (signed, action `read`)
+pub async fn list_price_alerts(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<PriceAlertsQuery>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<PriceAlert>>, StatusCode> {
+    authenticate(&state, &headers, "price_alerts", "read", &query.handle, &[]).await?;
+
+    let alerts = Database::list_price_alerts(&state.db, &query.handle).await.map_err(|e| {
+        error!("Failed to list price alerts: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(Json(alerts))
+}
+
+/// Create a price alert, armed
+///
+/// POST /api/price_alerts (signed, action `create`) — 422 if the coin has
+/// no price, 409 once the wallet has MAX_PRICE_ALERTS
+pub async fn create_price_alert(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<PriceAlert>, StatusCode> {
+    let request: CreatePriceAlertRequest = serde_json::from_slice(&body).map_err(|_| StatusCode::BAD_REQUEST)?;
+    let coin_type = validate_condition(&state, &request.coin_type, &request.direction, request.threshold_usd)?;
+    authenticate(&state, &headers, "price_alerts", "create", &request.handle, &body).await?;
+
+    let count = Database::count_price_alerts(&state.db, &request.handle).await.map_err(|e| {
+        error!("Failed to count price alerts: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    if count >= MAX_PRICE_ALERTS {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let alert = Database::insert_price_alert(
+        &state.db,
+        &request.handle,
+        &coin_type,
+        &request.direction,
+        request.threshold_usd,
+        chrono::Utc::now().timestamp_millis(),
+    )
+    .await
+    .map_err(|e| {
+        error!("Failed to save price alert: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    info!("{} set price alert {} ({} {} ${})", alert.handle, alert.id, coin_type, alert.direction, alert.threshold_usd);
+
+    Ok(Json(alert))
+}
+
+/// Replace a price alert's condition, re-arming it
+///
+/// PUT /api/price_alerts (signed, action `update`) — 404 if the wallet has
+/// no such alert
+pub async fn update_price_alert(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<PriceAlert>, StatusCode> {
+    let request: UpdatePriceAlertRequest = serde_json::from_slice(&body).map_err(|_| StatusCode::BAD_REQUEST)?;
+    let coin_type = validate_condition(&state, &request.coin_type, &request.direction, request.threshold_usd)?;
+    authenticate(&state, &headers, "price_alerts", "update", &request.handle, &body).await?;
+
+    Database::update_price_alert(
+        &state.db,
+        &request.handle,
+        request.id,
+        &coin_type,
+        &request.direction,
+        request.threshold_usd,
+        chrono::Utc::now().timestamp_millis(),
+    )
+    .await
+    .map_err(|e| {
+        error!("Failed to update price alert: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?
+    .map(Json)
+    .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Remove a price alert
+///
+/// DELETE /api/price_alerts (signed, action `delete`)
+pub async fn delete_price_alert(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<StatusCode, StatusCode> {
+    let request: DeletePriceAlertRequest = serde_json::from_slice(&body).map_err(|_| StatusCode::BAD_REQUEST)?;
+    authenticate(&state, &headers, "price_alerts", "delete", &request.handle, &body).await?;
+
+    let deleted = Database::delete_price_alert(&state.db, &request.handle, request.id).await.map_err(|e| {
+        error!("Failed to delete price alert: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    if !deleted {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// The coin key an alert is stored under, if its condition can be evaluated
+///
+/// 503 without a price feed, 400 for a malformed condition, 422 for a coin
+/// the feed doesn't price.
+fn validate_condition(state: &AppState, coin_type: &str, direction: &str, threshold_usd: f64) -> Result<String, StatusCode> {
+    let prices = state.prices.as_ref().ok_or(StatusCode::SERVICE_UNAVAILABLE)?;
+    if !valid_coin_type(coin_type) || ![BELOW, ABOVE].contains(&direction) || !threshold_usd.is_finite() || threshold_usd <= 0.0 {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    if !prices.is_priced(coin_type) {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+    Ok(coin_key(coin_type))
+}
+
+/// What an alert's latest valuation calls for
+#[derive(Debug, PartialEq, Eq)]
+enum Transition {
+    Trigger,
+    Rearm,
+}
+
+fn transition(alert: &PriceAlert, value_usd: f64) -> Option<Transition> {
+    let threshold = alert.threshold_usd;
+    let (holds, cleared) = match alert.direction.as_str() {
+        BELOW => (value_usd < threshold, value_usd >= threshold * (1.0 + REARM_MARGIN)),
+        ABOVE => (value_usd > threshold, value_usd <= threshold * (1.0 - REARM_MARGIN)),
+        _ => return None,
+    };
+    match alert.triggered_at_ms {
+        None if holds => Some(Transition::Trigger),
+        Some(_) if cleared => Some(Transition::Rearm),
+        _ => None,
+    }
+}
+
+/// Value every alerted balance at `now_ms`, notifying the owners of alerts
+/// that start to hold; returns how many were notified
+///
+/// A wallet whose balances can't be read is skipped until the next check.
+pub async fn check_price_alerts(pool: &DbPool, rpc: &SuiRpcClient, prices: &PriceService, now_ms: i64) -> Result<usize> {
+    let alerts = Database::get_all_price_alerts(pool).await?;
+    if alerts.is_empty() {
+        return Ok(0);
+    }
+    let quotes = prices.prices().await?;
+
+    let mut notified = 0;
+    for wallet_alerts in alerts.chunk_by(|a, b| a.handle == b.handle) {
+        let handle = &wallet_alerts[0].handle;
+        let balances = match wallet_balances(pool, rpc, handle).await {
+            Ok(Some(balances)) => balances,
+            Ok(None) => continue,
+            Err(e) => {
+                warn!("Skipping {}'s price alerts: {}", handle, e);
+                continue;
+            }
+        };
+
+        for alert in wallet_alerts {
+            // A coin missing from the wallet has a zero balance
+            let balance = balances.get(&alert.coin_type).copied().unwrap_or(0);
+            let value_usd = match prices.value_usd(&quotes, &alert.coin_type, balance).await {
+                Ok(Some(value)) => value,
+                Ok(None) => continue,
+                Err(e) => {
+                    warn!("Could not value {} for price alert {}: {}", alert.coin_type, alert.id, e);
+                    continue;
+                }
+            };
+
+            match transition(alert, value_usd) {
+                Some(Transition::Trigger) => {
+                    let data = json!({
+                        "alert_id": alert.id,
+                        "coin_type": alert.coin_type,
+                        "direction": alert.direction,
+                        "threshold_usd": alert.threshold_usd,
+                        "value_usd": value_usd,
+                        "balance": balance,
+                        "price_usd": quotes.get(&alert.coin_type),
+                    });
+                    if Database::trigger_price_alert(pool, alert, &data, now_ms).await? {
+                        notified += 1;
+                        info!(
+                            "Notified {} that its {} is worth ${:.2}, {} ${}",
+                            handle, alert.coin_type, value_usd, alert.direction, alert.threshold_usd
+                        );
+                    }
+                }
+                Some(Transition::Rearm) => Database::rearm_price_alert(pool, alert).await?,
+                None => {}
+            }
+        }
+    }
+
+    Ok(notified)
+}
+
+/// Live balances of a handle's wallet by coin key, None if it has no wallet
+async fn wallet_balances(pool: &DbPool, rpc: &SuiRpcClient, handle: &str) -> Result<Option<BTreeMap<String, u64>>> {
+    let Some(wallet_id) = Database::get_wallet_id(pool, handle).await? else {
+        return Ok(None);
+    };
+    let balances = fetch_wallet_balances(rpc, &wallet_id).await?;
+    Ok(Some(balances.into_iter().map(|(coin_type, amount)| (coin_key(&coin_type), amount)).collect()))
+}
+
+/// Check price alerts every `interval`, for as long as it runs (supervised,
+/// see supervisor.rs)
+pub async fn run_price_alerts(
+    pool: DbPool,
+    rpc: Arc<SuiRpcClient>,
+    prices: Arc<PriceService>,
+    interval: Duration,
+) -> Result<()> {
+    loop {
+        let now_ms = chrono::Utc::now().timestamp_millis();
+        if let Err(e) = check_price_alerts(&pool, &rpc, &prices, now_ms).await {
+            error!("Price alert check failed: {}", e);
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alert(direction: &str, triggered: bool) -> PriceAlert {
+        PriceAlert {
+            id: 1,
+            handle: "alice".to_string(),
+            coin_type: "0x2::sui::SUI".to_string(),
+            direction: direction.to_string(),
+            threshold_usd: 100.0,
+            triggered_at_ms: triggered.then_some(1),
+            created_at_ms: 0,
+            updated_at_ms: 0,
+        }
+    }
+
+    #[test]
+    fn test_alerts_fire_once_and_rearm_past_the_margin() {
+        assert_eq!(transition(&alert(BELOW, false), 99.0), Some(Transition::Trigger));
+        assert_eq!(transition(&alert(BELOW, false), 100.0), None);
+        assert_eq!(transition(&alert(BELOW, true), 50.0), None);
+        assert_eq!(transition(&alert(BELOW, true), 100.5), None);
+        assert_eq!(transition(&alert(BELOW, true), 101.0), Some(Transition::Rearm));
+
+        assert_eq!(transition(&alert(ABOVE, false), 101.0), Some(Transition::Trigger));
+        assert_eq!(transition(&alert(ABOVE, true), 99.5), None);
+        assert_eq!(transition(&alert(ABOVE, true), 99.0), Some(Transition::Rearm));
+    }
+}
@@ -0,0 +1,115 @@
+// USD prices of the coins wallets hold
+// Prices come from a CoinGecko-compatible `/simple/price` endpoint
+// (PRICE_FEED_URL), for the coins PRICE_FEED_COINS maps to the feed's IDs,
+// and are reused for PRICE_CACHE_SECS so callers can ask freely. Balances
+// are raw amounts: each coin's decimals are read once from its on-chain
+// metadata to turn them into whole coins.
+
+use anyhow::{anyhow, Result};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::config::PriceConfig;
+use crate::rpc::SuiRpcClient;
+use crate::travel_rule::coin_key;
+
+/// Deadline for the price feed to answer
+const FEED_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Cached prices and coin decimals
+pub struct PriceService {
+    feed_url: String,
+    config: PriceConfig,
+    client: reqwest::Client,
+    rpc: Arc<SuiRpcClient>,
+    /// USD per whole coin, keyed by coin key, and when it was fetched
+    prices: Mutex<Option<(Instant, BTreeMap<String, f64>)>>,
+    decimals: Mutex<HashMap<String, u8>>,
+}
+
+impl PriceService {
+    /// None if no price feed is configured
+    pub fn new(config: PriceConfig, rpc: Arc<SuiRpcClient>) -> Result<Option<Self>> {
+        let Some(feed_url) = config.feed_url.clone() else {
+            return Ok(None);
+        };
+        let client = reqwest::Client::builder()
+            .timeout(FEED_TIMEOUT)
+            .redirect(reqwest::redirect::Policy::none())
+            .build()?;
+        Ok(Some(Self {
+            feed_url,
+            config,
+            client,
+            rpc,
+            prices: Mutex::new(None),
+            decimals: Mutex::new(HashMap::new()),
+        }))
+    }
+
+    /// Whether the feed prices `coin_type` (any spelling of it)
+    pub fn is_priced(&self, coin_type: &str) -> bool {
+        self.config.coin_ids.contains_key(&coin_key(coin_type))
+    }
+
+    /// USD per whole coin of every priced coin the feed answered for, keyed
+    /// by `travel_rule::coin_key`
+    pub async fn prices(&self) -> Result<BTreeMap<String, f64>> {
+        if let Some((fetched_at, prices)) = self.prices.lock().unwrap().as_ref() {
+            if fetched_at.elapsed() < self.config.cache_ttl {
+                return Ok(prices.clone());
+            }
+        }
+
+        let ids: Vec<&str> = self.config.coin_ids.values().map(String::as_str).collect();
+        let response = self
+            .client
+            .get(format!("{}/simple/price", self.feed_url))
+            .query(&[("ids", ids.join(",")), ("vs_currencies", "usd".to_string())])
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(anyhow!("Price feed answered {}", response.status()));
+        }
+        let quotes: Value = response.json().await?;
+        let prices: BTreeMap<String, f64> = self
+            .config
+            .coin_ids
+            .iter()
+            .filter_map(|(coin, id)| {
+                let price = quotes[id.as_str()]["usd"].as_f64()?;
+                (price.is_finite() && price >= 0.0).then(|| (coin.clone(), price))
+            })
+            .collect();
+
+        *self.prices.lock().unwrap() = Some((Instant::now(), prices.clone()));
+        Ok(prices)
+    }
+
+    /// Decimals of `coin_type`, from its CoinMetadata
+    pub async fn decimals(&self, coin_type: &str) -> Result<u8> {
+        let key = coin_key(coin_type);
+        if let Some(decimals) = self.decimals.lock().unwrap().get(&key) {
+            return Ok(*decimals);
+        }
+        let metadata = self.rpc.call("suix_getCoinMetadata", json!([key])).await?;
+        let decimals = metadata["decimals"]
+            .as_u64()
+            .and_then(|d| u8::try_from(d).ok())
+            .ok_or_else(|| anyhow!("No coin metadata for {}", key))?;
+        self.decimals.lock().unwrap().insert(key, decimals);
+        Ok(decimals)
+    }
+
+    /// USD value of a raw `amount` of `coin_type` at `prices`, None if the
+    /// coin has no price
+    pub async fn value_usd(&self, prices: &BTreeMap<String, f64>, coin_type: &str, amount: u64) -> Result<Option<f64>> {
+        let Some(price) = prices.get(&coin_key(coin_type)) else {
+            return Ok(None);
+        };
+        let decimals = self.decimals(coin_type).await?;
+        Ok(Some(amount as f64 / 10f64.powi(decimals.into()) * price))
+    }
+}
@@ -0,0 +1,214 @@
+// Wallet profiles: display name, avatar, locale and notification settings
+// Anyone can read the public part a receive link or QR code shows; the rest,
+// and every change, needs a request signed by an address linked to the
+// wallet (see wallet_auth). The events API shows counterparties by their
+// display names.
+
+use axum::{
+    body::Bytes,
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use std::sync::Arc;
+use tracing::{error, info};
+
+use crate::database::Database;
+use crate::models::{Profile, ReceiveInfo, UpdateProfileRequest};
+use crate::wallet_auth::authenticate;
+use crate::AppState;
+
+/// Coins a wallet accepts until its profile says otherwise
+pub const DEFAULT_ACCEPTED_COIN_TYPES: &[&str] = &["0x2::sui::SUI"];
+/// Longest display name, in characters
+pub const MAX_DISPLAY_NAME_LEN: usize = 64;
+/// Most coin types a profile can list
+pub const MAX_ACCEPTED_COIN_TYPES: usize = 20;
+/// Longest locale tag
+pub const MAX_LOCALE_LEN: usize = 35;
+
+/// Get the data for a wallet's receive link
+///
+/// GET /api/receive/:handle
+pub async fn get_receive_info(
+    State(state): State<Arc<AppState>>,
+    Path(handle): Path<String>,
+) -> Result<Json<ReceiveInfo>, StatusCode> {
+    let profile = Database::get_profile(&state.read_db, &handle).await.map_err(|e| {
+        error!("Failed to fetch profile: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    if let Some(profile) = profile {
+        return Ok(Json(ReceiveInfo {
+            handle: profile.handle,
+            display_name: profile.display_name,
+            accepted_coin_types: profile.accepted_coin_types,
+            avatar_hash: profile.avatar_hash,
+        }));
+    }
+
+    // Wallets without a profile can still be paid, in the default coins
+    let registered = Database::get_registered_handle(&state.read_db, &handle).await.map_err(|e| {
+        error!("Failed to fetch wallet: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(Json(ReceiveInfo {
+        handle: registered.ok_or(StatusCode::NOT_FOUND)?,
+        display_name: None,
+        accepted_coin_types: DEFAULT_ACCEPTED_COIN_TYPES.iter().map(|t| t.to_string()).collect(),
+        avatar_hash: None,
+    }))
+}
+
+/// Get a wallet's full profile, settings included
+///
+/// GET /api/profile/:handle (signed, action `read`)
+pub async fn get_profile(
+    State(state): State<Arc<AppState>>,
+    Path(handle): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<Profile>, StatusCode> {
+    authenticate(&state, &headers, "profile", "read", &handle, &[]).await?;
+
+    let profile = Database::get_profile(&state.db, &handle).await.map_err(|e| {
+        error!("Failed to fetch profile: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    profile.map(Json).ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Create or replace a wallet's profile
+///
+/// PUT /api/profile/:handle (signed, action `update`) — 409 if a newer
+/// update is already stored
+pub async fn update_profile(
+    State(state): State<Arc<AppState>>,
+    Path(handle): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<Profile>, StatusCode> {
+    let request: UpdateProfileRequest = serde_json::from_slice(&body).map_err(|_| StatusCode::BAD_REQUEST)?;
+    let display_name = request
+        .display_name
+        .as_deref()
+        .map(str::trim)
+        .filter(|name| !name.is_empty());
+    let avatar_hash = request.avatar_hash.as_deref().map(str::to_lowercase);
+    let valid = display_name.is_none_or(valid_display_name)
+        && !request.accepted_coin_types.is_empty()
+        && request.accepted_coin_types.len() <= MAX_ACCEPTED_COIN_TYPES
+        && request.accepted_coin_types.iter().all(|t| valid_coin_type(t))
+        && avatar_hash.as_deref().is_none_or(valid_avatar_hash)
+        && request.preferred_locale.as_deref().is_none_or(valid_locale);
+    if !valid {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let signed = authenticate(&state, &headers, "profile", "update", &handle, &body).await?;
+
+    let profile = Profile {
+        handle,
+        display_name: display_name.map(str::to_string),
+        accepted_coin_types: request.accepted_coin_types,
+        avatar_hash,
+        preferred_locale: request.preferred_locale,
+        notifications: serde_json::to_value(&request.notifications).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?,
+        updated_by: signed.address,
+        updated_at_ms: signed.timestamp_ms,
+    };
+    let stored = Database::upsert_profile(&state.db, &profile).await.map_err(|e| {
+        error!("Failed to store profile: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    if !stored {
+        return Err(StatusCode::CONFLICT);
+    }
+    info!("Profile of {} updated by {}", profile.handle, profile.updated_by);
+
+    Ok(Json(profile))
+}
+
+/// Delete a wallet's profile; the receive link falls back to the defaults
+///
+/// DELETE /api/profile/:handle (signed, action `delete`)
+pub async fn delete_profile(
+    State(state): State<Arc<AppState>>,
+    Path(handle): Path<String>,
+    headers: HeaderMap,
+) -> Result<StatusCode, StatusCode> {
+    let signed = authenticate(&state, &headers, "profile", "delete", &handle, &[]).await?;
+
+    // Only profiles older than the signature, so a replay can't delete a newer one
+    let deleted = Database::delete_profile(&state.db, &handle, signed.timestamp_ms)
+        .await
+        .map_err(|e| {
+            error!("Failed to delete profile: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    if !deleted {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    info!("Profile of {} deleted by {}", handle, signed.address);
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+fn valid_display_name(name: &str) -> bool {
+    name.chars().count() <= MAX_DISPLAY_NAME_LEN && !name.chars().any(char::is_control)
+}
+
+/// `0x<address>::<module>::<Name>`, without type parameters
+pub(crate) fn valid_coin_type(coin_type: &str) -> bool {
+    let mut parts = coin_type.split("::");
+    let (Some(address), Some(module), Some(name), None) = (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return false;
+    };
+    let identifier = |s: &str| {
+        s.starts_with(|c: char| c.is_ascii_alphabetic()) && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    };
+    address.strip_prefix("0x").is_some_and(|hex| {
+        !hex.is_empty() && hex.len() <= 64 && hex.chars().all(|c| c.is_ascii_hexdigit())
+    }) && identifier(module)
+        && identifier(name)
+}
+
+fn valid_avatar_hash(hash: &str) -> bool {
+    hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// BCP 47-shaped: a 2-3 letter language, then alphanumeric subtags
+fn valid_locale(locale: &str) -> bool {
+    let mut subtags = locale.split('-');
+    let language = subtags.next().unwrap_or_default();
+    locale.len() <= MAX_LOCALE_LEN
+        && (2..=3).contains(&language.len())
+        && language.chars().all(|c| c.is_ascii_alphabetic())
+        && subtags.all(|s| (1..=8).contains(&s.len()) && s.chars().all(|c| c.is_ascii_alphanumeric()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_coin_types() {
+        assert!(valid_coin_type("0x2::sui::SUI"));
+        assert!(valid_coin_type(&format!("0x{}::usdc::USDC", "a".repeat(64))));
+        assert!(!valid_coin_type("SUI"));
+        assert!(!valid_coin_type("2::sui::SUI"));
+        assert!(!valid_coin_type("0x2::sui::SUI::extra"));
+        assert!(!valid_coin_type("0x2::coin::Coin<0x2::sui::SUI>"));
+    }
+
+    #[test]
+    fn test_locales() {
+        assert!(valid_locale("vi"));
+        assert!(valid_locale("vi-VN"));
+        assert!(valid_locale("zh-Hant-TW"));
+        assert!(!valid_locale("v"));
+        assert!(!valid_locale("vi-"));
+        assert!(!valid_locale("vi_VN"));
+        assert!(!valid_locale("english"));
+    }
+}
@@ -10,22 +10,66 @@ use axum::{
 use reqwest::Client;
 use serde_json::Value;
 use std::sync::Arc;
-use tracing::{error, info};
+use tracing::{debug, error, warn};
 
+use crate::aliases::RouteClass;
+use crate::api_keys;
+use crate::chaos;
+use crate::contacts;
+use crate::errors;
+use crate::forwarding::end_to_end_headers;
+use crate::refunds;
+use crate::reservations;
+use crate::travel_rule;
+use crate::routing::{Affinity, ENCLAVE_KEY_HEADER};
+use crate::signing::sign_request;
 use crate::AppState;
 
+/// Routes whose responses may carry a challenge or job ID to pin to the enclave
+const SESSION_ISSUING_PATHS: &[&str] = &["/bio_auth/challenge", "/unfreeze/challenge", "/bio_auth"];
+
 /// Generic proxy handler that forwards requests to Nautilus server
 pub async fn proxy_to_nautilus(
     State(state): State<Arc<AppState>>,
     req: Request<Body>,
 ) -> Result<Response, StatusCode> {
     let path = req.uri().path().to_string();
+    forward(&state, req, path).await
+}
+
+/// Forward a request to the enclave route its path is an alias of (see aliases.rs)
+///
+/// Fixed routes match first, so this only sees paths no other route serves.
+pub async fn proxy_alias(
+    State(state): State<Arc<AppState>>,
+    req: Request<Body>,
+) -> Result<Response, StatusCode> {
+    let alias = state.route_aliases.resolve(req.uri().path()).ok_or(StatusCode::NOT_FOUND)?;
+    let timeout = alias
+        .route_class
+        .parse::<RouteClass>()
+        .unwrap_or(RouteClass::Default)
+        .timeout(&state.proxy);
+    debug!("Route alias {} -> {}", alias.path, alias.upstream_path);
+
+    tokio::time::timeout(timeout, forward(&state, req, alias.upstream_path))
+        .await
+        .unwrap_or(Err(StatusCode::GATEWAY_TIMEOUT))
+}
+
+/// Forward a request to `path` on the enclave its affinity routes it to
+async fn forward(state: &AppState, req: Request<Body>, path: String) -> Result<Response, StatusCode> {
     let method_str = req.method().as_str().to_string();
     
-    info!("Proxying {} request to Nautilus: {}", method_str, path);
+    debug!("Proxying {} request to Nautilus: {}", method_str, path);
 
-    // Build Nautilus URL
-    let nautilus_url = format!("{}{}", state.nautilus_url, path);
+    // Inbound headers allowed through by the forwarding policy
+    let mut forwarded_headers = state.forward_headers.request_headers(req.headers());
+    // API keys are the backend's; the enclave never sees them
+    let api_key = api_keys::presented_key(req.headers()).map(str::to_string);
+    if api_key.is_some() {
+        forwarded_headers.remove(axum::http::header::AUTHORIZATION);
+    }
 
     // Extract body
     let body_bytes = axum::body::to_bytes(req.into_body(), usize::MAX)
@@ -34,53 +78,118 @@ pub async fn proxy_to_nautilus(
             error!("Failed to read request body: {}", e);
             StatusCode::BAD_REQUEST
         })?;
+    api_keys::check_transfer_initiation(state, api_key.as_deref(), &path, &body_bytes).await?;
+    // A reserved handle is only created by whoever holds the reservation
+    let body_bytes = reservations::apply_reservation(state, &path, body_bytes).await?;
+    // Transfers may name their recipient by contact nickname
+    let body_bytes = contacts::resolve_recipient(state, &path, body_bytes).await?;
+    // ... and pay out a merchant's refund by naming it
+    let body_bytes = refunds::apply_refund(state, &path, body_bytes).await?;
+    // Large transfers carry a travel-rule envelope, referenced in the memo
+    let body_bytes = travel_rule::apply_envelope(state, &path, body_bytes).await?;
+    let affinity = Affinity::of_request(&method_str, &path, &body_bytes);
 
-    // Forward request to Nautilus
     let client = Client::builder()
         .redirect(reqwest::redirect::Policy::none())
         .build()
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     let method = reqwest::Method::from_bytes(method_str.as_bytes())
         .map_err(|_| StatusCode::METHOD_NOT_ALLOWED)?;
-    
-    let response = client
-        .request(method, &nautilus_url)
-        .header("Content-Type", "application/json")
-        .body(body_bytes.to_vec())
-        .send()
-        .await
-        .map_err(|e| {
-            error!("Failed to proxy request to Nautilus: {}", e);
+
+    // Only GETs are retried: they're idempotent, while a repeated POST could
+    // get a second payload signed
+    let retries = if method == reqwest::Method::GET { state.proxy.get_retries } else { 0 };
+    let mut attempt = 0;
+    let (enclave, response) = loop {
+        // Routed afresh on every attempt, so a retry can go to another enclave
+        let enclave = state.enclaves.route(&affinity);
+
+        // Forward request to Nautilus, signed afresh on every attempt
+        chaos::upstream_latency().await;
+        let sent = if chaos::drop_nautilus_request() {
+            Err("connection dropped (injected fault)".to_string())
+        } else {
+            let request = client
+                .request(method.clone(), format!("{}{}", enclave.url, path))
+                .headers(forwarded_headers.clone())
+                .header("Content-Type", "application/json");
+            sign_request(request, state.nautilus_signing_key.as_deref(), &method_str, &path, &body_bytes)
+                .body(body_bytes.clone())
+                .send()
+                .await
+                .map_err(|e| {
+                    if e.is_connect() {
+                        state.enclaves.mark(enclave, false);
+                    }
+                    e.to_string()
+                })
+        };
+
+        match sent {
+            Ok(response) if attempt < retries && is_retryable(response.status()) => {
+                warn!("Nautilus answered {} to GET {}, retrying ({}/{})", response.status(), path, attempt + 1, retries);
+            }
+            Err(e) if attempt < retries => {
+                warn!("Failed to proxy GET {} to Nautilus: {}, retrying ({}/{})", path, e, attempt + 1, retries);
+            }
+            Ok(response) => break (enclave, response),
+            Err(e) => {
+                error!("Failed to proxy request to {}: {}", enclave.url, e);
+                return Err(StatusCode::BAD_GATEWAY);
+            }
+        }
+        tokio::time::sleep(state.proxy.retry_backoff * 2u32.saturating_pow(attempt)).await;
+        attempt += 1;
+    };
+
+    debug!("Nautilus response status from {}: {}", enclave.url, response.status());
+
+    let mut proxied = Response::builder().status(response.status());
+    if let Some(headers) = proxied.headers_mut() {
+        *headers = end_to_end_headers(response.headers());
+        if let Some(key) = enclave.public_key {
+            headers.insert(ENCLAVE_KEY_HEADER, hex::encode(key.as_bytes()).parse().unwrap());
+        }
+    }
+
+    // Enclave errors are read whole and answered in the envelope, whatever
+    // shape the enclave (or whatever fronts it) used
+    let status = response.status();
+    if status.is_client_error() || status.is_server_error() {
+        let body = response.bytes().await.unwrap_or_default();
+        let headers = proxied.headers_ref().cloned().unwrap_or_default();
+        return Ok(errors::error_response(status, headers, &errors::translate(status, &body)));
+    }
+
+    // Challenges and jobs are read whole to pin their IDs to this enclave
+    if SESSION_ISSUING_PATHS.contains(&path.as_str()) && response.status().is_success() {
+        let body = response.bytes().await.map_err(|e| {
+            error!("Failed to read Nautilus response from {}: {}", enclave.url, e);
             StatusCode::BAD_GATEWAY
         })?;
+        state.enclaves.pin_sessions(enclave, &body);
+        return Ok(proxied.body(Body::from(body)).unwrap());
+    }
 
-    // Get response status and body
-    let status_code = response.status().as_u16();
-    let response_bytes = response.bytes().await.map_err(|e| {
-        error!("Failed to read Nautilus response: {}", e);
-        StatusCode::BAD_GATEWAY
-    })?;
-
-    info!("Nautilus response status: {}", status_code);
+    // Relay the body frame by frame, so SSE, chunked responses and their
+    // trailers pass through without buffering
+    Ok(proxied.body(Body::new(reqwest::Body::from(response))).unwrap())
+}
 
-    // Return proxied response
-    Ok(Response::builder()
-        .status(status_code)
-        .header("Content-Type", "application/json")
-        .body(Body::from(response_bytes))
-        .unwrap())
+/// Upstream statuses worth another attempt: the enclave (or whatever fronts it)
+/// was unreachable or overloaded, so the request most likely never ran
+fn is_retryable(status: reqwest::StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE | StatusCode::GATEWAY_TIMEOUT
+    )
 }
 
 /// Health check endpoint
 pub async fn health_check(State(state): State<Arc<AppState>>) -> impl IntoResponse {
-    // Check Nautilus server health
-    let client = Client::new();
-    let nautilus_health = client
-        .get(format!("{}/health_check", state.nautilus_url))
-        .send()
-        .await
-        .map(|r| r.status().is_success())
-        .unwrap_or(false);
+    // Check Nautilus enclave health
+    state.enclaves.check_health().await;
+    let nautilus_health = state.enclaves.healthy_count() > 0;
 
     // Check database health
     let db_health = sqlx::query("SELECT 1")
@@ -88,7 +197,10 @@ pub async fn health_check(State(state): State<Arc<AppState>>) -> impl IntoRespon
         .await
         .is_ok();
 
-    let status = if nautilus_health && db_health {
+    let rpc_healthy = state.sui_rpc.healthy_count();
+    let tasks_healthy = state.supervisor.is_healthy();
+
+    let status = if nautilus_health && db_health && rpc_healthy > 0 && tasks_healthy {
         "healthy"
     } else {
         "unhealthy"
@@ -97,11 +209,22 @@ pub async fn health_check(State(state): State<Arc<AppState>>) -> impl IntoRespon
     Json(serde_json::json!({
         "status": status,
         "nautilus_server": if nautilus_health { "up" } else { "down" },
+        "enclaves": state.enclaves.status(),
         "database": if db_health { "up" } else { "down" },
-        "indexer": "running"
+        "sui_rpc": format!("{}/{} endpoints healthy", rpc_healthy, state.sui_rpc.metrics().len()),
+        "indexer": state.supervisor.state("indexer"),
+        "tasks": state.supervisor.statuses(),
+        "chain": state.chain.info(),
     }))
 }
 
+/// Network, chain ID and RAM package the backend works against
+///
+/// GET /meta/chain (the enclave reports its own in `/meta/intents`)
+pub async fn chain_info(State(state): State<Arc<AppState>>) -> Json<crate::config::ChainInfo> {
+    Json(state.chain.info())
+}
+
 /// Get events for a wallet
 pub async fn get_wallet_events(
     State(state): State<Arc<AppState>>,
@@ -110,7 +233,7 @@ pub async fn get_wallet_events(
     use crate::database::Database;
 
     let events = Database::get_events_by_handle(
-        &state.db,
+        &state.read_db,
         &req.handle,
         req.limit,
         req.offset,
@@ -124,6 +247,23 @@ pub async fn get_wallet_events(
     Ok(Json(events))
 }
 
+/// Get the Sui addresses currently linked to a wallet
+pub async fn get_linked_addresses(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<crate::models::GetLinkedAddressesRequest>,
+) -> Result<Json<Vec<crate::models::LinkedAddress>>, StatusCode> {
+    use crate::database::Database;
+
+    let addresses = Database::get_linked_addresses(&state.read_db, &req.handle)
+        .await
+        .map_err(|e| {
+            error!("Failed to fetch linked addresses: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(addresses))
+}
+
 /// Get wallet statistics
 pub async fn get_wallet_stats(
     State(_state): State<Arc<AppState>>,
@@ -0,0 +1,283 @@
+// Transfer receipts
+// Every transfer the indexer stores gets a receipt (payer, payee, amount,
+// coin, memo, transaction digest and time), which merchants download at
+// GET /api/receipts/:tx_digest as JSON or PDF as proof of payment. Receipts
+// are countersigned by the backend's RECEIPT_SIGNING_KEY (Ed25519) over
+// their message (see `receipt_message`), so anyone holding the public key
+// from GET /api/receipts/public_key can check one without trusting whoever
+// handed it over. The key is held by the backend: a receipt attests that
+// the backend indexed the transfer, and the digest leads to it on-chain.
+
+use anyhow::{anyhow, Context, Result};
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use ed25519_dalek::{Signer, SigningKey};
+use std::sync::Arc;
+use tracing::{error, info};
+
+use crate::database::{Database, DbPool};
+use crate::models::{RamEvent, ReceiptKey, ReceiptQuery, SignedReceipt, TransferReceipt};
+use crate::AppState;
+
+/// First line of every receipt message, versioning its layout
+pub const RECEIPT_DOMAIN: &str = "RAM transfer receipt v1";
+
+/// The receipt key
+#[derive(Clone)]
+pub struct ReceiptSigner {
+    key: SigningKey,
+}
+
+impl ReceiptSigner {
+    /// Signer from a hex Ed25519 private key (`0x` prefix optional)
+    pub fn from_hex(private_key: &str) -> Result<Self> {
+        let seed: [u8; 32] = hex::decode(private_key.trim_start_matches("0x"))
+            .context("RECEIPT_SIGNING_KEY is not hex")?
+            .try_into()
+            .map_err(|_| anyhow!("RECEIPT_SIGNING_KEY must be 32 bytes"))?;
+        Ok(Self { key: SigningKey::from_bytes(&seed) })
+    }
+
+    /// Hex public key receipts are checked against
+    pub fn public_key(&self) -> String {
+        hex::encode(self.key.verifying_key().as_bytes())
+    }
+
+    /// Countersign `receipt`
+    pub fn sign(&self, receipt: TransferReceipt) -> SignedReceipt {
+        let signature = self.key.sign(receipt_message(&receipt).as_bytes());
+        SignedReceipt { receipt, signer_public_key: self.public_key(), signature: hex::encode(signature.to_bytes()) }
+    }
+}
+
+/// What a receipt's signature covers: the domain line, then one
+/// `field: value` line per field, joined by `\n` (an empty memo for none)
+pub fn receipt_message(receipt: &TransferReceipt) -> String {
+    format!(
+        "{}\ntx_digest: {}\npayer: {}\npayee: {}\namount: {}\ncoin_type: {}\nmemo: {}\ntimestamp_ms: {}",
+        RECEIPT_DOMAIN,
+        receipt.tx_digest,
+        receipt.payer_handle,
+        receipt.payee_handle,
+        receipt.amount,
+        receipt.coin_type,
+        receipt.memo.as_deref().unwrap_or_default(),
+        receipt.timestamp_ms
+    )
+}
+
+/// Record the receipt of a stored event, if it's a transfer; true if one was recorded
+pub async fn record_receipt(pool: &DbPool, event: &RamEvent, now_ms: i64) -> Result<bool> {
+    if event.event_type != "Transferred" {
+        return Ok(false);
+    }
+    let (Some(payer_handle), Some(payee_handle), Some(amount), Some(coin_type)) =
+        (&event.from_handle, &event.to_handle, event.amount, &event.coin_type)
+    else {
+        return Ok(false);
+    };
+
+    let receipt = TransferReceipt {
+        tx_digest: event.tx_digest.clone(),
+        payer_handle: payer_handle.clone(),
+        payee_handle: payee_handle.clone(),
+        amount,
+        coin_type: coin_type.clone(),
+        memo: event.memo.clone(),
+        timestamp_ms: event.timestamp.timestamp_millis(),
+    };
+    let recorded = Database::insert_transfer_receipt(pool, &receipt, now_ms).await?;
+    if recorded {
+        info!("Recorded the receipt of {}", receipt.tx_digest);
+    }
+    Ok(recorded)
+}
+
+/// A transfer's countersigned receipt
+///
+/// GET /api/receipts/:tx_digest?format=json|pdf — 404 for a digest without
+/// an indexed transfer, 503 if no receipt key is configured
+pub async fn get_receipt(
+    State(state): State<Arc<AppState>>,
+    Path(tx_digest): Path<String>,
+    Query(query): Query<ReceiptQuery>,
+) -> Result<Response, StatusCode> {
+    let pdf = match query.format.as_deref() {
+        None | Some("json") => false,
+        Some("pdf") => true,
+        Some(_) => return Err(StatusCode::BAD_REQUEST),
+    };
+    let signer = state.receipts.as_ref().ok_or(StatusCode::SERVICE_UNAVAILABLE)?;
+
+    let receipt = Database::get_transfer_receipt(&state.read_db, &tx_digest)
+        .await
+        .map_err(|e| {
+            error!("Failed to fetch the receipt of {}: {}", tx_digest, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let signed = signer.sign(receipt);
+
+    if !pdf {
+        return Ok(Json(signed).into_response());
+    }
+    let disposition = format!("attachment; filename=\"receipt-{}.pdf\"", pdf_safe_filename(&tx_digest));
+    Ok((
+        [(header::CONTENT_TYPE, "application/pdf".to_string()), (header::CONTENT_DISPOSITION, disposition)],
+        render_pdf(&signed),
+    )
+        .into_response())
+}
+
+/// The key receipts are countersigned with
+///
+/// GET /api/receipts/public_key — 503 if none is configured
+pub async fn get_receipt_key(State(state): State<Arc<AppState>>) -> Result<Json<ReceiptKey>, StatusCode> {
+    let signer = state.receipts.as_ref().ok_or(StatusCode::SERVICE_UNAVAILABLE)?;
+    Ok(Json(ReceiptKey { algorithm: "ed25519", public_key: signer.public_key() }))
+}
+
+fn pdf_safe_filename(tx_digest: &str) -> String {
+    tx_digest.chars().filter(char::is_ascii_alphanumeric).collect()
+}
+
+/// A one-page PDF of a signed receipt, in the PDF core font Helvetica, so no
+/// font has to be embedded. Characters it can't show (outside ASCII) print
+/// as `?`; the JSON receipt carries the exact values.
+pub fn render_pdf(signed: &SignedReceipt) -> Vec<u8> {
+    let receipt = &signed.receipt;
+    let mut lines = vec![
+        ("F2", 18, "RAM transfer receipt".to_string()),
+        ("F1", 11, String::new()),
+        ("F1", 11, format!("Transaction: {}", receipt.tx_digest)),
+        ("F1", 11, format!("Payer: {}", receipt.payer_handle)),
+        ("F1", 11, format!("Payee: {}", receipt.payee_handle)),
+        ("F1", 11, format!("Amount: {} (raw units)", receipt.amount)),
+        ("F1", 11, format!("Coin: {}", receipt.coin_type)),
+        ("F1", 11, format!("Memo: {}", receipt.memo.as_deref().unwrap_or("-"))),
+        (
+            "F1",
+            11,
+            format!(
+                "Time: {} ({} ms)",
+                chrono::DateTime::from_timestamp_millis(receipt.timestamp_ms)
+                    .map_or_else(String::new, |time| time.format("%Y-%m-%d %H:%M:%S UTC").to_string()),
+                receipt.timestamp_ms
+            ),
+        ),
+        ("F1", 11, String::new()),
+        ("F2", 11, "Countersignature (Ed25519)".to_string()),
+        ("F1", 9, format!("Public key: {}", signed.signer_public_key)),
+    ];
+    for chunk in signed.signature.as_bytes().chunks(64) {
+        lines.push(("F1", 9, String::from_utf8_lossy(chunk).into_owned()));
+    }
+    lines.push(("F1", 9, String::new()));
+    lines.push(("F1", 9, format!("Signed message: \"{}\" and the fields above, one per line.", RECEIPT_DOMAIN)));
+
+    let mut content = String::from("BT\n50 790 Td\n");
+    for (font, size, text) in &lines {
+        content.push_str(&format!("/{} {} Tf\n0 -{} Td\n({}) Tj\n", font, size, size + 7, pdf_text(text)));
+    }
+    content.push_str("ET\n");
+
+    let objects = [
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+        "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 595 842] \
+         /Resources << /Font << /F1 4 0 R /F2 5 0 R >> >> /Contents 6 0 R >>"
+            .to_string(),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>".to_string(),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold >>".to_string(),
+        format!("<< /Length {} >>\nstream\n{}endstream", content.len(), content),
+    ];
+
+    let mut pdf = b"%PDF-1.4\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend_from_slice(format!("{} 0 obj\n{}\nendobj\n", i + 1, object).as_bytes());
+    }
+    let xref = pdf.len();
+    pdf.extend_from_slice(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes());
+    for offset in offsets {
+        pdf.extend_from_slice(format!("{:010} 00000 n \n", offset).as_bytes());
+    }
+    pdf.extend_from_slice(
+        format!("trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n", objects.len() + 1, xref).as_bytes(),
+    );
+    pdf
+}
+
+/// `text` as a PDF string body: printable ASCII with `\`, `(` and `)` escaped
+fn pdf_text(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '\\' | '(' | ')' => format!("\\{}", c),
+            ' '..='~' => c.to_string(),
+            _ => "?".to_string(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signature, Verifier};
+
+    fn receipt() -> TransferReceipt {
+        TransferReceipt {
+            tx_digest: "8DgDcBfPnJ3R".to_string(),
+            payer_handle: "alice".to_string(),
+            payee_handle: "coffee_shop".to_string(),
+            amount: 2_500_000_000,
+            coin_type: "0x2::sui::SUI".to_string(),
+            memo: Some("invoice (42)".to_string()),
+            timestamp_ms: 1_700_000_000_000,
+        }
+    }
+
+    #[test]
+    fn test_receipts_verify_against_their_message() {
+        let signer = ReceiptSigner::from_hex(&hex::encode([7u8; 32])).unwrap();
+        let signed = signer.sign(receipt());
+        assert_eq!(
+            receipt_message(&signed.receipt),
+            "RAM transfer receipt v1\ntx_digest: 8DgDcBfPnJ3R\npayer: alice\npayee: coffee_shop\namount: 2500000000\n\
+             coin_type: 0x2::sui::SUI\nmemo: invoice (42)\ntimestamp_ms: 1700000000000"
+        );
+
+        let key = SigningKey::from_bytes(&[7u8; 32]).verifying_key();
+        assert_eq!(signed.signer_public_key, hex::encode(key.as_bytes()));
+        let signature = Signature::from_slice(&hex::decode(&signed.signature).unwrap()).unwrap();
+        assert!(key.verify(receipt_message(&signed.receipt).as_bytes(), &signature).is_ok());
+
+        let tampered = TransferReceipt { amount: 25_000_000_000, ..signed.receipt.clone() };
+        assert!(key.verify(receipt_message(&tampered).as_bytes(), &signature).is_err());
+        assert!(ReceiptSigner::from_hex("0xabcd").is_err());
+    }
+
+    #[test]
+    fn test_pdf_receipt_is_well_formed() {
+        let signer = ReceiptSigner::from_hex(&hex::encode([7u8; 32])).unwrap();
+        let pdf = render_pdf(&signer.sign(TransferReceipt { memo: Some("cà phê".to_string()), ..receipt() }));
+        let text = String::from_utf8(pdf.clone()).unwrap();
+        assert!(text.starts_with("%PDF-1.4\n"));
+        assert!(text.ends_with("%%EOF\n"));
+        assert!(text.contains("(Memo: c? ph?) Tj"));
+        assert!(text.contains("(Payee: coffee_shop) Tj"));
+
+        // Every xref entry points at its object
+        let startxref: usize = text.rsplit("startxref\n").next().unwrap().lines().next().unwrap().parse().unwrap();
+        assert!(text[startxref..].starts_with("xref\n0 7\n"));
+        for (i, entry) in text[startxref..].lines().skip(3).take(6).enumerate() {
+            let offset: usize = entry[..10].parse().unwrap();
+            assert!(text[offset..].starts_with(&format!("{} 0 obj\n", i + 1)));
+        }
+        assert_eq!(pdf_text("a\\(b)"), "a\\\\\\(b\\)");
+    }
+}
@@ -0,0 +1,225 @@
+// Refunds initiated by merchants
+// A merchant (signing as usual, scope `refunds`) starts a refund of a
+// transfer it received, in full or in part. It then pays it out through the
+// normal bio-auth + `/transfer` flow, naming `refund_id` instead of the
+// recipient: the proxy fills in the payer, amount and coin and puts
+// `refund:<id>` in the memo, so the enclave signs the link into the transfer
+// itself. When the indexer stores that transfer, the refund is marked
+// completed with its transaction, and event history shows each payment's
+// refunds and each refund's payment.
+
+use anyhow::Result;
+use axum::{
+    body::Bytes,
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use serde_json::Value;
+use std::sync::Arc;
+use tracing::{error, info};
+
+use crate::database::{Database, DbPool};
+use crate::handles::normalize_handle;
+use crate::models::{CancelRefundRequest, CreateRefundRequest, RamEvent, Refund, RefundsQuery};
+use crate::wallet_auth::authenticate;
+use crate::AppState;
+
+/// Memo of a refund transfer, followed by the refund's ID
+pub const REFUND_MEMO_PREFIX: &str = "refund:";
+/// Longest refund reason, in characters
+pub const MAX_REASON_LEN: usize = 256;
+/// Most refunds listed, newest first
+pub const MAX_REFUNDS_LISTED: i64 = 100;
+
+/// Proxied route refunds are paid out through
+const TRANSFER_PATH: &str = "/transfer";
+
+/// The refund a transfer's memo names, if any
+///
+/// The memo may carry more after the refund (e.g. a travel-rule reference).
+pub fn refund_id_of(memo: &str) -> Option<&str> {
+    memo.split_whitespace().next()?.strip_prefix(REFUND_MEMO_PREFIX).filter(|id| !id.is_empty())
+}
+
+/// Fill in a transfer that pays out a refund (`refund_id` in its payload)
+///
+/// Other requests pass through untouched. 422 for an unknown refund, 403 if
+/// it's another wallet's, 409 if it isn't pending, 400 if the payload names
+/// a different recipient, amount, coin or memo than the refund's.
+pub async fn apply_refund(state: &AppState, path: &str, body: Bytes) -> Result<Bytes, StatusCode> {
+    if path != TRANSFER_PATH {
+        return Ok(body);
+    }
+    let Ok(mut request) = serde_json::from_slice::<Value>(&body) else {
+        return Ok(body);
+    };
+    let payload = &mut request["payload"];
+    let Some(refund_id) = payload["refund_id"].as_str() else {
+        return Ok(body);
+    };
+
+    let refund = Database::get_refund(&state.db, refund_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to fetch refund: {}", e);
+            StatusCode::SERVICE_UNAVAILABLE
+        })?
+        .ok_or(StatusCode::UNPROCESSABLE_ENTITY)?;
+    if payload["from_handle"].as_str().map(normalize_handle).as_deref() != Some(refund.handle.as_str()) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    if refund.status != "pending" {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let memo = format!("{}{}", REFUND_MEMO_PREFIX, refund.id);
+    let conflicts = payload["to_handle"].as_str().is_some_and(|h| normalize_handle(h) != refund.payer_handle)
+        || payload["amount"].as_i64().is_some_and(|a| a != refund.amount)
+        || payload["coin_type"].as_str().is_some_and(|c| c != refund.coin_type)
+        || payload["memo"].as_str().is_some_and(|m| m != memo);
+    if conflicts {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let payload = payload.as_object_mut().ok_or(StatusCode::BAD_REQUEST)?;
+    payload.remove("refund_id");
+    payload.insert("to_handle".to_string(), Value::String(refund.payer_handle));
+    payload.insert("amount".to_string(), Value::from(refund.amount));
+    payload.insert("coin_type".to_string(), Value::String(refund.coin_type));
+    payload.insert("memo".to_string(), Value::String(memo));
+    serde_json::to_vec(&request).map(Bytes::from).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+/// Complete the refund a stored transfer pays out, if it names one and
+/// matches it; true if it did
+pub async fn link_refund(pool: &DbPool, event: &RamEvent, now_ms: i64) -> Result<bool> {
+    if event.event_type != "Transferred" {
+        return Ok(false);
+    }
+    let (Some(id), Some(from_handle), Some(to_handle), Some(amount), Some(coin_type)) = (
+        event.memo.as_deref().and_then(refund_id_of),
+        &event.from_handle,
+        &event.to_handle,
+        event.amount,
+        &event.coin_type,
+    ) else {
+        return Ok(false);
+    };
+
+    let linked =
+        Database::complete_refund(pool, id, from_handle, to_handle, amount, coin_type, &event.tx_digest, now_ms).await?;
+    if linked {
+        info!("Refund {} completed in {}", id, event.tx_digest);
+    }
+    Ok(linked)
+}
+
+/// List a merchant's refunds
+///
+/// GET /api/refunds?handle= (signed, scope `refunds`, action `read`)
+pub async fn list_refunds(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<RefundsQuery>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<Refund>>, StatusCode> {
+    authenticate(&state, &headers, "refunds", "read", &query.handle, &[]).await?;
+
+    let refunds = Database::list_refunds(&state.db, &query.handle, MAX_REFUNDS_LISTED).await.map_err(|e| {
+        error!("Failed to list refunds: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(Json(refunds))
+}
+
+/// Start refunding a transfer the merchant received
+///
+/// POST /api/refunds (signed, action `create`) — 404 if `tx_digest` has no
+/// transfer to the merchant, 409 if the amount is more than is left to
+/// refund of it
+pub async fn create_refund(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<(StatusCode, Json<Refund>), StatusCode> {
+    let request: CreateRefundRequest = serde_json::from_slice(&body).map_err(|_| StatusCode::BAD_REQUEST)?;
+    let reason = request.reason.as_deref().map(str::trim).filter(|r| !r.is_empty());
+    if reason.is_some_and(|r| r.chars().count() > MAX_REASON_LEN || r.chars().any(char::is_control))
+        || request.amount.is_some_and(|a| a <= 0)
+    {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    authenticate(&state, &headers, "refunds", "create", &request.handle, &body).await?;
+
+    let payment = Database::get_refundable_payment(&state.db, &request.handle, &request.tx_digest)
+        .await
+        .map_err(|e| {
+            error!("Failed to fetch payment: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let amount = request.amount.unwrap_or(payment.amount - payment.refunded);
+    if amount <= 0 {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let id = uuid::Uuid::new_v4().simple().to_string();
+    let now_ms = chrono::Utc::now().timestamp_millis();
+    let refund = Database::create_refund(
+        &state.db,
+        &id,
+        &request.handle,
+        &payment.payer_handle,
+        &request.tx_digest,
+        payment.amount,
+        amount,
+        &payment.coin_type,
+        reason,
+        now_ms,
+    )
+    .await
+    .map_err(|e| {
+        error!("Failed to store refund: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?
+    .ok_or(StatusCode::CONFLICT)?;
+    info!("{} started refund {} of {} to {}", request.handle, id, request.tx_digest, refund.payer_handle);
+
+    Ok((StatusCode::CREATED, Json(refund)))
+}
+
+/// Cancel a refund that hasn't been paid out
+///
+/// POST /api/refunds/:id/cancel (signed, action `cancel`)
+pub async fn cancel_refund(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<StatusCode, StatusCode> {
+    let request: CancelRefundRequest = serde_json::from_slice(&body).map_err(|_| StatusCode::BAD_REQUEST)?;
+    authenticate(&state, &headers, "refunds", "cancel", &request.handle, &body).await?;
+
+    let cancelled = Database::cancel_refund(&state.db, &id, &request.handle).await.map_err(|e| {
+        error!("Failed to cancel refund: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    if !cancelled {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    info!("{} cancelled refund {}", request.handle, id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_refund_memos_name_their_refund() {
+        assert_eq!(refund_id_of("refund:4f2a"), Some("4f2a"));
+        assert_eq!(refund_id_of("refund:"), None);
+        assert_eq!(refund_id_of("refund:4f2a travel:5d41"), Some("4f2a"));
+        assert_eq!(refund_id_of("INV-2024-001"), None);
+    }
+}
@@ -0,0 +1,348 @@
+// Enclave registration transactions
+// Registering an enclave puts its attested public key on-chain as a shared
+// `Enclave<XWALLET>`, which RAM's Move code verifies enclave signatures
+// against. `POST /admin/register_enclave` prepares the whole registration as
+// one transaction for an operator to sign: it fetches the enclave's
+// attestation, reads the PCRs and key it attests to, checks those PCRs against
+// the `EnclaveConfig` (setting them first with its `Cap` when a new build is
+// rolled out and the operator asks to), then loads the document with
+// `0x2::nitro_attestation` and calls `enclave::register_enclave`. The
+// transaction is dry-run and budgeted like `/api/tx/estimate`, and expires
+// with the current epoch, so a registration prepared from an old attestation
+// can't be executed once the enclave has moved on.
+
+use anyhow::{anyhow, Context, Result};
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use reqwest::Client;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+use crate::admin::require_admin;
+use crate::attestation::AttestationDocument;
+use crate::database::json_u64;
+use crate::gas;
+use crate::models::{EnclaveRegistration, RegisterEnclaveRequest};
+use crate::routing::Enclave;
+use crate::rpc::SuiRpcClient;
+use crate::signing::sign_request;
+use crate::sponsor::{
+    self, parse_address, Address, Argument, CallArg, Command, GasData, ObjectArg, ObjectRef, ProgrammableMoveCall,
+    ProgrammableTransaction, StructTag, TransactionData, TransactionDataV1, TransactionExpiration, TransactionKind,
+    TypeTag,
+};
+use crate::AppState;
+
+/// Enclave route serving its attestation document
+const ATTESTATION_PATH: &str = "/get_attestation";
+/// How long the enclave gets to make an attestation
+const ATTESTATION_TIMEOUT: Duration = Duration::from_secs(10);
+/// Gas budget the transaction is dry-run with, and the most it's given (MIST)
+const REGISTRATION_GAS_BUDGET: u64 = 100_000_000;
+/// The shared `Clock` and its initial version
+const CLOCK_ID: &str = "0x6";
+const CLOCK_VERSION: u64 = 1;
+/// PCRs an `EnclaveConfig` holds (image, kernel, application)
+const CONFIG_PCRS: u64 = 3;
+
+/// An `EnclaveConfig` as the chain has it
+struct OnChainConfig {
+    initial_shared_version: u64,
+    version: u64,
+    pcrs: Vec<Vec<u8>>,
+}
+
+/// Everything a registration transaction is built from
+struct Registration {
+    framework_package: Address,
+    enclave_package: Address,
+    config: Address,
+    /// Initial shared version of the config
+    config_shared_version: u64,
+    /// The config's `Cap` and the PCRs to set, if they're updated first
+    pcr_update: Option<(ObjectRef, Vec<Vec<u8>>)>,
+    attestation: Vec<u8>,
+}
+
+impl Registration {
+    /// `EnclaveConfig<XWALLET>`'s type argument
+    fn witness(&self) -> TypeTag {
+        TypeTag::Struct(Box::new(StructTag {
+            address: self.enclave_package,
+            module: "core".to_string(),
+            name: "XWALLET".to_string(),
+            type_params: vec![],
+        }))
+    }
+
+    /// `[update_pcrs,] load_nitro_attestation, register_enclave`
+    fn programmable(&self) -> Result<ProgrammableTransaction> {
+        let mut inputs = vec![
+            CallArg::Pure(bcs::to_bytes(&self.attestation)?),
+            CallArg::Object(ObjectArg::SharedObject {
+                id: parse_address(CLOCK_ID)?,
+                initial_shared_version: CLOCK_VERSION,
+                mutable: false,
+            }),
+            CallArg::Object(ObjectArg::SharedObject {
+                id: self.config,
+                initial_shared_version: self.config_shared_version,
+                mutable: self.pcr_update.is_some(),
+            }),
+        ];
+        let mut commands = Vec::new();
+        if let Some((cap, pcrs)) = &self.pcr_update {
+            inputs.push(CallArg::Object(ObjectArg::ImmOrOwnedObject(cap.clone())));
+            for pcr in pcrs {
+                inputs.push(CallArg::Pure(bcs::to_bytes(pcr)?));
+            }
+            let arguments = (2..inputs.len() as u16).map(Argument::Input).collect();
+            commands.push(move_call(self.framework_package, "enclave", "update_pcrs", vec![self.witness()], arguments));
+        }
+        let document = commands.len() as u16;
+        commands.push(move_call(
+            parse_address("0x2")?,
+            "nitro_attestation",
+            "load_nitro_attestation",
+            vec![],
+            vec![Argument::Input(0), Argument::Input(1)],
+        ));
+        commands.push(move_call(
+            self.framework_package,
+            "enclave",
+            "register_enclave",
+            vec![self.witness()],
+            vec![Argument::Input(2), Argument::Result(document)],
+        ));
+        Ok(ProgrammableTransaction { inputs, commands })
+    }
+
+    /// BCS bytes of the transaction from `sender`, paid with `gas_coin`
+    fn transaction(&self, sender: Address, gas_coin: &ObjectRef, price: u64, budget: u64, epoch: u64) -> Result<Vec<u8>> {
+        Ok(bcs::to_bytes(&TransactionData::V1(TransactionDataV1 {
+            kind: TransactionKind::ProgrammableTransaction(self.programmable()?),
+            sender,
+            gas_data: GasData { payment: vec![gas_coin.clone()], owner: sender, price, budget },
+            expiration: TransactionExpiration::Epoch(epoch),
+        }))?)
+    }
+}
+
+/// A call to `package::module::function`
+fn move_call(package: Address, module: &str, function: &str, type_arguments: Vec<TypeTag>, arguments: Vec<Argument>) -> Command {
+    Command::MoveCall(Box::new(ProgrammableMoveCall {
+        package,
+        module: module.to_string(),
+        function: function.to_string(),
+        type_arguments,
+        arguments,
+    }))
+}
+
+/// Prepare the on-chain registration of an enclave's attested key
+///
+/// POST /admin/register_enclave — `{sender, enclave?, update_pcrs?}`; 400 for
+/// a bad sender, 404 for an unknown enclave, 409 if the attested PCRs differ
+/// from the config's (and weren't asked to be updated, or can't be) or the
+/// attested key isn't the one configured for the enclave, 422 if the dry run
+/// fails, 502 if the enclave or fullnode can't be reached, 503 if
+/// registration isn't configured
+pub async fn register_enclave(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<RegisterEnclaveRequest>,
+) -> Result<Json<EnclaveRegistration>, StatusCode> {
+    require_admin(&state, &headers)?;
+    let config = state.enclave_registration.as_ref().ok_or(StatusCode::SERVICE_UNAVAILABLE)?;
+    let sender = parse_address(&request.sender).map_err(|_| StatusCode::BAD_REQUEST)?;
+    let enclave = state.enclaves.enclaves().get(request.enclave).ok_or(StatusCode::NOT_FOUND)?;
+    let failed = |e: anyhow::Error| {
+        error!("Failed to prepare the registration of enclave {}: {}", enclave.url, e);
+        StatusCode::BAD_GATEWAY
+    };
+
+    let attestation = fetch_attestation(&state, enclave).await.map_err(failed)?;
+    let document = AttestationDocument::parse(&attestation).map_err(failed)?;
+    let public_key = document.public_key.clone().ok_or_else(|| failed(anyhow!("Attestation commits to no key")))?;
+    if enclave.public_key.as_ref().is_some_and(|key| key.as_bytes()[..] != public_key[..]) {
+        warn!("Enclave {} attests to key {}, not its configured one", enclave.url, hex::encode(&public_key));
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let config_id = parse_address(&config.config_id).map_err(failed)?;
+    let on_chain = load_config(&state.sui_rpc, &config_id).await.map_err(failed)?;
+    let attested: Vec<Vec<u8>> = (0..CONFIG_PCRS).map(|index| document.pcr(index).to_vec()).collect();
+    let pcr_update = if on_chain.pcrs == attested {
+        None
+    } else {
+        let cap = match (&config.cap_id, request.update_pcrs) {
+            (Some(cap), true) => cap,
+            _ => {
+                warn!(
+                    "Enclave {} attests to PCRs {:?}, the config has {:?}{}",
+                    enclave.url,
+                    attested.iter().map(hex::encode).collect::<Vec<_>>(),
+                    on_chain.pcrs.iter().map(hex::encode).collect::<Vec<_>>(),
+                    if config.cap_id.is_none() { " (no ENCLAVE_CAP_ID to update them with)" } else { "" },
+                );
+                return Err(StatusCode::CONFLICT);
+            }
+        };
+        let cap = sponsor::owned_object(&state.sui_rpc, &parse_address(cap).map_err(failed)?).await.map_err(failed)?;
+        Some((cap, attested.clone()))
+    };
+
+    let registration = Registration {
+        framework_package: parse_address(&config.framework_package_id).map_err(failed)?,
+        enclave_package: parse_address(&config.enclave_package_id).map_err(failed)?,
+        config: config_id,
+        config_shared_version: on_chain.initial_shared_version,
+        pcr_update,
+        attestation,
+    };
+    let rpc = &state.sui_rpc;
+    let epoch = current_epoch(rpc).await.map_err(failed)?;
+    let gas_coin = sponsor::gas_coin(rpc, &request.sender, REGISTRATION_GAS_BUDGET).await.map_err(failed)?;
+    let price = json_u64(&rpc.call("suix_getReferenceGasPrice", json!([])).await.map_err(failed)?)
+        .ok_or_else(|| failed(anyhow!("unexpected reference gas price")))?;
+
+    let tx_bytes = registration
+        .transaction(sender, &gas_coin, price, REGISTRATION_GAS_BUDGET, epoch)
+        .map_err(failed)?;
+    let estimate = gas::dry_run(rpc, &tx_bytes, &state.gas).await.map_err(failed)?;
+    if estimate.status != "success" {
+        warn!(
+            "Registration of enclave {} would fail: {}",
+            enclave.url,
+            estimate.error.as_deref().unwrap_or("unknown error")
+        );
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+    let budget = estimate.budget.min(REGISTRATION_GAS_BUDGET);
+    let tx_bytes = registration.transaction(sender, &gas_coin, price, budget, epoch).map_err(failed)?;
+
+    info!(
+        "Prepared registration of enclave {} (key {}) for {}, valid through epoch {}",
+        enclave.url,
+        hex::encode(&public_key),
+        request.sender,
+        epoch
+    );
+    Ok(Json(EnclaveRegistration {
+        enclave_url: enclave.url.clone(),
+        public_key: hex::encode(&public_key),
+        pcrs: attested.iter().map(hex::encode).collect(),
+        module_id: document.module_id,
+        attested_at_ms: document.timestamp_ms,
+        updates_pcrs: registration.pcr_update.is_some(),
+        config_version: on_chain.version + registration.pcr_update.is_some() as u64,
+        expires_after_epoch: epoch,
+        tx_bytes: STANDARD.encode(&tx_bytes),
+        gas: estimate,
+    }))
+}
+
+/// The enclave's attestation document
+async fn fetch_attestation(state: &AppState, enclave: &Enclave) -> Result<Vec<u8>> {
+    let request = Client::new().get(format!("{}{}", enclave.url, ATTESTATION_PATH)).timeout(ATTESTATION_TIMEOUT);
+    let body: Value = sign_request(request, state.nautilus_signing_key.as_deref(), "GET", ATTESTATION_PATH, &[])
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    let attestation = body["attestation"].as_str().ok_or_else(|| anyhow!("No attestation in the enclave's answer"))?;
+    hex::decode(attestation).context("Attestation is not hex")
+}
+
+/// Version, PCRs and initial shared version of an `EnclaveConfig`
+async fn load_config(rpc: &SuiRpcClient, id: &Address) -> Result<OnChainConfig> {
+    let id = format!("0x{}", hex::encode(id));
+    let object = rpc.call("sui_getObject", json!([id, { "showOwner": true, "showContent": true }])).await?;
+    let data = &object["data"];
+    let fields = &data["content"]["fields"];
+    let pcrs = (0..CONFIG_PCRS)
+        .map(|index| {
+            fields["pcrs"]["fields"][format!("pos{}", index)]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .map(|byte| byte.as_u64().and_then(|b| u8::try_from(b).ok()))
+                .collect::<Option<Vec<u8>>>()
+                .ok_or_else(|| anyhow!("Object {} has malformed PCRs", id))
+        })
+        .collect::<Result<_>>()?;
+    Ok(OnChainConfig {
+        initial_shared_version: json_u64(&data["owner"]["Shared"]["initial_shared_version"])
+            .ok_or_else(|| anyhow!("Object {} is not a shared EnclaveConfig", id))?,
+        version: json_u64(&fields["version"]).ok_or_else(|| anyhow!("Object {} has no config version", id))?,
+        pcrs,
+    })
+}
+
+/// The epoch the chain is in
+async fn current_epoch(rpc: &SuiRpcClient) -> Result<u64> {
+    let state = rpc.call("suix_getLatestSuiSystemState", json!([])).await?;
+    json_u64(&state["epoch"]).ok_or_else(|| anyhow!("No epoch in the system state"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registration(pcr_update: Option<(ObjectRef, Vec<Vec<u8>>)>) -> Registration {
+        Registration {
+            framework_package: [1; 32],
+            enclave_package: [2; 32],
+            config: [3; 32],
+            config_shared_version: 5,
+            pcr_update,
+            attestation: vec![0xd2; 10],
+        }
+    }
+
+    #[test]
+    fn test_registration_loads_the_document_before_registering() {
+        let transaction = registration(None).programmable().unwrap();
+        assert_eq!(transaction.inputs.len(), 3);
+        let [Command::MoveCall(load), Command::MoveCall(register)] = &transaction.commands[..] else {
+            panic!("expected two calls");
+        };
+        assert_eq!((load.module.as_str(), load.function.as_str()), ("nitro_attestation", "load_nitro_attestation"));
+        assert_eq!(register.package, [1; 32]);
+        assert!(matches!(register.arguments[..], [Argument::Input(2), Argument::Result(0)]));
+        // The config is only read
+        assert!(matches!(transaction.inputs[2], CallArg::Object(ObjectArg::SharedObject { mutable: false, .. })));
+    }
+
+    #[test]
+    fn test_pcr_update_comes_first_with_the_cap() {
+        let cap = ([4; 32], 8, vec![9; 32]);
+        let transaction = registration(Some((cap, vec![vec![1; 48], vec![2; 48], vec![3; 48]]))).programmable().unwrap();
+        assert_eq!(transaction.inputs.len(), 7);
+        let Command::MoveCall(update) = &transaction.commands[0];
+        assert_eq!(update.function, "update_pcrs");
+        assert!(matches!(update.arguments[..], [Argument::Input(2), Argument::Input(3), _, _, Argument::Input(6)]));
+        let Command::MoveCall(register) = &transaction.commands[2];
+        assert!(matches!(register.arguments[..], [Argument::Input(2), Argument::Result(1)]));
+        assert!(matches!(transaction.inputs[2], CallArg::Object(ObjectArg::SharedObject { mutable: true, .. })));
+    }
+
+    #[test]
+    fn test_transaction_expires_with_the_epoch() {
+        let bytes = registration(None).transaction([6; 32], &([7; 32], 1, vec![8; 32]), 750, 1_000, 42).unwrap();
+        // The expiration comes last: `Epoch` (variant 1), then the epoch
+        assert_eq!(&bytes[bytes.len() - 9..], &[&[1u8][..], &42u64.to_le_bytes()].concat()[..]);
+
+        // Owned objects are variant 0 with a length-prefixed digest, results variant 2
+        let owned = bcs::to_bytes(&ObjectArg::ImmOrOwnedObject(([7; 32], 1, vec![8; 32]))).unwrap();
+        assert_eq!((owned[0], owned.len()), (0, 1 + 32 + 8 + 1 + 32));
+        assert_eq!(bcs::to_bytes(&Argument::Result(3)).unwrap(), vec![2, 3, 0]);
+    }
+}
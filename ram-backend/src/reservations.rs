@@ -0,0 +1,148 @@
+// Handle reservations
+// Handles are first come, first served on-chain, so two users picking the
+// same handle would only find out who got it once one creation lands and
+// the other's fails after onboarding. POST /api/handles/reserve holds a free
+// handle for HANDLE_RESERVATION_MINUTES and returns a token; until the
+// reservation expires, wallet creations of the handle (the proxied
+// `/create_wallet` and `/api/onboard`) must carry that token, so whoever
+// reserved first wins and everyone else gets a 409 up front. Reserving is
+// one upsert that only takes over an expired row, so concurrent reservations
+// of a handle can't both succeed.
+
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::StatusCode,
+    Json,
+};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+use crate::database::Database;
+use crate::handles::normalize_handle;
+use crate::models::{HandleReservation, ReserveHandleRequest};
+use crate::AppState;
+
+/// How long a handle is held unless HANDLE_RESERVATION_MINUTES says otherwise
+pub const DEFAULT_RESERVATION_TTL: Duration = Duration::from_secs(15 * 60);
+/// Longest reservation token accepted
+pub const MAX_RESERVATION_TOKEN_LEN: usize = 128;
+
+/// Proxied route wallet creations go through
+const CREATE_WALLET_PATH: &str = "/create_wallet";
+/// Prefix of reservation tokens
+const RESERVATION_TOKEN_PREFIX: &str = "rsv_";
+
+/// What's stored for a reservation token
+pub fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+/// A fresh reservation token (two v4 UUIDs, 244 random bits)
+fn new_token() -> String {
+    format!("{}{}{}", RESERVATION_TOKEN_PREFIX, uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple())
+}
+
+/// Hold a handle while its user completes onboarding
+///
+/// POST /api/handles/reserve — `{handle}` returns a new reservation;
+/// `{handle, reservation_token}` extends that reservation. 409 if the handle
+/// has a wallet or someone else holds it, 400 for an empty handle or an
+/// oversized token.
+pub async fn reserve_handle(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<ReserveHandleRequest>,
+) -> Result<Json<HandleReservation>, StatusCode> {
+    let handle = normalize_handle(request.handle.trim());
+    let token = match request.reservation_token {
+        Some(token) if token.is_empty() || token.len() > MAX_RESERVATION_TOKEN_LEN => {
+            return Err(StatusCode::BAD_REQUEST)
+        }
+        Some(token) => token,
+        None => new_token(),
+    };
+    if handle.is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let wallet_id = Database::get_wallet_id(&state.db, &handle).await.map_err(|e| {
+        error!("Failed to fetch wallet id: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    if wallet_id.is_some() {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let now_ms = now_ms();
+    let expires_at_ms = now_ms + state.handle_reservation_ttl.as_millis() as i64;
+    let reserved = Database::reserve_handle(&state.db, &handle, &hash_token(&token), expires_at_ms, now_ms)
+        .await
+        .map_err(|e| {
+            error!("Failed to reserve {}: {}", handle, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    if !reserved {
+        return Err(StatusCode::CONFLICT);
+    }
+    info!("Handle {} reserved until {}", handle, expires_at_ms);
+
+    Ok(Json(HandleReservation { handle, reservation_token: token, expires_at_ms }))
+}
+
+/// Refuse the creation of a handle someone else holds
+///
+/// 409 unless the handle has no running reservation or `token` is its token.
+pub async fn check_reservation(state: &AppState, handle: &str, token: Option<&str>) -> Result<(), StatusCode> {
+    let reserved = Database::active_handle_reservation(&state.db, handle, now_ms())
+        .await
+        .map_err(|e| {
+            error!("Failed to check the reservation of {}: {}", handle, e);
+            StatusCode::SERVICE_UNAVAILABLE
+        })?;
+    holds(reserved.as_deref(), token)
+}
+
+fn holds(reserved: Option<&str>, token: Option<&str>) -> Result<(), StatusCode> {
+    match reserved {
+        Some(token_hash) if token.map(hash_token).as_deref() != Some(token_hash) => {
+            Err(StatusCode::CONFLICT)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Check a proxied wallet creation against the handle's reservation and
+/// take its `reservation_token` out of the payload
+///
+/// Other requests pass through untouched. If reservations can't be read the
+/// creation goes ahead: the chain still decides who gets the handle, and the
+/// proxy stays up without its database.
+pub async fn apply_reservation(state: &AppState, path: &str, body: Bytes) -> Result<Bytes, StatusCode> {
+    if path != CREATE_WALLET_PATH {
+        return Ok(body);
+    }
+    let Ok(mut request) = serde_json::from_slice::<Value>(&body) else {
+        return Ok(body);
+    };
+    let Some(payload) = request["payload"].as_object_mut() else {
+        return Ok(body);
+    };
+    let token = payload.remove("reservation_token");
+    if let Some(handle) = payload.get("handle").and_then(Value::as_str) {
+        match Database::active_handle_reservation(&state.db, handle, now_ms()).await {
+            Ok(reserved) => holds(reserved.as_deref(), token.as_ref().and_then(Value::as_str))?,
+            Err(e) => warn!("Failed to check the reservation of {}, letting it through: {}", handle, e),
+        }
+    }
+    if token.is_none() {
+        return Ok(body);
+    }
+    serde_json::to_vec(&request).map(Bytes::from).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+fn now_ms() -> i64 {
+    chrono::Utc::now().timestamp_millis()
+}
@@ -0,0 +1,63 @@
+// Event retention
+// ram_events grows with every on-chain operation, and support search, the
+// indexer's inserts and per-wallet scans all slow down with it. Events older
+// than EVENT_RETENTION_MONTHS are moved in batches to ram_events_archive.
+// Reads that are part of a wallet's state (its event list, coin flows,
+// bio-auths and last activity) go through the ram_events_history view and
+// see both; support search only covers the retention window. An archived
+// event the indexer stores again is dropped from ram_events by the next run.
+
+use anyhow::Result;
+use chrono::{DateTime, Months, Utc};
+use tracing::{error, info};
+
+use crate::config::RetentionConfig;
+use crate::database::{Database, DbPool};
+
+/// Events before this time are archived under `retention_months`
+pub fn archive_cutoff_ms(now: DateTime<Utc>, retention_months: u32) -> i64 {
+    now.checked_sub_months(Months::new(retention_months))
+        .unwrap_or(DateTime::<Utc>::MIN_UTC)
+        .timestamp_millis()
+}
+
+/// Archive every event older than `before_ms`, `batch_size` at a time,
+/// returning how many were moved
+pub async fn archive_events(pool: &DbPool, before_ms: i64, batch_size: i64) -> Result<i64> {
+    let mut moved = 0;
+    loop {
+        let batch = Database::archive_events_before(pool, before_ms, batch_size).await?;
+        moved += batch;
+        if batch < batch_size {
+            return Ok(moved);
+        }
+    }
+}
+
+/// Archive old events every `check_interval`, for as long as it runs
+/// (supervised, see supervisor.rs)
+pub async fn run_event_archival(pool: DbPool, config: RetentionConfig) -> Result<()> {
+    loop {
+        let before_ms = archive_cutoff_ms(Utc::now(), config.retention_months);
+        match archive_events(&pool, before_ms, config.batch_size).await {
+            Ok(0) => {}
+            Ok(moved) => info!("Archived {} events from before {}", moved, before_ms),
+            Err(e) => error!("Event archival failed: {}", e),
+        }
+        tokio::time::sleep(config.check_interval).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cutoff_is_whole_calendar_months_back() {
+        let now = DateTime::parse_from_rfc3339("2024-03-31T12:00:00Z").unwrap().to_utc();
+        // February has no 31st, so the cutoff clamps to its last day
+        let cutoff = DateTime::parse_from_rfc3339("2024-02-29T12:00:00Z").unwrap().to_utc();
+        assert_eq!(archive_cutoff_ms(now, 1), cutoff.timestamp_millis());
+        assert_eq!(archive_cutoff_ms(now, 12), now.timestamp_millis() - 366 * 86_400_000);
+    }
+}
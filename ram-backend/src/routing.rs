@@ -0,0 +1,392 @@
+// Routing across several Nautilus enclaves
+//
+// NAUTILUS_URL may list more than one enclave. A request naming a handle goes
+// to the same healthy enclave every time (rendezvous hashing, so every backend
+// replica agrees and only a failed enclave's wallets move): that enclave holds
+// the wallet's attempt counters, replayed clips and voice baseline. Requests
+// without a handle are spread round-robin. Challenges and queued bio-auth jobs
+// only exist on the enclave that issued them, so their IDs are pinned to it
+// from the issuing response, and the follow-up request goes back there even
+// while it's marked down. A background probe of /health_check brings enclaves
+// back; a connection failure marks one down at once. An enclave whose health
+// check reports another network or RAM package than the backend's is kept
+// down: what it signs is meant for another chain.
+
+use anyhow::{anyhow, Context, Result};
+use axum::http::HeaderName;
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use reqwest::Client;
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{error, info, warn};
+
+use crate::config::{ChainInfo, EnclaveConfig};
+
+/// Response header with the public key of the enclave that answered, for
+/// clients verifying its signatures (set when keys are configured)
+pub const ENCLAVE_KEY_HEADER: HeaderName = HeaderName::from_static("x-ram-enclave-key");
+/// How long a challenge or job stays pinned to its enclave (past the
+/// enclave's own expiry for either)
+pub const SESSION_TTL: Duration = Duration::from_secs(15 * 60);
+/// Per-probe timeout of the background health check
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+/// Response fields naming state that lives on the enclave that issued it
+const SESSION_FIELDS: &[&str] = &["challenge_id", "job_id"];
+/// `/bio_auth/*` GETs that aren't a job or challenge lookup
+const UNPINNED_BIO_AUTH_PATHS: &[&str] = &["queue", "shadow"];
+
+/// Which enclave a request should go to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Affinity {
+    /// A challenge or job issued by one enclave
+    Session(String),
+    /// A wallet, kept on one enclave while it's healthy
+    Handle(String),
+    /// Anything, round-robin
+    Any,
+}
+
+impl Affinity {
+    /// Affinity of a proxied request from its path and JSON body
+    ///
+    /// `GET /bio_auth/:job_id` and `GET /bio_auth/challenge_audio/:id` name a
+    /// session in the path; other requests by `challenge_id`, then `handle`
+    /// or `from_handle`, in the body or its `payload`.
+    pub fn of_request(method: &str, path: &str, body: &[u8]) -> Self {
+        if method.eq_ignore_ascii_case("GET") {
+            if let Some(rest) = path.strip_prefix("/bio_auth/") {
+                let id = rest.rsplit('/').next().unwrap_or(rest);
+                if !id.is_empty() && !UNPINNED_BIO_AUTH_PATHS.contains(&rest) {
+                    return Affinity::Session(id.to_string());
+                }
+            }
+        }
+
+        let Ok(body) = serde_json::from_slice::<Value>(body) else {
+            return Affinity::Any;
+        };
+        let fields = body.get("payload").unwrap_or(&body);
+        let field = |name: &str| fields.get(name).and_then(Value::as_str).filter(|v| !v.is_empty());
+        if let Some(id) = field("challenge_id") {
+            return Affinity::Session(id.to_string());
+        }
+        match field("handle").or_else(|| field("from_handle")) {
+            Some(handle) => Affinity::Handle(handle.to_string()),
+            None => Affinity::Any,
+        }
+    }
+}
+
+/// One enclave behind the backend
+pub struct Enclave {
+    pub index: usize,
+    pub url: String,
+    /// Key its payloads must verify against (not checked if unset)
+    pub public_key: Option<VerifyingKey>,
+    healthy: AtomicBool,
+}
+
+impl Enclave {
+    pub fn is_healthy(&self) -> bool {
+        self.healthy.load(Ordering::Relaxed)
+    }
+
+    /// Whether `signature` (hex) is this enclave's over the intent message of
+    /// `payload`; always true without a configured key
+    pub fn verifies<T: Serialize>(&self, intent: u8, timestamp_ms: u64, payload: &T, signature: &str) -> bool {
+        let Some(key) = &self.public_key else {
+            return true;
+        };
+        #[derive(Serialize)]
+        struct IntentMessage<'a, T: Serialize> {
+            intent: u8,
+            timestamp_ms: u64,
+            data: &'a T,
+        }
+        let Ok(message) = bcs::to_bytes(&IntentMessage { intent, timestamp_ms, data: payload }) else {
+            return false;
+        };
+        hex::decode(signature)
+            .ok()
+            .and_then(|bytes| Signature::from_slice(&bytes).ok())
+            .is_some_and(|signature| key.verify(&message, &signature).is_ok())
+    }
+}
+
+/// Health and key of one enclave, as reported by `/health`
+#[derive(Debug, Clone, Serialize)]
+pub struct EnclaveStatus {
+    pub url: String,
+    pub healthy: bool,
+    pub public_key: Option<String>,
+}
+
+struct Session {
+    enclave: usize,
+    pinned_at: Instant,
+}
+
+pub struct EnclaveRouter {
+    http: Client,
+    enclaves: Vec<Enclave>,
+    /// Chain enclaves must report signing for (not checked if unset)
+    chain: Option<ChainInfo>,
+    sessions: Mutex<HashMap<String, Session>>,
+    next: AtomicUsize,
+}
+
+impl EnclaveRouter {
+    pub fn new(configs: Vec<EnclaveConfig>) -> Result<Self> {
+        if configs.is_empty() {
+            return Err(anyhow!("At least one enclave must be configured"));
+        }
+        let enclaves = configs
+            .into_iter()
+            .enumerate()
+            .map(|(index, config)| {
+                let public_key = config
+                    .public_key
+                    .as_deref()
+                    .map(|key| parse_public_key(key).with_context(|| format!("Invalid public key for {}", config.url)))
+                    .transpose()?;
+                Ok(Enclave { index, url: config.url, public_key, healthy: AtomicBool::new(true) })
+            })
+            .collect::<Result<_>>()?;
+        let http = Client::builder()
+            .timeout(HEALTH_CHECK_TIMEOUT)
+            .build()
+            .expect("Failed to build enclave health check client");
+        Ok(Self { http, enclaves, chain: None, sessions: Mutex::new(HashMap::new()), next: AtomicUsize::new(0) })
+    }
+
+    /// Only count enclaves signing for `chain` as healthy
+    pub fn with_chain(mut self, chain: ChainInfo) -> Self {
+        self.chain = Some(chain);
+        self
+    }
+
+    /// One enclave at `url` whose payloads aren't verified
+    pub fn single(url: impl Into<String>) -> Self {
+        Self::new(vec![EnclaveConfig::new(url)]).expect("one enclave without a key is always valid")
+    }
+
+    pub fn enclaves(&self) -> &[Enclave] {
+        &self.enclaves
+    }
+
+    /// The enclave for a request
+    pub fn route(&self, affinity: &Affinity) -> &Enclave {
+        if let Affinity::Session(id) = affinity {
+            if let Some(session) = self.sessions.lock().unwrap().get(id) {
+                return &self.enclaves[session.enclave];
+            }
+        }
+
+        // Healthy enclaves only, unless none are
+        let healthy: Vec<&Enclave> = self.enclaves.iter().filter(|e| e.is_healthy()).collect();
+        let candidates = if healthy.is_empty() { self.enclaves.iter().collect() } else { healthy };
+        match affinity {
+            Affinity::Handle(handle) => candidates
+                .into_iter()
+                .max_by_key(|enclave| rendezvous_weight(handle, &enclave.url))
+                .expect("at least one enclave"),
+            _ => candidates[self.next.fetch_add(1, Ordering::Relaxed) % candidates.len()],
+        }
+    }
+
+    /// Pin the challenge or job IDs in an enclave's JSON response to it
+    pub fn pin_sessions(&self, enclave: &Enclave, response_body: &[u8]) {
+        let Ok(body) = serde_json::from_slice::<Value>(response_body) else {
+            return;
+        };
+        let mut sessions = self.sessions.lock().unwrap();
+        sessions.retain(|_, session| session.pinned_at.elapsed() < SESSION_TTL);
+        for field in SESSION_FIELDS {
+            if let Some(id) = body.get(*field).and_then(Value::as_str) {
+                sessions.insert(id.to_string(), Session { enclave: enclave.index, pinned_at: Instant::now() });
+            }
+        }
+    }
+
+    /// Mark an enclave up or down
+    pub fn mark(&self, enclave: &Enclave, healthy: bool) {
+        let was_healthy = enclave.healthy.swap(healthy, Ordering::Relaxed);
+        if was_healthy && !healthy {
+            warn!("Enclave {} marked down", enclave.url);
+        } else if healthy && !was_healthy {
+            info!("Enclave {} recovered", enclave.url);
+        }
+    }
+
+    /// Probe every enclave's /health_check once, updating its health
+    pub async fn check_health(&self) {
+        for enclave in &self.enclaves {
+            let healthy = match self.http.get(format!("{}/health_check", enclave.url)).send().await {
+                Ok(response) if response.status().is_success() => {
+                    let body = response.json::<Value>().await.unwrap_or_default();
+                    self.on_our_chain(enclave, &body)
+                }
+                _ => false,
+            };
+            self.mark(enclave, healthy);
+        }
+    }
+
+    /// Whether a health check body reports the backend's network and package
+    /// (enclaves that predate chain reporting are given the benefit of the doubt)
+    fn on_our_chain(&self, enclave: &Enclave, health: &Value) -> bool {
+        let (Some(ours), Some(theirs)) = (&self.chain, health.get("chain")) else {
+            return true;
+        };
+        match serde_json::from_value::<ChainInfo>(theirs.clone()) {
+            Ok(theirs) if theirs.network == ours.network && theirs.package_id == ours.package_id => true,
+            Ok(theirs) => {
+                error!(
+                    "Enclave {} signs for {} package {}, not {} package {}",
+                    enclave.url, theirs.network, theirs.package_id, ours.network, ours.package_id
+                );
+                false
+            }
+            Err(e) => {
+                warn!("Enclave {} reported an unreadable chain: {}", enclave.url, e);
+                false
+            }
+        }
+    }
+
+    /// Re-check enclave health every `interval` in the background
+    pub fn spawn_health_checks(self: &Arc<Self>, interval: Duration) {
+        let router = self.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                router.check_health().await;
+            }
+        });
+    }
+
+    pub fn healthy_count(&self) -> usize {
+        self.enclaves.iter().filter(|e| e.is_healthy()).count()
+    }
+
+    pub fn status(&self) -> Vec<EnclaveStatus> {
+        self.enclaves
+            .iter()
+            .map(|e| EnclaveStatus {
+                url: e.url.clone(),
+                healthy: e.is_healthy(),
+                public_key: e.public_key.map(|key| hex::encode(key.as_bytes())),
+            })
+            .collect()
+    }
+}
+
+/// Hex (optionally 0x-prefixed) Ed25519 public key
+pub fn parse_public_key(key: &str) -> Result<VerifyingKey> {
+    let bytes: [u8; 32] = hex::decode(key.trim_start_matches("0x"))
+        .context("not hex")?
+        .try_into()
+        .map_err(|_| anyhow!("must be 32 bytes"))?;
+    Ok(VerifyingKey::from_bytes(&bytes)?)
+}
+
+/// Rendezvous weight of a handle on an enclave
+fn rendezvous_weight(handle: &str, url: &str) -> u64 {
+    let digest = Sha256::new().chain_update(handle).chain_update("\n").chain_update(url).finalize();
+    u64::from_be_bytes(digest[..8].try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn router(urls: &[&str]) -> EnclaveRouter {
+        EnclaveRouter::new(urls.iter().map(|url| EnclaveConfig::new(*url)).collect()).unwrap()
+    }
+
+    #[test]
+    fn test_affinity_of_request() {
+        let body = |v: Value| serde_json::to_vec(&v).unwrap();
+        assert_eq!(Affinity::of_request("GET", "/bio_auth/job-1", &[]), Affinity::Session("job-1".into()));
+        assert_eq!(
+            Affinity::of_request("GET", "/bio_auth/challenge_audio/ch-1", &[]),
+            Affinity::Session("ch-1".into())
+        );
+        assert_eq!(Affinity::of_request("GET", "/bio_auth/queue", &[]), Affinity::Any);
+        assert_eq!(
+            Affinity::of_request(
+                "POST",
+                "/request_unfreeze",
+                &body(serde_json::json!({ "payload": { "handle": "alice", "challenge_id": "ch-2" } }))
+            ),
+            Affinity::Session("ch-2".into())
+        );
+        assert_eq!(
+            Affinity::of_request("POST", "/bio_auth", &body(serde_json::json!({ "handle": "alice" }))),
+            Affinity::Handle("alice".into())
+        );
+        assert_eq!(
+            Affinity::of_request("POST", "/transfer", &body(serde_json::json!({ "payload": { "from_handle": "bob" } }))),
+            Affinity::Handle("bob".into())
+        );
+        assert_eq!(Affinity::of_request("POST", "/create_wallet", b"not json"), Affinity::Any);
+    }
+
+    #[test]
+    fn test_handles_stay_put_and_only_failed_enclaves_move() {
+        let router = router(&["http://a", "http://b", "http://c"]);
+        let handles: Vec<Affinity> = (0..30).map(|i| Affinity::Handle(format!("user{}", i))).collect();
+        let before: Vec<usize> = handles.iter().map(|h| router.route(h).index).collect();
+        assert_eq!(before, handles.iter().map(|h| router.route(h).index).collect::<Vec<_>>());
+        assert!((0..3).all(|i| before.contains(&i)));
+
+        router.mark(&router.enclaves()[1], false);
+        for (handle, was) in handles.iter().zip(&before) {
+            let now = router.route(handle).index;
+            assert_ne!(now, 1);
+            if *was != 1 {
+                assert_eq!(now, *was);
+            }
+        }
+    }
+
+    #[test]
+    fn test_sessions_stick_to_their_enclave() {
+        let router = router(&["http://a", "http://b"]);
+        let issuer = &router.enclaves()[1];
+        router.pin_sessions(issuer, br#"{"challenge_id":"ch-1","phrase":"blue river"}"#);
+        router.mark(issuer, false);
+
+        // Even when down: no other enclave knows the challenge
+        assert_eq!(router.route(&Affinity::Session("ch-1".into())).index, 1);
+        // Unknown sessions and anonymous requests avoid the down enclave
+        assert_eq!(router.route(&Affinity::Session("ch-9".into())).index, 0);
+        assert_eq!(router.route(&Affinity::Any).index, 0);
+    }
+
+    #[test]
+    fn test_public_keys_verify_payloads() {
+        use ed25519_dalek::{Signer, SigningKey};
+
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let public_key = hex::encode(key.verifying_key().as_bytes());
+        let router = EnclaveRouter::new(vec![EnclaveConfig { url: "http://a".into(), public_key: Some(public_key) }])
+            .unwrap();
+        let payload = b"alice".to_vec();
+        let message = bcs::to_bytes(&(0u8, 42u64, &payload)).unwrap();
+        let signature = hex::encode(key.sign(&message).to_bytes());
+
+        let enclave = &router.enclaves()[0];
+        assert!(enclave.verifies(0, 42, &payload, &signature));
+        assert!(!enclave.verifies(0, 43, &payload, &signature));
+        assert!(!enclave.verifies(0, 42, &payload, "zz"));
+        assert!(EnclaveRouter::single("http://a").enclaves()[0].verifies(0, 42, &payload, "zz"));
+        assert!(EnclaveRouter::new(vec![EnclaveConfig { url: "http://a".into(), public_key: Some("abc".into()) }])
+            .is_err());
+    }
+}
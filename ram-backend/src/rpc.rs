@@ -0,0 +1,271 @@
+// Sui JSON-RPC client with multi-endpoint failover
+//
+// Calls go to the first healthy endpoint in configured order. Transport
+// failures (connection errors, timeouts, 5xx) mark the endpoint unhealthy and
+// the call moves on to the next one; JSON-RPC errors are returned as-is since
+// another fullnode would answer the same. A background health check brings
+// endpoints back once they respond again.
+
+use crate::chaos;
+use anyhow::{anyhow, Result};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Per-request timeout, so a hung fullnode fails over instead of stalling
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// JSON-RPC error returned by a fullnode
+#[derive(Debug, Deserialize, thiserror::Error)]
+#[error("RPC error: {message} ({code})")]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl RpcError {
+    /// Whether the fullnode rejected the cursor itself, typically because the
+    /// events it points at were pruned
+    pub fn is_stale_cursor(&self) -> bool {
+        let message = self.message.to_lowercase();
+        message.contains("cursor")
+            || message.contains("could not find the referenced")
+            || message.contains("pruned")
+    }
+}
+
+/// Latency and health for one endpoint, as reported by `/admin/rpc_metrics`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct EndpointMetrics {
+    pub url: String,
+    pub healthy: bool,
+    pub requests: u64,
+    pub failures: u64,
+    pub avg_latency_ms: f64,
+    pub last_latency_ms: Option<f64>,
+    pub last_error: Option<String>,
+}
+
+struct Endpoint {
+    url: String,
+    healthy: AtomicBool,
+    metrics: Mutex<EndpointMetrics>,
+}
+
+impl Endpoint {
+    fn record(&self, latency_ms: f64, error: Option<String>) {
+        self.healthy.store(error.is_none(), Ordering::Relaxed);
+        let mut m = self.metrics.lock().unwrap();
+        m.avg_latency_ms = (m.avg_latency_ms * m.requests as f64 + latency_ms) / (m.requests + 1) as f64;
+        m.requests += 1;
+        m.last_latency_ms = Some(latency_ms);
+        if error.is_some() {
+            m.failures += 1;
+            m.last_error = error;
+        }
+    }
+}
+
+pub struct SuiRpcClient {
+    http: Client,
+    endpoints: Vec<Endpoint>,
+}
+
+impl SuiRpcClient {
+    pub fn new(urls: Vec<String>) -> Self {
+        let http = Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .expect("Failed to build RPC HTTP client");
+        let endpoints = urls
+            .into_iter()
+            .map(|url| Endpoint {
+                metrics: Mutex::new(EndpointMetrics { url: url.clone(), ..Default::default() }),
+                url,
+                healthy: AtomicBool::new(true),
+            })
+            .collect();
+        Self { http, endpoints }
+    }
+
+    /// Parse a comma-separated list of RPC URLs, in order of preference
+    pub fn from_list(urls: &str) -> Self {
+        Self::new(parse_urls(urls))
+    }
+
+    /// Call `method`, failing over across endpoints; returns the JSON-RPC `result`
+    pub async fn call(&self, method: &str, params: Value) -> Result<Value> {
+        let body = json!({
+            "jsonrpc": "2.0",
+            "method": method,
+            "params": params,
+            "id": 1
+        });
+
+        // Healthy endpoints first; unhealthy ones are still tried as a last resort
+        let (healthy, unhealthy): (Vec<&Endpoint>, Vec<&Endpoint>) = self
+            .endpoints
+            .iter()
+            .partition(|e| e.healthy.load(Ordering::Relaxed));
+
+        let mut last_error = anyhow!("No Sui RPC endpoints configured");
+        for endpoint in healthy.into_iter().chain(unhealthy) {
+            let started = Instant::now();
+            let result = self.send(&endpoint.url, &body).await;
+            let latency_ms = started.elapsed().as_secs_f64() * 1000.0;
+
+            match result {
+                Ok(resp) => {
+                    endpoint.record(latency_ms, None);
+                    if let Some(error) = resp.get("error") {
+                        let error: RpcError = serde_json::from_value(error.clone())?;
+                        return Err(error.into());
+                    }
+                    return resp
+                        .get("result")
+                        .cloned()
+                        .ok_or_else(|| anyhow!("No result in {} response", method));
+                }
+                Err(e) => {
+                    warn!("Sui RPC {} failed on {}: {}", method, endpoint.url, e);
+                    endpoint.record(latency_ms, Some(e.to_string()));
+                    last_error = e;
+                }
+            }
+        }
+
+        Err(last_error)
+    }
+
+    async fn send(&self, url: &str, body: &Value) -> Result<Value> {
+        chaos::upstream_latency().await;
+        let resp = self.http.post(url).json(body).send().await?;
+        if resp.status().is_server_error() {
+            return Err(anyhow!("HTTP {}", resp.status()));
+        }
+        Ok(resp.json().await?)
+    }
+
+    /// Probe every endpoint once, updating its health
+    pub async fn check_health(&self) {
+        let body = json!({
+            "jsonrpc": "2.0",
+            "method": "sui_getLatestCheckpointSequenceNumber",
+            "params": [],
+            "id": 1
+        });
+        for endpoint in &self.endpoints {
+            let was_healthy = endpoint.healthy.load(Ordering::Relaxed);
+            let started = Instant::now();
+            let error = self.send(&endpoint.url, &body).await.err().map(|e| e.to_string());
+            let healthy = error.is_none();
+            endpoint.record(started.elapsed().as_secs_f64() * 1000.0, error);
+
+            if healthy && !was_healthy {
+                info!("Sui RPC endpoint {} recovered", endpoint.url);
+            }
+        }
+    }
+
+    /// Re-check endpoint health every `interval` in the background
+    pub fn spawn_health_checks(self: &Arc<Self>, interval: Duration) {
+        let client = self.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                client.check_health().await;
+            }
+        });
+    }
+
+    pub fn healthy_count(&self) -> usize {
+        self.endpoints
+            .iter()
+            .filter(|e| e.healthy.load(Ordering::Relaxed))
+            .count()
+    }
+
+    pub fn metrics(&self) -> Vec<EndpointMetrics> {
+        self.endpoints
+            .iter()
+            .map(|e| EndpointMetrics {
+                healthy: e.healthy.load(Ordering::Relaxed),
+                ..e.metrics.lock().unwrap().clone()
+            })
+            .collect()
+    }
+}
+
+/// Split a comma-separated URL list, dropping blanks
+pub fn parse_urls(urls: &str) -> Vec<String> {
+    urls.split(',')
+        .map(str::trim)
+        .filter(|u| !u.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    async fn fullnode(body: Value) -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(wiremock::matchers::method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(body))
+            .mount(&server)
+            .await;
+        server
+    }
+
+    #[test]
+    fn test_parse_urls() {
+        assert_eq!(
+            parse_urls(" https://a.example , ,https://b.example"),
+            vec!["https://a.example", "https://b.example"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fails_over_to_next_endpoint() {
+        let backup = fullnode(json!({ "jsonrpc": "2.0", "id": 1, "result": "42" })).await;
+        let client = SuiRpcClient::new(vec!["http://127.0.0.1:1".to_string(), backup.uri()]);
+
+        let result = client.call("sui_getLatestCheckpointSequenceNumber", json!([])).await.unwrap();
+        assert_eq!(result, "42");
+
+        let metrics = client.metrics();
+        assert!(!metrics[0].healthy);
+        assert_eq!(metrics[0].failures, 1);
+        assert!(metrics[1].healthy);
+        assert_eq!(client.healthy_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_rpc_errors_do_not_fail_over() {
+        let node = fullnode(json!({
+            "jsonrpc": "2.0", "id": 1,
+            "error": { "code": -32602, "message": "Invalid params" },
+        }))
+        .await;
+        let client = SuiRpcClient::new(vec![node.uri(), "http://127.0.0.1:1".to_string()]);
+
+        let err = client.call("suix_queryEvents", json!([])).await.unwrap_err();
+        assert_eq!(err.downcast_ref::<RpcError>().unwrap().code, -32602);
+        assert!(client.metrics()[0].healthy);
+        assert_eq!(client.metrics()[1].requests, 0);
+    }
+
+    #[test]
+    fn test_stale_cursor_detection() {
+        let error = |message: &str| RpcError { code: -32602, message: message.to_string() };
+        assert!(error("Could not find the referenced transaction events [TransactionEventsDigest(abc)]").is_stale_cursor());
+        assert!(error("Invalid params: cursor not found").is_stale_cursor());
+        assert!(!error("Request rate limited").is_stale_cursor());
+    }
+}
@@ -0,0 +1,62 @@
+// Request signing for backend → Nautilus calls
+// Must match the enclave's request_auth module: same headers, same canonical
+// form "<timestamp>\n<METHOD>\n<path>\n<query>\n" followed by the raw body,
+// where the query is its `&`-separated pairs sorted and rejoined
+
+use hmac::{Hmac, Mac};
+use reqwest::RequestBuilder;
+use sha2::Sha256;
+
+pub const TIMESTAMP_HEADER: &str = "x-ram-timestamp";
+pub const SIGNATURE_HEADER: &str = "x-ram-signature";
+
+/// A query string's pairs, sorted and rejoined
+fn canonical_query(query: &str) -> String {
+    let mut pairs: Vec<&str> = query.split('&').filter(|pair| !pair.is_empty()).collect();
+    pairs.sort_unstable();
+    pairs.join("&")
+}
+
+/// Hex HMAC-SHA256 of a request; `path` may carry a `?query`
+pub fn request_signature(key: &str, timestamp_ms: u64, method: &str, path: &str, body: &[u8]) -> String {
+    let (path, query) = path.split_once('?').unwrap_or((path, ""));
+    let mut mac = Hmac::<Sha256>::new_from_slice(key.as_bytes()).expect("HMAC can take key of any size");
+    mac.update(format!("{}\n{}\n{}\n{}\n", timestamp_ms, method.to_uppercase(), path, canonical_query(query)).as_bytes());
+    mac.update(body);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Add signature headers to an enclave request; unchanged when no key is configured
+pub fn sign_request(
+    request: RequestBuilder,
+    key: Option<&str>,
+    method: &str,
+    path: &str,
+    body: &[u8],
+) -> RequestBuilder {
+    let Some(key) = key else {
+        return request;
+    };
+    let timestamp_ms = chrono::Utc::now().timestamp_millis() as u64;
+    request
+        .header(TIMESTAMP_HEADER, timestamp_ms.to_string())
+        .header(SIGNATURE_HEADER, request_signature(key, timestamp_ms, method, path, body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signature_matches_enclave_vector() {
+        // Same vector as the enclave's request_auth tests
+        assert_eq!(
+            request_signature("test-signing-key", 1_700_000_000_000, "post", "/transfer", br#"{"payload":{}}"#),
+            "4838b9eda1f27329d221144f12bc949e5ef275b27c40e8f87b3299bda1c1d1a9"
+        );
+        assert_eq!(
+            request_signature("test-signing-key", 1_700_000_000_000, "GET", "/health?handle=alice&b=2", b""),
+            "760f85921c72c0d6c9f154fdf699d63262ce87d09285bce8b068830f3d0129fb"
+        );
+    }
+}
@@ -0,0 +1,448 @@
+// Sponsored Sui transactions for onboarding
+// The backend builds the `wallet::create_wallet` transaction with the user as
+// sender (the registry keys wallets by sender) and its own sponsor address as
+// gas owner, so a new user needs no SUI. The user signs the prepared bytes;
+// the sponsor adds its signature at submission. Transactions are encoded here
+// in Sui's BCS layout, since the JSON-RPC builders only make self-paid ones;
+// enclave registration (registration.rs) builds its transactions with it too.
+
+use anyhow::{anyhow, Context, Result};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use blake2::digest::consts::U32;
+use blake2::{Blake2b, Digest};
+use ed25519_dalek::{Signer, SigningKey};
+use serde::ser::{SerializeStructVariant, Serializer};
+use serde::Serialize;
+use serde_json::{json, Value};
+use tracing::warn;
+
+use crate::config::{GasConfig, SponsorConfig};
+use crate::gas;
+use crate::rpc::SuiRpcClient;
+
+/// Signature scheme flag of an Ed25519 Sui signature
+const ED25519_FLAG: u8 = 0x00;
+/// Intent prefix of a transaction (scope TransactionData, version 0, app Sui)
+const TRANSACTION_INTENT: [u8; 3] = [0, 0, 0];
+/// Gas coins considered when picking one to pay with
+const GAS_COIN_PAGE: u32 = 50;
+
+// ============================================================================
+// Sui transaction layout (BCS; variant order must match sui-types)
+// ============================================================================
+
+pub(crate) type Address = [u8; 32];
+/// (object ID, version, digest)
+pub(crate) type ObjectRef = (Address, u64, Vec<u8>);
+
+#[derive(Serialize)]
+pub(crate) enum TransactionData {
+    V1(TransactionDataV1),
+}
+
+#[derive(Serialize)]
+pub(crate) struct TransactionDataV1 {
+    pub(crate) kind: TransactionKind,
+    pub(crate) sender: Address,
+    pub(crate) gas_data: GasData,
+    pub(crate) expiration: TransactionExpiration,
+}
+
+#[derive(Serialize)]
+pub(crate) enum TransactionKind {
+    ProgrammableTransaction(ProgrammableTransaction),
+}
+
+#[derive(Serialize)]
+pub(crate) struct ProgrammableTransaction {
+    pub(crate) inputs: Vec<CallArg>,
+    pub(crate) commands: Vec<Command>,
+}
+
+#[derive(Serialize)]
+pub(crate) enum CallArg {
+    Pure(Vec<u8>),
+    Object(ObjectArg),
+}
+
+/// Owned objects (variant 0) and shared objects (variant 1); receiving
+/// objects are never passed
+pub(crate) enum ObjectArg {
+    ImmOrOwnedObject(ObjectRef),
+    SharedObject { id: Address, initial_shared_version: u64, mutable: bool },
+}
+
+impl Serialize for ObjectArg {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            ObjectArg::ImmOrOwnedObject(object) => {
+                serializer.serialize_newtype_variant("ObjectArg", 0, "ImmOrOwnedObject", object)
+            }
+            ObjectArg::SharedObject { id, initial_shared_version, mutable } => {
+                let mut variant = serializer.serialize_struct_variant("ObjectArg", 1, "SharedObject", 3)?;
+                variant.serialize_field("id", id)?;
+                variant.serialize_field("initial_shared_version", initial_shared_version)?;
+                variant.serialize_field("mutable", mutable)?;
+                variant.end()
+            }
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub(crate) enum Command {
+    MoveCall(Box<ProgrammableMoveCall>),
+}
+
+#[derive(Serialize)]
+pub(crate) struct ProgrammableMoveCall {
+    pub(crate) package: Address,
+    pub(crate) module: String,
+    pub(crate) function: String,
+    pub(crate) type_arguments: Vec<TypeTag>,
+    pub(crate) arguments: Vec<Argument>,
+}
+
+/// Transaction inputs (variant 1, after `GasCoin`) and results of earlier
+/// commands (variant 2); nested results are never referenced
+pub(crate) enum Argument {
+    Input(u16),
+    Result(u16),
+}
+
+impl Serialize for Argument {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Argument::Input(index) => serializer.serialize_newtype_variant("Argument", 1, "Input", index),
+            Argument::Result(index) => serializer.serialize_newtype_variant("Argument", 2, "Result", index),
+        }
+    }
+}
+
+/// Only struct type tags are needed (variant 7, after the primitives and vector)
+pub(crate) enum TypeTag {
+    Struct(Box<StructTag>),
+}
+
+impl Serialize for TypeTag {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let TypeTag::Struct(tag) = self;
+        serializer.serialize_newtype_variant("TypeTag", 7, "Struct", tag)
+    }
+}
+
+#[derive(Serialize)]
+pub(crate) struct StructTag {
+    pub(crate) address: Address,
+    pub(crate) module: String,
+    pub(crate) name: String,
+    pub(crate) type_params: Vec<TypeTag>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct GasData {
+    pub(crate) payment: Vec<ObjectRef>,
+    pub(crate) owner: Address,
+    pub(crate) price: u64,
+    pub(crate) budget: u64,
+}
+
+/// No expiration, or valid through the given epoch
+#[derive(Serialize)]
+pub(crate) enum TransactionExpiration {
+    None,
+    Epoch(u64),
+}
+
+// ============================================================================
+// Sponsor
+// ============================================================================
+
+/// Enclave-signed create-wallet authorization, as returned by `/create_wallet`
+#[derive(Debug, Clone)]
+pub struct CreateWalletAuthorization {
+    pub handle: Vec<u8>,
+    pub timestamp_ms: u64,
+    pub signature: Vec<u8>,
+    /// Index of the enclave that signed it, whose `Enclave` object verifies it
+    pub enclave: usize,
+}
+
+/// The sponsor key and the objects wallet creation touches
+#[derive(Clone)]
+pub struct Sponsor {
+    key: SigningKey,
+    package_id: Address,
+    registry_id: Address,
+    enclave_ids: Vec<Address>,
+    enclave_package_id: Address,
+    pub config: SponsorConfig,
+}
+
+impl Sponsor {
+    pub fn new(config: SponsorConfig, package_id: &str) -> Result<Self> {
+        let seed: [u8; 32] = hex::decode(config.private_key.trim_start_matches("0x"))
+            .context("SPONSOR_PRIVATE_KEY is not hex")?
+            .try_into()
+            .map_err(|_| anyhow!("SPONSOR_PRIVATE_KEY must be 32 bytes"))?;
+        Ok(Self {
+            key: SigningKey::from_bytes(&seed),
+            package_id: parse_address(package_id)?,
+            registry_id: parse_address(&config.registry_id)?,
+            enclave_ids: config.enclave_ids.iter().map(|id| parse_address(id)).collect::<Result<_>>()?,
+            enclave_package_id: parse_address(&config.enclave_package_id)?,
+            config,
+        })
+    }
+
+    /// Sui address of the sponsor key
+    pub fn address(&self) -> String {
+        format!("0x{}", hex::encode(ed25519_address(self.key.verifying_key().as_bytes())))
+    }
+
+    /// BCS bytes of a sponsored `wallet::create_wallet<XWALLET>` from `sender`
+    ///
+    /// The transaction is dry-run and budgeted as `/api/tx/estimate` would,
+    /// capped at the configured budget; if the dry run can't be made it keeps
+    /// the configured budget, and if it fails the transaction isn't prepared.
+    pub async fn build_create_wallet(
+        &self,
+        rpc: &SuiRpcClient,
+        sender: &str,
+        authorization: &CreateWalletAuthorization,
+        gas: &GasConfig,
+    ) -> Result<Vec<u8>> {
+        let sender = parse_address(sender)?;
+        let registry_version = shared_version(rpc, &self.registry_id).await?;
+        let enclave_id = *self
+            .enclave_ids
+            .get(authorization.enclave)
+            .ok_or_else(|| anyhow!("No Enclave object for enclave {}", authorization.enclave))?;
+        let enclave_version = shared_version(rpc, &enclave_id).await?;
+        let gas_coin = self.gas_coin(rpc).await?;
+        let price = parse_u64(&rpc.call("suix_getReferenceGasPrice", json!([])).await?)
+            .context("unexpected reference gas price")?;
+
+        let inputs = vec![
+            CallArg::Object(ObjectArg::SharedObject {
+                id: self.registry_id,
+                initial_shared_version: registry_version,
+                mutable: true,
+            }),
+            CallArg::Pure(bcs::to_bytes(&authorization.handle)?),
+            CallArg::Pure(bcs::to_bytes(&authorization.timestamp_ms)?),
+            CallArg::Pure(bcs::to_bytes(&authorization.signature)?),
+            CallArg::Object(ObjectArg::SharedObject {
+                id: enclave_id,
+                initial_shared_version: enclave_version,
+                mutable: false,
+            }),
+        ];
+        let call = ProgrammableMoveCall {
+            package: self.package_id,
+            module: "wallet".to_string(),
+            function: "create_wallet".to_string(),
+            type_arguments: vec![TypeTag::Struct(Box::new(StructTag {
+                address: self.enclave_package_id,
+                module: "core".to_string(),
+                name: "XWALLET".to_string(),
+                type_params: vec![],
+            }))],
+            arguments: (0..inputs.len() as u16).map(Argument::Input).collect(),
+        };
+        let mut transaction = TransactionData::V1(TransactionDataV1 {
+            kind: TransactionKind::ProgrammableTransaction(ProgrammableTransaction {
+                inputs,
+                commands: vec![Command::MoveCall(Box::new(call))],
+            }),
+            sender,
+            gas_data: GasData {
+                payment: vec![gas_coin],
+                owner: ed25519_address(self.key.verifying_key().as_bytes()),
+                price,
+                budget: self.config.gas_budget,
+            },
+            expiration: TransactionExpiration::None,
+        });
+        let tx_bytes = bcs::to_bytes(&transaction)?;
+
+        let budget = match gas::dry_run(rpc, &tx_bytes, gas).await {
+            Ok(estimate) if estimate.status == "success" => estimate.budget.min(self.config.gas_budget),
+            Ok(estimate) => {
+                return Err(anyhow!(
+                    "Wallet creation would fail: {}",
+                    estimate.error.as_deref().unwrap_or("unknown error")
+                ))
+            }
+            Err(e) => {
+                warn!("Dry run of a sponsored wallet creation failed, using the configured budget: {}", e);
+                return Ok(tx_bytes);
+            }
+        };
+        let TransactionData::V1(data) = &mut transaction;
+        data.gas_data.budget = budget;
+        Ok(bcs::to_bytes(&transaction)?)
+    }
+
+    /// The sponsor's serialized signature over transaction bytes (base64)
+    pub fn sign(&self, tx_bytes: &[u8]) -> String {
+        let signature = self.key.sign(&transaction_digest(tx_bytes));
+        let mut serialized = vec![ED25519_FLAG];
+        serialized.extend_from_slice(&signature.to_bytes());
+        serialized.extend_from_slice(self.key.verifying_key().as_bytes());
+        STANDARD.encode(serialized)
+    }
+
+    /// Sponsor SUI coin able to cover the gas budget (the largest one)
+    async fn gas_coin(&self, rpc: &SuiRpcClient) -> Result<ObjectRef> {
+        gas_coin(rpc, &self.address(), self.config.gas_budget).await
+    }
+}
+
+/// SUI coin of `owner` able to cover `budget` (the largest one)
+pub(crate) async fn gas_coin(rpc: &SuiRpcClient, owner: &str, budget: u64) -> Result<ObjectRef> {
+    let coins = rpc.call("suix_getCoins", json!([owner, "0x2::sui::SUI", null, GAS_COIN_PAGE])).await?;
+    let coin = coins["data"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|coin| Some((parse_u64(&coin["balance"])?, coin)))
+        .filter(|(balance, _)| *balance >= budget)
+        .max_by_key(|(balance, _)| *balance)
+        .map(|(_, coin)| coin)
+        .ok_or_else(|| anyhow!("{} has no coin covering the gas budget", owner))?;
+    object_ref(coin, "coinObjectId")
+}
+
+/// Current reference to an owned or immutable object
+pub(crate) async fn owned_object(rpc: &SuiRpcClient, id: &Address) -> Result<ObjectRef> {
+    let id = format!("0x{}", hex::encode(id));
+    let object = rpc.call("sui_getObject", json!([id, {}])).await?;
+    object_ref(&object["data"], "objectId").with_context(|| format!("Object {} not found", id))
+}
+
+/// (ID, version, digest) of an object as the RPC describes it
+fn object_ref(object: &Value, id_field: &str) -> Result<ObjectRef> {
+    let id = parse_address(object[id_field].as_str().unwrap_or_default())?;
+    let version = parse_u64(&object["version"]).context("object without version")?;
+    let digest = bs58::decode(object["digest"].as_str().unwrap_or_default())
+        .into_vec()
+        .context("object digest is not base58")?;
+    Ok((id, version, digest))
+}
+
+/// Execute a signed transaction; returns its digest once effects are final
+pub async fn execute(rpc: &SuiRpcClient, tx_bytes: &[u8], signatures: &[String]) -> Result<String> {
+    let result = rpc
+        .call(
+            "sui_executeTransactionBlock",
+            json!([STANDARD.encode(tx_bytes), signatures, { "showEffects": true }, "WaitForLocalExecution"]),
+        )
+        .await?;
+    let digest = result["digest"].as_str().ok_or_else(|| anyhow!("No digest in execution result"))?;
+    let status = &result["effects"]["status"];
+    if status["status"] != "success" {
+        return Err(anyhow!(
+            "Transaction {} failed: {}",
+            digest,
+            status["error"].as_str().unwrap_or("unknown error")
+        ));
+    }
+    Ok(digest.to_string())
+}
+
+/// What a Sui key signs for transaction bytes
+pub fn transaction_digest(tx_bytes: &[u8]) -> [u8; 32] {
+    let mut hasher = Blake2b::<U32>::new();
+    hasher.update(TRANSACTION_INTENT);
+    hasher.update(tx_bytes);
+    hasher.finalize().into()
+}
+
+/// Sui address of an Ed25519 public key
+pub fn ed25519_address(public_key: &[u8; 32]) -> Address {
+    let mut hasher = Blake2b::<U32>::new();
+    hasher.update([ED25519_FLAG]);
+    hasher.update(public_key);
+    hasher.finalize().into()
+}
+
+/// 0x-prefixed (or bare) 32-byte hex address
+pub fn parse_address(address: &str) -> Result<Address> {
+    let hex = address.trim().trim_start_matches("0x");
+    hex::decode(format!("{:0>64}", hex))
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| anyhow!("Invalid Sui address '{}'", address))
+}
+
+/// Initial shared version of a shared object
+pub(crate) async fn shared_version(rpc: &SuiRpcClient, id: &Address) -> Result<u64> {
+    let id = format!("0x{}", hex::encode(id));
+    let object = rpc.call("sui_getObject", json!([id, { "showOwner": true }])).await?;
+    parse_u64(&object["data"]["owner"]["Shared"]["initial_shared_version"])
+        .ok_or_else(|| anyhow!("Object {} is not shared", id))
+}
+
+/// u64 the RPC may send as a number or a string
+fn parse_u64(value: &Value) -> Option<u64> {
+    value.as_u64().or_else(|| value.as_str()?.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transaction_layout() {
+        let transaction = TransactionData::V1(TransactionDataV1 {
+            kind: TransactionKind::ProgrammableTransaction(ProgrammableTransaction {
+                inputs: vec![
+                    CallArg::Object(ObjectArg::SharedObject { id: [1; 32], initial_shared_version: 7, mutable: true }),
+                    CallArg::Pure(bcs::to_bytes(&b"alice".to_vec()).unwrap()),
+                ],
+                commands: vec![],
+            }),
+            sender: [2; 32],
+            gas_data: GasData { payment: vec![([3; 32], 9, vec![4; 32])], owner: [5; 32], price: 1000, budget: 50 },
+            expiration: TransactionExpiration::None,
+        });
+        let bytes = bcs::to_bytes(&transaction).unwrap();
+
+        // V1, programmable, two inputs, the first a mutable shared object
+        assert_eq!(&bytes[..4], &[0, 0, 2, 1]);
+        assert_eq!(bytes[4], 1);
+        assert_eq!(&bytes[5..37], &[1; 32]);
+        assert_eq!(&bytes[37..45], &7u64.to_le_bytes());
+        assert_eq!(bytes[45], 1);
+        // then a pure vector<u8>, wrapped in its own length
+        assert_eq!(&bytes[46..53], &[0, 6, 5, b'a', b'l', b'i', b'c']);
+        // gas payment digests are length-prefixed, and there's no expiration
+        assert!(bytes.windows(33).any(|w| w[0] == 32 && w[1..] == [4; 32]));
+        assert_eq!(bytes.last(), Some(&0));
+    }
+
+    #[test]
+    fn test_sponsor_signature_is_flagged_and_verifiable() {
+        use ed25519_dalek::{Signature, Verifier};
+
+        let config = SponsorConfig::new(hex::encode([9u8; 32]), "0x5".into(), vec!["0x6".into()], "0x7".into());
+        let sponsor = Sponsor::new(config, "0x8").unwrap();
+        let serialized = STANDARD.decode(sponsor.sign(b"tx")).unwrap();
+        assert_eq!(serialized.len(), 97);
+        assert_eq!(serialized[0], ED25519_FLAG);
+
+        let key = SigningKey::from_bytes(&[9u8; 32]).verifying_key();
+        assert_eq!(&serialized[65..], key.as_bytes());
+        let signature = Signature::from_slice(&serialized[1..65]).unwrap();
+        assert!(key.verify(&transaction_digest(b"tx"), &signature).is_ok());
+        assert_eq!(sponsor.address(), format!("0x{}", hex::encode(ed25519_address(key.as_bytes()))));
+    }
+
+    #[test]
+    fn test_short_addresses_are_padded() {
+        assert_eq!(parse_address("0x6").unwrap()[31], 6);
+        assert!(parse_address("0xzz").is_err());
+        assert!(parse_address(&format!("0x{}", "1".repeat(66))).is_err());
+    }
+}
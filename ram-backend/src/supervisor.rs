@@ -0,0 +1,219 @@
+// Background task supervisor
+// The indexer and periodic jobs run for the life of the process, so one that
+// returns an error or panics would otherwise stop for good while the API kept
+// serving stale data. Supervised tasks are restarted with exponential backoff
+// (see RestartPolicy); after too many consecutive failures a task is left
+// down and reported as failed in GET /health.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tracing::{error, info, warn};
+
+use crate::config::RestartPolicy;
+
+/// Where a supervised task is in its lifecycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskState {
+    Running,
+    /// Failed, waiting out the backoff before its next start
+    Restarting,
+    /// Returned without error; not restarted
+    Finished,
+    /// Failed more often in a row than the policy allows; not restarted
+    Failed,
+}
+
+/// A supervised task, as reported by GET /health
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TaskStatus {
+    pub state: TaskState,
+    /// Consecutive restarts (reset once the task runs for `reset_after`)
+    pub restarts: u32,
+    /// Restarts since the process started
+    pub total_restarts: u32,
+    pub last_error: Option<String>,
+    pub last_failure_ms: Option<i64>,
+}
+
+/// Restarts background tasks that fail, and tracks their health
+#[derive(Default)]
+pub struct Supervisor {
+    policy: RestartPolicy,
+    tasks: Mutex<BTreeMap<&'static str, TaskStatus>>,
+}
+
+impl Supervisor {
+    pub fn new(policy: RestartPolicy) -> Self {
+        Self { policy, tasks: Mutex::default() }
+    }
+
+    /// Run `task` in the background, starting it again whenever it fails or panics
+    pub fn spawn<F, Fut>(self: &Arc<Self>, name: &'static str, task: F)
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        self.update(name, |status| status.state = TaskState::Running);
+        let supervisor = self.clone();
+        tokio::spawn(async move {
+            loop {
+                let started = Instant::now();
+                // Spawned on its own so a panic surfaces as a JoinError here
+                let failure = match tokio::spawn(task()).await {
+                    Ok(Ok(())) => {
+                        info!("Task {} finished", name);
+                        supervisor.update(name, |status| status.state = TaskState::Finished);
+                        return;
+                    }
+                    Ok(Err(e)) => e.to_string(),
+                    Err(e) if e.is_panic() => format!("panicked: {}", panic_message(e.into_panic())),
+                    Err(e) => e.to_string(),
+                };
+
+                let ran_long_enough = started.elapsed() >= supervisor.policy.reset_after;
+                let status = supervisor.update(name, |status| {
+                    status.restarts = if ran_long_enough { 1 } else { status.restarts + 1 };
+                    status.last_error = Some(failure.clone());
+                    status.last_failure_ms = Some(chrono::Utc::now().timestamp_millis());
+                    status.state = if status.restarts > supervisor.policy.max_restarts {
+                        TaskState::Failed
+                    } else {
+                        status.total_restarts += 1;
+                        TaskState::Restarting
+                    };
+                });
+                if status.state == TaskState::Failed {
+                    error!(
+                        "Task {} failed {} times in a row, giving up: {}",
+                        name, supervisor.policy.max_restarts + 1, failure
+                    );
+                    return;
+                }
+
+                let backoff = supervisor.policy.backoff(status.restarts);
+                warn!(
+                    "Task {} failed ({}), restarting in {:?} (restart {}/{})",
+                    name, failure, backoff, status.restarts, supervisor.policy.max_restarts
+                );
+                tokio::time::sleep(backoff).await;
+                supervisor.update(name, |status| status.state = TaskState::Running);
+            }
+        });
+    }
+
+    /// Every supervised task by name
+    pub fn statuses(&self) -> BTreeMap<&'static str, TaskStatus> {
+        self.tasks.lock().unwrap().clone()
+    }
+
+    pub fn state(&self, name: &str) -> Option<TaskState> {
+        self.tasks.lock().unwrap().get(name).map(|status| status.state)
+    }
+
+    /// Whether no task has been given up on
+    pub fn is_healthy(&self) -> bool {
+        self.tasks.lock().unwrap().values().all(|status| status.state != TaskState::Failed)
+    }
+
+    fn update(&self, name: &'static str, change: impl FnOnce(&mut TaskStatus)) -> TaskStatus {
+        let mut tasks = self.tasks.lock().unwrap();
+        let status = tasks.entry(name).or_insert(TaskStatus {
+            state: TaskState::Running,
+            restarts: 0,
+            total_restarts: 0,
+            last_error: None,
+            last_failure_ms: None,
+        });
+        change(status);
+        status.clone()
+    }
+}
+
+fn panic_message(payload: Box<dyn std::any::Any + Send>) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::time::Duration;
+
+    fn policy(max_restarts: u32) -> RestartPolicy {
+        RestartPolicy {
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(4),
+            max_restarts,
+            reset_after: Duration::from_secs(60),
+        }
+    }
+
+    async fn settled(supervisor: &Supervisor, name: &str) -> TaskState {
+        for _ in 0..500 {
+            match supervisor.state(name) {
+                Some(state @ (TaskState::Finished | TaskState::Failed)) => return state,
+                _ => tokio::time::sleep(Duration::from_millis(2)).await,
+            }
+        }
+        panic!("task {} never settled", name);
+    }
+
+    #[test]
+    fn test_backoff_doubles_up_to_the_cap() {
+        let policy = RestartPolicy::default();
+        assert_eq!(policy.backoff(1), Duration::from_secs(1));
+        assert_eq!(policy.backoff(3), Duration::from_secs(4));
+        assert_eq!(policy.backoff(30), Duration::from_secs(60));
+    }
+
+    #[tokio::test]
+    async fn test_failures_and_panics_are_restarted() {
+        let supervisor = Arc::new(Supervisor::new(policy(5)));
+        let runs = Arc::new(AtomicU32::new(0));
+        let counter = runs.clone();
+        supervisor.spawn("flaky", move || {
+            let run = counter.fetch_add(1, Ordering::SeqCst);
+            async move {
+                match run {
+                    0 => Err(anyhow!("fullnode unreachable")),
+                    1 => panic!("cursor corrupted"),
+                    _ => Ok(()),
+                }
+            }
+        });
+
+        assert_eq!(settled(&supervisor, "flaky").await, TaskState::Finished);
+        assert_eq!(runs.load(Ordering::SeqCst), 3);
+        let status = &supervisor.statuses()["flaky"];
+        assert_eq!(status.total_restarts, 2);
+        assert_eq!(status.last_error.as_deref(), Some("panicked: cursor corrupted"));
+        assert!(supervisor.is_healthy());
+    }
+
+    #[tokio::test]
+    async fn test_task_is_given_up_on_after_max_restarts() {
+        let supervisor = Arc::new(Supervisor::new(policy(2)));
+        let runs = Arc::new(AtomicU32::new(0));
+        let counter = runs.clone();
+        supervisor.spawn("broken", move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+            async { Err(anyhow!("database gone")) }
+        });
+
+        assert_eq!(settled(&supervisor, "broken").await, TaskState::Failed);
+        // The first run and two restarts
+        assert_eq!(runs.load(Ordering::SeqCst), 3);
+        assert_eq!(supervisor.statuses()["broken"].total_restarts, 2);
+        assert!(!supervisor.is_healthy());
+    }
+}
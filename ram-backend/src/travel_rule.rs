@@ -0,0 +1,235 @@
+// Travel-rule envelopes for large transfers
+// Deployments that must pass originator/beneficiary information along with
+// transfers above a threshold (TRAVEL_RULE_THRESHOLDS, per coin type) have
+// the client attach it to the transfer as `travel_rule: {scheme, ciphertext}`,
+// encrypted for whoever must read it. The proxy stores the envelope and
+// appends `travel:<sha256 of the ciphertext>` to the memo, so the payload the
+// enclave signs, and the transfer on-chain, commit to it. Transfers over a
+// threshold without an envelope are refused. Either party can read the
+// envelope back (signed, scope `travel_rule`), and the indexer records the
+// transfer it travelled with.
+
+use anyhow::Result;
+use axum::{
+    body::Bytes,
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use tracing::{error, info};
+
+use crate::database::{Database, DbPool};
+use crate::handles::normalize_handle;
+use crate::models::{RamEvent, TravelRuleEnvelope, TravelRuleEnvelopeRequest, TravelRuleQuery};
+use crate::wallet_auth::authenticate;
+use crate::AppState;
+
+/// Memo tag followed by the envelope's hash
+pub const TRAVEL_RULE_MEMO_TAG: &str = "travel:";
+/// Largest envelope ciphertext, in bytes
+pub const MAX_ENVELOPE_BYTES: usize = 16 * 1024;
+/// Longest encryption scheme name
+pub const MAX_SCHEME_LEN: usize = 64;
+/// Longest transfer memo in bytes
+/// Must match MAX_MEMO_LEN in the enclave's validation.rs
+pub const MAX_MEMO_LEN: usize = 128;
+
+/// Proxied route envelopes are attached to
+const TRANSFER_PATH: &str = "/transfer";
+
+/// Key thresholds are looked up by: the coin type with its address in
+/// short form (`0x2::sui::SUI` for any spelling of it)
+pub fn coin_key(coin_type: &str) -> String {
+    match coin_type.split_once("::") {
+        Some((address, rest)) => {
+            let address = address.trim_start_matches("0x").trim_start_matches('0').to_lowercase();
+            format!("0x{}::{}", if address.is_empty() { "0" } else { &address }, rest)
+        }
+        None => coin_type.to_string(),
+    }
+}
+
+/// The envelope hash a transfer's memo references, if any
+pub fn envelope_hash_of(memo: &str) -> Option<&str> {
+    memo.split_whitespace()
+        .find_map(|token| token.strip_prefix(TRAVEL_RULE_MEMO_TAG))
+        .filter(|hash| is_envelope_hash(hash))
+}
+
+fn is_envelope_hash(hash: &str) -> bool {
+    hash.len() == 64 && hash.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+}
+
+/// `memo` with the envelope reference appended
+pub fn memo_with_reference(memo: Option<&str>, hash: &str) -> String {
+    match memo.map(str::trim).filter(|m| !m.is_empty()) {
+        Some(memo) => format!("{} {}{}", memo, TRAVEL_RULE_MEMO_TAG, hash),
+        None => format!("{}{}", TRAVEL_RULE_MEMO_TAG, hash),
+    }
+}
+
+/// Store a transfer's envelope and reference it in the memo
+///
+/// Other requests pass through untouched. 422 if the transfer is over its
+/// coin's threshold without an envelope, 400 for a malformed envelope, a
+/// memo that already references one or gets too long with the reference,
+/// 409 if the same ciphertext was stored for another transfer.
+pub async fn apply_envelope(state: &AppState, path: &str, body: Bytes) -> Result<Bytes, StatusCode> {
+    if path != TRANSFER_PATH {
+        return Ok(body);
+    }
+    let Ok(mut request) = serde_json::from_slice::<Value>(&body) else {
+        return Ok(body);
+    };
+    let payload = &mut request["payload"];
+    let Some(envelope) = payload.get("travel_rule").filter(|e| !e.is_null()) else {
+        let required = match (payload["amount"].as_u64(), payload["coin_type"].as_str()) {
+            (Some(amount), Some(coin_type)) => {
+                state.travel_rule.threshold(coin_type).is_some_and(|t| amount >= t as u64)
+            }
+            _ => false,
+        };
+        return if required { Err(StatusCode::UNPROCESSABLE_ENTITY) } else { Ok(body) };
+    };
+
+    let envelope: TravelRuleEnvelopeRequest =
+        serde_json::from_value(envelope.clone()).map_err(|_| StatusCode::BAD_REQUEST)?;
+    let scheme = envelope.scheme.trim();
+    let ciphertext = STANDARD.decode(envelope.ciphertext.trim()).map_err(|_| StatusCode::BAD_REQUEST)?;
+    if scheme.is_empty()
+        || scheme.len() > MAX_SCHEME_LEN
+        || scheme.chars().any(char::is_control)
+        || ciphertext.is_empty()
+        || ciphertext.len() > MAX_ENVELOPE_BYTES
+    {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let (Some(from_handle), Some(to_handle), Some(amount), Some(coin_type)) = (
+        payload["from_handle"].as_str(),
+        payload["to_handle"].as_str(),
+        payload["amount"].as_i64(),
+        payload["coin_type"].as_str(),
+    ) else {
+        return Err(StatusCode::BAD_REQUEST);
+    };
+
+    let memo = payload["memo"].as_str();
+    if memo.and_then(envelope_hash_of).is_some() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let hash = hex::encode(Sha256::digest(&ciphertext));
+    let memo = memo_with_reference(memo, &hash);
+    if memo.len() > MAX_MEMO_LEN {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let now_ms = chrono::Utc::now().timestamp_millis();
+    Database::store_travel_rule_envelope(
+        &state.db,
+        &hash,
+        from_handle,
+        to_handle,
+        amount,
+        coin_type,
+        scheme,
+        &STANDARD.encode(&ciphertext),
+        now_ms,
+    )
+    .await
+    .map_err(|e| {
+        error!("Failed to store travel-rule envelope: {}", e);
+        StatusCode::SERVICE_UNAVAILABLE
+    })?
+    .ok_or(StatusCode::CONFLICT)?;
+    info!("Stored travel-rule envelope {} for {} -> {}", hash, from_handle, to_handle);
+
+    let payload = payload.as_object_mut().ok_or(StatusCode::BAD_REQUEST)?;
+    payload.remove("travel_rule");
+    payload.insert("memo".to_string(), Value::String(memo));
+    serde_json::to_vec(&request).map(Bytes::from).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+/// Record the transfer a stored envelope travelled with, if its memo
+/// references one and it matches; true if it did
+pub async fn link_envelope(pool: &DbPool, event: &RamEvent) -> Result<bool> {
+    if event.event_type != "Transferred" {
+        return Ok(false);
+    }
+    let (Some(hash), Some(from_handle), Some(to_handle), Some(amount), Some(coin_type)) = (
+        event.memo.as_deref().and_then(envelope_hash_of),
+        &event.from_handle,
+        &event.to_handle,
+        event.amount,
+        &event.coin_type,
+    ) else {
+        return Ok(false);
+    };
+
+    let linked = Database::link_travel_rule_envelope(pool, hash, from_handle, to_handle, amount, coin_type, &event.tx_digest)
+        .await?;
+    if linked {
+        info!("Travel-rule envelope {} travelled with {}", hash, event.tx_digest);
+    }
+    Ok(linked)
+}
+
+/// Read an envelope as its originator or beneficiary
+///
+/// GET /api/travel_rule/:hash?handle= (signed, scope `travel_rule`, action
+/// `read`) — 404 unless `handle` is one of the two parties
+pub async fn get_envelope(
+    State(state): State<Arc<AppState>>,
+    Path(hash): Path<String>,
+    Query(query): Query<TravelRuleQuery>,
+    headers: HeaderMap,
+) -> Result<Json<TravelRuleEnvelope>, StatusCode> {
+    authenticate(&state, &headers, "travel_rule", "read", &query.handle, &[]).await?;
+    if !is_envelope_hash(&hash) {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let handle = normalize_handle(&query.handle);
+    let envelope = Database::get_travel_rule_envelope(&state.db, &hash)
+        .await
+        .map_err(|e| {
+            error!("Failed to fetch travel-rule envelope: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .filter(|e| e.from_handle == handle || e.to_handle == handle)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(envelope))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::TravelRuleConfig;
+
+    const HASH: &str = "5d41402abc4b2a76b9719d911017c5925d41402abc4b2a76b9719d911017c592";
+
+    #[test]
+    fn test_memos_reference_envelopes() {
+        assert_eq!(memo_with_reference(None, HASH), format!("travel:{}", HASH));
+        assert_eq!(memo_with_reference(Some("INV-2024-001"), HASH), format!("INV-2024-001 travel:{}", HASH));
+        assert_eq!(envelope_hash_of(&memo_with_reference(Some("refund:4f2a"), HASH)), Some(HASH));
+        assert_eq!(envelope_hash_of("travel:abc"), None);
+        assert_eq!(envelope_hash_of("INV-2024-001"), None);
+    }
+
+    #[test]
+    fn test_thresholds_match_any_spelling_of_the_coin() {
+        let long_sui = "0x0000000000000000000000000000000000000000000000000000000000000002::sui::SUI";
+        assert_eq!(coin_key(long_sui), "0x2::sui::SUI");
+
+        let config = TravelRuleConfig::parse(&format!("{}=1000000000000, 0xA1B::usdc::USDC=1000000000", long_sui)).unwrap();
+        assert_eq!(config.threshold("0x2::sui::SUI"), Some(1_000_000_000_000));
+        assert_eq!(config.threshold("0x0a1b::usdc::USDC"), Some(1_000_000_000));
+        assert_eq!(config.threshold("0x3::other::OTHER"), None);
+        assert!(TravelRuleConfig::parse("0x2::sui::SUI").is_err());
+        assert!(TravelRuleConfig::parse("0x2::sui::SUI=0").is_err());
+    }
+}
@@ -0,0 +1,104 @@
+// API versioning
+// Every route is served under /v1. The original flat routes still answer the
+// same way but carry Deprecation/Sunset headers and a Link to their /v1
+// successor, so breaking changes can ship as /v2 without stranding old
+// frontends. Clients may pin a version with the `API-Version` request header;
+// one the route can't serve is rejected with 406 instead of being ignored.
+
+use axum::{
+    extract::Request,
+    http::{header, HeaderMap, HeaderName, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use ram_sdk::{ApiErrorBody, ErrorCode};
+use serde_json::json;
+
+/// Version the /v1 routes (and, until their sunset, the flat routes) implement
+pub const CURRENT_API_VERSION: u32 = 1;
+/// Versions a client may ask for
+pub const SUPPORTED_API_VERSIONS: &[u32] = &[CURRENT_API_VERSION];
+/// Prefix of the current routes
+pub const CURRENT_API_PREFIX: &str = "/v1";
+
+/// Request header pinning a version, echoed on every response
+pub const API_VERSION_HEADER: HeaderName = HeaderName::from_static("api-version");
+/// RFC 9745 deprecation header
+pub const DEPRECATION_HEADER: HeaderName = HeaderName::from_static("deprecation");
+/// RFC 8594 sunset header
+pub const SUNSET_HEADER: HeaderName = HeaderName::from_static("sunset");
+
+/// Flat routes that stay put: load balancers probe /health and shouldn't chase a sunset
+pub const UNVERSIONED_PATHS: &[&str] = &["/health"];
+
+/// When the flat routes were deprecated (2026-10-17), as an RFC 9745 `@<unix seconds>` date
+pub const LEGACY_DEPRECATED_AT: &str = "@1792195200";
+/// When the flat routes stop being served
+pub const LEGACY_SUNSET: &str = "Sat, 17 Apr 2027 00:00:00 GMT";
+
+/// Version pinned by the request, if any; Err for unsupported or malformed pins
+fn requested_version(headers: &HeaderMap) -> Result<Option<u32>, String> {
+    let Some(value) = headers.get(&API_VERSION_HEADER) else {
+        return Ok(None);
+    };
+    let requested = value.to_str().ok().map(|v| v.trim().trim_start_matches(['v', 'V']));
+    match requested.and_then(|v| v.parse::<u32>().ok()) {
+        Some(version) if SUPPORTED_API_VERSIONS.contains(&version) => Ok(Some(version)),
+        _ => Err(format!(
+            "Unsupported API version '{}'",
+            String::from_utf8_lossy(value.as_bytes())
+        )),
+    }
+}
+
+/// Middleware for the /v1 routes: check the requested version, stamp the served one
+pub async fn negotiate_version(req: Request, next: Next) -> Response {
+    if let Err(error) = requested_version(req.headers()) {
+        let body = ApiErrorBody::new(ErrorCode::UnsupportedVersion, error)
+            .with_details(json!({ "supported_versions": SUPPORTED_API_VERSIONS }));
+        return (StatusCode::NOT_ACCEPTABLE, Json(body)).into_response();
+    }
+    let mut response = next.run(req).await;
+    response
+        .headers_mut()
+        .insert(API_VERSION_HEADER, HeaderValue::from(CURRENT_API_VERSION));
+    response
+}
+
+/// Middleware for the flat routes: v1 behavior plus deprecation headers
+pub async fn deprecate_legacy_routes(req: Request, next: Next) -> Response {
+    if UNVERSIONED_PATHS.contains(&req.uri().path()) {
+        return negotiate_version(req, next).await;
+    }
+    let successor = format!("<{}{}>; rel=\"successor-version\"", CURRENT_API_PREFIX, req.uri().path());
+    let mut response = negotiate_version(req, next).await;
+    let headers = response.headers_mut();
+    headers.insert(DEPRECATION_HEADER, HeaderValue::from_static(LEGACY_DEPRECATED_AT));
+    headers.insert(SUNSET_HEADER, HeaderValue::from_static(LEGACY_SUNSET));
+    if let Ok(link) = HeaderValue::from_str(&successor) {
+        headers.append(header::LINK, link);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pinned(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(API_VERSION_HEADER, value.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_requested_version() {
+        assert_eq!(requested_version(&HeaderMap::new()).unwrap(), None);
+        assert_eq!(requested_version(&pinned("1")).unwrap(), Some(1));
+        assert_eq!(requested_version(&pinned("v1")).unwrap(), Some(1));
+        for unsupported in ["2", "0", "latest", ""] {
+            assert!(requested_version(&pinned(unsupported)).is_err(), "{:?}", unsupported);
+        }
+    }
+}
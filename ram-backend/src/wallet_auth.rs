@@ -0,0 +1,150 @@
+// Requests signed by a wallet's linked address
+// Per-wallet data (profiles, contacts) has no session or password: each
+// request carries a Sui personal-message signature (Ed25519 keys) by an
+// address linked to the wallet, over the scope and action, the handle, a
+// timestamp and a hash of the body, in the X-Wallet-* headers. Integrations
+// can send one of the wallet's API keys instead (see api_keys).
+
+use axum::http::{HeaderMap, StatusCode};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use blake2::digest::consts::U32;
+use blake2::{Blake2b, Digest};
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use sha2::Sha256;
+use tracing::error;
+
+use crate::api_keys::{authenticate_key, presented_key};
+use crate::database::Database;
+use crate::sponsor::{ed25519_address, parse_address};
+use crate::AppState;
+
+/// Linked address signing the request
+pub const WALLET_ADDRESS_HEADER: &str = "x-wallet-address";
+/// Its serialized Sui signature (base64 flag || signature || public key)
+pub const WALLET_SIGNATURE_HEADER: &str = "x-wallet-signature";
+/// Signed time of the request, in ms
+pub const WALLET_TIMESTAMP_HEADER: &str = "x-wallet-timestamp";
+
+/// How far a signed request's timestamp may be from now
+pub const SIGNATURE_MAX_AGE_MS: i64 = 5 * 60 * 1000;
+
+/// Intent prefix of a personal message (scope PersonalMessage, version 0, app Sui)
+const PERSONAL_MESSAGE_INTENT: [u8; 3] = [3, 0, 0];
+
+/// A verified request
+pub struct SignedRequest {
+    /// The signer, 0x-prefixed, or `api_key:<id>` for a request made with a key
+    pub address: String,
+    pub timestamp_ms: i64,
+}
+
+/// The personal message a linked address signs for a request, e.g. scope
+/// `profile` and action `update`
+pub fn request_message(scope: &str, action: &str, handle: &str, timestamp_ms: i64, body: &[u8]) -> String {
+    format!(
+        "RAM {} {}\nhandle: {}\ntimestamp_ms: {}\nbody_sha256: {}",
+        scope,
+        action,
+        handle,
+        timestamp_ms,
+        hex::encode(Sha256::digest(body))
+    )
+}
+
+/// Check a request's signature and that its signer is linked to the wallet:
+/// 401 if it's missing, stale or doesn't verify, 403 if the signer isn't linked
+///
+/// A request with an API key is checked against the key instead.
+pub async fn authenticate(
+    state: &AppState,
+    headers: &HeaderMap,
+    scope: &str,
+    action: &str,
+    handle: &str,
+    body: &[u8],
+) -> Result<SignedRequest, StatusCode> {
+    if let Some(key) = presented_key(headers) {
+        return authenticate_key(state, key, scope, action, handle).await;
+    }
+    let header = |name| headers.get(name).and_then(|v| v.to_str().ok());
+    let (Some(address), Some(signature), Some(timestamp_ms)) = (
+        header(WALLET_ADDRESS_HEADER).and_then(|a| parse_address(a).ok()),
+        header(WALLET_SIGNATURE_HEADER),
+        header(WALLET_TIMESTAMP_HEADER).and_then(|t| t.parse::<i64>().ok()),
+    ) else {
+        return Err(StatusCode::UNAUTHORIZED);
+    };
+    let now_ms = chrono::Utc::now().timestamp_millis();
+    if (now_ms - timestamp_ms).abs() > SIGNATURE_MAX_AGE_MS
+        || !verify_personal_message(&request_message(scope, action, handle, timestamp_ms, body), signature, &address)
+    {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let linked = Database::get_linked_addresses(&state.db, handle).await.map_err(|e| {
+        error!("Failed to fetch linked addresses: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    if !linked.iter().any(|l| parse_address(&l.address).is_ok_and(|a| a == address)) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    Ok(SignedRequest {
+        address: format!("0x{}", hex::encode(address)),
+        timestamp_ms,
+    })
+}
+
+/// What a Sui key signs for a personal message
+pub fn personal_message_digest(message: &[u8]) -> [u8; 32] {
+    let mut hasher = Blake2b::<U32>::new();
+    hasher.update(PERSONAL_MESSAGE_INTENT);
+    hasher.update(bcs::to_bytes(message).expect("byte vectors always serialize"));
+    hasher.finalize().into()
+}
+
+/// Check a serialized Ed25519 signature (flag || signature || public key) by `address`
+fn verify_personal_message(message: &str, signature: &str, address: &[u8; 32]) -> bool {
+    let Ok(serialized) = STANDARD.decode(signature) else {
+        return false;
+    };
+    let [0x00, rest @ ..] = serialized.as_slice() else {
+        return false;
+    };
+    let Some((signature, public_key)) = rest.split_first_chunk::<64>() else {
+        return false;
+    };
+    let Ok(public_key) = <[u8; 32]>::try_from(public_key) else {
+        return false;
+    };
+    let Ok(key) = VerifyingKey::from_bytes(&public_key) else {
+        return false;
+    };
+    ed25519_address(&public_key) == *address
+        && key
+            .verify(&personal_message_digest(message.as_bytes()), &Signature::from_bytes(signature))
+            .is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+
+    #[test]
+    fn test_personal_message_signatures() {
+        let key = SigningKey::from_bytes(&[5u8; 32]);
+        let address = ed25519_address(key.verifying_key().as_bytes());
+        let serialize = |message: &str| {
+            let mut serialized = vec![0x00];
+            serialized.extend_from_slice(&key.sign(&personal_message_digest(message.as_bytes())).to_bytes());
+            serialized.extend_from_slice(key.verifying_key().as_bytes());
+            STANDARD.encode(serialized)
+        };
+
+        assert!(verify_personal_message("hello", &serialize("hello"), &address));
+        assert!(!verify_personal_message("hello", &serialize("other"), &address));
+        assert!(!verify_personal_message("hello", &serialize("hello"), &[1; 32]));
+        assert!(!verify_personal_message("hello", "AAAA", &address));
+    }
+}
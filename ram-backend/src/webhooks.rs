@@ -0,0 +1,492 @@
+// Merchant payment webhooks
+// A wallet's owner (signing as usual, scope `webhooks`) registers a URL and
+// gets back a secret. When the indexer stores a Transferred event crediting
+// the wallet, it queues a `payment.received` notification, which the
+// delivery job POSTs with an HMAC-SHA256 signature under that secret and
+// retries with backoff until the endpoint answers 2xx. Each notification
+// has an ID derived from its event, the same on every attempt and if the
+// event is indexed again, so merchants can drop replays. The SDK's
+// `webhook` module verifies them.
+//
+// The URL is the wallet owner's to choose, so it is never trusted to point
+// outside: only https URLs are accepted, and delivery resolves the host
+// itself and refuses loopback, private, link-local (cloud metadata) and other
+// non-public addresses, connecting only to the addresses it checked so a
+// hostname can't rebind to an internal one in between. What the owner is
+// shown of a failure is only its kind, never the underlying error.
+
+use anyhow::Result;
+use axum::{
+    body::Bytes,
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use hmac::{Hmac, Mac};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+use tracing::{error, info, warn};
+
+use crate::config::WebhookConfig;
+use crate::database::{Database, DbPool};
+use crate::indexer::retry_delay_ms;
+use crate::models::{
+    DeleteWebhookRequest, DueWebhookDelivery, RamEvent, SaveWebhookRequest, SavedWebhook, WebhookQuery, WebhookStatus,
+};
+use crate::wallet_auth::authenticate;
+use crate::AppState;
+
+/// Event ID of a notification, the same on every attempt
+pub const WEBHOOK_ID_HEADER: &str = "x-ram-webhook-id";
+/// When this attempt was signed (ms), so receivers can reject stale ones
+pub const WEBHOOK_TIMESTAMP_HEADER: &str = "x-ram-webhook-timestamp";
+/// Hex HMAC-SHA256 of `<timestamp>.<body>` under the webhook's secret
+pub const WEBHOOK_SIGNATURE_HEADER: &str = "x-ram-webhook-signature";
+/// Type of the notification sent for a transfer to the wallet
+pub const PAYMENT_RECEIVED: &str = "payment.received";
+/// Longest webhook URL accepted
+pub const MAX_URL_LEN: usize = 2048;
+/// Latest deliveries shown with a webhook
+const DELIVERIES_SHOWN: i64 = 20;
+/// Deliveries attempted per poll
+const DELIVERY_BATCH_SIZE: i64 = 20;
+
+/// Signature of a notification body sent at `timestamp_ms`
+/// Must match the SDK's `webhook::signature`
+pub fn webhook_signature(secret: &str, timestamp_ms: i64, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC can take key of any size");
+    mac.update(format!("{}.", timestamp_ms).as_bytes());
+    mac.update(body);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// A fresh webhook secret
+fn generate_secret() -> String {
+    format!("whsec_{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple())
+}
+
+/// Whether `ip` is a public unicast address: not loopback, private (RFC 1918,
+/// unique local), link-local, shared (CGNAT), multicast, documentation or
+/// otherwise reserved
+pub fn is_public_address(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, c, _] = ip.octets();
+            !(ip.is_unspecified()
+                || ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || ip.is_documentation()
+                || a == 0
+                || a >= 240
+                || (a == 100 && (64..128).contains(&b))
+                || (a == 192 && b == 0 && c == 0)
+                || (a == 198 && (18..20).contains(&b)))
+        }
+        IpAddr::V6(ip) => {
+            let segments = ip.segments();
+            // IPv4-mapped and NAT64 addresses reach the IPv4 address they embed
+            let embedded = match segments {
+                [0x64, 0xff9b, 0, 0, 0, 0, high, low] => Some(Ipv4Addr::from((u32::from(high) << 16) | u32::from(low))),
+                _ => ip.to_ipv4_mapped(),
+            };
+            if let Some(v4) = embedded {
+                return is_public_address(IpAddr::V4(v4));
+            }
+            !(ip.is_unspecified()
+                || ip.is_loopback()
+                || ip.is_multicast()
+                || ip.is_unique_local()
+                || ip.is_unicast_link_local()
+                || (segments[0] == 0x2001 && segments[1] == 0x0db8))
+        }
+    }
+}
+
+/// Whether notifications may be sent to `url`: https, at a hostname or a
+/// public address (hostnames are checked when resolved, see `PublicResolver`);
+/// with `allow_private`, any absolute http(s) URL
+fn is_allowed_target(url: &reqwest::Url, allow_private: bool) -> bool {
+    let Some(host) = url.host_str() else {
+        return false;
+    };
+    if allow_private {
+        return matches!(url.scheme(), "http" | "https");
+    }
+    let literal = host.trim_start_matches('[').trim_end_matches(']').parse::<IpAddr>();
+    url.scheme() == "https"
+        && match literal {
+            Ok(ip) => is_public_address(ip),
+            Err(_) => host != "localhost" && !host.ends_with(".localhost"),
+        }
+}
+
+/// Whether a webhook URL can be saved: absolute, and a target notifications
+/// may be sent to
+fn is_valid_url(url: &str, allow_private: bool) -> bool {
+    url.len() <= MAX_URL_LEN && reqwest::Url::parse(url).is_ok_and(|url| is_allowed_target(&url, allow_private))
+}
+
+/// Resolves webhook hosts for delivery, refusing any with a non-public
+/// address; connections go to exactly the addresses checked here, so a host
+/// can't pass and then rebind to an internal address
+struct PublicResolver;
+
+impl reqwest::dns::Resolve for PublicResolver {
+    fn resolve(&self, name: reqwest::dns::Name) -> reqwest::dns::Resolving {
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((name.as_str(), 0)).await?.collect();
+            if addrs.is_empty() || !addrs.iter().all(|addr| is_public_address(addr.ip())) {
+                return Err(format!("{} resolves to a non-public address", name.as_str()).into());
+            }
+            let addrs: reqwest::dns::Addrs = Box::new(addrs.into_iter());
+            Ok(addrs)
+        })
+    }
+}
+
+/// Client notifications are sent with
+pub fn delivery_client(config: &WebhookConfig) -> reqwest::Result<reqwest::Client> {
+    // Redirects aren't followed: the registered URL is the only one posted to.
+    // No proxy either, which would resolve the host itself
+    let builder = reqwest::Client::builder()
+        .timeout(config.timeout)
+        .redirect(reqwest::redirect::Policy::none())
+        .no_proxy();
+    if config.allow_private_targets {
+        builder.build()
+    } else {
+        builder.dns_resolver(Arc::new(PublicResolver)).build()
+    }
+}
+
+/// The `payment.received` notification for an event that credits a wallet,
+/// with its ID, or None for any other event
+pub fn payment_notification(event: &RamEvent) -> Option<(String, Value)> {
+    if event.event_type != "Transferred" {
+        return None;
+    }
+    let to_handle = event.to_handle.as_deref().filter(|h| !h.is_empty())?;
+
+    // The event's own key in ram_events
+    let key = format!("{}:{}:{}", event.tx_digest, event.event_type, event.handle.as_deref().unwrap_or(""));
+    let id = format!("evt_{}", &hex::encode(Sha256::digest(key.as_bytes()))[..32]);
+    let timestamp_ms = event.timestamp.timestamp_millis();
+    let payload = json!({
+        "id": id,
+        "type": PAYMENT_RECEIVED,
+        "created_at_ms": timestamp_ms,
+        "data": {
+            "handle": to_handle,
+            "payer_handle": event.from_handle,
+            "amount": event.amount,
+            "coin_type": event.coin_type,
+            "memo": event.memo,
+            "tx_digest": event.tx_digest,
+            "timestamp_ms": timestamp_ms,
+        },
+    });
+    Some((id, payload))
+}
+
+/// Queue the notification for a stored event, if it credits a wallet with a
+/// webhook; true if one was queued
+pub async fn queue_payment(pool: &DbPool, event: &RamEvent, now_ms: i64) -> Result<bool> {
+    let Some((id, payload)) = payment_notification(event) else {
+        return Ok(false);
+    };
+    let handle = event.to_handle.as_deref().unwrap_or_default();
+    let queued = Database::queue_webhook_delivery(pool, &id, handle, &payload, now_ms).await?;
+    if queued {
+        info!("Queued webhook notification {} for {}", id, handle);
+    }
+    Ok(queued)
+}
+
+/// Why a delivery attempt failed
+enum DeliveryFailure {
+    /// The URL isn't one notifications may be sent to
+    Refused,
+    /// No answer: the host didn't resolve to public addresses, the connection
+    /// failed or the endpoint timed out
+    Unreachable(reqwest::Error),
+    /// The endpoint answered with a status other than 2xx
+    Answered(reqwest::StatusCode),
+}
+
+impl DeliveryFailure {
+    /// What the wallet's owner is shown: the kind of failure, or the class of
+    /// the status answered, never the error itself, which would describe the
+    /// backend's network to whoever registered the URL
+    fn summary(&self) -> String {
+        match self {
+            DeliveryFailure::Refused => "refused".to_string(),
+            DeliveryFailure::Unreachable(_) => "unreachable".to_string(),
+            DeliveryFailure::Answered(status) => format!("answered {}xx", status.as_u16() / 100),
+        }
+    }
+}
+
+impl fmt::Display for DeliveryFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeliveryFailure::Refused => write!(f, "URL is not a public https endpoint"),
+            DeliveryFailure::Unreachable(e) => write!(f, "{}", e),
+            DeliveryFailure::Answered(status) => write!(f, "endpoint answered {}", status),
+        }
+    }
+}
+
+/// POST one notification, signed now
+async fn deliver(
+    client: &reqwest::Client,
+    config: &WebhookConfig,
+    delivery: &DueWebhookDelivery,
+) -> std::result::Result<(), DeliveryFailure> {
+    let url = reqwest::Url::parse(&delivery.url).map_err(|_| DeliveryFailure::Refused)?;
+    if !is_allowed_target(&url, config.allow_private_targets) {
+        return Err(DeliveryFailure::Refused);
+    }
+    let body = delivery.payload.to_string().into_bytes();
+    let timestamp_ms = chrono::Utc::now().timestamp_millis();
+    let response = client
+        .post(url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header(WEBHOOK_ID_HEADER, &delivery.id)
+        .header(WEBHOOK_TIMESTAMP_HEADER, timestamp_ms.to_string())
+        .header(WEBHOOK_SIGNATURE_HEADER, webhook_signature(&delivery.secret, timestamp_ms, &body))
+        .body(body)
+        .send()
+        .await
+        .map_err(DeliveryFailure::Unreachable)?;
+
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        Err(DeliveryFailure::Answered(response.status()))
+    }
+}
+
+/// Attempt every delivery due at `now_ms` with a client from
+/// `delivery_client`, returning how many succeeded
+pub async fn deliver_due(pool: &DbPool, client: &reqwest::Client, config: &WebhookConfig, now_ms: i64) -> Result<usize> {
+    let due = Database::get_due_webhook_deliveries(pool, now_ms, DELIVERY_BATCH_SIZE).await?;
+
+    let mut delivered = 0;
+    for delivery in due {
+        match deliver(client, config, &delivery).await {
+            Ok(()) => {
+                Database::mark_webhook_delivered(pool, &delivery.id, chrono::Utc::now().timestamp_millis()).await?;
+                delivered += 1;
+            }
+            Err(e) => {
+                let attempts = delivery.attempts + 1;
+                let next_attempt_ms = (attempts < config.max_attempts).then(|| now_ms + retry_delay_ms(attempts));
+                if next_attempt_ms.is_none() {
+                    error!(
+                        "Giving up on webhook notification {} for {} after {} attempts: {}",
+                        delivery.id, delivery.handle, attempts, e
+                    );
+                } else {
+                    warn!("Webhook notification {} for {} failed: {}", delivery.id, delivery.handle, e);
+                }
+                Database::reschedule_webhook_delivery(pool, &delivery.id, &e.summary(), next_attempt_ms).await?;
+            }
+        }
+    }
+
+    Ok(delivered)
+}
+
+/// Send due notifications every `poll_interval`, for as long as it runs
+/// (supervised, see supervisor.rs)
+pub async fn run_webhook_deliveries(pool: DbPool, config: WebhookConfig) -> Result<()> {
+    let client = delivery_client(&config)?;
+    loop {
+        let now_ms = chrono::Utc::now().timestamp_millis();
+        if let Err(e) = deliver_due(&pool, &client, &config, now_ms).await {
+            error!("Webhook delivery failed: {}", e);
+        }
+        tokio::time::sleep(config.poll_interval).await;
+    }
+}
+
+/// A wallet's webhook and its latest deliveries
+///
+/// GET /api/webhook?handle= (signed, scope `webhooks`, action `read`)
+pub async fn get_webhook(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<WebhookQuery>,
+    headers: HeaderMap,
+) -> Result<Json<WebhookStatus>, StatusCode> {
+    authenticate(&state, &headers, "webhooks", "read", &query.handle, &[]).await?;
+
+    let webhook = Database::get_webhook(&state.db, &query.handle)
+        .await
+        .map_err(|e| {
+            error!("Failed to fetch webhook: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let deliveries = Database::list_webhook_deliveries(&state.db, &query.handle, DELIVERIES_SHOWN)
+        .await
+        .map_err(|e| {
+            error!("Failed to list webhook deliveries: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    Ok(Json(WebhookStatus { webhook, deliveries }))
+}
+
+/// Register a wallet's webhook, or replace it; the new secret is only ever
+/// returned here
+///
+/// PUT /api/webhook (signed, action `save`)
+pub async fn save_webhook(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<SavedWebhook>, StatusCode> {
+    let request: SaveWebhookRequest = serde_json::from_slice(&body).map_err(|_| StatusCode::BAD_REQUEST)?;
+    if !is_valid_url(&request.url, state.webhooks.allow_private_targets) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    authenticate(&state, &headers, "webhooks", "save", &request.handle, &body).await?;
+
+    let secret = generate_secret();
+    let now_ms = chrono::Utc::now().timestamp_millis();
+    let webhook = Database::upsert_webhook(&state.db, &request.handle, &request.url, &secret, now_ms)
+        .await
+        .map_err(|e| {
+            error!("Failed to save webhook: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    info!("{} saved a webhook", request.handle);
+
+    Ok(Json(SavedWebhook { secret, webhook }))
+}
+
+/// Remove a wallet's webhook; notifications not yet delivered are dropped
+///
+/// DELETE /api/webhook (signed, action `delete`)
+pub async fn delete_webhook(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<StatusCode, StatusCode> {
+    let request: DeleteWebhookRequest = serde_json::from_slice(&body).map_err(|_| StatusCode::BAD_REQUEST)?;
+    authenticate(&state, &headers, "webhooks", "delete", &request.handle, &body).await?;
+
+    let deleted = Database::delete_webhook(&state.db, &request.handle).await.map_err(|e| {
+        error!("Failed to delete webhook: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    if !deleted {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    info!("{} removed its webhook", request.handle);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn transfer(tx_digest: &str) -> RamEvent {
+        RamEvent {
+            handle: Some("alice".to_string()),
+            event_type: "Transferred".to_string(),
+            amount: Some(5_000_000_000),
+            coin_type: Some("0x2::sui::SUI".to_string()),
+            result_code: None,
+            from_handle: Some("alice".to_string()),
+            to_handle: Some("shop".to_string()),
+            memo: Some("order-42".to_string()),
+            payload_version: Some(2),
+            owner: None,
+            wallet_id: None,
+            tx_digest: tx_digest.to_string(),
+            timestamp: Utc.timestamp_millis_opt(1_707_523_200_000).unwrap(),
+            raw_json: None,
+            gas_used: None,
+            sender: None,
+            sender_label: None,
+            from_display_name: None,
+            to_display_name: None,
+            refund_of: None,
+            refunded_in: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_signature_matches_sdk_vector() {
+        // Same vector as the SDK's webhook tests
+        assert_eq!(
+            webhook_signature("whsec_test", 1_700_000_000_000, br#"{"id":"evt_1"}"#),
+            "f510dfdb0b01915750c36f3e15401a7895306243fa607a5cba4496ce6c3a0ea3"
+        );
+    }
+
+    #[test]
+    fn test_only_transfers_notify_and_ids_are_stable() {
+        let (id, payload) = payment_notification(&transfer("Dig1")).unwrap();
+        assert_eq!(payment_notification(&transfer("Dig1")).unwrap().0, id);
+        assert_ne!(payment_notification(&transfer("Dig2")).unwrap().0, id);
+        assert_eq!(payload["id"], id.as_str());
+        assert_eq!(payload["data"]["handle"], "shop");
+        assert_eq!(payload["data"]["payer_handle"], "alice");
+        assert_eq!(payload["data"]["memo"], "order-42");
+
+        let mut withdrawal = transfer("Dig1");
+        withdrawal.event_type = "Withdrawn".to_string();
+        assert!(payment_notification(&withdrawal).is_none());
+
+    }
+
+    #[test]
+    fn test_only_public_https_urls_are_targets() {
+        assert!(is_valid_url("https://shop.example/hooks/ram", false));
+        assert!(is_valid_url("https://93.184.215.14/hooks", false));
+        for url in [
+            "http://shop.example/hooks/ram",
+            "ftp://shop.example/",
+            "/hooks/ram",
+            "https://localhost/hooks",
+            "https://127.0.0.1/hooks",
+            "https://10.0.0.5/hooks",
+            "https://169.254.169.254/latest/meta-data",
+            "https://[::1]/hooks",
+            "https://[::ffff:192.168.1.1]/hooks",
+            "https://[fd00::1]/hooks",
+        ] {
+            assert!(!is_valid_url(url, false), "{} should be refused", url);
+        }
+        // Local development may point at its own services
+        assert!(is_valid_url("http://127.0.0.1:8080/hooks", true));
+        assert!(!is_valid_url("ftp://127.0.0.1/", true));
+
+        for ip in ["100.64.0.1", "0.0.0.0", "255.255.255.255", "fe80::1", "64:ff9b::a9fe:a9fe", "2001:db8::1"] {
+            assert!(!is_public_address(ip.parse().unwrap()), "{}", ip);
+        }
+        for ip in ["1.1.1.1", "2606:4700:4700::1111", "64:ff9b::101:101"] {
+            assert!(is_public_address(ip.parse().unwrap()), "{}", ip);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_delivery_refuses_hosts_resolving_to_private_addresses() {
+        use reqwest::dns::Resolve;
+
+        let resolved = PublicResolver.resolve("localhost".parse().unwrap()).await;
+        assert!(resolved.is_err());
+
+        let failure = DeliveryFailure::Answered(reqwest::StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(failure.summary(), "answered 5xx");
+        assert_eq!(DeliveryFailure::Refused.summary(), "refused");
+    }
+}
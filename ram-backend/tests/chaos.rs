@@ -0,0 +1,33 @@
+// Fault injection (`--features chaos`): injected faults must fail the way
+// real ones do, so client retries and failover see the usual errors.
+// Runs in its own process since faults are installed process-wide.
+
+#![cfg(feature = "chaos")]
+
+mod common;
+
+use common::*;
+use ram_backend::chaos::{self, Faults};
+use ram_backend::database::Database;
+use serde_json::json;
+
+#[tokio::test]
+async fn test_injected_faults_look_like_real_outages() {
+    chaos::install(Faults { nautilus_drop_percent: 100, db_timeout_percent: 100, ..Faults::default() });
+
+    // Dropped Nautilus connection: the proxy answers 502 without reaching the enclave
+    let nautilus = start_mock_nautilus().await;
+    let backend = spawn_backend(lazy_pool(), nautilus.uri()).await;
+    let resp = reqwest::Client::new()
+        .post(format!("{}/v1/create_wallet", backend))
+        .json(&json!({ "payload": { "handle": "alice" } }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 502);
+    assert!(nautilus.received_requests().await.unwrap().is_empty());
+
+    // DB timeout: queries fail with the pool's own timeout error
+    let err = Database::get_wallet_id(&lazy_pool(), "alice").await.unwrap_err();
+    assert!(matches!(err.downcast_ref::<sqlx::Error>(), Some(sqlx::Error::PoolTimedOut)));
+}
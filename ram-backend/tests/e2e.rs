@@ -0,0 +1,3260 @@
+// End-to-end flow: create-wallet → link → bio-auth → transfer through the
+// backend proxy against a mock enclave, then indexer ingestion of the
+// resulting on-chain events from a mock Sui RPC.
+//
+// Indexer tests need Postgres: set TEST_DATABASE_URL to run them.
+
+mod common;
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use common::*;
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier};
+use ram_backend::config::{ChainConfig, EnclaveRegistrationConfig, ProxyConfig, SponsorConfig, TravelRuleConfig, WebhookConfig};
+use ram_backend::database::Database;
+use ram_backend::inactivity::warn_inactive_wallets;
+use ram_backend::indexer::{retry_delay_ms, EventId, Indexer, Throttle, MAX_RETRY_ATTEMPTS};
+use ram_backend::models::{Profile, RamEvent};
+use ram_backend::refunds::link_refund;
+use ram_backend::retention::archive_events;
+use ram_backend::travel_rule::link_envelope;
+use ram_backend::wallet_auth::SIGNATURE_MAX_AGE_MS;
+use ram_backend::routing::{Affinity, EnclaveRouter};
+use ram_backend::rpc::SuiRpcClient;
+use ram_backend::signing::{request_signature, SIGNATURE_HEADER, TIMESTAMP_HEADER};
+use ram_backend::sponsor::{ed25519_address, transaction_digest, Sponsor};
+use ram_backend::webhooks::{deliver_due, delivery_client};
+use ram_backend::AppState;
+use ram_sdk::{webhook, ApiErrorBody, ErrorCode};
+use std::sync::Arc;
+use serde_json::{json, Value};
+
+const SUI_TYPE: &str = "0000000000000000000000000000000000000000000000000000000000000002::sui::SUI";
+const ALICE_ADDRESS: &str = "0x00000000000000000000000000000000000000000000000000000000000a11ce";
+
+async fn post(client: &reqwest::Client, url: String, payload: Value) -> reqwest::Response {
+    client
+        .post(url)
+        .json(&json!({ "payload": payload }))
+        .send()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_wallet_flow_through_proxy() {
+    let nautilus = start_mock_nautilus().await;
+    let backend = spawn_backend(lazy_pool(), nautilus.uri()).await;
+    let client = reqwest::Client::new();
+    let pk = test_signing_key().verifying_key();
+
+    // 1. Create wallet
+    let resp = post(&client, format!("{}/v1/create_wallet", backend), json!({ "handle": "alice" })).await;
+    assert_eq!(resp.status(), 200);
+    let created: SignedResponse<CreateWalletPayload> = resp.json().await.unwrap();
+    assert_eq!(created.intent, CREATE_WALLET_INTENT);
+    assert_eq!(created.payload.handle, b"alice");
+    assert!(verify_signed_response(&pk, &created));
+
+    // 2. Link address
+    let resp = post(
+        &client,
+        format!("{}/v1/link_address", backend),
+        json!({
+            "handle": "alice",
+            "wallet_address": ALICE_ADDRESS,
+            "wallet_signature": "",
+            "message": "",
+            "label": "phone",
+        }),
+    )
+    .await;
+    let linked: SignedResponse<LinkAddressPayload> = resp.json().await.unwrap();
+    assert_eq!(linked.intent, LINK_ADDRESS_INTENT);
+    assert_eq!(linked.payload.address[31], 0xce);
+    assert_eq!(linked.payload.label, b"phone");
+    assert!(verify_signed_response(&pk, &linked));
+
+    // 3. Bio-auth (blind response; result only visible by verifying the payload)
+    let resp = post(
+        &client,
+        format!("{}/v1/bio_auth", backend),
+        json!({ "handle": "alice", "audio_base64": "UklGRg==", "expected_amount": 5_000_000_000u64 }),
+    )
+    .await;
+    let auth: SignedResponse<BioAuthPayload> = resp.json().await.unwrap();
+    assert_eq!(auth.intent, BIOAUTH_INTENT);
+    assert_eq!(auth.payload.result, 0);
+    assert!(verify_signed_response(&pk, &auth));
+
+    // 4. Transfer
+    let resp = post(
+        &client,
+        format!("{}/v1/transfer", backend),
+        json!({
+            "from_handle": "alice",
+            "to_handle": "bob",
+            "amount": 5_000_000_000u64,
+            "coin_type": "0x2::sui::SUI",
+        }),
+    )
+    .await;
+    let transfer: SignedResponse<TransferPayload> = resp.json().await.unwrap();
+    assert_eq!(transfer.intent, TRANSFER_INTENT);
+    assert_eq!(transfer.payload.to_handle, b"bob");
+    assert!(verify_signed_response(&pk, &transfer));
+}
+
+#[tokio::test]
+async fn test_duress_result_passes_through_proxy() {
+    let nautilus = start_mock_nautilus().await;
+    let backend = spawn_backend(lazy_pool(), nautilus.uri()).await;
+    let client = reqwest::Client::new();
+
+    let resp = post(
+        &client,
+        format!("{}/bio_auth", backend),
+        json!({ "handle": "alice", "audio_base64": "DURESS", "expected_amount": 1u64 }),
+    )
+    .await;
+    let mut auth: SignedResponse<BioAuthPayload> = resp.json().await.unwrap();
+    assert_eq!(auth.payload.result, 2);
+
+    // Tampering with the result must invalidate the signature
+    auth.payload.result = 0;
+    assert!(!verify_signed_response(&test_signing_key().verifying_key(), &auth));
+}
+
+#[tokio::test]
+async fn test_legacy_routes_are_deprecated_and_versions_negotiated() {
+    let nautilus = start_mock_nautilus().await;
+    let backend = spawn_backend(lazy_pool(), nautilus.uri()).await;
+    let client = reqwest::Client::new();
+    let payload = || json!({ "handle": "alice", "audio_base64": "UklGRg==", "expected_amount": 1u64 });
+
+    let resp = post(&client, format!("{}/v1/bio_auth", backend), payload()).await;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.headers()["api-version"], "1");
+    assert!(resp.headers().get("deprecation").is_none());
+
+    // Flat routes still work, pointing at their successor
+    let resp = post(&client, format!("{}/bio_auth", backend), payload()).await;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.headers()["api-version"], "1");
+    assert_eq!(resp.headers()["deprecation"], "@1792195200");
+    assert_eq!(resp.headers()["sunset"], "Sat, 17 Apr 2027 00:00:00 GMT");
+    assert_eq!(resp.headers()["link"], "</v1/bio_auth>; rel=\"successor-version\"");
+
+    // A pinned version is honored or refused, never silently ignored
+    for (pin, status) in [("1", 200), ("v1", 200), ("2", 406)] {
+        let resp = client
+            .post(format!("{}/v1/bio_auth", backend))
+            .header("api-version", pin)
+            .json(&json!({ "payload": payload() }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), status, "{}", pin);
+    }
+    let resp = client.get(format!("{}/api/portfolio/alice", backend)).header("api-version", "2").send().await.unwrap();
+    assert_eq!(resp.status(), 406);
+    let body = resp.json::<Value>().await.unwrap();
+    assert_eq!(body["code"], "unsupported_version");
+    assert_eq!(body["details"]["supported_versions"], json!([1]));
+}
+
+#[tokio::test]
+async fn test_proxy_reports_unreachable_enclave() {
+    let backend = spawn_backend(lazy_pool(), "http://127.0.0.1:1".to_string()).await;
+    let resp = post(
+        &reqwest::Client::new(),
+        format!("{}/create_wallet", backend),
+        json!({ "handle": "alice" }),
+    )
+    .await;
+    assert_eq!(resp.status(), 502);
+    let request_id = resp.headers()["x-request-id"].to_str().unwrap().to_string();
+    let body: ApiErrorBody = resp.json().await.unwrap();
+    assert_eq!(body.code, ErrorCode::EnclaveUnavailable);
+    assert_eq!(body.request_id, Some(request_id));
+}
+
+#[tokio::test]
+async fn test_errors_share_one_envelope() {
+    // An enclave still answering in the older `{"error", "fields"}` shape
+    let nautilus = wiremock::MockServer::start().await;
+    wiremock::Mock::given(wiremock::matchers::path("/withdraw"))
+        .respond_with(wiremock::ResponseTemplate::new(422).set_body_json(json!({
+            "error": "Request validation failed",
+            "fields": [{ "field": "payload.amount", "message": "must be greater than 0" }],
+        })))
+        .mount(&nautilus)
+        .await;
+    let backend = spawn_backend(lazy_pool(), nautilus.uri()).await;
+    let client = reqwest::Client::new();
+
+    let resp = client
+        .post(format!("{}/v1/withdraw", backend))
+        .header("x-request-id", "req-7")
+        .json(&json!({ "payload": { "handle": "alice", "amount": 0 } }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 422);
+    let body: ApiErrorBody = resp.json().await.unwrap();
+    assert_eq!(body.code, ErrorCode::ValidationFailed);
+    assert_eq!(body.fields().unwrap()[0].field, "payload.amount");
+    assert_eq!(body.request_id.as_deref(), Some("req-7"));
+    // The enclave saw the same ID
+    let received = nautilus.received_requests().await.unwrap();
+    assert_eq!(received[0].headers["x-request-id"], "req-7");
+
+    // The backend's own bare statuses get the envelope too
+    for (url, status, code) in [
+        (format!("{}/v1/no_such_route", backend), 404, ErrorCode::NotFound),
+        (format!("{}/v1/admin/db_metrics", backend), 403, ErrorCode::Forbidden),
+    ] {
+        let resp = client.get(url).send().await.unwrap();
+        assert_eq!(resp.status(), status);
+        let body: ApiErrorBody = resp.json().await.unwrap();
+        assert_eq!(body.code, code);
+        assert!(body.request_id.is_some());
+    }
+}
+
+#[tokio::test]
+async fn test_streamed_bio_auth_passes_through_proxy() {
+    let nautilus = wiremock::MockServer::start().await;
+    let events = "event: stage\ndata: {\"stage\":\"decoded\"}\n\n\
+                  event: stage\ndata: {\"stage\":\"transcribed\"}\n\n\
+                  event: signed\ndata: {\"signature\":\"abc\"}\n\n";
+    wiremock::Mock::given(wiremock::matchers::path("/bio_auth/stream"))
+        .respond_with(
+            wiremock::ResponseTemplate::new(200)
+                .set_body_raw(events, "text/event-stream")
+                .insert_header("cache-control", "no-cache"),
+        )
+        .mount(&nautilus)
+        .await;
+    let backend = spawn_backend(lazy_pool(), nautilus.uri()).await;
+
+    let resp = post(
+        &reqwest::Client::new(),
+        format!("{}/v1/bio_auth/stream", backend),
+        json!({ "handle": "alice", "audio_base64": "UklGRg==", "expected_amount": 1u64 }),
+    )
+    .await;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.headers()["content-type"], "text/event-stream");
+    assert_eq!(resp.headers()["cache-control"], "no-cache");
+    assert_eq!(resp.text().await.unwrap(), events);
+}
+
+#[tokio::test]
+async fn test_proxy_forwards_only_allowlisted_headers() {
+    let nautilus = wiremock::MockServer::start().await;
+    wiremock::Mock::given(wiremock::matchers::path("/create_wallet"))
+        .and(wiremock::matchers::header("x-request-id", "req-42"))
+        .and(wiremock::matchers::header("idempotency-key", "create-alice"))
+        .and(wiremock::matchers::header("accept-language", "vi-VN"))
+        .respond_with(wiremock::ResponseTemplate::new(200).set_body_json(json!({ "signed": true })))
+        .expect(1)
+        .mount(&nautilus)
+        .await;
+    let backend = spawn_backend(lazy_pool(), nautilus.uri()).await;
+
+    let resp = reqwest::Client::new()
+        .post(format!("{}/v1/create_wallet", backend))
+        .header("x-request-id", "req-42")
+        .header("idempotency-key", "create-alice")
+        .header("accept-language", "vi-VN")
+        .header("cookie", "session=secret")
+        .header(SIGNATURE_HEADER, "forged")
+        .json(&json!({ "payload": { "handle": "alice" } }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    let received = &nautilus.received_requests().await.unwrap()[0];
+    assert!(!received.headers.contains_key("cookie"));
+    assert!(!received.headers.contains_key(SIGNATURE_HEADER));
+    assert_eq!(received.headers["content-type"], "application/json");
+}
+
+/// Backend in front of `nautilus_url` with custom proxy timeouts and retries
+async fn spawn_backend_with_proxy(nautilus_url: String, proxy: ProxyConfig) -> String {
+    spawn_backend_with_state(AppState {
+        enclaves: Arc::new(EnclaveRouter::single(nautilus_url)),
+        proxy,
+        ..base_state(lazy_pool())
+    })
+    .await
+}
+
+#[tokio::test]
+async fn test_route_classes_have_their_own_timeouts() {
+    use std::time::Duration;
+
+    let nautilus = wiremock::MockServer::start().await;
+    let slow = || wiremock::ResponseTemplate::new(200).set_body_json(json!({})).set_delay(Duration::from_millis(500));
+    wiremock::Mock::given(wiremock::matchers::path("/bio_auth/queue"))
+        .respond_with(slow())
+        .mount(&nautilus)
+        .await;
+    wiremock::Mock::given(wiremock::matchers::path("/bio_auth"))
+        .respond_with(slow())
+        .mount(&nautilus)
+        .await;
+    let backend = spawn_backend_with_proxy(
+        nautilus.uri(),
+        ProxyConfig {
+            metadata_timeout: Duration::from_millis(200),
+            bio_auth_timeout: Duration::from_secs(5),
+            get_retries: 0,
+            ..ProxyConfig::default()
+        },
+    )
+    .await;
+    let client = reqwest::Client::new();
+
+    // Metadata routes give up quickly...
+    let resp = client.get(format!("{}/v1/bio_auth/queue", backend)).send().await.unwrap();
+    assert_eq!(resp.status(), 504);
+
+    // ...while bio-auth waits out a slow analysis
+    let resp = post(&client, format!("{}/v1/bio_auth", backend), json!({ "handle": "alice" })).await;
+    assert_eq!(resp.status(), 200);
+}
+
+#[tokio::test]
+async fn test_only_idempotent_gets_are_retried() {
+    use std::time::Duration;
+
+    let nautilus = wiremock::MockServer::start().await;
+    // One 503 on each route, then success
+    for route in ["/bio_auth/queue", "/create_wallet"] {
+        wiremock::Mock::given(wiremock::matchers::path(route))
+            .respond_with(wiremock::ResponseTemplate::new(503))
+            .up_to_n_times(1)
+            .with_priority(1)
+            .mount(&nautilus)
+            .await;
+        wiremock::Mock::given(wiremock::matchers::path(route))
+            .respond_with(wiremock::ResponseTemplate::new(200).set_body_json(json!({ "ok": true })))
+            .mount(&nautilus)
+            .await;
+    }
+    let backend = spawn_backend_with_proxy(
+        nautilus.uri(),
+        ProxyConfig { retry_backoff: Duration::from_millis(10), ..ProxyConfig::default() },
+    )
+    .await;
+    let client = reqwest::Client::new();
+
+    let resp = client.get(format!("{}/v1/bio_auth/queue", backend)).send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+
+    // A retried POST could get a second payload signed, so the 503 stands
+    let resp = post(&client, format!("{}/v1/create_wallet", backend), json!({ "handle": "alice" })).await;
+    assert_eq!(resp.status(), 503);
+
+    let requests = nautilus.received_requests().await.unwrap();
+    let attempts = |route: &str| requests.iter().filter(|r| r.url.path() == route).count();
+    assert_eq!(attempts("/bio_auth/queue"), 2);
+    assert_eq!(attempts("/create_wallet"), 1);
+}
+
+#[tokio::test]
+async fn test_chunked_response_streams_through_proxy_with_trailers() {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let (release, released) = tokio::sync::oneshot::channel();
+    let upstream = start_chunked_upstream(released).await;
+    let backend = spawn_backend(lazy_pool(), upstream).await;
+
+    // A raw client, since reqwest doesn't expose trailers; they're only sent
+    // to clients that accept them
+    let mut socket = tokio::net::TcpStream::connect(backend.trim_start_matches("http://")).await.unwrap();
+    socket
+        .write_all(b"GET /v1/bio_auth/export-1 HTTP/1.1\r\nHost: backend\r\nTE: trailers\r\nConnection: close\r\n\r\n")
+        .await
+        .unwrap();
+
+    // The first chunk arrives while the upstream is still holding the rest
+    let mut received = Vec::new();
+    tokio::time::timeout(std::time::Duration::from_secs(5), async {
+        while !String::from_utf8_lossy(&received).contains("row 1") {
+            let mut buf = [0u8; 1024];
+            let n = socket.read(&mut buf).await.unwrap();
+            assert!(n > 0, "connection closed before the first chunk");
+            received.extend_from_slice(&buf[..n]);
+        }
+    })
+    .await
+    .expect("first chunk was buffered by the proxy");
+
+    release.send(()).unwrap();
+    socket.read_to_end(&mut received).await.unwrap();
+    let response = String::from_utf8_lossy(&received).to_lowercase();
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+
+    assert!(head.starts_with("http/1.1 200"));
+    assert!(head.contains("content-type: application/x-ndjson"));
+    assert!(head.contains("x-export-rows: 2"));
+    assert!(head.contains("trailer: x-checksum"));
+    // Hop-by-hop headers describe the upstream connection only
+    assert!(!head.contains("keep-alive"));
+    assert!(body.contains("row 2"));
+    assert!(body.ends_with("0\r\nx-checksum: abc123\r\n\r\n"));
+}
+
+/// Matches requests whose x-ram-signature is valid for the given key
+struct SignedWith(&'static str);
+
+impl wiremock::Match for SignedWith {
+    fn matches(&self, request: &wiremock::Request) -> bool {
+        let header = |name: &str| request.headers.get(name).and_then(|v| v.to_str().ok());
+        let (Some(timestamp), Some(signature)) = (header(TIMESTAMP_HEADER), header(SIGNATURE_HEADER)) else {
+            return false;
+        };
+        let Ok(timestamp_ms) = timestamp.parse::<u64>() else {
+            return false;
+        };
+        let target = match request.url.query() {
+            Some(query) => format!("{}?{}", request.url.path(), query),
+            None => request.url.path().to_string(),
+        };
+        signature == request_signature(self.0, timestamp_ms, request.method.as_str(), &target, &request.body)
+    }
+}
+
+#[tokio::test]
+async fn test_proxy_signs_enclave_requests() {
+    let nautilus = wiremock::MockServer::start().await;
+    wiremock::Mock::given(wiremock::matchers::path("/create_wallet"))
+        .and(SignedWith("s3cret"))
+        .respond_with(wiremock::ResponseTemplate::new(200).set_body_json(json!({ "signed": true })))
+        .mount(&nautilus)
+        .await;
+    let backend = spawn_backend_with_state(AppState {
+        enclaves: Arc::new(EnclaveRouter::single(nautilus.uri())),
+        nautilus_signing_key: Some("s3cret".to_string()),
+        ..base_state(lazy_pool())
+    })
+    .await;
+
+    // Signed over the enclave path, without the /v1 prefix
+    let resp = post(&reqwest::Client::new(), format!("{}/v1/create_wallet", backend), json!({ "handle": "alice" })).await;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.json::<Value>().await.unwrap()["signed"], true);
+}
+
+#[tokio::test]
+async fn test_challenges_return_to_the_enclave_that_issued_them() {
+    use ram_backend::config::EnclaveConfig;
+
+    // Two enclaves, each issuing its own challenge and only accepting it back
+    let mut enclaves = Vec::new();
+    let mut configs = Vec::new();
+    for (name, seed) in [("a", 1u8), ("b", 2u8)] {
+        let server = wiremock::MockServer::start().await;
+        wiremock::Mock::given(wiremock::matchers::path("/unfreeze/challenge"))
+            .respond_with(wiremock::ResponseTemplate::new(200).set_body_json(json!({
+                "challenge_id": format!("ch-{}", name),
+                "phrase": "blue river",
+                "expires_at_ms": 0,
+            })))
+            .mount(&server)
+            .await;
+        wiremock::Mock::given(wiremock::matchers::path("/request_unfreeze"))
+            .and(wiremock::matchers::body_partial_json(json!({ "payload": { "challenge_id": format!("ch-{}", name) } })))
+            .respond_with(wiremock::ResponseTemplate::new(200).set_body_json(json!({ "enclave": name })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let public_key = hex::encode(SigningKey::from_bytes(&[seed; 32]).verifying_key().as_bytes());
+        configs.push(EnclaveConfig { url: server.uri(), public_key: Some(public_key) });
+        enclaves.push(server);
+    }
+    let router = Arc::new(EnclaveRouter::new(configs.clone()).unwrap());
+    let backend = spawn_backend_with_state(AppState {
+        enclaves: router.clone(),
+        ..base_state(lazy_pool())
+    })
+    .await;
+    let client = reqwest::Client::new();
+
+    // Requests without a handle alternate, and say which key signed them
+    let mut challenges = Vec::new();
+    for config in &configs {
+        let resp = post(&client, format!("{}/v1/unfreeze/challenge", backend), json!({})).await;
+        assert_eq!(resp.status(), 200);
+        assert_eq!(resp.headers()["x-ram-enclave-key"], config.public_key.as_deref().unwrap());
+        let challenge: Value = resp.json().await.unwrap();
+        challenges.push(challenge["challenge_id"].as_str().unwrap().to_string());
+    }
+    assert_eq!(challenges, ["ch-a", "ch-b"]);
+
+    // Each answer goes back to its issuer, whatever enclave the handle maps to
+    for (challenge_id, name) in challenges.iter().zip(["a", "b"]).rev() {
+        let resp = post(
+            &client,
+            format!("{}/v1/request_unfreeze", backend),
+            json!({ "handle": "alice", "challenge_id": challenge_id, "audio_base64": "UklGRg==" }),
+        )
+        .await;
+        assert_eq!(resp.status(), 200);
+        assert_eq!(resp.json::<Value>().await.unwrap()["enclave"], name);
+    }
+
+    // A handle sticks to one enclave, and moves only when it goes down
+    let home = router.route(&Affinity::Handle("alice".to_string())).index;
+    router.mark(&router.enclaves()[home], false);
+    assert_eq!(router.route(&Affinity::Handle("alice".to_string())).index, 1 - home);
+    router.check_health().await;
+    assert_eq!(router.healthy_count(), 0, "the mocks have no /health_check");
+}
+
+#[tokio::test]
+async fn test_unreachable_enclave_is_skipped_on_retry() {
+    use ram_backend::config::EnclaveConfig;
+    use std::time::Duration;
+
+    let nautilus = wiremock::MockServer::start().await;
+    wiremock::Mock::given(wiremock::matchers::path("/bio_auth/queue"))
+        .respond_with(wiremock::ResponseTemplate::new(200).set_body_json(json!({ "depth": 0 })))
+        .mount(&nautilus)
+        .await;
+    let router = Arc::new(
+        EnclaveRouter::new(vec![EnclaveConfig::new("http://127.0.0.1:1"), EnclaveConfig::new(nautilus.uri())])
+            .unwrap(),
+    );
+    let backend = spawn_backend_with_state(AppState {
+        enclaves: router.clone(),
+        proxy: ProxyConfig { retry_backoff: Duration::from_millis(10), ..ProxyConfig::default() },
+        ..base_state(lazy_pool())
+    })
+    .await;
+
+    // The first pick refuses the connection and is marked down; the retry moves on
+    let resp = reqwest::Client::new().get(format!("{}/v1/bio_auth/queue", backend)).send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+    assert!(!router.enclaves()[0].is_healthy());
+    assert_eq!(nautilus.received_requests().await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_enclaves_signing_for_another_chain_are_kept_down() {
+    use ram_backend::config::{EnclaveConfig, Network};
+    use std::collections::BTreeMap;
+
+    let chain = ChainConfig {
+        network: Network::Testnet,
+        chain_id: Some("4c78adac".to_string()),
+        package_ids: BTreeMap::from([(Network::Testnet, "0xabc".to_string()), (Network::Mainnet, "0xdef".to_string())]),
+        rpc_urls: vec!["http://127.0.0.1:1".to_string()],
+    }
+    .validated()
+    .unwrap();
+    let health = |network: &str, package_id: &str| {
+        json!({ "pk": "", "endpoints_status": {}, "chain": { "network": network, "chain_id": null, "package_id": package_id } })
+    };
+    let (same, mainnet, legacy) =
+        (wiremock::MockServer::start().await, wiremock::MockServer::start().await, wiremock::MockServer::start().await);
+    for (server, body) in [
+        (&same, health("testnet", chain.package_id())),
+        (&mainnet, health("mainnet", &format!("0x{:0>64}", "def"))),
+        (&legacy, json!({ "pk": "", "endpoints_status": {} })),
+    ] {
+        wiremock::Mock::given(wiremock::matchers::path("/health_check"))
+            .respond_with(wiremock::ResponseTemplate::new(200).set_body_json(body))
+            .mount(server)
+            .await;
+    }
+    let router = Arc::new(
+        EnclaveRouter::new(vec![
+            EnclaveConfig::new(same.uri()),
+            EnclaveConfig::new(mainnet.uri()),
+            EnclaveConfig::new(legacy.uri()),
+        ])
+        .unwrap()
+        .with_chain(chain.info()),
+    );
+
+    // Enclaves that don't report a chain predate the check and stay up
+    router.check_health().await;
+    let healthy: Vec<bool> = router.enclaves().iter().map(|e| e.is_healthy()).collect();
+    assert_eq!(healthy, [true, false, true]);
+
+    let backend = spawn_backend_with_state(AppState {
+        enclaves: router,
+        sui_rpc: Arc::new(SuiRpcClient::new(chain.rpc_urls.clone())),
+        chain: chain.clone(),
+        ..base_state(lazy_pool())
+    })
+    .await;
+    let client = reqwest::Client::new();
+    let meta: Value = client.get(format!("{}/v1/meta/chain", backend)).send().await.unwrap().json().await.unwrap();
+    assert_eq!(meta, json!({ "network": "testnet", "chain_id": "4c78adac", "package_id": chain.package_id() }));
+}
+
+#[tokio::test]
+async fn test_watchers_are_pushed_their_handle_changes() {
+    use futures_util::StreamExt;
+    use ram_backend::changes::ChangeFeed;
+    use tokio_tungstenite::tungstenite::Message;
+
+    let changes = Arc::new(ChangeFeed::default());
+    let backend = spawn_backend_with_state(AppState {
+        changes: changes.clone(),
+        ..base_state(lazy_pool())
+    })
+    .await;
+    let url = format!("{}/v1/api/changes/bob", backend.replace("http://", "ws://"));
+    let (mut socket, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+
+    // Only events naming bob are pushed: carol's deposit isn't, the transfer to bob is
+    let event = |handle: &str, event_type: &str, to_handle: Option<&str>| RamEvent {
+        handle: Some(handle.to_string()),
+        event_type: event_type.to_string(),
+        amount: Some(5),
+        coin_type: Some(SUI_TYPE.to_string()),
+        result_code: None,
+        from_handle: to_handle.map(|_| handle.to_string()),
+        to_handle: to_handle.map(str::to_string),
+        memo: None,
+        payload_version: None,
+        owner: None,
+        wallet_id: None,
+        tx_digest: format!("0x{}", event_type),
+        timestamp: chrono::Utc::now(),
+        raw_json: None,
+        gas_used: None,
+        sender: None,
+        sender_label: None,
+        from_display_name: None,
+        to_display_name: None,
+        refund_of: None,
+        refunded_in: Vec::new(),
+    };
+    changes.publish(&event("carol", "Deposited", None));
+    changes.publish(&event("alice", "Transferred", Some("bob")));
+
+    let pushed = tokio::time::timeout(std::time::Duration::from_secs(5), socket.next())
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    let Message::Text(text) = pushed else { panic!("expected a text frame, got {:?}", pushed) };
+    let pushed: Value = serde_json::from_str(&text).unwrap();
+    assert_eq!(pushed["type"], "change");
+    assert_eq!(pushed["handle"], "bob");
+    assert_eq!(pushed["event_type"], "Transferred");
+    assert_eq!(pushed["tx_digest"], "0xTransferred");
+}
+
+#[tokio::test]
+async fn test_indexer_ingests_fixture_events() {
+    let Some(db) = test_database().await else {
+        eprintln!("TEST_DATABASE_URL not set, skipping indexer ingestion test");
+        return;
+    };
+    let _db_lock = TEST_DB_LOCK.lock().await;
+    sqlx::query("TRUNCATE ram_events, ram_events_archive, linked_addresses, failed_events").execute(&db).await.unwrap();
+
+    let rpc = start_mock_sui_rpc("sui_events.json").await;
+    let throttle = Arc::new(Throttle::default());
+    let indexer = Indexer::new(rpc.uri(), "0xram".to_string(), db.clone()).with_throttle(throttle.clone());
+
+    let cursor = indexer.fetch_and_process_events(None).await.unwrap();
+    assert_eq!(cursor.unwrap().event_seq, "1");
+
+    let events = Database::get_events_by_handle(&db, "alice", 50, 0).await.unwrap();
+    assert_eq!(events.len(), 7);
+
+    let transfer = events.iter().find(|e| e.event_type == "Transferred").unwrap();
+    assert_eq!(transfer.to_handle.as_deref(), Some("bob"));
+    assert_eq!(transfer.amount, Some(5_000_000_000));
+    assert_eq!(transfer.coin_type.as_deref(), Some(SUI_TYPE));
+    assert_eq!(transfer.memo.as_deref(), Some("INV-2024-001"));
+    // The fixture predates versioned layouts
+    assert_eq!(transfer.payload_version, None);
+    assert_eq!(transfer.raw_json.as_ref().unwrap()["to_handle"], "bob");
+
+    let bioauth = events.iter().find(|e| e.event_type == "BioAuthSuccess").unwrap();
+    assert_eq!(bioauth.result_code, Some(0));
+
+    // Alice linked a ledger address, then unlinked her original one
+    let linked = Database::get_linked_addresses(&db, "alice").await.unwrap();
+    assert_eq!(linked.len(), 1);
+    assert_eq!(linked[0].label, "ledger");
+    assert!(linked[0].address.ends_with("1ed9e"));
+
+    // Each event records its signer, labelled while that address is still linked
+    assert_eq!(transfer.sender.as_deref(), Some(ALICE_ADDRESS));
+    assert_eq!(transfer.sender_label, None);
+    let unlink = events.iter().find(|e| e.event_type == "AddressUnlinked").unwrap();
+    assert_eq!(unlink.sender.as_deref(), Some(linked[0].address.as_str()));
+    assert_eq!(unlink.sender_label.as_deref(), Some("ledger"));
+
+    // Bob sees the incoming transfer too
+    let bob = Database::get_events_by_handle(&db, "bob", 50, 0).await.unwrap();
+    assert_eq!(bob.len(), 1);
+
+    // Re-ingesting the same page is idempotent
+    indexer.fetch_and_process_events(None).await.unwrap();
+    let again = Database::get_events_by_handle(&db, "alice", 50, 0).await.unwrap();
+    assert_eq!(again.len(), 7);
+    assert_eq!(Database::get_linked_addresses(&db, "alice").await.unwrap().len(), 1);
+
+    // Support search by partial handle, partial digest and payload contents
+    let backend = spawn_backend_with_state(AppState {
+        admin_token: Some("admin-secret".to_string()),
+        indexer_throttle: throttle,
+        ..base_state(db.clone())
+    })
+    .await;
+    let client = reqwest::Client::new();
+    let search = |q: &str| {
+        client
+            .get(format!("{}/api/search", backend))
+            .query(&[("q", q)])
+            .bearer_auth("admin-secret")
+            .send()
+    };
+
+    let by_handle: Value = search("lic").await.unwrap().json().await.unwrap();
+    assert_eq!(by_handle["handles"], json!(["alice"]));
+    assert_eq!(by_handle["events"].as_array().unwrap().len(), 7);
+
+    let by_digest: Value = search("TransferAlice").await.unwrap().json().await.unwrap();
+    assert_eq!(by_digest["events"].as_array().unwrap().len(), 2);
+
+    let by_payload: Value = search("ledger").await.unwrap().json().await.unwrap();
+    assert_eq!(by_payload["events"][0]["event_type"], "AddressLinked");
+
+    // Wildcards are matched literally
+    let wildcard: Value = search("%%%").await.unwrap().json().await.unwrap();
+    assert!(wildcard["events"].as_array().unwrap().is_empty());
+    assert_eq!(search("al").await.unwrap().status(), 400);
+
+    // Both pages reached the end of the fixture
+    let progress: Value = client
+        .get(format!("{}/admin/indexer_progress", backend))
+        .bearer_auth("admin-secret")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(progress["caught_up"], true);
+    assert_eq!(progress["events_indexed"].as_u64(), Some(14));
+    assert_eq!(progress["position"], "8xQmTransferAlice111111111111111111111111111111:1");
+    assert_eq!(progress["batch_size"], 50);
+    assert_eq!(progress["estimated_completion_ms"], Value::Null);
+}
+
+#[tokio::test]
+async fn test_portfolio_combines_flows_and_live_balances() {
+    let Some(db) = test_database().await else {
+        eprintln!("TEST_DATABASE_URL not set, skipping portfolio test");
+        return;
+    };
+    let _db_lock = TEST_DB_LOCK.lock().await;
+    sqlx::query("TRUNCATE ram_events, ram_events_archive, linked_addresses, failed_events").execute(&db).await.unwrap();
+
+    let events = start_mock_sui_rpc("sui_events.json").await;
+    Indexer::new(events.uri(), "0xram".to_string(), db.clone())
+        .fetch_and_process_events(None)
+        .await
+        .unwrap();
+
+    let wallet_id = "0x1111111111111111111111111111111111111111111111111111111111111111";
+    let rpc = start_mock_balance_rpc(wallet_id, &[(SUI_TYPE, 5_000_000_000), ("0xc0ffee::usdc::USDC", 42)]).await;
+    let backend = spawn_backend_with_rpc(db, "http://127.0.0.1:1".to_string(), rpc.uri()).await;
+
+    let alice: Value = reqwest::get(format!("{}/api/portfolio/alice", backend))
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(alice["wallet_id"], wallet_id);
+    let coins = alice["coins"].as_array().unwrap();
+    assert_eq!(coins.len(), 2);
+
+    // SUI: 10 deposited, 5 sent to bob; USDC only known from the live Bag
+    let sui = coins.iter().find(|c| c["coin_type"] == SUI_TYPE).unwrap();
+    assert_eq!(sui["deposited"], 10_000_000_000u64);
+    assert_eq!(sui["transferred_out"], 5_000_000_000u64);
+    assert_eq!(sui["net_flow"], 5_000_000_000u64);
+    assert_eq!(sui["live_balance"], 5_000_000_000u64);
+    let usdc = coins.iter().find(|c| c["coin_type"] == "0xc0ffee::usdc::USDC").unwrap();
+    assert_eq!(usdc["net_flow"], 0);
+    assert_eq!(usdc["live_balance"], 42);
+
+    // Bob has no indexed wallet, so only the incoming transfer shows and no live balance
+    let bob: Value = reqwest::get(format!("{}/api/portfolio/bob", backend))
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(bob["wallet_id"].is_null());
+    assert_eq!(bob["coins"][0]["transferred_in"], 5_000_000_000u64);
+    assert!(bob["coins"][0]["live_balance"].is_null());
+
+    // Handles are matched regardless of case and Unicode compatibility forms
+    for spelling in ["ALICE", "Ａｌｉｃｅ"] {
+        let same: Value = reqwest::get(format!("{}/api/portfolio/{}", backend, spelling))
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(same["handle"], "alice");
+        assert_eq!(same["wallet_id"], wallet_id);
+    }
+}
+
+#[tokio::test]
+async fn test_archived_events_stay_in_wallet_history() {
+    let Some(db) = test_database().await else {
+        eprintln!("TEST_DATABASE_URL not set, skipping retention test");
+        return;
+    };
+    let _db_lock = TEST_DB_LOCK.lock().await;
+    sqlx::query("TRUNCATE ram_events, ram_events_archive, linked_addresses, failed_events").execute(&db).await.unwrap();
+
+    let rpc = start_mock_sui_rpc("sui_events.json").await;
+    Indexer::new(rpc.uri(), "0xram".to_string(), db.clone())
+        .fetch_and_process_events(None)
+        .await
+        .unwrap();
+    let backend = spawn_backend(db.clone(), "http://127.0.0.1:1".to_string()).await;
+    let client = reqwest::Client::new();
+    let wallet = || async {
+        let events: Vec<Value> = client
+            .post(format!("{}/api/events", backend))
+            .json(&json!({ "handle": "alice" }))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        let portfolio: Value =
+            client.get(format!("{}/api/portfolio/alice", backend)).send().await.unwrap().json().await.unwrap();
+        (events, portfolio["coins"].clone())
+    };
+    let before = wallet().await;
+    let counts = || async {
+        let hot: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM ram_events").fetch_one(&db).await.unwrap();
+        let archived: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM ram_events_archive").fetch_one(&db).await.unwrap();
+        (hot, archived)
+    };
+
+    // Every fixture event is older than the cutoff; WalletCreated stays for handle lookups
+    let cutoff_ms = 1_707_600_000_000;
+    assert_eq!(archive_events(&db, cutoff_ms, 4).await.unwrap(), 6);
+    assert_eq!(counts().await, (1, 6));
+    assert_eq!(archive_events(&db, cutoff_ms, 4).await.unwrap(), 0);
+    assert_eq!(wallet().await, before);
+
+    // An archived event indexed again is moved without duplicating it
+    Indexer::new(rpc.uri(), "0xram".to_string(), db.clone())
+        .fetch_and_process_events(None)
+        .await
+        .unwrap();
+    assert_eq!(counts().await, (7, 6));
+    assert_eq!(archive_events(&db, cutoff_ms, 100).await.unwrap(), 6);
+    assert_eq!(counts().await, (1, 6));
+    assert_eq!(wallet().await, before);
+}
+
+#[tokio::test]
+async fn test_bioauth_history_lists_attempts() {
+    let Some(db) = test_database().await else {
+        eprintln!("TEST_DATABASE_URL not set, skipping bio-auth history test");
+        return;
+    };
+    let _db_lock = TEST_DB_LOCK.lock().await;
+    sqlx::query("TRUNCATE ram_events, ram_events_archive, linked_addresses, failed_events").execute(&db).await.unwrap();
+
+    let rpc = start_mock_sui_rpc("sui_events.json").await;
+    Indexer::new(rpc.uri(), "0xram".to_string(), db.clone())
+        .fetch_and_process_events(None)
+        .await
+        .unwrap();
+
+    // A typed attempt with the wrong amount, then a duress verdict
+    for (digest, result_code, method, timestamp_ms) in [
+        ("9yBadAmountAlice", 1, 1, 1_707_523_500_000i64),
+        ("9yDuressAlice", 2, 0, 1_707_523_600_000),
+    ] {
+        Database::insert_event(
+            &db,
+            &RamEvent {
+                handle: Some("alice".to_string()),
+                event_type: "BioAuthFailed".to_string(),
+                amount: Some(7_000_000_000),
+                coin_type: None,
+                result_code: Some(result_code),
+                from_handle: None,
+                to_handle: None,
+                memo: None,
+                payload_version: None,
+                owner: None,
+                wallet_id: None,
+                tx_digest: digest.to_string(),
+                timestamp: chrono::DateTime::from_timestamp_millis(timestamp_ms).unwrap(),
+                raw_json: Some(json!({
+                    "handle": "alice",
+                    "amount": "7000000000",
+                    "result": result_code,
+                    "destination_verified": false,
+                    "method": method,
+                })),
+                gas_used: None,
+                sender: Some(ALICE_ADDRESS.to_string()),
+                sender_label: None,
+                from_display_name: None,
+                to_display_name: None,
+                refund_of: None,
+                refunded_in: Vec::new(),
+            },
+        )
+        .await
+        .unwrap();
+    }
+
+    let backend = spawn_backend_with_state(AppState {
+        admin_token: Some("admin-secret".to_string()),
+        ..base_state(db.clone())
+    })
+    .await;
+    let client = reqwest::Client::new();
+    let url = format!("{}/api/bioauth_history/alice", backend);
+
+    assert_eq!(client.get(&url).send().await.unwrap().status(), 401);
+
+    let history: Value = client.get(&url).bearer_auth("admin-secret").send().await.unwrap().json().await.unwrap();
+    assert_eq!(history["failed_total"], 2);
+    let attempts = history["attempts"].as_array().unwrap();
+    assert_eq!(attempts.len(), 3);
+    assert_eq!(attempts[0]["result"], "duress");
+    assert_eq!(attempts[0]["tx_digest"], "9yDuressAlice");
+    assert_eq!(attempts[1]["result"], "invalid_amount");
+    assert_eq!(attempts[1]["method"], "typed");
+    assert_eq!(attempts[1]["destination_verified"], false);
+    // The fixture's event predates method and destination fields
+    assert_eq!(attempts[2]["result_code"], 0);
+    assert_eq!(attempts[2]["method"], "voice");
+    assert_eq!(attempts[2]["timestamp_ms"], 1_707_523_380_000i64);
+    assert!(attempts[2]["destination_verified"].is_null());
+
+    let page: Value = client
+        .get(&url)
+        .query(&[("limit", "1"), ("offset", "1")])
+        .bearer_auth("admin-secret")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(page["attempts"][0]["tx_digest"], "9yBadAmountAlice");
+    assert_eq!(page["failed_total"], 2);
+
+    // Transfers and other wallets' attempts stay out
+    let bob: Value = client
+        .get(format!("{}/api/bioauth_history/bob", backend))
+        .bearer_auth("admin-secret")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(bob["attempts"].as_array().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_failed_events_are_retried_and_requeued() {
+    let Some(db) = test_database().await else {
+        eprintln!("TEST_DATABASE_URL not set, skipping failed event retry test");
+        return;
+    };
+    let _db_lock = TEST_DB_LOCK.lock().await;
+    sqlx::query("TRUNCATE ram_events, ram_events_archive, linked_addresses, failed_events").execute(&db).await.unwrap();
+
+    // A Deposited event without a handle can't be processed: the cursor still
+    // advances but the event is queued instead of dropped
+    let rpc = start_mock_sui_rpc("sui_events_unprocessable.json").await;
+    let indexer = Indexer::new(rpc.uri(), "0xram".to_string(), db.clone());
+    assert!(indexer.fetch_and_process_events(None).await.unwrap().is_some());
+
+    let failed = Database::list_failed_events(&db, Some("pending"), 10).await.unwrap();
+    assert_eq!(failed.len(), 1);
+    assert_eq!(failed[0].attempts, 1);
+    let id = failed[0].id;
+
+    // Not retried before its backoff elapses
+    assert_eq!(indexer.retry_failed_events().await.unwrap(), 0);
+    assert_eq!(Database::list_failed_events(&db, None, 10).await.unwrap()[0].attempts, 1);
+
+    // A due retry that fails again on its last attempt goes dead
+    sqlx::query("UPDATE failed_events SET next_attempt_ms = 0, attempts = $1")
+        .bind(MAX_RETRY_ATTEMPTS - 1)
+        .execute(&db)
+        .await
+        .unwrap();
+    assert_eq!(indexer.retry_failed_events().await.unwrap(), 0);
+    let dead = Database::list_failed_events(&db, Some("dead"), 10).await.unwrap();
+    assert_eq!(dead.len(), 1);
+    assert_eq!(dead[0].attempts, MAX_RETRY_ATTEMPTS);
+
+    // Once the cause is fixed an operator requeues it and the next pass recovers it
+    sqlx::query("UPDATE failed_events SET event_json = jsonb_set(event_json, '{parsedJson,handle}', '\"alice\"')")
+        .execute(&db)
+        .await
+        .unwrap();
+    let backend = spawn_backend_with_state(AppState {
+        admin_token: Some("admin-secret".to_string()),
+        ..base_state(db.clone())
+    })
+    .await;
+    let client = reqwest::Client::new();
+    let url = format!("{}/admin/failed_events/{}/requeue", backend, id);
+    assert_eq!(client.post(&url).send().await.unwrap().status(), 401);
+    let resp = client.post(&url).bearer_auth("admin-secret").send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+
+    assert_eq!(indexer.retry_failed_events().await.unwrap(), 1);
+    assert!(Database::list_failed_events(&db, None, 10).await.unwrap().is_empty());
+    let events = Database::get_events_by_handle(&db, "alice", 50, 0).await.unwrap();
+    assert_eq!(events[0].event_type, "Deposited");
+    assert_eq!(events[0].amount, Some(7));
+
+    // Every database call is timed
+    let metrics: Value = client
+        .get(format!("{}/admin/db_metrics", backend))
+        .bearer_auth("admin-secret")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(metrics["queries"]["requeue_failed_event"]["calls"].as_u64().unwrap() >= 1);
+    assert!(metrics["pools"]["primary"]["size"].as_u64().is_some());
+}
+
+#[tokio::test]
+async fn test_admin_changes_are_audited() {
+    let Some(db) = test_database().await else {
+        eprintln!("TEST_DATABASE_URL not set, skipping admin audit test");
+        return;
+    };
+    let _db_lock = TEST_DB_LOCK.lock().await;
+    sqlx::query("TRUNCATE failed_events, admin_audit").execute(&db).await.unwrap();
+
+    let backend = spawn_backend_with_state(AppState {
+        admin_token: Some("admin-secret".to_string()),
+        ..base_state(db.clone())
+    })
+    .await;
+    let client = reqwest::Client::new();
+
+    // Unauthorized calls change nothing and leave no entry
+    let resp = client.post(format!("{}/admin/failed_events/42/requeue", backend)).send().await.unwrap();
+    assert_eq!(resp.status(), 401);
+
+    // Changes are recorded with their outcome, whether or not they succeed
+    let resp = client
+        .post(format!("{}/admin/failed_events/42/requeue", backend))
+        .bearer_auth("admin-secret")
+        .header("x-admin-actor", "alice@ops")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 404);
+    let resp = client
+        .delete(format!("{}/admin/voice_baseline/bob", backend))
+        .bearer_auth("admin-secret")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 503);
+
+    let audit_url = format!("{}/admin/audit", backend);
+    assert_eq!(client.get(&audit_url).send().await.unwrap().status(), 401);
+    let entries: Vec<Value> = client
+        .get(&audit_url)
+        .bearer_auth("admin-secret")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0]["action"], "voice_baseline.reset");
+    assert_eq!(entries[0]["actor"], "admin");
+    assert_eq!(entries[0]["target"], "bob");
+    assert_eq!(entries[0]["status"], 503);
+    assert_eq!(entries[1]["action"], "failed_event.requeue");
+    assert_eq!(entries[1]["actor"], "alice@ops");
+    assert_eq!(entries[1]["target"], "42");
+    assert_eq!(entries[1]["status"], 404);
+    // The token is fingerprinted, never stored
+    let credential = entries[1]["credential"].as_str().unwrap();
+    assert_eq!(credential.len(), 16);
+    assert_eq!(entries[0]["credential"], credential);
+
+    let by_actor: Vec<Value> = client
+        .get(format!("{}?actor=alice@ops", audit_url))
+        .bearer_auth("admin-secret")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(by_actor.len(), 1);
+    assert_eq!(by_actor[0]["action"], "failed_event.requeue");
+}
+
+#[tokio::test]
+async fn test_pruned_cursor_records_gap_and_reanchors() {
+    let Some(db) = test_database().await else {
+        eprintln!("TEST_DATABASE_URL not set, skipping stale cursor test");
+        return;
+    };
+    let _db_lock = TEST_DB_LOCK.lock().await;
+    sqlx::query("TRUNCATE ram_events, ram_events_archive, linked_addresses, failed_events, indexer_gaps")
+        .execute(&db)
+        .await
+        .unwrap();
+
+    let rpc = start_pruned_sui_rpc("sui_events.json").await;
+    let indexer = Indexer::new(rpc.uri(), "0xram".to_string(), db.clone());
+    let stale = EventId { tx_digest: "8xQmPruned".to_string(), event_seq: "3".to_string() };
+
+    // The stale cursor is abandoned and the earliest available page ingested
+    let cursor = indexer.fetch_and_process_events(Some(&stale)).await.unwrap();
+    assert_eq!(cursor.unwrap().event_seq, "1");
+    assert_eq!(Database::get_events_by_handle(&db, "alice", 50, 0).await.unwrap().len(), 7);
+
+    let gaps = Database::list_indexer_gaps(&db, 10).await.unwrap();
+    assert_eq!(gaps.len(), 1);
+    assert_eq!(gaps[0].stale_cursor, "8xQmPruned:3");
+    assert_eq!(
+        gaps[0].resumed_from.as_deref(),
+        Some("8xQmWalletCreatedAlice1111111111111111111111:0")
+    );
+
+    // Other RPC failures are not mistaken for pruning
+    let down = Indexer::new("http://127.0.0.1:1".to_string(), "0xram".to_string(), db.clone());
+    assert!(down.fetch_and_process_events(Some(&stale)).await.is_err());
+    assert_eq!(Database::list_indexer_gaps(&db, 10).await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_checkpoint_indexer_captures_tx_context() {
+    let Some(db) = test_database().await else {
+        eprintln!("TEST_DATABASE_URL not set, skipping checkpoint indexer test");
+        return;
+    };
+    let _db_lock = TEST_DB_LOCK.lock().await;
+    sqlx::query("TRUNCATE ram_events, ram_events_archive, linked_addresses, failed_events").execute(&db).await.unwrap();
+
+    let rpc = start_mock_checkpoint_rpc("sui_checkpoints.json").await;
+    let indexer = Indexer::new(
+        rpc.uri(),
+        "0x8d6ef0202e592745340d9c96efb32dba98191ea981eea5ad7ba8731f1545e216".to_string(),
+        db.clone(),
+    );
+
+    let last = indexer.fetch_and_process_checkpoints(Some(99)).await.unwrap();
+    assert_eq!(last, Some(101));
+
+    // Only RAM package events are kept, stamped with their transaction's gas
+    let events = Database::get_events_by_handle(&db, "alice", 50, 0).await.unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].event_type, "Deposited");
+    assert_eq!(events[0].amount, Some(10_000_000_000));
+    assert_eq!(events[0].gas_used, Some(2_735_880));
+    assert_eq!(events[0].sender.as_deref(), Some(ALICE_ADDRESS));
+    assert_eq!(events[0].timestamp.timestamp_millis(), 1707523320000);
+    assert!(Database::get_events_by_handle(&db, "bob", 50, 0).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_sponsored_onboarding_creates_wallet() {
+    let Some(db) = test_database().await else {
+        eprintln!("TEST_DATABASE_URL not set, skipping onboarding test");
+        return;
+    };
+    let _db_lock = TEST_DB_LOCK.lock().await;
+    sqlx::query("TRUNCATE ram_events, ram_events_archive, linked_addresses, failed_events, onboardings, handle_reservations").execute(&db).await.unwrap();
+
+    let nautilus = start_mock_nautilus().await;
+    let client = reqwest::Client::new();
+
+    // Without a sponsor key onboarding is off
+    let backend = spawn_backend(db.clone(), nautilus.uri()).await;
+    let resp = client
+        .post(format!("{}/api/onboard", backend))
+        .json(&json!({ "handle": "alice", "sender": ALICE_ADDRESS }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 503);
+
+    let rpc = start_mock_sponsor_rpc("9xSponsoredAlice").await;
+    let mut sponsor_config = SponsorConfig::new(hex::encode([9u8; 32]), "0x5".into(), vec!["0x6".into()], "0x7".into());
+    sponsor_config.daily_limit = 1;
+    sponsor_config.wallet_timeout = std::time::Duration::from_secs(5);
+    let backend = spawn_backend_with_state(AppState {
+        enclaves: Arc::new(EnclaveRouter::single(nautilus.uri())),
+        sui_rpc: Arc::new(SuiRpcClient::new(vec![rpc.uri()])),
+        sponsor: Some(Sponsor::new(sponsor_config, "0x8").unwrap()),
+        ..base_state(db.clone())
+    })
+    .await;
+
+    let user = SigningKey::from_bytes(&[3u8; 32]);
+    let sender = format!("0x{}", hex::encode(ed25519_address(user.verifying_key().as_bytes())));
+    let onboard = |handle: &str, sender: &str| {
+        client
+            .post(format!("{}/api/onboard", backend))
+            .json(&json!({ "handle": handle, "sender": sender }))
+            .send()
+    };
+
+    assert_eq!(onboard("alice", "not-an-address").await.unwrap().status(), 400);
+
+    // The prepared transaction is the user's, with the enclave's authorization
+    let resp = onboard("alice", &sender).await.unwrap();
+    assert_eq!(resp.status(), 200);
+    let prepared: Value = resp.json().await.unwrap();
+    let sponsor_key = SigningKey::from_bytes(&[9u8; 32]).verifying_key();
+    assert_eq!(prepared["sponsor"], format!("0x{}", hex::encode(ed25519_address(sponsor_key.as_bytes()))));
+    let tx_bytes = STANDARD.decode(prepared["tx_bytes"].as_str().unwrap()).unwrap();
+    let enclave_signature =
+        hex::decode(sign_intent(&test_signing_key(), CREATE_WALLET_INTENT, MOCK_TIMESTAMP_MS, &CreateWalletPayload {
+            handle: b"alice".to_vec(),
+        }))
+        .unwrap();
+    let contains = |needle: &[u8]| tx_bytes.windows(needle.len()).any(|w| w == needle);
+    assert!(contains(&enclave_signature));
+    assert!(contains(&ed25519_address(user.verifying_key().as_bytes())));
+    assert!(contains(&ed25519_address(sponsor_key.as_bytes())));
+    // Budgeted from the dry run: (computation + overhead + net storage) plus 20%
+    let budget = u64::from_le_bytes(tx_bytes[tx_bytes.len() - 9..tx_bytes.len() - 1].try_into().unwrap());
+    assert_eq!(budget, 3_900_000);
+
+    // The indexer sees the wallet while submission waits for it
+    let events = start_mock_sui_rpc("sui_events.json").await;
+    let indexer_db = db.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_millis(700)).await;
+        Indexer::new(events.uri(), "0xram".to_string(), indexer_db)
+            .fetch_and_process_events(None)
+            .await
+            .unwrap();
+    });
+
+    let mut user_signature = vec![0u8];
+    user_signature.extend_from_slice(&user.sign(&transaction_digest(&tx_bytes)).to_bytes());
+    user_signature.extend_from_slice(user.verifying_key().as_bytes());
+    let submit_url = format!("{}/api/onboard/{}", backend, prepared["id"]);
+    let resp = client
+        .post(&submit_url)
+        .json(&json!({ "signature": STANDARD.encode(&user_signature) }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let onboarding: Value = resp.json().await.unwrap();
+    assert_eq!(onboarding["status"], "executed");
+    assert_eq!(onboarding["tx_digest"], "9xSponsoredAlice");
+    assert_eq!(onboarding["wallet_id"], "0x1111111111111111111111111111111111111111111111111111111111111111");
+
+    // Executed with the user's signature and a valid sponsor signature
+    let requests = rpc.received_requests().await.unwrap();
+    let execute: Value = requests
+        .iter()
+        .map(|r| serde_json::from_slice::<Value>(&r.body).unwrap())
+        .find(|body| body["method"] == "sui_executeTransactionBlock")
+        .unwrap();
+    assert_eq!(execute["params"][0], prepared["tx_bytes"]);
+    assert_eq!(execute["params"][1][0], STANDARD.encode(&user_signature));
+    let sponsor_signature = STANDARD.decode(execute["params"][1][1].as_str().unwrap()).unwrap();
+    assert_eq!(&sponsor_signature[65..], sponsor_key.as_bytes());
+    let signature = Signature::from_slice(&sponsor_signature[1..65]).unwrap();
+    assert!(sponsor_key.verify(&transaction_digest(&tx_bytes), &signature).is_ok());
+
+    let status: Value = client.get(&submit_url).send().await.unwrap().json().await.unwrap();
+    assert_eq!(status["status"], "executed");
+
+    // Each transaction, handle and sender is sponsored once, within the daily limit
+    let resp = client.post(&submit_url).json(&json!({ "signature": "x" })).send().await.unwrap();
+    assert_eq!(resp.status(), 409);
+    assert_eq!(onboard("alice", &sender).await.unwrap().status(), 409);
+    assert_eq!(onboard("bob", "0xb0b").await.unwrap().status(), 429);
+    assert_eq!(client.get(format!("{}/api/onboard/999", backend)).send().await.unwrap().status(), 404);
+}
+
+#[tokio::test]
+async fn test_gas_estimate_dry_runs_the_transaction() {
+    let nautilus = start_mock_nautilus().await;
+    let rpc = start_mock_sponsor_rpc("unused").await;
+    let backend = spawn_backend_with_rpc(lazy_pool(), nautilus.uri(), rpc.uri()).await;
+    let client = reqwest::Client::new();
+    let estimate = |backend: &str, tx_bytes: String| {
+        client.post(format!("{}/api/tx/estimate", backend)).json(&json!({ "tx_bytes": tx_bytes })).send()
+    };
+
+    let tx_bytes = STANDARD.encode([7u8; 300]);
+    let resp = estimate(&backend, tx_bytes.clone()).await.unwrap();
+    assert_eq!(resp.status(), 200);
+    let recommendation: Value = resp.json().await.unwrap();
+    assert_eq!(recommendation["status"], "success");
+    assert_eq!(recommendation["gas_price"], 750);
+    assert_eq!(recommendation["fee"], 2_500_000);
+    assert_eq!(recommendation["budget"], 3_900_000);
+    assert_eq!(recommendation["gas_used"]["storage_rebate"], 500_000);
+
+    // The fullnode is handed the transaction as given
+    let requests = rpc.received_requests().await.unwrap();
+    let dry_run: Value = requests
+        .iter()
+        .map(|r| serde_json::from_slice::<Value>(&r.body).unwrap())
+        .find(|body| body["method"] == "sui_dryRunTransactionBlock")
+        .unwrap();
+    assert_eq!(dry_run["params"][0], tx_bytes);
+
+    assert_eq!(estimate(&backend, "not base64!".into()).await.unwrap().status(), 400);
+    assert_eq!(estimate(&backend, String::new()).await.unwrap().status(), 400);
+    let oversized = STANDARD.encode(vec![0u8; 128 * 1024 + 1]);
+    assert_eq!(estimate(&backend, oversized).await.unwrap().status(), 413);
+
+    // Without a reachable fullnode there's nothing to recommend
+    let offline = spawn_backend(lazy_pool(), nautilus.uri()).await;
+    assert_eq!(estimate(&offline, tx_bytes).await.unwrap().status(), 502);
+}
+
+/// A COSE_Sign1 attestation document committing to `public_key` and `pcrs`
+fn attestation_document(pcrs: &[[u8; 48]; 3], public_key: &[u8]) -> Vec<u8> {
+    let head = |major: u8, len: usize| match len {
+        0..=23 => vec![major << 5 | len as u8],
+        24..=0xff => vec![major << 5 | 24, len as u8],
+        _ => [vec![major << 5 | 25], (len as u16).to_be_bytes().to_vec()].concat(),
+    };
+    let bytes = |value: &[u8]| [head(2, value.len()), value.to_vec()].concat();
+    let text = |value: &str| [head(3, value.len()), value.as_bytes().to_vec()].concat();
+
+    let mut payload = head(5, 4);
+    payload.extend(text("module_id"));
+    payload.extend(text("i-0abc-enc0123"));
+    payload.extend(text("timestamp"));
+    payload.extend([0x1b, 0, 0, 1, 0x99, 0xe6, 0x51, 0xa8, 0x00]);
+    payload.extend(text("pcrs"));
+    payload.extend(head(5, 3));
+    for (index, pcr) in pcrs.iter().enumerate() {
+        payload.extend(head(0, index));
+        payload.extend(bytes(pcr));
+    }
+    payload.extend(text("public_key"));
+    payload.extend(bytes(public_key));
+
+    let mut document = vec![0xd2];
+    document.extend(head(4, 4));
+    document.extend(bytes(&[0xa1, 0x01, 0x38, 0x22]));
+    document.extend(head(5, 0));
+    document.extend(bytes(&payload));
+    document.extend(bytes(&[7; 96]));
+    document
+}
+
+/// Sui RPC for registrations: an `EnclaveConfig` with `pcrs`, a sender coin, epoch 42
+async fn start_mock_registration_rpc(pcrs: &[[u8; 48]; 3]) -> wiremock::MockServer {
+    use wiremock::matchers::body_partial_json;
+    use wiremock::{Mock, ResponseTemplate};
+
+    let server = start_mock_sponsor_rpc("unused").await;
+    let rpc = |method: &str, result: Value| {
+        Mock::given(body_partial_json(json!({ "method": method })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "jsonrpc": "2.0", "id": 1, "result": result })))
+            .with_priority(1)
+    };
+    rpc("sui_getObject", json!({ "data": {
+        "objectId": "0xc0",
+        "owner": { "Shared": { "initial_shared_version": 11 } },
+        "content": { "fields": {
+            "version": "1",
+            "pcrs": { "fields": { "pos0": pcrs[0].to_vec(), "pos1": pcrs[1].to_vec(), "pos2": pcrs[2].to_vec() } },
+        } },
+    } }))
+    .mount(&server)
+    .await;
+    rpc("suix_getLatestSuiSystemState", json!({ "epoch": "42" })).mount(&server).await;
+    server
+}
+
+#[tokio::test]
+async fn test_register_enclave_prepares_the_attested_registration() {
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, ResponseTemplate};
+
+    let pcrs = [[1u8; 48], [2u8; 48], [3u8; 48]];
+    let enclave_key = test_signing_key().verifying_key();
+    let attestation = attestation_document(&pcrs, enclave_key.as_bytes());
+    let nautilus = wiremock::MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/get_attestation"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "attestation": hex::encode(&attestation) })))
+        .mount(&nautilus)
+        .await;
+    let rpc = start_mock_registration_rpc(&pcrs).await;
+
+    let spawn = |rpc_url: String| {
+        spawn_backend_with_state(AppState {
+            enclaves: Arc::new(EnclaveRouter::single(nautilus.uri())),
+            sui_rpc: Arc::new(SuiRpcClient::new(vec![rpc_url])),
+            admin_token: Some("admin-secret".to_string()),
+            enclave_registration: Some(EnclaveRegistrationConfig {
+                framework_package_id: "0xe1".into(),
+                config_id: "0xc0".into(),
+                cap_id: None,
+                enclave_package_id: "0x7".into(),
+            }),
+            ..base_state(lazy_pool())
+        })
+    };
+    let backend = spawn(rpc.uri()).await;
+    let client = reqwest::Client::new();
+    let register = |backend: &str, body: Value| {
+        client.post(format!("{}/admin/register_enclave", backend)).bearer_auth("admin-secret").json(&body).send()
+    };
+
+    let resp = client.post(format!("{}/admin/register_enclave", backend)).json(&json!({ "sender": "0xa" })).send().await.unwrap();
+    assert_eq!(resp.status(), 401);
+    assert_eq!(register(&backend, json!({ "sender": "not-an-address" })).await.unwrap().status(), 400);
+    assert_eq!(register(&backend, json!({ "sender": "0xa", "enclave": 3 })).await.unwrap().status(), 404);
+
+    let resp = register(&backend, json!({ "sender": "0xa" })).await.unwrap();
+    assert_eq!(resp.status(), 200);
+    let registration: Value = resp.json().await.unwrap();
+    assert_eq!(registration["public_key"], hex::encode(enclave_key.as_bytes()));
+    assert_eq!(registration["pcrs"], json!([hex::encode([1u8; 48]), hex::encode([2u8; 48]), hex::encode([3u8; 48])]));
+    assert_eq!(registration["module_id"], "i-0abc-enc0123");
+    assert_eq!(registration["updates_pcrs"], false);
+    assert_eq!(registration["config_version"], 1);
+    assert_eq!(registration["expires_after_epoch"], 42);
+    assert_eq!(registration["gas"]["budget"], 3_900_000);
+
+    // The document goes on-chain as attested, and the transaction expires with the epoch
+    let tx_bytes = STANDARD.decode(registration["tx_bytes"].as_str().unwrap()).unwrap();
+    assert!(tx_bytes.windows(attestation.len()).any(|w| w == attestation));
+    assert_eq!(&tx_bytes[tx_bytes.len() - 9..], &[&[1u8][..], &42u64.to_le_bytes()].concat()[..]);
+    let budget = u64::from_le_bytes(tx_bytes[tx_bytes.len() - 17..tx_bytes.len() - 9].try_into().unwrap());
+    assert_eq!(budget, 3_900_000);
+
+    // A new build's PCRs aren't registered without a cap to set them with
+    let outdated = start_mock_registration_rpc(&[[9u8; 48], [2u8; 48], [3u8; 48]]).await;
+    let backend = spawn(outdated.uri()).await;
+    assert_eq!(register(&backend, json!({ "sender": "0xa" })).await.unwrap().status(), 409);
+    assert_eq!(register(&backend, json!({ "sender": "0xa", "update_pcrs": true })).await.unwrap().status(), 409);
+}
+
+#[tokio::test]
+async fn test_profiles_need_a_linked_signer() {
+    let Some(db) = test_database().await else {
+        eprintln!("TEST_DATABASE_URL not set, skipping profile test");
+        return;
+    };
+    let _db_lock = TEST_DB_LOCK.lock().await;
+    sqlx::query("TRUNCATE ram_events, ram_events_archive, linked_addresses, failed_events, profiles").execute(&db).await.unwrap();
+
+    let events = start_mock_sui_rpc("sui_events.json").await;
+    Indexer::new(events.uri(), "0xram".to_string(), db.clone())
+        .fetch_and_process_events(None)
+        .await
+        .unwrap();
+    let phone = SigningKey::from_bytes(&[3u8; 32]);
+    let phone_address = format!("0x{}", hex::encode(ed25519_address(phone.verifying_key().as_bytes())));
+    Database::upsert_linked_address(&db, "alice", &phone_address, "phone", 1_707_523_300_000).await.unwrap();
+
+    let backend = spawn_backend(db.clone(), "http://127.0.0.1:1".to_string()).await;
+    let client = reqwest::Client::new();
+    let profile_url = format!("{}/api/profile/alice", backend);
+
+    // Wallets without a profile get the defaults; unknown handles don't exist
+    let resp = client.get(format!("{}/api/receive/nobody", backend)).send().await.unwrap();
+    assert_eq!(resp.status(), 404);
+    let info: Value = client.get(format!("{}/api/receive/alice", backend)).send().await.unwrap().json().await.unwrap();
+    assert_eq!(info, json!({
+        "handle": "alice", "display_name": null, "accepted_coin_types": ["0x2::sui::SUI"], "avatar_hash": null,
+    }));
+
+    let signed = |request, key: &SigningKey, action: &str, body: &[u8], timestamp_ms: i64| {
+        sign_wallet_request(request, key, "profile", action, "alice", body, timestamp_ms)
+    };
+    let now_ms = chrono::Utc::now().timestamp_millis();
+    let coins = ["0x2::sui::SUI", "0xc0ffee::usdc::USDC"];
+    let update = serde_json::to_vec(&json!({
+        "display_name": "Alice Nguyen",
+        "accepted_coin_types": coins,
+        "avatar_hash": "AB".repeat(32),
+        "preferred_locale": "vi-VN",
+        "notifications": { "outgoing_transfers": false },
+    }))
+    .unwrap();
+
+    // A linked address can create the profile; its signature covers the body
+    let resp = signed(client.put(&profile_url), &phone, "update", &update, now_ms).send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+    let info: Value = client.get(format!("{}/api/receive/alice", backend)).send().await.unwrap().json().await.unwrap();
+    assert_eq!(info["display_name"], "Alice Nguyen");
+    assert_eq!(info["accepted_coin_types"], json!(coins));
+    assert_eq!(info["avatar_hash"], "ab".repeat(32));
+    assert!(info.get("preferred_locale").is_none());
+    let info: Value = client.get(format!("{}/api/receive/Alice", backend)).send().await.unwrap().json().await.unwrap();
+    assert_eq!((info["handle"].as_str(), info["display_name"].as_str()), (Some("alice"), Some("Alice Nguyen")));
+
+    // Settings are only readable with a signature
+    assert_eq!(client.get(&profile_url).send().await.unwrap().status(), 401);
+    let profile: Value =
+        signed(client.get(&profile_url), &phone, "read", b"", now_ms).send().await.unwrap().json().await.unwrap();
+    assert_eq!(profile["preferred_locale"], "vi-VN");
+    assert_eq!(profile["notifications"], json!({
+        "incoming_transfers": true, "outgoing_transfers": false, "security_alerts": true,
+    }));
+    assert_eq!(profile["updated_by"], phone_address);
+
+    let tampered = String::from_utf8(update.clone()).unwrap().replace("Alice Nguyen", "Mallory");
+    let resp = signed(client.put(&profile_url), &phone, "update", &update, now_ms + 1)
+        .body(tampered)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 401);
+    let stale = signed(client.put(&profile_url), &phone, "update", &update, now_ms - SIGNATURE_MAX_AGE_MS - 1_000);
+    assert_eq!(stale.send().await.unwrap().status(), 401);
+    let stranger = SigningKey::from_bytes(&[4u8; 32]);
+    let resp = signed(client.put(&profile_url), &stranger, "update", &update, now_ms + 1).send().await.unwrap();
+    assert_eq!(resp.status(), 403);
+    let invalid = br#"{"accepted_coin_types": ["USDC"]}"#;
+    let resp = signed(client.put(&profile_url), &phone, "update", invalid, now_ms + 1).send().await.unwrap();
+    assert_eq!(resp.status(), 400);
+    // Replays and out-of-order updates don't overwrite a newer one
+    let resp = signed(client.put(&profile_url), &phone, "update", &update, now_ms).send().await.unwrap();
+    assert_eq!(resp.status(), 409);
+
+    // The events API shows counterparties by display name
+    Database::upsert_profile(
+        &db,
+        &Profile {
+            handle: "bob".to_string(),
+            display_name: Some("Bob".to_string()),
+            accepted_coin_types: vec!["0x2::sui::SUI".to_string()],
+            avatar_hash: None,
+            preferred_locale: None,
+            notifications: json!({}),
+            updated_by: ALICE_ADDRESS.to_string(),
+            updated_at_ms: now_ms,
+        },
+    )
+    .await
+    .unwrap();
+    let history: Vec<Value> = client
+        .post(format!("{}/api/events", backend))
+        .json(&json!({ "handle": "alice" }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let transfer = history.iter().find(|e| e["event_type"] == "Transferred").unwrap();
+    assert_eq!(transfer["from_display_name"], "Alice Nguyen");
+    assert_eq!(transfer["to_display_name"], "Bob");
+
+    // A signed delete falls the receive link back to the defaults; a replay finds nothing
+    let resp = signed(client.delete(&profile_url), &phone, "delete", b"", now_ms + 2).send().await.unwrap();
+    assert_eq!(resp.status(), 204);
+    let resp = signed(client.delete(&profile_url), &phone, "delete", b"", now_ms + 2).send().await.unwrap();
+    assert_eq!(resp.status(), 404);
+    let info: Value = client.get(format!("{}/api/receive/alice", backend)).send().await.unwrap().json().await.unwrap();
+    assert!(info["display_name"].is_null());
+}
+
+#[tokio::test]
+async fn test_mixed_case_wallets_keep_their_registered_spelling() {
+    let Some(db) = test_database().await else {
+        eprintln!("TEST_DATABASE_URL not set, skipping handle spelling test");
+        return;
+    };
+    let _db_lock = TEST_DB_LOCK.lock().await;
+    sqlx::query("TRUNCATE ram_events, ram_events_archive, linked_addresses, profiles, beneficiaries")
+        .execute(&db)
+        .await
+        .unwrap();
+
+    // A wallet registered on-chain as "Carol", before handles were normalized
+    Database::insert_event(
+        &db,
+        &RamEvent {
+            handle: Some("Carol".to_string()),
+            event_type: "WalletCreated".to_string(),
+            amount: None,
+            coin_type: None,
+            result_code: None,
+            from_handle: None,
+            to_handle: None,
+            memo: None,
+            payload_version: None,
+            owner: None,
+            wallet_id: Some("0x3333333333333333333333333333333333333333333333333333333333333333".to_string()),
+            tx_digest: "8xQmWalletCreatedCarol".to_string(),
+            timestamp: chrono::DateTime::from_timestamp_millis(1_707_523_200_000).unwrap(),
+            raw_json: None,
+            gas_used: None,
+            sender: None,
+            sender_label: None,
+            from_display_name: None,
+            to_display_name: None,
+            refund_of: None,
+            refunded_in: Vec::new(),
+        },
+    )
+    .await
+    .unwrap();
+    let stored = |table: &'static str| {
+        let db = db.clone();
+        async move {
+            sqlx::query_scalar::<_, String>(&format!("SELECT handle FROM {}", table))
+                .fetch_all(&db)
+                .await
+                .unwrap()
+        }
+    };
+
+    // Indexed state keeps the on-chain spelling and is found under any spelling
+    Database::upsert_linked_address(&db, "Carol", ALICE_ADDRESS, "phone", 1_707_523_300_000).await.unwrap();
+    Database::upsert_linked_address(&db, "Carol", ALICE_ADDRESS, "laptop", 1_707_523_400_000).await.unwrap();
+    let linked = Database::get_linked_addresses(&db, "carol").await.unwrap();
+    assert_eq!((linked.len(), linked[0].label.as_str()), (1, "laptop"));
+    assert_eq!(stored("linked_addresses").await, ["Carol"]);
+
+    Database::upsert_beneficiary(&db, "Carol", "Dave", 30 * 86_400_000, 1_707_523_500_000).await.unwrap();
+    Database::upsert_beneficiary(&db, "Carol", "Erin", 30 * 86_400_000, 1_707_523_600_000).await.unwrap();
+    assert_eq!(stored("beneficiaries").await, ["Carol"]);
+
+    // A profile saved under another spelling takes the registered one
+    let profile = Profile {
+        handle: "CAROL".to_string(),
+        display_name: Some("Carol Tran".to_string()),
+        accepted_coin_types: vec!["0x2::sui::SUI".to_string()],
+        avatar_hash: None,
+        preferred_locale: None,
+        notifications: json!({}),
+        updated_by: ALICE_ADDRESS.to_string(),
+        updated_at_ms: 1_707_523_700_000,
+    };
+    assert!(Database::upsert_profile(&db, &profile).await.unwrap());
+    assert_eq!(stored("profiles").await, ["Carol"]);
+
+    // Payers are given the spelling a transfer to the wallet is signed for
+    let backend = spawn_backend(db.clone(), "http://127.0.0.1:1".to_string()).await;
+    let info: Value = reqwest::get(format!("{}/api/receive/carol", backend)).await.unwrap().json().await.unwrap();
+    assert_eq!((info["handle"].as_str(), info["display_name"].as_str()), (Some("Carol"), Some("Carol Tran")));
+    sqlx::query("TRUNCATE profiles").execute(&db).await.unwrap();
+    let info: Value = reqwest::get(format!("{}/api/receive/CAROL", backend)).await.unwrap().json().await.unwrap();
+    assert_eq!(info["handle"], "Carol");
+}
+
+#[tokio::test]
+async fn test_contacts_resolve_transfer_recipients() {
+    let Some(db) = test_database().await else {
+        eprintln!("TEST_DATABASE_URL not set, skipping contacts test");
+        return;
+    };
+    let _db_lock = TEST_DB_LOCK.lock().await;
+    sqlx::query("TRUNCATE ram_events, ram_events_archive, linked_addresses, failed_events, contacts").execute(&db).await.unwrap();
+
+    let events = start_mock_sui_rpc("sui_events.json").await;
+    Indexer::new(events.uri(), "0xram".to_string(), db.clone())
+        .fetch_and_process_events(None)
+        .await
+        .unwrap();
+    let phone = SigningKey::from_bytes(&[3u8; 32]);
+    let phone_address = format!("0x{}", hex::encode(ed25519_address(phone.verifying_key().as_bytes())));
+    Database::upsert_linked_address(&db, "alice", &phone_address, "phone", 1_707_523_300_000).await.unwrap();
+
+    let nautilus = start_mock_nautilus().await;
+    let backend = spawn_backend(db.clone(), nautilus.uri()).await;
+    let client = reqwest::Client::new();
+    let contacts_url = format!("{}/api/contacts", backend);
+    let now_ms = chrono::Utc::now().timestamp_millis();
+    let signed = |request, action: &str, body: &Value| {
+        let body = serde_json::to_vec(body).unwrap();
+        sign_wallet_request(request, &phone, "contacts", action, "alice", &body, now_ms)
+    };
+    let save = json!({ "handle": "alice", "nickname": "Bobby", "contact_handle": "bob" });
+
+    // Contacts must have a wallet
+    assert_eq!(client.post(&contacts_url).json(&save).send().await.unwrap().status(), 401);
+    assert_eq!(signed(client.post(&contacts_url), "save", &save).send().await.unwrap().status(), 404);
+
+    Database::insert_event(
+        &db,
+        &RamEvent {
+            handle: Some("bob".to_string()),
+            event_type: "WalletCreated".to_string(),
+            amount: None,
+            coin_type: None,
+            result_code: None,
+            from_handle: None,
+            to_handle: None,
+            memo: None,
+            payload_version: None,
+            owner: None,
+            wallet_id: Some("0x2222222222222222222222222222222222222222222222222222222222222222".to_string()),
+            tx_digest: "8xQmWalletCreatedBob".to_string(),
+            timestamp: chrono::DateTime::from_timestamp_millis(1_707_523_200_000).unwrap(),
+            raw_json: None,
+            gas_used: None,
+            sender: None,
+            sender_label: None,
+            from_display_name: None,
+            to_display_name: None,
+            refund_of: None,
+            refunded_in: Vec::new(),
+        },
+    )
+    .await
+    .unwrap();
+    let contact: Value = signed(client.post(&contacts_url), "save", &save).send().await.unwrap().json().await.unwrap();
+    assert_eq!(contact["nickname"], "Bobby");
+    assert_eq!(contact["contact_handle"], "bob");
+
+    let list = sign_wallet_request(
+        client.get(&contacts_url).query(&[("handle", "alice")]),
+        &phone,
+        "contacts",
+        "read",
+        "alice",
+        b"",
+        now_ms,
+    );
+    let contacts: Vec<Value> = list.send().await.unwrap().json().await.unwrap();
+    assert_eq!(contacts.len(), 1);
+    assert_eq!(contacts[0]["nickname"], "Bobby");
+
+    // Transfers name the recipient by nickname (any case); the enclave signs the handle
+    let transfer = |recipient: Value| {
+        let mut payload =
+            json!({ "from_handle": "alice", "amount": 5_000_000_000u64, "coin_type": SUI_TYPE, "memo": "rent" });
+        payload.as_object_mut().unwrap().extend(recipient.as_object().unwrap().clone());
+        post(&client, format!("{}/v1/transfer", backend), payload)
+    };
+    let resp = transfer(json!({ "to_contact": "bobby" })).await;
+    assert_eq!(resp.status(), 200);
+    let signed_transfer: SignedResponse<TransferPayload> = resp.json().await.unwrap();
+    assert_eq!(signed_transfer.payload.to_handle, b"bob");
+    assert_eq!(signed_transfer.payload.memo, b"rent");
+    assert!(verify_signed_response(&test_signing_key().verifying_key(), &signed_transfer));
+    assert_eq!(transfer(json!({ "to_contact": "stranger" })).await.status(), 422);
+    assert_eq!(transfer(json!({ "to_contact": "bobby", "to_handle": "carol" })).await.status(), 400);
+
+    // Deleting a contact stops it resolving
+    let delete = json!({ "handle": "alice", "nickname": "BOBBY" });
+    assert_eq!(signed(client.delete(&contacts_url), "delete", &delete).send().await.unwrap().status(), 204);
+    assert_eq!(signed(client.delete(&contacts_url), "delete", &delete).send().await.unwrap().status(), 404);
+    assert_eq!(transfer(json!({ "to_contact": "bobby" })).await.status(), 422);
+}
+
+#[tokio::test]
+async fn test_api_keys_stand_in_for_signatures() {
+    let Some(db) = test_database().await else {
+        eprintln!("TEST_DATABASE_URL not set, skipping API key test");
+        return;
+    };
+    let _db_lock = TEST_DB_LOCK.lock().await;
+    sqlx::query("TRUNCATE ram_events, ram_events_archive, linked_addresses, failed_events, notifications, api_keys")
+        .execute(&db)
+        .await
+        .unwrap();
+
+    let phone = SigningKey::from_bytes(&[3u8; 32]);
+    let phone_address = format!("0x{}", hex::encode(ed25519_address(phone.verifying_key().as_bytes())));
+    Database::upsert_linked_address(&db, "alice", &phone_address, "phone", 1_707_523_300_000).await.unwrap();
+    let nautilus = start_mock_nautilus().await;
+    let backend = spawn_backend(db.clone(), nautilus.uri()).await;
+    let client = reqwest::Client::new();
+    let keys_url = format!("{}/api/keys", backend);
+    let now_ms = chrono::Utc::now().timestamp_millis();
+    let signed = |request, action: &str, body: &Value| {
+        let body = serde_json::to_vec(body).unwrap();
+        sign_wallet_request(request, &phone, "api_keys", action, "alice", &body, now_ms)
+    };
+    let issue = |scope: &str| {
+        let body = json!({ "handle": "alice", "label": format!("{} bot", scope), "scope": scope });
+        signed(client.post(&keys_url), "create", &body).send()
+    };
+
+    // Only the owner issues keys, with a known scope
+    let body = json!({ "handle": "alice", "label": "bot", "scope": "read" });
+    assert_eq!(client.post(&keys_url).json(&body).send().await.unwrap().status(), 401);
+    assert_eq!(issue("admin").await.unwrap().status(), 400);
+    let resp = issue("read").await.unwrap();
+    assert_eq!(resp.status(), 201);
+    let read: Value = resp.json().await.unwrap();
+    let read_key = read["key"].as_str().unwrap().to_string();
+    assert!(read_key.starts_with(&format!("ram_{}_", read["id"].as_str().unwrap())));
+    assert_eq!(read["scope"], "read");
+
+    // A read key reads the wallet's data, and nothing else
+    let notifications = |handle: &str, key: &str| {
+        client.get(format!("{}/api/notifications", backend)).query(&[("handle", handle)]).bearer_auth(key).send()
+    };
+    assert_eq!(notifications("alice", &read_key).await.unwrap().status(), 200);
+    assert_eq!(notifications("ALICE", &read_key).await.unwrap().status(), 200);
+    assert_eq!(notifications("bob", &read_key).await.unwrap().status(), 403);
+    assert_eq!(notifications("alice", "ram_nope_secret").await.unwrap().status(), 401);
+    let resp = client
+        .put(format!("{}/api/profile/alice", backend))
+        .bearer_auth(&read_key)
+        .json(&json!({ "display_name": "Bot" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 403);
+    let resp = client.get(&keys_url).query(&[("handle", "alice")]).bearer_auth(&read_key).send().await.unwrap();
+    assert_eq!(resp.status(), 403);
+
+    // Initiating a transfer with a key takes a transfer key of the sender
+    let transfer = |key: Option<String>| {
+        let payload = json!({
+            "from_handle": "alice", "to_handle": "bob", "amount": 5_000_000_000u64, "coin_type": SUI_TYPE,
+        });
+        let mut request = client.post(format!("{}/v1/transfer", backend)).json(&json!({ "payload": payload }));
+        if let Some(key) = key {
+            request = request.bearer_auth(key);
+        }
+        async move { request.send().await.unwrap().status() }
+    };
+    assert_eq!(transfer(Some(read_key.clone())).await, 403);
+    let transfer_key = issue("transfer").await.unwrap().json::<Value>().await.unwrap();
+    let (transfer_id, transfer_key) = (transfer_key["id"].as_str().unwrap().to_string(), transfer_key["key"].clone());
+    assert_eq!(transfer(transfer_key.as_str().map(str::to_string)).await, 200);
+    assert_eq!(transfer(None).await, 200);
+
+    // Listing shows usage but never secrets
+    let list = sign_wallet_request(
+        client.get(&keys_url).query(&[("handle", "alice")]),
+        &phone,
+        "api_keys",
+        "read",
+        "alice",
+        b"",
+        now_ms,
+    );
+    let keys: Vec<Value> = list.send().await.unwrap().json().await.unwrap();
+    assert_eq!(keys.len(), 2);
+    assert!(keys.iter().all(|key| key.get("key").is_none() && key["last_used_at_ms"].is_i64()));
+
+    // A rotated key's old secret stops working; a revoked key stops altogether
+    let rotate_url = format!("{}/{}/rotate", keys_url, transfer_id);
+    let rotated: Value =
+        signed(client.post(&rotate_url), "rotate", &json!({ "handle": "alice" })).send().await.unwrap().json().await.unwrap();
+    assert_eq!(rotated["id"], transfer_id.as_str());
+    assert_eq!(transfer(transfer_key.as_str().map(str::to_string)).await, 401);
+    assert_eq!(transfer(rotated["key"].as_str().map(str::to_string)).await, 200);
+
+    let revoke_url = format!("{}/{}", keys_url, read["id"].as_str().unwrap());
+    let revoke = json!({ "handle": "alice" });
+    assert_eq!(signed(client.delete(&revoke_url), "revoke", &revoke).send().await.unwrap().status(), 204);
+    assert_eq!(signed(client.delete(&revoke_url), "revoke", &revoke).send().await.unwrap().status(), 404);
+    assert_eq!(notifications("alice", &read_key).await.unwrap().status(), 401);
+}
+
+#[tokio::test]
+async fn test_merchant_webhooks_are_signed_and_retried() {
+    let Some(db) = test_database().await else {
+        eprintln!("TEST_DATABASE_URL not set, skipping webhook test");
+        return;
+    };
+    let _db_lock = TEST_DB_LOCK.lock().await;
+    sqlx::query("TRUNCATE ram_events, ram_events_archive, linked_addresses, failed_events, webhooks, webhook_deliveries")
+        .execute(&db)
+        .await
+        .unwrap();
+
+    // The merchant's endpoint fails once, then accepts
+    let merchant = wiremock::MockServer::start().await;
+    wiremock::Mock::given(wiremock::matchers::path("/hooks/ram"))
+        .respond_with(wiremock::ResponseTemplate::new(500))
+        .up_to_n_times(1)
+        .mount(&merchant)
+        .await;
+    wiremock::Mock::given(wiremock::matchers::path("/hooks/ram"))
+        .respond_with(wiremock::ResponseTemplate::new(200))
+        .mount(&merchant)
+        .await;
+
+    let shop = SigningKey::from_bytes(&[4u8; 32]);
+    let shop_address = format!("0x{}", hex::encode(ed25519_address(shop.verifying_key().as_bytes())));
+    Database::upsert_linked_address(&db, "bob", &shop_address, "shop", 1_707_523_300_000).await.unwrap();
+    // The merchant is a local mock server, so private targets are allowed here
+    let config = WebhookConfig { allow_private_targets: true, ..WebhookConfig::default() };
+    let backend = spawn_backend_with_state(AppState { webhooks: config.clone(), ..base_state(db.clone()) }).await;
+    let client = reqwest::Client::new();
+    let webhook_url = format!("{}/api/webhook", backend);
+    let now_ms = chrono::Utc::now().timestamp_millis();
+    let signed = |request, action: &str, body: &Value| {
+        let body = serde_json::to_vec(body).unwrap();
+        sign_wallet_request(request, &shop, "webhooks", action, "bob", &body, now_ms).send()
+    };
+
+    // Only the owner registers a webhook, at an http(s) URL
+    let body = json!({ "handle": "bob", "url": format!("{}/hooks/ram", merchant.uri()) });
+    assert_eq!(client.put(&webhook_url).json(&body).send().await.unwrap().status(), 401);
+    let bad = json!({ "handle": "bob", "url": "file:///etc/passwd" });
+    assert_eq!(signed(client.put(&webhook_url), "save", &bad).await.unwrap().status(), 400);
+    // Deployments only take public https URLs
+    let deployed = spawn_backend(db.clone(), "http://127.0.0.1:1".to_string()).await;
+    let resp = signed(client.put(format!("{}/api/webhook", deployed)), "save", &body).await.unwrap();
+    assert_eq!(resp.status(), 400);
+    let resp = signed(client.put(&webhook_url), "save", &body).await.unwrap();
+    assert_eq!(resp.status(), 200);
+    let secret = resp.json::<Value>().await.unwrap()["secret"].as_str().unwrap().to_string();
+
+    // alice's transfer to bob is queued once, however often it's indexed
+    let rpc = start_mock_sui_rpc("sui_events.json").await;
+    let indexer = Indexer::new(rpc.uri(), "0xram".to_string(), db.clone());
+    indexer.fetch_and_process_events(None).await.unwrap();
+    indexer.fetch_and_process_events(None).await.unwrap();
+    let queued: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM webhook_deliveries").fetch_one(&db).await.unwrap();
+    assert_eq!(queued, 1);
+
+    // The failed attempt is retried after its backoff
+    let delivery = delivery_client(&config).unwrap();
+    let now_ms = chrono::Utc::now().timestamp_millis();
+    assert_eq!(deliver_due(&db, &delivery, &config, now_ms).await.unwrap(), 0);
+    let last_error: Option<String> =
+        sqlx::query_scalar("SELECT last_error FROM webhook_deliveries").fetch_one(&db).await.unwrap();
+    assert_eq!(last_error.as_deref(), Some("answered 5xx"));
+    assert_eq!(deliver_due(&db, &delivery, &config, now_ms).await.unwrap(), 0);
+    assert_eq!(deliver_due(&db, &delivery, &config, now_ms + retry_delay_ms(1)).await.unwrap(), 1);
+    assert_eq!(deliver_due(&db, &delivery, &config, now_ms + retry_delay_ms(8)).await.unwrap(), 0);
+
+    // Both attempts carry the same event ID, and verify with the SDK
+    let requests = merchant.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 2);
+    let header = |name: &str| requests[1].headers.get(name).unwrap().to_str().unwrap().to_string();
+    assert_eq!(requests[0].headers.get(webhook::ID_HEADER), requests[1].headers.get(webhook::ID_HEADER));
+    let verify = |secret: &str| {
+        webhook::verify(
+            secret,
+            &header(webhook::TIMESTAMP_HEADER),
+            &header(webhook::SIGNATURE_HEADER),
+            &requests[1].body,
+            chrono::Utc::now().timestamp_millis() as u64,
+            webhook::DEFAULT_TOLERANCE_MS,
+        )
+    };
+    assert!(verify("whsec_other").is_err());
+    let event = verify(&secret).unwrap();
+    assert_eq!(event.id, header(webhook::ID_HEADER));
+    assert_eq!(event.kind, "payment.received");
+    assert_eq!(event.data.handle, "bob");
+    assert_eq!(event.data.payer_handle.as_deref(), Some("alice"));
+    assert_eq!(event.data.amount, Some(5_000_000_000));
+    assert_eq!(event.data.memo.as_deref(), Some("INV-2024-001"));
+
+    // The owner sees how it went, without the secret
+    let status: Value = sign_wallet_request(
+        client.get(&webhook_url).query(&[("handle", "bob")]),
+        &shop,
+        "webhooks",
+        "read",
+        "bob",
+        b"",
+        now_ms,
+    )
+    .send()
+    .await
+    .unwrap()
+    .json()
+    .await
+    .unwrap();
+    assert!(status.get("secret").is_none());
+    assert_eq!(status["deliveries"][0]["status"], "delivered");
+    assert_eq!(status["deliveries"][0]["attempts"], 2);
+
+    let remove = json!({ "handle": "bob" });
+    assert_eq!(signed(client.delete(&webhook_url), "delete", &remove).await.unwrap().status(), 204);
+    assert_eq!(signed(client.delete(&webhook_url), "delete", &remove).await.unwrap().status(), 404);
+}
+
+#[tokio::test]
+async fn test_merchant_refunds_link_payment_and_refund() {
+    let Some(db) = test_database().await else {
+        eprintln!("TEST_DATABASE_URL not set, skipping refund test");
+        return;
+    };
+    let _db_lock = TEST_DB_LOCK.lock().await;
+    sqlx::query("TRUNCATE ram_events, ram_events_archive, linked_addresses, failed_events, refunds")
+        .execute(&db)
+        .await
+        .unwrap();
+
+    // alice paid bob 5 SUI
+    const PAYMENT: &str = "8xQmTransferAlice111111111111111111111111111111";
+    let rpc = start_mock_sui_rpc("sui_events.json").await;
+    Indexer::new(rpc.uri(), "0xram".to_string(), db.clone()).fetch_and_process_events(None).await.unwrap();
+
+    let shop = SigningKey::from_bytes(&[4u8; 32]);
+    let shop_address = format!("0x{}", hex::encode(ed25519_address(shop.verifying_key().as_bytes())));
+    Database::upsert_linked_address(&db, "bob", &shop_address, "shop", 1_707_523_300_000).await.unwrap();
+    let nautilus = start_mock_nautilus().await;
+    let backend = spawn_backend(db.clone(), nautilus.uri()).await;
+    let client = reqwest::Client::new();
+    let refunds_url = format!("{}/api/refunds", backend);
+    let now_ms = chrono::Utc::now().timestamp_millis();
+    let signed = |request, action: &str, body: &Value| {
+        let body = serde_json::to_vec(body).unwrap();
+        sign_wallet_request(request, &shop, "refunds", action, "bob", &body, now_ms).send()
+    };
+    let start = |refund: Value| signed(client.post(&refunds_url), "create", &refund);
+
+    // Only the recipient refunds a payment, up to its amount
+    let partial = json!({ "handle": "bob", "tx_digest": PAYMENT, "amount": 2_000_000_000i64, "reason": "damaged" });
+    assert_eq!(client.post(&refunds_url).json(&partial).send().await.unwrap().status(), 401);
+    assert_eq!(start(json!({ "handle": "bob", "tx_digest": "8xQmNope" })).await.unwrap().status(), 404);
+    let too_much = json!({ "handle": "bob", "tx_digest": PAYMENT, "amount": 6_000_000_000i64 });
+    assert_eq!(start(too_much).await.unwrap().status(), 409);
+    let resp = start(partial).await.unwrap();
+    assert_eq!(resp.status(), 201);
+    let refund: Value = resp.json().await.unwrap();
+    assert_eq!(refund["payer_handle"], "alice");
+    assert_eq!(refund["status"], "pending");
+    let refund_id = refund["id"].as_str().unwrap().to_string();
+
+    // The rest is refundable until cancelled
+    let rest: Value = start(json!({ "handle": "bob", "tx_digest": PAYMENT })).await.unwrap().json().await.unwrap();
+    assert_eq!(rest["amount"], 3_000_000_000i64);
+    assert_eq!(start(json!({ "handle": "bob", "tx_digest": PAYMENT })).await.unwrap().status(), 409);
+    let cancel_url = format!("{}/{}/cancel", refunds_url, rest["id"].as_str().unwrap());
+    let cancel = json!({ "handle": "bob" });
+    assert_eq!(signed(client.post(&cancel_url), "cancel", &cancel).await.unwrap().status(), 204);
+    assert_eq!(signed(client.post(&cancel_url), "cancel", &cancel).await.unwrap().status(), 404);
+
+    // Paying it out names the refund; the enclave signs the payer, amount and link
+    let transfer = |payload: Value| post(&client, format!("{}/v1/transfer", backend), payload);
+    let resp = transfer(json!({ "from_handle": "bob", "refund_id": refund_id })).await;
+    assert_eq!(resp.status(), 200);
+    let signed_refund: SignedResponse<TransferPayload> = resp.json().await.unwrap();
+    assert_eq!(signed_refund.payload.to_handle, b"alice");
+    assert_eq!(signed_refund.payload.amount, 2_000_000_000);
+    assert_eq!(signed_refund.payload.coin_type, SUI_TYPE.as_bytes());
+    assert_eq!(signed_refund.payload.memo, format!("refund:{}", refund_id).into_bytes());
+    assert_eq!(transfer(json!({ "from_handle": "bob", "refund_id": "nope" })).await.status(), 422);
+    assert_eq!(transfer(json!({ "from_handle": "carol", "refund_id": refund_id })).await.status(), 403);
+    let wrong_amount = json!({ "from_handle": "bob", "refund_id": refund_id, "amount": 5_000_000_000u64 });
+    assert_eq!(transfer(wrong_amount).await.status(), 400);
+
+    // Once indexed, the refund is completed and both transactions point at each other
+    let payout = RamEvent {
+        handle: Some("bob".to_string()),
+        event_type: "Transferred".to_string(),
+        amount: Some(2_000_000_000),
+        coin_type: Some(SUI_TYPE.to_string()),
+        result_code: None,
+        from_handle: Some("bob".to_string()),
+        to_handle: Some("alice".to_string()),
+        memo: Some(format!("refund:{}", refund_id)),
+        payload_version: Some(2),
+        owner: None,
+        wallet_id: None,
+        tx_digest: "8xQmRefundBob".to_string(),
+        timestamp: chrono::Utc::now(),
+        raw_json: None,
+        gas_used: None,
+        sender: None,
+        sender_label: None,
+        from_display_name: None,
+        to_display_name: None,
+        refund_of: None,
+        refunded_in: Vec::new(),
+    };
+    Database::insert_event(&db, &payout).await.unwrap();
+    assert!(link_refund(&db, &payout, now_ms).await.unwrap());
+    assert!(!link_refund(&db, &payout, now_ms).await.unwrap());
+
+    let events = Database::get_events_by_handle(&db, "alice", 50, 0).await.unwrap();
+    let payment = events.iter().find(|e| e.tx_digest == PAYMENT && e.event_type == "Transferred").unwrap();
+    assert_eq!(payment.refunded_in, vec!["8xQmRefundBob".to_string()]);
+    let refunded = events.iter().find(|e| e.tx_digest == "8xQmRefundBob").unwrap();
+    assert_eq!(refunded.refund_of.as_deref(), Some(PAYMENT));
+
+    let list = sign_wallet_request(
+        client.get(&refunds_url).query(&[("handle", "bob")]),
+        &shop,
+        "refunds",
+        "read",
+        "bob",
+        b"",
+        now_ms,
+    );
+    let refunds: Vec<Value> = list.send().await.unwrap().json().await.unwrap();
+    let completed = refunds.iter().find(|r| r["id"] == refund_id.as_str()).unwrap();
+    assert_eq!(completed["status"], "completed");
+    assert_eq!(completed["refund_tx_digest"], "8xQmRefundBob");
+    assert_eq!(transfer(json!({ "from_handle": "bob", "refund_id": refund_id })).await.status(), 409);
+}
+
+#[tokio::test]
+async fn test_large_transfers_carry_a_travel_rule_envelope() {
+    let Some(db) = test_database().await else {
+        eprintln!("TEST_DATABASE_URL not set, skipping travel-rule test");
+        return;
+    };
+    let _db_lock = TEST_DB_LOCK.lock().await;
+    sqlx::query("TRUNCATE ram_events, linked_addresses, travel_rule_envelopes").execute(&db).await.unwrap();
+
+    let nautilus = start_mock_nautilus().await;
+    let backend = spawn_backend_with_state(AppState {
+        enclaves: Arc::new(EnclaveRouter::single(nautilus.uri())),
+        travel_rule: TravelRuleConfig::parse("0x2::sui::SUI=1000000000000").unwrap(),
+        ..base_state(db.clone())
+    })
+    .await;
+    let client = reqwest::Client::new();
+    let transfer = |payload: Value| post(&client, format!("{}/v1/transfer", backend), payload);
+    let large = |extra: Value| {
+        let mut payload = json!({ "from_handle": "alice", "to_handle": "bob", "amount": 2_000_000_000_000u64, "coin_type": SUI_TYPE });
+        payload.as_object_mut().unwrap().extend(extra.as_object().unwrap().clone());
+        payload
+    };
+
+    // Small transfers pass untouched; large ones need an envelope, in any coin spelling
+    let long_sui = format!("0x{:0>64}::sui::SUI", "2");
+    assert_eq!(transfer(large(json!({ "amount": 1_000u64 }))).await.status(), 200);
+    assert_eq!(transfer(large(json!({}))).await.status(), 422);
+    assert_eq!(transfer(large(json!({ "coin_type": long_sui }))).await.status(), 422);
+
+    let ciphertext = b"sealed originator/beneficiary record";
+    let envelope = json!({ "scheme": "x25519-xsalsa20-poly1305", "ciphertext": STANDARD.encode(ciphertext) });
+    let hash = hex::encode(<sha2::Sha256 as sha2::Digest>::digest(ciphertext));
+    let resp = transfer(large(json!({ "memo": "INV-2024-001", "travel_rule": envelope }))).await;
+    assert_eq!(resp.status(), 200);
+    let signed: SignedResponse<TransferPayload> = resp.json().await.unwrap();
+    assert_eq!(signed.payload.memo, format!("INV-2024-001 travel:{}", hash).into_bytes());
+    // Retrying the same transfer is fine, reusing the envelope for another isn't
+    assert_eq!(transfer(large(json!({ "travel_rule": envelope }))).await.status(), 200);
+    assert_eq!(transfer(large(json!({ "to_handle": "carol", "travel_rule": envelope }))).await.status(), 409);
+    for bad in [
+        json!({ "travel_rule": { "scheme": "x25519", "ciphertext": "not base64!" } }),
+        json!({ "travel_rule": { "scheme": "", "ciphertext": STANDARD.encode(ciphertext) } }),
+        json!({ "travel_rule": envelope, "memo": "x".repeat(60) }),
+    ] {
+        assert_eq!(transfer(large(bad)).await.status(), 400);
+    }
+
+    // Once indexed the envelope points at its transfer, and either party can read it
+    let event = RamEvent {
+        handle: Some("alice".to_string()),
+        event_type: "Transferred".to_string(),
+        amount: Some(2_000_000_000_000),
+        coin_type: Some(SUI_TYPE.to_string()),
+        result_code: None,
+        from_handle: Some("alice".to_string()),
+        to_handle: Some("bob".to_string()),
+        memo: Some(format!("INV-2024-001 travel:{}", hash)),
+        payload_version: Some(2),
+        owner: None,
+        wallet_id: None,
+        tx_digest: "8xQmTravelAlice".to_string(),
+        timestamp: chrono::Utc::now(),
+        raw_json: None,
+        gas_used: None,
+        sender: None,
+        sender_label: None,
+        from_display_name: None,
+        to_display_name: None,
+        refund_of: None,
+        refunded_in: Vec::new(),
+    };
+    assert!(link_envelope(&db, &event).await.unwrap());
+    assert!(!link_envelope(&db, &event).await.unwrap());
+
+    let now_ms = chrono::Utc::now().timestamp_millis();
+    let bob = SigningKey::from_bytes(&[5u8; 32]);
+    let bob_address = format!("0x{}", hex::encode(ed25519_address(bob.verifying_key().as_bytes())));
+    Database::upsert_linked_address(&db, "bob", &bob_address, "bob", now_ms).await.unwrap();
+    Database::upsert_linked_address(&db, "mallory", &bob_address, "mallory", now_ms).await.unwrap();
+    let read = |handle: &str| {
+        sign_wallet_request(
+            client.get(format!("{}/api/travel_rule/{}", backend, hash)).query(&[("handle", handle)]),
+            &bob,
+            "travel_rule",
+            "read",
+            handle,
+            b"",
+            now_ms,
+        )
+        .send()
+    };
+    let stored: Value = read("bob").await.unwrap().json().await.unwrap();
+    assert_eq!(stored["from_handle"], "alice");
+    assert_eq!(stored["ciphertext"], STANDARD.encode(ciphertext));
+    assert_eq!(stored["tx_digest"], "8xQmTravelAlice");
+    assert_eq!(read("mallory").await.unwrap().status(), 404);
+}
+
+#[tokio::test]
+async fn test_owner_is_warned_before_beneficiary_can_claim() {
+    let Some(db) = test_database().await else {
+        eprintln!("TEST_DATABASE_URL not set, skipping inactivity test");
+        return;
+    };
+    let _db_lock = TEST_DB_LOCK.lock().await;
+    sqlx::query("TRUNCATE ram_events, ram_events_archive, linked_addresses, failed_events, beneficiaries, notifications")
+        .execute(&db)
+        .await
+        .unwrap();
+
+    let rpc = start_mock_sui_rpc("sui_events_beneficiary.json").await;
+    Indexer::new(rpc.uri(), "0xram".to_string(), db.clone())
+        .fetch_and_process_events(None)
+        .await
+        .unwrap();
+
+    // Carol named a beneficiary, then removed it; Alice named Bob and later
+    // checked in, which is her last activity (the deposit after it isn't hers)
+    let checked_in_ms = 1_707_609_600_000;
+    let timeout_ms = 2_592_000_000;
+    let week = std::time::Duration::from_secs(7 * 86_400);
+    let warn_at = checked_in_ms + timeout_ms - week.as_millis() as i64;
+    assert_eq!(warn_inactive_wallets(&db, week, warn_at - 1).await.unwrap(), 0);
+    assert_eq!(warn_inactive_wallets(&db, week, warn_at).await.unwrap(), 1);
+    // Once per inactive stretch
+    assert_eq!(warn_inactive_wallets(&db, week, warn_at + 60_000).await.unwrap(), 0);
+
+    // New activity restarts the clock, and the next stretch is warned again
+    let transferred_ms = warn_at + 120_000;
+    Database::insert_event(
+        &db,
+        &RamEvent {
+            handle: Some("alice".to_string()),
+            event_type: "Transferred".to_string(),
+            amount: Some(1_000_000_000),
+            coin_type: Some(SUI_TYPE.to_string()),
+            result_code: None,
+            from_handle: Some("alice".to_string()),
+            to_handle: Some("bob".to_string()),
+            memo: None,
+            payload_version: None,
+            owner: None,
+            wallet_id: None,
+            tx_digest: "8xQmTransferAlice2222222222222222222222222222".to_string(),
+            timestamp: chrono::DateTime::from_timestamp_millis(transferred_ms).unwrap(),
+            raw_json: None,
+            gas_used: None,
+            sender: None,
+            sender_label: None,
+            from_display_name: None,
+            to_display_name: None,
+            refund_of: None,
+            refunded_in: Vec::new(),
+        },
+    )
+    .await
+    .unwrap();
+    let next_warn_at = transferred_ms + timeout_ms - week.as_millis() as i64;
+    assert_eq!(warn_inactive_wallets(&db, week, next_warn_at - 1).await.unwrap(), 0);
+    assert_eq!(warn_inactive_wallets(&db, week, next_warn_at).await.unwrap(), 1);
+
+    // Only Alice's linked addresses can read her notifications
+    let phone = SigningKey::from_bytes(&[3u8; 32]);
+    let phone_address = format!("0x{}", hex::encode(ed25519_address(phone.verifying_key().as_bytes())));
+    Database::upsert_linked_address(&db, "alice", &phone_address, "phone", 1_707_523_300_000).await.unwrap();
+    let backend = spawn_backend(db.clone(), "http://127.0.0.1:1".to_string()).await;
+    let client = reqwest::Client::new();
+    let url = format!("{}/api/notifications", backend);
+    assert_eq!(client.get(&url).query(&[("handle", "alice")]).send().await.unwrap().status(), 401);
+
+    let list = sign_wallet_request(
+        client.get(&url).query(&[("handle", "alice")]),
+        &phone,
+        "notifications",
+        "read",
+        "alice",
+        b"",
+        chrono::Utc::now().timestamp_millis(),
+    );
+    let notifications: Vec<Value> = list.send().await.unwrap().json().await.unwrap();
+    assert_eq!(notifications.len(), 2);
+    assert_eq!(notifications[0]["kind"], "inactivity_warning");
+    assert_eq!(notifications[0]["data"]["beneficiary_handle"], "bob");
+    assert_eq!(notifications[0]["data"]["last_active_ms"], transferred_ms);
+    assert_eq!(notifications[0]["data"]["claimable_at_ms"], transferred_ms + timeout_ms);
+    assert_eq!(notifications[1]["data"]["last_active_ms"], checked_in_ms);
+}
+
+#[tokio::test]
+async fn test_proved_deposit_is_indexed_with_its_deposit_id() {
+    let Some(db) = test_database().await else {
+        eprintln!("TEST_DATABASE_URL not set, skipping deposit proof test");
+        return;
+    };
+    let _db_lock = TEST_DB_LOCK.lock().await;
+    sqlx::query("TRUNCATE ram_events, ram_events_archive, linked_addresses, failed_events").execute(&db).await.unwrap();
+
+    let rpc = start_mock_sui_rpc("sui_events_deposit_proof.json").await;
+    Indexer::new(rpc.uri(), "0xram".to_string(), db.clone())
+        .fetch_and_process_events(None)
+        .await
+        .unwrap();
+
+    let events = Database::get_events_by_handle(&db, "alice", 50, 0).await.unwrap();
+    assert_eq!(events.len(), 2);
+    let proved = events.iter().find(|e| e.event_type == "DepositProved").unwrap();
+    assert_eq!(proved.memo.as_deref(), Some("104877321"));
+    assert_eq!(proved.amount, Some(2_500_000_000));
+    assert_eq!(proved.coin_type.as_deref(), Some(SUI_TYPE));
+
+    // The accompanying Deposited event is the one balances count
+    let flows = Database::get_coin_flows(&db, "alice").await.unwrap();
+    assert_eq!(flows.len(), 1);
+    assert_eq!(flows[0].deposited, 2_500_000_000);
+}
+
+#[tokio::test]
+async fn test_route_aliases_are_edited_without_restart() {
+    use ram_backend::aliases::RouteAliases;
+
+    let Some(db) = test_database().await else {
+        eprintln!("TEST_DATABASE_URL not set, skipping route alias test");
+        return;
+    };
+    let _db_lock = TEST_DB_LOCK.lock().await;
+    sqlx::query("TRUNCATE admin_audit").execute(&db).await.unwrap();
+    sqlx::query("DELETE FROM proxy_routes WHERE path LIKE '/legacy/%'").execute(&db).await.unwrap();
+
+    let nautilus = wiremock::MockServer::start().await;
+    for route in ["/create_wallet", "/withdraw"] {
+        wiremock::Mock::given(wiremock::matchers::path(route))
+            .respond_with(wiremock::ResponseTemplate::new(200).set_body_json(json!({ "route": route })))
+            .mount(&nautilus)
+            .await;
+    }
+    let route_aliases = Arc::new(RouteAliases::load(&db).await.unwrap());
+    let backend = spawn_backend_with_state(AppState {
+        enclaves: Arc::new(EnclaveRouter::single(nautilus.uri())),
+        admin_token: Some("admin-secret".to_string()),
+        route_aliases: route_aliases.clone(),
+        ..base_state(db.clone())
+    })
+    .await;
+    let client = reqwest::Client::new();
+    let routed = |path: &'static str| {
+        let client = client.clone();
+        let url = format!("{}/v1{}", backend, path);
+        async move {
+            let resp = post(&client, url, json!({ "handle": "alice" })).await;
+            match resp.status().as_u16() {
+                200 => Some(resp.json::<Value>().await.unwrap()["route"].as_str().unwrap().to_string()),
+                status => {
+                    assert_eq!(status, 404);
+                    None
+                }
+            }
+        }
+    };
+
+    // Seeded legacy names reach the enclave's routes
+    assert_eq!(routed("/process_create_wallet").await.as_deref(), Some("/create_wallet"));
+    assert_eq!(routed("/legacy/withdraw").await, None);
+
+    let routes_url = format!("{}/admin/routes", backend);
+    let save = |alias: Value| client.put(&routes_url).bearer_auth("admin-secret").json(&alias).send();
+    assert_eq!(save(json!({ "path": "/legacy/withdraw", "upstream_path": "https://evil.example" })).await.unwrap().status(), 400);
+    assert_eq!(
+        save(json!({ "path": "/legacy/withdraw", "upstream_path": "/withdraw", "route_class": "slow" })).await.unwrap().status(),
+        400
+    );
+
+    // A saved alias works at once on this backend, and on others once they reload
+    let resp = save(json!({ "path": "/legacy/withdraw", "upstream_path": "/withdraw" })).await.unwrap();
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.json::<Value>().await.unwrap()["route_class"], "default");
+    assert_eq!(routed("/legacy/withdraw").await.as_deref(), Some("/withdraw"));
+    let other_backend = RouteAliases::default();
+    assert!(other_backend.resolve("/legacy/withdraw").is_none());
+    other_backend.reload(&db).await.unwrap();
+    assert_eq!(other_backend.resolve("/legacy/withdraw").unwrap().upstream_path, "/withdraw");
+
+    let listed: Vec<Value> =
+        client.get(&routes_url).bearer_auth("admin-secret").send().await.unwrap().json().await.unwrap();
+    assert!(listed.iter().any(|alias| alias["path"] == "/process_bio_auth" && alias["route_class"] == "bio_auth"));
+
+    // Fixed routes can't be hijacked by an alias
+    assert_eq!(save(json!({ "path": "/create_wallet", "upstream_path": "/withdraw" })).await.unwrap().status(), 200);
+    assert_eq!(routed("/create_wallet").await.as_deref(), Some("/create_wallet"));
+
+    let delete = |path: &str| client.delete(format!("{}?path={}", routes_url, path)).bearer_auth("admin-secret").send();
+    assert_eq!(delete("/legacy/withdraw").await.unwrap().status(), 200);
+    assert_eq!(delete("/legacy/withdraw").await.unwrap().status(), 404);
+    assert_eq!(delete("/create_wallet").await.unwrap().status(), 200);
+    assert_eq!(routed("/legacy/withdraw").await, None);
+
+    // Each change is audited with the alias it replaced
+    let entries: Vec<Value> = client
+        .get(format!("{}/admin/audit?action=proxy_route.save", backend))
+        .bearer_auth("admin-secret")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[1]["target"], "/legacy/withdraw");
+    assert_eq!(entries[1]["details"]["previous"], Value::Null);
+    assert_eq!(entries[1]["details"]["new"]["upstream_path"], "/withdraw");
+}
+
+#[tokio::test]
+async fn test_feature_flags_gate_features_without_restart() {
+    use ram_backend::flags::FeatureFlags;
+
+    let Some(db) = test_database().await else {
+        eprintln!("TEST_DATABASE_URL not set, skipping feature flag test");
+        return;
+    };
+    let _db_lock = TEST_DB_LOCK.lock().await;
+    sqlx::query("TRUNCATE admin_audit").execute(&db).await.unwrap();
+
+    let feature_flags = Arc::new(FeatureFlags::load(&db, "production").await.unwrap());
+    let backend = spawn_backend_with_state(AppState {
+        admin_token: Some("admin-secret".to_string()),
+        feature_flags: feature_flags.clone(),
+        ..base_state(db.clone())
+    })
+    .await;
+    let client = reqwest::Client::new();
+    let explorer = || async { client.get(format!("{}/v1/api/explorer/wallets", backend)).send().await.unwrap().status() };
+    let flags_url = format!("{}/admin/flags", backend);
+    let save = |flag: Value| client.put(&flags_url).bearer_auth("admin-secret").json(&flag).send();
+
+    // The seeded flags keep their features on
+    assert!(feature_flags.is_enabled("gray_zone_retry", "alice", false));
+    assert_eq!(explorer().await, 200);
+
+    assert_eq!(save(json!({ "name": "Public Explorer", "enabled": false })).await.unwrap().status(), 400);
+    assert_eq!(save(json!({ "name": "public_explorer", "enabled": true, "rollout_percent": 101 })).await.unwrap().status(), 400);
+    assert_eq!(client.put(&flags_url).json(&json!({ "name": "public_explorer", "enabled": false })).send().await.unwrap().status(), 401);
+
+    // A change is in effect at once, and flags are served for the enclave
+    let resp = save(json!({ "name": "public_explorer", "enabled": false })).await.unwrap();
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.json::<Value>().await.unwrap()["rollout_percent"], 100);
+    assert_eq!(explorer().await, 404);
+    let served: Value = client.get(format!("{}/v1/api/flags", backend)).send().await.unwrap().json().await.unwrap();
+    let served = served["flags"].as_array().unwrap();
+    assert!(served.iter().any(|flag| flag["name"] == "public_explorer" && flag["enabled"] == false));
+    assert!(served.iter().any(|flag| flag["name"] == "hume_stream"));
+
+    // Flags for another environment, or no subject in the rollout, leave it off
+    save(json!({ "name": "public_explorer", "enabled": true, "environments": ["staging"] })).await.unwrap();
+    assert_eq!(explorer().await, 404);
+    save(json!({ "name": "public_explorer", "enabled": true, "rollout_percent": 0 })).await.unwrap();
+    assert_eq!(explorer().await, 404);
+
+    // Without its flag a feature is back to its default
+    let delete = |name: &str| client.delete(format!("{}?name={}", flags_url, name)).bearer_auth("admin-secret").send();
+    assert_eq!(delete("public_explorer").await.unwrap().status(), 200);
+    assert_eq!(delete("public_explorer").await.unwrap().status(), 404);
+    assert_eq!(explorer().await, 200);
+
+    let listed: Vec<Value> = client.get(&flags_url).bearer_auth("admin-secret").send().await.unwrap().json().await.unwrap();
+    assert!(!listed.iter().any(|flag| flag["name"] == "public_explorer"));
+
+    // Each change is audited with the flag it replaced
+    let entries: Vec<Value> = client
+        .get(format!("{}/admin/audit?action=feature_flag.save", backend))
+        .bearer_auth("admin-secret")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(entries.len(), 3);
+    assert_eq!(entries[2]["details"]["previous"]["enabled"], true);
+    assert_eq!(entries[2]["details"]["new"]["enabled"], false);
+
+    // Restore the seeded flag for the other tests
+    let seeded = json!({
+        "name": "public_explorer",
+        "description": "Backend: /api/explorer aggregates (per client address)",
+        "enabled": true,
+    });
+    assert_eq!(save(seeded).await.unwrap().status(), 200);
+}
+
+#[tokio::test]
+async fn test_transfer_preview_reports_policy_checks() {
+    let Some(db) = test_database().await else {
+        eprintln!("TEST_DATABASE_URL not set, skipping transfer preview test");
+        return;
+    };
+    let _db_lock = TEST_DB_LOCK.lock().await;
+    sqlx::query("TRUNCATE ram_events, ram_events_archive, linked_addresses").execute(&db).await.unwrap();
+
+    let nautilus = start_mock_nautilus().await;
+    let backend = spawn_backend_with_state(AppState {
+        enclaves: Arc::new(EnclaveRouter::single(nautilus.uri())),
+        travel_rule: TravelRuleConfig::parse("0x2::sui::SUI=1000").unwrap(),
+        ..base_state(db.clone())
+    })
+    .await;
+
+    let now = chrono::Utc::now();
+    let event = |handle: &str, event_type: &str, amount: Option<i64>, raw_json: Option<Value>, digest: &str| RamEvent {
+        handle: Some(handle.to_string()),
+        event_type: event_type.to_string(),
+        amount,
+        coin_type: amount.map(|_| SUI_TYPE.to_string()),
+        result_code: None,
+        from_handle: (event_type == "Transferred").then(|| handle.to_string()),
+        to_handle: (event_type == "Transferred").then(|| "bob".to_string()),
+        memo: None,
+        payload_version: None,
+        owner: None,
+        wallet_id: (event_type == "WalletCreated").then(|| format!("0x{:0>64}", hex::encode(handle))),
+        tx_digest: digest.to_string(),
+        timestamp: now,
+        raw_json,
+        gas_used: None,
+        sender: None,
+        sender_label: None,
+        from_display_name: None,
+        to_display_name: None,
+        refund_of: None,
+        refunded_in: Vec::new(),
+    };
+    for event in [
+        event("alice", "WalletCreated", None, None, "PreviewAliceCreated"),
+        event("bob", "WalletCreated", None, None, "PreviewBobCreated"),
+        event("alice", "DailyLimitSet", Some(100), Some(json!({ "daily_limit": "100" })), "PreviewAliceLimit"),
+        event("alice", "Transferred", Some(60), None, "PreviewAliceSpend"),
+    ] {
+        Database::insert_event(&db, &event).await.unwrap();
+    }
+
+    let now_ms = now.timestamp_millis();
+    let alice = SigningKey::from_bytes(&[6u8; 32]);
+    let alice_address = format!("0x{}", hex::encode(ed25519_address(alice.verifying_key().as_bytes())));
+    Database::upsert_linked_address(&db, "alice", &alice_address, "alice", now_ms).await.unwrap();
+    let client = reqwest::Client::new();
+    let preview = |payload: Value| {
+        let body = payload.to_string();
+        let request = client.post(format!("{}/api/transfer/preview", backend));
+        sign_wallet_request(request, &alice, "transfers", "read", "alice", body.as_bytes(), now_ms).send()
+    };
+    let transfer = |extra: Value| {
+        let mut payload = json!({ "from_handle": "alice", "to_handle": "bob", "amount": 50, "coin_type": SUI_TYPE });
+        payload.as_object_mut().unwrap().extend(extra.as_object().unwrap().clone());
+        payload
+    };
+    let outcome = |preview: &Value, check: &str| {
+        preview["checks"].as_array().unwrap().iter().find(|c| c["check"] == check).unwrap()["outcome"].clone()
+    };
+
+    // Within the limit, but with what was spent today it may not be: a warning only
+    let result: Value = preview(transfer(json!({}))).await.unwrap().json().await.unwrap();
+    assert_eq!(result["allowed"], true);
+    assert_eq!(outcome(&result, "recipient"), "pass");
+    assert_eq!(outcome(&result, "daily_limit"), "warn");
+    assert_eq!(outcome(&result, "balance"), "unknown");
+    assert!(result["estimated_gas"]["budget"].is_null());
+    assert_eq!(result["bio_auth_required"], true);
+
+    // With the built transaction, gas is budgeted from its dry run
+    let rpc = start_mock_sponsor_rpc("unused").await;
+    let with_rpc = spawn_backend_with_state(AppState {
+        enclaves: Arc::new(EnclaveRouter::single(nautilus.uri())),
+        sui_rpc: Arc::new(SuiRpcClient::new(vec![rpc.uri()])),
+        ..base_state(db.clone())
+    })
+    .await;
+    let body = transfer(json!({ "tx_bytes": STANDARD.encode([7u8; 300]) })).to_string();
+    let request = client.post(format!("{}/api/transfer/preview", with_rpc));
+    let resp = sign_wallet_request(request, &alice, "transfers", "read", "alice", body.as_bytes(), now_ms).send().await.unwrap();
+    let result: Value = resp.json().await.unwrap();
+    assert_eq!(result["estimated_gas"]["budget"], 3_900_000);
+    assert_eq!(result["estimated_gas"]["reference_gas_price"], 750);
+    assert_eq!(preview(transfer(json!({ "tx_bytes": "not base64!" }))).await.unwrap().status(), 400);
+
+    // Over the limit, over the travel-rule threshold, to nobody: blocked
+    let result: Value = preview(transfer(json!({ "amount": 1000 }))).await.unwrap().json().await.unwrap();
+    assert_eq!(result["allowed"], false);
+    assert_eq!(outcome(&result, "daily_limit"), "block");
+    assert_eq!(outcome(&result, "travel_rule"), "block");
+    let result: Value = preview(transfer(json!({ "to_handle": "nobody" }))).await.unwrap().json().await.unwrap();
+    assert_eq!(outcome(&result, "recipient"), "block");
+
+    // A locked recipient can't receive either
+    let locked_until = (now_ms + 3_600_000).to_string();
+    let lock = event("bob", "WalletLocked", None, Some(json!({ "locked_until_ms": locked_until })), "PreviewBobLocked");
+    Database::insert_event(&db, &lock).await.unwrap();
+    let result: Value = preview(transfer(json!({}))).await.unwrap().json().await.unwrap();
+    assert_eq!(result["allowed"], false);
+    assert_eq!(outcome(&result, "wallet_state"), "block");
+
+    // Nothing was forwarded to the enclave, and only the sender may preview
+    assert!(nautilus.received_requests().await.unwrap().is_empty());
+    assert_eq!(preview(transfer(json!({ "from_handle": "bob" }))).await.unwrap().status(), 401);
+    assert_eq!(preview(json!({ "to_handle": "bob" })).await.unwrap().status(), 400);
+}
+
+#[tokio::test]
+async fn test_reserved_handles_are_only_created_by_their_reserver() {
+    let Some(db) = test_database().await else {
+        eprintln!("TEST_DATABASE_URL not set, skipping reservation test");
+        return;
+    };
+    let _db_lock = TEST_DB_LOCK.lock().await;
+    sqlx::query("TRUNCATE ram_events, handle_reservations").execute(&db).await.unwrap();
+
+    let nautilus = start_mock_nautilus().await;
+    let backend = spawn_backend(db.clone(), nautilus.uri()).await;
+    let client = reqwest::Client::new();
+    let reserve = |body: Value| client.post(format!("{}/v1/api/handles/reserve", backend)).json(&body).send();
+    let create_wallet = |payload: Value| post(&client, format!("{}/v1/create_wallet", backend), payload);
+
+    // The first to reserve a handle holds it, in any spelling
+    let resp = reserve(json!({ "handle": "Alice" })).await.unwrap();
+    assert_eq!(resp.status(), 200);
+    let reservation: Value = resp.json().await.unwrap();
+    assert_eq!(reservation["handle"], "alice");
+    let token = reservation["reservation_token"].as_str().unwrap().to_string();
+    assert!(reservation["expires_at_ms"].as_i64().unwrap() > chrono::Utc::now().timestamp_millis());
+    assert_eq!(reserve(json!({ "handle": "alice" })).await.unwrap().status(), 409);
+    assert_eq!(reserve(json!({ "handle": "ALICE", "reservation_token": "rsv_guess" })).await.unwrap().status(), 409);
+    assert_eq!(reserve(json!({ "handle": " " })).await.unwrap().status(), 400);
+
+    // ... and can extend it
+    let renewed: Value = reserve(json!({ "handle": "alice", "reservation_token": token })).await.unwrap().json().await.unwrap();
+    assert_eq!(renewed["reservation_token"], token.as_str());
+
+    // Creating the handle takes the reservation's token, which the enclave never sees
+    assert_eq!(create_wallet(json!({ "handle": "ALICE" })).await.status(), 409);
+    assert_eq!(create_wallet(json!({ "handle": "alice", "reservation_token": "rsv_guess" })).await.status(), 409);
+    assert_eq!(create_wallet(json!({ "handle": "alice", "reservation_token": token })).await.status(), 200);
+    let received = nautilus.received_requests().await.unwrap();
+    assert_eq!(received.len(), 1);
+    let forwarded: Value = serde_json::from_slice(&received[0].body).unwrap();
+    assert_eq!(forwarded, json!({ "payload": { "handle": "alice" } }));
+    // Handles nobody reserved need no token
+    assert_eq!(create_wallet(json!({ "handle": "carol" })).await.status(), 200);
+
+    // An expired reservation is anyone's to take over
+    sqlx::query("UPDATE handle_reservations SET expires_at_ms = 0").execute(&db).await.unwrap();
+    let taken: Value = reserve(json!({ "handle": "alice" })).await.unwrap().json().await.unwrap();
+    assert_ne!(taken["reservation_token"], token.as_str());
+    assert_eq!(create_wallet(json!({ "handle": "alice", "reservation_token": token })).await.status(), 409);
+
+    // Handles that already have a wallet can't be reserved
+    Database::insert_event(
+        &db,
+        &RamEvent {
+            handle: Some("bob".to_string()),
+            event_type: "WalletCreated".to_string(),
+            amount: None,
+            coin_type: None,
+            result_code: None,
+            from_handle: None,
+            to_handle: None,
+            memo: None,
+            payload_version: None,
+            owner: None,
+            wallet_id: Some("0x2222222222222222222222222222222222222222222222222222222222222222".to_string()),
+            tx_digest: "8xQmWalletCreatedBob".to_string(),
+            timestamp: chrono::Utc::now(),
+            raw_json: None,
+            gas_used: None,
+            sender: None,
+            sender_label: None,
+            from_display_name: None,
+            to_display_name: None,
+            refund_of: None,
+            refunded_in: Vec::new(),
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(reserve(json!({ "handle": "bob" })).await.unwrap().status(), 409);
+
+    // Other tests create these handles
+    sqlx::query("TRUNCATE handle_reservations").execute(&db).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_indexed_events_are_exported_at_least_once() {
+    use ram_backend::config::ExportSink;
+    use ram_backend::exports::{batch_key, export_batch};
+
+    let Some(db) = test_database().await else {
+        eprintln!("TEST_DATABASE_URL not set, skipping event export test");
+        return;
+    };
+    let _db_lock = TEST_DB_LOCK.lock().await;
+    sqlx::query("TRUNCATE ram_events, ram_events_archive, event_export_cursors").execute(&db).await.unwrap();
+
+    let event = |event_type: &str, amount: Option<i64>, digest: &str| RamEvent {
+        handle: Some("alice".to_string()),
+        event_type: event_type.to_string(),
+        amount,
+        coin_type: amount.map(|_| SUI_TYPE.to_string()),
+        result_code: None,
+        from_handle: (event_type == "Transferred").then(|| "alice".to_string()),
+        to_handle: (event_type == "Transferred").then(|| "bob".to_string()),
+        memo: None,
+        payload_version: None,
+        owner: None,
+        wallet_id: None,
+        tx_digest: digest.to_string(),
+        timestamp: chrono::Utc::now(),
+        raw_json: None,
+        gas_used: None,
+        sender: None,
+        sender_label: None,
+        from_display_name: None,
+        to_display_name: None,
+        refund_of: None,
+        refunded_in: Vec::new(),
+    };
+    for event in [
+        event("WalletCreated", None, "ExportCreated"),
+        event("Deposited", Some(1_000), "ExportDeposit"),
+        event("Transferred", Some(400), "ExportTransfer"),
+    ] {
+        Database::insert_event(&db, &event).await.unwrap();
+    }
+
+    let sinks = wiremock::MockServer::start().await;
+    wiremock::Mock::given(wiremock::matchers::path("/events"))
+        .respond_with(wiremock::ResponseTemplate::new(503))
+        .up_to_n_times(1)
+        .mount(&sinks)
+        .await;
+    wiremock::Mock::given(wiremock::matchers::path("/events"))
+        .respond_with(wiremock::ResponseTemplate::new(200))
+        .mount(&sinks)
+        .await;
+    wiremock::Mock::given(wiremock::matchers::path("/topics/ram-events"))
+        .respond_with(
+            wiremock::ResponseTemplate::new(200).set_body_json(json!({ "offsets": [{ "partition": 0, "offset": 0 }] })),
+        )
+        .mount(&sinks)
+        .await;
+    wiremock::Mock::given(wiremock::matchers::method("PUT"))
+        .respond_with(wiremock::ResponseTemplate::new(200))
+        .mount(&sinks)
+        .await;
+    let client = reqwest::Client::new();
+    let bodies = |path: &'static str| {
+        let sinks = &sinks;
+        async move {
+            sinks.received_requests().await.unwrap().into_iter().filter(|r| r.url.path().starts_with(path)).collect::<Vec<_>>()
+        }
+    };
+
+    // A batch the sink refused is sent again; the cursor only moves once it's accepted
+    let http = ExportSink::Http { url: format!("{}/events", sinks.uri()) };
+    assert!(export_batch(&db, &client, &http, 2).await.is_err());
+    assert_eq!(export_batch(&db, &client, &http, 2).await.unwrap(), 2);
+    assert_eq!(export_batch(&db, &client, &http, 2).await.unwrap(), 1);
+    assert_eq!(export_batch(&db, &client, &http, 2).await.unwrap(), 0);
+    let batches: Vec<Value> = bodies("/events").await.iter().map(|r| serde_json::from_slice(&r.body).unwrap()).collect();
+    assert_eq!(batches.len(), 3);
+    assert_eq!(batches[0], batches[1]);
+    let ids: Vec<i64> = batches[1..]
+        .iter()
+        .flat_map(|batch| batch["events"].as_array().unwrap().iter().map(|e| e["id"].as_i64().unwrap()))
+        .collect();
+    assert_eq!(ids.len(), 3);
+    assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+    assert_eq!(batches[2]["events"][0]["event_type"], "Transferred");
+    assert_eq!(batches[2]["events"][0]["to_handle"], "bob");
+
+    // Each sink keeps its own cursor: Kafka records are keyed by handle
+    let kafka = ExportSink::Kafka { rest_url: sinks.uri(), topic: "ram-events".to_string() };
+    assert_eq!(export_batch(&db, &client, &kafka, 10).await.unwrap(), 3);
+    let produced = &bodies("/topics/ram-events").await[0];
+    assert_eq!(produced.headers["content-type"], "application/vnd.kafka.json.v2+json");
+    let records: Value = serde_json::from_slice(&produced.body).unwrap();
+    assert_eq!(records["records"].as_array().unwrap().len(), 3);
+    assert_eq!(records["records"][0]["key"], "alice");
+    assert_eq!(records["records"][0]["value"]["event_type"], "WalletCreated");
+
+    // S3 batches are signed newline-delimited JSON objects named after their ids
+    let s3 = ExportSink::S3 {
+        bucket: "analytics".to_string(),
+        region: "eu-west-1".to_string(),
+        endpoint: sinks.uri(),
+        prefix: "ram/".to_string(),
+        access_key_id: "AKIDEXAMPLE".to_string(),
+        secret_access_key: "secret".to_string(),
+    };
+    assert_eq!(export_batch(&db, &client, &s3, 10).await.unwrap(), 3);
+    let object = &bodies("/analytics/").await[0];
+    assert_eq!(object.url.path(), format!("/analytics/ram/{}", batch_key(ids[0], ids[2])));
+    assert!(object.headers["authorization"]
+        .to_str()
+        .unwrap()
+        .starts_with("AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/"));
+    assert_eq!(String::from_utf8_lossy(&object.body).lines().count(), 3);
+    assert_eq!(export_batch(&db, &client, &s3, 10).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn test_public_explorer_withholds_small_groups() {
+    use ram_backend::config::ExplorerConfig;
+    use ram_backend::explorer::Explorer;
+
+    let Some(db) = test_database().await else {
+        eprintln!("TEST_DATABASE_URL not set, skipping explorer test");
+        return;
+    };
+    let _db_lock = TEST_DB_LOCK.lock().await;
+    sqlx::query("TRUNCATE ram_events, ram_events_archive").execute(&db).await.unwrap();
+
+    let now = chrono::Utc::now();
+    let event = |handle: &str, event_type: &str, to_handle: Option<&str>, coin_type: &str, digest: String| RamEvent {
+        handle: Some(handle.to_string()),
+        event_type: event_type.to_string(),
+        amount: to_handle.map(|_| 1_000),
+        coin_type: to_handle.map(|_| coin_type.to_string()),
+        result_code: None,
+        from_handle: to_handle.map(|_| handle.to_string()),
+        to_handle: to_handle.map(str::to_string),
+        memo: None,
+        payload_version: None,
+        owner: None,
+        wallet_id: None,
+        tx_digest: digest,
+        timestamp: now,
+        raw_json: None,
+        gas_used: None,
+        sender: None,
+        sender_label: None,
+        from_display_name: None,
+        to_display_name: None,
+        refund_of: None,
+        refunded_in: Vec::new(),
+    };
+    // Five wallets created today, each paying the next in SUI; one USDC transfer
+    let wallets = ["alice", "bob", "carol", "dave", "erin"];
+    for (i, handle) in wallets.iter().enumerate() {
+        Database::insert_event(&db, &event(handle, "WalletCreated", None, "", format!("Created{}", i))).await.unwrap();
+        let to = wallets[(i + 1) % wallets.len()];
+        Database::insert_event(&db, &event(handle, "Transferred", Some(to), SUI_TYPE, format!("Paid{}", i))).await.unwrap();
+    }
+    Database::insert_event(&db, &event("alice", "Transferred", Some("bob"), "0xa11ce::usdc::USDC", "Usdc".into()))
+        .await
+        .unwrap();
+
+    let backend = spawn_backend_with_state(AppState {
+        explorer: Arc::new(Explorer::new(ExplorerConfig {
+            k_anonymity: 5,
+            rate_limit_per_minute: 3,
+            trusted_proxies: 0,
+        })),
+        ..base_state(db.clone())
+    })
+    .await;
+    let client = reqwest::Client::new();
+    let get = |path: &str| client.get(format!("{}/v1/api/explorer/{}", backend, path)).send();
+
+    // The SUI transfers had five senders and recipients; the USDC one is withheld
+    let transfers: Value = get("transfers?days=7").await.unwrap().json().await.unwrap();
+    assert_eq!(transfers["k_anonymity"], 5);
+    let volumes = transfers["volumes"].as_array().unwrap();
+    assert_eq!(volumes.len(), 1);
+    assert_eq!(volumes[0]["transfers"], 5);
+    assert_eq!(volumes[0]["volume"], "5000");
+    assert!(!transfers.to_string().contains("alice"));
+
+    // Every day of the window is listed; only today reached k
+    let created: Value = get("wallets?days=7").await.unwrap().json().await.unwrap();
+    assert_eq!(created["total_wallets"], 5);
+    let daily = created["daily"].as_array().unwrap();
+    assert_eq!(daily.len(), 7);
+    assert_eq!(daily[6]["created"], 5);
+    assert!(daily[..6].iter().all(|day| day["created"].is_null()));
+
+    // Out-of-range windows are refused, and every request counts towards the limit
+    assert_eq!(get("wallets?days=91").await.unwrap().status(), 400);
+    let limited = get("transfers").await.unwrap();
+    assert_eq!(limited.status(), 429);
+    assert!(limited.headers().contains_key("retry-after"));
+}
+
+#[tokio::test]
+async fn test_analytics_store_serves_the_explorer_until_it_drifts() {
+    use ram_backend::analytics::{run_analytics_store, AnalyticsStore};
+    use ram_backend::config::{AnalyticsStoreConfig, ExplorerConfig};
+    use ram_backend::explorer::Explorer;
+
+    let Some(db) = test_database().await else {
+        eprintln!("TEST_DATABASE_URL not set, skipping analytics store test");
+        return;
+    };
+    let _db_lock = TEST_DB_LOCK.lock().await;
+    sqlx::query("TRUNCATE ram_events, ram_events_archive").execute(&db).await.unwrap();
+
+    let transfer = |from: &str, to: &str| RamEvent {
+        handle: Some(from.to_string()),
+        event_type: "Transferred".to_string(),
+        amount: Some(10),
+        coin_type: Some(SUI_TYPE.to_string()),
+        result_code: None,
+        from_handle: Some(from.to_string()),
+        to_handle: Some(to.to_string()),
+        memo: None,
+        payload_version: None,
+        owner: None,
+        wallet_id: None,
+        tx_digest: format!("0x{}{}", from, to),
+        timestamp: chrono::Utc::now(),
+        raw_json: None,
+        gas_used: None,
+        sender: None,
+        sender_label: None,
+        from_display_name: None,
+        to_display_name: None,
+        refund_of: None,
+        refunded_in: Vec::new(),
+    };
+    // Stored before the store existed: it catches up from Postgres
+    Database::insert_event(&db, &transfer("alice", "bob")).await.unwrap();
+    Database::insert_event(&db, &transfer("carol", "dave")).await.unwrap();
+
+    let dir = std::env::temp_dir().join(format!("ram-analytics-e2e-{}", uuid::Uuid::new_v4()));
+    let config = AnalyticsStoreConfig {
+        dir: Some(dir.clone()),
+        flush_interval: std::time::Duration::from_millis(100),
+        ..Default::default()
+    };
+    let store = Arc::new(AnalyticsStore::open(config).unwrap().unwrap());
+    assert!(!store.is_serving());
+    let task = tokio::spawn(run_analytics_store(db.clone(), store.clone()));
+    async fn wait_for(store: &AnalyticsStore, done: impl Fn(&AnalyticsStore) -> bool) {
+        for _ in 0..100 {
+            if done(store) {
+                return;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        }
+        panic!("analytics store didn't get there: {:?}", store.status());
+    }
+    wait_for(&store, AnalyticsStore::is_serving).await;
+    assert_eq!(store.status().rows, 2);
+
+    // Dual write: what the indexer hands over lands with the next flush
+    let handed_over = transfer("erin", "frank");
+    let id = Database::insert_event(&db, &handed_over).await.unwrap();
+    store.record(id, &handed_over);
+    wait_for(&store, |store| store.last_id() == id).await;
+
+    let backend = spawn_backend_with_state(AppState {
+        admin_token: Some("admin-secret".to_string()),
+        explorer: Arc::new(Explorer::new(ExplorerConfig {
+            k_anonymity: 2,
+            rate_limit_per_minute: 100,
+            trusted_proxies: 0,
+        })),
+        analytics: Some(store.clone()),
+        ..base_state(db.clone())
+    })
+    .await;
+    let client = reqwest::Client::new();
+    let transfers = || async {
+        let body: Value = client
+            .get(format!("{}/v1/api/explorer/transfers?days=1", backend))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        body["volumes"][0]["transfers"].as_i64().unwrap()
+    };
+    let check = || async {
+        let resp = client.get(format!("{}/admin/analytics_consistency", backend)).bearer_auth("admin-secret").send().await.unwrap();
+        assert_eq!(resp.status(), 200);
+        resp.json::<Value>().await.unwrap()
+    };
+
+    // Another indexer stored one the store never heard of: aggregates still
+    // come from the store, until a later event puts it below the checked id
+    Database::insert_event(&db, &transfer("grace", "heidi")).await.unwrap();
+    assert_eq!(transfers().await, 3);
+    assert_eq!(check().await["serving"], true);
+
+    let handed_over = transfer("ivan", "judy");
+    let id = Database::insert_event(&db, &handed_over).await.unwrap();
+    store.record(id, &handed_over);
+    wait_for(&store, |store| store.last_id() == id).await;
+    let status = check().await;
+    assert_eq!(status["serving"], false);
+    let mismatch = &status["last_check"]["mismatches"][0];
+    assert_eq!((mismatch["event_type"].as_str(), mismatch["primary"].as_i64(), mismatch["store"].as_i64()), (Some("Transferred"), Some(5), Some(4)));
+    // ...and the explorer is back on Postgres
+    assert_eq!(transfers().await, 5);
+
+    task.abort();
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_transfer_receipts_are_countersigned() {
+    use ram_backend::receipts::{receipt_message, record_receipt, ReceiptSigner};
+
+    let Some(db) = test_database().await else {
+        eprintln!("TEST_DATABASE_URL not set, skipping receipt test");
+        return;
+    };
+    let _db_lock = TEST_DB_LOCK.lock().await;
+    sqlx::query("TRUNCATE transfer_receipts").execute(&db).await.unwrap();
+
+    let state = |receipts: Option<ReceiptSigner>| AppState {
+        receipts,
+        ..base_state(db.clone())
+    };
+    let key = SigningKey::from_bytes(&[11u8; 32]);
+    let backend = spawn_backend_with_state(state(Some(ReceiptSigner::from_hex(&hex::encode([11u8; 32])).unwrap()))).await;
+    let unsigned = spawn_backend_with_state(state(None)).await;
+    let client = reqwest::Client::new();
+
+    let payment = RamEvent {
+        handle: Some("alice".to_string()),
+        event_type: "Transferred".to_string(),
+        amount: Some(1_500_000_000),
+        coin_type: Some(SUI_TYPE.to_string()),
+        result_code: None,
+        from_handle: Some("alice".to_string()),
+        to_handle: Some("coffee_shop".to_string()),
+        memo: Some("order 17".to_string()),
+        payload_version: Some(2),
+        owner: None,
+        wallet_id: None,
+        tx_digest: "5tReceiptPayment".to_string(),
+        timestamp: chrono::DateTime::from_timestamp_millis(1_700_000_000_123).unwrap(),
+        raw_json: None,
+        gas_used: None,
+        sender: None,
+        sender_label: None,
+        from_display_name: None,
+        to_display_name: None,
+        refund_of: None,
+        refunded_in: Vec::new(),
+    };
+    let now_ms = chrono::Utc::now().timestamp_millis();
+    assert!(record_receipt(&db, &payment, now_ms).await.unwrap());
+    assert!(!record_receipt(&db, &payment, now_ms).await.unwrap());
+    let frozen = RamEvent { event_type: "WalletFrozen".to_string(), tx_digest: "5tReceiptFrozen".to_string(), ..payment.clone() };
+    assert!(!record_receipt(&db, &frozen, now_ms).await.unwrap());
+
+    // The JSON receipt verifies against the published key
+    let receipt: Value = client
+        .get(format!("{}/v1/api/receipts/5tReceiptPayment", backend))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(receipt["payer_handle"], "alice");
+    assert_eq!(receipt["payee_handle"], "coffee_shop");
+    assert_eq!(receipt["amount"], 1_500_000_000i64);
+    assert_eq!(receipt["timestamp_ms"], 1_700_000_000_123i64);
+    let published: Value =
+        client.get(format!("{}/api/receipts/public_key", backend)).send().await.unwrap().json().await.unwrap();
+    assert_eq!(published["public_key"], hex::encode(key.verifying_key().as_bytes()));
+    assert_eq!(receipt["signer_public_key"], published["public_key"]);
+
+    let stored = Database::get_transfer_receipt(&db, "5tReceiptPayment").await.unwrap().unwrap();
+    let signature = Signature::from_slice(&hex::decode(receipt["signature"].as_str().unwrap()).unwrap()).unwrap();
+    assert!(key.verifying_key().verify(receipt_message(&stored).as_bytes(), &signature).is_ok());
+
+    // The PDF carries the same receipt
+    let pdf = client.get(format!("{}/api/receipts/5tReceiptPayment?format=pdf", backend)).send().await.unwrap();
+    assert_eq!(pdf.status(), 200);
+    assert_eq!(pdf.headers()["content-type"], "application/pdf");
+    assert!(pdf.headers()["content-disposition"].to_str().unwrap().contains("receipt-5tReceiptPayment.pdf"));
+    let pdf = pdf.bytes().await.unwrap();
+    assert!(pdf.starts_with(b"%PDF-1.4"));
+    assert!(String::from_utf8_lossy(&pdf).contains("(Payee: coffee_shop) Tj"));
+
+    let status = |url: String| {
+        let client = client.clone();
+        async move { client.get(url).send().await.unwrap().status() }
+    };
+    assert_eq!(status(format!("{}/api/receipts/5tReceiptFrozen", backend)).await, 404);
+    assert_eq!(status(format!("{}/api/receipts/5tReceiptPayment?format=xml", backend)).await, 400);
+    assert_eq!(status(format!("{}/api/receipts/5tReceiptPayment", unsigned)).await, 503);
+    assert_eq!(status(format!("{}/api/receipts/public_key", unsigned)).await, 503);
+}
+
+#[tokio::test]
+async fn test_price_alerts_notify_once_per_crossing() {
+    use ram_backend::config::PriceConfig;
+    use ram_backend::price_alerts::check_price_alerts;
+    use ram_backend::prices::PriceService;
+    use wiremock::matchers::{body_partial_json, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    let Some(db) = test_database().await else {
+        eprintln!("TEST_DATABASE_URL not set, skipping price alerts test");
+        return;
+    };
+    let _db_lock = TEST_DB_LOCK.lock().await;
+    sqlx::query("TRUNCATE ram_events, ram_events_archive, linked_addresses, failed_events, notifications, price_alerts")
+        .execute(&db)
+        .await
+        .unwrap();
+
+    let events = start_mock_sui_rpc("sui_events.json").await;
+    Indexer::new(events.uri(), "0xram".to_string(), db.clone())
+        .fetch_and_process_events(None)
+        .await
+        .unwrap();
+    let phone = SigningKey::from_bytes(&[3u8; 32]);
+    let phone_address = format!("0x{}", hex::encode(ed25519_address(phone.verifying_key().as_bytes())));
+    Database::upsert_linked_address(&db, "alice", &phone_address, "phone", 1_707_523_300_000).await.unwrap();
+
+    // Alice holds 5 SUI
+    let wallet_id = "0x1111111111111111111111111111111111111111111111111111111111111111";
+    let rpc = start_mock_balance_rpc(wallet_id, &[(SUI_TYPE, 5_000_000_000)]).await;
+    Mock::given(body_partial_json(json!({ "method": "suix_getCoinMetadata" })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "jsonrpc": "2.0", "id": 1, "result": { "decimals": 9, "symbol": "SUI" },
+        })))
+        .mount(&rpc)
+        .await;
+    let feed = MockServer::start().await;
+    let feed = &feed;
+    let quote = |usd: f64| async move {
+        feed.reset().await;
+        Mock::given(method("GET"))
+            .and(path("/simple/price"))
+            .and(query_param("ids", "sui"))
+            .and(query_param("vs_currencies", "usd"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "sui": { "usd": usd } })))
+            .mount(feed)
+            .await;
+    };
+    quote(3.0).await;
+
+    let sui_rpc = Arc::new(SuiRpcClient::new(vec![rpc.uri()]));
+    let config = PriceConfig { feed_url: Some(feed.uri()), cache_ttl: std::time::Duration::ZERO, ..Default::default() };
+    let prices = Arc::new(PriceService::new(config, sui_rpc.clone()).unwrap().unwrap());
+    let backend = spawn_backend_with_state(AppState {
+        sui_rpc: sui_rpc.clone(),
+        prices: Some(prices.clone()),
+        ..base_state(db.clone())
+    })
+    .await;
+    let client = reqwest::Client::new();
+    let alerts_url = format!("{}/api/price_alerts", backend);
+    let now_ms = chrono::Utc::now().timestamp_millis();
+    let signed = |request, action: &str, body: &Value| {
+        let body = serde_json::to_vec(body).unwrap();
+        sign_wallet_request(request, &phone, "price_alerts", action, "alice", &body, now_ms)
+    };
+    let list = || async {
+        let request = client.get(&alerts_url).query(&[("handle", "alice")]);
+        let alerts: Vec<Value> = sign_wallet_request(request, &phone, "price_alerts", "read", "alice", b"", now_ms)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        alerts
+    };
+
+    // "Tell me when my SUI is worth less than $10"
+    let create = json!({ "handle": "alice", "coin_type": "0x2::sui::SUI", "direction": "below", "threshold_usd": 10.0 });
+    assert_eq!(client.post(&alerts_url).json(&create).send().await.unwrap().status(), 401);
+    let sideways = json!({ "handle": "alice", "coin_type": "0x2::sui::SUI", "direction": "sideways", "threshold_usd": 10.0 });
+    assert_eq!(signed(client.post(&alerts_url), "create", &sideways).send().await.unwrap().status(), 400);
+    let unpriced = json!({ "handle": "alice", "coin_type": "0xc0ffee::usdc::USDC", "direction": "below", "threshold_usd": 10.0 });
+    assert_eq!(signed(client.post(&alerts_url), "create", &unpriced).send().await.unwrap().status(), 422);
+    let alert: Value = signed(client.post(&alerts_url), "create", &create).send().await.unwrap().json().await.unwrap();
+    assert_eq!(alert["coin_type"], "0x2::sui::SUI");
+    assert!(alert["triggered_at_ms"].is_null());
+    let alert_id = alert["id"].as_i64().unwrap();
+
+    // $15 holds the alert back; $7.50 fires it, once
+    assert_eq!(check_price_alerts(&db, &sui_rpc, &prices, now_ms).await.unwrap(), 0);
+    quote(1.5).await;
+    assert_eq!(check_price_alerts(&db, &sui_rpc, &prices, now_ms + 1).await.unwrap(), 1);
+    assert_eq!(check_price_alerts(&db, &sui_rpc, &prices, now_ms + 2).await.unwrap(), 0);
+    assert_eq!(list().await[0]["triggered_at_ms"], now_ms + 1);
+
+    let notifications: Vec<Value> = sign_wallet_request(
+        client.get(format!("{}/api/notifications", backend)).query(&[("handle", "alice")]),
+        &phone,
+        "notifications",
+        "read",
+        "alice",
+        b"",
+        now_ms,
+    )
+    .send()
+    .await
+    .unwrap()
+    .json()
+    .await
+    .unwrap();
+    assert_eq!(notifications.len(), 1);
+    assert_eq!(notifications[0]["kind"], "price_alert");
+    assert_eq!(notifications[0]["data"]["alert_id"], alert_id);
+    assert_eq!(notifications[0]["data"]["value_usd"], 7.5);
+    assert_eq!(notifications[0]["data"]["balance"], 5_000_000_000u64);
+
+    // Barely back at the threshold it stays quiet; clearly past it, it re-arms
+    quote(2.0).await;
+    check_price_alerts(&db, &sui_rpc, &prices, now_ms + 3).await.unwrap();
+    assert!(!list().await[0]["triggered_at_ms"].is_null());
+    quote(2.5).await;
+    check_price_alerts(&db, &sui_rpc, &prices, now_ms + 4).await.unwrap();
+    assert!(list().await[0]["triggered_at_ms"].is_null());
+    quote(1.0).await;
+    assert_eq!(check_price_alerts(&db, &sui_rpc, &prices, now_ms + 5).await.unwrap(), 1);
+
+    // Changing the condition re-arms it; deleting it stops the checks
+    let update = json!({ "handle": "alice", "id": alert_id, "coin_type": "0x2::sui::SUI", "direction": "above", "threshold_usd": 20.0 });
+    let updated: Value = signed(client.put(&alerts_url), "update", &update).send().await.unwrap().json().await.unwrap();
+    assert_eq!(updated["direction"], "above");
+    assert!(updated["triggered_at_ms"].is_null());
+    let missing = json!({ "handle": "alice", "id": alert_id + 1, "coin_type": "0x2::sui::SUI", "direction": "above", "threshold_usd": 20.0 });
+    assert_eq!(signed(client.put(&alerts_url), "update", &missing).send().await.unwrap().status(), 404);
+    let delete = json!({ "handle": "alice", "id": alert_id });
+    assert_eq!(signed(client.delete(&alerts_url), "delete", &delete).send().await.unwrap().status(), 204);
+    assert_eq!(signed(client.delete(&alerts_url), "delete", &delete).send().await.unwrap().status(), 404);
+    assert!(list().await.is_empty());
+}
@@ -0,0 +1,231 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! RAM CLI
+//!
+//! Wallet operations against a RAM backend, for testing and power users.
+//!
+//! ```bash
+//! cargo run -p ram-cli -- --backend-url http://localhost:4000 status
+//! cargo run -p ram-cli -- bio-auth alice --audio confirm.wav --amount 5
+//! ```
+//!
+//! Environment variables:
+//! - RAM_BACKEND_URL: Backend base URL (default: http://localhost:4000)
+//! - RAM_ENCLAVE_PUBLIC_KEY: Hex Ed25519 key to verify signed responses against (optional)
+//! - RAM_PAYLOAD_VERSION: Payload layout to request, 1, 2 or 3 (default: the enclave's choice)
+//! - RAM_PACKAGE_ID: RAM package on the target network, to verify layout v3 responses (optional)
+
+mod print;
+mod wav;
+
+use anyhow::{Context, Result};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use clap::{Parser, Subcommand};
+use ram_sdk::verify::{parse_package_id, parse_public_key};
+use ram_sdk::{BioAuthRequest, PayloadVersion, RamClient};
+use serde_json::json;
+use std::path::PathBuf;
+
+#[derive(Parser)]
+#[command(name = "ram-cli", version, about = "RAM voice-protected wallet operations")]
+struct Cli {
+    /// Backend base URL
+    #[arg(long, env = "RAM_BACKEND_URL", default_value = "http://localhost:4000", global = true)]
+    backend_url: String,
+
+    /// Enclave public key (hex); signed responses are verified against it
+    #[arg(long, env = "RAM_ENCLAVE_PUBLIC_KEY", global = true)]
+    enclave_key: Option<String>,
+
+    /// Payload layout to request (1, 2 or 3); must be one the contract verifies
+    #[arg(long, env = "RAM_PAYLOAD_VERSION", global = true, value_parser = clap::value_parser!(u8).range(1..=3))]
+    payload_version: Option<u8>,
+
+    /// RAM package on the target network; layout v3 responses are verified as signed for it
+    #[arg(long, env = "RAM_PACKAGE_ID", global = true)]
+    package_id: Option<String>,
+
+    /// Print raw JSON instead of formatted output
+    #[arg(long, global = true)]
+    json: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Get a signed create-wallet payload for a handle
+    CreateWallet { handle: String },
+
+    /// Get a signed payload linking a Sui address to a wallet
+    LinkAddress {
+        handle: String,
+        /// Sui address (0x...)
+        address: String,
+        #[arg(long, default_value = "")]
+        label: String,
+        /// Wallet signature over --message proving ownership of the address
+        #[arg(long, default_value = "")]
+        signature: String,
+        #[arg(long, default_value = "")]
+        message: String,
+    },
+
+    /// Voice bio-auth from a recorded WAV file
+    BioAuth {
+        handle: String,
+        /// Recording of the confirmation phrase (16 kHz mono 16-bit WAV works best)
+        #[arg(long)]
+        audio: PathBuf,
+        /// Amount in human units (e.g. 5 for 5 SUI)
+        #[arg(long)]
+        amount: f64,
+        /// Move coin type (e.g. 0x2::sui::SUI)
+        #[arg(long, default_value = "0x2::sui::SUI")]
+        coin: String,
+        /// Withdrawal destination; its last 4 characters must be spoken too
+        #[arg(long)]
+        destination: Option<String>,
+    },
+
+    /// Get a signed transfer between two wallets
+    Transfer {
+        from: String,
+        to: String,
+        /// Amount in human units (e.g. 5 for 5 SUI)
+        #[arg(long)]
+        amount: f64,
+        /// Move coin type (e.g. 0x2::sui::SUI)
+        #[arg(long, default_value = "0x2::sui::SUI")]
+        coin: String,
+        /// Payment reference carried on-chain (e.g. an invoice number)
+        #[arg(long)]
+        memo: Option<String>,
+    },
+
+    /// Indexed events for a wallet, newest first
+    Events {
+        handle: String,
+        #[arg(long, default_value_t = 20)]
+        limit: i64,
+        #[arg(long, default_value_t = 0)]
+        offset: i64,
+    },
+
+    /// Backend health, plus wallet stats and linked addresses for a handle
+    Status { handle: Option<String> },
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+
+    let mut client = RamClient::new(&cli.backend_url);
+    let verified = cli.enclave_key.is_some();
+    if let Some(key) = &cli.enclave_key {
+        client = client.with_enclave_key(parse_public_key(key)?);
+    }
+    if let Some(package_id) = &cli.package_id {
+        client = client.with_package(parse_package_id(package_id)?);
+    }
+    if let Some(version) = cli.payload_version {
+        client = client.with_payload_version(match version {
+            3 => PayloadVersion::V3,
+            2 => PayloadVersion::V2,
+            _ => PayloadVersion::V1,
+        });
+    }
+
+    match cli.command {
+        Command::CreateWallet { handle } => {
+            let signed = client.create_wallet(&handle).await?;
+            if cli.json {
+                return print_json(&signed);
+            }
+            print::signed("Create wallet", &signed, verified);
+        }
+        Command::LinkAddress { handle, address, label, signature, message } => {
+            let signed = client.link_address(&handle, &address, &signature, &message, &label).await?;
+            if cli.json {
+                return print_json(&signed);
+            }
+            print::signed("Link address", &signed, verified);
+        }
+        Command::BioAuth { handle, audio, amount, coin, destination } => {
+            let bytes = std::fs::read(&audio).with_context(|| format!("reading {}", audio.display()))?;
+
+            let mut phrase = format!("I confirm sending {} {}", amount, print::symbol(&coin));
+            if let Some(suffix) = destination.as_deref().and_then(|d| d.get(d.len().saturating_sub(4)..)) {
+                phrase.push_str(&format!(" to the address ending in {}", suffix));
+            }
+            eprintln!("Recording should say: \"{}\"", phrase);
+            for hint in wav::hints(wav::inspect(&bytes).as_ref()) {
+                eprintln!("  hint: {}", hint);
+            }
+
+            let response = client
+                .bio_auth(&BioAuthRequest {
+                    handle,
+                    audio_base64: STANDARD.encode(&bytes),
+                    expected_amount: print::to_raw(amount, &coin),
+                    coin_type: Some(coin),
+                    destination_hint: destination,
+                    locale: None,
+                    skip_analysis_cache: false,
+                })
+                .await?;
+            if cli.json {
+                return print_json(&response);
+            }
+            print::signed("Bio-auth (result is revealed on-chain by apply_bioauth)", &response.signed, verified);
+            println!("  {:<12} {}", "request_id", response.request_id);
+        }
+        Command::Transfer { from, to, amount, coin, memo } => {
+            let signed = client.transfer(&from, &to, print::to_raw(amount, &coin), &coin, memo.as_deref()).await?;
+            if cli.json {
+                return print_json(&signed);
+            }
+            print::signed("Transfer", &signed, verified);
+        }
+        Command::Events { handle, limit, offset } => {
+            let events = client.events(&handle, limit, offset).await?;
+            if cli.json {
+                return print_json(&events);
+            }
+            print::events(&events);
+        }
+        Command::Status { handle } => {
+            let health = client.health().await?;
+            let wallet = match &handle {
+                Some(handle) => Some((client.stats(handle).await?, client.linked_addresses(handle).await?)),
+                None => None,
+            };
+            if cli.json {
+                return print_json(&json!({ "health": health, "wallet": wallet }));
+            }
+
+            println!("Backend {} ({})", health.status, cli.backend_url);
+            println!("  {:<12} {}", "enclave", health.nautilus_server);
+            println!("  {:<12} {}", "database", health.database);
+            println!("  {:<12} {}", "sui rpc", health.sui_rpc);
+            println!("  {:<12} {}", "indexer", health.indexer);
+            if let Some((stats, addresses)) = wallet {
+                println!("Wallet {}", stats.handle);
+                println!("  {:<12} {}", "deposits", stats.total_deposits);
+                println!("  {:<12} {}", "withdrawals", stats.total_withdrawals);
+                println!("  {:<12} {} sent, {} received", "transfers", stats.total_transfers_sent, stats.total_transfers_received);
+                for linked in addresses {
+                    println!("  {:<12} {} ({})", "linked", linked.address, linked.label);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_json<T: serde::Serialize>(value: &T) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
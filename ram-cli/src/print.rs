@@ -0,0 +1,173 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! Human-readable output for signed payloads and backend data
+
+use ram_sdk::*;
+
+/// "0x2::sui::SUI" -> "SUI"
+pub fn symbol(coin: &str) -> String {
+    coin.rsplit("::").next().unwrap_or(coin).to_uppercase()
+}
+
+/// Decimal places for a coin (same table as the enclave and frontend)
+pub fn decimals(coin: &str) -> u32 {
+    match symbol(coin).as_str() {
+        "USDC" | "USDT" => 6,
+        _ => 9,
+    }
+}
+
+/// "5.5" SUI -> 5_500_000_000
+pub fn to_raw(amount: f64, coin: &str) -> u64 {
+    (amount * 10_f64.powi(decimals(coin) as i32)).round() as u64
+}
+
+/// 5_500_000_000 -> "5.5 SUI"
+pub fn human(raw: u64, coin: &str) -> String {
+    let human = raw as f64 / 10_f64.powi(decimals(coin) as i32);
+    format!("{} {}", human, symbol(coin))
+}
+
+fn text(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+fn address(bytes: &[u8; 32]) -> String {
+    format!("0x{}", hex::encode(bytes))
+}
+
+fn intent_name(intent: u8) -> &'static str {
+    match intent {
+        0 => "CREATE_WALLET",
+        1 => "LINK_ADDRESS",
+        2 => "TRANSFER",
+        3 => "BIOAUTH",
+        4 => "WITHDRAW",
+        5 => "UNLINK_ADDRESS",
+        _ => "UNKNOWN",
+    }
+}
+
+/// Payload fields as label/value rows
+pub trait Rows {
+    fn rows(&self) -> Vec<(&'static str, String)>;
+}
+
+impl Rows for CreateWalletPayload {
+    fn rows(&self) -> Vec<(&'static str, String)> {
+        vec![("handle", text(&self.handle))]
+    }
+}
+
+impl Rows for LinkAddressPayload {
+    fn rows(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("handle", text(&self.handle)),
+            ("address", address(&self.address)),
+            ("label", text(&self.label)),
+        ]
+    }
+}
+
+impl Rows for UnlinkAddressPayload {
+    fn rows(&self) -> Vec<(&'static str, String)> {
+        vec![("handle", text(&self.handle)), ("address", address(&self.address))]
+    }
+}
+
+impl Rows for TransferPayload {
+    fn rows(&self) -> Vec<(&'static str, String)> {
+        let coin = text(&self.coin_type);
+        let mut rows = vec![
+            ("from", text(&self.from_handle)),
+            ("to", text(&self.to_handle)),
+            ("amount", format!("{} ({} raw)", human(self.amount, &coin), self.amount)),
+        ];
+        if !self.memo.is_empty() {
+            rows.push(("memo", text(&self.memo)));
+        }
+        rows
+    }
+}
+
+impl Rows for WithdrawPayload {
+    fn rows(&self) -> Vec<(&'static str, String)> {
+        let coin = text(&self.coin_type);
+        vec![
+            ("handle", text(&self.handle)),
+            ("amount", format!("{} ({} raw)", human(self.amount, &coin), self.amount)),
+        ]
+    }
+}
+
+impl Rows for BioAuthPayload {
+    // The result code is deliberately not decoded: it's blind until applied on-chain
+    fn rows(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("handle", text(&self.handle)),
+            ("amount", format!("{} raw", self.amount)),
+            ("transcript", text(&self.transcript)),
+            ("destination", if self.destination_verified { "verified" } else { "-" }.to_string()),
+            ("method", if self.method == BioAuthMethod::Typed as u8 { "typed" } else { "voice" }.to_string()),
+        ]
+    }
+}
+
+/// Print a signed response: payload rows, then intent, layout, timestamp and signature
+pub fn signed<P: Rows>(title: &str, signed: &Signed<P>, verified: bool) {
+    println!("{}", title);
+    for (label, value) in signed.payload.rows() {
+        println!("  {:<12} {}", label, value);
+    }
+    println!("  {:<12} {} ({})", "intent", signed.intent, intent_name(signed.intent));
+    println!("  {:<12} v{}", "layout", signed.payload_version as u8);
+    println!("  {:<12} {}", "timestamp", signed.timestamp_ms);
+    let short = if signed.signature.len() > 16 {
+        format!("{}…{}", &signed.signature[..8], &signed.signature[signed.signature.len() - 8..])
+    } else {
+        signed.signature.clone()
+    };
+    println!(
+        "  {:<12} {} {}",
+        "signature",
+        short,
+        if verified { "(verified)" } else { "(not verified: set --enclave-key)" }
+    );
+}
+
+pub fn events(events: &[RamEvent]) {
+    if events.is_empty() {
+        println!("No events");
+        return;
+    }
+    for event in events {
+        let amount = match (event.amount, event.coin_type.as_deref()) {
+            (Some(amount), coin) => human(amount.max(0) as u64, coin.unwrap_or("SUI")),
+            (None, _) => String::new(),
+        };
+        let party = match (&event.from_handle, &event.to_handle, event.memo.as_deref()) {
+            (Some(from), Some(to), Some(memo)) if !memo.is_empty() => format!("{} -> {} ({})", from, to, memo),
+            (Some(from), Some(to), _) => format!("{} -> {}", from, to),
+            _ => event.sender_label.clone().or_else(|| event.sender.clone()).unwrap_or_default(),
+        };
+        println!(
+            "{}  {:<18} {:<16} {:<24} {}",
+            event.timestamp, event.event_type, amount, party, event.tx_digest
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_amounts() {
+        assert_eq!(to_raw(5.0, "SUI"), 5_000_000_000);
+        assert_eq!(to_raw(2.5, "usdc"), 2_500_000);
+        assert_eq!(to_raw(0.3, "SUI"), 300_000_000);
+        assert_eq!(human(5_500_000_000, "sui"), "5.5 SUI");
+        assert_eq!(human(1_000_000, "0xa::usdc::USDC"), "1 USDC");
+    }
+}
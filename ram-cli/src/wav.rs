@@ -0,0 +1,127 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! Recording checks for `bio-auth --audio`
+//!
+//! The enclave's DSP analysis reads PCM WAV directly, so a recording in the
+//! wrong shape degrades duress detection quietly instead of failing. These
+//! checks turn that into hints before anything is sent.
+
+/// Format fields from a WAV `fmt ` chunk
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WavInfo {
+    pub channels: u16,
+    pub sample_rate: u32,
+    pub bits_per_sample: u16,
+    pub duration_ms: u64,
+}
+
+fn u16_at(bytes: &[u8], at: usize) -> Option<u16> {
+    bytes.get(at..at + 2).map(|b| u16::from_le_bytes([b[0], b[1]]))
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> Option<u32> {
+    bytes.get(at..at + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+/// Parse the RIFF header; None if this isn't a PCM WAV file
+pub fn inspect(bytes: &[u8]) -> Option<WavInfo> {
+    if bytes.get(0..4)? != b"RIFF" || bytes.get(8..12)? != b"WAVE" {
+        return None;
+    }
+
+    let mut format = None;
+    let mut offset = 12;
+    while offset + 8 <= bytes.len() {
+        let id = &bytes[offset..offset + 4];
+        let size = u32_at(bytes, offset + 4)? as usize;
+        let body = offset + 8;
+        match id {
+            b"fmt " => {
+                format = Some((u16_at(bytes, body + 2)?, u32_at(bytes, body + 4)?, u16_at(bytes, body + 14)?));
+            }
+            b"data" => {
+                let (channels, sample_rate, bits_per_sample) = format?;
+                let bytes_per_sec = sample_rate as u64 * channels as u64 * (bits_per_sample as u64 / 8);
+                let data_len = size.min(bytes.len() - body) as u64;
+                return Some(WavInfo {
+                    channels,
+                    sample_rate,
+                    bits_per_sample,
+                    duration_ms: (data_len * 1000).checked_div(bytes_per_sec).unwrap_or(0),
+                });
+            }
+            _ => {}
+        }
+        // Chunks are word-aligned
+        offset = body + size + (size % 2);
+    }
+    None
+}
+
+/// Hints for a recording that the analyzer will handle poorly
+pub fn hints(info: Option<&WavInfo>) -> Vec<String> {
+    let Some(info) = info else {
+        return vec![
+            "not a PCM WAV file: transcription still works, but acoustic stress analysis is skipped".to_string(),
+        ];
+    };
+
+    let mut hints = Vec::new();
+    if info.channels != 1 {
+        hints.push(format!("{} channels: record mono", info.channels));
+    }
+    if info.sample_rate < 16_000 {
+        hints.push(format!("{} Hz sample rate: record at 16 kHz or higher", info.sample_rate));
+    }
+    if info.bits_per_sample != 16 {
+        hints.push(format!("{}-bit samples: record 16-bit PCM", info.bits_per_sample));
+    }
+    if info.duration_ms < 1_500 {
+        hints.push(format!("only {} ms of audio: say the whole phrase at a normal pace", info.duration_ms));
+    } else if info.duration_ms > 15_000 {
+        hints.push(format!("{} s of audio: trim silence around the phrase", info.duration_ms / 1000));
+    }
+    hints
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wav(channels: u16, sample_rate: u32, samples: u32) -> Vec<u8> {
+        let data_size = samples * channels as u32 * 2;
+        let mut wav = Vec::new();
+        wav.extend_from_slice(b"RIFF");
+        wav.extend_from_slice(&(36 + data_size).to_le_bytes());
+        wav.extend_from_slice(b"WAVEfmt ");
+        wav.extend_from_slice(&16u32.to_le_bytes());
+        wav.extend_from_slice(&1u16.to_le_bytes());
+        wav.extend_from_slice(&channels.to_le_bytes());
+        wav.extend_from_slice(&sample_rate.to_le_bytes());
+        wav.extend_from_slice(&(sample_rate * channels as u32 * 2).to_le_bytes());
+        wav.extend_from_slice(&(channels * 2).to_le_bytes());
+        wav.extend_from_slice(&16u16.to_le_bytes());
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&data_size.to_le_bytes());
+        wav.resize(wav.len() + data_size as usize, 0);
+        wav
+    }
+
+    #[test]
+    fn test_inspect() {
+        let info = inspect(&wav(1, 16_000, 48_000)).unwrap();
+        assert_eq!(info, WavInfo { channels: 1, sample_rate: 16_000, bits_per_sample: 16, duration_ms: 3_000 });
+        assert!(hints(Some(&info)).is_empty());
+        assert!(inspect(b"ID3\x03not a wav").is_none());
+    }
+
+    #[test]
+    fn test_hints() {
+        let info = inspect(&wav(2, 8_000, 4_000)).unwrap();
+        let hints = hints(Some(&info));
+        assert_eq!(hints.len(), 3);
+        assert!(hints[0].contains("mono"));
+        assert_eq!(super::hints(None).len(), 1);
+    }
+}
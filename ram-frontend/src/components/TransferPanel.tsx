@@ -1,7 +1,8 @@
 import { useState, useEffect } from 'react'
 import { useCurrentAccount, useSuiClient, useSignAndExecuteTransaction } from '@mysten/dapp-kit'
 import { Transaction } from '@mysten/sui/transactions'
-import { SUI_PACKAGE_ID, RAM_REGISTRY_ID, ENCLAVE_ID, ENCLAVE_PACKAGE_ID, requestTransferSignature } from '../services/ramApi'
+import { toBase64 } from '@mysten/sui/utils'
+import { SUI_PACKAGE_ID, RAM_REGISTRY_ID, ENCLAVE_ID, ENCLAVE_PACKAGE_ID, SUI_COIN_TYPE, requestTransferSignature, estimateTransactionGas } from '../services/ramApi'
 import type { BioAuthResponse } from '../services/ramApi'
 import { useRamWallet } from '../hooks/useRamWallet'
 import './TransferPanel.css'
@@ -14,6 +15,9 @@ type Toast = {
     visible: boolean;
 };
 
+// Budget the transfer is built with for its gas estimate (MIST)
+const PROVISIONAL_GAS_BUDGET = 10_000_000
+
 export function TransferPanel() {
     const account = useCurrentAccount()
     const suiClient = useSuiClient()
@@ -370,17 +374,19 @@ export function TransferPanel() {
 
         try {
             const amountInMist = Math.floor(parseFloat(amount) * 1_000_000_000)
-            const coinTypeStr = '0000000000000000000000000000000000000000000000000000000000000002::sui::SUI'
 
             // Determine recipient handle for enclave signature
             const recipientHandle = toHandle || handleName || `user_${(resolvedAddress || walletAddress).slice(2, 8)}`
 
             // Step 1: Get enclave signature for the transfer
+            // Dry-run first so a transfer that would abort costs no gas
             const transferSig = await requestTransferSignature(
                 currentUserHandle,
                 recipientHandle,
                 amountInMist,
-                coinTypeStr,
+                SUI_COIN_TYPE,
+                undefined,
+                { from_wallet_id: fromWalletId!, to_wallet_id: recipientWalletId },
             )
 
             // === TX1: apply_bioauth (always executes on-chain) ===
@@ -401,6 +407,11 @@ export function TransferPanel() {
                     bioauthTx.pure.u64(response.payload.amount),
                     bioauthTx.pure.u8(response.payload.result),
                     bioauthTx.pure('vector<u8>', response.payload.transcript),
+                    bioauthTx.pure.bool(response.payload.destination_verified),
+                    bioauthTx.pure.u8(response.payload.method),
+                    bioauthTx.pure('vector<u8>', response.payload.seal),
+                    bioauthTx.pure.u8(response.payload.failed_attempts),
+                    bioauthTx.pure.u8(response.payload_version),
                     bioauthTx.pure.u64(response.timestamp_ms),
                     bioauthTx.pure('vector<u8>', bioSigBytes),
                     bioauthTx.object(ENCLAVE_ID),
@@ -434,7 +445,8 @@ export function TransferPanel() {
                 transferSigBytes.push(parseInt(transferSigHex.substring(i, i + 2), 16))
             }
 
-            const coinTypeBytes = Array.from(new TextEncoder().encode(coinTypeStr))
+            // The enclave signs the coin type in type_name form; submit exactly those bytes
+            const coinTypeBytes = transferSig.payload.coin_type
 
             transferTx.moveCall({
                 target: `${SUI_PACKAGE_ID}::transfers::transfer_with_signature`,
@@ -443,17 +455,34 @@ export function TransferPanel() {
                     transferTx.object(recipientWalletId!),
                     transferTx.pure.u64(amountInMist),
                     transferTx.pure('vector<u8>', coinTypeBytes),
+                    transferTx.pure('vector<u8>', transferSig.payload.memo),
+                    transferTx.pure.u8(transferSig.payload_version),
                     transferTx.pure.u64(transferSig.timestamp_ms),
                     transferTx.pure('vector<u8>', transferSigBytes),
                     transferTx.object(ENCLAVE_ID),
                     transferTx.object('0x6'),
                 ],
                 typeArguments: [
-                    '0x2::sui::SUI',
+                    SUI_COIN_TYPE,
                     `${ENCLAVE_PACKAGE_ID}::core::XWALLET`,
                 ]
             })
 
+            // Budget TX2 from a backend dry run and show its fee; the
+            // provisional budget stands if the estimate can't be made
+            try {
+                transferTx.setSender(account!.address)
+                transferTx.setGasBudget(PROVISIONAL_GAS_BUDGET)
+                const estimate = await estimateTransactionGas(toBase64(await transferTx.build({ client: suiClient })))
+                if (estimate.status === 'success') {
+                    transferTx.setGasPrice(estimate.gas_price)
+                    transferTx.setGasBudget(estimate.budget)
+                    showToast(`Network fee ≈ ${(Math.max(estimate.fee, 0) / 1_000_000_000).toFixed(6)} SUI`)
+                }
+            } catch (estimateError) {
+                console.warn('Gas estimate failed, using the provisional budget:', estimateError)
+            }
+
             // Execute TX2 — may fail if wallet was just locked
             try {
                 await new Promise<void>((resolve, reject) => {
@@ -668,7 +697,7 @@ export function TransferPanel() {
                 <VoiceAuth
                     handle={currentUserHandle}
                     amount={parseFloat(amount)}
-                    coinType="Sui"
+                    coinType={SUI_COIN_TYPE}
                     recipientHandle={handleName || undefined}
                     recipientAddress={walletAddress || undefined}
                     onSuccess={handleVoiceAuthSuccess}
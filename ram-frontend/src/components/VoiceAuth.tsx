@@ -1,13 +1,13 @@
 import { useState, useRef, useCallback, useEffect } from 'react';
 import { motion, AnimatePresence } from 'framer-motion';
-import { bioAuth, isBioAuthSuccess, getTranscript } from '../services/ramApi';
+import { bioAuth, coinSymbol, isBioAuthSuccess, getTranscript } from '../services/ramApi';
 import type { BioAuthResponse } from '../services/ramApi';
 import './VoiceAuth.css';
 
 interface VoiceAuthProps {
   handle: string;
   amount: number;
-  coinType: string;
+  coinType: string; // Move coin type, e.g. 0x2::sui::SUI
   action?: string;
   recipientAddress?: string;
   recipientHandle?: string;
@@ -142,7 +142,7 @@ export function VoiceAuth({
         setTimeout(() => onSuccess(response), 1500);
       } else {
         // Only InvalidAmount gets an error
-        setError(`Voice verification failed. Please say "${action} ${amount} ${coinType}" clearly and try again.`);
+        setError(`Voice verification failed. Please say "${action} ${amount} ${coinSymbol(coinType)}" clearly and try again.`);
         setState('error');
       }
 
@@ -166,7 +166,7 @@ export function VoiceAuth({
   };
 
   const getPromptText = () => {
-    return `"${action} ${amount} ${coinType}"`;
+    return `"${action} ${amount} ${coinSymbol(coinType)}"`;
   };
 
   return (
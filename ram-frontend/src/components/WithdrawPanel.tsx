@@ -1,7 +1,7 @@
 import { useState, useEffect } from 'react'
 import { useCurrentAccount, useSuiClient, useSignAndExecuteTransaction } from '@mysten/dapp-kit'
 import { Transaction } from '@mysten/sui/transactions'
-import { SUI_PACKAGE_ID, RAM_REGISTRY_ID, ENCLAVE_ID, ENCLAVE_PACKAGE_ID, requestWithdrawSignature } from '../services/ramApi'
+import { SUI_PACKAGE_ID, RAM_REGISTRY_ID, ENCLAVE_ID, ENCLAVE_PACKAGE_ID, SUI_COIN_TYPE, requestWithdrawSignature } from '../services/ramApi'
 import type { BioAuthResponse } from '../services/ramApi'
 import { useRamWallet } from '../hooks/useRamWallet'
 import './WithdrawPanel.css'
@@ -128,13 +128,12 @@ export function WithdrawPanel() {
 
         try {
             const amountInMist = Math.floor(parseFloat(amount) * 1_000_000_000)
-            const coinTypeStr = '0000000000000000000000000000000000000000000000000000000000000002::sui::SUI'
 
             // Step 1: Get enclave signature for the withdrawal
             const withdrawSig = await requestWithdrawSignature(
                 currentUserHandle,
                 amountInMist,
-                coinTypeStr,
+                SUI_COIN_TYPE,
             )
 
             // === TX1: apply_bioauth (always executes on-chain) ===
@@ -155,6 +154,11 @@ export function WithdrawPanel() {
                     bioauthTx.pure.u64(response.payload.amount),
                     bioauthTx.pure.u8(response.payload.result),
                     bioauthTx.pure('vector<u8>', response.payload.transcript),
+                    bioauthTx.pure.bool(response.payload.destination_verified),
+                    bioauthTx.pure.u8(response.payload.method),
+                    bioauthTx.pure('vector<u8>', response.payload.seal),
+                    bioauthTx.pure.u8(response.payload.failed_attempts),
+                    bioauthTx.pure.u8(response.payload_version),
                     bioauthTx.pure.u64(response.timestamp_ms),
                     bioauthTx.pure('vector<u8>', bioSigBytes),
                     bioauthTx.object(ENCLAVE_ID),
@@ -188,7 +192,8 @@ export function WithdrawPanel() {
                 withdrawSigBytes.push(parseInt(withdrawSigHex.substring(i, i + 2), 16))
             }
 
-            const coinTypeBytes = Array.from(new TextEncoder().encode(coinTypeStr))
+            // The enclave signs the coin type in type_name form; submit exactly those bytes
+            const coinTypeBytes = withdrawSig.payload.coin_type
 
             const [coin] = withdrawTx.moveCall({
                 target: `${SUI_PACKAGE_ID}::wallet::withdraw`,
@@ -196,13 +201,14 @@ export function WithdrawPanel() {
                     withdrawTx.object(walletId),
                     withdrawTx.pure.u64(amountInMist),
                     withdrawTx.pure('vector<u8>', coinTypeBytes),
+                    withdrawTx.pure.u8(withdrawSig.payload_version),
                     withdrawTx.pure.u64(withdrawSig.timestamp_ms),
                     withdrawTx.pure('vector<u8>', withdrawSigBytes),
                     withdrawTx.object(ENCLAVE_ID),
                     withdrawTx.object('0x6'),
                 ],
                 typeArguments: [
-                    '0x2::sui::SUI',
+                    SUI_COIN_TYPE,
                     `${ENCLAVE_PACKAGE_ID}::core::XWALLET`,
                 ]
             })
@@ -263,7 +269,7 @@ export function WithdrawPanel() {
             <VoiceAuth
                 amount={parseFloat(amount)}
                 handle={currentUserHandle}
-                coinType="Sui"
+                coinType={SUI_COIN_TYPE}
                 action="withdraw"
                 onSuccess={handleVoiceAuthSuccess}
                 onCancel={handleVoiceAuthCancel}
@@ -2,8 +2,12 @@
 // All requests are proxied through the backend to Nautilus server
 
 const RAM_BACKEND_URL = import.meta.env.VITE_RAM_BACKEND_URL || 'http://localhost:4000';
+// Versioned routes; the backend's flat paths are deprecated
+const RAM_API_URL = `${RAM_BACKEND_URL}/v1`;
 
 // Sui Blockchain Constants
+// Coin used when none is chosen; every API call takes full Move coin types
+export const SUI_COIN_TYPE = '0x2::sui::SUI';
 export const SUI_PACKAGE_ID = import.meta.env.VITE_SUI_PACKAGE_ID || '0x8d6ef0202e592745340d9c96efb32dba98191ea981eea5ad7ba8731f1545e216';
 export const RAM_REGISTRY_ID = import.meta.env.VITE_RAM_REGISTRY_ID || '0xc91902a23f2b159175da4b9728cb6018c51dc42b2437b59f6cfe54f189206ae4';
 export const ENCLAVE_ID = import.meta.env.VITE_ENCLAVE_ID || '0x1d154f7d2c12f7e611d39cc1a261e56eda39bcdc09c7b0f973c67ac67e8094aa';
@@ -24,6 +28,7 @@ export interface CreateWalletResponse {
     handle: number[];
   };
   intent: number;
+  payload_version: number; // Layout the payload was signed in (1 or 2)
   timestamp_ms: number;
   signature: string;
 }
@@ -31,23 +36,78 @@ export interface CreateWalletResponse {
 export interface LinkAddressRequest {
   handle: string;
   wallet_address: string;
+  label?: string;
 }
 
 export interface LinkAddressResponse {
   payload: {
     handle: number[];
     address: number[];
+    label: number[];
   };
   intent: number;
+  payload_version: number;
   timestamp_ms: number;
   signature: string;
 }
 
+export interface UnlinkAddressResponse {
+  payload: {
+    handle: number[];
+    address: number[];
+  };
+  intent: number;
+  payload_version: number;
+  timestamp_ms: number;
+  signature: string;
+}
+
+export interface LinkedAddress {
+  address: string;
+  label: string;
+  linked_at_ms: number;
+}
+
+export interface CoinPosition {
+  coin_type: string;
+  deposited: number;
+  withdrawn: number;
+  transferred_in: number;
+  transferred_out: number;
+  net_flow: number;
+  live_balance: number | null; // null if the fullnode couldn't be read
+}
+
+export interface Portfolio {
+  handle: string;
+  wallet_id: string | null;
+  coins: CoinPosition[];
+}
+
+export interface GasUsed {
+  computation_cost: number;
+  storage_cost: number;
+  storage_rebate: number;
+  non_refundable_storage_fee: number;
+}
+
+export interface GasRecommendation {
+  status: 'success' | 'failure';
+  error?: string; // Why the dry run failed
+  gas_used: GasUsed;
+  fee: number; // Net MIST the dry run charged: its cost less the storage rebate
+  gas_price: number; // Reference gas price to set
+  budget: number; // Gas budget to set, in MIST
+}
+
 export interface BioAuthRequest {
   handle: string;
   audio_base64: string;
   expected_amount: number; // In smallest unit (e.g., 1 SUI = 1_000_000_000)
-  coin_type?: string;
+  coin_type?: string; // Move coin type, e.g. 0x2::sui::SUI
+  destination_hint?: string; // Withdrawal address whose suffix must be spoken
+  locale?: string; // BCP 47 tag; the expected amount is shown and read the user's way
+  skip_analysis_cache?: boolean; // Analyze afresh even if this clip was analyzed moments ago
 }
 
 export interface BioAuthResponse {
@@ -56,22 +116,59 @@ export interface BioAuthResponse {
     amount: number;
     result: number;
     transcript: number[];
+    destination_verified: boolean;
+    method: number; // 0 = voice, 1 = typed confirmation
+    seal: number[]; // opaque, passed through to apply_bioauth as-is
+    failed_attempts: number; // consecutive failed bio-auths before this one
   };
   intent: number;
+  payload_version: number;
   timestamp_ms: number;
   signature: string;
 }
 
+/** Timing of one key press, ms since the confirmation field was focused */
+export interface Keystroke {
+  down_ms: number;
+  up_ms: number;
+}
+
+export interface BioAuthChallenge {
+  challenge_id: string;
+  phrase: string; // What the user must say
+  expires_at_ms: number;
+}
+
+export interface EnrollVoiceResponse {
+  handle: string;
+  samples: number; // Calm samples accepted so far
+  required: number; // Samples needed before the baseline is used
+  complete: boolean;
+  panic_phrase_set: boolean;
+}
+
 export interface TransferResponse {
   payload: {
     from_handle: number[];
     to_handle: number[];
     amount: number;
     coin_type: number[];
+    memo: number[];
   };
   intent: number;
+  payload_version: number;
   timestamp_ms: number;
   signature: string;
+  // Present when the request asked for a dry run and the enclave could run it
+  simulation?: {
+    status: string;
+    gas_used: {
+      computation_cost: number;
+      storage_cost: number;
+      storage_rebate: number;
+      non_refundable_storage_fee: number;
+    };
+  };
 }
 
 export interface WithdrawResponse {
@@ -81,12 +178,99 @@ export interface WithdrawResponse {
     coin_type: number[];
   };
   intent: number;
+  payload_version: number;
+  timestamp_ms: number;
+  signature: string;
+}
+
+export interface FreezeWalletResponse {
+  payload: {
+    handle: number[];
+  };
+  intent: number;
+  payload_version: number;
+  timestamp_ms: number;
+  signature: string;
+}
+
+export interface UnfreezeResponse {
+  payload: {
+    handle: number[];
+  };
+  intent: number;
+  payload_version: number;
+  timestamp_ms: number;
+  signature: string;
+}
+
+export interface SetBeneficiaryResponse {
+  payload: {
+    handle: number[];
+    beneficiary_handle: number[];
+    inactivity_timeout_ms: number;
+  };
+  intent: number;
+  payload_version: number;
+  timestamp_ms: number;
+  signature: string;
+}
+
+export interface ProveDepositResponse {
+  payload: {
+    handle: number[];
+    deposit_id: number[];
+  };
+  intent: number;
+  payload_version: number;
+  timestamp_ms: number;
+  signature: string;
+}
+
+export interface SetLimitResponse {
+  payload: {
+    handle: number[];
+    daily_limit: number;
+    coin_type: number[];
+  };
+  intent: number;
+  payload_version: number;
   timestamp_ms: number;
   signature: string;
 }
 
 
 
+/** Error code of an ApiErrorBody (ram_sdk::ErrorCode) */
+export type ErrorCode =
+  | 'bad_request'
+  | 'validation_failed'
+  | 'unauthorized'
+  | 'forbidden'
+  | 'peer_not_allowed'
+  | 'not_found'
+  | 'method_not_allowed'
+  | 'unsupported_version'
+  | 'conflict'
+  | 'replay_detected'
+  | 'payload_too_large'
+  | 'rate_limited'
+  | 'compliance_blocked'
+  | 'analysis_inconclusive'
+  | 'human_check_failed'
+  | 'simulation_failed'
+  | 'internal'
+  | 'enclave_unavailable'
+  | 'unavailable'
+  | 'timeout';
+
+/** Error body from the enclave or backend (ram_sdk::ApiErrorBody) */
+export interface ApiErrorBody {
+  code?: ErrorCode;
+  message?: string;
+  details?: { fields?: { field: string; message: string }[] } & Record<string, unknown>;
+  request_id?: string;
+}
+
 export interface HealthCheckResponse {
   pk: string;
   endpoints_status: Record<string, unknown>;
@@ -96,11 +280,22 @@ export interface HealthCheckResponse {
 // API Functions
 // ============================================================================
 
+/**
+ * Message of an error body; 422s list each invalid field
+ */
+function apiErrorMessage(error: ApiErrorBody, fallback: string): string {
+  const fields = error.details?.fields;
+  if (fields?.length) {
+    return fields.map((f) => `${f.field}: ${f.message}`).join('; ');
+  }
+  return error.message || fallback;
+}
+
 /**
  * Check if RAM server is healthy
  */
 export async function healthCheck(): Promise<HealthCheckResponse> {
-  const response = await fetch(`${RAM_BACKEND_URL}/health_check`);
+  const response = await fetch(`${RAM_API_URL}/health_check`);
   if (!response.ok) {
     throw new Error(`Health check failed: ${response.status}`);
   }
@@ -111,7 +306,7 @@ export async function healthCheck(): Promise<HealthCheckResponse> {
  * Create a new RAM wallet
  */
 export async function createWallet(handle: string): Promise<CreateWalletResponse> {
-  const response = await fetch(`${RAM_BACKEND_URL}/create_wallet`, {
+  const response = await fetch(`${RAM_API_URL}/create_wallet`, {
     method: 'POST',
     headers: { 'Content-Type': 'application/json' },
     body: JSON.stringify({
@@ -121,20 +316,45 @@ export async function createWallet(handle: string): Promise<CreateWalletResponse
 
   if (!response.ok) {
     const error = await response.json().catch(() => ({ error: 'Unknown error' }));
-    throw new Error(error.error || `Failed to create wallet: ${response.status}`);
+    throw new Error(apiErrorMessage(error, `Failed to create wallet: ${response.status}`));
   }
 
   return response.json();
 }
 
 /**
- * Link a Sui wallet address to RAM wallet
+ * Link a labeled Sui wallet address to RAM wallet
+ * A wallet can hold several addresses; re-linking an address updates its label.
  */
 export async function linkAddress(
   handle: string,
-  walletAddress: string
+  walletAddress: string,
+  label: string = ''
 ): Promise<LinkAddressResponse> {
-  const response = await fetch(`${RAM_BACKEND_URL}/link_address`, {
+  const response = await fetch(`${RAM_API_URL}/link_address`, {
+    method: 'POST',
+    headers: { 'Content-Type': 'application/json' },
+    body: JSON.stringify({
+      payload: { handle, wallet_address: walletAddress, label },
+    }),
+  });
+
+  if (!response.ok) {
+    const error = await response.json().catch(() => ({ error: 'Unknown error' }));
+    throw new Error(apiErrorMessage(error, `Failed to link address: ${response.status}`));
+  }
+
+  return response.json();
+}
+
+/**
+ * Unlink a Sui wallet address from RAM wallet
+ */
+export async function unlinkAddress(
+  handle: string,
+  walletAddress: string
+): Promise<UnlinkAddressResponse> {
+  const response = await fetch(`${RAM_API_URL}/unlink_address`, {
     method: 'POST',
     headers: { 'Content-Type': 'application/json' },
     body: JSON.stringify({
@@ -144,7 +364,56 @@ export async function linkAddress(
 
   if (!response.ok) {
     const error = await response.json().catch(() => ({ error: 'Unknown error' }));
-    throw new Error(error.error || `Failed to link address: ${response.status}`);
+    throw new Error(apiErrorMessage(error, `Failed to unlink address: ${response.status}`));
+  }
+
+  return response.json();
+}
+
+/**
+ * List the Sui addresses linked to a wallet (from the indexer)
+ */
+export async function getLinkedAddresses(handle: string): Promise<LinkedAddress[]> {
+  const response = await fetch(`${RAM_API_URL}/api/addresses`, {
+    method: 'POST',
+    headers: { 'Content-Type': 'application/json' },
+    body: JSON.stringify({ handle }),
+  });
+
+  if (!response.ok) {
+    throw new Error(`Failed to fetch linked addresses: ${response.status}`);
+  }
+
+  return response.json();
+}
+
+/**
+ * Per-coin history and live balances for a wallet
+ */
+export async function getPortfolio(handle: string): Promise<Portfolio> {
+  const response = await fetch(`${RAM_API_URL}/api/portfolio/${encodeURIComponent(handle)}`);
+
+  if (!response.ok) {
+    throw new Error(`Failed to fetch portfolio: ${response.status}`);
+  }
+
+  return response.json();
+}
+
+/**
+ * Dry-run a built transaction and get the gas price and budget to set on it
+ *
+ * @param txBytes - Base64 BCS transaction data, built with a provisional budget
+ */
+export async function estimateTransactionGas(txBytes: string): Promise<GasRecommendation> {
+  const response = await fetch(`${RAM_API_URL}/api/tx/estimate`, {
+    method: 'POST',
+    headers: { 'Content-Type': 'application/json' },
+    body: JSON.stringify({ tx_bytes: txBytes }),
+  });
+
+  if (!response.ok) {
+    throw new Error(`Failed to estimate gas: ${response.status}`);
   }
 
   return response.json();
@@ -156,19 +425,20 @@ export async function linkAddress(
  * @param handle - User's handle name
  * @param audioBase64 - Base64-encoded audio recording
  * @param amount - Amount in human-readable format (e.g., 5 for 5 SUI)
- * @param coinType - Coin type (SUI, USDC, WAL)
+ * @param coinType - Move coin type, e.g. 0x2::sui::SUI
  */
 export async function bioAuth(
   handle: string,
   audioBase64: string,
   amount: number,
-  coinType: string = 'SUI'
+  coinType: string = SUI_COIN_TYPE,
+  destinationHint?: string
 ): Promise<BioAuthResponse> {
   // Convert to smallest unit
   const decimals = getDecimals(coinType);
   const amountRaw = Math.round(amount * Math.pow(10, decimals));
 
-  const response = await fetch(`${RAM_BACKEND_URL}/bio_auth`, {
+  const response = await fetch(`${RAM_API_URL}/bio_auth`, {
     method: 'POST',
     headers: { 'Content-Type': 'application/json' },
     body: JSON.stringify({
@@ -177,28 +447,184 @@ export async function bioAuth(
         audio_base64: audioBase64,
         expected_amount: amountRaw,
         coin_type: coinType,
+        destination_hint: destinationHint,
+        locale: navigator.language,
+      },
+    }),
+  });
+
+  if (!response.ok) {
+    const error = await response.json().catch(() => ({ error: 'Unknown error' }));
+    throw new Error(apiErrorMessage(error, `BioAuth failed: ${response.status}`));
+  }
+
+  // Queued mode: the enclave returns a job ID and analyzes in the background
+  if (response.status === 202) {
+    const { job_id } = await response.json();
+    return pollBioAuthJob(job_id);
+  }
+
+  return response.json();
+}
+
+/**
+ * Typed-confirmation auth for users who cannot speak
+ *
+ * The user types the amount (plus the destination's last 4 characters for
+ * withdrawals); `keystrokes` are the key timings captured while they typed.
+ * Returns the same signed payload as bioAuth, with method = 1.
+ *
+ * @param amount - Amount in human-readable format (e.g., 5 for 5 SUI)
+ */
+export async function typedAuth(
+  handle: string,
+  typedText: string,
+  keystrokes: Keystroke[],
+  amount: number,
+  coinType: string = SUI_COIN_TYPE,
+  destinationHint?: string,
+  pasted: boolean = false
+): Promise<BioAuthResponse> {
+  const decimals = getDecimals(coinType);
+  const amountRaw = Math.round(amount * Math.pow(10, decimals));
+
+  const response = await fetch(`${RAM_API_URL}/typed_auth`, {
+    method: 'POST',
+    headers: { 'Content-Type': 'application/json' },
+    body: JSON.stringify({
+      payload: {
+        handle,
+        typed_text: typedText,
+        expected_amount: amountRaw,
+        coin_type: coinType,
+        destination_hint: destinationHint,
+        locale: navigator.language,
+        keystrokes,
+        pasted,
+      },
+    }),
+  });
+
+  if (!response.ok) {
+    const error = await response.json().catch(() => ({ error: 'Unknown error' }));
+    throw new Error(apiErrorMessage(error, `Typed auth failed: ${response.status}`));
+  }
+
+  return response.json();
+}
+
+/**
+ * Get the phrase to speak for an upcoming bio-auth
+ *
+ * @param amount - Amount in human-readable format (e.g., 5 for 5 SUI)
+ */
+export async function getBioAuthChallenge(
+  handle: string,
+  amount: number,
+  coinType: string = SUI_COIN_TYPE,
+  destinationHint?: string
+): Promise<BioAuthChallenge> {
+  const decimals = getDecimals(coinType);
+  const amountRaw = Math.round(amount * Math.pow(10, decimals));
+
+  const response = await fetch(`${RAM_API_URL}/bio_auth/challenge`, {
+    method: 'POST',
+    headers: { 'Content-Type': 'application/json' },
+    body: JSON.stringify({
+      payload: {
+        handle,
+        expected_amount: amountRaw,
+        coin_type: coinType,
+        destination_hint: destinationHint,
       },
     }),
   });
 
   if (!response.ok) {
     const error = await response.json().catch(() => ({ error: 'Unknown error' }));
-    throw new Error(error.error || `BioAuth failed: ${response.status}`);
+    throw new Error(apiErrorMessage(error, `Challenge request failed: ${response.status}`));
+  }
+
+  return response.json();
+}
+
+/**
+ * URL of the spoken challenge phrase, usable directly as an <audio> source
+ */
+export function getChallengeAudioUrl(challengeId: string): string {
+  return `${RAM_API_URL}/bio_auth/challenge_audio/${encodeURIComponent(challengeId)}`;
+}
+
+/**
+ * Enroll a calm voice sample towards the user's personal stress baseline
+ *
+ * Call repeatedly until `complete`; samples that sound stressed are rejected.
+ * `panicPhrase` registers a covert phrase that always signals duress; it can't
+ * be changed once the baseline is complete.
+ */
+export async function enrollVoice(
+  handle: string,
+  audioBase64: string,
+  panicPhrase?: string,
+): Promise<EnrollVoiceResponse> {
+  const response = await fetch(`${RAM_API_URL}/bio_auth/enroll`, {
+    method: 'POST',
+    headers: { 'Content-Type': 'application/json' },
+    body: JSON.stringify({
+      payload: { handle, audio_base64: audioBase64, panic_phrase: panicPhrase },
+    }),
+  });
+
+  if (!response.ok) {
+    const error = await response.json().catch(() => ({ error: 'Unknown error' }));
+    throw new Error(apiErrorMessage(error, `Voice enrollment failed: ${response.status}`));
   }
 
   return response.json();
 }
 
+/**
+ * Poll a queued BioAuth job until it finishes
+ */
+async function pollBioAuthJob(
+  jobId: string,
+  intervalMs: number = 1000,
+  timeoutMs: number = 120000
+): Promise<BioAuthResponse> {
+  const deadline = Date.now() + timeoutMs;
+  while (Date.now() < deadline) {
+    const response = await fetch(`${RAM_API_URL}/bio_auth/${jobId}`);
+    if (!response.ok) {
+      const error = await response.json().catch(() => ({ error: 'Unknown error' }));
+      throw new Error(apiErrorMessage(error, `BioAuth job failed: ${response.status}`));
+    }
+
+    const job = await response.json();
+    if (job.status === 'done') return job.response;
+    if (job.status === 'failed') throw new Error(job.error || 'BioAuth failed');
+
+    await new Promise((resolve) => setTimeout(resolve, intervalMs));
+  }
+  throw new Error('BioAuth timed out');
+}
+
 /**
  * Request enclave signature for a transfer between wallets
+ *
+ * `memo` is an optional payment reference (up to 128 bytes) that is signed
+ * and emitted on-chain with the transfer. With `simulate`, the enclave first
+ * dry-runs the call against the two wallet objects and refuses to sign one
+ * that would abort (`simulation_failed`).
  */
 export async function requestTransferSignature(
   fromHandle: string,
   toHandle: string,
   amount: number,
-  coinType: string
+  coinType: string,
+  memo?: string,
+  simulate?: { from_wallet_id: string; to_wallet_id: string }
 ): Promise<TransferResponse> {
-  const response = await fetch(`${RAM_BACKEND_URL}/transfer`, {
+  const response = await fetch(`${RAM_API_URL}/transfer`, {
     method: 'POST',
     headers: { 'Content-Type': 'application/json' },
     body: JSON.stringify({
@@ -207,13 +633,15 @@ export async function requestTransferSignature(
         to_handle: toHandle,
         amount,
         coin_type: coinType,
+        memo,
+        simulate,
       },
     }),
   });
 
   if (!response.ok) {
     const error = await response.json().catch(() => ({ error: 'Unknown error' }));
-    throw new Error(error.error || `Transfer signature failed: ${response.status}`);
+    throw new Error(apiErrorMessage(error, `Transfer signature failed: ${response.status}`));
   }
 
   return response.json();
@@ -227,7 +655,7 @@ export async function requestWithdrawSignature(
   amount: number,
   coinType: string
 ): Promise<WithdrawResponse> {
-  const response = await fetch(`${RAM_BACKEND_URL}/withdraw`, {
+  const response = await fetch(`${RAM_API_URL}/withdraw`, {
     method: 'POST',
     headers: { 'Content-Type': 'application/json' },
     body: JSON.stringify({
@@ -241,7 +669,156 @@ export async function requestWithdrawSignature(
 
   if (!response.ok) {
     const error = await response.json().catch(() => ({ error: 'Unknown error' }));
-    throw new Error(error.error || `Withdraw signature failed: ${response.status}`);
+    throw new Error(apiErrorMessage(error, `Withdraw signature failed: ${response.status}`));
+  }
+
+  return response.json();
+}
+
+/**
+ * Request enclave signature for a daily spending limit spoken by the user
+ * ("set my daily limit to 50 SUI"); refused with 403 unless calm and matching
+ */
+export async function requestSetLimitSignature(
+  handle: string,
+  audioBase64: string,
+  dailyLimit: number,
+  coinType: string
+): Promise<SetLimitResponse> {
+  const response = await fetch(`${RAM_API_URL}/set_limit`, {
+    method: 'POST',
+    headers: { 'Content-Type': 'application/json' },
+    body: JSON.stringify({
+      payload: {
+        handle,
+        audio_base64: audioBase64,
+        daily_limit: dailyLimit,
+        coin_type: coinType,
+      },
+    }),
+  });
+
+  if (!response.ok) {
+    const error = await response.json().catch(() => ({ error: 'Unknown error' }));
+    throw new Error(apiErrorMessage(error, `Set limit signature failed: ${response.status}`));
+  }
+
+  return response.json();
+}
+
+/**
+ * Request enclave signature for an instant freeze (no voice needed)
+ */
+export async function requestFreezeSignature(handle: string): Promise<FreezeWalletResponse> {
+  const response = await fetch(`${RAM_API_URL}/freeze_wallet`, {
+    method: 'POST',
+    headers: { 'Content-Type': 'application/json' },
+    body: JSON.stringify({ payload: { handle } }),
+  });
+
+  if (!response.ok) {
+    const error = await response.json().catch(() => ({ error: 'Unknown error' }));
+    throw new Error(apiErrorMessage(error, `Freeze signature failed: ${response.status}`));
+  }
+
+  return response.json();
+}
+
+/**
+ * Get the one-time phrase to speak for an unfreeze
+ */
+export async function getUnfreezeChallenge(handle: string): Promise<BioAuthChallenge> {
+  const response = await fetch(`${RAM_API_URL}/unfreeze/challenge`, {
+    method: 'POST',
+    headers: { 'Content-Type': 'application/json' },
+    body: JSON.stringify({ payload: { handle } }),
+  });
+
+  if (!response.ok) {
+    const error = await response.json().catch(() => ({ error: 'Unknown error' }));
+    throw new Error(apiErrorMessage(error, `Unfreeze challenge failed: ${response.status}`));
+  }
+
+  return response.json();
+}
+
+/**
+ * Request enclave signature for an unfreeze from a recording of the challenge
+ * phrase; the contract lifts the freeze 24 hours after it is submitted
+ */
+export async function requestUnfreezeSignature(
+  handle: string,
+  challengeId: string,
+  audioBase64: string
+): Promise<UnfreezeResponse> {
+  const response = await fetch(`${RAM_API_URL}/request_unfreeze`, {
+    method: 'POST',
+    headers: { 'Content-Type': 'application/json' },
+    body: JSON.stringify({
+      payload: {
+        handle,
+        challenge_id: challengeId,
+        audio_base64: audioBase64,
+      },
+    }),
+  });
+
+  if (!response.ok) {
+    const error = await response.json().catch(() => ({ error: 'Unknown error' }));
+    throw new Error(apiErrorMessage(error, `Unfreeze signature failed: ${response.status}`));
+  }
+
+  return response.json();
+}
+
+/**
+ * Request enclave signature for an inactivity beneficiary (no voice needed);
+ * an empty beneficiaryHandle removes it
+ */
+export async function requestSetBeneficiarySignature(
+  handle: string,
+  beneficiaryHandle: string,
+  inactivityTimeoutMs: number
+): Promise<SetBeneficiaryResponse> {
+  const response = await fetch(`${RAM_API_URL}/set_beneficiary`, {
+    method: 'POST',
+    headers: { 'Content-Type': 'application/json' },
+    body: JSON.stringify({
+      payload: {
+        handle,
+        beneficiary_handle: beneficiaryHandle,
+        inactivity_timeout_ms: inactivityTimeoutMs,
+      },
+    }),
+  });
+
+  if (!response.ok) {
+    const error = await response.json().catch(() => ({ error: 'Unknown error' }));
+    throw new Error(apiErrorMessage(error, `Beneficiary signature failed: ${response.status}`));
+  }
+
+  return response.json();
+}
+
+/**
+ * Request enclave signature binding an exchange's deposit memo to a handle;
+ * the exchange passes it with each deposit to wallet::deposit_with_proof
+ */
+export async function requestDepositProof(handle: string, depositId: string): Promise<ProveDepositResponse> {
+  const response = await fetch(`${RAM_API_URL}/prove_deposit`, {
+    method: 'POST',
+    headers: { 'Content-Type': 'application/json' },
+    body: JSON.stringify({
+      payload: {
+        handle,
+        deposit_id: depositId,
+      },
+    }),
+  });
+
+  if (!response.ok) {
+    const error = await response.json().catch(() => ({ error: 'Unknown error' }));
+    throw new Error(apiErrorMessage(error, `Deposit proof failed: ${response.status}`));
   }
 
   return response.json();
@@ -251,8 +828,16 @@ export async function requestWithdrawSignature(
 // Helpers
 // ============================================================================
 
+/**
+ * Ticker of a Move coin type, e.g. "USDC" for "0x...::usdc::USDC"
+ */
+export function coinSymbol(coinType: string): string {
+  return (coinType.split('::').pop() || coinType).toUpperCase();
+}
+
+// Only converts the user's input; the enclave resolves the coin's real decimals
 function getDecimals(coinType: string): number {
-  switch (coinType.toUpperCase()) {
+  switch (coinSymbol(coinType)) {
     case 'SUI':
       return 9;
     case 'USDC':
@@ -293,11 +878,20 @@ export interface WalletEvent {
   handle: string | null;
   event_type: string;
   amount: number | null;
+  coin_type: string | null;
+  result_code: number | null; // BioAuth: 0=OK, 1=InvalidAmount, 2=Duress
   from_handle: string | null;
   to_handle: string | null;
+  memo: string | null; // Transferred: the sender's payment reference
+  payload_version: number | null; // Transferred: signed layout (0 = wallet-signed, null = pre-versioning)
   owner: string | null;
+  wallet_id: string | null;
   tx_digest: string;
   timestamp: string;
+  raw_json: Record<string, unknown> | null;
+  gas_used: number | null; // Only set by the checkpoint indexer
+  sender: string | null; // Address that signed the transaction
+  sender_label: string | null; // Label if the sender is linked to this wallet
 }
 
 export interface GetEventsRequest {
@@ -310,7 +904,7 @@ export interface GetEventsRequest {
  * Get wallet events from backend indexer
  */
 export async function getWalletEvents(request: GetEventsRequest): Promise<WalletEvent[]> {
-  const response = await fetch(`${RAM_BACKEND_URL}/api/events`, {
+  const response = await fetch(`${RAM_API_URL}/api/events`, {
     method: 'POST',
     headers: {
       'Content-Type': 'application/json',
@@ -9,6 +9,11 @@
 /// 2. Server analyzes voice for stress/duress
 /// 3. If OK -> transfer proceeds
 /// 4. If duress detected -> wallet locks for 24 hours
+///
+/// With the enclave's decoy mode on, a duress verdict is signed with result OK
+/// and a seal that opens to decoy (see `core::is_decoy_seal`): the wallet
+/// still locks, but signed transfers and withdrawals "succeed" without moving
+/// funds, so a coercer watching the screen sees the transfer go through.
 module ram::bioguard {
     use sui::clock::Clock;
     use ram::core::{Self, RamWallet};
@@ -23,12 +28,30 @@ module ram::bioguard {
     /// - 0 (OK): Voice verified, no stress detected
     /// - 1 (InvalidAmount): Spoken amount doesn't match
     /// - 2 (Duress): Stress/panic detected -> LOCK WALLET
+    ///
+    /// `destination_verified` is true when the user also spoke the last
+    /// characters of a withdrawal's destination address.
+    ///
+    /// `method` is how the user confirmed: 0 = voice, 1 = typed confirmation
+    /// (accessibility path, verified with keystroke dynamics instead of voice).
+    ///
+    /// `seal` commits to whether this is a duress verdict disguised as OK; a
+    /// decoy locks like duress and is recorded as duress in events.
+    ///
+    /// `failed_attempts` is the enclave's count of consecutive failures before
+    /// this one. Non-duress results must respect the same cooldown schedule
+    /// (1 min, 5 min, 1 h after the 3rd, 4th, 5th+ failure); duress always applies.
     public fun apply_bioauth<T>(
         wallet: &mut RamWallet,
         handle: vector<u8>,
         amount: u64,
         result: u8,
         transcript: vector<u8>,
+        destination_verified: bool,
+        method: u8,
+        seal: vector<u8>,
+        failed_attempts: u8,
+        payload_version: u8,
         timestamp: u64,
         signature: &vector<u8>,
         enclave: &Enclave<T>,
@@ -41,10 +64,13 @@ module ram::bioguard {
         );
 
         // Verify signature from enclave
-        let payload = core::new_bioauth_payload(handle, amount, result, transcript);
-        let is_valid = enclave.verify_signature(
+        let decoy = core::is_decoy_seal(&seal);
+        let payload = core::new_bioauth_payload(handle, amount, result, transcript, destination_verified, method, seal, failed_attempts);
+        let is_valid = core::verify_payload(
+            enclave,
             core::bioauth_intent(),
             timestamp,
+            payload_version,
             payload,
             signature,
         );
@@ -55,10 +81,23 @@ module ram::bioguard {
         core::wallet_set_last_timestamp(wallet, timestamp);
 
         // Handle result
-        if (result == core::bioauth_duress()) {
+        let duress = result == core::bioauth_duress() || decoy;
+        if (!duress) {
+            core::assert_bioauth_cooldown_elapsed(wallet, failed_attempts, timestamp);
+            if (result == core::bioauth_ok()) {
+                core::clear_bioauth_failures(wallet);
+            } else {
+                core::record_bioauth_failure(wallet, failed_attempts, timestamp);
+            };
+        };
+
+        if (duress) {
             // DURESS DETECTED - Lock wallet for 24 hours
             core::lock_wallet(wallet, clock);
-            
+            if (decoy) {
+                core::arm_decoy(wallet);
+            };
+
             // Emit lock event
             events::emit_wallet_locked(
                 core::wallet_handle(wallet),
@@ -70,7 +109,9 @@ module ram::bioguard {
         events::emit_bioauth_completed(
             core::wallet_handle(wallet),
             amount,
-            result,
+            if (decoy) core::bioauth_duress() else result,
+            destination_verified,
+            method,
         );
     }
 
@@ -83,8 +124,8 @@ module ram::bioguard {
         ctx: &TxContext,
     ) {
         // Check sender is linked
-        assert!(core::wallet_linked_address(wallet).is_some(), core::e_wallet_not_linked());
-        assert!(ctx.sender() == *core::wallet_linked_address(wallet).borrow(), core::e_not_owner());
+        core::assert_linked_sender(wallet, ctx.sender());
+        core::record_activity(wallet, clock);
 
         // Lock wallet
         core::lock_wallet(wallet, clock);
@@ -96,6 +137,74 @@ module ram::bioguard {
         );
     }
 
+    // ====== Freeze/Unfreeze ======
+
+    /// Freeze the wallet with an enclave-signed request
+    ///
+    /// Needs no voice, so a user who suspects compromise can freeze from any
+    /// device. Freezing only stops funds moving; lifting it takes a
+    /// voice-confirmed `request_unfreeze` and a 24 hour delay, during which
+    /// freezing again cancels the unfreeze.
+    public fun freeze_wallet<T>(
+        wallet: &mut RamWallet,
+        payload_version: u8,
+        timestamp: u64,
+        signature: &vector<u8>,
+        enclave: &Enclave<T>,
+    ) {
+        // Verify signature from enclave
+        let payload = core::new_freeze_payload(core::wallet_handle(wallet).into_bytes());
+        let is_valid = core::verify_payload(
+            enclave,
+            core::freeze_intent(),
+            timestamp,
+            payload_version,
+            payload,
+            signature,
+        );
+        assert!(is_valid, core::e_invalid_signature());
+
+        // Check replay
+        assert!(timestamp > core::wallet_last_timestamp(wallet), core::e_replay_attempt());
+        core::wallet_set_last_timestamp(wallet, timestamp);
+
+        core::freeze_wallet(wallet);
+
+        // Emit event
+        events::emit_wallet_frozen(core::wallet_handle(wallet));
+    }
+
+    /// Schedule the end of a freeze with an enclave-signed, voice-confirmed request
+    public fun request_unfreeze<T>(
+        wallet: &mut RamWallet,
+        payload_version: u8,
+        timestamp: u64,
+        signature: &vector<u8>,
+        enclave: &Enclave<T>,
+        clock: &Clock,
+    ) {
+        // Verify signature from enclave
+        let payload = core::new_unfreeze_payload(core::wallet_handle(wallet).into_bytes());
+        let is_valid = core::verify_payload(
+            enclave,
+            core::unfreeze_intent(),
+            timestamp,
+            payload_version,
+            payload,
+            signature,
+        );
+        assert!(is_valid, core::e_invalid_signature());
+
+        // Check replay
+        assert!(timestamp > core::wallet_last_timestamp(wallet), core::e_replay_attempt());
+        core::wallet_set_last_timestamp(wallet, timestamp);
+
+        let unfrozen_at_ms = core::request_unfreeze(wallet, clock);
+
+        // Emit event
+        events::emit_wallet_unfrozen(core::wallet_handle(wallet), unfrozen_at_ms);
+    }
+
     /// Check remaining lock time in milliseconds (0 if unlocked)
     public fun remaining_lock_time(wallet: &RamWallet, clock: &Clock): u64 {
         let now = sui::clock::timestamp_ms(clock);
@@ -7,10 +7,16 @@
 /// to protect users from coerced transfers. When duress is detected,
 /// the wallet automatically locks for 24 hours.
 module ram::core {
-    use std::string::String;
+    use std::ascii;
+    use std::string::{Self, String};
     use sui::table::{Self, Table};
+    use sui::vec_map::{Self, VecMap};
     use sui::bag::{Self, Bag};
     use sui::clock::{Self, Clock};
+    use std::type_name;
+    use sui::address;
+    use sui::dynamic_field as df;
+    use std::hash;
     use enclave::enclave;
 
     // ====== Error Codes ======
@@ -23,6 +29,16 @@ module ram::core {
     const EWalletLocked: u64 = 5;
     const EWalletNotLinked: u64 = 6;
     const EAddressNotFound: u64 = 7;
+    const EAddressNotLinked: u64 = 8;
+    const EBioAuthCooldown: u64 = 9;
+    const EDailyLimitExceeded: u64 = 10;
+    const EWalletFrozen: u64 = 11;
+    const ENotFrozen: u64 = 12;
+    const EInactivityTimeoutTooShort: u64 = 13;
+    const ENoBeneficiary: u64 = 14;
+    const EStillActive: u64 = 15;
+    const EUnsupportedPayloadVersion: u64 = 16;
+    const EInvalidDecoySeal: u64 = 17;
 
     // ====== Intent Constants (must match Rust server) ======
 
@@ -31,6 +47,22 @@ module ram::core {
     const TRANSFER_INTENT: u8 = 2;
     const BIOAUTH_INTENT: u8 = 3;
     const WITHDRAW_INTENT: u8 = 4;
+    const UNLINK_ADDRESS_INTENT: u8 = 5;
+    const SET_LIMIT_INTENT: u8 = 6;
+    const FREEZE_INTENT: u8 = 7;
+    const UNFREEZE_INTENT: u8 = 8;
+    const BENEFICIARY_INTENT: u8 = 9;
+    const DEPOSIT_PROOF_INTENT: u8 = 10;
+
+    // ====== Payload Layout Versions (must match Rust server) ======
+
+    /// The payload struct's fields, as signed since launch
+    const PAYLOAD_V1: u8 = 1;
+    /// A version byte, then the payload struct's fields (see `Versioned`)
+    const PAYLOAD_V2: u8 = 2;
+    /// A version byte, this package's original ID, then the payload struct's
+    /// fields (see `Domain`)
+    const PAYLOAD_V3: u8 = 3;
 
     // ====== BioAuth Result Codes ======
 
@@ -38,10 +70,34 @@ module ram::core {
     const BIOAUTH_INVALID_AMOUNT: u8 = 1;
     const BIOAUTH_DURESS: u8 = 2;
 
+    // ====== BioAuth Methods ======
+
+    const BIOAUTH_METHOD_VOICE: u8 = 0;
+    const BIOAUTH_METHOD_TYPED: u8 = 1;
+
     // ====== Lock Duration ======
 
     const LOCK_DURATION_MS: u64 = 86_400_000; // 24 hours
 
+    // ====== Unfreeze Delay ======
+
+    const UNFREEZE_DELAY_MS: u64 = 86_400_000; // 24 hours
+
+    // ====== Inactivity Beneficiary ======
+
+    const MIN_INACTIVITY_TIMEOUT_MS: u64 = 2_592_000_000; // 30 days
+
+    // ====== Daily Limit Window ======
+
+    const LIMIT_WINDOW_MS: u64 = 86_400_000; // 24 hours
+
+    // ====== Failed BioAuth Cooldowns (must match the enclave's attempts.rs) ======
+
+    const FREE_FAILED_ATTEMPTS: u8 = 2;
+    const COOLDOWN_1_MS: u64 = 60_000; // 3rd failure: 1 minute
+    const COOLDOWN_2_MS: u64 = 300_000; // 4th failure: 5 minutes
+    const COOLDOWN_3_MS: u64 = 3_600_000; // 5th+ failure: 1 hour
+
     // ====== Core Structs ======
 
     /// One-Time Witness
@@ -65,8 +121,8 @@ module ram::core {
         handle: String,
         /// Coin balances stored in bag (type -> Balance<T>)
         balances: Bag,
-        /// Linked Sui wallet address (can withdraw directly)
-        linked_address: Option<address>,
+        /// Linked Sui wallet addresses and their labels (any can withdraw directly)
+        linked_addresses: VecMap<address, String>,
         /// Timestamp when wallet will unlock (0 = not locked)
         locked_until_ms: u64,
         /// Last operation timestamp for replay protection
@@ -84,6 +140,13 @@ module ram::core {
     public struct LinkAddressPayload has copy, drop {
         handle: vector<u8>,
         address: address,
+        label: vector<u8>,
+    }
+
+    #[allow(unused_field)]
+    public struct UnlinkAddressPayload has copy, drop {
+        handle: vector<u8>,
+        address: address,
     }
 
     #[allow(unused_field)]
@@ -92,6 +155,7 @@ module ram::core {
         to_handle: vector<u8>,
         amount: u64,
         coin_type: vector<u8>,
+        memo: vector<u8>,
     }
 
     #[allow(unused_field)]
@@ -100,6 +164,10 @@ module ram::core {
         amount: u64,
         result: u8,
         transcript: vector<u8>,
+        destination_verified: bool,
+        method: u8,
+        seal: vector<u8>,
+        failed_attempts: u8,
     }
 
     #[allow(unused_field)]
@@ -109,6 +177,55 @@ module ram::core {
         coin_type: vector<u8>,
     }
 
+    #[allow(unused_field)]
+    public struct SetLimitPayload has copy, drop {
+        handle: vector<u8>,
+        daily_limit: u64,
+        coin_type: vector<u8>,
+    }
+
+    #[allow(unused_field)]
+    public struct FreezePayload has copy, drop {
+        handle: vector<u8>,
+    }
+
+    #[allow(unused_field)]
+    public struct UnfreezePayload has copy, drop {
+        handle: vector<u8>,
+    }
+
+    #[allow(unused_field)]
+    public struct BeneficiaryPayload has copy, drop {
+        handle: vector<u8>,
+        beneficiary_handle: vector<u8>,
+        inactivity_timeout_ms: u64,
+    }
+
+    #[allow(unused_field)]
+    public struct DepositProofPayload has copy, drop {
+        handle: vector<u8>,
+        deposit_id: vector<u8>,
+    }
+
+    /// Layout v2 of any payload: its BCS bytes are the version byte followed
+    /// by the payload's own fields, so a payload struct can change shape
+    /// under a new version while older signatures still verify
+    #[allow(unused_field)]
+    public struct Versioned<P: copy + drop> has copy, drop {
+        version: u8,
+        payload: P,
+    }
+
+    /// Layout v3 of any payload: v2 with the package the enclave signed for
+    /// after the version byte. Package IDs differ on every network, so a
+    /// payload signed for testnet never verifies on mainnet
+    #[allow(unused_field)]
+    public struct Domain<P: copy + drop> has copy, drop {
+        version: u8,
+        package: address,
+        payload: P,
+    }
+
     // ====== Init Function ======
 
     fun init(_otw: CORE, ctx: &mut TxContext) {
@@ -144,6 +261,16 @@ module ram::core {
     public fun e_wallet_locked(): u64 { EWalletLocked }
     public fun e_wallet_not_linked(): u64 { EWalletNotLinked }
     public fun e_address_not_found(): u64 { EAddressNotFound }
+    public fun e_address_not_linked(): u64 { EAddressNotLinked }
+    public fun e_bioauth_cooldown(): u64 { EBioAuthCooldown }
+    public fun e_daily_limit_exceeded(): u64 { EDailyLimitExceeded }
+    public fun e_wallet_frozen(): u64 { EWalletFrozen }
+    public fun e_not_frozen(): u64 { ENotFrozen }
+    public fun e_inactivity_timeout_too_short(): u64 { EInactivityTimeoutTooShort }
+    public fun e_no_beneficiary(): u64 { ENoBeneficiary }
+    public fun e_still_active(): u64 { EStillActive }
+    public fun e_unsupported_payload_version(): u64 { EUnsupportedPayloadVersion }
+    public fun e_invalid_decoy_seal(): u64 { EInvalidDecoySeal }
 
     // ====== Public Getter Functions for Intent Constants ======
 
@@ -152,12 +279,55 @@ module ram::core {
     public fun transfer_intent(): u8 { TRANSFER_INTENT }
     public fun bioauth_intent(): u8 { BIOAUTH_INTENT }
     public fun withdraw_intent(): u8 { WITHDRAW_INTENT }
+    public fun unlink_address_intent(): u8 { UNLINK_ADDRESS_INTENT }
+    public fun set_limit_intent(): u8 { SET_LIMIT_INTENT }
+    public fun freeze_intent(): u8 { FREEZE_INTENT }
+    public fun unfreeze_intent(): u8 { UNFREEZE_INTENT }
+    public fun beneficiary_intent(): u8 { BENEFICIARY_INTENT }
+    public fun deposit_proof_intent(): u8 { DEPOSIT_PROOF_INTENT }
 
     // ====== Public Getter Functions for BioAuth Results ======
 
     public fun bioauth_ok(): u8 { BIOAUTH_OK }
     public fun bioauth_invalid_amount(): u8 { BIOAUTH_INVALID_AMOUNT }
     public fun bioauth_duress(): u8 { BIOAUTH_DURESS }
+    public fun bioauth_method_voice(): u8 { BIOAUTH_METHOD_VOICE }
+    public fun bioauth_method_typed(): u8 { BIOAUTH_METHOD_TYPED }
+
+    // ====== Public Getter Functions for Payload Versions ======
+
+    public fun payload_v1(): u8 { PAYLOAD_V1 }
+    public fun payload_v2(): u8 { PAYLOAD_V2 }
+    public fun payload_v3(): u8 { PAYLOAD_V3 }
+
+    // ====== Signature Verification ======
+
+    /// Verify an enclave signature over `payload` in the given layout version
+    /// Aborts on a version this package doesn't know
+    public(package) fun verify_payload<E, P: copy + drop>(
+        enclave: &enclave::Enclave<E>,
+        intent: u8,
+        timestamp: u64,
+        payload_version: u8,
+        payload: P,
+        signature: &vector<u8>,
+    ): bool {
+        if (payload_version == PAYLOAD_V1) {
+            enclave.verify_signature(intent, timestamp, payload, signature)
+        } else if (payload_version == PAYLOAD_V2) {
+            enclave.verify_signature(intent, timestamp, Versioned { version: payload_version, payload }, signature)
+        } else {
+            assert!(payload_version == PAYLOAD_V3, EUnsupportedPayloadVersion);
+            let domain = Domain { version: payload_version, package: package_address(), payload };
+            enclave.verify_signature(intent, timestamp, domain, signature)
+        }
+    }
+
+    /// Original ID of this package, unchanged by upgrades
+    public fun package_address(): address {
+        let id = type_name::get_with_original_ids<RAM>().get_address();
+        address::from_ascii_bytes(id.as_bytes())
+    }
 
     // ====== Registry Functions ======
 
@@ -180,8 +350,21 @@ module ram::core {
         wallet.handle
     }
 
-    public fun wallet_linked_address(wallet: &RamWallet): &Option<address> {
-        &wallet.linked_address
+    public fun wallet_linked_addresses(wallet: &RamWallet): &VecMap<address, String> {
+        &wallet.linked_addresses
+    }
+
+    public fun wallet_has_linked_address(wallet: &RamWallet): bool {
+        !wallet.linked_addresses.is_empty()
+    }
+
+    public fun wallet_is_linked(wallet: &RamWallet, addr: address): bool {
+        wallet.linked_addresses.contains(&addr)
+    }
+
+    public fun wallet_address_label(wallet: &RamWallet, addr: address): String {
+        assert!(wallet.linked_addresses.contains(&addr), EAddressNotLinked);
+        *wallet.linked_addresses.get(&addr)
     }
 
     public fun wallet_locked_until(wallet: &RamWallet): u64 {
@@ -204,8 +387,24 @@ module ram::core {
         &mut wallet.balances
     }
 
-    public(package) fun wallet_set_linked_address(wallet: &mut RamWallet, addr: address) {
-        wallet.linked_address.swap_or_fill(addr);
+    /// Link an address, or relabel it if already linked
+    public(package) fun wallet_add_linked_address(wallet: &mut RamWallet, addr: address, label: String) {
+        if (wallet.linked_addresses.contains(&addr)) {
+            *wallet.linked_addresses.get_mut(&addr) = label;
+        } else {
+            wallet.linked_addresses.insert(addr, label);
+        };
+    }
+
+    public(package) fun wallet_remove_linked_address(wallet: &mut RamWallet, addr: address) {
+        assert!(wallet.linked_addresses.contains(&addr), EAddressNotLinked);
+        let (_, _) = wallet.linked_addresses.remove(&addr);
+    }
+
+    /// Assert the sender is one of the wallet's linked addresses
+    public(package) fun assert_linked_sender(wallet: &RamWallet, sender: address) {
+        assert!(wallet_has_linked_address(wallet), EWalletNotLinked);
+        assert!(wallet_is_linked(wallet, sender), ENotOwner);
     }
 
     public(package) fun wallet_set_locked_until(wallet: &mut RamWallet, until_ms: u64) {
@@ -224,9 +423,10 @@ module ram::core {
         now < wallet.locked_until_ms
     }
 
-    /// Assert wallet is not locked (for operations)
+    /// Assert wallet is neither locked nor frozen (for operations)
     public(package) fun assert_wallet_unlocked(wallet: &RamWallet, clock: &Clock) {
         assert!(!is_wallet_locked(wallet, clock), EWalletLocked);
+        assert!(!is_wallet_frozen(wallet, clock), EWalletFrozen);
     }
 
     /// Lock wallet for 24 hours from now
@@ -239,6 +439,309 @@ module ram::core {
         };
     }
 
+    // ====== Duress Decoy ======
+
+    /// Domain of a bio-auth payload's decoy seal (must match the Rust server)
+    const DECOY_SEAL_DOMAIN: vector<u8> = b"RAM_DECOY_SEAL_V1";
+    /// Random bytes before the seal's digest
+    const DECOY_SEAL_NONCE_LEN: u64 = 16;
+
+    /// Open a bio-auth payload's seal: 16 random bytes, then
+    /// `sha2_256(DECOY_SEAL_DOMAIN || nonce || decoy)`. The client only sees
+    /// the bytes, so a decoy reads the same as any other verdict; aborts if
+    /// the seal commits to neither.
+    public(package) fun is_decoy_seal(seal: &vector<u8>): bool {
+        assert!(seal.length() == DECOY_SEAL_NONCE_LEN + 32, EInvalidDecoySeal);
+        let mut nonce = vector[];
+        let mut tag = vector[];
+        let mut i = 0;
+        while (i < seal.length()) {
+            if (i < DECOY_SEAL_NONCE_LEN) nonce.push_back(seal[i]) else tag.push_back(seal[i]);
+            i = i + 1;
+        };
+        if (decoy_seal_digest(nonce, true) == tag) {
+            return true
+        };
+        assert!(decoy_seal_digest(nonce, false) == tag, EInvalidDecoySeal);
+        false
+    }
+
+    fun decoy_seal_digest(nonce: vector<u8>, decoy: bool): vector<u8> {
+        let mut preimage = DECOY_SEAL_DOMAIN;
+        preimage.append(nonce);
+        preimage.push_back(if (decoy) 1 else 0);
+        hash::sha2_256(preimage)
+    }
+
+    /// Dynamic field on a wallet locked by a duress decoy; holds the lock's end
+    public struct DecoyKey has copy, drop, store {}
+
+    /// Mark the current lock as a decoy: signed transfers and withdrawals are
+    /// accepted without moving funds until it ends (call after `lock_wallet`)
+    public(package) fun arm_decoy(wallet: &mut RamWallet) {
+        let until = wallet.locked_until_ms;
+        if (df::exists_(&wallet.id, DecoyKey {})) {
+            *df::borrow_mut<DecoyKey, u64>(&mut wallet.id, DecoyKey {}) = until;
+        } else {
+            df::add(&mut wallet.id, DecoyKey {}, until);
+        };
+    }
+
+    /// True while the wallet is locked by a duress decoy. Package-only: a
+    /// public view would tell a coercer the transfer they watched was a decoy.
+    public(package) fun is_decoy_active(wallet: &RamWallet, clock: &Clock): bool {
+        df::exists_(&wallet.id, DecoyKey {})
+            && is_wallet_locked(wallet, clock)
+            && *df::borrow<DecoyKey, u64>(&wallet.id, DecoyKey {}) == wallet.locked_until_ms
+    }
+
+    // ====== Freeze ======
+
+    /// Dynamic field on a frozen wallet; holds when a requested unfreeze takes
+    /// effect (0 = none requested)
+    public struct FrozenKey has copy, drop, store {}
+
+    /// True while the wallet is frozen and any requested unfreeze is pending
+    public fun is_wallet_frozen(wallet: &RamWallet, clock: &Clock): bool {
+        if (!df::exists_(&wallet.id, FrozenKey {})) {
+            return false
+        };
+        let unfreeze_at = *df::borrow<FrozenKey, u64>(&wallet.id, FrozenKey {});
+        unfreeze_at == 0 || clock::timestamp_ms(clock) < unfreeze_at
+    }
+
+    /// When a requested unfreeze takes effect (0 = not frozen or none requested)
+    public fun wallet_unfreeze_at(wallet: &RamWallet): u64 {
+        if (df::exists_(&wallet.id, FrozenKey {})) {
+            *df::borrow<FrozenKey, u64>(&wallet.id, FrozenKey {})
+        } else {
+            0
+        }
+    }
+
+    /// Freeze until an unfreeze is requested and its delay passes
+    ///
+    /// Freezing again cancels a pending unfreeze.
+    public(package) fun freeze_wallet(wallet: &mut RamWallet) {
+        if (df::exists_(&wallet.id, FrozenKey {})) {
+            *df::borrow_mut<FrozenKey, u64>(&mut wallet.id, FrozenKey {}) = 0;
+        } else {
+            df::add(&mut wallet.id, FrozenKey {}, 0u64);
+        };
+    }
+
+    /// Schedule the unfreeze 24 hours from now and return when it takes effect
+    ///
+    /// A pending unfreeze keeps its time, so asking again can't push it back.
+    public(package) fun request_unfreeze(wallet: &mut RamWallet, clock: &Clock): u64 {
+        assert!(is_wallet_frozen(wallet, clock), ENotFrozen);
+        let unfreeze_at = df::borrow_mut<FrozenKey, u64>(&mut wallet.id, FrozenKey {});
+        if (*unfreeze_at == 0) {
+            *unfreeze_at = clock::timestamp_ms(clock) + UNFREEZE_DELAY_MS;
+        };
+        *unfreeze_at
+    }
+
+    // ====== Inactivity Beneficiary ======
+
+    /// Dynamic field on a wallet with a beneficiary
+    public struct BeneficiaryKey has copy, drop, store {}
+
+    /// Who may claim the wallet's funds, and after how long without activity
+    public struct Beneficiary has store, drop {
+        handle: String,
+        inactivity_timeout_ms: u64,
+        /// Last activity by a linked address signing directly (enclave-signed
+        /// operations count via `last_timestamp`)
+        last_seen_ms: u64,
+    }
+
+    public fun min_inactivity_timeout_ms(): u64 { MIN_INACTIVITY_TIMEOUT_MS }
+
+    public fun has_beneficiary(wallet: &RamWallet): bool {
+        df::exists_(&wallet.id, BeneficiaryKey {})
+    }
+
+    /// The beneficiary's handle (empty = none set)
+    public fun wallet_beneficiary(wallet: &RamWallet): String {
+        if (df::exists_(&wallet.id, BeneficiaryKey {})) {
+            df::borrow<BeneficiaryKey, Beneficiary>(&wallet.id, BeneficiaryKey {}).handle
+        } else {
+            string::utf8(b"")
+        }
+    }
+
+    /// Last owner activity: the latest enclave-signed or directly signed operation
+    public fun wallet_last_active(wallet: &RamWallet): u64 {
+        let mut last = wallet.last_timestamp;
+        if (df::exists_(&wallet.id, BeneficiaryKey {})) {
+            let seen = df::borrow<BeneficiaryKey, Beneficiary>(&wallet.id, BeneficiaryKey {}).last_seen_ms;
+            if (seen > last) {
+                last = seen;
+            };
+        };
+        last
+    }
+
+    /// When the beneficiary may claim, if the owner stays inactive (0 = no beneficiary)
+    public fun wallet_claimable_at(wallet: &RamWallet): u64 {
+        if (!df::exists_(&wallet.id, BeneficiaryKey {})) {
+            return 0
+        };
+        let beneficiary = df::borrow<BeneficiaryKey, Beneficiary>(&wallet.id, BeneficiaryKey {});
+        wallet_last_active(wallet) + beneficiary.inactivity_timeout_ms
+    }
+
+    /// Name a beneficiary, or remove it with an empty handle
+    public(package) fun set_beneficiary(wallet: &mut RamWallet, handle: String, inactivity_timeout_ms: u64) {
+        if (df::exists_(&wallet.id, BeneficiaryKey {})) {
+            let _: Beneficiary = df::remove(&mut wallet.id, BeneficiaryKey {});
+        };
+        if (handle.is_empty()) {
+            return
+        };
+        assert!(inactivity_timeout_ms >= MIN_INACTIVITY_TIMEOUT_MS, EInactivityTimeoutTooShort);
+        df::add(&mut wallet.id, BeneficiaryKey {}, Beneficiary { handle, inactivity_timeout_ms, last_seen_ms: 0 });
+    }
+
+    /// Record that the owner is still around (no-op without a beneficiary)
+    ///
+    /// Called by operations a linked address signs directly; enclave-signed
+    /// ones already count through `last_timestamp`.
+    public(package) fun record_activity(wallet: &mut RamWallet, clock: &Clock) {
+        if (df::exists_(&wallet.id, BeneficiaryKey {})) {
+            df::borrow_mut<BeneficiaryKey, Beneficiary>(&mut wallet.id, BeneficiaryKey {}).last_seen_ms =
+                clock::timestamp_ms(clock);
+        };
+    }
+
+    /// Assert `claimant` is the beneficiary and the owner has been inactive long enough
+    public(package) fun assert_claimable(wallet: &RamWallet, claimant: &RamWallet, clock: &Clock) {
+        assert!(df::exists_(&wallet.id, BeneficiaryKey {}), ENoBeneficiary);
+        let beneficiary = df::borrow<BeneficiaryKey, Beneficiary>(&wallet.id, BeneficiaryKey {});
+        assert!(beneficiary.handle == claimant.handle, ENotOwner);
+        assert!(clock::timestamp_ms(clock) >= wallet_claimable_at(wallet), EStillActive);
+    }
+
+    // ====== Failed BioAuth Cooldowns ======
+
+    /// Dynamic field on a wallet with failed bio-auths since its last success
+    public struct FailuresKey has copy, drop, store {}
+
+    /// Consecutive failed bio-auths and the enclave timestamp of the last one
+    public struct BioAuthFailures has store, drop {
+        count: u8,
+        last_failed_ms: u64,
+    }
+
+    /// Wait required after `failures` consecutive failures
+    public fun bioauth_cooldown_ms(failures: u8): u64 {
+        if (failures <= FREE_FAILED_ATTEMPTS) {
+            0
+        } else if (failures == FREE_FAILED_ATTEMPTS + 1) {
+            COOLDOWN_1_MS
+        } else if (failures == FREE_FAILED_ATTEMPTS + 2) {
+            COOLDOWN_2_MS
+        } else {
+            COOLDOWN_3_MS
+        }
+    }
+
+    /// Consecutive failed bio-auths recorded on-chain
+    public fun wallet_failed_bioauths(wallet: &RamWallet): u8 {
+        if (df::exists_(&wallet.id, FailuresKey {})) {
+            df::borrow<FailuresKey, BioAuthFailures>(&wallet.id, FailuresKey {}).count
+        } else {
+            0
+        }
+    }
+
+    /// Assert a bio-auth signed at `timestamp` comes after the cooldown
+    ///
+    /// Uses the higher of the on-chain count and the enclave's signed count,
+    /// so neither a skipped submission nor an enclave restart resets it.
+    public(package) fun assert_bioauth_cooldown_elapsed(wallet: &RamWallet, failed_attempts: u8, timestamp: u64) {
+        if (!df::exists_(&wallet.id, FailuresKey {})) {
+            return
+        };
+        let failures = df::borrow<FailuresKey, BioAuthFailures>(&wallet.id, FailuresKey {});
+        let count = if (failed_attempts > failures.count) failed_attempts else failures.count;
+        assert!(timestamp >= failures.last_failed_ms + bioauth_cooldown_ms(count), EBioAuthCooldown);
+    }
+
+    /// Count a failed bio-auth signed at `timestamp`
+    public(package) fun record_bioauth_failure(wallet: &mut RamWallet, failed_attempts: u8, timestamp: u64) {
+        let previous = wallet_failed_bioauths(wallet);
+        let count = if (failed_attempts > previous) failed_attempts else previous;
+        let count = if (count == 255) count else count + 1;
+        if (df::exists_(&wallet.id, FailuresKey {})) {
+            let _: BioAuthFailures = df::remove(&mut wallet.id, FailuresKey {});
+        };
+        df::add(&mut wallet.id, FailuresKey {}, BioAuthFailures { count, last_failed_ms: timestamp });
+    }
+
+    /// Forget failures after a successful bio-auth
+    public(package) fun clear_bioauth_failures(wallet: &mut RamWallet) {
+        if (df::exists_(&wallet.id, FailuresKey {})) {
+            let _: BioAuthFailures = df::remove(&mut wallet.id, FailuresKey {});
+        };
+    }
+
+    // ====== Daily Spending Limits ======
+
+    /// Dynamic field on a wallet with a daily limit for one coin type
+    public struct LimitKey has copy, drop, store {
+        coin_type: ascii::String,
+    }
+
+    /// A coin's daily limit and what was spent in the current window
+    public struct DailyLimit has store, drop {
+        limit: u64,
+        window_start_ms: u64,
+        spent: u64,
+    }
+
+    /// Daily limit for a coin type (0 = none set)
+    public fun wallet_daily_limit(wallet: &RamWallet, coin_type: ascii::String): u64 {
+        let key = LimitKey { coin_type };
+        if (df::exists_(&wallet.id, key)) {
+            df::borrow<LimitKey, DailyLimit>(&wallet.id, key).limit
+        } else {
+            0
+        }
+    }
+
+    /// Set a coin type's daily limit
+    ///
+    /// Spending already counted in the current window still counts against
+    /// the new limit.
+    public(package) fun set_daily_limit(wallet: &mut RamWallet, coin_type: ascii::String, limit: u64) {
+        let key = LimitKey { coin_type };
+        if (df::exists_(&wallet.id, key)) {
+            df::borrow_mut<LimitKey, DailyLimit>(&mut wallet.id, key).limit = limit;
+        } else {
+            df::add(&mut wallet.id, key, DailyLimit { limit, window_start_ms: 0, spent: 0 });
+        };
+    }
+
+    /// Count an outgoing `amount` against the coin's daily limit, if any
+    ///
+    /// The window restarts 24 hours after the first spend counted in it.
+    public(package) fun record_spend(wallet: &mut RamWallet, coin_type: ascii::String, amount: u64, clock: &Clock) {
+        let key = LimitKey { coin_type };
+        if (!df::exists_(&wallet.id, key)) {
+            return
+        };
+        let now = clock::timestamp_ms(clock);
+        let daily = df::borrow_mut<LimitKey, DailyLimit>(&mut wallet.id, key);
+        if (now >= daily.window_start_ms + LIMIT_WINDOW_MS) {
+            daily.window_start_ms = now;
+            daily.spent = 0;
+        };
+        assert!(daily.spent <= daily.limit && amount <= daily.limit - daily.spent, EDailyLimitExceeded);
+        daily.spent = daily.spent + amount;
+    }
+
     // ====== Wallet Creation ======
 
     public(package) fun new_wallet(
@@ -249,7 +752,7 @@ module ram::core {
             id: object::new(ctx),
             handle,
             balances: bag::new(ctx),
-            linked_address: option::none(),
+            linked_addresses: vec_map::empty(),
             locked_until_ms: 0,
             last_timestamp: 0,
         }
@@ -261,8 +764,16 @@ module ram::core {
         CreateWalletPayload { handle }
     }
 
-    public(package) fun new_link_address_payload(handle: vector<u8>, address: address): LinkAddressPayload {
-        LinkAddressPayload { handle, address }
+    public(package) fun new_link_address_payload(
+        handle: vector<u8>,
+        address: address,
+        label: vector<u8>,
+    ): LinkAddressPayload {
+        LinkAddressPayload { handle, address, label }
+    }
+
+    public(package) fun new_unlink_address_payload(handle: vector<u8>, address: address): UnlinkAddressPayload {
+        UnlinkAddressPayload { handle, address }
     }
 
     public(package) fun new_transfer_payload(
@@ -270,8 +781,9 @@ module ram::core {
         to_handle: vector<u8>,
         amount: u64,
         coin_type: vector<u8>,
+        memo: vector<u8>,
     ): TransferPayload {
-        TransferPayload { from_handle, to_handle, amount, coin_type }
+        TransferPayload { from_handle, to_handle, amount, coin_type, memo }
     }
 
     public(package) fun new_bioauth_payload(
@@ -279,8 +791,12 @@ module ram::core {
         amount: u64,
         result: u8,
         transcript: vector<u8>,
+        destination_verified: bool,
+        method: u8,
+        seal: vector<u8>,
+        failed_attempts: u8,
     ): BioAuthPayload {
-        BioAuthPayload { handle, amount, result, transcript }
+        BioAuthPayload { handle, amount, result, transcript, destination_verified, method, seal, failed_attempts }
     }
 
     public(package) fun new_withdraw_payload(
@@ -291,6 +807,34 @@ module ram::core {
         WithdrawPayload { handle, amount, coin_type }
     }
 
+    public(package) fun new_set_limit_payload(
+        handle: vector<u8>,
+        daily_limit: u64,
+        coin_type: vector<u8>,
+    ): SetLimitPayload {
+        SetLimitPayload { handle, daily_limit, coin_type }
+    }
+
+    public(package) fun new_freeze_payload(handle: vector<u8>): FreezePayload {
+        FreezePayload { handle }
+    }
+
+    public(package) fun new_unfreeze_payload(handle: vector<u8>): UnfreezePayload {
+        UnfreezePayload { handle }
+    }
+
+    public(package) fun new_beneficiary_payload(
+        handle: vector<u8>,
+        beneficiary_handle: vector<u8>,
+        inactivity_timeout_ms: u64,
+    ): BeneficiaryPayload {
+        BeneficiaryPayload { handle, beneficiary_handle, inactivity_timeout_ms }
+    }
+
+    public(package) fun new_deposit_proof_payload(handle: vector<u8>, deposit_id: vector<u8>): DepositProofPayload {
+        DepositProofPayload { handle, deposit_id }
+    }
+
     // ====== Test-Only Functions ======
 
     #[test_only]
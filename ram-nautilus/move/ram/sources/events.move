@@ -15,10 +15,17 @@ module ram::events {
         wallet_id: ID,
     }
 
-    /// Emitted when an address is linked to a wallet
+    /// Emitted when an address is linked to a wallet (or relabeled)
     public struct AddressLinked has copy, drop {
         handle: String,
         linked_address: address,
+        label: String,
+    }
+
+    /// Emitted when an address is unlinked from a wallet
+    public struct AddressUnlinked has copy, drop {
+        handle: String,
+        unlinked_address: address,
     }
 
     /// Emitted when coins are deposited
@@ -28,6 +35,15 @@ module ram::events {
         amount: u64,
     }
 
+    /// Emitted alongside `Deposited` for a deposit made under an
+    /// enclave-signed deposit ID (an exchange's memo for this handle)
+    public struct DepositProved has copy, drop {
+        handle: String,
+        deposit_id: String,
+        coin_type: String,
+        amount: u64,
+    }
+
     /// Emitted when coins are withdrawn
     public struct Withdrawn has copy, drop {
         handle: String,
@@ -36,11 +52,16 @@ module ram::events {
     }
 
     /// Emitted when coins are transferred between wallets
+    /// `memo` is the sender's reference (e.g. an invoice number), empty if none
+    /// `payload_version` is the layout of the signed transfer payload, 0 when
+    /// the transfer was signed by a linked wallet instead of the enclave
     public struct Transferred has copy, drop {
         from_handle: String,
         to_handle: String,
         coin_type: String,
         amount: u64,
+        memo: String,
+        payload_version: u8,
     }
 
     /// Emitted when a wallet is locked (duress detected or manual)
@@ -54,6 +75,48 @@ module ram::events {
         handle: String,
         amount: u64,
         result: u8, // 0=OK, 1=InvalidAmount, 2=Duress
+        destination_verified: bool,
+        method: u8, // 0=Voice, 1=Typed
+    }
+
+    /// Emitted when a voice-confirmed daily limit is set
+    public struct DailyLimitSet has copy, drop {
+        handle: String,
+        coin_type: String,
+        daily_limit: u64,
+    }
+
+    /// Emitted when a wallet is frozen (self-service, until unfrozen)
+    public struct WalletFrozen has copy, drop {
+        handle: String,
+    }
+
+    /// Emitted when a voice-confirmed unfreeze is accepted; the wallet stays
+    /// frozen until `unfrozen_at_ms`
+    public struct WalletUnfrozen has copy, drop {
+        handle: String,
+        unfrozen_at_ms: u64,
+    }
+
+    /// Emitted when a wallet names, changes or removes (empty handle) its
+    /// inactivity beneficiary
+    public struct BeneficiarySet has copy, drop {
+        handle: String,
+        beneficiary_handle: String,
+        inactivity_timeout_ms: u64,
+    }
+
+    /// Emitted when the owner of a wallet with a beneficiary checks in
+    public struct CheckedIn has copy, drop {
+        handle: String,
+    }
+
+    /// Emitted when a beneficiary claims an inactive wallet's balance of one coin
+    public struct InheritanceClaimed has copy, drop {
+        handle: String,
+        beneficiary_handle: String,
+        coin_type: String,
+        amount: u64,
     }
 
     // ====== Emit Functions ======
@@ -62,14 +125,22 @@ module ram::events {
         event::emit(WalletCreated { handle, wallet_id });
     }
 
-    public(package) fun emit_address_linked(handle: String, linked_address: address) {
-        event::emit(AddressLinked { handle, linked_address });
+    public(package) fun emit_address_linked(handle: String, linked_address: address, label: String) {
+        event::emit(AddressLinked { handle, linked_address, label });
+    }
+
+    public(package) fun emit_address_unlinked(handle: String, unlinked_address: address) {
+        event::emit(AddressUnlinked { handle, unlinked_address });
     }
 
     public(package) fun emit_deposited(handle: String, coin_type: String, amount: u64) {
         event::emit(Deposited { handle, coin_type, amount });
     }
 
+    public(package) fun emit_deposit_proved(handle: String, deposit_id: String, coin_type: String, amount: u64) {
+        event::emit(DepositProved { handle, deposit_id, coin_type, amount });
+    }
+
     public(package) fun emit_withdrawn(handle: String, coin_type: String, amount: u64) {
         event::emit(Withdrawn { handle, coin_type, amount });
     }
@@ -79,15 +150,52 @@ module ram::events {
         to_handle: String,
         coin_type: String,
         amount: u64,
+        memo: String,
+        payload_version: u8,
     ) {
-        event::emit(Transferred { from_handle, to_handle, coin_type, amount });
+        event::emit(Transferred { from_handle, to_handle, coin_type, amount, memo, payload_version });
     }
 
     public(package) fun emit_wallet_locked(handle: String, locked_until_ms: u64) {
         event::emit(WalletLocked { handle, locked_until_ms });
     }
 
-    public(package) fun emit_bioauth_completed(handle: String, amount: u64, result: u8) {
-        event::emit(BioAuthCompleted { handle, amount, result });
+    public(package) fun emit_bioauth_completed(
+        handle: String,
+        amount: u64,
+        result: u8,
+        destination_verified: bool,
+        method: u8,
+    ) {
+        event::emit(BioAuthCompleted { handle, amount, result, destination_verified, method });
+    }
+
+    public(package) fun emit_daily_limit_set(handle: String, coin_type: String, daily_limit: u64) {
+        event::emit(DailyLimitSet { handle, coin_type, daily_limit });
+    }
+
+    public(package) fun emit_wallet_frozen(handle: String) {
+        event::emit(WalletFrozen { handle });
+    }
+
+    public(package) fun emit_wallet_unfrozen(handle: String, unfrozen_at_ms: u64) {
+        event::emit(WalletUnfrozen { handle, unfrozen_at_ms });
+    }
+
+    public(package) fun emit_beneficiary_set(handle: String, beneficiary_handle: String, inactivity_timeout_ms: u64) {
+        event::emit(BeneficiarySet { handle, beneficiary_handle, inactivity_timeout_ms });
+    }
+
+    public(package) fun emit_checked_in(handle: String) {
+        event::emit(CheckedIn { handle });
+    }
+
+    public(package) fun emit_inheritance_claimed(
+        handle: String,
+        beneficiary_handle: String,
+        coin_type: String,
+        amount: u64,
+    ) {
+        event::emit(InheritanceClaimed { handle, beneficiary_handle, coin_type, amount });
     }
 }
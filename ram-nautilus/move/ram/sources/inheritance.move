@@ -0,0 +1,111 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+/// Inactivity beneficiary ("dead man's switch") for RAM wallet
+///
+/// The owner names another wallet's handle and an inactivity timeout (at
+/// least 30 days) with an enclave-signed request. Every signed operation,
+/// and every direct operation by a linked address, counts as activity; an
+/// owner with nothing to do can just `check_in`. Once the wallet has been
+/// inactive for the whole timeout, the beneficiary's linked addresses can
+/// `claim` its balances into the beneficiary wallet, one coin type per call.
+/// The backend warns the owner before that window opens.
+module ram::inheritance {
+    use std::type_name;
+    use sui::balance::Balance;
+    use sui::clock::Clock;
+    use ram::core::{Self, RamWallet};
+    use ram::events;
+    use ram::transfers;
+    use enclave::enclave::Enclave;
+
+    /// Name the wallet's beneficiary, or remove it with an empty handle
+    public fun set_beneficiary<E>(
+        wallet: &mut RamWallet,
+        beneficiary_handle: vector<u8>,
+        inactivity_timeout_ms: u64,
+        payload_version: u8,
+        timestamp: u64,
+        signature: &vector<u8>,
+        enclave: &Enclave<E>,
+    ) {
+        // Verify signature from enclave
+        let payload = core::new_beneficiary_payload(
+            core::wallet_handle(wallet).into_bytes(),
+            beneficiary_handle,
+            inactivity_timeout_ms,
+        );
+        let is_valid = core::verify_payload(
+            enclave,
+            core::beneficiary_intent(),
+            timestamp,
+            payload_version,
+            payload,
+            signature,
+        );
+        assert!(is_valid, core::e_invalid_signature());
+
+        // Check replay
+        assert!(timestamp > core::wallet_last_timestamp(wallet), core::e_replay_attempt());
+        core::wallet_set_last_timestamp(wallet, timestamp);
+
+        let beneficiary_handle = beneficiary_handle.to_string(); // Aborts on invalid UTF-8
+        core::set_beneficiary(wallet, beneficiary_handle, inactivity_timeout_ms);
+
+        // Emit event
+        events::emit_beneficiary_set(
+            core::wallet_handle(wallet),
+            beneficiary_handle,
+            inactivity_timeout_ms,
+        );
+    }
+
+    /// Show the owner is still around, pushing back the claim window
+    public fun check_in(
+        wallet: &mut RamWallet,
+        clock: &Clock,
+        ctx: &TxContext,
+    ) {
+        // Check sender is linked
+        core::assert_linked_sender(wallet, ctx.sender());
+        assert!(core::has_beneficiary(wallet), core::e_no_beneficiary());
+
+        core::record_activity(wallet, clock);
+
+        // Emit event
+        events::emit_checked_in(core::wallet_handle(wallet));
+    }
+
+    /// Move an inactive wallet's whole balance of `T` to its beneficiary
+    ///
+    /// The sender must be linked to the beneficiary wallet. Locked or frozen
+    /// wallets can't be claimed from or into.
+    public fun claim<T>(
+        wallet: &mut RamWallet,
+        beneficiary: &mut RamWallet,
+        clock: &Clock,
+        ctx: &TxContext,
+    ) {
+        core::assert_wallet_unlocked(wallet, clock);
+        core::assert_wallet_unlocked(beneficiary, clock);
+
+        // Check sender is linked to the beneficiary wallet
+        core::assert_linked_sender(beneficiary, ctx.sender());
+        core::assert_claimable(wallet, beneficiary, clock);
+
+        let type_key = type_name::get<T>().into_string();
+        let balances = core::wallet_balances(wallet);
+        assert!(balances.contains(type_key), core::e_insufficient_balance());
+        let amount = balances.borrow<std::ascii::String, Balance<T>>(type_key).value();
+
+        transfers::transfer_internal<T>(wallet, beneficiary, amount);
+
+        // Emit event
+        events::emit_inheritance_claimed(
+            core::wallet_handle(wallet),
+            core::wallet_handle(beneficiary),
+            type_key.to_string(),
+            amount,
+        );
+    }
+}
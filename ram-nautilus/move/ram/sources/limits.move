@@ -0,0 +1,59 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+/// Daily spending limits for RAM wallet
+///
+/// The user sets a standing limit by voice ("set my daily limit to 50 SUI");
+/// the enclave signs it only once the spoken amount matches and the voice
+/// shows no stress. Every outgoing transfer and withdrawal of that coin then
+/// counts against the limit for 24 hours.
+module ram::limits {
+    use std::type_name;
+    use ram::core::{Self, RamWallet};
+    use ram::events;
+    use enclave::enclave::Enclave;
+
+    /// Set the wallet's daily limit for coin `T`
+    public fun set_daily_limit<T, E>(
+        wallet: &mut RamWallet,
+        daily_limit: u64,
+        coin_type: vector<u8>,
+        payload_version: u8,
+        timestamp: u64,
+        signature: &vector<u8>,
+        enclave: &Enclave<E>,
+    ) {
+        // Verify coin type matches generic T
+        let type_key = type_name::get<T>().into_string();
+        assert!(coin_type == type_key.into_bytes(), 100); // ECoinTypeMismatch
+
+        // Verify signature from enclave
+        let payload = core::new_set_limit_payload(
+            core::wallet_handle(wallet).into_bytes(),
+            daily_limit,
+            coin_type,
+        );
+        let is_valid = core::verify_payload(
+            enclave,
+            core::set_limit_intent(),
+            timestamp,
+            payload_version,
+            payload,
+            signature,
+        );
+        assert!(is_valid, core::e_invalid_signature());
+
+        // Check replay
+        assert!(timestamp > core::wallet_last_timestamp(wallet), core::e_replay_attempt());
+        core::wallet_set_last_timestamp(wallet, timestamp);
+
+        core::set_daily_limit(wallet, type_key, daily_limit);
+
+        // Emit event
+        events::emit_daily_limit_set(
+            core::wallet_handle(wallet),
+            type_key.to_string(),
+            daily_limit,
+        );
+    }
+}
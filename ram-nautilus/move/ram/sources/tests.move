@@ -61,7 +61,8 @@ module ram::tests {
 
             assert!(core::wallet_handle(&wallet) == b"alice_handle".to_string());
             assert!(core::wallet_locked_until(&wallet) == 0);
-            assert!(core::wallet_linked_address(&wallet).is_some());
+            assert!(core::wallet_is_linked(&wallet, ALICE));
+            assert!(core::wallet_address_label(&wallet, ALICE) == b"default".to_string());
 
             ts::return_shared(wallet);
         };
@@ -154,6 +155,110 @@ module ram::tests {
         ts::end(scenario);
     }
 
+    #[test]
+    fun test_decoy_active_only_for_its_lock() {
+        let mut scenario = ts::begin(ALICE);
+        setup_test(&mut scenario);
+
+        create_test_wallet(&mut scenario, ALICE, b"alice");
+
+        ts::next_tx(&mut scenario, ALICE);
+        {
+            let mut wallet = ts::take_shared<RamWallet>(&scenario);
+            let mut clock = create_clock(&mut scenario, 1000);
+
+            core::lock_wallet(&mut wallet, &clock);
+            assert!(!core::is_decoy_active(&wallet, &clock));
+            core::arm_decoy(&mut wallet);
+            assert!(core::is_decoy_active(&wallet, &clock));
+
+            // A later genuine duress lock is no longer a decoy
+            clock::set_for_testing(&mut clock, 2000);
+            core::lock_wallet(&mut wallet, &clock);
+            assert!(!core::is_decoy_active(&wallet, &clock));
+
+            // Nor is an expired lock
+            core::arm_decoy(&mut wallet);
+            clock::set_for_testing(&mut clock, 2000 + 86_400_000);
+            assert!(!core::is_decoy_active(&wallet, &clock));
+
+            clock::destroy_for_testing(clock);
+            ts::return_shared(wallet);
+        };
+
+        ts::end(scenario);
+    }
+
+    #[test]
+    fun test_bioauth_cooldown_schedule() {
+        assert!(core::bioauth_cooldown_ms(2) == 0);
+        assert!(core::bioauth_cooldown_ms(3) == 60_000);
+        assert!(core::bioauth_cooldown_ms(4) == 300_000);
+        assert!(core::bioauth_cooldown_ms(9) == 3_600_000);
+    }
+
+    #[test]
+    fun test_bioauth_failures_recorded_and_cleared() {
+        let mut scenario = ts::begin(ALICE);
+        setup_test(&mut scenario);
+
+        create_test_wallet(&mut scenario, ALICE, b"alice");
+
+        ts::next_tx(&mut scenario, ALICE);
+        {
+            let mut wallet = ts::take_shared<RamWallet>(&scenario);
+
+            // The enclave's signed count wins over a lower on-chain count
+            core::record_bioauth_failure(&mut wallet, 0, 1000);
+            core::record_bioauth_failure(&mut wallet, 2, 2000);
+            assert!(core::wallet_failed_bioauths(&wallet) == 3);
+            core::assert_bioauth_cooldown_elapsed(&wallet, 3, 2000 + 60_000);
+
+            core::clear_bioauth_failures(&mut wallet);
+            assert!(core::wallet_failed_bioauths(&wallet) == 0);
+            core::assert_bioauth_cooldown_elapsed(&wallet, 0, 0);
+
+            ts::return_shared(wallet);
+        };
+
+        ts::end(scenario);
+    }
+
+    #[test]
+    #[expected_failure(abort_code = core::EBioAuthCooldown)]
+    fun test_bioauth_during_cooldown_fails() {
+        let mut scenario = ts::begin(ALICE);
+        setup_test(&mut scenario);
+
+        create_test_wallet(&mut scenario, ALICE, b"alice");
+
+        ts::next_tx(&mut scenario, ALICE);
+        {
+            let mut wallet = ts::take_shared<RamWallet>(&scenario);
+
+            core::record_bioauth_failure(&mut wallet, 2, 1000);
+            // Third failure: one minute cooldown
+            core::assert_bioauth_cooldown_elapsed(&wallet, 3, 1000 + 59_999);
+
+            ts::return_shared(wallet);
+        };
+
+        ts::end(scenario);
+    }
+
+    #[test]
+    fun test_decoy_seal_vector() {
+        // Same vectors as the enclave's decoy::tests::test_seal_vector_matches_move
+        assert!(core::is_decoy_seal(&x"07070707070707070707070707070707ba5b4229accca50720c102a3a3385944ea968edbca0c8cc9a240fa4ee48ba4e5"));
+        assert!(!core::is_decoy_seal(&x"07070707070707070707070707070707d860d666256b38d1f7ffe9ed495e3b645e5981096e6982bcaeafb4ccb37dc086"));
+    }
+
+    #[test]
+    #[expected_failure(abort_code = core::EInvalidDecoySeal)]
+    fun test_decoy_seal_rejects_tampering() {
+        core::is_decoy_seal(&x"07070707070707070707070707070707ba5b4229accca50720c102a3a3385944ea968edbca0c8cc9a240fa4ee48ba4e4");
+    }
+
     // ====== Deposit Tests ======
 
     #[test]
@@ -247,4 +352,237 @@ module ram::tests {
 
         ts::end(scenario);
     }
+
+    // ====== Multi-Address Linking Tests ======
+
+    #[test]
+    fun test_link_multiple_labeled_addresses() {
+        let mut scenario = ts::begin(ALICE);
+        setup_test(&mut scenario);
+
+        create_test_wallet(&mut scenario, ALICE, b"alice");
+
+        ts::next_tx(&mut scenario, ALICE);
+        {
+            let mut wallet = ts::take_shared<RamWallet>(&scenario);
+
+            core::wallet_add_linked_address(&mut wallet, BOB, b"ledger".to_string());
+            assert!(core::wallet_linked_addresses(&wallet).size() == 2);
+            assert!(core::wallet_address_label(&wallet, BOB) == b"ledger".to_string());
+
+            // Re-linking relabels instead of duplicating
+            core::wallet_add_linked_address(&mut wallet, BOB, b"phone".to_string());
+            assert!(core::wallet_linked_addresses(&wallet).size() == 2);
+            assert!(core::wallet_address_label(&wallet, BOB) == b"phone".to_string());
+
+            // Unlinking one address keeps the other
+            core::wallet_remove_linked_address(&mut wallet, ALICE);
+            assert!(!core::wallet_is_linked(&wallet, ALICE));
+            assert!(core::wallet_is_linked(&wallet, BOB));
+
+            ts::return_shared(wallet);
+        };
+
+        ts::end(scenario);
+    }
+
+    #[test]
+    #[expected_failure(abort_code = core::EAddressNotLinked, location = core)]
+    fun test_unlink_unknown_address_fails() {
+        let mut scenario = ts::begin(ALICE);
+        setup_test(&mut scenario);
+
+        create_test_wallet(&mut scenario, ALICE, b"alice");
+
+        ts::next_tx(&mut scenario, ALICE);
+        {
+            let mut wallet = ts::take_shared<RamWallet>(&scenario);
+            core::wallet_remove_linked_address(&mut wallet, BOB);
+            ts::return_shared(wallet);
+        };
+
+        ts::end(scenario);
+    }
+
+    // ====== Daily Limit Tests ======
+
+    #[test]
+    fun test_daily_limit_window() {
+        let mut scenario = ts::begin(ALICE);
+        setup_test(&mut scenario);
+
+        create_test_wallet(&mut scenario, ALICE, b"alice");
+
+        ts::next_tx(&mut scenario, ALICE);
+        {
+            let mut wallet = ts::take_shared<RamWallet>(&scenario);
+            let sui = std::type_name::get<SUI>().into_string();
+            let mut clock = create_clock(&mut scenario, 1000);
+
+            // No limit: anything goes
+            assert!(core::wallet_daily_limit(&wallet, sui) == 0);
+            core::record_spend(&mut wallet, sui, 1_000_000, &clock);
+
+            core::set_daily_limit(&mut wallet, sui, 50);
+            assert!(core::wallet_daily_limit(&wallet, sui) == 50);
+            core::record_spend(&mut wallet, sui, 30, &clock);
+            core::record_spend(&mut wallet, sui, 20, &clock);
+
+            // A new window starts 24 hours after the first counted spend
+            clock::set_for_testing(&mut clock, 1000 + 86_400_000);
+            core::record_spend(&mut wallet, sui, 50, &clock);
+
+            clock::destroy_for_testing(clock);
+            ts::return_shared(wallet);
+        };
+
+        ts::end(scenario);
+    }
+
+    #[test]
+    #[expected_failure(abort_code = core::EDailyLimitExceeded, location = core)]
+    fun test_daily_limit_exceeded() {
+        let mut scenario = ts::begin(ALICE);
+        setup_test(&mut scenario);
+
+        create_test_wallet(&mut scenario, ALICE, b"alice");
+
+        ts::next_tx(&mut scenario, ALICE);
+        {
+            let mut wallet = ts::take_shared<RamWallet>(&scenario);
+            let sui = std::type_name::get<SUI>().into_string();
+            let clock = create_clock(&mut scenario, 1000);
+
+            core::set_daily_limit(&mut wallet, sui, 50);
+            core::record_spend(&mut wallet, sui, 30, &clock);
+            // Lowering the limit below what was spent blocks the rest of the window
+            core::set_daily_limit(&mut wallet, sui, 20);
+            core::record_spend(&mut wallet, sui, 1, &clock);
+
+            clock::destroy_for_testing(clock);
+            ts::return_shared(wallet);
+        };
+
+        ts::end(scenario);
+    }
+
+    // ====== Freeze Tests ======
+
+    #[test]
+    fun test_freeze_and_delayed_unfreeze() {
+        let mut scenario = ts::begin(ALICE);
+        setup_test(&mut scenario);
+
+        create_test_wallet(&mut scenario, ALICE, b"alice");
+
+        ts::next_tx(&mut scenario, ALICE);
+        {
+            let mut wallet = ts::take_shared<RamWallet>(&scenario);
+            let mut clock = create_clock(&mut scenario, 1000);
+
+            core::freeze_wallet(&mut wallet);
+            assert!(core::is_wallet_frozen(&wallet, &clock));
+
+            // The unfreeze waits 24 hours, and asking again doesn't move it
+            assert!(core::request_unfreeze(&mut wallet, &clock) == 1000 + 86_400_000);
+            clock::set_for_testing(&mut clock, 5000);
+            assert!(core::request_unfreeze(&mut wallet, &clock) == 1000 + 86_400_000);
+            assert!(core::is_wallet_frozen(&wallet, &clock));
+
+            // Freezing again cancels the pending unfreeze
+            core::freeze_wallet(&mut wallet);
+            assert!(core::wallet_unfreeze_at(&wallet) == 0);
+            assert!(core::request_unfreeze(&mut wallet, &clock) == 5000 + 86_400_000);
+
+            clock::set_for_testing(&mut clock, 5000 + 86_400_000);
+            assert!(!core::is_wallet_frozen(&wallet, &clock));
+            let coin = coin::mint_for_testing<SUI>(1000000, ts::ctx(&mut scenario));
+            wallet::deposit<SUI>(&mut wallet, coin, &clock);
+
+            clock::destroy_for_testing(clock);
+            ts::return_shared(wallet);
+        };
+
+        ts::end(scenario);
+    }
+
+    #[test]
+    #[expected_failure(abort_code = core::EWalletFrozen)]
+    fun test_deposit_blocked_when_frozen() {
+        let mut scenario = ts::begin(ALICE);
+        setup_test(&mut scenario);
+
+        create_test_wallet(&mut scenario, ALICE, b"alice");
+
+        ts::next_tx(&mut scenario, ALICE);
+        {
+            let mut wallet = ts::take_shared<RamWallet>(&scenario);
+            let clock = create_clock(&mut scenario, 3000);
+            core::freeze_wallet(&mut wallet);
+
+            let coin = coin::mint_for_testing<SUI>(1000000, ts::ctx(&mut scenario));
+            wallet::deposit<SUI>(&mut wallet, coin, &clock);
+
+            clock::destroy_for_testing(clock);
+            ts::return_shared(wallet);
+        };
+
+        ts::end(scenario);
+    }
+
+    // ====== Inactivity Beneficiary Tests ======
+
+    #[test]
+    fun test_beneficiary_claim_window_follows_activity() {
+        let mut scenario = ts::begin(ALICE);
+        setup_test(&mut scenario);
+
+        create_test_wallet(&mut scenario, ALICE, b"alice");
+
+        ts::next_tx(&mut scenario, ALICE);
+        {
+            let mut wallet = ts::take_shared<RamWallet>(&scenario);
+            let mut clock = create_clock(&mut scenario, 1000);
+            let timeout = core::min_inactivity_timeout_ms();
+            assert!(core::wallet_claimable_at(&wallet) == 0);
+
+            core::wallet_set_last_timestamp(&mut wallet, 1000);
+            core::set_beneficiary(&mut wallet, b"bob".to_string(), timeout);
+            assert!(core::wallet_beneficiary(&wallet) == b"bob".to_string());
+            assert!(core::wallet_claimable_at(&wallet) == 1000 + timeout);
+
+            // Activity pushes the window back
+            clock::set_for_testing(&mut clock, 9000);
+            core::record_activity(&mut wallet, &clock);
+            assert!(core::wallet_claimable_at(&wallet) == 9000 + timeout);
+
+            // An empty handle removes the beneficiary
+            core::set_beneficiary(&mut wallet, b"".to_string(), 0);
+            assert!(!core::has_beneficiary(&wallet));
+            assert!(core::wallet_claimable_at(&wallet) == 0);
+
+            clock::destroy_for_testing(clock);
+            ts::return_shared(wallet);
+        };
+
+        ts::end(scenario);
+    }
+
+    #[test]
+    #[expected_failure(abort_code = core::EInactivityTimeoutTooShort)]
+    fun test_beneficiary_timeout_too_short() {
+        let mut scenario = ts::begin(ALICE);
+        setup_test(&mut scenario);
+
+        create_test_wallet(&mut scenario, ALICE, b"alice");
+
+        ts::next_tx(&mut scenario, ALICE);
+        {
+            let mut wallet = ts::take_shared<RamWallet>(&scenario);
+            core::set_beneficiary(&mut wallet, b"bob".to_string(), 86_400_000);
+            ts::return_shared(wallet);
+        };
+
+        ts::end(scenario);
+    }
 }
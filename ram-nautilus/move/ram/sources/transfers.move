@@ -5,6 +5,7 @@
 /// Handles transfers between wallets (by handle or direct)
 module ram::transfers {
     use std::ascii;
+    use std::string;
     use std::type_name;
     use sui::balance::Balance;
     use sui::clock::Clock;
@@ -16,19 +17,28 @@ module ram::transfers {
 
     /// Transfer coins between wallets with enclave signature verification
     /// Only this transfer function requires a signature param
+    /// `memo` is the signed reference carried into the Transferred event
+    /// `payload_version` is the layout the enclave signed (see `core::verify_payload`)
     public fun transfer_with_signature<T, E>(
         from: &mut RamWallet,
         to: &mut RamWallet,
         amount: u64,
         coin_type: vector<u8>,
+        memo: vector<u8>,
+        payload_version: u8,
         timestamp: u64,
         signature: &vector<u8>,
         enclave: &Enclave<E>,
         clock: &Clock,
     ) {
+        // A duress decoy lets the transfer "succeed" below without moving funds
+        let decoy = core::is_decoy_active(from, clock);
+
         // Check both wallets not locked
-        core::assert_wallet_unlocked(from, clock);
-        core::assert_wallet_unlocked(to, clock);
+        if (!decoy) {
+            core::assert_wallet_unlocked(from, clock);
+            core::assert_wallet_unlocked(to, clock);
+        };
 
         // Verify coin type matches generic T
         let expected_type = type_name::get<T>().into_string().into_bytes();
@@ -40,10 +50,13 @@ module ram::transfers {
             core::wallet_handle(to).into_bytes(),
             amount,
             coin_type,
+            memo,
         );
-        let is_valid = enclave.verify_signature(
+        let is_valid = core::verify_payload(
+            enclave,
             core::transfer_intent(),
             timestamp,
+            payload_version,
             payload,
             signature,
         );
@@ -53,6 +66,13 @@ module ram::transfers {
         assert!(timestamp > core::wallet_last_timestamp(from), core::e_replay_attempt());
         core::wallet_set_last_timestamp(from, timestamp);
 
+        if (decoy) {
+            return
+        };
+
+        // Count against the sender's daily limit
+        core::record_spend(from, type_name::get<T>().into_string(), amount, clock);
+
         // Execute transfer
         transfer_internal<T>(from, to, amount);
 
@@ -62,6 +82,8 @@ module ram::transfers {
             core::wallet_handle(to),
             type_name::get<T>().into_string().to_string(),
             amount,
+            string::utf8(memo), // Aborts on invalid UTF-8
+            payload_version,
         );
     }
 
@@ -81,8 +103,11 @@ module ram::transfers {
         core::assert_wallet_unlocked(to, clock);
 
         // Check sender is linked to source wallet
-        assert!(core::wallet_linked_address(from).is_some(), core::e_wallet_not_linked());
-        assert!(ctx.sender() == *core::wallet_linked_address(from).borrow(), core::e_not_owner());
+        core::assert_linked_sender(from, ctx.sender());
+        core::record_activity(from, clock);
+
+        // Count against the sender's daily limit
+        core::record_spend(from, type_name::get<T>().into_string(), amount, clock);
 
         // Execute transfer
         transfer_internal<T>(from, to, amount);
@@ -93,12 +118,14 @@ module ram::transfers {
             core::wallet_handle(to),
             type_name::get<T>().into_string().to_string(),
             amount,
+            string::utf8(b""),
+            0,
         );
     }
 
     // ====== Internal Helper ======
 
-    fun transfer_internal<T>(
+    public(package) fun transfer_internal<T>(
         from: &mut RamWallet,
         to: &mut RamWallet,
         amount: u64,
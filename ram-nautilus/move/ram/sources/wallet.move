@@ -8,18 +8,22 @@ module ram::wallet {
     use std::ascii;
     use std::type_name;
     use sui::balance::Balance;
-    use sui::coin::Coin;
+    use sui::coin::{Self, Coin};
     use sui::clock::Clock;
     use ram::core::{Self, RamRegistry, RamWallet};
     use ram::events;
     use enclave::enclave::Enclave;
 
+    /// Label given to the address linked at wallet creation
+    const DEFAULT_LABEL: vector<u8> = b"default";
+
     // ====== Wallet Creation ======
 
     /// Create a new RAM wallet with enclave signature verification
     public fun create_wallet<T>(
         registry: &mut RamRegistry,
         handle: vector<u8>,
+        payload_version: u8,
         timestamp: u64,
         signature: &vector<u8>,
         enclave: &Enclave<T>,
@@ -33,9 +37,11 @@ module ram::wallet {
 
         // Verify signature from enclave
         let payload = core::new_create_wallet_payload(handle);
-        let is_valid = enclave.verify_signature(
+        let is_valid = core::verify_payload(
+            enclave,
             core::create_wallet_intent(),
             timestamp,
+            payload_version,
             payload,
             signature,
         );
@@ -76,11 +82,11 @@ module ram::wallet {
         core::registry_add_address(registry, sender_addr, wallet_id);
 
         // Automatically link the creator's address to the wallet
-        core::wallet_set_linked_address(&mut wallet, sender_addr);
+        core::wallet_add_linked_address(&mut wallet, sender_addr, DEFAULT_LABEL.to_string());
 
         // Emit events
         events::emit_wallet_created(handle_str, wallet_id);
-        events::emit_address_linked(handle_str, sender_addr);
+        events::emit_address_linked(handle_str, sender_addr, DEFAULT_LABEL.to_string());
 
         // Share wallet so it can be accessed in transfers
         transfer::public_share_object(wallet);
@@ -108,11 +114,11 @@ module ram::wallet {
         core::registry_add_address(registry, target_address, wallet_id);
 
         // Automatically link to target address
-        core::wallet_set_linked_address(&mut wallet, target_address);
+        core::wallet_add_linked_address(&mut wallet, target_address, DEFAULT_LABEL.to_string());
 
         // Emit events
         events::emit_wallet_created(handle_str, wallet_id);
-        events::emit_address_linked(handle_str, target_address);
+        events::emit_address_linked(handle_str, target_address, DEFAULT_LABEL.to_string());
 
         // Share wallet so it can be accessed in transfers
         transfer::public_share_object(wallet);
@@ -121,9 +127,13 @@ module ram::wallet {
     // ====== Address Linking ======
 
     /// Link a Sui wallet address to RAM wallet (with signature)
+    /// A wallet can hold several labeled addresses; linking an already linked
+    /// address updates its label.
     public fun link_address<T>(
         wallet: &mut RamWallet,
         address: address,
+        label: vector<u8>,
+        payload_version: u8,
         timestamp: u64,
         signature: &vector<u8>,
         enclave: &Enclave<T>,
@@ -132,10 +142,13 @@ module ram::wallet {
         let payload = core::new_link_address_payload(
             core::wallet_handle(wallet).into_bytes(),
             address,
+            label,
         );
-        let is_valid = enclave.verify_signature(
+        let is_valid = core::verify_payload(
+            enclave,
             core::link_address_intent(),
             timestamp,
+            payload_version,
             payload,
             signature,
         );
@@ -146,15 +159,50 @@ module ram::wallet {
         core::wallet_set_last_timestamp(wallet, timestamp);
 
         // Link address
-        core::wallet_set_linked_address(wallet, address);
+        let label_str = string::utf8(label);
+        core::wallet_add_linked_address(wallet, address, label_str);
 
         // Emit event
-        events::emit_address_linked(core::wallet_handle(wallet), address);
+        events::emit_address_linked(core::wallet_handle(wallet), address, label_str);
+    }
+
+    /// Unlink a Sui wallet address from RAM wallet (with signature)
+    public fun unlink_address<T>(
+        wallet: &mut RamWallet,
+        address: address,
+        payload_version: u8,
+        timestamp: u64,
+        signature: &vector<u8>,
+        enclave: &Enclave<T>,
+    ) {
+        // Verify signature from enclave
+        let payload = core::new_unlink_address_payload(
+            core::wallet_handle(wallet).into_bytes(),
+            address,
+        );
+        let is_valid = core::verify_payload(
+            enclave,
+            core::unlink_address_intent(),
+            timestamp,
+            payload_version,
+            payload,
+            signature,
+        );
+        assert!(is_valid, core::e_invalid_signature());
+
+        // Check replay
+        assert!(timestamp > core::wallet_last_timestamp(wallet), core::e_replay_attempt());
+        core::wallet_set_last_timestamp(wallet, timestamp);
+
+        // Unlink address
+        core::wallet_remove_linked_address(wallet, address);
+
+        // Emit event
+        events::emit_address_unlinked(core::wallet_handle(wallet), address);
     }
 
     // ====== Deposit Functions ======
 
-    /// Deposit coins into wallet (anyone can deposit, but wallet must be unlocked)
     /// Deposit coins into wallet (anyone can deposit, but wallet must be unlocked)
     public fun deposit<T>(
         wallet: &mut RamWallet,
@@ -164,6 +212,53 @@ module ram::wallet {
         // Check wallet not locked
         core::assert_wallet_unlocked(wallet, clock);
 
+        let (_, _) = deposit_internal(wallet, coin);
+    }
+
+    /// Deposit coins under an enclave-signed deposit ID, so an exchange can
+    /// credit the deposit to this handle by its own memo
+    ///
+    /// The proof must name this wallet's handle. It only ever adds funds, so
+    /// one proof serves every deposit under its ID and isn't subject to the
+    /// wallet's replay check.
+    public fun deposit_with_proof<T, E>(
+        wallet: &mut RamWallet,
+        coin: Coin<T>,
+        deposit_id: vector<u8>,
+        payload_version: u8,
+        timestamp: u64,
+        signature: &vector<u8>,
+        enclave: &Enclave<E>,
+        clock: &Clock,
+    ) {
+        // Check wallet not locked
+        core::assert_wallet_unlocked(wallet, clock);
+
+        // Verify signature from enclave
+        let payload = core::new_deposit_proof_payload(core::wallet_handle(wallet).into_bytes(), deposit_id);
+        let is_valid = core::verify_payload(
+            enclave,
+            core::deposit_proof_intent(),
+            timestamp,
+            payload_version,
+            payload,
+            signature,
+        );
+        assert!(is_valid, core::e_invalid_signature());
+
+        let (coin_type, amount) = deposit_internal(wallet, coin);
+
+        // Emit event
+        events::emit_deposit_proved(
+            core::wallet_handle(wallet),
+            deposit_id.to_string(), // Aborts on invalid UTF-8
+            coin_type,
+            amount,
+        );
+    }
+
+    /// Add `coin` to the wallet's balance of `T` and emit `Deposited`
+    fun deposit_internal<T>(wallet: &mut RamWallet, coin: Coin<T>): (String, u64) {
         let type_key = type_name::get<T>().into_string();
         let amount = coin.value();
         let balance = coin.into_balance();
@@ -177,19 +272,23 @@ module ram::wallet {
         };
 
         // Emit event
+        let coin_type = type_key.to_string();
         events::emit_deposited(
             core::wallet_handle(wallet),
-            type_key.to_string(),
+            coin_type,
             amount,
         );
+        (coin_type, amount)
     }
 
     /// Withdraw coins from wallet (owner only, wallet must be unlocked)
     /// Requires enclave signature verification
+    /// While a duress decoy is active, returns an empty coin instead of failing
     public fun withdraw<T, E>(
         wallet: &mut RamWallet,
         amount: u64,
         coin_type: vector<u8>,
+        payload_version: u8,
         timestamp: u64,
         signature: &vector<u8>,
         enclave: &Enclave<E>,
@@ -197,11 +296,14 @@ module ram::wallet {
         ctx: &mut TxContext,
     ): Coin<T> {
         // Check wallet not locked
-        core::assert_wallet_unlocked(wallet, clock);
+        let decoy = core::is_decoy_active(wallet, clock);
+        if (!decoy) {
+            core::assert_wallet_unlocked(wallet, clock);
+        };
 
-        // Check linked address
-        assert!(core::wallet_linked_address(wallet).is_some(), core::e_wallet_not_linked());
-        assert!(ctx.sender() == *core::wallet_linked_address(wallet).borrow(), core::e_not_owner());
+        // Check sender is a linked address
+        core::assert_linked_sender(wallet, ctx.sender());
+        core::record_activity(wallet, clock);
 
         // Verify coin type matches
         let expected_type = type_name::get<T>().into_string().into_bytes();
@@ -213,15 +315,25 @@ module ram::wallet {
             amount,
             coin_type,
         );
-        let is_valid = enclave.verify_signature(
+        let is_valid = core::verify_payload(
+            enclave,
             core::withdraw_intent(),
             timestamp,
+            payload_version,
             payload,
             signature,
         );
         assert!(is_valid, core::e_invalid_signature());
 
+        if (decoy) {
+            return coin::zero<T>(ctx)
+        };
+
         let type_key = type_name::get<T>().into_string();
+
+        // Count against the wallet's daily limit
+        core::record_spend(wallet, type_key, amount, clock);
+
         let balances = core::wallet_balances_mut(wallet);
 
         // Check balance exists and is sufficient
@@ -1,5 +1,9 @@
 #!/bin/bash
 
+# For RAM, the backend's POST /admin/register_enclave prepares this registration
+# (checking the attested PCRs and updating them if asked) as one transaction to
+# sign; see ram-backend/README.md.
+
 # Check if both arguments are provided
 if [ "$#" -ne 6 ]; then
     echo "Usage: $0 <enclave_package_id> <app_package_id> <enclave_config_id> <enclave_url> <module_name> <otw_name>"
@@ -0,0 +1,138 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! DSP pipeline benchmarks: WAV parsing, feature extraction, per-frame
+//! autocorrelation pitch and full stress analysis on 1-15 s clips at the
+//! two sample rates clients record at (16 kHz and 44.1 kHz).
+//!
+//! ```bash
+//! cargo bench --bench dsp
+//! ```
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use nautilus_server::ram_app::{analyze_voice_stress, decode_wav_base64, extract_features, parse_wav, Autocorrelator};
+
+const SAMPLE_RATES: [u32; 2] = [16_000, 44_100];
+const DURATIONS_SECS: [u32; 4] = [1, 5, 10, 15];
+
+/// Voiced-speech stand-in: a 150 Hz tone with harmonics, vibrato and syllable-rate loudness swings
+fn voice(sample_rate: u32, secs: u32) -> Vec<f32> {
+    let mut phase = 0.0f64;
+    (0..sample_rate * secs)
+        .map(|i| {
+            let t = i as f64 / sample_rate as f64;
+            let freq = 150.0 + 20.0 * (2.0 * std::f64::consts::PI * 6.0 * t).sin();
+            phase += 2.0 * std::f64::consts::PI * freq / sample_rate as f64;
+            let amp = 0.4 + 0.2 * (2.0 * std::f64::consts::PI * 4.0 * t).sin();
+            (amp * (phase.sin() + 0.4 * (2.0 * phase).sin() + 0.2 * (3.0 * phase).sin()) / 1.6) as f32
+        })
+        .collect()
+}
+
+/// 16-bit mono PCM WAV
+fn wav(sample_rate: u32, samples: &[f32]) -> Vec<u8> {
+    let data_size = (samples.len() * 2) as u32;
+    let mut wav = Vec::with_capacity(44 + samples.len() * 2);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_size).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&sample_rate.to_le_bytes());
+    wav.extend_from_slice(&(sample_rate * 2).to_le_bytes());
+    wav.extend_from_slice(&2u16.to_le_bytes());
+    wav.extend_from_slice(&16u16.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_size.to_le_bytes());
+    for &s in samples {
+        wav.extend_from_slice(&((s.clamp(-1.0, 1.0) * 32767.0) as i16).to_le_bytes());
+    }
+    wav
+}
+
+fn clips() -> impl Iterator<Item = (String, u32, Vec<f32>)> {
+    SAMPLE_RATES.into_iter().flat_map(|rate| {
+        DURATIONS_SECS
+            .into_iter()
+            .map(move |secs| (format!("{}s@{}Hz", secs, rate), rate, voice(rate, secs)))
+    })
+}
+
+fn bench_parse_wav(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse_wav");
+    for (id, rate, samples) in clips() {
+        let bytes = wav(rate, &samples);
+        group.throughput(Throughput::Bytes(bytes.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(id), &bytes, |b, bytes| {
+            b.iter(|| parse_wav(black_box(bytes)))
+        });
+    }
+    group.finish();
+}
+
+fn bench_decode_wav_base64(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decode_wav_base64");
+    for (id, rate, samples) in clips() {
+        let encoded = STANDARD.encode(wav(rate, &samples));
+        group.throughput(Throughput::Bytes(encoded.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(id), &encoded, |b, encoded| {
+            b.iter(|| decode_wav_base64(black_box(encoded)))
+        });
+    }
+    group.finish();
+}
+
+fn bench_extract_features(c: &mut Criterion) {
+    let mut group = c.benchmark_group("extract_features");
+    group.sample_size(20);
+    for (id, rate, samples) in clips() {
+        group.throughput(Throughput::Elements(samples.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(id), &samples, |b, samples| {
+            b.iter(|| extract_features(black_box(samples), rate))
+        });
+    }
+    group.finish();
+}
+
+fn bench_autocorrelation_pitch(c: &mut Criterion) {
+    // One 40 ms analysis frame, searched over the 80-400 Hz pitch range
+    let mut group = c.benchmark_group("autocorrelation_pitch");
+    for rate in SAMPLE_RATES {
+        let frame_size = rate as usize / 25;
+        let (min_lag, max_lag) = (rate as usize / 400, rate as usize / 80);
+        let frames = voice(rate, 1);
+        let (first, second) = (&frames[..frame_size], &frames[frame_size..2 * frame_size]);
+        let mut autocorrelator = Autocorrelator::new(frame_size, max_lag);
+        group.bench_function(BenchmarkId::new("single", format!("{}Hz", rate)), |b| {
+            b.iter(|| autocorrelator.pitch_period(black_box(first), min_lag, max_lag))
+        });
+        group.bench_function(BenchmarkId::new("pair", format!("{}Hz", rate)), |b| {
+            b.iter(|| autocorrelator.pitch_period_pair(black_box(first), black_box(second), min_lag, max_lag))
+        });
+    }
+    group.finish();
+}
+
+fn bench_analyze_voice_stress(c: &mut Criterion) {
+    let mut group = c.benchmark_group("analyze_voice_stress");
+    group.sample_size(20);
+    for (id, rate, samples) in clips() {
+        let bytes = wav(rate, &samples);
+        group.bench_with_input(BenchmarkId::from_parameter(id), &bytes, |b, bytes| {
+            b.iter(|| analyze_voice_stress(black_box(bytes), None))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_parse_wav,
+    bench_decode_wav_base64,
+    bench_extract_features,
+    bench_autocorrelation_pitch,
+    bench_analyze_voice_stress
+);
+criterion_main!(benches);
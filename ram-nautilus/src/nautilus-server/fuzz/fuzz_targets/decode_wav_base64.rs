@@ -0,0 +1,30 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! The streaming base64 decoder against decode-then-`parse_wav`: both must
+//! agree on every input, however its blocks split the header
+
+#![no_main]
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use libfuzzer_sys::fuzz_target;
+use nautilus_server::ram_app::{decode_wav_base64, parse_wav};
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(audio_base64) = std::str::from_utf8(data) else {
+        return;
+    };
+    let streamed = decode_wav_base64(audio_base64);
+    match STANDARD.decode(audio_base64) {
+        Ok(bytes) => {
+            let expected = parse_wav(&bytes);
+            let streamed = streamed.expect("valid base64 rejected by the streaming decoder");
+            assert_eq!(
+                streamed.as_ref().map(|(samples, rate)| (&**samples, *rate)),
+                expected.as_ref().map(|(samples, rate)| (&**samples, *rate)),
+            );
+        }
+        // Past an unreadable header the rest of the input isn't decoded
+        Err(_) => assert!(!matches!(streamed, Ok(Some(_)))),
+    }
+});
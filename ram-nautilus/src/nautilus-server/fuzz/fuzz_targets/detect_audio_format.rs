@@ -0,0 +1,14 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! Format sniffing on the raw `audio_base64` string, before any validation
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use nautilus_server::ram_app::detect_audio_format;
+
+fuzz_target!(|audio_base64: &str| {
+    let format = detect_audio_format(audio_base64);
+    assert!(["wav", "mp3", "ogg", "flac", "webm"].contains(&format.as_str()));
+});
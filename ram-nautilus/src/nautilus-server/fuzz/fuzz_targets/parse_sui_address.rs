@@ -0,0 +1,16 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! The handlers' hex address decoding: only 64 hex digits (optionally
+//! 0x-prefixed) may parse, and nothing may panic
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use nautilus_server::ram_app::parse_sui_address;
+
+fuzz_target!(|address: &str| {
+    let hex = address.strip_prefix("0x").unwrap_or(address);
+    let well_formed = hex.len() == 64 && hex.bytes().all(|b| b.is_ascii_hexdigit());
+    assert_eq!(parse_sui_address(address).is_ok(), well_formed, "{:?}", address);
+});
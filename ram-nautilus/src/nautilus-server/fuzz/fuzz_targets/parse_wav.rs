@@ -0,0 +1,16 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! Arbitrary bytes through the WAV parser and into feature extraction,
+//! which sizes its frames from the header's sample rate
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use nautilus_server::ram_app::{extract_features, parse_wav};
+
+fuzz_target!(|data: &[u8]| {
+    if let Some((samples, sample_rate)) = parse_wav(data) {
+        extract_features(&samples, sample_rate);
+    }
+});
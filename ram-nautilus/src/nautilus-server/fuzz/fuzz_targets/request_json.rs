@@ -0,0 +1,37 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! Request bodies as `ValidatedJson` sees them: JSON deserialization of every
+//! request type, then field validation of whatever parses
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use nautilus_server::common::ProcessDataRequest;
+use nautilus_server::ram_app::*;
+use serde::de::DeserializeOwned;
+
+fn check<T: DeserializeOwned + Validate>(body: &[u8]) {
+    if let Ok(request) = serde_json::from_slice::<ProcessDataRequest<T>>(body) {
+        let _ = Validator::check(&request);
+    }
+}
+
+fuzz_target!(|body: &[u8]| {
+    check::<CreateWalletRequest>(body);
+    check::<LinkAddressRequest>(body);
+    check::<LinkZkLoginRequest>(body);
+    check::<UnlinkAddressRequest>(body);
+    check::<BioAuthRequest>(body);
+    check::<TypedAuthRequest>(body);
+    check::<EnrollVoiceRequest>(body);
+    check::<ChallengeRequest>(body);
+    check::<TransferRequest>(body);
+    check::<WithdrawRequest>(body);
+    check::<SetLimitRequest>(body);
+    check::<FreezeWalletRequest>(body);
+    check::<UnfreezeChallengeRequest>(body);
+    check::<RequestUnfreezeRequest>(body);
+    check::<SetBeneficiaryRequest>(body);
+    check::<ProveDepositRequest>(body);
+});
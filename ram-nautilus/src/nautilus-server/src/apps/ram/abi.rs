@@ -0,0 +1,353 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! ABI sync check between the Rust payloads and the deployed Move contract
+//!
+//! The enclave signs BCS bytes that `core.move` deserializes, so a field added,
+//! renamed or reordered on only one side silently breaks every signature check
+//! on-chain. This compares the Rust payload layouts (traced via serde) against
+//! the package's normalized Move structs, and the Rust intent codes against the
+//! Move intent constants.
+
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use serde_reflection::{ContainerFormat, Format, Samples, Tracer, TracerConfig};
+use std::collections::HashMap;
+
+use super::types::{
+    BeneficiaryPayload, BioAuthPayload, CreateWalletPayload, DepositProofPayload, FreezePayload,
+    LinkAddressPayload, SetLimitPayload, TransferPayload, UnfreezePayload, UnlinkAddressPayload,
+    WithdrawPayload,
+};
+use crate::common::IntentScope;
+
+/// Move module holding the payload structs and intent constants
+pub const CORE_MODULE: &str = "core";
+
+/// One signed payload: Rust layout, Move struct name and the intent it's signed under
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PayloadAbi {
+    pub struct_name: &'static str,
+    pub intent_const: &'static str,
+    pub intent: u8,
+    /// Field name and Move-style type, in BCS order
+    pub fields: Vec<(String, String)>,
+    /// JSON Schema of the payload as signed responses carry it
+    pub schema: Value,
+}
+
+/// Layouts of every payload the enclave signs
+pub fn rust_payloads() -> Result<Vec<PayloadAbi>, String> {
+    Ok(vec![
+        trace::<CreateWalletPayload>("CreateWalletPayload", "CREATE_WALLET_INTENT", IntentScope::ProcessData)?,
+        trace::<LinkAddressPayload>("LinkAddressPayload", "LINK_ADDRESS_INTENT", IntentScope::LinkWallet)?,
+        trace::<TransferPayload>("TransferPayload", "TRANSFER_INTENT", IntentScope::TransferCoin)?,
+        trace::<BioAuthPayload>("BioAuthPayload", "BIOAUTH_INTENT", IntentScope::TransferNft)?,
+        trace::<WithdrawPayload>("WithdrawPayload", "WITHDRAW_INTENT", IntentScope::UpdateHandle)?,
+        trace::<UnlinkAddressPayload>("UnlinkAddressPayload", "UNLINK_ADDRESS_INTENT", IntentScope::UnlinkWallet)?,
+        trace::<SetLimitPayload>("SetLimitPayload", "SET_LIMIT_INTENT", IntentScope::SetLimit)?,
+        trace::<FreezePayload>("FreezePayload", "FREEZE_INTENT", IntentScope::Freeze)?,
+        trace::<UnfreezePayload>("UnfreezePayload", "UNFREEZE_INTENT", IntentScope::Unfreeze)?,
+        trace::<BeneficiaryPayload>("BeneficiaryPayload", "BENEFICIARY_INTENT", IntentScope::Beneficiary)?,
+        trace::<DepositProofPayload>("DepositProofPayload", "DEPOSIT_PROOF_INTENT", IntentScope::DepositProof)?,
+    ])
+}
+
+fn trace<T: DeserializeOwned>(
+    struct_name: &'static str,
+    intent_const: &'static str,
+    intent: IntentScope,
+) -> Result<PayloadAbi, String> {
+    let samples = Samples::new();
+    let mut tracer = Tracer::new(TracerConfig::default());
+    tracer
+        .trace_type::<T>(&samples)
+        .map_err(|e| format!("Failed to trace {}: {}", struct_name, e))?;
+    let registry = tracer
+        .registry()
+        .map_err(|e| format!("Failed to trace {}: {}", struct_name, e))?;
+
+    let Some(ContainerFormat::Struct(named)) = registry.get(struct_name) else {
+        return Err(format!("{} is not a struct: {:?}", struct_name, registry.get(struct_name)));
+    };
+    let fields = named
+        .iter()
+        .map(|f| Ok((f.name.clone(), rust_type(&f.value)?)))
+        .collect::<Result<Vec<_>, String>>()?;
+    let properties = named
+        .iter()
+        .map(|f| Ok((f.name.clone(), json_schema(&f.value)?)))
+        .collect::<Result<serde_json::Map<_, _>, String>>()?;
+    let required: Vec<&str> = named.iter().map(|f| f.name.as_str()).collect();
+
+    Ok(PayloadAbi {
+        struct_name,
+        intent_const,
+        intent: intent as u8,
+        fields,
+        schema: json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "title": struct_name,
+            "type": "object",
+            "properties": properties,
+            "required": required,
+            "additionalProperties": false,
+        }),
+    })
+}
+
+/// JSON Schema of a serde format as serde_json renders it (bytes and
+/// addresses are arrays of numbers)
+fn json_schema(format: &Format) -> Result<Value, String> {
+    let integer = |maximum: u64| json!({ "type": "integer", "minimum": 0, "maximum": maximum });
+    Ok(match format {
+        Format::Bool => json!({ "type": "boolean" }),
+        Format::U8 => integer(u8::MAX as u64),
+        Format::U16 => integer(u16::MAX as u64),
+        Format::U32 => integer(u32::MAX as u64),
+        Format::U64 => integer(u64::MAX),
+        Format::U128 => json!({ "type": "integer", "minimum": 0 }),
+        Format::TupleArray { content, size } => json!({
+            "type": "array",
+            "items": json_schema(content)?,
+            "minItems": size,
+            "maxItems": size,
+        }),
+        Format::Seq(inner) => json!({ "type": "array", "items": json_schema(inner)? }),
+        Format::Bytes => json!({ "type": "array", "items": integer(u8::MAX as u64) }),
+        Format::Option(inner) => json!({ "anyOf": [json_schema(inner)?, { "type": "null" }] }),
+        other => return Err(format!("No JSON Schema for {:?}", other)),
+    })
+}
+
+/// Move type a serde format is BCS-compatible with
+fn rust_type(format: &Format) -> Result<String, String> {
+    Ok(match format {
+        Format::Bool => "bool".to_string(),
+        Format::U8 => "u8".to_string(),
+        Format::U16 => "u16".to_string(),
+        Format::U32 => "u32".to_string(),
+        Format::U64 => "u64".to_string(),
+        Format::U128 => "u128".to_string(),
+        Format::TupleArray { content, size: 32 } if **content == Format::U8 => "address".to_string(),
+        Format::Seq(inner) => format!("vector<{}>", rust_type(inner)?),
+        Format::Bytes => "vector<u8>".to_string(),
+        Format::Option(inner) => format!("0x1::option::Option<{}>", rust_type(inner)?),
+        other => return Err(format!("No Move equivalent for {:?}", other)),
+    })
+}
+
+/// Render a normalized Move type (`sui_getNormalizedMoveModulesByPackage`) in Move syntax
+pub fn move_type(ty: &Value) -> String {
+    match ty {
+        Value::String(prim) => prim.to_lowercase(),
+        Value::Object(obj) => {
+            if let Some(inner) = obj.get("Vector") {
+                format!("vector<{}>", move_type(inner))
+            } else if let Some(s) = obj.get("Struct") {
+                let address = s["address"].as_str().unwrap_or_default();
+                // Normalize 0x000..01 to 0x1 so framework types compare equal
+                let address = format!("0x{}", address.trim_start_matches("0x").trim_start_matches('0'));
+                let args: Vec<String> = s["typeArguments"]
+                    .as_array()
+                    .map(|a| a.iter().map(move_type).collect())
+                    .unwrap_or_default();
+                let base = format!(
+                    "{}::{}::{}",
+                    address,
+                    s["module"].as_str().unwrap_or_default(),
+                    s["name"].as_str().unwrap_or_default()
+                );
+                if args.is_empty() {
+                    base
+                } else {
+                    format!("{}<{}>", base, args.join(", "))
+                }
+            } else {
+                ty.to_string()
+            }
+        }
+        _ => ty.to_string(),
+    }
+}
+
+/// Fields of a normalized Move struct, in declaration (= BCS) order
+pub fn move_struct_fields(module: &Value, struct_name: &str) -> Option<Vec<(String, String)>> {
+    let fields = module["structs"][struct_name]["fields"].as_array()?;
+    Some(
+        fields
+            .iter()
+            .map(|f| {
+                (
+                    f["name"].as_str().unwrap_or_default().to_string(),
+                    move_type(&f["type"]),
+                )
+            })
+            .collect(),
+    )
+}
+
+/// Extract `const NAME: u8 = N;` declarations from Move source
+///
+/// Constant values aren't part of the normalized module ABI, so intents are
+/// checked against the contract source the package was published from.
+pub fn move_u8_constants(source: &str) -> HashMap<String, u8> {
+    let re = regex::Regex::new(r"const\s+([A-Z0-9_]+)\s*:\s*u8\s*=\s*(\d+)\s*;").unwrap();
+    re.captures_iter(source)
+        .filter_map(|c| Some((c[1].to_string(), c[2].parse().ok()?)))
+        .collect()
+}
+
+/// Compare Rust payloads against the Move module; returns one message per drift
+pub fn check(
+    payloads: &[PayloadAbi],
+    module: &Value,
+    constants: Option<&HashMap<String, u8>>,
+) -> Vec<String> {
+    let mut drift = Vec::new();
+
+    for payload in payloads {
+        match move_struct_fields(module, payload.struct_name) {
+            None => drift.push(format!("{}: missing from Move module", payload.struct_name)),
+            Some(move_fields) if move_fields != payload.fields => drift.push(format!(
+                "{}: field mismatch\n    rust: {}\n    move: {}",
+                payload.struct_name,
+                render(&payload.fields),
+                render(&move_fields)
+            )),
+            Some(_) => {}
+        }
+
+        if let Some(constants) = constants {
+            match constants.get(payload.intent_const) {
+                None => drift.push(format!("{}: not declared in Move source", payload.intent_const)),
+                Some(&value) if value != payload.intent => drift.push(format!(
+                    "{}: rust signs {} with intent {}, Move expects {}",
+                    payload.intent_const, payload.struct_name, payload.intent, value
+                )),
+                Some(_) => {}
+            }
+        }
+    }
+
+    drift
+}
+
+fn render(fields: &[(String, String)]) -> String {
+    fields
+        .iter()
+        .map(|(name, ty)| format!("{}: {}", name, ty))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn core_module() -> Value {
+        json!({
+            "structs": {
+                "CreateWalletPayload": { "fields": [
+                    { "name": "handle", "type": { "Vector": "U8" } },
+                ]},
+                "LinkAddressPayload": { "fields": [
+                    { "name": "handle", "type": { "Vector": "U8" } },
+                    { "name": "address", "type": "Address" },
+                    { "name": "label", "type": { "Vector": "U8" } },
+                ]},
+                "UnlinkAddressPayload": { "fields": [
+                    { "name": "handle", "type": { "Vector": "U8" } },
+                    { "name": "address", "type": "Address" },
+                ]},
+                "TransferPayload": { "fields": [
+                    { "name": "from_handle", "type": { "Vector": "U8" } },
+                    { "name": "to_handle", "type": { "Vector": "U8" } },
+                    { "name": "amount", "type": "U64" },
+                    { "name": "coin_type", "type": { "Vector": "U8" } },
+                    { "name": "memo", "type": { "Vector": "U8" } },
+                ]},
+                "BioAuthPayload": { "fields": [
+                    { "name": "handle", "type": { "Vector": "U8" } },
+                    { "name": "amount", "type": "U64" },
+                    { "name": "result", "type": "U8" },
+                    { "name": "transcript", "type": { "Vector": "U8" } },
+                    { "name": "destination_verified", "type": "Bool" },
+                    { "name": "method", "type": "U8" },
+                    { "name": "seal", "type": { "Vector": "U8" } },
+                    { "name": "failed_attempts", "type": "U8" },
+                ]},
+                "WithdrawPayload": { "fields": [
+                    { "name": "handle", "type": { "Vector": "U8" } },
+                    { "name": "amount", "type": "U64" },
+                    { "name": "coin_type", "type": { "Vector": "U8" } },
+                ]},
+                "SetLimitPayload": { "fields": [
+                    { "name": "handle", "type": { "Vector": "U8" } },
+                    { "name": "daily_limit", "type": "U64" },
+                    { "name": "coin_type", "type": { "Vector": "U8" } },
+                ]},
+                "FreezePayload": { "fields": [
+                    { "name": "handle", "type": { "Vector": "U8" } },
+                ]},
+                "UnfreezePayload": { "fields": [
+                    { "name": "handle", "type": { "Vector": "U8" } },
+                ]},
+                "BeneficiaryPayload": { "fields": [
+                    { "name": "handle", "type": { "Vector": "U8" } },
+                    { "name": "beneficiary_handle", "type": { "Vector": "U8" } },
+                    { "name": "inactivity_timeout_ms", "type": "U64" },
+                ]},
+                "DepositProofPayload": { "fields": [
+                    { "name": "handle", "type": { "Vector": "U8" } },
+                    { "name": "deposit_id", "type": { "Vector": "U8" } },
+                ]},
+            }
+        })
+    }
+
+    #[test]
+    fn test_matches_move_source() {
+        let source = include_str!("../../../../../move/ram/sources/core.move");
+        let constants = move_u8_constants(source);
+        assert_eq!(constants.get("BIOAUTH_INTENT"), Some(&3));
+
+        let drift = check(&rust_payloads().unwrap(), &core_module(), Some(&constants));
+        assert!(drift.is_empty(), "{:?}", drift);
+    }
+
+    #[test]
+    fn test_detects_field_reorder() {
+        let mut module = core_module();
+        module["structs"]["BioAuthPayload"]["fields"]
+            .as_array_mut()
+            .unwrap()
+            .swap(1, 2);
+        let drift = check(&rust_payloads().unwrap(), &module, None);
+        assert_eq!(drift.len(), 1);
+        assert!(drift[0].starts_with("BioAuthPayload"));
+    }
+
+    #[test]
+    fn test_detects_intent_drift() {
+        let mut constants = move_u8_constants("const WITHDRAW_INTENT: u8 = 9;");
+        constants.extend(move_u8_constants(include_str!(
+            "../../../../../move/ram/sources/core.move"
+        )).into_iter().filter(|(k, _)| k != "WITHDRAW_INTENT"));
+        let drift = check(&rust_payloads().unwrap(), &core_module(), Some(&constants));
+        assert_eq!(drift.len(), 1);
+        assert!(drift[0].contains("WITHDRAW_INTENT"));
+    }
+
+    #[test]
+    fn test_move_type_rendering() {
+        assert_eq!(move_type(&json!({ "Vector": "U8" })), "vector<u8>");
+        assert_eq!(
+            move_type(&json!({ "Struct": {
+                "address": "0x0000000000000000000000000000000000000000000000000000000000000001",
+                "module": "option", "name": "Option", "typeArguments": ["Address"]
+            }})),
+            "0x1::option::Option<address>"
+        );
+    }
+}
@@ -0,0 +1,202 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! Peer allowlists for the enclave's routes
+//!
+//! Defense in depth next to request signing: only peers inside configured
+//! CIDR ranges may reach the signing endpoints, and the privileged
+//! diagnostics/baseline routes can be narrowed further. Public routes
+//! (`/health_check`, `/get_attestation`, ...) stay open to everyone.
+//!
+//! The peer is the TCP source address. When the enclave is reached through
+//! the vsock traffic forwarder every peer is the forwarder, so the allowlist
+//! only adds protection on deployments that receive connections directly.
+
+use crate::{current_request_id, ErrorBody, ErrorCode};
+use axum::extract::{ConnectInfo, Request, State};
+use axum::http::StatusCode;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde_json::json;
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+use std::sync::Arc;
+use tracing::warn;
+
+/// Routes that return only public data; never restricted by ACL or request signing
+pub const PUBLIC_PATHS: &[&str] = &["/", "/health_check", "/get_attestation", "/public_key", "/meta/intents"];
+
+/// Route prefixes of the privileged server-to-server channel
+const PRIVILEGED_PREFIXES: &[&str] = &["/bio_auth/diagnostics/", "/bio_auth/baseline/"];
+
+/// Which allowlist a route falls under
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RouteClass {
+    Public,
+    Signing,
+    Privileged,
+}
+
+impl RouteClass {
+    pub fn of(path: &str) -> Self {
+        if PUBLIC_PATHS.contains(&path) {
+            RouteClass::Public
+        } else if PRIVILEGED_PREFIXES.iter().any(|prefix| path.starts_with(prefix)) {
+            RouteClass::Privileged
+        } else {
+            RouteClass::Signing
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RouteClass::Public => "public",
+            RouteClass::Signing => "signing",
+            RouteClass::Privileged => "privileged",
+        }
+    }
+}
+
+/// An IPv4 or IPv6 network, e.g. `10.0.0.0/8`; a bare address is a single host
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cidr {
+    network: IpAddr,
+    prefix_len: u8,
+}
+
+impl FromStr for Cidr {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (addr, prefix) = match s.trim().split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (s.trim(), None),
+        };
+        let network: IpAddr = addr.parse().map_err(|_| format!("invalid address in '{}'", s))?;
+        let max_len = if network.is_ipv4() { 32 } else { 128 };
+        let prefix_len = match prefix {
+            Some(p) => p
+                .parse::<u8>()
+                .ok()
+                .filter(|p| *p <= max_len)
+                .ok_or_else(|| format!("invalid prefix length in '{}'", s))?,
+            None => max_len,
+        };
+        Ok(Self { network, prefix_len })
+    }
+}
+
+impl Cidr {
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        // IPv4 peers on a dual-stack socket show up as ::ffff:a.b.c.d
+        match (self.network, ip.to_canonical()) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix_len as u32).unwrap_or(0);
+                u32::from(net) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix_len as u32).unwrap_or(0);
+                u128::from(net) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Parse a comma-separated CIDR list; an empty string is an empty list
+pub fn parse_cidrs(list: &str) -> Result<Vec<Cidr>, String> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(Cidr::from_str)
+        .collect()
+}
+
+/// Per-route-class peer allowlists
+pub struct PeerAcl {
+    signing: Vec<Cidr>,
+    privileged: Option<Vec<Cidr>>,
+}
+
+impl PeerAcl {
+    /// Empty `signing` allows every peer; privileged routes use `signing` unless given their own list
+    pub fn new(signing: Vec<Cidr>, privileged: Option<Vec<Cidr>>) -> Self {
+        Self { signing, privileged }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.signing.is_empty() || self.privileged.as_ref().is_some_and(|p| !p.is_empty())
+    }
+
+    pub fn allows(&self, class: RouteClass, peer: IpAddr) -> bool {
+        let list = match class {
+            RouteClass::Public => return true,
+            RouteClass::Signing => &self.signing,
+            RouteClass::Privileged => self.privileged.as_ref().unwrap_or(&self.signing),
+        };
+        list.is_empty() || list.iter().any(|cidr| cidr.contains(peer))
+    }
+}
+
+/// Middleware rejecting peers outside the route's allowlist with a structured 403
+///
+/// Needs the server to be started with `into_make_service_with_connect_info::<SocketAddr>()`.
+pub async fn enforce_peer_acl(
+    State(acl): State<Arc<PeerAcl>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let path = request.uri().path();
+    let class = RouteClass::of(path);
+    if acl.allows(class, peer.ip()) {
+        return next.run(request).await;
+    }
+
+    warn!("RAM ACL: rejected {} {} from {} ({} route)", request.method(), path, peer.ip(), class.as_str());
+    let body = ErrorBody {
+        code: ErrorCode::PeerNotAllowed,
+        message: format!("Peer {} is not allowed to call {} routes", peer.ip(), class.as_str()),
+        details: Some(json!({ "route_class": class.as_str(), "peer": peer.ip().to_string() })),
+        request_id: current_request_id(),
+    };
+    (StatusCode::FORBIDDEN, Json(body)).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_cidr_contains() {
+        let net: Cidr = "10.1.0.0/16".parse().unwrap();
+        assert!(net.contains(ip("10.1.200.3")));
+        assert!(!net.contains(ip("10.2.0.1")));
+        assert!(net.contains(ip("::ffff:10.1.0.9")));
+        assert!("0.0.0.0/0".parse::<Cidr>().unwrap().contains(ip("8.8.8.8")));
+        assert!("127.0.0.1".parse::<Cidr>().unwrap().contains(ip("127.0.0.1")));
+        assert!("fd00::/8".parse::<Cidr>().unwrap().contains(ip("fd12::1")));
+        assert!("10.0.0.0/33".parse::<Cidr>().is_err());
+        assert!(parse_cidrs("10.0.0.0/8, ,bogus").is_err());
+        assert!(parse_cidrs("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_route_classes() {
+        let acl = PeerAcl::new(parse_cidrs("10.0.0.0/8").unwrap(), Some(parse_cidrs("10.0.0.5").unwrap()));
+        let outside = ip("203.0.113.7");
+        assert!(acl.allows(RouteClass::of("/health_check"), outside));
+        assert!(acl.allows(RouteClass::of("/meta/intents"), outside));
+        assert!(!acl.allows(RouteClass::of("/bio_auth"), outside));
+        assert!(acl.allows(RouteClass::of("/bio_auth"), ip("10.3.4.5")));
+        assert_eq!(RouteClass::of("/bio_auth/diagnostics/abc"), RouteClass::Privileged);
+        assert!(!acl.allows(RouteClass::Privileged, ip("10.3.4.5")));
+        assert!(acl.allows(RouteClass::Privileged, ip("10.0.0.5")));
+        assert!(PeerAcl::new(Vec::new(), None).allows(RouteClass::Signing, outside));
+    }
+}
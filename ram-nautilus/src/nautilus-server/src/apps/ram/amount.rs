@@ -0,0 +1,174 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! Fixed-point money amounts
+//!
+//! Amounts stay in raw coin units (MIST for SUI) from the request to the
+//! signed payload and are only converted at the edges: decimal text the user
+//! said or typed is parsed into raw units, and raw units are rendered back
+//! into the text the user should say. None of it goes through `f64`, which
+//! can't hold most decimals exactly and loses whole raw units past 2^53
+//! (about 9M SUI).
+//!
+//! Rounding rules:
+//! - Parsing keeps every digit the coin has decimals for; one more digit
+//!   rounds half-up (`1.0000000005` SUI is 1_000_000_001 MIST).
+//! - Rendering is exact, with trailing zeros dropped (`2.5`, `5`).
+//! - A detected amount matches when it's within `AMOUNT_TOLERANCE_BPS` of the
+//!   expected one, computed in integers.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+
+/// How far a detected amount may be from the expected one, in basis points (1%)
+pub const AMOUNT_TOLERANCE_BPS: u64 = 100;
+
+/// An amount in a coin's smallest unit
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct RawAmount(pub u64);
+
+impl RawAmount {
+    /// Parse decimal text in whole coins ("2.5") into raw units
+    ///
+    /// Only plain digits with an optional `.` are accepted; signs, exponents
+    /// and group separators are not. None when it doesn't fit in a u64.
+    pub fn parse(text: &str, decimals: u8) -> Option<Self> {
+        let (whole, fraction) = text.split_once('.').unwrap_or((text, ""));
+        if whole.is_empty() && fraction.is_empty() {
+            return None;
+        }
+        if !whole.bytes().chain(fraction.bytes()).all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+
+        let digits = |s: &str| {
+            s.bytes().try_fold(0u128, |acc, b| acc.checked_mul(10)?.checked_add((b - b'0') as u128))
+        };
+        let decimals = decimals as usize;
+        let (kept, dropped) = fraction.split_at(fraction.len().min(decimals));
+        let pad = 10u128.pow((decimals - kept.len()) as u32);
+
+        let mut raw = digits(whole)?
+            .checked_mul(10u128.pow(decimals as u32))?
+            .checked_add(digits(kept)? * pad)?;
+        if dropped.bytes().next().is_some_and(|b| b >= b'5') {
+            raw += 1;
+        }
+        u64::try_from(raw).ok().map(Self)
+    }
+
+    /// Exact decimal text in whole coins, without trailing zeros
+    pub fn to_decimal(self, decimals: u8) -> String {
+        let scale = 10u64.pow(decimals as u32);
+        let (whole, fraction) = (self.0 / scale, self.0 % scale);
+        if fraction == 0 {
+            return whole.to_string();
+        }
+        let fraction = format!("{:0width$}", fraction, width = decimals as usize);
+        format!("{}.{}", whole, fraction.trim_end_matches('0'))
+    }
+
+    /// Whether this detected amount is close enough to the expected one
+    pub fn matches(self, expected: RawAmount) -> bool {
+        let diff = self.0.abs_diff(expected.0) as u128;
+        diff * 10_000 <= expected.0 as u128 * AMOUNT_TOLERANCE_BPS as u128
+    }
+}
+
+impl fmt::Display for RawAmount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Decimal text of an amount in model JSON, which may be a string or a number
+pub fn json_decimal(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.trim().to_string()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_rounding_and_limits() {
+        assert_eq!(RawAmount::parse("2.5", 9), Some(RawAmount(2_500_000_000)));
+        assert_eq!(RawAmount::parse(".5", 6), Some(RawAmount(500_000)));
+        assert_eq!(RawAmount::parse("5.", 0), Some(RawAmount(5)));
+        assert_eq!(RawAmount::parse("1.0000000005", 9), Some(RawAmount(1_000_000_001)));
+        assert_eq!(RawAmount::parse("1.0000000004999", 9), Some(RawAmount(1_000_000_000)));
+        assert_eq!(RawAmount::parse("18446744073.709551615", 9), Some(RawAmount(u64::MAX)));
+        assert_eq!(RawAmount::parse("18446744073.709551616", 9), None);
+        for text in ["", ".", "-1", "+1", "1e3", "1,000", "1.2.3", "five", " 5"] {
+            assert_eq!(RawAmount::parse(text, 9), None, "{:?} should be rejected", text);
+        }
+    }
+
+    #[test]
+    fn test_large_amounts_are_exact() {
+        // 123456789.123456789 SUI has no exact f64, so float math was off by whole MIST
+        let raw = RawAmount(123_456_789_123_456_789);
+        assert_eq!(raw.to_decimal(9), "123456789.123456789");
+        assert_eq!(RawAmount::parse("123456789.123456789", 9), Some(raw));
+        assert_eq!(RawAmount(5_000_000_000).to_decimal(9), "5");
+        assert_eq!(RawAmount(u64::MAX).to_decimal(19), "1.8446744073709551615");
+    }
+
+    #[test]
+    fn test_matches_tolerance() {
+        let expected = RawAmount(5_000_000_000);
+        assert!(RawAmount(5_050_000_000).matches(expected));
+        assert!(RawAmount(4_950_000_000).matches(expected));
+        assert!(!RawAmount(5_050_000_001).matches(expected));
+        assert!(!RawAmount(10_000_000_000).matches(expected));
+        assert!(RawAmount(0).matches(RawAmount(0)));
+        assert!(!RawAmount(1).matches(RawAmount(0)));
+        assert!(RawAmount(u64::MAX).matches(RawAmount(u64::MAX)));
+    }
+
+    #[test]
+    fn test_json_decimal() {
+        assert_eq!(json_decimal(&json!("10.5")).as_deref(), Some("10.5"));
+        assert_eq!(json_decimal(&json!(5)).as_deref(), Some("5"));
+        assert_eq!(json_decimal(&json!(2.5)).as_deref(), Some("2.5"));
+        assert_eq!(json_decimal(&Value::Null), None);
+    }
+
+    proptest! {
+        #[test]
+        fn prop_render_then_parse_round_trips(raw in any::<u64>(), decimals in 0u8..=19) {
+            let text = RawAmount(raw).to_decimal(decimals);
+            prop_assert_eq!(RawAmount::parse(&text, decimals), Some(RawAmount(raw)));
+        }
+
+        #[test]
+        fn prop_extra_digit_rounds_half_up(raw in 0u64..u64::MAX, decimals in 0u8..=18, extra in 0u8..=9) {
+            // Full-precision text plus one digit the coin can't hold
+            let scale = 10u64.pow(decimals as u32);
+            let fraction = match decimals {
+                0 => String::new(),
+                width => format!("{:0width$}", raw % scale, width = width as usize),
+            };
+            let text = format!("{}.{}{}", raw / scale, fraction, extra);
+            let expected = if extra >= 5 { raw + 1 } else { raw };
+            prop_assert_eq!(RawAmount::parse(&text, decimals), Some(RawAmount(expected)));
+        }
+
+        #[test]
+        fn prop_matches_exactly_one_percent(expected in 0u64..u64::MAX / 2, offset in any::<u64>()) {
+            let allowed = expected / 100;
+            let inside = offset % (allowed + 1);
+            prop_assert!(RawAmount(expected + inside).matches(RawAmount(expected)));
+            prop_assert!(RawAmount(expected - inside).matches(RawAmount(expected)));
+            prop_assert!(!RawAmount(expected + allowed + 1).matches(RawAmount(expected)));
+        }
+    }
+}
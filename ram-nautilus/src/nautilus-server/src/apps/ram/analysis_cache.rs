@@ -0,0 +1,228 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! Short-lived cache of bio-auth analyses
+//!
+//! Clients sometimes send the same clip twice in quick succession, e.g. a
+//! preview followed by the real auth, and analyzing it again would pay
+//! OpenRouter and Hume twice for the same answer. Each analysis is kept for a
+//! short TTL, keyed by a hash of the clip and of everything else the result
+//! depends on: the handle, expected amount, coin, locale and the number of
+//! samples in the handle's voice baseline (enrolling or resetting changes
+//! it). A request with `skip_analysis_cache` is always analyzed afresh, and
+//! its result replaces the cached one. Hits, misses and bypasses are served
+//! at `GET /metrics`. Only results are kept, never audio.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use super::amount::RawAmount;
+use super::audio::AudioAnalysisResult;
+use super::coin::CoinType;
+
+/// Default lifetime of a cached analysis
+pub const DEFAULT_ANALYSIS_CACHE_TTL_SECS: u64 = 60;
+/// Analyses kept at once; past this the oldest is dropped
+const ANALYSIS_CACHE_CAPACITY: usize = 256;
+
+/// What a cached analysis is valid for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AnalysisKey([u8; 32]);
+
+impl AnalysisKey {
+    pub fn new(
+        handle: &str,
+        audio_base64: &str,
+        expected_amount: RawAmount,
+        coin_type: &CoinType,
+        locale: Option<&str>,
+        baseline_samples: u32,
+    ) -> Self {
+        let mut hasher = Sha256::new();
+        // Length-prefixed, so no two field lists hash the same bytes
+        for field in [handle, &coin_type.to_string(), locale.unwrap_or(""), audio_base64] {
+            hasher.update((field.len() as u64).to_le_bytes());
+            hasher.update(field.as_bytes());
+        }
+        hasher.update(expected_amount.0.to_le_bytes());
+        hasher.update(baseline_samples.to_le_bytes());
+        Self(hasher.finalize().into())
+    }
+}
+
+/// Recent analyses keyed by clip and request
+pub struct AnalysisCache {
+    ttl: Duration,
+    entries: Mutex<HashMap<AnalysisKey, (Instant, AudioAnalysisResult)>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    bypassed: AtomicU64,
+}
+
+impl AnalysisCache {
+    /// A zero `ttl` disables the cache
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            bypassed: AtomicU64::new(0),
+        }
+    }
+
+    /// TTL from `ANALYSIS_CACHE_TTL_SECS` (0 disables); unset or invalid keeps the default
+    pub fn from_env() -> Self {
+        let secs = std::env::var("ANALYSIS_CACHE_TTL_SECS")
+            .ok()
+            .and_then(|v| v.trim().parse::<u64>().ok())
+            .unwrap_or(DEFAULT_ANALYSIS_CACHE_TTL_SECS);
+        Self::new(Duration::from_secs(secs))
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.ttl.is_zero()
+    }
+
+    /// The cached analysis for `key`, unless `bypass` is set
+    pub fn get(&self, key: &AnalysisKey, bypass: bool) -> Option<AudioAnalysisResult> {
+        if !self.is_enabled() {
+            return None;
+        }
+        if bypass {
+            self.bypassed.fetch_add(1, Ordering::Relaxed);
+            return None;
+        }
+        let cached = self
+            .entries
+            .lock()
+            .unwrap()
+            .get(key)
+            .filter(|(stored_at, _)| stored_at.elapsed() < self.ttl)
+            .map(|(_, analysis)| analysis.clone());
+        let counter = if cached.is_some() { &self.hits } else { &self.misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+        cached
+    }
+
+    /// Keep `analysis` for `key`, pruning expired entries
+    ///
+    /// Mock-fallback analyses aren't kept: the next try should reach the
+    /// upstream again rather than reuse a stand-in.
+    pub fn insert(&self, key: AnalysisKey, analysis: &AudioAnalysisResult) {
+        if !self.is_enabled() || !analysis.transcribed {
+            return;
+        }
+        let now = Instant::now();
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, (stored_at, _)| now.duration_since(*stored_at) < self.ttl);
+        if entries.len() >= ANALYSIS_CACHE_CAPACITY && !entries.contains_key(&key) {
+            let oldest = entries.iter().min_by_key(|(_, (stored_at, _))| *stored_at).map(|(key, _)| *key);
+            if let Some(oldest) = oldest {
+                entries.remove(&oldest);
+            }
+        }
+        entries.insert(key, (now, analysis.clone()));
+    }
+
+    pub fn metrics(&self) -> AnalysisCacheMetrics {
+        AnalysisCacheMetrics {
+            enabled: self.is_enabled(),
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            bypassed: self.bypassed.load(Ordering::Relaxed),
+            entries: self.entries.lock().unwrap().len(),
+        }
+    }
+}
+
+impl Default for AnalysisCache {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(DEFAULT_ANALYSIS_CACHE_TTL_SECS))
+    }
+}
+
+/// Analysis cache use since startup
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AnalysisCacheMetrics {
+    pub enabled: bool,
+    /// Analyses served from the cache, each one upstream call pair saved
+    pub hits: u64,
+    pub misses: u64,
+    /// Requests that skipped the cache on purpose
+    pub bypassed: u64,
+    pub entries: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apps::ram::confidence::Confidence;
+    use crate::apps::ram::disagreement::StressScores;
+
+    fn analysis(transcript: &str) -> AudioAnalysisResult {
+        AudioAnalysisResult {
+            transcript: transcript.to_string(),
+            stress_level: 20,
+            amount: Some(RawAmount(5_000_000_000)),
+            emotions: None,
+            amount_verified: true,
+            transcribed: true,
+            confidence: Confidence::default(),
+            stress_scores: StressScores::default(),
+        }
+    }
+
+    fn key(handle: &str, baseline_samples: u32) -> AnalysisKey {
+        let sui = CoinType::parse_or_sui(None).unwrap();
+        AnalysisKey::new(handle, "UklGRg==", RawAmount(5_000_000_000), &sui, None, baseline_samples)
+    }
+
+    #[test]
+    fn test_hits_misses_and_bypass() {
+        let cache = AnalysisCache::default();
+        assert!(cache.get(&key("alice", 0), false).is_none());
+        cache.insert(key("alice", 0), &analysis("five sui"));
+
+        assert_eq!(cache.get(&key("alice", 0), false).unwrap().transcript, "five sui");
+        assert!(cache.get(&key("alice", 0), true).is_none());
+        // Another handle, or a re-enrolled baseline, is a different analysis
+        assert!(cache.get(&key("bob", 0), false).is_none());
+        assert!(cache.get(&key("alice", 1), false).is_none());
+
+        let metrics = cache.metrics();
+        assert_eq!((metrics.hits, metrics.misses, metrics.bypassed, metrics.entries), (1, 3, 1, 1));
+    }
+
+    #[test]
+    fn test_expired_and_disabled() {
+        let cache = AnalysisCache::new(Duration::from_millis(20));
+        cache.insert(key("alice", 0), &analysis("five sui"));
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(cache.get(&key("alice", 0), false).is_none());
+
+        let mock = AudioAnalysisResult { transcribed: false, ..analysis("five sui") };
+        cache.insert(key("alice", 0), &mock);
+        assert!(cache.get(&key("alice", 0), false).is_none());
+
+        let disabled = AnalysisCache::new(Duration::ZERO);
+        disabled.insert(key("alice", 0), &analysis("five sui"));
+        assert!(disabled.get(&key("alice", 0), false).is_none());
+        assert_eq!(disabled.metrics(), AnalysisCacheMetrics::default());
+    }
+
+    #[test]
+    fn test_capacity_drops_the_oldest() {
+        let cache = AnalysisCache::default();
+        for n in 0..=ANALYSIS_CACHE_CAPACITY as u32 {
+            cache.insert(key("alice", n), &analysis("five sui"));
+        }
+        assert_eq!(cache.metrics().entries, ANALYSIS_CACHE_CAPACITY);
+        assert!(cache.get(&key("alice", 0), false).is_none());
+        assert!(cache.get(&key("alice", ANALYSIS_CACHE_CAPACITY as u32), false).is_some());
+    }
+}
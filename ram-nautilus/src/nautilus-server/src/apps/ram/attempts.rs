@@ -0,0 +1,114 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! Cooldowns after repeated failed bio-auths
+//!
+//! Without a limit, a coercer can keep re-recording until a clip passes the
+//! amount and stress checks. Consecutive failures (InvalidAmount, voice or
+//! typed) are counted per handle; after a few free retries each further
+//! attempt must wait an increasing cooldown. A successful bio-auth resets the
+//! count, a duress verdict leaves it alone (the wallet locks anyway).
+//!
+//! The count is also signed into every `BioAuthPayload` as `failed_attempts`,
+//! so the Move contract can apply the same schedule even if the enclave
+//! restarts and forgets it.
+
+use crate::EnclaveError;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use super::types::BioAuthResult;
+use super::validation::normalize_handle;
+
+/// Consecutive failures allowed before cooldowns start
+pub const FREE_FAILED_ATTEMPTS: u8 = 2;
+/// Cooldown after the 3rd, 4th and 5th+ consecutive failure (must match core.move)
+pub const COOLDOWN_SCHEDULE_MS: [u64; 3] = [60_000, 300_000, 3_600_000];
+
+/// Wait required after `failures` consecutive failures
+pub fn cooldown_ms(failures: u8) -> u64 {
+    match failures.checked_sub(FREE_FAILED_ATTEMPTS + 1) {
+        None => 0,
+        Some(step) => COOLDOWN_SCHEDULE_MS[(step as usize).min(COOLDOWN_SCHEDULE_MS.len() - 1)],
+    }
+}
+
+/// Consecutive failures per normalized handle and when the last one happened
+#[derive(Default)]
+pub struct AttemptTracker {
+    failures: Mutex<HashMap<String, (u8, u64)>>,
+}
+
+impl AttemptTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Consecutive failures so far, or `TooManyRequests` while the cooldown runs
+    pub fn check(&self, handle: &str, now_ms: u64) -> Result<u8, EnclaveError> {
+        let key = normalize_handle(handle);
+        let Some((count, last_failed_ms)) = self.failures.lock().unwrap().get(&key).copied() else {
+            return Ok(0);
+        };
+        let ready_at = last_failed_ms.saturating_add(cooldown_ms(count));
+        if now_ms < ready_at {
+            return Err(EnclaveError::TooManyRequests(format!(
+                "Too many failed confirmations for '{}', try again in {}s",
+                handle,
+                (ready_at - now_ms).div_ceil(1000)
+            )));
+        }
+        Ok(count)
+    }
+
+    /// Update the count with a signed result
+    pub fn record(&self, handle: &str, result: BioAuthResult, now_ms: u64) {
+        let mut failures = self.failures.lock().unwrap();
+        match result {
+            BioAuthResult::Ok => {
+                failures.remove(&normalize_handle(handle));
+            }
+            BioAuthResult::InvalidAmount => {
+                let entry = failures.entry(normalize_handle(handle)).or_insert((0, 0));
+                *entry = (entry.0.saturating_add(1), now_ms);
+            }
+            BioAuthResult::Duress => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cooldown_schedule() {
+        assert_eq!(cooldown_ms(0), 0);
+        assert_eq!(cooldown_ms(2), 0);
+        assert_eq!(cooldown_ms(3), 60_000);
+        assert_eq!(cooldown_ms(4), 300_000);
+        assert_eq!(cooldown_ms(5), 3_600_000);
+        assert_eq!(cooldown_ms(u8::MAX), 3_600_000);
+    }
+
+    #[test]
+    fn test_failures_escalate_and_reset() {
+        let tracker = AttemptTracker::new();
+        let mut now = 1_000;
+        for expected in 0..3 {
+            assert_eq!(tracker.check("alice", now).unwrap(), expected);
+            tracker.record("alice", BioAuthResult::InvalidAmount, now);
+        }
+        // Third failure: one minute before the next attempt
+        assert!(matches!(tracker.check("alice", now + 59_000), Err(EnclaveError::TooManyRequests(_))));
+        now += 60_000;
+        assert_eq!(tracker.check("alice", now).unwrap(), 3);
+        tracker.record("alice", BioAuthResult::InvalidAmount, now);
+        assert!(tracker.check("alice", now + 60_000).is_err());
+        assert!(tracker.check("bob", now).is_ok());
+
+        now += 300_000;
+        tracker.record("alice", BioAuthResult::Ok, now);
+        assert_eq!(tracker.check("alice", now).unwrap(), 0);
+    }
+}
@@ -7,15 +7,29 @@
 //! - Speech-to-Text transcription via GPT-4o Audio or Hume AI
 //! - Stress/Panic detection for duress protection
 //! - Amount verification from spoken words
+//! - Withdrawal destination verification (spoken address suffix)
 //!
 //! Supported APIs:
 //! - OpenRouter GPT-4o Audio: General-purpose, single API call
-//! - Hume AI Expression Measurement: Specialized emotion detection
+//! - Hume AI Expression Measurement: Specialized emotion detection, streamed
+//!   over a WebSocket with a batch job as fallback
 
 use crate::EnclaveError;
 use serde::{Deserialize, Serialize};
 use tracing::{error, info, warn};
+use zeroize::Zeroizing;
 
+use super::amount::{json_decimal, RawAmount};
+use super::chaos::{self, Upstream};
+use super::coin::CoinInfo;
+use super::confidence::Confidence;
+use super::costs::{CostTracker, Usage};
+use super::disagreement::StressScores;
+use super::endpoints::{self, Endpoint};
+use super::locale::NumberLocale;
+use super::prompt;
+use super::retry::{self, Provider};
+use super::stream::{BioAuthStage, Progress};
 use super::voice_stress;
 
 /// Stress threshold - above this is considered duress
@@ -28,18 +42,71 @@ const OPENROUTER_API_URL: &str = "https://openrouter.ai/api/v1/chat/completions"
 /// Hume AI API URL for Expression Measurement
 const HUME_API_URL: &str = "https://api.hume.ai/v0/batch/jobs";
 
+/// Hume AI streaming Expression Measurement (scores in one WebSocket round trip)
+const HUME_STREAM_URL: &str = "wss://api.hume.ai/v0/stream/models";
+
+/// How long a streamed Hume analysis may take before falling back to a batch job
+const DEFAULT_HUME_STREAM_TIMEOUT_MS: u64 = 3_000;
+
+/// OpenRouter endpoint (a mock server's in test and `mock` builds)
+fn openrouter_api_url() -> String {
+    endpoints::resolve(Endpoint::OpenRouter, OPENROUTER_API_URL)
+}
+
+/// Hume endpoint (a mock server's in test and `mock` builds)
+fn hume_api_url() -> String {
+    endpoints::resolve(Endpoint::Hume, HUME_API_URL)
+}
+
+/// Hume streaming endpoint (a mock server's in test and `mock` builds)
+fn hume_stream_url() -> String {
+    endpoints::resolve(Endpoint::HumeStream, HUME_STREAM_URL)
+}
+
+/// Whether Hume is tried over its streaming API first; HUME_MODE=batch skips
+/// it, as do a cassette, which only records HTTP, and the `hume_stream`
+/// feature flag for its share of analyses
+fn hume_streaming_enabled() -> bool {
+    #[cfg(feature = "cassettes")]
+    if super::cassette::is_set() {
+        return false;
+    }
+    !std::env::var("HUME_MODE").is_ok_and(|mode| mode.trim().eq_ignore_ascii_case("batch"))
+        && super::feature_flags::current().is_enabled(super::feature_flags::HUME_STREAM, None, true)
+}
+
+/// Budget for a streamed Hume analysis, from HUME_STREAM_TIMEOUT_MS
+fn hume_stream_timeout() -> std::time::Duration {
+    let ms = std::env::var("HUME_STREAM_TIMEOUT_MS")
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .filter(|ms| *ms > 0)
+        .unwrap_or(DEFAULT_HUME_STREAM_TIMEOUT_MS);
+    std::time::Duration::from_millis(ms)
+}
+
 /// Response from audio analysis (unified across providers)
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct AudioAnalysisResult {
     pub transcript: String,
     pub stress_level: u8,
-    pub amount: Option<f64>,
+    /// Detected amount in the coin's raw units
+    pub amount: Option<RawAmount>,
     /// Detailed emotion scores from Hume (optional)
     #[serde(default)]
     pub emotions: Option<EmotionScores>,
     /// Whether amount matches expected (set after verification)
     #[serde(default)]
     pub amount_verified: bool,
+    /// Whether an upstream model transcribed the clip (false for the mock fallback)
+    #[serde(default)]
+    pub transcribed: bool,
+    /// How sure the model is of the transcript, amount and stress score
+    #[serde(default)]
+    pub confidence: Confidence,
+    /// Each analyzer's stress score before they were combined
+    #[serde(default)]
+    pub stress_scores: StressScores,
 }
 
 /// Detailed emotion scores from Hume AI
@@ -90,6 +157,14 @@ struct OpenRouterRequest {
     audio: Option<AudioConfig>,
     #[serde(skip_serializing_if = "Option::is_none")]
     response_format: Option<ResponseFormat>,
+    /// Ask for token counts and cost in the response
+    #[serde(skip_serializing_if = "Option::is_none")]
+    usage: Option<UsageAccounting>,
+}
+
+#[derive(Serialize)]
+struct UsageAccounting {
+    include: bool,
 }
 
 /// Force structured JSON output
@@ -108,6 +183,19 @@ struct AudioConfig {
 #[derive(Deserialize)]
 struct OpenRouterResponse {
     choices: Vec<Choice>,
+    #[serde(default)]
+    usage: Option<OpenRouterUsage>,
+}
+
+/// Token counts and cost (credits, USD) of one call
+#[derive(Deserialize)]
+struct OpenRouterUsage {
+    #[serde(default)]
+    prompt_tokens: u64,
+    #[serde(default)]
+    completion_tokens: u64,
+    #[serde(default)]
+    cost: Option<f64>,
 }
 
 #[derive(Deserialize)]
@@ -130,76 +218,28 @@ struct ResponseMessage {
 /// # Arguments
 /// * `audio_base64` - Base64-encoded audio data (WAV, MP3, etc.)
 /// * `api_key` - OpenRouter API key
-/// * `expected_amount` - The amount the user should confirm (for verification)
-/// * `coin_type` - The coin type being transferred (SUI, USDC, etc.)
+/// * `expected` - What the user should confirm (for the prompt and verification)
+/// * `costs` - Metered with the call's reported usage
 pub async fn analyze_audio_gpt4o(
     audio_base64: &str,
     api_key: &str,
-    expected_amount: Option<f64>,
-    coin_type: &str,
+    expected: &Expected<'_>,
+    costs: &CostTracker,
 ) -> Result<AudioAnalysisResult, EnclaveError> {
     use base64::{Engine as _, engine::general_purpose::STANDARD};
     
     // Validate base64 encoding first
-    let audio_bytes = STANDARD.decode(audio_base64)
-        .map_err(|e| EnclaveError::GenericError(format!("Invalid audio base64: {}", e)))?;
-    
-    info!("RAM: Analyzing audio: {} bytes via GPT-4o", audio_bytes.len());
+    let audio_bytes = Zeroizing::new(STANDARD.decode(audio_base64)
+        .map_err(|e| EnclaveError::GenericError(format!("Invalid audio base64: {}", e)))?);
     
-    // Build the request with RAM-specific prompt
-    let expected_info = match expected_amount {
-        Some(amt) => format!("Expected amount: {} {}", amt, coin_type),
-        None => "No specific amount expected".to_string(),
-    };
+    info!("RAM: Analyzing audio: {} bytes via {}", audio_bytes.len(), prompt::current().model);
     
-    let prompt = format!(r#"You are a voice security analyzer for a cryptocurrency wallet called RAM.
-Your job is to analyze the user's voice recording to:
-1. Transcribe exactly what they said
-2. Detect if they are under stress/duress (being forced/coerced)
-3. Extract the monetary amount they mentioned
-
-{}
-
-STRESS DETECTION CRITERIA (0-100 scale):
-- 0-20: Completely calm, relaxed, confident voice
-- 21-40: Normal speaking voice with minor natural nervousness
-- 41-60: Noticeable stress - voice tension, slight trembling, speaking faster than normal
-- 61-79: High stress - clear vocal distress, shaking voice, fear detectable
-- 80-100: EXTREME DURESS - crying, sobbing, screaming, whispering in fear, clearly coerced
-
-VOCAL INDICATORS TO ANALYZE (pay close attention to these):
-- **Pitch**: Is the voice higher than normal? Unstable or wavering pitch?
-- **Tremor**: Does the voice shake or tremble?
-- **Speech rate**: Is speaking unusually fast (panic) or unnaturally slow/robotic (reading a script under threat)?
-- **Breathing**: Heavy, irregular, or panicked breathing?
-- **Vocal quality**: Voice breaking, cracking, crying, sobbing?
-- **Volume**: Whispering (hiding), or shouting (panic/anger)?
-- **Emotional tone**: Does the speaker sound scared, anxious, angry, or distressed?
-- **Background**: Other voices (threats, commands), sounds of struggle?
-- **Content keywords** in ANY language: "help", "please", "forced", "gun", "kidnap", "hurry", "giúp", "cứu", "bắt ép", "sợ", "đe dọa"
-
-IMPORTANT: This is a SECURITY feature. A person under duress (robbery, kidnapping) may TRY to sound calm but still show subtle vocal stress. Pay attention to:
-- Micro-tremors in the voice even if they try to sound steady
-- Unnatural control (trying too hard to sound calm)
-- Any emotional leakage (brief moments of fear breaking through)
-
-AMOUNT EXTRACTION:
-- Listen for numbers followed by currency: "5 SUI", "10.5 USDC", "một trăm SUI"
-- Support both English and Vietnamese number words
-- Vietnamese: một=1, hai=2, ba=3, bốn=4, năm=5, sáu=6, bảy=7, tám=8, chín=9, mười=10, trăm=100, nghìn=1000
-
-Return ONLY valid JSON with these exact fields:
-{{
-  "transcript": "<exact words in original language>",
-  "stress_level": <integer 0-100>,
-  "amount": <number or null if no amount mentioned>
-}}
-
-Do NOT default to low stress scores. Analyze the actual vocal characteristics carefully.
-If there is ANY detectable stress or fear in the voice, reflect it in the score."#, expected_info);
+    // Model and prompt come from configuration (see `prompt`)
+    let config = prompt::current();
+    let prompt = config.prompt(expected).map_err(EnclaveError::GenericError)?;
 
     let request = OpenRouterRequest {
-        model: "openai/gpt-4o-audio-preview".to_string(),
+        model: config.model.clone(),
         messages: vec![ChatMessage {
             role: "user".to_string(),
             content: vec![
@@ -216,20 +256,24 @@ If there is ANY detectable stress or fear in the voice, reflect it in the score.
         modalities: Some(vec!["text".to_string()]), // Only text output, no audio
         audio: None, // No audio output needed
         response_format: None, // gpt-4o-audio-preview doesn't support json_object
+        usage: Some(UsageAccounting { include: true }),
     };
 
     // Make the API call
+    chaos::before_upstream(Upstream::OpenRouter).await?;
     let client = reqwest::Client::new();
-    let response = client
-        .post(OPENROUTER_API_URL)
-        .header("Authorization", format!("Bearer {}", api_key))
-        .header("Content-Type", "application/json")
-        .header("HTTP-Referer", "https://ram.sui.io")
-        .header("X-Title", "RAM Voice Wallet Auth")
-        .json(&request)
-        .send()
-        .await
-        .map_err(|e| EnclaveError::GenericError(format!("OpenRouter API error: {}", e)))?;
+    let url = openrouter_api_url();
+    let response = retry::send(Provider::OpenRouter, || {
+        client
+            .post(&url)
+            .header("Authorization", format!("Bearer {}", api_key))
+            .header("Content-Type", "application/json")
+            .header("HTTP-Referer", "https://ram.sui.io")
+            .header("X-Title", "RAM Voice Wallet Auth")
+            .json(&request)
+    })
+    .await
+    .map_err(|e| EnclaveError::GenericError(format!("OpenRouter API error: {}", e)))?;
 
     if !response.status().is_success() {
         let status = response.status();
@@ -243,6 +287,10 @@ If there is ANY detectable stress or fear in the voice, reflect it in the score.
         .json()
         .await
         .map_err(|e| EnclaveError::GenericError(format!("Failed to parse OpenRouter response: {}", e)))?;
+    let usage = api_response.usage.as_ref().map_or_else(Usage::default, |u| {
+        Usage::from_openrouter(u.prompt_tokens, u.completion_tokens, u.cost)
+    });
+    costs.record(Upstream::OpenRouter, usage);
 
     let content = api_response
         .choices
@@ -250,14 +298,20 @@ If there is ANY detectable stress or fear in the voice, reflect it in the score.
         .map(|c| c.message.content.clone())
         .ok_or_else(|| EnclaveError::GenericError("No response from OpenRouter".to_string()))?;
 
-    info!("GPT-4o response: {}", content);
+    // Content carries the transcript, which may hold the user's panic phrase
+    info!("GPT-4o response received ({} chars)", content.len());
 
     // Parse the JSON response - GPT-4o returns basic fields
     #[derive(Deserialize)]
     struct GptResponse {
         transcript: String,
         stress_level: u8,
-        amount: Option<f64>,
+        /// Decimal text, though a bare number is accepted too
+        amount: Option<serde_json::Value>,
+        /// 0 to 1; absent when the prompt doesn't ask for them
+        transcript_confidence: Option<f32>,
+        amount_confidence: Option<f32>,
+        stress_confidence: Option<f32>,
     }
     
     // Try direct parse first, then extract JSON from mixed text as fallback
@@ -274,38 +328,55 @@ If there is ANY detectable stress or fear in the voice, reflect it in the score.
                 ))
         })?;
     
-    // Verify amount if expected
-    let amount_verified = match (expected_amount, gpt_result.amount) {
-        (Some(expected), Some(detected)) => {
-            let tolerance = 0.01; // 1% tolerance for floating point
-            let diff = (expected - detected).abs() / expected.max(1.0);
-            diff < tolerance
-        },
-        (None, _) => true, // No expectation = always pass
-        (Some(_), None) => false, // Expected but not detected
+    // Parsed straight into raw units, so the comparison never touches floats;
+    // the model sometimes echoes the locale's own separators back
+    let amount = gpt_result
+        .amount
+        .as_ref()
+        .and_then(json_decimal)
+        .and_then(|text| {
+            let locale = NumberLocale::from_tag(expected.locale);
+            expected.coin.parse(&text).or_else(|| locale.parse(&text, expected.coin.decimals))
+        });
+    let amount_verified = match expected.amount {
+        Some(expected) => verify_amount(expected, amount),
+        None => true, // No expectation = always pass
     };
     
+    // Nothing hinges on the amount when none is expected
+    let sure = |reported: Option<f32>| reported.filter(|c| c.is_finite()).map_or(1.0, |c| c.clamp(0.0, 1.0));
+    let confidence = Confidence {
+        transcript: sure(gpt_result.transcript_confidence),
+        amount: if expected.amount.is_some() { sure(gpt_result.amount_confidence) } else { 1.0 },
+        stress: sure(gpt_result.stress_confidence),
+    };
+
     let result = AudioAnalysisResult {
         transcript: gpt_result.transcript.clone(),
         stress_level: gpt_result.stress_level,
-        amount: gpt_result.amount,
+        amount,
         emotions: None,
         amount_verified,
+        transcribed: true,
+        confidence,
+        stress_scores: StressScores::default(),
     };
 
     info!(
-        "RAM audio analysis: transcript='{}', stress={}, amount={:?}, verified={}",
-        result.transcript, result.stress_level, result.amount, result.amount_verified
+        "RAM audio analysis: transcript={} chars, stress={}, amount={:?}, verified={}, confidence={:?}",
+        result.transcript.chars().count(), result.stress_level, result.amount, result.amount_verified, result.confidence
     );
 
     Ok(result)
 }
 
 /// Detect audio format from base64 header bytes
-fn detect_audio_format(audio_base64: &str) -> String {
+pub fn detect_audio_format(audio_base64: &str) -> String {
     use base64::{Engine as _, engine::general_purpose::STANDARD};
     
-    if let Ok(bytes) = STANDARD.decode(audio_base64) {
+    // The magic numbers fit in the first 6 bytes, so only decode those
+    let prefix = audio_base64.get(..8).unwrap_or(audio_base64);
+    if let Ok(bytes) = STANDARD.decode(prefix) {
         if bytes.len() >= 4 {
             // WAV: starts with "RIFF"
             if bytes.starts_with(b"RIFF") {
@@ -339,37 +410,119 @@ fn detect_audio_format(audio_base64: &str) -> String {
 
 /// Analyze audio using Hume AI Expression Measurement
 /// Provides detailed emotion scores for more accurate stress detection
+///
+/// Streams the clip first, where scores come back in about a second; if the
+/// stream fails or misses its timeout the clip is sent as a batch job instead
 pub async fn analyze_audio_hume(
     audio_base64: &str,
     api_key: &str,
+    costs: &CostTracker,
+) -> Result<EmotionScores, EnclaveError> {
+    if hume_streaming_enabled() {
+        let timeout = hume_stream_timeout();
+        match tokio::time::timeout(timeout, analyze_audio_hume_stream(audio_base64, api_key, costs)).await {
+            Ok(Ok(emotions)) => return Ok(emotions),
+            Ok(Err(e)) => warn!("Hume stream failed, falling back to a batch job: {}", e),
+            Err(_) => warn!("Hume stream returned no scores within {:?}, falling back to a batch job", timeout),
+        }
+    }
+    analyze_audio_hume_batch(audio_base64, api_key, costs).await
+}
+
+/// Score the clip over Hume's streaming WebSocket: one prosody request, one reply
+async fn analyze_audio_hume_stream(
+    audio_base64: &str,
+    api_key: &str,
+    costs: &CostTracker,
+) -> Result<EmotionScores, EnclaveError> {
+    use futures_util::{SinkExt, StreamExt};
+    use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+    use tokio_tungstenite::tungstenite::http::HeaderValue;
+    use tokio_tungstenite::tungstenite::Message;
+
+    let mut request = hume_stream_url()
+        .into_client_request()
+        .map_err(|e| EnclaveError::GenericError(format!("Invalid Hume stream URL: {}", e)))?;
+    let key = HeaderValue::from_str(api_key)
+        .map_err(|_| EnclaveError::GenericError("Invalid Hume API key".to_string()))?;
+    request.headers_mut().insert("X-Hume-Api-Key", key);
+
+    info!("RAM: Streaming audio: {} base64 chars to Hume AI", audio_base64.len());
+
+    chaos::before_upstream(Upstream::Hume).await?;
+    let (mut socket, _) = tokio_tungstenite::connect_async(request)
+        .await
+        .map_err(|e| EnclaveError::GenericError(format!("Hume stream error: {}", e)))?;
+
+    let message = serde_json::json!({ "models": { "prosody": {} }, "data": audio_base64 });
+    socket
+        .send(Message::Text(message.to_string()))
+        .await
+        .map_err(|e| EnclaveError::GenericError(format!("Hume stream error: {}", e)))?;
+
+    // Hume answers each message with one JSON reply; skip pings in between
+    let reply = loop {
+        match socket.next().await {
+            Some(Ok(Message::Text(text))) => break text,
+            Some(Ok(Message::Close(_))) | None => {
+                return Err(EnclaveError::GenericError("Hume stream closed before replying".to_string()));
+            }
+            Some(Ok(_)) => continue,
+            Some(Err(e)) => return Err(EnclaveError::GenericError(format!("Hume stream error: {}", e))),
+        }
+    };
+    // The scores are in; a failed close changes nothing
+    let _ = socket.close(None).await;
+
+    let reply: serde_json::Value = serde_json::from_str(&reply)
+        .map_err(|e| EnclaveError::GenericError(format!("Failed to parse Hume stream reply: {}", e)))?;
+    if let Some(error) = reply.get("error") {
+        return Err(EnclaveError::GenericError(format!("Hume stream returned an error: {}", error)));
+    }
+
+    // Hume bills a scored message whatever we make of the scores
+    costs.record(Upstream::Hume, Usage::default());
+
+    let emotions = extract_hume_stream_emotions(&reply)?;
+
+    info!("Hume streamed emotion analysis: fear={:.2}, anxiety={:.2}, distress={:.2}",
+        emotions.fear, emotions.anxiety, emotions.distress);
+
+    Ok(emotions)
+}
+
+/// Score the clip as a Hume batch job
+async fn analyze_audio_hume_batch(
+    audio_base64: &str,
+    api_key: &str,
+    costs: &CostTracker,
 ) -> Result<EmotionScores, EnclaveError> {
     use base64::{Engine as _, engine::general_purpose::STANDARD};
     
-    let audio_bytes = STANDARD.decode(audio_base64)
-        .map_err(|e| EnclaveError::GenericError(format!("Invalid audio base64: {}", e)))?;
+    // Copied into each attempt's multipart body; reqwest owns and frees those copies
+    let audio_bytes = Zeroizing::new(STANDARD.decode(audio_base64)
+        .map_err(|e| EnclaveError::GenericError(format!("Invalid audio base64: {}", e)))?);
     
     info!("RAM: Analyzing audio: {} bytes via Hume AI", audio_bytes.len());
     
     // Hume API request for prosody (voice) analysis
     let client = reqwest::Client::new();
+    let url = hume_api_url();
     
-    // Create multipart form with audio file
-    let part = reqwest::multipart::Part::bytes(audio_bytes)
-        .file_name("audio.wav")
-        .mime_str("audio/wav")
-        .map_err(|e| EnclaveError::GenericError(format!("Failed to create audio part: {}", e)))?;
-    
-    let form = reqwest::multipart::Form::new()
-        .part("file", part)
-        .text("models", r#"{"prosody": {}}"#);
-    
-    let response = client
-        .post(HUME_API_URL)
-        .header("X-Hume-Api-Key", api_key)
-        .multipart(form)
-        .send()
-        .await
-        .map_err(|e| EnclaveError::GenericError(format!("Hume API error: {}", e)))?;
+    chaos::before_upstream(Upstream::Hume).await?;
+    let response = retry::send(Provider::Hume, || {
+        // Multipart form with the audio file, rebuilt for each attempt
+        let part = reqwest::multipart::Part::bytes(audio_bytes.to_vec())
+            .file_name("audio.wav")
+            .mime_str("audio/wav")
+            .expect("valid MIME type");
+        let form = reqwest::multipart::Form::new()
+            .part("file", part)
+            .text("models", r#"{"prosody": {}}"#);
+        client.post(&url).header("X-Hume-Api-Key", api_key).multipart(form)
+    })
+    .await
+    .map_err(|e| EnclaveError::GenericError(format!("Hume API error: {}", e)))?;
     
     if !response.status().is_success() {
         let status = response.status();
@@ -379,6 +532,9 @@ pub async fn analyze_audio_hume(
         )));
     }
     
+    // Hume bills the call whatever we make of the response
+    costs.record(Upstream::Hume, Usage::default());
+
     // Parse Hume response and extract emotion scores
     let hume_response: serde_json::Value = response
         .json()
@@ -412,6 +568,40 @@ fn extract_hume_emotions(response: &serde_json::Value) -> Result<EmotionScores,
         .and_then(|e| e.as_array())
         .ok_or_else(|| EnclaveError::GenericError("No emotions in Hume response".to_string()))?;
     
+    Ok(emotion_scores(emotions_array))
+}
+
+/// Extract emotion scores from a Hume stream reply
+///
+/// The reply holds one prediction per segment of the clip. Each emotion keeps
+/// its peak across segments, since duress may only show in part of the clip.
+fn extract_hume_stream_emotions(reply: &serde_json::Value) -> Result<EmotionScores, EnclaveError> {
+    // Hume streams emotions in prosody.predictions[*].emotions
+    let segments: Vec<EmotionScores> = reply
+        .get("prosody")
+        .and_then(|p| p.get("predictions"))
+        .and_then(|p| p.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|prediction| prediction.get("emotions").and_then(|e| e.as_array()))
+        .map(|emotions| emotion_scores(emotions))
+        .collect();
+    
+    segments
+        .into_iter()
+        .reduce(|peak, segment| EmotionScores {
+            fear: peak.fear.max(segment.fear),
+            anxiety: peak.anxiety.max(segment.anxiety),
+            distress: peak.distress.max(segment.distress),
+            anger: peak.anger.max(segment.anger),
+            sadness: peak.sadness.max(segment.sadness),
+            confusion: peak.confusion.max(segment.confusion),
+        })
+        .ok_or_else(|| EnclaveError::GenericError("No emotions in Hume stream reply".to_string()))
+}
+
+/// The emotions we track out of a Hume `[{name, score}]` list
+fn emotion_scores(emotions_array: &[serde_json::Value]) -> EmotionScores {
     let mut scores = EmotionScores::default();
     
     for emotion in emotions_array {
@@ -429,7 +619,7 @@ fn extract_hume_emotions(response: &serde_json::Value) -> Result<EmotionScores,
         }
     }
     
-    Ok(scores)
+    scores
 }
 
 /// Calculate stress level from Hume emotion scores
@@ -453,40 +643,89 @@ pub fn calculate_stress_from_emotions(emotions: &EmotionScores) -> u8 {
 // UNIFIED ANALYSIS FUNCTION
 // ============================================================================
 
+/// Upstream API keys (None or empty = not configured) and the tracker their
+/// calls are metered against
+pub struct Upstreams<'a> {
+    pub openrouter_api_key: Option<&'a str>,
+    pub hume_api_key: Option<&'a str>,
+    pub costs: &'a CostTracker,
+}
+
+/// What the user was asked to confirm
+#[derive(Debug, Clone, Copy)]
+pub struct Expected<'a> {
+    /// Amount they should speak, if any
+    pub amount: Option<RawAmount>,
+    /// The prompt names it by its registry symbol
+    pub coin: &'a CoinInfo,
+    /// BCP 47 tag of how they write numbers; the amount is shown that way
+    pub locale: Option<&'a str>,
+    /// The exact phrase they were asked to say, if there is one
+    pub phrase: Option<&'a str>,
+}
+
+impl<'a> Upstreams<'a> {
+    /// The provider's key, unless it has spent today's budget (skipped until 00:00 UTC)
+    fn key(&self, upstream: Upstream) -> Option<&'a str> {
+        let key = match upstream {
+            Upstream::OpenRouter => self.openrouter_api_key,
+            Upstream::Hume => self.hume_api_key,
+        };
+        match key {
+            Some(key) if !key.is_empty() && !self.costs.allows(upstream) => {
+                warn!("RAM: {:?} daily budget exhausted, skipping it", upstream);
+                None
+            }
+            key => key,
+        }
+    }
+}
+
 /// Main entry point for audio analysis
 /// Tries GPT-4o first, falls back to mock if no API key
 /// Optionally enhances with Hume AI for better stress detection
+/// Providers past their daily budget are skipped (DSP-only stress)
 pub async fn analyze_audio(
     audio_base64: &str,
-    openrouter_api_key: Option<&str>,
-    hume_api_key: Option<&str>,
-    expected_amount: Option<f64>,
-    coin_type: &str,
+    upstreams: Upstreams<'_>,
+    expected: Expected<'_>,
+    baseline: Option<&voice_stress::VoiceBaseline>,
+    progress: &Progress,
 ) -> Result<AudioAnalysisResult, EnclaveError> {
     // === Step 1: DSP-based voice stress analysis (always runs) ===
-    // Analyze the raw WAV audio for acoustic stress indicators
-    let dsp_stress = {
-        use base64::{Engine as _, engine::general_purpose::STANDARD};
-        match STANDARD.decode(audio_base64) {
-            Ok(wav_bytes) => {
-                let analysis = voice_stress::analyze_voice_stress(&wav_bytes);
+    // Analyze the raw WAV audio for acoustic stress indicators, relative to
+    // the speaker's enrolled baseline when there is one
+    // A clip that isn't a WAV gets a neutral DSP score, which isn't a sub-score
+    let (dsp_stress, dsp_scored) = {
+        match voice_stress::decode_wav_base64(audio_base64) {
+            Ok(pcm) => {
+                let scored = pcm.is_some();
+                let analysis = voice_stress::analyze_pcm(pcm, baseline);
                 info!("RAM: DSP stress analysis: level={}, reasons={:?}", 
                     analysis.stress_level, analysis.reasons);
-                analysis.stress_level
+                (analysis.stress_level, scored)
             },
             Err(e) => {
                 warn!("RAM: Failed to decode audio for DSP analysis: {}", e);
-                0u8
+                (0u8, false)
             }
         }
     };
+    let dsp_score = dsp_scored.then_some(dsp_stress);
+    progress.report(BioAuthStage::Decoded);
+
+    let costs = upstreams.costs;
+    let openrouter_api_key = upstreams.key(Upstream::OpenRouter);
+    let hume_api_key = upstreams.key(Upstream::Hume);
 
     // === Step 2: GPT-4o content analysis (if API key available) ===
     if let Some(api_key) = openrouter_api_key {
         if !api_key.is_empty() {
-            match analyze_audio_gpt4o(audio_base64, api_key, expected_amount, coin_type).await {
+            match analyze_audio_gpt4o(audio_base64, api_key, &expected, costs).await {
                 Ok(mut result) => {
+                    progress.report(BioAuthStage::Transcribed);
                     let gpt_stress = result.stress_level;
+                    result.stress_scores = StressScores { gpt: Some(gpt_stress), hume: None, dsp: dsp_score };
                     
                     // Combine: use MAX of DSP and GPT-4o stress
                     // If EITHER method detects stress, we should flag it
@@ -500,7 +739,7 @@ pub async fn analyze_audio(
                     // Optionally enhance with Hume AI for stress detection
                     if let Some(hume_key) = hume_api_key {
                         if !hume_key.is_empty() {
-                            match analyze_audio_hume(audio_base64, hume_key).await {
+                            match analyze_audio_hume(audio_base64, hume_key, costs).await {
                                 Ok(emotions) => {
                                     let hume_stress = calculate_stress_from_emotions(&emotions);
                                     // Take max of all three
@@ -510,6 +749,7 @@ pub async fn analyze_audio(
                                         hume_stress, final_stress);
                                     
                                     result.stress_level = final_stress;
+                                    result.stress_scores.hume = Some(hume_stress);
                                     result.emotions = Some(emotions);
                                 },
                                 Err(e) => {
@@ -530,7 +770,9 @@ pub async fn analyze_audio(
     
     // Fallback to mock implementation but use DSP stress score
     warn!("Using mock audio analysis (GPT-4o unavailable or failed)");
-    let mut mock_result = analyze_audio_mock(audio_base64, expected_amount, coin_type)?;
+    let mut mock_result = analyze_audio_mock(audio_base64, expected.amount, expected.coin)?;
+    progress.report(BioAuthStage::Transcribed);
+    mock_result.stress_scores.dsp = dsp_score;
     // Override mock stress with DSP stress if higher
     if dsp_stress > mock_result.stress_level {
         info!("RAM: Overriding mock stress {} with DSP stress {}", mock_result.stress_level, dsp_stress);
@@ -546,38 +788,33 @@ pub async fn analyze_audio(
 /// Complete mock analysis (MOCKED fallback)
 pub fn analyze_audio_mock(
     audio_base64: &str,
-    expected_amount: Option<f64>,
-    _coin_type: &str, // unused in mock, but kept for API consistency
+    expected_amount: Option<RawAmount>,
+    coin: &CoinInfo,
 ) -> Result<AudioAnalysisResult, EnclaveError> {
     use base64::{Engine as _, engine::general_purpose::STANDARD};
     
-    let audio_bytes = STANDARD.decode(audio_base64)
-        .map_err(|e| EnclaveError::GenericError(format!("Invalid audio base64: {}", e)))?;
+    let audio_bytes = Zeroizing::new(STANDARD.decode(audio_base64)
+        .map_err(|e| EnclaveError::GenericError(format!("Invalid audio base64: {}", e)))?);
     
     warn!("RAM: Using MOCK audio analysis (no API keys)");
     info!("Received audio: {} bytes", audio_bytes.len());
     
     // Mock transcript based on audio size
     let (transcript, mock_amount) = if audio_bytes.len() < 1000 {
-        ("confirm sending 5 SUI".to_string(), Some(5.0))
+        ("confirm sending 5 SUI".to_string(), coin.parse("5"))
     } else if audio_bytes.len() < 5000 {
-        ("yes confirm transfer of 10 SUI".to_string(), Some(10.0))
+        ("yes confirm transfer of 10 SUI".to_string(), coin.parse("10"))
     } else {
-        ("I confirm sending 100 SUI to the specified address".to_string(), Some(100.0))
+        ("I confirm sending 100 SUI to the specified address".to_string(), coin.parse("100"))
     };
     
     // Check for stress keywords in any mock scenario
     let stress_level = analyze_stress_from_transcript(&transcript, audio_bytes.len());
     
     // Verify amount
-    let amount_verified = match (expected_amount, mock_amount) {
-        (Some(expected), Some(detected)) => {
-            let tolerance = 0.01;
-            let diff = (expected - detected).abs() / expected.max(1.0);
-            diff < tolerance
-        },
-        (None, _) => true,
-        (Some(_), None) => false,
+    let amount_verified = match expected_amount {
+        Some(expected) => verify_amount(expected, mock_amount),
+        None => true,
     };
     
     let result = AudioAnalysisResult {
@@ -586,10 +823,13 @@ pub fn analyze_audio_mock(
         amount: mock_amount,
         emotions: None,
         amount_verified,
+        transcribed: false,
+        confidence: Confidence::default(),
+        stress_scores: StressScores::default(),
     };
     
-    info!("Mock analysis result: transcript='{}', stress={}, amount={:?}, verified={}", 
-        result.transcript, result.stress_level, result.amount, result.amount_verified);
+    info!("Mock analysis result: transcript={} chars, stress={}, amount={:?}, verified={}", 
+        result.transcript.chars().count(), result.stress_level, result.amount, result.amount_verified);
     
     Ok(result)
 }
@@ -598,8 +838,8 @@ pub fn analyze_audio_mock(
 pub fn transcribe_audio_mock(audio_base64: &str) -> Result<String, EnclaveError> {
     use base64::{Engine as _, engine::general_purpose::STANDARD};
     
-    let audio_bytes = STANDARD.decode(audio_base64)
-        .map_err(|e| EnclaveError::GenericError(format!("Invalid audio base64: {}", e)))?;
+    let audio_bytes = Zeroizing::new(STANDARD.decode(audio_base64)
+        .map_err(|e| EnclaveError::GenericError(format!("Invalid audio base64: {}", e)))?);
     
     warn!("RAM: Using MOCK transcription (no OPENROUTER_API_KEY)");
     info!("Received audio: {} bytes", audio_bytes.len());
@@ -654,8 +894,8 @@ fn analyze_stress_from_transcript(transcript: &str, audio_length: usize) -> u8 {
 pub fn analyze_stress_mock(audio_base64: &str, transcript: &str) -> Result<u8, EnclaveError> {
     use base64::{Engine as _, engine::general_purpose::STANDARD};
     
-    let audio_bytes = STANDARD.decode(audio_base64)
-        .map_err(|e| EnclaveError::GenericError(format!("Invalid audio: {}", e)))?;
+    let audio_bytes = Zeroizing::new(STANDARD.decode(audio_base64)
+        .map_err(|e| EnclaveError::GenericError(format!("Invalid audio: {}", e)))?);
     
     warn!("RAM: Using MOCK stress analysis (no OPENROUTER_API_KEY)");
     
@@ -713,37 +953,29 @@ pub fn is_under_duress(stress_level: u8) -> bool {
 /// Parse amount from transcript text
 /// Supports formats: "5 SUI", "5.5 USDC", "100 tokens"
 /// Also supports Vietnamese: "năm SUI", "mười USDC"
-pub fn parse_amount_from_text(text: &str, coin_type: &str) -> Option<u64> {
+pub fn parse_amount_from_text(text: &str, coin: &CoinInfo) -> Option<RawAmount> {
     let words: Vec<&str> = text.split_whitespace().collect();
     
     for (i, word) in words.iter().enumerate() {
         // Try parsing as number
-        if let Ok(amount) = word.parse::<f64>() {
+        if let Some(amount) = coin.parse(word) {
             // Check if next word is the coin type
             if i + 1 < words.len() {
                 let next_word = words[i + 1].to_uppercase();
-                if next_word == coin_type.to_uppercase() || 
-                   next_word.starts_with(&coin_type.to_uppercase()) {
-                    let decimals = get_decimals_for_coin(coin_type);
-                    let multiplier = 10_u64.pow(decimals);
-                    return Some((amount * multiplier as f64) as u64);
+                if next_word == coin.symbol || next_word.starts_with(&coin.symbol) {
+                    return Some(amount);
                 }
             }
             // If no coin type specified, assume it's the amount
-            let decimals = get_decimals_for_coin(coin_type);
-            let multiplier = 10_u64.pow(decimals);
-            return Some((amount * multiplier as f64) as u64);
+            return Some(amount);
         }
         
         // Try parsing Vietnamese number words
         if let Some(amount) = parse_vietnamese_number(word) {
             if i + 1 < words.len() {
                 let next_word = words[i + 1].to_uppercase();
-                if next_word == coin_type.to_uppercase() || 
-                   next_word.starts_with(&coin_type.to_uppercase()) {
-                    let decimals = get_decimals_for_coin(coin_type);
-                    let multiplier = 10_u64.pow(decimals);
-                    return Some((amount as f64 * multiplier as f64) as u64);
+                if next_word == coin.symbol || next_word.starts_with(&coin.symbol) {
+                    return amount.checked_mul(coin.multiplier()).map(RawAmount);
                 }
             }
         }
@@ -773,59 +1005,146 @@ fn parse_vietnamese_number(word: &str) -> Option<u64> {
     }
 }
 
-/// Get decimal places for coin type
-fn get_decimals_for_coin(coin_type: &str) -> u32 {
-    match coin_type.to_uppercase().as_str() {
-        "SUI" => 9,
-        "USDC" => 6,
-        "USDT" => 6,
-        "WAL" => 9,
-        _ => 9, // Default to 9 decimals
+/// Number of trailing address characters the user must speak for a withdrawal
+/// (the shortest a handle's `challenge_length` preference allows)
+pub const DESTINATION_SUFFIX_LEN: usize = 4;
+/// Most trailing address characters a handle can choose to speak
+pub const MAX_DESTINATION_SUFFIX_LEN: usize = 8;
+
+/// The characters a user must speak to confirm a withdrawal destination
+///
+/// Accepts a full address (`0x...`) or just its ending; returns its last `len`
+/// hex characters (all of them if it's shorter, as long as it has at least
+/// `DESTINATION_SUFFIX_LEN`), lowercased.
+pub fn destination_suffix(destination: &str, len: usize) -> Option<String> {
+    let hex: Vec<char> = destination
+        .trim()
+        .trim_start_matches("0x")
+        .chars()
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    if hex.len() < DESTINATION_SUFFIX_LEN || !hex.iter().all(|c| c.is_ascii_hexdigit()) {
+        return None;
     }
+    Some(hex[hex.len().saturating_sub(len)..].iter().collect())
 }
 
-/// Verify that detected amount matches expected amount
-pub fn verify_amount(expected: u64, detected: Option<f64>, coin_type: &str) -> bool {
-    match detected {
-        Some(detected_val) => {
-            let decimals = get_decimals_for_coin(coin_type);
-            let multiplier = 10_u64.pow(decimals);
-            let detected_raw = (detected_val * multiplier as f64) as u64;
-            
-            // Allow 1% tolerance for floating point
-            let tolerance = expected / 100;
-            let diff = if expected > detected_raw {
-                expected - detected_raw
-            } else {
-                detected_raw - expected
-            };
-            
-            diff <= tolerance
-        },
-        None => false,
+/// Map one spoken token to the address characters it stands for
+///
+/// Handles spelled digits/letters ("nine", "bee", "ef") and short literal
+/// chunks ("3f", "9a"). Longer ordinary words return None and break a run.
+fn spoken_address_chars(token: &str) -> Option<String> {
+    let word = token.to_lowercase();
+    let mapped = match word.as_str() {
+        "zero" | "oh" | "không" | "khong" => "0",
+        "one" | "một" | "mot" => "1",
+        "two" | "hai" => "2",
+        "three" | "ba" => "3",
+        "four" | "bốn" | "bon" => "4",
+        "five" | "năm" | "nam" => "5",
+        "six" | "sáu" | "sau" => "6",
+        "seven" | "bảy" | "bay" => "7",
+        "eight" | "tám" | "tam" => "8",
+        "nine" | "chín" | "chin" => "9",
+        "ay" | "eh" => "a",
+        "bee" | "be" => "b",
+        "see" | "sea" | "cee" => "c",
+        "dee" => "d",
+        "ee" => "e",
+        "ef" | "eff" => "f",
+        _ if word.len() <= MAX_DESTINATION_SUFFIX_LEN && word.chars().all(|c| c.is_ascii_hexdigit()) => {
+            return Some(word);
+        }
+        _ => return None,
+    };
+    Some(mapped.to_string())
+}
+
+/// Check that the transcript contains the destination's `len`-character address suffix
+///
+/// The suffix may be spoken as one chunk ("3f9a") or character by character
+/// ("three F nine A"); characters must be consecutive, so the suffix can't be
+/// assembled from letters scattered across unrelated words.
+pub fn verify_destination_suffix(transcript: &str, destination: &str, len: usize) -> bool {
+    let Some(suffix) = destination_suffix(destination, len) else {
+        return false;
+    };
+
+    let mut run = String::new();
+    for token in transcript.split(|c: char| c.is_whitespace() || c == ',' || c == '-' || c == '.') {
+        if token.is_empty() {
+            continue;
+        }
+        match spoken_address_chars(token) {
+            Some(chars) => {
+                run.push_str(&chars);
+                if run.contains(&suffix) {
+                    return true;
+                }
+            }
+            None => run.clear(),
+        }
     }
+    false
+}
+
+/// Verify that detected amount matches expected amount (both raw units)
+pub fn verify_amount(expected: RawAmount, detected: Option<RawAmount>) -> bool {
+    detected.is_some_and(|detected| detected.matches(expected))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+
+    #[test]
+    fn test_destination_suffix() {
+        assert_eq!(destination_suffix("0xABCDEF0123456789", DESTINATION_SUFFIX_LEN).as_deref(), Some("6789"));
+        assert_eq!(destination_suffix("3f9a", DESTINATION_SUFFIX_LEN).as_deref(), Some("3f9a"));
+        assert_eq!(destination_suffix("0x12", DESTINATION_SUFFIX_LEN), None);
+        assert_eq!(destination_suffix("0xzzzz", DESTINATION_SUFFIX_LEN), None);
+        // A longer chosen suffix, or the whole hint when it's shorter than that
+        assert_eq!(destination_suffix("0xABCDEF0123456789", 8).as_deref(), Some("23456789"));
+        assert_eq!(destination_suffix("3f9a12", 8).as_deref(), Some("3f9a12"));
+    }
+
+    #[test]
+    fn test_verify_destination_suffix_spoken_forms() {
+        let dest = "0x00000000000000000000000000000000000000000000000000000000deadb3f9a";
+        assert!(verify_destination_suffix("withdraw 5 SUI to address ending 3f9a", dest, DESTINATION_SUFFIX_LEN));
+        assert!(verify_destination_suffix("withdraw 5 SUI to address ending three F nine A", dest, DESTINATION_SUFFIX_LEN));
+        assert!(verify_destination_suffix("ending in 3, f, 9, a", dest, DESTINATION_SUFFIX_LEN));
+        assert!(!verify_destination_suffix("withdraw 5 SUI to address ending 3f9b", dest, DESTINATION_SUFFIX_LEN));
+        assert!(!verify_destination_suffix("withdraw 5 SUI", dest, DESTINATION_SUFFIX_LEN));
+        // Letters scattered across words must not count
+        assert!(!verify_destination_suffix("three friends nine apples", dest, DESTINATION_SUFFIX_LEN));
+        // Handles that chose a longer suffix must speak all of it
+        assert!(verify_destination_suffix("ending eadb 3f9a", dest, 8));
+        assert!(verify_destination_suffix("ending e, a, d, b, three, f, nine, a", dest, 8));
+        assert!(!verify_destination_suffix("withdraw 5 SUI to address ending 3f9a", dest, 8));
+    }
     
     #[test]
     fn test_parse_amount_sui() {
-        let result = parse_amount_from_text("confirm sending 5 SUI", "SUI");
-        assert_eq!(result, Some(5_000_000_000));
+        let result = parse_amount_from_text("confirm sending 5 SUI", &CoinInfo::sui());
+        assert_eq!(result, Some(RawAmount(5_000_000_000)));
     }
     
     #[test]
     fn test_parse_amount_usdc() {
-        let result = parse_amount_from_text("transfer 10.5 USDC to alice", "USDC");
-        assert_eq!(result, Some(10_500_000));
+        let usdc = CoinInfo {
+            coin_type: crate::apps::ram::coin::CoinType::parse("0xc0ffee::usdc::USDC").unwrap(),
+            symbol: "USDC".to_string(),
+            decimals: 6,
+        };
+        let result = parse_amount_from_text("transfer 10.5 USDC to alice", &usdc);
+        assert_eq!(result, Some(RawAmount(10_500_000)));
     }
     
     #[test]
     fn test_parse_amount_no_coin() {
-        let result = parse_amount_from_text("yes confirm 100", "SUI");
-        assert_eq!(result, Some(100_000_000_000));
+        let result = parse_amount_from_text("yes confirm 100", &CoinInfo::sui());
+        assert_eq!(result, Some(RawAmount(100_000_000_000)));
     }
     
     #[test]
@@ -865,19 +1184,20 @@ mod tests {
 
     #[test]
     fn test_parse_amount_with_decimal() {
-        let result = parse_amount_from_text("send 2.5 SUI please", "SUI");
-        assert_eq!(result, Some(2_500_000_000));
+        let result = parse_amount_from_text("send 2.5 SUI please", &CoinInfo::sui());
+        assert_eq!(result, Some(RawAmount(2_500_000_000)));
     }
     
     #[test]
     fn test_verify_amount() {
         // 5 SUI = 5_000_000_000 raw
-        assert!(verify_amount(5_000_000_000, Some(5.0), "SUI"));
-        assert!(!verify_amount(5_000_000_000, Some(10.0), "SUI"));
-        assert!(!verify_amount(5_000_000_000, None, "SUI"));
+        let expected = RawAmount(5_000_000_000);
+        assert!(verify_amount(expected, Some(RawAmount(5_000_000_000))));
+        assert!(!verify_amount(expected, Some(RawAmount(10_000_000_000))));
+        assert!(!verify_amount(expected, None));
         
         // Allow small tolerance
-        assert!(verify_amount(5_000_000_000, Some(5.01), "SUI"));
+        assert!(verify_amount(expected, Some(RawAmount(5_010_000_000))));
     }
     
     #[test]
@@ -921,13 +1241,28 @@ mod tests {
         };
         assert!(calculate_stress_from_emotions(&duress) >= 70);
     }
-    
+
+    #[test]
+    fn test_hume_stream_reply_keeps_peak_emotions() {
+        let reply = serde_json::json!({
+            "prosody": { "predictions": [
+                { "emotions": [{ "name": "Fear", "score": 0.2 }, { "name": "Distress", "score": 0.7 }] },
+                { "emotions": [{ "name": "Fear", "score": 0.9 }, { "name": "Distress", "score": 0.1 }] },
+            ]}
+        });
+        let emotions = extract_hume_stream_emotions(&reply).unwrap();
+        assert_eq!((emotions.fear, emotions.distress), (0.9, 0.7));
+
+        let error = serde_json::json!({ "error": "Payload too long", "code": "E0203" });
+        assert!(extract_hume_stream_emotions(&error).is_err());
+    }
+
     #[test]
     fn test_mock_analysis() {
         use base64::{engine::general_purpose::STANDARD, Engine as _};
         let audio = STANDARD.encode(&[0u8; 100]);
         
-        let result = analyze_audio_mock(&audio, Some(5.0), "SUI").unwrap();
+        let result = analyze_audio_mock(&audio, Some(RawAmount(5_000_000_000)), &CoinInfo::sui()).unwrap();
         assert!(!result.transcript.is_empty());
         assert!(result.stress_level < 70); // Normal mock shouldn't trigger duress
         assert!(result.amount.is_some());
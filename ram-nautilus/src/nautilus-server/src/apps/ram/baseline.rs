@@ -0,0 +1,189 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! Per-handle voice baselines
+//!
+//! Pitch, jitter and tension vary a lot between speakers, so absolute DSP
+//! thresholds over-flag naturally high or unsteady voices. A handle can enroll
+//! a few calm samples; once complete, bio-auth scores stress relative to that
+//! baseline. Baselines live only in enclave memory (never exported or
+//! persisted) and are lost on restart, in which case scoring falls back to the
+//! absolute thresholds until the user re-enrolls.
+//!
+//! Only samples that already read as calm are accepted, and a completed
+//! baseline is frozen, so an attacker can't enroll a stressed voice to mask
+//! duress later. Operators can reset a baseline over the privileged channel.
+//!
+//! Enrollment can also register a covert panic phrase (see `panic_phrase`),
+//! which is frozen and reset together with the baseline.
+
+use crate::common::ProcessDataRequest;
+use crate::AppState;
+use crate::EnclaveError;
+use axum::extract::{Path, State};
+use axum::http::HeaderMap;
+use axum::Json;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tracing::{info, warn};
+
+use super::audio;
+use super::diagnostics::authorize_privileged;
+use super::panic_phrase::PanicPhrase;
+use super::types::{EnrollVoiceRequest, EnrollVoiceResponse};
+use super::validation::{normalize_handle, ValidatedJson};
+use super::voice_stress::{self, VoiceBaseline, BASELINE_MIN_SAMPLES};
+
+/// In-memory voice baselines and panic phrases keyed by normalized handle
+#[derive(Default)]
+pub struct BaselineStore {
+    baselines: Mutex<HashMap<String, VoiceBaseline>>,
+    panic_phrases: Mutex<HashMap<String, PanicPhrase>>,
+}
+
+impl BaselineStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Baseline for a handle, complete or not
+    pub fn get(&self, handle: &str) -> Option<VoiceBaseline> {
+        self.baselines.lock().unwrap().get(&normalize_handle(handle)).cloned()
+    }
+
+    /// Panic phrase registered for a handle
+    pub fn panic_phrase(&self, handle: &str) -> Option<PanicPhrase> {
+        self.panic_phrases.lock().unwrap().get(&normalize_handle(handle)).cloned()
+    }
+
+    /// Add a calm sample's features, failing with `Conflict` once the baseline is frozen
+    ///
+    /// A `panic_phrase` replaces the handle's current one.
+    pub fn enroll(
+        &self,
+        handle: &str,
+        features: &voice_stress::AcousticFeatures,
+        panic_phrase: Option<PanicPhrase>,
+    ) -> Result<VoiceBaseline, EnclaveError> {
+        let key = normalize_handle(handle);
+        let mut baselines = self.baselines.lock().unwrap();
+        let baseline = baselines.entry(key.clone()).or_default();
+        if baseline.is_ready() {
+            return Err(EnclaveError::Conflict(format!(
+                "Voice baseline for '{}' is already enrolled",
+                handle
+            )));
+        }
+        baseline.add_sample(features);
+        if let Some(phrase) = panic_phrase {
+            self.panic_phrases.lock().unwrap().insert(key, phrase);
+        }
+        Ok(baseline.clone())
+    }
+
+    /// Drop a handle's baseline and panic phrase
+    pub fn remove(&self, handle: &str) -> bool {
+        let key = normalize_handle(handle);
+        self.panic_phrases.lock().unwrap().remove(&key);
+        self.baselines.lock().unwrap().remove(&key).is_some()
+    }
+}
+
+/// Enroll one calm voice sample towards a handle's baseline
+///
+/// POST /bio_auth/enroll
+pub async fn process_enroll_voice(
+    State(state): State<Arc<AppState>>,
+    ValidatedJson(request): ValidatedJson<ProcessDataRequest<EnrollVoiceRequest>>,
+) -> Result<Json<EnrollVoiceResponse>, EnclaveError> {
+    let req = &request.payload;
+    // Validate before analysis so a bad phrase doesn't cost a sample
+    let panic_phrase = req.panic_phrase.as_deref().map(PanicPhrase::new).transpose()?;
+
+    let analysis = voice_stress::analyze_voice_stress_base64(&req.audio_base64, None)
+        .map_err(|e| EnclaveError::GenericError(format!("Invalid audio encoding: {}", e)))?;
+
+    // A sample that already reads as duress would teach the baseline to ignore it
+    if audio::is_under_duress(analysis.stress_level) {
+        warn!(
+            "RAM Enroll: rejected stressed sample for '{}' (stress_level={})",
+            req.handle, analysis.stress_level
+        );
+        return Err(EnclaveError::GenericError(
+            "Sample is not calm enough to enroll, please record again".to_string(),
+        ));
+    }
+
+    let phrase_registered = panic_phrase.is_some();
+    let baseline = state.voice_baselines.enroll(&req.handle, &analysis.features, panic_phrase)?;
+    info!(
+        "RAM Enroll: '{}' has {}/{} baseline samples{}",
+        req.handle,
+        baseline.samples,
+        BASELINE_MIN_SAMPLES,
+        if phrase_registered { ", panic phrase registered" } else { "" }
+    );
+
+    Ok(Json(EnrollVoiceResponse {
+        handle: req.handle.clone(),
+        samples: baseline.samples,
+        required: BASELINE_MIN_SAMPLES,
+        complete: baseline.is_ready(),
+        panic_phrase_set: state.voice_baselines.panic_phrase(&req.handle).is_some(),
+    }))
+}
+
+/// Drop a handle's baseline so the user can re-enroll
+///
+/// DELETE /bio_auth/baseline/:handle
+/// Requires `Authorization: Bearer <DIAGNOSTICS_API_SECRET>`.
+pub async fn reset_voice_baseline(
+    State(state): State<Arc<AppState>>,
+    Path(handle): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<Value>, EnclaveError> {
+    authorize_privileged(&headers, &state.diagnostics_secret)?;
+
+    if !state.voice_baselines.remove(&handle) {
+        return Err(EnclaveError::NotFound(format!("No voice baseline for '{}'", handle)));
+    }
+    info!("RAM Enroll: baseline for '{}' reset by operator", handle);
+
+    Ok(Json(json!({ "handle": handle, "reset": true })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn features() -> voice_stress::AcousticFeatures {
+        voice_stress::AcousticFeatures {
+            pitch_jitter: 0.02,
+            energy_variance: 0.2,
+            zero_crossing_rate: 0.1,
+            high_freq_ratio: 0.2,
+            rms_energy: 0.3,
+            estimated_f0: 210.0,
+        }
+    }
+
+    #[test]
+    fn test_enroll_freezes_when_complete() {
+        let store = BaselineStore::new();
+        for i in 1..=BASELINE_MIN_SAMPLES {
+            let phrase = (i == 1).then(|| PanicPhrase::new("blue heron").unwrap());
+            assert_eq!(store.enroll("alice", &features(), phrase).unwrap().samples, i);
+        }
+        assert!(store.get("alice").unwrap().is_ready());
+        // Frozen with the baseline: a coercer can't replace the phrase
+        let other = PanicPhrase::new("green fox").unwrap();
+        assert!(matches!(store.enroll("alice", &features(), Some(other)), Err(EnclaveError::Conflict(_))));
+        assert_eq!(store.panic_phrase("alice"), Some(PanicPhrase::new("blue heron").unwrap()));
+
+        assert!(store.remove("alice"));
+        assert!(store.get("alice").is_none());
+        assert!(store.panic_phrase("alice").is_none());
+        assert!(!store.remove("alice"));
+    }
+}
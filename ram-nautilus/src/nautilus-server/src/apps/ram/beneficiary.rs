@@ -0,0 +1,66 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! Inactivity beneficiary ("dead man's switch")
+//!
+//! `POST /set_beneficiary` signs a `BeneficiaryPayload` naming the wallet that
+//! may claim this one's funds once it has been inactive for
+//! `inactivity_timeout_ms`, for the contract's `inheritance::set_beneficiary`.
+//! An empty `beneficiary_handle` removes it. Like a transfer, it needs no
+//! voice: the claim window only opens after a long silence, any activity
+//! pushes it back, and the backend warns the owner before it opens.
+
+use crate::common::{IntentScope, ProcessDataRequest};
+use crate::AppState;
+use crate::EnclaveError;
+use axum::extract::State;
+use axum::Json;
+use std::sync::Arc;
+use tracing::info;
+
+use super::types::*;
+use super::versions::sign_payload;
+use super::validation::ValidatedJson;
+
+/// Shortest inactivity timeout (30 days, as enforced by core.move)
+pub const MIN_INACTIVITY_TIMEOUT_MS: u64 = 30 * 86_400_000;
+/// Longest inactivity timeout (10 years)
+pub const MAX_INACTIVITY_TIMEOUT_MS: u64 = 3_650 * 86_400_000;
+
+/// Sign an inactivity beneficiary, or its removal
+///
+/// Request: handle, beneficiary_handle, inactivity_timeout_ms
+/// Response: signed BeneficiaryPayload (BENEFICIARY_INTENT)
+pub async fn process_set_beneficiary(
+    State(state): State<Arc<AppState>>,
+    ValidatedJson(request): ValidatedJson<ProcessDataRequest<SetBeneficiaryRequest>>,
+) -> Result<Json<SetBeneficiaryResponse>, EnclaveError> {
+    let req = &request.payload;
+    let version = state.payload_versions.select(request.payload_version)?;
+    // Removal signs a zero timeout whatever was sent
+    let inactivity_timeout_ms = if req.beneficiary_handle.is_empty() { 0 } else { req.inactivity_timeout_ms };
+    info!(
+        "RAM SetBeneficiary: handle='{}', beneficiary='{}', inactivity_timeout_ms={}",
+        req.handle, req.beneficiary_handle, inactivity_timeout_ms
+    );
+
+    let current_timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_err(|e| EnclaveError::GenericError(format!("Failed to get timestamp: {}", e)))?
+        .as_millis() as u64;
+
+    let payload = BeneficiaryPayload {
+        handle: req.handle.clone().into_bytes(),
+        beneficiary_handle: req.beneficiary_handle.clone().into_bytes(),
+        inactivity_timeout_ms,
+    };
+    let signature = sign_payload(&state.eph_kp, &payload, current_timestamp, IntentScope::Beneficiary, version);
+
+    Ok(Json(SetBeneficiaryResponse {
+        payload,
+        intent: BENEFICIARY_INTENT,
+        payload_version: version as u8,
+        timestamp_ms: current_timestamp,
+        signature,
+    }))
+}
@@ -0,0 +1,258 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! Recorded upstream interactions ("cassettes") for deterministic tests
+//!
+//! Builds with the `cassettes` feature can record the OpenRouter and Hume
+//! HTTP exchanges of a session to a JSON file and later replay them instead
+//! of calling out, so the whole `analyze_audio` path runs in CI without live
+//! keys. `UPSTREAM_CASSETTE` names the file and `UPSTREAM_CASSETTE_MODE` is
+//! `record` or `replay` (the default); both are read on every call, like the
+//! upstream URL overrides.
+//!
+//! Cassettes are sanitized as they are written: request headers (the API
+//! keys) and bodies (the user's audio) are never stored, only each request's
+//! provider, method and path, with the response's status, content type and
+//! body. A recording session replaces the file. Replay answers each request
+//! with the next recorded interaction, starting over after the last; a
+//! request that doesn't match it gets a 404 saying what was expected.
+//! Hume's streaming API isn't HTTP and isn't recorded, so batch jobs are
+//! used while a cassette is set. The Sui RPC and screening API always go to
+//! the network.
+//!
+//! The module only exists in builds with the feature, so a production
+//! enclave can't be pointed at canned answers.
+
+use reqwest::header::CONTENT_TYPE;
+use reqwest::{RequestBuilder, Response};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use tracing::{info, warn};
+
+use super::retry::Provider;
+
+/// One recorded request and the response it got
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Interaction {
+    pub provider: String,
+    pub method: String,
+    pub path: String,
+    pub status: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    pub body: String,
+}
+
+/// Contents of a cassette file
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Cassette {
+    pub interactions: Vec<Interaction>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Record,
+    Replay,
+}
+
+/// Cassettes being recorded, and replayed ones with the index of their next interaction
+#[derive(Default)]
+struct Tapes {
+    recording: HashMap<PathBuf, Cassette>,
+    replaying: HashMap<PathBuf, (Cassette, usize)>,
+}
+
+fn tapes() -> &'static Mutex<Tapes> {
+    static TAPES: OnceLock<Mutex<Tapes>> = OnceLock::new();
+    TAPES.get_or_init(Mutex::default)
+}
+
+/// The cassette `provider`'s requests go through, if one is set
+pub(crate) fn active(provider: Provider) -> Option<(PathBuf, Mode)> {
+    if !matches!(provider, Provider::OpenRouter | Provider::Hume) {
+        return None;
+    }
+    let path = std::env::var("UPSTREAM_CASSETTE").ok().map(|p| p.trim().to_string()).filter(|p| !p.is_empty())?;
+    let mode = match std::env::var("UPSTREAM_CASSETTE_MODE") {
+        Ok(mode) if mode.trim().eq_ignore_ascii_case("record") => Mode::Record,
+        _ => Mode::Replay,
+    };
+    Some((PathBuf::from(path), mode))
+}
+
+/// Whether any cassette is set (Hume streaming is skipped while one is)
+pub(crate) fn is_set() -> bool {
+    active(Provider::Hume).is_some()
+}
+
+/// Send `request` through the cassette at `path`: to the network and onto
+/// the tape when recording, from the tape when replaying
+pub(crate) async fn send(
+    provider: Provider,
+    request: RequestBuilder,
+    path: &Path,
+    mode: Mode,
+) -> Result<Response, reqwest::Error> {
+    let (client, request) = request.build_split();
+    let request = request?;
+    let method = request.method().to_string();
+    let url_path = request.url().path().to_string();
+
+    if mode == Mode::Replay {
+        return Ok(replay(path, provider, &method, &url_path));
+    }
+    let response = client.execute(request).await?;
+    let status = response.status().as_u16();
+    let content_type = response.headers().get(CONTENT_TYPE).and_then(|v| v.to_str().ok()).map(str::to_string);
+    let body = response.bytes().await?;
+    record(
+        path,
+        Interaction {
+            provider: provider.name().to_string(),
+            method,
+            path: url_path,
+            status,
+            content_type: content_type.clone(),
+            body: String::from_utf8_lossy(&body).into_owned(),
+        },
+    );
+    Ok(rebuild(status, content_type.as_deref(), body.to_vec()))
+}
+
+/// Append `interaction` to the cassette being recorded at `path`
+fn record(path: &Path, interaction: Interaction) {
+    let mut tapes = tapes().lock().unwrap();
+    tapes.replaying.remove(path);
+    let cassette = tapes.recording.entry(path.to_path_buf()).or_default();
+    info!("RAM Cassette: recorded {} {} {} -> {}", interaction.provider, interaction.method, interaction.path, interaction.status);
+    cassette.interactions.push(interaction);
+    let written = serde_json::to_string_pretty(cassette)
+        .map_err(|e| e.to_string())
+        .and_then(|json| std::fs::write(path, json).map_err(|e| e.to_string()));
+    if let Err(e) = written {
+        warn!("RAM Cassette: can't write {}: {}", path.display(), e);
+    }
+}
+
+/// The next interaction on the cassette at `path`, if it is this request's
+fn replay(path: &Path, provider: Provider, method: &str, url_path: &str) -> Response {
+    let mut tapes = tapes().lock().unwrap();
+    if !tapes.replaying.contains_key(path) {
+        let loaded = std::fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|json| serde_json::from_str::<Cassette>(&json).map_err(|e| e.to_string()));
+        match loaded {
+            Ok(cassette) => {
+                tapes.replaying.insert(path.to_path_buf(), (cassette, 0));
+            }
+            Err(e) => return missing(format!("can't read cassette {}: {}", path.display(), e)),
+        }
+    }
+    let (cassette, next) = tapes.replaying.get_mut(path).expect("loaded above");
+    let Some(interaction) = cassette.interactions.get(*next) else {
+        return missing(format!("cassette {} is empty", path.display()));
+    };
+    if (interaction.provider.as_str(), interaction.method.as_str(), interaction.path.as_str())
+        != (provider.name(), method, url_path)
+    {
+        return missing(format!(
+            "cassette {} has {} {} {} next, not {} {} {}",
+            path.display(),
+            interaction.provider,
+            interaction.method,
+            interaction.path,
+            provider.name(),
+            method,
+            url_path
+        ));
+    }
+    *next = (*next + 1) % cassette.interactions.len();
+    rebuild(interaction.status, interaction.content_type.as_deref(), interaction.body.clone().into_bytes())
+}
+
+/// Answer for a request the cassette can't replay; a 404 isn't retried
+fn missing(reason: String) -> Response {
+    warn!("RAM Cassette: {}", reason);
+    rebuild(404, Some("text/plain"), reason.into_bytes())
+}
+
+fn rebuild(status: u16, content_type: Option<&str>, body: Vec<u8>) -> Response {
+    let mut response = http::Response::builder().status(status);
+    if let Some(content_type) = content_type {
+        response = response.header(http::header::CONTENT_TYPE, content_type);
+    }
+    match response.body(body) {
+        Ok(response) => Response::from(response),
+        Err(e) => {
+            let mut response = http::Response::new(format!("unreplayable interaction: {}", e).into_bytes());
+            *response.status_mut() = http::StatusCode::INTERNAL_SERVER_ERROR;
+            Response::from(response)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn cassette_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("ram-cassette-{}-{}.json", name, uuid::Uuid::new_v4()))
+    }
+
+    #[tokio::test]
+    async fn test_recordings_are_sanitized_and_replay_in_order() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat"))
+            .and(header("authorization", "Bearer secret-key"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "choices": [] })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST")).and(path("/jobs")).respond_with(ResponseTemplate::new(503)).mount(&server).await;
+
+        let file = cassette_path("record");
+        let client = reqwest::Client::new();
+        let chat = || client.post(format!("{}/chat", server.uri())).bearer_auth("secret-key").body("audio-bytes");
+        let jobs = || client.post(format!("{}/jobs", server.uri())).body("audio-bytes");
+        let response = send(Provider::OpenRouter, chat(), &file, Mode::Record).await.unwrap();
+        assert_eq!(response.text().await.unwrap(), r#"{"choices":[]}"#);
+        assert_eq!(send(Provider::Hume, jobs(), &file, Mode::Record).await.unwrap().status(), 503);
+
+        let recorded = std::fs::read_to_string(&file).unwrap();
+        assert!(!recorded.contains("secret-key") && !recorded.contains("audio-bytes"));
+        let cassette: Cassette = serde_json::from_str(&recorded).unwrap();
+        assert_eq!(cassette.interactions.len(), 2);
+        assert_eq!(cassette.interactions[0].path, "/chat");
+        assert_eq!(cassette.interactions[0].content_type.as_deref(), Some("application/json"));
+
+        // Replayed in order, from anywhere, and again from the start
+        server.reset().await;
+        let replayed = || reqwest::Client::new().post("http://127.0.0.1:1/chat");
+        for _ in 0..2 {
+            let response = send(Provider::OpenRouter, replayed(), &file, Mode::Replay).await.unwrap();
+            assert_eq!(response.status(), 200);
+            assert_eq!(response.text().await.unwrap(), r#"{"choices":[]}"#);
+            let response = send(Provider::Hume, jobs(), &file, Mode::Replay).await.unwrap();
+            assert_eq!(response.status(), 503);
+        }
+        assert!(server.received_requests().await.unwrap().is_empty());
+
+        // Out of order: nothing is replayed, and the tape doesn't move
+        let response = send(Provider::Hume, jobs(), &file, Mode::Replay).await.unwrap();
+        assert_eq!(response.status(), 404);
+        assert!(response.text().await.unwrap().contains("has openrouter POST /chat next"));
+        std::fs::remove_file(&file).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_missing_cassettes_are_reported() {
+        let request = reqwest::Client::new().post("http://127.0.0.1:1/chat");
+        let response = send(Provider::OpenRouter, request, &cassette_path("missing"), Mode::Replay).await.unwrap();
+        assert_eq!(response.status(), 404);
+        assert!(response.text().await.unwrap().starts_with("can't read cassette"));
+    }
+}
@@ -0,0 +1,302 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! Chain-aware configuration
+//!
+//! The enclave signs for one Sui network at a time: its [`ChainConfig`] names
+//! the network, its chain identifier, the RAM package published there and the
+//! fullnode to read from. Payload layout v3 signs the package ID along with
+//! the payload (see `versions`), and package IDs are unique across networks,
+//! so a payload signed for testnet can't verify against the mainnet package.
+//!
+//! Read from the environment once at startup and refused if inconsistent:
+//! - `SUI_NETWORK`: mainnet, testnet, devnet or localnet (default: testnet)
+//! - `SUI_CHAIN_ID`: chain identifier; mainnet and testnet default to (and
+//!   must match) their known one, devnet and localnet have none unless set
+//! - `RAM_PACKAGE_IDS`: `network=0x…` pairs, comma-separated, one per network
+//!   the package is published on
+//! - `RAM_PACKAGE_ID`: the package on `SUI_NETWORK`, overriding `RAM_PACKAGE_IDS`
+//! - `SUI_RPC_URL`: fullnode (default: the network's public one); coin metadata
+//!   is always read from the public one (see `coin`)
+//!
+//! The package must be the original (first published) ID, which is what the
+//! contract reads from its own type names after an upgrade.
+
+use fastcrypto::encoding::{Encoding, Hex};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+/// Chain identifier of Sui mainnet (first 4 bytes of its genesis checkpoint digest)
+pub const MAINNET_CHAIN_ID: &str = "35834a8a";
+/// Chain identifier of Sui testnet
+pub const TESTNET_CHAIN_ID: &str = "4c78adac";
+
+/// Sui network the enclave signs for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Network {
+    Mainnet,
+    Testnet,
+    Devnet,
+    Localnet,
+}
+
+impl Network {
+    pub const ALL: [Network; 4] = [Network::Mainnet, Network::Testnet, Network::Devnet, Network::Localnet];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Network::Mainnet => "mainnet",
+            Network::Testnet => "testnet",
+            Network::Devnet => "devnet",
+            Network::Localnet => "localnet",
+        }
+    }
+
+    /// Chain identifier that never changes (devnet and localnet are regenesised)
+    pub fn known_chain_id(&self) -> Option<&'static str> {
+        match self {
+            Network::Mainnet => Some(MAINNET_CHAIN_ID),
+            Network::Testnet => Some(TESTNET_CHAIN_ID),
+            Network::Devnet | Network::Localnet => None,
+        }
+    }
+
+    pub fn default_rpc_url(&self) -> &'static str {
+        match self {
+            Network::Mainnet => "https://fullnode.mainnet.sui.io:443",
+            Network::Testnet => "https://fullnode.testnet.sui.io:443",
+            Network::Devnet => "https://fullnode.devnet.sui.io:443",
+            Network::Localnet => "http://127.0.0.1:9000",
+        }
+    }
+}
+
+impl fmt::Display for Network {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Network {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Network::ALL
+            .into_iter()
+            .find(|network| network.as_str().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| format!("expected mainnet, testnet, devnet or localnet, got '{}'", s))
+    }
+}
+
+/// The network the enclave signs for, and where the RAM package lives on it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainConfig {
+    pub network: Network,
+    /// Chain identifier, if known for this network
+    pub chain_id: Option<String>,
+    /// Original RAM package ID on each network it's published on (0x + 64 hex)
+    pub package_ids: BTreeMap<Network, String>,
+    /// Fullnode for coin metadata and zkLogin epochs
+    pub rpc_url: String,
+}
+
+impl Default for ChainConfig {
+    /// Localnet with a zero package: what tests and the mock server sign for
+    fn default() -> Self {
+        Self {
+            network: Network::Localnet,
+            chain_id: None,
+            package_ids: BTreeMap::from([(Network::Localnet, format!("0x{}", "0".repeat(64)))]),
+            rpc_url: Network::Localnet.default_rpc_url().to_string(),
+        }
+    }
+}
+
+impl ChainConfig {
+    /// Configuration from the environment (see the module docs), validated
+    pub fn from_env() -> Result<Self, String> {
+        let var = |name: &str| std::env::var(name).ok().map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+        let network = match var("SUI_NETWORK") {
+            Some(network) => network.parse().map_err(|e| format!("SUI_NETWORK: {}", e))?,
+            None => Network::Testnet,
+        };
+
+        let mut package_ids = BTreeMap::new();
+        for pair in var("RAM_PACKAGE_IDS").iter().flat_map(|list| list.split(',')).map(str::trim) {
+            if pair.is_empty() {
+                continue;
+            }
+            let (name, id) = pair
+                .split_once('=')
+                .ok_or_else(|| format!("RAM_PACKAGE_IDS: expected network=0x…, got '{}'", pair))?;
+            let name: Network = name.parse().map_err(|e| format!("RAM_PACKAGE_IDS: {}", e))?;
+            if package_ids.insert(name, id.trim().to_string()).is_some() {
+                return Err(format!("RAM_PACKAGE_IDS lists {} twice", name));
+            }
+        }
+        if let Some(id) = var("RAM_PACKAGE_ID") {
+            package_ids.insert(network, id);
+        }
+
+        let config = Self {
+            network,
+            chain_id: var("SUI_CHAIN_ID").or_else(|| network.known_chain_id().map(str::to_string)),
+            package_ids,
+            rpc_url: var("SUI_RPC_URL").unwrap_or_else(|| network.default_rpc_url().to_string()),
+        };
+        config.validated()
+    }
+
+    /// This configuration with package IDs normalized, or why it can't be signed for
+    pub fn validated(mut self) -> Result<Self, String> {
+        if let Some(chain_id) = &self.chain_id {
+            if chain_id.len() != 8 || !chain_id.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(format!("SUI_CHAIN_ID '{}' must be 8 hex characters", chain_id));
+            }
+            if let Some(known) = self.network.known_chain_id() {
+                if !chain_id.eq_ignore_ascii_case(known) {
+                    return Err(format!("SUI_CHAIN_ID {} is not {}'s ({})", chain_id, self.network, known));
+                }
+            }
+            self.chain_id = Some(chain_id.to_ascii_lowercase());
+        }
+
+        for (network, id) in self.package_ids.iter_mut() {
+            *id = normalize_package_id(id).map_err(|e| format!("{} package ID: {}", network, e))?;
+        }
+        let mut seen: BTreeMap<&str, Network> = BTreeMap::new();
+        for (network, id) in &self.package_ids {
+            if let Some(other) = seen.insert(id, *network) {
+                return Err(format!("{} and {} are configured with the same package {}", other, network, id));
+            }
+        }
+        if !self.package_ids.contains_key(&self.network) {
+            return Err(format!("no RAM package ID for {} (set RAM_PACKAGE_ID or RAM_PACKAGE_IDS)", self.network));
+        }
+
+        if !(self.rpc_url.starts_with("https://") || self.rpc_url.starts_with("http://")) {
+            return Err(format!("SUI_RPC_URL '{}' must be an http(s) URL", self.rpc_url));
+        }
+        // A public fullnode of another network is a copy-paste mistake, not a choice
+        let host = self.rpc_url.split("://").nth(1).unwrap_or_default().split(['/', ':']).next().unwrap_or_default();
+        if let Some(other) = Network::ALL
+            .into_iter()
+            .find(|n| *n != self.network && host == format!("fullnode.{}.sui.io", n))
+        {
+            return Err(format!("SUI_RPC_URL {} is a {} fullnode, not {}", self.rpc_url, other, self.network));
+        }
+        Ok(self)
+    }
+
+    /// RAM package on the configured network
+    pub fn package_id(&self) -> &str {
+        // validated() guarantees it; the default config has one too
+        self.package_ids.get(&self.network).map(String::as_str).unwrap_or_default()
+    }
+
+    /// The package ID as the Move `address` signed into layout v3
+    pub(crate) fn package_address(&self) -> [u8; 32] {
+        let mut address = [0u8; 32];
+        if let Ok(bytes) = Hex::decode(self.package_id().trim_start_matches("0x")) {
+            if bytes.len() == 32 {
+                address.copy_from_slice(&bytes);
+            }
+        }
+        address
+    }
+
+    /// What `/health_check` and `/meta/intents` report (not the RPC URL,
+    /// which may carry a provider key)
+    pub fn info(&self) -> ChainInfo {
+        ChainInfo {
+            network: self.network,
+            chain_id: self.chain_id.clone(),
+            package_id: self.package_id().to_string(),
+        }
+    }
+}
+
+/// The chain the enclave signs for
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainInfo {
+    pub network: Network,
+    pub chain_id: Option<String>,
+    /// RAM package v3 payloads are bound to
+    pub package_id: String,
+}
+
+/// `0x`-prefixed, zero-padded 64 hex characters
+fn normalize_package_id(id: &str) -> Result<String, String> {
+    let hex = id.trim().strip_prefix("0x").ok_or_else(|| format!("'{}' must start with 0x", id))?;
+    if hex.is_empty() || hex.len() > 64 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("'{}' must be 0x followed by 1 to 64 hex characters", id));
+    }
+    Ok(format!("0x{:0>64}", hex.to_ascii_lowercase()))
+}
+
+static CHAIN: OnceLock<ChainConfig> = OnceLock::new();
+
+/// Sign for `config` for the rest of the process
+pub fn install(config: ChainConfig) {
+    let _ = CHAIN.set(config);
+}
+
+/// The installed chain, or the default one if none was installed
+pub fn current() -> &'static ChainConfig {
+    CHAIN.get_or_init(ChainConfig::default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(network: Network, packages: &[(Network, &str)]) -> ChainConfig {
+        ChainConfig {
+            network,
+            chain_id: network.known_chain_id().map(str::to_string),
+            package_ids: packages.iter().map(|(n, id)| (*n, id.to_string())).collect(),
+            rpc_url: network.default_rpc_url().to_string(),
+        }
+    }
+
+    #[test]
+    fn test_validation() {
+        let valid = config(Network::Testnet, &[(Network::Testnet, "0xAB"), (Network::Mainnet, "0xcd")])
+            .validated()
+            .unwrap();
+        assert_eq!(valid.package_id(), format!("0x{:0>64}", "ab"));
+        assert_eq!(valid.package_address()[31], 0xab);
+        assert_eq!(valid.info().chain_id.as_deref(), Some(TESTNET_CHAIN_ID));
+
+        // No package for the network signed for
+        assert!(config(Network::Mainnet, &[(Network::Testnet, "0xab")]).validated().is_err());
+        // The same package on two networks would make their signatures interchangeable
+        assert!(config(Network::Testnet, &[(Network::Testnet, "0xab"), (Network::Mainnet, "0x00ab")])
+            .validated()
+            .is_err());
+        assert!(config(Network::Testnet, &[(Network::Testnet, "ab")]).validated().is_err());
+
+        let mut wrong_chain = config(Network::Mainnet, &[(Network::Mainnet, "0xab")]);
+        wrong_chain.chain_id = Some(TESTNET_CHAIN_ID.to_string());
+        assert!(wrong_chain.validated().is_err());
+
+        let mut wrong_rpc = config(Network::Mainnet, &[(Network::Mainnet, "0xab")]);
+        wrong_rpc.rpc_url = Network::Testnet.default_rpc_url().to_string();
+        assert!(wrong_rpc.validated().is_err());
+
+        let mut devnet = config(Network::Devnet, &[(Network::Devnet, "0x1")]);
+        devnet.chain_id = Some("A1B2C3D4".to_string());
+        assert_eq!(devnet.validated().unwrap().chain_id.as_deref(), Some("a1b2c3d4"));
+    }
+
+    #[test]
+    fn test_default_is_valid() {
+        let default = ChainConfig::default();
+        assert_eq!(default.clone().validated().unwrap(), default);
+        assert_eq!(default.package_address(), [0u8; 32]);
+    }
+}
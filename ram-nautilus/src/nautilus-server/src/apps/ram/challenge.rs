@@ -0,0 +1,312 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! Bio-auth challenge phrases and their spoken (TTS) rendering
+//!
+//! A challenge is the exact phrase a user must say for a bio-auth ("I confirm
+//! sending 5 SUI", plus the destination's last characters for withdrawals),
+//! issued under a short-lived ID. Clients can show it as text or fetch it as
+//! audio so visually-impaired users hear what to repeat. Rendering goes
+//! through an OpenAI-compatible speech API; audio is cached by phrase, since
+//! most users confirm the same few amounts. The phrase is in the request's
+//! `locale` language (or the handle's preferred one, see `preferences`), or its
+//! `Accept-Language`, where there is a catalog for it.
+
+use crate::common::ProcessDataRequest;
+use crate::i18n::{self, current_lang, Lang};
+use crate::AppState;
+use crate::EnclaveError;
+use axum::extract::{Path, State};
+use axum::http::header;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use zeroize::Zeroizing;
+use tracing::{info, warn};
+
+use super::amount::RawAmount;
+use super::audio;
+use super::coin::{CoinInfo, CoinType};
+use super::endpoints::{self, Endpoint};
+use super::locale::NumberLocale;
+use super::snapshot;
+use super::types::{ChallengeRequest, ChallengeResponse};
+use super::validation::ValidatedJson;
+
+/// How long an issued challenge can be played back
+pub const DEFAULT_CHALLENGE_TTL_SECS: u64 = 300;
+/// Rendered phrases kept in the audio cache
+const TTS_CACHE_CAPACITY: usize = 256;
+/// Rendered audio is reused for a day
+const TTS_CACHE_TTL: Duration = Duration::from_secs(86_400);
+
+/// OpenAI-compatible text-to-speech endpoint
+const TTS_API_URL: &str = "https://api.openai.com/v1/audio/speech";
+
+/// TTS endpoint (a mock server's in test and `mock` builds)
+fn tts_api_url() -> String {
+    endpoints::resolve(Endpoint::Tts, TTS_API_URL)
+}
+
+/// An issued challenge, as a snapshot keeps it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(super) struct SavedChallenge {
+    challenge_id: String,
+    issued_at_ms: u64,
+    phrase: String,
+}
+
+/// Issued challenges plus the rendered-audio cache
+pub struct ChallengeStore {
+    pub(super) ttl: Duration,
+    tts_api_key: Zeroizing<String>,
+    challenges: Mutex<HashMap<String, (Instant, String)>>,
+    audio_cache: Mutex<HashMap<String, (Instant, Vec<u8>)>>,
+}
+
+impl ChallengeStore {
+    /// Store with the given challenge TTL; an empty `tts_api_key` disables playback
+    pub fn new(ttl: Duration, tts_api_key: String) -> Self {
+        Self {
+            ttl,
+            tts_api_key: Zeroizing::new(tts_api_key),
+            challenges: Mutex::new(HashMap::new()),
+            audio_cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Record a phrase under a fresh challenge ID
+    pub fn issue(&self, phrase: String) -> String {
+        let now = Instant::now();
+        let challenge_id = uuid::Uuid::new_v4().to_string();
+        let mut challenges = self.challenges.lock().unwrap();
+        challenges.retain(|_, (issued_at, _)| now.duration_since(*issued_at) < self.ttl);
+        challenges.insert(challenge_id.clone(), (now, phrase));
+        challenge_id
+    }
+
+    /// Phrase for a challenge if it hasn't expired
+    pub fn phrase(&self, challenge_id: &str) -> Option<String> {
+        self.challenges
+            .lock()
+            .unwrap()
+            .get(challenge_id)
+            .filter(|(issued_at, _)| issued_at.elapsed() < self.ttl)
+            .map(|(_, phrase)| phrase.clone())
+    }
+
+    /// Consume a challenge: its phrase if it hadn't expired, and never again
+    pub fn take(&self, challenge_id: &str) -> Option<String> {
+        self.challenges
+            .lock()
+            .unwrap()
+            .remove(challenge_id)
+            .filter(|(issued_at, _)| issued_at.elapsed() < self.ttl)
+            .map(|(_, phrase)| phrase)
+    }
+
+    /// Challenges not taken or expired yet, for a snapshot (rendered audio is not kept)
+    pub(super) fn save(&self, now_ms: u64) -> Vec<SavedChallenge> {
+        self.challenges
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, (issued_at, _))| issued_at.elapsed() < self.ttl)
+            .map(|(challenge_id, (issued_at, phrase))| SavedChallenge {
+                challenge_id: challenge_id.clone(),
+                issued_at_ms: snapshot::wall_clock_ms(*issued_at, now_ms),
+                phrase: phrase.clone(),
+            })
+            .collect()
+    }
+
+    /// Bring back the challenges of a snapshot that haven't expired since
+    pub(super) fn restore(&self, saved: Vec<SavedChallenge>, now_ms: u64) {
+        let mut challenges = self.challenges.lock().unwrap();
+        for challenge in saved {
+            let issued_at = snapshot::instant_at(challenge.issued_at_ms, now_ms);
+            if let Some(issued_at) = issued_at.filter(|at| at.elapsed() < self.ttl) {
+                challenges.entry(challenge.challenge_id).or_insert((issued_at, challenge.phrase));
+            }
+        }
+    }
+
+    /// Spoken rendering of `phrase`, from cache or the TTS provider
+    async fn render(&self, phrase: &str) -> Result<Vec<u8>, EnclaveError> {
+        if let Some((_, audio)) = self
+            .audio_cache
+            .lock()
+            .unwrap()
+            .get(phrase)
+            .filter(|(rendered_at, _)| rendered_at.elapsed() < TTS_CACHE_TTL)
+        {
+            return Ok(audio.clone());
+        }
+
+        if self.tts_api_key.is_empty() {
+            return Err(EnclaveError::Forbidden("Challenge audio is disabled".to_string()));
+        }
+
+        let response = reqwest::Client::new()
+            .post(tts_api_url())
+            .bearer_auth(self.tts_api_key.as_str())
+            .json(&serde_json::json!({
+                "model": "tts-1",
+                "voice": "alloy",
+                "input": phrase,
+                "response_format": "mp3",
+            }))
+            .send()
+            .await
+            .map_err(|e| EnclaveError::GenericError(format!("TTS request failed: {}", e)))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            warn!("RAM Challenge: TTS provider returned {}", status);
+            return Err(EnclaveError::GenericError(format!("TTS provider returned {}", status)));
+        }
+        let audio = response
+            .bytes()
+            .await
+            .map_err(|e| EnclaveError::GenericError(format!("Failed to read TTS audio: {}", e)))?
+            .to_vec();
+
+        let now = Instant::now();
+        let mut cache = self.audio_cache.lock().unwrap();
+        cache.retain(|_, (rendered_at, _)| now.duration_since(*rendered_at) < TTS_CACHE_TTL);
+        if cache.len() >= TTS_CACHE_CAPACITY {
+            // Evict the oldest rendering
+            if let Some(oldest) = cache.iter().min_by_key(|(_, (at, _))| *at).map(|(k, _)| k.clone()) {
+                cache.remove(&oldest);
+            }
+        }
+        cache.insert(phrase.to_string(), (now, audio.clone()));
+
+        Ok(audio)
+    }
+}
+
+/// The phrase bio-auth verifies for this amount (and the destination's last
+/// `suffix_len` characters, for withdrawals), in `lang`; other languages
+/// write the amount their way
+pub fn challenge_phrase(
+    expected_amount: RawAmount,
+    coin: &CoinInfo,
+    destination_hint: Option<&str>,
+    suffix_len: usize,
+    lang: Lang,
+) -> String {
+    let amount = match lang {
+        Lang::En => format!("{} {}", coin.format(expected_amount), coin.symbol),
+        _ => NumberLocale::from_tag(Some(lang.tag())).format_amount(expected_amount, coin),
+    };
+    let mut phrase = i18n::format(lang, "I confirm sending {}", &[&amount]);
+
+    // Spaced out so the suffix is read character by character
+    if let Some(suffix) = destination_hint.and_then(|hint| audio::destination_suffix(hint, suffix_len)) {
+        let spelled: Vec<String> = suffix.chars().map(|c| c.to_string()).collect();
+        phrase.push_str(&i18n::format(lang, " to the address ending in {}", &[&spelled.join(" ")]));
+    }
+    phrase
+}
+
+/// Issue the challenge phrase for an upcoming bio-auth
+///
+/// POST /bio_auth/challenge
+pub async fn process_bio_auth_challenge(
+    State(state): State<Arc<AppState>>,
+    ValidatedJson(request): ValidatedJson<ProcessDataRequest<ChallengeRequest>>,
+) -> Result<Json<ChallengeResponse>, EnclaveError> {
+    let req = &request.payload;
+    let coin = state.coin_registry.resolve(&CoinType::parse_or_sui(req.coin_type.as_deref())?).await?;
+    // The handle's preferences fill in what the request leaves out
+    let preferences = state.user_preferences.get(&req.handle);
+    let lang = req
+        .locale
+        .as_deref()
+        .or(preferences.locale.as_deref())
+        .and_then(Lang::from_tag)
+        .unwrap_or_else(current_lang);
+    let phrase = challenge_phrase(
+        RawAmount(req.expected_amount),
+        &coin,
+        req.destination_hint.as_deref(),
+        preferences.challenge_length(),
+        lang,
+    );
+
+    let challenge_id = state.bioauth_challenges.issue(phrase.clone());
+    info!("RAM Challenge: issued {} for handle='{}'", challenge_id, req.handle);
+
+    let now_ms = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_err(|e| EnclaveError::GenericError(format!("Failed to get timestamp: {}", e)))?
+        .as_millis() as u64;
+
+    Ok(Json(ChallengeResponse {
+        challenge_id,
+        phrase,
+        expires_at_ms: now_ms + state.bioauth_challenges.ttl.as_millis() as u64,
+    }))
+}
+
+/// Spoken rendering of a challenge phrase (audio/mpeg)
+///
+/// GET /bio_auth/challenge_audio/:challenge_id
+pub async fn get_challenge_audio(
+    State(state): State<Arc<AppState>>,
+    Path(challenge_id): Path<String>,
+) -> Result<Response, EnclaveError> {
+    let phrase = state
+        .bioauth_challenges
+        .phrase(&challenge_id)
+        .ok_or_else(|| EnclaveError::NotFound(format!("No challenge '{}'", challenge_id)))?;
+
+    let audio = state.bioauth_challenges.render(&phrase).await?;
+    Ok(([(header::CONTENT_TYPE, "audio/mpeg")], audio).into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::audio::DESTINATION_SUFFIX_LEN;
+
+    #[test]
+    fn test_challenge_phrase() {
+        let sui = CoinInfo::sui();
+        let usdc = CoinInfo {
+            coin_type: CoinType::parse("0xc0ffee::usdc::USDC").unwrap(),
+            symbol: "USDC".to_string(),
+            decimals: 6,
+        };
+        assert_eq!(challenge_phrase(RawAmount(5_000_000_000), &sui, None, DESTINATION_SUFFIX_LEN, Lang::En), "I confirm sending 5 SUI");
+        assert_eq!(challenge_phrase(RawAmount(2_500_000), &usdc, None, DESTINATION_SUFFIX_LEN, Lang::En), "I confirm sending 2.5 USDC");
+        assert_eq!(
+            challenge_phrase(RawAmount(1_000_000_000), &sui, Some("0x00a11ce"), DESTINATION_SUFFIX_LEN, Lang::En),
+            "I confirm sending 1 SUI to the address ending in 1 1 c e"
+        );
+        assert_eq!(
+            challenge_phrase(RawAmount(1_000_000_000), &sui, Some("0x00a11ce"), 6, Lang::En),
+            "I confirm sending 1 SUI to the address ending in 0 a 1 1 c e"
+        );
+        assert_eq!(
+            challenge_phrase(RawAmount(2_500_000), &usdc, Some("0x00a11ce"), DESTINATION_SUFFIX_LEN, Lang::Vi),
+            "Tôi xác nhận gửi 2,5 USDC đến địa chỉ kết thúc bằng 1 1 c e"
+        );
+    }
+
+    #[test]
+    fn test_challenge_expiry() {
+        let store = ChallengeStore::new(Duration::from_secs(60), String::new());
+        let id = store.issue("I confirm sending 5 SUI".to_string());
+        assert_eq!(store.phrase(&id).as_deref(), Some("I confirm sending 5 SUI"));
+        assert!(store.phrase("unknown").is_none());
+
+        let expired = ChallengeStore::new(Duration::from_millis(0), String::new());
+        let id = expired.issue("I confirm sending 5 SUI".to_string());
+        assert!(expired.phrase(&id).is_none());
+    }
+}
@@ -0,0 +1,107 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! Fault injection for resilience testing
+//!
+//! Builds with the `chaos` feature read `CHAOS_*` env vars that delay
+//! OpenRouter and Hume calls and fail OpenRouter calls with a 500 on a
+//! percentage of requests, so the DSP/mock fallback and client retries can be
+//! exercised in staging. Injected failures take the same path as a real 500.
+//! Without the feature the settings are ignored and the hooks do nothing.
+
+use crate::EnclaveError;
+use rand::Rng;
+use std::sync::OnceLock;
+use std::time::Duration;
+use tracing::warn;
+
+/// What to inject and how often (percentages of calls, 0-100)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UpstreamFaults {
+    /// Extra delay before OpenRouter and Hume calls
+    pub latency: Duration,
+    pub latency_percent: u8,
+    /// OpenRouter calls answered with a 500
+    pub openrouter_error_percent: u8,
+}
+
+impl UpstreamFaults {
+    /// Settings from `CHAOS_UPSTREAM_LATENCY_MS`, `CHAOS_UPSTREAM_LATENCY_PERCENT`
+    /// and `CHAOS_OPENROUTER_ERROR_PERCENT`; unset or invalid values are 0
+    pub fn from_env() -> Self {
+        let var = |name: &str| std::env::var(name).ok().and_then(|v| v.parse::<u64>().ok()).unwrap_or(0);
+        let percent = |name: &str| var(name).min(100) as u8;
+        Self {
+            latency: Duration::from_millis(var("CHAOS_UPSTREAM_LATENCY_MS")),
+            latency_percent: percent("CHAOS_UPSTREAM_LATENCY_PERCENT"),
+            openrouter_error_percent: percent("CHAOS_OPENROUTER_ERROR_PERCENT"),
+        }
+    }
+
+    /// Whether any fault is configured
+    pub fn is_active(&self) -> bool {
+        (self.latency_percent > 0 && !self.latency.is_zero()) || self.openrouter_error_percent > 0
+    }
+}
+
+static FAULTS: OnceLock<UpstreamFaults> = OnceLock::new();
+
+/// Turn on injection for the rest of the process; ignored without the `chaos` feature
+pub fn install(faults: UpstreamFaults) {
+    if !faults.is_active() {
+        return;
+    }
+    if cfg!(feature = "chaos") {
+        warn!("Fault injection enabled: {:?}", faults);
+        let _ = FAULTS.set(faults);
+    } else {
+        warn!("CHAOS_* settings ignored: built without the `chaos` feature");
+    }
+}
+
+/// Upstream services the hooks can disrupt
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Upstream {
+    OpenRouter,
+    Hume,
+}
+
+fn roll(percent: u8) -> bool {
+    percent > 0 && rand::thread_rng().gen_range(0..100) < percent
+}
+
+/// Call before an upstream request: may delay it, or fail it like a 500 would
+pub(crate) async fn before_upstream(upstream: Upstream) -> Result<(), EnclaveError> {
+    let Some(faults) = FAULTS.get() else {
+        return Ok(());
+    };
+    if roll(faults.latency_percent) {
+        tokio::time::sleep(faults.latency).await;
+    }
+    if upstream == Upstream::OpenRouter && roll(faults.openrouter_error_percent) {
+        return Err(EnclaveError::GenericError(
+            "OpenRouter API returned 500 Internal Server Error: injected fault".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roll_extremes() {
+        assert!((0..1000).all(|_| !roll(0)));
+        assert!((0..1000).all(|_| roll(100)));
+    }
+
+    #[test]
+    fn test_is_active() {
+        assert!(!UpstreamFaults::default().is_active());
+        assert!(!UpstreamFaults { latency_percent: 50, ..Default::default() }.is_active());
+        assert!(UpstreamFaults { latency: Duration::from_millis(200), latency_percent: 50, ..Default::default() }
+            .is_active());
+        assert!(UpstreamFaults { openrouter_error_percent: 5, ..Default::default() }.is_active());
+    }
+}
@@ -0,0 +1,144 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! Confidence of an analysis, and abstaining when it is too low
+//!
+//! The transcription model reports how sure it is of the transcript, the
+//! amount it heard and the stress score, from 0 to 1. A clip it isn't sure
+//! about (noise, a clipped recording, mumbling) is answered with a 422
+//! `analysis_inconclusive` asking the user to record again, instead of
+//! signing a verdict that could lock the wallet for nothing or approve an
+//! amount nobody said. Nothing is signed or recorded as a failed attempt.
+//!
+//! A low stress confidence always abstains; low transcript or amount
+//! confidence only when the stress score isn't confidently duress, since
+//! duress doesn't depend on what was said. A panic phrase is never
+//! second-guessed. Minimums are read from `BIOAUTH_MIN_{TRANSCRIPT,AMOUNT,
+//! STRESS}_CONFIDENCE` and installed once at startup; 0 turns a check off.
+//! Analyses without a confidence (the mock fallback, models not asked for
+//! one) count as sure.
+
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+use crate::EnclaveError;
+
+/// How sure the analysis is of each of its findings, each from 0 to 1
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Confidence {
+    pub transcript: f32,
+    pub amount: f32,
+    pub stress: f32,
+}
+
+impl Default for Confidence {
+    /// Sure of everything
+    fn default() -> Self {
+        Self { transcript: 1.0, amount: 1.0, stress: 1.0 }
+    }
+}
+
+/// Lowest confidence each finding may have for a verdict to be signed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ConfidenceThresholds {
+    pub transcript: f32,
+    pub amount: f32,
+    pub stress: f32,
+}
+
+impl Default for ConfidenceThresholds {
+    fn default() -> Self {
+        Self { transcript: 0.5, amount: 0.6, stress: 0.5 }
+    }
+}
+
+impl ConfidenceThresholds {
+    /// Thresholds from `BIOAUTH_MIN_{TRANSCRIPT,AMOUNT,STRESS}_CONFIDENCE`;
+    /// unset values, and ones outside 0 to 1, keep the defaults
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let var = |name: &str, default: f32| {
+            std::env::var(format!("BIOAUTH_MIN_{}_CONFIDENCE", name))
+                .ok()
+                .and_then(|v| v.trim().parse::<f32>().ok())
+                .filter(|v| (0.0..=1.0).contains(v))
+                .unwrap_or(default)
+        };
+        Self {
+            transcript: var("TRANSCRIPT", defaults.transcript),
+            amount: var("AMOUNT", defaults.amount),
+            stress: var("STRESS", defaults.stress),
+        }
+    }
+
+    /// Findings of `confidence` too uncertain to sign a verdict on, given
+    /// whether its stress score is duress
+    pub fn shortfalls(&self, confidence: &Confidence, duress: bool) -> Vec<&'static str> {
+        let mut short = Vec::new();
+        if confidence.stress < self.stress {
+            short.push("stress");
+        }
+        if !duress || !short.is_empty() {
+            if confidence.transcript < self.transcript {
+                short.push("transcript");
+            }
+            if confidence.amount < self.amount {
+                short.push("amount");
+            }
+        }
+        short
+    }
+
+    /// An `analysis_inconclusive` error if `confidence` falls short
+    pub fn check(&self, confidence: &Confidence, duress: bool) -> Result<(), EnclaveError> {
+        let short = self.shortfalls(confidence, duress);
+        if short.is_empty() {
+            return Ok(());
+        }
+        Err(EnclaveError::Inconclusive(format!(
+            "The recording couldn't be analyzed with confidence ({}); record the confirmation again",
+            short.join(", ")
+        )))
+    }
+}
+
+static THRESHOLDS: OnceLock<ConfidenceThresholds> = OnceLock::new();
+
+/// Use `thresholds` for the rest of the process
+pub fn install(thresholds: ConfidenceThresholds) {
+    let _ = THRESHOLDS.set(thresholds);
+}
+
+/// The installed thresholds, or the defaults if none were installed
+pub fn current() -> &'static ConfidenceThresholds {
+    THRESHOLDS.get_or_init(ConfidenceThresholds::default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_low_confidence_abstains() {
+        let thresholds = ConfidenceThresholds::default();
+        let sure = Confidence::default();
+        assert!(thresholds.check(&sure, false).is_ok());
+        assert!(thresholds.check(&sure, true).is_ok());
+
+        // A stress score it isn't sure of decides nothing either way
+        let unsure_stress = Confidence { stress: 0.2, ..sure };
+        assert_eq!(thresholds.shortfalls(&unsure_stress, true), ["stress"]);
+        assert_eq!(thresholds.shortfalls(&unsure_stress, false), ["stress"]);
+
+        // What was said doesn't matter once duress is certain
+        let unsure_words = Confidence { transcript: 0.3, amount: 0.1, ..sure };
+        assert_eq!(thresholds.shortfalls(&unsure_words, false), ["transcript", "amount"]);
+        assert!(thresholds.check(&unsure_words, true).is_ok());
+        let error = thresholds.check(&unsure_words, false).unwrap_err();
+        assert!(matches!(error, EnclaveError::Inconclusive(ref message) if message.contains("(transcript, amount)")));
+
+        // 0 turns a check off
+        let off = ConfidenceThresholds { transcript: 0.0, amount: 0.0, stress: 0.0 };
+        assert!(off.check(&Confidence { transcript: 0.0, amount: 0.0, stress: 0.0 }, false).is_ok());
+    }
+}
@@ -0,0 +1,362 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! Upstream API cost tracking and daily budget caps
+//!
+//! Every OpenRouter and Hume call is metered. OpenRouter reports tokens and
+//! the credit cost of each call in its `usage` field (asked for with
+//! `usage.include`); Hume responses carry no cost, so each call is charged a
+//! configured rate. With a daily cap set, a provider that has spent it for the
+//! current UTC day is skipped and bio-auth falls back to DSP-only stress
+//! analysis until midnight. Crossing a cap raises one operator alert per day,
+//! and a `snapshot` carries the day's spend over a restart.
+//! Totals are served at `GET /metrics`, along with analysis cache use.
+
+use crate::AppState;
+use axum::extract::State;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use tracing::error;
+
+use super::analysis_cache::AnalysisCacheMetrics;
+use super::gray_zone::GrayZoneMetrics;
+use super::chaos::Upstream;
+
+const MICRO_USD_PER_USD: f64 = 1_000_000.0;
+const DAY_MS: u64 = 86_400_000;
+
+/// Daily caps and per-call rates, in micro-USD
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CostLimits {
+    /// Spend per UTC day before OpenRouter is skipped (None = unlimited)
+    pub openrouter_daily_micro_usd: Option<u64>,
+    /// Spend per UTC day before Hume is skipped (None = unlimited)
+    pub hume_daily_micro_usd: Option<u64>,
+    /// Charged per Hume call, which reports no cost of its own
+    pub hume_call_micro_usd: u64,
+}
+
+impl CostLimits {
+    /// Limits from `OPENROUTER_DAILY_BUDGET_USD`, `HUME_DAILY_BUDGET_USD` and
+    /// `HUME_COST_PER_CALL_USD`; unset or invalid caps are unlimited
+    pub fn from_env() -> Self {
+        let usd = |name: &str| std::env::var(name).ok().and_then(|v| parse_usd(&v));
+        Self {
+            openrouter_daily_micro_usd: usd("OPENROUTER_DAILY_BUDGET_USD"),
+            hume_daily_micro_usd: usd("HUME_DAILY_BUDGET_USD"),
+            hume_call_micro_usd: usd("HUME_COST_PER_CALL_USD").unwrap_or(0),
+        }
+    }
+}
+
+/// Dollar amount to micro-USD
+fn parse_usd(text: &str) -> Option<u64> {
+    let usd = text.trim().parse::<f64>().ok()?;
+    (usd.is_finite() && usd >= 0.0).then(|| (usd * MICRO_USD_PER_USD).round() as u64)
+}
+
+fn to_usd(micro_usd: u64) -> f64 {
+    micro_usd as f64 / MICRO_USD_PER_USD
+}
+
+/// What one upstream call consumed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct Usage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub cost_micro_usd: u64,
+}
+
+impl Usage {
+    /// From an OpenRouter `usage` object; `cost` is in credits (USD)
+    pub fn from_openrouter(prompt_tokens: u64, completion_tokens: u64, cost_usd: Option<f64>) -> Self {
+        let cost_micro_usd = cost_usd
+            .filter(|usd| usd.is_finite() && *usd > 0.0)
+            .map_or(0, |usd| (usd * MICRO_USD_PER_USD).round() as u64);
+        Self { prompt_tokens, completion_tokens, cost_micro_usd }
+    }
+}
+
+/// Running totals for one provider
+#[derive(Debug, Default)]
+struct Ledger {
+    requests: u64,
+    prompt_tokens: u64,
+    completion_tokens: u64,
+    cost_micro_usd: u64,
+    /// UTC day (days since the epoch) that `today_micro_usd` covers
+    day: u64,
+    today_micro_usd: u64,
+    /// Whether today's over-budget alert has gone out
+    alerted: bool,
+}
+
+impl Ledger {
+    fn roll_over(&mut self, day: u64) {
+        if self.day != day {
+            self.day = day;
+            self.today_micro_usd = 0;
+            self.alerted = false;
+        }
+    }
+}
+
+/// Today's spend per provider, as a snapshot keeps it
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(super) struct SavedSpend {
+    /// UTC day (days since the epoch) the amounts cover
+    day: u64,
+    openrouter_micro_usd: u64,
+    hume_micro_usd: u64,
+}
+
+/// Per-provider spend against the daily caps
+pub struct CostTracker {
+    limits: CostLimits,
+    openrouter: Mutex<Ledger>,
+    hume: Mutex<Ledger>,
+}
+
+/// Spend and usage for one provider
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderCosts {
+    /// Calls since boot
+    pub requests: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    /// Spend since boot
+    pub cost_usd: f64,
+    /// Spend in the current UTC day
+    pub today_cost_usd: f64,
+    pub daily_budget_usd: Option<f64>,
+    /// Whether the provider is being skipped for the rest of the day
+    pub budget_exhausted: bool,
+}
+
+/// Upstream spend, as served at `/metrics`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpstreamCostMetrics {
+    pub openrouter: ProviderCosts,
+    pub hume: ProviderCosts,
+    /// Upstream calls saved by reusing recent analyses
+    #[serde(default)]
+    pub analysis_cache: AnalysisCacheMetrics,
+    /// Borderline stress scores sent through a second analysis pass
+    #[serde(default)]
+    pub gray_zone: GrayZoneMetrics,
+}
+
+impl CostTracker {
+    pub fn new(limits: CostLimits) -> Self {
+        Self {
+            limits,
+            openrouter: Mutex::new(Ledger::default()),
+            hume: Mutex::new(Ledger::default()),
+        }
+    }
+
+    fn ledger(&self, upstream: Upstream) -> (&Mutex<Ledger>, Option<u64>) {
+        match upstream {
+            Upstream::OpenRouter => (&self.openrouter, self.limits.openrouter_daily_micro_usd),
+            Upstream::Hume => (&self.hume, self.limits.hume_daily_micro_usd),
+        }
+    }
+
+    /// Whether the provider still has budget today
+    pub(crate) fn allows(&self, upstream: Upstream) -> bool {
+        self.allows_at(upstream, now_ms())
+    }
+
+    fn allows_at(&self, upstream: Upstream, now_ms: u64) -> bool {
+        let (ledger, cap) = self.ledger(upstream);
+        let Some(cap) = cap else {
+            return true;
+        };
+        let mut ledger = ledger.lock().unwrap();
+        ledger.roll_over(now_ms / DAY_MS);
+        ledger.today_micro_usd < cap
+    }
+
+    /// Add one completed call; Hume calls are charged the configured rate
+    pub(crate) fn record(&self, upstream: Upstream, usage: Usage) {
+        self.record_at(upstream, usage, now_ms())
+    }
+
+    fn record_at(&self, upstream: Upstream, usage: Usage, now_ms: u64) {
+        let cost = match upstream {
+            Upstream::OpenRouter => usage.cost_micro_usd,
+            Upstream::Hume => self.limits.hume_call_micro_usd,
+        };
+        let (ledger, cap) = self.ledger(upstream);
+        let mut ledger = ledger.lock().unwrap();
+        ledger.roll_over(now_ms / DAY_MS);
+        ledger.requests += 1;
+        ledger.prompt_tokens += usage.prompt_tokens;
+        ledger.completion_tokens += usage.completion_tokens;
+        ledger.cost_micro_usd += cost;
+        ledger.today_micro_usd += cost;
+
+        if let Some(cap) = cap {
+            if ledger.today_micro_usd >= cap && !ledger.alerted {
+                ledger.alerted = true;
+                error!(
+                    "ALERT: {:?} daily budget of ${:.2} exhausted (${:.2} spent today); \
+                     bio-auth uses DSP-only stress analysis until 00:00 UTC",
+                    upstream,
+                    to_usd(cap),
+                    to_usd(ledger.today_micro_usd)
+                );
+            }
+        }
+    }
+
+    /// Today's spend, for a snapshot; totals since boot start over anyway
+    pub(super) fn save(&self, now_ms: u64) -> SavedSpend {
+        let today = |upstream: Upstream| {
+            let mut ledger = self.ledger(upstream).0.lock().unwrap();
+            ledger.roll_over(now_ms / DAY_MS);
+            ledger.today_micro_usd
+        };
+        SavedSpend {
+            day: now_ms / DAY_MS,
+            openrouter_micro_usd: today(Upstream::OpenRouter),
+            hume_micro_usd: today(Upstream::Hume),
+        }
+    }
+
+    /// Count a snapshot's spend toward today's caps, if it was taken today
+    pub(super) fn restore(&self, saved: SavedSpend, now_ms: u64) {
+        if saved.day != now_ms / DAY_MS {
+            return;
+        }
+        for (upstream, spent) in [(Upstream::OpenRouter, saved.openrouter_micro_usd), (Upstream::Hume, saved.hume_micro_usd)] {
+            let (ledger, cap) = self.ledger(upstream);
+            let mut ledger = ledger.lock().unwrap();
+            ledger.roll_over(saved.day);
+            ledger.today_micro_usd = ledger.today_micro_usd.max(spent);
+            // A cap already crossed was alerted on before the restart
+            let crossed = cap.is_some_and(|cap| ledger.today_micro_usd >= cap);
+            ledger.alerted |= crossed;
+        }
+    }
+
+    pub fn metrics(&self) -> UpstreamCostMetrics {
+        self.metrics_at(now_ms())
+    }
+
+    fn metrics_at(&self, now_ms: u64) -> UpstreamCostMetrics {
+        let provider = |upstream: Upstream| {
+            let (ledger, cap) = self.ledger(upstream);
+            let mut ledger = ledger.lock().unwrap();
+            ledger.roll_over(now_ms / DAY_MS);
+            ProviderCosts {
+                requests: ledger.requests,
+                prompt_tokens: ledger.prompt_tokens,
+                completion_tokens: ledger.completion_tokens,
+                cost_usd: to_usd(ledger.cost_micro_usd),
+                today_cost_usd: to_usd(ledger.today_micro_usd),
+                daily_budget_usd: cap.map(to_usd),
+                budget_exhausted: cap.is_some_and(|cap| ledger.today_micro_usd >= cap),
+            }
+        };
+        UpstreamCostMetrics {
+            openrouter: provider(Upstream::OpenRouter),
+            hume: provider(Upstream::Hume),
+            analysis_cache: AnalysisCacheMetrics::default(),
+            gray_zone: GrayZoneMetrics::default(),
+        }
+    }
+}
+
+impl Default for CostTracker {
+    /// Metering only, no caps
+    fn default() -> Self {
+        Self::new(CostLimits::default())
+    }
+}
+
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Upstream API usage and spend against the daily budgets
+///
+/// GET /metrics
+pub async fn get_metrics(State(state): State<Arc<AppState>>) -> Json<UpstreamCostMetrics> {
+    let mut metrics = state.upstream_costs.metrics();
+    metrics.analysis_cache = state.analysis_cache.metrics();
+    metrics.gray_zone = state.bioauth_gray_zone.metrics();
+    Json(metrics)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY_1: u64 = 20_000 * DAY_MS;
+
+    fn openrouter_call(cost_usd: f64) -> Usage {
+        Usage::from_openrouter(1_200, 40, Some(cost_usd))
+    }
+
+    #[test]
+    fn test_parse_usd() {
+        assert_eq!(parse_usd("20"), Some(20_000_000));
+        assert_eq!(parse_usd(" 0.015 "), Some(15_000));
+        assert_eq!(parse_usd("-1"), None);
+        assert_eq!(parse_usd("NaN"), None);
+        assert_eq!(parse_usd("lots"), None);
+    }
+
+    #[test]
+    fn test_cap_exhausts_then_resets_at_midnight() {
+        let tracker = CostTracker::new(CostLimits {
+            openrouter_daily_micro_usd: Some(100_000),
+            ..CostLimits::default()
+        });
+        tracker.record_at(Upstream::OpenRouter, openrouter_call(0.06), DAY_1);
+        assert!(tracker.allows_at(Upstream::OpenRouter, DAY_1));
+        tracker.record_at(Upstream::OpenRouter, openrouter_call(0.06), DAY_1 + 1_000);
+        assert!(!tracker.allows_at(Upstream::OpenRouter, DAY_1 + 2_000));
+        // Hume has no cap here
+        assert!(tracker.allows_at(Upstream::Hume, DAY_1 + 2_000));
+
+        let metrics = tracker.metrics_at(DAY_1 + 2_000).openrouter;
+        assert_eq!(metrics.requests, 2);
+        assert_eq!(metrics.prompt_tokens, 2_400);
+        assert!((metrics.cost_usd - 0.12).abs() < 1e-9);
+        assert!(metrics.budget_exhausted);
+
+        // A new UTC day starts with a fresh budget; totals carry on
+        assert!(tracker.allows_at(Upstream::OpenRouter, DAY_1 + DAY_MS));
+        let metrics = tracker.metrics_at(DAY_1 + DAY_MS).openrouter;
+        assert_eq!(metrics.today_cost_usd, 0.0);
+        assert_eq!(metrics.requests, 2);
+    }
+
+    #[test]
+    fn test_hume_calls_charged_configured_rate() {
+        let tracker = CostTracker::new(CostLimits {
+            hume_daily_micro_usd: Some(50_000),
+            hume_call_micro_usd: 25_000,
+            ..CostLimits::default()
+        });
+        tracker.record_at(Upstream::Hume, Usage::default(), DAY_1);
+        assert!(tracker.allows_at(Upstream::Hume, DAY_1));
+        tracker.record_at(Upstream::Hume, Usage::default(), DAY_1);
+        assert!(!tracker.allows_at(Upstream::Hume, DAY_1));
+        assert_eq!(tracker.metrics_at(DAY_1).hume.today_cost_usd, 0.05);
+    }
+
+    #[test]
+    fn test_uncapped_always_allows() {
+        let tracker = CostTracker::default();
+        tracker.record_at(Upstream::OpenRouter, openrouter_call(1_000.0), DAY_1);
+        assert!(tracker.allows_at(Upstream::OpenRouter, DAY_1));
+        assert!(!tracker.metrics_at(DAY_1).openrouter.budget_exhausted);
+    }
+}
@@ -0,0 +1,81 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! Sealed duress decoy flag
+//!
+//! A decoy is duress signed with result OK, so the client payload must not
+//! say which it is: a coercer holding the device reads the same response.
+//! Every bio-auth payload carries a `seal` instead of a flag: 16 random bytes
+//! followed by `sha256(DECOY_SEAL_DOMAIN || nonce || decoy)`, with `decoy`
+//! as one byte. It looks the same whatever the verdict, is covered by the
+//! enclave's signature, and core.move's `is_decoy_seal` opens it by hashing
+//! both possibilities; anything else fails `apply_bioauth`.
+
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+
+/// Must match `DECOY_SEAL_DOMAIN` in core.move
+const DECOY_SEAL_DOMAIN: &[u8] = b"RAM_DECOY_SEAL_V1";
+/// Random bytes before the digest
+const NONCE_LEN: usize = 16;
+
+fn digest(nonce: &[u8], decoy: bool) -> [u8; 32] {
+    Sha256::new()
+        .chain_update(DECOY_SEAL_DOMAIN)
+        .chain_update(nonce)
+        .chain_update([decoy as u8])
+        .finalize()
+        .into()
+}
+
+/// A fresh seal over `decoy` (48 bytes, different on every call)
+pub fn seal(decoy: bool) -> Vec<u8> {
+    let mut nonce = [0u8; NONCE_LEN];
+    rand::thread_rng().fill_bytes(&mut nonce);
+    let mut sealed = nonce.to_vec();
+    sealed.extend_from_slice(&digest(&nonce, decoy));
+    sealed
+}
+
+/// What a seal commits to, or None if it isn't one
+pub fn open(seal: &[u8]) -> Option<bool> {
+    if seal.len() != NONCE_LEN + 32 {
+        return None;
+    }
+    let (nonce, tag) = seal.split_at(NONCE_LEN);
+    [false, true].into_iter().find(|&decoy| digest(nonce, decoy) == tag)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fastcrypto::encoding::{Encoding, Hex};
+
+    #[test]
+    fn test_seal_opens_to_its_flag() {
+        assert_eq!(open(&seal(true)), Some(true));
+        assert_eq!(open(&seal(false)), Some(false));
+        assert_ne!(seal(false), seal(false));
+    }
+
+    #[test]
+    fn test_seal_rejects_tampering() {
+        let mut sealed = seal(true);
+        sealed[NONCE_LEN] ^= 1;
+        assert_eq!(open(&sealed), None);
+        assert_eq!(open(&[]), None);
+        assert_eq!(open(&seal(true)[..40]), None);
+    }
+
+    #[test]
+    fn test_seal_vector_matches_move() {
+        // Same bytes as tests.move's test_decoy_seal_vector
+        let nonce = [7u8; NONCE_LEN];
+        let mut sealed = nonce.to_vec();
+        sealed.extend_from_slice(&digest(&nonce, true));
+        assert_eq!(
+            Hex::encode(&sealed),
+            "07070707070707070707070707070707ba5b4229accca50720c102a3a3385944ea968edbca0c8cc9a240fa4ee48ba4e5"
+        );
+    }
+}
@@ -0,0 +1,60 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! Deposit proofs for exchanges
+//!
+//! An exchange crediting withdrawals to RAM users needs to know which of its
+//! own deposit memos (or intent IDs) belongs to which handle.
+//! `POST /prove_deposit` signs a `DepositProofPayload` binding a `deposit_id`
+//! to a handle; the exchange passes it to the contract's
+//! `wallet::deposit_with_proof`, which checks the handle is the receiving
+//! wallet's and emits `DepositProved` with the ID for the indexer. The proof
+//! only ever adds funds to the wallet it names, so it needs no voice and is
+//! reusable for every deposit under the same ID.
+
+use crate::common::{IntentScope, ProcessDataRequest};
+use crate::AppState;
+use crate::EnclaveError;
+use axum::extract::State;
+use axum::Json;
+use std::sync::Arc;
+use tracing::info;
+
+use super::types::*;
+use super::validation::ValidatedJson;
+use super::versions::sign_payload;
+
+/// Longest deposit ID in bytes
+pub const MAX_DEPOSIT_ID_LEN: usize = 64;
+
+/// Sign a deposit ID as belonging to a handle
+///
+/// Request: handle, deposit_id
+/// Response: signed DepositProofPayload (DEPOSIT_PROOF_INTENT)
+pub async fn process_prove_deposit(
+    State(state): State<Arc<AppState>>,
+    ValidatedJson(request): ValidatedJson<ProcessDataRequest<ProveDepositRequest>>,
+) -> Result<Json<ProveDepositResponse>, EnclaveError> {
+    let req = &request.payload;
+    let version = state.payload_versions.select(request.payload_version)?;
+    info!("RAM ProveDeposit: handle='{}', deposit_id='{}'", req.handle, req.deposit_id);
+
+    let current_timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_err(|e| EnclaveError::GenericError(format!("Failed to get timestamp: {}", e)))?
+        .as_millis() as u64;
+
+    let payload = DepositProofPayload {
+        handle: req.handle.clone().into_bytes(),
+        deposit_id: req.deposit_id.clone().into_bytes(),
+    };
+    let signature = sign_payload(&state.eph_kp, &payload, current_timestamp, IntentScope::DepositProof, version);
+
+    Ok(Json(ProveDepositResponse {
+        payload,
+        intent: DEPOSIT_PROOF_INTENT,
+        payload_version: version as u8,
+        timestamp_ms: current_timestamp,
+        signature,
+    }))
+}
@@ -0,0 +1,171 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! Privileged BioAuth diagnostics channel
+//!
+//! The `/bio_auth` response is intentionally blind, so operators otherwise have
+//! no way to learn why a wallet got locked. Each bio-auth stores its
+//! human-readable `BioAuthData` here for a short TTL, keyed by the request ID
+//! returned to the caller. Only a server-to-server caller presenting the shared
+//! diagnostics secret can read it back.
+
+use crate::AppState;
+use crate::EnclaveError;
+use axum::extract::{Path, State};
+use axum::http::HeaderMap;
+use axum::Json;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+use super::types::BioAuthData;
+
+/// Default retention for stored diagnostics (10 minutes)
+pub const DEFAULT_DIAGNOSTICS_TTL_SECS: u64 = 600;
+
+/// Short-term in-memory store of BioAuth diagnostics keyed by request ID
+pub struct DiagnosticsStore {
+    ttl: Duration,
+    entries: Mutex<HashMap<String, (Instant, BioAuthData)>>,
+}
+
+impl DiagnosticsStore {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Store diagnostics for a request, pruning expired entries
+    pub fn insert(&self, request_id: String, data: BioAuthData) {
+        let now = Instant::now();
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, (stored_at, _)| now.duration_since(*stored_at) < self.ttl);
+        entries.insert(request_id, (now, data));
+    }
+
+    /// Fetch diagnostics for a request if still within the TTL
+    pub fn get(&self, request_id: &str) -> Option<BioAuthData> {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .get(request_id)
+            .filter(|(stored_at, _)| stored_at.elapsed() < self.ttl)
+            .map(|(_, data)| data.clone())
+    }
+}
+
+impl Default for DiagnosticsStore {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(DEFAULT_DIAGNOSTICS_TTL_SECS))
+    }
+}
+
+/// Constant-time comparison so the secret can't be recovered via timing
+fn secret_matches(provided: &str, expected: &str) -> bool {
+    if provided.len() != expected.len() {
+        return false;
+    }
+    provided
+        .bytes()
+        .zip(expected.bytes())
+        .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+        == 0
+}
+
+/// Check the `Authorization: Bearer <secret>` header against the configured secret
+pub(crate) fn authorize_privileged(headers: &HeaderMap, secret: &str) -> Result<(), EnclaveError> {
+    if secret.is_empty() {
+        return Err(EnclaveError::Forbidden(
+            "Privileged channel is disabled".to_string(),
+        ));
+    }
+
+    let provided = headers
+        .get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .unwrap_or("");
+
+    if secret_matches(provided, secret) {
+        Ok(())
+    } else {
+        Err(EnclaveError::Forbidden("Invalid credentials".to_string()))
+    }
+}
+
+/// Return stored BioAuth diagnostics to an authenticated backend
+///
+/// GET /bio_auth/diagnostics/:request_id
+/// Requires `Authorization: Bearer <DIAGNOSTICS_API_SECRET>`.
+pub async fn get_bio_auth_diagnostics(
+    State(state): State<Arc<AppState>>,
+    Path(request_id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<BioAuthData>, EnclaveError> {
+    if let Err(e) = authorize_privileged(&headers, &state.diagnostics_secret) {
+        warn!("RAM Diagnostics: rejected request for '{}'", request_id);
+        return Err(e);
+    }
+
+    let data = state
+        .bioauth_diagnostics
+        .get(&request_id)
+        .ok_or_else(|| EnclaveError::NotFound(format!("No diagnostics for request '{}'", request_id)))?;
+
+    info!("RAM Diagnostics: served diagnostics for request '{}'", request_id);
+
+    Ok(Json(data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(handle: &str) -> BioAuthData {
+        BioAuthData {
+            handle: handle.to_string(),
+            amount: 5_000_000_000,
+            amount_display: "5 SUI".to_string(),
+            result: "duress".to_string(),
+            transcript: "help".to_string(),
+            stress_level: 80,
+            locked: true,
+            destination_verified: false,
+            shadow_mode: false,
+            detected_result: "duress".to_string(),
+            method: "voice".to_string(),
+            decoy: false,
+            panic_phrase: false,
+            analyzer_disagreement: None,
+        }
+    }
+
+    #[test]
+    fn test_store_roundtrip() {
+        let store = DiagnosticsStore::default();
+        store.insert("req-1".to_string(), sample("alice"));
+        assert_eq!(store.get("req-1").unwrap().handle, "alice");
+        assert!(store.get("req-2").is_none());
+    }
+
+    #[test]
+    fn test_store_expiry() {
+        let store = DiagnosticsStore::new(Duration::from_millis(0));
+        store.insert("req-1".to_string(), sample("alice"));
+        assert!(store.get("req-1").is_none());
+    }
+
+    #[test]
+    fn test_authorize_privileged() {
+        let mut headers = HeaderMap::new();
+        assert!(authorize_privileged(&headers, "s3cret").is_err());
+        headers.insert("authorization", "Bearer wrong!".parse().unwrap());
+        assert!(authorize_privileged(&headers, "s3cret").is_err());
+        headers.insert("authorization", "Bearer s3cret".parse().unwrap());
+        assert!(authorize_privileged(&headers, "s3cret").is_ok());
+        // Empty secret disables the channel entirely
+        assert!(authorize_privileged(&headers, "").is_err());
+    }
+}
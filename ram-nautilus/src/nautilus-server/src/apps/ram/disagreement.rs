@@ -0,0 +1,113 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! Structured warnings when the stress analyzers disagree
+//!
+//! GPT-4o, Hume and the DSP analysis score stress independently and the
+//! highest score wins, so a provider drifting (a model update, a changed
+//! calibration) first shows up as it parting ways with the others. When a
+//! clip's sub-scores differ by more than `ANALYZER_DISAGREEMENT_POINTS` (30
+//! by default; 0 turns it off), a warning with every sub-score is logged
+//! under the `analyzer_disagreement` target for log pipelines to aggregate,
+//! and the sub-scores are kept with the request's diagnostics. Analyzers
+//! that didn't score the clip (not configured, failed, not a WAV) are left
+//! out; it takes two to disagree.
+
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+use tracing::warn;
+
+/// Log target of analyzer disagreements
+pub const ANALYZER_DISAGREEMENT_TARGET: &str = "analyzer_disagreement";
+
+/// Largest sub-score spread not reported unless `ANALYZER_DISAGREEMENT_POINTS` says otherwise
+pub const DEFAULT_DISAGREEMENT_POINTS: u8 = 30;
+
+/// Stress score of each analyzer that scored a clip, before they're combined
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StressScores {
+    pub gpt: Option<u8>,
+    pub hume: Option<u8>,
+    pub dsp: Option<u8>,
+}
+
+impl StressScores {
+    /// Gap between the highest and lowest sub-score, if at least two analyzers scored the clip
+    pub fn spread(&self) -> Option<u8> {
+        let scores: Vec<u8> = [self.gpt, self.hume, self.dsp].into_iter().flatten().collect();
+        let (min, max) = (scores.iter().min()?, scores.iter().max()?);
+        (scores.len() >= 2).then(|| max - min)
+    }
+
+    /// The disagreement in these scores, if they spread over `points` (0 never)
+    pub fn disagreement(&self, points: u8) -> Option<Disagreement> {
+        let spread = self.spread().filter(|spread| points > 0 && *spread > points)?;
+        Some(Disagreement { scores: *self, spread })
+    }
+}
+
+/// Sub-scores of a clip the analyzers disagreed on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Disagreement {
+    pub scores: StressScores,
+    pub spread: u8,
+}
+
+/// Threshold from `ANALYZER_DISAGREEMENT_POINTS`; unset or invalid keeps the default
+pub fn points_from_env() -> u8 {
+    std::env::var("ANALYZER_DISAGREEMENT_POINTS")
+        .ok()
+        .and_then(|v| v.trim().parse::<u8>().ok())
+        .filter(|points| *points <= 100)
+        .unwrap_or(DEFAULT_DISAGREEMENT_POINTS)
+}
+
+static POINTS: OnceLock<u8> = OnceLock::new();
+
+/// Report spreads over `points` for the rest of the process
+pub fn install(points: u8) {
+    let _ = POINTS.set(points);
+}
+
+/// The installed threshold, or the default if none was installed
+pub fn current() -> u8 {
+    *POINTS.get_or_init(|| DEFAULT_DISAGREEMENT_POINTS)
+}
+
+/// Log the disagreement in `scores` under the installed threshold, if any,
+/// and return it for the request's diagnostics
+pub fn report(handle: &str, request_id: &str, scores: &StressScores) -> Option<Disagreement> {
+    let disagreement = scores.disagreement(current())?;
+    warn!(
+        target: ANALYZER_DISAGREEMENT_TARGET,
+        handle,
+        request_id,
+        gpt = scores.gpt,
+        hume = scores.hume,
+        dsp = scores.dsp,
+        spread = disagreement.spread,
+        threshold = current(),
+        "Stress analyzers disagree"
+    );
+    Some(disagreement)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spread_over_the_threshold_is_a_disagreement() {
+        let scores = StressScores { gpt: Some(15), hume: Some(80), dsp: Some(40) };
+        assert_eq!(scores.spread(), Some(65));
+        assert_eq!(scores.disagreement(30), Some(Disagreement { scores, spread: 65 }));
+        assert_eq!(scores.disagreement(65), None);
+        assert_eq!(scores.disagreement(0), None);
+
+        // Analyzers that didn't score the clip don't count
+        let alone = StressScores { gpt: Some(90), ..StressScores::default() };
+        assert_eq!(alone.spread(), None);
+        let pair = StressScores { gpt: Some(90), dsp: Some(10), hume: None };
+        assert_eq!(pair.disagreement(DEFAULT_DISAGREEMENT_POINTS).map(|d| d.spread), Some(80));
+    }
+}
@@ -0,0 +1,481 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! Dry runs of transfers before they are answered
+//!
+//! A `/transfer` request can name the two wallet objects in `simulate`. The
+//! enclave signs as usual, then dev-inspects the
+//! `transfers::transfer_with_signature` call the client is about to submit
+//! (`sui_devInspectTransactionBlock`, which needs no gas or sender key). If
+//! the call would abort (a locked or frozen wallet, a short balance, a
+//! spent daily limit, or wallets that aren't the two handles') the
+//! signature is withheld. The client gets a 422 `simulation_failed` with the
+//! reason and Move abort code instead, and no gas is spent on a transaction
+//! that can't succeed. Otherwise the response carries the simulated status
+//! and gas cost.
+//!
+//! The call needs this enclave's registered `Enclave` object
+//! (`RAM_ENCLAVE_ID`) and the package of its witness type
+//! (`RAM_ENCLAVE_PACKAGE_ID`, the RAM package by default), as the backend's
+//! sponsor does. An enclave without them answers unsimulated: the response
+//! then has no `simulation`.
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use fastcrypto::encoding::{Encoding, Hex};
+use serde::ser::{SerializeStructVariant, Serializer};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use tracing::{info, warn};
+
+use super::chain;
+use super::coin::CoinType;
+use super::retry::{self, Provider};
+use super::types::{SimulatedGas, SimulatedTransfer, TransferPayload, TransferSimulation};
+use super::validation::normalize_handle;
+use crate::{EnclaveError, SimulationFailure};
+
+/// The shared `Clock` object and the version it was shared at
+const CLOCK_ID: Address = {
+    let mut id = [0u8; 32];
+    id[31] = 6;
+    id
+};
+const CLOCK_SHARED_VERSION: u64 = 1;
+/// Shared objects kept; lookups past this still work, they just aren't cached
+const SHARED_OBJECT_CACHE_CAPACITY: usize = 4096;
+/// RAM modules whose abort codes are the core ones (see `abort_reason`)
+const RAM_MODULES: [&str; 4] = ["core", "transfers", "limits", "bioguard"];
+
+// ============================================================================
+// Sui transaction kind layout (BCS; variant order must match sui-types)
+// ============================================================================
+
+type Address = [u8; 32];
+
+#[derive(Serialize)]
+enum TransactionKind {
+    ProgrammableTransaction(ProgrammableTransaction),
+}
+
+#[derive(Serialize)]
+struct ProgrammableTransaction {
+    inputs: Vec<CallArg>,
+    commands: Vec<Command>,
+}
+
+#[derive(Serialize)]
+enum CallArg {
+    Pure(Vec<u8>),
+    Object(ObjectArg),
+}
+
+/// Only shared objects are passed (variant 1, after `ImmOrOwnedObject`)
+enum ObjectArg {
+    SharedObject { id: Address, initial_shared_version: u64, mutable: bool },
+}
+
+impl Serialize for ObjectArg {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let ObjectArg::SharedObject { id, initial_shared_version, mutable } = self;
+        let mut variant = serializer.serialize_struct_variant("ObjectArg", 1, "SharedObject", 3)?;
+        variant.serialize_field("id", id)?;
+        variant.serialize_field("initial_shared_version", initial_shared_version)?;
+        variant.serialize_field("mutable", mutable)?;
+        variant.end()
+    }
+}
+
+#[derive(Serialize)]
+enum Command {
+    MoveCall(Box<ProgrammableMoveCall>),
+}
+
+#[derive(Serialize)]
+struct ProgrammableMoveCall {
+    package: Address,
+    module: String,
+    function: String,
+    type_arguments: Vec<TypeTag>,
+    arguments: Vec<Argument>,
+}
+
+/// Only transaction inputs are referenced (variant 1, after `GasCoin`)
+enum Argument {
+    Input(u16),
+}
+
+impl Serialize for Argument {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let Argument::Input(index) = self;
+        serializer.serialize_newtype_variant("Argument", 1, "Input", index)
+    }
+}
+
+/// Only struct type tags are needed (variant 7, after the primitives and vector)
+enum TypeTag {
+    Struct(Box<StructTag>),
+}
+
+impl Serialize for TypeTag {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let TypeTag::Struct(tag) = self;
+        serializer.serialize_newtype_variant("TypeTag", 7, "Struct", tag)
+    }
+}
+
+#[derive(Serialize)]
+struct StructTag {
+    address: Address,
+    module: String,
+    name: String,
+    type_params: Vec<TypeTag>,
+}
+
+impl TypeTag {
+    /// A non-generic struct type
+    fn of(address: Address, module: &str, name: &str) -> Self {
+        TypeTag::Struct(Box::new(StructTag {
+            address,
+            module: module.to_string(),
+            name: name.to_string(),
+            type_params: vec![],
+        }))
+    }
+}
+
+// ============================================================================
+// Simulator
+// ============================================================================
+
+/// Where the simulated call finds the enclave
+struct EnclaveObject {
+    id: Address,
+    /// Package of the `core::XWALLET` witness the Enclave object is typed with
+    package: Address,
+}
+
+/// A transfer's signed call, as the client will submit it
+pub(crate) struct SignedTransfer<'a> {
+    pub payload: &'a TransferPayload,
+    pub coin_type: &'a CoinType,
+    pub payload_version: u8,
+    pub timestamp_ms: u64,
+    /// Hex, as answered
+    pub signature: &'a str,
+}
+
+/// A shared object's initial version and, for wallets, its handle
+#[derive(Clone)]
+struct SharedObject {
+    version: u64,
+    handle: Option<String>,
+}
+
+/// Dev-inspects transfers before they are answered
+#[derive(Default)]
+pub struct TransferSimulator {
+    /// Unset: transfers are answered unsimulated
+    enclave: Option<EnclaveObject>,
+    rpc_url: String,
+    /// Version each object was shared at and its `handle`, neither of which changes
+    shared_objects: Mutex<HashMap<Address, SharedObject>>,
+}
+
+impl TransferSimulator {
+    /// Simulate against the Enclave object `enclave_id`, whose witness is
+    /// defined in `enclave_package_id`, over the fullnode at `rpc_url`
+    pub fn new(enclave_id: &str, enclave_package_id: &str, rpc_url: impl Into<String>) -> Result<Self, String> {
+        let enclave = EnclaveObject {
+            id: parse_object_id(enclave_id).ok_or_else(|| format!("invalid Enclave object ID '{}'", enclave_id))?,
+            package: parse_object_id(enclave_package_id)
+                .ok_or_else(|| format!("invalid enclave package ID '{}'", enclave_package_id))?,
+        };
+        Ok(Self { enclave: Some(enclave), rpc_url: rpc_url.into(), shared_objects: Mutex::new(HashMap::new()) })
+    }
+
+    /// Simulator from `RAM_ENCLAVE_ID` and `RAM_ENCLAVE_PACKAGE_ID`, over
+    /// the chain's fullnode; disabled without an Enclave object
+    pub fn from_env() -> Result<Self, String> {
+        let var = |name: &str| std::env::var(name).ok().map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+        let Some(enclave_id) = var("RAM_ENCLAVE_ID") else {
+            return Ok(Self::default());
+        };
+        let chain = chain::current();
+        let package = var("RAM_ENCLAVE_PACKAGE_ID").unwrap_or_else(|| chain.package_id().to_string());
+        Self::new(&enclave_id, &package, chain.rpc_url.clone())
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enclave.is_some()
+    }
+
+    /// Dry-run `transfer` between the `wallets`; None if simulation is off
+    ///
+    /// A call that would abort, or wallets that aren't the payload's
+    /// handles', is a `SimulationFailed`; a fullnode that can't be asked is
+    /// a plain error, like a failed coin lookup.
+    pub(crate) async fn simulate(
+        &self,
+        wallets: &TransferSimulation,
+        transfer: SignedTransfer<'_>,
+    ) -> Result<Option<SimulatedTransfer>, EnclaveError> {
+        let Some(enclave) = &self.enclave else {
+            return Ok(None);
+        };
+        let invalid_id = |field: &str| EnclaveError::GenericError(format!("Invalid {}", field));
+        let from_id = parse_object_id(&wallets.from_wallet_id).ok_or_else(|| invalid_id("from_wallet_id"))?;
+        let to_id = parse_object_id(&wallets.to_wallet_id).ok_or_else(|| invalid_id("to_wallet_id"))?;
+        let signature = Hex::decode(transfer.signature)
+            .map_err(|e| EnclaveError::GenericError(format!("Invalid signature: {}", e)))?;
+
+        // The contract reads the handles off the wallets, so wallets of other
+        // handles would only fail the signature check, less helpfully
+        let from_version = self.wallet_version(&from_id, &transfer.payload.from_handle).await?;
+        let to_version = self.wallet_version(&to_id, &transfer.payload.to_handle).await?;
+        let enclave_version = match self.shared_object(&enclave.id).await? {
+            Some(object) => object.version,
+            None => return Err(rpc_failed(format!("Enclave object 0x{} not found", Hex::encode(enclave.id)))),
+        };
+
+        let pure = |bytes: Result<Vec<u8>, bcs::Error>| {
+            bytes.map(CallArg::Pure).map_err(|e| EnclaveError::GenericError(format!("BCS: {}", e)))
+        };
+        let shared = |id, initial_shared_version, mutable| {
+            CallArg::Object(ObjectArg::SharedObject { id, initial_shared_version, mutable })
+        };
+        let inputs = vec![
+            shared(from_id, from_version, true),
+            shared(to_id, to_version, true),
+            pure(bcs::to_bytes(&transfer.payload.amount))?,
+            pure(bcs::to_bytes(&transfer.payload.coin_type))?,
+            pure(bcs::to_bytes(&transfer.payload.memo))?,
+            pure(bcs::to_bytes(&transfer.payload_version))?,
+            pure(bcs::to_bytes(&transfer.timestamp_ms))?,
+            pure(bcs::to_bytes(&signature))?,
+            shared(enclave.id, enclave_version, false),
+            shared(CLOCK_ID, CLOCK_SHARED_VERSION, false),
+        ];
+        let coin_type = transfer.coin_type;
+        let call = ProgrammableMoveCall {
+            package: chain::current().package_address(),
+            module: "transfers".to_string(),
+            function: "transfer_with_signature".to_string(),
+            type_arguments: vec![
+                TypeTag::of(coin_type.address_bytes(), coin_type.module(), coin_type.name()),
+                TypeTag::of(enclave.package, "core", "XWALLET"),
+            ],
+            arguments: (0..inputs.len() as u16).map(Argument::Input).collect(),
+        };
+        let kind = TransactionKind::ProgrammableTransaction(ProgrammableTransaction {
+            inputs,
+            commands: vec![Command::MoveCall(Box::new(call))],
+        });
+        let kind = bcs::to_bytes(&kind).map_err(|e| EnclaveError::GenericError(format!("BCS: {}", e)))?;
+
+        // Anyone may send it: the contract checks the enclave's signature, not the sender
+        let sender = format!("0x{}", "0".repeat(64));
+        let result = self.call("sui_devInspectTransactionBlock", json!([sender, STANDARD.encode(kind)])).await?;
+        outcome(&result).map(Some)
+    }
+
+    /// Shared version of a wallet that belongs to `handle`
+    async fn wallet_version(&self, id: &Address, handle: &[u8]) -> Result<u64, EnclaveError> {
+        let fail = |reason: &str, error: String| {
+            EnclaveError::SimulationFailed(SimulationFailure { reason: reason.to_string(), abort_code: None, error })
+        };
+        let handle = String::from_utf8_lossy(handle);
+        match self.shared_object(id).await? {
+            None => Err(fail("wallet_not_found", format!("No wallet 0x{}", Hex::encode(id)))),
+            Some(SharedObject { handle: Some(owner), .. }) if normalize_handle(&owner) != handle => Err(fail(
+                "wallet_handle_mismatch",
+                format!("Wallet 0x{} belongs to '{}', not '{}'", Hex::encode(id), owner, handle),
+            )),
+            Some(object) => Ok(object.version),
+        }
+    }
+
+    /// A shared object, None if there's no such object or it isn't shared
+    async fn shared_object(&self, id: &Address) -> Result<Option<SharedObject>, EnclaveError> {
+        if let Some(object) = self.shared_objects.lock().unwrap().get(id) {
+            return Ok(Some(object.clone()));
+        }
+        let object = self
+            .call("sui_getObject", json!([format!("0x{}", Hex::encode(id)), { "showOwner": true, "showContent": true }]))
+            .await?;
+        let Some(version) = parse_u64(&object["data"]["owner"]["Shared"]["initial_shared_version"]) else {
+            return Ok(None);
+        };
+        let object = SharedObject {
+            version,
+            handle: object["data"]["content"]["fields"]["handle"].as_str().map(str::to_string),
+        };
+
+        let mut cache = self.shared_objects.lock().unwrap();
+        if cache.len() < SHARED_OBJECT_CACHE_CAPACITY {
+            cache.insert(*id, object.clone());
+        }
+        Ok(Some(object))
+    }
+
+    async fn call(&self, method: &str, params: Value) -> Result<Value, EnclaveError> {
+        let client = reqwest::Client::new();
+        let request = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+        let response: Value = retry::send(Provider::SuiRpc, || client.post(&self.rpc_url).json(&request))
+            .await
+            .map_err(|e| rpc_failed(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| rpc_failed(e.to_string()))?;
+        if let Some(error) = response.get("error") {
+            return Err(rpc_failed(error.to_string()));
+        }
+        Ok(response.get("result").cloned().unwrap_or(Value::Null))
+    }
+}
+
+fn rpc_failed(error: String) -> EnclaveError {
+    warn!("RAM: transfer simulation failed: {}", error);
+    EnclaveError::GenericError("Could not simulate the transfer".to_string())
+}
+
+/// The dry run's result as an answer: its gas if it succeeded, why not if it didn't
+fn outcome(result: &Value) -> Result<SimulatedTransfer, EnclaveError> {
+    let effects = &result["effects"];
+    let status = effects["status"]["status"].as_str().unwrap_or_default();
+    if status == "success" {
+        let gas = &effects["gasUsed"];
+        let cost = |field: &str| parse_u64(&gas[field]).unwrap_or_default();
+        let simulated = SimulatedTransfer {
+            status: status.to_string(),
+            gas_used: SimulatedGas {
+                computation_cost: cost("computationCost"),
+                storage_cost: cost("storageCost"),
+                storage_rebate: cost("storageRebate"),
+                non_refundable_storage_fee: cost("nonRefundableStorageFee"),
+            },
+        };
+        info!("RAM: transfer simulation succeeded ({:?})", simulated.gas_used);
+        return Ok(simulated);
+    }
+
+    let error = effects["status"]["error"]
+        .as_str()
+        .or_else(|| result["error"].as_str())
+        .unwrap_or("unknown execution failure")
+        .to_string();
+    let abort = parse_move_abort(&error);
+    let reason = match &abort {
+        Some((module, code)) => abort_reason(module, *code),
+        None => "execution_failed",
+    };
+    info!("RAM: transfer simulation says it would fail ({}): {}", reason, error);
+    Err(EnclaveError::SimulationFailed(SimulationFailure {
+        reason: reason.to_string(),
+        abort_code: abort.map(|(_, code)| code),
+        error,
+    }))
+}
+
+/// Module and code of a `MoveAbort(MoveLocation { .. name: Identifier("m") .. }, code)` error
+fn parse_move_abort(error: &str) -> Option<(String, u64)> {
+    static MOVE_ABORT: OnceLock<regex::Regex> = OnceLock::new();
+    let re = MOVE_ABORT.get_or_init(|| {
+        regex::Regex::new(r#"MoveAbort\(.*?name: Identifier\("(\w+)"\).*\}, (\d+)\)"#).unwrap()
+    });
+    let captures = re.captures(error)?;
+    Some((captures[1].to_string(), captures[2].parse().ok()?))
+}
+
+/// What a RAM abort code means (the E* constants in core.move)
+fn abort_reason(module: &str, code: u64) -> &'static str {
+    if !RAM_MODULES.contains(&module) {
+        return "move_abort";
+    }
+    match (module, code) {
+        ("transfers", 100) => "coin_type_mismatch",
+        (_, 2) => "invalid_signature",
+        (_, 3) => "replay",
+        (_, 4) => "insufficient_balance",
+        (_, 5) => "wallet_locked",
+        (_, 10) => "daily_limit_exceeded",
+        (_, 11) => "wallet_frozen",
+        (_, 16) => "unsupported_payload_version",
+        _ => "move_abort",
+    }
+}
+
+/// An object ID, short or long, with or without `0x`
+fn parse_object_id(id: &str) -> Option<Address> {
+    let hex = id.trim().strip_prefix("0x").unwrap_or(id.trim());
+    if hex.is_empty() || hex.len() > 64 {
+        return None;
+    }
+    Hex::decode(&format!("{:0>64}", hex)).ok()?.try_into().ok()
+}
+
+/// u64 the RPC may send as a number or a string
+fn parse_u64(value: &Value) -> Option<u64> {
+    value.as_u64().or_else(|| value.as_str()?.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_move_aborts_are_named() {
+        let error = "MoveAbort(MoveLocation { module: ModuleId { address: 8d6e, name: Identifier(\"transfers\") }, \
+                     function: 0, instruction: 42, function_name: Some(\"transfer_with_signature\") }, 5) in command 0";
+        assert_eq!(parse_move_abort(error), Some(("transfers".to_string(), 5)));
+        assert_eq!(abort_reason("transfers", 5), "wallet_locked");
+        assert_eq!(abort_reason("transfers", 100), "coin_type_mismatch");
+        assert_eq!(abort_reason("core", 100), "move_abort");
+        assert_eq!(abort_reason("string", 4), "move_abort");
+        assert_eq!(parse_move_abort("InsufficientGas"), None);
+
+        let failed = outcome(&json!({ "effects": { "status": { "status": "failure", "error": error } } }));
+        let Err(EnclaveError::SimulationFailed(failure)) = failed else {
+            panic!("expected a failed simulation");
+        };
+        assert_eq!((failure.reason.as_str(), failure.abort_code), ("wallet_locked", Some(5)));
+    }
+
+    #[test]
+    fn test_transaction_kind_layout() {
+        let kind = TransactionKind::ProgrammableTransaction(ProgrammableTransaction {
+            inputs: vec![
+                CallArg::Object(ObjectArg::SharedObject { id: CLOCK_ID, initial_shared_version: 1, mutable: false }),
+                CallArg::Pure(bcs::to_bytes(&7u64).unwrap()),
+            ],
+            commands: vec![Command::MoveCall(Box::new(ProgrammableMoveCall {
+                package: [1; 32],
+                module: "transfers".to_string(),
+                function: "f".to_string(),
+                type_arguments: vec![TypeTag::of([2; 32], "sui", "SUI")],
+                arguments: vec![Argument::Input(1)],
+            }))],
+        });
+        let bytes = bcs::to_bytes(&kind).unwrap();
+
+        // Programmable, two inputs, the first the immutable shared clock
+        assert_eq!(&bytes[..4], &[0, 2, 1, 1]);
+        assert_eq!(&bytes[4..36], &CLOCK_ID);
+        assert_eq!(&bytes[36..45], &[1, 0, 0, 0, 0, 0, 0, 0, 0]);
+        // then a pure u64, wrapped in its own length, and one move call
+        assert_eq!(&bytes[45..55], &[0, 8, 7, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(&bytes[55..57], &[1, 0]);
+        // whose type argument is a struct and whose argument is input 1
+        assert!(bytes.windows(33).any(|w| w[0] == 7 && w[1..] == [2; 32]));
+        assert_eq!(&bytes[bytes.len() - 4..], &[1, 1, 1, 0]);
+    }
+
+    #[test]
+    fn test_object_ids_are_padded() {
+        assert_eq!(parse_object_id("0x6"), Some(CLOCK_ID));
+        assert_eq!(parse_object_id(&format!("0x{}", "ab".repeat(32))), Some([0xab; 32]));
+        assert_eq!(parse_object_id("0x"), None);
+        assert_eq!(parse_object_id("0xzz"), None);
+    }
+}
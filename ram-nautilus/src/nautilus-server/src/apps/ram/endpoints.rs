@@ -0,0 +1,116 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! Upstream endpoints and image settings
+//!
+//! The services whose answers decide what the enclave signs, or whether it
+//! signs at all, are called at fixed URLs compiled into the image and so
+//! measured into its PCRs: the providers' own, or for services that belong
+//! to the deployment (screening, feature flags) the URL given when the image
+//! is built. They are never read from the environment at runtime: the parent
+//! instance supplies that, and could otherwise point analysis, the wallet
+//! creation CAPTCHA, screening or the duress decoy flag at a server of its own
+//! that clears every request. Test builds and builds with the `mock` feature,
+//! which are never deployed, can redirect an endpoint to a mock server with
+//! [`set_override`].
+//!
+//! Settings that turn such a check off, like the wallet creation gate, failing
+//! screening open or the environment flags are evaluated in, are fixed when
+//! the image is built the same way (see [`image_setting`]).
+
+/// Upstream endpoints the enclave calls
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endpoint {
+    /// OpenRouter chat completions (GPT-4o Audio)
+    OpenRouter,
+    /// Hume batch Expression Measurement jobs
+    Hume,
+    /// Hume streaming Expression Measurement (WebSocket)
+    HumeStream,
+    /// Text-to-speech rendering of challenge phrases
+    Tts,
+    /// The CAPTCHA provider's siteverify API, for the wallet creation gate
+    CaptchaVerify,
+    /// The signed-for network's public fullnode, for coin metadata
+    SuiFullnode,
+    /// The deployment's screening API (`SCREENING_API_URL` at build time)
+    Screening,
+    /// The backend's `GET /api/flags` (`FEATURE_FLAGS_URL` at build time)
+    FeatureFlags,
+}
+
+impl Endpoint {
+    /// URL the image was built with, for endpoints that belong to the deployment
+    fn built_url(self) -> Option<&'static str> {
+        match self {
+            Endpoint::Screening => image_setting(option_env!("SCREENING_API_URL")),
+            Endpoint::FeatureFlags => image_setting(option_env!("FEATURE_FLAGS_URL")),
+            _ => None,
+        }
+    }
+}
+
+/// A setting given when the image was built (an `option_env!`), if it's set and not blank
+pub(crate) fn image_setting(value: Option<&'static str>) -> Option<&'static str> {
+    value.map(str::trim).filter(|value| !value.is_empty())
+}
+
+#[cfg(any(test, feature = "mock"))]
+static OVERRIDES: std::sync::Mutex<Vec<(Endpoint, String)>> = std::sync::Mutex::new(Vec::new());
+
+/// Point `endpoint` at `url`, or back at the real service with `None`
+#[cfg(any(test, feature = "mock"))]
+pub fn set_override(endpoint: Endpoint, url: Option<String>) {
+    let mut overrides = OVERRIDES.lock().unwrap();
+    overrides.retain(|(overridden, _)| *overridden != endpoint);
+    if let Some(url) = url {
+        overrides.push((endpoint, url));
+    }
+}
+
+/// The URL to call `endpoint` at: `url`, unless a test or mock build overrides it
+pub(crate) fn resolve(endpoint: Endpoint, url: &str) -> String {
+    #[cfg(any(test, feature = "mock"))]
+    if let Some((_, overridden)) = OVERRIDES.lock().unwrap().iter().find(|(e, _)| *e == endpoint) {
+        return overridden.clone();
+    }
+    #[cfg(not(any(test, feature = "mock")))]
+    let _ = endpoint;
+    url.to_string()
+}
+
+/// The URL a deployment endpoint is called at, unless a test or mock build
+/// overrides it; `None` if the image was built without one
+pub(crate) fn configured(endpoint: Endpoint) -> Option<String> {
+    #[cfg(any(test, feature = "mock"))]
+    if let Some((_, overridden)) = OVERRIDES.lock().unwrap().iter().find(|(e, _)| *e == endpoint) {
+        return Some(overridden.clone());
+    }
+    endpoint.built_url().map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overrides_replace_and_reset_the_endpoint() {
+        let real = "https://api.hume.ai/v0/batch/jobs";
+        assert_eq!(resolve(Endpoint::Hume, real), real);
+
+        set_override(Endpoint::Hume, Some("http://127.0.0.1:1/jobs".to_string()));
+        set_override(Endpoint::Hume, Some("http://127.0.0.1:2/jobs".to_string()));
+        assert_eq!(resolve(Endpoint::Hume, real), "http://127.0.0.1:2/jobs");
+
+        set_override(Endpoint::Hume, None);
+        assert_eq!(resolve(Endpoint::Hume, real), real);
+
+        // Deployment endpoints fall back to the URL the image was built with
+        set_override(Endpoint::Screening, Some("http://127.0.0.1:3/screen".to_string()));
+        assert_eq!(configured(Endpoint::Screening).as_deref(), Some("http://127.0.0.1:3/screen"));
+        set_override(Endpoint::Screening, None);
+        assert_eq!(configured(Endpoint::Screening).as_deref(), Endpoint::Screening.built_url());
+        assert_eq!(image_setting(Some("  ")), None);
+        assert_eq!(image_setting(Some(" pow ")), Some("pow"));
+    }
+}
@@ -0,0 +1,246 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! Feature flags gating risky analysis features
+//!
+//! The flags live in the backend (`/admin/flags`) and are fetched from
+//! `FEATURE_FLAGS_URL` (the backend's `GET /api/flags`) every
+//! `FEATURE_FLAGS_REFRESH_SECS`, then evaluated here in `RAM_ENVIRONMENT`
+//! the way the backend evaluates them: a flag is on where it's enabled, the
+//! environment is one of its environments (or it lists none) and the subject
+//! falls in its rollout, subjects being bucketed 0-99 by
+//! `SHA-256(flag ":" subject)`. Checks without a subject are on for that
+//! share of calls instead. Gated here:
+//!
+//! - `gray_zone_retry`: the second pass over borderline stress scores, per handle
+//! - `duress_decoy`: signing duress as a decoy (with `BIOAUTH_DURESS_DECOY`), per handle
+//! - `hume_stream`: scoring over Hume's streaming API first, per analysis
+//!
+//! A feature whose flag doesn't exist keeps its default (on for all three),
+//! and a fetch that fails keeps the flags fetched last, so the backend being
+//! away changes nothing.
+//!
+//! The URL and the environment are set when the image is built (see
+//! `endpoints`): `duress_decoy` decides how duress is signed, so the parent
+//! mustn't be able to serve flags of its own or move the enclave to an
+//! environment they're off in.
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{OnceLock, RwLock};
+use std::time::Duration;
+use tracing::{info, warn};
+
+use super::endpoints::{self, Endpoint};
+
+/// Second analysis pass over borderline stress scores (see gray_zone.rs)
+pub const GRAY_ZONE_RETRY: &str = "gray_zone_retry";
+/// Duress signed as an OK-looking decoy
+pub const DURESS_DECOY: &str = "duress_decoy";
+/// Hume's streaming API tried before its batch API
+pub const HUME_STREAM: &str = "hume_stream";
+
+/// How often flags are fetched unless `FEATURE_FLAGS_REFRESH_SECS` says otherwise
+pub const DEFAULT_REFRESH_SECS: u64 = 30;
+/// Budget for one fetch of the flags
+const FETCH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A flag as the backend serves it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeatureFlag {
+    pub name: String,
+    pub enabled: bool,
+    /// Share of subjects (0-100) the flag is on for
+    pub rollout_percent: u8,
+    /// Environments the flag is on in; empty for every environment
+    #[serde(default)]
+    pub environments: Vec<String>,
+}
+
+/// `GET /api/flags` on the backend
+#[derive(Deserialize)]
+struct FlagList {
+    flags: Vec<FeatureFlag>,
+}
+
+/// Rollout bucket (0-99) of `subject` for the flag `name`, as the backend computes it
+pub fn bucket(name: &str, subject: &str) -> u8 {
+    let digest = Sha256::new()
+        .chain_update(name.as_bytes())
+        .chain_update(b":")
+        .chain_update(subject.as_bytes())
+        .finalize();
+    let mut head = [0u8; 8];
+    head.copy_from_slice(&digest[..8]);
+    (u64::from_be_bytes(head) % 100) as u8
+}
+
+/// The flags in effect and the environment they're evaluated in
+pub struct FeatureFlags {
+    environment: String,
+    flags: RwLock<HashMap<String, FeatureFlag>>,
+}
+
+impl Default for FeatureFlags {
+    fn default() -> Self {
+        Self::new("production")
+    }
+}
+
+impl FeatureFlags {
+    /// No flags yet, evaluated in `environment`
+    pub fn new(environment: impl Into<String>) -> Self {
+        Self { environment: environment.into(), flags: RwLock::default() }
+    }
+
+    /// Flags evaluated in `RAM_ENVIRONMENT` as the image was built (default: production)
+    pub fn from_env() -> Self {
+        match endpoints::image_setting(option_env!("RAM_ENVIRONMENT")) {
+            Some(env) => Self::new(env),
+            None => Self::default(),
+        }
+    }
+
+    pub fn environment(&self) -> &str {
+        &self.environment
+    }
+
+    /// Use `flags` from now on
+    pub fn replace(&self, flags: Vec<FeatureFlag>) {
+        *self.flags.write().unwrap_or_else(|e| e.into_inner()) =
+            flags.into_iter().map(|flag| (flag.name.clone(), flag)).collect();
+    }
+
+    /// Whether the feature behind `name` is on for `subject` (or, without
+    /// one, for this call); `default` if there's no such flag
+    pub fn is_enabled(&self, name: &str, subject: Option<&str>, default: bool) -> bool {
+        let flags = self.flags.read().unwrap_or_else(|e| e.into_inner());
+        let Some(flag) = flags.get(name) else {
+            return default;
+        };
+        let rolled_out = match subject {
+            Some(subject) => bucket(name, subject),
+            None => rand::thread_rng().gen_range(0..100),
+        } < flag.rollout_percent;
+        flag.enabled
+            && (flag.environments.is_empty() || flag.environments.contains(&self.environment))
+            && rolled_out
+    }
+
+    /// Replace the flags with the ones served at `url`, returning how many there are
+    pub async fn refresh(&self, client: &reqwest::Client, url: &str) -> Result<usize, String> {
+        let response = client.get(url).timeout(FETCH_TIMEOUT).send().await.map_err(|e| e.to_string())?;
+        if !response.status().is_success() {
+            return Err(format!("flags answered {}", response.status()));
+        }
+        let list = response.json::<FlagList>().await.map_err(|e| format!("unreadable flags: {}", e))?;
+        let count = list.flags.len();
+        self.replace(list.flags);
+        Ok(count)
+    }
+}
+
+static FLAGS: OnceLock<FeatureFlags> = OnceLock::new();
+
+/// Evaluate `flags` for the rest of the process
+pub fn install(flags: FeatureFlags) {
+    let _ = FLAGS.set(flags);
+}
+
+/// The installed flags, or none (every feature at its default) if none were installed
+pub fn current() -> &'static FeatureFlags {
+    FLAGS.get_or_init(FeatureFlags::default)
+}
+
+/// Fetch the installed flags from the image's `FEATURE_FLAGS_URL` now and
+/// every `FEATURE_FLAGS_REFRESH_SECS`; returns the URL, or `None` if it's unset
+pub fn spawn_refresh_from_env() -> Option<String> {
+    let url = endpoints::configured(Endpoint::FeatureFlags)?;
+    let interval = std::env::var("FEATURE_FLAGS_REFRESH_SECS")
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .filter(|secs| *secs > 0)
+        .unwrap_or(DEFAULT_REFRESH_SECS);
+
+    let source = url.clone();
+    tokio::spawn(async move {
+        let client = reqwest::Client::new();
+        let mut loaded = false;
+        loop {
+            match current().refresh(&client, &source).await {
+                Ok(count) if !loaded => {
+                    info!("RAM: loaded {} feature flags for {}", count, current().environment());
+                    loaded = true;
+                }
+                Ok(_) => {}
+                Err(e) => warn!("RAM: failed to refresh feature flags, keeping the last ones: {}", e),
+            }
+            tokio::time::sleep(Duration::from_secs(interval)).await;
+        }
+    });
+    Some(url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::method;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn flag(name: &str, rollout_percent: u8, environments: &[&str]) -> FeatureFlag {
+        FeatureFlag {
+            name: name.to_string(),
+            enabled: true,
+            rollout_percent,
+            environments: environments.iter().map(|env| env.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_flags_bucket_like_the_backend() {
+        // Shared with the backend's flags.rs
+        assert_eq!(bucket(GRAY_ZONE_RETRY, "alice"), 46);
+
+        let flags = FeatureFlags::new("staging");
+        assert!(flags.is_enabled(GRAY_ZONE_RETRY, Some("alice"), true));
+        assert!(!flags.is_enabled(GRAY_ZONE_RETRY, Some("alice"), false));
+
+        flags.replace(vec![flag(GRAY_ZONE_RETRY, 47, &["staging"]), flag(DURESS_DECOY, 100, &["production"])]);
+        assert!(flags.is_enabled(GRAY_ZONE_RETRY, Some("alice"), false));
+        assert!(!flags.is_enabled(DURESS_DECOY, Some("alice"), true));
+
+        flags.replace(vec![flag(GRAY_ZONE_RETRY, 46, &[]), flag(HUME_STREAM, 0, &[])]);
+        assert!(!flags.is_enabled(GRAY_ZONE_RETRY, Some("alice"), true));
+        assert!(!flags.is_enabled(HUME_STREAM, None, true));
+        assert!(flags.is_enabled(DURESS_DECOY, Some("alice"), true));
+    }
+
+    #[tokio::test]
+    async fn test_failed_refresh_keeps_the_last_flags() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "flags": [{
+                    "name": "hume_stream",
+                    "description": "served by the backend, ignored here",
+                    "enabled": false,
+                    "rollout_percent": 100,
+                    "environments": [],
+                    "updated_at_ms": 1
+                }]
+            })))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET")).respond_with(ResponseTemplate::new(503)).mount(&server).await;
+
+        let flags = FeatureFlags::default();
+        let client = reqwest::Client::new();
+        assert_eq!(flags.refresh(&client, &server.uri()).await, Ok(1));
+        assert!(!flags.is_enabled(HUME_STREAM, None, true));
+        assert!(flags.refresh(&client, &server.uri()).await.is_err());
+        assert!(!flags.is_enabled(HUME_STREAM, None, true));
+    }
+}
@@ -0,0 +1,268 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! Self-service wallet freeze and voice-confirmed unfreeze
+//!
+//! A user who suspects compromise freezes their wallet from any device with
+//! `POST /freeze_wallet`: no voice, signed at once, and the contract stops all
+//! funds moving until the freeze is lifted. Lifting it is deliberately slow:
+//! the user asks for a one-time phrase (`POST /unfreeze/challenge`), speaks it
+//! calmly to `POST /request_unfreeze`, and the contract only unfreezes 24
+//! hours after that request lands. Freezing again in that window cancels it,
+//! so an attacker who unfreezes has to outlast the real owner.
+
+use crate::common::{IntentScope, ProcessDataRequest};
+use crate::i18n::{self, current_lang, Lang};
+use crate::AppState;
+use crate::EnclaveError;
+use axum::extract::State;
+use axum::Json;
+use rand::Rng;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+use super::audio;
+use super::coin::CoinInfo;
+use super::confidence;
+use super::stream::Progress;
+use super::types::*;
+use super::validation::ValidatedJson;
+use super::versions::sign_payload;
+
+/// Digits in an unfreeze challenge code
+const UNFREEZE_CODE_DIGITS: usize = 6;
+/// Highest stress level at which an unfreeze is signed ("normal speaking voice")
+pub const MAX_UNFREEZE_STRESS: u8 = 40;
+
+/// Start of every unfreeze phrase for `handle` in `lang`, up to the code
+fn unfreeze_phrase_prefix(lang: Lang, handle: &str) -> String {
+    i18n::format(lang, "Unfreeze wallet {}, code ", &[handle])
+}
+
+/// The phrase to speak in `lang`, with the code spaced out so it is read digit by digit
+pub(super) fn unfreeze_phrase(lang: Lang, handle: &str, code: &str) -> String {
+    let spelled: Vec<String> = code.chars().map(|c| c.to_string()).collect();
+    format!("{}{}", unfreeze_phrase_prefix(lang, handle), spelled.join(" "))
+}
+
+/// Language and code of an issued unfreeze phrase for `handle`
+fn unfreeze_code(phrase: &str, handle: &str) -> Option<(Lang, String)> {
+    Lang::ALL.into_iter().find_map(|lang| {
+        let code = phrase.strip_prefix(&unfreeze_phrase_prefix(lang, handle))?;
+        Some((lang, code.replace(' ', "")))
+    })
+}
+
+/// Words for the digits 0-9 in `lang`, with their common variants
+fn digit_words(lang: Lang) -> &'static [(&'static str, char)] {
+    match lang {
+        Lang::En => &[
+            ("zero", '0'), ("oh", '0'), ("one", '1'), ("two", '2'), ("three", '3'), ("four", '4'),
+            ("five", '5'), ("six", '6'), ("seven", '7'), ("eight", '8'), ("nine", '9'),
+        ],
+        Lang::Vi => &[
+            ("không", '0'), ("một", '1'), ("hai", '2'), ("ba", '3'), ("bốn", '4'), ("tư", '4'),
+            ("năm", '5'), ("lăm", '5'), ("sáu", '6'), ("bảy", '7'), ("bẩy", '7'), ("tám", '8'), ("chín", '9'),
+        ],
+    }
+}
+
+/// Digits spoken in a transcript, whether transcribed as numerals or words
+fn spoken_digits(lang: Lang, transcript: &str) -> String {
+    let words = digit_words(lang);
+    transcript
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .flat_map(|word| match words.iter().find(|(w, _)| *w == word) {
+            Some((_, digit)) => vec![*digit],
+            None => word.chars().filter(char::is_ascii_digit).collect(),
+        })
+        .collect()
+}
+
+/// Whether the transcript asks to unfreeze, in `lang`, and says the challenge code
+fn confirms_unfreeze(lang: Lang, transcript: &str, code: &str) -> bool {
+    let keyword = match lang {
+        Lang::En => "unfreeze",
+        Lang::Vi => "mở đóng băng",
+    };
+    transcript.to_lowercase().contains(keyword) && spoken_digits(lang, transcript).contains(code)
+}
+
+fn now_ms() -> Result<u64, EnclaveError> {
+    Ok(std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_err(|e| EnclaveError::GenericError(format!("Failed to get timestamp: {}", e)))?
+        .as_millis() as u64)
+}
+
+/// Sign a freeze; needs no voice
+///
+/// Request: handle
+/// Response: signed FreezePayload (FREEZE_INTENT)
+pub async fn process_freeze_wallet(
+    State(state): State<Arc<AppState>>,
+    ValidatedJson(request): ValidatedJson<ProcessDataRequest<FreezeWalletRequest>>,
+) -> Result<Json<FreezeWalletResponse>, EnclaveError> {
+    let req = &request.payload;
+    let version = state.payload_versions.select(request.payload_version)?;
+    info!("RAM Freeze: freezing wallet for handle='{}'", req.handle);
+
+    let current_timestamp = now_ms()?;
+    let payload = FreezePayload { handle: req.handle.clone().into_bytes() };
+    let signature = sign_payload(&state.eph_kp, &payload, current_timestamp, IntentScope::Freeze, version);
+
+    Ok(Json(FreezeWalletResponse {
+        payload,
+        intent: FREEZE_INTENT,
+        payload_version: version as u8,
+        timestamp_ms: current_timestamp,
+        signature,
+    }))
+}
+
+/// Issue the one-time phrase to speak for an unfreeze
+///
+/// POST /unfreeze/challenge — playback via `GET /bio_auth/challenge_audio/:challenge_id`
+pub async fn process_unfreeze_challenge(
+    State(state): State<Arc<AppState>>,
+    ValidatedJson(request): ValidatedJson<ProcessDataRequest<UnfreezeChallengeRequest>>,
+) -> Result<Json<ChallengeResponse>, EnclaveError> {
+    let req = &request.payload;
+    let code: String = {
+        let mut rng = rand::thread_rng();
+        (0..UNFREEZE_CODE_DIGITS).map(|_| char::from(b'0' + rng.gen_range(0..10u8))).collect()
+    };
+    let lang = req.locale.as_deref().and_then(Lang::from_tag).unwrap_or_else(current_lang);
+    let phrase = unfreeze_phrase(lang, &req.handle, &code);
+
+    let challenge_id = state.bioauth_challenges.issue(phrase.clone());
+    info!("RAM Unfreeze: issued challenge {} for handle='{}'", challenge_id, req.handle);
+
+    Ok(Json(ChallengeResponse {
+        challenge_id,
+        phrase,
+        expires_at_ms: now_ms()? + state.bioauth_challenges.ttl.as_millis() as u64,
+    }))
+}
+
+/// Sign an unfreeze request once the user calmly speaks the challenge phrase
+///
+/// Request: handle, challenge_id, audio_base64
+/// Response: signed UnfreezePayload (UNFREEZE_INTENT); the contract applies
+/// it after its delay
+pub async fn process_request_unfreeze(
+    State(state): State<Arc<AppState>>,
+    ValidatedJson(request): ValidatedJson<ProcessDataRequest<RequestUnfreezeRequest>>,
+) -> Result<Json<UnfreezeResponse>, EnclaveError> {
+    let req = &request.payload;
+    let version = state.payload_versions.select(request.payload_version)?;
+    let _handle_lock = state.bioauth_locks.try_acquire(&req.handle).inspect_err(|_| {
+        warn!("RAM Unfreeze: concurrent request rejected for handle '{}'", req.handle);
+    })?;
+    info!("RAM Unfreeze: handle='{}', challenge={}", req.handle, req.challenge_id);
+
+    let current_timestamp = now_ms()?;
+
+    // Shares the failure count and clip fingerprints with bio-auth
+    state.bioauth_attempts.check(&req.handle, current_timestamp).inspect_err(|_| {
+        warn!("RAM Unfreeze: attempt during failure cooldown rejected for handle '{}'", req.handle);
+    })?;
+    let fingerprint = state
+        .bioauth_replays
+        .check(&req.handle, &req.audio_base64, current_timestamp)
+        .inspect_err(|_| warn!("RAM Unfreeze: replayed clip rejected for handle '{}'", req.handle))?;
+
+    // Single use: a failed attempt needs a new challenge
+    let (phrase, (lang, code)) = state
+        .bioauth_challenges
+        .take(&req.challenge_id)
+        .and_then(|phrase| unfreeze_code(&phrase, &req.handle).map(|code| (phrase, code)))
+        .ok_or_else(|| EnclaveError::NotFound(format!("No unfreeze challenge '{}'", req.challenge_id)))?;
+
+    let baseline = state.voice_baselines.get(&req.handle);
+    let upstreams = audio::Upstreams {
+        openrouter_api_key: Some(state.openrouter_api_key.as_str()).filter(|key| !key.is_empty()),
+        hume_api_key: Some(state.hume_api_key.as_str()).filter(|key| !key.is_empty()),
+        costs: &state.upstream_costs,
+    };
+    let analysis = audio::analyze_audio(
+        &req.audio_base64,
+        upstreams,
+        audio::Expected { amount: None, coin: &CoinInfo::sui(), locale: Some(lang.tag()), phrase: Some(&phrase) },
+        baseline.as_ref(),
+        &Progress::default(),
+    )
+    .await?;
+
+    let panic = state
+        .voice_baselines
+        .panic_phrase(&req.handle)
+        .and_then(|phrase| phrase.strip(&analysis.transcript))
+        .is_some();
+    let calm = analysis.stress_level <= MAX_UNFREEZE_STRESS && !panic;
+    let confirmed = confirms_unfreeze(lang, &analysis.transcript, &code);
+
+    // Only an approval needs findings the model is sure of
+    if calm && confirmed {
+        confidence::current().check(&analysis.confidence, false).inspect_err(|_| {
+            warn!("RAM Unfreeze: inconclusive analysis for '{}' ({:?})", req.handle, analysis.confidence);
+        })?;
+    }
+
+    if !(calm && confirmed) {
+        info!(
+            "RAM Unfreeze: ✗ refused for '{}' (stress_level={}, panic_phrase={}, code_spoken={})",
+            req.handle, analysis.stress_level, panic, confirmed
+        );
+        // A stressed voice isn't a failed attempt, as with duress in bio-auth
+        if calm {
+            state.bioauth_attempts.record(&req.handle, BioAuthResult::InvalidAmount, current_timestamp);
+        }
+        return Err(EnclaveError::Forbidden("Unfreeze was not confirmed".to_string()));
+    }
+
+    info!("RAM Unfreeze: ✓ confirmed for '{}'", req.handle);
+    state.bioauth_attempts.record(&req.handle, BioAuthResult::Ok, current_timestamp);
+    if let Some(fingerprint) = fingerprint {
+        state.bioauth_replays.record(&req.handle, fingerprint, current_timestamp);
+    }
+
+    let payload = UnfreezePayload { handle: req.handle.clone().into_bytes() };
+    let signature = sign_payload(&state.eph_kp, &payload, current_timestamp, IntentScope::Unfreeze, version);
+
+    Ok(Json(UnfreezeResponse {
+        payload,
+        intent: UNFREEZE_INTENT,
+        payload_version: version as u8,
+        timestamp_ms: current_timestamp,
+        signature,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unfreeze_phrase_must_be_spoken_with_its_code() {
+        let phrase = unfreeze_phrase(Lang::En, "alice99", "481529");
+        assert_eq!(phrase, "Unfreeze wallet alice99, code 4 8 1 5 2 9");
+        assert_eq!(unfreeze_code(&phrase, "alice99"), Some((Lang::En, "481529".to_string())));
+        assert_eq!(unfreeze_code(&phrase, "bob"), None);
+        assert!(confirms_unfreeze(Lang::En, &phrase, "481529"));
+        assert!(confirms_unfreeze(Lang::En, "Unfreeze wallet alice, code four eight one five two nine", "481529"));
+        assert!(confirms_unfreeze(Lang::En, "unfreeze my wallet code 481-529", "481529"));
+        assert!(!confirms_unfreeze(Lang::En, "Unfreeze wallet alice, code 4 8 1 5 2", "481529"));
+        assert!(!confirms_unfreeze(Lang::En, "I confirm sending 481529 SUI", "481529"));
+    }
+
+    #[test]
+    fn test_unfreeze_phrase_in_vietnamese() {
+        let phrase = unfreeze_phrase(Lang::Vi, "alice99", "401529");
+        assert_eq!(phrase, "Mở đóng băng ví alice99, mã 4 0 1 5 2 9");
+        assert_eq!(unfreeze_code(&phrase, "alice99"), Some((Lang::Vi, "401529".to_string())));
+        assert!(confirms_unfreeze(Lang::Vi, "Mở đóng băng ví alice, mã bốn không một lăm hai chín", "401529"));
+        assert!(!confirms_unfreeze(Lang::Vi, "Unfreeze wallet alice, code 4 0 1 5 2 9", "401529"));
+    }
+}
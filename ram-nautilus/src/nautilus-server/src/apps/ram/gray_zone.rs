@@ -0,0 +1,233 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! Second analysis pass for borderline stress scores
+//!
+//! A combined stress score just over the duress threshold is as likely to be
+//! a noisy room as a coerced user, and either mistake is expensive: a 24h
+//! lock for nothing, or a signed transfer under duress. Scores inside the
+//! gray zone (`BIOAUTH_GRAY_ZONE`, `60-75` by default; `off` disables it) get
+//! a second, independent pass before anything is decided:
+//!
+//! - the DSP analysis again, on a denoised signal (high-passed, with frames
+//!   at the noise floor dropped), when the clip is a WAV
+//! - Hume, when it's configured and the first pass went without it
+//!
+//! The second pass scores the clip the way the first does, by the highest of
+//! its opinions, and the verdict is taken on the mean of the two passes. A
+//! clip the second pass can't score keeps its first score. How often scores
+//! land in the zone, and how often the second pass changed the verdict, are
+//! served at `GET /metrics`. The `gray_zone_retry` feature flag turns the
+//! second pass off per handle (see feature_flags.rs).
+
+use serde::{Deserialize, Serialize};
+use std::ops::RangeInclusive;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tracing::{info, warn};
+
+use super::audio::{self, AudioAnalysisResult, Expected, Upstreams};
+use super::chaos::Upstream;
+use super::feature_flags;
+use super::stream::Progress;
+use super::validation::normalize_handle;
+use super::voice_stress::{self, VoiceBaseline};
+use crate::EnclaveError;
+
+/// Stress scores re-checked unless `BIOAUTH_GRAY_ZONE` says otherwise
+pub const DEFAULT_GRAY_ZONE: RangeInclusive<u8> = 60..=75;
+
+/// The gray zone and how often scores have landed in it
+pub struct GrayZone {
+    range: Option<RangeInclusive<u8>>,
+    analyzed: AtomicU64,
+    borderline: AtomicU64,
+    escalated: AtomicU64,
+    cleared: AtomicU64,
+}
+
+impl GrayZone {
+    /// Re-check scores in `range`; `None` never re-checks
+    pub fn new(range: Option<RangeInclusive<u8>>) -> Self {
+        Self {
+            range,
+            analyzed: AtomicU64::new(0),
+            borderline: AtomicU64::new(0),
+            escalated: AtomicU64::new(0),
+            cleared: AtomicU64::new(0),
+        }
+    }
+
+    /// Zone from `BIOAUTH_GRAY_ZONE` as `low-high` (`off` disables); unset
+    /// or invalid keeps the default
+    pub fn from_env() -> Self {
+        let range = match std::env::var("BIOAUTH_GRAY_ZONE") {
+            Ok(spec) if spec.trim().eq_ignore_ascii_case("off") => None,
+            Ok(spec) => Some(parse_range(&spec).unwrap_or_else(|| {
+                warn!("RAM GrayZone: invalid BIOAUTH_GRAY_ZONE '{}', using the default", spec);
+                DEFAULT_GRAY_ZONE
+            })),
+            Err(_) => Some(DEFAULT_GRAY_ZONE),
+        };
+        Self::new(range)
+    }
+
+    /// The re-checked scores, if the zone is enabled
+    pub fn range(&self) -> Option<&RangeInclusive<u8>> {
+        self.range.as_ref()
+    }
+
+    /// Whether `stress` is too close to call on one pass
+    pub fn contains(&self, stress: u8) -> bool {
+        self.range.as_ref().is_some_and(|range| range.contains(&stress))
+    }
+
+    /// Gray-zone frequency since startup
+    pub fn metrics(&self) -> GrayZoneMetrics {
+        let analyzed = self.analyzed.load(Ordering::Relaxed);
+        let borderline = self.borderline.load(Ordering::Relaxed);
+        GrayZoneMetrics {
+            enabled: self.range.is_some(),
+            low: self.range.as_ref().map(|range| *range.start()),
+            high: self.range.as_ref().map(|range| *range.end()),
+            analyzed,
+            borderline,
+            borderline_rate: if analyzed == 0 { 0.0 } else { borderline as f64 / analyzed as f64 },
+            escalated: self.escalated.load(Ordering::Relaxed),
+            cleared: self.cleared.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Count a decision on `first`, re-checked as `decided` if it was borderline
+    fn record(&self, first: u8, decided: Option<u8>) {
+        self.analyzed.fetch_add(1, Ordering::Relaxed);
+        let Some(decided) = decided else {
+            return;
+        };
+        self.borderline.fetch_add(1, Ordering::Relaxed);
+        match (audio::is_under_duress(first), audio::is_under_duress(decided)) {
+            (false, true) => self.escalated.fetch_add(1, Ordering::Relaxed),
+            (true, false) => self.cleared.fetch_add(1, Ordering::Relaxed),
+            _ => 0,
+        };
+    }
+}
+
+impl Default for GrayZone {
+    fn default() -> Self {
+        Self::new(Some(DEFAULT_GRAY_ZONE))
+    }
+}
+
+/// Gray-zone use since startup
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GrayZoneMetrics {
+    pub enabled: bool,
+    pub low: Option<u8>,
+    pub high: Option<u8>,
+    /// Analyses decided since startup
+    pub analyzed: u64,
+    /// Those whose first score was in the gray zone
+    pub borderline: u64,
+    pub borderline_rate: f64,
+    /// Second passes that turned a calm first score into duress
+    pub escalated: u64,
+    /// Second passes that turned a duress first score calm
+    pub cleared: u64,
+}
+
+fn parse_range(spec: &str) -> Option<RangeInclusive<u8>> {
+    let (low, high) = spec.trim().split_once('-')?;
+    let (low, high) = (low.trim().parse::<u8>().ok()?, high.trim().parse::<u8>().ok()?);
+    (low <= high && high <= 100).then_some(low..=high)
+}
+
+/// The verdict score of a borderline clip: the mean of both passes, rounded
+fn aggregate(first: u8, second: u8) -> u8 {
+    ((first as u16 + second as u16 + 1) / 2) as u8
+}
+
+/// [`audio::analyze_audio`], with a second pass over scores in the gray
+/// zone for handles `gray_zone_retry` is on for; the result's stress level
+/// is the one to decide on
+pub async fn analyze_audio(
+    zone: &GrayZone,
+    handle: &str,
+    audio_base64: &str,
+    upstreams: Upstreams<'_>,
+    expected: Expected<'_>,
+    baseline: Option<&VoiceBaseline>,
+    progress: &Progress,
+) -> Result<AudioAnalysisResult, EnclaveError> {
+    let (hume_api_key, costs) = (upstreams.hume_api_key, upstreams.costs);
+    let mut analysis = audio::analyze_audio(audio_base64, upstreams, expected, baseline, progress).await?;
+    let first = analysis.stress_level;
+    if !zone.contains(first) || !feature_flags::current().is_enabled(feature_flags::GRAY_ZONE_RETRY, Some(&normalize_handle(handle)), true) {
+        zone.record(first, None);
+        return Ok(analysis);
+    }
+
+    let mut opinions = Vec::new();
+    match voice_stress::analyze_denoised_voice_stress_base64(audio_base64, baseline) {
+        Ok(Some(dsp)) => opinions.push(dsp.stress_level),
+        Ok(None) => info!("RAM GrayZone: clip isn't a WAV, no denoised DSP opinion"),
+        Err(e) => warn!("RAM GrayZone: failed to decode audio for the denoised DSP pass: {}", e),
+    }
+    if analysis.emotions.is_none() {
+        if let Some(hume_key) = hume_api_key.filter(|key| !key.is_empty() && costs.allows(Upstream::Hume)) {
+            match audio::analyze_audio_hume(audio_base64, hume_key, costs).await {
+                Ok(emotions) => {
+                    opinions.push(audio::calculate_stress_from_emotions(&emotions));
+                    analysis.emotions = Some(emotions);
+                }
+                Err(e) => warn!("RAM GrayZone: Hume second pass failed: {}", e),
+            }
+        }
+    }
+
+    let decided = match opinions.iter().max() {
+        Some(&second) => {
+            let decided = aggregate(first, second);
+            info!("RAM GrayZone: borderline stress {}, second pass {}, deciding on {}", first, second, decided);
+            decided
+        }
+        None => {
+            warn!("RAM GrayZone: borderline stress {} has no second opinion, keeping it", first);
+            first
+        }
+    };
+    zone.record(first, Some(decided));
+    analysis.stress_level = decided;
+    Ok(analysis)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gray_zone_configuration_and_metrics() {
+        assert_eq!(parse_range(" 55 - 80 "), Some(55..=80));
+        for invalid in ["80-55", "60-101", "60", "low-high", ""] {
+            assert_eq!(parse_range(invalid), None, "{}", invalid);
+        }
+
+        let zone = GrayZone::default();
+        assert!(zone.contains(60) && zone.contains(75));
+        assert!(!zone.contains(59) && !zone.contains(76));
+        assert!(!GrayZone::new(None).contains(65));
+
+        // The mean of both passes decides
+        assert_eq!(aggregate(64, 30), 47);
+        assert_eq!(aggregate(64, 70), 67);
+
+        zone.record(20, None);
+        zone.record(64, Some(47));
+        zone.record(66, Some(68));
+        zone.record(90, None);
+        let metrics = zone.metrics();
+        assert_eq!((metrics.low, metrics.high), (Some(60), Some(75)));
+        assert_eq!((metrics.analyzed, metrics.borderline, metrics.cleared, metrics.escalated), (4, 2, 1, 0));
+        assert_eq!(metrics.borderline_rate, 0.5);
+        assert!(!GrayZone::new(None).metrics().enabled);
+    }
+}
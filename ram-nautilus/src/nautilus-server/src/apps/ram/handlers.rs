@@ -5,27 +5,47 @@
 //!
 //! Contains all the process_* functions for handling wallet operations.
 
-use crate::common::{to_signed_response, IntentScope, ProcessDataRequest};
+use crate::common::{IntentScope, ProcessDataRequest};
+use crate::i18n::{current_lang, Lang};
 use crate::AppState;
 use crate::EnclaveError;
 use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
 use axum::Json;
+use serde_json::json;
 use std::sync::Arc;
-use tracing::info;
+use tracing::{info, warn};
 
+use super::amount::RawAmount;
+use super::analysis_cache::AnalysisKey;
 use super::audio;
+use super::challenge::challenge_phrase;
+use super::coin::CoinType;
+use super::confidence;
+use super::decoy;
+use super::disagreement;
+use super::dry_run::SignedTransfer;
+use super::feature_flags;
+use super::gray_zone;
+use super::locale::NumberLocale;
+use super::stream::{BioAuthStage, Progress};
 use super::types::*;
+use super::validation::{normalize_handle, ValidatedJson};
+use super::versions::{sign_payload, PayloadVersion};
 
 /// Create a new RAM wallet (signed by enclave)
 /// 
 /// This is called when a new user wants to create their voice-protected wallet.
 pub async fn process_create_wallet(
     State(state): State<Arc<AppState>>,
-    Json(request): Json<ProcessDataRequest<CreateWalletRequest>>,
+    ValidatedJson(request): ValidatedJson<ProcessDataRequest<CreateWalletRequest>>,
 ) -> Result<Json<CreateWalletResponse>, EnclaveError> {
     let req = &request.payload;
+    let version = state.payload_versions.select(request.payload_version)?;
     
     info!("RAM: Creating wallet for handle='{}'", req.handle);
+    state.registration_gate.check(&req.handle, req.pow_nonce.as_deref(), req.captcha_token.as_deref()).await?;
 
     let current_timestamp = std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
@@ -38,18 +58,20 @@ pub async fn process_create_wallet(
     };
 
     // Sign payload
-    let signed = to_signed_response(
+    let signature = sign_payload(
         &state.eph_kp,
-        payload.clone(),
+        &payload,
         current_timestamp,
         IntentScope::ProcessData, // Use CREATE_WALLET_INTENT = 0
+        version,
     );
 
     let response = CreateWalletResponse {
         payload,
         intent: CREATE_WALLET_INTENT,
+        payload_version: version as u8,
         timestamp_ms: current_timestamp,
-        signature: signed.signature,
+        signature,
     };
 
     info!("RAM: Wallet creation signed for handle='{}'", req.handle);
@@ -63,56 +85,122 @@ pub async fn process_create_wallet(
 /// TODO: Add wallet signature verification
 pub async fn process_link_address(
     State(state): State<Arc<AppState>>,
-    Json(request): Json<ProcessDataRequest<LinkAddressRequest>>,
+    ValidatedJson(request): ValidatedJson<ProcessDataRequest<LinkAddressRequest>>,
 ) -> Result<Json<LinkAddressResponse>, EnclaveError> {
     let req = &request.payload;
+    let version = state.payload_versions.select(request.payload_version)?;
     
     info!(
-        "RAM: Linking address for handle='{}' -> {}",
-        req.handle, req.wallet_address
+        "RAM: Linking address for handle='{}' -> {} (label='{}')",
+        req.handle, req.wallet_address, req.label
     );
 
+    let addr_bytes = parse_sui_address(&req.wallet_address)?;
+
+    // TODO: Verify wallet signature to prove ownership
+    // For now, we'll trust the request (zkLogin addresses are verified by /link_zklogin)
+
+    let response = sign_link(&state, &req.handle, addr_bytes, &req.label, version)?;
+
+    info!("RAM: Address linked for handle='{}'", req.handle);
+
+    Ok(Json(response))
+}
+
+/// Sign the link of a Sui address to a handle's wallet
+pub(crate) fn sign_link(
+    state: &AppState,
+    handle: &str,
+    address: [u8; 32],
+    label: &str,
+    version: PayloadVersion,
+) -> Result<LinkAddressResponse, EnclaveError> {
     let current_timestamp = std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
         .map_err(|e| EnclaveError::GenericError(format!("Failed to get timestamp: {}", e)))?
         .as_millis() as u64;
 
-    // Parse wallet address (remove 0x prefix if present)
-    let addr_hex = req.wallet_address.strip_prefix("0x").unwrap_or(&req.wallet_address);
-    let addr_bytes: [u8; 32] = hex::decode(addr_hex)
-        .map_err(|e| EnclaveError::GenericError(format!("Invalid address: {}", e)))?
-        .try_into()
-        .map_err(|_| EnclaveError::GenericError("Address must be 32 bytes".to_string()))?;
-
-    // TODO: Verify wallet signature to prove ownership
-    // For now, we'll trust the request
-
     // Build payload
     let payload = LinkAddressPayload {
-        handle: req.handle.clone().into_bytes(),
-        address: addr_bytes,
+        handle: handle.as_bytes().to_vec(),
+        address,
+        label: label.as_bytes().to_vec(),
     };
 
     // Sign payload
-    let signed = to_signed_response(
+    let signature = sign_payload(
         &state.eph_kp,
-        payload.clone(),
+        &payload,
         current_timestamp,
         IntentScope::LinkWallet, // LINK_ADDRESS_INTENT = 1
+        version,
     );
 
-    let response = LinkAddressResponse {
+    Ok(LinkAddressResponse {
         payload,
         intent: LINK_ADDRESS_INTENT,
+        payload_version: version as u8,
         timestamp_ms: current_timestamp,
-        signature: signed.signature,
+        signature,
+    })
+}
+
+/// Unlink a Sui wallet address from RAM wallet
+///
+/// The remaining linked addresses keep their access.
+pub async fn process_unlink_address(
+    State(state): State<Arc<AppState>>,
+    ValidatedJson(request): ValidatedJson<ProcessDataRequest<UnlinkAddressRequest>>,
+) -> Result<Json<UnlinkAddressResponse>, EnclaveError> {
+    let req = &request.payload;
+    let version = state.payload_versions.select(request.payload_version)?;
+
+    info!(
+        "RAM: Unlinking address for handle='{}' -> {}",
+        req.handle, req.wallet_address
+    );
+
+    let current_timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_err(|e| EnclaveError::GenericError(format!("Failed to get timestamp: {}", e)))?
+        .as_millis() as u64;
+
+    let payload = UnlinkAddressPayload {
+        handle: req.handle.clone().into_bytes(),
+        address: parse_sui_address(&req.wallet_address)?,
     };
 
-    info!("RAM: Address linked for handle='{}'", req.handle);
+    // Sign payload
+    let signature = sign_payload(
+        &state.eph_kp,
+        &payload,
+        current_timestamp,
+        IntentScope::UnlinkWallet, // UNLINK_ADDRESS_INTENT = 5
+        version,
+    );
+
+    let response = UnlinkAddressResponse {
+        payload,
+        intent: UNLINK_ADDRESS_INTENT,
+        payload_version: version as u8,
+        timestamp_ms: current_timestamp,
+        signature,
+    };
+
+    info!("RAM: Address unlinked for handle='{}'", req.handle);
 
     Ok(Json(response))
 }
 
+/// Parse a 0x-prefixed (or bare) hex Sui address into 32 bytes
+pub fn parse_sui_address(address: &str) -> Result<[u8; 32], EnclaveError> {
+    let addr_hex = address.strip_prefix("0x").unwrap_or(address);
+    hex::decode(addr_hex)
+        .map_err(|e| EnclaveError::GenericError(format!("Invalid address: {}", e)))?
+        .try_into()
+        .map_err(|_| EnclaveError::GenericError("Address must be 32 bytes".to_string()))
+}
+
 /// BioGuard voice authentication endpoint
 /// 
 /// This is the core security feature of RAM:
@@ -124,24 +212,53 @@ pub async fn process_link_address(
 /// 
 /// Request: handle, audio_base64, expected_amount
 /// Response: signed BioAuthPayload + human-readable data
+///
+/// When the async job queue is enabled, returns `202 Accepted` with a job ID
+/// instead; poll `GET /bio_auth/:job_id` for the signed response.
 pub async fn process_bio_auth(
     State(state): State<Arc<AppState>>,
-    Json(request): Json<ProcessDataRequest<BioAuthRequest>>,
-) -> Result<Json<BioAuthResponse>, EnclaveError> {
-    let req = &request.payload;
-    let coin_type = req.coin_type.as_deref().unwrap_or("SUI");
-    
-    // Convert expected amount to human-readable format for analysis
-    let decimals = match coin_type.to_uppercase().as_str() {
-        "SUI" => 9u32,
-        "USDC" | "USDT" => 6,
-        _ => 9,
-    };
-    let expected_human = req.expected_amount as f64 / (10_u64.pow(decimals)) as f64;
+    ValidatedJson(request): ValidatedJson<ProcessDataRequest<BioAuthRequest>>,
+) -> Result<Response, EnclaveError> {
+    let version = state.payload_versions.select(request.payload_version)?;
+    if state.bioauth_jobs.is_enabled() {
+        let job_id = state.bioauth_jobs.submit(request.payload, version)?;
+        return Ok((
+            StatusCode::ACCEPTED,
+            Json(json!({ "job_id": job_id, "status": "queued" })),
+        )
+            .into_response());
+    }
+
+    let response = run_bio_auth(&state, &request.payload, version, &Progress::default()).await?;
+    Ok(Json(response).into_response())
+}
+
+/// Analyze a bio-auth request and produce the signed, blind response
+///
+/// Shared by the synchronous endpoint, the async job workers and the SSE
+/// stream, which follows along through `progress`. Fails with `Conflict` if
+/// another bio-auth for the same handle is still running.
+pub(crate) async fn run_bio_auth(
+    state: &Arc<AppState>,
+    req: &BioAuthRequest,
+    version: PayloadVersion,
+    progress: &Progress,
+) -> Result<BioAuthResponse, EnclaveError> {
+    let _handle_lock = state.bioauth_locks.try_acquire(&req.handle).inspect_err(|_| {
+        warn!("RAM BioAuth: concurrent request rejected for handle '{}'", req.handle);
+    })?;
+
+    // Symbol and decimals come from the coin registry, so any coin type can be confirmed
+    let coin = state.coin_registry.resolve(&CoinType::parse_or_sui(req.coin_type.as_deref())?).await?;
+    let expected_amount = RawAmount(req.expected_amount);
+    // The handle's preferences fill in what the request leaves out
+    let preferences = state.user_preferences.get(&req.handle);
+    let locale_tag = req.locale.as_deref().or(preferences.locale.as_deref());
+    let locale = NumberLocale::from_tag(locale_tag);
     
     info!(
         "RAM BioAuth: handle='{}', expected_amount={} {} ({} raw)",
-        req.handle, expected_human, coin_type, req.expected_amount
+        req.handle, coin.format(expected_amount), coin.symbol, expected_amount
     );
 
     let current_timestamp = std::time::SystemTime::now()
@@ -149,6 +266,17 @@ pub async fn process_bio_auth(
         .map_err(|e| EnclaveError::GenericError(format!("Failed to get timestamp: {}", e)))?
         .as_millis() as u64;
 
+    // Repeated failures must wait out a cooldown before the next attempt
+    let failed_attempts = state.bioauth_attempts.check(&req.handle, current_timestamp).inspect_err(|_| {
+        warn!("RAM BioAuth: attempt during failure cooldown rejected for handle '{}'", req.handle);
+    })?;
+
+    // A clip that already authorized a transfer must not authorize another
+    let fingerprint = state
+        .bioauth_replays
+        .check(&req.handle, &req.audio_base64, current_timestamp)
+        .inspect_err(|_| warn!("RAM BioAuth: replayed clip rejected for handle '{}'", req.handle))?;
+
     // Real audio analysis with stress detection
     let openrouter_key = if state.openrouter_api_key.is_empty() { 
         None 
@@ -162,21 +290,111 @@ pub async fn process_bio_auth(
         Some(state.hume_api_key.as_str())
     };
 
-    let analysis = audio::analyze_audio(
+    // The phrase /bio_auth/challenge issues for this confirmation, for the prompt
+    let lang = locale_tag.and_then(Lang::from_tag).unwrap_or_else(current_lang);
+    let phrase = challenge_phrase(
+        expected_amount,
+        &coin,
+        req.destination_hint.as_deref(),
+        preferences.challenge_length(),
+        lang,
+    );
+
+    // Score stress against the user's own calm voice once they've enrolled one
+    let baseline = state.voice_baselines.get(&req.handle);
+
+    let upstreams = audio::Upstreams {
+        openrouter_api_key: openrouter_key,
+        hume_api_key: hume_key,
+        costs: &state.upstream_costs,
+    };
+    // A clip analyzed moments ago (e.g. a preview) isn't paid for twice
+    let cache_key = AnalysisKey::new(
+        &req.handle,
         &req.audio_base64,
-        openrouter_key,
-        hume_key,
-        Some(expected_human),
-        coin_type,
-    ).await?;
+        expected_amount,
+        &coin.coin_type,
+        locale_tag,
+        baseline.as_ref().map_or(0, |baseline| baseline.samples),
+    );
+    let analysis = match state.analysis_cache.get(&cache_key, req.skip_analysis_cache) {
+        Some(analysis) => {
+            info!("RAM BioAuth: reusing the analysis of the same clip for '{}'", req.handle);
+            progress.report(BioAuthStage::Decoded);
+            progress.report(BioAuthStage::Transcribed);
+            analysis
+        }
+        None => {
+            // Borderline stress scores get a second pass before anything is decided
+            let analysis = gray_zone::analyze_audio(
+                &state.bioauth_gray_zone,
+                &req.handle,
+                &req.audio_base64,
+                upstreams,
+                audio::Expected {
+                    amount: Some(expected_amount),
+                    coin: &coin,
+                    locale: locale_tag,
+                    phrase: Some(&phrase),
+                },
+                baseline.as_ref(),
+                progress,
+            ).await?;
+            state.analysis_cache.insert(cache_key, &analysis);
+            analysis
+        }
+    };
+
+    // Covert panic phrase: an intentional duress signal, whatever the stress.
+    // It's cut from the transcript, which is signed and ends up on-chain.
+    let stripped = state
+        .voice_baselines
+        .panic_phrase(&req.handle)
+        .and_then(|phrase| phrase.strip(&analysis.transcript));
+    let panic = stripped.is_some();
+
+    // Nothing is signed on findings the model isn't sure of: the user records
+    // again rather than risk a false lock or approval
+    if !panic {
+        let duress = state.bioauth_shadow.detects_duress(preferences.adjust_stress(analysis.stress_level));
+        confidence::current().check(&analysis.confidence, duress).inspect_err(|_| {
+            warn!("RAM BioAuth: inconclusive analysis for '{}' ({:?}), nothing signed", req.handle, analysis.confidence);
+        })?;
+    }
 
     // Extract analysis results
-    let transcript = analysis.transcript;
+    let transcript = stripped.unwrap_or(analysis.transcript);
     let stress_level = analysis.stress_level;
     let amount_verified = analysis.amount_verified;
 
-    // Determine result based on analysis
-    let result = if audio::is_under_duress(stress_level) {
+    // Withdrawals to an external address: the user must also speak the
+    // destination's last characters so a swapped address can't be signed off
+    let destination_verified = match &req.destination_hint {
+        Some(destination) => {
+            let verified = audio::verify_destination_suffix(&transcript, destination, preferences.challenge_length());
+            info!(
+                "RAM BioAuth: destination suffix {:?} {}",
+                audio::destination_suffix(destination, preferences.challenge_length()),
+                if verified { "confirmed" } else { "NOT confirmed" }
+            );
+            verified
+        }
+        None => false,
+    };
+
+    // Determine result based on analysis, on the stress score shifted by the
+    // handle's chosen sensitivity
+    let stress_duress = state.bioauth_shadow.detects_duress(preferences.adjust_stress(stress_level));
+    let shadow = state.bioauth_shadow.is_enabled();
+    if shadow {
+        state.bioauth_shadow.record(stress_duress);
+    }
+    let duress = panic || stress_duress;
+    let result = if panic {
+        // Deliberate signal, so shadow mode (which trials stress scoring) doesn't apply
+        info!("RAM BioAuth: ⚠️ DURESS DETECTED for '{}' (panic phrase)", req.handle);
+        BioAuthResult::Duress
+    } else if stress_duress && !shadow {
         // DURESS DETECTED - This will lock the wallet for 24 hours!
         info!(
             "RAM BioAuth: ⚠️ DURESS DETECTED for '{}' (stress_level={})",
@@ -189,55 +407,121 @@ pub async fn process_bio_auth(
     } else {
         // Amount doesn't match or couldn't be parsed
         info!(
-            "RAM BioAuth: ✗ INVALID AMOUNT (expected={:.4} {}, detected={:?})",
-            expected_human, coin_type, analysis.amount
+            "RAM BioAuth: ✗ INVALID AMOUNT (expected={} {}, detected={})",
+            coin.format(expected_amount),
+            coin.symbol,
+            analysis.amount.map_or("none".to_string(), |amount| coin.format(amount))
         );
         BioAuthResult::InvalidAmount
     };
 
+    progress.report(BioAuthStage::StressScored);
+
+    // Shadow mode: the verdict is kept for evaluation but never signed
+    let detected_result = if duress { BioAuthResult::Duress } else { result };
+    if shadow && stress_duress && !panic {
+        info!(
+            "RAM BioAuth: [shadow] duress detected for '{}' (stress_level={}), signing {} instead",
+            req.handle, stress_level, result.as_str()
+        );
+    }
+
+    // Decoy mode: duress is signed as OK with a sealed decoy flag, so the contract
+    // locks the wallet while the coercer's screen shows the transfer going through
+    let decoy = result == BioAuthResult::Duress
+        && state.bioauth_decoy
+        && feature_flags::current().is_enabled(feature_flags::DURESS_DECOY, Some(&normalize_handle(&req.handle)), true);
+    let signed_result = if decoy { BioAuthResult::Ok } else { result };
+    if decoy {
+        info!("RAM BioAuth: [decoy] signing duress for '{}' as an OK-looking decoy", req.handle);
+    }
+
+    state.bioauth_attempts.record(&req.handle, result, current_timestamp);
+    if let (BioAuthResult::Ok, Some(fingerprint)) = (result, fingerprint) {
+        state.bioauth_replays.record(&req.handle, fingerprint, current_timestamp);
+    }
+
     // Build payload for Move contract
     let payload = BioAuthPayload {
         handle: req.handle.clone().into_bytes(),
         amount: req.expected_amount,
-        result: result as u8,
+        result: signed_result as u8,
         transcript: transcript.clone().into_bytes(),
+        destination_verified,
+        method: BioAuthMethod::Voice as u8,
+        seal: decoy::seal(decoy),
+        failed_attempts,
     };
 
     // Sign with BioAuth intent scope
-    let signed = to_signed_response(
+    let signature = sign_payload(
         &state.eph_kp,
-        payload.clone(),
+        &payload,
         current_timestamp,
         IntentScope::TransferNft, // BIOAUTH_INTENT = 3 (RAM reuses TransferNft slot)
+        version,
     );
 
+    // Keep human-readable diagnostics for the privileged backend channel only
+    let request_id = uuid::Uuid::new_v4().to_string();
+    let analyzer_disagreement = disagreement::report(&req.handle, &request_id, &analysis.stress_scores);
+    state.bioauth_diagnostics.insert(
+        request_id.clone(),
+        BioAuthData {
+            handle: req.handle.clone(),
+            amount: req.expected_amount,
+            amount_display: locale.format_amount(expected_amount, &coin),
+            result: result.as_str().to_string(),
+            transcript,
+            stress_level,
+            locked: result == BioAuthResult::Duress,
+            destination_verified,
+            shadow_mode: shadow,
+            detected_result: detected_result.as_str().to_string(),
+            method: BioAuthMethod::Voice.as_str().to_string(),
+            decoy,
+            panic_phrase: panic,
+            analyzer_disagreement,
+        },
+    );
+
+    // Only a bio-auth that was OK in every respect lets the handle change its preferences
+    if detected_result == BioAuthResult::Ok {
+        state.user_preferences.grant(&request_id, &req.handle);
+    }
+
     // Return BLIND response - frontend cannot see stress_level or result!
     // Frontend will learn the result ONLY from blockchain events after submission.
     let response = BioAuthResponse {
         payload,
         intent: BIOAUTH_INTENT,
+        payload_version: version as u8,
         timestamp_ms: current_timestamp,
-        signature: signed.signature,
+        signature,
+        request_id: request_id.clone(),
         // NO data field - prevents frontend bypass!
     };
 
     info!(
-        "RAM BioAuth response (BLIND): handle='{}', result={}, stress={} (frontend cannot see this)",
-        req.handle, result.as_str(), stress_level
+        "RAM BioAuth response (BLIND): handle='{}', result={}, stress={}, request_id={} (frontend cannot see this)",
+        req.handle, result.as_str(), stress_level, request_id
     );
 
-    Ok(Json(response))
+    Ok(response)
 }
 
 /// Hex encoding/decoding utilities
 mod hex {
     pub fn decode(s: &str) -> Result<Vec<u8>, String> {
-        if s.len() % 2 != 0 {
+        if !s.len().is_multiple_of(2) {
             return Err("Hex string must have even length".to_string());
         }
-        (0..s.len())
-            .step_by(2)
-            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).map_err(|e| e.to_string()))
+        // Byte-wise: slicing the str could split a multi-byte char, and
+        // from_str_radix would accept a leading '+'
+        let digit = |b: u8| (b as char).to_digit(16).ok_or_else(|| "Invalid hex digit".to_string());
+        s.as_bytes()
+            .chunks(2)
+            .map(|pair| Ok((digit(pair[0])? << 4 | digit(pair[1])?) as u8))
             .collect()
     }
 }
@@ -248,14 +532,17 @@ mod hex {
 /// for the `transfer_with_signature` Move function.
 pub async fn process_transfer(
     State(state): State<Arc<AppState>>,
-    Json(request): Json<ProcessDataRequest<TransferRequest>>,
+    ValidatedJson(request): ValidatedJson<ProcessDataRequest<TransferRequest>>,
 ) -> Result<Json<TransferResponse>, EnclaveError> {
     let req = &request.payload;
+    let version = state.payload_versions.select(request.payload_version)?;
 
+    let coin_type = CoinType::parse(&req.coin_type)?;
     info!(
         "RAM Transfer: from='{}' -> to='{}', amount={}, coin_type='{}'",
-        req.from_handle, req.to_handle, req.amount, req.coin_type
+        req.from_handle, req.to_handle, req.amount, coin_type
     );
+    state.screening.check("transfer", &req.to_handle).await?;
 
     let current_timestamp = std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
@@ -267,22 +554,42 @@ pub async fn process_transfer(
         from_handle: req.from_handle.clone().into_bytes(),
         to_handle: req.to_handle.clone().into_bytes(),
         amount: req.amount,
-        coin_type: req.coin_type.clone().into_bytes(),
+        // Must equal type_name::get<T>() on-chain
+        coin_type: coin_type.type_name().into_bytes(),
+        memo: req.memo.clone().unwrap_or_default().into_bytes(),
     };
 
     // Sign with TRANSFER_INTENT = 2
-    let signed = to_signed_response(
+    let signature = sign_payload(
         &state.eph_kp,
-        payload.clone(),
+        &payload,
         current_timestamp,
         IntentScope::TransferCoin, // TRANSFER_INTENT = 2
+        version,
     );
 
+    // Nothing is answered for a call that would abort on-chain
+    let simulation = match &req.simulate {
+        Some(wallets) => {
+            let transfer = SignedTransfer {
+                payload: &payload,
+                coin_type: &coin_type,
+                payload_version: version as u8,
+                timestamp_ms: current_timestamp,
+                signature: &signature,
+            };
+            state.transfer_simulator.simulate(wallets, transfer).await?
+        }
+        None => None,
+    };
+
     let response = TransferResponse {
         payload,
         intent: TRANSFER_INTENT,
+        payload_version: version as u8,
         timestamp_ms: current_timestamp,
-        signature: signed.signature,
+        signature,
+        simulation,
     };
 
     info!(
@@ -299,14 +606,18 @@ pub async fn process_transfer(
 /// for the `withdraw` Move function.
 pub async fn process_withdraw(
     State(state): State<Arc<AppState>>,
-    Json(request): Json<ProcessDataRequest<WithdrawRequest>>,
+    ValidatedJson(request): ValidatedJson<ProcessDataRequest<WithdrawRequest>>,
 ) -> Result<Json<WithdrawResponse>, EnclaveError> {
     let req = &request.payload;
+    let version = state.payload_versions.select(request.payload_version)?;
 
+    let coin_type = CoinType::parse(&req.coin_type)?;
     info!(
         "RAM Withdraw: handle='{}', amount={}, coin_type='{}'",
-        req.handle, req.amount, req.coin_type
+        req.handle, req.amount, coin_type
     );
+    // The coin goes to a linked address the enclave doesn't see; screen the wallet
+    state.screening.check("withdraw", &req.handle).await?;
 
     let current_timestamp = std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
@@ -317,22 +628,24 @@ pub async fn process_withdraw(
     let payload = WithdrawPayload {
         handle: req.handle.clone().into_bytes(),
         amount: req.amount,
-        coin_type: req.coin_type.clone().into_bytes(),
+        coin_type: coin_type.type_name().into_bytes(),
     };
 
     // Sign with WITHDRAW_INTENT = 4
-    let signed = to_signed_response(
+    let signature = sign_payload(
         &state.eph_kp,
-        payload.clone(),
+        &payload,
         current_timestamp,
         IntentScope::UpdateHandle, // WITHDRAW_INTENT = 4 (RAM reuses UpdateHandle slot)
+        version,
     );
 
     let response = WithdrawResponse {
         payload,
         intent: WITHDRAW_INTENT,
+        payload_version: version as u8,
         timestamp_ms: current_timestamp,
-        signature: signed.signature,
+        signature,
     };
 
     info!(
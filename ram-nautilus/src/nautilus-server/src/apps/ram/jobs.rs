@@ -0,0 +1,279 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! Async BioAuth job queue
+//!
+//! Analyzing a clip blocks for several seconds (LLM + Hume + DSP). In queued
+//! mode `POST /bio_auth` only enqueues the request and returns a job ID; a
+//! bounded pool of workers runs the analysis and clients poll
+//! `GET /bio_auth/:job_id` for the signed response. When the queue is full new
+//! submissions are rejected with 429 so callers back off instead of piling up.
+
+use crate::AppState;
+use crate::EnclaveError;
+use axum::extract::{Path, State};
+use axum::Json;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+use super::handlers::run_bio_auth;
+use super::stream::Progress;
+use super::types::{BioAuthRequest, BioAuthResponse};
+use super::versions::PayloadVersion;
+
+/// Default number of jobs that may wait in the queue
+pub const DEFAULT_QUEUE_CAPACITY: usize = 32;
+/// Default number of concurrent analysis workers
+pub const DEFAULT_WORKERS: usize = 2;
+/// Default retention for finished job results (10 minutes)
+pub const DEFAULT_JOB_TTL_SECS: u64 = 600;
+
+type Job = (String, BioAuthRequest, PayloadVersion);
+
+/// Lifecycle of a queued bio-auth job
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Done { response: BioAuthResponse },
+    Failed { error: String },
+}
+
+/// Poll response for `GET /bio_auth/:job_id`
+#[derive(Debug, Clone, Serialize)]
+pub struct JobView {
+    pub job_id: String,
+    #[serde(flatten)]
+    pub status: JobStatus,
+}
+
+/// Queue depth and throughput counters for `GET /bio_auth/queue`
+#[derive(Debug, Clone, Serialize)]
+pub struct QueueMetrics {
+    pub enabled: bool,
+    pub capacity: usize,
+    pub depth: usize,
+    pub in_flight: usize,
+    pub completed: u64,
+    pub failed: u64,
+    pub rejected: u64,
+    /// Uploads refused by load shedding before reaching the queue
+    pub shed: u64,
+}
+
+/// Bounded bio-auth job queue with result store
+pub struct JobQueue {
+    enabled: bool,
+    ttl: Duration,
+    sender: mpsc::Sender<Job>,
+    receiver: Mutex<Option<mpsc::Receiver<Job>>>,
+    jobs: Mutex<HashMap<String, (Instant, JobStatus)>>,
+    in_flight: AtomicUsize,
+    completed: AtomicU64,
+    failed: AtomicU64,
+    rejected: AtomicU64,
+    shed: AtomicU64,
+}
+
+impl JobQueue {
+    pub fn new(enabled: bool, capacity: usize, ttl: Duration) -> Self {
+        let (sender, receiver) = mpsc::channel(capacity.max(1));
+        Self {
+            enabled,
+            ttl,
+            sender,
+            receiver: Mutex::new(Some(receiver)),
+            jobs: Mutex::new(HashMap::new()),
+            in_flight: AtomicUsize::new(0),
+            completed: AtomicU64::new(0),
+            failed: AtomicU64::new(0),
+            rejected: AtomicU64::new(0),
+            shed: AtomicU64::new(0),
+        }
+    }
+
+    /// Whether `POST /bio_auth` should enqueue instead of analyzing inline
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Enqueue a request to be signed in `version`, returning its job ID, or
+    /// reject if the queue is full
+    pub fn submit(&self, request: BioAuthRequest, version: PayloadVersion) -> Result<String, EnclaveError> {
+        let job_id = uuid::Uuid::new_v4().to_string();
+        // Recorded first: a worker may pick the job up, and move it on, before try_send returns
+        self.set_status(&job_id, JobStatus::Queued);
+        if self.sender.try_send((job_id.clone(), request, version)).is_err() {
+            self.jobs.lock().unwrap().remove(&job_id);
+            self.rejected.fetch_add(1, Ordering::Relaxed);
+            warn!("RAM BioAuth: queue full, rejecting job");
+            return Err(EnclaveError::TooManyRequests(
+                "BioAuth queue is full, retry later".to_string(),
+            ));
+        }
+        Ok(job_id)
+    }
+
+    /// Count a request shed before it was analyzed or queued
+    pub fn record_shed(&self) {
+        self.shed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Current status of a job if it is still within the TTL
+    pub fn status(&self, job_id: &str) -> Option<JobStatus> {
+        let jobs = self.jobs.lock().unwrap();
+        jobs.get(job_id)
+            .filter(|(updated_at, _)| updated_at.elapsed() < self.ttl)
+            .map(|(_, status)| status.clone())
+    }
+
+    pub fn metrics(&self) -> QueueMetrics {
+        let capacity = self.sender.max_capacity();
+        QueueMetrics {
+            enabled: self.enabled,
+            capacity,
+            depth: capacity - self.sender.capacity(),
+            in_flight: self.in_flight.load(Ordering::Relaxed),
+            completed: self.completed.load(Ordering::Relaxed),
+            failed: self.failed.load(Ordering::Relaxed),
+            rejected: self.rejected.load(Ordering::Relaxed),
+            shed: self.shed.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Record a status change, pruning expired entries
+    fn set_status(&self, job_id: &str, status: JobStatus) {
+        let now = Instant::now();
+        let mut jobs = self.jobs.lock().unwrap();
+        jobs.retain(|_, (updated_at, _)| now.duration_since(*updated_at) < self.ttl);
+        jobs.insert(job_id.to_string(), (now, status));
+    }
+}
+
+impl Default for JobQueue {
+    fn default() -> Self {
+        Self::new(
+            false,
+            DEFAULT_QUEUE_CAPACITY,
+            Duration::from_secs(DEFAULT_JOB_TTL_SECS),
+        )
+    }
+}
+
+/// Spawn `workers` tasks draining the bio-auth queue
+///
+/// No-op if the queue is disabled or workers were already started.
+pub fn start_bio_auth_workers(state: Arc<AppState>, workers: usize) {
+    let queue = &state.bioauth_jobs;
+    if !queue.is_enabled() {
+        return;
+    }
+    let Some(receiver) = queue.receiver.lock().unwrap().take() else {
+        return;
+    };
+    let receiver = Arc::new(tokio::sync::Mutex::new(receiver));
+
+    for worker in 0..workers.max(1) {
+        let state = state.clone();
+        let receiver = receiver.clone();
+        tokio::spawn(async move {
+            loop {
+                let Some((job_id, request, version)) = receiver.lock().await.recv().await else {
+                    break;
+                };
+                let queue = &state.bioauth_jobs;
+                queue.in_flight.fetch_add(1, Ordering::Relaxed);
+                queue.set_status(&job_id, JobStatus::Running);
+
+                let status = match run_bio_auth(&state, &request, version, &Progress::default()).await {
+                    Ok(response) => {
+                        queue.completed.fetch_add(1, Ordering::Relaxed);
+                        JobStatus::Done { response }
+                    }
+                    Err(e) => {
+                        queue.failed.fetch_add(1, Ordering::Relaxed);
+                        warn!("RAM BioAuth: job {} failed on worker {}: {}", job_id, worker, e);
+                        JobStatus::Failed { error: e.to_string() }
+                    }
+                };
+                queue.set_status(&job_id, status);
+                queue.in_flight.fetch_sub(1, Ordering::Relaxed);
+            }
+        });
+    }
+
+    info!("RAM BioAuth: started {} queue workers", workers.max(1));
+}
+
+/// Poll a queued bio-auth job
+///
+/// GET /bio_auth/:job_id
+pub async fn get_bio_auth_job(
+    State(state): State<Arc<AppState>>,
+    Path(job_id): Path<String>,
+) -> Result<Json<JobView>, EnclaveError> {
+    let status = state
+        .bioauth_jobs
+        .status(&job_id)
+        .ok_or_else(|| EnclaveError::NotFound(format!("No job '{}'", job_id)))?;
+    Ok(Json(JobView { job_id, status }))
+}
+
+/// Queue depth and throughput metrics
+///
+/// GET /bio_auth/queue
+pub async fn get_bio_auth_queue(State(state): State<Arc<AppState>>) -> Json<QueueMetrics> {
+    Json(state.bioauth_jobs.metrics())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request() -> BioAuthRequest {
+        BioAuthRequest {
+            handle: "alice".to_string(),
+            audio_base64: String::new(),
+            expected_amount: 1,
+            coin_type: None,
+            destination_hint: None,
+            locale: None,
+            skip_analysis_cache: false,
+        }
+    }
+
+    #[test]
+    fn test_submit_tracks_depth_and_status() {
+        let queue = JobQueue::new(true, 2, Duration::from_secs(60));
+        let job_id = queue.submit(request(), PayloadVersion::V1).unwrap();
+        assert!(matches!(queue.status(&job_id), Some(JobStatus::Queued)));
+        let metrics = queue.metrics();
+        assert_eq!(metrics.depth, 1);
+        assert_eq!(metrics.capacity, 2);
+    }
+
+    #[test]
+    fn test_full_queue_rejects() {
+        let queue = JobQueue::new(true, 1, Duration::from_secs(60));
+        queue.submit(request(), PayloadVersion::V1).unwrap();
+        assert!(matches!(
+            queue.submit(request(), PayloadVersion::V1),
+            Err(EnclaveError::TooManyRequests(_))
+        ));
+        assert_eq!(queue.metrics().rejected, 1);
+        // Only the accepted job is tracked
+        assert_eq!(queue.jobs.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_unknown_job() {
+        let queue = JobQueue::default();
+        assert!(queue.status("missing").is_none());
+    }
+}
@@ -0,0 +1,197 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! Locale-aware amount formatting
+//!
+//! A German user reads 1.234,5 where an American reads 1,234.5, and an Indian
+//! user groups by lakh and crore (12,34,567.5). When the LLM is told to expect
+//! "1234.5 SUI" but hears "eintausendzweihundertvierunddreißig Komma fünf",
+//! or a typed "1.234,5" is read as 1.234, an honest confirmation comes back
+//! InvalidAmount. Requests carry the user's BCP 47 locale, and the expected
+//! amount is rendered (and typed amounts parsed) the way that locale writes
+//! numbers. Unknown or missing locales use `1,234.5`.
+
+use super::amount::RawAmount;
+use super::coin::CoinInfo;
+
+/// How a locale groups digits and marks decimals
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NumberLocale {
+    /// 1,234,567.5 (en-US, en-GB, zh, ja, ...)
+    #[default]
+    Western,
+    /// 1.234.567,5 (de, es, it, pt, vi, id, ...)
+    Continental,
+    /// 1 234 567,5 (fr, ru, pl, sv, ...)
+    Spaced,
+    /// 12,34,567.5 (en-IN, hi, bn, ...)
+    Indian,
+}
+
+impl NumberLocale {
+    /// Style for a BCP 47 tag like `de-DE` or `en_IN`
+    pub fn from_tag(tag: Option<&str>) -> Self {
+        let Some(tag) = tag else {
+            return Self::Western;
+        };
+        let tag = tag.trim().to_ascii_lowercase();
+        let mut parts = tag.split(['-', '_']);
+        let language = parts.next().unwrap_or_default();
+        let region = parts.find(|p| p.len() == 2).unwrap_or_default();
+
+        match (language, region) {
+            (_, "in") | ("hi" | "bn" | "mr" | "gu" | "ta" | "te" | "kn" | "ml" | "pa", _) => Self::Indian,
+            // Spanish in the Americas mostly writes 1,234.5
+            ("es", "mx" | "us" | "pr" | "gt" | "do" | "pa" | "hn" | "ni" | "sv") => Self::Western,
+            ("de" | "es" | "it" | "pt" | "nl" | "vi" | "id" | "tr" | "da" | "el" | "ro" | "hr" | "sl" | "sr", _) => {
+                Self::Continental
+            }
+            ("fr" | "ru" | "uk" | "pl" | "cs" | "sk" | "sv" | "nb" | "nn" | "no" | "fi" | "hu" | "bg" | "lt" | "lv"
+            | "et", _) => Self::Spaced,
+            _ => Self::Western,
+        }
+    }
+
+    fn group_separator(self) -> char {
+        match self {
+            Self::Western | Self::Indian => ',',
+            Self::Continental => '.',
+            Self::Spaced => ' ',
+        }
+    }
+
+    fn decimal_separator(self) -> char {
+        match self {
+            Self::Western | Self::Indian => '.',
+            Self::Continental | Self::Spaced => ',',
+        }
+    }
+
+    /// Size of each digit group, most significant first, for a whole part of `len` digits
+    fn group_sizes(self, len: usize) -> Vec<usize> {
+        let (last, rest) = match self {
+            Self::Indian => (3, 2),
+            _ => (3, 3),
+        };
+        if len <= last {
+            return vec![len];
+        }
+        let mut sizes = vec![last];
+        let mut remaining = len - last;
+        while remaining > rest {
+            sizes.push(rest);
+            remaining -= rest;
+        }
+        sizes.push(remaining);
+        sizes.reverse();
+        sizes
+    }
+
+    /// A raw amount as this locale writes it, e.g. `1.234,5`
+    pub fn format(self, raw: RawAmount, decimals: u8) -> String {
+        let text = raw.to_decimal(decimals);
+        let (whole, fraction) = text.split_once('.').unwrap_or((&text, ""));
+
+        let mut grouped = String::new();
+        let mut start = 0;
+        for size in self.group_sizes(whole.len()) {
+            if start > 0 {
+                grouped.push(self.group_separator());
+            }
+            grouped.push_str(&whole[start..start + size]);
+            start += size;
+        }
+        if !fraction.is_empty() {
+            grouped.push(self.decimal_separator());
+            grouped.push_str(fraction);
+        }
+        grouped
+    }
+
+    /// The expected amount with its symbol, as shown to the user and the LLM
+    pub fn format_amount(self, raw: RawAmount, coin: &CoinInfo) -> String {
+        format!("{} {}", self.format(raw, coin.decimals), coin.symbol)
+    }
+
+    /// Text written the way this locale writes numbers, as a raw amount
+    ///
+    /// Group separators are optional but, when present, must sit at this
+    /// locale's group boundaries, so `1.5` is never read as 15 in `de`.
+    pub fn parse(self, text: &str, decimals: u8) -> Option<RawAmount> {
+        let (whole, fraction) = match text.split_once(self.decimal_separator()) {
+            Some((whole, fraction)) => (whole, Some(fraction)),
+            None => (text, None),
+        };
+        let groups: Vec<&str> = whole.split(self.group_separator()).collect();
+        if groups.len() > 1 {
+            let digits: usize = groups.iter().map(|g| g.len()).sum();
+            let sizes: Vec<usize> = groups.iter().map(|g| g.len()).collect();
+            if sizes != self.group_sizes(digits) {
+                return None;
+            }
+        }
+
+        let whole = groups.concat();
+        match fraction {
+            Some(fraction) => RawAmount::parse(&format!("{}.{}", whole, fraction), decimals),
+            None => RawAmount::parse(&whole, decimals),
+        }
+    }
+
+    /// How the prompt explains the expected amount's separators
+    pub fn describe(self) -> &'static str {
+        match self {
+            Self::Western => "',' groups thousands and '.' marks decimals",
+            Self::Continental => "'.' groups thousands and ',' marks decimals",
+            Self::Spaced => "spaces group thousands and ',' marks decimals",
+            Self::Indian => "',' groups lakhs and crores and '.' marks decimals",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_tag() {
+        assert_eq!(NumberLocale::from_tag(None), NumberLocale::Western);
+        assert_eq!(NumberLocale::from_tag(Some("en-US")), NumberLocale::Western);
+        assert_eq!(NumberLocale::from_tag(Some("de-DE")), NumberLocale::Continental);
+        assert_eq!(NumberLocale::from_tag(Some("vi")), NumberLocale::Continental);
+        assert_eq!(NumberLocale::from_tag(Some("es_MX")), NumberLocale::Western);
+        assert_eq!(NumberLocale::from_tag(Some("fr-CA")), NumberLocale::Spaced);
+        assert_eq!(NumberLocale::from_tag(Some("en-IN")), NumberLocale::Indian);
+        assert_eq!(NumberLocale::from_tag(Some("hi")), NumberLocale::Indian);
+        assert_eq!(NumberLocale::from_tag(Some("zh-Hant-TW")), NumberLocale::Western);
+        assert_eq!(NumberLocale::from_tag(Some("???")), NumberLocale::Western);
+    }
+
+    #[test]
+    fn test_format() {
+        let raw = RawAmount(1_234_567_500_000_000);
+        assert_eq!(NumberLocale::Western.format(raw, 9), "1,234,567.5");
+        assert_eq!(NumberLocale::Continental.format(raw, 9), "1.234.567,5");
+        assert_eq!(NumberLocale::Spaced.format(raw, 9), "1 234 567,5");
+        assert_eq!(NumberLocale::Indian.format(raw, 9), "12,34,567.5");
+        assert_eq!(NumberLocale::Indian.format(RawAmount(100_000_000_000_000), 9), "1,00,000");
+        assert_eq!(NumberLocale::Continental.format(RawAmount(5_000_000_000), 9), "5");
+        assert_eq!(NumberLocale::Continental.format(RawAmount(2_500_000), 6), "2,5");
+        assert_eq!(NumberLocale::Continental.format_amount(RawAmount(2_500_000_000), &CoinInfo::sui()), "2,5 SUI");
+    }
+
+    #[test]
+    fn test_parse_round_trips_and_rejects_misgrouping() {
+        for locale in [NumberLocale::Western, NumberLocale::Continental, NumberLocale::Spaced, NumberLocale::Indian] {
+            for raw in [0, 1, 2_500_000_000, 1_234_567_500_000_000, u64::MAX] {
+                let text = locale.format(RawAmount(raw), 9);
+                assert_eq!(locale.parse(&text, 9), Some(RawAmount(raw)), "{:?} {}", locale, text);
+            }
+        }
+        assert_eq!(NumberLocale::Continental.parse("1234,5", 9), Some(RawAmount(1_234_500_000_000)));
+        assert_eq!(NumberLocale::Continental.parse("1.5", 9), None);
+        assert_eq!(NumberLocale::Western.parse("1,00,000", 9), None);
+        assert_eq!(NumberLocale::Indian.parse("1,00,000", 0), Some(RawAmount(100_000)));
+        assert_eq!(NumberLocale::Indian.parse("100,000", 0), None);
+    }
+}
@@ -0,0 +1,101 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! Per-handle bio-auth serialization
+//!
+//! Two bio-auths for the same handle racing through analysis could both come
+//! back signed, letting a coerced user's duress result be raced by a calm one.
+//! Each handle gets its own async lock; a second request that arrives while
+//! one is in flight fails fast with `Conflict` instead of waiting.
+
+use crate::EnclaveError;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::OwnedMutexGuard;
+
+use super::validation::normalize_handle;
+
+/// Keyed async lock map, one lock per normalized handle
+#[derive(Default)]
+pub struct HandleLocks {
+    locks: Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+}
+
+impl HandleLocks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Take the lock for `handle`, or fail with `Conflict` if it is already held
+    ///
+    /// The lock is released when the returned guard is dropped.
+    pub fn try_acquire(&self, handle: &str) -> Result<OwnedMutexGuard<()>, EnclaveError> {
+        let lock = {
+            let mut locks = self.locks.lock().unwrap();
+            // Drop entries nobody holds so the map doesn't grow with every handle seen
+            locks.retain(|_, lock| Arc::strong_count(lock) > 1);
+            locks.entry(normalize_handle(handle)).or_default().clone()
+        };
+
+        lock.try_lock_owned().map_err(|_| {
+            EnclaveError::Conflict(format!(
+                "A bio-auth for handle '{}' is already in progress",
+                handle
+            ))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_handle_conflicts() {
+        let locks = HandleLocks::new();
+        let guard = locks.try_acquire("alice").unwrap();
+        assert!(matches!(
+            locks.try_acquire("alice"),
+            Err(EnclaveError::Conflict(_))
+        ));
+        // Other handles are unaffected
+        assert!(locks.try_acquire("bob").is_ok());
+
+        drop(guard);
+        assert!(locks.try_acquire("alice").is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_acquire_only_one_wins() {
+        let locks = Arc::new(HandleLocks::new());
+        let barrier = Arc::new(tokio::sync::Barrier::new(8));
+        let tasks: Vec<_> = (0..8)
+            .map(|_| {
+                let locks = locks.clone();
+                let barrier = barrier.clone();
+                tokio::spawn(async move {
+                    let guard = locks.try_acquire("alice");
+                    // Hold the lock until every task has attempted
+                    barrier.wait().await;
+                    guard.is_ok()
+                })
+            })
+            .collect();
+
+        let mut winners = 0;
+        for task in tasks {
+            if task.await.unwrap() {
+                winners += 1;
+            }
+        }
+        assert_eq!(winners, 1);
+    }
+
+    #[test]
+    fn test_released_entries_are_pruned() {
+        let locks = HandleLocks::new();
+        drop(locks.try_acquire("alice").unwrap());
+        let _bob = locks.try_acquire("bob").unwrap();
+        assert_eq!(locks.locks.lock().unwrap().len(), 1);
+    }
+}
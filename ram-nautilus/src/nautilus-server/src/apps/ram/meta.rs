@@ -0,0 +1,188 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! Machine-readable intent and result code table
+//!
+//! `GET /meta/intents` describes every payload the enclave signs: its intent
+//! code and Move constant, the routes that sign it, its BCS field layout and
+//! a JSON Schema of the payload as responses carry it, plus what each
+//! payload layout version and bio-auth result and method code means, and the
+//! chain signed for. The layouts and schemas are traced from the Rust types
+//! (see `abi`), so the table can't drift from what is actually signed;
+//! integrators and the Move tooling read it instead of copying constants by
+//! hand.
+
+use crate::EnclaveError;
+use axum::Json;
+use serde::Serialize;
+use serde_json::Value;
+
+use super::abi::{rust_payloads, PayloadAbi};
+use super::chain::{self, ChainInfo};
+use super::types::{BioAuthMethod, BioAuthResult};
+use super::versions::PayloadVersion;
+
+/// Routes answering with each intent's signed payload
+const INTENT_ROUTES: &[(&str, &[&str])] = &[
+    ("CREATE_WALLET_INTENT", &["/create_wallet"]),
+    ("LINK_ADDRESS_INTENT", &["/link_address", "/link_zklogin"]),
+    ("TRANSFER_INTENT", &["/transfer"]),
+    ("BIOAUTH_INTENT", &["/bio_auth", "/bio_auth/stream", "/typed_auth"]),
+    ("WITHDRAW_INTENT", &["/withdraw"]),
+    ("UNLINK_ADDRESS_INTENT", &["/unlink_address"]),
+    ("SET_LIMIT_INTENT", &["/set_limit"]),
+    ("FREEZE_INTENT", &["/freeze_wallet"]),
+    ("UNFREEZE_INTENT", &["/request_unfreeze"]),
+    ("BENEFICIARY_INTENT", &["/set_beneficiary"]),
+    ("DEPOSIT_PROOF_INTENT", &["/prove_deposit"]),
+];
+
+/// Payload layout versions, with their core.move constants
+const PAYLOAD_VERSIONS: &[(PayloadVersion, &str, &str)] = &[
+    (PayloadVersion::V1, "PAYLOAD_V1", "The payload's fields in the order listed"),
+    (PayloadVersion::V2, "PAYLOAD_V2", "The version byte, then the payload's fields (core.move `Versioned<P>`)"),
+    (
+        PayloadVersion::V3,
+        "PAYLOAD_V3",
+        "The version byte, the RAM package ID of `chain`, then the payload's fields (core.move `Domain<P>`)",
+    ),
+];
+
+/// `BioAuthPayload.result` codes, with their core.move constants
+const BIOAUTH_RESULTS: &[(BioAuthResult, &str, &str)] = &[
+    (BioAuthResult::Ok, "BIOAUTH_OK", "Confirmation verified, amount matches, no stress: the operation may proceed"),
+    (BioAuthResult::InvalidAmount, "BIOAUTH_INVALID_AMOUNT", "The confirmed amount doesn't match the expected one"),
+    (BioAuthResult::Duress, "BIOAUTH_DURESS", "Stress or a panic phrase was detected: the contract locks the wallet"),
+];
+
+/// `BioAuthPayload.method` codes, with their core.move constants
+const BIOAUTH_METHODS: &[(BioAuthMethod, &str, &str)] = &[
+    (BioAuthMethod::Voice, "BIOAUTH_METHOD_VOICE", "Spoken confirmation with stress analysis"),
+    (BioAuthMethod::Typed, "BIOAUTH_METHOD_TYPED", "Typed confirmation with keystroke-dynamics checks"),
+];
+
+#[derive(Debug, Serialize)]
+pub struct IntentsTable {
+    pub intents: Vec<IntentInfo>,
+    pub payload_versions: Vec<CodeInfo>,
+    pub bio_auth_results: Vec<CodeInfo>,
+    pub bio_auth_methods: Vec<CodeInfo>,
+    /// Network, chain ID and RAM package that v3 payloads are bound to
+    pub chain: ChainInfo,
+}
+
+/// One intent and the payload signed under it
+#[derive(Debug, Serialize)]
+pub struct IntentInfo {
+    pub code: u8,
+    /// Move constant, e.g. `TRANSFER_INTENT`
+    pub name: &'static str,
+    pub payload: &'static str,
+    pub routes: &'static [&'static str],
+    /// BCS field order and Move types
+    pub fields: Vec<FieldInfo>,
+    pub schema: Value,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FieldInfo {
+    pub name: String,
+    pub move_type: String,
+}
+
+/// Meaning of one version, result or method code
+#[derive(Debug, Serialize)]
+pub struct CodeInfo {
+    pub code: u8,
+    pub name: &'static str,
+    pub meaning: &'static str,
+}
+
+/// The table served at `/meta/intents`
+pub fn intents_table() -> Result<IntentsTable, String> {
+    let intents = rust_payloads()?
+        .into_iter()
+        .map(|PayloadAbi { struct_name, intent_const, intent, fields, schema }| IntentInfo {
+            code: intent,
+            name: intent_const,
+            payload: struct_name,
+            routes: INTENT_ROUTES
+                .iter()
+                .find(|(name, _)| *name == intent_const)
+                .map_or(&[], |(_, routes)| *routes),
+            fields: fields
+                .into_iter()
+                .map(|(name, move_type)| FieldInfo { name, move_type })
+                .collect(),
+            schema,
+        })
+        .collect();
+    Ok(IntentsTable {
+        intents,
+        payload_versions: PAYLOAD_VERSIONS
+            .iter()
+            .map(|(version, name, meaning)| CodeInfo { code: *version as u8, name, meaning })
+            .collect(),
+        bio_auth_results: BIOAUTH_RESULTS
+            .iter()
+            .map(|(result, name, meaning)| CodeInfo { code: *result as u8, name, meaning })
+            .collect(),
+        bio_auth_methods: BIOAUTH_METHODS
+            .iter()
+            .map(|(method, name, meaning)| CodeInfo { code: *method as u8, name, meaning })
+            .collect(),
+        chain: chain::current().info(),
+    })
+}
+
+/// Intent codes, payload schemas and result code meanings
+///
+/// GET /meta/intents (public)
+pub async fn get_intents() -> Result<Json<IntentsTable>, EnclaveError> {
+    intents_table()
+        .map(Json)
+        .map_err(|e| EnclaveError::GenericError(format!("Failed to describe intents: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apps::ram::abi::move_u8_constants;
+    use crate::apps::ram::types::TransferPayload;
+
+    #[test]
+    fn test_table_matches_move_constants() {
+        let constants = move_u8_constants(include_str!("../../../../../move/ram/sources/core.move"));
+        let table = intents_table().unwrap();
+        assert_eq!(table.intents.len(), INTENT_ROUTES.len());
+        for intent in &table.intents {
+            assert_eq!(constants.get(intent.name), Some(&intent.code), "{}", intent.name);
+            assert!(!intent.routes.is_empty(), "{} has no routes", intent.name);
+        }
+        let codes = table.payload_versions.iter().chain(&table.bio_auth_results).chain(&table.bio_auth_methods);
+        for code in codes {
+            assert_eq!(constants.get(code.name), Some(&code.code), "{}", code.name);
+        }
+    }
+
+    #[test]
+    fn test_schema_describes_serialized_payload() {
+        let table = intents_table().unwrap();
+        let transfer = table.intents.iter().find(|i| i.payload == "TransferPayload").unwrap();
+        let payload = serde_json::to_value(TransferPayload {
+            from_handle: b"alice".to_vec(),
+            to_handle: b"bob".to_vec(),
+            amount: 5,
+            coin_type: Vec::new(),
+            memo: Vec::new(),
+        })
+        .unwrap();
+
+        let properties = transfer.schema["properties"].as_object().unwrap();
+        let keys: Vec<&String> = payload.as_object().unwrap().keys().collect();
+        assert_eq!(properties.keys().collect::<Vec<_>>(), keys);
+        assert_eq!(properties["amount"]["type"], "integer");
+        assert_eq!(properties["from_handle"]["items"]["maximum"], 255);
+        assert_eq!(transfer.fields[2].move_type, "u64");
+    }
+}
@@ -0,0 +1,705 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! Mock enclave for frontend and backend development
+//!
+//! Serves every enclave route with the real request/response types, but
+//! without Nitro, OpenRouter, Hume or TTS. Responses are signed with a key
+//! derived from a fixed seed, so they verify against a known public key, and
+//! with `MOCK_TIMESTAMP_MS` set the same request always gets the same
+//! signature.
+//!
+//! Bio-auth outcomes are scripted per request with the `x-mock-outcome`
+//! header (`ok`, `invalid_amount`, `duress`, `decoy` or `inconclusive`, which
+//! answers 422 `analysis_inconclusive`; default `ok`). The transcript
+//! is the challenge phrase, so flows that display it look realistic. `/set_limit`
+//! and `/request_unfreeze` sign only for `ok`; unfreeze challenges always carry
+//! the code `123456`.
+//!
+//! Coin types are validated like the real enclave's, but there is no coin
+//! registry lookup: only SUI resolves for bio-auth and challenges.
+
+use crate::common::{
+    GetPublicKeyResponse, HealthCheckResponse, IntentScope, ProcessDataRequest,
+};
+use crate::i18n::{assign_request_lang, current_lang, Lang};
+use crate::{assign_request_id, EnclaveError};
+use axum::extract::{Path, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{delete, get, post};
+use axum::{Json, Router};
+use fastcrypto::ed25519::{Ed25519KeyPair, Ed25519PrivateKey};
+use fastcrypto::encoding::{Encoding, Hex};
+use fastcrypto::traits::{KeyPair, ToFromBytes};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tracing::info;
+
+use super::amount::RawAmount;
+use super::audio::DESTINATION_SUFFIX_LEN;
+use super::chain;
+use super::challenge::{challenge_phrase, DEFAULT_CHALLENGE_TTL_SECS};
+use super::coin::{CoinInfo, CoinRegistry, CoinType};
+use super::decoy;
+use super::freeze::unfreeze_phrase;
+use super::handlers::parse_sui_address;
+use super::jobs::{JobStatus, JobView, QueueMetrics, DEFAULT_QUEUE_CAPACITY};
+use super::locale::NumberLocale;
+use super::meta::get_intents;
+use super::panic_phrase::PanicPhrase;
+use super::shadow::ShadowMetrics;
+use super::types::*;
+use super::validation::{normalize_handle, ValidatedJson};
+use super::versions::{sign_payload, PayloadVersions};
+use super::voice_stress::BASELINE_MIN_SAMPLES;
+
+/// Request header that picks the bio-auth outcome
+pub const MOCK_OUTCOME_HEADER: &str = "x-mock-outcome";
+
+/// Seed of the mock signing key (never use outside development)
+pub const MOCK_KEY_SEED: [u8; 32] = [0x42; 32];
+
+/// Stress level reported in diagnostics for calm and duress outcomes
+const MOCK_CALM_STRESS: u8 = 15;
+const MOCK_DURESS_STRESS: u8 = 85;
+/// Code in every mock unfreeze challenge
+const MOCK_UNFREEZE_CODE: &str = "123456";
+
+/// Outcome forced through `x-mock-outcome`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MockOutcome {
+    Ok,
+    InvalidAmount,
+    Duress,
+    /// Duress signed as an OK-looking decoy, like `BIOAUTH_DURESS_DECOY`
+    Decoy,
+    /// Nothing signed: the analysis wasn't sure enough, the user should record again
+    Inconclusive,
+}
+
+impl MockOutcome {
+    /// Outcome requested by the headers (`ok` when absent)
+    pub fn from_headers(headers: &HeaderMap) -> Result<Self, EnclaveError> {
+        let Some(value) = headers.get(MOCK_OUTCOME_HEADER) else {
+            return Ok(MockOutcome::Ok);
+        };
+        match value.to_str().unwrap_or_default().trim().to_lowercase().as_str() {
+            "ok" => Ok(MockOutcome::Ok),
+            "invalid_amount" => Ok(MockOutcome::InvalidAmount),
+            "duress" => Ok(MockOutcome::Duress),
+            "decoy" => Ok(MockOutcome::Decoy),
+            "inconclusive" => Ok(MockOutcome::Inconclusive),
+            other => Err(EnclaveError::GenericError(format!(
+                "Unknown {} '{}' (expected ok, invalid_amount, duress, decoy or inconclusive)",
+                MOCK_OUTCOME_HEADER, other
+            ))),
+        }
+    }
+}
+
+/// In-memory state of the mock enclave
+pub struct MockState {
+    kp: Ed25519KeyPair,
+    /// Sign every response with this timestamp instead of the clock
+    fixed_timestamp_ms: Option<u64>,
+    /// Answer `POST /bio_auth` with a job ID, like `BIOAUTH_ASYNC_MODE`
+    async_mode: bool,
+    next_id: AtomicU64,
+    jobs: Mutex<HashMap<String, BioAuthResponse>>,
+    diagnostics: Mutex<HashMap<String, BioAuthData>>,
+    challenges: Mutex<HashMap<String, String>>,
+    /// Samples enrolled and whether a panic phrase was registered, per normalized handle
+    enrollments: Mutex<HashMap<String, (u32, bool)>>,
+    /// No RPC, so only SUI resolves
+    coins: CoinRegistry,
+}
+
+impl MockState {
+    pub fn new(fixed_timestamp_ms: Option<u64>, async_mode: bool) -> Self {
+        let sk = Ed25519PrivateKey::from_bytes(&MOCK_KEY_SEED).expect("32-byte seed");
+        Self {
+            kp: Ed25519KeyPair::from(sk),
+            fixed_timestamp_ms,
+            async_mode,
+            next_id: AtomicU64::new(1),
+            jobs: Mutex::new(HashMap::new()),
+            diagnostics: Mutex::new(HashMap::new()),
+            challenges: Mutex::new(HashMap::new()),
+            enrollments: Mutex::new(HashMap::new()),
+            coins: CoinRegistry::default(),
+        }
+    }
+
+    /// Hex-encoded public key that mock signatures verify against
+    pub fn public_key_hex(&self) -> String {
+        Hex::encode(self.kp.public().as_bytes())
+    }
+
+    fn timestamp_ms(&self) -> u64 {
+        self.fixed_timestamp_ms.unwrap_or_else(|| {
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or_default()
+        })
+    }
+
+    /// Sequential IDs, so runs are reproducible
+    fn next_id(&self, prefix: &str) -> String {
+        format!("mock-{}-{}", prefix, self.next_id.fetch_add(1, Ordering::Relaxed))
+    }
+
+    /// Sign a payload in the requested layout (v1 by default), returning
+    /// (timestamp_ms, payload_version, signature)
+    fn sign<T: Serialize + Clone>(
+        &self,
+        payload: &T,
+        intent: IntentScope,
+        requested: Option<u8>,
+    ) -> Result<(u64, u8, String), EnclaveError> {
+        let version = PayloadVersions::default().select(requested)?;
+        let timestamp_ms = self.timestamp_ms();
+        let signature = sign_payload(&self.kp, payload, timestamp_ms, intent, version);
+        Ok((timestamp_ms, version as u8, signature))
+    }
+}
+
+/// Router with every enclave route, backed by the mock
+pub fn mock_router(state: Arc<MockState>) -> Router {
+    Router::new()
+        .route("/", get(|| async { "RAM Mock Enclave Server - Pong!" }))
+        .route("/get_attestation", get(mock_get_attestation))
+        .route("/public_key", get(mock_public_key))
+        .route("/meta/intents", get(get_intents))
+        .route("/create_wallet", post(mock_create_wallet))
+        .route("/link_address", post(mock_link_address))
+        .route("/unlink_address", post(mock_unlink_address))
+        .route("/bio_auth", post(mock_bio_auth))
+        .route("/typed_auth", post(mock_typed_auth))
+        .route("/bio_auth/enroll", post(mock_enroll_voice))
+        .route("/bio_auth/challenge", post(mock_challenge))
+        .route("/bio_auth/challenge_audio/:challenge_id", get(mock_challenge_audio))
+        .route("/transfer", post(mock_transfer))
+        .route("/withdraw", post(mock_withdraw))
+        .route("/set_limit", post(mock_set_limit))
+        .route("/freeze_wallet", post(mock_freeze_wallet))
+        .route("/unfreeze/challenge", post(mock_unfreeze_challenge))
+        .route("/request_unfreeze", post(mock_request_unfreeze))
+        .route("/set_beneficiary", post(mock_set_beneficiary))
+        .route("/prove_deposit", post(mock_prove_deposit))
+        .route("/bio_auth/diagnostics/:request_id", get(mock_diagnostics))
+        .route("/bio_auth/baseline/:handle", delete(mock_reset_baseline))
+        .route("/bio_auth/queue", get(mock_queue))
+        .route("/bio_auth/shadow", get(mock_shadow))
+        .route("/bio_auth/:job_id", get(mock_job))
+        .route("/health_check", get(mock_health_check))
+        .with_state(state)
+        .layer(axum::middleware::from_fn(assign_request_lang))
+        .layer(axum::middleware::from_fn(assign_request_id))
+}
+
+async fn mock_get_attestation() -> EnclaveError {
+    EnclaveError::NotFound(
+        "The mock enclave has no attestation document; trust the key from /public_key".to_string(),
+    )
+}
+
+async fn mock_public_key(State(state): State<Arc<MockState>>) -> Json<GetPublicKeyResponse> {
+    Json(GetPublicKeyResponse { public_key: state.public_key_hex() })
+}
+
+async fn mock_health_check(State(state): State<Arc<MockState>>) -> Json<HealthCheckResponse> {
+    Json(HealthCheckResponse {
+        pk: state.public_key_hex(),
+        endpoints_status: HashMap::new(),
+        chain: chain::current().info(),
+    })
+}
+
+async fn mock_create_wallet(
+    State(state): State<Arc<MockState>>,
+    ValidatedJson(request): ValidatedJson<ProcessDataRequest<CreateWalletRequest>>,
+) -> Result<Json<CreateWalletResponse>, EnclaveError> {
+    let payload = CreateWalletPayload { handle: request.payload.handle.into_bytes() };
+    let (timestamp_ms, payload_version, signature) =
+        state.sign(&payload, IntentScope::ProcessData, request.payload_version)?;
+    Ok(Json(CreateWalletResponse { payload, intent: CREATE_WALLET_INTENT, payload_version, timestamp_ms, signature }))
+}
+
+async fn mock_link_address(
+    State(state): State<Arc<MockState>>,
+    ValidatedJson(request): ValidatedJson<ProcessDataRequest<LinkAddressRequest>>,
+) -> Result<Json<LinkAddressResponse>, EnclaveError> {
+    let req = request.payload;
+    let payload = LinkAddressPayload {
+        handle: req.handle.into_bytes(),
+        address: parse_sui_address(&req.wallet_address)?,
+        label: req.label.into_bytes(),
+    };
+    let (timestamp_ms, payload_version, signature) =
+        state.sign(&payload, IntentScope::LinkWallet, request.payload_version)?;
+    Ok(Json(LinkAddressResponse { payload, intent: LINK_ADDRESS_INTENT, payload_version, timestamp_ms, signature }))
+}
+
+async fn mock_unlink_address(
+    State(state): State<Arc<MockState>>,
+    ValidatedJson(request): ValidatedJson<ProcessDataRequest<UnlinkAddressRequest>>,
+) -> Result<Json<UnlinkAddressResponse>, EnclaveError> {
+    let req = request.payload;
+    let payload = UnlinkAddressPayload {
+        handle: req.handle.into_bytes(),
+        address: parse_sui_address(&req.wallet_address)?,
+    };
+    let (timestamp_ms, payload_version, signature) =
+        state.sign(&payload, IntentScope::UnlinkWallet, request.payload_version)?;
+    Ok(Json(UnlinkAddressResponse { payload, intent: UNLINK_ADDRESS_INTENT, payload_version, timestamp_ms, signature }))
+}
+
+async fn mock_transfer(
+    State(state): State<Arc<MockState>>,
+    ValidatedJson(request): ValidatedJson<ProcessDataRequest<TransferRequest>>,
+) -> Result<Json<TransferResponse>, EnclaveError> {
+    let req = request.payload;
+    let payload = TransferPayload {
+        from_handle: req.from_handle.into_bytes(),
+        to_handle: req.to_handle.into_bytes(),
+        amount: req.amount,
+        coin_type: CoinType::parse(&req.coin_type)?.type_name().into_bytes(),
+        memo: req.memo.unwrap_or_default().into_bytes(),
+    };
+    let (timestamp_ms, payload_version, signature) =
+        state.sign(&payload, IntentScope::TransferCoin, request.payload_version)?;
+    // Nothing to dry-run against: `simulate` is accepted and left unanswered
+    Ok(Json(TransferResponse {
+        payload,
+        intent: TRANSFER_INTENT,
+        payload_version,
+        timestamp_ms,
+        signature,
+        simulation: None,
+    }))
+}
+
+async fn mock_withdraw(
+    State(state): State<Arc<MockState>>,
+    ValidatedJson(request): ValidatedJson<ProcessDataRequest<WithdrawRequest>>,
+) -> Result<Json<WithdrawResponse>, EnclaveError> {
+    let req = request.payload;
+    let payload = WithdrawPayload {
+        handle: req.handle.into_bytes(),
+        amount: req.amount,
+        coin_type: CoinType::parse(&req.coin_type)?.type_name().into_bytes(),
+    };
+    let (timestamp_ms, payload_version, signature) =
+        state.sign(&payload, IntentScope::UpdateHandle, request.payload_version)?;
+    Ok(Json(WithdrawResponse { payload, intent: WITHDRAW_INTENT, payload_version, timestamp_ms, signature }))
+}
+
+/// Only `ok` signs; every other outcome is refused, as the real enclave refuses
+/// a stressed or mismatched limit the same way
+async fn mock_set_limit(
+    State(state): State<Arc<MockState>>,
+    headers: HeaderMap,
+    ValidatedJson(request): ValidatedJson<ProcessDataRequest<SetLimitRequest>>,
+) -> Result<Json<SetLimitResponse>, EnclaveError> {
+    let outcome = MockOutcome::from_headers(&headers)?;
+    let req = request.payload;
+    let coin_type = CoinType::parse_or_sui(req.coin_type.as_deref())?;
+    if outcome != MockOutcome::Ok {
+        return Err(EnclaveError::Forbidden("Daily limit was not confirmed".to_string()));
+    }
+    let payload = SetLimitPayload {
+        handle: req.handle.into_bytes(),
+        daily_limit: req.daily_limit,
+        coin_type: coin_type.type_name().into_bytes(),
+    };
+    let (timestamp_ms, payload_version, signature) =
+        state.sign(&payload, IntentScope::SetLimit, request.payload_version)?;
+    Ok(Json(SetLimitResponse { payload, intent: SET_LIMIT_INTENT, payload_version, timestamp_ms, signature }))
+}
+
+async fn mock_freeze_wallet(
+    State(state): State<Arc<MockState>>,
+    ValidatedJson(request): ValidatedJson<ProcessDataRequest<FreezeWalletRequest>>,
+) -> Result<Json<FreezeWalletResponse>, EnclaveError> {
+    let payload = FreezePayload { handle: request.payload.handle.into_bytes() };
+    let (timestamp_ms, payload_version, signature) =
+        state.sign(&payload, IntentScope::Freeze, request.payload_version)?;
+    Ok(Json(FreezeWalletResponse { payload, intent: FREEZE_INTENT, payload_version, timestamp_ms, signature }))
+}
+
+async fn mock_unfreeze_challenge(
+    State(state): State<Arc<MockState>>,
+    ValidatedJson(request): ValidatedJson<ProcessDataRequest<UnfreezeChallengeRequest>>,
+) -> Result<Json<ChallengeResponse>, EnclaveError> {
+    let req = &request.payload;
+    let lang = req.locale.as_deref().and_then(Lang::from_tag).unwrap_or_else(current_lang);
+    let phrase = unfreeze_phrase(lang, &req.handle, MOCK_UNFREEZE_CODE);
+    let challenge_id = state.next_id("challenge");
+    state.challenges.lock().unwrap().insert(challenge_id.clone(), phrase.clone());
+    Ok(Json(ChallengeResponse {
+        challenge_id,
+        phrase,
+        expires_at_ms: state.timestamp_ms() + DEFAULT_CHALLENGE_TTL_SECS * 1000,
+    }))
+}
+
+/// Consumes the challenge like the real enclave, then signs only for `ok`
+async fn mock_request_unfreeze(
+    State(state): State<Arc<MockState>>,
+    headers: HeaderMap,
+    ValidatedJson(request): ValidatedJson<ProcessDataRequest<RequestUnfreezeRequest>>,
+) -> Result<Json<UnfreezeResponse>, EnclaveError> {
+    let outcome = MockOutcome::from_headers(&headers)?;
+    let req = request.payload;
+    if state.challenges.lock().unwrap().remove(&req.challenge_id).is_none() {
+        return Err(EnclaveError::NotFound(format!("No unfreeze challenge '{}'", req.challenge_id)));
+    }
+    if outcome != MockOutcome::Ok {
+        return Err(EnclaveError::Forbidden("Unfreeze was not confirmed".to_string()));
+    }
+    let payload = UnfreezePayload { handle: req.handle.into_bytes() };
+    let (timestamp_ms, payload_version, signature) =
+        state.sign(&payload, IntentScope::Unfreeze, request.payload_version)?;
+    Ok(Json(UnfreezeResponse { payload, intent: UNFREEZE_INTENT, payload_version, timestamp_ms, signature }))
+}
+
+async fn mock_set_beneficiary(
+    State(state): State<Arc<MockState>>,
+    ValidatedJson(request): ValidatedJson<ProcessDataRequest<SetBeneficiaryRequest>>,
+) -> Result<Json<SetBeneficiaryResponse>, EnclaveError> {
+    let req = request.payload;
+    let inactivity_timeout_ms = if req.beneficiary_handle.is_empty() { 0 } else { req.inactivity_timeout_ms };
+    let payload = BeneficiaryPayload {
+        handle: req.handle.into_bytes(),
+        beneficiary_handle: req.beneficiary_handle.into_bytes(),
+        inactivity_timeout_ms,
+    };
+    let (timestamp_ms, payload_version, signature) =
+        state.sign(&payload, IntentScope::Beneficiary, request.payload_version)?;
+    Ok(Json(SetBeneficiaryResponse { payload, intent: BENEFICIARY_INTENT, payload_version, timestamp_ms, signature }))
+}
+
+async fn mock_prove_deposit(
+    State(state): State<Arc<MockState>>,
+    ValidatedJson(request): ValidatedJson<ProcessDataRequest<ProveDepositRequest>>,
+) -> Result<Json<ProveDepositResponse>, EnclaveError> {
+    let req = request.payload;
+    let payload = DepositProofPayload { handle: req.handle.into_bytes(), deposit_id: req.deposit_id.into_bytes() };
+    let (timestamp_ms, payload_version, signature) =
+        state.sign(&payload, IntentScope::DepositProof, request.payload_version)?;
+    Ok(Json(ProveDepositResponse { payload, intent: DEPOSIT_PROOF_INTENT, payload_version, timestamp_ms, signature }))
+}
+
+/// What a bio-auth request asked the user to confirm
+struct Confirmation<'a> {
+    handle: &'a str,
+    expected_amount: u64,
+    coin: &'a CoinInfo,
+    destination_hint: Option<&'a str>,
+    locale: NumberLocale,
+}
+
+/// Build, sign and record a scripted bio-auth
+fn mock_bio_auth_response(
+    state: &MockState,
+    outcome: MockOutcome,
+    method: BioAuthMethod,
+    confirmation: Confirmation,
+    requested_version: Option<u8>,
+) -> Result<BioAuthResponse, EnclaveError> {
+    let Confirmation { handle, expected_amount, coin, destination_hint, locale } = confirmation;
+    let spoken = RawAmount(expected_amount);
+    let phrase = |amount| challenge_phrase(amount, coin, destination_hint, DESTINATION_SUFFIX_LEN, Lang::En);
+    let (result, transcript) = match outcome {
+        MockOutcome::Ok => (BioAuthResult::Ok, phrase(spoken)),
+        // Speak double the amount so the transcript shows the mismatch
+        MockOutcome::InvalidAmount => (BioAuthResult::InvalidAmount, phrase(RawAmount(expected_amount.saturating_mul(2)))),
+        MockOutcome::Duress | MockOutcome::Decoy => (BioAuthResult::Duress, phrase(spoken)),
+        MockOutcome::Inconclusive => {
+            return Err(EnclaveError::Inconclusive(
+                "The recording couldn't be analyzed with confidence (stress); record the confirmation again".to_string(),
+            ))
+        }
+    };
+    let decoy = outcome == MockOutcome::Decoy;
+    let signed_result = if decoy { BioAuthResult::Ok } else { result };
+    let destination_verified = destination_hint.is_some();
+
+    let payload = BioAuthPayload {
+        handle: handle.as_bytes().to_vec(),
+        amount: expected_amount,
+        result: signed_result as u8,
+        transcript: transcript.clone().into_bytes(),
+        destination_verified,
+        method: method as u8,
+        seal: decoy::seal(decoy),
+        failed_attempts: 0,
+    };
+    let (timestamp_ms, payload_version, signature) =
+        state.sign(&payload, IntentScope::TransferNft, requested_version)?;
+
+    let request_id = state.next_id("request");
+    state.diagnostics.lock().unwrap().insert(
+        request_id.clone(),
+        BioAuthData {
+            handle: handle.to_string(),
+            amount: expected_amount,
+            amount_display: locale.format_amount(spoken, coin),
+            result: result.as_str().to_string(),
+            transcript,
+            stress_level: if result == BioAuthResult::Duress { MOCK_DURESS_STRESS } else { MOCK_CALM_STRESS },
+            locked: result == BioAuthResult::Duress,
+            destination_verified,
+            shadow_mode: false,
+            detected_result: result.as_str().to_string(),
+            method: method.as_str().to_string(),
+            decoy,
+            panic_phrase: false,
+            analyzer_disagreement: None,
+        },
+    );
+    info!("RAM Mock BioAuth: handle='{}', method={}, result={}", handle, method.as_str(), result.as_str());
+
+    Ok(BioAuthResponse { payload, intent: BIOAUTH_INTENT, payload_version, timestamp_ms, signature, request_id })
+}
+
+async fn mock_bio_auth(
+    State(state): State<Arc<MockState>>,
+    headers: HeaderMap,
+    ValidatedJson(request): ValidatedJson<ProcessDataRequest<BioAuthRequest>>,
+) -> Result<Response, EnclaveError> {
+    let outcome = MockOutcome::from_headers(&headers)?;
+    let req = request.payload;
+    let coin = state.coins.resolve(&CoinType::parse_or_sui(req.coin_type.as_deref())?).await?;
+    let response = mock_bio_auth_response(
+        &state,
+        outcome,
+        BioAuthMethod::Voice,
+        Confirmation {
+            handle: &req.handle,
+            expected_amount: req.expected_amount,
+            coin: &coin,
+            destination_hint: req.destination_hint.as_deref(),
+            locale: NumberLocale::from_tag(req.locale.as_deref()),
+        },
+        request.payload_version,
+    )?;
+
+    if state.async_mode {
+        // Jobs finish immediately; the first poll returns the result
+        let job_id = state.next_id("job");
+        state.jobs.lock().unwrap().insert(job_id.clone(), response);
+        return Ok((StatusCode::ACCEPTED, Json(json!({ "job_id": job_id, "status": "queued" }))).into_response());
+    }
+    Ok(Json(response).into_response())
+}
+
+/// Typed auth never signs duress or abstains (as in the real enclave), so `duress`,
+/// `decoy` and `inconclusive` act as `ok`
+async fn mock_typed_auth(
+    State(state): State<Arc<MockState>>,
+    headers: HeaderMap,
+    ValidatedJson(request): ValidatedJson<ProcessDataRequest<TypedAuthRequest>>,
+) -> Result<Json<BioAuthResponse>, EnclaveError> {
+    let outcome = match MockOutcome::from_headers(&headers)? {
+        MockOutcome::InvalidAmount => MockOutcome::InvalidAmount,
+        MockOutcome::Ok | MockOutcome::Duress | MockOutcome::Decoy | MockOutcome::Inconclusive => MockOutcome::Ok,
+    };
+    let req = request.payload;
+    let coin = state.coins.resolve(&CoinType::parse_or_sui(req.coin_type.as_deref())?).await?;
+    Ok(Json(mock_bio_auth_response(
+        &state,
+        outcome,
+        BioAuthMethod::Typed,
+        Confirmation {
+            handle: &req.handle,
+            expected_amount: req.expected_amount,
+            coin: &coin,
+            destination_hint: req.destination_hint.as_deref(),
+            locale: NumberLocale::from_tag(req.locale.as_deref()),
+        },
+        request.payload_version,
+    )?))
+}
+
+async fn mock_job(
+    State(state): State<Arc<MockState>>,
+    Path(job_id): Path<String>,
+) -> Result<Json<JobView>, EnclaveError> {
+    let response = state
+        .jobs
+        .lock()
+        .unwrap()
+        .get(&job_id)
+        .cloned()
+        .ok_or_else(|| EnclaveError::NotFound(format!("No job '{}'", job_id)))?;
+    Ok(Json(JobView { job_id, status: JobStatus::Done { response } }))
+}
+
+async fn mock_queue(State(state): State<Arc<MockState>>) -> Json<QueueMetrics> {
+    Json(QueueMetrics {
+        enabled: state.async_mode,
+        capacity: DEFAULT_QUEUE_CAPACITY,
+        depth: 0,
+        in_flight: 0,
+        completed: state.jobs.lock().unwrap().len() as u64,
+        failed: 0,
+        rejected: 0,
+        shed: 0,
+    })
+}
+
+async fn mock_shadow() -> Json<ShadowMetrics> {
+    Json(ShadowMetrics { enabled: false, threshold: None, analyzed: 0, would_lock: 0, would_lock_rate: 0.0 })
+}
+
+/// Diagnostics are served without the bearer secret the real enclave requires
+async fn mock_diagnostics(
+    State(state): State<Arc<MockState>>,
+    Path(request_id): Path<String>,
+) -> Result<Json<BioAuthData>, EnclaveError> {
+    state
+        .diagnostics
+        .lock()
+        .unwrap()
+        .get(&request_id)
+        .cloned()
+        .map(Json)
+        .ok_or_else(|| EnclaveError::NotFound(format!("No diagnostics for request '{}'", request_id)))
+}
+
+/// Every sample counts towards enrollment unless `x-mock-outcome: duress` marks it stressed
+///
+/// Panic phrases are validated and reported but never matched: script duress with the header.
+async fn mock_enroll_voice(
+    State(state): State<Arc<MockState>>,
+    headers: HeaderMap,
+    ValidatedJson(request): ValidatedJson<ProcessDataRequest<EnrollVoiceRequest>>,
+) -> Result<Json<EnrollVoiceResponse>, EnclaveError> {
+    if matches!(MockOutcome::from_headers(&headers)?, MockOutcome::Duress | MockOutcome::Decoy) {
+        return Err(EnclaveError::GenericError(
+            "Sample is not calm enough to enroll, please record again".to_string(),
+        ));
+    }
+    let phrase = request.payload.panic_phrase.as_deref().map(PanicPhrase::new).transpose()?;
+    let handle = request.payload.handle;
+    let mut enrollments = state.enrollments.lock().unwrap();
+    let (samples, phrase_set) = enrollments.entry(normalize_handle(&handle)).or_insert((0, false));
+    *samples = (*samples + 1).min(BASELINE_MIN_SAMPLES);
+    *phrase_set |= phrase.is_some();
+    Ok(Json(EnrollVoiceResponse {
+        handle,
+        samples: *samples,
+        required: BASELINE_MIN_SAMPLES,
+        complete: *samples >= BASELINE_MIN_SAMPLES,
+        panic_phrase_set: *phrase_set,
+    }))
+}
+
+async fn mock_reset_baseline(
+    State(state): State<Arc<MockState>>,
+    Path(handle): Path<String>,
+) -> Result<Json<Value>, EnclaveError> {
+    let handle = normalize_handle(&handle);
+    if state.enrollments.lock().unwrap().remove(&handle).is_none() {
+        return Err(EnclaveError::NotFound(format!("No voice baseline for '{}'", handle)));
+    }
+    Ok(Json(json!({ "handle": handle, "reset": true })))
+}
+
+async fn mock_challenge(
+    State(state): State<Arc<MockState>>,
+    ValidatedJson(request): ValidatedJson<ProcessDataRequest<ChallengeRequest>>,
+) -> Result<Json<ChallengeResponse>, EnclaveError> {
+    let req = request.payload;
+    let coin = state.coins.resolve(&CoinType::parse_or_sui(req.coin_type.as_deref())?).await?;
+    let lang = req.locale.as_deref().and_then(Lang::from_tag).unwrap_or_else(current_lang);
+    let phrase = challenge_phrase(
+        RawAmount(req.expected_amount),
+        &coin,
+        req.destination_hint.as_deref(),
+        DESTINATION_SUFFIX_LEN,
+        lang,
+    );
+    let challenge_id = state.next_id("challenge");
+    state.challenges.lock().unwrap().insert(challenge_id.clone(), phrase.clone());
+    Ok(Json(ChallengeResponse {
+        challenge_id,
+        phrase,
+        expires_at_ms: state.timestamp_ms() + DEFAULT_CHALLENGE_TTL_SECS * 1000,
+    }))
+}
+
+/// Half a second of silence stands in for the TTS rendering
+async fn mock_challenge_audio(
+    State(state): State<Arc<MockState>>,
+    Path(challenge_id): Path<String>,
+) -> Result<Response, EnclaveError> {
+    if !state.challenges.lock().unwrap().contains_key(&challenge_id) {
+        return Err(EnclaveError::NotFound(format!("No challenge '{}'", challenge_id)));
+    }
+    Ok(([(header::CONTENT_TYPE, "audio/wav")], silent_wav(16_000, 8_000)).into_response())
+}
+
+/// 16-bit mono PCM WAV of `samples` zero samples
+fn silent_wav(sample_rate: u32, samples: u32) -> Vec<u8> {
+    let data_size = samples * 2;
+    let mut wav = Vec::with_capacity(44 + data_size as usize);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_size).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&sample_rate.to_le_bytes());
+    wav.extend_from_slice(&(sample_rate * 2).to_le_bytes());
+    wav.extend_from_slice(&2u16.to_le_bytes());
+    wav.extend_from_slice(&16u16.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_size.to_le_bytes());
+    wav.resize(44 + data_size as usize, 0);
+    wav
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_outcome_header() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(MockOutcome::from_headers(&headers).unwrap(), MockOutcome::Ok);
+        headers.insert(MOCK_OUTCOME_HEADER, "Duress".parse().unwrap());
+        assert_eq!(MockOutcome::from_headers(&headers).unwrap(), MockOutcome::Duress);
+        headers.insert(MOCK_OUTCOME_HEADER, "inconclusive".parse().unwrap());
+        assert_eq!(MockOutcome::from_headers(&headers).unwrap(), MockOutcome::Inconclusive);
+        headers.insert(MOCK_OUTCOME_HEADER, "locked".parse().unwrap());
+        assert!(MockOutcome::from_headers(&headers).is_err());
+    }
+
+    #[test]
+    fn test_fixed_clock_signatures_are_deterministic() {
+        let a = MockState::new(Some(1_700_000_000_000), false);
+        let b = MockState::new(Some(1_700_000_000_000), false);
+        assert_eq!(a.public_key_hex(), b.public_key_hex());
+
+        let sui = CoinInfo::sui();
+        let confirmation = || Confirmation {
+            handle: "alice",
+            expected_amount: 5,
+            coin: &sui,
+            destination_hint: None,
+            locale: NumberLocale::default(),
+        };
+        let first = mock_bio_auth_response(&a, MockOutcome::Duress, BioAuthMethod::Voice, confirmation(), None).unwrap();
+        let second = mock_bio_auth_response(&b, MockOutcome::Duress, BioAuthMethod::Voice, confirmation(), None).unwrap();
+        assert_eq!(first.signature, second.signature);
+        assert_eq!(first.request_id, second.request_id);
+        assert_eq!(first.payload.result, BioAuthResult::Duress as u8);
+    }
+}
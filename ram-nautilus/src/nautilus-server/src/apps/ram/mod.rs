@@ -9,42 +9,348 @@
 //!
 //! - `types`: Request/response structs and payload definitions
 //! - `audio`: Audio processing and stress detection
+//! - `amount`: Fixed-point raw amounts, decimal parsing/rendering and tolerance
+//! - `locale`: Locale-aware rendering and parsing of expected amounts
+//! - `coin`: Coin type tag parsing and registry symbol/decimals lookup
 //! - `handlers`: HTTP endpoint handlers
+//! - `diagnostics`: Privileged server-to-server BioAuth diagnostics
+//! - `jobs`: Async BioAuth job queue with bounded worker pool
+//! - `shed`: Concurrency limit and queue-depth load shedding on the analysis routes
+//! - `attempts`: Cooldowns after repeated failed bio-auths
+//! - `locks`: Per-handle serialization of bio-auth requests
+//! - `shadow`: Shadow mode that records duress verdicts without enforcing them
+//! - `decoy`: The duress decoy flag, sealed inside signed bio-auth payloads
+//! - `simulation`: Synthetic calm/stressed voices and classifier hit rates (`simulation` feature)
+//! - `abi`: Payload/intent sync check against the deployed Move contract
+//! - `meta`: `GET /meta/intents`, the machine-readable intent and result code table
+//! - `versions`: Versioned payload layouts and the v1 → v2 migration window
+//! - `chain`: The Sui network signed for, its chain ID, RAM package IDs and fullnode
+//! - `baseline`: Per-handle voice baselines for speaker-relative stress scoring
+//! - `panic_phrase`: Covert per-handle panic phrases that force Duress
+//! - `preferences`: Sealed per-handle policy preferences, updated after a successful bio-auth
+//! - `sealing`: Authenticated encryption of preferences and snapshots
+//! - `snapshot`: Sealed periodic snapshots of volatile state, restored after a restart
+//! - `challenge`: Bio-auth challenge phrases and their cached TTS playback
+//! - `confidence`: Per-finding analysis confidence and abstaining when it is too low
+//! - `replay`: Fingerprints of accepted clips so recordings can't be replayed
+//! - `analysis_cache`: Short-lived reuse of the analysis of a clip sent twice
+//! - `disagreement`: Structured warnings when the stress analyzers' scores diverge
+//! - `gray_zone`: Second analysis pass before deciding on borderline stress scores
+//! - `feature_flags`: Backend-managed flags gating risky analysis features per environment and rollout
+//! - `typed_auth`: Typed-confirmation auth with keystroke checks (accessibility path)
+//! - `acl`: Peer CIDR allowlists per route class (signing, privileged)
+//! - `request_auth`: HMAC request signing required on the backend → enclave hop
+//! - `registration_gate`: Proof-of-work or CAPTCHA gate in front of wallet creation
+//! - `screening`: Deny-list / sanctions screening of transfer and withdrawal subjects
+//! - `dry_run`: Dev-inspecting a transfer's Move call before it is signed
+//! - `validation`: Field-level request validation answered as structured 422s
+//! - `chaos`: Env-controlled upstream fault injection (`chaos` feature)
+//! - `endpoints`: Upstream endpoints and settings fixed in the image, redirectable to mock servers only in test and `mock` builds
+//! - `cassette`: Record/replay of OpenRouter and Hume exchanges for tests (`cassettes` feature)
+//! - `prompt`: Configurable transcription model and prompt template
+//! - `retry`: Per-provider timeouts and retries for OpenRouter, Hume and the Sui RPC
+//! - `costs`: Upstream API cost tracking and daily budget caps
+//! - `stream`: Server-Sent Events variant of bio-auth with stage progress
+//! - `zklogin`: Linking zkLogin (Google/Apple sign-in) addresses with proof verification
+//! - `set_limit`: Daily spending limits confirmed by a calm voice
+//! - `freeze`: Instant self-service freeze and voice-confirmed, delayed unfreeze
+//! - `beneficiary`: Inactivity beneficiary who may claim a long-dormant wallet
+//! - `deposit`: Deposit proofs binding an exchange's deposit ID to a handle
+//! - `mock`: Mock enclave with scripted outcomes for frontend/backend development
 
 // Submodules
+pub mod abi;
+mod acl;
+mod amount;
+mod analysis_cache;
+mod attempts;
 mod audio;
+mod baseline;
+mod beneficiary;
+#[cfg(feature = "cassettes")]
+mod cassette;
+mod chain;
+mod challenge;
+mod chaos;
+mod coin;
+mod confidence;
+mod costs;
+mod decoy;
+mod deposit;
+mod diagnostics;
+mod dry_run;
+mod disagreement;
+mod endpoints;
+mod feature_flags;
+mod freeze;
+mod gray_zone;
 mod handlers;
+mod jobs;
+mod locale;
+mod locks;
+mod meta;
+mod mock;
+mod panic_phrase;
+mod preferences;
+mod prompt;
+mod registration_gate;
+mod replay;
+mod request_auth;
+mod retry;
+mod screening;
+mod sealing;
+mod set_limit;
+mod shadow;
+mod shed;
+#[cfg(any(test, feature = "simulation"))]
+pub mod simulation;
+mod snapshot;
+mod stream;
+mod typed_auth;
 mod types;
+mod validation;
+mod versions;
 mod voice_stress;
+mod zklogin;
 
 // Re-export types
 pub use types::{
     // Payloads (for Move contract integration)
     CreateWalletPayload,
     LinkAddressPayload,
+    UnlinkAddressPayload,
     TransferPayload,
     WithdrawPayload,
     BioAuthPayload,
+    SetLimitPayload,
+    FreezePayload,
+    UnfreezePayload,
+    BeneficiaryPayload,
+    DepositProofPayload,
     // Request types
     CreateWalletRequest,
     LinkAddressRequest,
+    LinkZkLoginRequest,
+    UnlinkAddressRequest,
     BioAuthRequest,
+    TypedAuthRequest,
+    Keystroke,
+    EnrollVoiceRequest,
+    ChallengeRequest,
     TransferRequest,
+    TransferSimulation,
     WithdrawRequest,
+    SetLimitRequest,
+    FreezeWalletRequest,
+    UnfreezeChallengeRequest,
+    RequestUnfreezeRequest,
+    SetBeneficiaryRequest,
+    ProveDepositRequest,
+    UpdatePreferencesRequest,
+    UserPreferences,
     // Response types
     CreateWalletResponse,
     LinkAddressResponse,
+    UnlinkAddressResponse,
     BioAuthResponse,
+    EnrollVoiceResponse,
+    ChallengeResponse,
     TransferResponse,
+    SimulatedTransfer,
+    SimulatedGas,
     WithdrawResponse,
+    SetLimitResponse,
+    FreezeWalletResponse,
+    UnfreezeResponse,
+    SetBeneficiaryResponse,
+    ProveDepositResponse,
+    PreferencesResponse,
     BioAuthData,
     BioAuthResult,
+    BioAuthMethod,
 };
 
+// Fixed-point amounts
+pub use amount::{RawAmount, AMOUNT_TOLERANCE_BPS};
+
+// Locale-aware amount formatting
+pub use locale::NumberLocale;
+
+// Coin type tags and registry metadata
+pub use coin::{CoinInfo, CoinRegistry, CoinType, SUI_COIN_TYPE};
+
+// Privileged diagnostics channel
+pub use diagnostics::{get_bio_auth_diagnostics, DiagnosticsStore, DEFAULT_DIAGNOSTICS_TTL_SECS};
+
+// Async BioAuth job queue
+pub use jobs::{
+    get_bio_auth_job, get_bio_auth_queue, start_bio_auth_workers, JobQueue, JobStatus, JobView,
+    QueueMetrics, DEFAULT_JOB_TTL_SECS, DEFAULT_QUEUE_CAPACITY, DEFAULT_WORKERS,
+};
+
+// Load shedding on the analysis routes
+pub use shed::{shed_analysis_load, AnalysisLimits, DEFAULT_MAX_CONCURRENT_ANALYSES, DEFAULT_SHED_RETRY_AFTER_SECS};
+
+// Per-handle bio-auth locks
+pub use locks::HandleLocks;
+
+// Failed-attempt cooldowns
+pub use attempts::{cooldown_ms, AttemptTracker, COOLDOWN_SCHEDULE_MS, FREE_FAILED_ATTEMPTS};
+
+// Per-handle voice baselines
+pub use baseline::{process_enroll_voice, reset_voice_baseline, BaselineStore};
+
+// DSP voice stress pipeline (public for the benchmarks in benches/dsp.rs)
+pub use voice_stress::{
+    analyze_voice_stress, analyze_voice_stress_base64, decode_wav_base64, extract_features, parse_wav,
+    AcousticFeatures, Autocorrelator, Pcm, StressAnalysis, VoiceBaseline,
+};
+
+// Duress cut-off on the combined stress score
+pub use audio::is_under_duress;
+
+// Covert panic phrases
+pub use panic_phrase::PanicPhrase;
+
+// Per-handle policy preferences
+pub use preferences::{
+    process_update_preferences, PreferenceStore, DEFAULT_PREFERENCE_GRANT_TTL_SECS, MAX_STRESS_SENSITIVITY,
+};
+
+// Sealed snapshots of volatile state
+pub use snapshot::{
+    restore_snapshot, save_snapshot, spawn_snapshots, SnapshotConfig, DEFAULT_SNAPSHOT_INTERVAL_SECS,
+    DEFAULT_SNAPSHOT_MAX_AGE_SECS,
+};
+
+// Challenge phrases and TTS playback
+pub use challenge::{
+    get_challenge_audio, process_bio_auth_challenge, ChallengeStore, DEFAULT_CHALLENGE_TTL_SECS,
+};
+
+// Replay protection for bio-auth clips
+pub use replay::{ClipFingerprint, ReplayGuard, DEFAULT_REPLAY_WINDOW_DAYS};
+
+// Reuse of recent analyses
+pub use analysis_cache::{AnalysisCache, AnalysisCacheMetrics, AnalysisKey, DEFAULT_ANALYSIS_CACHE_TTL_SECS};
+
+// Analyzer disagreement reports
+pub use disagreement::{
+    install as install_disagreement_threshold, points_from_env as disagreement_threshold_from_env, Disagreement,
+    StressScores, ANALYZER_DISAGREEMENT_TARGET, DEFAULT_DISAGREEMENT_POINTS,
+};
+
+// Second pass over borderline stress scores
+pub use gray_zone::{GrayZone, GrayZoneMetrics, DEFAULT_GRAY_ZONE};
+
+// Feature flags
+pub use feature_flags::{
+    current as current_feature_flags, install as install_feature_flags, spawn_refresh_from_env as spawn_feature_flag_refresh,
+    FeatureFlag, FeatureFlags, DURESS_DECOY, GRAY_ZONE_RETRY, HUME_STREAM,
+};
+
+// Sealed duress decoy flag
+pub use decoy::open as open_decoy_seal;
+
+// Typed-confirmation auth (accessibility path)
+pub use typed_auth::process_typed_auth;
+
+// Voice-confirmed daily spending limits
+pub use set_limit::{process_set_limit, MAX_SET_LIMIT_STRESS};
+
+// Self-service freeze and delayed unfreeze
+pub use freeze::{process_freeze_wallet, process_request_unfreeze, process_unfreeze_challenge, MAX_UNFREEZE_STRESS};
+
+// Inactivity beneficiary
+pub use beneficiary::{process_set_beneficiary, MAX_INACTIVITY_TIMEOUT_MS, MIN_INACTIVITY_TIMEOUT_MS};
+
+// Deposit proofs for exchanges
+pub use deposit::{process_prove_deposit, MAX_DEPOSIT_ID_LEN};
+
+// Peer allowlists
+pub use acl::{enforce_peer_acl, parse_cidrs, Cidr, PeerAcl, RouteClass, PUBLIC_PATHS};
+
+// Backend → enclave request signing
+pub use request_auth::{
+    request_signature, require_signed_request, RequestAuth, DEFAULT_REQUEST_MAX_SKEW_MS,
+    REQUEST_SIGNATURE_HEADER, REQUEST_TIMESTAMP_HEADER,
+};
+
+// Request validation
+pub use validation::{
+    Validate, ValidatedJson, Validator, MAX_ADDRESS_LABEL_LEN, MAX_AUDIO_BASE64_LEN, MAX_HANDLE_LEN,
+    MIN_AUDIO_BASE64_LEN,
+};
+
+// Compliance screening
+pub use screening::{parse_deny_list, Screener, ScreeningDecision, SCREENING_AUDIT_TARGET};
+
+// Transfer dry runs
+pub use dry_run::TransferSimulator;
+
+// Anti-abuse gate on wallet creation
+pub use registration_gate::{
+    get_registration_gate, pow_zero_bits, solve_pow, CaptchaProvider, GateRequirement, RegistrationGate,
+    DEFAULT_POW_BITS, MAX_GATE_PROOF_LEN,
+};
+
+// Upstream fault injection
+pub use chaos::{install as install_upstream_faults, UpstreamFaults};
+
+// Upstream timeouts and retries
+pub use retry::{install as install_retry_policies, ProviderPolicies, RetryPolicy};
+
+// Analysis confidence and abstention
+pub use confidence::{install as install_confidence_thresholds, Confidence, ConfidenceThresholds};
+
+// Transcription model and prompt
+pub use audio::Expected;
+pub use prompt::{install as install_llm_config, LlmConfig, DEFAULT_LLM_MODEL};
+
+// Upstream API spend and budgets
+pub use costs::{get_metrics, CostLimits, CostTracker, ProviderCosts, UpstreamCostMetrics};
+
+// Streamed bio-auth progress (SSE)
+pub use stream::{process_bio_auth_stream, BioAuthStage, Progress};
+
+// zkLogin address linking
+pub use zklogin::{
+    link_message as zklogin_link_message, process_link_zklogin, ZkLoginVerifier, LINK_MESSAGE_MAX_AGE_MS,
+    ZKLOGIN_PROVIDERS,
+};
+
+// Intent and result code table
+pub use meta::{get_intents, intents_table, IntentsTable};
+
+// Payload layout versions
+pub use versions::{PayloadVersion, PayloadVersions};
+
+// Chain the enclave signs for
+pub use chain::{
+    current as current_chain, install as install_chain, ChainConfig, ChainInfo, Network, MAINNET_CHAIN_ID,
+    TESTNET_CHAIN_ID,
+};
+
+// Mock enclave for local development
+pub use mock::{mock_router, MockOutcome, MockState, MOCK_KEY_SEED, MOCK_OUTCOME_HEADER};
+
+// Upstream endpoints, and pointing them at mock servers in test and mock builds
+pub use endpoints::Endpoint;
+#[cfg(any(test, feature = "mock"))]
+pub use endpoints::set_override as set_endpoint_override;
+
+// Duress shadow mode
+pub use shadow::{get_bio_auth_shadow, ShadowMetrics, ShadowMode};
+
+// Untrusted-input parsers (public for the fuzz targets in fuzz/)
+pub use audio::detect_audio_format;
+pub use handlers::parse_sui_address;
+
 // Re-export handlers (public endpoints)
 pub use handlers::{
     process_create_wallet,
     process_link_address,
+    process_unlink_address,
     process_bio_auth,
     process_transfer,
     process_withdraw,
@@ -60,4 +366,21 @@ mod tests {
         assert_eq!(BioAuthResult::InvalidAmount as u8, 1);
         assert_eq!(BioAuthResult::Duress as u8, 2);
     }
+
+    #[test]
+    fn test_bioauth_method_codes() {
+        assert_eq!(BioAuthMethod::Voice as u8, 0);
+        assert_eq!(BioAuthMethod::Typed as u8, 1);
+    }
+
+    #[test]
+    fn test_parse_sui_address_rejects_non_hex() {
+        let address = format!("0x{}", "ab".repeat(32));
+        assert_eq!(parse_sui_address(&address).unwrap(), [0xab; 32]);
+        // Regressions (fuzz): a multi-byte char split by the old 2-byte str
+        // slicing panicked, and from_str_radix accepted "+f" as a digit pair
+        assert!(parse_sui_address(&format!("a\u{e9}b{}", "00".repeat(30))).is_err());
+        assert!(parse_sui_address(&"+f".repeat(32)).is_err());
+        assert!(parse_sui_address("0xabc").is_err());
+    }
 }
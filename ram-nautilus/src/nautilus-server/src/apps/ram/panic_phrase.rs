@@ -0,0 +1,155 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! Covert panic phrases
+//!
+//! Stress detection guesses at duress; a panic phrase lets the user signal it
+//! on purpose. The phrase is registered with voice enrollment (so it is frozen
+//! with the baseline and can't be swapped out by a coercer later) and, when
+//! the transcript contains it, bio-auth returns Duress whatever the acoustic
+//! stress. Transcripts are noisy, so matching tolerates small spelling and
+//! word-split differences.
+//!
+//! The phrase is a secret: it is never logged, never returned by any route,
+//! and removed from the transcript before it is signed (transcripts end up
+//! on-chain) or kept in diagnostics.
+
+use crate::EnclaveError;
+
+/// Shortest phrase accepted, in letters and digits
+const MIN_PHRASE_CHARS: usize = 8;
+/// Longest phrase accepted, in letters and digits
+const MAX_PHRASE_CHARS: usize = 64;
+/// Words a phrase needs, so it isn't said by accident
+const MIN_PHRASE_WORDS: usize = 2;
+/// Allowed edit distance per this many phrase characters
+const CHARS_PER_EDIT: usize = 5;
+
+/// Lowercase letters and digits of a word; empty for pure punctuation
+fn normalize_word(word: &str) -> String {
+    word.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Character-level edit distance
+fn levenshtein(a: &[char], b: &[char]) -> usize {
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut curr = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = prev[j] + usize::from(ca != cb);
+            curr[j + 1] = substitution.min(prev[j + 1] + 1).min(curr[j] + 1);
+        }
+        prev = curr;
+    }
+    prev[b.len()]
+}
+
+/// A registered panic phrase, normalized for matching
+#[derive(Clone, PartialEq, Eq)]
+pub struct PanicPhrase {
+    words: Vec<String>,
+}
+
+// Keep the phrase out of any `{:?}` that ends up in a log line
+impl std::fmt::Debug for PanicPhrase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("PanicPhrase(<redacted>)")
+    }
+}
+
+impl PanicPhrase {
+    /// Validate a phrase supplied at enrollment
+    pub fn new(phrase: &str) -> Result<Self, EnclaveError> {
+        let words: Vec<String> = phrase
+            .split_whitespace()
+            .map(normalize_word)
+            .filter(|w| !w.is_empty())
+            .collect();
+        let chars: usize = words.iter().map(|w| w.chars().count()).sum();
+        if words.len() < MIN_PHRASE_WORDS || chars < MIN_PHRASE_CHARS {
+            return Err(EnclaveError::GenericError(format!(
+                "Panic phrase needs at least {} words and {} letters",
+                MIN_PHRASE_WORDS, MIN_PHRASE_CHARS
+            )));
+        }
+        if chars > MAX_PHRASE_CHARS {
+            return Err(EnclaveError::GenericError(format!(
+                "Panic phrase can have at most {} letters",
+                MAX_PHRASE_CHARS
+            )));
+        }
+        Ok(Self { words })
+    }
+
+    /// Word range of the transcript that matches the phrase, if any
+    fn find(&self, transcript_words: &[String]) -> Option<std::ops::Range<usize>> {
+        let target: Vec<char> = self.words.concat().chars().collect();
+        let max_edits = (target.len() / CHARS_PER_EDIT).max(1);
+        // A word the speech-to-text split or merged shifts the window size by one
+        let n = self.words.len();
+        (n.saturating_sub(1).max(1)..=n + 1)
+            .flat_map(|size| (0..=transcript_words.len().saturating_sub(size)).map(move |start| start..start + size))
+            .filter(|range| range.end <= transcript_words.len())
+            .map(|range| {
+                let window: Vec<char> = transcript_words[range.clone()].concat().chars().collect();
+                (levenshtein(&window, &target), range)
+            })
+            .filter(|(distance, _)| *distance <= max_edits)
+            .min_by_key(|(distance, range)| (*distance, range.start))
+            .map(|(_, range)| range)
+    }
+
+    /// The transcript with the phrase removed, or `None` if it wasn't said
+    pub fn strip(&self, transcript: &str) -> Option<String> {
+        // Original words paired with their normalized form, punctuation-only words dropped
+        let (original, normalized): (Vec<&str>, Vec<String>) = transcript
+            .split_whitespace()
+            .map(|w| (w, normalize_word(w)))
+            .filter(|(_, n)| !n.is_empty())
+            .unzip();
+        let range = self.find(&normalized)?;
+        Some(
+            original
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| !range.contains(i))
+                .map(|(_, w)| *w)
+                .collect::<Vec<_>>()
+                .join(" "),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_phrase_validation() {
+        assert!(PanicPhrase::new("blue heron").is_ok());
+        assert!(PanicPhrase::new("heron").is_err());
+        assert!(PanicPhrase::new("a b c").is_err());
+        assert!(PanicPhrase::new(&"word ".repeat(20)).is_err());
+        assert_eq!(format!("{:?}", PanicPhrase::new("blue heron").unwrap()), "PanicPhrase(<redacted>)");
+    }
+
+    #[test]
+    fn test_fuzzy_match_and_strip() {
+        let phrase = PanicPhrase::new("Blue heron sunrise").unwrap();
+        assert_eq!(
+            phrase.strip("I confirm sending 5 SUI, blue heron sunrise.").as_deref(),
+            Some("I confirm sending 5 SUI,")
+        );
+        // Misheard letter, split word, merged words
+        assert!(phrase.strip("blue herin sunrise, I confirm sending 5 SUI").is_some());
+        assert!(phrase.strip("I confirm blue her on sunrise sending 5 SUI").is_some());
+        assert!(phrase.strip("blueheron sunrise I confirm").is_some());
+        // Not said
+        assert!(phrase.strip("I confirm sending 5 SUI").is_none());
+        assert!(phrase.strip("blue sky at sunrise").is_none());
+        assert!(phrase.strip("").is_none());
+    }
+}
@@ -0,0 +1,210 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! Per-handle preferences for the enclave's policies
+//!
+//! A handle can tune a few policies without a round trip through the chain:
+//!
+//! - `stress_sensitivity` (-10 to 10, default 0): points added to its stress
+//!   scores before the duress threshold is applied, so a positive value locks
+//!   sooner and a negative one gives a naturally tense voice a little room
+//! - `locale`: the BCP 47 tag challenges, bio-auth and typed auth use when a
+//!   request carries none
+//! - `challenge_length` (4 to 8, default 4): how many of a withdrawal
+//!   destination's last characters the challenge asks for and bio-auth checks
+//!
+//! `POST /preferences` replaces them, and only with the `request_id` of a
+//! voice bio-auth of the same handle that was OK in every respect (no duress,
+//! even decoyed or in shadow mode), used within `DEFAULT_PREFERENCE_GRANT_TTL_SECS`
+//! and only once. Anything else is refused the same way, so a coercer can't
+//! learn from it what the bio-auth found.
+//!
+//! Preferences live only in enclave memory, sealed (see `sealing`) to their
+//! handle under a key generated at boot, so an entry moved to another handle
+//! doesn't open. Like voice baselines they're lost on restart, along with
+//! grants not used yet, and every policy falls back to its default.
+
+use crate::common::ProcessDataRequest;
+use crate::AppState;
+use crate::EnclaveError;
+use axum::extract::State;
+use axum::Json;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+use zeroize::Zeroizing;
+
+use super::audio::{DESTINATION_SUFFIX_LEN, MAX_DESTINATION_SUFFIX_LEN};
+use super::sealing::{Sealed, SealingKey};
+use super::types::{PreferencesResponse, UpdatePreferencesRequest, UserPreferences};
+use super::validation::{normalize_handle, ValidatedJson};
+
+/// Most points a handle can add to (or take off) its stress scores
+pub const MAX_STRESS_SENSITIVITY: i8 = 10;
+/// How long after a successful bio-auth its `request_id` can update preferences
+pub const DEFAULT_PREFERENCE_GRANT_TTL_SECS: u64 = 300;
+
+impl UserPreferences {
+    /// `stress` shifted by the chosen sensitivity, within 0-100
+    pub fn adjust_stress(&self, stress: u8) -> u8 {
+        let sensitivity = self.stress_sensitivity.clamp(-MAX_STRESS_SENSITIVITY, MAX_STRESS_SENSITIVITY);
+        (i16::from(stress) + i16::from(sensitivity)).clamp(0, 100) as u8
+    }
+
+    /// Destination characters to speak for a withdrawal
+    pub fn challenge_length(&self) -> usize {
+        self.challenge_length
+            .map_or(DESTINATION_SUFFIX_LEN, usize::from)
+            .clamp(DESTINATION_SUFFIX_LEN, MAX_DESTINATION_SUFFIX_LEN)
+    }
+}
+
+/// Sealed preferences by normalized handle, and the bio-auths that may still update them
+pub struct PreferenceStore {
+    key: SealingKey,
+    grant_ttl: Duration,
+    sealed: Mutex<HashMap<String, Sealed>>,
+    /// Bio-auth request ID → (handle, when it was granted)
+    grants: Mutex<HashMap<String, (String, Instant)>>,
+}
+
+impl Default for PreferenceStore {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(DEFAULT_PREFERENCE_GRANT_TTL_SECS))
+    }
+}
+
+impl PreferenceStore {
+    /// Empty store under a fresh key; a bio-auth can update preferences for `grant_ttl`
+    pub fn new(grant_ttl: Duration) -> Self {
+        Self {
+            key: SealingKey::generate(),
+            grant_ttl,
+            sealed: Mutex::new(HashMap::new()),
+            grants: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// A handle's preferences, or the defaults if it has set none
+    pub fn get(&self, handle: &str) -> UserPreferences {
+        let key = normalize_handle(handle);
+        let sealed = self.sealed.lock().unwrap();
+        let Some(entry) = sealed.get(&key) else {
+            return UserPreferences::default();
+        };
+        match self.key.open(&key, entry).map(|plaintext| serde_json::from_slice(&plaintext)) {
+            Some(Ok(preferences)) => preferences,
+            _ => {
+                warn!("RAM Preferences: entry for '{}' doesn't open, using the defaults", handle);
+                UserPreferences::default()
+            }
+        }
+    }
+
+    /// Replace a handle's preferences
+    pub fn set(&self, handle: &str, preferences: &UserPreferences) {
+        let plaintext = Zeroizing::new(serde_json::to_vec(preferences).expect("preferences serialize"));
+        let key = normalize_handle(handle);
+        let entry = self.key.seal(&key, &plaintext);
+        self.sealed.lock().unwrap().insert(key, entry);
+    }
+
+    /// Let the bio-auth `request_id` of `handle` update its preferences once
+    pub fn grant(&self, request_id: &str, handle: &str) {
+        let now = Instant::now();
+        let mut grants = self.grants.lock().unwrap();
+        grants.retain(|_, (_, granted_at)| now.duration_since(*granted_at) < self.grant_ttl);
+        grants.insert(request_id.to_string(), (normalize_handle(handle), now));
+    }
+
+    /// Use up the grant of `request_id`: whether it was for `handle` and hadn't expired
+    pub fn take_grant(&self, request_id: &str, handle: &str) -> bool {
+        self.grants
+            .lock()
+            .unwrap()
+            .remove(request_id)
+            .is_some_and(|(granted, granted_at)| {
+                granted == normalize_handle(handle) && granted_at.elapsed() < self.grant_ttl
+            })
+    }
+}
+
+/// Replace a handle's preferences after a successful bio-auth
+///
+/// Request: handle, bio_auth_request_id, preferences
+/// Response: the preferences now stored
+///
+/// POST /preferences
+pub async fn process_update_preferences(
+    State(state): State<Arc<AppState>>,
+    ValidatedJson(request): ValidatedJson<ProcessDataRequest<UpdatePreferencesRequest>>,
+) -> Result<Json<PreferencesResponse>, EnclaveError> {
+    let req = request.payload;
+    if !state.user_preferences.take_grant(&req.bio_auth_request_id, &req.handle) {
+        warn!("RAM Preferences: update for '{}' without a successful bio-auth refused", req.handle);
+        return Err(EnclaveError::Forbidden(
+            "Preferences can only be changed right after a successful bio-auth".to_string(),
+        ));
+    }
+
+    state.user_preferences.set(&req.handle, &req.preferences);
+    info!("RAM Preferences: updated for '{}'", req.handle);
+    Ok(Json(PreferencesResponse { handle: req.handle, preferences: req.preferences }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preferences_are_sealed_per_handle() {
+        let store = PreferenceStore::default();
+        assert_eq!(store.get("alice"), UserPreferences::default());
+
+        let preferences =
+            UserPreferences { stress_sensitivity: 5, locale: Some("vi-VN".to_string()), challenge_length: Some(6) };
+        store.set("alice", &preferences);
+        assert_eq!(store.get("alice"), preferences);
+
+        // Nothing readable in memory, and an entry moved to another handle doesn't open
+        let moved = store.sealed.lock().unwrap().remove("alice").unwrap();
+        assert!(!moved.ciphertext.windows(5).any(|window| window == b"vi-VN"));
+        store.sealed.lock().unwrap().insert("mallory".to_string(), moved);
+        assert_eq!(store.get("mallory"), UserPreferences::default());
+    }
+
+    #[test]
+    fn test_grants_are_single_use_and_per_handle() {
+        let store = PreferenceStore::default();
+        store.grant("req-1", "alice");
+        store.grant("req-2", "alice");
+        assert!(!store.take_grant("unknown", "alice"));
+        assert!(!store.take_grant("req-1", "mallory"));
+        // A refused attempt still uses the grant up
+        assert!(!store.take_grant("req-1", "alice"));
+        assert!(store.take_grant("req-2", "alice"));
+        assert!(!store.take_grant("req-2", "alice"));
+
+        let expired = PreferenceStore::new(Duration::ZERO);
+        expired.grant("req-1", "alice");
+        assert!(!expired.take_grant("req-1", "alice"));
+    }
+
+    #[test]
+    fn test_policies_stay_within_bounds() {
+        let defaults = UserPreferences::default();
+        assert_eq!(defaults.adjust_stress(60), 60);
+        assert_eq!(defaults.challenge_length(), DESTINATION_SUFFIX_LEN);
+
+        let sensitive = UserPreferences { stress_sensitivity: 10, challenge_length: Some(8), ..defaults.clone() };
+        assert_eq!(sensitive.adjust_stress(50), 60);
+        assert_eq!(sensitive.adjust_stress(95), 100);
+        assert_eq!(sensitive.challenge_length(), 8);
+
+        let out_of_bounds = UserPreferences { stress_sensitivity: -100, challenge_length: Some(64), ..defaults };
+        assert_eq!(out_of_bounds.adjust_stress(60), 50);
+        assert_eq!(out_of_bounds.adjust_stress(3), 0);
+        assert_eq!(out_of_bounds.challenge_length(), MAX_DESTINATION_SUFFIX_LEN);
+    }
+}
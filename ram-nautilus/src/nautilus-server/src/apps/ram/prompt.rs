@@ -0,0 +1,179 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! Model and prompt of the OpenRouter transcription call
+//!
+//! `LLM_MODEL` names the OpenRouter model and `LLM_PROMPT_TEMPLATE_PATH` a
+//! minijinja template for its prompt, so either can change without a
+//! rebuild. Templates can use:
+//!
+//! - `expected_amount`: the amount to confirm, written the user's way (none if there isn't one)
+//! - `coin_symbol`: the coin's registry symbol
+//! - `locale`: the request's BCP 47 tag (`en` if it sent none)
+//! - `number_format`: how that locale groups thousands and marks decimals
+//! - `challenge_phrase`: the exact phrase the user was asked to say (none if there isn't one)
+//!
+//! Any other variable is an error. Templates are checked when the server
+//! starts, by rendering them with and without an amount and phrase, so a bad
+//! one stops the server instead of failing every bio-auth. The built-in
+//! template is `prompts/transcription.j2`.
+
+use minijinja::{context, Environment, UndefinedBehavior};
+use std::sync::OnceLock;
+
+use super::amount::RawAmount;
+use super::audio::Expected;
+use super::coin::CoinInfo;
+use super::locale::NumberLocale;
+
+/// OpenRouter model used unless `LLM_MODEL` names another
+pub const DEFAULT_LLM_MODEL: &str = "openai/gpt-4o-audio-preview";
+
+const DEFAULT_PROMPT_TEMPLATE: &str = include_str!("prompts/transcription.j2");
+
+const TEMPLATE_NAME: &str = "prompt";
+
+/// The transcription model and its compiled prompt template
+pub struct LlmConfig {
+    pub model: String,
+    templates: Environment<'static>,
+}
+
+impl Default for LlmConfig {
+    fn default() -> Self {
+        Self::new(DEFAULT_LLM_MODEL.to_string(), DEFAULT_PROMPT_TEMPLATE.to_string())
+            .expect("the built-in prompt template is valid")
+    }
+}
+
+impl std::fmt::Debug for LlmConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LlmConfig").field("model", &self.model).finish_non_exhaustive()
+    }
+}
+
+impl LlmConfig {
+    /// Config for `model` prompted with `template`, if the template renders
+    pub fn new(model: String, template: String) -> Result<Self, String> {
+        let model = model.trim().to_string();
+        if model.is_empty() {
+            return Err("the model must not be empty".to_string());
+        }
+        let mut templates = Environment::new();
+        // Every variable is always set, so an undefined one is a typo
+        templates.set_undefined_behavior(UndefinedBehavior::Strict);
+        templates
+            .add_template_owned(TEMPLATE_NAME, template)
+            .map_err(|e| format!("prompt template: {}", e))?;
+        let config = Self { model, templates };
+
+        let coin = CoinInfo::sui();
+        let samples = [
+            Expected {
+                amount: Some(RawAmount(5_000_000_000)),
+                coin: &coin,
+                locale: Some("vi-VN"),
+                phrase: Some("I confirm sending 5 SUI"),
+            },
+            Expected { amount: None, coin: &coin, locale: None, phrase: None },
+        ];
+        for sample in &samples {
+            if config.prompt(sample)?.trim().is_empty() {
+                return Err("prompt template renders an empty prompt".to_string());
+            }
+        }
+        Ok(config)
+    }
+
+    /// `LLM_MODEL` prompted with the template at `LLM_PROMPT_TEMPLATE_PATH`,
+    /// the built-in ones where unset
+    pub fn from_env() -> Result<Self, String> {
+        let model = std::env::var("LLM_MODEL")
+            .ok()
+            .filter(|model| !model.trim().is_empty())
+            .unwrap_or_else(|| DEFAULT_LLM_MODEL.to_string());
+        let template = match std::env::var("LLM_PROMPT_TEMPLATE_PATH") {
+            Ok(path) if !path.trim().is_empty() => std::fs::read_to_string(path.trim())
+                .map_err(|e| format!("LLM_PROMPT_TEMPLATE_PATH {}: {}", path.trim(), e))?,
+            _ => DEFAULT_PROMPT_TEMPLATE.to_string(),
+        };
+        Self::new(model, template)
+    }
+
+    /// The prompt for a recording that should say `expected`
+    pub fn prompt(&self, expected: &Expected) -> Result<String, String> {
+        let locale = NumberLocale::from_tag(expected.locale);
+        self.templates
+            .get_template(TEMPLATE_NAME)
+            .and_then(|template| {
+                template.render(context! {
+                    expected_amount => expected.amount.map(|amount| locale.format_amount(amount, expected.coin)),
+                    coin_symbol => expected.coin.symbol,
+                    locale => expected.locale.unwrap_or("en"),
+                    number_format => locale.describe(),
+                    challenge_phrase => expected.phrase,
+                })
+            })
+            .map_err(|e| format!("prompt template: {}", e))
+    }
+}
+
+static CONFIG: OnceLock<LlmConfig> = OnceLock::new();
+
+/// Transcribe with `config` for the rest of the process
+pub fn install(config: LlmConfig) {
+    let _ = CONFIG.set(config);
+}
+
+/// The installed config, or the built-in one if none was installed
+pub fn current() -> &'static LlmConfig {
+    CONFIG.get_or_init(LlmConfig::default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_prompt_describes_the_expected_confirmation() {
+        let config = LlmConfig::default();
+        let coin = CoinInfo::sui();
+        let prompt = config
+            .prompt(&Expected {
+                amount: Some(RawAmount(1_234_500_000_000)),
+                coin: &coin,
+                locale: Some("de-DE"),
+                phrase: Some("I confirm sending 1234.5 SUI"),
+            })
+            .unwrap();
+        assert!(prompt.starts_with("You are a voice security analyzer"));
+        assert!(prompt.contains("Expected amount: 1.234,5 SUI (written the user's way: '.' groups thousands"));
+        assert!(prompt.contains("The user was asked to say: \"I confirm sending 1234.5 SUI\""));
+        assert!(prompt.contains("\"transcript\": \"<exact words in original language>\""));
+
+        let prompt = config.prompt(&Expected { amount: None, coin: &coin, locale: None, phrase: None }).unwrap();
+        assert!(prompt.contains("\nNo specific amount expected\n"));
+        assert!(!prompt.contains("asked to say"));
+    }
+
+    #[test]
+    fn test_templates_are_validated() {
+        let custom = LlmConfig::new(
+            " openai/gpt-audio-mini ".to_string(),
+            "Confirm {{ expected_amount or 'nothing' }} in {{ coin_symbol }} ({{ locale }})".to_string(),
+        )
+        .unwrap();
+        assert_eq!(custom.model, "openai/gpt-audio-mini");
+        let coin = CoinInfo::sui();
+        let expected = Expected { amount: Some(RawAmount(5_000_000_000)), coin: &coin, locale: None, phrase: None };
+        assert_eq!(custom.prompt(&expected).unwrap(), "Confirm 5 SUI in SUI (en)");
+
+        // Unparsable, an unknown variable, nothing rendered, no model
+        let model = || DEFAULT_LLM_MODEL.to_string();
+        assert!(LlmConfig::new(model(), "{% if expected_amount %}".to_string()).is_err());
+        let typo = LlmConfig::new(model(), "Say {{ challenge_phrse }}".to_string()).unwrap_err();
+        assert!(typo.contains("undefined"), "{}", typo);
+        assert!(LlmConfig::new(model(), "{# nothing #}".to_string()).is_err());
+        assert!(LlmConfig::new(" ".to_string(), DEFAULT_PROMPT_TEMPLATE.to_string()).is_err());
+    }
+}
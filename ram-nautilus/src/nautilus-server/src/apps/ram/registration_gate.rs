@@ -0,0 +1,316 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! Anti-abuse gate in front of wallet creation
+//!
+//! Handles are first come, first served, so nothing but cost keeps a script
+//! from registering thousands of them. `CREATE_WALLET_GATE`, set when the
+//! image is built so the parent can't turn it off (see `endpoints`), puts a
+//! check in front of `process_create_wallet`:
+//!
+//! - `pow`: the request's `pow_nonce` must make
+//!   `SHA-256("ram-create-wallet:" || handle || ":" || nonce)` start with
+//!   `CREATE_WALLET_POW_BITS` zero bits (20 by default, about a million
+//!   hashes). The work is bound to the handle, so it can't be spent on
+//!   another one, and checking it needs no server state.
+//! - `turnstile` / `hcaptcha`: the request's `captcha_token` is checked with
+//!   the provider's siteverify API using `CAPTCHA_SECRET`. The endpoint is
+//!   fixed in the image (see `endpoints`), so the host can't answer for the
+//!   provider and wave a script through. Tokens are single use,
+//!   so the check isn't retried, and a provider that can't be reached
+//!   refuses the request.
+//! - `off` (the default): no check.
+//!
+//! A missing or failed proof is a 403 `human_check_failed` and nothing is
+//! signed. Clients learn what to send from `GET /create_wallet/gate`.
+
+use axum::extract::State;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::warn;
+
+use super::endpoints::{self, Endpoint};
+use crate::{AppState, EnclaveError};
+
+/// Zero bits required of a proof of work unless `CREATE_WALLET_POW_BITS` says otherwise
+pub const DEFAULT_POW_BITS: u8 = 20;
+/// Hardest proof of work that may be configured (hours of browser time past this)
+const MAX_POW_BITS: u8 = 32;
+/// Longest `pow_nonce` or `captcha_token` accepted
+pub const MAX_GATE_PROOF_LEN: usize = 2048;
+/// Budget for one siteverify call
+const CAPTCHA_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// CAPTCHA services whose tokens can be verified
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CaptchaProvider {
+    Turnstile,
+    Hcaptcha,
+}
+
+impl CaptchaProvider {
+    fn verify_url(self) -> &'static str {
+        match self {
+            CaptchaProvider::Turnstile => "https://challenges.cloudflare.com/turnstile/v0/siteverify",
+            CaptchaProvider::Hcaptcha => "https://api.hcaptcha.com/siteverify",
+        }
+    }
+}
+
+/// Server-side check of CAPTCHA tokens
+pub struct CaptchaVerifier {
+    provider: CaptchaProvider,
+    secret: String,
+    client: reqwest::Client,
+}
+
+/// Answer of a siteverify API (the same shape for Turnstile and hCaptcha)
+#[derive(Deserialize)]
+struct SiteVerify {
+    success: bool,
+    #[serde(default, rename = "error-codes")]
+    error_codes: Vec<String>,
+}
+
+/// The check wallet creations must pass
+#[derive(Default)]
+pub enum RegistrationGate {
+    #[default]
+    Off,
+    ProofOfWork { difficulty_bits: u8 },
+    Captcha(CaptchaVerifier),
+}
+
+/// What `GET /create_wallet/gate` tells clients to send
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum GateRequirement {
+    Off,
+    ProofOfWork { difficulty_bits: u8 },
+    Captcha { provider: CaptchaProvider },
+}
+
+impl RegistrationGate {
+    /// Check CAPTCHA tokens with `provider`
+    pub fn captcha(provider: CaptchaProvider, secret: impl Into<String>) -> Self {
+        Self::Captcha(CaptchaVerifier {
+            provider,
+            secret: secret.into(),
+            client: reqwest::Client::new(),
+        })
+    }
+
+    /// Gate from `CREATE_WALLET_GATE` and `CREATE_WALLET_POW_BITS` as the
+    /// image was built, and `CAPTCHA_SECRET` from the environment. An unknown
+    /// mode, a difficulty out of range or a CAPTCHA without a secret is an
+    /// error, so a misconfigured enclave doesn't start ungated.
+    pub fn from_env() -> Result<Self, String> {
+        let secret = std::env::var("CAPTCHA_SECRET").ok().map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+        Self::configured(
+            endpoints::image_setting(option_env!("CREATE_WALLET_GATE")),
+            endpoints::image_setting(option_env!("CREATE_WALLET_POW_BITS")),
+            secret,
+        )
+    }
+
+    /// Gate for a `mode`, proof-of-work difficulty and CAPTCHA secret
+    fn configured(mode: Option<&str>, pow_bits: Option<&str>, secret: Option<String>) -> Result<Self, String> {
+        let provider = match mode.map(|mode| mode.to_ascii_lowercase()).as_deref() {
+            None | Some("off") => return Ok(Self::Off),
+            Some("pow") => {
+                let difficulty_bits = match pow_bits {
+                    Some(bits) => bits
+                        .parse::<u8>()
+                        .ok()
+                        .filter(|bits| (1..=MAX_POW_BITS).contains(bits))
+                        .ok_or_else(|| format!("CREATE_WALLET_POW_BITS must be 1 to {}, got '{}'", MAX_POW_BITS, bits))?,
+                    None => DEFAULT_POW_BITS,
+                };
+                return Ok(Self::ProofOfWork { difficulty_bits });
+            }
+            Some("turnstile") => CaptchaProvider::Turnstile,
+            Some("hcaptcha") => CaptchaProvider::Hcaptcha,
+            Some(other) => {
+                return Err(format!("unknown CREATE_WALLET_GATE '{}' (off, pow, turnstile or hcaptcha)", other))
+            }
+        };
+        let secret = secret.ok_or("CAPTCHA_SECRET is required for a CAPTCHA gate")?;
+        Ok(Self::captcha(provider, secret))
+    }
+
+    pub fn requirement(&self) -> GateRequirement {
+        match self {
+            RegistrationGate::Off => GateRequirement::Off,
+            RegistrationGate::ProofOfWork { difficulty_bits } => {
+                GateRequirement::ProofOfWork { difficulty_bits: *difficulty_bits }
+            }
+            RegistrationGate::Captcha(verifier) => GateRequirement::Captcha { provider: verifier.provider },
+        }
+    }
+
+    /// Let the creation of `handle` through if its proof passes the gate
+    pub async fn check(
+        &self,
+        handle: &str,
+        pow_nonce: Option<&str>,
+        captcha_token: Option<&str>,
+    ) -> Result<(), EnclaveError> {
+        match self {
+            RegistrationGate::Off => Ok(()),
+            RegistrationGate::ProofOfWork { difficulty_bits } => {
+                let solved = pow_nonce.is_some_and(|nonce| pow_zero_bits(handle, nonce) >= u32::from(*difficulty_bits));
+                if !solved {
+                    return Err(EnclaveError::HumanCheckFailed(format!(
+                        "Creating a wallet needs a proof of work of {} bits in pow_nonce",
+                        difficulty_bits
+                    )));
+                }
+                Ok(())
+            }
+            RegistrationGate::Captcha(verifier) => {
+                let Some(token) = captcha_token.filter(|token| !token.is_empty()) else {
+                    return Err(EnclaveError::HumanCheckFailed(
+                        "Creating a wallet needs a solved CAPTCHA in captcha_token".to_string(),
+                    ));
+                };
+                verifier.verify(handle, token).await
+            }
+        }
+    }
+}
+
+impl CaptchaVerifier {
+    async fn verify(&self, handle: &str, token: &str) -> Result<(), EnclaveError> {
+        let answer = async {
+            let response = self
+                .client
+                .post(endpoints::resolve(Endpoint::CaptchaVerify, self.provider.verify_url()))
+                .timeout(CAPTCHA_TIMEOUT)
+                .form(&[("secret", self.secret.as_str()), ("response", token)])
+                .send()
+                .await
+                .map_err(|e| e.to_string())?;
+            if !response.status().is_success() {
+                return Err(format!("siteverify answered {}", response.status()));
+            }
+            response.json::<SiteVerify>().await.map_err(|e| format!("unreadable siteverify answer: {}", e))
+        }
+        .await;
+
+        match answer {
+            Ok(SiteVerify { success: true, .. }) => Ok(()),
+            Ok(SiteVerify { error_codes, .. }) => {
+                warn!("RAM: CAPTCHA for '{}' was rejected: {:?}", handle, error_codes);
+                Err(EnclaveError::HumanCheckFailed(
+                    "Creating a wallet needs a solved CAPTCHA in captcha_token".to_string(),
+                ))
+            }
+            Err(e) => {
+                warn!("RAM: CAPTCHA for '{}' couldn't be verified: {}", handle, e);
+                Err(EnclaveError::HumanCheckFailed("The CAPTCHA couldn't be verified; try again later".to_string()))
+            }
+        }
+    }
+}
+
+/// Leading zero bits of the proof-of-work digest of `nonce` for `handle`
+pub fn pow_zero_bits(handle: &str, nonce: &str) -> u32 {
+    let digest = Sha256::new()
+        .chain_update(b"ram-create-wallet:")
+        .chain_update(handle.as_bytes())
+        .chain_update(b":")
+        .chain_update(nonce.as_bytes())
+        .finalize();
+    let mut bits = 0;
+    for byte in digest {
+        bits += byte.leading_zeros();
+        if byte != 0 {
+            break;
+        }
+    }
+    bits
+}
+
+/// A nonce with at least `difficulty_bits` of proof of work for `handle`,
+/// the way a client finds one
+pub fn solve_pow(handle: &str, difficulty_bits: u8) -> String {
+    (0u64..)
+        .map(|counter| counter.to_string())
+        .find(|nonce| pow_zero_bits(handle, nonce) >= u32::from(difficulty_bits))
+        .expect("a nonce exists below u64::MAX")
+}
+
+/// What wallet creation currently requires
+///
+/// GET /create_wallet/gate
+pub async fn get_registration_gate(State(state): State<Arc<AppState>>) -> Json<GateRequirement> {
+    Json(state.registration_gate.requirement())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_string_contains, method};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_proof_of_work_is_bound_to_the_handle() {
+        let gate = RegistrationGate::ProofOfWork { difficulty_bits: 12 };
+        let nonce = solve_pow("alice", 12);
+        assert!(gate.check("alice", Some(&nonce), None).await.is_ok());
+
+        // Not for another handle (with overwhelming odds), and never without one
+        let other = (0..).map(|i| format!("{}{}", nonce, i)).find(|n| pow_zero_bits("alice", n) < 12).unwrap();
+        let error = gate.check("alice", Some(&other), None).await.unwrap_err();
+        assert!(matches!(error, EnclaveError::HumanCheckFailed(ref message) if message.contains("12 bits")));
+        assert!(gate.check("alice", None, Some("token")).await.is_err());
+        assert!(RegistrationGate::Off.check("alice", None, None).await.is_ok());
+    }
+
+    #[test]
+    fn test_misconfigured_gates_are_refused() {
+        assert!(matches!(RegistrationGate::configured(None, None, None), Ok(RegistrationGate::Off)));
+        assert!(matches!(
+            RegistrationGate::configured(Some("POW"), None, None),
+            Ok(RegistrationGate::ProofOfWork { difficulty_bits: DEFAULT_POW_BITS })
+        ));
+        assert!(RegistrationGate::configured(Some("pow"), Some("40"), None).is_err());
+        assert!(RegistrationGate::configured(Some("turnstile"), None, None).is_err());
+        assert!(RegistrationGate::configured(Some("captcha"), None, Some("s3cret".into())).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_captcha_tokens_are_verified_server_side() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_string_contains("secret=s3cret"))
+            .and(body_string_contains("response=good"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "success": true })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(
+                serde_json::json!({ "success": false, "error-codes": ["invalid-input-response"] }),
+            ))
+            .mount(&server)
+            .await;
+
+        endpoints::set_override(Endpoint::CaptchaVerify, Some(server.uri()));
+        let gate = RegistrationGate::captcha(CaptchaProvider::Turnstile, "s3cret");
+        assert_eq!(gate.requirement(), GateRequirement::Captcha { provider: CaptchaProvider::Turnstile });
+        assert!(gate.check("alice", None, Some("good")).await.is_ok());
+        assert!(gate.check("alice", None, Some("forged")).await.is_err());
+        assert!(gate.check("alice", None, None).await.is_err());
+
+        // A provider that can't be reached refuses
+        endpoints::set_override(Endpoint::CaptchaVerify, Some("http://127.0.0.1:1".to_string()));
+        let down = RegistrationGate::captcha(CaptchaProvider::Hcaptcha, "s3cret");
+        let error = down.check("alice", None, Some("good")).await.unwrap_err();
+        assert!(error.to_string().contains("couldn't be verified"));
+        endpoints::set_override(Endpoint::CaptchaVerify, None);
+    }
+}
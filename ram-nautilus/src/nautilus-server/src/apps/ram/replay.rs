@@ -0,0 +1,243 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! Replay protection for bio-auth clips
+//!
+//! A recording of "I confirm sending 5 SUI" would otherwise approve every
+//! later transfer of the same amount, whether it was captured off the wire or
+//! played back from the user's phone. Each clip that yields an `Ok` verdict is
+//! fingerprinted and kept per handle for a rolling window; a later clip that
+//! matches one of them is rejected before any analysis runs. This works
+//! whether or not the client used a challenge phrase.
+//!
+//! A fingerprint is the SHA-256 of the clip bytes (exact reuse) plus, for WAV
+//! clips, a coarse energy contour: one bit per segment saying whether the
+//! clip got louder or quieter. Re-encoding, resampling or a small gain change
+//! keep the contour, so near-duplicates are caught too, while two separate
+//! recordings of the same phrase differ in timing well beyond the tolerance.
+//! Fingerprints live only in enclave memory and are lost on restart.
+
+use crate::EnclaveError;
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::Duration;
+use zeroize::Zeroizing;
+
+use super::validation::normalize_handle;
+use super::voice_stress;
+
+/// How long accepted clips are remembered
+pub const DEFAULT_REPLAY_WINDOW_DAYS: u64 = 7;
+/// Most recent accepted clips kept per handle
+const MAX_CLIPS_PER_HANDLE: usize = 64;
+/// Contour length in bits
+const CONTOUR_BITS: usize = 256;
+/// Contours differing in at most this many bits are the same recording
+const NEAR_DUPLICATE_MAX_BITS: u32 = 16;
+/// Durations further apart than this (fraction) are never near-duplicates
+const DURATION_TOLERANCE: f64 = 0.05;
+
+/// Exact digest and (for WAV) energy contour of one clip
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClipFingerprint {
+    digest: [u8; 32],
+    contour: Option<Contour>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Contour {
+    bits: [u64; CONTOUR_BITS / 64],
+    duration_ms: u64,
+}
+
+impl ClipFingerprint {
+    pub fn of(audio: &[u8]) -> Self {
+        Self {
+            digest: Sha256::digest(audio).into(),
+            contour: energy_contour(audio),
+        }
+    }
+
+    /// Same bytes, or a WAV whose contour is within tolerance
+    pub fn matches(&self, other: &ClipFingerprint) -> bool {
+        if self.digest == other.digest {
+            return true;
+        }
+        match (&self.contour, &other.contour) {
+            (Some(a), Some(b)) => {
+                let longer = a.duration_ms.max(b.duration_ms) as f64;
+                let duration_close = a.duration_ms.abs_diff(b.duration_ms) as f64 <= longer * DURATION_TOLERANCE;
+                let distance: u32 = a.bits.iter().zip(&b.bits).map(|(x, y)| (x ^ y).count_ones()).sum();
+                duration_close && distance <= NEAR_DUPLICATE_MAX_BITS
+            }
+            _ => false,
+        }
+    }
+}
+
+/// One bit per segment boundary: did the energy rise? `None` for non-WAV or silent clips
+fn energy_contour(audio: &[u8]) -> Option<Contour> {
+    let (samples, sample_rate) = voice_stress::parse_wav(audio)?;
+    let segment_len = samples.len() / (CONTOUR_BITS + 1);
+    if segment_len == 0 || sample_rate == 0 {
+        return None;
+    }
+
+    let energies: Vec<f64> = samples
+        .chunks_exact(segment_len)
+        .take(CONTOUR_BITS + 1)
+        .map(|segment| segment.iter().map(|s| (*s as f64).powi(2)).sum::<f64>())
+        .collect();
+    // Silence has no shape; leave it to the exact digest
+    if energies.iter().all(|e| *e < 1e-6) {
+        return None;
+    }
+
+    let mut bits = [0u64; CONTOUR_BITS / 64];
+    for (i, pair) in energies.windows(2).enumerate() {
+        if pair[1] > pair[0] {
+            bits[i / 64] |= 1 << (i % 64);
+        }
+    }
+    Some(Contour {
+        bits,
+        duration_ms: samples.len() as u64 * 1000 / sample_rate as u64,
+    })
+}
+
+/// Per-handle rolling window of accepted clip fingerprints, keyed by normalized handle
+pub struct ReplayGuard {
+    window: Duration,
+    accepted: Mutex<HashMap<String, VecDeque<(u64, ClipFingerprint)>>>,
+}
+
+impl Default for ReplayGuard {
+    /// Disabled: nothing is remembered
+    fn default() -> Self {
+        Self::new(Duration::ZERO)
+    }
+}
+
+impl ReplayGuard {
+    /// A zero window disables replay checks
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            accepted: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.window.is_zero()
+    }
+
+    /// Fingerprint a clip and fail with `ReplayDetected` if the handle used it recently
+    ///
+    /// Returns the fingerprint to `record` once the clip is accepted, or `None`
+    /// when disabled or the audio isn't valid base64 (analysis rejects it anyway).
+    pub fn check(&self, handle: &str, audio_base64: &str, now_ms: u64) -> Result<Option<ClipFingerprint>, EnclaveError> {
+        if !self.is_enabled() {
+            return Ok(None);
+        }
+        let Ok(audio) = STANDARD.decode(audio_base64).map(Zeroizing::new) else {
+            return Ok(None);
+        };
+        let fingerprint = ClipFingerprint::of(&audio);
+
+        let mut accepted = self.accepted.lock().unwrap();
+        if let Some(clips) = accepted.get_mut(&normalize_handle(handle)) {
+            self.expire(clips, now_ms);
+            if clips.iter().any(|(_, seen)| seen.matches(&fingerprint)) {
+                return Err(EnclaveError::ReplayDetected(format!(
+                    "This recording was already used to authorize a transfer for '{}'; record a new confirmation",
+                    handle
+                )));
+            }
+        }
+        Ok(Some(fingerprint))
+    }
+
+    /// Remember an accepted clip for the handle
+    pub fn record(&self, handle: &str, fingerprint: ClipFingerprint, now_ms: u64) {
+        let mut accepted = self.accepted.lock().unwrap();
+        let clips = accepted.entry(normalize_handle(handle)).or_default();
+        self.expire(clips, now_ms);
+        if clips.len() == MAX_CLIPS_PER_HANDLE {
+            clips.pop_front();
+        }
+        clips.push_back((now_ms, fingerprint));
+    }
+
+    fn expire(&self, clips: &mut VecDeque<(u64, ClipFingerprint)>, now_ms: u64) {
+        let window_ms = self.window.as_millis() as u64;
+        while clips.front().is_some_and(|(at, _)| now_ms.saturating_sub(*at) > window_ms) {
+            clips.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY_MS: u64 = 86_400_000;
+
+    /// 16-bit mono WAV of a tone whose loudness follows `envelope`
+    fn wav(sample_rate: u32, seconds: f64, gain: f64, envelope: impl Fn(f64) -> f64) -> Vec<u8> {
+        let n = (sample_rate as f64 * seconds) as usize;
+        let mut wav = Vec::new();
+        wav.extend_from_slice(b"RIFF");
+        wav.extend_from_slice(&(36 + n as u32 * 2).to_le_bytes());
+        wav.extend_from_slice(b"WAVEfmt ");
+        wav.extend_from_slice(&16u32.to_le_bytes());
+        wav.extend_from_slice(&1u16.to_le_bytes());
+        wav.extend_from_slice(&1u16.to_le_bytes());
+        wav.extend_from_slice(&sample_rate.to_le_bytes());
+        wav.extend_from_slice(&(sample_rate * 2).to_le_bytes());
+        wav.extend_from_slice(&2u16.to_le_bytes());
+        wav.extend_from_slice(&16u16.to_le_bytes());
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&(n as u32 * 2).to_le_bytes());
+        for i in 0..n {
+            let t = i as f64 / sample_rate as f64;
+            let s = (2.0 * std::f64::consts::PI * 180.0 * t).sin() * envelope(t / seconds) * gain;
+            wav.extend_from_slice(&((s * 32767.0) as i16).to_le_bytes());
+        }
+        wav
+    }
+
+    fn syllables(x: f64) -> f64 {
+        0.2 + 0.6 * (x * 23.0).sin().abs() * (x * 7.0).cos().abs()
+    }
+
+    #[test]
+    fn test_fingerprint_matching() {
+        let original = ClipFingerprint::of(&wav(16_000, 2.0, 1.0, syllables));
+        assert!(original.matches(&original.clone()));
+        // Resampled and quieter copy of the same recording
+        assert!(original.matches(&ClipFingerprint::of(&wav(8_000, 2.0, 0.6, syllables))));
+        // Same length, different delivery
+        let other = ClipFingerprint::of(&wav(16_000, 2.0, 1.0, |x| 0.2 + 0.6 * (x * 11.0).cos().abs()));
+        assert!(!original.matches(&other));
+        // Non-WAV clips only match byte for byte
+        assert!(ClipFingerprint::of(b"webm-bytes").matches(&ClipFingerprint::of(b"webm-bytes")));
+        assert!(!ClipFingerprint::of(b"webm-bytes").matches(&ClipFingerprint::of(b"webm-bytez")));
+    }
+
+    #[test]
+    fn test_replay_window() {
+        let guard = ReplayGuard::new(Duration::from_secs(DEFAULT_REPLAY_WINDOW_DAYS * 86_400));
+        let clip = STANDARD.encode(wav(16_000, 2.0, 1.0, syllables));
+
+        let fingerprint = guard.check("alice", &clip, 0).unwrap().unwrap();
+        guard.record("alice", fingerprint, 0);
+        assert!(matches!(guard.check("alice", &clip, DAY_MS), Err(EnclaveError::ReplayDetected(_))));
+        // Other handles and expired entries are unaffected
+        assert!(guard.check("bob", &clip, DAY_MS).is_ok());
+        assert!(guard.check("alice", &clip, 8 * DAY_MS).is_ok());
+
+        assert!(ReplayGuard::default().check("alice", &clip, 0).unwrap().is_none());
+    }
+}
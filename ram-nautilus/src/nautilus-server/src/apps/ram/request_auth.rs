@@ -0,0 +1,217 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! Request signing on the backend → enclave hop
+//!
+//! The enclave signs whatever it is asked to, so it must only be asked by the
+//! backend. With `REQUEST_SIGNING_KEY` set, every request must carry an
+//! HMAC-SHA256 over its timestamp, method, path, query string and body, keyed
+//! with a secret provisioned to both sides at deploy. A security group that
+//! accidentally exposes the enclave port then yields 401s instead of signed
+//! payloads.
+//!
+//! ```text
+//! x-ram-timestamp: <unix ms>
+//! x-ram-signature: hex(HMAC-SHA256(key, "<timestamp>\n<METHOD>\n<path>\n<query>\n" || body))
+//! ```
+//!
+//! The query is signed in canonical form: its `&`-separated pairs, as sent,
+//! sorted and joined with `&` (empty without one), so parameters can't be
+//! added or changed but their order doesn't matter.
+//!
+//! Timestamps outside a short window are rejected to limit replays. Routes
+//! that only expose public information stay open.
+
+use crate::EnclaveError;
+use axum::body::Body;
+use axum::extract::{Request, State};
+use axum::http::HeaderMap;
+use axum::middleware::Next;
+use axum::response::Response;
+use fastcrypto::encoding::{Encoding, Hex};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::sync::Arc;
+use zeroize::Zeroizing;
+use tracing::warn;
+
+use super::acl::PUBLIC_PATHS;
+
+/// Unix-ms timestamp the signature covers
+pub const REQUEST_TIMESTAMP_HEADER: &str = "x-ram-timestamp";
+/// Hex HMAC-SHA256 of the canonical request
+pub const REQUEST_SIGNATURE_HEADER: &str = "x-ram-signature";
+/// How far a request timestamp may drift from the enclave clock
+pub const DEFAULT_REQUEST_MAX_SKEW_MS: u64 = 30_000;
+/// Largest body buffered for verification (base64 audio clips included)
+const MAX_SIGNED_BODY_BYTES: usize = 32 * 1024 * 1024;
+
+/// A query string's pairs, sorted and rejoined
+fn canonical_query(query: &str) -> String {
+    let mut pairs: Vec<&str> = query.split('&').filter(|pair| !pair.is_empty()).collect();
+    pairs.sort_unstable();
+    pairs.join("&")
+}
+
+/// Bytes the signature covers; `path` may carry a `?query`
+fn canonical_request(timestamp_ms: u64, method: &str, path: &str, body: &[u8]) -> Vec<u8> {
+    let (path, query) = path.split_once('?').unwrap_or((path, ""));
+    let mut message =
+        format!("{}\n{}\n{}\n{}\n", timestamp_ms, method.to_uppercase(), path, canonical_query(query)).into_bytes();
+    message.extend_from_slice(body);
+    message
+}
+
+fn mac(key: &[u8]) -> Hmac<Sha256> {
+    Hmac::<Sha256>::new_from_slice(key).expect("HMAC can take key of any size")
+}
+
+/// Hex signature of a request, as the backend computes it
+pub fn request_signature(key: &str, timestamp_ms: u64, method: &str, path: &str, body: &[u8]) -> String {
+    let mut mac = mac(key.as_bytes());
+    mac.update(&canonical_request(timestamp_ms, method, path, body));
+    Hex::encode(mac.finalize().into_bytes())
+}
+
+/// Request-signing key and replay window
+pub struct RequestAuth {
+    key: Zeroizing<String>,
+    max_skew_ms: u64,
+}
+
+impl RequestAuth {
+    /// An empty key disables verification
+    pub fn new(key: String, max_skew_ms: u64) -> Self {
+        Self { key: Zeroizing::new(key), max_skew_ms }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.key.is_empty()
+    }
+
+    /// Check the signature headers against a buffered request
+    pub fn verify(
+        &self,
+        headers: &HeaderMap,
+        method: &str,
+        path: &str,
+        body: &[u8],
+        now_ms: u64,
+    ) -> Result<(), EnclaveError> {
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .ok_or_else(|| EnclaveError::Unauthorized(format!("Missing {} header", name)))
+        };
+
+        let timestamp_ms: u64 = header(REQUEST_TIMESTAMP_HEADER)?
+            .parse()
+            .map_err(|_| EnclaveError::Unauthorized(format!("Invalid {} header", REQUEST_TIMESTAMP_HEADER)))?;
+        if timestamp_ms.abs_diff(now_ms) > self.max_skew_ms {
+            return Err(EnclaveError::Unauthorized("Request timestamp outside the allowed window".to_string()));
+        }
+
+        let signature = Hex::decode(header(REQUEST_SIGNATURE_HEADER)?)
+            .map_err(|_| EnclaveError::Unauthorized(format!("Invalid {} header", REQUEST_SIGNATURE_HEADER)))?;
+        let mut mac = mac(self.key.as_bytes());
+        mac.update(&canonical_request(timestamp_ms, method, path, body));
+        // Constant-time comparison
+        mac.verify_slice(&signature)
+            .map_err(|_| EnclaveError::Unauthorized("Invalid request signature".to_string()))
+    }
+}
+
+/// Middleware rejecting unsigned or mis-signed requests when a key is configured
+pub async fn require_signed_request(
+    State(auth): State<Arc<RequestAuth>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, EnclaveError> {
+    if !auth.is_enabled() || PUBLIC_PATHS.contains(&request.uri().path()) {
+        return Ok(next.run(request).await);
+    }
+
+    let (parts, body) = request.into_parts();
+    let body = axum::body::to_bytes(body, MAX_SIGNED_BODY_BYTES)
+        .await
+        .map_err(|e| EnclaveError::GenericError(format!("Failed to read request body: {}", e)))?;
+
+    let now_ms = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_err(|e| EnclaveError::GenericError(format!("Failed to get timestamp: {}", e)))?
+        .as_millis() as u64;
+    let path = parts.uri.path_and_query().map_or(parts.uri.path(), |target| target.as_str());
+    if let Err(e) = auth.verify(&parts.headers, parts.method.as_str(), path, &body, now_ms) {
+        warn!("RAM Request auth: rejected {} {}: {}", parts.method, parts.uri.path(), e);
+        return Err(e);
+    }
+
+    Ok(next.run(Request::from_parts(parts, Body::from(body))).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 1_700_000_000_000;
+    const BODY: &[u8] = br#"{"payload":{}}"#;
+
+    fn signed_headers(timestamp_ms: u64, signature: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(REQUEST_TIMESTAMP_HEADER, timestamp_ms.to_string().parse().unwrap());
+        headers.insert(REQUEST_SIGNATURE_HEADER, signature.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_signature_vector() {
+        // Same vector as ram-backend's signing tests, so both sides stay in sync
+        assert_eq!(
+            request_signature("test-signing-key", NOW, "POST", "/transfer", BODY),
+            "4838b9eda1f27329d221144f12bc949e5ef275b27c40e8f87b3299bda1c1d1a9"
+        );
+        assert_eq!(
+            request_signature("test-signing-key", NOW, "GET", "/health?handle=alice&b=2", b""),
+            "760f85921c72c0d6c9f154fdf699d63262ce87d09285bce8b068830f3d0129fb"
+        );
+    }
+
+    #[test]
+    fn test_verify() {
+        let auth = RequestAuth::new("test-signing-key".to_string(), DEFAULT_REQUEST_MAX_SKEW_MS);
+        let signature = request_signature("test-signing-key", NOW, "POST", "/transfer", BODY);
+
+        let headers = signed_headers(NOW, &signature);
+        assert!(auth.verify(&headers, "POST", "/transfer", BODY, NOW + 1_000).is_ok());
+        // Body, path or key changed
+        assert!(auth.verify(&headers, "POST", "/transfer", br#"{"payload":{"amount":1}}"#, NOW).is_err());
+        assert!(auth.verify(&headers, "POST", "/withdraw", BODY, NOW).is_err());
+        let other = RequestAuth::new("other-key".to_string(), DEFAULT_REQUEST_MAX_SKEW_MS);
+        assert!(other.verify(&headers, "POST", "/transfer", BODY, NOW).is_err());
+        // Stale
+        assert!(auth.verify(&headers, "POST", "/transfer", BODY, NOW + 60_000).is_err());
+        // Unsigned
+        assert!(auth.verify(&HeaderMap::new(), "POST", "/transfer", BODY, NOW).is_err());
+    }
+
+    #[test]
+    fn test_query_is_signed() {
+        let auth = RequestAuth::new("test-signing-key".to_string(), DEFAULT_REQUEST_MAX_SKEW_MS);
+        let target = "/bio_auth/diagnostics?handle=alice&limit=5";
+        let headers = signed_headers(NOW, &request_signature("test-signing-key", NOW, "GET", target, b""));
+
+        assert!(auth.verify(&headers, "GET", target, b"", NOW).is_ok());
+        // Reordered pairs sign the same
+        assert!(auth.verify(&headers, "GET", "/bio_auth/diagnostics?limit=5&handle=alice", b"", NOW).is_ok());
+        // Changed, added or dropped parameters don't
+        for tampered in [
+            "/bio_auth/diagnostics?handle=bob&limit=5",
+            "/bio_auth/diagnostics?handle=alice&limit=5&admin=1",
+            "/bio_auth/diagnostics?handle=alice",
+            "/bio_auth/diagnostics",
+        ] {
+            assert!(auth.verify(&headers, "GET", tampered, b"", NOW).is_err(), "{}", tampered);
+        }
+    }
+}
@@ -0,0 +1,257 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! Timeouts and retries for upstream calls
+//!
+//! OpenRouter, Hume batch jobs, the Sui RPC and the screening API each get a [`RetryPolicy`]: a
+//! per-attempt timeout, a number of retries and an exponential backoff.
+//! Requests go through [`send`], which retries connection failures,
+//! timeouts, 429s and 5xx answers; any other answer (including a 4xx) goes
+//! straight back to the caller. Policies are read from
+//! `{OPENROUTER,HUME,SUI_RPC,SCREENING}_{TIMEOUT_MS,MAX_RETRIES,BACKOFF_MS}` and
+//! installed once at startup; until then the defaults apply. With the
+//! `cassettes` feature, OpenRouter and Hume attempts can be recorded or
+//! replayed (see `cassette`).
+
+use reqwest::{RequestBuilder, Response, StatusCode};
+use std::sync::OnceLock;
+use std::time::Duration;
+use tracing::warn;
+
+/// Longest wait between two attempts, however many retries are configured
+const MAX_BACKOFF: Duration = Duration::from_secs(10);
+
+/// How one provider's requests are timed out and retried
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Limit on each attempt, from sending the request to reading the body
+    pub timeout: Duration,
+    /// Attempts after the first one
+    pub max_retries: u32,
+    /// Wait before the first retry; doubles on each one after
+    pub backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// Wait before retry `retry` (1 for the first)
+    pub fn delay(&self, retry: u32) -> Duration {
+        let factor = 2u32.saturating_pow(retry.saturating_sub(1));
+        self.backoff.saturating_mul(factor).min(MAX_BACKOFF)
+    }
+
+    /// This policy with any of `{prefix}_TIMEOUT_MS`, `{prefix}_MAX_RETRIES`
+    /// and `{prefix}_BACKOFF_MS` that are set; invalid values are ignored
+    fn from_env(prefix: &str, defaults: Self) -> Self {
+        let var = |name: &str| {
+            std::env::var(format!("{}_{}", prefix, name)).ok().and_then(|v| v.trim().parse::<u64>().ok())
+        };
+        Self {
+            timeout: var("TIMEOUT_MS").filter(|ms| *ms > 0).map_or(defaults.timeout, Duration::from_millis),
+            max_retries: var("MAX_RETRIES").map_or(defaults.max_retries, |n| n.min(u32::MAX as u64) as u32),
+            backoff: var("BACKOFF_MS").map_or(defaults.backoff, Duration::from_millis),
+        }
+    }
+}
+
+/// Retry policy of every upstream provider
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProviderPolicies {
+    pub openrouter: RetryPolicy,
+    pub hume: RetryPolicy,
+    pub sui_rpc: RetryPolicy,
+    pub screening: RetryPolicy,
+}
+
+impl Default for ProviderPolicies {
+    fn default() -> Self {
+        Self {
+            // Audio transcription is slow; one retry keeps a bio-auth under a minute
+            openrouter: RetryPolicy {
+                timeout: Duration::from_secs(30),
+                max_retries: 1,
+                backoff: Duration::from_millis(500),
+            },
+            hume: RetryPolicy {
+                timeout: Duration::from_secs(15),
+                max_retries: 1,
+                backoff: Duration::from_millis(500),
+            },
+            sui_rpc: RetryPolicy {
+                timeout: Duration::from_secs(10),
+                max_retries: 2,
+                backoff: Duration::from_millis(200),
+            },
+            // Every transfer waits on it
+            screening: RetryPolicy {
+                timeout: Duration::from_secs(5),
+                max_retries: 1,
+                backoff: Duration::from_millis(200),
+            },
+        }
+    }
+}
+
+impl ProviderPolicies {
+    /// Policies from `OPENROUTER_*`, `HUME_*`, `SUI_RPC_*` and `SCREENING_*` (`TIMEOUT_MS`,
+    /// `MAX_RETRIES`, `BACKOFF_MS`); unset or invalid values keep the defaults
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            openrouter: RetryPolicy::from_env("OPENROUTER", defaults.openrouter),
+            hume: RetryPolicy::from_env("HUME", defaults.hume),
+            sui_rpc: RetryPolicy::from_env("SUI_RPC", defaults.sui_rpc),
+            screening: RetryPolicy::from_env("SCREENING", defaults.screening),
+        }
+    }
+
+    fn get(&self, provider: Provider) -> &RetryPolicy {
+        match provider {
+            Provider::OpenRouter => &self.openrouter,
+            Provider::Hume => &self.hume,
+            Provider::SuiRpc => &self.sui_rpc,
+            Provider::Screening => &self.screening,
+        }
+    }
+}
+
+static POLICIES: OnceLock<ProviderPolicies> = OnceLock::new();
+
+/// Use `policies` for the rest of the process
+pub fn install(policies: ProviderPolicies) {
+    let _ = POLICIES.set(policies);
+}
+
+/// Upstream providers with a retry policy
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Provider {
+    OpenRouter,
+    Hume,
+    SuiRpc,
+    Screening,
+}
+
+impl Provider {
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Provider::OpenRouter => "openrouter",
+            Provider::Hume => "hume",
+            Provider::SuiRpc => "sui_rpc",
+            Provider::Screening => "screening",
+        }
+    }
+}
+
+/// Send the request `build` makes under `provider`'s policy, rebuilding it
+/// for each retry (multipart bodies can't be cloned)
+pub(crate) async fn send(
+    provider: Provider,
+    build: impl FnMut() -> RequestBuilder,
+) -> Result<Response, reqwest::Error> {
+    let default = ProviderPolicies::default();
+    let policy = POLICIES.get().unwrap_or(&default).get(provider);
+    send_with(provider, policy, build).await
+}
+
+async fn send_with(
+    provider: Provider,
+    policy: &RetryPolicy,
+    mut build: impl FnMut() -> RequestBuilder,
+) -> Result<Response, reqwest::Error> {
+    let mut retry = 0;
+    loop {
+        let request = build().timeout(policy.timeout);
+        #[cfg(feature = "cassettes")]
+        let result = match super::cassette::active(provider) {
+            Some((cassette, mode)) => super::cassette::send(provider, request, &cassette, mode).await,
+            None => request.send().await,
+        };
+        #[cfg(not(feature = "cassettes"))]
+        let result = request.send().await;
+        let retryable = match &result {
+            Ok(response) => is_retryable_status(response.status()),
+            Err(e) => e.is_timeout() || e.is_connect() || e.is_request(),
+        };
+        if !retryable || retry >= policy.max_retries {
+            return result;
+        }
+        retry += 1;
+        let delay = policy.delay(retry);
+        match &result {
+            Ok(response) => warn!("RAM: {:?} returned {}, retry {} in {:?}", provider, response.status(), retry, delay),
+            Err(e) => warn!("RAM: {:?} request failed ({}), retry {} in {:?}", provider, e, retry, delay),
+        }
+        tokio::time::sleep(delay).await;
+    }
+}
+
+fn is_retryable_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::method;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const FAST: RetryPolicy = RetryPolicy {
+        timeout: Duration::from_secs(5),
+        max_retries: 2,
+        backoff: Duration::from_millis(1),
+    };
+
+    #[test]
+    fn test_backoff_doubles_up_to_the_cap() {
+        let policy = RetryPolicy { backoff: Duration::from_millis(500), ..FAST };
+        assert_eq!(policy.delay(1), Duration::from_millis(500));
+        assert_eq!(policy.delay(2), Duration::from_millis(1000));
+        assert_eq!(policy.delay(3), Duration::from_millis(2000));
+        assert_eq!(policy.delay(40), MAX_BACKOFF);
+    }
+
+    #[tokio::test]
+    async fn test_server_errors_are_retried_until_success() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET")).respond_with(ResponseTemplate::new(200)).mount(&server).await;
+
+        let client = reqwest::Client::new();
+        let response = send_with(Provider::SuiRpc, &FAST, || client.get(server.uri())).await.unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(server.received_requests().await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_client_errors_and_exhausted_retries_are_returned() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET")).respond_with(ResponseTemplate::new(400)).mount(&server).await;
+        let client = reqwest::Client::new();
+        let response = send_with(Provider::OpenRouter, &FAST, || client.get(server.uri())).await.unwrap();
+        assert_eq!(response.status(), 400);
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+
+        server.reset().await;
+        Mock::given(method("GET")).respond_with(ResponseTemplate::new(429)).mount(&server).await;
+        let response = send_with(Provider::Hume, &FAST, || client.get(server.uri())).await.unwrap();
+        assert_eq!(response.status(), 429);
+        assert_eq!(server.received_requests().await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_attempts_time_out() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(2)))
+            .mount(&server)
+            .await;
+        let policy = RetryPolicy { timeout: Duration::from_millis(50), max_retries: 1, ..FAST };
+        let client = reqwest::Client::new();
+        let error = send_with(Provider::SuiRpc, &policy, || client.get(server.uri())).await.unwrap_err();
+        assert!(error.is_timeout());
+        assert_eq!(server.received_requests().await.unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,261 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! Sanctions / deny-list screening before transfers and withdrawals are signed
+//!
+//! `process_transfer` screens its recipient and `process_withdraw` the wallet
+//! being emptied (the coin goes to one of its linked addresses, which the
+//! enclave never sees). A subject is checked against a local deny list, then
+//! against an external screening API if one is configured; either can be
+//! used alone. A match is answered with a 451 `compliance_blocked` and
+//! nothing is signed.
+//!
+//! The deny list is a file of handles and Sui addresses, one per line (`#`
+//! starts a comment), read at startup from `SCREENING_DENY_LIST_PATH`. The
+//! API at `SCREENING_API_URL` gets `POST {"subject", "action"}` (with
+//! `SCREENING_API_KEY` as a bearer token) and answers
+//! `{"blocked": bool, "reason"?: string}`. If it can't be reached the
+//! request is blocked too, unless `SCREENING_FAIL_OPEN=true`. All but the API
+//! key are set when the image is built (see `endpoints`), so the parent can't
+//! empty the deny list, point the API elsewhere or make screening fail open;
+//! the deny list file has to be part of the image too.
+//!
+//! Every decision is logged under the `screening_audit` target with the
+//! action, subject, outcome and the source that decided it.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use tracing::{info, warn};
+
+use super::endpoints::{self, Endpoint};
+use super::retry::{self, Provider};
+use super::validation::normalize_handle;
+use crate::EnclaveError;
+
+/// Log target of screening decisions
+pub const SCREENING_AUDIT_TARGET: &str = "screening_audit";
+
+/// External screening service
+struct ScreeningApi {
+    url: String,
+    api_key: String,
+    client: reqwest::Client,
+}
+
+#[derive(Serialize)]
+struct ScreeningQuery<'a> {
+    subject: &'a str,
+    action: &'a str,
+}
+
+#[derive(Deserialize)]
+struct ScreeningAnswer {
+    blocked: bool,
+    #[serde(default)]
+    reason: Option<String>,
+}
+
+/// Outcome of screening one subject
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScreeningDecision {
+    Allowed,
+    Blocked { source: &'static str, reason: String },
+    /// The API couldn't answer; blocked unless failing open
+    Unavailable { error: String },
+}
+
+/// Deny-list and screening-API checks
+#[derive(Default)]
+pub struct Screener {
+    deny_list: HashSet<String>,
+    api: Option<ScreeningApi>,
+    fail_open: bool,
+}
+
+impl Screener {
+    /// Screen against `entries` only (normalized like handles)
+    pub fn with_deny_list<I: IntoIterator<Item = S>, S: AsRef<str>>(entries: I) -> Self {
+        Self { deny_list: entries.into_iter().map(|e| normalize_handle(e.as_ref().trim())).collect(), ..Self::default() }
+    }
+
+    /// Also ask the screening API at `url`
+    pub fn with_api(mut self, url: impl Into<String>, api_key: impl Into<String>) -> Self {
+        self.api = Some(ScreeningApi { url: url.into(), api_key: api_key.into(), client: reqwest::Client::new() });
+        self
+    }
+
+    /// Let requests through when the screening API can't be reached
+    pub fn with_fail_open(mut self, fail_open: bool) -> Self {
+        self.fail_open = fail_open;
+        self
+    }
+
+    /// Screener from `SCREENING_DENY_LIST_PATH`, `SCREENING_API_URL` and
+    /// `SCREENING_FAIL_OPEN` as the image was built, and `SCREENING_API_KEY`
+    /// from the environment; disabled if neither source is set. An
+    /// unreadable deny list is an error, so a misconfigured enclave doesn't
+    /// start unscreened.
+    pub fn from_env() -> Result<Self, String> {
+        let mut screener = match endpoints::image_setting(option_env!("SCREENING_DENY_LIST_PATH")) {
+            Some(path) => {
+                let contents =
+                    std::fs::read_to_string(path).map_err(|e| format!("can't read deny list {}: {}", path, e))?;
+                Self::with_deny_list(parse_deny_list(&contents))
+            }
+            None => Self::default(),
+        };
+        if let Some(url) = endpoints::configured(Endpoint::Screening) {
+            let api_key = std::env::var("SCREENING_API_KEY").map(|key| key.trim().to_string()).unwrap_or_default();
+            screener = screener.with_api(url, api_key);
+        }
+        let fail_open = endpoints::image_setting(option_env!("SCREENING_FAIL_OPEN"));
+        Ok(screener.with_fail_open(fail_open.is_some_and(|v| v.eq_ignore_ascii_case("true"))))
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.deny_list.is_empty() || self.api.is_some()
+    }
+
+    pub fn deny_list_len(&self) -> usize {
+        self.deny_list.len()
+    }
+
+    pub fn has_api(&self) -> bool {
+        self.api.is_some()
+    }
+
+    /// Decide on `subject` (a handle or address) for `action`
+    pub async fn screen(&self, action: &str, subject: &str) -> ScreeningDecision {
+        let subject = normalize_handle(subject.trim());
+        if self.deny_list.contains(&subject) {
+            return ScreeningDecision::Blocked { source: "deny_list", reason: "listed".to_string() };
+        }
+        let Some(api) = &self.api else {
+            return ScreeningDecision::Allowed;
+        };
+
+        let query = ScreeningQuery { subject: &subject, action };
+        let answer = async {
+            let response = retry::send(Provider::Screening, || {
+                api.client.post(&api.url).bearer_auth(&api.api_key).json(&query)
+            })
+            .await
+            .map_err(|e| e.to_string())?;
+            if !response.status().is_success() {
+                return Err(format!("screening API answered {}", response.status()));
+            }
+            response.json::<ScreeningAnswer>().await.map_err(|e| format!("unreadable screening answer: {}", e))
+        }
+        .await;
+
+        match answer {
+            Ok(ScreeningAnswer { blocked: false, .. }) => ScreeningDecision::Allowed,
+            Ok(ScreeningAnswer { blocked: true, reason }) => ScreeningDecision::Blocked {
+                source: "api",
+                reason: reason.unwrap_or_else(|| "listed".to_string()),
+            },
+            Err(error) => ScreeningDecision::Unavailable { error },
+        }
+    }
+
+    /// Screen `subject` for `action`, log the decision for audit, and fail
+    /// with `ComplianceBlocked` unless it's allowed
+    pub async fn check(&self, action: &str, subject: &str) -> Result<(), EnclaveError> {
+        if !self.is_enabled() {
+            return Ok(());
+        }
+        match self.screen(action, subject).await {
+            ScreeningDecision::Allowed => {
+                info!(target: SCREENING_AUDIT_TARGET, action, subject, decision = "allow", "Screening passed");
+                Ok(())
+            }
+            ScreeningDecision::Blocked { source, reason } => {
+                warn!(
+                    target: SCREENING_AUDIT_TARGET,
+                    action, subject, decision = "block", source, reason = %reason, "Screening blocked request"
+                );
+                Err(EnclaveError::ComplianceBlocked(format!("{} is blocked by compliance screening", subject)))
+            }
+            ScreeningDecision::Unavailable { error } if self.fail_open => {
+                warn!(
+                    target: SCREENING_AUDIT_TARGET,
+                    action, subject, decision = "allow", source = "fail_open", error = %error,
+                    "Screening API unavailable, allowing request"
+                );
+                Ok(())
+            }
+            ScreeningDecision::Unavailable { error } => {
+                warn!(
+                    target: SCREENING_AUDIT_TARGET,
+                    action, subject, decision = "block", source = "unavailable", error = %error,
+                    "Screening API unavailable, blocking request"
+                );
+                Err(EnclaveError::ComplianceBlocked("Compliance screening is unavailable; try again later".to_string()))
+            }
+        }
+    }
+}
+
+/// Entries of a deny-list file: one per line, blank lines and `#` comments skipped
+pub fn parse_deny_list(contents: &str) -> Vec<&str> {
+    contents
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or("").trim())
+        .filter(|entry| !entry.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_json, header, method};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn test_deny_list_file_skips_comments() {
+        let contents = "# OFAC export\nmallory\n\n0xABC  # exchange hot wallet\n   \n";
+        assert_eq!(parse_deny_list(contents), vec!["mallory", "0xABC"]);
+    }
+
+    #[tokio::test]
+    async fn test_deny_list_matches_normalized_subjects() {
+        let screener = Screener::with_deny_list(["Mallory", "0xABC"]);
+        assert!(screener.is_enabled());
+        assert!(matches!(screener.screen("transfer", "ＭＡＬＬＯＲＹ").await, ScreeningDecision::Blocked { .. }));
+        assert!(matches!(screener.check("transfer", "0xabc").await, Err(EnclaveError::ComplianceBlocked(_))));
+        assert!(screener.check("transfer", "bob").await.is_ok());
+
+        // Nothing configured: everything passes without a lookup
+        assert!(!Screener::default().is_enabled());
+        assert!(Screener::default().check("withdraw", "mallory").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_api_decides_and_unavailability_fails_closed() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(header("authorization", "Bearer key"))
+            .and(body_json(serde_json::json!({ "subject": "eve", "action": "transfer" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "blocked": true, "reason": "SDN" })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(body_json(serde_json::json!({ "subject": "bob", "action": "transfer" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "blocked": false })))
+            .mount(&server)
+            .await;
+
+        let screener = Screener::default().with_api(server.uri(), "key");
+        assert_eq!(
+            screener.screen("transfer", "eve").await,
+            ScreeningDecision::Blocked { source: "api", reason: "SDN".to_string() }
+        );
+        assert!(screener.check("transfer", "bob").await.is_ok());
+
+        // Unmatched requests get a 404 from the mock: unavailable
+        assert!(matches!(screener.screen("withdraw", "carol").await, ScreeningDecision::Unavailable { .. }));
+        assert!(matches!(screener.check("withdraw", "carol").await, Err(EnclaveError::ComplianceBlocked(_))));
+        let fail_open = Screener::default().with_api(server.uri(), "key").with_fail_open(true);
+        assert!(fail_open.check("withdraw", "carol").await.is_ok());
+    }
+}
@@ -0,0 +1,113 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! Authenticated encryption of state the enclave keeps sealed
+//!
+//! AES-256-GCM under a random 96-bit nonce, with the blob version and a
+//! context (a handle, or the snapshot label) as associated data, so a blob
+//! moved to another context doesn't open. The key is a 32-byte secret:
+//! generated at boot for what may be lost on restart (preferences), or given
+//! for what must open after one (snapshots).
+
+use fastcrypto::aes::{Aes256Gcm, AesKey, AuthenticatedCipher, InitializationVector};
+use fastcrypto::traits::ToFromBytes;
+use typenum::{U12, U32};
+use zeroize::Zeroizing;
+
+/// Layout of sealed blobs; bumped with the cipher or the associated data
+const SEALED_VERSION: u8 = 1;
+/// Bytes of the random nonce each sealing uses
+pub(super) const NONCE_LEN: usize = 12;
+/// Bytes of the GCM tag at the end of the ciphertext
+const TAG_LEN: usize = 16;
+
+/// Encrypted and authenticated bytes
+pub(super) struct Sealed {
+    version: u8,
+    nonce: [u8; NONCE_LEN],
+    /// Ciphertext with its tag appended
+    ciphertext: Vec<u8>,
+}
+
+impl Sealed {
+    /// Version, nonce and ciphertext, in that order
+    pub(super) fn to_bytes(&self) -> Vec<u8> {
+        [&[self.version], self.nonce.as_slice(), &self.ciphertext].concat()
+    }
+
+    /// The parts of `to_bytes`, or None if `bytes` is too short to hold them
+    pub(super) fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let (&version, rest) = bytes.split_first()?;
+        let (nonce, ciphertext) = rest.split_first_chunk::<NONCE_LEN>()?;
+        (ciphertext.len() >= TAG_LEN).then(|| Self { version, nonce: *nonce, ciphertext: ciphertext.to_vec() })
+    }
+}
+
+/// AES-256-GCM key
+pub(super) struct SealingKey {
+    cipher: Aes256Gcm<U12>,
+}
+
+impl SealingKey {
+    /// Key from a fresh random secret, good until the enclave restarts
+    pub(super) fn generate() -> Self {
+        Self::from_secret(&Zeroizing::new(rand::random::<[u8; 32]>()))
+    }
+
+    pub(super) fn from_secret(secret: &[u8; 32]) -> Self {
+        let key = AesKey::<U32>::from_bytes(secret).expect("an AES-256 key is 32 bytes");
+        Self { cipher: Aes256Gcm::new(key) }
+    }
+
+    /// Associated data: the version, then the context, length-prefixed
+    fn aad(version: u8, context: &str) -> Vec<u8> {
+        [&[version], (context.len() as u64).to_be_bytes().as_slice(), context.as_bytes()].concat()
+    }
+
+    pub(super) fn seal(&self, context: &str, plaintext: &[u8]) -> Sealed {
+        let nonce = rand::random::<[u8; NONCE_LEN]>();
+        let iv = InitializationVector::<U12>::from_bytes(&nonce).expect("a GCM nonce is 12 bytes");
+        let ciphertext = self.cipher.encrypt_authenticated(&iv, &Self::aad(SEALED_VERSION, context), plaintext);
+        Sealed { version: SEALED_VERSION, nonce, ciphertext }
+    }
+
+    /// The plaintext, or None if `sealed` wasn't sealed for `context` under this key
+    pub(super) fn open(&self, context: &str, sealed: &Sealed) -> Option<Zeroizing<Vec<u8>>> {
+        if sealed.version != SEALED_VERSION {
+            return None;
+        }
+        let iv = InitializationVector::<U12>::from_bytes(&sealed.nonce).ok()?;
+        self.cipher
+            .decrypt_authenticated(&iv, &Self::aad(sealed.version, context), &sealed.ciphertext)
+            .ok()
+            .map(Zeroizing::new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sealed_bytes_open_only_in_their_context_under_their_key() {
+        let key = SealingKey::from_secret(&[7; 32]);
+        let bytes = key.seal("snapshot", b"state worth keeping").to_bytes();
+        assert_eq!(bytes.len(), 1 + NONCE_LEN + 19 + TAG_LEN);
+
+        let sealed = Sealed::from_bytes(&bytes).unwrap();
+        assert_eq!(key.open("snapshot", &sealed).unwrap().as_slice(), b"state worth keeping");
+        // Same secret after a restart, another context, another key
+        assert!(SealingKey::from_secret(&[7; 32]).open("snapshot", &sealed).is_some());
+        assert!(key.open("alice", &sealed).is_none());
+        assert!(SealingKey::generate().open("snapshot", &sealed).is_none());
+
+        let mut tampered = bytes.clone();
+        tampered[1 + NONCE_LEN] ^= 1;
+        assert!(key.open("snapshot", &Sealed::from_bytes(&tampered).unwrap()).is_none());
+        // Another version is refused, even with the rest intact
+        let mut versioned = bytes.clone();
+        versioned[0] = SEALED_VERSION + 1;
+        assert!(key.open("snapshot", &Sealed::from_bytes(&versioned).unwrap()).is_none());
+        assert!(Sealed::from_bytes(&bytes[..1 + NONCE_LEN + TAG_LEN - 1]).is_none());
+    }
+}
@@ -0,0 +1,149 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! Voice-confirmed daily spending limits
+//!
+//! The user records "set my daily limit to 50 SUI". The clip goes through the
+//! same analysis as bio-auth, and the enclave signs a `SetLimitPayload` for the
+//! contract's `limits::set_daily_limit` only if the transcript asks for a limit,
+//! the spoken amount matches `daily_limit` and the voice is calm: a standing
+//! consent has to hold for every later transfer, so it needs less stress than
+//! the duress threshold. A stressed or panic-phrase recording is refused the
+//! same way as a mismatched amount, so a coercer can't tell which it was.
+
+use crate::common::{IntentScope, ProcessDataRequest};
+use crate::AppState;
+use crate::EnclaveError;
+use axum::extract::State;
+use axum::Json;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+use super::amount::RawAmount;
+use super::audio;
+use super::coin::CoinType;
+use super::confidence;
+use super::stream::Progress;
+use super::types::*;
+use super::validation::ValidatedJson;
+use super::versions::sign_payload;
+
+/// Highest stress level at which a limit is signed ("normal speaking voice")
+pub const MAX_SET_LIMIT_STRESS: u8 = 40;
+
+/// Whether the transcript asks for a limit rather than confirming a transfer
+fn mentions_limit(transcript: &str) -> bool {
+    transcript.to_lowercase().contains("limit")
+}
+
+/// Sign a daily spending limit confirmed by voice
+///
+/// Request: handle, audio_base64, daily_limit, coin_type, locale
+/// Response: signed SetLimitPayload (SET_LIMIT_INTENT)
+pub async fn process_set_limit(
+    State(state): State<Arc<AppState>>,
+    ValidatedJson(request): ValidatedJson<ProcessDataRequest<SetLimitRequest>>,
+) -> Result<Json<SetLimitResponse>, EnclaveError> {
+    let req = &request.payload;
+    let version = state.payload_versions.select(request.payload_version)?;
+    let _handle_lock = state.bioauth_locks.try_acquire(&req.handle).inspect_err(|_| {
+        warn!("RAM SetLimit: concurrent request rejected for handle '{}'", req.handle);
+    })?;
+
+    let coin = state.coin_registry.resolve(&CoinType::parse_or_sui(req.coin_type.as_deref())?).await?;
+    let daily_limit = RawAmount(req.daily_limit);
+    info!(
+        "RAM SetLimit: handle='{}', daily_limit={} {} ({} raw)",
+        req.handle, coin.format(daily_limit), coin.symbol, daily_limit
+    );
+
+    let current_timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_err(|e| EnclaveError::GenericError(format!("Failed to get timestamp: {}", e)))?
+        .as_millis() as u64;
+
+    // Shares the failure count and clip fingerprints with bio-auth
+    state.bioauth_attempts.check(&req.handle, current_timestamp).inspect_err(|_| {
+        warn!("RAM SetLimit: attempt during failure cooldown rejected for handle '{}'", req.handle);
+    })?;
+    let fingerprint = state
+        .bioauth_replays
+        .check(&req.handle, &req.audio_base64, current_timestamp)
+        .inspect_err(|_| warn!("RAM SetLimit: replayed clip rejected for handle '{}'", req.handle))?;
+
+    let baseline = state.voice_baselines.get(&req.handle);
+    let upstreams = audio::Upstreams {
+        openrouter_api_key: Some(state.openrouter_api_key.as_str()).filter(|key| !key.is_empty()),
+        hume_api_key: Some(state.hume_api_key.as_str()).filter(|key| !key.is_empty()),
+        costs: &state.upstream_costs,
+    };
+    let analysis = audio::analyze_audio(
+        &req.audio_base64,
+        upstreams,
+        audio::Expected { amount: Some(daily_limit), coin: &coin, locale: req.locale.as_deref(), phrase: None },
+        baseline.as_ref(),
+        &Progress::default(),
+    )
+    .await?;
+
+    let panic = state
+        .voice_baselines
+        .panic_phrase(&req.handle)
+        .and_then(|phrase| phrase.strip(&analysis.transcript))
+        .is_some();
+    let calm = analysis.stress_level <= MAX_SET_LIMIT_STRESS && !panic;
+    let confirmed = analysis.amount_verified && mentions_limit(&analysis.transcript);
+
+    // Only an approval needs findings the model is sure of
+    if calm && confirmed {
+        confidence::current().check(&analysis.confidence, false).inspect_err(|_| {
+            warn!("RAM SetLimit: inconclusive analysis for '{}' ({:?})", req.handle, analysis.confidence);
+        })?;
+    }
+
+    if !(calm && confirmed) {
+        info!(
+            "RAM SetLimit: ✗ refused for '{}' (stress_level={}, panic_phrase={}, amount_verified={})",
+            req.handle, analysis.stress_level, panic, analysis.amount_verified
+        );
+        // A stressed voice isn't a failed attempt, as with duress in bio-auth
+        if calm {
+            state.bioauth_attempts.record(&req.handle, BioAuthResult::InvalidAmount, current_timestamp);
+        }
+        return Err(EnclaveError::Forbidden("Daily limit was not confirmed".to_string()));
+    }
+
+    info!("RAM SetLimit: ✓ confirmed for '{}'", req.handle);
+    state.bioauth_attempts.record(&req.handle, BioAuthResult::Ok, current_timestamp);
+    if let Some(fingerprint) = fingerprint {
+        state.bioauth_replays.record(&req.handle, fingerprint, current_timestamp);
+    }
+
+    let payload = SetLimitPayload {
+        handle: req.handle.clone().into_bytes(),
+        daily_limit: req.daily_limit,
+        // Must equal type_name::get<T>() on-chain
+        coin_type: coin.coin_type.type_name().into_bytes(),
+    };
+    let signature = sign_payload(&state.eph_kp, &payload, current_timestamp, IntentScope::SetLimit, version);
+
+    Ok(Json(SetLimitResponse {
+        payload,
+        intent: SET_LIMIT_INTENT,
+        payload_version: version as u8,
+        timestamp_ms: current_timestamp,
+        signature,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transcript_must_ask_for_a_limit() {
+        assert!(mentions_limit("Set my daily limit to 50 SUI"));
+        assert!(mentions_limit("LIMIT fifty sui"));
+        assert!(!mentions_limit("I confirm sending 50 SUI"));
+    }
+}
@@ -0,0 +1,127 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! Shadow (canary) mode for duress detection
+//!
+//! With shadow mode on, bio-auth still runs the full analysis but a duress
+//! verdict is only logged, counted and kept in diagnostics: the signed result
+//! falls back to the amount check, so no wallet is locked. Operators enable it
+//! per deployment to measure how often a new analyzer or threshold would have
+//! locked wallets on real traffic before letting it enforce.
+
+use crate::AppState;
+use axum::extract::State;
+use axum::Json;
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use super::audio;
+
+/// Shadow-mode switch and counters for the would-be duress rate
+pub struct ShadowMode {
+    enabled: bool,
+    /// Candidate stress threshold evaluated in shadow (None = production threshold)
+    threshold: Option<u8>,
+    analyzed: AtomicU64,
+    would_lock: AtomicU64,
+}
+
+/// Shadow-mode counters
+#[derive(Debug, Clone, Serialize)]
+pub struct ShadowMetrics {
+    pub enabled: bool,
+    pub threshold: Option<u8>,
+    /// Bio-auths analyzed while in shadow mode
+    pub analyzed: u64,
+    /// Of those, how many the analyzer flagged as duress
+    pub would_lock: u64,
+    pub would_lock_rate: f64,
+}
+
+impl ShadowMode {
+    pub fn new(enabled: bool, threshold: Option<u8>) -> Self {
+        Self {
+            enabled,
+            threshold,
+            analyzed: AtomicU64::new(0),
+            would_lock: AtomicU64::new(0),
+        }
+    }
+
+    /// Whether duress verdicts should be recorded instead of enforced
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Duress verdict for a stress level, using the candidate threshold in shadow mode
+    pub fn detects_duress(&self, stress_level: u8) -> bool {
+        match self.threshold.filter(|_| self.enabled) {
+            Some(threshold) => stress_level >= threshold,
+            None => audio::is_under_duress(stress_level),
+        }
+    }
+
+    /// Count one shadow-mode analysis and whether it would have locked the wallet
+    pub fn record(&self, duress: bool) {
+        self.analyzed.fetch_add(1, Ordering::Relaxed);
+        if duress {
+            self.would_lock.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn metrics(&self) -> ShadowMetrics {
+        let analyzed = self.analyzed.load(Ordering::Relaxed);
+        let would_lock = self.would_lock.load(Ordering::Relaxed);
+        ShadowMetrics {
+            enabled: self.enabled,
+            threshold: self.threshold,
+            analyzed,
+            would_lock,
+            would_lock_rate: if analyzed == 0 { 0.0 } else { would_lock as f64 / analyzed as f64 },
+        }
+    }
+}
+
+impl Default for ShadowMode {
+    fn default() -> Self {
+        Self::new(false, None)
+    }
+}
+
+/// Shadow-mode duress counters
+///
+/// GET /bio_auth/shadow
+pub async fn get_bio_auth_shadow(State(state): State<Arc<AppState>>) -> Json<ShadowMetrics> {
+    Json(state.bioauth_shadow.metrics())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_candidate_threshold_only_applies_in_shadow() {
+        let shadow = ShadowMode::new(true, Some(40));
+        assert!(shadow.detects_duress(45));
+        assert!(!shadow.detects_duress(39));
+
+        let enforcing = ShadowMode::new(false, Some(40));
+        assert!(!enforcing.detects_duress(45));
+        assert_eq!(enforcing.detects_duress(100), audio::is_under_duress(100));
+    }
+
+    #[test]
+    fn test_metrics_rate() {
+        let shadow = ShadowMode::new(true, None);
+        assert_eq!(shadow.metrics().would_lock_rate, 0.0);
+        shadow.record(true);
+        shadow.record(false);
+        shadow.record(false);
+        shadow.record(true);
+        let metrics = shadow.metrics();
+        assert_eq!(metrics.analyzed, 4);
+        assert_eq!(metrics.would_lock, 2);
+        assert_eq!(metrics.would_lock_rate, 0.5);
+    }
+}
@@ -0,0 +1,161 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! Load shedding on the analysis routes
+//!
+//! A bio-auth holds the decoded clip in memory and keeps a CPU busy with DSP
+//! for seconds, so a burst of uploads can exhaust the enclave long before any
+//! of them times out. Two guards sit in front of `/bio_auth` and
+//! `/bio_auth/stream`:
+//!
+//! - a concurrency limit shared by both routes; once every slot is taken new
+//!   requests are shed at once instead of waiting for one
+//! - in queued mode, a queue-depth check that refuses uploads before their
+//!   body is read once the job queue holds `shed_queue_depth` jobs
+//!
+//! Shed requests get the usual `rate_limited` 429 plus `Retry-After`, so
+//! clients back off instead of timing out mid-analysis.
+
+use crate::{AppState, EnclaveError};
+use axum::error_handling::HandleErrorLayer;
+use axum::extract::{Request, State};
+use axum::http::header::RETRY_AFTER;
+use axum::http::HeaderValue;
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::{BoxError, Router};
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tower::limit::GlobalConcurrencyLimitLayer;
+use tower::ServiceBuilder;
+use tracing::warn;
+
+/// Default number of analysis requests handled at once
+pub const DEFAULT_MAX_CONCURRENT_ANALYSES: usize = 8;
+/// Default `Retry-After` on shed requests
+pub const DEFAULT_SHED_RETRY_AFTER_SECS: u64 = 5;
+
+/// How much analysis load the enclave accepts before shedding
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnalysisLimits {
+    /// Requests analyzed (or, in queued mode, received) at once
+    pub max_concurrent: usize,
+    /// Queue depth at which new uploads are shed (None = the queue's capacity)
+    pub shed_queue_depth: Option<usize>,
+    /// Seconds clients are told to wait before retrying a shed request
+    pub retry_after_secs: u64,
+}
+
+impl Default for AnalysisLimits {
+    fn default() -> Self {
+        Self {
+            max_concurrent: DEFAULT_MAX_CONCURRENT_ANALYSES,
+            shed_queue_depth: None,
+            retry_after_secs: DEFAULT_SHED_RETRY_AFTER_SECS,
+        }
+    }
+}
+
+impl AnalysisLimits {
+    /// Limits from `BIOAUTH_MAX_CONCURRENT`, `BIOAUTH_SHED_QUEUE_DEPTH` and
+    /// `BIOAUTH_RETRY_AFTER_SECS`; unset or invalid values keep the defaults
+    pub fn from_env() -> Self {
+        let var = |name: &str| std::env::var(name).ok().and_then(|v| v.trim().parse::<u64>().ok());
+        let defaults = Self::default();
+        Self {
+            max_concurrent: var("BIOAUTH_MAX_CONCURRENT")
+                .filter(|n| *n > 0)
+                .map_or(defaults.max_concurrent, |n| n as usize),
+            shed_queue_depth: var("BIOAUTH_SHED_QUEUE_DEPTH").map(|n| n as usize),
+            retry_after_secs: var("BIOAUTH_RETRY_AFTER_SECS").unwrap_or(defaults.retry_after_secs),
+        }
+    }
+
+    /// Whether a queue holding `depth` of `capacity` jobs should shed new uploads
+    pub fn queue_is_saturated(&self, depth: usize, capacity: usize) -> bool {
+        depth >= self.shed_queue_depth.unwrap_or(capacity).min(capacity)
+    }
+}
+
+/// Wrap the analysis routes in `routes` with the concurrency limit and
+/// queue-depth shedding
+pub fn shed_analysis_load(
+    routes: Router<Arc<AppState>>,
+    state: Arc<AppState>,
+    limits: AnalysisLimits,
+) -> Router<Arc<AppState>> {
+    // One semaphore for every route, rather than a limit per route
+    let semaphore = Arc::new(Semaphore::new(limits.max_concurrent.max(1)));
+    let overloaded = {
+        let state = state.clone();
+        let retry_after_secs = limits.retry_after_secs;
+        move |_: BoxError| {
+            let state = state.clone();
+            async move {
+                state.bioauth_jobs.record_shed();
+                warn!("RAM BioAuth: all analysis slots busy, shedding request");
+                shed_response("Too many bio-auths in progress, retry later", retry_after_secs)
+            }
+        }
+    };
+    routes
+        .route_layer(
+            ServiceBuilder::new()
+                .layer(HandleErrorLayer::new(overloaded))
+                .load_shed()
+                .layer(GlobalConcurrencyLimitLayer::with_semaphore(semaphore)),
+        )
+        .route_layer(middleware::from_fn_with_state((state, limits), shed_when_queue_is_deep))
+}
+
+/// Refuse uploads the job queue has no room for, before their body is read
+async fn shed_when_queue_is_deep(
+    State((state, limits)): State<(Arc<AppState>, AnalysisLimits)>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let queue = &state.bioauth_jobs;
+    if queue.is_enabled() {
+        let metrics = queue.metrics();
+        if limits.queue_is_saturated(metrics.depth, metrics.capacity) {
+            queue.record_shed();
+            warn!("RAM BioAuth: queue depth {} of {}, shedding request", metrics.depth, metrics.capacity);
+            return shed_response("BioAuth queue is full, retry later", limits.retry_after_secs);
+        }
+    }
+    next.run(request).await
+}
+
+/// A `rate_limited` 429 telling the client when to retry
+fn shed_response(message: &str, retry_after_secs: u64) -> Response {
+    let mut response = EnclaveError::TooManyRequests(message.to_string()).into_response();
+    response.headers_mut().insert(RETRY_AFTER, HeaderValue::from(retry_after_secs));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_queue_saturation_threshold() {
+        let limits = AnalysisLimits::default();
+        assert!(!limits.queue_is_saturated(31, 32));
+        assert!(limits.queue_is_saturated(32, 32));
+
+        let early = AnalysisLimits { shed_queue_depth: Some(24), ..AnalysisLimits::default() };
+        assert!(!early.queue_is_saturated(23, 32));
+        assert!(early.queue_is_saturated(24, 32));
+
+        // A threshold past the capacity still sheds once the queue is full
+        let late = AnalysisLimits { shed_queue_depth: Some(100), ..AnalysisLimits::default() };
+        assert!(late.queue_is_saturated(32, 32));
+    }
+
+    #[test]
+    fn test_shed_response_carries_retry_after() {
+        let response = shed_response("busy", 7);
+        assert_eq!(response.status(), axum::http::StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[RETRY_AFTER], "7");
+    }
+}
@@ -0,0 +1,260 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! Synthetic voices for exercising the DSP stress classifier
+//!
+//! A voice is a sine at its fundamental with a few decaying harmonics, shaped
+//! by the cues the classifier looks for: pitch tremor, a pitch rise over the
+//! clip, volume swings and background noise. [`calm_voices`] and
+//! [`stressed_voices`] sweep those parameters across male and female pitch
+//! ranges, and [`evaluate`] reports the classifier's hit and false-positive
+//! rates over them, so threshold or feature changes can be checked against
+//! target bands instead of a handful of hand-picked clips.
+//!
+//! Noise comes from a seeded RNG: the same voice and seed always render the
+//! same samples.
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use super::voice_stress::{analyze_voice_stress, VoiceBaseline};
+
+/// Sample rate voices are rendered at unless stated otherwise
+pub const DEFAULT_SAMPLE_RATE: u32 = 16_000;
+
+/// Shape of a synthetic voice
+#[derive(Debug, Clone, PartialEq)]
+pub struct Voice {
+    /// Fundamental frequency at the start of the clip (Hz)
+    pub f0_hz: f64,
+    /// How often the pitch wobbles (Hz)
+    pub tremor_rate_hz: f64,
+    /// How far the pitch wobbles either side of its current value (Hz)
+    pub tremor_depth_hz: f64,
+    /// Pitch rise by the end of the clip, as a fraction of `f0_hz`
+    pub pitch_rise: f64,
+    /// Depth of the loudness swings, 0 (steady) to 1 (down to silence)
+    pub volume_swing: f64,
+    /// How often the loudness swings (Hz)
+    pub volume_swing_rate_hz: f64,
+    /// White noise amplitude relative to the voice's peak
+    pub noise: f64,
+}
+
+impl Voice {
+    /// A steady voice at `f0_hz`: no tremor, rise, swings or noise
+    pub fn calm(f0_hz: f64) -> Self {
+        Self {
+            f0_hz,
+            tremor_rate_hz: 0.0,
+            tremor_depth_hz: 0.0,
+            pitch_rise: 0.0,
+            volume_swing: 0.0,
+            volume_swing_rate_hz: 0.0,
+            noise: 0.0,
+        }
+    }
+
+    pub fn with_tremor(mut self, rate_hz: f64, depth_hz: f64) -> Self {
+        self.tremor_rate_hz = rate_hz;
+        self.tremor_depth_hz = depth_hz;
+        self
+    }
+
+    pub fn with_pitch_rise(mut self, rise: f64) -> Self {
+        self.pitch_rise = rise;
+        self
+    }
+
+    pub fn with_volume_swings(mut self, depth: f64, rate_hz: f64) -> Self {
+        self.volume_swing = depth.clamp(0.0, 1.0);
+        self.volume_swing_rate_hz = rate_hz;
+        self
+    }
+
+    pub fn with_noise(mut self, noise: f64) -> Self {
+        self.noise = noise.max(0.0);
+        self
+    }
+
+    /// Render `duration_secs` of the voice as samples in [-1, 1]
+    pub fn render(&self, sample_rate: u32, duration_secs: f64, seed: u64) -> Vec<f32> {
+        // Relative strength of the fundamental and its first harmonics
+        const HARMONICS: [f64; 3] = [1.0, 0.4, 0.15];
+        const PEAK: f64 = 0.5;
+
+        let mut rng = StdRng::seed_from_u64(seed);
+        let rate = sample_rate as f64;
+        let num_samples = (rate * duration_secs) as usize;
+        let tau = 2.0 * std::f64::consts::PI;
+        let norm = PEAK / HARMONICS.iter().sum::<f64>();
+        let mut phase = 0.0;
+        (0..num_samples)
+            .map(|i| {
+                let t = i as f64 / rate;
+                let progress = t / duration_secs.max(f64::EPSILON);
+                let f0 = self.f0_hz * (1.0 + self.pitch_rise * progress)
+                    + self.tremor_depth_hz * (tau * self.tremor_rate_hz * t).sin();
+                phase += tau * f0 / rate;
+                let tone: f64 = HARMONICS
+                    .iter()
+                    .enumerate()
+                    .map(|(k, weight)| weight * (phase * (k + 1) as f64).sin())
+                    .sum();
+                let loudness = 1.0 - self.volume_swing * 0.5 * (1.0 - (tau * self.volume_swing_rate_hz * t).cos());
+                let noise = self.noise * PEAK * rng.gen_range(-1.0..=1.0);
+                (tone * norm * loudness + noise).clamp(-1.0, 1.0) as f32
+            })
+            .collect()
+    }
+}
+
+/// A pure tone at half scale, the simplest voiced signal
+pub fn sine_wave(freq_hz: f64, sample_rate: u32, duration_secs: f64) -> Vec<f32> {
+    let num_samples = (sample_rate as f64 * duration_secs) as usize;
+    (0..num_samples)
+        .map(|i| {
+            let t = i as f64 / sample_rate as f64;
+            (2.0 * std::f64::consts::PI * freq_hz * t).sin() as f32 * 0.5
+        })
+        .collect()
+}
+
+/// A 16-bit mono PCM WAV of `samples`
+pub fn to_wav(sample_rate: u32, samples: &[f32]) -> Vec<u8> {
+    let data_size = samples.len() * 2;
+    let mut wav = Vec::with_capacity(44 + data_size);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&((36 + data_size) as u32).to_le_bytes());
+    wav.extend_from_slice(b"WAVE");
+    wav.extend_from_slice(b"fmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    wav.extend_from_slice(&1u16.to_le_bytes()); // Mono
+    wav.extend_from_slice(&sample_rate.to_le_bytes());
+    wav.extend_from_slice(&(sample_rate * 2).to_le_bytes()); // byte rate
+    wav.extend_from_slice(&2u16.to_le_bytes()); // block align
+    wav.extend_from_slice(&16u16.to_le_bytes()); // bits per sample
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&(data_size as u32).to_le_bytes());
+    for &s in samples {
+        wav.extend_from_slice(&((s.clamp(-1.0, 1.0) * 32767.0) as i16).to_le_bytes());
+    }
+    wav
+}
+
+/// Fundamentals the matrices sweep: low and typical male, typical and high female
+pub const SPEAKER_F0_HZ: [f64; 4] = [110.0, 130.0, 180.0, 210.0];
+
+/// Relaxed speakers: steady or with the small drift and room noise of a
+/// normal recording
+pub fn calm_voices() -> Vec<Voice> {
+    SPEAKER_F0_HZ
+        .iter()
+        .flat_map(|&f0| {
+            [
+                Voice::calm(f0),
+                Voice::calm(f0).with_tremor(4.0, 1.0),
+                Voice::calm(f0).with_pitch_rise(0.03),
+                Voice::calm(f0).with_volume_swings(0.15, 2.0),
+                Voice::calm(f0).with_noise(0.02),
+            ]
+        })
+        .collect()
+}
+
+/// Speakers under pressure: pitch tremor (scaled to the speaker, since
+/// jitter is relative to F0) with unsteady volume, alone and combined with a
+/// raised pitch and a noisier, tenser signal
+pub fn stressed_voices() -> Vec<Voice> {
+    SPEAKER_F0_HZ
+        .iter()
+        .flat_map(|&f0| {
+            let tremor_hz = 0.2 * f0;
+            [
+                Voice::calm(f0).with_tremor(6.0, tremor_hz).with_volume_swings(0.9, 3.0),
+                Voice::calm(f0).with_tremor(8.0, 1.3 * tremor_hz).with_volume_swings(0.95, 2.0),
+                Voice::calm(f0).with_pitch_rise(0.5).with_tremor(6.0, tremor_hz).with_volume_swings(0.9, 3.0),
+                Voice::calm(f0).with_tremor(7.0, tremor_hz).with_volume_swings(0.9, 3.0).with_noise(0.3),
+                Voice::calm(f0)
+                    .with_tremor(6.0, tremor_hz)
+                    .with_pitch_rise(0.4)
+                    .with_volume_swings(0.8, 4.0)
+                    .with_noise(0.2),
+            ]
+        })
+        .collect()
+}
+
+/// How a classifier threshold did over a set of voices
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ClassifierRates {
+    /// Stressed voices scored at or above the threshold
+    pub hits: usize,
+    pub stressed: usize,
+    /// Calm voices scored at or above the threshold
+    pub false_positives: usize,
+    pub calm: usize,
+}
+
+impl ClassifierRates {
+    pub fn hit_rate(&self) -> f64 {
+        self.hits as f64 / self.stressed.max(1) as f64
+    }
+
+    pub fn false_positive_rate(&self) -> f64 {
+        self.false_positives as f64 / self.calm.max(1) as f64
+    }
+}
+
+/// DSP stress score of each voice, rendered for `duration_secs` with one seed per voice
+pub fn stress_levels(
+    voices: &[Voice],
+    sample_rate: u32,
+    duration_secs: f64,
+    baseline: Option<&VoiceBaseline>,
+) -> Vec<u8> {
+    voices
+        .iter()
+        .enumerate()
+        .map(|(seed, voice)| {
+            let wav = to_wav(sample_rate, &voice.render(sample_rate, duration_secs, seed as u64));
+            analyze_voice_stress(&wav, baseline).stress_level
+        })
+        .collect()
+}
+
+/// Hit and false-positive rates of flagging a score `>= threshold` as stress
+pub fn evaluate(calm: &[u8], stressed: &[u8], threshold: u8) -> ClassifierRates {
+    ClassifierRates {
+        hits: stressed.iter().filter(|&&level| level >= threshold).count(),
+        stressed: stressed.len(),
+        false_positives: calm.iter().filter(|&&level| level >= threshold).count(),
+        calm: calm.len(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apps::ram::voice_stress::parse_wav;
+
+    #[test]
+    fn test_render_is_deterministic_and_bounded() {
+        let voice = Voice::calm(150.0).with_tremor(6.0, 30.0).with_noise(0.5);
+        let a = voice.render(DEFAULT_SAMPLE_RATE, 0.25, 7);
+        assert_eq!(a, voice.render(DEFAULT_SAMPLE_RATE, 0.25, 7));
+        assert_ne!(a, voice.render(DEFAULT_SAMPLE_RATE, 0.25, 8));
+        assert_eq!(a.len(), DEFAULT_SAMPLE_RATE as usize / 4);
+        assert!(a.iter().all(|s| (-1.0..=1.0).contains(s)));
+    }
+
+    #[test]
+    fn test_to_wav_round_trips() {
+        let samples = Voice::calm(200.0).render(DEFAULT_SAMPLE_RATE, 0.1, 0);
+        let (parsed, rate) = parse_wav(&to_wav(DEFAULT_SAMPLE_RATE, &samples)).unwrap();
+        assert_eq!(rate, DEFAULT_SAMPLE_RATE);
+        assert_eq!(parsed.len(), samples.len());
+        assert!(parsed.iter().zip(&samples).all(|(a, b)| (a - b).abs() < 1e-4));
+    }
+}
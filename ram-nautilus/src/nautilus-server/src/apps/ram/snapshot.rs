@@ -0,0 +1,240 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! Sealed snapshots of the enclave's volatile state
+//!
+//! Issued challenges and today's upstream spend live in enclave memory, so a
+//! restart used to drop them: a user who had just been given a challenge had
+//! to ask again, and budgets started over. With `SNAPSHOT_URL` set, the
+//! enclave seals them (see `sealing`) every `SNAPSHOT_INTERVAL_SECS` and PUTs
+//! the blob there, an endpoint on the parent instance, and at boot GETs the
+//! last one back before it serves anything.
+//!
+//! Blobs are sealed under `SNAPSHOT_KEY`, a 32-byte secret delivered like the
+//! API keys, since a key generated at boot couldn't open them after a
+//! restart. That environment comes from the parent, so the parent holds the
+//! key too: it can read, edit and reseal a snapshot, or serve an older one.
+//! Until the key is unwrapped inside the enclave against its attestation, a
+//! snapshot only carries state that is safe in the parent's hands: a
+//! challenge it brings back or makes up is only a phrase the user still has
+//! to say, and expires with its own TTL, and spend only caps what the enclave
+//! pays upstream. Failed-attempt counts, replay fingerprints and preference
+//! grants, which a forged snapshot could clear or hand out, are not part of
+//! it and are lost on restart, like voice baselines and preferences.
+//!
+//! A blob that doesn't open, or was taken more than `SNAPSHOT_MAX_AGE_SECS`
+//! ago, is ignored and the enclave starts empty, as it did before.
+
+use crate::AppState;
+use fastcrypto::encoding::{Encoding, Hex};
+use reqwest::header::CONTENT_TYPE;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+use zeroize::Zeroizing;
+
+use super::challenge::SavedChallenge;
+use super::costs::SavedSpend;
+use super::sealing::{Sealed, SealingKey};
+
+/// How often the state is snapshotted unless `SNAPSHOT_INTERVAL_SECS` says otherwise
+pub const DEFAULT_SNAPSHOT_INTERVAL_SECS: u64 = 30;
+/// Oldest snapshot restored unless `SNAPSHOT_MAX_AGE_SECS` says otherwise
+pub const DEFAULT_SNAPSHOT_MAX_AGE_SECS: u64 = 600;
+
+/// Context snapshots are sealed to; a new layout needs a new one
+const SNAPSHOT_CONTEXT: &str = "ram-enclave-snapshot-v2";
+/// Budget for one upload or download of a snapshot
+const TRANSFER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Wall-clock time, in ms, of `instant`
+pub(super) fn wall_clock_ms(instant: Instant, now_ms: u64) -> u64 {
+    now_ms.saturating_sub(instant.elapsed().as_millis() as u64)
+}
+
+/// The instant of wall-clock `at_ms`, if the monotonic clock reaches back that far
+pub(super) fn instant_at(at_ms: u64, now_ms: u64) -> Option<Instant> {
+    Instant::now().checked_sub(Duration::from_millis(now_ms.saturating_sub(at_ms)))
+}
+
+/// What a restart would otherwise lose, of what the parent may see and edit
+#[derive(Serialize, Deserialize)]
+struct Snapshot {
+    taken_at_ms: u64,
+    challenges: Vec<SavedChallenge>,
+    upstream_spend: SavedSpend,
+}
+
+impl Snapshot {
+    fn take(state: &AppState, now_ms: u64) -> Self {
+        Self {
+            taken_at_ms: now_ms,
+            challenges: state.bioauth_challenges.save(now_ms),
+            upstream_spend: state.upstream_costs.save(now_ms),
+        }
+    }
+
+    /// Add what hasn't expired since to `state`; entries it already has win
+    fn restore(self, state: &AppState, now_ms: u64) {
+        state.bioauth_challenges.restore(self.challenges, now_ms);
+        state.upstream_costs.restore(self.upstream_spend, now_ms);
+    }
+}
+
+/// Where snapshots are kept and the key they're sealed under
+pub struct SnapshotConfig {
+    url: String,
+    key: SealingKey,
+    /// Time between two snapshots
+    pub interval: Duration,
+    /// Snapshots older than this at boot are ignored
+    pub max_age: Duration,
+}
+
+impl SnapshotConfig {
+    /// Snapshots kept at `url`, sealed under `secret`, at the default interval and age
+    pub fn new(url: impl Into<String>, secret: &[u8; 32]) -> Self {
+        Self {
+            url: url.into(),
+            key: SealingKey::from_secret(secret),
+            interval: Duration::from_secs(DEFAULT_SNAPSHOT_INTERVAL_SECS),
+            max_age: Duration::from_secs(DEFAULT_SNAPSHOT_MAX_AGE_SECS),
+        }
+    }
+
+    /// From `SNAPSHOT_URL` and `SNAPSHOT_KEY` (hex); `None` if `SNAPSHOT_URL` is unset
+    pub fn from_env() -> Result<Option<Self>, String> {
+        let var = |name: &str| std::env::var(name).ok().map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+        let secs = |name: &str, default: u64| match var(name) {
+            Some(v) => v.parse::<u64>().map_err(|_| format!("{} must be a number of seconds, got '{}'", name, v)),
+            None => Ok(default),
+        };
+
+        let Some(url) = var("SNAPSHOT_URL") else {
+            return Ok(None);
+        };
+        let hex = Zeroizing::new(var("SNAPSHOT_KEY").ok_or("SNAPSHOT_KEY is required with SNAPSHOT_URL")?);
+        let secret = Hex::decode(hex.trim_start_matches("0x"))
+            .ok()
+            .map(Zeroizing::new)
+            .and_then(|bytes| <[u8; 32]>::try_from(bytes.as_slice()).ok())
+            .map(Zeroizing::new)
+            .ok_or("SNAPSHOT_KEY must be 32 bytes of hex")?;
+
+        let mut config = Self::new(url, &secret);
+        config.interval = Duration::from_secs(secs("SNAPSHOT_INTERVAL_SECS", DEFAULT_SNAPSHOT_INTERVAL_SECS)?.max(1));
+        config.max_age = Duration::from_secs(secs("SNAPSHOT_MAX_AGE_SECS", DEFAULT_SNAPSHOT_MAX_AGE_SECS)?);
+        Ok(Some(config))
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// The state as of `now_ms`, sealed
+    pub fn seal(&self, state: &AppState, now_ms: u64) -> Vec<u8> {
+        let plaintext =
+            Zeroizing::new(serde_json::to_vec(&Snapshot::take(state, now_ms)).expect("snapshot serializes"));
+        self.key.seal(SNAPSHOT_CONTEXT, &plaintext).to_bytes()
+    }
+
+    /// Restore a sealed snapshot into `state`: its age in ms, or why it was ignored
+    pub fn restore(&self, state: &AppState, blob: &[u8], now_ms: u64) -> Result<u64, String> {
+        let plaintext = Sealed::from_bytes(blob)
+            .and_then(|sealed| self.key.open(SNAPSHOT_CONTEXT, &sealed))
+            .ok_or("snapshot doesn't open under SNAPSHOT_KEY")?;
+        let snapshot: Snapshot =
+            serde_json::from_slice(&plaintext).map_err(|e| format!("unreadable snapshot: {}", e))?;
+        let age_ms = now_ms.saturating_sub(snapshot.taken_at_ms);
+        if age_ms > self.max_age.as_millis() as u64 {
+            return Err(format!("snapshot is {}s old", age_ms / 1000));
+        }
+        snapshot.restore(state, now_ms);
+        Ok(age_ms)
+    }
+}
+
+/// Seal the state and PUT it to the snapshot URL
+pub async fn save_snapshot(client: &reqwest::Client, state: &AppState, config: &SnapshotConfig) -> Result<(), String> {
+    let response = client
+        .put(&config.url)
+        .timeout(TRANSFER_TIMEOUT)
+        .header(CONTENT_TYPE, "application/octet-stream")
+        .body(config.seal(state, now_ms()))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("snapshot store answered {}", response.status()));
+    }
+    Ok(())
+}
+
+/// GET the last snapshot from the snapshot URL and restore it
+///
+/// Meant to run before the server listens. Whatever goes wrong, the enclave
+/// carries on with what it has, as it would without snapshots.
+pub async fn restore_snapshot(state: &AppState, config: &SnapshotConfig) {
+    let restored = match fetch_snapshot(config).await {
+        Ok(Some(blob)) => config.restore(state, &blob, now_ms()),
+        Ok(None) => {
+            info!("RAM Snapshot: no snapshot yet, starting empty");
+            return;
+        }
+        Err(e) => Err(e),
+    };
+    match restored {
+        Ok(age_ms) => info!("RAM Snapshot: restored the state of {}s ago", age_ms / 1000),
+        Err(e) => warn!("RAM Snapshot: {}, starting empty", e),
+    }
+}
+
+/// The last snapshot saved, or `None` if the store has none
+async fn fetch_snapshot(config: &SnapshotConfig) -> Result<Option<Vec<u8>>, String> {
+    let response = reqwest::Client::new()
+        .get(&config.url)
+        .timeout(TRANSFER_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| format!("couldn't fetch the last snapshot: {}", e))?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    if !response.status().is_success() {
+        return Err(format!("snapshot store answered {}", response.status()));
+    }
+    let blob = response.bytes().await.map_err(|e| format!("couldn't read the last snapshot: {}", e))?;
+    Ok(Some(blob.to_vec()))
+}
+
+/// Save a snapshot every `config.interval`
+pub fn spawn_snapshots(state: Arc<AppState>, config: SnapshotConfig) {
+    tokio::spawn(async move {
+        let client = reqwest::Client::new();
+        let mut failing = false;
+        loop {
+            tokio::time::sleep(config.interval).await;
+            match save_snapshot(&client, &state, &config).await {
+                Ok(()) if failing => {
+                    info!("RAM Snapshot: saving snapshots again");
+                    failing = false;
+                }
+                Ok(()) => {}
+                // Once per outage; a restart during it restores an older snapshot, or none
+                Err(e) if !failing => {
+                    warn!("RAM Snapshot: failed to save a snapshot: {}", e);
+                    failing = true;
+                }
+                Err(_) => {}
+            }
+        }
+    });
+}
+
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
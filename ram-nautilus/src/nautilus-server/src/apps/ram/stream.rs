@@ -0,0 +1,124 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! Server-Sent Events variant of bio-auth
+//!
+//! A voice analysis takes 5–10 s. `POST /bio_auth/stream` runs the same
+//! analysis as `POST /bio_auth` but answers with an event stream, so the
+//! client can show progress as each stage finishes:
+//!
+//! ```text
+//! event: stage    data: {"stage":"decoded"}
+//! event: stage    data: {"stage":"transcribed"}
+//! event: stage    data: {"stage":"stress_scored"}
+//! event: signed   data: <BioAuthResponse>
+//! ```
+//!
+//! Stage events carry only the stage name: like the signed response they stay
+//! blind, so the stream never reveals the transcript, stress level or verdict.
+//! A failure ends the stream with `event: error`: the usual error envelope
+//! plus the HTTP `status` the plain route would have answered with.
+//! Streamed requests skip the async queue; the analysis runs to completion
+//! even if the client disconnects.
+
+use crate::common::ProcessDataRequest;
+use crate::AppState;
+use crate::i18n::{current_lang, Lang};
+use crate::{current_request_id, EnclaveError};
+use axum::extract::State;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use serde::Serialize;
+use serde_json::json;
+use std::convert::Infallible;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::UnboundedReceiverStream;
+use tokio_stream::{Stream, StreamExt};
+use tracing::warn;
+
+use super::handlers::run_bio_auth;
+use super::types::BioAuthRequest;
+use super::validation::ValidatedJson;
+
+/// Bio-auth stages reported while the analysis runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BioAuthStage {
+    /// The clip was decoded and the DSP stress pass ran
+    Decoded,
+    /// Speech was transcribed (GPT-4o, or the mock fallback)
+    Transcribed,
+    /// Stress and amount were scored into a verdict
+    StressScored,
+    /// The blind response was signed
+    Signed,
+}
+
+/// Where a running bio-auth reports its stages (nowhere by default)
+#[derive(Debug, Clone, Default)]
+pub struct Progress(Option<mpsc::UnboundedSender<Event>>);
+
+impl Progress {
+    pub(crate) fn report(&self, stage: BioAuthStage) {
+        self.send(stage_event(stage));
+    }
+
+    fn send(&self, event: Event) {
+        if let Some(sender) = &self.0 {
+            // The client may have gone away; the analysis carries on regardless
+            let _ = sender.send(event);
+        }
+    }
+}
+
+fn stage_event(stage: BioAuthStage) -> Event {
+    Event::default()
+        .event("stage")
+        .json_data(json!({ "stage": stage }))
+        .expect("stage serializes")
+}
+
+fn error_event(error: EnclaveError, request_id: Option<String>, lang: Lang) -> Event {
+    let status = error.status().as_u16();
+    let mut data = json!(error.into_body(request_id).localized(lang));
+    data["status"] = json!(status);
+    Event::default().event("error").json_data(data).expect("error serializes")
+}
+
+/// Voice authentication with stage-by-stage progress over SSE
+///
+/// POST /bio_auth/stream
+/// Request: same as `POST /bio_auth`
+/// Response: `text/event-stream` of stage events, then `signed` or `error`
+pub async fn process_bio_auth_stream(
+    State(state): State<Arc<AppState>>,
+    ValidatedJson(request): ValidatedJson<ProcessDataRequest<BioAuthRequest>>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let progress = Progress(Some(sender));
+    // The analysis task runs outside the request's scope
+    let request_id = current_request_id();
+    let lang = current_lang();
+    let version = state.payload_versions.select(request.payload_version);
+
+    tokio::spawn(async move {
+        let request = request.payload;
+        let result = match version {
+            Ok(version) => run_bio_auth(&state, &request, version, &progress).await,
+            Err(e) => Err(e),
+        };
+        let event = match result {
+            Ok(response) => {
+                progress.report(BioAuthStage::Signed);
+                Event::default().event("signed").json_data(&response).expect("response serializes")
+            }
+            Err(e) => {
+                warn!("RAM BioAuth: streamed request for '{}' failed: {}", request.handle, e);
+                error_event(e, request_id, lang)
+            }
+        };
+        progress.send(event);
+    });
+
+    Sse::new(UnboundedReceiverStream::new(receiver).map(Ok)).keep_alive(KeepAlive::default())
+}
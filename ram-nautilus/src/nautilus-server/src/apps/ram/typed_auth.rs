@@ -0,0 +1,253 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! Typed-confirmation auth (accessibility path)
+//!
+//! Users who cannot speak confirm a transfer by typing the amount (and, for
+//! withdrawals, the destination's last characters) instead of saying it. There
+//! is no voice to analyze, so the enclave checks the keystroke timings the
+//! client recorded while the user typed: a paste, synthetic key events with no
+//! dwell time, or perfectly even machine rhythm is rejected as not typed by a
+//! person. The result is signed as the same BioAuthPayload with
+//! `method = Typed`, so the contract and indexer treat both paths alike.
+//!
+//! Typed auth never yields Duress: without a voice there is no stress signal,
+//! and failing the behavioral checks only means the confirmation is invalid.
+
+use crate::common::{IntentScope, ProcessDataRequest};
+use crate::AppState;
+use crate::EnclaveError;
+use axum::extract::State;
+use axum::Json;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+use super::amount::RawAmount;
+use super::audio;
+use super::coin::{CoinInfo, CoinType};
+use super::decoy;
+use super::locale::NumberLocale;
+use super::types::*;
+use super::versions::sign_payload;
+use super::validation::ValidatedJson;
+
+/// Shortest plausible key hold; injected events are usually released instantly
+const MIN_DWELL_MS: u64 = 15;
+/// Longest key hold before it reads as a stuck key or replayed trace
+const MAX_DWELL_MS: u64 = 2_000;
+/// Minimum coefficient of variation of the gaps between key presses
+const MIN_RHYTHM_VARIATION: f64 = 0.1;
+
+/// Check that the keystroke trace looks like a person typing `typed_text`
+///
+/// Returns the reason the trace was rejected.
+pub fn check_keystrokes(typed_text: &str, keystrokes: &[Keystroke], pasted: bool) -> Result<(), String> {
+    if pasted {
+        return Err("text was pasted".to_string());
+    }
+
+    // Every typed character needs a key press (corrections only add more)
+    let typed_chars = typed_text.trim().chars().count();
+    if keystrokes.len() < typed_chars {
+        return Err(format!("{} keystrokes for {} typed characters", keystrokes.len(), typed_chars));
+    }
+
+    for (i, key) in keystrokes.iter().enumerate() {
+        if key.up_ms < key.down_ms {
+            return Err(format!("keystroke {} released before it was pressed", i));
+        }
+        let dwell = key.up_ms - key.down_ms;
+        if !(MIN_DWELL_MS..=MAX_DWELL_MS).contains(&dwell) {
+            return Err(format!("implausible key hold of {}ms", dwell));
+        }
+        if i > 0 && key.down_ms < keystrokes[i - 1].down_ms {
+            return Err("keystrokes out of order".to_string());
+        }
+    }
+
+    // Scripts replay keys at a fixed interval; people never do
+    let gaps: Vec<f64> = keystrokes
+        .windows(2)
+        .map(|pair| (pair[1].down_ms - pair[0].down_ms) as f64)
+        .collect();
+    if gaps.len() >= 2 {
+        let mean = gaps.iter().sum::<f64>() / gaps.len() as f64;
+        let variance = gaps.iter().map(|g| (g - mean).powi(2)).sum::<f64>() / gaps.len() as f64;
+        let variation = if mean > 0.0 { variance.sqrt() / mean } else { 0.0 };
+        if variation < MIN_RHYTHM_VARIATION {
+            return Err(format!("typing rhythm too uniform (variation={:.3})", variation));
+        }
+    }
+
+    Ok(())
+}
+
+/// First number in the typed text, in raw units ("5 SUI" -> 5_000_000_000)
+///
+/// People type numbers their locale's way, so that reading wins; plain
+/// `1234.5` is accepted too. Trailing punctuation ("5, SUI") is ignored.
+fn typed_amount(typed_text: &str, coin: &CoinInfo, locale: NumberLocale) -> Option<RawAmount> {
+    typed_text
+        .split_whitespace()
+        .map(|token| token.trim_end_matches([',', '.', ';', ':']))
+        .find_map(|token| locale.parse(token, coin.decimals).or_else(|| coin.parse(token)))
+}
+
+/// Typed-confirmation auth for users who cannot speak
+///
+/// Request: handle, typed_text, expected_amount, keystroke timings
+/// Response: signed BioAuthPayload (method = Typed), same blind shape as /bio_auth
+///
+/// POST /typed_auth
+pub async fn process_typed_auth(
+    State(state): State<Arc<AppState>>,
+    ValidatedJson(request): ValidatedJson<ProcessDataRequest<TypedAuthRequest>>,
+) -> Result<Json<BioAuthResponse>, EnclaveError> {
+    let req = &request.payload;
+    let version = state.payload_versions.select(request.payload_version)?;
+    let _handle_lock = state.bioauth_locks.try_acquire(&req.handle).inspect_err(|_| {
+        warn!("RAM TypedAuth: concurrent request rejected for handle '{}'", req.handle);
+    })?;
+
+    let coin = state.coin_registry.resolve(&CoinType::parse_or_sui(req.coin_type.as_deref())?).await?;
+    info!(
+        "RAM TypedAuth: handle='{}', expected_amount={} raw {}, {} keystrokes",
+        req.handle, req.expected_amount, coin.symbol, req.keystrokes.len()
+    );
+
+    let current_timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_err(|e| EnclaveError::GenericError(format!("Failed to get timestamp: {}", e)))?
+        .as_millis() as u64;
+
+    // Shares the failure count with voice bio-auth, so switching method doesn't skip a cooldown
+    let failed_attempts = state.bioauth_attempts.check(&req.handle, current_timestamp).inspect_err(|_| {
+        warn!("RAM TypedAuth: attempt during failure cooldown rejected for handle '{}'", req.handle);
+    })?;
+
+    let behavior = check_keystrokes(&req.typed_text, &req.keystrokes, req.pasted);
+    if let Err(reason) = &behavior {
+        warn!("RAM TypedAuth: behavioral check failed for '{}': {}", req.handle, reason);
+    }
+
+    // Typed the way the handle's challenge was shown
+    let preferences = state.user_preferences.get(&req.handle);
+    let expected_amount = RawAmount(req.expected_amount);
+    let locale = NumberLocale::from_tag(req.locale.as_deref().or(preferences.locale.as_deref()));
+    let amount_verified = audio::verify_amount(expected_amount, typed_amount(&req.typed_text, &coin, locale));
+    let destination_verified = req.destination_hint.as_deref().is_some_and(|destination| {
+        audio::verify_destination_suffix(&req.typed_text, destination, preferences.challenge_length())
+    });
+
+    let result = if behavior.is_ok() && amount_verified {
+        info!("RAM TypedAuth: ✓ OK (amount verified)");
+        BioAuthResult::Ok
+    } else {
+        info!("RAM TypedAuth: ✗ INVALID (amount_verified={})", amount_verified);
+        BioAuthResult::InvalidAmount
+    };
+    state.bioauth_attempts.record(&req.handle, result, current_timestamp);
+
+    let payload = BioAuthPayload {
+        handle: req.handle.clone().into_bytes(),
+        amount: req.expected_amount,
+        result: result as u8,
+        transcript: req.typed_text.clone().into_bytes(),
+        destination_verified,
+        method: BioAuthMethod::Typed as u8,
+        seal: decoy::seal(false),
+        failed_attempts,
+    };
+
+    let signature = sign_payload(
+        &state.eph_kp,
+        &payload,
+        current_timestamp,
+        IntentScope::TransferNft, // BIOAUTH_INTENT = 3 (RAM reuses TransferNft slot)
+        version,
+    );
+
+    let request_id = uuid::Uuid::new_v4().to_string();
+    state.bioauth_diagnostics.insert(
+        request_id.clone(),
+        BioAuthData {
+            handle: req.handle.clone(),
+            amount: req.expected_amount,
+            amount_display: locale.format_amount(expected_amount, &coin),
+            result: result.as_str().to_string(),
+            transcript: req.typed_text.clone(),
+            stress_level: 0,
+            locked: false,
+            destination_verified,
+            shadow_mode: false,
+            detected_result: result.as_str().to_string(),
+            method: BioAuthMethod::Typed.as_str().to_string(),
+            decoy: false,
+            panic_phrase: false,
+            analyzer_disagreement: None,
+        },
+    );
+
+    Ok(Json(BioAuthResponse {
+        payload,
+        intent: BIOAUTH_INTENT,
+        payload_version: version as u8,
+        timestamp_ms: current_timestamp,
+        signature,
+        request_id,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Keystrokes with human-ish uneven gaps and ~80ms holds
+    fn human(count: usize) -> Vec<Keystroke> {
+        let mut t = 0;
+        (0..count)
+            .map(|i| {
+                t += 120 + (i as u64 * 37) % 90;
+                Keystroke { down_ms: t, up_ms: t + 70 + (i as u64 * 13) % 30 }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_human_typing_passes() {
+        assert!(check_keystrokes("5 SUI", &human(5), false).is_ok());
+        // Corrections add keystrokes
+        assert!(check_keystrokes("5 SUI", &human(8), false).is_ok());
+    }
+
+    #[test]
+    fn test_scripted_input_rejected() {
+        assert!(check_keystrokes("5 SUI", &human(5), true).is_err());
+        assert!(check_keystrokes("5 SUI", &human(2), false).is_err());
+
+        let uniform: Vec<Keystroke> = (0..5)
+            .map(|i| Keystroke { down_ms: i * 100, up_ms: i * 100 + 50 })
+            .collect();
+        assert!(check_keystrokes("5 SUI", &uniform, false).is_err());
+
+        let instant: Vec<Keystroke> = human(5).iter().map(|k| Keystroke { down_ms: k.down_ms, up_ms: k.down_ms }).collect();
+        assert!(check_keystrokes("5 SUI", &instant, false).is_err());
+    }
+
+    #[test]
+    fn test_typed_amount() {
+        let (sui, us, de) = (CoinInfo::sui(), NumberLocale::Western, NumberLocale::Continental);
+        assert_eq!(typed_amount("5 SUI", &sui, us), Some(RawAmount(5_000_000_000)));
+        assert_eq!(typed_amount("send 2.5 SUI", &sui, us), Some(RawAmount(2_500_000_000)));
+        assert_eq!(typed_amount("5, SUI", &sui, us), Some(RawAmount(5_000_000_000)));
+        assert_eq!(typed_amount("five", &sui, us), None);
+        // f64 parsing used to accept these
+        assert_eq!(typed_amount("inf NaN 1e3", &sui, us), None);
+
+        // A German user's 1.234,5 is 1234.5, not 1.234
+        assert_eq!(typed_amount("1.234,5 SUI", &sui, de), Some(RawAmount(1_234_500_000_000)));
+        assert_eq!(typed_amount("2,5 SUI", &sui, de), Some(RawAmount(2_500_000_000)));
+        assert_eq!(typed_amount("2.5 SUI", &sui, de), Some(RawAmount(2_500_000_000)));
+        assert_eq!(typed_amount("1,234.5 SUI", &sui, us), Some(RawAmount(1_234_500_000_000)));
+    }
+}
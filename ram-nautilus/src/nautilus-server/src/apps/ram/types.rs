@@ -9,6 +9,8 @@
 use serde::{Deserialize, Serialize};
 use serde_repr::{Deserialize_repr, Serialize_repr};
 
+use super::disagreement::Disagreement;
+
 // ============================================================================
 // INTENT CONSTANTS - Must match Move contract (core.move)
 // ============================================================================
@@ -19,6 +21,12 @@ pub const LINK_ADDRESS_INTENT: u8 = 1;
 pub const TRANSFER_INTENT: u8 = 2;
 pub const BIOAUTH_INTENT: u8 = 3;
 pub const WITHDRAW_INTENT: u8 = 4;
+pub const UNLINK_ADDRESS_INTENT: u8 = 5;
+pub const SET_LIMIT_INTENT: u8 = 6;
+pub const FREEZE_INTENT: u8 = 7;
+pub const UNFREEZE_INTENT: u8 = 8;
+pub const BENEFICIARY_INTENT: u8 = 9;
+pub const DEPOSIT_PROOF_INTENT: u8 = 10;
 
 // ============================================================================
 // PAYLOAD TYPES - Must match Move contract definitions
@@ -37,6 +45,15 @@ pub struct CreateWalletPayload {
 pub struct LinkAddressPayload {
     pub handle: Vec<u8>,         // User handle as bytes
     pub address: [u8; 32],       // Sui wallet address (32 bytes)
+    pub label: Vec<u8>,          // Address label (e.g. "ledger", "phone")
+}
+
+/// Unlink address payload
+/// Must match UnlinkAddressPayload in core.move
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UnlinkAddressPayload {
+    pub handle: Vec<u8>,         // User handle as bytes
+    pub address: [u8; 32],       // Sui wallet address to remove
 }
 
 /// Transfer payload
@@ -46,7 +63,8 @@ pub struct TransferPayload {
     pub from_handle: Vec<u8>,    // Source handle as bytes
     pub to_handle: Vec<u8>,      // Destination handle as bytes
     pub amount: u64,             // Amount in smallest unit
-    pub coin_type: Vec<u8>,      // Coin type as bytes
+    pub coin_type: Vec<u8>,      // type_name::get<T>() bytes (unprefixed 64-hex address)
+    pub memo: Vec<u8>,           // UTF-8 payment reference, empty if none
 }
 
 /// BioAuth payload
@@ -57,6 +75,50 @@ pub struct BioAuthPayload {
     pub amount: u64,             // Expected transfer amount
     pub result: u8,              // 0=OK, 1=InvalidAmount, 2=Duress
     pub transcript: Vec<u8>,     // What user said (for debugging)
+    pub destination_verified: bool, // User spoke the withdrawal address suffix
+    pub method: u8,              // 0=Voice, 1=Typed (accessibility path)
+    pub seal: Vec<u8>,           // Sealed decoy flag (see `decoy`): the contract locks on a decoy
+    pub failed_attempts: u8,     // Consecutive failed bio-auths before this one
+}
+
+/// Daily spending limit payload
+/// Must match SetLimitPayload in core.move
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SetLimitPayload {
+    pub handle: Vec<u8>,         // User handle as bytes
+    pub daily_limit: u64,        // Most that may leave the wallet per 24 h, in smallest unit
+    pub coin_type: Vec<u8>,      // type_name::get<T>() bytes (unprefixed 64-hex address)
+}
+
+/// Self-service freeze payload
+/// Must match FreezePayload in core.move
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FreezePayload {
+    pub handle: Vec<u8>,         // User handle as bytes
+}
+
+/// Voice-confirmed unfreeze payload (takes effect after the contract's delay)
+/// Must match UnfreezePayload in core.move
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UnfreezePayload {
+    pub handle: Vec<u8>,         // User handle as bytes
+}
+
+/// Inactivity beneficiary payload (an empty beneficiary removes it)
+/// Must match BeneficiaryPayload in core.move
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BeneficiaryPayload {
+    pub handle: Vec<u8>,             // User handle as bytes
+    pub beneficiary_handle: Vec<u8>, // Who may claim the wallet once inactive, empty for none
+    pub inactivity_timeout_ms: u64,  // How long the wallet must be inactive first
+}
+
+/// Deposit proof payload: binds a deposit ID (an exchange's memo) to a handle
+/// Must match DepositProofPayload in core.move
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DepositProofPayload {
+    pub handle: Vec<u8>,             // User handle as bytes
+    pub deposit_id: Vec<u8>,         // Memo or intent ID deposits under this proof carry
 }
 
 /// Withdraw payload
@@ -65,7 +127,7 @@ pub struct BioAuthPayload {
 pub struct WithdrawPayload {
     pub handle: Vec<u8>,         // User handle as bytes
     pub amount: u64,             // Amount in smallest unit
-    pub coin_type: Vec<u8>,      // Coin type as bytes
+    pub coin_type: Vec<u8>,      // type_name::get<T>() bytes (unprefixed 64-hex address)
 }
 
 // ============================================================================
@@ -76,6 +138,10 @@ pub struct WithdrawPayload {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CreateWalletRequest {
     pub handle: String,  // User's unique handle (e.g., username, phone number hash)
+    #[serde(default)]
+    pub pow_nonce: Option<String>,     // Proof of work, when the registration gate asks for one
+    #[serde(default)]
+    pub captcha_token: Option<String>, // Solved CAPTCHA, when the registration gate asks for one
 }
 
 /// Request to link a Sui address to RAM wallet
@@ -85,6 +151,29 @@ pub struct LinkAddressRequest {
     pub wallet_address: String,      // Sui wallet address (0x...)
     pub wallet_signature: String,    // Signature of message proving ownership
     pub message: String,             // The message that was signed
+    #[serde(default)]
+    pub label: String,               // Address label (e.g. "ledger", "phone")
+}
+
+/// Request to link a zkLogin (Google/Apple sign-in) address to RAM wallet
+///
+/// The ephemeral key signs `link_message(handle, wallet_address, issued_at_ms)`
+/// as a Sui personal message.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LinkZkLoginRequest {
+    pub handle: String,              // User's handle
+    pub wallet_address: String,      // zkLogin address (0x...)
+    pub zklogin_signature: String,   // Serialized zkLogin signature (base64, flag 0x05)
+    pub issued_at_ms: u64,           // When the link message was signed
+    #[serde(default)]
+    pub label: String,               // Address label (e.g. "google")
+}
+
+/// Request to unlink a Sui address from RAM wallet
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UnlinkAddressRequest {
+    pub handle: String,              // User's handle
+    pub wallet_address: String,      // Sui wallet address (0x...) to remove
 }
 
 /// BioAuth request containing voice audio
@@ -93,7 +182,81 @@ pub struct BioAuthRequest {
     pub handle: String,              // User's handle
     pub audio_base64: String,        // Base64 encoded audio file (WAV/MP3)
     pub expected_amount: u64,        // Amount in smallest unit (MIST for SUI)
-    pub coin_type: Option<String>,   // Optional coin type (default: SUI)
+    pub coin_type: Option<String>,   // Move coin type, e.g. "0x2::sui::SUI" (the default)
+    #[serde(default)]
+    pub destination_hint: Option<String>, // Withdrawal destination; user must speak its last 4 chars
+    #[serde(default)]
+    pub locale: Option<String>,      // BCP 47 tag, e.g. "de-DE"; how the user writes numbers
+    #[serde(default)]
+    pub skip_analysis_cache: bool,   // Analyze afresh even if this clip was analyzed moments ago
+}
+
+/// Typed-confirmation auth for users who cannot speak
+///
+/// The user types the amount (and, for withdrawals, the destination suffix);
+/// the client records key timings while they type so the enclave can check
+/// that a person entered it rather than a script or a paste.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TypedAuthRequest {
+    pub handle: String,              // User's handle
+    pub typed_text: String,          // What the user typed, e.g. "5 SUI" or "5 SUI 3f9a"
+    pub expected_amount: u64,        // Amount in smallest unit (MIST for SUI)
+    pub coin_type: Option<String>,   // Move coin type, e.g. "0x2::sui::SUI" (the default)
+    #[serde(default)]
+    pub destination_hint: Option<String>, // Withdrawal destination; user must type its last 4 chars
+    #[serde(default)]
+    pub locale: Option<String>,      // BCP 47 tag; typed amounts are read the way it writes numbers
+    pub keystrokes: Vec<Keystroke>,  // One entry per key press, in order
+    #[serde(default)]
+    pub pasted: bool,                // Client saw a paste/autofill into the field
+}
+
+/// Timing of one key press, relative to when the field was focused
+///
+/// Only timings are sent, never which key was pressed.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Keystroke {
+    pub down_ms: u64,
+    pub up_ms: u64,
+}
+
+/// Request for the phrase to speak in an upcoming bio-auth
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChallengeRequest {
+    pub handle: String,
+    pub expected_amount: u64,         // Amount in smallest unit, as in BioAuthRequest
+    pub coin_type: Option<String>,    // Move coin type, as in BioAuthRequest
+    #[serde(default)]
+    pub destination_hint: Option<String>, // Withdrawal destination, adds its spoken suffix
+    #[serde(default)]
+    pub locale: Option<String>,       // BCP 47 tag; language of the phrase (default: Accept-Language)
+}
+
+/// Issued bio-auth challenge; play it back via `GET /bio_auth/challenge_audio/:challenge_id`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChallengeResponse {
+    pub challenge_id: String,
+    pub phrase: String,
+    pub expires_at_ms: u64,
+}
+
+/// Calm voice sample enrolling a handle's acoustic baseline
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EnrollVoiceRequest {
+    pub handle: String,
+    pub audio_base64: String,        // Base64 encoded WAV of the user speaking normally
+    #[serde(default)]
+    pub panic_phrase: Option<String>, // Covert phrase that forces Duress in bio-auth (never echoed)
+}
+
+/// Enrollment progress (not signed; the baseline never leaves the enclave)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnrollVoiceResponse {
+    pub handle: String,
+    pub samples: u32,                // Calm samples accepted so far
+    pub required: u32,               // Samples needed before the baseline is used
+    pub complete: bool,              // Baseline is active and frozen
+    pub panic_phrase_set: bool,      // A panic phrase is registered
 }
 
 /// Request to sign a transfer
@@ -102,7 +265,89 @@ pub struct TransferRequest {
     pub from_handle: String,         // Sender's handle
     pub to_handle: String,           // Recipient's handle
     pub amount: u64,                 // Amount in smallest unit
-    pub coin_type: String,           // Coin type string (e.g., "0x2::sui::SUI")
+    pub coin_type: String,           // Move coin type, e.g. "0x2::sui::SUI"
+    #[serde(default)]
+    pub memo: Option<String>,        // Payment reference (e.g. an invoice number), signed as-is
+    #[serde(default)]
+    pub simulate: Option<TransferSimulation>, // Dry-run the transfer against these wallets before answering
+}
+
+/// Wallet objects a transfer is dry-run against before it is signed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransferSimulation {
+    pub from_wallet_id: String,      // Sender's RamWallet object ID
+    pub to_wallet_id: String,        // Recipient's RamWallet object ID
+}
+
+/// Request to set a daily spending limit by voice
+/// ("set my daily limit to 50 SUI")
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SetLimitRequest {
+    pub handle: String,              // User's handle
+    pub audio_base64: String,        // Base64 encoded audio file (WAV/MP3)
+    pub daily_limit: u64,            // Limit in smallest unit; must match what is spoken
+    #[serde(default)]
+    pub coin_type: Option<String>,   // Move coin type, e.g. "0x2::sui::SUI" (the default)
+    #[serde(default)]
+    pub locale: Option<String>,      // BCP 47 tag; how the user says numbers
+}
+
+/// Request to freeze a wallet (no voice needed)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FreezeWalletRequest {
+    pub handle: String,              // User's handle
+}
+
+/// Request to name (or, with an empty beneficiary, remove) an inactivity beneficiary
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SetBeneficiaryRequest {
+    pub handle: String,              // User's handle
+    pub beneficiary_handle: String,  // Wallet that may claim the funds, empty to remove
+    #[serde(default)]
+    pub inactivity_timeout_ms: u64,  // Inactivity before the claim window opens
+}
+
+/// Request to bind a deposit ID to a handle, for an exchange crediting deposits
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProveDepositRequest {
+    pub handle: String,              // User's handle
+    pub deposit_id: String,          // The exchange's memo or intent ID for this user
+}
+
+/// A handle's policy preferences, kept sealed in the enclave (see `preferences`)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UserPreferences {
+    #[serde(default)]
+    pub stress_sensitivity: i8,      // Points added to stress scores before the duress threshold (-10 to 10)
+    #[serde(default)]
+    pub locale: Option<String>,      // BCP 47 tag for requests that carry none
+    #[serde(default)]
+    pub challenge_length: Option<u8>, // Destination characters to speak for withdrawals (4 to 8, default 4)
+}
+
+/// Request to replace a handle's preferences after a successful bio-auth
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdatePreferencesRequest {
+    pub handle: String,              // User's handle
+    pub bio_auth_request_id: String, // `request_id` of an OK bio-auth of the handle; single use
+    #[serde(default)]
+    pub preferences: UserPreferences, // Unset fields go back to their defaults
+}
+
+/// Request for the phrase to speak to unfreeze a wallet
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UnfreezeChallengeRequest {
+    pub handle: String,              // User's handle
+    #[serde(default)]
+    pub locale: Option<String>,      // BCP 47 tag; language of the phrase (default: Accept-Language)
+}
+
+/// Request to unfreeze a wallet, speaking an issued unfreeze challenge
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RequestUnfreezeRequest {
+    pub handle: String,              // User's handle
+    pub challenge_id: String,        // From POST /unfreeze/challenge; single use
+    pub audio_base64: String,        // Base64 encoded audio of the challenge phrase
 }
 
 /// Request to sign a withdrawal
@@ -110,7 +355,7 @@ pub struct TransferRequest {
 pub struct WithdrawRequest {
     pub handle: String,              // User's handle
     pub amount: u64,                 // Amount in smallest unit
-    pub coin_type: String,           // Coin type string
+    pub coin_type: String,           // Move coin type, e.g. "0x2::sui::SUI"
 }
 
 // ============================================================================
@@ -122,6 +367,7 @@ pub struct WithdrawRequest {
 pub struct CreateWalletResponse {
     pub payload: CreateWalletPayload,
     pub intent: u8,
+    pub payload_version: u8,
     pub timestamp_ms: u64,
     pub signature: String,
 }
@@ -131,6 +377,17 @@ pub struct CreateWalletResponse {
 pub struct LinkAddressResponse {
     pub payload: LinkAddressPayload,
     pub intent: u8,
+    pub payload_version: u8,
+    pub timestamp_ms: u64,
+    pub signature: String,
+}
+
+/// Response for unlink address
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnlinkAddressResponse {
+    pub payload: UnlinkAddressPayload,
+    pub intent: u8,
+    pub payload_version: u8,
     pub timestamp_ms: u64,
     pub signature: String,
 }
@@ -145,6 +402,24 @@ pub enum BioAuthResult {
     Duress = 2,        // Stress/panic detected -> LOCK WALLET
 }
 
+/// How the user confirmed a bio-auth
+/// Must match BIOAUTH_METHOD_VOICE, BIOAUTH_METHOD_TYPED in core.move
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize_repr, Deserialize_repr)]
+#[repr(u8)]
+pub enum BioAuthMethod {
+    Voice = 0, // Spoken confirmation with stress analysis
+    Typed = 1, // Typed confirmation with keystroke-dynamics checks
+}
+
+impl BioAuthMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BioAuthMethod::Voice => "voice",
+            BioAuthMethod::Typed => "typed",
+        }
+    }
+}
+
 impl BioAuthResult {
     pub fn as_str(&self) -> &'static str {
         match self {
@@ -160,10 +435,19 @@ impl BioAuthResult {
 pub struct BioAuthData {
     pub handle: String,
     pub amount: u64,
+    pub amount_display: String, // Expected amount in the user's locale, e.g. "1.234,5 SUI"
     pub result: String,       // "ok", "invalid_amount", "duress"
     pub transcript: String,   // What the AI heard
     pub stress_level: u8,     // 0-100 stress indicator
     pub locked: bool,         // Will wallet be locked?
+    pub destination_verified: bool, // Spoken address suffix matched (withdrawals)
+    pub shadow_mode: bool,    // Duress detection was not enforced
+    pub detected_result: String, // Analyzer verdict before shadow mode (differs from result only in shadow)
+    pub method: String,       // "voice" or "typed"
+    pub decoy: bool,          // Duress was signed as an OK-looking decoy
+    pub panic_phrase: bool,   // Duress was triggered by the user's panic phrase
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub analyzer_disagreement: Option<Disagreement>, // Stress sub-scores, when the analyzers diverged
 }
 
 /// Complete BioAuth response (BLIND - no human-readable data)
@@ -174,10 +458,14 @@ pub struct BioAuthResponse {
     pub payload: BioAuthPayload,
     /// Intent code (should be BIOAUTH_INTENT = 3)
     pub intent: u8,
+    /// Layout the payload was signed in (1 or 2, see `versions`)
+    pub payload_version: u8,
     /// Timestamp used in signature
     pub timestamp_ms: u64,
     /// Hex-encoded signature
     pub signature: String,
+    /// Opaque ID the backend can use to fetch diagnostics over the privileged channel
+    pub request_id: String,
     // NO data field! Frontend learns result from blockchain events only.
 }
 
@@ -186,8 +474,32 @@ pub struct BioAuthResponse {
 pub struct TransferResponse {
     pub payload: TransferPayload,
     pub intent: u8,
+    pub payload_version: u8,
     pub timestamp_ms: u64,
     pub signature: String,
+    /// Dry run of the signed call, if the request asked for one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub simulation: Option<SimulatedTransfer>,
+}
+
+/// A transfer's dry run that found it would succeed
+///
+/// A failing one is answered with `simulation_failed` instead. Events and
+/// object changes are left out: under a duress decoy they'd give it away.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SimulatedTransfer {
+    /// The fullnode's execution status ("success")
+    pub status: String,
+    pub gas_used: SimulatedGas,
+}
+
+/// Gas the dry run used, in MIST
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SimulatedGas {
+    pub computation_cost: u64,
+    pub storage_cost: u64,
+    pub storage_rebate: u64,
+    pub non_refundable_storage_fee: u64,
 }
 
 /// Response for withdraw signature
@@ -195,6 +507,64 @@ pub struct TransferResponse {
 pub struct WithdrawResponse {
     pub payload: WithdrawPayload,
     pub intent: u8,
+    pub payload_version: u8,
+    pub timestamp_ms: u64,
+    pub signature: String,
+}
+
+/// Response for a voice-confirmed daily limit
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetLimitResponse {
+    pub payload: SetLimitPayload,
+    pub intent: u8,
+    pub payload_version: u8,
+    pub timestamp_ms: u64,
+    pub signature: String,
+}
+
+/// Response for a freeze
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FreezeWalletResponse {
+    pub payload: FreezePayload,
+    pub intent: u8,
+    pub payload_version: u8,
+    pub timestamp_ms: u64,
+    pub signature: String,
+}
+
+/// Response for an inactivity beneficiary
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetBeneficiaryResponse {
+    pub payload: BeneficiaryPayload,
+    pub intent: u8,
+    pub payload_version: u8,
+    pub timestamp_ms: u64,
+    pub signature: String,
+}
+
+/// A handle's preferences as now stored
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreferencesResponse {
+    pub handle: String,
+    pub preferences: UserPreferences,
+}
+
+/// Response for a deposit proof
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProveDepositResponse {
+    pub payload: DepositProofPayload,
+    pub intent: u8,
+    pub payload_version: u8,
+    pub timestamp_ms: u64,
+    pub signature: String,
+}
+
+/// Response for a voice-confirmed unfreeze request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnfreezeResponse {
+    pub payload: UnfreezePayload,
+    pub intent: u8,
+    pub payload_version: u8,
     pub timestamp_ms: u64,
     pub signature: String,
 }
@@ -0,0 +1,766 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! Request validation
+//!
+//! Every request body is checked field by field before a handler runs, and
+//! all problems are answered together as a 422 listing each field:
+//!
+//! ```json
+//! { "code": "validation_failed", "message": "Request validation failed",
+//!   "details": { "fields": [{ "field": "payload.expected_amount", "message": "must be greater than 0" }] },
+//!   "request_id": "..." }
+//! ```
+//!
+//! Without this, a bad coin type or a truncated clip surfaced as an opaque
+//! 400 from deep inside audio analysis, one problem per round trip, and a
+//! malformed body got axum's plain-text rejection. Handlers take
+//! `ValidatedJson<ProcessDataRequest<T>>` instead of `Json<...>` and can
+//! assume the checks below hold.
+//!
+//! Handles are NFKC-normalized before they're checked, so "ａｌｉｃｅ" signs
+//! as "alice", but their case is kept: the contract checks signatures against
+//! the handle a wallet was registered with, and wallets registered as "Alice"
+//! must keep signing as "Alice". State the enclave keeps per handle (baselines,
+//! attempts, locks, preferences) is keyed by `normalize_handle`, which also
+//! lowercases, so "Alice" and "alice" never get two of it. The backend matches
+//! handles in its database the same way.
+
+use crate::common::ProcessDataRequest;
+use crate::{EnclaveError, FieldError};
+use axum::extract::rejection::JsonRejection;
+use axum::extract::{FromRequest, Request};
+use axum::Json;
+use serde::de::DeserializeOwned;
+use unicode_normalization::UnicodeNormalization;
+
+use super::audio::{destination_suffix, DESTINATION_SUFFIX_LEN, MAX_DESTINATION_SUFFIX_LEN};
+use super::beneficiary::{MAX_INACTIVITY_TIMEOUT_MS, MIN_INACTIVITY_TIMEOUT_MS};
+use super::coin::CoinType;
+use super::deposit::MAX_DEPOSIT_ID_LEN;
+use super::panic_phrase::PanicPhrase;
+use super::preferences::MAX_STRESS_SENSITIVITY;
+use super::registration_gate::MAX_GATE_PROOF_LEN;
+use super::types::*;
+use super::versions::PayloadVersion;
+
+/// Longest handle in bytes
+pub const MAX_HANDLE_LEN: usize = 64;
+/// Shortest accepted audio clip, base64 encoded: a WAV header plus a few samples
+pub const MIN_AUDIO_BASE64_LEN: usize = 64;
+/// Longest accepted audio clip, base64 encoded (about 12 MiB of audio)
+pub const MAX_AUDIO_BASE64_LEN: usize = 16 * 1024 * 1024;
+/// Longest address label in bytes
+pub const MAX_ADDRESS_LABEL_LEN: usize = 32;
+/// Longest transfer memo in bytes
+pub const MAX_MEMO_LEN: usize = 128;
+/// Longest BCP 47 locale tag
+const MAX_LOCALE_LEN: usize = 35;
+/// Longest typed confirmation in characters
+const MAX_TYPED_TEXT_CHARS: usize = 256;
+/// Most keystrokes a typed confirmation may carry
+const MAX_KEYSTROKES: usize = 512;
+/// Longest ownership message or signature
+const MAX_PROOF_LEN: usize = 1024;
+/// Longest bio-auth request ID (they're UUIDs)
+const MAX_REQUEST_ID_LEN: usize = 64;
+/// Longest serialized zkLogin signature (a Groth16 proof plus JWT claims), base64
+const MAX_ZKLOGIN_SIGNATURE_LEN: usize = 8 * 1024;
+
+/// A request type that can check its own fields
+pub trait Validate {
+    /// Report every invalid field to `v`
+    fn validate(&self, v: &mut Validator);
+
+    /// Rewrite fields that have a canonical form (handles) into it; runs before `validate`
+    fn normalize(&mut self) {}
+}
+
+/// The form of a handle that is signed: NFKC, with its case kept
+pub fn signed_handle(handle: &str) -> String {
+    handle.nfkc().collect()
+}
+
+/// The form of a handle that per-handle state is keyed and compared by: NFKC, then lowercased
+pub fn normalize_handle(handle: &str) -> String {
+    signed_handle(handle).to_lowercase()
+}
+
+/// Collects field errors under a JSON path prefix
+#[derive(Debug, Default)]
+pub struct Validator {
+    prefix: String,
+    errors: Vec<FieldError>,
+}
+
+impl Validator {
+    /// Field errors for `value`, or Ok when there are none
+    pub fn check<T: Validate>(value: &T) -> Result<(), EnclaveError> {
+        let mut v = Self::default();
+        value.validate(&mut v);
+        if v.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(EnclaveError::InvalidRequest(v.errors))
+        }
+    }
+
+    /// Validate a nested value with its fields under `field.`
+    fn nested<T: Validate>(&mut self, field: &str, value: &T) {
+        let inner = self.path(field);
+        let outer = std::mem::replace(&mut self.prefix, inner);
+        value.validate(self);
+        self.prefix = outer;
+    }
+
+    fn path(&self, field: &str) -> String {
+        if self.prefix.is_empty() {
+            field.to_string()
+        } else {
+            format!("{}.{}", self.prefix, field)
+        }
+    }
+
+    /// Record an error for `field` unless `ok`
+    pub fn require(&mut self, ok: bool, field: &str, message: impl Into<String>) {
+        if !ok {
+            self.errors.push(FieldError::new(self.path(field), message));
+        }
+    }
+
+    /// 1 to `MAX_HANDLE_LEN` bytes of letters, digits and `_ - . @ +`
+    pub fn handle(&mut self, field: &str, handle: &str) {
+        if handle.is_empty() {
+            return self.require(false, field, "is required");
+        }
+        self.require(
+            handle.len() <= MAX_HANDLE_LEN,
+            field,
+            format!("must be at most {} bytes", MAX_HANDLE_LEN),
+        );
+        self.require(
+            handle.chars().all(|c| c.is_ascii_alphanumeric() || "_-.@+".contains(c)),
+            field,
+            "may only contain letters, digits and _ - . @ +",
+        );
+    }
+
+    /// An amount in raw units; zero never authorizes anything
+    pub fn amount(&mut self, field: &str, amount: u64) {
+        self.require(amount > 0, field, "must be greater than 0");
+    }
+
+    /// A `0x<address>::<module>::<Type>` tag
+    pub fn coin_type(&mut self, field: &str, tag: &str) {
+        if let Err(e) = CoinType::parse(tag) {
+            self.require(false, field, e.to_string());
+        }
+    }
+
+    /// Padded standard base64 audio within the size bounds
+    pub fn audio_base64(&mut self, field: &str, audio: &str) {
+        if audio.len() < MIN_AUDIO_BASE64_LEN || audio.len() > MAX_AUDIO_BASE64_LEN {
+            return self.require(
+                false,
+                field,
+                format!(
+                    "must be {} to {} base64 characters (got {})",
+                    MIN_AUDIO_BASE64_LEN,
+                    MAX_AUDIO_BASE64_LEN,
+                    audio.len()
+                ),
+            );
+        }
+        let body = audio.trim_end_matches('=');
+        self.require(
+            audio.len().is_multiple_of(4)
+                && audio.len() - body.len() <= 2
+                && body.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'+' || b == b'/'),
+            field,
+            "must be padded standard base64",
+        );
+    }
+
+    /// A 0x-prefixed (or bare) 32-byte hex Sui address
+    pub fn sui_address(&mut self, field: &str, address: &str) {
+        let hex = address.strip_prefix("0x").unwrap_or(address);
+        self.require(
+            hex.len() == 64 && hex.chars().all(|c| c.is_ascii_hexdigit()),
+            field,
+            "must be a 32-byte hex address",
+        );
+    }
+
+    /// A hex destination whose spoken suffix can be checked
+    pub fn destination_hint(&mut self, field: &str, hint: Option<&str>) {
+        if let Some(hint) = hint {
+            self.require(
+                hint.len() <= 66 && destination_suffix(hint, DESTINATION_SUFFIX_LEN).is_some(),
+                field,
+                format!("must be a hex address of at least {} digits", DESTINATION_SUFFIX_LEN),
+            );
+        }
+    }
+
+    /// A BCP 47-looking tag such as `de-DE`
+    pub fn locale(&mut self, field: &str, locale: Option<&str>) {
+        if let Some(locale) = locale {
+            self.require(
+                !locale.is_empty()
+                    && locale.len() <= MAX_LOCALE_LEN
+                    && locale.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'),
+                field,
+                "must be a BCP 47 tag like de-DE",
+            );
+        }
+    }
+
+    /// Up to `MAX_MEMO_LEN` bytes of printable text
+    pub fn memo(&mut self, field: &str, memo: Option<&str>) {
+        if let Some(memo) = memo {
+            self.max_len(field, memo, MAX_MEMO_LEN);
+            self.require(!memo.chars().any(char::is_control), field, "may not contain control characters");
+        }
+    }
+
+    /// At most `max` bytes
+    pub fn max_len(&mut self, field: &str, value: &str, max: usize) {
+        self.require(value.len() <= max, field, format!("must be at most {} bytes", max));
+    }
+}
+
+impl<T: Validate> Validate for ProcessDataRequest<T> {
+    fn validate(&self, v: &mut Validator) {
+        v.nested("payload", &self.payload);
+        let known = self.payload_version.is_none_or(|version| PayloadVersion::from_u8(version).is_some());
+        v.require(known, "payload_version", "must be 1, 2 or 3");
+    }
+
+    fn normalize(&mut self) {
+        self.payload.normalize();
+    }
+}
+
+impl Validate for CreateWalletRequest {
+    fn validate(&self, v: &mut Validator) {
+        v.handle("handle", &self.handle);
+        for (field, proof) in [("pow_nonce", &self.pow_nonce), ("captcha_token", &self.captcha_token)] {
+            if let Some(proof) = proof {
+                v.max_len(field, proof, MAX_GATE_PROOF_LEN);
+            }
+        }
+    }
+
+    fn normalize(&mut self) {
+        self.handle = signed_handle(&self.handle);
+    }
+}
+
+impl Validate for LinkAddressRequest {
+    fn validate(&self, v: &mut Validator) {
+        v.handle("handle", &self.handle);
+        v.sui_address("wallet_address", &self.wallet_address);
+        v.max_len("wallet_signature", &self.wallet_signature, MAX_PROOF_LEN);
+        v.max_len("message", &self.message, MAX_PROOF_LEN);
+        v.max_len("label", &self.label, MAX_ADDRESS_LABEL_LEN);
+    }
+
+    fn normalize(&mut self) {
+        self.handle = signed_handle(&self.handle);
+    }
+}
+
+impl Validate for LinkZkLoginRequest {
+    fn validate(&self, v: &mut Validator) {
+        v.handle("handle", &self.handle);
+        v.sui_address("wallet_address", &self.wallet_address);
+        v.require(!self.zklogin_signature.is_empty(), "zklogin_signature", "is required");
+        v.max_len("zklogin_signature", &self.zklogin_signature, MAX_ZKLOGIN_SIGNATURE_LEN);
+        v.max_len("label", &self.label, MAX_ADDRESS_LABEL_LEN);
+    }
+
+    fn normalize(&mut self) {
+        self.handle = signed_handle(&self.handle);
+    }
+}
+
+impl Validate for UnlinkAddressRequest {
+    fn validate(&self, v: &mut Validator) {
+        v.handle("handle", &self.handle);
+        v.sui_address("wallet_address", &self.wallet_address);
+    }
+
+    fn normalize(&mut self) {
+        self.handle = signed_handle(&self.handle);
+    }
+}
+
+impl Validate for BioAuthRequest {
+    fn validate(&self, v: &mut Validator) {
+        v.handle("handle", &self.handle);
+        v.audio_base64("audio_base64", &self.audio_base64);
+        v.amount("expected_amount", self.expected_amount);
+        if let Some(coin_type) = &self.coin_type {
+            v.coin_type("coin_type", coin_type);
+        }
+        v.destination_hint("destination_hint", self.destination_hint.as_deref());
+        v.locale("locale", self.locale.as_deref());
+    }
+
+    fn normalize(&mut self) {
+        self.handle = signed_handle(&self.handle);
+    }
+}
+
+impl Validate for TypedAuthRequest {
+    fn validate(&self, v: &mut Validator) {
+        v.handle("handle", &self.handle);
+        v.require(!self.typed_text.trim().is_empty(), "typed_text", "is required");
+        v.require(
+            self.typed_text.chars().count() <= MAX_TYPED_TEXT_CHARS,
+            "typed_text",
+            format!("must be at most {} characters", MAX_TYPED_TEXT_CHARS),
+        );
+        v.amount("expected_amount", self.expected_amount);
+        if let Some(coin_type) = &self.coin_type {
+            v.coin_type("coin_type", coin_type);
+        }
+        v.destination_hint("destination_hint", self.destination_hint.as_deref());
+        v.locale("locale", self.locale.as_deref());
+        v.require(
+            self.keystrokes.len() <= MAX_KEYSTROKES,
+            "keystrokes",
+            format!("must have at most {} entries", MAX_KEYSTROKES),
+        );
+        for (i, key) in self.keystrokes.iter().enumerate() {
+            v.require(key.up_ms >= key.down_ms, &format!("keystrokes[{}].up_ms", i), "must not be before down_ms");
+        }
+    }
+
+    fn normalize(&mut self) {
+        self.handle = signed_handle(&self.handle);
+    }
+}
+
+impl Validate for ChallengeRequest {
+    fn validate(&self, v: &mut Validator) {
+        v.handle("handle", &self.handle);
+        v.amount("expected_amount", self.expected_amount);
+        if let Some(coin_type) = &self.coin_type {
+            v.coin_type("coin_type", coin_type);
+        }
+        v.destination_hint("destination_hint", self.destination_hint.as_deref());
+        v.locale("locale", self.locale.as_deref());
+    }
+
+    fn normalize(&mut self) {
+        self.handle = signed_handle(&self.handle);
+    }
+}
+
+impl Validate for EnrollVoiceRequest {
+    fn validate(&self, v: &mut Validator) {
+        v.handle("handle", &self.handle);
+        v.audio_base64("audio_base64", &self.audio_base64);
+        if let Some(Err(e)) = self.panic_phrase.as_deref().map(PanicPhrase::new) {
+            v.require(false, "panic_phrase", e.to_string());
+        }
+    }
+
+    fn normalize(&mut self) {
+        self.handle = signed_handle(&self.handle);
+    }
+}
+
+impl Validate for TransferRequest {
+    fn validate(&self, v: &mut Validator) {
+        v.handle("from_handle", &self.from_handle);
+        v.handle("to_handle", &self.to_handle);
+        v.amount("amount", self.amount);
+        v.coin_type("coin_type", &self.coin_type);
+        v.memo("memo", self.memo.as_deref());
+        if let Some(wallets) = &self.simulate {
+            v.sui_address("simulate.from_wallet_id", &wallets.from_wallet_id);
+            v.sui_address("simulate.to_wallet_id", &wallets.to_wallet_id);
+        }
+    }
+
+    fn normalize(&mut self) {
+        self.from_handle = signed_handle(&self.from_handle);
+        self.to_handle = signed_handle(&self.to_handle);
+    }
+}
+
+impl Validate for SetLimitRequest {
+    fn validate(&self, v: &mut Validator) {
+        v.handle("handle", &self.handle);
+        v.audio_base64("audio_base64", &self.audio_base64);
+        v.amount("daily_limit", self.daily_limit);
+        if let Some(coin_type) = &self.coin_type {
+            v.coin_type("coin_type", coin_type);
+        }
+        v.locale("locale", self.locale.as_deref());
+    }
+
+    fn normalize(&mut self) {
+        self.handle = signed_handle(&self.handle);
+    }
+}
+
+impl Validate for FreezeWalletRequest {
+    fn validate(&self, v: &mut Validator) {
+        v.handle("handle", &self.handle);
+    }
+
+    fn normalize(&mut self) {
+        self.handle = signed_handle(&self.handle);
+    }
+}
+
+impl Validate for SetBeneficiaryRequest {
+    fn validate(&self, v: &mut Validator) {
+        v.handle("handle", &self.handle);
+        // Empty removes the beneficiary, and then the timeout is unused
+        if self.beneficiary_handle.is_empty() {
+            return;
+        }
+        v.handle("beneficiary_handle", &self.beneficiary_handle);
+        v.require(
+            normalize_handle(&self.beneficiary_handle) != normalize_handle(&self.handle),
+            "beneficiary_handle",
+            "must be another wallet",
+        );
+        v.require(
+            (MIN_INACTIVITY_TIMEOUT_MS..=MAX_INACTIVITY_TIMEOUT_MS).contains(&self.inactivity_timeout_ms),
+            "inactivity_timeout_ms",
+            format!(
+                "must be between {} and {} days",
+                MIN_INACTIVITY_TIMEOUT_MS / 86_400_000,
+                MAX_INACTIVITY_TIMEOUT_MS / 86_400_000
+            ),
+        );
+    }
+
+    fn normalize(&mut self) {
+        self.handle = signed_handle(&self.handle);
+        self.beneficiary_handle = signed_handle(&self.beneficiary_handle);
+    }
+}
+
+impl Validate for ProveDepositRequest {
+    fn validate(&self, v: &mut Validator) {
+        v.handle("handle", &self.handle);
+        if self.deposit_id.is_empty() {
+            return v.require(false, "deposit_id", "is required");
+        }
+        v.max_len("deposit_id", &self.deposit_id, MAX_DEPOSIT_ID_LEN);
+        // Exchange memos are plain identifiers; anything else is likely a paste error
+        v.require(
+            self.deposit_id.chars().all(|c| c.is_ascii_alphanumeric() || "_-.:".contains(c)),
+            "deposit_id",
+            "may only contain letters, digits and _ - . :",
+        );
+    }
+
+    fn normalize(&mut self) {
+        self.handle = signed_handle(&self.handle);
+    }
+}
+
+impl Validate for UpdatePreferencesRequest {
+    fn validate(&self, v: &mut Validator) {
+        v.handle("handle", &self.handle);
+        v.require(!self.bio_auth_request_id.is_empty(), "bio_auth_request_id", "is required");
+        v.max_len("bio_auth_request_id", &self.bio_auth_request_id, MAX_REQUEST_ID_LEN);
+        v.nested("preferences", &self.preferences);
+    }
+
+    fn normalize(&mut self) {
+        self.handle = signed_handle(&self.handle);
+    }
+}
+
+impl Validate for UserPreferences {
+    fn validate(&self, v: &mut Validator) {
+        v.require(
+            (-MAX_STRESS_SENSITIVITY..=MAX_STRESS_SENSITIVITY).contains(&self.stress_sensitivity),
+            "stress_sensitivity",
+            format!("must be between {} and {}", -MAX_STRESS_SENSITIVITY, MAX_STRESS_SENSITIVITY),
+        );
+        v.locale("locale", self.locale.as_deref());
+        if let Some(length) = self.challenge_length {
+            v.require(
+                (DESTINATION_SUFFIX_LEN..=MAX_DESTINATION_SUFFIX_LEN).contains(&usize::from(length)),
+                "challenge_length",
+                format!("must be between {} and {}", DESTINATION_SUFFIX_LEN, MAX_DESTINATION_SUFFIX_LEN),
+            );
+        }
+    }
+}
+
+impl Validate for UnfreezeChallengeRequest {
+    fn validate(&self, v: &mut Validator) {
+        v.handle("handle", &self.handle);
+        v.locale("locale", self.locale.as_deref());
+    }
+
+    fn normalize(&mut self) {
+        self.handle = signed_handle(&self.handle);
+    }
+}
+
+impl Validate for RequestUnfreezeRequest {
+    fn validate(&self, v: &mut Validator) {
+        v.handle("handle", &self.handle);
+        v.require(!self.challenge_id.is_empty(), "challenge_id", "is required");
+        v.audio_base64("audio_base64", &self.audio_base64);
+    }
+
+    fn normalize(&mut self) {
+        self.handle = signed_handle(&self.handle);
+    }
+}
+
+impl Validate for WithdrawRequest {
+    fn validate(&self, v: &mut Validator) {
+        v.handle("handle", &self.handle);
+        v.amount("amount", self.amount);
+        v.coin_type("coin_type", &self.coin_type);
+    }
+
+    fn normalize(&mut self) {
+        self.handle = signed_handle(&self.handle);
+    }
+}
+
+/// JSON body extractor that runs `Validate` before the handler
+///
+/// Malformed JSON and missing or mistyped fields are reported the same way,
+/// under the `body` field.
+pub struct ValidatedJson<T>(pub T);
+
+#[axum::async_trait]
+impl<T, S> FromRequest<S> for ValidatedJson<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = EnclaveError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(value) = Json::<T>::from_request(req, state)
+            .await
+            .map_err(|rejection: JsonRejection| {
+                EnclaveError::InvalidRequest(vec![FieldError::new("body", rejection.body_text())])
+            })?;
+        value.normalize();
+        Validator::check(&value)?;
+        Ok(Self(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apps::ram::attempts::AttemptTracker;
+    use crate::apps::ram::locks::HandleLocks;
+
+    const AUDIO: &str = "UklGRiQAAABXQVZFZm10IBAAAAABAAEAgD4AAAB9AAACABAAZGF0YQAAAAAAAAAAAAAAAA==";
+
+    fn fields(err: Result<(), EnclaveError>) -> Vec<String> {
+        match err {
+            Err(EnclaveError::InvalidRequest(fields)) => fields.into_iter().map(|f| f.field).collect(),
+            Err(other) => panic!("unexpected error {}", other),
+            Ok(()) => vec![],
+        }
+    }
+
+    fn bio_auth() -> BioAuthRequest {
+        BioAuthRequest {
+            handle: "alice".to_string(),
+            audio_base64: AUDIO.to_string(),
+            expected_amount: 5_000_000_000,
+            coin_type: None,
+            destination_hint: None,
+            locale: Some("de-DE".to_string()),
+            skip_analysis_cache: false,
+        }
+    }
+
+    #[test]
+    fn test_valid_request_passes() {
+        assert!(Validator::check(&ProcessDataRequest { payload: bio_auth(), payload_version: None }).is_ok());
+    }
+
+    #[test]
+    fn test_every_invalid_field_is_reported_with_its_path() {
+        let payload = BioAuthRequest {
+            handle: "alice smith".to_string(),
+            audio_base64: "dGVzdA==".to_string(),
+            expected_amount: 0,
+            coin_type: Some("SUI".to_string()),
+            destination_hint: Some("0x12".to_string()),
+            locale: Some("de DE".to_string()),
+            skip_analysis_cache: false,
+        };
+        assert_eq!(
+            fields(Validator::check(&ProcessDataRequest { payload, payload_version: Some(9) })),
+            [
+                "payload.handle",
+                "payload.audio_base64",
+                "payload.expected_amount",
+                "payload.coin_type",
+                "payload.destination_hint",
+                "payload.locale",
+                "payload_version",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_audio_base64_bounds_and_alphabet() {
+        let check = |audio: String| fields(Validator::check(&BioAuthRequest { audio_base64: audio, ..bio_auth() }));
+        assert!(check(AUDIO.to_string()).is_empty());
+        assert_eq!(check(String::new()), ["audio_base64"]);
+        assert_eq!(check("A".repeat(MAX_AUDIO_BASE64_LEN + 4)), ["audio_base64"]);
+        assert_eq!(check(format!("-{}", &AUDIO[1..])), ["audio_base64"]);
+        assert_eq!(check(format!("{}A", AUDIO)), ["audio_base64"]);
+    }
+
+    #[test]
+    fn test_handle_length_and_charset() {
+        let check = |handle: &str| fields(Validator::check(&CreateWalletRequest {
+            handle: handle.to_string(),
+            pow_nonce: None,
+            captcha_token: None,
+        }));
+        assert!(check("alice_handle").is_empty());
+        assert!(check("+15551234567").is_empty());
+        assert_eq!(check(""), ["handle"]);
+        assert_eq!(check(&"a".repeat(MAX_HANDLE_LEN + 1)), ["handle"]);
+        assert_eq!(check("alice\n"), ["handle"]);
+    }
+
+    #[test]
+    fn test_handles_are_nfkc_normalized_before_checking() {
+        let mut request = ProcessDataRequest {
+            payload: TransferRequest {
+                from_handle: "Alice".to_string(),
+                to_handle: "ＢＯＢ".to_string(),
+                amount: 1,
+                coin_type: "0x2::sui::SUI".to_string(),
+                memo: None,
+                simulate: None,
+            },
+            payload_version: None,
+        };
+        request.normalize();
+        assert_eq!((request.payload.from_handle.as_str(), request.payload.to_handle.as_str()), ("Alice", "BOB"));
+        assert!(Validator::check(&request).is_ok());
+        assert_eq!(normalize_handle(&request.payload.from_handle), "alice");
+
+        // A beneficiary differing only by case is still the same wallet
+        let mut beneficiary = SetBeneficiaryRequest {
+            handle: "alice".to_string(),
+            beneficiary_handle: "ALICE".to_string(),
+            inactivity_timeout_ms: MIN_INACTIVITY_TIMEOUT_MS,
+        };
+        beneficiary.normalize();
+        assert_eq!(fields(Validator::check(&beneficiary)), ["beneficiary_handle"]);
+    }
+
+    #[test]
+    fn test_mixed_case_wallets_sign_as_registered() {
+        // A wallet registered as "Alice" before handles were matched case-insensitively
+        let mut request = ProcessDataRequest {
+            payload: BioAuthRequest { handle: "Alice".to_string(), ..bio_auth() },
+            payload_version: None,
+        };
+        request.normalize();
+        assert!(Validator::check(&request).is_ok());
+        assert_eq!(request.payload.handle, "Alice");
+
+        // Its state is shared with every other spelling of the handle
+        let attempts = AttemptTracker::new();
+        for _ in 0..3 {
+            attempts.record("alice", BioAuthResult::InvalidAmount, 1_000);
+        }
+        assert!(attempts.check(&request.payload.handle, 1_000).is_err());
+        let locks = HandleLocks::new();
+        let _held = locks.try_acquire("alice").unwrap();
+        assert!(locks.try_acquire(&request.payload.handle).is_err());
+    }
+
+    #[test]
+    fn test_transfer_and_enrollment_fields() {
+        let transfer = TransferRequest {
+            from_handle: "alice".to_string(),
+            to_handle: String::new(),
+            amount: 0,
+            coin_type: "0x2::sui".to_string(),
+            memo: Some(format!("INV-1\n{}", "x".repeat(MAX_MEMO_LEN))),
+            simulate: Some(TransferSimulation {
+                from_wallet_id: format!("0x{}", "a".repeat(64)),
+                to_wallet_id: "0x2".to_string(),
+            }),
+        };
+        assert_eq!(
+            fields(Validator::check(&transfer)),
+            ["to_handle", "amount", "coin_type", "memo", "memo", "simulate.to_wallet_id"]
+        );
+
+        let enroll = EnrollVoiceRequest {
+            handle: "alice".to_string(),
+            audio_base64: AUDIO.to_string(),
+            panic_phrase: Some("blue".to_string()),
+        };
+        assert_eq!(fields(Validator::check(&enroll)), ["panic_phrase"]);
+    }
+
+    #[test]
+    fn test_beneficiary_fields() {
+        let check = |beneficiary_handle: &str, inactivity_timeout_ms: u64| {
+            fields(Validator::check(&SetBeneficiaryRequest {
+                handle: "alice".to_string(),
+                beneficiary_handle: beneficiary_handle.to_string(),
+                inactivity_timeout_ms,
+            }))
+        };
+        assert!(check("bob", MIN_INACTIVITY_TIMEOUT_MS).is_empty());
+        // Removal ignores the timeout
+        assert!(check("", 0).is_empty());
+        assert_eq!(check("alice", MIN_INACTIVITY_TIMEOUT_MS), ["beneficiary_handle"]);
+        assert_eq!(check("bob", MIN_INACTIVITY_TIMEOUT_MS - 1), ["inactivity_timeout_ms"]);
+        assert_eq!(check("bob", MAX_INACTIVITY_TIMEOUT_MS + 1), ["inactivity_timeout_ms"]);
+    }
+
+    #[test]
+    fn test_deposit_id_fields() {
+        let check = |deposit_id: &str| {
+            fields(Validator::check(&ProveDepositRequest {
+                handle: "alice".to_string(),
+                deposit_id: deposit_id.to_string(),
+            }))
+        };
+        assert!(check("104877321").is_empty());
+        assert!(check("exchange:SUI-7f3a_01").is_empty());
+        assert_eq!(check(""), ["deposit_id"]);
+        assert_eq!(check("memo 42"), ["deposit_id"]);
+        assert_eq!(check(&"9".repeat(MAX_DEPOSIT_ID_LEN + 1)), ["deposit_id"]);
+    }
+
+    #[test]
+    fn test_preference_fields() {
+        let check = |stress_sensitivity: i8, locale: Option<&str>, challenge_length: Option<u8>| {
+            fields(Validator::check(&UpdatePreferencesRequest {
+                handle: "alice".to_string(),
+                bio_auth_request_id: "5f0c7a52-0d7e-4a1b-9d3e-3b2f1c0a9e71".to_string(),
+                preferences: UserPreferences { stress_sensitivity, locale: locale.map(str::to_string), challenge_length },
+            }))
+        };
+        assert!(check(0, None, None).is_empty());
+        assert!(check(-10, Some("vi-VN"), Some(8)).is_empty());
+        assert_eq!(check(11, None, None), ["preferences.stress_sensitivity"]);
+        assert_eq!(check(0, Some("vi VN"), Some(3)), ["preferences.locale", "preferences.challenge_length"]);
+    }
+}
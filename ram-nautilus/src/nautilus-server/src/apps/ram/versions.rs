@@ -0,0 +1,228 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! Versioned payload layouts
+//!
+//! A payload struct can't change shape while contracts verify signatures
+//! over its old BCS layout, so every signed payload has a layout version.
+//! v1 is the struct's fields as signed since launch; v2 prefixes them with
+//! the version byte (core.move's `Versioned<P>`), which lets a later version
+//! change a struct while the contract still tells the layouts apart. v3 adds
+//! the RAM package ID of the configured chain after the version byte
+//! (core.move's `Domain<P>`), so a payload signed for one network never
+//! verifies against the package on another (see `chain`).
+//!
+//! Clients pick a version per request with `payload_version` next to
+//! `payload`, and every signed response says which one it used. During a
+//! migration window the enclave signs both: `PAYLOAD_VERSION_DEFAULT` is used
+//! when a request doesn't ask, and once `PAYLOAD_V1_SUNSET_MS` has passed v1
+//! is refused and unversioned requests get v2 (or v3, if that's the default).
+
+use super::chain;
+use crate::common::{to_signed_response, IntentScope};
+use crate::{EnclaveError, FieldError};
+use fastcrypto::ed25519::Ed25519KeyPair;
+use serde::Serialize;
+
+/// Layout of a signed payload (must match core.move's `PAYLOAD_V*`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum PayloadVersion {
+    /// The payload struct's fields
+    V1 = 1,
+    /// The version byte, then the payload struct's fields
+    V2 = 2,
+    /// The version byte, the RAM package ID, then the payload struct's fields
+    V3 = 3,
+}
+
+impl PayloadVersion {
+    pub fn from_u8(version: u8) -> Option<Self> {
+        match version {
+            1 => Some(PayloadVersion::V1),
+            2 => Some(PayloadVersion::V2),
+            3 => Some(PayloadVersion::V3),
+            _ => None,
+        }
+    }
+}
+
+/// A payload in layout v2+, BCS-identical to core.move's `Versioned<P>`
+#[derive(Debug, Clone, Serialize)]
+struct Versioned<P> {
+    version: u8,
+    payload: P,
+}
+
+/// A payload in layout v3, BCS-identical to core.move's `Domain<P>`
+#[derive(Debug, Clone, Serialize)]
+struct Domain<P> {
+    version: u8,
+    package: [u8; 32],
+    payload: P,
+}
+
+/// Sign `payload` in the given layout, returning the hex signature
+pub(crate) fn sign_payload<P: Serialize + Clone>(
+    kp: &Ed25519KeyPair,
+    payload: &P,
+    timestamp_ms: u64,
+    intent: IntentScope,
+    version: PayloadVersion,
+) -> String {
+    match version {
+        PayloadVersion::V1 => to_signed_response(kp, payload.clone(), timestamp_ms, intent).signature,
+        PayloadVersion::V2 => {
+            let versioned = Versioned { version: version as u8, payload: payload.clone() };
+            to_signed_response(kp, versioned, timestamp_ms, intent).signature
+        }
+        PayloadVersion::V3 => {
+            let package = chain::current().package_address();
+            let domain = Domain { version: version as u8, package, payload: payload.clone() };
+            to_signed_response(kp, domain, timestamp_ms, intent).signature
+        }
+    }
+}
+
+/// Which layouts are signed during the migration window
+#[derive(Debug, Clone)]
+pub struct PayloadVersions {
+    /// Layout for requests that don't ask for one
+    default: PayloadVersion,
+    /// After this time (unix ms) v1 is no longer signed (None = never)
+    v1_sunset_ms: Option<u64>,
+}
+
+impl Default for PayloadVersions {
+    /// v1 unless asked otherwise, with no sunset: what every client expects today
+    fn default() -> Self {
+        Self::new(PayloadVersion::V1, None)
+    }
+}
+
+impl PayloadVersions {
+    pub fn new(default: PayloadVersion, v1_sunset_ms: Option<u64>) -> Self {
+        Self { default, v1_sunset_ms }
+    }
+
+    /// Window from `PAYLOAD_VERSION_DEFAULT` (1, 2 or 3) and `PAYLOAD_V1_SUNSET_MS`;
+    /// unset or invalid values keep the defaults
+    pub fn from_env() -> Self {
+        let default = std::env::var("PAYLOAD_VERSION_DEFAULT")
+            .ok()
+            .and_then(|v| v.trim().parse::<u8>().ok())
+            .and_then(PayloadVersion::from_u8)
+            .unwrap_or(PayloadVersion::V1);
+        let v1_sunset_ms = std::env::var("PAYLOAD_V1_SUNSET_MS")
+            .ok()
+            .and_then(|v| v.trim().parse::<u64>().ok());
+        Self::new(default, v1_sunset_ms)
+    }
+
+    pub fn default_version(&self) -> PayloadVersion {
+        self.default
+    }
+
+    pub fn v1_sunset_ms(&self) -> Option<u64> {
+        self.v1_sunset_ms
+    }
+
+    /// Layout to sign a request in, given the version it asked for
+    pub fn select(&self, requested: Option<u8>) -> Result<PayloadVersion, EnclaveError> {
+        let now_ms = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or_default();
+        self.select_at(requested, now_ms)
+    }
+
+    pub fn select_at(&self, requested: Option<u8>, now_ms: u64) -> Result<PayloadVersion, EnclaveError> {
+        let v1_retired = self.v1_sunset_ms.is_some_and(|sunset| now_ms >= sunset);
+        match requested.map(PayloadVersion::from_u8) {
+            None if v1_retired && self.default == PayloadVersion::V1 => Ok(PayloadVersion::V2),
+            None => Ok(self.default),
+            Some(Some(PayloadVersion::V1)) if v1_retired => Err(EnclaveError::InvalidRequest(vec![FieldError::new(
+                "payload_version",
+                "payload version 1 is no longer signed; use 2 or 3",
+            )])),
+            Some(Some(version)) => Ok(version),
+            Some(None) => Err(EnclaveError::InvalidRequest(vec![FieldError::new(
+                "payload_version",
+                "must be 1, 2 or 3",
+            )])),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apps::ram::abi::move_u8_constants;
+    use crate::apps::ram::types::TransferPayload;
+    use fastcrypto::ed25519::{Ed25519PublicKey, Ed25519Signature};
+    use fastcrypto::encoding::{Encoding, Hex};
+    use fastcrypto::traits::{KeyPair, ToFromBytes, VerifyingKey};
+
+    #[test]
+    fn test_versions_match_move_constants() {
+        let constants = move_u8_constants(include_str!("../../../../../move/ram/sources/core.move"));
+        assert_eq!(constants.get("PAYLOAD_V1"), Some(&(PayloadVersion::V1 as u8)));
+        assert_eq!(constants.get("PAYLOAD_V2"), Some(&(PayloadVersion::V2 as u8)));
+        assert_eq!(constants.get("PAYLOAD_V3"), Some(&(PayloadVersion::V3 as u8)));
+    }
+
+    #[test]
+    fn test_v2_signs_version_byte_then_v1_fields() {
+        let kp = Ed25519KeyPair::generate(&mut rand::thread_rng());
+        let payload = TransferPayload {
+            from_handle: b"alice".to_vec(),
+            to_handle: b"bob".to_vec(),
+            amount: 5,
+            coin_type: b"0x2::sui::SUI".to_vec(),
+            memo: Vec::new(),
+        };
+        let fields = bcs::to_bytes(&payload).unwrap();
+        // IntentMessage: intent, timestamp_ms, then the data
+        let mut message = vec![IntentScope::TransferCoin as u8];
+        message.extend_from_slice(&7u64.to_le_bytes());
+        message.push(2);
+        message.extend_from_slice(&fields);
+
+        let pk: &Ed25519PublicKey = kp.public();
+        let signed = |version| {
+            let hex = sign_payload(&kp, &payload, 7, IntentScope::TransferCoin, version);
+            Ed25519Signature::from_bytes(&Hex::decode(&hex).unwrap()).unwrap()
+        };
+        assert!(pk.verify(&message, &signed(PayloadVersion::V2)).is_ok());
+        assert!(pk.verify(&message, &signed(PayloadVersion::V1)).is_err());
+
+        // v3: the version byte, the package address, then the same fields
+        let mut message = vec![IntentScope::TransferCoin as u8];
+        message.extend_from_slice(&7u64.to_le_bytes());
+        message.push(3);
+        message.extend_from_slice(&chain::current().package_address());
+        message.extend_from_slice(&fields);
+        assert!(pk.verify(&message, &signed(PayloadVersion::V3)).is_ok());
+        assert!(pk.verify(&message, &signed(PayloadVersion::V2)).is_err());
+    }
+
+    #[test]
+    fn test_migration_window() {
+        let window = PayloadVersions::new(PayloadVersion::V1, Some(1_000));
+        assert_eq!(window.select_at(None, 999).unwrap(), PayloadVersion::V1);
+        assert_eq!(window.select_at(Some(2), 999).unwrap(), PayloadVersion::V2);
+        assert_eq!(window.select_at(Some(1), 999).unwrap(), PayloadVersion::V1);
+
+        // Past the sunset v1 is refused and unversioned requests move to v2
+        assert_eq!(window.select_at(None, 1_000).unwrap(), PayloadVersion::V2);
+        assert!(matches!(window.select_at(Some(1), 1_000), Err(EnclaveError::InvalidRequest(_))));
+        assert_eq!(window.select_at(Some(3), 1_000).unwrap(), PayloadVersion::V3);
+        assert!(matches!(window.select_at(Some(4), 0), Err(EnclaveError::InvalidRequest(_))));
+
+        let chain_bound = PayloadVersions::new(PayloadVersion::V3, Some(1_000));
+        assert_eq!(chain_bound.select_at(None, 1_000).unwrap(), PayloadVersion::V3);
+
+        let default = PayloadVersions::default();
+        assert_eq!(default.select_at(None, u64::MAX).unwrap(), PayloadVersion::V1);
+    }
+}
@@ -12,7 +12,11 @@
 //! These are scientifically-validated vocal stress indicators used in
 //! voice stress analysis (VSA) systems.
 
+use base64::engine::general_purpose::STANDARD;
+use base64::{DecodeError, DecodeSliceError, Engine as _};
+use rayon::prelude::*;
 use tracing::info;
+use zeroize::Zeroizing;
 
 /// Acoustic features extracted from voice
 #[derive(Debug, Clone)]
@@ -43,11 +47,148 @@ pub struct StressAnalysis {
     pub reasons: Vec<String>,
 }
 
+/// Enrollment samples needed before a personal baseline is applied
+pub const BASELINE_MIN_SAMPLES: u32 = 3;
+
+// Calm "reference voice" the absolute thresholds in `calculate_stress` were
+// tuned for; a personal baseline maps each speaker onto it
+const REFERENCE_JITTER: f64 = 0.02;
+const REFERENCE_ENERGY_VARIANCE: f64 = 0.2;
+const REFERENCE_HF_RATIO: f64 = 0.2;
+const REFERENCE_F0: f64 = 150.0;
+
+/// A speaker's calm acoustic baseline, averaged over enrollment samples
+#[derive(Debug, Clone, Default)]
+pub struct VoiceBaseline {
+    pub samples: u32,
+    pub pitch_jitter: f64,
+    pub energy_variance: f64,
+    pub high_freq_ratio: f64,
+    pub estimated_f0: f64,
+}
+
+impl VoiceBaseline {
+    /// Fold one calm enrollment sample into the running mean
+    pub fn add_sample(&mut self, features: &AcousticFeatures) {
+        let n = self.samples as f64;
+        let mean = |current: f64, value: f64| (current * n + value) / (n + 1.0);
+        self.pitch_jitter = mean(self.pitch_jitter, features.pitch_jitter);
+        self.energy_variance = mean(self.energy_variance, features.energy_variance);
+        self.high_freq_ratio = mean(self.high_freq_ratio, features.high_freq_ratio);
+        self.estimated_f0 = mean(self.estimated_f0, features.estimated_f0);
+        self.samples += 1;
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.samples >= BASELINE_MIN_SAMPLES
+    }
+
+    /// Re-express features relative to this speaker, as if spoken by the reference voice
+    ///
+    /// Jitter, energy variance and HF ratio are shifted by the speaker's offset
+    /// from the reference; pitch is scaled, since stress raises F0 by a ratio.
+    /// Baselines are clamped to plausible calm ranges so a skewed enrollment
+    /// can only discount so much.
+    fn normalize(&self, features: &AcousticFeatures) -> AcousticFeatures {
+        let shift = |value: f64, baseline: f64, reference: f64, max: f64| {
+            (value - baseline.min(max) + reference).max(0.0)
+        };
+        let estimated_f0 = if features.estimated_f0 > 0.0 && self.estimated_f0 > 0.0 {
+            features.estimated_f0 * REFERENCE_F0 / self.estimated_f0.clamp(80.0, 300.0)
+        } else {
+            features.estimated_f0
+        };
+
+        AcousticFeatures {
+            pitch_jitter: shift(features.pitch_jitter, self.pitch_jitter, REFERENCE_JITTER, 0.05),
+            energy_variance: shift(features.energy_variance, self.energy_variance, REFERENCE_ENERGY_VARIANCE, 0.5),
+            high_freq_ratio: shift(features.high_freq_ratio, self.high_freq_ratio, REFERENCE_HF_RATIO, 0.4),
+            estimated_f0,
+            ..features.clone()
+        }
+    }
+}
+
 /// Analyze WAV PCM audio bytes for stress indicators
 /// Expects standard WAV format (16-bit PCM, mono preferred)
-pub fn analyze_voice_stress(wav_bytes: &[u8]) -> StressAnalysis {
-    // Parse WAV header
-    let (samples, sample_rate) = match parse_wav(wav_bytes) {
+///
+/// With a ready `baseline`, stress is scored relative to the speaker's own
+/// calm voice; the returned features are always the raw measurements.
+pub fn analyze_voice_stress(wav_bytes: &[u8], baseline: Option<&VoiceBaseline>) -> StressAnalysis {
+    analyze_pcm(parse_wav(wav_bytes), baseline)
+}
+
+/// [`analyze_voice_stress`] on base64 audio through the streaming decoder;
+/// `Err` if it isn't valid base64
+pub fn analyze_voice_stress_base64(
+    audio_base64: &str,
+    baseline: Option<&VoiceBaseline>,
+) -> Result<StressAnalysis, DecodeSliceError> {
+    Ok(analyze_pcm(decode_wav_base64(audio_base64)?, baseline))
+}
+
+/// [`analyze_voice_stress_base64`] on the [`denoise`]d signal, for a second
+/// opinion on a borderline score; `None` if the clip isn't a WAV we can read
+pub fn analyze_denoised_voice_stress_base64(
+    audio_base64: &str,
+    baseline: Option<&VoiceBaseline>,
+) -> Result<Option<StressAnalysis>, DecodeSliceError> {
+    Ok(decode_wav_base64(audio_base64)?
+        .map(|(samples, sample_rate)| analyze_pcm(Some((denoise(&samples, sample_rate), sample_rate)), baseline)))
+}
+
+/// Cutoff of the high-pass filter in [`denoise`]: below the lowest voices
+const DENOISE_CUTOFF_HZ: f64 = 70.0;
+/// Length of the frames the noise gate keeps or drops
+const GATE_FRAME_SECS: f64 = 0.02;
+/// Frames quieter than this multiple of the noise floor are dropped
+const GATE_RATIO: f64 = 2.0;
+
+/// The samples with background noise taken out
+///
+/// A first-order high-pass filter removes hum, rumble and DC offset, then
+/// 20 ms frames no louder than twice the noise floor (the level of the
+/// quietest tenth of frames) are dropped, so hiss and pauses don't read as
+/// jitter or energy swings. A clip with no quiet frames to measure the floor
+/// from is only filtered.
+pub fn denoise(samples: &[f32], sample_rate: u32) -> Zeroizing<Vec<f32>> {
+    let rc = 1.0 / (2.0 * std::f64::consts::PI * DENOISE_CUTOFF_HZ);
+    let alpha = rc / (rc + 1.0 / sample_rate as f64);
+    let mut filtered = Zeroizing::new(Vec::with_capacity(samples.len()));
+    let (mut prev_in, mut prev_out) = (samples.first().copied().unwrap_or(0.0) as f64, 0.0f64);
+    for &sample in samples {
+        let out = alpha * (prev_out + sample as f64 - prev_in);
+        prev_in = sample as f64;
+        prev_out = out;
+        filtered.push(out as f32);
+    }
+
+    let frame_len = ((sample_rate as f64 * GATE_FRAME_SECS) as usize).max(1);
+    let levels: Vec<f64> =
+        filtered.chunks(frame_len).map(|frame| (sum_squares(frame) / frame.len() as f64).sqrt()).collect();
+    if levels.len() < 10 {
+        return filtered;
+    }
+    let mut sorted = levels.clone();
+    sorted.sort_by(f64::total_cmp);
+    let gate = sorted[sorted.len() / 10] * GATE_RATIO;
+
+    let mut kept = Zeroizing::new(Vec::with_capacity(filtered.len()));
+    for (frame, level) in filtered.chunks(frame_len).zip(&levels) {
+        if *level > gate {
+            kept.extend_from_slice(frame);
+        }
+    }
+    if kept.is_empty() {
+        filtered
+    } else {
+        kept
+    }
+}
+
+pub(crate) fn analyze_pcm(pcm: Option<Pcm>, baseline: Option<&VoiceBaseline>) -> StressAnalysis {
+    // Decoded WAV samples, zeroized when analysis is done
+    let (samples, sample_rate) = match pcm {
         Some(data) => data,
         None => {
             info!("RAM DSP: Failed to parse WAV, returning neutral stress");
@@ -73,7 +214,14 @@ pub fn analyze_voice_stress(wav_bytes: &[u8]) -> StressAnalysis {
     let features = extract_features(&samples, sample_rate);
     
     // Calculate stress score from features
-    let (stress_level, reasons) = calculate_stress(&features);
+    let (stress_level, reasons) = match baseline.filter(|b| b.is_ready()) {
+        Some(baseline) => {
+            info!("RAM DSP: Scoring against personal baseline ({} samples, f0={:.1}Hz)",
+                baseline.samples, baseline.estimated_f0);
+            calculate_stress(&baseline.normalize(&features))
+        }
+        None => calculate_stress(&features),
+    };
 
     info!("RAM DSP: pitch_jitter={:.4}, energy_var={:.4}, zcr={:.4}, hf_ratio={:.4}, f0={:.1}Hz",
         features.pitch_jitter, features.energy_variance, 
@@ -87,49 +235,139 @@ pub fn analyze_voice_stress(wav_bytes: &[u8]) -> StressAnalysis {
     }
 }
 
+/// Bytes before the PCM data in the WAV layout we accept
+const WAV_HEADER_LEN: usize = 44;
+/// Base64 characters decoded per block when streaming; a multiple of 4
+const BASE64_BLOCK_LEN: usize = 4096;
+/// Sample rates the analysis frames are sized for; a header outside them is
+/// refused rather than producing empty (or zero-length) frames
+const MIN_SAMPLE_RATE: u32 = 8_000;
+const MAX_SAMPLE_RATE: u32 = 192_000;
+
+/// Decoded samples (wiped when dropped) and their sample rate
+pub type Pcm = (Zeroizing<Vec<f32>>, u32);
+
 /// Parse WAV file and extract f32 samples
-fn parse_wav(data: &[u8]) -> Option<(Vec<f32>, u32)> {
-    if data.len() < 44 { return None; }
-    
-    // Check RIFF header
-    if &data[0..4] != b"RIFF" || &data[8..12] != b"WAVE" {
-        return None;
+pub fn parse_wav(data: &[u8]) -> Option<Pcm> {
+    let capacity = data.len().saturating_sub(WAV_HEADER_LEN);
+    let (mut decoder, sample_rate) = PcmDecoder::new(data, capacity)?;
+    decoder.push(&data[WAV_HEADER_LEN..]);
+    Some((decoder.finish(), sample_rate))
+}
+
+/// [`parse_wav`] straight from base64, without materializing the WAV bytes
+///
+/// The clip is decoded a block at a time into a stack buffer and converted to
+/// samples as it goes, so peak memory is the sample buffer alone rather than
+/// the decoded clip plus its samples. `Err` for invalid base64, `Ok(None)` for
+/// audio that isn't a WAV we can read.
+pub fn decode_wav_base64(audio_base64: &str) -> Result<Option<Pcm>, DecodeSliceError> {
+    let mut block = Zeroizing::new([0u8; BASE64_BLOCK_LEN / 4 * 3]);
+    let mut chunks = audio_base64.as_bytes().chunks(BASE64_BLOCK_LEN).peekable();
+    let Some(first) = chunks.next() else {
+        return Ok(None);
+    };
+    let len = decode_block(first, &mut block[..], chunks.peek().is_none())?;
+    let capacity = (audio_base64.len() / 4 * 3).saturating_sub(WAV_HEADER_LEN);
+    let Some((mut decoder, sample_rate)) = PcmDecoder::new(&block[..len], capacity) else {
+        return Ok(None);
+    };
+    decoder.push(&block[WAV_HEADER_LEN..len]);
+    while let Some(chunk) = chunks.next() {
+        let len = decode_block(chunk, &mut block[..], chunks.peek().is_none())?;
+        decoder.push(&block[..len]);
     }
-    
-    // Read format info
-    let _audio_format = u16::from_le_bytes([data[20], data[21]]);
-    let num_channels = u16::from_le_bytes([data[22], data[23]]) as u32;
-    let sample_rate = u32::from_le_bytes([data[24], data[25], data[26], data[27]]);
-    let bits_per_sample = u16::from_le_bytes([data[34], data[35]]) as u32;
-    
-    if bits_per_sample != 16 {
-        info!("RAM DSP: Unsupported bits_per_sample: {}", bits_per_sample);
-        return None;
+    Ok(Some((decoder.finish(), sample_rate)))
+}
+
+/// Decode one block of base64; padding may only end the last one, as it
+/// would be rejected anywhere but the end of the whole input
+fn decode_block(chunk: &[u8], block: &mut [u8], last: bool) -> Result<usize, DecodeSliceError> {
+    let len = STANDARD.decode_slice(chunk, block)?;
+    if !last && len != block.len() {
+        return Err(DecodeSliceError::DecodeError(DecodeError::InvalidPadding));
     }
-    
-    // Find data chunk
-    let data_start = 44; // Standard WAV header
-    let pcm_data = &data[data_start..];
-    
-    // Convert 16-bit PCM to f32 (mono)
-    let bytes_per_sample = (bits_per_sample / 8) as usize;
-    let frame_size = bytes_per_sample * num_channels as usize;
-    let num_frames = pcm_data.len() / frame_size;
-    
-    let mut samples = Vec::with_capacity(num_frames);
-    for i in 0..num_frames {
-        let offset = i * frame_size;
-        if offset + 1 < pcm_data.len() {
-            let sample = i16::from_le_bytes([pcm_data[offset], pcm_data[offset + 1]]);
-            samples.push(sample as f32 / 32768.0);
+    Ok(len)
+}
+
+/// Incremental 16-bit PCM to f32 conversion (first channel only), fed the
+/// data chunk in pieces
+///
+/// The sample buffer is allocated once, for the largest clip the input could
+/// hold: a reallocation would leave a copy of the audio behind that
+/// `Zeroizing` never sees.
+struct PcmDecoder {
+    frame_size: usize,
+    /// Position within the current frame
+    frame_offset: usize,
+    low_byte: u8,
+    samples: Zeroizing<Vec<f32>>,
+}
+
+impl PcmDecoder {
+    /// Read the header; `data_len` bounds the PCM bytes that will be pushed
+    fn new(header: &[u8], data_len: usize) -> Option<(Self, u32)> {
+        if header.len() < WAV_HEADER_LEN { return None; }
+        
+        // Check RIFF header
+        if &header[0..4] != b"RIFF" || &header[8..12] != b"WAVE" {
+            return None;
+        }
+        
+        // Read format info
+        let _audio_format = u16::from_le_bytes([header[20], header[21]]);
+        let num_channels = u16::from_le_bytes([header[22], header[23]]) as usize;
+        let sample_rate = u32::from_le_bytes([header[24], header[25], header[26], header[27]]);
+        let bits_per_sample = u16::from_le_bytes([header[34], header[35]]) as u32;
+        
+        if bits_per_sample != 16 {
+            info!("RAM DSP: Unsupported bits_per_sample: {}", bits_per_sample);
+            return None;
         }
+        if num_channels == 0 {
+            return None;
+        }
+        if !(MIN_SAMPLE_RATE..=MAX_SAMPLE_RATE).contains(&sample_rate) {
+            info!("RAM DSP: Unsupported sample_rate: {}", sample_rate);
+            return None;
+        }
+        
+        let frame_size = 2 * num_channels;
+        let decoder = Self {
+            frame_size,
+            frame_offset: 0,
+            low_byte: 0,
+            samples: Zeroizing::new(Vec::with_capacity(data_len / frame_size)),
+        };
+        Some((decoder, sample_rate))
+    }
+
+    fn push(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            match self.frame_offset {
+                0 => self.low_byte = byte,
+                1 => self.samples.push(i16::from_le_bytes([self.low_byte, byte]) as f32 / 32768.0),
+                _ => {}
+            }
+            self.frame_offset += 1;
+            if self.frame_offset == self.frame_size {
+                self.frame_offset = 0;
+            }
+        }
+    }
+
+    /// Samples of the complete frames
+    fn finish(mut self) -> Zeroizing<Vec<f32>> {
+        if self.frame_offset >= 2 {
+            self.samples.pop();
+        }
+        self.low_byte = 0;
+        std::mem::take(&mut self.samples)
     }
-    
-    Some((samples, sample_rate))
 }
 
 /// Extract acoustic features from audio samples
-fn extract_features(samples: &[f32], sample_rate: u32) -> AcousticFeatures {
+pub fn extract_features(samples: &[f32], sample_rate: u32) -> AcousticFeatures {
     if samples.is_empty() {
         return AcousticFeatures {
             pitch_jitter: 0.0,
@@ -142,7 +380,7 @@ fn extract_features(samples: &[f32], sample_rate: u32) -> AcousticFeatures {
     }
     
     // 1. RMS Energy
-    let rms_energy = (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt();
+    let rms_energy = (sum_squares(samples) / samples.len() as f64).sqrt();
     
     // 2. Zero-Crossing Rate (per second)
     let zero_crossings = samples.windows(2)
@@ -169,7 +407,7 @@ fn extract_features(samples: &[f32], sample_rate: u32) -> AcousticFeatures {
         energy_variance,
         zero_crossing_rate,
         high_freq_ratio,
-        rms_energy: rms_energy as f64,
+        rms_energy,
         estimated_f0,
     }
 }
@@ -181,12 +419,8 @@ fn calculate_energy_variance(samples: &[f32], sample_rate: u32) -> f64 {
         return 0.0;
     }
     
-    let frame_energies: Vec<f64> = samples.chunks(frame_size)
-        .filter(|chunk| chunk.len() == frame_size)
-        .map(|frame| {
-            let energy: f32 = frame.iter().map(|s| s * s).sum();
-            (energy / frame.len() as f32).sqrt() as f64
-        })
+    let frame_energies: Vec<f64> = samples.par_chunks_exact(frame_size)
+        .map(|frame| (sum_squares(frame) / frame.len() as f64).sqrt())
         .collect();
     
     if frame_energies.len() < 2 { return 0.0; }
@@ -212,11 +446,14 @@ fn calculate_energy_variance(samples: &[f32], sample_rate: u32) -> f64 {
 }
 
 /// Estimate pitch (F0) using autocorrelation and calculate pitch jitter
+///
+/// Voiced frames are analysed two per FFT (see [`Autocorrelator`]), pairs in
+/// parallel across the rayon pool; periods keep frame order for the jitter.
 fn estimate_pitch_and_jitter(samples: &[f32], sample_rate: u32) -> (f64, f64) {
     let frame_size = (sample_rate as usize) / 25; // 40ms frames  
     let hop_size = frame_size / 2; // 50% overlap
     
-    if samples.len() < frame_size {
+    if hop_size == 0 || samples.len() < frame_size {
         return (0.0, 0.0);
     }
     
@@ -224,26 +461,27 @@ fn estimate_pitch_and_jitter(samples: &[f32], sample_rate: u32) -> (f64, f64) {
     let min_lag = sample_rate as usize / 400; // Max frequency
     let max_lag = sample_rate as usize / 80;  // Min frequency
     
-    let mut periods: Vec<f64> = Vec::new();
+    // Only frames with enough energy (voiced)
+    let voiced: Vec<&[f32]> = (0..=(samples.len() - frame_size) / hop_size)
+        .map(|i| &samples[i * hop_size..i * hop_size + frame_size])
+        .filter(|frame| sum_squares(frame) / (frame.len() as f64) >= 0.0001)
+        .collect();
     
-    let mut offset = 0;
-    while offset + frame_size <= samples.len() {
-        let frame = &samples[offset..offset + frame_size];
-        
-        // Check if frame has enough energy (voiced)
-        let energy: f32 = frame.iter().map(|s| s * s).sum::<f32>() / frame.len() as f32;
-        if energy < 0.0001 {
-            offset += hop_size;
-            continue;
-        }
-        
-        // Autocorrelation to find pitch period
-        if let Some(period) = autocorrelation_pitch(frame, min_lag, max_lag) {
-            periods.push(period as f64);
-        }
-        
-        offset += hop_size;
-    }
+    // Autocorrelation to find pitch periods
+    let periods: Vec<f64> = voiced
+        .par_chunks(2)
+        .map_init(
+            || Autocorrelator::new(frame_size, max_lag),
+            |autocorrelator, pair| {
+                autocorrelator.pitch_period_pair(pair[0], pair.get(1).copied().unwrap_or_default(), min_lag, max_lag)
+            },
+        )
+        .collect::<Vec<_>>()
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|period| period as f64)
+        .collect();
     
     if periods.is_empty() {
         return (0.0, 0.0);
@@ -275,38 +513,137 @@ fn estimate_pitch_and_jitter(samples: &[f32], sample_rate: u32) -> (f64, f64) {
     (estimated_f0, jitter)
 }
 
-/// Find pitch period using autocorrelation
-fn autocorrelation_pitch(frame: &[f32], min_lag: usize, max_lag: usize) -> Option<usize> {
+/// Sum of squares over eight independent accumulators, so the loop vectorizes
+/// (a single running sum is a serial dependency chain)
+fn sum_squares(samples: &[f32]) -> f64 {
+    let mut lanes = [0.0f64; 8];
+    let chunks = samples.chunks_exact(8);
+    let tail: f64 = chunks.remainder().iter().map(|s| (*s as f64) * (*s as f64)).sum();
+    for chunk in chunks {
+        for (lane, &s) in lanes.iter_mut().zip(chunk) {
+            *lane += (s as f64) * (s as f64);
+        }
+    }
+    lanes.iter().sum::<f64>() + tail
+}
+
+/// Pitch periods of equal-length frames via FFT autocorrelation
+///
+/// The lag-by-lag sum was O(n·lags) per frame, which dominated analysis of
+/// long 44.1 kHz clips. The autocorrelation is now IFFT(|FFT(x)|²) with x
+/// zero-padded to at least n + max_lag (so no searched lag wraps around),
+/// O(n log n), and the per-lag normalization comes from a running sum of
+/// squares. Frames are real, so two go through each complex FFT (one as the
+/// real part, one as the imaginary part) and are separated in the spectrum.
+/// Buffers are split-complex (separate re/im arrays) and twiddles are laid
+/// out contiguously per butterfly stage so the inner loops vectorize; all of
+/// it is reused across the frames of a clip.
+pub struct Autocorrelator {
+    frame_len: usize,
+    /// e^(-πik/h) for k < h, for each stage half-width h = 1, 2, 4, ..., at offset h - 1
+    twiddle_re: Vec<f64>,
+    twiddle_im: Vec<f64>,
+    re: Vec<f64>,
+    im: Vec<f64>,
+}
+
+impl Autocorrelator {
+    /// Buffers for frames of `frame_len` samples searched at lags below `max_lag`
+    pub fn new(frame_len: usize, max_lag: usize) -> Self {
+        let size = (frame_len + max_lag.min(frame_len)).next_power_of_two().max(2);
+        let (mut twiddle_re, mut twiddle_im) = (Vec::with_capacity(size), Vec::with_capacity(size));
+        let mut half = 1;
+        while half < size {
+            for k in 0..half {
+                let angle = -std::f64::consts::PI * k as f64 / half as f64;
+                twiddle_re.push(angle.cos());
+                twiddle_im.push(angle.sin());
+            }
+            half *= 2;
+        }
+        Self { frame_len, twiddle_re, twiddle_im, re: vec![0.0; size], im: vec![0.0; size] }
+    }
+
+    /// Unnormalized autocorrelations r[lag] of two frames (the second may be
+    /// empty), valid for lags below the `max_lag` given to [`Self::new`]
+    fn autocorrelate_pair(&mut self, a: &[f32], b: &[f32]) -> (&[f64], &[f64]) {
+        debug_assert!(a.len() <= self.frame_len && b.len() <= self.frame_len);
+        let size = self.re.len();
+        self.re.fill(0.0);
+        self.im.fill(0.0);
+        for (re, &sample) in self.re.iter_mut().zip(a) {
+            *re = sample as f64;
+        }
+        for (im, &sample) in self.im.iter_mut().zip(b) {
+            *im = sample as f64;
+        }
+        fft(&mut self.re, &mut self.im, &self.twiddle_re, &self.twiddle_im);
+
+        // With z = a + ib: A[k] = (Z[k] + conj Z[-k]) / 2, B[k] = (Z[k] - conj Z[-k]) / 2i.
+        // Both power spectra are real and even; pack them as |A|² + i|B|².
+        let scale = 0.25 / size as f64;
+        for k in 0..=size / 2 {
+            let j = (size - k) % size;
+            let (zr, zi, cr, ci) = (self.re[k], self.im[k], self.re[j], self.im[j]);
+            let power_a = ((zr + cr) * (zr + cr) + (zi - ci) * (zi - ci)) * scale;
+            let power_b = ((zr - cr) * (zr - cr) + (zi + ci) * (zi + ci)) * scale;
+            self.re[k] = power_a;
+            self.re[j] = power_a;
+            self.im[k] = power_b;
+            self.im[j] = power_b;
+        }
+        // An even spectrum's inverse transform is its forward transform over size,
+        // folded into `scale` above
+        fft(&mut self.re, &mut self.im, &self.twiddle_re, &self.twiddle_im);
+        (&self.re[..a.len()], &self.im[..b.len()])
+    }
+
+    /// Find the pitch period of one frame: the lag in `min_lag..max_lag` with
+    /// the strongest normalized autocorrelation, if strong enough
+    pub fn pitch_period(&mut self, frame: &[f32], min_lag: usize, max_lag: usize) -> Option<usize> {
+        self.pitch_period_pair(frame, &[], min_lag, max_lag)[0]
+    }
+
+    /// [`Self::pitch_period`] for two frames at the cost of one
+    pub fn pitch_period_pair(
+        &mut self,
+        a: &[f32],
+        b: &[f32],
+        min_lag: usize,
+        max_lag: usize,
+    ) -> [Option<usize>; 2] {
+        let (ra, rb) = self.autocorrelate_pair(a, b);
+        [best_lag(a, ra, min_lag, max_lag), best_lag(b, rb, min_lag, max_lag)]
+    }
+}
+
+/// Lag with the strongest autocorrelation `r` normalized by the overlap energy
+fn best_lag(frame: &[f32], r: &[f64], min_lag: usize, max_lag: usize) -> Option<usize> {
     let max_lag = max_lag.min(frame.len() / 2);
     if min_lag >= max_lag {
         return None;
     }
-    
-    // Compute autocorrelation at lag 0 for normalization
-    let r0: f64 = frame.iter().map(|s| (*s as f64) * (*s as f64)).sum();
+
+    // Autocorrelation at lag 0 for normalization
+    let r0 = sum_squares(frame);
     if r0 < 1e-10 {
         return None;
     }
-    
+
+    // Energy of frame[lag..], the overlapping part at each lag
+    let mut norm = r0 - sum_squares(&frame[..min_lag]);
+
     let mut best_lag = min_lag;
     let mut best_corr: f64 = -1.0;
-    
     for lag in min_lag..max_lag {
-        let mut corr: f64 = 0.0;
-        let mut norm: f64 = 0.0;
-        for i in 0..(frame.len() - lag) {
-            corr += frame[i] as f64 * frame[i + lag] as f64;
-            norm += frame[i + lag] as f64 * frame[i + lag] as f64;
-        }
-        
-        let normalized = if norm > 1e-10 { corr / (r0 * norm).sqrt() } else { 0.0 };
-        
+        let normalized = if norm > 1e-10 { r[lag] / (r0 * norm).sqrt() } else { 0.0 };
         if normalized > best_corr {
             best_corr = normalized;
             best_lag = lag;
         }
+        norm -= (frame[lag] as f64) * (frame[lag] as f64);
     }
-    
+
     // Only accept if correlation is strong enough
     if best_corr > 0.3 {
         Some(best_lag)
@@ -315,6 +652,46 @@ fn autocorrelation_pitch(frame: &[f32], min_lag: usize, max_lag: usize) -> Optio
     }
 }
 
+/// In-place iterative radix-2 FFT on split-complex buffers; the length must be
+/// a power of two and the twiddles laid out as in [`Autocorrelator`]
+fn fft(re: &mut [f64], im: &mut [f64], twiddle_re: &[f64], twiddle_im: &[f64]) {
+    let n = re.len();
+
+    // Bit-reversal permutation
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+
+    let mut half = 1;
+    while half < n {
+        let wr = &twiddle_re[half - 1..2 * half - 1];
+        let wi = &twiddle_im[half - 1..2 * half - 1];
+        for (block_re, block_im) in re.chunks_exact_mut(2 * half).zip(im.chunks_exact_mut(2 * half)) {
+            let (ar, br) = block_re.split_at_mut(half);
+            let (ai, bi) = block_im.split_at_mut(half);
+            for k in 0..half {
+                let tr = br[k] * wr[k] - bi[k] * wi[k];
+                let ti = br[k] * wi[k] + bi[k] * wr[k];
+                br[k] = ar[k] - tr;
+                bi[k] = ai[k] - ti;
+                ar[k] += tr;
+                ai[k] += ti;
+            }
+        }
+        half *= 2;
+    }
+}
+
 /// Calculate ratio of high-frequency energy (> 2kHz) to total energy
 /// Stressed/tense voices have more high-frequency harmonics
 fn calculate_high_freq_ratio(samples: &[f32], sample_rate: u32) -> f64 {
@@ -435,100 +812,221 @@ fn calculate_stress(features: &AcousticFeatures) -> (u8, Vec<String>) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::apps::ram::simulation::{sine_wave, to_wav, Voice};
+
+    /// Pitch tremor at 6 Hz, 30 Hz deep, with the loudness wavering along
+    fn trembling_voice(f0_hz: f64, sample_rate: u32, duration_secs: f64) -> Vec<f32> {
+        Voice::calm(f0_hz)
+            .with_tremor(6.0, 30.0)
+            .with_volume_swings(0.6, 4.2)
+            .render(sample_rate, duration_secs, 0)
+    }
     
     #[test]
     fn test_parse_wav() {
         // Create a minimal valid WAV file
-        let wav = create_test_wav(16000, &generate_sine_wave(440.0, 16000, 0.5));
+        let wav = to_wav(16000, &sine_wave(440.0, 16000, 0.5));
         let (samples, sr) = parse_wav(&wav).expect("Should parse WAV");
         assert_eq!(sr, 16000);
         assert!(!samples.is_empty());
     }
+
+    #[test]
+    fn test_parse_wav_refuses_unusable_sample_rates() {
+        // Regression (fuzz): a header rate under 50 Hz gave zero-length pitch
+        // hops and a division by zero
+        for rate in [0, 10, 49, MAX_SAMPLE_RATE + 1] {
+            let mut wav = to_wav(16000, &sine_wave(440.0, 16000, 0.1));
+            wav[24..28].copy_from_slice(&rate.to_le_bytes());
+            assert!(parse_wav(&wav).is_none(), "{} Hz", rate);
+            assert!(decode_wav_base64(&STANDARD.encode(&wav)).unwrap().is_none(), "{} Hz", rate);
+        }
+        let samples = sine_wave(440.0, 16000, 0.1);
+        for rate in [0, 10, 49] {
+            assert_eq!(extract_features(&samples, rate).estimated_f0, 0.0);
+        }
+    }
+
+    #[test]
+    fn test_streaming_decode_matches_parse_wav() {
+        let mono = to_wav(16000, &trembling_voice(150.0, 16000, 0.5));
+        // Stereo with a partial frame at the end: only whole frames count
+        let mut stereo = mono.clone();
+        stereo[22] = 2;
+        stereo.extend_from_slice(&[1, 2, 3]);
+        for wav in [&mono, &stereo] {
+            let (expected, rate) = parse_wav(wav).unwrap();
+            let (streamed, streamed_rate) = decode_wav_base64(&STANDARD.encode(wav)).unwrap().unwrap();
+            assert_eq!(streamed_rate, rate);
+            assert_eq!(*streamed, *expected);
+        }
+        assert_eq!(parse_wav(&stereo).unwrap().0.len(), (stereo.len() - WAV_HEADER_LEN) / 4);
+
+        assert!(decode_wav_base64(&STANDARD.encode(b"OggS not a wav file at all")).unwrap().is_none());
+        assert!(decode_wav_base64("").unwrap().is_none());
+        let mut corrupt = STANDARD.encode(&mono);
+        corrupt.replace_range(BASE64_BLOCK_LEN + 10..BASE64_BLOCK_LEN + 11, "*");
+        assert!(decode_wav_base64(&corrupt).is_err());
+    }
+
+    #[test]
+    fn test_streaming_decode_refuses_padding_mid_input() {
+        // Regression (fuzz): a block ending in padding was accepted when more
+        // blocks followed, where decoding the whole input fails
+        let wav = to_wav(16000, &sine_wave(440.0, 16000, 0.5));
+        let split = BASE64_BLOCK_LEN / 4 * 3 - 2;
+        let padded = STANDARD.encode(&wav[..split]) + &STANDARD.encode(&wav[split..]);
+        assert_eq!(STANDARD.encode(&wav[..split]).len(), BASE64_BLOCK_LEN);
+        assert!(STANDARD.decode(&padded).is_err());
+        assert!(decode_wav_base64(&padded).is_err());
+    }
     
     #[test]
     fn test_calm_voice() {
         // Steady sine wave = calm voice
-        let steady_sine = generate_sine_wave(150.0, 16000, 1.0);
-        let wav = create_test_wav(16000, &steady_sine);
-        let analysis = analyze_voice_stress(&wav);
+        let steady_sine = sine_wave(150.0, 16000, 1.0);
+        let wav = to_wav(16000, &steady_sine);
+        let analysis = analyze_voice_stress(&wav, None);
         assert!(analysis.stress_level < 50, "Steady tone should be low stress, got {}", analysis.stress_level);
     }
     
     #[test]
     fn test_trembling_voice() {
         // Sine wave with frequency modulation (trembling) = stressed voice
-        let trembling = generate_trembling_voice(150.0, 16000, 1.0, 6.0, 30.0);
-        let wav = create_test_wav(16000, &trembling);
-        let analysis = analyze_voice_stress(&wav);
+        let trembling = trembling_voice(150.0, 16000, 1.0);
+        let wav = to_wav(16000, &trembling);
+        let analysis = analyze_voice_stress(&wav, None);
         assert!(analysis.stress_level > 30, "Trembling voice should show stress, got {}", analysis.stress_level);
     }
     
+    #[test]
+    fn test_baseline_scores_high_voice_relative_to_speaker() {
+        // A naturally high-pitched speaker reads as elevated pitch in absolute terms
+        let high_voice = to_wav(16000, &sine_wave(320.0, 16000, 1.0));
+        let absolute = analyze_voice_stress(&high_voice, None);
+
+        let mut baseline = VoiceBaseline::default();
+        for _ in 0..BASELINE_MIN_SAMPLES {
+            baseline.add_sample(&absolute.features);
+        }
+        let relative = analyze_voice_stress(&high_voice, Some(&baseline));
+        assert!(
+            relative.stress_level < absolute.stress_level,
+            "baseline should discount the speaker's own pitch ({} vs {})",
+            relative.stress_level, absolute.stress_level
+        );
+    }
+
+    #[test]
+    fn test_baseline_unused_until_enrolled() {
+        let high_voice = to_wav(16000, &sine_wave(320.0, 16000, 1.0));
+        let absolute = analyze_voice_stress(&high_voice, None);
+
+        let mut baseline = VoiceBaseline::default();
+        baseline.add_sample(&absolute.features);
+        assert!(!baseline.is_ready());
+        assert_eq!(analyze_voice_stress(&high_voice, Some(&baseline)).stress_level, absolute.stress_level);
+    }
+
     #[test]
     fn test_stress_features_extraction() {
-        let samples = generate_sine_wave(200.0, 16000, 0.5);
+        let samples = sine_wave(200.0, 16000, 0.5);
         let features = extract_features(&samples, 16000);
         assert!(features.estimated_f0 > 150.0 && features.estimated_f0 < 250.0,
             "F0 should be ~200Hz, got {:.1}", features.estimated_f0);
     }
     
-    // Helper: generate a pure sine wave
-    fn generate_sine_wave(freq: f64, sample_rate: u32, duration: f64) -> Vec<f32> {
-        let num_samples = (sample_rate as f64 * duration) as usize;
-        (0..num_samples)
-            .map(|i| {
-                let t = i as f64 / sample_rate as f64;
-                (2.0 * std::f64::consts::PI * freq * t).sin() as f32 * 0.5
-            })
-            .collect()
-    }
-    
-    // Helper: generate a trembling voice (frequency-modulated sine)
-    fn generate_trembling_voice(
-        base_freq: f64, sample_rate: u32, duration: f64,
-        tremor_rate: f64, tremor_depth: f64
-    ) -> Vec<f32> {
-        let num_samples = (sample_rate as f64 * duration) as usize;
-        let mut phase = 0.0;
-        (0..num_samples)
-            .map(|i| {
-                let t = i as f64 / sample_rate as f64;
-                let freq = base_freq + tremor_depth * (2.0 * std::f64::consts::PI * tremor_rate * t).sin();
-                phase += 2.0 * std::f64::consts::PI * freq / sample_rate as f64;
-                // Add some amplitude variation too
-                let amp = 0.5 + 0.2 * (2.0 * std::f64::consts::PI * tremor_rate * 0.7 * t).sin();
-                (phase.sin() * amp) as f32
-            })
-            .collect()
-    }
-    
-    // Helper: create WAV file from samples
-    fn create_test_wav(sample_rate: u32, samples: &[f32]) -> Vec<u8> {
-        let data_size = samples.len() * 2;
-        let file_size = 36 + data_size;
-        let mut wav = Vec::with_capacity(44 + data_size);
-        
-        // RIFF header
-        wav.extend_from_slice(b"RIFF");
-        wav.extend_from_slice(&(file_size as u32).to_le_bytes());
-        wav.extend_from_slice(b"WAVE");
-        // fmt chunk
-        wav.extend_from_slice(b"fmt ");
-        wav.extend_from_slice(&16u32.to_le_bytes());
-        wav.extend_from_slice(&1u16.to_le_bytes()); // PCM
-        wav.extend_from_slice(&1u16.to_le_bytes()); // Mono
-        wav.extend_from_slice(&sample_rate.to_le_bytes());
-        wav.extend_from_slice(&(sample_rate * 2).to_le_bytes()); // byte rate
-        wav.extend_from_slice(&2u16.to_le_bytes()); // block align
-        wav.extend_from_slice(&16u16.to_le_bytes()); // bits per sample
-        // data chunk
-        wav.extend_from_slice(b"data");
-        wav.extend_from_slice(&(data_size as u32).to_le_bytes());
-        
-        for &s in samples {
-            let val = (s.max(-1.0).min(1.0) * 32767.0) as i16;
-            wav.extend_from_slice(&val.to_le_bytes());
+    #[test]
+    fn test_fft_autocorrelation_matches_direct_sum() {
+        let a: Vec<f32> = trembling_voice(180.0, 16000, 0.04);
+        let b: Vec<f32> = sine_wave(310.0, 16000, 0.04);
+        let max_lag = 200;
+        let mut autocorrelator = Autocorrelator::new(a.len(), max_lag);
+        let (ra, rb) = autocorrelator.autocorrelate_pair(&a, &b);
+        let (ra, rb) = (ra.to_vec(), rb.to_vec());
+        for (frame, r) in [(&a, &ra), (&b, &rb)] {
+            for lag in [0, 1, 40, max_lag - 1] {
+                let direct: f64 = (0..frame.len() - lag).map(|i| frame[i] as f64 * frame[i + lag] as f64).sum();
+                assert!((r[lag] - direct).abs() < 1e-9, "lag {}: {} vs {}", lag, r[lag], direct);
+            }
         }
-        
-        wav
+    }
+
+    #[test]
+    fn test_sum_squares_matches_naive_sum() {
+        let samples = trembling_voice(150.0, 16000, 0.1);
+        for len in [0, 7, 8, 9, samples.len()] {
+            let naive: f64 = samples[..len].iter().map(|s| (*s as f64) * (*s as f64)).sum();
+            assert!((sum_squares(&samples[..len]) - naive).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_denoise_drops_background_and_keeps_speech() {
+        // Half a second of faint hiss on a DC offset, then half a second of voice
+        let sample_rate = 16000;
+        let mut samples: Vec<f32> =
+            (0..sample_rate / 2).map(|i| 0.2 + 0.005 * ((i * 7919 % 101) as f32 / 50.0 - 1.0)).collect();
+        samples.extend(sine_wave(180.0, sample_rate, 0.5).iter().map(|s| s * 0.5));
+
+        let cleaned = denoise(&samples, sample_rate);
+        let kept = cleaned.len() as f64 / (sample_rate / 2) as f64;
+        assert!((0.9..=1.05).contains(&kept), "kept {:.2} of the voice", kept);
+        let mean = cleaned.iter().map(|s| *s as f64).sum::<f64>() / cleaned.len() as f64;
+        assert!(mean.abs() < 0.01, "offset left: {}", mean);
+
+        // Nothing quiet to gate on: only filtered
+        let tone = sine_wave(180.0, sample_rate, 0.5);
+        assert_eq!(denoise(&tone, sample_rate).len(), tone.len());
+
+        // Not a WAV: no second opinion
+        assert!(analyze_denoised_voice_stress_base64(&STANDARD.encode(b"ID3 not a wav"), None).unwrap().is_none());
+        let wav = STANDARD.encode(to_wav(sample_rate, &samples));
+        assert!(analyze_denoised_voice_stress_base64(&wav, None).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_fft_pitch_period_matches_direct_search() {
+        for sample_rate in [16000u32, 44100] {
+            let frame_size = sample_rate as usize / 25;
+            let (min_lag, max_lag) = (sample_rate as usize / 400, sample_rate as usize / 80);
+            let mut autocorrelator = Autocorrelator::new(frame_size, max_lag);
+            let voices = [
+                sine_wave(150.0, sample_rate, 1.0),
+                sine_wave(320.0, sample_rate, 1.0),
+                trembling_voice(150.0, sample_rate, 1.0),
+            ];
+            for voice in &voices {
+                for pair in voice.chunks_exact(2 * frame_size) {
+                    let (a, b) = pair.split_at(frame_size);
+                    let expected = [direct_pitch_period(a, min_lag, max_lag), direct_pitch_period(b, min_lag, max_lag)];
+                    assert_eq!(autocorrelator.pitch_period_pair(a, b, min_lag, max_lag), expected, "{} Hz", sample_rate);
+                    assert_eq!(autocorrelator.pitch_period(a, min_lag, max_lag), expected[0], "{} Hz", sample_rate);
+                }
+            }
+        }
+    }
+
+    // Reference: the original O(n·lags) lag-by-lag search
+    fn direct_pitch_period(frame: &[f32], min_lag: usize, max_lag: usize) -> Option<usize> {
+        let max_lag = max_lag.min(frame.len() / 2);
+        let r0: f64 = frame.iter().map(|s| (*s as f64) * (*s as f64)).sum();
+        if min_lag >= max_lag || r0 < 1e-10 {
+            return None;
+        }
+        let (mut best_lag, mut best_corr) = (min_lag, -1.0);
+        for lag in min_lag..max_lag {
+            let (mut corr, mut norm) = (0.0, 0.0);
+            for i in 0..(frame.len() - lag) {
+                corr += frame[i] as f64 * frame[i + lag] as f64;
+                norm += frame[i + lag] as f64 * frame[i + lag] as f64;
+            }
+            let normalized = if norm > 1e-10 { corr / (r0 * norm).sqrt() } else { 0.0 };
+            if normalized > best_corr {
+                best_corr = normalized;
+                best_lag = lag;
+            }
+        }
+        (best_corr > 0.3).then_some(best_lag)
     }
 }
@@ -0,0 +1,396 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! zkLogin address linking
+//!
+//! Users who sign in with Google or Apple hold a zkLogin address rather than an
+//! extension wallet. `POST /link_zklogin` links such an address: the client
+//! signs [`link_message`] with its ephemeral key and sends the serialized
+//! zkLogin signature (`0x05 || BCS(inputs, max_epoch, user_signature)`, as
+//! built by the Sui SDK's `getZkLoginSignature`). The link is signed only if
+//!
+//! 1. the message was issued within the last [`LINK_MESSAGE_MAX_AGE_MS`],
+//! 2. the ephemeral Ed25519 key signed it as a Sui personal message,
+//! 3. the address is the one derived from the proof's issuer and address seed,
+//! 4. the ephemeral key is still valid (`max_epoch` not before the current
+//!    epoch, read over `SUI_RPC_URL`),
+//! 5. the Groth16 proof verifies against the provider's current JWK.
+//!
+//! The result is the same `LinkAddressPayload` `/link_address` signs, so the
+//! contract links zkLogin addresses like any other.
+
+use crate::common::ProcessDataRequest;
+use crate::AppState;
+use crate::EnclaveError;
+use axum::extract::State;
+use axum::Json;
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use blake2::digest::consts::U32;
+use blake2::{Blake2b, Digest};
+use fastcrypto::ed25519::{
+    Ed25519PublicKey, Ed25519Signature, ED25519_PUBLIC_KEY_LENGTH, ED25519_SIGNATURE_LENGTH,
+};
+use fastcrypto::encoding::{Encoding, Hex};
+use fastcrypto::traits::{ToFromBytes, VerifyingKey};
+use fastcrypto_zkp::bn254::zk_login::{JwkId, ZkLoginInputs, JWK};
+use fastcrypto_zkp::bn254::zk_login_api::{verify_zk_login, ZkLoginEnv};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+use super::handlers::{parse_sui_address, sign_link};
+use super::retry::{self, Provider};
+use super::types::{LinkAddressResponse, LinkZkLoginRequest};
+use super::validation::ValidatedJson;
+
+/// OpenID providers whose zkLogin addresses can be linked: issuer and JWKS URL
+pub const ZKLOGIN_PROVIDERS: &[(&str, &str)] = &[
+    ("https://accounts.google.com", "https://www.googleapis.com/oauth2/v3/certs"),
+    ("https://appleid.apple.com", "https://appleid.apple.com/auth/keys"),
+];
+/// How old a signed link message may be
+pub const LINK_MESSAGE_MAX_AGE_MS: u64 = 5 * 60 * 1000;
+/// Clock drift allowed for messages dated in the future
+const LINK_MESSAGE_MAX_SKEW_MS: u64 = 30 * 1000;
+
+/// Signature scheme flag of a serialized zkLogin signature
+const ZKLOGIN_FLAG: u8 = 0x05;
+/// Signature scheme flag of an Ed25519 ephemeral signature
+const ED25519_FLAG: u8 = 0x00;
+/// Intent prefix of a Sui personal message (scope 3, version 0, app Sui)
+const PERSONAL_MESSAGE_INTENT: [u8; 3] = [3, 0, 0];
+
+/// How long a provider's fetched keys are used before refetching
+const JWK_TTL: Duration = Duration::from_secs(60 * 60);
+/// Least time between refetches for an unknown key ID (providers rotate rarely)
+const JWK_MISS_REFETCH: Duration = Duration::from_secs(60);
+/// How long the current epoch is reused (epochs last a day)
+const EPOCH_TTL: Duration = Duration::from_secs(60);
+/// Timeout for one JWKS lookup (epoch lookups follow the Sui RPC retry policy)
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The personal message a zkLogin link is signed over
+pub fn link_message(handle: &str, address: &[u8; 32], issued_at_ms: u64) -> String {
+    format!("Link 0x{} to RAM wallet {} (issued {})", Hex::encode(address), handle, issued_at_ms)
+}
+
+/// BCS body of a serialized zkLogin signature
+#[derive(Deserialize)]
+struct ZkLoginSignature {
+    inputs: ZkLoginInputs,
+    max_epoch: u64,
+    /// The ephemeral key's signature, `flag || signature || public key`
+    user_signature: Vec<u8>,
+}
+
+/// Keys of one provider and when they were fetched
+struct ProviderKeys {
+    fetched_at: Instant,
+    by_kid: HashMap<String, JWK>,
+}
+
+/// Verifies zkLogin signatures, caching provider keys and the current epoch
+#[derive(Default)]
+pub struct ZkLoginVerifier {
+    /// Fullnode for the current epoch; zkLogin links are refused when empty
+    rpc_url: String,
+    jwks: Mutex<HashMap<String, ProviderKeys>>,
+    epoch: Mutex<Option<(Instant, u64)>>,
+}
+
+impl ZkLoginVerifier {
+    pub fn new(rpc_url: String) -> Self {
+        Self { rpc_url, ..Self::default() }
+    }
+
+    /// Check that `signature` proves the owner of `address` signed `message`
+    pub async fn verify(&self, address: &[u8; 32], message: &str, signature: &str) -> Result<(), EnclaveError> {
+        let malformed = |e: String| EnclaveError::GenericError(format!("Malformed zkLogin signature: {}", e));
+        let bytes = STANDARD.decode(signature).map_err(|e| malformed(e.to_string()))?;
+        let Some((&ZKLOGIN_FLAG, body)) = bytes.split_first() else {
+            return Err(malformed("not a zkLogin signature".to_string()));
+        };
+        let ZkLoginSignature { mut inputs, max_epoch, user_signature } =
+            bcs::from_bytes(body).map_err(|e| malformed(e.to_string()))?;
+        let inputs = inputs.init().map_err(|e| malformed(e.to_string()))?;
+
+        let eph_pubkey = verify_personal_message(&user_signature, message.as_bytes())?;
+
+        let iss = inputs.get_iss();
+        let seed = inputs.get_address_seed();
+        // Sui accepts the address seed both zero-padded to 32 bytes and unpadded
+        if ![seed.padded(), seed.unpadded()].iter().any(|seed| zklogin_address(iss, seed) == *address) {
+            return Err(EnclaveError::Unauthorized(format!(
+                "zkLogin proof is not for 0x{}",
+                Hex::encode(address)
+            )));
+        }
+
+        let epoch = self.current_epoch().await?;
+        if max_epoch < epoch {
+            return Err(EnclaveError::Unauthorized(format!(
+                "zkLogin session expired at epoch {} (now {}); sign in again",
+                max_epoch, epoch
+            )));
+        }
+
+        let kid = inputs.get_kid();
+        let jwk = self.jwk(iss, kid).await?;
+        let jwks = [(JwkId { iss: iss.to_string(), kid: kid.to_string() }, jwk)].into_iter().collect();
+        verify_zk_login(&inputs, max_epoch, &eph_pubkey, &jwks, &ZkLoginEnv::Prod)
+            .map_err(|e| EnclaveError::Unauthorized(format!("zkLogin proof rejected: {}", e)))
+    }
+
+    /// The provider's key `kid`, refetching its keys when stale or unknown
+    async fn jwk(&self, iss: &str, kid: &str) -> Result<JWK, EnclaveError> {
+        let Some(&(_, url)) = ZKLOGIN_PROVIDERS.iter().find(|(issuer, _)| *issuer == iss) else {
+            return Err(EnclaveError::Forbidden(format!("zkLogin provider '{}' is not supported", iss)));
+        };
+
+        if let Some(keys) = self.jwks.lock().unwrap().get(iss) {
+            let age = keys.fetched_at.elapsed();
+            match keys.by_kid.get(kid) {
+                Some(jwk) if age < JWK_TTL => return Ok(jwk.clone()),
+                None if age < JWK_MISS_REFETCH => {
+                    return Err(EnclaveError::Unauthorized(format!("Unknown {} key '{}'", iss, kid)));
+                }
+                _ => {}
+            }
+        }
+
+        let response = reqwest::Client::new().get(url).timeout(FETCH_TIMEOUT).send().await;
+        let by_kid = parse_jwks(&read_json(response).await.map_err(|e| {
+            warn!("RAM: fetching {} keys failed: {}", iss, e);
+            EnclaveError::GenericError(format!("Could not fetch {} keys", iss))
+        })?);
+        info!("RAM: fetched {} zkLogin keys for {}", by_kid.len(), iss);
+
+        let jwk = by_kid.get(kid).cloned();
+        self.jwks
+            .lock()
+            .unwrap()
+            .insert(iss.to_string(), ProviderKeys { fetched_at: Instant::now(), by_kid });
+        jwk.ok_or_else(|| EnclaveError::Unauthorized(format!("Unknown {} key '{}'", iss, kid)))
+    }
+
+    /// Current Sui epoch, cached briefly
+    async fn current_epoch(&self) -> Result<u64, EnclaveError> {
+        if let Some((fetched_at, epoch)) = *self.epoch.lock().unwrap() {
+            if fetched_at.elapsed() < EPOCH_TTL {
+                return Ok(epoch);
+            }
+        }
+        if self.rpc_url.is_empty() {
+            return Err(EnclaveError::GenericError(
+                "zkLogin linking needs a Sui RPC (SUI_RPC_URL)".to_string(),
+            ));
+        }
+
+        let lookup_failed = |e: String| {
+            warn!("RAM: current epoch lookup failed: {}", e);
+            EnclaveError::GenericError("Could not read the current Sui epoch".to_string())
+        };
+        let client = reqwest::Client::new();
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "suix_getLatestSuiSystemState",
+            "params": [],
+        });
+        let response = retry::send(Provider::SuiRpc, || client.post(&self.rpc_url).json(&request)).await;
+        let response = read_json(response).await.map_err(lookup_failed)?;
+        let epoch = response["result"]["epoch"]
+            .as_str()
+            .and_then(|epoch| epoch.parse().ok())
+            .ok_or_else(|| lookup_failed(format!("unexpected response {}", response)))?;
+
+        *self.epoch.lock().unwrap() = Some((Instant::now(), epoch));
+        Ok(epoch)
+    }
+}
+
+async fn read_json(response: Result<reqwest::Response, reqwest::Error>) -> Result<Value, String> {
+    response
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| e.to_string())?
+        .json()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// RSA keys of a JWKS document by key ID
+fn parse_jwks(document: &Value) -> HashMap<String, JWK> {
+    let field = |key: &Value, name: &str| key[name].as_str().map(str::to_string);
+    document["keys"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|key| key["kty"] == "RSA")
+        .filter_map(|key| {
+            let jwk = JWK {
+                kty: field(key, "kty")?,
+                e: field(key, "e")?,
+                // Padding is optional in JWKS; the proof's modulus never has it
+                n: field(key, "n")?.trim_end_matches('=').to_string(),
+                alg: field(key, "alg").unwrap_or_else(|| "RS256".to_string()),
+            };
+            Some((field(key, "kid")?, jwk))
+        })
+        .collect()
+}
+
+/// Check the ephemeral key's signature over a Sui personal message
+///
+/// Returns the key as `flag || public key`, the form the proof commits to.
+fn verify_personal_message(user_signature: &[u8], message: &[u8]) -> Result<Vec<u8>, EnclaveError> {
+    let rejected = |reason: &str| EnclaveError::Unauthorized(format!("Ephemeral signature {}", reason));
+    let Some((&ED25519_FLAG, rest)) = user_signature.split_first() else {
+        return Err(rejected("must be Ed25519"));
+    };
+    if rest.len() != ED25519_SIGNATURE_LENGTH + ED25519_PUBLIC_KEY_LENGTH {
+        return Err(rejected("has the wrong length"));
+    }
+    let (signature, public_key) = rest.split_at(ED25519_SIGNATURE_LENGTH);
+    let signature = Ed25519Signature::from_bytes(signature).map_err(|_| rejected("is malformed"))?;
+    let public_key = Ed25519PublicKey::from_bytes(public_key).map_err(|_| rejected("has a malformed key"))?;
+
+    public_key
+        .verify(&personal_message_digest(message), &signature)
+        .map_err(|_| rejected("does not match the link message"))?;
+    Ok([&[ED25519_FLAG], public_key.as_bytes()].concat())
+}
+
+/// What a Sui wallet signs for a personal message
+fn personal_message_digest(message: &[u8]) -> [u8; 32] {
+    let mut hasher = Blake2b::<U32>::new();
+    hasher.update(PERSONAL_MESSAGE_INTENT);
+    hasher.update(bcs::to_bytes(message).expect("bytes serialize"));
+    hasher.finalize().into()
+}
+
+/// Sui address of a zkLogin identity: issuer plus address seed
+fn zklogin_address(iss: &str, address_seed: &[u8]) -> [u8; 32] {
+    let mut hasher = Blake2b::<U32>::new();
+    hasher.update([ZKLOGIN_FLAG, iss.len() as u8]);
+    hasher.update(iss.as_bytes());
+    hasher.update(address_seed);
+    hasher.finalize().into()
+}
+
+/// Link a zkLogin (Google/Apple sign-in) address to RAM wallet
+///
+/// POST /link_zklogin
+/// Request: handle, wallet_address, zklogin_signature, issued_at_ms, label
+/// Response: signed `LinkAddressPayload`, as from `/link_address`
+pub async fn process_link_zklogin(
+    State(state): State<Arc<AppState>>,
+    ValidatedJson(request): ValidatedJson<ProcessDataRequest<LinkZkLoginRequest>>,
+) -> Result<Json<LinkAddressResponse>, EnclaveError> {
+    let req = &request.payload;
+    let version = state.payload_versions.select(request.payload_version)?;
+
+    info!(
+        "RAM: Linking zkLogin address for handle='{}' -> {} (label='{}')",
+        req.handle, req.wallet_address, req.label
+    );
+
+    let current_timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_err(|e| EnclaveError::GenericError(format!("Failed to get timestamp: {}", e)))?
+        .as_millis() as u64;
+    if req.issued_at_ms > current_timestamp + LINK_MESSAGE_MAX_SKEW_MS
+        || current_timestamp.saturating_sub(req.issued_at_ms) > LINK_MESSAGE_MAX_AGE_MS
+    {
+        return Err(EnclaveError::GenericError(
+            "Link message is expired or not yet valid; sign a new one".to_string(),
+        ));
+    }
+
+    let address = parse_sui_address(&req.wallet_address)?;
+    let message = link_message(&req.handle, &address, req.issued_at_ms);
+    if let Err(e) = state.zklogin.verify(&address, &message, &req.zklogin_signature).await {
+        warn!("RAM: zkLogin link for handle='{}' refused: {}", req.handle, e);
+        return Err(e);
+    }
+
+    let response = sign_link(&state, &req.handle, address, &req.label, version)?;
+
+    info!("RAM: zkLogin address linked for handle='{}'", req.handle);
+
+    Ok(Json(response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fastcrypto::ed25519::Ed25519KeyPair;
+    use fastcrypto::traits::{KeyPair, Signer};
+    use rand::thread_rng;
+
+    fn user_signature(kp: &Ed25519KeyPair, message: &[u8]) -> Vec<u8> {
+        let signature: Ed25519Signature = kp.sign(&personal_message_digest(message));
+        [&[ED25519_FLAG], signature.as_bytes(), kp.public().as_bytes()].concat()
+    }
+
+    #[test]
+    fn test_personal_message_signature_yields_flagged_key() {
+        let kp = Ed25519KeyPair::generate(&mut thread_rng());
+        let message = link_message("alice", &[7; 32], 1_700_000_000_000);
+        let eph_pubkey = verify_personal_message(&user_signature(&kp, message.as_bytes()), message.as_bytes()).unwrap();
+        assert_eq!(eph_pubkey[0], ED25519_FLAG);
+        assert_eq!(&eph_pubkey[1..], kp.public().as_bytes());
+    }
+
+    #[test]
+    fn test_signature_over_other_message_or_scheme_is_rejected() {
+        let kp = Ed25519KeyPair::generate(&mut thread_rng());
+        let signed = link_message("alice", &[7; 32], 1);
+        let signature = user_signature(&kp, signed.as_bytes());
+
+        // Another handle, address or issue time is another message
+        for other in [link_message("mallory", &[7; 32], 1), link_message("alice", &[8; 32], 1), link_message("alice", &[7; 32], 2)] {
+            assert!(verify_personal_message(&signature, other.as_bytes()).is_err());
+        }
+        // Only Ed25519 ephemeral keys are accepted
+        let mut secp = signature.clone();
+        secp[0] = 0x01;
+        assert!(verify_personal_message(&secp, signed.as_bytes()).is_err());
+        assert!(verify_personal_message(&signature[..64], signed.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_address_depends_on_issuer_and_seed() {
+        let seed = [9; 32];
+        let google = zklogin_address("https://accounts.google.com", &seed);
+        assert_eq!(google, zklogin_address("https://accounts.google.com", &seed));
+        assert_ne!(google, zklogin_address("https://appleid.apple.com", &seed));
+        assert_ne!(google, zklogin_address("https://accounts.google.com", &seed[1..]));
+    }
+
+    #[test]
+    fn test_jwks_keeps_rsa_keys_by_kid() {
+        let document = json!({ "keys": [
+            { "kid": "k1", "kty": "RSA", "e": "AQAB", "n": "abc==", "alg": "RS256" },
+            { "kid": "k2", "kty": "RSA", "e": "AQAB", "n": "def" },
+            { "kid": "k3", "kty": "EC", "crv": "P-256", "x": "x", "y": "y" },
+            { "kty": "RSA", "e": "AQAB", "n": "no-kid" },
+        ]});
+        let keys = parse_jwks(&document);
+        assert_eq!(keys.len(), 2);
+        assert_eq!(keys["k1"].n, "abc");
+        assert_eq!(keys["k2"].alg, "RS256");
+        assert!(parse_jwks(&json!({})).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_non_zklogin_signatures_are_rejected_before_any_lookup() {
+        let verifier = ZkLoginVerifier::default();
+        for signature in ["not base64!", "", &STANDARD.encode([0x00; 97]), &STANDARD.encode([ZKLOGIN_FLAG, 1, 2, 3])] {
+            let err = verifier.verify(&[7; 32], "message", signature).await.unwrap_err();
+            assert!(err.to_string().contains("Malformed zkLogin signature"), "{}", err);
+        }
+    }
+}
@@ -0,0 +1,89 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! RAM ABI Check
+//!
+//! Verifies that the deployed Move package and the enclave agree on payload
+//! layouts and intent codes. Exits non-zero on any drift, so it can gate CI and
+//! staging deploys.
+//!
+//! Build and run:
+//! ```bash
+//! cargo run --no-default-features --features ram --bin ram-abi-check -- [path/to/core.move]
+//! ```
+//!
+//! Environment variables:
+//! - SUI_RPC_URL: Fullnode to fetch normalized modules from (default: testnet)
+//! - RAM_PACKAGE_ID: Deployed RAM package ID (required)
+//!
+//! The optional argument is the Move source the package was published from;
+//! intent constants are only checked when it is given, since constant values
+//! are not part of the on-chain ABI.
+
+use anyhow::{anyhow, Context, Result};
+use nautilus_server::ram_app::abi;
+use serde_json::{json, Value};
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    dotenvy::dotenv().ok();
+
+    let rpc_url = std::env::var("SUI_RPC_URL")
+        .unwrap_or_else(|_| "https://fullnode.testnet.sui.io:443".to_string());
+    let package_id = std::env::var("RAM_PACKAGE_ID").context("RAM_PACKAGE_ID must be set")?;
+    let move_source = std::env::args().nth(1);
+
+    println!("Checking RAM ABI for package {} via {}", package_id, rpc_url);
+
+    let module = fetch_core_module(&rpc_url, &package_id).await?;
+    let constants = match &move_source {
+        Some(path) => {
+            let source = std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read Move source {}", path))?;
+            Some(abi::move_u8_constants(&source))
+        }
+        None => {
+            println!("No Move source given, skipping intent constant check");
+            None
+        }
+    };
+
+    let payloads = abi::rust_payloads().map_err(|e| anyhow!(e))?;
+    let drift = abi::check(&payloads, &module, constants.as_ref());
+
+    if drift.is_empty() {
+        println!("OK: {} payloads match {}::{}", payloads.len(), package_id, abi::CORE_MODULE);
+        return Ok(());
+    }
+
+    eprintln!("ABI drift detected between enclave and Move contract:");
+    for d in &drift {
+        eprintln!("  - {}", d);
+    }
+    std::process::exit(1);
+}
+
+async fn fetch_core_module(rpc_url: &str, package_id: &str) -> Result<Value> {
+    let response: Value = reqwest::Client::new()
+        .post(rpc_url)
+        .json(&json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "sui_getNormalizedMoveModule",
+            "params": [package_id, abi::CORE_MODULE],
+        }))
+        .send()
+        .await
+        .context("Failed to reach Sui RPC")?
+        .json()
+        .await
+        .context("Invalid Sui RPC response")?;
+
+    if let Some(error) = response.get("error") {
+        return Err(anyhow!("Sui RPC error: {}", error));
+    }
+    response
+        .get("result")
+        .cloned()
+        .ok_or_else(|| anyhow!("Sui RPC response has no result"))
+}
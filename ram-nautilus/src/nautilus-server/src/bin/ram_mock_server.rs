@@ -0,0 +1,67 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! RAM Mock Server Binary
+//!
+//! Stand-in for the enclave so the frontend and backend can be developed
+//! without AWS Nitro or API keys. Serves every enclave route with scripted
+//! bio-auth outcomes and signatures from a fixed development key.
+//!
+//! Build and run:
+//! ```bash
+//! cargo run --no-default-features --features ram --bin ram-mock-server
+//! ```
+//!
+//! Force a bio-auth outcome per request:
+//! ```bash
+//! curl -X POST localhost:3000/bio_auth -H 'x-mock-outcome: duress' -H 'content-type: application/json' \
+//!   -d '{"payload":{"handle":"alice","audio_base64":"","expected_amount":5000000000}}'
+//! ```
+//!
+//! Environment variables:
+//! - PORT: Listen port (default: 3000, same as ram-server)
+//! - MOCK_TIMESTAMP_MS: Sign everything with this timestamp, making signatures reproducible (optional)
+//! - MOCK_ASYNC_MODE: Set to "true" to answer /bio_auth with a job ID like BIOAUTH_ASYNC_MODE (default: false)
+
+use anyhow::Result;
+use nautilus_server::ram_app::{mock_router, MockState, MOCK_OUTCOME_HEADER};
+use std::sync::Arc;
+use tower_http::cors::{Any, CorsLayer};
+use tracing::{info, warn};
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    dotenvy::dotenv().ok();
+
+    tracing_subscriber::fmt()
+        .with_target(false)
+        .with_level(true)
+        .init();
+
+    let fixed_timestamp_ms = std::env::var("MOCK_TIMESTAMP_MS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok());
+    let async_mode = std::env::var("MOCK_ASYNC_MODE")
+        .map(|v| v == "true" || v == "1")
+        .unwrap_or(false);
+
+    let state = Arc::new(MockState::new(fixed_timestamp_ms, async_mode));
+
+    warn!("Starting RAM MOCK enclave: signatures use a public development key, never deploy this");
+    info!("Mock config:");
+    info!("  Public key: {}", state.public_key_hex());
+    info!("  Timestamps: {}", fixed_timestamp_ms.map_or("clock".to_string(), |t| format!("fixed at {}", t)));
+    info!("  BioAuth mode: {}", if async_mode { "queued (jobs finish immediately)" } else { "synchronous" });
+    info!("  Outcomes: set '{}: ok | invalid_amount | duress | decoy | inconclusive' per request", MOCK_OUTCOME_HEADER);
+
+    let cors = CorsLayer::new().allow_methods(Any).allow_headers(Any).allow_origin(Any);
+    let app = mock_router(state).layer(cors);
+
+    let port = std::env::var("PORT").unwrap_or_else(|_| "3000".to_string());
+    let listener = tokio::net::TcpListener::bind(format!("0.0.0.0:{}", port)).await?;
+    info!("RAM Mock Server listening on {}", listener.local_addr().unwrap());
+
+    axum::serve(listener, app.into_make_service())
+        .await
+        .map_err(|e| anyhow::anyhow!("Server error: {}", e))
+}
@@ -13,20 +13,89 @@
 //! Environment variables:
 //! - OPENROUTER_API_KEY: For GPT-4o Audio API (optional, falls back to mock)
 //! - HUME_API_KEY: For Hume AI emotion detection (optional, enhances stress detection)
+//! - LLM_MODEL: OpenRouter model that transcribes and scores clips (default: openai/gpt-4o-audio-preview)
+//! - LLM_PROMPT_TEMPLATE_PATH: minijinja template for its prompt, checked at startup (default: the built-in `prompts/transcription.j2`)
+//! - BIOAUTH_MIN_TRANSCRIPT_CONFIDENCE / BIOAUTH_MIN_AMOUNT_CONFIDENCE / BIOAUTH_MIN_STRESS_CONFIDENCE: Below these (0-1) nothing is signed and the user is asked to record again; 0 disables (default: 0.5 / 0.6 / 0.5)
+//! - HUME_MODE: "stream" to score over Hume's streaming API with a batch fallback, or "batch" (default: stream)
+//! - HUME_STREAM_TIMEOUT_MS: How long a streamed Hume analysis may take before the batch fallback (default: 3000)
+//! - DIAGNOSTICS_API_SECRET: Shared secret for backend diagnostics access (optional, disabled if unset)
+//! - DIAGNOSTICS_TTL_SECS: How long BioAuth diagnostics are retained (default: 600)
+//! - BIOAUTH_ASYNC_MODE: Set to "true" to queue /bio_auth and return a job ID (default: false)
+//! - BIOAUTH_QUEUE_CAPACITY: Max jobs waiting in the BioAuth queue (default: 32)
+//! - BIOAUTH_WORKERS: Number of concurrent BioAuth analysis workers (default: 2)
+//! - BIOAUTH_MAX_CONCURRENT: /bio_auth and /bio_auth/stream requests handled at once; past it they get a 429 (default: 8)
+//! - BIOAUTH_SHED_QUEUE_DEPTH: Queue depth at which queued-mode uploads are shed before their body is read (default: the queue's capacity)
+//! - BIOAUTH_RETRY_AFTER_SECS: Retry-After sent with shed requests (default: 5)
+//! - BIOAUTH_DURESS_DECOY: Set to "true" to sign duress as an OK-looking decoy that still locks on-chain (default: false)
+//! - ANALYSIS_CACHE_TTL_SECS: How long an analysis is reused for the same clip, handle and amount; 0 disables (default: 60)
+//! - ANALYZER_DISAGREEMENT_POINTS: Warn (log target analyzer_disagreement) when GPT-4o, Hume and DSP stress scores differ by more than this; 0 disables (default: 30)
+//! - BIOAUTH_GRAY_ZONE: Stress scores re-checked by a second analysis pass before deciding, as low-high; "off" disables (default: 60-75)
+//! - FEATURE_FLAGS_REFRESH_SECS: How often the flags are fetched again (default: 30)
+//! - BIOAUTH_REPLAY_WINDOW_DAYS: How long accepted clips are remembered to reject replays, 0 disables (default: 7)
+//! - REQUEST_SIGNING_KEY: Shared HMAC key; requests without a valid signature are rejected (optional, disabled if unset)
+//! - REQUEST_MAX_SKEW_MS: Allowed request timestamp drift when signing is on (default: 30000)
+//! - SIGNING_ALLOWED_CIDRS: Comma-separated CIDRs allowed to call signing routes (optional, all peers if unset)
+//! - PRIVILEGED_ALLOWED_CIDRS: CIDRs allowed to call diagnostics/baseline routes (default: SIGNING_ALLOWED_CIDRS)
+//! - SUI_NETWORK: Network signed for: mainnet, testnet, devnet or localnet (default: testnet)
+//! - SUI_CHAIN_ID: The network's chain identifier; mainnet and testnet must match their known one (default: known for mainnet/testnet)
+//! - RAM_PACKAGE_IDS / RAM_PACKAGE_ID: Original RAM package per network (`mainnet=0x…,testnet=0x…`) / on SUI_NETWORK; required for SUI_NETWORK, bound into v3 payloads
+//! - SUI_RPC_URL: Fullnode used for the current epoch of zkLogin links and for transfer simulation (default: SUI_NETWORK's public fullnode); coin symbols and decimals are always read from SUI_NETWORK's public fullnode
+//! - DEV_FIXED_KEY: Hex Ed25519 private key to sign with instead of a fresh one (integration environments only)
+//! - CHAOS_UPSTREAM_LATENCY_MS / CHAOS_UPSTREAM_LATENCY_PERCENT: Delay that share of OpenRouter/Hume calls (`chaos` builds only)
+//! - CHAOS_OPENROUTER_ERROR_PERCENT: Fail that share of OpenRouter calls with a 500 (`chaos` builds only)
+//! - OPENROUTER_DAILY_BUDGET_USD / HUME_DAILY_BUDGET_USD: Daily spend caps; past them bio-auth is DSP-only until 00:00 UTC (optional, unlimited if unset)
+//! - HUME_COST_PER_CALL_USD: Cost charged per Hume call toward its budget (default: 0)
+//! - OPENROUTER_TIMEOUT_MS / OPENROUTER_MAX_RETRIES / OPENROUTER_BACKOFF_MS: Per-attempt timeout, retries after it, and first backoff (doubling) for OpenRouter (default: 30000 / 1 / 500)
+//! - HUME_TIMEOUT_MS / HUME_MAX_RETRIES / HUME_BACKOFF_MS: The same for Hume batch jobs (default: 15000 / 1 / 500)
+//! - SUI_RPC_TIMEOUT_MS / SUI_RPC_MAX_RETRIES / SUI_RPC_BACKOFF_MS: The same for Sui RPC calls (default: 10000 / 2 / 200)
+//! - CAPTCHA_SECRET: Server-side secret of the Turnstile / hCaptcha site (required for a CAPTCHA gate)
+//! - SCREENING_API_KEY: Bearer token for the screening service (optional)
+//! - SCREENING_TIMEOUT_MS / SCREENING_MAX_RETRIES / SCREENING_BACKOFF_MS: Retry policy of the screening service (default: 5000 / 1 / 200)
+//! - RAM_ENCLAVE_ID: This enclave's registered Enclave object; transfers asking for `simulate` are dry-run against it over SUI_RPC_URL before they're answered (optional, answered unsimulated if unset)
+//! - RAM_ENCLAVE_PACKAGE_ID: Package defining the enclave's `core::XWALLET` witness (default: RAM_PACKAGE_ID)
+//! - SNAPSHOT_URL: Store on the parent instance that sealed snapshots of challenges and upstream spend are PUT to and restored from at boot (optional, disabled if unset)
+//! - SNAPSHOT_KEY: Hex 32-byte key snapshots are sealed under, delivered with the other secrets (required with SNAPSHOT_URL)
+//! - SNAPSHOT_INTERVAL_SECS / SNAPSHOT_MAX_AGE_SECS: Time between snapshots / oldest snapshot restored at boot (default: 30 / 600)
+//!
+//! Set when the image is built (compiled in, so the parent can't change them; see `endpoints`):
+//! - FEATURE_FLAGS_URL: Backend `GET /api/flags` the gray_zone_retry, duress_decoy and hume_stream flags are fetched from (optional, all on if unset)
+//! - RAM_ENVIRONMENT: Environment the flags are evaluated in (default: production)
+//! - CREATE_WALLET_GATE: Anti-abuse check on wallet creation: off, pow, turnstile or hcaptcha (default: off)
+//! - CREATE_WALLET_POW_BITS: Leading zero bits a create_wallet pow_nonce must produce (default: 20)
+//! - SCREENING_DENY_LIST_PATH: File in the image of denied handles/addresses, one per line; transfers to them and withdrawals from them get a 451 (optional)
+//! - SCREENING_API_URL: Screening service asked about each transfer recipient and withdrawing wallet (optional)
+//! - SCREENING_FAIL_OPEN: Set to "true" to allow requests when the screening service is unreachable (default: false, they're blocked)
 
 use anyhow::Result;
-use axum::{routing::get, routing::post, Router};
+use axum::{middleware, routing::delete, routing::get, routing::post, Router};
+use fastcrypto::encoding::{Encoding, Hex};
+use fastcrypto::traits::ToFromBytes;
 use fastcrypto::{ed25519::Ed25519KeyPair, traits::KeyPair};
 // Import RAM app handlers
 use nautilus_server::ram_app::{
-    process_create_wallet, process_link_address, process_bio_auth,
-    process_transfer, process_withdraw,
+    process_create_wallet, process_link_address, process_unlink_address, process_bio_auth, process_bio_auth_stream,
+    process_transfer, process_withdraw, get_bio_auth_diagnostics, DiagnosticsStore,
+    get_bio_auth_job, get_bio_auth_queue, start_bio_auth_workers, JobQueue, HandleLocks,
+    get_bio_auth_shadow, ShadowMode, process_enroll_voice, reset_voice_baseline, BaselineStore,
+    process_bio_auth_challenge, get_challenge_audio, ChallengeStore, process_typed_auth, ReplayGuard, AttemptTracker,
+    CoinRegistry, install_upstream_faults, UpstreamFaults, get_metrics, CostLimits, CostTracker,
+    require_signed_request, RequestAuth, enforce_peer_acl, parse_cidrs, PeerAcl, process_link_zklogin, ZkLoginVerifier,
+    process_set_limit, process_freeze_wallet, process_unfreeze_challenge, process_request_unfreeze,
+    process_set_beneficiary, process_prove_deposit, get_intents, PayloadVersions, shed_analysis_load, AnalysisLimits,
+    install_retry_policies, ProviderPolicies, AnalysisCache, GrayZone, RegistrationGate, GateRequirement, get_registration_gate, install_disagreement_threshold, disagreement_threshold_from_env, install_chain, ChainConfig, Screener, TransferSimulator,
+    install_llm_config, LlmConfig, install_confidence_thresholds, ConfidenceThresholds,
+    install_feature_flags, spawn_feature_flag_refresh, current_feature_flags, FeatureFlags,
+    process_update_preferences, PreferenceStore, SnapshotConfig, restore_snapshot, spawn_snapshots,
 };
-use nautilus_server::common::{get_attestation, health_check};
-use nautilus_server::AppState;
+use nautilus_server::common::{get_attestation, get_public_key, health_check, keypair_from_hex};
+use nautilus_server::i18n::assign_request_lang;
+use nautilus_server::{assign_request_id, AppState};
+use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Duration;
 use tower_http::cors::{Any, CorsLayer};
-use tracing::info;
+use tracing::{info, warn};
+use zeroize::Zeroizing;
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -41,51 +110,339 @@ async fn main() -> Result<()> {
 
     info!("Starting RAM Voice Wallet Server");
 
-    let eph_kp = Ed25519KeyPair::generate(&mut rand::thread_rng());
+    // Integration environments can pin the key so tests verify against a known one.
+    // Never inside a real enclave: the attestation would vouch for a key held elsewhere.
+    let fixed_key = Zeroizing::new(std::env::var("DEV_FIXED_KEY").unwrap_or_default());
+    let eph_kp = if fixed_key.is_empty() {
+        Ed25519KeyPair::generate(&mut rand::thread_rng())
+    } else {
+        if std::path::Path::new("/dev/nsm").exists() {
+            anyhow::bail!("DEV_FIXED_KEY must not be set inside a Nitro enclave");
+        }
+        warn!("DEV_FIXED_KEY set: signing with a fixed development key");
+        keypair_from_hex(&fixed_key)?
+    };
 
     // RAM configuration (loaded from environment variables)
-    let openrouter_api_key = std::env::var("OPENROUTER_API_KEY").unwrap_or_default();
-    let hume_api_key = std::env::var("HUME_API_KEY").unwrap_or_default();
+    let openrouter_api_key = Zeroizing::new(std::env::var("OPENROUTER_API_KEY").unwrap_or_default());
+    let hume_api_key = Zeroizing::new(std::env::var("HUME_API_KEY").unwrap_or_default());
+    let tts_api_key = std::env::var("TTS_API_KEY").unwrap_or_default();
+    let diagnostics_secret = Zeroizing::new(std::env::var("DIAGNOSTICS_API_SECRET").unwrap_or_default());
+    let diagnostics_ttl_secs = std::env::var("DIAGNOSTICS_TTL_SECS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(nautilus_server::ram_app::DEFAULT_DIAGNOSTICS_TTL_SECS);
+    let bioauth_async = std::env::var("BIOAUTH_ASYNC_MODE")
+        .map(|v| v == "true" || v == "1")
+        .unwrap_or(false);
+    let bioauth_queue_capacity = std::env::var("BIOAUTH_QUEUE_CAPACITY")
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(nautilus_server::ram_app::DEFAULT_QUEUE_CAPACITY);
+    let bioauth_workers = std::env::var("BIOAUTH_WORKERS")
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(nautilus_server::ram_app::DEFAULT_WORKERS);
+    let bioauth_decoy = std::env::var("BIOAUTH_DURESS_DECOY")
+        .map(|v| v == "true" || v == "1")
+        .unwrap_or(false);
+    let bioauth_replay_window_days = std::env::var("BIOAUTH_REPLAY_WINDOW_DAYS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(nautilus_server::ram_app::DEFAULT_REPLAY_WINDOW_DAYS);
+
+    let request_auth = Arc::new(RequestAuth::new(
+        std::env::var("REQUEST_SIGNING_KEY").unwrap_or_default(),
+        std::env::var("REQUEST_MAX_SKEW_MS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(nautilus_server::ram_app::DEFAULT_REQUEST_MAX_SKEW_MS),
+    ));
+
+    // A typo here must not silently open the signing routes, so refuse to start
+    let signing_cidrs = parse_cidrs(&std::env::var("SIGNING_ALLOWED_CIDRS").unwrap_or_default())
+        .map_err(|e| anyhow::anyhow!("SIGNING_ALLOWED_CIDRS: {}", e))?;
+    let privileged_cidrs = match std::env::var("PRIVILEGED_ALLOWED_CIDRS") {
+        Ok(list) if !list.trim().is_empty() => {
+            Some(parse_cidrs(&list).map_err(|e| anyhow::anyhow!("PRIVILEGED_ALLOWED_CIDRS: {}", e))?)
+        }
+        _ => None,
+    };
+    let peer_acl = Arc::new(PeerAcl::new(signing_cidrs, privileged_cidrs));
+
+    let bioauth_shadow = std::env::var("BIOAUTH_SHADOW_MODE")
+        .map(|v| v == "true" || v == "1")
+        .unwrap_or(false);
+    let bioauth_shadow_threshold = std::env::var("BIOAUTH_SHADOW_THRESHOLD")
+        .ok()
+        .and_then(|v| v.parse::<u8>().ok());
 
     info!("RAM Config:");
+    info!("  Signing key: {} ({})", Hex::encode(eph_kp.public().as_bytes()), if fixed_key.is_empty() { "ephemeral" } else { "DEV_FIXED_KEY" });
     info!("  OpenRouter API: {}", if openrouter_api_key.is_empty() { "(not set - using mock)" } else { "(configured)" });
     info!("  Hume AI API: {}", if hume_api_key.is_empty() { "(not set - GPT-4o stress only)" } else { "(configured - enhanced stress detection)" });
+    info!("  Challenge audio (TTS): {}", if tts_api_key.is_empty() { "(disabled)" } else { "(configured)" });
+    info!("  Peer allowlist: {}", if peer_acl.is_enabled() { "(enforced)" } else { "(disabled - all peers)" });
+    info!("  Request signing: {}", if request_auth.is_enabled() { "(required)" } else { "(disabled - any client that reaches this port gets signed payloads)" });
+    info!("  Diagnostics channel: {}", if diagnostics_secret.is_empty() { "(disabled)".to_string() } else { format!("(enabled, ttl={}s)", diagnostics_ttl_secs) });
+    info!("  Duress detection: {}", if bioauth_shadow { format!("SHADOW (not enforced, threshold={})", bioauth_shadow_threshold.map_or("default".to_string(), |t| t.to_string())) } else { "enforced".to_string() });
+    info!("  Duress decoy: {}", if bioauth_decoy { "(enabled - duress is signed as OK, wallet still locks)" } else { "(disabled)" });
+    info!("  Replay protection: {}", if bioauth_replay_window_days == 0 { "(disabled)".to_string() } else { format!("(clips remembered {} days)", bioauth_replay_window_days) });
+    info!("  BioAuth mode: {}", if bioauth_async { format!("queued (capacity={}, workers={})", bioauth_queue_capacity, bioauth_workers) } else { "synchronous".to_string() });
+
+    let analysis_limits = AnalysisLimits::from_env();
+    info!(
+        "  Load shedding: {} concurrent analyses{}, Retry-After {}s",
+        analysis_limits.max_concurrent,
+        analysis_limits.shed_queue_depth.map_or(String::new(), |depth| format!(", queue depth {}", depth)),
+        analysis_limits.retry_after_secs
+    );
+
+    let payload_versions = PayloadVersions::from_env();
+    info!(
+        "  Payload layout: v{} by default{}",
+        payload_versions.default_version() as u8,
+        payload_versions.v1_sunset_ms().map_or(String::new(), |ms| format!(", v1 refused from {} ms", ms))
+    );
+
+    install_upstream_faults(UpstreamFaults::from_env());
+
+    let retry_policies = ProviderPolicies::from_env();
+    info!("  Upstream retries: {:?}", retry_policies);
+    install_retry_policies(retry_policies);
+
+    // A template that doesn't render would fail every bio-auth, so refuse to start
+    let llm = LlmConfig::from_env().map_err(|e| anyhow::anyhow!("LLM configuration: {}", e))?;
+    info!(
+        "  Transcription model: {}{}",
+        llm.model,
+        if std::env::var("LLM_PROMPT_TEMPLATE_PATH").is_ok_and(|p| !p.trim().is_empty()) { " (custom prompt)" } else { "" }
+    );
+    install_llm_config(llm);
+
+    let confidence_thresholds = ConfidenceThresholds::from_env();
+    info!("  Minimum analysis confidence: {:?}", confidence_thresholds);
+    install_confidence_thresholds(confidence_thresholds);
 
+    let cost_limits = CostLimits::from_env();
+    let budget = |cap: Option<u64>| cap.map_or("unlimited".to_string(), |c| format!("${:.2}/day", c as f64 / 1e6));
+    info!("  Upstream budgets: OpenRouter {}, Hume {}", budget(cost_limits.openrouter_daily_micro_usd), budget(cost_limits.hume_daily_micro_usd));
+
+    let analysis_cache = AnalysisCache::from_env();
+    info!(
+        "  Analysis cache: {}",
+        if analysis_cache.is_enabled() { "(enabled - identical clips reuse a recent analysis)" } else { "(disabled)" }
+    );
+
+    let disagreement_threshold = disagreement_threshold_from_env();
+    info!(
+        "  Analyzer disagreement warnings: {}",
+        if disagreement_threshold == 0 { "(disabled)".to_string() } else { format!("sub-scores more than {} points apart", disagreement_threshold) }
+    );
+    install_disagreement_threshold(disagreement_threshold);
+
+    let bioauth_gray_zone = GrayZone::from_env();
+    info!(
+        "  Stress gray zone: {}",
+        match bioauth_gray_zone.range() {
+            Some(range) => format!("{}-{} (scores in it get a second analysis pass)", range.start(), range.end()),
+            None => "(disabled)".to_string(),
+        }
+    );
+
+    // Risky analysis features stay at their defaults until the backend's flags arrive
+    install_feature_flags(FeatureFlags::from_env());
+    info!(
+        "  Feature flags: {}",
+        match spawn_feature_flag_refresh() {
+            Some(url) => format!("from {} in {}", url, current_feature_flags().environment()),
+            None => "(not fetched - every feature at its default)".to_string(),
+        }
+    );
+
+    // A gate that can't be set up must not leave wallet creation open
+    let registration_gate =
+        RegistrationGate::from_env().map_err(|e| anyhow::anyhow!("Registration gate configuration: {}", e))?;
+    info!(
+        "  Wallet creation gate: {}",
+        match registration_gate.requirement() {
+            GateRequirement::Off => "(disabled)".to_string(),
+            GateRequirement::ProofOfWork { difficulty_bits } => format!("proof of work, {} bits", difficulty_bits),
+            GateRequirement::Captcha { provider } => format!("CAPTCHA ({:?})", provider),
+        }
+    );
+
+    // A deny list that can't be read must not leave transfers unscreened
+    let screening = Screener::from_env().map_err(|e| anyhow::anyhow!("Screening configuration: {}", e))?;
+    info!(
+        "  Compliance screening: {}",
+        if screening.is_enabled() {
+            format!(
+                "(enabled - {} deny-list entries{})",
+                screening.deny_list_len(),
+                if screening.has_api() { ", screening API" } else { "" }
+            )
+        } else {
+            "(disabled)".to_string()
+        }
+    );
+
+    // Signing for the wrong network or package would hand out payloads meant for another chain
+    let chain = ChainConfig::from_env().map_err(|e| anyhow::anyhow!("Chain configuration: {}", e))?;
+    info!(
+        "  Chain: {} (chain ID {}), package {}, RPC {}",
+        chain.network,
+        chain.chain_id.as_deref().unwrap_or("unknown"),
+        chain.package_id(),
+        chain.rpc_url
+    );
+    // A snapshot key that doesn't parse would lose every snapshot, so refuse to start
+    let snapshots = SnapshotConfig::from_env().map_err(|e| anyhow::anyhow!("Snapshot configuration: {}", e))?;
+    info!(
+        "  State snapshots: {}",
+        match &snapshots {
+            Some(config) => format!(
+                "every {}s to {} (restored at boot if under {}s old)",
+                config.interval.as_secs(),
+                config.url(),
+                config.max_age.as_secs()
+            ),
+            None => "(disabled - challenges, cooldowns and replay checks start over on restart)".to_string(),
+        }
+    );
+
+    let sui_rpc_url = chain.rpc_url.clone();
+    install_chain(chain);
+    // Read after the chain is installed: the call goes to its package and fullnode
+    let transfer_simulator =
+        TransferSimulator::from_env().map_err(|e| anyhow::anyhow!("Transfer simulation configuration: {}", e))?;
+    info!(
+        "  Transfer dry runs: {}",
+        if transfer_simulator.is_enabled() { "(enabled - on request)" } else { "(disabled - RAM_ENCLAVE_ID unset)" }
+    );
     let state = Arc::new(AppState {
         eph_kp,
-        sui_rpc_url: std::env::var("SUI_RPC_URL").unwrap_or_else(|_| "https://fullnode.testnet.sui.io:443".to_string()),
+        sui_rpc_url: sui_rpc_url.clone(),
         openrouter_api_key,
         hume_api_key,
+        diagnostics_secret,
+        bioauth_diagnostics: DiagnosticsStore::new(Duration::from_secs(diagnostics_ttl_secs)),
+        bioauth_jobs: JobQueue::new(
+            bioauth_async,
+            bioauth_queue_capacity,
+            Duration::from_secs(nautilus_server::ram_app::DEFAULT_JOB_TTL_SECS),
+        ),
+        bioauth_locks: HandleLocks::new(),
+        bioauth_shadow: ShadowMode::new(bioauth_shadow, bioauth_shadow_threshold),
+        voice_baselines: BaselineStore::new(),
+        user_preferences: PreferenceStore::default(),
+        bioauth_challenges: ChallengeStore::new(
+            Duration::from_secs(nautilus_server::ram_app::DEFAULT_CHALLENGE_TTL_SECS),
+            tts_api_key,
+        ),
+        bioauth_attempts: AttemptTracker::new(),
+        bioauth_decoy,
+        bioauth_replays: ReplayGuard::new(Duration::from_secs(bioauth_replay_window_days * 86_400)),
+        coin_registry: CoinRegistry::new(chain.network),
+        upstream_costs: CostTracker::new(cost_limits),
+        zklogin: ZkLoginVerifier::new(sui_rpc_url),
+        payload_versions,
+        analysis_cache,
+        bioauth_gray_zone,
+        registration_gate,
+        screening,
+        transfer_simulator,
     });
+    // Resume in-flight challenges, cooldowns and replay checks before serving
+    if let Some(snapshots) = snapshots {
+        restore_snapshot(&state, &snapshots).await;
+        spawn_snapshots(state.clone(), snapshots);
+    }
+    start_bio_auth_workers(state.clone(), bioauth_workers);
 
     // Define your own restricted CORS policy here if needed.
     let cors = CorsLayer::new().allow_methods(Any).allow_headers(Any).allow_origin(Any);
 
+    // Uploads that would overload the enclave are shed with a 429
+    let analysis_routes = shed_analysis_load(
+        Router::new()
+            .route("/bio_auth", post(process_bio_auth))
+            .route("/bio_auth/stream", post(process_bio_auth_stream)),
+        state.clone(),
+        analysis_limits,
+    );
+
     let app = Router::new()
         .route("/", get(ping))
         .route("/get_attestation", get(get_attestation))
+        .route("/public_key", get(get_public_key))
+        .route("/meta/intents", get(get_intents))
         // RAM endpoints
         .route("/create_wallet", post(process_create_wallet))
+        .route("/create_wallet/gate", get(get_registration_gate))
         .route("/link_address", post(process_link_address))
-        .route("/bio_auth", post(process_bio_auth))
+        .route("/link_zklogin", post(process_link_zklogin))
+        .route("/unlink_address", post(process_unlink_address))
+        .merge(analysis_routes)
+        .route("/typed_auth", post(process_typed_auth))
+        .route("/bio_auth/enroll", post(process_enroll_voice))
+        .route("/bio_auth/challenge", post(process_bio_auth_challenge))
+        .route("/bio_auth/challenge_audio/:challenge_id", get(get_challenge_audio))
         .route("/transfer", post(process_transfer))
         .route("/withdraw", post(process_withdraw))
+        .route("/set_limit", post(process_set_limit))
+        .route("/freeze_wallet", post(process_freeze_wallet))
+        .route("/unfreeze/challenge", post(process_unfreeze_challenge))
+        .route("/request_unfreeze", post(process_request_unfreeze))
+        .route("/set_beneficiary", post(process_set_beneficiary))
+        .route("/prove_deposit", post(process_prove_deposit))
+        .route("/preferences", post(process_update_preferences))
+        // Privileged server-to-server channel
+        .route("/bio_auth/diagnostics/:request_id", get(get_bio_auth_diagnostics))
+        .route("/bio_auth/baseline/:handle", delete(reset_voice_baseline))
+        // Queued BioAuth polling and metrics
+        .route("/bio_auth/queue", get(get_bio_auth_queue))
+        .route("/bio_auth/shadow", get(get_bio_auth_shadow))
+        .route("/bio_auth/:job_id", get(get_bio_auth_job))
+        .route("/metrics", get(get_metrics))
         // Health check
         .route("/health_check", get(health_check))
         .with_state(state)
+        .layer(middleware::from_fn_with_state(request_auth, require_signed_request))
+        .layer(middleware::from_fn_with_state(peer_acl, enforce_peer_acl))
+        .layer(middleware::from_fn(assign_request_lang))
+        .layer(middleware::from_fn(assign_request_id))
         .layer(cors);
 
     let port = std::env::var("PORT").unwrap_or_else(|_| "3000".to_string());
     let listener = tokio::net::TcpListener::bind(format!("0.0.0.0:{}", port)).await?;
     info!("RAM Server listening on {}", listener.local_addr().unwrap());
     info!("Endpoints:");
+    info!("  GET  /public_key    - Current signing public key (hex)");
+    info!("  GET  /meta/intents  - Intent codes, payload schemas and result code meanings");
     info!("  POST /create_wallet - Create a new RAM wallet");
-    info!("  POST /link_address  - Link Sui address to wallet");
+    info!("  GET  /create_wallet/gate - Proof of work or CAPTCHA wallet creation requires");
+    info!("  POST /link_address  - Link a labeled Sui address to wallet");
+    info!("  POST /link_zklogin  - Link a zkLogin (Google/Apple) address with a verified proof");
+    info!("  POST /unlink_address - Unlink a Sui address from wallet");
     info!("  POST /bio_auth      - Voice authentication with duress detection");
+    info!("  POST /bio_auth/stream - Voice authentication with stage progress (SSE)");
+    info!("  POST /typed_auth    - Typed confirmation with keystroke checks (accessibility)");
     info!("  POST /transfer      - Sign a transfer between wallets");
     info!("  POST /withdraw      - Sign a withdrawal from wallet");
+    info!("  POST /set_limit     - Sign a daily spending limit confirmed by a calm voice");
+    info!("  POST /freeze_wallet - Sign an instant self-service freeze");
+    info!("  POST /unfreeze/challenge - Issue the one-time phrase for an unfreeze");
+    info!("  POST /request_unfreeze - Sign a delayed unfreeze confirmed by voice");
+    info!("  POST /set_beneficiary - Sign an inactivity beneficiary (or its removal)");
+    info!("  POST /prove_deposit - Sign a deposit ID as belonging to a handle, for exchanges");
+    info!("  POST /preferences   - Replace a handle's policy preferences after a successful bio-auth");
+    info!("  GET  /bio_auth/:job_id  - Poll a queued voice authentication job");
+    info!("  GET  /bio_auth/queue    - BioAuth queue depth metrics");
+    info!("  GET  /metrics           - Upstream API usage and spend against daily budgets");
+    info!("  GET  /bio_auth/diagnostics/:request_id - BioAuth diagnostics (privileged)");
     
-    axum::serve(listener, app.into_make_service())
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
         .await
         .map_err(|e| anyhow::anyhow!("Server error: {}", e))
 }
@@ -19,8 +19,9 @@ use std::fmt::Debug;
 use std::sync::Arc;
 use std::time::Duration;
 use tracing::info;
+use zeroize::Zeroizing;
 
-use fastcrypto::ed25519::Ed25519KeyPair;
+use fastcrypto::ed25519::{Ed25519KeyPair, Ed25519PrivateKey};
 /// ==== COMMON TYPES ====
 /// Intent message wrapper struct containing the intent scope and timestamp.
 /// This standardizes the serialized payload for signing.
@@ -41,7 +42,12 @@ pub enum IntentScope {
     TransferCoin = 2,     // TRANSFER_INTENT
     TransferNft = 3,      // BIOAUTH_INTENT
     UpdateHandle = 4,     // WITHDRAW_INTENT
-    BioAuth = 5,          // Reserved
+    UnlinkWallet = 5,     // UNLINK_ADDRESS_INTENT
+    SetLimit = 6,         // SET_LIMIT_INTENT
+    Freeze = 7,           // FREEZE_INTENT
+    Unfreeze = 8,         // UNFREEZE_INTENT
+    Beneficiary = 9,      // BENEFICIARY_INTENT
+    DepositProof = 10,    // DEPOSIT_PROOF_INTENT
 }
 
 impl<T: Serialize + Debug> IntentMessage<T> {
@@ -65,6 +71,9 @@ pub struct ProcessedDataResponse<T> {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ProcessDataRequest<T> {
     pub payload: T,
+    /// Payload layout to sign (None = the enclave's default)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payload_version: Option<u8>,
 }
 
 /// Sign the bcs bytes of the the payload with keypair.
@@ -130,6 +139,33 @@ pub async fn get_attestation(
     }
 }
 
+/// Response for get public key.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetPublicKeyResponse {
+    /// Hex encoded public key the enclave signs with.
+    pub public_key: String,
+}
+
+/// Endpoint that returns the enclave's current public key, for integration
+/// environments that verify signatures without parsing the attestation
+/// document. Production verifiers should take the key from the attestation.
+pub async fn get_public_key(State(state): State<Arc<AppState>>) -> Json<GetPublicKeyResponse> {
+    Json(GetPublicKeyResponse {
+        public_key: Hex::encode(state.eph_kp.public().as_bytes()),
+    })
+}
+
+/// Load a fixed keypair from a hex encoded 32-byte Ed25519 private key
+/// (`DEV_FIXED_KEY`), so integration environments sign with a known key.
+pub fn keypair_from_hex(private_key_hex: &str) -> Result<Ed25519KeyPair, EnclaveError> {
+    let bytes = Hex::decode(private_key_hex.trim().trim_start_matches("0x"))
+        .map(Zeroizing::new)
+        .map_err(|e| EnclaveError::GenericError(format!("Invalid private key hex: {}", e)))?;
+    let sk = Ed25519PrivateKey::from_bytes(&bytes)
+        .map_err(|_| EnclaveError::GenericError("Private key must be 32 bytes".to_string()))?;
+    Ok(Ed25519KeyPair::from(sk))
+}
+
 /// Health check response.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct HealthCheckResponse {
@@ -137,6 +173,9 @@ pub struct HealthCheckResponse {
     pub pk: String,
     /// Status of endpoint connectivity checks
     pub endpoints_status: HashMap<String, bool>,
+    /// Network, chain ID and RAM package the enclave signs for
+    #[cfg(feature = "ram")]
+    pub chain: crate::ram_app::ChainInfo,
 }
 
 /// Endpoint that health checks the enclave connectivity to all
@@ -222,5 +261,7 @@ pub async fn health_check(
     Ok(Json(HealthCheckResponse {
         pk: Hex::encode(pk.as_bytes()),
         endpoints_status,
+        #[cfg(feature = "ram")]
+        chain: crate::ram_app::current_chain().info(),
     }))
 }
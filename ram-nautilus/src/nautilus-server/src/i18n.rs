@@ -0,0 +1,228 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! Message catalogs for user-facing text
+//!
+//! Error messages and challenge phrases are written in English where they
+//! are raised. The catalog maps each English message (its format string,
+//! with `{}` for every argument) to its translations, and error bodies are
+//! translated as they are rendered, into the language the request asked for
+//! in `Accept-Language` (which the backend forwards). Logs stay in English,
+//! and machine-readable `code`s and field paths never change. A message the
+//! catalog doesn't know is sent as written.
+
+use axum::extract::Request;
+use axum::http::header;
+use axum::middleware::Next;
+use axum::response::Response;
+
+/// Languages user-facing text is available in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Lang {
+    #[default]
+    En,
+    Vi,
+}
+
+impl Lang {
+    pub const ALL: [Lang; 2] = [Lang::En, Lang::Vi];
+
+    pub fn tag(self) -> &'static str {
+        match self {
+            Lang::En => "en",
+            Lang::Vi => "vi",
+        }
+    }
+
+    /// Language of a BCP 47 tag like `vi-VN`, if there is a catalog for it
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        let language = tag.trim().split(['-', '_']).next().unwrap_or_default();
+        Self::ALL.into_iter().find(|lang| language.eq_ignore_ascii_case(lang.tag()))
+    }
+
+    /// Preferred language of an `Accept-Language` header: the supported one
+    /// with the highest weight, English if there is none
+    pub fn from_accept_language(header: &str) -> Self {
+        let mut best: Option<(Lang, f32)> = None;
+        for range in header.split(',') {
+            let mut parts = range.split(';');
+            let Some(lang) = parts.next().and_then(Self::from_tag) else {
+                continue;
+            };
+            let weight = parts
+                .find_map(|p| p.trim().strip_prefix("q="))
+                .map_or(Some(1.0), |q| q.trim().parse::<f32>().ok())
+                .unwrap_or(0.0);
+            if weight > 0.0 && !matches!(best, Some((_, w)) if w >= weight) {
+                best = Some((lang, weight));
+            }
+        }
+        best.map(|(lang, _)| lang).unwrap_or_default()
+    }
+}
+
+tokio::task_local! {
+    static REQUEST_LANG: Lang;
+}
+
+/// Language of the request being handled, if `assign_request_lang` runs in front of it
+pub fn current_lang() -> Lang {
+    REQUEST_LANG.try_with(|lang| *lang).unwrap_or_default()
+}
+
+/// Middleware recording the language a request's `Accept-Language` prefers
+pub async fn assign_request_lang(request: Request, next: Next) -> Response {
+    let lang = request
+        .headers()
+        .get(header::ACCEPT_LANGUAGE)
+        .and_then(|v| v.to_str().ok())
+        .map(Lang::from_accept_language)
+        .unwrap_or_default();
+    REQUEST_LANG.scope(lang, next.run(request)).await
+}
+
+/// English format strings and their Vietnamese translations, with the
+/// arguments in the same order
+const CATALOG: &[(&str, &str)] = &[
+    // Error envelopes
+    ("Request validation failed", "Yêu cầu không hợp lệ"),
+    ("Too many failed confirmations for '{}', try again in {}s", "Quá nhiều lần xác nhận thất bại cho '{}', hãy thử lại sau {} giây"),
+    (
+        "This recording was already used to authorize a transfer for '{}'; record a new confirmation",
+        "Bản ghi âm này đã được dùng để xác nhận một giao dịch cho '{}'; hãy ghi âm lời xác nhận mới",
+    ),
+    ("A bio-auth for handle '{}' is already in progress", "Một lần xác thực giọng nói cho '{}' đang được xử lý"),
+    ("BioAuth queue is full, retry later", "Hàng đợi xác thực đang đầy, hãy thử lại sau"),
+    ("{} is blocked by compliance screening", "{} bị chặn bởi kiểm tra tuân thủ"),
+    ("Compliance screening is unavailable; try again later", "Kiểm tra tuân thủ tạm thời không khả dụng; hãy thử lại sau"),
+    ("Unfreeze was not confirmed", "Yêu cầu mở đóng băng chưa được xác nhận"),
+    ("Daily limit was not confirmed", "Hạn mức hằng ngày chưa được xác nhận"),
+    (
+        "The recording couldn't be analyzed with confidence ({}); record the confirmation again",
+        "Không thể phân tích chắc chắn bản ghi âm ({}); hãy ghi âm lại lời xác nhận",
+    ),
+    ("Creating a wallet needs a proof of work of {} bits in pow_nonce", "Tạo ví cần bằng chứng công việc {} bit trong pow_nonce"),
+    ("Creating a wallet needs a solved CAPTCHA in captcha_token", "Tạo ví cần một CAPTCHA đã giải trong captcha_token"),
+    ("The CAPTCHA couldn't be verified; try again later", "Không thể xác minh CAPTCHA; hãy thử lại sau"),
+    ("The transfer would fail on-chain ({})", "Giao dịch chuyển sẽ thất bại trên chuỗi ({})"),
+    ("Could not simulate the transfer", "Không thể mô phỏng giao dịch chuyển"),
+    ("No challenge '{}'", "Không tìm thấy mã thử thách '{}'"),
+    ("No unfreeze challenge '{}'", "Không tìm thấy mã thử thách mở đóng băng '{}'"),
+    ("Challenge audio is disabled", "Tính năng phát âm thanh thử thách đã bị tắt"),
+    ("No voice baseline for '{}'", "Chưa có mẫu giọng nói cho '{}'"),
+    ("Voice baseline for '{}' is already enrolled", "Mẫu giọng nói cho '{}' đã được đăng ký"),
+    ("zkLogin session expired at epoch {} (now {}); sign in again", "Phiên zkLogin đã hết hạn ở epoch {} (hiện tại {}); hãy đăng nhập lại"),
+    // Field errors
+    ("is required", "là bắt buộc"),
+    ("must be at most {} bytes", "tối đa {} byte"),
+    ("must be at most {} characters", "tối đa {} ký tự"),
+    ("must have at most {} entries", "tối đa {} mục"),
+    ("may only contain letters, digits and _ - . @ +", "chỉ được chứa chữ cái, chữ số và _ - . @ +"),
+    ("must be greater than 0", "phải lớn hơn 0"),
+    ("must be {} to {} base64 characters (got {})", "phải có từ {} đến {} ký tự base64 (nhận được {})"),
+    ("must be padded standard base64", "phải là base64 chuẩn có đệm"),
+    ("must be a 32-byte hex address", "phải là địa chỉ hex 32 byte"),
+    ("must be a hex address of at least {} digits", "phải là địa chỉ hex có ít nhất {} chữ số"),
+    ("must be a BCP 47 tag like de-DE", "phải là thẻ BCP 47, ví dụ vi-VN"),
+    ("must not be before down_ms", "không được trước down_ms"),
+    ("must be another wallet", "phải là một ví khác"),
+    ("must be between {} and {} days", "phải từ {} đến {} ngày"),
+    ("must be 1, 2 or 3", "phải là 1, 2 hoặc 3"),
+    // Challenge phrases
+    ("I confirm sending {}", "Tôi xác nhận gửi {}"),
+    (" to the address ending in {}", " đến địa chỉ kết thúc bằng {}"),
+    ("Unfreeze wallet {}, code ", "Mở đóng băng ví {}, mã "),
+];
+
+/// The translation of `english` (a format string from the catalog)
+fn translation(lang: Lang, english: &'static str) -> &'static str {
+    match lang {
+        Lang::En => english,
+        Lang::Vi => CATALOG.iter().find(|(en, _)| *en == english).map_or(english, |(_, vi)| vi),
+    }
+}
+
+/// Fill each `{}` of `template` with the next argument
+fn fill(template: &str, args: &[&str]) -> String {
+    let mut pieces = template.split("{}");
+    let mut text = pieces.next().unwrap_or_default().to_string();
+    for (i, piece) in pieces.enumerate() {
+        text.push_str(args.get(i).copied().unwrap_or_default());
+        text.push_str(piece);
+    }
+    text
+}
+
+/// `english` (a format string from the catalog) in `lang`, with
+/// its arguments filled in
+pub fn format(lang: Lang, english: &'static str, args: &[&str]) -> String {
+    fill(translation(lang, english), args)
+}
+
+/// The arguments `message` was formatted with, if it was formatted from `template`
+fn match_template<'a>(template: &str, message: &'a str) -> Option<Vec<&'a str>> {
+    let mut pieces = template.split("{}");
+    let mut rest = message.strip_prefix(pieces.next().unwrap_or_default())?;
+    let pieces: Vec<&str> = pieces.collect();
+    let mut args = Vec::with_capacity(pieces.len());
+    for (i, piece) in pieces.iter().enumerate() {
+        let end = if i + 1 == pieces.len() {
+            rest.strip_suffix(piece).map(str::len)?
+        } else if piece.is_empty() {
+            return None;
+        } else {
+            rest.find(piece)?
+        };
+        args.push(&rest[..end]);
+        rest = &rest[end + piece.len()..];
+    }
+    rest.is_empty().then_some(args)
+}
+
+/// An English message in `lang`, if the catalog has its format string;
+/// unchanged otherwise
+pub fn translate(lang: Lang, message: &str) -> String {
+    if lang == Lang::En {
+        return message.to_string();
+    }
+    CATALOG
+        .iter()
+        .find_map(|&(english, _)| match_template(english, message).map(|args| format(lang, english, &args)))
+        .unwrap_or_else(|| message.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accept_language_picks_the_preferred_supported_language() {
+        assert_eq!(Lang::from_accept_language("vi-VN,vi;q=0.9,en-US;q=0.8"), Lang::Vi);
+        assert_eq!(Lang::from_accept_language("fr-FR, vi;q=0.5, en;q=0.7"), Lang::En);
+        assert_eq!(Lang::from_accept_language("en;q=0, vi;q=0.1"), Lang::Vi);
+        assert_eq!(Lang::from_accept_language("de-DE"), Lang::En);
+        assert_eq!(Lang::from_accept_language(""), Lang::En);
+    }
+
+    #[test]
+    fn test_formatted_messages_are_translated() {
+        assert_eq!(
+            translate(Lang::Vi, "Too many failed confirmations for 'alice', try again in 30s"),
+            "Quá nhiều lần xác nhận thất bại cho 'alice', hãy thử lại sau 30 giây"
+        );
+        assert_eq!(translate(Lang::Vi, "must be at most 64 bytes"), "tối đa 64 byte");
+        assert_eq!(translate(Lang::Vi, "is required"), "là bắt buộc");
+        assert_eq!(translate(Lang::En, "is required"), "is required");
+        // Unknown messages, and known ones with something appended, stay as written
+        assert_eq!(translate(Lang::Vi, "Invalid request signature"), "Invalid request signature");
+        assert_eq!(translate(Lang::Vi, "is required!"), "is required!");
+        assert_eq!(format(Lang::Vi, "I confirm sending {}", &["5 SUI"]), "Tôi xác nhận gửi 5 SUI");
+    }
+
+    #[test]
+    fn test_catalog_translations_keep_their_arguments() {
+        for (english, vietnamese) in CATALOG {
+            assert_eq!(english.matches("{}").count(), vietnamese.matches("{}").count(), "{}", english);
+        }
+    }
+}
@@ -1,13 +1,17 @@
 // Copyright (c), Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use axum::http::StatusCode;
+use axum::extract::Request;
+use axum::http::{HeaderValue, StatusCode};
+use axum::middleware::Next;
 use axum::response::IntoResponse;
 use axum::response::Response;
 use axum::Json;
 use fastcrypto::ed25519::Ed25519KeyPair;
-use serde_json::json;
+use serde::Serialize;
+use serde_json::{json, Value};
 use std::fmt;
+use zeroize::Zeroizing;
 
 mod apps {
     #[cfg(feature = "ram")]
@@ -21,6 +25,7 @@ pub mod ram_app {
 }
 
 pub mod common;
+pub mod i18n;
 
 /// App state, at minimum needs to maintain the ephemeral keypair.
 pub struct AppState {
@@ -28,37 +33,313 @@ pub struct AppState {
     pub eph_kp: Ed25519KeyPair,
     /// Sui RPC URL for blockchain queries
     pub sui_rpc_url: String,
-    /// OpenRouter API key for GPT-4o audio processing
-    pub openrouter_api_key: String,
+    /// OpenRouter API key for GPT-4o audio processing (wiped on drop, like the other secrets)
+    pub openrouter_api_key: Zeroizing<String>,
     /// Hume AI API key for emotion/stress detection
-    pub hume_api_key: String,
+    pub hume_api_key: Zeroizing<String>,
+    /// Shared secret for the privileged backend diagnostics channel (empty = disabled)
+    pub diagnostics_secret: Zeroizing<String>,
+    /// Short-term store of BioAuth diagnostics for the privileged channel
+    #[cfg(feature = "ram")]
+    pub bioauth_diagnostics: ram_app::DiagnosticsStore,
+    /// Bounded queue for async BioAuth analysis (queued mode)
+    #[cfg(feature = "ram")]
+    pub bioauth_jobs: ram_app::JobQueue,
+    /// Per-handle locks so concurrent bio-auths for one handle can't both be signed
+    #[cfg(feature = "ram")]
+    pub bioauth_locks: ram_app::HandleLocks,
+    /// Shadow mode: duress verdicts are recorded but not signed
+    #[cfg(feature = "ram")]
+    pub bioauth_shadow: ram_app::ShadowMode,
+    /// Enrolled per-handle voice baselines (enclave memory only)
+    #[cfg(feature = "ram")]
+    pub voice_baselines: ram_app::BaselineStore,
+    /// Sealed per-handle policy preferences (enclave memory only)
+    #[cfg(feature = "ram")]
+    pub user_preferences: ram_app::PreferenceStore,
+    /// Issued challenge phrases and their cached TTS audio
+    #[cfg(feature = "ram")]
+    pub bioauth_challenges: ram_app::ChallengeStore,
+    /// Consecutive failed bio-auths per handle, for cooldowns
+    #[cfg(feature = "ram")]
+    pub bioauth_attempts: ram_app::AttemptTracker,
+    /// Decoy mode: duress is signed as OK with the decoy flag (the contract still locks)
+    #[cfg(feature = "ram")]
+    pub bioauth_decoy: bool,
+    /// Fingerprints of recently accepted bio-auth clips, to reject replays
+    #[cfg(feature = "ram")]
+    pub bioauth_replays: ram_app::ReplayGuard,
+    /// Symbol and decimals of coin types, looked up over `sui_rpc_url`
+    #[cfg(feature = "ram")]
+    pub coin_registry: ram_app::CoinRegistry,
+    /// OpenRouter/Hume spend against the daily budget caps
+    #[cfg(feature = "ram")]
+    pub upstream_costs: ram_app::CostTracker,
+    /// zkLogin signature checks, with cached provider keys and epoch
+    #[cfg(feature = "ram")]
+    pub zklogin: ram_app::ZkLoginVerifier,
+    /// Payload layouts signed during the v1 → v2 migration window
+    #[cfg(feature = "ram")]
+    pub payload_versions: ram_app::PayloadVersions,
+    /// Recent bio-auth analyses, so a clip sent twice isn't paid for twice
+    #[cfg(feature = "ram")]
+    pub analysis_cache: ram_app::AnalysisCache,
+    /// Stress scores re-checked by a second pass, and how often they occur
+    #[cfg(feature = "ram")]
+    pub bioauth_gray_zone: ram_app::GrayZone,
+    /// Proof of work or CAPTCHA wallet creations must pass
+    #[cfg(feature = "ram")]
+    pub registration_gate: ram_app::RegistrationGate,
+    /// Deny-list / sanctions screening of transfer and withdrawal subjects
+    #[cfg(feature = "ram")]
+    pub screening: ram_app::Screener,
+    /// Dry runs of transfers that ask for one, before they are answered
+    #[cfg(feature = "ram")]
+    pub transfer_simulator: ram_app::TransferSimulator,
 }
 
 /// Implement IntoResponse for EnclaveError.
 impl IntoResponse for EnclaveError {
     fn into_response(self) -> Response {
-        let (status, error_message) = match self {
-            EnclaveError::GenericError(e) => (StatusCode::BAD_REQUEST, e),
-        };
-        let body = Json(json!({
-            "error": error_message,
-        }));
-        (status, body).into_response()
+        let status = self.status();
+        (status, Json(self.into_body(current_request_id()).localized(i18n::current_lang()))).into_response()
+    }
+}
+
+/// Machine-readable code of an `ErrorBody`
+/// Must match `ram_sdk::ErrorCode`, which the backend and clients use
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    BadRequest,
+    ValidationFailed,
+    Unauthorized,
+    Forbidden,
+    PeerNotAllowed,
+    NotFound,
+    Conflict,
+    ReplayDetected,
+    RateLimited,
+    ComplianceBlocked,
+    AnalysisInconclusive,
+    HumanCheckFailed,
+    SimulationFailed,
+    Internal,
+}
+
+impl ErrorCode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::BadRequest => "bad_request",
+            ErrorCode::ValidationFailed => "validation_failed",
+            ErrorCode::Unauthorized => "unauthorized",
+            ErrorCode::Forbidden => "forbidden",
+            ErrorCode::PeerNotAllowed => "peer_not_allowed",
+            ErrorCode::NotFound => "not_found",
+            ErrorCode::Conflict => "conflict",
+            ErrorCode::ReplayDetected => "replay_detected",
+            ErrorCode::RateLimited => "rate_limited",
+            ErrorCode::ComplianceBlocked => "compliance_blocked",
+            ErrorCode::AnalysisInconclusive => "analysis_inconclusive",
+            ErrorCode::HumanCheckFailed => "human_check_failed",
+            ErrorCode::SimulationFailed => "simulation_failed",
+            ErrorCode::Internal => "internal",
+        }
+    }
+}
+
+impl Serialize for ErrorCode {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
     }
 }
 
+/// Body of every error response, the same envelope the backend answers with:
+/// `{ "code", "message", "details", "request_id" }`
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorBody {
+    pub code: ErrorCode,
+    pub message: String,
+    /// Code-specific context, e.g. `fields` for `validation_failed`
+    pub details: Option<Value>,
+    pub request_id: Option<String>,
+}
+
+/// Header carrying the request ID, set by the backend or assigned here
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// ID of the request being handled, if `assign_request_id` runs in front of it
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(|id| id.clone()).ok()
+}
+
+/// Middleware giving each request an ID: the backend's `x-request-id` if it
+/// sent one, a fresh one otherwise. Error bodies quote it and every response
+/// echoes it, so a failure can be found in both services' logs.
+pub async fn assign_request_id(request: Request, next: Next) -> Response {
+    let id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .filter(|v| !v.is_empty() && v.len() <= 128)
+        .map(str::to_string)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let mut response = REQUEST_ID.scope(id.clone(), next.run(request)).await;
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
 /// Enclave errors enum.
 #[derive(Debug)]
 pub enum EnclaveError {
     GenericError(String),
+    Unauthorized(String),
+    Forbidden(String),
+    NotFound(String),
+    Conflict(String),
+    TooManyRequests(String),
+    ReplayDetected(String),
+    /// Compliance screening refused the request's subject, answered as a 451
+    ComplianceBlocked(String),
+    /// The voice analysis wasn't sure enough to sign a verdict; the user should record again
+    Inconclusive(String),
+    /// Wallet creation lacked the proof of work or CAPTCHA the registration gate asks for
+    HumanCheckFailed(String),
+    /// A transfer's dry run says the signed call would abort on-chain, answered as a 422
+    SimulationFailed(SimulationFailure),
+    /// Request fields that failed validation, answered as a 422 listing each one
+    InvalidRequest(Vec<FieldError>),
+}
+
+/// One request field that failed validation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FieldError {
+    /// JSON path of the field, e.g. `payload.handle`
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self { field: field.into(), message: message.into() }
+    }
+}
+
+/// Why a transfer's dry run would fail on-chain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SimulationFailure {
+    /// Short reason, e.g. `wallet_locked` or `insufficient_balance`
+    pub reason: String,
+    /// Move abort code, if the call aborted
+    pub abort_code: Option<u64>,
+    /// The fullnode's error, as it reported it
+    pub error: String,
 }
 
 impl fmt::Display for EnclaveError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             EnclaveError::GenericError(e) => write!(f, "{}", e),
+            EnclaveError::Unauthorized(e) => write!(f, "Unauthorized: {}", e),
+            EnclaveError::Forbidden(e) => write!(f, "Forbidden: {}", e),
+            EnclaveError::NotFound(e) => write!(f, "Not found: {}", e),
+            EnclaveError::Conflict(e) => write!(f, "Conflict: {}", e),
+            EnclaveError::TooManyRequests(e) => write!(f, "Too many requests: {}", e),
+            EnclaveError::ReplayDetected(e) => write!(f, "Replay detected: {}", e),
+            EnclaveError::ComplianceBlocked(e) => write!(f, "Compliance blocked: {}", e),
+            EnclaveError::Inconclusive(e) => write!(f, "Inconclusive: {}", e),
+            EnclaveError::HumanCheckFailed(e) => write!(f, "Human check failed: {}", e),
+            EnclaveError::SimulationFailed(failure) => write!(f, "Simulation failed: {}", failure.error),
+            EnclaveError::InvalidRequest(fields) => {
+                write!(f, "Invalid request:")?;
+                for (i, error) in fields.iter().enumerate() {
+                    let sep = if i == 0 { " " } else { "; " };
+                    write!(f, "{}{}: {}", sep, error.field, error.message)?;
+                }
+                Ok(())
+            }
         }
     }
 }
 
 impl std::error::Error for EnclaveError {}
+
+impl EnclaveError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            EnclaveError::GenericError(_) => StatusCode::BAD_REQUEST,
+            EnclaveError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            EnclaveError::Forbidden(_) | EnclaveError::HumanCheckFailed(_) => StatusCode::FORBIDDEN,
+            EnclaveError::NotFound(_) => StatusCode::NOT_FOUND,
+            EnclaveError::Conflict(_) => StatusCode::CONFLICT,
+            EnclaveError::TooManyRequests(_) => StatusCode::TOO_MANY_REQUESTS,
+            EnclaveError::ComplianceBlocked(_) => StatusCode::UNAVAILABLE_FOR_LEGAL_REASONS,
+            EnclaveError::ReplayDetected(_)
+            | EnclaveError::InvalidRequest(_)
+            | EnclaveError::Inconclusive(_)
+            | EnclaveError::SimulationFailed(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        }
+    }
+
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            EnclaveError::GenericError(_) => ErrorCode::BadRequest,
+            EnclaveError::Unauthorized(_) => ErrorCode::Unauthorized,
+            EnclaveError::Forbidden(_) => ErrorCode::Forbidden,
+            EnclaveError::NotFound(_) => ErrorCode::NotFound,
+            EnclaveError::Conflict(_) => ErrorCode::Conflict,
+            EnclaveError::TooManyRequests(_) => ErrorCode::RateLimited,
+            EnclaveError::ReplayDetected(_) => ErrorCode::ReplayDetected,
+            EnclaveError::ComplianceBlocked(_) => ErrorCode::ComplianceBlocked,
+            EnclaveError::Inconclusive(_) => ErrorCode::AnalysisInconclusive,
+            EnclaveError::HumanCheckFailed(_) => ErrorCode::HumanCheckFailed,
+            EnclaveError::SimulationFailed(_) => ErrorCode::SimulationFailed,
+            EnclaveError::InvalidRequest(_) => ErrorCode::ValidationFailed,
+        }
+    }
+
+    /// The error's envelope; validation failures list their fields in
+    /// `details`, failed dry runs their reason and abort code
+    pub fn into_body(self, request_id: Option<String>) -> ErrorBody {
+        let code = self.code();
+        let (message, details) = match self {
+            EnclaveError::InvalidRequest(fields) => {
+                ("Request validation failed".to_string(), Some(json!({ "fields": fields })))
+            }
+            EnclaveError::SimulationFailed(failure) => (
+                format!("The transfer would fail on-chain ({})", failure.reason),
+                serde_json::to_value(failure).ok(),
+            ),
+            EnclaveError::GenericError(e)
+            | EnclaveError::Unauthorized(e)
+            | EnclaveError::Forbidden(e)
+            | EnclaveError::NotFound(e)
+            | EnclaveError::Conflict(e)
+            | EnclaveError::TooManyRequests(e)
+            | EnclaveError::ReplayDetected(e)
+            | EnclaveError::ComplianceBlocked(e)
+            | EnclaveError::Inconclusive(e)
+            | EnclaveError::HumanCheckFailed(e) => (e, None),
+        };
+        ErrorBody { code, message, details, request_id }
+    }
+}
+
+impl ErrorBody {
+    /// The body with its message, and its fields' messages, in `lang`
+    pub fn localized(mut self, lang: i18n::Lang) -> Self {
+        self.message = i18n::translate(lang, &self.message);
+        let fields = self.details.as_mut().and_then(|d| d.get_mut("fields")).and_then(Value::as_array_mut);
+        for field in fields.into_iter().flatten() {
+            if let Some(message) = field["message"].as_str() {
+                field["message"] = Value::String(i18n::translate(lang, message));
+            }
+        }
+        self
+    }
+}
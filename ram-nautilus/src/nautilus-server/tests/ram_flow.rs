@@ -0,0 +1,3270 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! End-to-end enclave flow against mock upstreams
+//!
+//! Spins up the RAM routes in-process with a wiremock-backed OpenRouter and
+//! Hume, then exercises create-wallet → link → bio-auth → transfer and checks
+//! every signature against the enclave's public key. Redirecting the
+//! upstreams needs the `mock` feature: `cargo test --features mock`.
+
+use axum::{routing::get, routing::post, Router};
+use fastcrypto::ed25519::{Ed25519KeyPair, Ed25519PublicKey, Ed25519Signature};
+use fastcrypto::encoding::{Encoding, Hex};
+use fastcrypto::traits::{KeyPair, ToFromBytes, VerifyingKey};
+use nautilus_server::common::{get_public_key, keypair_from_hex, IntentMessage, IntentScope};
+use nautilus_server::ram_app::*;
+use nautilus_server::i18n::assign_request_lang;
+use nautilus_server::{assign_request_id, AppState, EnclaveError};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use wiremock::matchers::{body_string_contains, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// Upstream endpoint overrides and settings are process-wide, so tests that set them run serially
+static UPSTREAM_ENV: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+/// 1s of a steady 150 Hz tone as a 16 kHz mono WAV (acoustically calm)
+fn calm_wav_base64() -> String {
+    use base64::{engine::general_purpose::STANDARD, Engine as _};
+    let sample_rate = 16_000u32;
+    let samples: Vec<i16> = (0..sample_rate)
+        .map(|i| {
+            let t = i as f64 / sample_rate as f64;
+            ((2.0 * std::f64::consts::PI * 150.0 * t).sin() * 0.5 * 32767.0) as i16
+        })
+        .collect();
+    let data_size = (samples.len() * 2) as u32;
+    let mut wav = Vec::new();
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_size).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&sample_rate.to_le_bytes());
+    wav.extend_from_slice(&(sample_rate * 2).to_le_bytes());
+    wav.extend_from_slice(&2u16.to_le_bytes());
+    wav.extend_from_slice(&16u16.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_size.to_le_bytes());
+    for s in samples {
+        wav.extend_from_slice(&s.to_le_bytes());
+    }
+    STANDARD.encode(wav)
+}
+
+/// OpenRouter chat completion whose content is the analyzer JSON
+fn openrouter_reply(stress_level: u8, amount: f64) -> ResponseTemplate {
+    let content = json!({
+        "transcript": format!("I confirm sending {} SUI", amount),
+        "stress_level": stress_level,
+        "amount": amount,
+    })
+    .to_string();
+    ResponseTemplate::new(200).set_body_json(json!({
+        "choices": [{ "message": { "content": content } }]
+    }))
+}
+
+fn verify<T: Serialize + std::fmt::Debug>(
+    pk: &Ed25519PublicKey,
+    payload: T,
+    timestamp_ms: u64,
+    intent: IntentScope,
+    signature: &str,
+) -> bool {
+    let msg = bcs::to_bytes(&IntentMessage::new(payload, timestamp_ms, intent)).unwrap();
+    let sig = Ed25519Signature::from_bytes(&Hex::decode(signature).unwrap()).unwrap();
+    pk.verify(&msg, &sig).is_ok()
+}
+
+async fn spawn_enclave(state: Arc<AppState>) -> String {
+    let app = Router::new()
+        .route("/public_key", get(get_public_key))
+        .route("/create_wallet", post(process_create_wallet))
+        .route("/create_wallet/gate", get(get_registration_gate))
+        .route("/link_address", post(process_link_address))
+        .route("/link_zklogin", post(process_link_zklogin))
+        .route("/unlink_address", post(process_unlink_address))
+        .route("/bio_auth", post(process_bio_auth))
+        .route("/bio_auth/stream", post(process_bio_auth_stream))
+        .route("/typed_auth", post(process_typed_auth))
+        .route("/bio_auth/enroll", post(process_enroll_voice))
+        .route("/bio_auth/challenge", post(process_bio_auth_challenge))
+        .route("/bio_auth/challenge_audio/:challenge_id", get(get_challenge_audio))
+        .route("/transfer", post(process_transfer))
+        .route("/withdraw", post(process_withdraw))
+        .route("/set_limit", post(process_set_limit))
+        .route("/freeze_wallet", post(process_freeze_wallet))
+        .route("/unfreeze/challenge", post(process_unfreeze_challenge))
+        .route("/request_unfreeze", post(process_request_unfreeze))
+        .route("/set_beneficiary", post(process_set_beneficiary))
+        .route("/prove_deposit", post(process_prove_deposit))
+        .route("/preferences", post(process_update_preferences))
+        .route("/bio_auth/queue", get(get_bio_auth_queue))
+        .route("/bio_auth/shadow", get(get_bio_auth_shadow))
+        .route("/bio_auth/:job_id", get(get_bio_auth_job))
+        .route("/metrics", get(get_metrics))
+        .with_state(state)
+        .layer(axum::middleware::from_fn(assign_request_lang))
+        .layer(axum::middleware::from_fn(assign_request_id));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app.into_make_service()).await.unwrap();
+    });
+    format!("http://{}", addr)
+}
+
+/// Hume streaming stand-in: answers each message with `reply`, or never when
+/// it is None
+async fn spawn_hume_stream(reply: Option<Value>) -> String {
+    use futures_util::{SinkExt, StreamExt};
+    use tokio_tungstenite::tungstenite::Message;
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        while let Ok((tcp, _)) = listener.accept().await {
+            let reply = reply.clone();
+            tokio::spawn(async move {
+                let Ok(mut socket) = tokio_tungstenite::accept_async(tcp).await else {
+                    return;
+                };
+                while let Some(Ok(Message::Text(_))) = socket.next().await {
+                    if let Some(reply) = &reply {
+                        let _ = socket.send(Message::Text(reply.to_string())).await;
+                    }
+                }
+            });
+        }
+    });
+    format!("ws://{}", addr)
+}
+
+async fn call(client: &reqwest::Client, url: String, payload: Value) -> reqwest::Response {
+    client.post(url).json(&json!({ "payload": payload })).send().await.unwrap()
+}
+
+#[tokio::test]
+async fn test_full_flow_with_mock_upstreams() {
+    let _env = UPSTREAM_ENV.lock().await;
+    let openrouter = MockServer::start().await;
+    let hume = MockServer::start().await;
+    set_endpoint_override(Endpoint::OpenRouter, Some(format!("{}/chat", openrouter.uri())));
+    set_endpoint_override(Endpoint::Hume, Some(format!("{}/jobs", hume.uri())));
+    std::env::set_var("HUME_MODE", "batch");
+
+    // Hume is down: analysis must fall back to GPT-4o + DSP
+    Mock::given(method("POST"))
+        .and(path("/jobs"))
+        .respond_with(ResponseTemplate::new(503))
+        .mount(&hume)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/chat"))
+        .respond_with(openrouter_reply(10, 5.0))
+        .mount(&openrouter)
+        .await;
+
+    let eph_kp = Ed25519KeyPair::generate(&mut rand::thread_rng());
+    let pk = eph_kp.public().clone();
+    let state = Arc::new(AppState {
+        eph_kp,
+        sui_rpc_url: String::new(),
+        openrouter_api_key: "test-key".to_string().into(),
+        hume_api_key: "test-key".to_string().into(),
+        diagnostics_secret: String::new().into(),
+        bioauth_diagnostics: DiagnosticsStore::default(),
+        bioauth_jobs: JobQueue::default(),
+        bioauth_locks: HandleLocks::new(),
+        bioauth_shadow: ShadowMode::default(),
+        voice_baselines: BaselineStore::new(),
+        user_preferences: PreferenceStore::default(),
+        bioauth_challenges: ChallengeStore::new(std::time::Duration::from_secs(60), String::new()),
+        bioauth_attempts: AttemptTracker::new(),
+        bioauth_decoy: false,
+        bioauth_replays: ReplayGuard::default(),
+        coin_registry: CoinRegistry::default(),
+        upstream_costs: CostTracker::default(),
+        zklogin: ZkLoginVerifier::default(),
+        payload_versions: PayloadVersions::default(),
+        analysis_cache: AnalysisCache::default(),
+        screening: Screener::default(),
+        transfer_simulator: TransferSimulator::default(),
+        bioauth_gray_zone: GrayZone::default(),
+        registration_gate: RegistrationGate::default(),
+    });
+    let enclave = spawn_enclave(state.clone()).await;
+    let client = reqwest::Client::new();
+
+    // 1. Create wallet
+    let resp: CreateWalletResponse = call(&client, format!("{}/create_wallet", enclave), json!({ "handle": "alice" }))
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert!(verify(&pk, resp.payload, resp.timestamp_ms, IntentScope::ProcessData, &resp.signature));
+
+    // 2. Link address
+    let resp: LinkAddressResponse = call(
+        &client,
+        format!("{}/link_address", enclave),
+        json!({
+            "handle": "alice",
+            "wallet_address": format!("0x{}", "ab".repeat(32)),
+            "wallet_signature": "",
+            "message": "",
+            "label": "ledger",
+        }),
+    )
+    .await
+    .json()
+    .await
+    .unwrap();
+    assert_eq!(resp.payload.address, [0xab; 32]);
+    assert_eq!(resp.payload.label, b"ledger");
+    assert!(verify(&pk, resp.payload, resp.timestamp_ms, IntentScope::LinkWallet, &resp.signature));
+
+    // 2b. Unlink it again
+    let resp: UnlinkAddressResponse = call(
+        &client,
+        format!("{}/unlink_address", enclave),
+        json!({ "handle": "alice", "wallet_address": format!("0x{}", "ab".repeat(32)) }),
+    )
+    .await
+    .json()
+    .await
+    .unwrap();
+    assert_eq!(resp.intent, 5);
+    assert!(verify(&pk, resp.payload, resp.timestamp_ms, IntentScope::UnlinkWallet, &resp.signature));
+
+    // 3. Bio-auth: calm voice, matching amount → Ok
+    let bio_request = json!({
+        "handle": "alice",
+        "audio_base64": calm_wav_base64(),
+        "expected_amount": 5_000_000_000u64,
+        "coin_type": "0x2::sui::SUI",
+    });
+    let resp: BioAuthResponse = call(&client, format!("{}/bio_auth", enclave), bio_request.clone())
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(resp.payload.result, BioAuthResult::Ok as u8);
+    assert_eq!(state.bioauth_diagnostics.get(&resp.request_id).unwrap().result, "ok");
+    assert!(verify(&pk, resp.payload, resp.timestamp_ms, IntentScope::TransferNft, &resp.signature));
+
+    // 4. Transfer
+    let resp: TransferResponse = call(
+        &client,
+        format!("{}/transfer", enclave),
+        json!({
+            "from_handle": "alice",
+            "to_handle": "bob",
+            "amount": 5_000_000_000u64,
+            "coin_type": "0x2::sui::SUI",
+            "memo": "INV-2024-001 · café",
+        }),
+    )
+    .await
+    .json()
+    .await
+    .unwrap();
+    assert_eq!(resp.payload.to_handle, b"bob");
+    assert_eq!(resp.payload.memo, "INV-2024-001 · café".as_bytes());
+    assert!(verify(&pk, resp.payload, resp.timestamp_ms, IntentScope::TransferCoin, &resp.signature));
+
+    // 5. Stressed voice according to the LLM → Duress. Same clip, so skip
+    // the cached analysis of step 3
+    let mut bio_request = bio_request;
+    bio_request["skip_analysis_cache"] = json!(true);
+    openrouter.reset().await;
+    Mock::given(method("POST"))
+        .and(path("/chat"))
+        .respond_with(openrouter_reply(90, 5.0))
+        .mount(&openrouter)
+        .await;
+    let resp: BioAuthResponse = call(&client, format!("{}/bio_auth", enclave), bio_request.clone())
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(resp.payload.result, BioAuthResult::Duress as u8);
+
+    // 6. Wrong spoken amount → InvalidAmount
+    openrouter.reset().await;
+    Mock::given(method("POST"))
+        .and(path("/chat"))
+        .respond_with(openrouter_reply(10, 50.0))
+        .mount(&openrouter)
+        .await;
+    let resp: BioAuthResponse = call(&client, format!("{}/bio_auth", enclave), bio_request)
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(resp.payload.result, BioAuthResult::InvalidAmount as u8);
+}
+
+/// The whole analysis path against a recorded upstream session, with no
+/// live keys or network. Run with `cargo test --features mock,cassettes`.
+#[cfg(feature = "cassettes")]
+#[tokio::test]
+async fn test_bio_auth_replays_a_recorded_upstream_session() {
+    let _env = UPSTREAM_ENV.lock().await;
+    // The cassette has the real endpoints' paths, which are never called
+    set_endpoint_override(Endpoint::OpenRouter, None);
+    set_endpoint_override(Endpoint::Hume, None);
+    std::env::remove_var("HUME_MODE");
+    std::env::set_var(
+        "UPSTREAM_CASSETTE",
+        concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/cassettes/bio_auth_fearful.json"),
+    );
+    std::env::set_var("UPSTREAM_CASSETTE_MODE", "replay");
+
+    let state = Arc::new(AppState {
+        eph_kp: Ed25519KeyPair::generate(&mut rand::thread_rng()),
+        sui_rpc_url: String::new(),
+        openrouter_api_key: "replay-key".to_string().into(),
+        hume_api_key: "replay-key".to_string().into(),
+        diagnostics_secret: String::new().into(),
+        bioauth_diagnostics: DiagnosticsStore::default(),
+        bioauth_jobs: JobQueue::default(),
+        bioauth_locks: HandleLocks::new(),
+        bioauth_shadow: ShadowMode::default(),
+        voice_baselines: BaselineStore::new(),
+        user_preferences: PreferenceStore::default(),
+        bioauth_challenges: ChallengeStore::new(std::time::Duration::from_secs(60), String::new()),
+        bioauth_attempts: AttemptTracker::new(),
+        bioauth_decoy: false,
+        bioauth_replays: ReplayGuard::default(),
+        coin_registry: CoinRegistry::default(),
+        upstream_costs: CostTracker::default(),
+        zklogin: ZkLoginVerifier::default(),
+        payload_versions: PayloadVersions::default(),
+        analysis_cache: AnalysisCache::default(),
+        screening: Screener::default(),
+        transfer_simulator: TransferSimulator::default(),
+        bioauth_gray_zone: GrayZone::default(),
+        registration_gate: RegistrationGate::default(),
+    });
+    let enclave = spawn_enclave(state.clone()).await;
+    let client = reqwest::Client::new();
+    let bio_auth = |handle: &str| {
+        call(
+            &client,
+            format!("{}/bio_auth", enclave),
+            json!({
+                "handle": handle,
+                "audio_base64": calm_wav_base64(),
+                "expected_amount": 5_000_000_000u64,
+                "skip_analysis_cache": true,
+            }),
+        )
+    };
+
+    // The transcript is calm but Hume heard fear: both tapes were played
+    let resp: BioAuthResponse = bio_auth("alice").await.json().await.unwrap();
+    assert_eq!(resp.payload.result, BioAuthResult::Duress as u8);
+    let diagnostics = state.bioauth_diagnostics.get(&resp.request_id).unwrap();
+    assert_eq!(diagnostics.transcript, "I confirm sending 5 SUI");
+    assert!(diagnostics.stress_level >= 60);
+
+    // Recording against mock upstreams keeps neither the keys nor the audio
+    let openrouter = MockServer::start().await;
+    let hume = MockServer::start().await;
+    Mock::given(method("POST")).and(path("/chat")).respond_with(openrouter_reply(10, 5.0)).mount(&openrouter).await;
+    Mock::given(method("POST")).and(path("/jobs")).respond_with(ResponseTemplate::new(503)).mount(&hume).await;
+    set_endpoint_override(Endpoint::OpenRouter, Some(format!("{}/chat", openrouter.uri())));
+    set_endpoint_override(Endpoint::Hume, Some(format!("{}/jobs", hume.uri())));
+    let recording = std::env::temp_dir().join(format!("ram-cassette-{}.json", uuid::Uuid::new_v4()));
+    std::env::set_var("UPSTREAM_CASSETTE", &recording);
+    std::env::set_var("UPSTREAM_CASSETTE_MODE", "record");
+
+    let resp: BioAuthResponse = bio_auth("bob").await.json().await.unwrap();
+    assert_eq!(resp.payload.result, BioAuthResult::Ok as u8);
+    let recorded = std::fs::read_to_string(&recording).unwrap();
+    std::env::remove_var("UPSTREAM_CASSETTE");
+    std::env::remove_var("UPSTREAM_CASSETTE_MODE");
+    std::fs::remove_file(&recording).unwrap();
+
+    let cassette: Value = serde_json::from_str(&recorded).unwrap();
+    let interactions: Vec<(&str, &str, u64)> = cassette["interactions"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|i| (i["provider"].as_str().unwrap(), i["path"].as_str().unwrap(), i["status"].as_u64().unwrap()))
+        .collect();
+    // Hume's 503 was retried once before falling back
+    assert_eq!(interactions, [("openrouter", "/chat", 200), ("hume", "/jobs", 503), ("hume", "/jobs", 503)]);
+    assert!(!recorded.contains("replay-key"));
+    assert!(!recorded.contains(&calm_wav_base64()[..64]));
+}
+
+#[tokio::test]
+async fn test_queued_bio_auth_mode() {
+    let _env = UPSTREAM_ENV.lock().await;
+    let openrouter = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/chat"))
+        .respond_with(openrouter_reply(10, 5.0))
+        .mount(&openrouter)
+        .await;
+
+    let eph_kp = Ed25519KeyPair::generate(&mut rand::thread_rng());
+    let pk = eph_kp.public().clone();
+    let state = Arc::new(AppState {
+        eph_kp,
+        sui_rpc_url: String::new(),
+        openrouter_api_key: "test-key".to_string().into(),
+        hume_api_key: String::new().into(),
+        diagnostics_secret: String::new().into(),
+        bioauth_diagnostics: DiagnosticsStore::default(),
+        bioauth_jobs: JobQueue::new(true, 4, std::time::Duration::from_secs(60)),
+        bioauth_locks: HandleLocks::new(),
+        bioauth_shadow: ShadowMode::default(),
+        voice_baselines: BaselineStore::new(),
+        user_preferences: PreferenceStore::default(),
+        bioauth_challenges: ChallengeStore::new(std::time::Duration::from_secs(60), String::new()),
+        bioauth_attempts: AttemptTracker::new(),
+        bioauth_decoy: false,
+        bioauth_replays: ReplayGuard::default(),
+        coin_registry: CoinRegistry::default(),
+        upstream_costs: CostTracker::default(),
+        zklogin: ZkLoginVerifier::default(),
+        payload_versions: PayloadVersions::default(),
+        analysis_cache: AnalysisCache::default(),
+        screening: Screener::default(),
+        transfer_simulator: TransferSimulator::default(),
+        bioauth_gray_zone: GrayZone::default(),
+        registration_gate: RegistrationGate::default(),
+    });
+    set_endpoint_override(Endpoint::OpenRouter, Some(format!("{}/chat", openrouter.uri())));
+    start_bio_auth_workers(state.clone(), 1);
+    let enclave = spawn_enclave(state).await;
+    let client = reqwest::Client::new();
+
+    let resp = call(
+        &client,
+        format!("{}/bio_auth", enclave),
+        json!({
+            "handle": "alice",
+            "audio_base64": calm_wav_base64(),
+            "expected_amount": 5_000_000_000u64,
+        }),
+    )
+    .await;
+    assert_eq!(resp.status(), 202);
+    let queued: Value = resp.json().await.unwrap();
+    let job_id = queued["job_id"].as_str().unwrap().to_string();
+
+    let mut job = Value::Null;
+    for _ in 0..100 {
+        job = client
+            .get(format!("{}/bio_auth/{}", enclave, job_id))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        if job["status"] == "done" || job["status"] == "failed" {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    }
+    assert_eq!(job["status"], "done", "job did not finish: {}", job);
+    let resp: BioAuthResponse = serde_json::from_value(job["response"].clone()).unwrap();
+    assert_eq!(resp.payload.result, BioAuthResult::Ok as u8);
+    assert!(verify(&pk, resp.payload, resp.timestamp_ms, IntentScope::TransferNft, &resp.signature));
+
+    let metrics: Value = client
+        .get(format!("{}/bio_auth/queue", enclave))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(metrics["completed"], 1);
+    assert_eq!(metrics["depth"], 0);
+
+    let missing = client.get(format!("{}/bio_auth/unknown", enclave)).send().await.unwrap();
+    assert_eq!(missing.status(), 404);
+}
+
+#[tokio::test]
+async fn test_concurrent_bio_auth_same_handle_conflicts() {
+    let _env = UPSTREAM_ENV.lock().await;
+    let openrouter = MockServer::start().await;
+    // Slow analysis so both requests overlap
+    Mock::given(method("POST"))
+        .and(path("/chat"))
+        .respond_with(openrouter_reply(10, 5.0).set_delay(std::time::Duration::from_millis(500)))
+        .mount(&openrouter)
+        .await;
+    set_endpoint_override(Endpoint::OpenRouter, Some(format!("{}/chat", openrouter.uri())));
+
+    let state = Arc::new(AppState {
+        eph_kp: Ed25519KeyPair::generate(&mut rand::thread_rng()),
+        sui_rpc_url: String::new(),
+        openrouter_api_key: "test-key".to_string().into(),
+        hume_api_key: String::new().into(),
+        diagnostics_secret: String::new().into(),
+        bioauth_diagnostics: DiagnosticsStore::default(),
+        bioauth_jobs: JobQueue::default(),
+        bioauth_locks: HandleLocks::new(),
+        bioauth_shadow: ShadowMode::default(),
+        voice_baselines: BaselineStore::new(),
+        user_preferences: PreferenceStore::default(),
+        bioauth_challenges: ChallengeStore::new(std::time::Duration::from_secs(60), String::new()),
+        bioauth_attempts: AttemptTracker::new(),
+        bioauth_decoy: false,
+        bioauth_replays: ReplayGuard::default(),
+        coin_registry: CoinRegistry::default(),
+        upstream_costs: CostTracker::default(),
+        zklogin: ZkLoginVerifier::default(),
+        payload_versions: PayloadVersions::default(),
+        analysis_cache: AnalysisCache::default(),
+        screening: Screener::default(),
+        transfer_simulator: TransferSimulator::default(),
+        bioauth_gray_zone: GrayZone::default(),
+        registration_gate: RegistrationGate::default(),
+    });
+    let enclave = spawn_enclave(state).await;
+    let client = reqwest::Client::new();
+    let request = |handle: &str| {
+        call(
+            &client,
+            format!("{}/bio_auth", enclave),
+            json!({
+                "handle": handle,
+                "audio_base64": calm_wav_base64(),
+                "expected_amount": 5_000_000_000u64,
+            }),
+        )
+    };
+
+    let (a, b, other) = tokio::join!(request("alice"), request("alice"), request("bob"));
+    let mut statuses = [a.status().as_u16(), b.status().as_u16()];
+    statuses.sort();
+    assert_eq!(statuses, [200, 409]);
+    assert_eq!(other.status(), 200);
+
+    // Lock is released once the first request finishes
+    assert_eq!(request("alice").await.status(), 200);
+}
+
+#[tokio::test]
+async fn test_shadow_mode_records_duress_without_locking() {
+    let _env = UPSTREAM_ENV.lock().await;
+    let openrouter = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/chat"))
+        .respond_with(openrouter_reply(95, 5.0))
+        .mount(&openrouter)
+        .await;
+    set_endpoint_override(Endpoint::OpenRouter, Some(format!("{}/chat", openrouter.uri())));
+
+    let eph_kp = Ed25519KeyPair::generate(&mut rand::thread_rng());
+    let pk = eph_kp.public().clone();
+    let state = Arc::new(AppState {
+        eph_kp,
+        sui_rpc_url: String::new(),
+        openrouter_api_key: "test-key".to_string().into(),
+        hume_api_key: String::new().into(),
+        diagnostics_secret: String::new().into(),
+        bioauth_diagnostics: DiagnosticsStore::default(),
+        bioauth_jobs: JobQueue::default(),
+        bioauth_locks: HandleLocks::new(),
+        bioauth_shadow: ShadowMode::new(true, None),
+        voice_baselines: BaselineStore::new(),
+        user_preferences: PreferenceStore::default(),
+        bioauth_challenges: ChallengeStore::new(std::time::Duration::from_secs(60), String::new()),
+        bioauth_attempts: AttemptTracker::new(),
+        bioauth_decoy: false,
+        bioauth_replays: ReplayGuard::default(),
+        coin_registry: CoinRegistry::default(),
+        upstream_costs: CostTracker::default(),
+        zklogin: ZkLoginVerifier::default(),
+        payload_versions: PayloadVersions::default(),
+        analysis_cache: AnalysisCache::default(),
+        screening: Screener::default(),
+        transfer_simulator: TransferSimulator::default(),
+        bioauth_gray_zone: GrayZone::default(),
+        registration_gate: RegistrationGate::default(),
+    });
+    let enclave = spawn_enclave(state.clone()).await;
+    let client = reqwest::Client::new();
+
+    // The analyzer flags duress, but the signed result only reflects the amount check
+    let resp: BioAuthResponse = call(
+        &client,
+        format!("{}/bio_auth", enclave),
+        json!({
+            "handle": "alice",
+            "audio_base64": calm_wav_base64(),
+            "expected_amount": 5_000_000_000u64,
+        }),
+    )
+    .await
+    .json()
+    .await
+    .unwrap();
+    assert_eq!(resp.payload.result, BioAuthResult::Ok as u8);
+    assert!(verify(&pk, resp.payload, resp.timestamp_ms, IntentScope::TransferNft, &resp.signature));
+
+    let data = state.bioauth_diagnostics.get(&resp.request_id).unwrap();
+    assert!(data.shadow_mode);
+    assert!(!data.locked);
+    assert_eq!(data.result, "ok");
+    assert_eq!(data.detected_result, "duress");
+    // GPT-4o hears panic in a steady tone the DSP analysis scores calm
+    let disagreement = data.analyzer_disagreement.unwrap();
+    assert_eq!((disagreement.scores.gpt, disagreement.scores.dsp), (Some(95), Some(10)));
+    assert_eq!(disagreement.spread, 85);
+
+    let metrics: Value = client
+        .get(format!("{}/bio_auth/shadow", enclave))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(metrics["enabled"], true);
+    assert_eq!(metrics["analyzed"], 1);
+    assert_eq!(metrics["would_lock"], 1);
+}
+
+#[tokio::test]
+async fn test_voice_baseline_enrollment() {
+    let state = Arc::new(AppState {
+        eph_kp: Ed25519KeyPair::generate(&mut rand::thread_rng()),
+        sui_rpc_url: String::new(),
+        openrouter_api_key: String::new().into(),
+        hume_api_key: String::new().into(),
+        diagnostics_secret: String::new().into(),
+        bioauth_diagnostics: DiagnosticsStore::default(),
+        bioauth_jobs: JobQueue::default(),
+        bioauth_locks: HandleLocks::new(),
+        bioauth_shadow: ShadowMode::default(),
+        voice_baselines: BaselineStore::new(),
+        user_preferences: PreferenceStore::default(),
+        bioauth_challenges: ChallengeStore::new(std::time::Duration::from_secs(60), String::new()),
+        bioauth_attempts: AttemptTracker::new(),
+        bioauth_decoy: false,
+        bioauth_replays: ReplayGuard::default(),
+        coin_registry: CoinRegistry::default(),
+        upstream_costs: CostTracker::default(),
+        zklogin: ZkLoginVerifier::default(),
+        payload_versions: PayloadVersions::default(),
+        analysis_cache: AnalysisCache::default(),
+        screening: Screener::default(),
+        transfer_simulator: TransferSimulator::default(),
+        bioauth_gray_zone: GrayZone::default(),
+        registration_gate: RegistrationGate::default(),
+    });
+    let enclave = spawn_enclave(state.clone()).await;
+    let client = reqwest::Client::new();
+    let enroll = || {
+        call(
+            &client,
+            format!("{}/bio_auth/enroll", enclave),
+            json!({ "handle": "alice", "audio_base64": calm_wav_base64() }),
+        )
+    };
+
+    let mut progress = Value::Null;
+    for _ in 0..3 {
+        let resp = enroll().await;
+        assert_eq!(resp.status(), 200);
+        progress = resp.json().await.unwrap();
+    }
+    assert_eq!(progress["samples"], 3);
+    assert_eq!(progress["complete"], true);
+    assert!(state.voice_baselines.get("alice").unwrap().is_ready());
+
+    // A completed baseline is frozen
+    assert_eq!(enroll().await.status(), 409);
+}
+
+#[tokio::test]
+async fn test_challenge_audio_is_rendered_once_and_cached() {
+    let _env = UPSTREAM_ENV.lock().await;
+    let tts = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/speech"))
+        .respond_with(ResponseTemplate::new(200).set_body_bytes(b"ID3-fake-mp3".to_vec()))
+        .expect(1)
+        .mount(&tts)
+        .await;
+    set_endpoint_override(Endpoint::Tts, Some(format!("{}/speech", tts.uri())));
+
+    let state = Arc::new(AppState {
+        eph_kp: Ed25519KeyPair::generate(&mut rand::thread_rng()),
+        sui_rpc_url: String::new(),
+        openrouter_api_key: String::new().into(),
+        hume_api_key: String::new().into(),
+        diagnostics_secret: String::new().into(),
+        bioauth_diagnostics: DiagnosticsStore::default(),
+        bioauth_jobs: JobQueue::default(),
+        bioauth_locks: HandleLocks::new(),
+        bioauth_shadow: ShadowMode::default(),
+        voice_baselines: BaselineStore::new(),
+        user_preferences: PreferenceStore::default(),
+        bioauth_challenges: ChallengeStore::new(std::time::Duration::from_secs(60), "test-key".to_string()),
+        bioauth_attempts: AttemptTracker::new(),
+        bioauth_decoy: false,
+        bioauth_replays: ReplayGuard::default(),
+        coin_registry: CoinRegistry::default(),
+        upstream_costs: CostTracker::default(),
+        zklogin: ZkLoginVerifier::default(),
+        payload_versions: PayloadVersions::default(),
+        analysis_cache: AnalysisCache::default(),
+        screening: Screener::default(),
+        transfer_simulator: TransferSimulator::default(),
+        bioauth_gray_zone: GrayZone::default(),
+        registration_gate: RegistrationGate::default(),
+    });
+    let enclave = spawn_enclave(state).await;
+    let client = reqwest::Client::new();
+
+    let challenge: Value = call(
+        &client,
+        format!("{}/bio_auth/challenge", enclave),
+        json!({ "handle": "alice", "expected_amount": 5_000_000_000u64 }),
+    )
+    .await
+    .json()
+    .await
+    .unwrap();
+    assert_eq!(challenge["phrase"], "I confirm sending 5 SUI");
+
+    let url = format!("{}/bio_auth/challenge_audio/{}", enclave, challenge["challenge_id"].as_str().unwrap());
+    for _ in 0..2 {
+        let resp = client.get(&url).send().await.unwrap();
+        assert_eq!(resp.status(), 200);
+        assert_eq!(resp.headers()["content-type"], "audio/mpeg");
+        assert_eq!(resp.bytes().await.unwrap().as_ref(), b"ID3-fake-mp3");
+    }
+
+    let missing = client.get(format!("{}/bio_auth/challenge_audio/unknown", enclave)).send().await.unwrap();
+    assert_eq!(missing.status(), 404);
+}
+
+#[tokio::test]
+async fn test_typed_auth_signs_typed_method() {
+    let eph_kp = Ed25519KeyPair::generate(&mut rand::thread_rng());
+    let pk = eph_kp.public().clone();
+    let state = Arc::new(AppState {
+        eph_kp,
+        sui_rpc_url: String::new(),
+        openrouter_api_key: String::new().into(),
+        hume_api_key: String::new().into(),
+        diagnostics_secret: String::new().into(),
+        bioauth_diagnostics: DiagnosticsStore::default(),
+        bioauth_jobs: JobQueue::default(),
+        bioauth_locks: HandleLocks::new(),
+        bioauth_shadow: ShadowMode::default(),
+        voice_baselines: BaselineStore::new(),
+        user_preferences: PreferenceStore::default(),
+        bioauth_challenges: ChallengeStore::new(std::time::Duration::from_secs(60), String::new()),
+        bioauth_attempts: AttemptTracker::new(),
+        bioauth_decoy: false,
+        bioauth_replays: ReplayGuard::default(),
+        coin_registry: CoinRegistry::default(),
+        upstream_costs: CostTracker::default(),
+        zklogin: ZkLoginVerifier::default(),
+        payload_versions: PayloadVersions::default(),
+        analysis_cache: AnalysisCache::default(),
+        screening: Screener::default(),
+        transfer_simulator: TransferSimulator::default(),
+        bioauth_gray_zone: GrayZone::default(),
+        registration_gate: RegistrationGate::default(),
+    });
+    let enclave = spawn_enclave(state.clone()).await;
+    let client = reqwest::Client::new();
+    let typed = |keystrokes: Value, pasted: bool| {
+        call(
+            &client,
+            format!("{}/typed_auth", enclave),
+            json!({
+                "handle": "alice",
+                "typed_text": "5 SUI 11ce",
+                "expected_amount": 5_000_000_000u64,
+                "destination_hint": "0x00a11ce",
+                "keystrokes": keystrokes,
+                "pasted": pasted,
+            }),
+        )
+    };
+    let human: Vec<Value> = [0u64, 140, 390, 470, 700, 820, 1010, 1300, 1420, 1610]
+        .iter()
+        .map(|t| json!({ "down_ms": t, "up_ms": t + 80 }))
+        .collect();
+
+    let resp: BioAuthResponse = typed(json!(human), false).await.json().await.unwrap();
+    assert_eq!(resp.payload.result, BioAuthResult::Ok as u8);
+    assert_eq!(resp.payload.method, BioAuthMethod::Typed as u8);
+    assert!(resp.payload.destination_verified);
+    assert_eq!(state.bioauth_diagnostics.get(&resp.request_id).unwrap().method, "typed");
+    assert!(verify(&pk, resp.payload, resp.timestamp_ms, IntentScope::TransferNft, &resp.signature));
+
+    // Pasted input is signed as invalid, never as duress
+    let resp: BioAuthResponse = typed(json!(human), true).await.json().await.unwrap();
+    assert_eq!(resp.payload.result, BioAuthResult::InvalidAmount as u8);
+}
+
+#[tokio::test]
+async fn test_mock_server_scripted_outcomes() {
+    let app = mock_router(Arc::new(MockState::new(None, true)));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let mock = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        axum::serve(listener, app.into_make_service()).await.unwrap();
+    });
+    let client = reqwest::Client::new();
+
+    // Published key is the one derived from the fixed seed
+    let health: Value = client.get(format!("{}/health_check", mock)).send().await.unwrap().json().await.unwrap();
+    let seed_kp = Ed25519KeyPair::from(fastcrypto::ed25519::Ed25519PrivateKey::from_bytes(&MOCK_KEY_SEED).unwrap());
+    assert_eq!(health["pk"], Hex::encode(seed_kp.public().as_bytes()));
+    // Both the health check and the intent table say which chain is signed for
+    assert_eq!(health["chain"]["network"], "localnet");
+    assert_eq!(health["chain"]["package_id"], current_chain().package_id());
+    let meta: Value = client.get(format!("{}/meta/intents", mock)).send().await.unwrap().json().await.unwrap();
+    assert_eq!(meta["chain"], health["chain"]);
+    assert!(meta["payload_versions"].as_array().unwrap().iter().any(|v| v["name"] == "PAYLOAD_V3"));
+    let pk = seed_kp.public().clone();
+
+    // Queued like BIOAUTH_ASYNC_MODE, and the job is done on the first poll
+    let queued: Value = client
+        .post(format!("{}/bio_auth", mock))
+        .header(MOCK_OUTCOME_HEADER, "duress")
+        .json(&json!({ "payload": {
+            "handle": "alice",
+            "audio_base64": calm_wav_base64(),
+            "expected_amount": 5_000_000_000u64,
+        }}))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let job_id = queued["job_id"].as_str().unwrap();
+    let job: Value = client.get(format!("{}/bio_auth/{}", mock, job_id)).send().await.unwrap().json().await.unwrap();
+    assert_eq!(job["status"], "done");
+    let resp: BioAuthResponse = serde_json::from_value(job["response"].clone()).unwrap();
+    assert_eq!(resp.payload.result, BioAuthResult::Duress as u8);
+    assert_eq!(resp.payload.transcript, b"I confirm sending 5 SUI".to_vec());
+    assert!(verify(&pk, resp.payload, resp.timestamp_ms, IntentScope::TransferNft, &resp.signature));
+
+    let resp: BioAuthResponse = client
+        .post(format!("{}/typed_auth", mock))
+        .header(MOCK_OUTCOME_HEADER, "invalid_amount")
+        .json(&json!({ "payload": {
+            "handle": "alice",
+            "typed_text": "5 SUI",
+            "expected_amount": 5_000_000_000u64,
+            "keystrokes": [],
+        }}))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(resp.payload.result, BioAuthResult::InvalidAmount as u8);
+    assert_eq!(resp.payload.method, BioAuthMethod::Typed as u8);
+
+    let resp = call(&client, format!("{}/transfer", mock), json!({
+        "from_handle": "alice",
+        "to_handle": "bob",
+        "amount": 1u64,
+        "coin_type": "0x2::sui::SUI",
+    }))
+    .await;
+    let resp: TransferResponse = resp.json().await.unwrap();
+    assert!(verify(&pk, resp.payload, resp.timestamp_ms, IntentScope::TransferCoin, &resp.signature));
+
+    let set_limit = json!({ "payload": {
+        "handle": "alice",
+        "audio_base64": calm_wav_base64(),
+        "daily_limit": 50_000_000_000u64,
+    }});
+    let resp = client
+        .post(format!("{}/set_limit", mock))
+        .header(MOCK_OUTCOME_HEADER, "duress")
+        .json(&set_limit)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 403);
+    let resp: SetLimitResponse =
+        client.post(format!("{}/set_limit", mock)).json(&set_limit).send().await.unwrap().json().await.unwrap();
+    assert_eq!(resp.intent, IntentScope::SetLimit as u8);
+    assert!(verify(&pk, resp.payload, resp.timestamp_ms, IntentScope::SetLimit, &resp.signature));
+
+    let resp: FreezeWalletResponse =
+        call(&client, format!("{}/freeze_wallet", mock), json!({ "handle": "alice" })).await.json().await.unwrap();
+    assert!(verify(&pk, resp.payload, resp.timestamp_ms, IntentScope::Freeze, &resp.signature));
+    let challenge: ChallengeResponse =
+        call(&client, format!("{}/unfreeze/challenge", mock), json!({ "handle": "alice" })).await.json().await.unwrap();
+    assert_eq!(challenge.phrase, "Unfreeze wallet alice, code 1 2 3 4 5 6");
+    let resp: UnfreezeResponse = call(&client, format!("{}/request_unfreeze", mock), json!({
+        "handle": "alice",
+        "challenge_id": challenge.challenge_id,
+        "audio_base64": calm_wav_base64(),
+    }))
+    .await
+    .json()
+    .await
+    .unwrap();
+    assert!(verify(&pk, resp.payload, resp.timestamp_ms, IntentScope::Unfreeze, &resp.signature));
+
+    let resp: SetBeneficiaryResponse = call(&client, format!("{}/set_beneficiary", mock), json!({
+        "handle": "alice",
+        "beneficiary_handle": "bob",
+        "inactivity_timeout_ms": MIN_INACTIVITY_TIMEOUT_MS,
+    }))
+    .await
+    .json()
+    .await
+    .unwrap();
+    assert!(verify(&pk, resp.payload, resp.timestamp_ms, IntentScope::Beneficiary, &resp.signature));
+
+    let resp: ProveDepositResponse = call(&client, format!("{}/prove_deposit", mock), json!({
+        "handle": "alice",
+        "deposit_id": "104877321",
+    }))
+    .await
+    .json()
+    .await
+    .unwrap();
+    assert!(verify(&pk, resp.payload, resp.timestamp_ms, IntentScope::DepositProof, &resp.signature));
+}
+
+#[tokio::test]
+async fn test_dev_fixed_key_is_published() {
+    let private_key = "07".repeat(32);
+    let eph_kp = keypair_from_hex(&private_key).unwrap();
+    let expected = Hex::encode(eph_kp.public().as_bytes());
+    assert!(keypair_from_hex("0707").is_err());
+    let state = Arc::new(AppState {
+        eph_kp,
+        sui_rpc_url: String::new(),
+        openrouter_api_key: String::new().into(),
+        hume_api_key: String::new().into(),
+        diagnostics_secret: String::new().into(),
+        bioauth_diagnostics: DiagnosticsStore::default(),
+        bioauth_jobs: JobQueue::default(),
+        bioauth_locks: HandleLocks::new(),
+        bioauth_shadow: ShadowMode::default(),
+        voice_baselines: BaselineStore::new(),
+        user_preferences: PreferenceStore::default(),
+        bioauth_challenges: ChallengeStore::new(std::time::Duration::from_secs(60), String::new()),
+        bioauth_attempts: AttemptTracker::new(),
+        bioauth_decoy: false,
+        bioauth_replays: ReplayGuard::default(),
+        coin_registry: CoinRegistry::default(),
+        upstream_costs: CostTracker::default(),
+        zklogin: ZkLoginVerifier::default(),
+        payload_versions: PayloadVersions::default(),
+        analysis_cache: AnalysisCache::default(),
+        screening: Screener::default(),
+        transfer_simulator: TransferSimulator::default(),
+        bioauth_gray_zone: GrayZone::default(),
+        registration_gate: RegistrationGate::default(),
+    });
+    let enclave = spawn_enclave(state).await;
+    let client = reqwest::Client::new();
+
+    let published: Value = client.get(format!("{}/public_key", enclave)).send().await.unwrap().json().await.unwrap();
+    assert_eq!(published["public_key"], expected);
+
+    // Same key across restarts, so the published key verifies fresh signatures
+    let pk = keypair_from_hex(&format!("0x{}", private_key)).unwrap().public().clone();
+    let resp: CreateWalletResponse = call(&client, format!("{}/create_wallet", enclave), json!({ "handle": "alice" }))
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert!(verify(&pk, resp.payload, resp.timestamp_ms, IntentScope::ProcessData, &resp.signature));
+}
+
+#[tokio::test]
+async fn test_signed_requests_required_when_key_set() {
+    let state = Arc::new(AppState {
+        eph_kp: Ed25519KeyPair::generate(&mut rand::thread_rng()),
+        sui_rpc_url: String::new(),
+        openrouter_api_key: String::new().into(),
+        hume_api_key: String::new().into(),
+        diagnostics_secret: String::new().into(),
+        bioauth_diagnostics: DiagnosticsStore::default(),
+        bioauth_jobs: JobQueue::default(),
+        bioauth_locks: HandleLocks::new(),
+        bioauth_shadow: ShadowMode::default(),
+        voice_baselines: BaselineStore::new(),
+        user_preferences: PreferenceStore::default(),
+        bioauth_challenges: ChallengeStore::new(std::time::Duration::from_secs(60), String::new()),
+        bioauth_attempts: AttemptTracker::new(),
+        bioauth_decoy: false,
+        bioauth_replays: ReplayGuard::default(),
+        coin_registry: CoinRegistry::default(),
+        upstream_costs: CostTracker::default(),
+        zklogin: ZkLoginVerifier::default(),
+        payload_versions: PayloadVersions::default(),
+        analysis_cache: AnalysisCache::default(),
+        screening: Screener::default(),
+        transfer_simulator: TransferSimulator::default(),
+        bioauth_gray_zone: GrayZone::default(),
+        registration_gate: RegistrationGate::default(),
+    });
+    let auth = Arc::new(RequestAuth::new("s3cret".to_string(), DEFAULT_REQUEST_MAX_SKEW_MS));
+    let app = Router::new()
+        .route("/public_key", get(get_public_key))
+        .route("/create_wallet", post(process_create_wallet))
+        .with_state(state)
+        .layer(axum::middleware::from_fn_with_state(auth, require_signed_request));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let enclave = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        axum::serve(listener, app.into_make_service()).await.unwrap();
+    });
+    let client = reqwest::Client::new();
+    let body = json!({ "payload": { "handle": "alice" } }).to_string();
+
+    // Public routes stay open
+    let resp = client.get(format!("{}/public_key", enclave)).send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+
+    let resp = call(&client, format!("{}/create_wallet", enclave), json!({ "handle": "alice" })).await;
+    assert_eq!(resp.status(), 401);
+
+    let now_ms = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64;
+    let signed = |key: &str| {
+        client
+            .post(format!("{}/create_wallet", enclave))
+            .header("content-type", "application/json")
+            .header(REQUEST_TIMESTAMP_HEADER, now_ms.to_string())
+            .header(REQUEST_SIGNATURE_HEADER, request_signature(key, now_ms, "POST", "/create_wallet", body.as_bytes()))
+            .body(body.clone())
+            .send()
+    };
+    assert_eq!(signed("wrong").await.unwrap().status(), 401);
+    let resp: CreateWalletResponse = signed("s3cret").await.unwrap().json().await.unwrap();
+    assert_eq!(resp.payload.handle, b"alice".to_vec());
+}
+
+#[tokio::test]
+async fn test_peer_acl_blocks_signing_routes() {
+    let state = Arc::new(AppState {
+        eph_kp: Ed25519KeyPair::generate(&mut rand::thread_rng()),
+        sui_rpc_url: String::new(),
+        openrouter_api_key: String::new().into(),
+        hume_api_key: String::new().into(),
+        diagnostics_secret: String::new().into(),
+        bioauth_diagnostics: DiagnosticsStore::default(),
+        bioauth_jobs: JobQueue::default(),
+        bioauth_locks: HandleLocks::new(),
+        bioauth_shadow: ShadowMode::default(),
+        voice_baselines: BaselineStore::new(),
+        user_preferences: PreferenceStore::default(),
+        bioauth_challenges: ChallengeStore::new(std::time::Duration::from_secs(60), String::new()),
+        bioauth_attempts: AttemptTracker::new(),
+        bioauth_decoy: false,
+        bioauth_replays: ReplayGuard::default(),
+        coin_registry: CoinRegistry::default(),
+        upstream_costs: CostTracker::default(),
+        zklogin: ZkLoginVerifier::default(),
+        payload_versions: PayloadVersions::default(),
+        analysis_cache: AnalysisCache::default(),
+        screening: Screener::default(),
+        transfer_simulator: TransferSimulator::default(),
+        bioauth_gray_zone: GrayZone::default(),
+        registration_gate: RegistrationGate::default(),
+    });
+    // Loopback may call signing routes but not the privileged channel
+    let acl = Arc::new(PeerAcl::new(parse_cidrs("127.0.0.0/8").unwrap(), Some(parse_cidrs("10.0.0.1").unwrap())));
+    let app = Router::new()
+        .route("/public_key", get(get_public_key))
+        .route("/create_wallet", post(process_create_wallet))
+        .route("/bio_auth/diagnostics/:request_id", get(get_bio_auth_diagnostics))
+        .with_state(state)
+        .layer(axum::middleware::from_fn_with_state(acl, enforce_peer_acl));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let enclave = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        axum::serve(listener, app.into_make_service_with_connect_info::<std::net::SocketAddr>())
+            .await
+            .unwrap();
+    });
+    let client = reqwest::Client::new();
+
+    let resp = call(&client, format!("{}/create_wallet", enclave), json!({ "handle": "alice" })).await;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(client.get(format!("{}/public_key", enclave)).send().await.unwrap().status(), 200);
+
+    let resp = client.get(format!("{}/bio_auth/diagnostics/abc", enclave)).send().await.unwrap();
+    assert_eq!(resp.status(), 403);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["code"], "peer_not_allowed");
+    assert_eq!(body["details"]["route_class"], "privileged");
+}
+
+#[tokio::test]
+async fn test_replayed_clip_is_rejected() {
+    let _env = UPSTREAM_ENV.lock().await;
+    let openrouter = MockServer::start().await;
+    set_endpoint_override(Endpoint::OpenRouter, Some(format!("{}/chat", openrouter.uri())));
+    Mock::given(method("POST"))
+        .and(path("/chat"))
+        .respond_with(openrouter_reply(10, 5.0))
+        .mount(&openrouter)
+        .await;
+
+    let state = Arc::new(AppState {
+        eph_kp: Ed25519KeyPair::generate(&mut rand::thread_rng()),
+        sui_rpc_url: String::new(),
+        openrouter_api_key: "test-key".to_string().into(),
+        hume_api_key: String::new().into(),
+        diagnostics_secret: String::new().into(),
+        bioauth_diagnostics: DiagnosticsStore::default(),
+        bioauth_jobs: JobQueue::default(),
+        bioauth_locks: HandleLocks::new(),
+        bioauth_shadow: ShadowMode::default(),
+        voice_baselines: BaselineStore::new(),
+        user_preferences: PreferenceStore::default(),
+        bioauth_challenges: ChallengeStore::new(std::time::Duration::from_secs(60), String::new()),
+        bioauth_attempts: AttemptTracker::new(),
+        bioauth_decoy: false,
+        bioauth_replays: ReplayGuard::new(std::time::Duration::from_secs(DEFAULT_REPLAY_WINDOW_DAYS * 86_400)),
+        coin_registry: CoinRegistry::default(),
+        upstream_costs: CostTracker::default(),
+        zklogin: ZkLoginVerifier::default(),
+        payload_versions: PayloadVersions::default(),
+        analysis_cache: AnalysisCache::default(),
+        screening: Screener::default(),
+        transfer_simulator: TransferSimulator::default(),
+        bioauth_gray_zone: GrayZone::default(),
+        registration_gate: RegistrationGate::default(),
+    });
+    let enclave = spawn_enclave(state).await;
+    let client = reqwest::Client::new();
+    let bio_request = |handle: &str| {
+        json!({
+            "handle": handle,
+            "audio_base64": calm_wav_base64(),
+            "expected_amount": 5_000_000_000u64,
+            "coin_type": "0x2::sui::SUI",
+        })
+    };
+
+    let resp: BioAuthResponse = call(&client, format!("{}/bio_auth", enclave), bio_request("alice"))
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(resp.payload.result, BioAuthResult::Ok as u8);
+
+    // Same recording again, before any upstream analysis
+    let resp = call(&client, format!("{}/bio_auth", enclave), bio_request("alice")).await;
+    assert_eq!(resp.status(), 422);
+    assert_eq!(openrouter.received_requests().await.unwrap().len(), 1);
+
+    // Fingerprints are per handle
+    let resp = call(&client, format!("{}/bio_auth", enclave), bio_request("bob")).await;
+    assert_eq!(resp.status(), 200);
+}
+
+#[tokio::test]
+async fn test_wallet_creation_behind_a_proof_of_work_gate() {
+    let state = Arc::new(AppState {
+        eph_kp: Ed25519KeyPair::generate(&mut rand::thread_rng()),
+        sui_rpc_url: String::new(),
+        openrouter_api_key: String::new().into(),
+        hume_api_key: String::new().into(),
+        diagnostics_secret: String::new().into(),
+        bioauth_diagnostics: DiagnosticsStore::default(),
+        bioauth_jobs: JobQueue::default(),
+        bioauth_locks: HandleLocks::new(),
+        bioauth_shadow: ShadowMode::default(),
+        voice_baselines: BaselineStore::new(),
+        user_preferences: PreferenceStore::default(),
+        bioauth_challenges: ChallengeStore::new(std::time::Duration::from_secs(60), String::new()),
+        bioauth_attempts: AttemptTracker::new(),
+        bioauth_decoy: false,
+        bioauth_replays: ReplayGuard::default(),
+        coin_registry: CoinRegistry::default(),
+        upstream_costs: CostTracker::default(),
+        zklogin: ZkLoginVerifier::default(),
+        payload_versions: PayloadVersions::default(),
+        analysis_cache: AnalysisCache::default(),
+        screening: Screener::default(),
+        transfer_simulator: TransferSimulator::default(),
+        bioauth_gray_zone: GrayZone::default(),
+        registration_gate: RegistrationGate::ProofOfWork { difficulty_bits: 8 },
+    });
+    let enclave = spawn_enclave(state).await;
+    let client = reqwest::Client::new();
+
+    let gate: GateRequirement =
+        client.get(format!("{}/create_wallet/gate", enclave)).send().await.unwrap().json().await.unwrap();
+    assert_eq!(gate, GateRequirement::ProofOfWork { difficulty_bits: 8 });
+
+    // No work, or work done for another handle: nothing is signed
+    let resp = call(&client, format!("{}/create_wallet", enclave), json!({ "handle": "alice" })).await;
+    assert_eq!(resp.status(), 403);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["code"], "human_check_failed");
+    let nonce = solve_pow("alice", 8);
+    if pow_zero_bits("mallory", &nonce) < 8 {
+        let resp =
+            call(&client, format!("{}/create_wallet", enclave), json!({ "handle": "mallory", "pow_nonce": nonce })).await;
+        assert_eq!(resp.status(), 403);
+    }
+
+    let resp = call(&client, format!("{}/create_wallet", enclave), json!({ "handle": "alice", "pow_nonce": nonce })).await;
+    assert_eq!(resp.status(), 200);
+    let resp: CreateWalletResponse = resp.json().await.unwrap();
+    assert_eq!(resp.payload.handle, b"alice");
+}
+
+#[tokio::test]
+async fn test_openrouter_skipped_once_daily_budget_is_spent() {
+    let _env = UPSTREAM_ENV.lock().await;
+    let openrouter = MockServer::start().await;
+    set_endpoint_override(Endpoint::OpenRouter, Some(format!("{}/chat", openrouter.uri())));
+    let content = json!({ "transcript": "I confirm sending 5 SUI", "stress_level": 10, "amount": "5" }).to_string();
+    Mock::given(method("POST"))
+        .and(path("/chat"))
+        .and(body_string_contains(r#""usage":{"include":true}"#))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "choices": [{ "message": { "content": content } }],
+            "usage": { "prompt_tokens": 1500, "completion_tokens": 30, "cost": 0.05 }
+        })))
+        .expect(1)
+        .mount(&openrouter)
+        .await;
+
+    let state = Arc::new(AppState {
+        eph_kp: Ed25519KeyPair::generate(&mut rand::thread_rng()),
+        sui_rpc_url: String::new(),
+        openrouter_api_key: "test-key".to_string().into(),
+        hume_api_key: String::new().into(),
+        diagnostics_secret: String::new().into(),
+        bioauth_diagnostics: DiagnosticsStore::default(),
+        bioauth_jobs: JobQueue::default(),
+        bioauth_locks: HandleLocks::new(),
+        bioauth_shadow: ShadowMode::default(),
+        voice_baselines: BaselineStore::new(),
+        user_preferences: PreferenceStore::default(),
+        bioauth_challenges: ChallengeStore::new(std::time::Duration::from_secs(60), String::new()),
+        bioauth_attempts: AttemptTracker::new(),
+        bioauth_decoy: false,
+        bioauth_replays: ReplayGuard::default(),
+        coin_registry: CoinRegistry::default(),
+        upstream_costs: CostTracker::new(CostLimits {
+            openrouter_daily_micro_usd: Some(40_000),
+            ..CostLimits::default()
+        }),
+        zklogin: ZkLoginVerifier::default(),
+        payload_versions: PayloadVersions::default(),
+        analysis_cache: AnalysisCache::default(),
+        screening: Screener::default(),
+        transfer_simulator: TransferSimulator::default(),
+        bioauth_gray_zone: GrayZone::default(),
+        registration_gate: RegistrationGate::default(),
+    });
+    let enclave = spawn_enclave(state).await;
+    let client = reqwest::Client::new();
+    let bio_request = json!({
+        "handle": "alice",
+        "audio_base64": calm_wav_base64(),
+        "expected_amount": 5_000_000_000u64,
+        "coin_type": "0x2::sui::SUI",
+    });
+
+    // The first call spends $0.05 of the $0.04 budget; the second stays on DSP
+    for _ in 0..2 {
+        let resp = call(&client, format!("{}/bio_auth", enclave), bio_request.clone()).await;
+        assert_eq!(resp.status(), 200);
+    }
+
+    let metrics: UpstreamCostMetrics = client.get(format!("{}/metrics", enclave)).send().await.unwrap().json().await.unwrap();
+    assert_eq!(metrics.openrouter.requests, 1);
+    assert_eq!(metrics.openrouter.prompt_tokens, 1500);
+    assert_eq!(metrics.openrouter.cost_usd, 0.05);
+    assert!(metrics.openrouter.budget_exhausted);
+    assert_eq!(metrics.hume.requests, 0);
+}
+
+#[tokio::test]
+async fn test_duress_decoy_signs_ok_looking_payload() {
+    let _env = UPSTREAM_ENV.lock().await;
+    let openrouter = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/chat"))
+        .respond_with(openrouter_reply(95, 5.0))
+        .mount(&openrouter)
+        .await;
+    set_endpoint_override(Endpoint::OpenRouter, Some(format!("{}/chat", openrouter.uri())));
+
+    let eph_kp = Ed25519KeyPair::generate(&mut rand::thread_rng());
+    let pk = eph_kp.public().clone();
+    let state = Arc::new(AppState {
+        eph_kp,
+        sui_rpc_url: String::new(),
+        openrouter_api_key: "test-key".to_string().into(),
+        hume_api_key: String::new().into(),
+        diagnostics_secret: String::new().into(),
+        bioauth_diagnostics: DiagnosticsStore::default(),
+        bioauth_jobs: JobQueue::default(),
+        bioauth_locks: HandleLocks::new(),
+        bioauth_shadow: ShadowMode::default(),
+        voice_baselines: BaselineStore::new(),
+        user_preferences: PreferenceStore::default(),
+        bioauth_challenges: ChallengeStore::new(std::time::Duration::from_secs(60), String::new()),
+        bioauth_attempts: AttemptTracker::new(),
+        bioauth_decoy: true,
+        bioauth_replays: ReplayGuard::default(),
+        coin_registry: CoinRegistry::default(),
+        upstream_costs: CostTracker::default(),
+        zklogin: ZkLoginVerifier::default(),
+        payload_versions: PayloadVersions::default(),
+        analysis_cache: AnalysisCache::default(),
+        screening: Screener::default(),
+        transfer_simulator: TransferSimulator::default(),
+        bioauth_gray_zone: GrayZone::default(),
+        registration_gate: RegistrationGate::default(),
+    });
+    let enclave = spawn_enclave(state.clone()).await;
+    let client = reqwest::Client::new();
+
+    // Reads as OK; only the seal, opened by the contract, makes it lock the wallet
+    let body: Value = call(
+        &client,
+        format!("{}/bio_auth", enclave),
+        json!({
+            "handle": "alice",
+            "audio_base64": calm_wav_base64(),
+            "expected_amount": 5_000_000_000u64,
+        }),
+    )
+    .await
+    .json()
+    .await
+    .unwrap();
+    assert!(body.get("data").is_none());
+    assert!(body["payload"].get("decoy").is_none());
+    let resp: BioAuthResponse = serde_json::from_value(body).unwrap();
+    assert_eq!(resp.payload.result, BioAuthResult::Ok as u8);
+    assert_eq!(open_decoy_seal(&resp.payload.seal), Some(true));
+    let request_id = resp.request_id.clone();
+    assert!(verify(&pk, resp.payload, resp.timestamp_ms, IntentScope::TransferNft, &resp.signature));
+
+    let data = state.bioauth_diagnostics.get(&request_id).unwrap();
+    assert!(data.decoy);
+    assert!(data.locked);
+    assert_eq!(data.result, "duress");
+}
+
+/// Log sink for tests that check what the enclave writes to its logs
+#[derive(Clone, Default)]
+struct CapturedLogs(Arc<std::sync::Mutex<Vec<u8>>>);
+
+impl std::io::Write for CapturedLogs {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_panic_phrase_forces_duress_and_stays_secret() {
+    let _env = UPSTREAM_ENV.lock().await;
+    // Current-thread runtime: the in-process enclave logs into this subscriber
+    let logs = CapturedLogs::default();
+    let writer = logs.clone();
+    let _logging = tracing::subscriber::set_default(
+        tracing_subscriber::fmt().with_writer(move || writer.clone()).with_ansi(false).finish(),
+    );
+
+    // Calm voice, right amount, but the user slips in their panic phrase (slightly misheard)
+    let openrouter = MockServer::start().await;
+    let content = json!({
+        "transcript": "I confirm sending 5 SUI, blue herin sunrise",
+        "stress_level": 10,
+        "amount": 5.0,
+    })
+    .to_string();
+    Mock::given(method("POST"))
+        .and(path("/chat"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "choices": [{ "message": { "content": content } }]
+        })))
+        .mount(&openrouter)
+        .await;
+    set_endpoint_override(Endpoint::OpenRouter, Some(format!("{}/chat", openrouter.uri())));
+
+    let eph_kp = Ed25519KeyPair::generate(&mut rand::thread_rng());
+    let pk = eph_kp.public().clone();
+    let state = Arc::new(AppState {
+        eph_kp,
+        sui_rpc_url: String::new(),
+        openrouter_api_key: "test-key".to_string().into(),
+        hume_api_key: String::new().into(),
+        diagnostics_secret: String::new().into(),
+        bioauth_diagnostics: DiagnosticsStore::default(),
+        bioauth_jobs: JobQueue::default(),
+        bioauth_locks: HandleLocks::new(),
+        bioauth_shadow: ShadowMode::new(true, None),
+        voice_baselines: BaselineStore::new(),
+        user_preferences: PreferenceStore::default(),
+        bioauth_challenges: ChallengeStore::new(std::time::Duration::from_secs(60), String::new()),
+        bioauth_attempts: AttemptTracker::new(),
+        bioauth_decoy: false,
+        bioauth_replays: ReplayGuard::default(),
+        coin_registry: CoinRegistry::default(),
+        upstream_costs: CostTracker::default(),
+        zklogin: ZkLoginVerifier::default(),
+        payload_versions: PayloadVersions::default(),
+        analysis_cache: AnalysisCache::default(),
+        screening: Screener::default(),
+        transfer_simulator: TransferSimulator::default(),
+        bioauth_gray_zone: GrayZone::default(),
+        registration_gate: RegistrationGate::default(),
+    });
+    let enclave = spawn_enclave(state.clone()).await;
+    let client = reqwest::Client::new();
+
+    let resp = call(
+        &client,
+        format!("{}/bio_auth/enroll", enclave),
+        json!({ "handle": "alice", "audio_base64": calm_wav_base64(), "panic_phrase": "Blue heron sunrise" }),
+    )
+    .await;
+    assert_eq!(resp.status(), 200);
+    let progress: Value = resp.json().await.unwrap();
+    assert_eq!(progress["panic_phrase_set"], true);
+    assert!(!progress.to_string().to_lowercase().contains("heron"));
+
+    // Duress even in shadow mode, and the phrase is cut from the signed transcript
+    let resp: BioAuthResponse = call(
+        &client,
+        format!("{}/bio_auth", enclave),
+        json!({ "handle": "alice", "audio_base64": calm_wav_base64(), "expected_amount": 5_000_000_000u64 }),
+    )
+    .await
+    .json()
+    .await
+    .unwrap();
+    assert_eq!(resp.payload.result, BioAuthResult::Duress as u8);
+    assert_eq!(resp.payload.transcript, b"I confirm sending 5 SUI,");
+    let request_id = resp.request_id.clone();
+    assert!(verify(&pk, resp.payload, resp.timestamp_ms, IntentScope::TransferNft, &resp.signature));
+
+    let data = state.bioauth_diagnostics.get(&request_id).unwrap();
+    assert!(data.panic_phrase);
+    assert!(!data.transcript.contains("her"));
+
+    let logs = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap().to_lowercase();
+    assert!(logs.contains("panic phrase"));
+    assert!(!logs.contains("heron") && !logs.contains("herin") && !logs.contains("sunrise"));
+}
+
+#[tokio::test]
+async fn test_repeated_failures_start_a_cooldown() {
+    let _env = UPSTREAM_ENV.lock().await;
+    let openrouter = MockServer::start().await;
+    // The user keeps saying the wrong amount
+    Mock::given(method("POST"))
+        .and(path("/chat"))
+        .respond_with(openrouter_reply(10, 7.0))
+        .mount(&openrouter)
+        .await;
+    set_endpoint_override(Endpoint::OpenRouter, Some(format!("{}/chat", openrouter.uri())));
+
+    let state = Arc::new(AppState {
+        eph_kp: Ed25519KeyPair::generate(&mut rand::thread_rng()),
+        sui_rpc_url: String::new(),
+        openrouter_api_key: "test-key".to_string().into(),
+        hume_api_key: String::new().into(),
+        diagnostics_secret: String::new().into(),
+        bioauth_diagnostics: DiagnosticsStore::default(),
+        bioauth_jobs: JobQueue::default(),
+        bioauth_locks: HandleLocks::new(),
+        bioauth_shadow: ShadowMode::default(),
+        voice_baselines: BaselineStore::new(),
+        user_preferences: PreferenceStore::default(),
+        bioauth_challenges: ChallengeStore::new(std::time::Duration::from_secs(60), String::new()),
+        bioauth_attempts: AttemptTracker::new(),
+        bioauth_decoy: false,
+        bioauth_replays: ReplayGuard::default(),
+        coin_registry: CoinRegistry::default(),
+        upstream_costs: CostTracker::default(),
+        zklogin: ZkLoginVerifier::default(),
+        payload_versions: PayloadVersions::default(),
+        analysis_cache: AnalysisCache::default(),
+        screening: Screener::default(),
+        transfer_simulator: TransferSimulator::default(),
+        bioauth_gray_zone: GrayZone::default(),
+        registration_gate: RegistrationGate::default(),
+    });
+    let enclave = spawn_enclave(state).await;
+    let client = reqwest::Client::new();
+    // Every attempt is analyzed, not answered from the cache
+    let bio_request = json!({
+        "handle": "alice",
+        "audio_base64": calm_wav_base64(),
+        "expected_amount": 5_000_000_000u64,
+        "skip_analysis_cache": true,
+    });
+
+    // Each signed result carries the failures before it
+    for expected in 0..=FREE_FAILED_ATTEMPTS {
+        let resp: BioAuthResponse = call(&client, format!("{}/bio_auth", enclave), bio_request.clone())
+            .await
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(resp.payload.result, BioAuthResult::InvalidAmount as u8);
+        assert_eq!(resp.payload.failed_attempts, expected);
+    }
+
+    let resp = call(&client, format!("{}/bio_auth", enclave), bio_request).await;
+    assert_eq!(resp.status(), 429);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["code"], "rate_limited");
+    assert!(body["message"].as_str().unwrap().contains("try again in"));
+    // Rejected before any upstream analysis
+    assert_eq!(openrouter.received_requests().await.unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn test_coin_types_are_resolved_and_canonicalized() {
+    let _env = UPSTREAM_ENV.lock().await;
+    let usdc = "0xc0ffee::usdc::USDC";
+    let fullnode = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(body_string_contains("suix_getCoinMetadata"))
+        .and(body_string_contains(usdc.replace("0x", "0x0000000000000000000000000000000000000000000000000000000000")))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "result": { "decimals": 6, "symbol": "USDC", "name": "USD Coin" },
+        })))
+        .expect(1)
+        .mount(&fullnode)
+        .await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "jsonrpc": "2.0", "id": 1, "result": null })))
+        .mount(&fullnode)
+        .await;
+    // The LLM hears about the registry symbol, scaled by the registry decimals
+    let openrouter = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/chat"))
+        .and(body_string_contains("Expected amount: 2.5 USDC"))
+        .respond_with(openrouter_reply(10, 2.5))
+        .mount(&openrouter)
+        .await;
+    set_endpoint_override(Endpoint::OpenRouter, Some(format!("{}/chat", openrouter.uri())));
+    // Coin metadata comes from the network's public fullnode, not sui_rpc_url
+    set_endpoint_override(Endpoint::SuiFullnode, Some(fullnode.uri()));
+
+    let eph_kp = Ed25519KeyPair::generate(&mut rand::thread_rng());
+    let pk = eph_kp.public().clone();
+    let state = Arc::new(AppState {
+        eph_kp,
+        sui_rpc_url: String::new(),
+        openrouter_api_key: "test-key".to_string().into(),
+        hume_api_key: String::new().into(),
+        diagnostics_secret: String::new().into(),
+        bioauth_diagnostics: DiagnosticsStore::default(),
+        bioauth_jobs: JobQueue::default(),
+        bioauth_locks: HandleLocks::new(),
+        bioauth_shadow: ShadowMode::default(),
+        voice_baselines: BaselineStore::new(),
+        user_preferences: PreferenceStore::default(),
+        bioauth_challenges: ChallengeStore::new(std::time::Duration::from_secs(60), String::new()),
+        bioauth_attempts: AttemptTracker::new(),
+        bioauth_decoy: false,
+        bioauth_replays: ReplayGuard::default(),
+        coin_registry: CoinRegistry::new(Network::Localnet),
+        upstream_costs: CostTracker::default(),
+        zklogin: ZkLoginVerifier::default(),
+        payload_versions: PayloadVersions::default(),
+        analysis_cache: AnalysisCache::default(),
+        screening: Screener::default(),
+        transfer_simulator: TransferSimulator::default(),
+        bioauth_gray_zone: GrayZone::default(),
+        registration_gate: RegistrationGate::default(),
+    });
+    let enclave = spawn_enclave(state).await;
+    let client = reqwest::Client::new();
+
+    let challenge: ChallengeResponse = call(&client, format!("{}/bio_auth/challenge", enclave), json!({
+        "handle": "alice",
+        "expected_amount": 2_500_000u64,
+        "coin_type": usdc,
+    }))
+    .await
+    .json()
+    .await
+    .unwrap();
+    assert_eq!(challenge.phrase, "I confirm sending 2.5 USDC");
+
+    // Second lookup of the same coin is served from the cache
+    let resp: BioAuthResponse = call(&client, format!("{}/bio_auth", enclave), json!({
+        "handle": "alice",
+        "audio_base64": calm_wav_base64(),
+        "expected_amount": 2_500_000u64,
+        "coin_type": usdc,
+    }))
+    .await
+    .json()
+    .await
+    .unwrap();
+    assert_eq!(resp.payload.result, BioAuthResult::Ok as u8);
+
+    // Symbols and malformed tags fail validation, unregistered coins can't be resolved
+    for (coin_type, status) in [("SUI", 422), ("0x2::sui", 422), ("0xnothex::sui::SUI", 422), ("0xbad::fake::FAKE", 400)] {
+        let resp = call(&client, format!("{}/bio_auth/challenge", enclave), json!({
+            "handle": "alice",
+            "expected_amount": 1u64,
+            "coin_type": coin_type,
+        }))
+        .await;
+        assert_eq!(resp.status(), status, "{}", coin_type);
+    }
+
+    // Short and full tags sign the same bytes, in the form type_name::get<T>() produces
+    let long_sui = "0000000000000000000000000000000000000000000000000000000000000002::sui::SUI";
+    for coin_type in ["0x2::sui::SUI", long_sui] {
+        let resp: WithdrawResponse = call(&client, format!("{}/withdraw", enclave), json!({
+            "handle": "alice",
+            "amount": 1u64,
+            "coin_type": coin_type,
+        }))
+        .await
+        .json()
+        .await
+        .unwrap();
+        assert_eq!(resp.payload.coin_type, long_sui.as_bytes());
+        assert!(verify(&pk, resp.payload, resp.timestamp_ms, IntentScope::UpdateHandle, &resp.signature));
+    }
+    let resp = call(&client, format!("{}/transfer", enclave), json!({
+        "from_handle": "alice",
+        "to_handle": "bob",
+        "amount": 1u64,
+        "coin_type": "sui",
+    }))
+    .await;
+    assert_eq!(resp.status(), 422);
+    let resp = call(&client, format!("{}/transfer", enclave), json!({
+        "from_handle": "alice",
+        "to_handle": "bob",
+        "amount": 1u64,
+        "coin_type": "0x2::sui::SUI",
+        "memo": "x".repeat(129),
+    }))
+    .await;
+    assert_eq!(resp.status(), 422);
+    set_endpoint_override(Endpoint::SuiFullnode, None);
+}
+
+#[tokio::test]
+async fn test_expected_amount_follows_user_locale() {
+    let _env = UPSTREAM_ENV.lock().await;
+    // The model is told 1.234,5 SUI and answers in plain digits
+    let openrouter = MockServer::start().await;
+    let content = json!({
+        "transcript": "Ich bestätige eintausendzweihundertvierunddreißig Komma fünf SUI",
+        "stress_level": 10,
+        "amount": "1234.5",
+    })
+    .to_string();
+    Mock::given(method("POST"))
+        .and(path("/chat"))
+        .and(body_string_contains("Expected amount: 1.234,5 SUI"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "choices": [{ "message": { "content": content } }]
+        })))
+        .expect(1)
+        .mount(&openrouter)
+        .await;
+    set_endpoint_override(Endpoint::OpenRouter, Some(format!("{}/chat", openrouter.uri())));
+
+    let eph_kp = Ed25519KeyPair::generate(&mut rand::thread_rng());
+    let state = Arc::new(AppState {
+        eph_kp,
+        sui_rpc_url: String::new(),
+        openrouter_api_key: "test-key".to_string().into(),
+        hume_api_key: String::new().into(),
+        diagnostics_secret: String::new().into(),
+        bioauth_diagnostics: DiagnosticsStore::default(),
+        bioauth_jobs: JobQueue::default(),
+        bioauth_locks: HandleLocks::new(),
+        bioauth_shadow: ShadowMode::default(),
+        voice_baselines: BaselineStore::new(),
+        user_preferences: PreferenceStore::default(),
+        bioauth_challenges: ChallengeStore::new(std::time::Duration::from_secs(60), String::new()),
+        bioauth_attempts: AttemptTracker::new(),
+        bioauth_decoy: false,
+        bioauth_replays: ReplayGuard::default(),
+        coin_registry: CoinRegistry::default(),
+        upstream_costs: CostTracker::default(),
+        zklogin: ZkLoginVerifier::default(),
+        payload_versions: PayloadVersions::default(),
+        analysis_cache: AnalysisCache::default(),
+        screening: Screener::default(),
+        transfer_simulator: TransferSimulator::default(),
+        bioauth_gray_zone: GrayZone::default(),
+        registration_gate: RegistrationGate::default(),
+    });
+    let enclave = spawn_enclave(state.clone()).await;
+    let client = reqwest::Client::new();
+
+    let resp: BioAuthResponse = call(&client, format!("{}/bio_auth", enclave), json!({
+        "handle": "alice",
+        "audio_base64": calm_wav_base64(),
+        "expected_amount": 1_234_500_000_000u64,
+        "locale": "de-DE",
+    }))
+    .await
+    .json()
+    .await
+    .unwrap();
+    assert_eq!(resp.payload.result, BioAuthResult::Ok as u8);
+    assert_eq!(state.bioauth_diagnostics.get(&resp.request_id).unwrap().amount_display, "1.234,5 SUI");
+
+    // Typed amounts are read the locale's way too
+    let keystrokes: Vec<Value> = [0u64, 140, 390, 470, 700, 820, 1010, 1300, 1420, 1610, 1700, 1930]
+        .iter()
+        .map(|t| json!({ "down_ms": t, "up_ms": t + 80 }))
+        .collect();
+    let typed = |text: &'static str, locale: &'static str| {
+        call(
+            &client,
+            format!("{}/typed_auth", enclave),
+            json!({
+                "handle": "bob",
+                "typed_text": text,
+                "expected_amount": 1_234_500_000_000u64,
+                "locale": locale,
+                "keystrokes": keystrokes,
+            }),
+        )
+    };
+    let resp: BioAuthResponse = typed("1.234,5 SUI", "de-DE").await.json().await.unwrap();
+    assert_eq!(resp.payload.result, BioAuthResult::Ok as u8);
+    let resp: BioAuthResponse = typed("12,34.5 SUI", "en-US").await.json().await.unwrap();
+    assert_eq!(resp.payload.result, BioAuthResult::InvalidAmount as u8);
+}
+
+#[tokio::test]
+async fn test_invalid_requests_list_every_bad_field() {
+    let state = Arc::new(AppState {
+        eph_kp: Ed25519KeyPair::generate(&mut rand::thread_rng()),
+        sui_rpc_url: String::new(),
+        openrouter_api_key: String::new().into(),
+        hume_api_key: String::new().into(),
+        diagnostics_secret: String::new().into(),
+        bioauth_diagnostics: DiagnosticsStore::default(),
+        bioauth_jobs: JobQueue::default(),
+        bioauth_locks: HandleLocks::new(),
+        bioauth_shadow: ShadowMode::default(),
+        voice_baselines: BaselineStore::new(),
+        user_preferences: PreferenceStore::default(),
+        bioauth_challenges: ChallengeStore::new(std::time::Duration::from_secs(60), String::new()),
+        bioauth_attempts: AttemptTracker::new(),
+        bioauth_decoy: false,
+        bioauth_replays: ReplayGuard::default(),
+        coin_registry: CoinRegistry::default(),
+        upstream_costs: CostTracker::default(),
+        zklogin: ZkLoginVerifier::default(),
+        payload_versions: PayloadVersions::default(),
+        analysis_cache: AnalysisCache::default(),
+        screening: Screener::default(),
+        transfer_simulator: TransferSimulator::default(),
+        bioauth_gray_zone: GrayZone::default(),
+        registration_gate: RegistrationGate::default(),
+    });
+    let enclave = spawn_enclave(state).await;
+    let client = reqwest::Client::new();
+
+    // Every problem comes back at once, before any analysis
+    let resp = call(&client, format!("{}/bio_auth", enclave), json!({
+        "handle": "",
+        "audio_base64": "not audio",
+        "expected_amount": 0u64,
+        "coin_type": "SUI",
+        "locale": "de DE",
+    }))
+    .await;
+    assert_eq!(resp.status(), 422);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["code"], "validation_failed");
+    assert_eq!(body["message"], "Request validation failed");
+    assert!(!body["request_id"].as_str().unwrap().is_empty());
+    let fields: Vec<&str> = body["details"]["fields"].as_array().unwrap().iter().map(|f| f["field"].as_str().unwrap()).collect();
+    assert_eq!(
+        fields,
+        ["payload.handle", "payload.audio_base64", "payload.expected_amount", "payload.coin_type", "payload.locale"]
+    );
+
+    // Missing fields and broken JSON get the same shape; the backend's
+    // request ID is kept
+    let resp = client
+        .post(format!("{}/withdraw", enclave))
+        .header("x-request-id", "req-42")
+        .json(&json!({ "payload": { "handle": "alice" } }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 422);
+    assert_eq!(resp.headers()["x-request-id"], "req-42");
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["request_id"], "req-42");
+    assert_eq!(body["details"]["fields"][0]["field"], "body");
+    assert!(body["details"]["fields"][0]["message"].as_str().unwrap().contains("missing field `amount`"));
+
+    let resp = client
+        .post(format!("{}/create_wallet", enclave))
+        .header("content-type", "application/json")
+        .body("{\"payload\": ")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 422);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["details"]["fields"][0]["field"], "body");
+
+    // Messages follow Accept-Language; codes and field paths don't
+    let resp = client
+        .post(format!("{}/bio_auth/challenge", enclave))
+        .header("accept-language", "vi-VN,vi;q=0.9,en;q=0.8")
+        .json(&json!({ "payload": { "handle": "alice", "expected_amount": 0u64 } }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 422);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["code"], "validation_failed");
+    assert_eq!(body["message"], "Yêu cầu không hợp lệ");
+    assert_eq!(body["details"]["fields"][0]["field"], "payload.expected_amount");
+    assert_eq!(body["details"]["fields"][0]["message"], "phải lớn hơn 0");
+
+    let resp = client
+        .post(format!("{}/bio_auth/challenge", enclave))
+        .header("accept-language", "vi")
+        .json(&json!({ "payload": { "handle": "alice", "expected_amount": 5_000_000_000u64 } }))
+        .send()
+        .await
+        .unwrap();
+    let challenge: Value = resp.json().await.unwrap();
+    assert_eq!(challenge["phrase"], "Tôi xác nhận gửi 5 SUI");
+}
+
+/// (event, data) pairs of a finished SSE response
+async fn sse_events(resp: reqwest::Response) -> Vec<(String, Value)> {
+    assert_eq!(resp.headers()["content-type"], "text/event-stream");
+    let body = resp.text().await.unwrap();
+    body.split("\n\n")
+        .filter_map(|block| {
+            let field = |name: &str| {
+                block.lines().find_map(|line| line.strip_prefix(name).map(|v| v.trim_start().to_string()))
+            };
+            Some((field("event:")?, serde_json::from_str(&field("data:")?).unwrap()))
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_streamed_bio_auth_reports_each_stage() {
+    let _env = UPSTREAM_ENV.lock().await;
+    let openrouter = MockServer::start().await;
+    set_endpoint_override(Endpoint::OpenRouter, Some(format!("{}/chat", openrouter.uri())));
+    Mock::given(method("POST"))
+        .and(path("/chat"))
+        .respond_with(openrouter_reply(10, 5.0))
+        .mount(&openrouter)
+        .await;
+
+    let eph_kp = Ed25519KeyPair::generate(&mut rand::thread_rng());
+    let pk = eph_kp.public().clone();
+    let state = Arc::new(AppState {
+        eph_kp,
+        sui_rpc_url: String::new(),
+        openrouter_api_key: "test-key".to_string().into(),
+        hume_api_key: String::new().into(),
+        diagnostics_secret: String::new().into(),
+        bioauth_diagnostics: DiagnosticsStore::default(),
+        bioauth_jobs: JobQueue::default(),
+        bioauth_locks: HandleLocks::new(),
+        bioauth_shadow: ShadowMode::default(),
+        voice_baselines: BaselineStore::new(),
+        user_preferences: PreferenceStore::default(),
+        bioauth_challenges: ChallengeStore::new(std::time::Duration::from_secs(60), String::new()),
+        bioauth_attempts: AttemptTracker::new(),
+        bioauth_decoy: false,
+        bioauth_replays: ReplayGuard::new(std::time::Duration::from_secs(DEFAULT_REPLAY_WINDOW_DAYS * 86_400)),
+        coin_registry: CoinRegistry::default(),
+        upstream_costs: CostTracker::default(),
+        zklogin: ZkLoginVerifier::default(),
+        payload_versions: PayloadVersions::default(),
+        analysis_cache: AnalysisCache::default(),
+        screening: Screener::default(),
+        transfer_simulator: TransferSimulator::default(),
+        bioauth_gray_zone: GrayZone::default(),
+        registration_gate: RegistrationGate::default(),
+    });
+    let enclave = spawn_enclave(state).await;
+    let client = reqwest::Client::new();
+    let bio_request = json!({
+        "handle": "alice",
+        "audio_base64": calm_wav_base64(),
+        "expected_amount": 5_000_000_000u64,
+        "coin_type": "0x2::sui::SUI",
+    });
+
+    let events = sse_events(call(&client, format!("{}/bio_auth/stream", enclave), bio_request.clone()).await).await;
+    let stages: Vec<&str> = events
+        .iter()
+        .filter(|(event, _)| event == "stage")
+        .map(|(_, data)| data["stage"].as_str().unwrap())
+        .collect();
+    assert_eq!(stages, ["decoded", "transcribed", "stress_scored", "signed"]);
+
+    // Stages stay blind; the last event is the usual signed response
+    assert!(events.iter().all(|(event, data)| event != "stage" || data.as_object().unwrap().len() == 1));
+    let (event, data) = events.last().unwrap();
+    assert_eq!(event, "signed");
+    let resp: BioAuthResponse = serde_json::from_value(data.clone()).unwrap();
+    assert_eq!(resp.payload.result, BioAuthResult::Ok as u8);
+    assert!(verify(&pk, resp.payload, resp.timestamp_ms, IntentScope::TransferNft, &resp.signature));
+
+    // Failures end the stream with an error event carrying the HTTP status;
+    // here the same clip is replayed
+    let events = sse_events(call(&client, format!("{}/bio_auth/stream", enclave), bio_request).await).await;
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].0, "error");
+    assert_eq!(events[0].1["status"], 422);
+    assert_eq!(events[0].1["code"], "replay_detected");
+}
+
+#[tokio::test]
+async fn test_zklogin_link_refuses_stale_or_unproven_requests() {
+    let state = Arc::new(AppState {
+        eph_kp: Ed25519KeyPair::generate(&mut rand::thread_rng()),
+        sui_rpc_url: String::new(),
+        openrouter_api_key: String::new().into(),
+        hume_api_key: String::new().into(),
+        diagnostics_secret: String::new().into(),
+        bioauth_diagnostics: DiagnosticsStore::default(),
+        bioauth_jobs: JobQueue::default(),
+        bioauth_locks: HandleLocks::new(),
+        bioauth_shadow: ShadowMode::default(),
+        voice_baselines: BaselineStore::new(),
+        user_preferences: PreferenceStore::default(),
+        bioauth_challenges: ChallengeStore::new(std::time::Duration::from_secs(60), String::new()),
+        bioauth_attempts: AttemptTracker::new(),
+        bioauth_decoy: false,
+        bioauth_replays: ReplayGuard::default(),
+        coin_registry: CoinRegistry::default(),
+        upstream_costs: CostTracker::default(),
+        zklogin: ZkLoginVerifier::default(),
+        payload_versions: PayloadVersions::default(),
+        analysis_cache: AnalysisCache::default(),
+        screening: Screener::default(),
+        transfer_simulator: TransferSimulator::default(),
+        bioauth_gray_zone: GrayZone::default(),
+        registration_gate: RegistrationGate::default(),
+    });
+    let enclave = spawn_enclave(state).await;
+    let client = reqwest::Client::new();
+    let now_ms = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64;
+    let link = |zklogin_signature: &str, issued_at_ms: u64| {
+        json!({
+            "handle": "alice",
+            "wallet_address": format!("0x{}", "ab".repeat(32)),
+            "zklogin_signature": zklogin_signature,
+            "issued_at_ms": issued_at_ms,
+            "label": "google",
+        })
+    };
+    // A wallet signature is not a zkLogin signature (flag 0x00, not 0x05)
+    let wallet_signature = {
+        use base64::{engine::general_purpose::STANDARD, Engine as _};
+        STANDARD.encode([0u8; 97])
+    };
+
+    let resp = call(&client, format!("{}/link_zklogin", enclave), link("", now_ms)).await;
+    assert_eq!(resp.status(), 422);
+
+    // A message signed too long ago (or in the future) is never checked further
+    let stale = now_ms - LINK_MESSAGE_MAX_AGE_MS - 1_000;
+    for issued_at_ms in [stale, now_ms + 10 * 60 * 1000] {
+        let resp = call(&client, format!("{}/link_zklogin", enclave), link(&wallet_signature, issued_at_ms)).await;
+        assert_eq!(resp.status(), 400);
+        let body: Value = resp.json().await.unwrap();
+        assert!(body["message"].as_str().unwrap().contains("sign a new one"), "{}", body);
+    }
+
+    let resp = call(&client, format!("{}/link_zklogin", enclave), link(&wallet_signature, now_ms)).await;
+    assert_eq!(resp.status(), 400);
+    let body: Value = resp.json().await.unwrap();
+    assert!(body["message"].as_str().unwrap().contains("not a zkLogin signature"), "{}", body);
+}
+
+#[tokio::test]
+async fn test_set_limit_needs_a_calm_spoken_limit() {
+    let _env = UPSTREAM_ENV.lock().await;
+    let openrouter = MockServer::start().await;
+    set_endpoint_override(Endpoint::OpenRouter, Some(format!("{}/chat", openrouter.uri())));
+    // Stressed, then a transfer confirmation for the right amount, then the real thing
+    for (transcript, stress_level) in [
+        ("Set my daily limit to 50 SUI", 60),
+        ("I confirm sending 50 SUI", 10),
+        ("Set my daily limit to 50 SUI", 10),
+    ] {
+        let content = json!({ "transcript": transcript, "stress_level": stress_level, "amount": 50.0 }).to_string();
+        Mock::given(method("POST"))
+            .and(path("/chat"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "choices": [{ "message": { "content": content } }]
+            })))
+            .up_to_n_times(1)
+            .mount(&openrouter)
+            .await;
+    }
+
+    let eph_kp = Ed25519KeyPair::generate(&mut rand::thread_rng());
+    let pk = eph_kp.public().clone();
+    let state = Arc::new(AppState {
+        eph_kp,
+        sui_rpc_url: String::new(),
+        openrouter_api_key: "test-key".to_string().into(),
+        hume_api_key: String::new().into(),
+        diagnostics_secret: String::new().into(),
+        bioauth_diagnostics: DiagnosticsStore::default(),
+        bioauth_jobs: JobQueue::default(),
+        bioauth_locks: HandleLocks::new(),
+        bioauth_shadow: ShadowMode::default(),
+        voice_baselines: BaselineStore::new(),
+        user_preferences: PreferenceStore::default(),
+        bioauth_challenges: ChallengeStore::new(std::time::Duration::from_secs(60), String::new()),
+        bioauth_attempts: AttemptTracker::new(),
+        bioauth_decoy: false,
+        bioauth_replays: ReplayGuard::default(),
+        coin_registry: CoinRegistry::default(),
+        upstream_costs: CostTracker::default(),
+        zklogin: ZkLoginVerifier::default(),
+        payload_versions: PayloadVersions::default(),
+        analysis_cache: AnalysisCache::default(),
+        screening: Screener::default(),
+        transfer_simulator: TransferSimulator::default(),
+        bioauth_gray_zone: GrayZone::default(),
+        registration_gate: RegistrationGate::default(),
+    });
+    let enclave = spawn_enclave(state).await;
+    let client = reqwest::Client::new();
+    let request = json!({
+        "handle": "alice",
+        "audio_base64": calm_wav_base64(),
+        "daily_limit": 50_000_000_000u64,
+    });
+
+    // Both refusals look the same
+    for _ in 0..2 {
+        let resp = call(&client, format!("{}/set_limit", enclave), request.clone()).await;
+        assert_eq!(resp.status(), 403);
+        let body: Value = resp.json().await.unwrap();
+        assert_eq!(body["message"], "Daily limit was not confirmed");
+    }
+
+    let resp: SetLimitResponse = call(&client, format!("{}/set_limit", enclave), request)
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(resp.intent, IntentScope::SetLimit as u8);
+    assert_eq!(resp.payload.daily_limit, 50_000_000_000);
+    // As type_name::get<SUI>() reports it on-chain
+    assert_eq!(resp.payload.coin_type, format!("{:0>64}::sui::SUI", 2).into_bytes());
+    assert!(verify(&pk, resp.payload, resp.timestamp_ms, IntentScope::SetLimit, &resp.signature));
+}
+
+#[tokio::test]
+async fn test_freeze_is_instant_and_unfreeze_needs_the_spoken_code() {
+    let _env = UPSTREAM_ENV.lock().await;
+    let openrouter = MockServer::start().await;
+    set_endpoint_override(Endpoint::OpenRouter, Some(format!("{}/chat", openrouter.uri())));
+    let reply = |transcript: &str| {
+        let content = json!({ "transcript": transcript, "stress_level": 10, "amount": null }).to_string();
+        ResponseTemplate::new(200).set_body_json(json!({
+            "choices": [{ "message": { "content": content } }]
+        }))
+    };
+
+    let eph_kp = Ed25519KeyPair::generate(&mut rand::thread_rng());
+    let pk = eph_kp.public().clone();
+    let state = Arc::new(AppState {
+        eph_kp,
+        sui_rpc_url: String::new(),
+        openrouter_api_key: "test-key".to_string().into(),
+        hume_api_key: String::new().into(),
+        diagnostics_secret: String::new().into(),
+        bioauth_diagnostics: DiagnosticsStore::default(),
+        bioauth_jobs: JobQueue::default(),
+        bioauth_locks: HandleLocks::new(),
+        bioauth_shadow: ShadowMode::default(),
+        voice_baselines: BaselineStore::new(),
+        user_preferences: PreferenceStore::default(),
+        bioauth_challenges: ChallengeStore::new(std::time::Duration::from_secs(60), String::new()),
+        bioauth_attempts: AttemptTracker::new(),
+        bioauth_decoy: false,
+        bioauth_replays: ReplayGuard::default(),
+        coin_registry: CoinRegistry::default(),
+        upstream_costs: CostTracker::default(),
+        zklogin: ZkLoginVerifier::default(),
+        payload_versions: PayloadVersions::default(),
+        analysis_cache: AnalysisCache::default(),
+        screening: Screener::default(),
+        transfer_simulator: TransferSimulator::default(),
+        bioauth_gray_zone: GrayZone::default(),
+        registration_gate: RegistrationGate::default(),
+    });
+    let enclave = spawn_enclave(state).await;
+    let client = reqwest::Client::new();
+
+    // Freezing needs nothing but the handle, and no upstream call
+    let resp: FreezeWalletResponse = call(&client, format!("{}/freeze_wallet", enclave), json!({ "handle": "alice" }))
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(resp.intent, IntentScope::Freeze as u8);
+    assert_eq!(resp.payload.handle, b"alice".to_vec());
+    assert!(verify(&pk, resp.payload, resp.timestamp_ms, IntentScope::Freeze, &resp.signature));
+    assert!(openrouter.received_requests().await.unwrap().is_empty());
+
+    let challenge = || async {
+        let challenge: ChallengeResponse =
+            call(&client, format!("{}/unfreeze/challenge", enclave), json!({ "handle": "alice" }))
+                .await
+                .json()
+                .await
+                .unwrap();
+        challenge
+    };
+    let unfreeze = |challenge_id: String| {
+        json!({ "handle": "alice", "challenge_id": challenge_id, "audio_base64": calm_wav_base64() })
+    };
+
+    // The wrong code is refused, and the challenge can't be retried
+    let first = challenge().await;
+    assert!(first.phrase.starts_with("Unfreeze wallet alice, code "));
+    Mock::given(method("POST"))
+        .and(path("/chat"))
+        .respond_with(reply("Unfreeze wallet alice, code 0 0 0 0 0 0"))
+        .up_to_n_times(1)
+        .mount(&openrouter)
+        .await;
+    let resp = call(&client, format!("{}/request_unfreeze", enclave), unfreeze(first.challenge_id.clone())).await;
+    assert_eq!(resp.status(), 403);
+    let resp = call(&client, format!("{}/request_unfreeze", enclave), unfreeze(first.challenge_id)).await;
+    assert_eq!(resp.status(), 404);
+
+    let second = challenge().await;
+    Mock::given(method("POST"))
+        .and(path("/chat"))
+        .respond_with(reply(&second.phrase))
+        .mount(&openrouter)
+        .await;
+    let resp: UnfreezeResponse = call(&client, format!("{}/request_unfreeze", enclave), unfreeze(second.challenge_id))
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(resp.intent, IntentScope::Unfreeze as u8);
+    assert!(verify(&pk, resp.payload, resp.timestamp_ms, IntentScope::Unfreeze, &resp.signature));
+}
+
+#[tokio::test]
+async fn test_set_beneficiary_signs_a_valid_timeout_or_a_removal() {
+    let eph_kp = Ed25519KeyPair::generate(&mut rand::thread_rng());
+    let pk = eph_kp.public().clone();
+    let state = Arc::new(AppState {
+        eph_kp,
+        sui_rpc_url: String::new(),
+        openrouter_api_key: String::new().into(),
+        hume_api_key: String::new().into(),
+        diagnostics_secret: String::new().into(),
+        bioauth_diagnostics: DiagnosticsStore::default(),
+        bioauth_jobs: JobQueue::default(),
+        bioauth_locks: HandleLocks::new(),
+        bioauth_shadow: ShadowMode::default(),
+        voice_baselines: BaselineStore::new(),
+        user_preferences: PreferenceStore::default(),
+        bioauth_challenges: ChallengeStore::new(std::time::Duration::from_secs(60), String::new()),
+        bioauth_attempts: AttemptTracker::new(),
+        bioauth_decoy: false,
+        bioauth_replays: ReplayGuard::default(),
+        coin_registry: CoinRegistry::default(),
+        upstream_costs: CostTracker::default(),
+        zklogin: ZkLoginVerifier::default(),
+        payload_versions: PayloadVersions::default(),
+        analysis_cache: AnalysisCache::default(),
+        screening: Screener::default(),
+        transfer_simulator: TransferSimulator::default(),
+        bioauth_gray_zone: GrayZone::default(),
+        registration_gate: RegistrationGate::default(),
+    });
+    let enclave = spawn_enclave(state).await;
+    let client = reqwest::Client::new();
+    let set = |beneficiary_handle: &str, inactivity_timeout_ms: u64| {
+        call(&client, format!("{}/set_beneficiary", enclave), json!({
+            "handle": "alice",
+            "beneficiary_handle": beneficiary_handle,
+            "inactivity_timeout_ms": inactivity_timeout_ms,
+        }))
+    };
+
+    let resp: SetBeneficiaryResponse = set("bob", 90 * 86_400_000).await.json().await.unwrap();
+    assert_eq!(resp.intent, IntentScope::Beneficiary as u8);
+    assert_eq!(resp.payload.beneficiary_handle, b"bob".to_vec());
+    assert_eq!(resp.payload.inactivity_timeout_ms, 90 * 86_400_000);
+    assert!(verify(&pk, resp.payload, resp.timestamp_ms, IntentScope::Beneficiary, &resp.signature));
+
+    // The contract would reject a timeout under 30 days, and naming yourself is pointless
+    assert_eq!(set("bob", 86_400_000).await.status(), 422);
+    assert_eq!(set("alice", MIN_INACTIVITY_TIMEOUT_MS).await.status(), 422);
+
+    // Removal always signs a zero timeout
+    let resp: SetBeneficiaryResponse = set("", 86_400_000).await.json().await.unwrap();
+    assert!(resp.payload.beneficiary_handle.is_empty());
+    assert_eq!(resp.payload.inactivity_timeout_ms, 0);
+    assert!(verify(&pk, resp.payload, resp.timestamp_ms, IntentScope::Beneficiary, &resp.signature));
+}
+
+/// core.move's `Versioned<P>`: the layout v2 payloads are signed in
+#[derive(Debug, Serialize)]
+struct Versioned<P> {
+    version: u8,
+    payload: P,
+}
+
+/// core.move's `Domain<P>`: the layout v3 payloads are signed in
+#[derive(Debug, Serialize)]
+struct Domain<P> {
+    version: u8,
+    package: [u8; 32],
+    payload: P,
+}
+
+#[tokio::test]
+async fn test_payload_version_is_chosen_per_request() {
+    let eph_kp = Ed25519KeyPair::generate(&mut rand::thread_rng());
+    let pk = eph_kp.public().clone();
+    let state = |eph_kp, payload_versions| {
+        Arc::new(AppState {
+            eph_kp,
+            sui_rpc_url: String::new(),
+            openrouter_api_key: String::new().into(),
+            hume_api_key: String::new().into(),
+            diagnostics_secret: String::new().into(),
+            bioauth_diagnostics: DiagnosticsStore::default(),
+            bioauth_jobs: JobQueue::default(),
+            bioauth_locks: HandleLocks::new(),
+            bioauth_shadow: ShadowMode::default(),
+            voice_baselines: BaselineStore::new(),
+        user_preferences: PreferenceStore::default(),
+            bioauth_challenges: ChallengeStore::new(std::time::Duration::from_secs(60), String::new()),
+            bioauth_attempts: AttemptTracker::new(),
+            bioauth_decoy: false,
+            bioauth_replays: ReplayGuard::default(),
+            coin_registry: CoinRegistry::default(),
+            upstream_costs: CostTracker::default(),
+            zklogin: ZkLoginVerifier::default(),
+            payload_versions,
+            analysis_cache: AnalysisCache::default(),
+        screening: Screener::default(),
+        transfer_simulator: TransferSimulator::default(),
+        bioauth_gray_zone: GrayZone::default(),
+        registration_gate: RegistrationGate::default(),
+        })
+    };
+    let client = reqwest::Client::new();
+    let transfer = |enclave: String, payload_version: Option<u8>| {
+        let mut body = json!({ "payload": {
+            "from_handle": "alice",
+            "to_handle": "bob",
+            "amount": 5,
+            "coin_type": "0x2::sui::SUI",
+        }});
+        if let Some(version) = payload_version {
+            body["payload_version"] = json!(version);
+        }
+        client.post(format!("{}/transfer", enclave)).json(&body).send()
+    };
+
+    // Migration window: v1 unless the request asks for v2
+    let enclave = spawn_enclave(state(eph_kp, PayloadVersions::default())).await;
+    let resp: TransferResponse = transfer(enclave.clone(), None).await.unwrap().json().await.unwrap();
+    assert_eq!(resp.payload_version, 1);
+    assert!(verify(&pk, resp.payload, resp.timestamp_ms, IntentScope::TransferCoin, &resp.signature));
+
+    let resp: TransferResponse = transfer(enclave.clone(), Some(2)).await.unwrap().json().await.unwrap();
+    assert_eq!(resp.payload_version, 2);
+    let versioned = Versioned { version: 2, payload: resp.payload.clone() };
+    assert!(verify(&pk, versioned, resp.timestamp_ms, IntentScope::TransferCoin, &resp.signature));
+    assert!(!verify(&pk, resp.payload, resp.timestamp_ms, IntentScope::TransferCoin, &resp.signature));
+
+    // v3 binds the package of the chain signed for (the default localnet one here)
+    let resp: TransferResponse = transfer(enclave.clone(), Some(3)).await.unwrap().json().await.unwrap();
+    assert_eq!(resp.payload_version, 3);
+    let package: [u8; 32] = Hex::decode(current_chain().package_id().trim_start_matches("0x")).unwrap().try_into().unwrap();
+    let domain = Domain { version: 3, package, payload: resp.payload.clone() };
+    assert!(verify(&pk, domain, resp.timestamp_ms, IntentScope::TransferCoin, &resp.signature));
+    let other_chain = Domain { version: 3, package: [1; 32], payload: resp.payload.clone() };
+    assert!(!verify(&pk, other_chain, resp.timestamp_ms, IntentScope::TransferCoin, &resp.signature));
+
+    let resp = transfer(enclave, Some(4)).await.unwrap();
+    assert_eq!(resp.status(), 422);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["details"]["fields"][0]["field"], "payload_version");
+
+    // Past the v1 sunset, unversioned requests get v2 and v1 is refused
+    let enclave = spawn_enclave(state(Ed25519KeyPair::generate(&mut rand::thread_rng()), PayloadVersions::new(PayloadVersion::V1, Some(1)))).await;
+    let resp: TransferResponse = transfer(enclave.clone(), None).await.unwrap().json().await.unwrap();
+    assert_eq!(resp.payload_version, 2);
+    let resp = transfer(enclave, Some(1)).await.unwrap();
+    assert_eq!(resp.status(), 422);
+}
+
+#[tokio::test]
+async fn test_analysis_load_is_shed_with_retry_after() {
+    let _env = UPSTREAM_ENV.lock().await;
+    let openrouter = MockServer::start().await;
+    // Slow analysis so both requests overlap
+    Mock::given(method("POST"))
+        .and(path("/chat"))
+        .respond_with(openrouter_reply(10, 5.0).set_delay(std::time::Duration::from_millis(500)))
+        .mount(&openrouter)
+        .await;
+    set_endpoint_override(Endpoint::OpenRouter, Some(format!("{}/chat", openrouter.uri())));
+
+    let spawn = |jobs: JobQueue| async move {
+        let state = Arc::new(AppState {
+            eph_kp: Ed25519KeyPair::generate(&mut rand::thread_rng()),
+            sui_rpc_url: String::new(),
+            openrouter_api_key: "test-key".to_string().into(),
+            hume_api_key: String::new().into(),
+            diagnostics_secret: String::new().into(),
+            bioauth_diagnostics: DiagnosticsStore::default(),
+            bioauth_jobs: jobs,
+            bioauth_locks: HandleLocks::new(),
+            bioauth_shadow: ShadowMode::default(),
+            voice_baselines: BaselineStore::new(),
+        user_preferences: PreferenceStore::default(),
+            bioauth_challenges: ChallengeStore::new(std::time::Duration::from_secs(60), String::new()),
+            bioauth_attempts: AttemptTracker::new(),
+            bioauth_decoy: false,
+            bioauth_replays: ReplayGuard::default(),
+            coin_registry: CoinRegistry::default(),
+            upstream_costs: CostTracker::default(),
+            zklogin: ZkLoginVerifier::default(),
+            payload_versions: PayloadVersions::default(),
+            analysis_cache: AnalysisCache::default(),
+        screening: Screener::default(),
+        transfer_simulator: TransferSimulator::default(),
+        bioauth_gray_zone: GrayZone::default(),
+        registration_gate: RegistrationGate::default(),
+        });
+        let limits = AnalysisLimits { max_concurrent: 1, shed_queue_depth: None, retry_after_secs: 3 };
+        let app = shed_analysis_load(
+            Router::new().route("/bio_auth", post(process_bio_auth)),
+            state.clone(),
+            limits,
+        )
+        .route("/bio_auth/queue", get(get_bio_auth_queue))
+        .with_state(state);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let enclave = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            axum::serve(listener, app.into_make_service()).await.unwrap();
+        });
+        enclave
+    };
+    let client = reqwest::Client::new();
+    let request = |enclave: &str, handle: &str| {
+        call(
+            &client,
+            format!("{}/bio_auth", enclave),
+            json!({
+                "handle": handle,
+                "audio_base64": calm_wav_base64(),
+                "expected_amount": 5_000_000_000u64,
+            }),
+        )
+    };
+    let shed_count = |enclave: String| {
+        let client = client.clone();
+        async move {
+            let metrics: Value =
+                client.get(format!("{}/bio_auth/queue", enclave)).send().await.unwrap().json().await.unwrap();
+            metrics["shed"].clone()
+        }
+    };
+
+    // Inline analysis: the one slot is taken, so the second upload is shed at once
+    let enclave = spawn(JobQueue::default()).await;
+    let (a, b) = tokio::join!(request(&enclave, "alice"), async {
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        request(&enclave, "bob").await
+    });
+    assert_eq!(a.status(), 200);
+    assert_eq!(b.status(), 429);
+    assert_eq!(b.headers()["retry-after"], "3");
+    let body: Value = b.json().await.unwrap();
+    assert_eq!(body["code"], "rate_limited");
+    assert_eq!(shed_count(enclave.clone()).await, 1);
+
+    // The slot is free again once the first analysis finishes
+    assert_eq!(request(&enclave, "bob").await.status(), 200);
+
+    // Queued mode with no workers: once the queue is full uploads are shed
+    let enclave = spawn(JobQueue::new(true, 1, std::time::Duration::from_secs(60))).await;
+    assert_eq!(request(&enclave, "alice").await.status(), 202);
+    let shed = request(&enclave, "bob").await;
+    assert_eq!(shed.status(), 429);
+    assert_eq!(shed.headers()["retry-after"], "3");
+    assert_eq!(shed_count(enclave).await, 1);
+}
+
+#[tokio::test]
+async fn test_hume_streams_scores_and_falls_back_to_batch() {
+    let _env = UPSTREAM_ENV.lock().await;
+    let openrouter = MockServer::start().await;
+    let hume = MockServer::start().await;
+    set_endpoint_override(Endpoint::OpenRouter, Some(format!("{}/chat", openrouter.uri())));
+    set_endpoint_override(Endpoint::Hume, Some(format!("{}/jobs", hume.uri())));
+    std::env::set_var("HUME_MODE", "stream");
+    std::env::set_var("HUME_STREAM_TIMEOUT_MS", "300");
+
+    // The LLM hears a calm voice; only Hume's fear and distress flag duress
+    Mock::given(method("POST"))
+        .and(path("/chat"))
+        .respond_with(openrouter_reply(10, 5.0))
+        .mount(&openrouter)
+        .await;
+    let fearful = json!([
+        { "name": "Fear", "score": 0.95 },
+        { "name": "Distress", "score": 0.9 },
+        { "name": "Anxiety", "score": 0.9 },
+    ]);
+    Mock::given(method("POST"))
+        .and(path("/jobs"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "predictions": [{ "models": { "prosody": { "grouped_predictions": [{
+                "predictions": [{ "emotions": fearful }]
+            }]}}}]
+        })))
+        .mount(&hume)
+        .await;
+
+    let state = Arc::new(AppState {
+        eph_kp: Ed25519KeyPair::generate(&mut rand::thread_rng()),
+        sui_rpc_url: String::new(),
+        openrouter_api_key: "test-key".to_string().into(),
+        hume_api_key: "test-key".to_string().into(),
+        diagnostics_secret: String::new().into(),
+        bioauth_diagnostics: DiagnosticsStore::default(),
+        bioauth_jobs: JobQueue::default(),
+        bioauth_locks: HandleLocks::new(),
+        bioauth_shadow: ShadowMode::default(),
+        voice_baselines: BaselineStore::new(),
+        user_preferences: PreferenceStore::default(),
+        bioauth_challenges: ChallengeStore::new(std::time::Duration::from_secs(60), String::new()),
+        bioauth_attempts: AttemptTracker::new(),
+        bioauth_decoy: false,
+        bioauth_replays: ReplayGuard::default(),
+        coin_registry: CoinRegistry::default(),
+        upstream_costs: CostTracker::default(),
+        zklogin: ZkLoginVerifier::default(),
+        payload_versions: PayloadVersions::default(),
+        analysis_cache: AnalysisCache::default(),
+        screening: Screener::default(),
+        transfer_simulator: TransferSimulator::default(),
+        bioauth_gray_zone: GrayZone::default(),
+        registration_gate: RegistrationGate::default(),
+    });
+    let enclave = spawn_enclave(state).await;
+    let client = reqwest::Client::new();
+    let bio_request = |handle: &str| {
+        json!({
+            "handle": handle,
+            "audio_base64": calm_wav_base64(),
+            "expected_amount": 5_000_000_000u64,
+            "coin_type": "0x2::sui::SUI",
+        })
+    };
+
+    // 1. Streamed scores decide without a batch job
+    let stream = spawn_hume_stream(Some(json!({ "prosody": { "predictions": [{ "emotions": fearful }] } }))).await;
+    set_endpoint_override(Endpoint::HumeStream, Some(stream));
+    let resp: BioAuthResponse = call(&client, format!("{}/bio_auth", enclave), bio_request("alice"))
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(resp.payload.result, BioAuthResult::Duress as u8);
+    assert!(hume.received_requests().await.unwrap().is_empty());
+
+    // 2. A stream that never answers times out and the batch job decides
+    set_endpoint_override(Endpoint::HumeStream, Some(spawn_hume_stream(None).await));
+    let resp: BioAuthResponse = call(&client, format!("{}/bio_auth", enclave), bio_request("bob"))
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(resp.payload.result, BioAuthResult::Duress as u8);
+    assert_eq!(hume.received_requests().await.unwrap().len(), 1);
+
+    let metrics: UpstreamCostMetrics = client.get(format!("{}/metrics", enclave)).send().await.unwrap().json().await.unwrap();
+    assert_eq!(metrics.hume.requests, 2);
+}
+
+#[tokio::test]
+async fn test_transient_openrouter_failure_is_retried() {
+    let _env = UPSTREAM_ENV.lock().await;
+    let openrouter = MockServer::start().await;
+    set_endpoint_override(Endpoint::OpenRouter, Some(format!("{}/chat", openrouter.uri())));
+    Mock::given(method("POST"))
+        .and(path("/chat"))
+        .respond_with(ResponseTemplate::new(503))
+        .up_to_n_times(1)
+        .mount(&openrouter)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/chat"))
+        .respond_with(openrouter_reply(10, 5.0))
+        .mount(&openrouter)
+        .await;
+
+    let state = Arc::new(AppState {
+        eph_kp: Ed25519KeyPair::generate(&mut rand::thread_rng()),
+        sui_rpc_url: String::new(),
+        openrouter_api_key: "test-key".to_string().into(),
+        hume_api_key: String::new().into(),
+        diagnostics_secret: String::new().into(),
+        bioauth_diagnostics: DiagnosticsStore::default(),
+        bioauth_jobs: JobQueue::default(),
+        bioauth_locks: HandleLocks::new(),
+        bioauth_shadow: ShadowMode::default(),
+        voice_baselines: BaselineStore::new(),
+        user_preferences: PreferenceStore::default(),
+        bioauth_challenges: ChallengeStore::new(std::time::Duration::from_secs(60), String::new()),
+        bioauth_attempts: AttemptTracker::new(),
+        bioauth_decoy: false,
+        bioauth_replays: ReplayGuard::default(),
+        coin_registry: CoinRegistry::default(),
+        upstream_costs: CostTracker::default(),
+        zklogin: ZkLoginVerifier::default(),
+        payload_versions: PayloadVersions::default(),
+        analysis_cache: AnalysisCache::default(),
+        screening: Screener::default(),
+        transfer_simulator: TransferSimulator::default(),
+        bioauth_gray_zone: GrayZone::default(),
+        registration_gate: RegistrationGate::default(),
+    });
+    let enclave = spawn_enclave(state).await;
+    let client = reqwest::Client::new();
+
+    // The 503 is retried under the default OpenRouter policy, so the LLM's
+    // transcript (not the mock fallback) verifies the amount
+    let resp: BioAuthResponse = call(
+        &client,
+        format!("{}/bio_auth", enclave),
+        json!({
+            "handle": "alice",
+            "audio_base64": calm_wav_base64(),
+            "expected_amount": 5_000_000_000u64,
+            "coin_type": "0x2::sui::SUI",
+        }),
+    )
+    .await
+    .json()
+    .await
+    .unwrap();
+    assert_eq!(resp.payload.result, BioAuthResult::Ok as u8);
+    assert_eq!(openrouter.received_requests().await.unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn test_unsure_analysis_is_not_signed() {
+    let _env = UPSTREAM_ENV.lock().await;
+    let openrouter = MockServer::start().await;
+    set_endpoint_override(Endpoint::OpenRouter, Some(format!("{}/chat", openrouter.uri())));
+
+    let state = Arc::new(AppState {
+        eph_kp: Ed25519KeyPair::generate(&mut rand::thread_rng()),
+        sui_rpc_url: String::new(),
+        openrouter_api_key: "test-key".to_string().into(),
+        hume_api_key: String::new().into(),
+        diagnostics_secret: String::new().into(),
+        bioauth_diagnostics: DiagnosticsStore::default(),
+        bioauth_jobs: JobQueue::default(),
+        bioauth_locks: HandleLocks::new(),
+        bioauth_shadow: ShadowMode::default(),
+        voice_baselines: BaselineStore::new(),
+        user_preferences: PreferenceStore::default(),
+        bioauth_challenges: ChallengeStore::new(std::time::Duration::from_secs(60), String::new()),
+        bioauth_attempts: AttemptTracker::new(),
+        bioauth_decoy: false,
+        bioauth_replays: ReplayGuard::default(),
+        coin_registry: CoinRegistry::default(),
+        upstream_costs: CostTracker::default(),
+        zklogin: ZkLoginVerifier::default(),
+        payload_versions: PayloadVersions::default(),
+        analysis_cache: AnalysisCache::default(),
+        screening: Screener::default(),
+        transfer_simulator: TransferSimulator::default(),
+        bioauth_gray_zone: GrayZone::default(),
+        registration_gate: RegistrationGate::default(),
+    });
+    let enclave = spawn_enclave(state.clone()).await;
+    let client = reqwest::Client::new();
+    let bio_auth = |stress_level: u8, confidence: [f32; 3]| {
+        let openrouter = &openrouter;
+        let client = &client;
+        let enclave = &enclave;
+        async move {
+            let content = json!({
+                "transcript": "I confirm sending 5 SUI",
+                "stress_level": stress_level,
+                "amount": "5",
+                "transcript_confidence": confidence[0],
+                "amount_confidence": confidence[1],
+                "stress_confidence": confidence[2],
+            });
+            openrouter.reset().await;
+            Mock::given(method("POST"))
+                .and(path("/chat"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                    "choices": [{ "message": { "content": content.to_string() } }]
+                })))
+                .mount(openrouter)
+                .await;
+            call(
+                client,
+                format!("{}/bio_auth", enclave),
+                json!({
+                    "handle": "alice",
+                    "audio_base64": calm_wav_base64(),
+                    "expected_amount": 5_000_000_000u64,
+                    "skip_analysis_cache": true,
+                }),
+            )
+            .await
+        }
+    };
+
+    // Unsure of the stress score, or of the amount: nothing is signed
+    for (stress_level, confidence, short) in [(90, [0.9, 0.9, 0.2], "(stress)"), (10, [0.9, 0.3, 0.9], "(amount)")] {
+        let resp = bio_auth(stress_level, confidence).await;
+        assert_eq!(resp.status(), 422);
+        let body: Value = resp.json().await.unwrap();
+        assert_eq!(body["code"], "analysis_inconclusive");
+        assert!(body["message"].as_str().unwrap().contains(short), "{}", body);
+    }
+
+    // Sure of duress, the words don't matter
+    let resp: BioAuthResponse = bio_auth(90, [0.2, 0.2, 0.9]).await.json().await.unwrap();
+    assert_eq!(resp.payload.result, BioAuthResult::Duress as u8);
+
+    // Sure of everything; the inconclusive tries weren't failed attempts
+    let resp: BioAuthResponse = bio_auth(10, [0.9, 0.9, 0.9]).await.json().await.unwrap();
+    assert_eq!(resp.payload.result, BioAuthResult::Ok as u8);
+    assert_eq!(resp.payload.failed_attempts, 0);
+}
+
+#[tokio::test]
+async fn test_borderline_stress_gets_a_second_pass() {
+    let _env = UPSTREAM_ENV.lock().await;
+    let openrouter = MockServer::start().await;
+    set_endpoint_override(Endpoint::OpenRouter, Some(format!("{}/chat", openrouter.uri())));
+
+    let state = Arc::new(AppState {
+        eph_kp: Ed25519KeyPair::generate(&mut rand::thread_rng()),
+        sui_rpc_url: String::new(),
+        openrouter_api_key: "test-key".to_string().into(),
+        hume_api_key: String::new().into(),
+        diagnostics_secret: String::new().into(),
+        bioauth_diagnostics: DiagnosticsStore::default(),
+        bioauth_jobs: JobQueue::default(),
+        bioauth_locks: HandleLocks::new(),
+        bioauth_shadow: ShadowMode::default(),
+        voice_baselines: BaselineStore::new(),
+        user_preferences: PreferenceStore::default(),
+        bioauth_challenges: ChallengeStore::new(std::time::Duration::from_secs(60), String::new()),
+        bioauth_attempts: AttemptTracker::new(),
+        bioauth_decoy: false,
+        bioauth_replays: ReplayGuard::default(),
+        coin_registry: CoinRegistry::default(),
+        upstream_costs: CostTracker::default(),
+        zklogin: ZkLoginVerifier::default(),
+        payload_versions: PayloadVersions::default(),
+        analysis_cache: AnalysisCache::default(),
+        screening: Screener::default(),
+        transfer_simulator: TransferSimulator::default(),
+        bioauth_gray_zone: GrayZone::default(),
+        registration_gate: RegistrationGate::default(),
+    });
+    let enclave = spawn_enclave(state).await;
+    let client = reqwest::Client::new();
+    let bio_auth = |handle: &'static str, stress_level: u8| {
+        let openrouter = &openrouter;
+        let client = &client;
+        let enclave = &enclave;
+        async move {
+            openrouter.reset().await;
+            Mock::given(method("POST"))
+                .and(path("/chat"))
+                .respond_with(openrouter_reply(stress_level, 5.0))
+                .mount(openrouter)
+                .await;
+            let resp = call(
+                client,
+                format!("{}/bio_auth", enclave),
+                json!({
+                    "handle": handle,
+                    "audio_base64": calm_wav_base64(),
+                    "expected_amount": 5_000_000_000u64,
+                    "coin_type": "0x2::sui::SUI",
+                }),
+            )
+            .await;
+            resp.json::<BioAuthResponse>().await.unwrap()
+        }
+    };
+
+    // 64 is borderline; the denoised steady tone scores calm, so the mean clears it
+    let resp = bio_auth("alice", 64).await;
+    assert_eq!(resp.payload.result, BioAuthResult::Ok as u8);
+
+    // Clear duress isn't second-guessed
+    let resp = bio_auth("bob", 90).await;
+    assert_eq!(resp.payload.result, BioAuthResult::Duress as u8);
+
+    let metrics: UpstreamCostMetrics = client.get(format!("{}/metrics", enclave)).send().await.unwrap().json().await.unwrap();
+    let gray_zone = metrics.gray_zone;
+    assert_eq!((gray_zone.low, gray_zone.high), (Some(60), Some(75)));
+    assert_eq!((gray_zone.analyzed, gray_zone.borderline, gray_zone.cleared, gray_zone.escalated), (2, 1, 1, 0));
+    assert_eq!(gray_zone.borderline_rate, 0.5);
+}
+
+#[tokio::test]
+async fn test_identical_clip_reuses_the_cached_analysis() {
+    let _env = UPSTREAM_ENV.lock().await;
+    let openrouter = MockServer::start().await;
+    set_endpoint_override(Endpoint::OpenRouter, Some(format!("{}/chat", openrouter.uri())));
+    Mock::given(method("POST"))
+        .and(path("/chat"))
+        .respond_with(openrouter_reply(10, 5.0))
+        .mount(&openrouter)
+        .await;
+
+    let state = Arc::new(AppState {
+        eph_kp: Ed25519KeyPair::generate(&mut rand::thread_rng()),
+        sui_rpc_url: String::new(),
+        openrouter_api_key: "test-key".to_string().into(),
+        hume_api_key: String::new().into(),
+        diagnostics_secret: String::new().into(),
+        bioauth_diagnostics: DiagnosticsStore::default(),
+        bioauth_jobs: JobQueue::default(),
+        bioauth_locks: HandleLocks::new(),
+        bioauth_shadow: ShadowMode::default(),
+        voice_baselines: BaselineStore::new(),
+        user_preferences: PreferenceStore::default(),
+        bioauth_challenges: ChallengeStore::new(std::time::Duration::from_secs(60), String::new()),
+        bioauth_attempts: AttemptTracker::new(),
+        bioauth_decoy: false,
+        bioauth_replays: ReplayGuard::default(),
+        coin_registry: CoinRegistry::default(),
+        upstream_costs: CostTracker::default(),
+        zklogin: ZkLoginVerifier::default(),
+        payload_versions: PayloadVersions::default(),
+        analysis_cache: AnalysisCache::default(),
+        screening: Screener::default(),
+        transfer_simulator: TransferSimulator::default(),
+        bioauth_gray_zone: GrayZone::default(),
+        registration_gate: RegistrationGate::default(),
+    });
+    let enclave = spawn_enclave(state).await;
+    let client = reqwest::Client::new();
+    let bio_auth = |skip_analysis_cache: bool| {
+        call(
+            &client,
+            format!("{}/bio_auth", enclave),
+            json!({
+                "handle": "alice",
+                "audio_base64": calm_wav_base64(),
+                "expected_amount": 5_000_000_000u64,
+                "skip_analysis_cache": skip_analysis_cache,
+            }),
+        )
+    };
+
+    // The second send of the same clip is answered without OpenRouter
+    for _ in 0..2 {
+        let resp: BioAuthResponse = bio_auth(false).await.json().await.unwrap();
+        assert_eq!(resp.payload.result, BioAuthResult::Ok as u8);
+    }
+    assert_eq!(openrouter.received_requests().await.unwrap().len(), 1);
+
+    // Unless the client asks for a fresh analysis
+    let resp: BioAuthResponse = bio_auth(true).await.json().await.unwrap();
+    assert_eq!(resp.payload.result, BioAuthResult::Ok as u8);
+    assert_eq!(openrouter.received_requests().await.unwrap().len(), 2);
+
+    let metrics: UpstreamCostMetrics = client.get(format!("{}/metrics", enclave)).send().await.unwrap().json().await.unwrap();
+    assert_eq!(metrics.analysis_cache.hits, 1);
+    assert_eq!(metrics.analysis_cache.misses, 1);
+    assert_eq!(metrics.analysis_cache.bypassed, 1);
+    assert_eq!(metrics.openrouter.requests, 2);
+}
+
+#[tokio::test]
+async fn test_prove_deposit_binds_the_deposit_id_to_the_handle() {
+    let eph_kp = Ed25519KeyPair::generate(&mut rand::thread_rng());
+    let pk = eph_kp.public().clone();
+    let state = Arc::new(AppState {
+        eph_kp,
+        sui_rpc_url: String::new(),
+        openrouter_api_key: String::new().into(),
+        hume_api_key: String::new().into(),
+        diagnostics_secret: String::new().into(),
+        bioauth_diagnostics: DiagnosticsStore::default(),
+        bioauth_jobs: JobQueue::default(),
+        bioauth_locks: HandleLocks::new(),
+        bioauth_shadow: ShadowMode::default(),
+        voice_baselines: BaselineStore::new(),
+        user_preferences: PreferenceStore::default(),
+        bioauth_challenges: ChallengeStore::new(std::time::Duration::from_secs(60), String::new()),
+        bioauth_attempts: AttemptTracker::new(),
+        bioauth_decoy: false,
+        bioauth_replays: ReplayGuard::default(),
+        coin_registry: CoinRegistry::default(),
+        upstream_costs: CostTracker::default(),
+        zklogin: ZkLoginVerifier::default(),
+        payload_versions: PayloadVersions::default(),
+        analysis_cache: AnalysisCache::default(),
+        screening: Screener::default(),
+        transfer_simulator: TransferSimulator::default(),
+        bioauth_gray_zone: GrayZone::default(),
+        registration_gate: RegistrationGate::default(),
+    });
+    let enclave = spawn_enclave(state).await;
+    let client = reqwest::Client::new();
+    let prove = |deposit_id: &str| {
+        call(&client, format!("{}/prove_deposit", enclave), json!({
+            "handle": "alice",
+            "deposit_id": deposit_id,
+        }))
+    };
+
+    let resp: ProveDepositResponse = prove("104877321").await.json().await.unwrap();
+    assert_eq!(resp.intent, IntentScope::DepositProof as u8);
+    assert_eq!(resp.payload.handle, b"alice".to_vec());
+    assert_eq!(resp.payload.deposit_id, b"104877321".to_vec());
+    assert!(verify(&pk, resp.payload.clone(), resp.timestamp_ms, IntentScope::DepositProof, &resp.signature));
+
+    // The contract rebuilds the payload from the receiving wallet's handle,
+    // so the proof can't credit the ID to anyone else
+    let for_bob = DepositProofPayload { handle: b"bob".to_vec(), ..resp.payload };
+    assert!(!verify(&pk, for_bob, resp.timestamp_ms, IntentScope::DepositProof, &resp.signature));
+
+    assert_eq!(prove("").await.status(), 422);
+    assert_eq!(prove("memo 42").await.status(), 422);
+}
+
+#[tokio::test]
+async fn test_screened_subjects_are_not_signed() {
+    let eph_kp = Ed25519KeyPair::generate(&mut rand::thread_rng());
+    let state = Arc::new(AppState {
+        eph_kp,
+        sui_rpc_url: String::new(),
+        openrouter_api_key: String::new().into(),
+        hume_api_key: String::new().into(),
+        diagnostics_secret: String::new().into(),
+        bioauth_diagnostics: DiagnosticsStore::default(),
+        bioauth_jobs: JobQueue::default(),
+        bioauth_locks: HandleLocks::new(),
+        bioauth_shadow: ShadowMode::default(),
+        voice_baselines: BaselineStore::new(),
+        user_preferences: PreferenceStore::default(),
+        bioauth_challenges: ChallengeStore::new(std::time::Duration::from_secs(60), String::new()),
+        bioauth_attempts: AttemptTracker::new(),
+        bioauth_decoy: false,
+        bioauth_replays: ReplayGuard::default(),
+        coin_registry: CoinRegistry::default(),
+        upstream_costs: CostTracker::default(),
+        zklogin: ZkLoginVerifier::default(),
+        payload_versions: PayloadVersions::default(),
+        analysis_cache: AnalysisCache::default(),
+        screening: Screener::with_deny_list(parse_deny_list("# sanctioned\nmallory\n")),
+        transfer_simulator: TransferSimulator::default(),
+        bioauth_gray_zone: GrayZone::default(),
+        registration_gate: RegistrationGate::default(),
+    });
+    let enclave = spawn_enclave(state).await;
+    let client = reqwest::Client::new();
+    let transfer = |to_handle: &str| {
+        call(&client, format!("{}/transfer", enclave), json!({
+            "from_handle": "alice",
+            "to_handle": to_handle,
+            "amount": 1u64,
+            "coin_type": "0x2::sui::SUI",
+        }))
+    };
+
+    assert_eq!(transfer("bob").await.status(), 200);
+    // Handles are normalized before screening
+    let resp = transfer("Mallory").await;
+    assert_eq!(resp.status(), 451);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["code"], "compliance_blocked");
+    assert!(body.get("signature").is_none());
+
+    let resp = call(&client, format!("{}/withdraw", enclave), json!({
+        "handle": "mallory",
+        "amount": 1u64,
+        "coin_type": "0x2::sui::SUI",
+    }))
+    .await;
+    assert_eq!(resp.status(), 451);
+}
+
+#[tokio::test]
+async fn test_simulated_transfers_are_answered_only_if_they_would_succeed() {
+    let rpc = MockServer::start().await;
+    let id = |byte: &str| format!("0x{}", byte.repeat(32));
+    let shared_object = |handle: Option<&str>| {
+        let mut data = json!({ "owner": { "Shared": { "initial_shared_version": "12" } } });
+        if let Some(handle) = handle {
+            data["content"] = json!({ "fields": { "handle": handle } });
+        }
+        ResponseTemplate::new(200).set_body_json(json!({ "jsonrpc": "2.0", "id": 1, "result": { "data": data } }))
+    };
+    for (object, handle) in [("aa", Some("alice")), ("bb", Some("bob")), ("ee", None)] {
+        Mock::given(method("POST"))
+            .and(body_string_contains("sui_getObject"))
+            .and(body_string_contains(id(object)))
+            .respond_with(shared_object(handle))
+            .mount(&rpc)
+            .await;
+    }
+    Mock::given(method("POST"))
+        .and(body_string_contains("sui_getObject"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "jsonrpc": "2.0", "id": 1, "result": { "error": { "code": "notExists" } },
+        })))
+        .mount(&rpc)
+        .await;
+    // The first dry run finds the balance short, the next one succeeds
+    let abort = "MoveAbort(MoveLocation { module: ModuleId { address: 0, name: Identifier(\"transfers\") }, \
+                 function: 0, instruction: 9, function_name: Some(\"transfer_internal\") }, 4) in command 0";
+    let inspected = |status: Value| {
+        let gas_used = json!({
+            "computationCost": "1000",
+            "storageCost": "2000",
+            "storageRebate": "1500",
+            "nonRefundableStorageFee": "10",
+        });
+        let effects = json!({ "status": status, "gasUsed": gas_used });
+        ResponseTemplate::new(200).set_body_json(json!({ "jsonrpc": "2.0", "id": 1, "result": { "effects": effects } }))
+    };
+    Mock::given(method("POST"))
+        .and(body_string_contains("sui_devInspectTransactionBlock"))
+        .respond_with(inspected(json!({ "status": "failure", "error": abort })))
+        .up_to_n_times(1)
+        .mount(&rpc)
+        .await;
+    Mock::given(method("POST"))
+        .and(body_string_contains("sui_devInspectTransactionBlock"))
+        .respond_with(inspected(json!({ "status": "success" })))
+        .mount(&rpc)
+        .await;
+
+    let state = Arc::new(AppState {
+        eph_kp: Ed25519KeyPair::generate(&mut rand::thread_rng()),
+        sui_rpc_url: rpc.uri(),
+        openrouter_api_key: String::new().into(),
+        hume_api_key: String::new().into(),
+        diagnostics_secret: String::new().into(),
+        bioauth_diagnostics: DiagnosticsStore::default(),
+        bioauth_jobs: JobQueue::default(),
+        bioauth_locks: HandleLocks::new(),
+        bioauth_shadow: ShadowMode::default(),
+        voice_baselines: BaselineStore::new(),
+        user_preferences: PreferenceStore::default(),
+        bioauth_challenges: ChallengeStore::new(std::time::Duration::from_secs(60), String::new()),
+        bioauth_attempts: AttemptTracker::new(),
+        bioauth_decoy: false,
+        bioauth_replays: ReplayGuard::default(),
+        coin_registry: CoinRegistry::default(),
+        upstream_costs: CostTracker::default(),
+        zklogin: ZkLoginVerifier::default(),
+        payload_versions: PayloadVersions::default(),
+        analysis_cache: AnalysisCache::default(),
+        screening: Screener::default(),
+        transfer_simulator: TransferSimulator::new(&id("ee"), "0x7", rpc.uri()).unwrap(),
+        bioauth_gray_zone: GrayZone::default(),
+        registration_gate: RegistrationGate::default(),
+    });
+    let enclave = spawn_enclave(state).await;
+    let client = reqwest::Client::new();
+    let transfer = |from_wallet_id: String, to_wallet_id: String| {
+        call(&client, format!("{}/transfer", enclave), json!({
+            "from_handle": "alice",
+            "to_handle": "bob",
+            "amount": 1_000u64,
+            "coin_type": "0x2::sui::SUI",
+            "simulate": { "from_wallet_id": from_wallet_id, "to_wallet_id": to_wallet_id },
+        }))
+    };
+
+    let resp = transfer(id("aa"), id("bb")).await;
+    assert_eq!(resp.status(), 422);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["code"], "simulation_failed");
+    assert_eq!(body["details"]["reason"], "insufficient_balance");
+    assert_eq!(body["details"]["abort_code"], 4);
+    assert!(body.get("signature").is_none());
+
+    let resp = transfer(id("aa"), id("bb")).await;
+    assert_eq!(resp.status(), 200);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["simulation"]["status"], "success");
+    assert_eq!(body["simulation"]["gas_used"]["computation_cost"], 1000);
+    assert!(body["signature"].is_string());
+
+    // Wallets must be the handles' and exist, before anything is inspected
+    let body: Value = transfer(id("bb"), id("aa")).await.json().await.unwrap();
+    assert_eq!(body["details"]["reason"], "wallet_handle_mismatch");
+    let body: Value = transfer(id("aa"), id("cc")).await.json().await.unwrap();
+    assert_eq!(body["details"]["reason"], "wallet_not_found");
+    let inspections = rpc.received_requests().await.unwrap();
+    let inspections = inspections.iter().filter(|r| String::from_utf8_lossy(&r.body).contains("devInspect"));
+    assert_eq!(inspections.count(), 2);
+
+    // Without `simulate` nothing is dry-run
+    let resp = call(&client, format!("{}/transfer", enclave), json!({
+        "from_handle": "alice",
+        "to_handle": "bob",
+        "amount": 1u64,
+        "coin_type": "0x2::sui::SUI",
+    }))
+    .await;
+    let body: Value = resp.json().await.unwrap();
+    assert!(body.get("simulation").is_none());
+}
+
+#[tokio::test]
+async fn test_preferences_change_only_after_a_successful_bio_auth() {
+    let _env = UPSTREAM_ENV.lock().await;
+    let openrouter = MockServer::start().await;
+    set_endpoint_override(Endpoint::OpenRouter, Some(format!("{}/chat", openrouter.uri())));
+    // Calm, then a stress score only a raised sensitivity reads as duress
+    for stress_level in [10, 55] {
+        Mock::given(method("POST"))
+            .and(path("/chat"))
+            .respond_with(openrouter_reply(stress_level, 5.0))
+            .up_to_n_times(1)
+            .mount(&openrouter)
+            .await;
+    }
+
+    let state = Arc::new(AppState {
+        eph_kp: Ed25519KeyPair::generate(&mut rand::thread_rng()),
+        sui_rpc_url: String::new(),
+        openrouter_api_key: "test-key".to_string().into(),
+        hume_api_key: String::new().into(),
+        diagnostics_secret: String::new().into(),
+        bioauth_diagnostics: DiagnosticsStore::default(),
+        bioauth_jobs: JobQueue::default(),
+        bioauth_locks: HandleLocks::new(),
+        bioauth_shadow: ShadowMode::default(),
+        voice_baselines: BaselineStore::new(),
+        user_preferences: PreferenceStore::default(),
+        bioauth_challenges: ChallengeStore::new(std::time::Duration::from_secs(60), String::new()),
+        bioauth_attempts: AttemptTracker::new(),
+        bioauth_decoy: false,
+        bioauth_replays: ReplayGuard::new(std::time::Duration::ZERO),
+        coin_registry: CoinRegistry::default(),
+        upstream_costs: CostTracker::default(),
+        zklogin: ZkLoginVerifier::default(),
+        payload_versions: PayloadVersions::default(),
+        analysis_cache: AnalysisCache::default(),
+        screening: Screener::default(),
+        transfer_simulator: TransferSimulator::default(),
+        bioauth_gray_zone: GrayZone::default(),
+        registration_gate: RegistrationGate::default(),
+    });
+    let enclave = spawn_enclave(state.clone()).await;
+    let client = reqwest::Client::new();
+    let bio_auth = || async {
+        let resp: BioAuthResponse = call(&client, format!("{}/bio_auth", enclave), json!({
+            "handle": "alice",
+            "audio_base64": calm_wav_base64(),
+            "expected_amount": 5_000_000_000u64,
+            "skip_analysis_cache": true,
+        }))
+        .await
+        .json()
+        .await
+        .unwrap();
+        resp.request_id
+    };
+    let update = |request_id: &str, preferences: Value| {
+        call(&client, format!("{}/preferences", enclave), json!({
+            "handle": "alice",
+            "bio_auth_request_id": request_id,
+            "preferences": preferences,
+        }))
+    };
+    let preferences = json!({ "stress_sensitivity": 5, "locale": "vi-VN", "challenge_length": 6 });
+
+    let request_id = bio_auth().await;
+    assert_eq!(state.bioauth_diagnostics.get(&request_id).unwrap().result, "ok");
+    assert_eq!(update("not-a-bio-auth", preferences.clone()).await.status(), 403);
+    assert_eq!(update(&request_id, json!({ "challenge_length": 3 })).await.status(), 422);
+
+    // The bio-auth's request ID works once
+    let resp = update(&request_id, preferences.clone()).await;
+    assert_eq!(resp.status(), 200);
+    let stored: PreferencesResponse = resp.json().await.unwrap();
+    assert_eq!(stored.preferences.locale.as_deref(), Some("vi-VN"));
+    assert_eq!(update(&request_id, json!({})).await.status(), 403);
+
+    // Challenges follow the preferred locale and length when the request doesn't say
+    let challenge: ChallengeResponse = call(&client, format!("{}/bio_auth/challenge", enclave), json!({
+        "handle": "alice",
+        "expected_amount": 5_000_000_000u64,
+        "destination_hint": "0x00a11ce",
+    }))
+    .await
+    .json()
+    .await
+    .unwrap();
+    assert!(challenge.phrase.starts_with("Tôi xác nhận gửi"), "{}", challenge.phrase);
+    assert!(challenge.phrase.ends_with("0 a 1 1 c e"), "{}", challenge.phrase);
+
+    // A raised sensitivity reads 55 as duress, and duress grants no update
+    let request_id = bio_auth().await;
+    assert_eq!(state.bioauth_diagnostics.get(&request_id).unwrap().result, "duress");
+    assert_eq!(update(&request_id, json!({})).await.status(), 403);
+}
+
+#[tokio::test]
+async fn test_challenges_survive_a_restart_through_a_sealed_snapshot() {
+    let now_ms = || {
+        std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_millis() as u64
+    };
+    let boot = || {
+        Arc::new(AppState {
+            eph_kp: Ed25519KeyPair::generate(&mut rand::thread_rng()),
+            sui_rpc_url: String::new(),
+            openrouter_api_key: String::new().into(),
+            hume_api_key: String::new().into(),
+            diagnostics_secret: String::new().into(),
+            bioauth_diagnostics: DiagnosticsStore::default(),
+            bioauth_jobs: JobQueue::default(),
+            bioauth_locks: HandleLocks::new(),
+            bioauth_shadow: ShadowMode::default(),
+            voice_baselines: BaselineStore::new(),
+            user_preferences: PreferenceStore::default(),
+            bioauth_challenges: ChallengeStore::new(std::time::Duration::from_secs(60), String::new()),
+            bioauth_attempts: AttemptTracker::new(),
+            bioauth_decoy: false,
+            bioauth_replays: ReplayGuard::new(std::time::Duration::from_secs(86_400)),
+            coin_registry: CoinRegistry::default(),
+            upstream_costs: CostTracker::default(),
+            zklogin: ZkLoginVerifier::default(),
+            payload_versions: PayloadVersions::default(),
+            analysis_cache: AnalysisCache::default(),
+            screening: Screener::default(),
+            transfer_simulator: TransferSimulator::default(),
+            bioauth_gray_zone: GrayZone::default(),
+            registration_gate: RegistrationGate::default(),
+        })
+    };
+    let parent = MockServer::start().await;
+    let config = SnapshotConfig::new(format!("{}/snapshot", parent.uri()), &[7; 32]);
+
+    // Before the restart: a challenge, and a cooldown, a grant and an accepted clip
+    // that a snapshot must not carry, since the parent could forge them
+    let before = boot();
+    let clip = calm_wav_base64();
+    let challenge_id = before.bioauth_challenges.issue("I confirm sending 5 SUI".to_string());
+    for _ in 0..=FREE_FAILED_ATTEMPTS {
+        before.bioauth_attempts.record("alice", BioAuthResult::InvalidAmount, now_ms());
+    }
+    before.user_preferences.grant("req-1", "alice");
+    let fingerprint = before.bioauth_replays.check("alice", &clip, now_ms()).unwrap().unwrap();
+    before.bioauth_replays.record("alice", fingerprint, now_ms());
+
+    Mock::given(method("PUT")).and(path("/snapshot")).respond_with(ResponseTemplate::new(200)).mount(&parent).await;
+    save_snapshot(&reqwest::Client::new(), &before, &config).await.unwrap();
+    let blob = parent.received_requests().await.unwrap()[0].body.clone();
+    // The parent only sees ciphertext
+    assert!(!blob.windows(9).any(|window| window == b"I confirm"));
+
+    // Nothing saved yet: the enclave starts empty
+    let empty = boot();
+    Mock::given(method("GET"))
+        .and(path("/snapshot"))
+        .respond_with(ResponseTemplate::new(404))
+        .up_to_n_times(1)
+        .mount(&parent)
+        .await;
+    restore_snapshot(&empty, &config).await;
+    assert!(empty.bioauth_challenges.phrase(&challenge_id).is_none());
+
+    Mock::given(method("GET"))
+        .and(path("/snapshot"))
+        .respond_with(ResponseTemplate::new(200).set_body_bytes(blob.clone()))
+        .mount(&parent)
+        .await;
+    let after = boot();
+    restore_snapshot(&after, &config).await;
+    assert_eq!(after.bioauth_challenges.phrase(&challenge_id).as_deref(), Some("I confirm sending 5 SUI"));
+    assert!(after.bioauth_attempts.check("alice", now_ms()).is_ok());
+    assert!(after.bioauth_replays.check("alice", &clip, now_ms()).is_ok());
+    assert!(!after.user_preferences.take_grant("req-1", "alice"));
+
+    // Another key, or a snapshot past its maximum age, restores nothing
+    let other_key = boot();
+    restore_snapshot(&other_key, &SnapshotConfig::new(config.url(), &[8; 32])).await;
+    assert!(other_key.bioauth_challenges.phrase(&challenge_id).is_none());
+    let stale = config.restore(&boot(), &blob, now_ms() + (DEFAULT_SNAPSHOT_MAX_AGE_SECS + 1) * 1000);
+    assert!(stale.unwrap_err().contains("old"));
+}
@@ -0,0 +1,41 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! Classifier hit and false-positive rates over the synthetic voice matrix
+//!
+//! Renders every calm and stressed voice from `ram_app::simulation` at the
+//! sample rates clients upload and checks the DSP score against the two
+//! cut-offs that matter: the calm gate on sensitive actions
+//! (`MAX_SET_LIMIT_STRESS`) and the duress threshold. Run with
+//! `cargo test --features simulation --test simulation`.
+
+use nautilus_server::ram_app::simulation::{calm_voices, evaluate, stress_levels, stressed_voices, DEFAULT_SAMPLE_RATE};
+use nautilus_server::ram_app::{is_under_duress, MAX_SET_LIMIT_STRESS};
+
+/// Seconds of audio per voice, about one spoken confirmation
+const CLIP_SECS: f64 = 1.5;
+
+/// Stressed voices the calm gate must refuse
+const MIN_GATE_HIT_RATE: f64 = 0.8;
+
+#[test]
+fn test_classifier_rates_stay_in_band() {
+    for sample_rate in [DEFAULT_SAMPLE_RATE, 44_100] {
+        let calm = stress_levels(&calm_voices(), sample_rate, CLIP_SECS, None);
+        let stressed = stress_levels(&stressed_voices(), sample_rate, CLIP_SECS, None);
+
+        let gate = evaluate(&calm, &stressed, MAX_SET_LIMIT_STRESS + 1);
+        assert_eq!(gate.false_positives, 0, "calm voices refused at {sample_rate} Hz: {gate:?}");
+        assert!(
+            gate.hit_rate() >= MIN_GATE_HIT_RATE,
+            "stressed voices let through at {sample_rate} Hz: {gate:?}"
+        );
+
+        // The DSP score is maxed with the LLM's, so a calm voice reading as
+        // duress here would be flagged whatever the user said
+        assert!(
+            !calm.iter().any(|&level| is_under_duress(level)),
+            "calm voices read as duress at {sample_rate} Hz: {calm:?}"
+        );
+    }
+}
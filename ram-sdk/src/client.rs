@@ -0,0 +1,476 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! HTTP client for the RAM backend (and the enclave routes it proxies)
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use ram_sdk::{BioAuthRequest, RamClient, verify::parse_public_key};
+//!
+//! # async fn run() -> ram_sdk::Result<()> {
+//! let client = RamClient::new("http://localhost:4000")
+//!     .with_enclave_key(parse_public_key("<enclave public key hex>")?);
+//!
+//! let bio = client
+//!     .bio_auth(&BioAuthRequest {
+//!         handle: "alice".to_string(),
+//!         audio_base64: "<wav>".to_string(),
+//!         expected_amount: 5_000_000_000,
+//!         coin_type: Some("0x2::sui::SUI".to_string()),
+//!         destination_hint: None,
+//!         locale: Some("en-US".to_string()),
+//!         skip_analysis_cache: false,
+//!     })
+//!     .await?;
+//! let transfer = client.transfer("alice", "bob", 5_000_000_000, "0x2::sui::SUI", Some("INV-42")).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use ed25519_dalek::VerifyingKey;
+use reqwest::{Method, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::time::Duration;
+
+use crate::error::{Result, SdkError};
+use crate::types::*;
+use crate::verify::VerifySigned;
+
+/// Retry schedule for transient failures (see `SdkError::is_retryable`)
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Total attempts, including the first
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(5),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Single attempt, no retries
+    pub fn none() -> Self {
+        Self { max_attempts: 1, ..Self::default() }
+    }
+
+    fn backoff(&self, attempt: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_backoff)
+    }
+}
+
+/// How often a queued bio-auth job is polled
+const JOB_POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// Give up on a queued bio-auth job after this many polls
+const JOB_MAX_POLLS: u32 = 120;
+
+/// Backend API version the client speaks; the unprefixed paths are deprecated
+const API_PREFIX: &str = "/v1";
+
+/// `GET /bio_auth/:job_id` poll response
+#[derive(Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+enum JobStatus {
+    Queued,
+    Running,
+    Done { response: BioAuthResponse },
+    Failed { error: String },
+}
+
+/// Client for one RAM backend
+///
+/// With an enclave key set, every signed response is checked against it (and
+/// against the intent its endpoint signs under) before it's returned.
+#[derive(Debug, Clone)]
+pub struct RamClient {
+    http: reqwest::Client,
+    base_url: String,
+    enclave_key: Option<VerifyingKey>,
+    package: Option<[u8; 32]>,
+    retry: RetryPolicy,
+    payload_version: Option<PayloadVersion>,
+}
+
+impl RamClient {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            enclave_key: None,
+            package: None,
+            retry: RetryPolicy::default(),
+            payload_version: None,
+        }
+    }
+
+    /// Verify signed responses against the enclave's public key
+    pub fn with_enclave_key(mut self, key: VerifyingKey) -> Self {
+        self.enclave_key = Some(key);
+        self
+    }
+
+    /// RAM package on the network transactions go to (see `verify::parse_package_id`)
+    ///
+    /// Needed to verify layout v3 responses, which are signed for one package
+    /// and so for one network.
+    pub fn with_package(mut self, package: [u8; 32]) -> Self {
+        self.package = Some(package);
+        self
+    }
+
+    /// Ask for payloads signed in this layout (default: the enclave's choice)
+    ///
+    /// The layout must be one the deployed contract verifies; responses say
+    /// which one was used and are verified accordingly either way.
+    pub fn with_payload_version(mut self, version: PayloadVersion) -> Self {
+        self.payload_version = Some(version);
+        self
+    }
+
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Use a preconfigured reqwest client (timeouts, proxies, TLS)
+    pub fn with_http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = http;
+        self
+    }
+
+    // ====== Enclave (signing) endpoints ======
+
+    pub async fn create_wallet(&self, handle: &str) -> Result<CreateWalletResponse> {
+        let signed: CreateWalletResponse = self
+            .post("/create_wallet", &self.signing_body(json!({ "handle": handle })))
+            .await?;
+        self.check(&signed, signed.intent, Intent::CreateWallet)?;
+        Ok(signed)
+    }
+
+    /// Link a Sui address; `wallet_signature` proves ownership of `message`
+    pub async fn link_address(
+        &self,
+        handle: &str,
+        wallet_address: &str,
+        wallet_signature: &str,
+        message: &str,
+        label: &str,
+    ) -> Result<LinkAddressResponse> {
+        let body = self.signing_body(json!({
+        "handle": handle,
+        "wallet_address": wallet_address,
+        "wallet_signature": wallet_signature,
+        "message": message,
+        "label": label,
+        }));
+        let signed: LinkAddressResponse = self.post("/link_address", &body).await?;
+        self.check(&signed, signed.intent, Intent::LinkAddress)?;
+        Ok(signed)
+    }
+
+    /// Link a zkLogin address; `zklogin_signature` is the serialized zkLogin
+    /// signature over the enclave's link message for `issued_at_ms`
+    pub async fn link_zklogin(
+        &self,
+        handle: &str,
+        wallet_address: &str,
+        zklogin_signature: &str,
+        issued_at_ms: u64,
+        label: &str,
+    ) -> Result<LinkAddressResponse> {
+        let body = self.signing_body(json!({
+        "handle": handle,
+        "wallet_address": wallet_address,
+        "zklogin_signature": zklogin_signature,
+        "issued_at_ms": issued_at_ms,
+        "label": label,
+        }));
+        let signed: LinkAddressResponse = self.post("/link_zklogin", &body).await?;
+        self.check(&signed, signed.intent, Intent::LinkAddress)?;
+        Ok(signed)
+    }
+
+    pub async fn unlink_address(&self, handle: &str, wallet_address: &str) -> Result<UnlinkAddressResponse> {
+        let body = self.signing_body(json!({ "handle": handle, "wallet_address": wallet_address }));
+        let signed: UnlinkAddressResponse = self.post("/unlink_address", &body).await?;
+        self.check(&signed, signed.intent, Intent::UnlinkAddress)?;
+        Ok(signed)
+    }
+
+    /// Voice bio-auth; waits for the result if the enclave queues the job
+    pub async fn bio_auth(&self, request: &BioAuthRequest) -> Result<BioAuthResponse> {
+        let (status, body) = self
+            .send(Method::POST, "/bio_auth", Some(&self.signing_body(json!(request))))
+            .await?;
+
+        let response = if status == StatusCode::ACCEPTED {
+            let job_id = body["job_id"]
+                .as_str()
+                .ok_or_else(|| SdkError::JobFailed("queued response without job_id".to_string()))?;
+            self.wait_for_job(job_id).await?
+        } else {
+            decode(body)?
+        };
+        self.check(&response, response.signed.intent, Intent::BioAuth)?;
+        Ok(response)
+    }
+
+    /// Typed-confirmation auth (accessibility path)
+    pub async fn typed_auth(&self, request: &TypedAuthRequest) -> Result<BioAuthResponse> {
+        let response: BioAuthResponse = self.post("/typed_auth", &self.signing_body(json!(request))).await?;
+        self.check(&response, response.signed.intent, Intent::BioAuth)?;
+        Ok(response)
+    }
+
+    /// Sign a transfer; `coin_type` is the Move type (`0x2::sui::SUI`), signed in `type_name` form
+    ///
+    /// `memo` is an optional reference (up to 128 bytes) signed with the
+    /// transfer and emitted on-chain.
+    pub async fn transfer(
+        &self,
+        from_handle: &str,
+        to_handle: &str,
+        amount: u64,
+        coin_type: &str,
+        memo: Option<&str>,
+    ) -> Result<TransferResponse> {
+        let body = self.signing_body(json!({
+        "from_handle": from_handle,
+        "to_handle": to_handle,
+        "amount": amount,
+        "coin_type": coin_type,
+        "memo": memo,
+        }));
+        let signed: TransferResponse = self.post("/transfer", &body).await?;
+        self.check(&signed, signed.intent, Intent::Transfer)?;
+        Ok(signed)
+    }
+
+    pub async fn withdraw(&self, handle: &str, amount: u64, coin_type: &str) -> Result<WithdrawResponse> {
+        let body = self.signing_body(json!({ "handle": handle, "amount": amount, "coin_type": coin_type }));
+        let signed: WithdrawResponse = self.post("/withdraw", &body).await?;
+        self.check(&signed, signed.intent, Intent::Withdraw)?;
+        Ok(signed)
+    }
+
+    /// Sign a daily spending limit the user confirmed by voice
+    ///
+    /// The recording must ask for the limit ("set my daily limit to 50 SUI")
+    /// in a calm voice; anything else is refused with 403.
+    pub async fn set_limit(
+        &self,
+        handle: &str,
+        audio_base64: &str,
+        daily_limit: u64,
+        coin_type: &str,
+    ) -> Result<SetLimitResponse> {
+        let body = self.signing_body(json!({
+        "handle": handle,
+        "audio_base64": audio_base64,
+        "daily_limit": daily_limit,
+        "coin_type": coin_type,
+        }));
+        let signed: SetLimitResponse = self.post("/set_limit", &body).await?;
+        self.check(&signed, signed.intent, Intent::SetLimit)?;
+        Ok(signed)
+    }
+
+    /// Sign an instant freeze; needs no voice
+    pub async fn freeze_wallet(&self, handle: &str) -> Result<FreezeWalletResponse> {
+        let body = self.signing_body(json!({ "handle": handle }));
+        let signed: FreezeWalletResponse = self.post("/freeze_wallet", &body).await?;
+        self.check(&signed, signed.intent, Intent::Freeze)?;
+        Ok(signed)
+    }
+
+    /// The one-time phrase to speak for [`request_unfreeze`](Self::request_unfreeze)
+    pub async fn unfreeze_challenge(&self, handle: &str) -> Result<ChallengeResponse> {
+        self.post("/unfreeze/challenge", &json!({ "payload": { "handle": handle } })).await
+    }
+
+    /// Sign an unfreeze request from a recording of the challenge phrase
+    ///
+    /// The contract lifts the freeze 24 hours after the request lands.
+    pub async fn request_unfreeze(
+        &self,
+        handle: &str,
+        challenge_id: &str,
+        audio_base64: &str,
+    ) -> Result<UnfreezeResponse> {
+        let body = self.signing_body(json!({
+        "handle": handle,
+        "challenge_id": challenge_id,
+        "audio_base64": audio_base64,
+        }));
+        let signed: UnfreezeResponse = self.post("/request_unfreeze", &body).await?;
+        self.check(&signed, signed.intent, Intent::Unfreeze)?;
+        Ok(signed)
+    }
+
+    /// Sign an inactivity beneficiary; needs no voice
+    ///
+    /// Once the wallet has been inactive for `inactivity_timeout_ms` (30 days
+    /// to 10 years), `beneficiary_handle` can claim its balances. An empty
+    /// `beneficiary_handle` removes it.
+    pub async fn set_beneficiary(
+        &self,
+        handle: &str,
+        beneficiary_handle: &str,
+        inactivity_timeout_ms: u64,
+    ) -> Result<SetBeneficiaryResponse> {
+        let body = self.signing_body(json!({
+        "handle": handle,
+        "beneficiary_handle": beneficiary_handle,
+        "inactivity_timeout_ms": inactivity_timeout_ms,
+        }));
+        let signed: SetBeneficiaryResponse = self.post("/set_beneficiary", &body).await?;
+        self.check(&signed, signed.intent, Intent::Beneficiary)?;
+        Ok(signed)
+    }
+
+    /// Sign `deposit_id` (an exchange's memo for this user) as belonging to
+    /// `handle`, for the exchange to pass to `wallet::deposit_with_proof`
+    pub async fn prove_deposit(&self, handle: &str, deposit_id: &str) -> Result<ProveDepositResponse> {
+        let body = self.signing_body(json!({
+        "handle": handle,
+        "deposit_id": deposit_id,
+        }));
+        let signed: ProveDepositResponse = self.post("/prove_deposit", &body).await?;
+        self.check(&signed, signed.intent, Intent::DepositProof)?;
+        Ok(signed)
+    }
+
+    // ====== Backend (indexed) endpoints ======
+
+    /// Backend, enclave, database and RPC health
+    pub async fn health(&self) -> Result<BackendHealth> {
+        let (_, body) = self.send(Method::GET, "/health", None).await?;
+        decode(body)
+    }
+
+    /// Indexed events for a wallet, newest first
+    pub async fn events(&self, handle: &str, limit: i64, offset: i64) -> Result<Vec<RamEvent>> {
+        self.post("/api/events", &json!({ "handle": handle, "limit": limit, "offset": offset }))
+            .await
+    }
+
+    pub async fn stats(&self, handle: &str) -> Result<WalletStats> {
+        self.post("/api/stats", &json!({ "handle": handle })).await
+    }
+
+    pub async fn linked_addresses(&self, handle: &str) -> Result<Vec<LinkedAddress>> {
+        self.post("/api/addresses", &json!({ "handle": handle })).await
+    }
+
+    pub async fn portfolio(&self, handle: &str) -> Result<PortfolioResponse> {
+        let (_, body) = self.send(Method::GET, &format!("/api/portfolio/{}", handle), None).await?;
+        decode(body)
+    }
+
+    // ====== Internals ======
+
+    /// `{ "payload": ... }` body for a signing route, with the chosen layout
+    fn signing_body(&self, payload: Value) -> Value {
+        let mut body = json!({ "payload": payload });
+        if let Some(version) = self.payload_version {
+            body["payload_version"] = json!(version);
+        }
+        body
+    }
+
+    async fn post<T: DeserializeOwned>(&self, path: &str, body: &Value) -> Result<T> {
+        let (_, body) = self.send(Method::POST, path, Some(body)).await?;
+        decode(body)
+    }
+
+    /// Send with retries; returns the success status and JSON body
+    async fn send(&self, method: Method, path: &str, body: Option<&Value>) -> Result<(StatusCode, Value)> {
+        let url = format!("{}{}{}", self.base_url, API_PREFIX, path);
+        let mut attempt = 0;
+        loop {
+            match self.send_once(method.clone(), &url, body).await {
+                Err(e) if e.is_retryable() && attempt + 1 < self.retry.max_attempts => {
+                    tokio::time::sleep(self.retry.backoff(attempt)).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    async fn send_once(&self, method: Method, url: &str, body: Option<&Value>) -> Result<(StatusCode, Value)> {
+        let mut request = self.http.request(method, url);
+        if let Some(body) = body {
+            request = request.json(body);
+        }
+        let response = request.send().await?;
+        let status = response.status();
+        let text = response.text().await?;
+
+        if !status.is_success() {
+            // Backend errors share one envelope (`ApiErrorBody`); anything else,
+            // e.g. from a load balancer in front of it, keeps its status and text
+            let Ok(error) = serde_json::from_str::<ApiErrorBody>(&text) else {
+                return Err(SdkError::Api {
+                    status: status.as_u16(),
+                    code: ErrorCode::from_status(status.as_u16()),
+                    message: text,
+                    request_id: None,
+                });
+            };
+            if let Some(fields) = error.fields().filter(|_| error.code == ErrorCode::ValidationFailed) {
+                return Err(SdkError::Validation(fields));
+            }
+            return Err(SdkError::Api {
+                status: status.as_u16(),
+                code: error.code,
+                message: error.message,
+                request_id: error.request_id,
+            });
+        }
+
+        let body = serde_json::from_str(&text)
+            .map_err(|e| SdkError::InvalidInput(format!("response is not JSON: {}", e)))?;
+        Ok((status, body))
+    }
+
+    async fn wait_for_job(&self, job_id: &str) -> Result<BioAuthResponse> {
+        for _ in 0..JOB_MAX_POLLS {
+            let (_, body) = self.send(Method::GET, &format!("/bio_auth/{}", job_id), None).await?;
+            match decode::<JobStatus>(body)? {
+                JobStatus::Done { response } => return Ok(response),
+                JobStatus::Failed { error } => return Err(SdkError::JobFailed(error)),
+                JobStatus::Queued | JobStatus::Running => tokio::time::sleep(JOB_POLL_INTERVAL).await,
+            }
+        }
+        Err(SdkError::JobFailed(format!("job {} did not finish in time", job_id)))
+    }
+
+    /// Intent and signature check for a signed response
+    fn check<S: VerifySigned>(&self, signed: &S, intent: u8, expected: Intent) -> Result<()> {
+        if intent != expected as u8 {
+            return Err(SdkError::IntentMismatch { expected: expected as u8, actual: intent });
+        }
+        match &self.enclave_key {
+            Some(key) => signed.verify_for(key, self.package.as_ref()),
+            None => Ok(()),
+        }
+    }
+}
+
+fn decode<T: DeserializeOwned>(body: Value) -> Result<T> {
+    serde_json::from_value(body).map_err(|e| SdkError::InvalidInput(format!("unexpected response shape: {}", e)))
+}
@@ -0,0 +1,74 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! SDK error type
+
+use thiserror::Error;
+
+use crate::types::{ErrorCode, FieldError};
+
+#[derive(Debug, Error)]
+pub enum SdkError {
+    /// Connection, timeout or body decoding failure
+    #[cfg(feature = "client")]
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
+
+    /// Backend or enclave answered with a non-success status
+    #[error("API error ({status} {code}): {message}")]
+    Api {
+        status: u16,
+        code: ErrorCode,
+        message: String,
+        /// Quote this when reporting the failure
+        request_id: Option<String>,
+    },
+
+    /// Request was rejected with a 422 listing every invalid field
+    #[error("invalid request: {}", describe_fields(.0))]
+    Validation(Vec<FieldError>),
+
+    /// Signature doesn't verify against the configured enclave key
+    #[error("invalid enclave signature: {0}")]
+    InvalidSignature(String),
+
+    /// Response was signed under a different intent than its endpoint
+    #[error("unexpected intent {actual} (expected {expected})")]
+    IntentMismatch { expected: u8, actual: u8 },
+
+    /// Queued bio-auth job failed in the enclave
+    #[error("bio-auth job failed: {0}")]
+    JobFailed(String),
+
+    /// Webhook notification whose signature or timestamp doesn't check out
+    #[error("invalid webhook notification: {0}")]
+    InvalidWebhook(String),
+
+    /// Malformed key, address or payload
+    #[error("invalid input: {0}")]
+    InvalidInput(String),
+}
+
+impl SdkError {
+    /// Whether the request may succeed if sent again
+    ///
+    /// Transport failures, 429 and 5xx are retried; other 4xx are final.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            #[cfg(feature = "client")]
+            SdkError::Http(e) => e.is_connect() || e.is_timeout(),
+            SdkError::Api { status, .. } => *status == 429 || *status >= 500,
+            _ => false,
+        }
+    }
+}
+
+fn describe_fields(fields: &[FieldError]) -> String {
+    fields
+        .iter()
+        .map(|f| format!("{}: {}", f.field, f.message))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+pub type Result<T> = std::result::Result<T, SdkError>;
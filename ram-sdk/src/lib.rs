@@ -0,0 +1,42 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! RAM SDK - Rust client for voice-protected wallets
+//!
+//! Wraps the RAM backend API (including the enclave routes it proxies) so
+//! other Rust services can integrate voice-protected transfers without
+//! re-implementing the HTTP contracts.
+//!
+//! ## Module Structure
+//!
+//! - `client`: `RamClient` for the backend, with retries and signature checks
+//! - `types`: Payloads, signed responses and backend models
+//! - `verify`: Enclave signature verification
+//! - `tx`: Move calls for submitting signed responses on-chain
+//! - `webhook`: Merchant webhook signature verification
+//! - `error`: `SdkError`
+//! - `wasm`: Browser bindings for signature checks and BCS encoding (`wasm` feature)
+//!
+//! ## Features
+//!
+//! - `client` (default): the HTTP client. Disable it (`default-features = false`)
+//!   to build the types, verification and transaction helpers for `wasm32`.
+//! - `wasm`: `wasm-bindgen` exports so web frontends verify enclave signatures
+//!   with these exact definitions instead of hand-written TypeScript.
+
+#[cfg(feature = "client")]
+pub mod client;
+pub mod error;
+pub mod tx;
+pub mod types;
+pub mod verify;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+pub mod webhook;
+
+#[cfg(feature = "client")]
+pub use client::{RamClient, RetryPolicy};
+pub use error::{Result, SdkError};
+pub use tx::{CallArg, Deployment, MoveCall};
+pub use types::*;
+pub use verify::VerifySigned;
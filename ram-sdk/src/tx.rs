@@ -0,0 +1,359 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! Move calls for submitting signed enclave responses
+//!
+//! Each builder turns a verified enclave response into the `MoveCall` the
+//! frontend would make (target, type arguments and BCS-encoded arguments),
+//! ready to drop into a programmable transaction with any Sui SDK. The
+//! response's `payload_version` is passed along so the contract checks the
+//! signature against the layout the enclave actually signed.
+
+use serde::Serialize;
+
+use crate::error::{Result, SdkError};
+use crate::types::{
+    BioAuthResponse, CreateWalletResponse, FreezeWalletResponse, LinkAddressResponse, ProveDepositResponse,
+    SetBeneficiaryResponse, SetLimitResponse, Signed, TransferResponse, UnfreezeResponse, UnlinkAddressResponse,
+    WithdrawResponse,
+};
+
+/// Shared `Clock` object
+pub const CLOCK_OBJECT_ID: &str = "0x6";
+
+/// One argument of a Move call
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CallArg {
+    /// Object ID (shared or owned), resolved by the transaction builder
+    Object(String),
+    /// BCS-encoded pure value
+    Pure(Vec<u8>),
+}
+
+/// A Move call ready to be added to a programmable transaction
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MoveCall {
+    /// `package::module::function`
+    pub target: String,
+    pub type_arguments: Vec<String>,
+    pub arguments: Vec<CallArg>,
+}
+
+/// Deployment the calls target
+#[derive(Debug, Clone)]
+pub struct Deployment {
+    /// Published `ram` package
+    pub package_id: String,
+    /// Package defining the enclave witness type (`<pkg>::core::XWALLET`)
+    pub enclave_package_id: String,
+    /// Registered `Enclave` object
+    pub enclave_id: String,
+    /// Shared `RamRegistry` object
+    pub registry_id: String,
+}
+
+fn pure<T: Serialize>(value: &T) -> Result<CallArg> {
+    bcs::to_bytes(value)
+        .map(CallArg::Pure)
+        .map_err(|e| SdkError::InvalidInput(format!("BCS encoding failed: {}", e)))
+}
+
+fn signature_bytes<P>(signed: &Signed<P>) -> Result<CallArg> {
+    let bytes = hex::decode(signed.signature.trim_start_matches("0x"))
+        .map_err(|e| SdkError::InvalidSignature(format!("not hex: {}", e)))?;
+    pure(&bytes)
+}
+
+impl Deployment {
+    fn target(&self, module: &str, function: &str) -> String {
+        format!("{}::{}::{}", self.package_id, module, function)
+    }
+
+    fn witness_type(&self) -> String {
+        format!("{}::core::XWALLET", self.enclave_package_id)
+    }
+
+    /// `wallet::create_wallet` for a signed create-wallet response
+    pub fn create_wallet(&self, signed: &CreateWalletResponse) -> Result<MoveCall> {
+        Ok(MoveCall {
+            target: self.target("wallet", "create_wallet"),
+            type_arguments: vec![self.witness_type()],
+            arguments: vec![
+                CallArg::Object(self.registry_id.clone()),
+                pure(&signed.payload.handle)?,
+                pure(&signed.payload_version)?,
+                pure(&signed.timestamp_ms)?,
+                signature_bytes(signed)?,
+                CallArg::Object(self.enclave_id.clone()),
+            ],
+        })
+    }
+
+    /// `wallet::link_address` for a signed link response
+    pub fn link_address(&self, wallet_id: &str, signed: &LinkAddressResponse) -> Result<MoveCall> {
+        Ok(MoveCall {
+            target: self.target("wallet", "link_address"),
+            type_arguments: vec![self.witness_type()],
+            arguments: vec![
+                CallArg::Object(wallet_id.to_string()),
+                pure(&signed.payload.address)?,
+                pure(&signed.payload.label)?,
+                pure(&signed.payload_version)?,
+                pure(&signed.timestamp_ms)?,
+                signature_bytes(signed)?,
+                CallArg::Object(self.enclave_id.clone()),
+            ],
+        })
+    }
+
+    /// `wallet::unlink_address` for a signed unlink response
+    pub fn unlink_address(&self, wallet_id: &str, signed: &UnlinkAddressResponse) -> Result<MoveCall> {
+        Ok(MoveCall {
+            target: self.target("wallet", "unlink_address"),
+            type_arguments: vec![self.witness_type()],
+            arguments: vec![
+                CallArg::Object(wallet_id.to_string()),
+                pure(&signed.payload.address)?,
+                pure(&signed.payload_version)?,
+                pure(&signed.timestamp_ms)?,
+                signature_bytes(signed)?,
+                CallArg::Object(self.enclave_id.clone()),
+            ],
+        })
+    }
+
+    /// `bioguard::apply_bioauth` for a signed bio-auth result
+    ///
+    /// Submit it on its own transaction: a duress result must lock the wallet
+    /// even if the transfer that follows aborts.
+    pub fn apply_bioauth(&self, wallet_id: &str, response: &BioAuthResponse) -> Result<MoveCall> {
+        let signed = &response.signed;
+        let payload = &signed.payload;
+        Ok(MoveCall {
+            target: self.target("bioguard", "apply_bioauth"),
+            type_arguments: vec![self.witness_type()],
+            arguments: vec![
+                CallArg::Object(wallet_id.to_string()),
+                pure(&payload.handle)?,
+                pure(&payload.amount)?,
+                pure(&payload.result)?,
+                pure(&payload.transcript)?,
+                pure(&payload.destination_verified)?,
+                pure(&payload.method)?,
+                pure(&payload.seal)?,
+                pure(&payload.failed_attempts)?,
+                pure(&signed.payload_version)?,
+                pure(&signed.timestamp_ms)?,
+                signature_bytes(signed)?,
+                CallArg::Object(self.enclave_id.clone()),
+                CallArg::Object(CLOCK_OBJECT_ID.to_string()),
+            ],
+        })
+    }
+
+    /// `transfers::transfer_with_signature` for a signed transfer
+    ///
+    /// `coin_type_tag` is the full Move type, e.g. `0x2::sui::SUI`.
+    pub fn transfer(
+        &self,
+        from_wallet_id: &str,
+        to_wallet_id: &str,
+        coin_type_tag: &str,
+        signed: &TransferResponse,
+    ) -> Result<MoveCall> {
+        Ok(MoveCall {
+            target: self.target("transfers", "transfer_with_signature"),
+            type_arguments: vec![coin_type_tag.to_string(), self.witness_type()],
+            arguments: vec![
+                CallArg::Object(from_wallet_id.to_string()),
+                CallArg::Object(to_wallet_id.to_string()),
+                pure(&signed.payload.amount)?,
+                pure(&signed.payload.coin_type)?,
+                pure(&signed.payload.memo)?,
+                pure(&signed.payload_version)?,
+                pure(&signed.timestamp_ms)?,
+                signature_bytes(signed)?,
+                CallArg::Object(self.enclave_id.clone()),
+                CallArg::Object(CLOCK_OBJECT_ID.to_string()),
+            ],
+        })
+    }
+
+    /// `wallet::withdraw` for a signed withdrawal
+    pub fn withdraw(&self, wallet_id: &str, coin_type_tag: &str, signed: &WithdrawResponse) -> Result<MoveCall> {
+        Ok(MoveCall {
+            target: self.target("wallet", "withdraw"),
+            type_arguments: vec![coin_type_tag.to_string(), self.witness_type()],
+            arguments: vec![
+                CallArg::Object(wallet_id.to_string()),
+                pure(&signed.payload.amount)?,
+                pure(&signed.payload.coin_type)?,
+                pure(&signed.payload_version)?,
+                pure(&signed.timestamp_ms)?,
+                signature_bytes(signed)?,
+                CallArg::Object(self.enclave_id.clone()),
+                CallArg::Object(CLOCK_OBJECT_ID.to_string()),
+            ],
+        })
+    }
+
+    /// `wallet::deposit_with_proof`, depositing the coin object `coin_id`
+    /// under a signed deposit ID; the proof is reusable for every deposit
+    pub fn deposit_with_proof(
+        &self,
+        wallet_id: &str,
+        coin_type_tag: &str,
+        coin_id: &str,
+        signed: &ProveDepositResponse,
+    ) -> Result<MoveCall> {
+        Ok(MoveCall {
+            target: self.target("wallet", "deposit_with_proof"),
+            type_arguments: vec![coin_type_tag.to_string(), self.witness_type()],
+            arguments: vec![
+                CallArg::Object(wallet_id.to_string()),
+                CallArg::Object(coin_id.to_string()),
+                pure(&signed.payload.deposit_id)?,
+                pure(&signed.payload_version)?,
+                pure(&signed.timestamp_ms)?,
+                signature_bytes(signed)?,
+                CallArg::Object(self.enclave_id.clone()),
+                CallArg::Object(CLOCK_OBJECT_ID.to_string()),
+            ],
+        })
+    }
+
+    /// `limits::set_daily_limit` for a signed daily limit
+    pub fn set_daily_limit(&self, wallet_id: &str, coin_type_tag: &str, signed: &SetLimitResponse) -> Result<MoveCall> {
+        Ok(MoveCall {
+            target: self.target("limits", "set_daily_limit"),
+            type_arguments: vec![coin_type_tag.to_string(), self.witness_type()],
+            arguments: vec![
+                CallArg::Object(wallet_id.to_string()),
+                pure(&signed.payload.daily_limit)?,
+                pure(&signed.payload.coin_type)?,
+                pure(&signed.payload_version)?,
+                pure(&signed.timestamp_ms)?,
+                signature_bytes(signed)?,
+                CallArg::Object(self.enclave_id.clone()),
+            ],
+        })
+    }
+
+    /// `bioguard::freeze_wallet` for a signed freeze
+    pub fn freeze_wallet(&self, wallet_id: &str, signed: &FreezeWalletResponse) -> Result<MoveCall> {
+        Ok(MoveCall {
+            target: self.target("bioguard", "freeze_wallet"),
+            type_arguments: vec![self.witness_type()],
+            arguments: vec![
+                CallArg::Object(wallet_id.to_string()),
+                pure(&signed.payload_version)?,
+                pure(&signed.timestamp_ms)?,
+                signature_bytes(signed)?,
+                CallArg::Object(self.enclave_id.clone()),
+            ],
+        })
+    }
+
+    /// `bioguard::request_unfreeze` for a signed unfreeze request
+    pub fn request_unfreeze(&self, wallet_id: &str, signed: &UnfreezeResponse) -> Result<MoveCall> {
+        Ok(MoveCall {
+            target: self.target("bioguard", "request_unfreeze"),
+            type_arguments: vec![self.witness_type()],
+            arguments: vec![
+                CallArg::Object(wallet_id.to_string()),
+                pure(&signed.payload_version)?,
+                pure(&signed.timestamp_ms)?,
+                signature_bytes(signed)?,
+                CallArg::Object(self.enclave_id.clone()),
+                CallArg::Object(CLOCK_OBJECT_ID.to_string()),
+            ],
+        })
+    }
+
+    /// `inheritance::set_beneficiary` for a signed beneficiary (or its removal)
+    pub fn set_beneficiary(&self, wallet_id: &str, signed: &SetBeneficiaryResponse) -> Result<MoveCall> {
+        Ok(MoveCall {
+            target: self.target("inheritance", "set_beneficiary"),
+            type_arguments: vec![self.witness_type()],
+            arguments: vec![
+                CallArg::Object(wallet_id.to_string()),
+                pure(&signed.payload.beneficiary_handle)?,
+                pure(&signed.payload.inactivity_timeout_ms)?,
+                pure(&signed.payload_version)?,
+                pure(&signed.timestamp_ms)?,
+                signature_bytes(signed)?,
+                CallArg::Object(self.enclave_id.clone()),
+            ],
+        })
+    }
+
+    /// `inheritance::check_in`, sent by a linked address to push back the claim window
+    pub fn check_in(&self, wallet_id: &str) -> MoveCall {
+        MoveCall {
+            target: self.target("inheritance", "check_in"),
+            type_arguments: vec![],
+            arguments: vec![CallArg::Object(wallet_id.to_string()), CallArg::Object(CLOCK_OBJECT_ID.to_string())],
+        }
+    }
+
+    /// `inheritance::claim`, sent by an address linked to the beneficiary wallet
+    pub fn claim_inheritance(&self, wallet_id: &str, beneficiary_wallet_id: &str, coin_type_tag: &str) -> MoveCall {
+        MoveCall {
+            target: self.target("inheritance", "claim"),
+            type_arguments: vec![coin_type_tag.to_string()],
+            arguments: vec![
+                CallArg::Object(wallet_id.to_string()),
+                CallArg::Object(beneficiary_wallet_id.to_string()),
+                CallArg::Object(CLOCK_OBJECT_ID.to_string()),
+            ],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{BioAuthPayload, PayloadVersion};
+
+    #[test]
+    fn test_apply_bioauth_arguments() {
+        let deployment = Deployment {
+            package_id: "0xram".to_string(),
+            enclave_package_id: "0xenc".to_string(),
+            enclave_id: "0xe1".to_string(),
+            registry_id: "0xr1".to_string(),
+        };
+        let response = BioAuthResponse {
+            signed: Signed {
+                payload: BioAuthPayload {
+                    handle: b"alice".to_vec(),
+                    amount: 5,
+                    result: 0,
+                    transcript: vec![],
+                    destination_verified: true,
+                    method: 1,
+                    seal: vec![7; 48],
+                    failed_attempts: 3,
+                },
+                intent: 3,
+                payload_version: PayloadVersion::V2,
+                timestamp_ms: 42,
+                signature: "abcd".to_string(),
+            },
+            request_id: String::new(),
+        };
+
+        let call = deployment.apply_bioauth("0xw1", &response).unwrap();
+        assert_eq!(call.target, "0xram::bioguard::apply_bioauth");
+        assert_eq!(call.type_arguments, vec!["0xenc::core::XWALLET"]);
+        assert_eq!(call.arguments.len(), 14);
+        assert_eq!(call.arguments[1], CallArg::Pure(b"\x05alice".to_vec()));
+        assert_eq!(call.arguments[5], CallArg::Pure(vec![1]));
+        assert_eq!(call.arguments[7], CallArg::Pure([vec![48], vec![7; 48]].concat()));
+        assert_eq!(call.arguments[8], CallArg::Pure(vec![3]));
+        assert_eq!(call.arguments[9], CallArg::Pure(vec![2]));
+        assert_eq!(call.arguments[11], CallArg::Pure(vec![2, 0xab, 0xcd]));
+        assert_eq!(call.arguments[13], CallArg::Object("0x6".to_string()));
+    }
+}
@@ -0,0 +1,559 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! Wire types shared with the enclave, backend and Move contract
+//!
+//! Payload structs are BCS-encoded for signing, so field order and types must
+//! match the enclave's `apps/ram/types.rs` and the structs in `core.move`.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use serde_repr::{Deserialize_repr, Serialize_repr};
+
+// ============================================================================
+// INTENTS
+// ============================================================================
+
+/// Intent each payload is signed under
+/// Must match the *_INTENT constants in core.move
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize_repr, Deserialize_repr)]
+#[repr(u8)]
+pub enum Intent {
+    CreateWallet = 0,
+    LinkAddress = 1,
+    Transfer = 2,
+    BioAuth = 3,
+    Withdraw = 4,
+    UnlinkAddress = 5,
+    SetLimit = 6,
+    Freeze = 7,
+    Unfreeze = 8,
+    Beneficiary = 9,
+    DepositProof = 10,
+}
+
+// ============================================================================
+// PAYLOADS (signed by the enclave)
+// ============================================================================
+
+/// Must match CreateWalletPayload in core.move
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CreateWalletPayload {
+    pub handle: Vec<u8>,
+}
+
+/// Must match LinkAddressPayload in core.move
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LinkAddressPayload {
+    pub handle: Vec<u8>,
+    pub address: [u8; 32],
+    pub label: Vec<u8>,
+}
+
+/// Must match UnlinkAddressPayload in core.move
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnlinkAddressPayload {
+    pub handle: Vec<u8>,
+    pub address: [u8; 32],
+}
+
+/// Must match TransferPayload in core.move
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransferPayload {
+    pub from_handle: Vec<u8>,
+    pub to_handle: Vec<u8>,
+    pub amount: u64,
+    pub coin_type: Vec<u8>,
+    /// UTF-8 payment reference, empty if none
+    pub memo: Vec<u8>,
+}
+
+/// Must match BioAuthPayload in core.move
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BioAuthPayload {
+    pub handle: Vec<u8>,
+    pub amount: u64,
+    pub result: u8,              // 0=OK, 1=InvalidAmount, 2=Duress
+    pub transcript: Vec<u8>,
+    pub destination_verified: bool,
+    pub method: u8,              // 0=Voice, 1=Typed
+    pub seal: Vec<u8>,           // Sealed decoy flag, passed to the contract as-is
+    pub failed_attempts: u8,     // Consecutive failed bio-auths before this one
+}
+
+/// Must match WithdrawPayload in core.move
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WithdrawPayload {
+    pub handle: Vec<u8>,
+    pub amount: u64,
+    pub coin_type: Vec<u8>,
+}
+
+/// Must match SetLimitPayload in core.move
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SetLimitPayload {
+    pub handle: Vec<u8>,
+    pub daily_limit: u64,
+    pub coin_type: Vec<u8>,
+}
+
+/// Must match FreezePayload in core.move
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FreezePayload {
+    pub handle: Vec<u8>,
+}
+
+/// Must match UnfreezePayload in core.move
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnfreezePayload {
+    pub handle: Vec<u8>,
+}
+
+/// Must match BeneficiaryPayload in core.move
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BeneficiaryPayload {
+    pub handle: Vec<u8>,
+    /// Empty to remove the beneficiary
+    pub beneficiary_handle: Vec<u8>,
+    pub inactivity_timeout_ms: u64,
+}
+
+/// Must match DepositProofPayload in core.move
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DepositProofPayload {
+    pub handle: Vec<u8>,
+    /// The exchange's memo or intent ID for this handle
+    pub deposit_id: Vec<u8>,
+}
+
+/// BioAuth result codes
+/// Must match BIOAUTH_OK, BIOAUTH_INVALID_AMOUNT, BIOAUTH_DURESS in core.move
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize_repr, Deserialize_repr)]
+#[repr(u8)]
+pub enum BioAuthResult {
+    Ok = 0,
+    InvalidAmount = 1,
+    Duress = 2,
+}
+
+/// How the user confirmed a bio-auth
+/// Must match BIOAUTH_METHOD_VOICE, BIOAUTH_METHOD_TYPED in core.move
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize_repr, Deserialize_repr)]
+#[repr(u8)]
+pub enum BioAuthMethod {
+    Voice = 0,
+    Typed = 1,
+}
+
+// ============================================================================
+// SIGNED RESPONSES
+// ============================================================================
+
+/// Layout a payload is signed in
+/// Must match PAYLOAD_V1, PAYLOAD_V2, PAYLOAD_V3 in core.move
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize_repr, Deserialize_repr)]
+#[repr(u8)]
+pub enum PayloadVersion {
+    /// The payload struct's fields
+    #[default]
+    V1 = 1,
+    /// The version byte, then the payload struct's fields
+    V2 = 2,
+    /// The version byte, the RAM package ID, then the payload struct's fields
+    V3 = 3,
+}
+
+/// Enclave-signed payload, as returned by every signing endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Signed<P> {
+    pub payload: P,
+    pub intent: u8,
+    /// Absent from enclaves that predate versioned layouts, which sign v1
+    #[serde(default)]
+    pub payload_version: PayloadVersion,
+    pub timestamp_ms: u64,
+    /// Hex-encoded Ed25519 signature over the BCS intent message
+    pub signature: String,
+}
+
+pub type CreateWalletResponse = Signed<CreateWalletPayload>;
+pub type LinkAddressResponse = Signed<LinkAddressPayload>;
+pub type UnlinkAddressResponse = Signed<UnlinkAddressPayload>;
+pub type TransferResponse = Signed<TransferPayload>;
+pub type WithdrawResponse = Signed<WithdrawPayload>;
+pub type SetLimitResponse = Signed<SetLimitPayload>;
+pub type FreezeWalletResponse = Signed<FreezePayload>;
+pub type UnfreezeResponse = Signed<UnfreezePayload>;
+pub type SetBeneficiaryResponse = Signed<BeneficiaryPayload>;
+pub type ProveDepositResponse = Signed<DepositProofPayload>;
+
+/// Signed bio-auth result (blind: the verdict is only learnt on-chain)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BioAuthResponse {
+    #[serde(flatten)]
+    pub signed: Signed<BioAuthPayload>,
+    /// Opaque ID for the backend's privileged diagnostics channel
+    #[serde(default)]
+    pub request_id: String,
+}
+
+/// Phrase to speak, issued under a short-lived, single-use ID
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChallengeResponse {
+    pub challenge_id: String,
+    pub phrase: String,
+    pub expires_at_ms: u64,
+}
+
+// ============================================================================
+// REQUESTS
+// ============================================================================
+
+/// Voice bio-auth request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BioAuthRequest {
+    pub handle: String,
+    pub audio_base64: String,
+    pub expected_amount: u64,        // Amount in smallest unit (MIST for SUI)
+    pub coin_type: Option<String>,   // Move coin type, e.g. "0x2::sui::SUI" (the default)
+    #[serde(default)]
+    pub destination_hint: Option<String>, // Withdrawal destination; user speaks its last 4 chars
+    #[serde(default)]
+    pub locale: Option<String>,      // BCP 47 tag, e.g. "de-DE"; how the user writes numbers
+    #[serde(default)]
+    pub skip_analysis_cache: bool,   // Analyze afresh even if this clip was analyzed moments ago
+}
+
+/// Timing of one key press, ms since the confirmation field was focused
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Keystroke {
+    pub down_ms: u64,
+    pub up_ms: u64,
+}
+
+/// Typed-confirmation auth for users who cannot speak
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TypedAuthRequest {
+    pub handle: String,
+    pub typed_text: String,
+    pub expected_amount: u64,
+    pub coin_type: Option<String>,
+    #[serde(default)]
+    pub destination_hint: Option<String>,
+    #[serde(default)]
+    pub locale: Option<String>,
+    pub keystrokes: Vec<Keystroke>,
+    #[serde(default)]
+    pub pasted: bool,
+}
+
+// ============================================================================
+// BACKEND MODELS
+// ============================================================================
+
+/// Indexed on-chain event (`POST /api/events`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RamEvent {
+    pub handle: Option<String>,
+    pub event_type: String,
+    pub amount: Option<i64>,
+    pub coin_type: Option<String>,
+    /// BioAuth result: 0=OK, 1=InvalidAmount, 2=Duress
+    pub result_code: Option<i32>,
+    pub from_handle: Option<String>,
+    pub to_handle: Option<String>,
+    pub owner: Option<String>,
+    pub wallet_id: Option<String>,
+    pub tx_digest: String,
+    /// RFC 3339 timestamp
+    pub timestamp: String,
+    pub raw_json: Option<Value>,
+    pub gas_used: Option<i64>,
+    pub sender: Option<String>,
+    pub sender_label: Option<String>,
+    /// Transfer memo as emitted on-chain
+    pub memo: Option<String>,
+    /// Payload layout a transfer was verified against (0 = wallet-signed,
+    /// None = emitted before layouts were versioned)
+    pub payload_version: Option<i16>,
+    /// On a refund, the transaction of the payment it refunds
+    #[serde(default)]
+    pub refund_of: Option<String>,
+    /// On a payment, the transactions of its completed refunds
+    #[serde(default)]
+    pub refunded_in: Vec<String>,
+}
+
+/// Wallet summary statistics (`POST /api/stats`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WalletStats {
+    pub handle: String,
+    pub total_deposits: i64,
+    pub total_withdrawals: i64,
+    pub total_transfers_sent: i64,
+    pub total_transfers_received: i64,
+}
+
+/// Sui address linked to a wallet (`POST /api/addresses`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinkedAddress {
+    pub address: String,
+    pub label: String,
+    pub linked_at_ms: i64,
+}
+
+/// One coin in a wallet's portfolio
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoinPosition {
+    pub coin_type: String,
+    pub deposited: i64,
+    pub withdrawn: i64,
+    pub transferred_in: i64,
+    pub transferred_out: i64,
+    pub net_flow: i64,
+    pub live_balance: Option<u64>,
+}
+
+/// Multi-coin portfolio (`GET /api/portfolio/:handle`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortfolioResponse {
+    pub handle: String,
+    pub wallet_id: Option<String>,
+    pub coins: Vec<CoinPosition>,
+}
+
+/// Backend health (`GET /health`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackendHealth {
+    pub status: String,           // "healthy" or "unhealthy"
+    pub nautilus_server: String,  // "up" or "down"
+    pub database: String,
+    pub sui_rpc: String,
+    pub indexer: String,
+}
+
+/// Merchant webhook notification, verified with `webhook::verify`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookEvent {
+    /// Derived from the on-chain event: the same on every delivery attempt,
+    /// so receivers drop notifications whose ID they've already handled
+    pub id: String,
+    /// `payment.received`
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub created_at_ms: i64,
+    pub data: PaymentReceived,
+}
+
+/// A transfer credited to the webhook's wallet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaymentReceived {
+    /// Wallet that was paid
+    pub handle: String,
+    pub payer_handle: Option<String>,
+    pub amount: Option<i64>,
+    pub coin_type: Option<String>,
+    /// The payer's reference (e.g. an order number), if the transfer carried one
+    pub memo: Option<String>,
+    pub tx_digest: String,
+    pub timestamp_ms: i64,
+}
+
+// ============================================================================
+// ERRORS
+// ============================================================================
+
+/// Machine-readable error code of an `ApiErrorBody`
+/// Must match `ErrorCode` in the enclave's `lib.rs`; the backend uses this one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// Malformed request the enclave or backend couldn't act on
+    BadRequest,
+    /// Request fields failed validation; `details.fields` lists each one
+    ValidationFailed,
+    /// Missing or invalid request signature, or admin token
+    Unauthorized,
+    Forbidden,
+    /// Caller's address isn't allowlisted for the route class
+    PeerNotAllowed,
+    NotFound,
+    MethodNotAllowed,
+    /// `API-Version` pin the route can't serve
+    UnsupportedVersion,
+    Conflict,
+    /// Audio clip or signed request was already used
+    ReplayDetected,
+    PayloadTooLarge,
+    /// Too many attempts; the message says when to try again
+    RateLimited,
+    /// Compliance screening refused the recipient or wallet (HTTP 451)
+    ComplianceBlocked,
+    /// The voice analysis wasn't confident enough to sign a verdict; record again
+    AnalysisInconclusive,
+    /// Wallet creation lacked the proof of work or CAPTCHA the enclave asks for
+    HumanCheckFailed,
+    /// A transfer's dry run says it would abort on-chain; nothing was signed
+    SimulationFailed,
+    Internal,
+    /// No enclave could be reached
+    EnclaveUnavailable,
+    /// A dependency (database, sponsor, admin API) is down or disabled
+    Unavailable,
+    Timeout,
+    /// Code added after this SDK was built
+    #[serde(other)]
+    Unknown,
+}
+
+impl ErrorCode {
+    /// Every code a service may answer with (all but `Unknown`)
+    pub const ALL: &'static [ErrorCode] = &[
+        ErrorCode::BadRequest,
+        ErrorCode::ValidationFailed,
+        ErrorCode::Unauthorized,
+        ErrorCode::Forbidden,
+        ErrorCode::PeerNotAllowed,
+        ErrorCode::NotFound,
+        ErrorCode::MethodNotAllowed,
+        ErrorCode::UnsupportedVersion,
+        ErrorCode::Conflict,
+        ErrorCode::ReplayDetected,
+        ErrorCode::PayloadTooLarge,
+        ErrorCode::RateLimited,
+        ErrorCode::ComplianceBlocked,
+        ErrorCode::AnalysisInconclusive,
+        ErrorCode::HumanCheckFailed,
+        ErrorCode::SimulationFailed,
+        ErrorCode::Internal,
+        ErrorCode::EnclaveUnavailable,
+        ErrorCode::Unavailable,
+        ErrorCode::Timeout,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::BadRequest => "bad_request",
+            ErrorCode::ValidationFailed => "validation_failed",
+            ErrorCode::Unauthorized => "unauthorized",
+            ErrorCode::Forbidden => "forbidden",
+            ErrorCode::PeerNotAllowed => "peer_not_allowed",
+            ErrorCode::NotFound => "not_found",
+            ErrorCode::MethodNotAllowed => "method_not_allowed",
+            ErrorCode::UnsupportedVersion => "unsupported_version",
+            ErrorCode::Conflict => "conflict",
+            ErrorCode::ReplayDetected => "replay_detected",
+            ErrorCode::PayloadTooLarge => "payload_too_large",
+            ErrorCode::RateLimited => "rate_limited",
+            ErrorCode::ComplianceBlocked => "compliance_blocked",
+            ErrorCode::AnalysisInconclusive => "analysis_inconclusive",
+            ErrorCode::HumanCheckFailed => "human_check_failed",
+            ErrorCode::SimulationFailed => "simulation_failed",
+            ErrorCode::Internal => "internal",
+            ErrorCode::EnclaveUnavailable => "enclave_unavailable",
+            ErrorCode::Unavailable => "unavailable",
+            ErrorCode::Timeout => "timeout",
+            ErrorCode::Unknown => "unknown",
+        }
+    }
+
+    /// Code for an error that only has an HTTP status
+    pub fn from_status(status: u16) -> Self {
+        match status {
+            401 => ErrorCode::Unauthorized,
+            403 => ErrorCode::Forbidden,
+            404 => ErrorCode::NotFound,
+            405 => ErrorCode::MethodNotAllowed,
+            406 => ErrorCode::UnsupportedVersion,
+            409 => ErrorCode::Conflict,
+            413 => ErrorCode::PayloadTooLarge,
+            422 => ErrorCode::ValidationFailed,
+            429 => ErrorCode::RateLimited,
+            451 => ErrorCode::ComplianceBlocked,
+            502 => ErrorCode::EnclaveUnavailable,
+            503 => ErrorCode::Unavailable,
+            504 => ErrorCode::Timeout,
+            500..=599 => ErrorCode::Internal,
+            _ => ErrorCode::BadRequest,
+        }
+    }
+}
+
+impl std::fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Body of every error response from the backend and the enclave
+///
+/// ```json
+/// { "code": "validation_failed", "message": "Request validation failed",
+///   "details": { "fields": [{ "field": "payload.amount", "message": "must be greater than 0" }] },
+///   "request_id": "6f1c..." }
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApiErrorBody {
+    pub code: ErrorCode,
+    pub message: String,
+    /// Code-specific context, e.g. `fields` for `validation_failed`
+    #[serde(default)]
+    pub details: Option<Value>,
+    /// `x-request-id` of the failed request, shared by the backend and enclave logs
+    #[serde(default)]
+    pub request_id: Option<String>,
+}
+
+impl ApiErrorBody {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self { code, message: message.into(), details: None, request_id: None }
+    }
+
+    pub fn with_details(mut self, details: Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+
+    /// Invalid fields of a `validation_failed` error
+    pub fn fields(&self) -> Option<Vec<FieldError>> {
+        let fields = self.details.as_ref()?.get("fields")?;
+        serde_json::from_value(fields.clone()).ok()
+    }
+}
+
+/// Request field that failed validation (entries of `details.fields`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldError {
+    pub field: String,            // JSON path, e.g. "payload.expected_amount"
+    pub message: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_codes_round_trip() {
+        for code in ErrorCode::ALL {
+            assert_eq!(serde_json::to_value(code).unwrap(), code.as_str());
+            assert_eq!(serde_json::from_value::<ErrorCode>(code.as_str().into()).unwrap(), *code);
+        }
+        assert_eq!(serde_json::from_value::<ErrorCode>("teapot".into()).unwrap(), ErrorCode::Unknown);
+    }
+
+    /// The enclave can't depend on this crate (it builds in its own context),
+    /// so its copy of the codes is checked here
+    #[test]
+    fn test_enclave_error_codes_match() {
+        let source = include_str!("../../ram-nautilus/src/nautilus-server/src/lib.rs");
+        let enclave: Vec<&str> = source
+            .lines()
+            .filter(|line| line.trim_start().starts_with("ErrorCode::") && line.contains("=> \""))
+            .filter_map(|line| line.split('"').nth(1))
+            .collect();
+        assert!(!enclave.is_empty());
+        for code in enclave {
+            assert!(ErrorCode::ALL.iter().any(|c| c.as_str() == code), "enclave code '{}' missing here", code);
+        }
+    }
+}
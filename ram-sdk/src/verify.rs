@@ -0,0 +1,239 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! Enclave signature verification
+//!
+//! The enclave signs `IntentMessage { intent, timestamp_ms, data }` BCS bytes
+//! with its ephemeral Ed25519 key, where `data` is the payload (layout v1) or
+//! the version byte followed by the payload (v2 and later); the Move contract checks the same bytes
+//! against the key registered on the `Enclave` object. Verifying here catches
+//! a tampered or misrouted response before a transaction is built from it.
+//!
+//! Layout v3 also signs the RAM package the enclave signs for, between the
+//! version byte and the payload, so verifying it needs the package ID of the
+//! network the transaction goes to (see [`VerifySigned::verify_for`]).
+
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use serde::Serialize;
+
+use crate::error::{Result, SdkError};
+use crate::types::{BioAuthResponse, PayloadVersion, Signed};
+
+/// Message the enclave signs
+/// Must match IntentMessage in the enclave's common.rs
+#[derive(Serialize)]
+struct IntentMessage<'a, T> {
+    intent: u8,
+    timestamp_ms: u64,
+    data: &'a T,
+}
+
+/// A payload in layout v2+
+/// Must match Versioned in core.move
+#[derive(Serialize)]
+struct Versioned<'a, T> {
+    version: u8,
+    payload: &'a T,
+}
+
+/// A payload in layout v3
+/// Must match Domain in core.move
+#[derive(Serialize)]
+struct Domain<'a, T> {
+    version: u8,
+    package: [u8; 32],
+    payload: &'a T,
+}
+
+/// BCS bytes the enclave signs for a payload in layout v1
+pub fn signing_bytes<T: Serialize>(intent: u8, timestamp_ms: u64, payload: &T) -> Result<Vec<u8>> {
+    bcs::to_bytes(&IntentMessage { intent, timestamp_ms, data: payload })
+        .map_err(|e| SdkError::InvalidInput(format!("BCS encoding failed: {}", e)))
+}
+
+/// BCS bytes the enclave signs for a payload in the given layout
+///
+/// `package` is the RAM package on the target network; only layout v3 signs
+/// it, and v3 can't be encoded without it.
+pub fn versioned_signing_bytes<T: Serialize>(
+    intent: u8,
+    timestamp_ms: u64,
+    version: PayloadVersion,
+    package: Option<&[u8; 32]>,
+    payload: &T,
+) -> Result<Vec<u8>> {
+    match version {
+        PayloadVersion::V1 => signing_bytes(intent, timestamp_ms, payload),
+        PayloadVersion::V2 => signing_bytes(intent, timestamp_ms, &Versioned { version: version as u8, payload }),
+        PayloadVersion::V3 => {
+            let package = *package.ok_or_else(|| {
+                SdkError::InvalidInput("payload layout v3 is bound to a RAM package; its ID is needed".to_string())
+            })?;
+            signing_bytes(intent, timestamp_ms, &Domain { version: version as u8, package, payload })
+        }
+    }
+}
+
+/// Parse a RAM package ID (0x-prefixed hex, leading zeros optional) into its address bytes
+pub fn parse_package_id(package_id: &str) -> Result<[u8; 32]> {
+    let hex_id = package_id
+        .trim()
+        .strip_prefix("0x")
+        .ok_or_else(|| SdkError::InvalidInput(format!("package ID '{}' must start with 0x", package_id)))?;
+    if hex_id.is_empty() || hex_id.len() > 64 {
+        return Err(SdkError::InvalidInput(format!("package ID '{}' must have 1 to 64 hex digits", package_id)));
+    }
+    hex::decode(format!("{:0>64}", hex_id))
+        .map_err(|e| SdkError::InvalidInput(format!("invalid package ID hex: {}", e)))?
+        .try_into()
+        .map_err(|_| SdkError::InvalidInput("package ID must be 32 bytes".to_string()))
+}
+
+/// Parse a hex-encoded (optionally 0x-prefixed) Ed25519 public key
+pub fn parse_public_key(hex_key: &str) -> Result<VerifyingKey> {
+    let bytes: [u8; 32] = hex::decode(hex_key.trim_start_matches("0x"))
+        .map_err(|e| SdkError::InvalidInput(format!("invalid public key hex: {}", e)))?
+        .try_into()
+        .map_err(|_| SdkError::InvalidInput("public key must be 32 bytes".to_string()))?;
+    VerifyingKey::from_bytes(&bytes).map_err(|e| SdkError::InvalidInput(format!("invalid public key: {}", e)))
+}
+
+/// Check a hex signature over `payload` signed under `intent` at `timestamp_ms`
+/// in layout `version` (for `package`, if v3)
+pub fn verify_signature<T: Serialize>(
+    public_key: &VerifyingKey,
+    package: Option<&[u8; 32]>,
+    intent: u8,
+    timestamp_ms: u64,
+    version: PayloadVersion,
+    payload: &T,
+    signature_hex: &str,
+) -> Result<()> {
+    let sig_bytes: [u8; 64] = hex::decode(signature_hex.trim_start_matches("0x"))
+        .map_err(|e| SdkError::InvalidSignature(format!("not hex: {}", e)))?
+        .try_into()
+        .map_err(|_| SdkError::InvalidSignature("signature must be 64 bytes".to_string()))?;
+    let msg = versioned_signing_bytes(intent, timestamp_ms, version, package, payload)?;
+    public_key
+        .verify(&msg, &Signature::from_bytes(&sig_bytes))
+        .map_err(|e| SdkError::InvalidSignature(e.to_string()))
+}
+
+/// Signed enclave responses that can be checked against the enclave key
+pub trait VerifySigned {
+    /// Verify a v1 or v2 response; v3 responses need [`Self::verify_for`]
+    fn verify(&self, public_key: &VerifyingKey) -> Result<()> {
+        self.verify_for(public_key, None)
+    }
+
+    /// Verify a response in any layout, v3 ones as signed for `package`
+    fn verify_for(&self, public_key: &VerifyingKey, package: Option<&[u8; 32]>) -> Result<()>;
+}
+
+impl<P: Serialize> VerifySigned for Signed<P> {
+    fn verify_for(&self, public_key: &VerifyingKey, package: Option<&[u8; 32]>) -> Result<()> {
+        verify_signature(
+            public_key,
+            package,
+            self.intent,
+            self.timestamp_ms,
+            self.payload_version,
+            &self.payload,
+            &self.signature,
+        )
+    }
+}
+
+impl VerifySigned for BioAuthResponse {
+    fn verify_for(&self, public_key: &VerifyingKey, package: Option<&[u8; 32]>) -> Result<()> {
+        self.signed.verify_for(public_key, package)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Intent, TransferPayload};
+    use ed25519_dalek::{Signer, SigningKey};
+
+    fn signed_transfer(key: &SigningKey) -> Signed<TransferPayload> {
+        let payload = TransferPayload {
+            from_handle: b"alice".to_vec(),
+            to_handle: b"bob".to_vec(),
+            amount: 5_000_000_000,
+            coin_type: b"0x2::sui::SUI".to_vec(),
+            memo: b"INV-1".to_vec(),
+        };
+        let msg = signing_bytes(Intent::Transfer as u8, 1_700_000_000_000, &payload).unwrap();
+        Signed {
+            signature: hex::encode(key.sign(&msg).to_bytes()),
+            payload,
+            intent: Intent::Transfer as u8,
+            payload_version: PayloadVersion::V1,
+            timestamp_ms: 1_700_000_000_000,
+        }
+    }
+
+    #[test]
+    fn test_verify_roundtrip() {
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let pk = parse_public_key(&hex::encode(key.verifying_key().to_bytes())).unwrap();
+        let mut signed = signed_transfer(&key);
+        assert!(signed.verify(&pk).is_ok());
+
+        signed.payload.amount += 1;
+        assert!(matches!(signed.verify(&pk), Err(SdkError::InvalidSignature(_))));
+    }
+
+    #[test]
+    fn test_v2_signs_the_version_byte_before_the_payload() {
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let mut signed = signed_transfer(&key);
+        let v1 = signing_bytes(signed.intent, signed.timestamp_ms, &signed.payload).unwrap();
+        let v2 = versioned_signing_bytes(signed.intent, signed.timestamp_ms, PayloadVersion::V2, None, &signed.payload).unwrap();
+        // intent (1 byte) and timestamp (8 bytes), then the version byte
+        assert_eq!(v2[..9], v1[..9]);
+        assert_eq!(v2[9], PayloadVersion::V2 as u8);
+        assert_eq!(v2[10..], v1[9..]);
+
+        // A v1 signature doesn't verify as v2, and vice versa
+        signed.payload_version = PayloadVersion::V2;
+        assert!(signed.verify(&key.verifying_key()).is_err());
+        signed.signature = hex::encode(key.sign(&v2).to_bytes());
+        assert!(signed.verify(&key.verifying_key()).is_ok());
+    }
+
+    #[test]
+    fn test_v3_is_bound_to_the_package() {
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let mut signed = signed_transfer(&key);
+        let testnet = parse_package_id("0xabc").unwrap();
+        let mainnet = parse_package_id(&format!("0x{}", "d".repeat(64))).unwrap();
+        assert_eq!(testnet[30..], [0x0a, 0xbc]);
+
+        let v1 = signing_bytes(signed.intent, signed.timestamp_ms, &signed.payload).unwrap();
+        let v3 = versioned_signing_bytes(signed.intent, signed.timestamp_ms, PayloadVersion::V3, Some(&testnet), &signed.payload)
+            .unwrap();
+        // intent and timestamp, the version byte, the package, then the payload
+        assert_eq!(v3[9], PayloadVersion::V3 as u8);
+        assert_eq!(v3[10..42], testnet);
+        assert_eq!(v3[42..], v1[9..]);
+
+        signed.payload_version = PayloadVersion::V3;
+        signed.signature = hex::encode(key.sign(&v3).to_bytes());
+        let pk = key.verifying_key();
+        assert!(signed.verify_for(&pk, Some(&testnet)).is_ok());
+        // Not replayable against another network's package, and not checkable without one
+        assert!(matches!(signed.verify_for(&pk, Some(&mainnet)), Err(SdkError::InvalidSignature(_))));
+        assert!(matches!(signed.verify(&pk), Err(SdkError::InvalidInput(_))));
+        assert!(parse_package_id("abc").is_err());
+    }
+
+    #[test]
+    fn test_intent_is_part_of_the_message() {
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let mut signed = signed_transfer(&key);
+        signed.intent = Intent::Withdraw as u8;
+        assert!(signed.verify(&key.verifying_key()).is_err());
+    }
+}
@@ -0,0 +1,219 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! Browser bindings (`wasm` feature)
+//!
+//! Lets a web frontend check enclave signatures and BCS-encode payloads with
+//! the same Rust definitions the SDK uses, instead of hand-written TypeScript
+//! that can drift from the Move structs. Responses are passed as the JSON the
+//! backend returned; `kind` names the endpoint that produced them.
+//!
+//! ```bash
+//! wasm-pack build ram-sdk --target web -- --no-default-features --features wasm
+//! ```
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::str::FromStr;
+use wasm_bindgen::prelude::*;
+
+use crate::error::SdkError;
+use crate::types::*;
+use crate::verify::{self, VerifySigned};
+
+/// Which signed payload a JSON response carries
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PayloadKind {
+    CreateWallet,
+    LinkAddress,
+    UnlinkAddress,
+    Transfer,
+    BioAuth,
+    Withdraw,
+    SetLimit,
+    Freeze,
+    Unfreeze,
+    Beneficiary,
+    DepositProof,
+}
+
+impl FromStr for PayloadKind {
+    type Err = SdkError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "create_wallet" => Ok(PayloadKind::CreateWallet),
+            "link_address" => Ok(PayloadKind::LinkAddress),
+            "unlink_address" => Ok(PayloadKind::UnlinkAddress),
+            "transfer" => Ok(PayloadKind::Transfer),
+            "bio_auth" | "typed_auth" => Ok(PayloadKind::BioAuth),
+            "withdraw" => Ok(PayloadKind::Withdraw),
+            "set_limit" => Ok(PayloadKind::SetLimit),
+            "freeze_wallet" => Ok(PayloadKind::Freeze),
+            "request_unfreeze" => Ok(PayloadKind::Unfreeze),
+            "set_beneficiary" => Ok(PayloadKind::Beneficiary),
+            "prove_deposit" => Ok(PayloadKind::DepositProof),
+            other => Err(SdkError::InvalidInput(format!("unknown payload kind '{}'", other))),
+        }
+    }
+}
+
+impl PayloadKind {
+    /// Intent the enclave signs this payload under
+    pub fn intent(&self) -> Intent {
+        match self {
+            PayloadKind::CreateWallet => Intent::CreateWallet,
+            PayloadKind::LinkAddress => Intent::LinkAddress,
+            PayloadKind::UnlinkAddress => Intent::UnlinkAddress,
+            PayloadKind::Transfer => Intent::Transfer,
+            PayloadKind::BioAuth => Intent::BioAuth,
+            PayloadKind::Withdraw => Intent::Withdraw,
+            PayloadKind::SetLimit => Intent::SetLimit,
+            PayloadKind::Freeze => Intent::Freeze,
+            PayloadKind::Unfreeze => Intent::Unfreeze,
+            PayloadKind::Beneficiary => Intent::Beneficiary,
+            PayloadKind::DepositProof => Intent::DepositProof,
+        }
+    }
+}
+
+fn js_err(e: SdkError) -> JsError {
+    JsError::new(&e.to_string())
+}
+
+fn parse<T: DeserializeOwned>(json: &str) -> Result<T, SdkError> {
+    serde_json::from_str(json).map_err(|e| SdkError::InvalidInput(format!("invalid JSON: {}", e)))
+}
+
+fn bcs_bytes<T: Serialize>(value: &T) -> Result<Vec<u8>, SdkError> {
+    bcs::to_bytes(value).map_err(|e| SdkError::InvalidInput(format!("BCS encoding failed: {}", e)))
+}
+
+/// Verify one signed response of a known payload type
+fn verify_as<P: DeserializeOwned + Serialize>(
+    kind: PayloadKind,
+    public_key_hex: &str,
+    response_json: &str,
+    package: Option<&[u8; 32]>,
+) -> Result<bool, SdkError> {
+    let key = verify::parse_public_key(public_key_hex)?;
+    let signed: Signed<P> = parse(response_json)?;
+    if signed.intent != kind.intent() as u8 {
+        return Ok(false);
+    }
+    match signed.verify_for(&key, package) {
+        Ok(()) => Ok(true),
+        Err(SdkError::InvalidSignature(_)) => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+/// Whether a signed response JSON verifies against the enclave key (false on bad signature or intent)
+///
+/// `package_id` is the RAM package on the target network, needed for layout v3 responses.
+pub fn verify_response(
+    kind: PayloadKind,
+    public_key_hex: &str,
+    response_json: &str,
+    package_id: Option<&str>,
+) -> Result<bool, SdkError> {
+    let package = package_id.map(verify::parse_package_id).transpose()?;
+    let package = package.as_ref();
+    match kind {
+        PayloadKind::CreateWallet => verify_as::<CreateWalletPayload>(kind, public_key_hex, response_json, package),
+        PayloadKind::LinkAddress => verify_as::<LinkAddressPayload>(kind, public_key_hex, response_json, package),
+        PayloadKind::UnlinkAddress => verify_as::<UnlinkAddressPayload>(kind, public_key_hex, response_json, package),
+        PayloadKind::Transfer => verify_as::<TransferPayload>(kind, public_key_hex, response_json, package),
+        PayloadKind::BioAuth => verify_as::<BioAuthPayload>(kind, public_key_hex, response_json, package),
+        PayloadKind::Withdraw => verify_as::<WithdrawPayload>(kind, public_key_hex, response_json, package),
+        PayloadKind::SetLimit => verify_as::<SetLimitPayload>(kind, public_key_hex, response_json, package),
+        PayloadKind::Freeze => verify_as::<FreezePayload>(kind, public_key_hex, response_json, package),
+        PayloadKind::Unfreeze => verify_as::<UnfreezePayload>(kind, public_key_hex, response_json, package),
+        PayloadKind::Beneficiary => verify_as::<BeneficiaryPayload>(kind, public_key_hex, response_json, package),
+        PayloadKind::DepositProof => verify_as::<DepositProofPayload>(kind, public_key_hex, response_json, package),
+    }
+}
+
+/// BCS bytes of a payload JSON
+pub fn encode_payload(kind: PayloadKind, payload_json: &str) -> Result<Vec<u8>, SdkError> {
+    match kind {
+        PayloadKind::CreateWallet => bcs_bytes(&parse::<CreateWalletPayload>(payload_json)?),
+        PayloadKind::LinkAddress => bcs_bytes(&parse::<LinkAddressPayload>(payload_json)?),
+        PayloadKind::UnlinkAddress => bcs_bytes(&parse::<UnlinkAddressPayload>(payload_json)?),
+        PayloadKind::Transfer => bcs_bytes(&parse::<TransferPayload>(payload_json)?),
+        PayloadKind::BioAuth => bcs_bytes(&parse::<BioAuthPayload>(payload_json)?),
+        PayloadKind::Withdraw => bcs_bytes(&parse::<WithdrawPayload>(payload_json)?),
+        PayloadKind::SetLimit => bcs_bytes(&parse::<SetLimitPayload>(payload_json)?),
+        PayloadKind::Freeze => bcs_bytes(&parse::<FreezePayload>(payload_json)?),
+        PayloadKind::Unfreeze => bcs_bytes(&parse::<UnfreezePayload>(payload_json)?),
+        PayloadKind::Beneficiary => bcs_bytes(&parse::<BeneficiaryPayload>(payload_json)?),
+        PayloadKind::DepositProof => bcs_bytes(&parse::<DepositProofPayload>(payload_json)?),
+    }
+}
+
+/// Whether a signed enclave response verifies against the enclave public key
+///
+/// `kind` is the endpoint (`"transfer"`, `"bio_auth"`, ...). Returns false
+/// for a bad signature or wrong intent; throws on malformed input, or on a
+/// layout v3 response without `package_id` (the RAM package it must be
+/// signed for).
+#[wasm_bindgen(js_name = verifyEnclaveSignature)]
+pub fn verify_enclave_signature(
+    kind: &str,
+    public_key_hex: &str,
+    response_json: &str,
+    package_id: Option<String>,
+) -> Result<bool, JsError> {
+    let kind = PayloadKind::from_str(kind).map_err(js_err)?;
+    verify_response(kind, public_key_hex, response_json, package_id.as_deref()).map_err(js_err)
+}
+
+/// BCS encoding of a payload, exactly as it is embedded in the signed message
+#[wasm_bindgen(js_name = bcsEncodePayload)]
+pub fn bcs_encode_payload(kind: &str, payload_json: &str) -> Result<Vec<u8>, JsError> {
+    let kind = PayloadKind::from_str(kind).map_err(js_err)?;
+    encode_payload(kind, payload_json).map_err(js_err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_payload_kinds() {
+        assert_eq!(PayloadKind::from_str("typed_auth").unwrap(), PayloadKind::BioAuth);
+        assert_eq!(PayloadKind::from_str("withdraw").unwrap().intent(), Intent::Withdraw);
+        assert_eq!(PayloadKind::from_str("set_limit").unwrap().intent(), Intent::SetLimit);
+        assert_eq!(PayloadKind::from_str("request_unfreeze").unwrap().intent(), Intent::Unfreeze);
+        assert_eq!(PayloadKind::from_str("set_beneficiary").unwrap().intent(), Intent::Beneficiary);
+        assert_eq!(PayloadKind::from_str("prove_deposit").unwrap().intent(), Intent::DepositProof);
+        assert!(PayloadKind::from_str("tweet").is_err());
+    }
+
+    #[test]
+    fn test_verify_and_encode_match_signed_bytes() {
+        use ed25519_dalek::{Signer, SigningKey};
+
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let payload = WithdrawPayload { handle: b"alice".to_vec(), amount: 5, coin_type: b"SUI".to_vec() };
+        let msg = verify::signing_bytes(Intent::Withdraw as u8, 42, &payload).unwrap();
+        let response = serde_json::json!({
+            "payload": payload,
+            "intent": Intent::Withdraw as u8,
+            "timestamp_ms": 42,
+            "signature": hex::encode(key.sign(&msg).to_bytes()),
+        })
+        .to_string();
+        let pk = hex::encode(key.verifying_key().to_bytes());
+
+        assert!(verify_response(PayloadKind::Withdraw, &pk, &response, None).unwrap());
+        let tampered = response.replace("\"amount\":5", "\"amount\":6");
+        assert!(!verify_response(PayloadKind::Withdraw, &pk, &tampered, None).unwrap());
+        // A withdraw response isn't a transfer
+        assert!(verify_response(PayloadKind::Transfer, &pk, &response, None).is_err());
+
+        // Signed message = intent byte + timestamp (u64 LE) + BCS payload
+        let encoded = encode_payload(PayloadKind::Withdraw, &serde_json::to_string(&payload).unwrap()).unwrap();
+        assert_eq!(&msg[9..], encoded.as_slice());
+    }
+}
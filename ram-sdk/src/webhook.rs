@@ -0,0 +1,112 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! Merchant webhook verification
+//!
+//! The backend POSTs a [`WebhookEvent`] to a wallet's registered webhook for
+//! every transfer the wallet receives, with three headers: the event ID, the
+//! time the attempt was signed (ms) and a hex HMAC-SHA256 of
+//! `<timestamp>.<body>` under the webhook's secret. [`verify`] checks the
+//! signature and the timestamp before parsing the body; receivers should
+//! also drop IDs they've already handled, since failed deliveries are
+//! retried.
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::error::{Result, SdkError};
+use crate::types::WebhookEvent;
+
+/// Event ID header
+pub const ID_HEADER: &str = "x-ram-webhook-id";
+/// Signing time header (ms)
+pub const TIMESTAMP_HEADER: &str = "x-ram-webhook-timestamp";
+/// Signature header
+pub const SIGNATURE_HEADER: &str = "x-ram-webhook-signature";
+/// How far a notification's timestamp may be from the receiver's clock
+pub const DEFAULT_TOLERANCE_MS: u64 = 5 * 60 * 1000;
+
+fn mac(secret: &str, timestamp_ms: u64, body: &[u8]) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC can take key of any size");
+    mac.update(format!("{}.", timestamp_ms).as_bytes());
+    mac.update(body);
+    mac
+}
+
+/// Hex signature of a body signed at `timestamp_ms`
+/// Must match webhook_signature in the backend's webhooks.rs
+pub fn signature(secret: &str, timestamp_ms: u64, body: &[u8]) -> String {
+    hex::encode(mac(secret, timestamp_ms, body).finalize().into_bytes())
+}
+
+/// Check a notification's headers against its raw body and parse it
+///
+/// `timestamp` and `signature` are the header values as received; `now_ms`
+/// is the receiver's clock. Fails with `SdkError::InvalidWebhook` if the
+/// signature doesn't match (compared in constant time), the timestamp is
+/// more than `tolerance_ms` off, or the body isn't a notification.
+pub fn verify(
+    secret: &str,
+    timestamp: &str,
+    signature: &str,
+    body: &[u8],
+    now_ms: u64,
+    tolerance_ms: u64,
+) -> Result<WebhookEvent> {
+    let timestamp_ms: u64 = timestamp
+        .trim()
+        .parse()
+        .map_err(|_| SdkError::InvalidWebhook(format!("malformed timestamp {:?}", timestamp)))?;
+    let expected =
+        hex::decode(signature.trim()).map_err(|_| SdkError::InvalidWebhook("malformed signature".to_string()))?;
+    mac(secret, timestamp_ms, body)
+        .verify_slice(&expected)
+        .map_err(|_| SdkError::InvalidWebhook("signature mismatch".to_string()))?;
+    if now_ms.abs_diff(timestamp_ms) > tolerance_ms {
+        return Err(SdkError::InvalidWebhook(format!(
+            "signed at {}, more than {}ms from now",
+            timestamp_ms, tolerance_ms
+        )));
+    }
+
+    serde_json::from_slice(body).map_err(|e| SdkError::InvalidWebhook(format!("unreadable body: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BODY: &[u8] = br#"{"id":"evt_1","type":"payment.received","created_at_ms":1700000000000,"data":{"handle":"shop","payer_handle":"alice","amount":5000000000,"coin_type":"0x2::sui::SUI","memo":"order-42","tx_digest":"Dig1","timestamp_ms":1700000000000}}"#;
+
+    #[test]
+    fn test_signature_matches_backend_vector() {
+        // Same vector as the backend's webhooks tests
+        assert_eq!(
+            signature("whsec_test", 1_700_000_000_000, br#"{"id":"evt_1"}"#),
+            "f510dfdb0b01915750c36f3e15401a7895306243fa607a5cba4496ce6c3a0ea3"
+        );
+    }
+
+    #[test]
+    fn test_verify_checks_signature_and_age() {
+        let now_ms = 1_700_000_060_000;
+        let sig = signature("whsec_test", 1_700_000_000_000, BODY);
+
+        let event = verify("whsec_test", "1700000000000", &sig, BODY, now_ms, DEFAULT_TOLERANCE_MS).unwrap();
+        assert_eq!(event.id, "evt_1");
+        assert_eq!(event.kind, "payment.received");
+        assert_eq!(event.data.memo.as_deref(), Some("order-42"));
+
+        // Wrong secret, tampered body, re-dated signature, stale timestamp
+        for (secret, timestamp, body, now) in [
+            ("whsec_other", "1700000000000", BODY, now_ms),
+            ("whsec_test", "1700000000000", &BODY[..BODY.len() - 1], now_ms),
+            ("whsec_test", "1700000001000", BODY, now_ms),
+            ("whsec_test", "1700000000000", BODY, now_ms + DEFAULT_TOLERANCE_MS),
+        ] {
+            let err = verify(secret, timestamp, &sig, body, now, DEFAULT_TOLERANCE_MS).unwrap_err();
+            assert!(matches!(err, SdkError::InvalidWebhook(_)), "{}", err);
+        }
+        assert!(verify("whsec_test", "soon", &sig, BODY, now_ms, DEFAULT_TOLERANCE_MS).is_err());
+    }
+}
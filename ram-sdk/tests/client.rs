@@ -0,0 +1,226 @@
+// Copyright (c) RAM
+// SPDX-License-Identifier: Apache-2.0
+
+//! RamClient against a mock backend
+
+use ed25519_dalek::{Signer, SigningKey};
+use ram_sdk::verify::signing_bytes;
+use ram_sdk::*;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::time::Duration;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn enclave_key() -> SigningKey {
+    SigningKey::from_bytes(&[7u8; 32])
+}
+
+fn signed_json<T: Serialize>(key: &SigningKey, intent: Intent, payload: T) -> Value {
+    let msg = signing_bytes(intent as u8, 1_700_000_000_000, &payload).unwrap();
+    json!({
+        "payload": payload,
+        "intent": intent as u8,
+        "timestamp_ms": 1_700_000_000_000u64,
+        "signature": hex::encode(key.sign(&msg).to_bytes()),
+    })
+}
+
+fn transfer_payload(amount: u64) -> TransferPayload {
+    TransferPayload {
+        from_handle: b"alice".to_vec(),
+        to_handle: b"bob".to_vec(),
+        amount,
+        coin_type: b"SUI".to_vec(),
+        memo: Vec::new(),
+    }
+}
+
+fn bioauth_payload() -> BioAuthPayload {
+    BioAuthPayload {
+        handle: b"alice".to_vec(),
+        amount: 5_000_000_000,
+        result: BioAuthResult::Ok as u8,
+        transcript: b"I confirm sending 5 SUI".to_vec(),
+        destination_verified: false,
+        method: BioAuthMethod::Voice as u8,
+        seal: vec![0; 48],
+        failed_attempts: 0,
+    }
+}
+
+fn client(server: &MockServer) -> RamClient {
+    RamClient::new(server.uri())
+        .with_enclave_key(enclave_key().verifying_key())
+        .with_retry(RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(5),
+        })
+}
+
+#[tokio::test]
+async fn test_signed_response_is_verified() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/transfer"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(signed_json(
+            &enclave_key(),
+            Intent::Transfer,
+            transfer_payload(5),
+        )))
+        .mount(&server)
+        .await;
+
+    let signed = client(&server).transfer("alice", "bob", 5, "SUI", None).await.unwrap();
+    assert_eq!(signed.payload, transfer_payload(5));
+
+    // A response whose payload doesn't match its signature is rejected
+    let mut tampered = signed_json(&enclave_key(), Intent::Transfer, transfer_payload(5));
+    tampered["payload"]["amount"] = json!(500);
+    server.reset().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/transfer"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(tampered))
+        .mount(&server)
+        .await;
+    let err = client(&server).transfer("alice", "bob", 5, "SUI", None).await.unwrap_err();
+    assert!(matches!(err, SdkError::InvalidSignature(_)));
+}
+
+#[tokio::test]
+async fn test_wrong_intent_is_rejected() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/withdraw"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(signed_json(
+            &enclave_key(),
+            Intent::Transfer,
+            WithdrawPayload { handle: b"alice".to_vec(), amount: 5, coin_type: b"SUI".to_vec() },
+        )))
+        .mount(&server)
+        .await;
+
+    let err = client(&server).withdraw("alice", 5, "SUI").await.unwrap_err();
+    assert!(matches!(err, SdkError::IntentMismatch { expected: 4, actual: 2 }));
+}
+
+#[tokio::test]
+async fn test_retries_transient_failures_only() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/api/stats"))
+        .respond_with(ResponseTemplate::new(503))
+        .up_to_n_times(2)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/api/stats"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "handle": "alice",
+            "total_deposits": 1,
+            "total_withdrawals": 0,
+            "total_transfers_sent": 2,
+            "total_transfers_received": 0,
+        })))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/bio_auth"))
+        .respond_with(ResponseTemplate::new(409).set_body_json(json!({
+            "code": "conflict",
+            "message": "already in progress",
+            "details": null,
+            "request_id": "req-1",
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let stats = client(&server).stats("alice").await.unwrap();
+    assert_eq!(stats.total_transfers_sent, 2);
+
+    let request = BioAuthRequest {
+        handle: "alice".to_string(),
+        audio_base64: String::new(),
+        expected_amount: 5_000_000_000,
+        coin_type: None,
+        destination_hint: None,
+        locale: None,
+        skip_analysis_cache: false,
+    };
+    match client(&server).bio_auth(&request).await.unwrap_err() {
+        SdkError::Api { status, code, message, request_id } => {
+            assert_eq!(status, 409);
+            assert_eq!(code, ErrorCode::Conflict);
+            assert_eq!(message, "already in progress");
+            assert_eq!(request_id.as_deref(), Some("req-1"));
+        }
+        other => panic!("unexpected error: {other}"),
+    }
+}
+
+#[tokio::test]
+async fn test_validation_errors_list_fields() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/withdraw"))
+        .respond_with(ResponseTemplate::new(422).set_body_json(json!({
+            "code": "validation_failed",
+            "message": "Request validation failed",
+            "details": {
+                "fields": [
+                    { "field": "payload.amount", "message": "must be greater than 0" },
+                    { "field": "payload.coin_type", "message": "Invalid coin type 'SUI'" },
+                ],
+            },
+            "request_id": "req-2",
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let err = client(&server).withdraw("alice", 0, "SUI").await.unwrap_err();
+    assert!(!err.is_retryable());
+    match err {
+        SdkError::Validation(fields) => {
+            assert_eq!(fields.len(), 2);
+            assert_eq!(fields[0].field, "payload.amount");
+        }
+        other => panic!("unexpected error: {other}"),
+    }
+}
+
+#[tokio::test]
+async fn test_queued_bio_auth_is_awaited() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/bio_auth"))
+        .respond_with(ResponseTemplate::new(202).set_body_json(json!({ "job_id": "job-1", "status": "queued" })))
+        .mount(&server)
+        .await;
+    let mut done = signed_json(&enclave_key(), Intent::BioAuth, bioauth_payload());
+    done["request_id"] = json!("req-1");
+    Mock::given(method("GET"))
+        .and(path("/v1/bio_auth/job-1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "job_id": "job-1",
+            "status": "done",
+            "response": done,
+        })))
+        .mount(&server)
+        .await;
+
+    let request = BioAuthRequest {
+        handle: "alice".to_string(),
+        audio_base64: String::new(),
+        expected_amount: 5_000_000_000,
+        coin_type: Some("SUI".to_string()),
+        destination_hint: None,
+        locale: None,
+        skip_analysis_cache: false,
+    };
+    let response = client(&server).bio_auth(&request).await.unwrap();
+    assert_eq!(response.request_id, "req-1");
+    assert_eq!(response.signed.payload, bioauth_payload());
+}